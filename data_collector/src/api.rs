@@ -0,0 +1,1405 @@
+use crate::battery_health::{
+    BatteryAlert, BatteryFleetReport, BatteryHistory, BatteryRegistry, BatteryRegistryError,
+    BatterySessionSummary,
+};
+use crate::upload::{UploadError, UploadInitRequest, UploadManager, UploadStatus, UploadTicket};
+use crate::upload_client::CHUNK_SHA256_HEADER;
+use crate::{
+    DataCollectorService, DataType, DerivedProduct, FlightDataProvenanceError, FlightDataRecord,
+    FlightSession, NewDerivedProduct, ProductRegistryError, SessionLifecycleError, SessionStatus,
+    SessionTrackFix,
+};
+use axum::{
+    body::Bytes,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        DefaultBodyLimit, Path, Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::webhooks::{WebhookDispatcher, WebhookEvent, WebhookEventKind};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, Mutex};
+use uuid::Uuid;
+
+const ACK_CHANNEL_CAPACITY: usize = 256;
+/// Headroom above the chunk size for the request framing axum counts
+/// against the body limit.
+const CHUNK_BODY_SLACK_BYTES: usize = 64 * 1024;
+
+pub type SharedDataCollectorService = Arc<Mutex<DataCollectorService>>;
+
+/// Acknowledgement emitted once a remotely ingested record has been stored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordIngestAck {
+    pub session_id: Uuid,
+    pub record_id: Uuid,
+    pub data_type: DataType,
+    pub record_count: u32,
+    pub stored_at: DateTime<Utc>,
+    /// Set when the record was already held by the session and not stored again.
+    #[serde(default)]
+    pub duplicate: bool,
+}
+
+/// Published for every newly stored record so downstream stages (e.g. live
+/// overlay generation) can react to ingestion without polling the store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordNotification {
+    pub session_id: Uuid,
+    pub record: FlightDataRecord,
+}
+
+/// HTTP/WebSocket ingestion surface for distributed sensor nodes.
+#[derive(Clone)]
+pub struct IngestApiState {
+    service: SharedDataCollectorService,
+    uploads: Arc<UploadManager>,
+    acks: broadcast::Sender<RecordIngestAck>,
+    records: broadcast::Sender<RecordNotification>,
+    batteries: Arc<Mutex<BatteryRegistry>>,
+    battery_alerts: broadcast::Sender<BatteryAlert>,
+}
+
+/// Body of `POST /sessions/{session_id}/records`, tagged by `kind`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IngestRequest {
+    Record(FlightDataRecord),
+    /// Starts a chunked upload; the assembled file becomes a record.
+    Upload(UploadInitRequest),
+}
+
+/// Response to a posted battery session summary.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatterySessionAck {
+    /// False when the session carried no battery serial and was only counted.
+    pub attributed: bool,
+    pub alerts: Vec<BatteryAlert>,
+}
+
+impl IngestApiState {
+    pub fn new(service: SharedDataCollectorService, uploads: Arc<UploadManager>) -> Self {
+        let (acks, _) = broadcast::channel(ACK_CHANNEL_CAPACITY);
+        let (records, _) = broadcast::channel(ACK_CHANNEL_CAPACITY);
+        let (battery_alerts, _) = broadcast::channel(ACK_CHANNEL_CAPACITY);
+        Self {
+            service,
+            uploads,
+            acks,
+            records,
+            batteries: Arc::new(Mutex::new(BatteryRegistry::default())),
+            battery_alerts,
+        }
+    }
+
+    /// Replaces the default registry, e.g. with one built from configured
+    /// battery profiles.
+    pub fn with_battery_registry(mut self, registry: BatteryRegistry) -> Self {
+        self.batteries = Arc::new(Mutex::new(registry));
+        self
+    }
+
+    pub fn batteries(&self) -> Arc<Mutex<BatteryRegistry>> {
+        Arc::clone(&self.batteries)
+    }
+
+    pub fn subscribe_battery_alerts(&self) -> broadcast::Receiver<BatteryAlert> {
+        self.battery_alerts.subscribe()
+    }
+
+    pub fn service(&self) -> SharedDataCollectorService {
+        Arc::clone(&self.service)
+    }
+
+    pub fn uploads(&self) -> Arc<UploadManager> {
+        Arc::clone(&self.uploads)
+    }
+
+    pub fn subscribe_acks(&self) -> broadcast::Receiver<RecordIngestAck> {
+        self.acks.subscribe()
+    }
+
+    /// In-process subscription to newly stored records. Duplicates are not
+    /// re-published.
+    pub fn subscribe_records(&self) -> broadcast::Receiver<RecordNotification> {
+        self.records.subscribe()
+    }
+
+    /// Flushes the collector's pending record batch once it has waited past
+    /// the configured age, so records are written even when ingestion stalls.
+    pub fn spawn_batch_flusher(&self) -> tokio::task::JoinHandle<()> {
+        let service = self.service();
+        tokio::spawn(async move {
+            let max_age = service.lock().await.batch_config().max_age;
+            let mut ticker = tokio::time::interval((max_age / 2).max(Duration::from_millis(10)));
+            loop {
+                ticker.tick().await;
+                if let Err(error) = service.lock().await.flush_due_batch().await {
+                    tracing::warn!(%error, "flushing pending record batch failed");
+                }
+            }
+        })
+    }
+
+    /// Aborts sessions that have gone without a record for the collector's
+    /// idle timeout, checking every `interval`.
+    pub fn spawn_idle_session_sweeper(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let service = self.service();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match service.lock().await.sweep_idle_sessions(Utc::now()).await {
+                    Ok(aborted) if !aborted.is_empty() => {
+                        tracing::info!(count = aborted.len(), "aborted idle capture sessions");
+                    }
+                    Ok(_) => {}
+                    Err(error) => tracing::warn!(%error, "sweeping idle capture sessions failed"),
+                }
+            }
+        })
+    }
+
+    /// Publishes every newly stored record through `webhooks` as a
+    /// `record.stored` event whose payload is its [`RecordNotification`].
+    /// The dispatcher signs and retries each delivery and dead-letters the
+    /// ones that keep failing. The task ends once the state is dropped.
+    pub fn spawn_record_webhook(&self, webhooks: WebhookDispatcher) -> tokio::task::JoinHandle<()> {
+        spawn_webhook_publisher(
+            self.subscribe_records(),
+            webhooks,
+            WebhookEventKind::RecordStored,
+        )
+    }
+
+    /// Publishes every [`BatteryAlert`] through `webhooks` as a
+    /// `battery.alert` event, delivered like [`Self::spawn_record_webhook`].
+    pub fn spawn_battery_alert_webhook(
+        &self,
+        webhooks: WebhookDispatcher,
+    ) -> tokio::task::JoinHandle<()> {
+        spawn_webhook_publisher(
+            self.subscribe_battery_alerts(),
+            webhooks,
+            WebhookEventKind::BatteryAlert,
+        )
+    }
+}
+
+/// Publishes each message from `messages` as a `kind` event until the
+/// channel closes.
+fn spawn_webhook_publisher<T>(
+    mut messages: broadcast::Receiver<T>,
+    webhooks: WebhookDispatcher,
+    kind: WebhookEventKind,
+) -> tokio::task::JoinHandle<()>
+where
+    T: Serialize + Clone + Send + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            let message = match messages.recv().await {
+                Ok(message) => message,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(kind = kind.as_str(), skipped, "webhook publisher lagged");
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let published = serde_json::to_value(&message)
+                .map_err(|error| error.to_string())
+                .and_then(|payload| {
+                    webhooks
+                        .publish(WebhookEvent::new(kind, payload))
+                        .map_err(|error| error.to_string())
+                });
+            if let Err(error) = published {
+                tracing::warn!(kind = kind.as_str(), %error, "dropping webhook event");
+            }
+        }
+    })
+}
+
+pub fn router(state: IngestApiState) -> Router {
+    let chunk_body_limit = usize::try_from(state.uploads.config().chunk_size_bytes)
+        .unwrap_or(usize::MAX)
+        .saturating_add(CHUNK_BODY_SLACK_BYTES);
+    Router::new()
+        .route("/sessions/:session_id/records", post(ingest_record))
+        .route("/sessions/:session_id/stream", get(stream_acks))
+        .route(
+            "/sessions/:session_id/products",
+            get(list_products).post(register_product),
+        )
+        .route("/sessions/:session_id/track", get(session_track))
+        .route("/uploads/:upload_id", get(upload_status))
+        .route(
+            "/uploads/:upload_id/chunks/:index",
+            put(receive_chunk).layer(DefaultBodyLimit::max(chunk_body_limit)),
+        )
+        .route("/uploads/:upload_id/complete", post(complete_upload))
+        .route("/batteries", get(list_batteries))
+        .route("/batteries/sessions", post(record_battery_session))
+        .route("/batteries/alerts", get(stream_battery_alerts))
+        .route("/batteries/:serial/history", get(battery_history))
+        .with_state(state)
+}
+
+type ApiError = (StatusCode, String);
+
+#[derive(Debug, Default, Deserialize)]
+struct ProductListQuery {
+    product_type: Option<String>,
+}
+
+/// Stores a complete record or starts a chunked upload for a file, as the
+/// body's `kind` says.
+async fn ingest_record(
+    State(state): State<IngestApiState>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<IngestRequest>,
+) -> Result<Response, ApiError> {
+    let record = match request {
+        IngestRequest::Record(record) => record,
+        IngestRequest::Upload(request) => {
+            return initiate_upload(state, session_id, request)
+                .await
+                .map(IntoResponse::into_response)
+        }
+    };
+    let mut service = state.service.lock().await;
+    active_session(&mut service, session_id).await?;
+    let (status, ack) = store_record(&state, &mut service, session_id, record).await?;
+    Ok((status, Json(ack)).into_response())
+}
+
+async fn initiate_upload(
+    state: IngestApiState,
+    session_id: Uuid,
+    request: UploadInitRequest,
+) -> Result<(StatusCode, Json<UploadTicket>), ApiError> {
+    active_session(&mut *state.service.lock().await, session_id).await?;
+    let ticket = state
+        .uploads
+        .initiate(session_id, request, Utc::now())
+        .await
+        .map_err(upload_error_response)?;
+    Ok((StatusCode::CREATED, Json(ticket)))
+}
+
+async fn receive_chunk(
+    State(state): State<IngestApiState>,
+    Path((upload_id, index)): Path<(Uuid, u32)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<UploadStatus>, ApiError> {
+    let declared_sha256 = headers
+        .get(CHUNK_SHA256_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                format!("missing {CHUNK_SHA256_HEADER} header"),
+            )
+        })?;
+    state
+        .uploads
+        .receive_chunk(upload_id, index, declared_sha256, &body, Utc::now())
+        .await
+        .map(Json)
+        .map_err(upload_error_response)
+}
+
+async fn upload_status(
+    State(state): State<IngestApiState>,
+    Path(upload_id): Path<Uuid>,
+) -> Result<Json<UploadStatus>, ApiError> {
+    state
+        .uploads
+        .status(upload_id)
+        .await
+        .map(Json)
+        .map_err(upload_error_response)
+}
+
+async fn complete_upload(
+    State(state): State<IngestApiState>,
+    Path(upload_id): Path<Uuid>,
+) -> Result<(StatusCode, Json<RecordIngestAck>), ApiError> {
+    let session_id = state
+        .uploads
+        .status(upload_id)
+        .await
+        .map_err(upload_error_response)?
+        .session_id;
+    let mut service = state.service.lock().await;
+    let session = active_session(&mut service, session_id).await?;
+    let assembled = state
+        .uploads
+        .assemble(upload_id)
+        .await
+        .map_err(upload_error_response)?;
+    let record = assembled
+        .into_record(session.flight_id, session.drone_id)
+        .map_err(|error| (StatusCode::UNPROCESSABLE_ENTITY, error.to_string()))?;
+    let response = store_record(&state, &mut service, session_id, record).await?;
+    drop(service);
+
+    if let Err(error) = state.uploads.discard(upload_id).await {
+        tracing::warn!(%upload_id, %error, "failed to remove chunks of completed upload");
+    }
+    Ok((response.0, Json(response.1)))
+}
+
+async fn list_products(
+    State(state): State<IngestApiState>,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<ProductListQuery>,
+) -> Result<Json<Vec<DerivedProduct>>, ApiError> {
+    state
+        .service
+        .lock()
+        .await
+        .list_products(&session_id, query.product_type.as_deref())
+        .await
+        .map(Json)
+        .map_err(product_error_response)
+}
+
+/// Lets pipeline stages running outside this process record what they wrote;
+/// post_processor registers the artifacts of jobs submitted with a
+/// `session_id` here.
+async fn register_product(
+    State(state): State<IngestApiState>,
+    Path(session_id): Path<Uuid>,
+    Json(request): Json<NewDerivedProduct>,
+) -> Result<(StatusCode, Json<DerivedProduct>), ApiError> {
+    let product = state
+        .service
+        .lock()
+        .await
+        .register_product(&session_id, request)
+        .await
+        .map_err(product_error_response)?;
+    Ok((StatusCode::CREATED, Json(product)))
+}
+
+/// The session's telemetry track, which mission_planner turns into a draft
+/// re-fly mission.
+async fn session_track(
+    State(state): State<IngestApiState>,
+    Path(session_id): Path<Uuid>,
+) -> Result<Json<Vec<SessionTrackFix>>, ApiError> {
+    state
+        .service
+        .lock()
+        .await
+        .session_track(&session_id)
+        .await
+        .map_err(internal_error)?
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                SessionLifecycleError::SessionNotFound { session_id }.to_string(),
+            )
+        })
+}
+
+async fn active_session(
+    service: &mut DataCollectorService,
+    session_id: Uuid,
+) -> Result<FlightSession, ApiError> {
+    let session = service
+        .get_session(&session_id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                SessionLifecycleError::SessionNotFound { session_id }.to_string(),
+            )
+        })?;
+    if !matches!(
+        session.status,
+        SessionStatus::Started | SessionStatus::Collecting
+    ) {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "capture session {session_id} is not active (status {:?})",
+                session.status
+            ),
+        ));
+    }
+    Ok(session)
+}
+
+async fn store_record(
+    state: &IngestApiState,
+    service: &mut DataCollectorService,
+    session_id: Uuid,
+    record: FlightDataRecord,
+) -> Result<(StatusCode, RecordIngestAck), ApiError> {
+    let record_id = record.id;
+    let data_type = record.data_type.clone();
+    let notification = (state.records.receiver_count() > 0).then(|| RecordNotification {
+        session_id,
+        record: record.clone(),
+    });
+    let outcome = service
+        .collect_data(&session_id, record)
+        .await
+        .map_err(collect_error_response)?;
+    let record_count = service
+        .get_session(&session_id)
+        .await
+        .map_err(internal_error)?
+        .map(|session| session.summary.record_count)
+        .unwrap_or_default();
+
+    let ack = RecordIngestAck {
+        session_id,
+        record_id,
+        data_type,
+        record_count,
+        stored_at: Utc::now(),
+        duplicate: outcome.is_duplicate(),
+    };
+    if ack.duplicate {
+        return Ok((StatusCode::OK, ack));
+    }
+    // No subscribers is not an error; the ack is still returned to the caller.
+    let _ = state.acks.send(ack.clone());
+    if let Some(notification) = notification {
+        let _ = state.records.send(notification);
+    }
+    Ok((StatusCode::CREATED, ack))
+}
+
+fn upload_error_response(error: UploadError) -> ApiError {
+    let status = match &error {
+        UploadError::NotFound { .. } => StatusCode::NOT_FOUND,
+        UploadError::UnsupportedFileType { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        UploadError::Incomplete { .. } => StatusCode::CONFLICT,
+        UploadError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+        UploadError::InvalidRequest { .. }
+        | UploadError::ChunkOutOfRange { .. }
+        | UploadError::ChunkSizeMismatch { .. }
+        | UploadError::ChunkHashMismatch { .. }
+        | UploadError::FileHashMismatch { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        UploadError::Io(_) | UploadError::Manifest(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, error.to_string())
+}
+
+fn collect_error_response(error: anyhow::Error) -> ApiError {
+    if error.downcast_ref::<FlightDataProvenanceError>().is_some() {
+        return (StatusCode::UNPROCESSABLE_ENTITY, error.to_string());
+    }
+    if let Some(lifecycle) = error.downcast_ref::<SessionLifecycleError>() {
+        let status = match lifecycle {
+            SessionLifecycleError::SessionNotFound { .. } => StatusCode::NOT_FOUND,
+            SessionLifecycleError::InvalidStatusTransition { .. }
+            | SessionLifecycleError::DroneSessionActive { .. } => StatusCode::CONFLICT,
+        };
+        return (status, error.to_string());
+    }
+    internal_error(error)
+}
+
+fn product_error_response(error: anyhow::Error) -> ApiError {
+    if let Some(product_error) = error.downcast_ref::<ProductRegistryError>() {
+        let status = match product_error {
+            ProductRegistryError::UnknownProduct { .. } => StatusCode::NOT_FOUND,
+            ProductRegistryError::SessionActive { .. } => StatusCode::CONFLICT,
+            ProductRegistryError::EmptyProductType | ProductRegistryError::UnknownParent { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+        };
+        return (status, error.to_string());
+    }
+    collect_error_response(error)
+}
+
+fn internal_error(error: anyhow::Error) -> ApiError {
+    (StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+}
+
+async fn list_batteries(State(state): State<IngestApiState>) -> Json<BatteryFleetReport> {
+    Json(state.batteries.lock().await.fleet_report())
+}
+
+async fn battery_history(
+    State(state): State<IngestApiState>,
+    Path(serial): Path<String>,
+) -> Result<Json<BatteryHistory>, ApiError> {
+    state
+        .batteries
+        .lock()
+        .await
+        .history(&serial)
+        .map(Json)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("unknown battery: {serial}")))
+}
+
+/// Takes a finished session's battery summary and publishes any alerts it
+/// raised to the alert stream and webhook.
+async fn record_battery_session(
+    State(state): State<IngestApiState>,
+    Json(summary): Json<BatterySessionSummary>,
+) -> Result<Json<BatterySessionAck>, ApiError> {
+    let attributed = summary
+        .battery_serial
+        .as_deref()
+        .is_some_and(|serial| !serial.trim().is_empty());
+    let alerts = state
+        .batteries
+        .lock()
+        .await
+        .record_session(summary)
+        .map_err(battery_error_response)?;
+    for alert in &alerts {
+        let _ = state.battery_alerts.send(alert.clone());
+    }
+    Ok(Json(BatterySessionAck { attributed, alerts }))
+}
+
+fn battery_error_response(error: BatteryRegistryError) -> ApiError {
+    let status = match error {
+        BatteryRegistryError::DuplicateSession { .. } => StatusCode::CONFLICT,
+        BatteryRegistryError::InvalidSession { .. } | BatteryRegistryError::InvalidConfig(_) => {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+    };
+    (status, error.to_string())
+}
+
+/// Streams battery alerts as `SystemStatus` messages.
+async fn stream_battery_alerts(
+    ws: WebSocketUpgrade,
+    State(state): State<IngestApiState>,
+) -> Response {
+    let alerts = state.subscribe_battery_alerts();
+    ws.on_upgrade(move |socket| forward_battery_alerts(socket, alerts))
+}
+
+async fn forward_battery_alerts(
+    mut socket: WebSocket,
+    mut alerts: broadcast::Receiver<BatteryAlert>,
+) {
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            },
+            alert = alerts.recv() => match alert {
+                Ok(alert) => {
+                    let Ok(json) = serde_json::to_string(&alert.to_system_status()) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(json)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "battery alert stream lagged");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+async fn stream_acks(
+    ws: WebSocketUpgrade,
+    State(state): State<IngestApiState>,
+    Path(session_id): Path<Uuid>,
+) -> Response {
+    let acks = state.subscribe_acks();
+    ws.on_upgrade(move |socket| forward_acks(socket, session_id, acks))
+}
+
+async fn forward_acks(
+    mut socket: WebSocket,
+    session_id: Uuid,
+    mut acks: broadcast::Receiver<RecordIngestAck>,
+) {
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            },
+            ack = acks.recv() => match ack {
+                Ok(ack) if ack.session_id == session_id => {
+                    let Ok(json) = serde_json::to_string(&ack) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(json)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(%session_id, skipped, "ingest ack stream lagged");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upload::{sha256_hex, UploadConfig};
+    use crate::{DataPayload, FlightDataProvenance, GpsCoords, MediaFilePayload, TelemetryPayload};
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, Request},
+    };
+    use shared::webhooks::{
+        verify_webhook_signature, WebhookConfig, WebhookDeliveryPolicy, WebhookEndpoint,
+        WEBHOOK_SIGNATURE_HEADER,
+    };
+    use std::time::Duration;
+    use tempfile::tempdir;
+    use tower::ServiceExt;
+
+    fn upload_manager(root: &std::path::Path, chunk_size_bytes: u64) -> Arc<UploadManager> {
+        Arc::new(
+            UploadManager::open(
+                root.join("uploads"),
+                UploadConfig {
+                    chunk_size_bytes,
+                    ..UploadConfig::default()
+                },
+            )
+            .unwrap(),
+        )
+    }
+
+    fn upload_request(file_name: &str, contents: &[u8], sha256: String) -> UploadInitRequest {
+        UploadInitRequest {
+            file_name: file_name.to_string(),
+            mime_type: None,
+            size_bytes: contents.len() as u64,
+            sha256,
+            sensor_id: "lidar-01".to_string(),
+            gps_coords: GpsCoords {
+                latitude: 40.0,
+                longitude: -105.0,
+                altitude: 30.0,
+            },
+            captured_at: Utc::now(),
+            calibration_ref: "lidar-calibration-v1".to_string(),
+        }
+    }
+
+    fn put_chunk(
+        upload_id: Uuid,
+        index: u32,
+        declared_sha256: &str,
+        chunk: &[u8],
+    ) -> Request<Body> {
+        Request::builder()
+            .method("PUT")
+            .uri(format!("/uploads/{upload_id}/chunks/{index}"))
+            .header(CHUNK_SHA256_HEADER, declared_sha256)
+            .body(Body::from(chunk.to_vec()))
+            .expect("request should build")
+    }
+
+    fn empty_request(method: &str, uri: String) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .expect("request should build")
+    }
+
+    async fn json_body<T: serde::de::DeserializeOwned>(response: Response) -> T {
+        let body = to_bytes(response.into_body(), 64 * 1024).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    async fn start_upload(
+        app: &Router,
+        session_id: Uuid,
+        request: &UploadInitRequest,
+    ) -> UploadTicket {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/sessions/{session_id}/records"))
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&IngestRequest::Upload(request.clone())).unwrap(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        json_body(response).await
+    }
+
+    fn telemetry_record(session_id: Uuid) -> FlightDataRecord {
+        FlightDataRecord::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            DataType::Telemetry,
+            DataPayload::Telemetry(TelemetryPayload {
+                position: (40.0, -105.0, 30.0),
+                velocity: (1.0, 0.0, 0.0),
+                orientation: (0.0, 0.0, 0.0),
+                battery_level: 0.9,
+                signal_strength: 0.95,
+            }),
+            FlightDataProvenance::complete(
+                session_id,
+                "sensor-node-02".to_string(),
+                GpsCoords {
+                    latitude: 40.0,
+                    longitude: -105.0,
+                    altitude: 30.0,
+                },
+                Utc::now(),
+                "calibration-2026-06".to_string(),
+            ),
+            256,
+        )
+        .unwrap()
+    }
+
+    fn post_record(session_id: Uuid, record: &FlightDataRecord) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/sessions/{session_id}/records"))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::to_vec(&IngestRequest::Record(record.clone())).unwrap(),
+            ))
+            .expect("request should build")
+    }
+
+    #[tokio::test]
+    async fn posted_record_is_stored_and_increments_session_count() {
+        let temp_dir = tempdir().unwrap();
+        let service = Arc::new(Mutex::new(
+            DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap(),
+        ));
+        let session_id = service
+            .lock()
+            .await
+            .start_session(Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let state =
+            IngestApiState::new(Arc::clone(&service), upload_manager(temp_dir.path(), 1024));
+        let mut acks = state.subscribe_acks();
+        let mut records = state.subscribe_records();
+        let record = telemetry_record(session_id);
+        let app = router(state);
+
+        let untagged = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/sessions/{session_id}/records"))
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(serde_json::to_vec(&record).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(untagged.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = app
+            .oneshot(post_record(session_id, &record))
+            .await
+            .expect("router should handle ingest");
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = to_bytes(response.into_body(), 64 * 1024).await.unwrap();
+        let ack: RecordIngestAck = serde_json::from_slice(&body).unwrap();
+        assert_eq!(ack.record_id, record.id);
+        assert_eq!(ack.record_count, 1);
+        assert_eq!(acks.try_recv().unwrap(), ack);
+        let notification = records.try_recv().unwrap();
+        assert_eq!(notification.session_id, session_id);
+        assert_eq!(notification.record.id, record.id);
+
+        let session = service
+            .lock()
+            .await
+            .get_session(&session_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(session.summary.record_count, 1);
+        assert_eq!(session.data_records, vec![record.id]);
+    }
+
+    #[tokio::test]
+    async fn record_webhook_publishes_stored_records_once() {
+        let temp_dir = tempdir().unwrap();
+        let service = Arc::new(Mutex::new(
+            DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap(),
+        ));
+        let session_id = service
+            .lock()
+            .await
+            .start_session(Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let (delivered_tx, mut delivered) = tokio::sync::mpsc::unbounded_channel();
+        let hook = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| async move {
+                delivered_tx.send((headers, body)).unwrap();
+                StatusCode::NO_CONTENT
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, hook).await });
+        let (webhooks, _worker) = WebhookDispatcher::spawn(WebhookConfig {
+            endpoints: vec![WebhookEndpoint {
+                id: "overlay-engine".to_string(),
+                url: format!("http://{address}/hook"),
+                secret: "record-secret".to_string(),
+                events: vec![WebhookEventKind::RecordStored],
+            }],
+            delivery: WebhookDeliveryPolicy::default(),
+            queue_capacity: 16,
+        })
+        .unwrap();
+
+        let state =
+            IngestApiState::new(Arc::clone(&service), upload_manager(temp_dir.path(), 1024));
+        let webhook = state.spawn_record_webhook(webhooks.clone());
+        let app = router(state);
+        let record = telemetry_record(session_id);
+        for expected in [StatusCode::CREATED, StatusCode::OK] {
+            let response = app
+                .clone()
+                .oneshot(post_record(session_id, &record))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected);
+        }
+        drop(app);
+
+        let (headers, body) = tokio::time::timeout(Duration::from_secs(5), delivered.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let signature = headers[WEBHOOK_SIGNATURE_HEADER].to_str().unwrap();
+        assert!(verify_webhook_signature("record-secret", &body, signature));
+        let event: WebhookEvent = serde_json::from_slice(&body).unwrap();
+        assert_eq!(event.kind, WebhookEventKind::RecordStored);
+        let notification: RecordNotification = serde_json::from_value(event.payload).unwrap();
+        assert_eq!(notification.session_id, session_id);
+        assert_eq!(notification.record.id, record.id);
+        tokio::time::timeout(Duration::from_secs(5), webhook)
+            .await
+            .unwrap()
+            .unwrap();
+        webhooks.flush().await;
+        assert!(delivered.try_recv().is_err());
+        assert!(webhooks.dead_letters().is_empty());
+    }
+
+    #[tokio::test]
+    async fn session_products_are_registered_and_listed_by_type() {
+        let temp_dir = tempdir().unwrap();
+        let service = Arc::new(Mutex::new(
+            DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap(),
+        ));
+        let session_id = service
+            .lock()
+            .await
+            .start_session(Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let app = router(IngestApiState::new(
+            service,
+            upload_manager(temp_dir.path(), 1024),
+        ));
+        let post_product = |body: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/sessions/{session_id}/products"))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let created = app
+            .clone()
+            .oneshot(post_product(serde_json::json!({
+                "producer": "ndvi_analysis",
+                "product_type": "ndvi",
+                "file_path": "/outputs/ndvi.tif",
+            })))
+            .await
+            .unwrap();
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let ndvi: DerivedProduct = json_body(created).await;
+        let report = app
+            .clone()
+            .oneshot(post_product(serde_json::json!({
+                "producer": "report",
+                "product_type": "report",
+                "file_path": "/outputs/report.pdf",
+                "parent_product_ids": [ndvi.id],
+            })))
+            .await
+            .unwrap();
+        assert_eq!(report.status(), StatusCode::CREATED);
+        let unknown_parent = app
+            .clone()
+            .oneshot(post_product(serde_json::json!({
+                "producer": "report",
+                "product_type": "report",
+                "file_path": "/outputs/other.pdf",
+                "parent_product_ids": [Uuid::new_v4()],
+            })))
+            .await
+            .unwrap();
+        assert_eq!(unknown_parent.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let listed = app
+            .clone()
+            .oneshot(empty_request(
+                "GET",
+                format!("/sessions/{session_id}/products"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(listed.status(), StatusCode::OK);
+        assert_eq!(json_body::<Vec<DerivedProduct>>(listed).await.len(), 2);
+        let filtered = app
+            .clone()
+            .oneshot(empty_request(
+                "GET",
+                format!("/sessions/{session_id}/products?product_type=ndvi"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(json_body::<Vec<DerivedProduct>>(filtered).await, vec![ndvi]);
+        let missing = app
+            .oneshot(empty_request(
+                "GET",
+                format!("/sessions/{}/products", Uuid::new_v4()),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn session_track_lists_telemetry_fixes_in_time_order() {
+        let temp_dir = tempdir().unwrap();
+        let service = Arc::new(Mutex::new(
+            DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap(),
+        ));
+        let session_id = service
+            .lock()
+            .await
+            .start_session(Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let app = router(IngestApiState::new(
+            service,
+            upload_manager(temp_dir.path(), 1024),
+        ));
+        let first = telemetry_record(session_id);
+        let mut second = telemetry_record(session_id);
+        second.timestamp = first.timestamp + chrono::Duration::seconds(2);
+        second.payload = DataPayload::Telemetry(TelemetryPayload {
+            position: (40.001, -105.0, 32.0),
+            velocity: (1.0, 0.0, 0.0),
+            orientation: (0.0, 0.0, 0.0),
+            battery_level: 0.89,
+            signal_strength: 0.95,
+        });
+        let mut photo = telemetry_record(session_id);
+        photo.timestamp = first.timestamp + chrono::Duration::seconds(1);
+        photo.payload = DataPayload::MediaFile(MediaFilePayload {
+            file_type: "jpeg".to_string(),
+            dimensions: Some((4000, 3000)),
+            duration_seconds: None,
+            compression: None,
+        });
+        photo.data_type = photo.payload.data_type();
+        for record in [&second, &photo, &first] {
+            let response = app
+                .clone()
+                .oneshot(post_record(session_id, record))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+
+        let response = app
+            .clone()
+            .oneshot(empty_request(
+                "GET",
+                format!("/sessions/{session_id}/track"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let track: Vec<SessionTrackFix> = json_body(response).await;
+        assert_eq!(track.len(), 2);
+        assert_eq!(track[0].timestamp, first.timestamp);
+        assert!(track[0].capture_event);
+        assert_eq!((track[1].latitude, track[1].altitude_m), (40.001, 32.0));
+        assert!(!track[1].capture_event);
+
+        let unknown = app
+            .oneshot(empty_request(
+                "GET",
+                format!("/sessions/{}/track", Uuid::new_v4()),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn records_for_ended_or_unknown_sessions_are_rejected() {
+        let temp_dir = tempdir().unwrap();
+        let service = Arc::new(Mutex::new(
+            DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap(),
+        ));
+        let session_id = {
+            let mut service = service.lock().await;
+            let session_id = service.start_session(Uuid::new_v4(), None).await.unwrap();
+            service.end_session(&session_id).await.unwrap();
+            session_id
+        };
+        let app = router(IngestApiState::new(
+            service,
+            upload_manager(temp_dir.path(), 1024),
+        ));
+
+        let ended = app
+            .clone()
+            .oneshot(post_record(session_id, &telemetry_record(session_id)))
+            .await
+            .expect("router should handle ingest");
+        assert_eq!(ended.status(), StatusCode::CONFLICT);
+
+        let unknown_session = Uuid::new_v4();
+        let unknown = app
+            .oneshot(post_record(
+                unknown_session,
+                &telemetry_record(unknown_session),
+            ))
+            .await
+            .expect("router should handle ingest");
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn chunked_upload_resumes_out_of_order_and_rejects_corrupted_chunks() {
+        let temp_dir = tempdir().unwrap();
+        let service = Arc::new(Mutex::new(
+            DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap(),
+        ));
+        let session_id = service
+            .lock()
+            .await
+            .start_session(Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let state = IngestApiState::new(Arc::clone(&service), upload_manager(temp_dir.path(), 4));
+        let mut acks = state.subscribe_acks();
+        let app = router(state);
+        let contents = b"lidar-scan";
+        let chunks = [&contents[0..4], &contents[4..8], &contents[8..10]];
+        let mut oversized = upload_request("north_field.las", contents, sha256_hex(contents));
+        oversized.size_bytes = 1 << 40;
+        let refused = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/sessions/{session_id}/records"))
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&IngestRequest::Upload(oversized)).unwrap(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(refused.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let ticket = start_upload(
+            &app,
+            session_id,
+            &upload_request("north_field.las", contents, sha256_hex(contents)),
+        )
+        .await;
+        assert_eq!(ticket.chunk_count, 3);
+        assert_eq!(ticket.data_type, DataType::LidarScan);
+        let upload_id = ticket.upload_id;
+
+        for index in [2, 0, 0] {
+            let chunk = chunks[index as usize];
+            let response = app
+                .clone()
+                .oneshot(put_chunk(upload_id, index, &sha256_hex(chunk), chunk))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let corrupted = app
+            .clone()
+            .oneshot(put_chunk(upload_id, 1, &sha256_hex(chunks[1]), b"XXXX"))
+            .await
+            .unwrap();
+        assert_eq!(corrupted.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let status: UploadStatus = json_body(
+            app.clone()
+                .oneshot(empty_request("GET", format!("/uploads/{upload_id}")))
+                .await
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status.received_bitmap, "101");
+        assert_eq!(status.missing_chunks, vec![1]);
+        let incomplete = app
+            .clone()
+            .oneshot(empty_request(
+                "POST",
+                format!("/uploads/{upload_id}/complete"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(incomplete.status(), StatusCode::CONFLICT);
+
+        app.clone()
+            .oneshot(put_chunk(upload_id, 1, &sha256_hex(chunks[1]), chunks[1]))
+            .await
+            .unwrap();
+        let completed = app
+            .clone()
+            .oneshot(empty_request(
+                "POST",
+                format!("/uploads/{upload_id}/complete"),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(completed.status(), StatusCode::CREATED);
+        let ack: RecordIngestAck = json_body(completed).await;
+        assert_eq!(ack.data_type, DataType::LidarScan);
+        assert_eq!(ack.record_count, 1);
+        assert_eq!(acks.try_recv().unwrap(), ack);
+
+        let record = {
+            let mut service = service.lock().await;
+            service.flush_batch().await.unwrap();
+            service
+                .storage
+                .load_data(&ack.record_id)
+                .await
+                .unwrap()
+                .unwrap()
+        };
+        let file_path = record
+            .file_path
+            .expect("uploaded record should point at its file");
+        assert_eq!(std::fs::read(file_path).unwrap(), contents);
+        assert_eq!(record.metadata.get("sha256"), Some(&sha256_hex(contents)));
+        let gone = app
+            .oneshot(empty_request("GET", format!("/uploads/{upload_id}")))
+            .await
+            .unwrap();
+        assert_eq!(gone.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn completing_upload_with_wrong_file_hash_is_rejected() {
+        let temp_dir = tempdir().unwrap();
+        let service = Arc::new(Mutex::new(
+            DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap(),
+        ));
+        let session_id = service
+            .lock()
+            .await
+            .start_session(Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let app = router(IngestApiState::new(
+            Arc::clone(&service),
+            upload_manager(temp_dir.path(), 8),
+        ));
+        let contents = b"frame-0001";
+        let ticket = start_upload(
+            &app,
+            session_id,
+            &upload_request("frame_0001.jpg", contents, sha256_hex(b"frame-0002")),
+        )
+        .await;
+        for (index, chunk) in contents.chunks(8).enumerate() {
+            let response = app
+                .clone()
+                .oneshot(put_chunk(
+                    ticket.upload_id,
+                    index as u32,
+                    &sha256_hex(chunk),
+                    chunk,
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let response = app
+            .oneshot(empty_request(
+                "POST",
+                format!("/uploads/{}/complete", ticket.upload_id),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let session = service
+            .lock()
+            .await
+            .get_session(&session_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(session.summary.record_count, 0);
+    }
+
+    #[tokio::test]
+    async fn battery_sessions_are_listed_with_history_and_publish_alerts() {
+        let temp_dir = tempdir().unwrap();
+        let service = Arc::new(Mutex::new(
+            DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap(),
+        ));
+        let state = IngestApiState::new(service, upload_manager(temp_dir.path(), 1024))
+            .with_battery_registry(
+                BatteryRegistry::new(crate::BatteryRegistryConfig {
+                    batteries: vec![crate::BatteryProfile {
+                        serial: "PK-11".to_string(),
+                        nominal_capacity_wh: 100.0,
+                    }],
+                    ..crate::BatteryRegistryConfig::default()
+                })
+                .unwrap(),
+            );
+        let mut alerts = state.subscribe_battery_alerts();
+        // No endpoint is listening, so the alert ends up dead-lettered.
+        let unreachable = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = unreachable.local_addr().unwrap();
+        drop(unreachable);
+        let (webhooks, _worker) = WebhookDispatcher::spawn(WebhookConfig {
+            endpoints: vec![WebhookEndpoint {
+                id: "fleet-ops".to_string(),
+                url: format!("http://{address}/battery"),
+                secret: "battery-secret".to_string(),
+                events: vec![WebhookEventKind::BatteryAlert],
+            }],
+            delivery: WebhookDeliveryPolicy {
+                max_retries: 1,
+                initial_backoff_ms: 1,
+                ..WebhookDeliveryPolicy::default()
+            },
+            queue_capacity: 16,
+        })
+        .unwrap();
+        let webhook = state.spawn_battery_alert_webhook(webhooks.clone());
+        let app = router(state);
+
+        let started_at = Utc::now();
+        for (serial, energy_delivered_wh) in [(Some("PK-11"), 60.0), (None, 60.0)] {
+            let summary = BatterySessionSummary {
+                session_id: Uuid::new_v4(),
+                battery_serial: serial.map(str::to_string),
+                started_at,
+                ended_at: started_at + chrono::Duration::minutes(20),
+                start_percent: 100.0,
+                end_percent: 20.0,
+                min_percent: None,
+                energy_delivered_wh,
+            };
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri("/batteries/sessions")
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .body(Body::from(serde_json::to_vec(&summary).unwrap()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let ack: BatterySessionAck = json_body(response).await;
+            assert_eq!(ack.attributed, serial.is_some());
+        }
+
+        // 60 Wh over 80% of the pack is 75 Wh, under 80% of nominal.
+        let alert = alerts.try_recv().unwrap();
+        assert_eq!(alert.serial, "PK-11");
+        assert_eq!(alert.kind, crate::BatteryAlertKind::CapacityBelowThreshold);
+
+        let response = app
+            .clone()
+            .oneshot(empty_request("GET", "/batteries".to_string()))
+            .await
+            .unwrap();
+        let fleet: BatteryFleetReport = json_body(response).await;
+        assert_eq!(fleet.sessions_without_serial, 1);
+        assert_eq!(fleet.batteries[0].estimated_capacity_wh, Some(75.0));
+
+        let response = app
+            .clone()
+            .oneshot(empty_request("GET", "/batteries/PK-11/history".to_string()))
+            .await
+            .unwrap();
+        let history: BatteryHistory = json_body(response).await;
+        assert_eq!(history.cycles.len(), 1);
+
+        let response = app
+            .oneshot(empty_request("GET", "/batteries/PK-99/history".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        tokio::time::timeout(Duration::from_secs(5), webhook)
+            .await
+            .unwrap()
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), webhooks.flush())
+            .await
+            .unwrap();
+        let dead_letters = webhooks.dead_letters();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].endpoint_id, "fleet-ops");
+        assert_eq!(dead_letters[0].attempts, 2);
+        assert_eq!(dead_letters[0].event.kind, WebhookEventKind::BatteryAlert);
+        assert_eq!(dead_letters[0].event.payload["serial"], "PK-11");
+    }
+}
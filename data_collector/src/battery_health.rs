@@ -0,0 +1,800 @@
+//! Battery pack registry: per-pack session history, inferred charge cycles,
+//! capacity fade and degradation alerts.
+//!
+//! Packs are identified by serial. Each flight session contributes its start
+//! and end charge, its deepest discharge and the energy the energy model says
+//! it drew. Sessions flown back to back on one charge belong to the same
+//! cycle; a session that starts well above where the previous one ended means
+//! the pack was recharged in between. A cycle's capacity is the energy it
+//! delivered divided by the share of the pack it used, and the fade is a
+//! least-squares line through the most recent cycle capacities.
+
+use crate::{DataPayload, FlightDataRecord, TelemetryPayload};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::schemas::WebSocketMessage;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Telemetry metadata key carrying the serial of the pack in use.
+pub const BATTERY_SERIAL_METADATA_KEY: &str = "battery_serial";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatteryProfile {
+    pub serial: String,
+    /// Rated capacity; packs without a profile are measured against their
+    /// first capacity estimate instead.
+    pub nominal_capacity_wh: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatteryRegistryConfig {
+    pub batteries: Vec<BatteryProfile>,
+    /// Most recent cycles with a capacity estimate that the fade fit uses.
+    pub fade_window_cycles: usize,
+    /// Alert once the estimated capacity falls below this share of nominal.
+    pub capacity_alert_percent: f64,
+    /// A session starting at least this many points above the previous
+    /// session's end charge starts a new cycle.
+    pub recharge_min_gain_percent: f64,
+    /// Cycles that used less of the pack than this are too shallow to
+    /// estimate capacity from.
+    pub min_capacity_estimate_depth_percent: f64,
+    /// A session draining faster than this multiple of the pack's recent
+    /// median rate is flagged as a possible bad cell.
+    pub discharge_rate_anomaly_ratio: f64,
+    /// Earlier sessions the median discharge rate needs before sessions are
+    /// checked against it.
+    pub discharge_rate_baseline_sessions: usize,
+}
+
+impl Default for BatteryRegistryConfig {
+    fn default() -> Self {
+        Self {
+            batteries: Vec::new(),
+            fade_window_cycles: 10,
+            capacity_alert_percent: 80.0,
+            recharge_min_gain_percent: 5.0,
+            min_capacity_estimate_depth_percent: 30.0,
+            discharge_rate_anomaly_ratio: 1.5,
+            discharge_rate_baseline_sessions: 3,
+        }
+    }
+}
+
+impl BatteryRegistryConfig {
+    pub fn validate(&self) -> Result<(), BatteryRegistryError> {
+        let invalid = |reason: String| Err(BatteryRegistryError::InvalidConfig(reason));
+        if self.fade_window_cycles < 2 {
+            return invalid("fade_window_cycles must be at least 2".to_string());
+        }
+        if !(self.capacity_alert_percent > 0.0 && self.capacity_alert_percent <= 100.0) {
+            return invalid("capacity_alert_percent must be in (0, 100]".to_string());
+        }
+        if !(self.recharge_min_gain_percent > 0.0 && self.recharge_min_gain_percent <= 100.0) {
+            return invalid("recharge_min_gain_percent must be in (0, 100]".to_string());
+        }
+        if !(0.0..=100.0).contains(&self.min_capacity_estimate_depth_percent) {
+            return invalid("min_capacity_estimate_depth_percent must be in [0, 100]".to_string());
+        }
+        if self.discharge_rate_anomaly_ratio.is_nan() || self.discharge_rate_anomaly_ratio <= 1.0 {
+            return invalid("discharge_rate_anomaly_ratio must be greater than 1".to_string());
+        }
+        if self.discharge_rate_baseline_sessions == 0 {
+            return invalid("discharge_rate_baseline_sessions must be at least 1".to_string());
+        }
+        for profile in &self.batteries {
+            if profile.serial.trim().is_empty() {
+                return invalid("battery profiles need a serial".to_string());
+            }
+            if !(profile.nominal_capacity_wh.is_finite() && profile.nominal_capacity_wh > 0.0) {
+                return invalid(format!(
+                    "battery {} nominal_capacity_wh must be positive",
+                    profile.serial
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What one flight session did to the pack it flew on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatterySessionSummary {
+    pub session_id: Uuid,
+    /// Sessions without a serial cannot be attributed and are only counted.
+    #[serde(default)]
+    pub battery_serial: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub start_percent: f64,
+    pub end_percent: f64,
+    /// Lowest charge seen during the session; the end charge when absent.
+    #[serde(default)]
+    pub min_percent: Option<f64>,
+    /// Energy drawn from the pack, from the energy model.
+    pub energy_delivered_wh: f64,
+}
+
+impl BatterySessionSummary {
+    /// Builds a summary from a session's telemetry records, reading the
+    /// serial from their [`BATTERY_SERIAL_METADATA_KEY`] metadata. Returns
+    /// `None` when the records hold no telemetry.
+    pub fn from_telemetry(
+        session_id: Uuid,
+        records: &[FlightDataRecord],
+        energy_delivered_wh: f64,
+    ) -> Option<Self> {
+        let mut samples: Vec<(DateTime<Utc>, f64, &FlightDataRecord)> = records
+            .iter()
+            .filter_map(|record| match record.payload {
+                DataPayload::Telemetry(TelemetryPayload { battery_level, .. }) => {
+                    Some((record.timestamp, f64::from(battery_level) * 100.0, record))
+                }
+                _ => None,
+            })
+            .collect();
+        samples.sort_by_key(|(timestamp, _, _)| *timestamp);
+        let (first, last) = (samples.first()?, samples.last()?);
+        let battery_serial = samples.iter().find_map(|(_, _, record)| {
+            record
+                .metadata
+                .get(BATTERY_SERIAL_METADATA_KEY)
+                .map(|serial| serial.trim())
+                .filter(|serial| !serial.is_empty())
+                .map(str::to_string)
+        });
+
+        Some(Self {
+            session_id,
+            battery_serial,
+            started_at: first.0,
+            ended_at: last.0,
+            start_percent: first.1,
+            end_percent: last.1,
+            min_percent: samples
+                .iter()
+                .map(|(_, percent, _)| *percent)
+                .reduce(f64::min),
+            energy_delivered_wh,
+        })
+    }
+
+    pub fn deepest_discharge_percent(&self) -> f64 {
+        self.min_percent
+            .map_or(self.end_percent, |min| min.min(self.end_percent))
+    }
+
+    pub fn consumed_percent(&self) -> f64 {
+        self.start_percent - self.end_percent
+    }
+
+    /// Charge used per minute of flight; `None` for a zero-length session.
+    pub fn discharge_rate_percent_per_minute(&self) -> Option<f64> {
+        let minutes = (self.ended_at - self.started_at).num_milliseconds() as f64 / 60_000.0;
+        (minutes > 0.0).then(|| self.consumed_percent() / minutes)
+    }
+
+    fn validate(&self) -> Result<(), BatteryRegistryError> {
+        let invalid = |reason: &str| {
+            Err(BatteryRegistryError::InvalidSession {
+                session_id: self.session_id,
+                reason: reason.to_string(),
+            })
+        };
+        let percent = |value: f64| (0.0..=100.0).contains(&value);
+        if self.ended_at < self.started_at {
+            return invalid("session ends before it starts");
+        }
+        if !percent(self.start_percent) || !percent(self.end_percent) {
+            return invalid("charge percentages must be within 0-100");
+        }
+        if self.end_percent > self.start_percent {
+            return invalid("session ends with more charge than it started with");
+        }
+        if self
+            .min_percent
+            .is_some_and(|min| !percent(min) || min > self.start_percent)
+        {
+            return invalid("minimum charge must be within 0-100 and not above the start");
+        }
+        if !(self.energy_delivered_wh.is_finite() && self.energy_delivered_wh >= 0.0) {
+            return invalid("energy delivered must be a non-negative number");
+        }
+        Ok(())
+    }
+}
+
+/// Sessions flown on one charge.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChargeCycle {
+    /// 1-based, in flight order.
+    pub cycle: u32,
+    pub session_ids: Vec<Uuid>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub start_percent: f64,
+    pub end_percent: f64,
+    pub deepest_discharge_percent: f64,
+    pub consumed_percent: f64,
+    pub energy_delivered_wh: f64,
+    /// Full-pack capacity implied by the cycle; `None` when the cycle was
+    /// too shallow to estimate it.
+    pub estimated_capacity_wh: Option<f64>,
+}
+
+/// Least-squares line through recent cycle capacity estimates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CapacityFade {
+    pub cycles_used: usize,
+    pub slope_wh_per_cycle: f64,
+    /// Capacity lost per cycle as a share of nominal; negative when the
+    /// estimates are rising.
+    pub fade_percent_per_cycle: f64,
+    /// Fitted capacity at the latest cycle.
+    pub fitted_capacity_wh: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryHealthStatus {
+    Healthy,
+    Degraded,
+    InsufficientData,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatteryHealthReport {
+    pub serial: String,
+    pub nominal_capacity_wh: Option<f64>,
+    pub session_count: usize,
+    pub cycle_count: u32,
+    pub total_energy_delivered_wh: f64,
+    pub deepest_discharge_percent: Option<f64>,
+    pub latest_capacity_wh: Option<f64>,
+    /// Fitted capacity when a fade is available, the latest estimate
+    /// otherwise.
+    pub estimated_capacity_wh: Option<f64>,
+    pub capacity_percent: Option<f64>,
+    pub fade: Option<CapacityFade>,
+    /// Cycles until the fitted line crosses the alert threshold; `None` when
+    /// it is not falling or has already crossed.
+    pub cycles_to_alert_threshold: Option<f64>,
+    pub status: BatteryHealthStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatteryHistory {
+    pub serial: String,
+    pub sessions: Vec<BatterySessionSummary>,
+    pub cycles: Vec<ChargeCycle>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatteryFleetReport {
+    pub batteries: Vec<BatteryHealthReport>,
+    pub sessions_without_serial: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryAlertKind {
+    CapacityBelowThreshold,
+    DischargeRateAnomaly,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatteryAlert {
+    pub serial: String,
+    pub kind: BatteryAlertKind,
+    pub session_id: Uuid,
+    pub raised_at: DateTime<Utc>,
+    pub message: String,
+}
+
+impl BatteryAlert {
+    pub fn to_system_status(&self) -> WebSocketMessage {
+        WebSocketMessage::SystemStatus {
+            status: "warn".to_string(),
+            message: self.message.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, thiserror::Error, PartialEq)]
+pub enum BatteryRegistryError {
+    #[error("invalid battery registry config: {0}")]
+    InvalidConfig(String),
+    #[error("invalid battery session {session_id}: {reason}")]
+    InvalidSession { session_id: Uuid, reason: String },
+    #[error("battery {serial} already holds session {session_id}")]
+    DuplicateSession { serial: String, session_id: Uuid },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BatteryRecord {
+    nominal_capacity_wh: Option<f64>,
+    /// Ordered by start time.
+    sessions: Vec<BatterySessionSummary>,
+    capacity_alert_active: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatteryRegistry {
+    config: BatteryRegistryConfig,
+    batteries: BTreeMap<String, BatteryRecord>,
+    sessions_without_serial: u64,
+}
+
+impl BatteryRegistry {
+    pub fn new(config: BatteryRegistryConfig) -> Result<Self, BatteryRegistryError> {
+        config.validate()?;
+        let batteries = config
+            .batteries
+            .iter()
+            .map(|profile| {
+                (
+                    profile.serial.trim().to_string(),
+                    BatteryRecord {
+                        nominal_capacity_wh: Some(profile.nominal_capacity_wh),
+                        ..BatteryRecord::default()
+                    },
+                )
+            })
+            .collect();
+        Ok(Self {
+            config,
+            batteries,
+            sessions_without_serial: 0,
+        })
+    }
+
+    pub fn config(&self) -> &BatteryRegistryConfig {
+        &self.config
+    }
+
+    pub fn sessions_without_serial(&self) -> u64 {
+        self.sessions_without_serial
+    }
+
+    /// Adds a session to its pack's history and returns any alerts it
+    /// raised. The capacity alert fires once when the estimate first drops
+    /// below the threshold and re-arms if it recovers.
+    pub fn record_session(
+        &mut self,
+        summary: BatterySessionSummary,
+    ) -> Result<Vec<BatteryAlert>, BatteryRegistryError> {
+        summary.validate()?;
+        let Some(serial) = summary
+            .battery_serial
+            .as_deref()
+            .map(str::trim)
+            .filter(|serial| !serial.is_empty())
+            .map(str::to_string)
+        else {
+            self.sessions_without_serial += 1;
+            return Ok(Vec::new());
+        };
+
+        let record = self.batteries.entry(serial.clone()).or_default();
+        if record
+            .sessions
+            .iter()
+            .any(|session| session.session_id == summary.session_id)
+        {
+            return Err(BatteryRegistryError::DuplicateSession {
+                serial,
+                session_id: summary.session_id,
+            });
+        }
+        let position = record
+            .sessions
+            .partition_point(|session| session.started_at <= summary.started_at);
+        record.sessions.insert(position, summary.clone());
+
+        let mut alerts = Vec::new();
+        if let Some(alert) = discharge_rate_alert(&serial, record, position, &self.config) {
+            alerts.push(alert);
+        }
+
+        let report = health_report(&serial, record, &self.config);
+        let below_threshold = report.status == BatteryHealthStatus::Degraded;
+        if below_threshold && !record.capacity_alert_active {
+            alerts.push(BatteryAlert {
+                serial: serial.clone(),
+                kind: BatteryAlertKind::CapacityBelowThreshold,
+                session_id: summary.session_id,
+                raised_at: summary.ended_at,
+                message: format!(
+                    "Battery {serial} capacity estimated at {:.0}% of nominal ({:.1} Wh), below the {:.0}% threshold",
+                    report.capacity_percent.unwrap_or_default(),
+                    report.estimated_capacity_wh.unwrap_or_default(),
+                    self.config.capacity_alert_percent
+                ),
+            });
+        }
+        record.capacity_alert_active = below_threshold;
+        Ok(alerts)
+    }
+
+    pub fn report(&self, serial: &str) -> Option<BatteryHealthReport> {
+        self.batteries
+            .get(serial)
+            .map(|record| health_report(serial, record, &self.config))
+    }
+
+    pub fn fleet_report(&self) -> BatteryFleetReport {
+        BatteryFleetReport {
+            batteries: self
+                .batteries
+                .iter()
+                .map(|(serial, record)| health_report(serial, record, &self.config))
+                .collect(),
+            sessions_without_serial: self.sessions_without_serial,
+        }
+    }
+
+    pub fn history(&self, serial: &str) -> Option<BatteryHistory> {
+        self.batteries.get(serial).map(|record| BatteryHistory {
+            serial: serial.to_string(),
+            sessions: record.sessions.clone(),
+            cycles: infer_cycles(&record.sessions, &self.config),
+        })
+    }
+}
+
+/// Groups sessions, in flight order, into charge cycles.
+pub fn infer_cycles(
+    sessions: &[BatterySessionSummary],
+    config: &BatteryRegistryConfig,
+) -> Vec<ChargeCycle> {
+    let mut cycles: Vec<ChargeCycle> = Vec::new();
+    for session in sessions {
+        let recharged = cycles.last().is_none_or(|cycle| {
+            session.start_percent >= cycle.end_percent + config.recharge_min_gain_percent
+        });
+        if recharged {
+            cycles.push(ChargeCycle {
+                cycle: cycles.len() as u32 + 1,
+                session_ids: Vec::new(),
+                started_at: session.started_at,
+                ended_at: session.ended_at,
+                start_percent: session.start_percent,
+                end_percent: session.end_percent,
+                deepest_discharge_percent: session.deepest_discharge_percent(),
+                consumed_percent: 0.0,
+                energy_delivered_wh: 0.0,
+                estimated_capacity_wh: None,
+            });
+        }
+        let cycle = cycles.last_mut().expect("a cycle was just ensured");
+        cycle.session_ids.push(session.session_id);
+        cycle.ended_at = session.ended_at;
+        cycle.end_percent = session.end_percent;
+        cycle.deepest_discharge_percent = cycle
+            .deepest_discharge_percent
+            .min(session.deepest_discharge_percent());
+        cycle.consumed_percent += session.consumed_percent();
+        cycle.energy_delivered_wh += session.energy_delivered_wh;
+    }
+
+    for cycle in &mut cycles {
+        if cycle.consumed_percent > 0.0
+            && cycle.consumed_percent >= config.min_capacity_estimate_depth_percent
+        {
+            cycle.estimated_capacity_wh =
+                Some(cycle.energy_delivered_wh / (cycle.consumed_percent / 100.0));
+        }
+    }
+    cycles
+}
+
+/// Fits capacity against cycle number over the last `window` cycles that
+/// have an estimate. Needs at least two.
+pub fn fit_capacity_fade(
+    cycles: &[ChargeCycle],
+    window: usize,
+    nominal_capacity_wh: f64,
+) -> Option<CapacityFade> {
+    let mut points: Vec<(f64, f64)> = cycles
+        .iter()
+        .filter_map(|cycle| {
+            cycle
+                .estimated_capacity_wh
+                .map(|capacity| (f64::from(cycle.cycle), capacity))
+        })
+        .collect();
+    if points.len() > window {
+        points.drain(..points.len() - window);
+    }
+    if points.len() < 2 {
+        return None;
+    }
+
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let (covariance, variance) = points.iter().fold((0.0, 0.0), |(cov, var), (x, y)| {
+        (
+            cov + (x - mean_x) * (y - mean_y),
+            var + (x - mean_x).powi(2),
+        )
+    });
+    let slope = covariance / variance;
+    let latest_cycle = points.last().map(|(x, _)| *x)?;
+    Some(CapacityFade {
+        cycles_used: points.len(),
+        slope_wh_per_cycle: slope,
+        fade_percent_per_cycle: -slope / nominal_capacity_wh * 100.0,
+        fitted_capacity_wh: mean_y + slope * (latest_cycle - mean_x),
+    })
+}
+
+fn health_report(
+    serial: &str,
+    record: &BatteryRecord,
+    config: &BatteryRegistryConfig,
+) -> BatteryHealthReport {
+    let cycles = infer_cycles(&record.sessions, config);
+    let latest_capacity_wh = cycles
+        .iter()
+        .rev()
+        .find_map(|cycle| cycle.estimated_capacity_wh);
+    let nominal_capacity_wh = record
+        .nominal_capacity_wh
+        .or_else(|| cycles.iter().find_map(|cycle| cycle.estimated_capacity_wh));
+    let fade = nominal_capacity_wh
+        .and_then(|nominal| fit_capacity_fade(&cycles, config.fade_window_cycles, nominal));
+    let estimated_capacity_wh = fade
+        .map(|fade| fade.fitted_capacity_wh)
+        .or(latest_capacity_wh);
+    let capacity_percent = estimated_capacity_wh
+        .zip(nominal_capacity_wh)
+        .map(|(estimated, nominal)| estimated / nominal * 100.0);
+    let cycles_to_alert_threshold = fade.zip(nominal_capacity_wh).and_then(|(fade, nominal)| {
+        let threshold_wh = nominal * config.capacity_alert_percent / 100.0;
+        (fade.slope_wh_per_cycle < 0.0 && fade.fitted_capacity_wh > threshold_wh)
+            .then(|| (fade.fitted_capacity_wh - threshold_wh) / -fade.slope_wh_per_cycle)
+    });
+    let status = match capacity_percent {
+        Some(percent) if percent < config.capacity_alert_percent => BatteryHealthStatus::Degraded,
+        Some(_) => BatteryHealthStatus::Healthy,
+        None => BatteryHealthStatus::InsufficientData,
+    };
+
+    BatteryHealthReport {
+        serial: serial.to_string(),
+        nominal_capacity_wh,
+        session_count: record.sessions.len(),
+        cycle_count: cycles.len() as u32,
+        total_energy_delivered_wh: record
+            .sessions
+            .iter()
+            .map(|session| session.energy_delivered_wh)
+            .sum(),
+        deepest_discharge_percent: record
+            .sessions
+            .iter()
+            .map(BatterySessionSummary::deepest_discharge_percent)
+            .reduce(f64::min),
+        latest_capacity_wh,
+        estimated_capacity_wh,
+        capacity_percent,
+        fade,
+        cycles_to_alert_threshold,
+        status,
+    }
+}
+
+/// Compares the session at `position` with the median rate of the sessions
+/// flown on the pack just before it.
+fn discharge_rate_alert(
+    serial: &str,
+    record: &BatteryRecord,
+    position: usize,
+    config: &BatteryRegistryConfig,
+) -> Option<BatteryAlert> {
+    let session = &record.sessions[position];
+    let rate = session.discharge_rate_percent_per_minute()?;
+    let mut baseline: Vec<f64> = record.sessions[..position]
+        .iter()
+        .rev()
+        .filter_map(BatterySessionSummary::discharge_rate_percent_per_minute)
+        .take(config.discharge_rate_baseline_sessions)
+        .collect();
+    if baseline.len() < config.discharge_rate_baseline_sessions {
+        return None;
+    }
+    baseline.sort_by(f64::total_cmp);
+    let middle = baseline.len() / 2;
+    let median = if baseline.len().is_multiple_of(2) {
+        (baseline[middle - 1] + baseline[middle]) / 2.0
+    } else {
+        baseline[middle]
+    };
+    if median <= 0.0 || rate <= median * config.discharge_rate_anomaly_ratio {
+        return None;
+    }
+
+    Some(BatteryAlert {
+        serial: serial.to_string(),
+        kind: BatteryAlertKind::DischargeRateAnomaly,
+        session_id: session.session_id,
+        raised_at: session.ended_at,
+        message: format!(
+            "Battery {serial} drained at {rate:.2}%/min, {:.1}x its recent median of {median:.2}%/min; check for a bad cell",
+            rate / median
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn session(
+        serial: Option<&str>,
+        started_at: DateTime<Utc>,
+        minutes: i64,
+        start_percent: f64,
+        end_percent: f64,
+        energy_delivered_wh: f64,
+    ) -> BatterySessionSummary {
+        BatterySessionSummary {
+            session_id: Uuid::new_v4(),
+            battery_serial: serial.map(str::to_string),
+            started_at,
+            ended_at: started_at + Duration::minutes(minutes),
+            start_percent,
+            end_percent,
+            min_percent: None,
+            energy_delivered_wh,
+        }
+    }
+
+    fn start() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-05-01T08:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn declining_capacity_is_fitted_and_alerts_once_below_threshold() {
+        let mut registry = BatteryRegistry::new(BatteryRegistryConfig {
+            batteries: vec![BatteryProfile {
+                serial: "PK-07".to_string(),
+                nominal_capacity_wh: 100.0,
+            }],
+            ..BatteryRegistryConfig::default()
+        })
+        .unwrap();
+
+        // One full 100% -> 20% cycle a day, losing 3 Wh of capacity each
+        // time: 100, 97, ..., 82, then 79 on the eighth cycle.
+        let mut first_alert_cycle = None;
+        for cycle in 0..10 {
+            let capacity = 100.0 - 3.0 * f64::from(cycle);
+            let summary = session(
+                Some("PK-07"),
+                start() + Duration::days(i64::from(cycle)),
+                20,
+                100.0,
+                20.0,
+                capacity * 0.8,
+            );
+            let alerts = registry.record_session(summary).unwrap();
+            if !alerts.is_empty() {
+                assert_eq!(alerts.len(), 1);
+                assert_eq!(alerts[0].kind, BatteryAlertKind::CapacityBelowThreshold);
+                assert!(first_alert_cycle.is_none(), "capacity alert repeated");
+                first_alert_cycle = Some(cycle + 1);
+            }
+        }
+        assert_eq!(first_alert_cycle, Some(8));
+
+        let report = registry.report("PK-07").unwrap();
+        assert_eq!(report.cycle_count, 10);
+        assert_eq!(report.status, BatteryHealthStatus::Degraded);
+        let fade = report.fade.unwrap();
+        assert_eq!(fade.cycles_used, 10);
+        assert!((fade.slope_wh_per_cycle + 3.0).abs() < 1e-9);
+        assert!((fade.fade_percent_per_cycle - 3.0).abs() < 1e-9);
+        assert!((fade.fitted_capacity_wh - 73.0).abs() < 1e-9);
+        assert_eq!(report.cycles_to_alert_threshold, None);
+
+        let history = registry.history("PK-07").unwrap();
+        assert_eq!(history.cycles.len(), 10);
+        assert!((history.cycles[1].estimated_capacity_wh.unwrap() - 97.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn back_to_back_sessions_share_a_cycle_and_unserialled_sessions_are_counted() {
+        let mut registry = BatteryRegistry::default();
+        let day = start();
+
+        registry
+            .record_session(session(Some("PK-01"), day, 10, 100.0, 70.0, 27.0))
+            .unwrap();
+        // Resumed on the same charge after a field change.
+        registry
+            .record_session(session(
+                Some("PK-01"),
+                day + Duration::minutes(30),
+                10,
+                71.0,
+                40.0,
+                27.9,
+            ))
+            .unwrap();
+        registry
+            .record_session(session(
+                Some("PK-01"),
+                day + Duration::hours(4),
+                10,
+                98.0,
+                68.0,
+                27.0,
+            ))
+            .unwrap();
+        registry
+            .record_session(session(None, day, 10, 100.0, 60.0, 36.0))
+            .unwrap();
+
+        let history = registry.history("PK-01").unwrap();
+        assert_eq!(history.cycles.len(), 2);
+        assert_eq!(history.cycles[0].session_ids.len(), 2);
+        assert!((history.cycles[0].consumed_percent - 61.0).abs() < 1e-9);
+        assert!((history.cycles[0].estimated_capacity_wh.unwrap() - 90.0).abs() < 1e-9);
+        assert_eq!(history.cycles[0].deepest_discharge_percent, 40.0);
+        assert_eq!(registry.sessions_without_serial(), 1);
+
+        let fleet = registry.fleet_report();
+        assert_eq!(fleet.batteries.len(), 1);
+        assert_eq!(fleet.sessions_without_serial, 1);
+        // No profile: nominal is the first cycle's estimate.
+        assert_eq!(fleet.batteries[0].nominal_capacity_wh, Some(90.0));
+    }
+
+    #[test]
+    fn fast_drain_against_the_recent_median_flags_a_possible_bad_cell() {
+        let mut registry = BatteryRegistry::default();
+        for day in 0..3 {
+            let alerts = registry
+                .record_session(session(
+                    Some("PK-03"),
+                    start() + Duration::days(day),
+                    20,
+                    100.0,
+                    40.0,
+                    54.0,
+                ))
+                .unwrap();
+            assert!(alerts.is_empty());
+        }
+
+        // 3%/min normally; this flight drains 60 points in 10 minutes.
+        let alerts = registry
+            .record_session(session(
+                Some("PK-03"),
+                start() + Duration::days(3),
+                10,
+                100.0,
+                40.0,
+                54.0,
+            ))
+            .unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, BatteryAlertKind::DischargeRateAnomaly);
+        assert!(matches!(
+            alerts[0].to_system_status(),
+            WebSocketMessage::SystemStatus { ref status, .. } if status == "warn"
+        ));
+
+        let error = registry
+            .record_session(session(Some("PK-03"), start(), 10, 40.0, 60.0, 5.0))
+            .unwrap_err();
+        assert!(matches!(error, BatteryRegistryError::InvalidSession { .. }));
+    }
+}
@@ -0,0 +1,695 @@
+//! Regulatory compliance bundle for a single flight session.
+//!
+//! The bundle is a zip holding the linked mission as GeoJSON, the flown track as
+//! GPX, a human-readable summary page and a `manifest.json` that records a SHA-256
+//! for every artifact. Anything that could not be produced is listed in the
+//! manifest (and on the summary page) instead of being silently left out.
+
+use crate::export::xml_escape;
+use crate::{haversine_distance_m, FlightDataRecord, FlightSession};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared::config::OperatorConfig;
+use shared::schemas::{GpsCoords, Mission};
+use std::fmt::Write as _;
+use std::io::{Seek, Write};
+use thiserror::Error;
+use uuid::Uuid;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+pub const MISSION_ARTIFACT: &str = "mission.geojson";
+pub const TRACK_ARTIFACT: &str = "track.gpx";
+pub const SUMMARY_ARTIFACT: &str = "summary.html";
+pub const MANIFEST_ARTIFACT: &str = "manifest.json";
+
+/// Height above home the aircraft must exceed to count as airborne.
+const AIRBORNE_THRESHOLD_M: f64 = 1.0;
+
+#[derive(Debug, Error)]
+pub enum ComplianceExportError {
+    #[error("mission {provided} does not match mission {linked} linked to session {session_id}")]
+    MissionMismatch {
+        session_id: Uuid,
+        linked: Uuid,
+        provided: Uuid,
+    },
+    #[error("failed to serialize compliance artifact: {0}")]
+    Serialize(#[from] serde_json::Error),
+    #[error("failed to write compliance bundle: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("failed to write compliance bundle: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// An emergency event or safety violation raised during the flight.
+///
+/// The collector does not see the swarm controller's event types, so callers map
+/// `EmergencyEvent`s and `SafetyViolation`s into this shape.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComplianceIncident {
+    pub timestamp: DateTime<Utc>,
+    pub drone_id: Option<Uuid>,
+    pub kind: String,
+    pub severity: String,
+    pub description: String,
+}
+
+/// Inputs the collector does not own itself.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComplianceBundleRequest {
+    pub operator: OperatorConfig,
+    /// Mission linked to the session, if the caller could resolve it.
+    pub mission: Option<Mission>,
+    /// `None` means no incident log was available, which is reported as missing;
+    /// an empty list means the flight had no incidents.
+    pub incidents: Option<Vec<ComplianceIncident>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FlightSummary {
+    pub track_point_count: usize,
+    pub home: Option<GpsCoords>,
+    pub takeoff_time: Option<DateTime<Utc>>,
+    pub landing_time: Option<DateTime<Utc>>,
+    /// Height above the home altitude; terrain between waypoints is not modelled.
+    pub max_altitude_agl_m: Option<f64>,
+    pub max_distance_from_home_m: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestArtifact {
+    pub name: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComplianceManifest {
+    pub session_id: Uuid,
+    pub mission_id: Option<Uuid>,
+    pub generated_at: DateTime<Utc>,
+    pub summary: FlightSummary,
+    pub artifacts: Vec<ManifestArtifact>,
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComplianceBundle {
+    pub manifest: ComplianceManifest,
+    artifacts: Vec<(String, Vec<u8>)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TrackPoint {
+    timestamp: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+    altitude_m: f64,
+}
+
+impl ComplianceBundle {
+    pub fn build(
+        session: &FlightSession,
+        records: &[FlightDataRecord],
+        request: &ComplianceBundleRequest,
+    ) -> Result<Self, ComplianceExportError> {
+        let mut missing = Vec::new();
+
+        let mission = match (session.mission_id, &request.mission) {
+            (Some(linked), Some(mission)) if mission.id != linked => {
+                return Err(ComplianceExportError::MissionMismatch {
+                    session_id: session.id,
+                    linked,
+                    provided: mission.id,
+                });
+            }
+            (Some(_), Some(mission)) => Some(mission),
+            (Some(linked), None) => {
+                missing.push(format!(
+                    "{MISSION_ARTIFACT}: mission {linked} is linked to the session but was not provided"
+                ));
+                None
+            }
+            (None, _) => {
+                missing.push(format!(
+                    "{MISSION_ARTIFACT}: session is not linked to a mission"
+                ));
+                None
+            }
+        };
+
+        let track = track_points(records);
+        let home = mission
+            .map(|mission| mission.home_position.clone())
+            .or_else(|| {
+                track.first().map(|point| GpsCoords {
+                    latitude: point.latitude,
+                    longitude: point.longitude,
+                    altitude: point.altitude_m,
+                })
+            });
+        let summary = summarize_track(&track, home);
+        if track.is_empty() {
+            missing.push(format!(
+                "{TRACK_ARTIFACT}: session has no telemetry records; takeoff/landing times, \
+                 max altitude and max distance from home are unavailable"
+            ));
+        } else if summary.takeoff_time.is_none() {
+            missing.push(format!(
+                "takeoff/landing times: aircraft never climbed above {AIRBORNE_THRESHOLD_M} m over home"
+            ));
+        }
+
+        for (label, value) in [
+            ("name", &request.operator.name),
+            ("organization", &request.operator.organization),
+            ("license id", &request.operator.license_id),
+            ("contact", &request.operator.contact),
+        ] {
+            if value.trim().is_empty() {
+                missing.push(format!("operator {label}: not configured"));
+            }
+        }
+        if request.incidents.is_none() {
+            missing.push(
+                "emergency events / safety violations: no incident log was provided".to_string(),
+            );
+        }
+
+        let mut artifacts = Vec::new();
+        if let Some(mission) = mission {
+            artifacts.push((
+                MISSION_ARTIFACT.to_string(),
+                serde_json::to_vec_pretty(&mission_geojson(mission))?,
+            ));
+        }
+        if !track.is_empty() {
+            artifacts.push((
+                TRACK_ARTIFACT.to_string(),
+                track_gpx(session, &track).into_bytes(),
+            ));
+        }
+        artifacts.push((
+            SUMMARY_ARTIFACT.to_string(),
+            summary_html(session, request, &summary, &missing).into_bytes(),
+        ));
+
+        let manifest = ComplianceManifest {
+            session_id: session.id,
+            mission_id: session.mission_id,
+            generated_at: Utc::now(),
+            summary,
+            artifacts: artifacts
+                .iter()
+                .map(|(name, contents)| ManifestArtifact {
+                    name: name.clone(),
+                    sha256: format!("{:x}", Sha256::digest(contents)),
+                    size_bytes: contents.len() as u64,
+                })
+                .collect(),
+            missing,
+        };
+
+        Ok(Self {
+            manifest,
+            artifacts,
+        })
+    }
+
+    pub fn artifact(&self, name: &str) -> Option<&[u8]> {
+        self.artifacts
+            .iter()
+            .find(|(artifact, _)| artifact == name)
+            .map(|(_, contents)| contents.as_slice())
+    }
+
+    /// Writes every artifact followed by `manifest.json`.
+    pub fn write_zip<W: Write + Seek>(&self, writer: W) -> Result<W, ComplianceExportError> {
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(writer);
+        for (name, contents) in &self.artifacts {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(contents)?;
+        }
+        zip.start_file(MANIFEST_ARTIFACT, options)?;
+        zip.write_all(&serde_json::to_vec_pretty(&self.manifest)?)?;
+        Ok(zip.finish()?)
+    }
+}
+
+fn track_points(records: &[FlightDataRecord]) -> Vec<TrackPoint> {
+    let mut points: Vec<TrackPoint> = records
+        .iter()
+        .filter_map(|record| {
+            let position = record.payload.as_telemetry()?.position;
+            Some(TrackPoint {
+                timestamp: record.timestamp,
+                latitude: position.0,
+                longitude: position.1,
+                altitude_m: f64::from(position.2),
+            })
+        })
+        .collect();
+    points.sort_by_key(|point| point.timestamp);
+    points
+}
+
+fn summarize_track(track: &[TrackPoint], home: Option<GpsCoords>) -> FlightSummary {
+    let Some(home) = home else {
+        return FlightSummary::default();
+    };
+    if track.is_empty() {
+        return FlightSummary {
+            home: Some(home),
+            ..FlightSummary::default()
+        };
+    }
+
+    let height = |point: &TrackPoint| point.altitude_m - home.altitude;
+    let mut airborne = track
+        .iter()
+        .filter(|point| height(point) > AIRBORNE_THRESHOLD_M);
+    let takeoff_time = airborne.next().map(|point| point.timestamp);
+    let landing_time = airborne
+        .next_back()
+        .map(|point| point.timestamp)
+        .or(takeoff_time);
+
+    FlightSummary {
+        track_point_count: track.len(),
+        takeoff_time,
+        landing_time,
+        max_altitude_agl_m: track.iter().map(height).reduce(f64::max),
+        max_distance_from_home_m: track
+            .iter()
+            .map(|point| {
+                haversine_distance_m(
+                    home.latitude,
+                    home.longitude,
+                    point.latitude,
+                    point.longitude,
+                )
+            })
+            .reduce(f64::max),
+        home: Some(home),
+    }
+}
+
+fn mission_geojson(mission: &Mission) -> serde_json::Value {
+    let mut waypoints = mission.waypoints.clone();
+    waypoints.sort_by_key(|waypoint| waypoint.sequence);
+    let coordinates = |position: &GpsCoords| {
+        serde_json::json!([position.longitude, position.latitude, position.altitude])
+    };
+
+    let mut features = vec![
+        serde_json::json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": coordinates(&mission.home_position) },
+            "properties": { "role": "home" }
+        }),
+        serde_json::json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "LineString",
+                "coordinates": waypoints
+                    .iter()
+                    .map(|waypoint| coordinates(&waypoint.position))
+                    .collect::<Vec<_>>()
+            },
+            "properties": { "role": "route", "waypoint_count": waypoints.len() }
+        }),
+    ];
+    features.extend(waypoints.iter().map(|waypoint| {
+        serde_json::json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": coordinates(&waypoint.position) },
+            "properties": {
+                "role": "waypoint",
+                "sequence": waypoint.sequence,
+                "command": waypoint.command,
+                "auto_continue": waypoint.auto_continue
+            }
+        })
+    }));
+
+    serde_json::json!({
+        "type": "FeatureCollection",
+        "properties": {
+            "mission_id": mission.id,
+            "name": mission.name,
+            "created_at": mission.created_at
+        },
+        "features": features
+    })
+}
+
+fn track_gpx(session: &FlightSession, track: &[TrackPoint]) -> String {
+    let mut gpx = String::new();
+    gpx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    gpx.push_str(
+        "<gpx version=\"1.1\" creator=\"agbot data_collector\" \
+         xmlns=\"http://www.topografix.com/GPX/1/1\">\n",
+    );
+    let _ = writeln!(
+        gpx,
+        "  <trk>\n    <name>{}</name>\n    <trkseg>",
+        xml_escape(&format!("session {}", session.id))
+    );
+    for point in track {
+        let _ = writeln!(
+            gpx,
+            "      <trkpt lat=\"{:.7}\" lon=\"{:.7}\"><ele>{:.2}</ele><time>{}</time></trkpt>",
+            point.latitude,
+            point.longitude,
+            point.altitude_m,
+            point.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+        );
+    }
+    gpx.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+    gpx
+}
+
+fn summary_html(
+    session: &FlightSession,
+    request: &ComplianceBundleRequest,
+    summary: &FlightSummary,
+    missing: &[String],
+) -> String {
+    let or_unavailable = |value: Option<String>| value.unwrap_or_else(|| "unavailable".to_string());
+    let operator = |value: &str| {
+        if value.trim().is_empty() {
+            "not configured".to_string()
+        } else {
+            xml_escape(value)
+        }
+    };
+    let time = |value: Option<DateTime<Utc>>| {
+        or_unavailable(value.map(|value| value.to_rfc3339_opts(SecondsFormat::Secs, true)))
+    };
+    let meters = |value: Option<f64>| or_unavailable(value.map(|value| format!("{value:.1} m")));
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(
+        html,
+        "<title>Flight compliance summary {}</title>\n</head>\n<body>",
+        session.id
+    );
+    let _ = writeln!(html, "<h1>Flight compliance summary</h1>");
+
+    let _ = writeln!(html, "<h2>Operator</h2>\n<table>");
+    for (label, value) in [
+        ("Name", &request.operator.name),
+        ("Organization", &request.operator.organization),
+        ("License", &request.operator.license_id),
+        ("Contact", &request.operator.contact),
+    ] {
+        let _ = writeln!(
+            html,
+            "<tr><th>{label}</th><td>{}</td></tr>",
+            operator(value)
+        );
+    }
+    html.push_str("</table>\n");
+
+    let _ = writeln!(html, "<h2>Flight</h2>\n<table>");
+    for (label, value) in [
+        ("Session", session.id.to_string()),
+        ("Drone", session.drone_id.to_string()),
+        (
+            "Mission",
+            or_unavailable(session.mission_id.map(|id| id.to_string())),
+        ),
+        ("Takeoff", time(summary.takeoff_time)),
+        ("Landing", time(summary.landing_time)),
+        (
+            "Max altitude AGL (above home)",
+            meters(summary.max_altitude_agl_m),
+        ),
+        (
+            "Max distance from home",
+            meters(summary.max_distance_from_home_m),
+        ),
+        ("Track points", summary.track_point_count.to_string()),
+    ] {
+        let _ = writeln!(html, "<tr><th>{label}</th><td>{value}</td></tr>");
+    }
+    html.push_str("</table>\n");
+
+    let _ = writeln!(html, "<h2>Emergency events and safety violations</h2>");
+    match &request.incidents {
+        None => html.push_str("<p>No incident log was provided.</p>\n"),
+        Some(incidents) if incidents.is_empty() => {
+            html.push_str("<p>No emergency events or safety violations were recorded.</p>\n")
+        }
+        Some(incidents) => {
+            html.push_str(
+                "<table>\n<tr><th>Time</th><th>Drone</th><th>Kind</th><th>Severity</th>\
+                 <th>Description</th></tr>\n",
+            );
+            for incident in incidents {
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    incident
+                        .timestamp
+                        .to_rfc3339_opts(SecondsFormat::Secs, true),
+                    incident
+                        .drone_id
+                        .map(|id| id.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    xml_escape(&incident.kind),
+                    xml_escape(&incident.severity),
+                    xml_escape(&incident.description)
+                );
+            }
+            html.push_str("</table>\n");
+        }
+    }
+
+    if !missing.is_empty() {
+        let _ = writeln!(html, "<h2>Not included</h2>\n<ul>");
+        for item in missing {
+            let _ = writeln!(html, "<li>{}</li>", xml_escape(item));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataPayload, DataType, SessionStatus, SessionSummary, TelemetryPayload};
+    use chrono::Duration;
+    use shared::schemas::Waypoint;
+    use shared::AltitudeReference;
+    use std::collections::HashMap;
+    use std::io::{Cursor, Read};
+
+    const HOME: GpsCoords = GpsCoords {
+        latitude: 40.0,
+        longitude: -105.0,
+        altitude: 1600.0,
+    };
+
+    fn session(mission_id: Option<Uuid>) -> FlightSession {
+        FlightSession {
+            id: Uuid::new_v4(),
+            flight_id: Uuid::new_v4(),
+            field_id: Uuid::new_v4(),
+            scene_id: Uuid::new_v4(),
+            owner_id: "grower-ops".to_string(),
+            mission_id,
+            drone_id: Uuid::new_v4(),
+            start_time: Utc::now(),
+            end_time: None,
+            status: SessionStatus::Ended,
+            data_records: Vec::new(),
+            summary: SessionSummary::default(),
+            tags: Vec::new(),
+        }
+    }
+
+    fn telemetry(
+        session: &FlightSession,
+        timestamp: DateTime<Utc>,
+        latitude: f64,
+        altitude: f32,
+    ) -> FlightDataRecord {
+        FlightDataRecord {
+            id: Uuid::new_v4(),
+            session_id: session.id,
+            flight_id: session.flight_id,
+            drone_id: session.drone_id,
+            timestamp,
+            data_type: DataType::Telemetry,
+            payload: DataPayload::Telemetry(TelemetryPayload {
+                position: (latitude, HOME.longitude, altitude),
+                velocity: (1.0, 0.0, 0.0),
+                orientation: (0.0, 0.0, 0.0),
+                battery_level: 0.8,
+                signal_strength: 0.9,
+            }),
+            sensor_id: "telemetry-01".to_string(),
+            gps_coords: None,
+            calibration_ref: "calibration-2026-06".to_string(),
+            metadata: HashMap::new(),
+            file_path: None,
+            size_bytes: 256,
+        }
+    }
+
+    fn mission() -> Mission {
+        let waypoint = |sequence: u16, latitude: f64| Waypoint {
+            sequence,
+            position: GpsCoords {
+                latitude,
+                longitude: HOME.longitude,
+                altitude: 40.0,
+            },
+            altitude_reference: AltitudeReference::RelativeToHome,
+            command: 16,
+            auto_continue: true,
+            param1: 0.0,
+            param2: 0.0,
+            param3: 0.0,
+            param4: 0.0,
+        };
+        Mission {
+            id: Uuid::new_v4(),
+            name: "North field <survey>".to_string(),
+            created_at: Utc::now(),
+            waypoints: vec![waypoint(1, 40.001), waypoint(0, 40.0)],
+            home_position: HOME,
+        }
+    }
+
+    fn unzip(bytes: Vec<u8>) -> HashMap<String, String> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        (0..archive.len())
+            .map(|index| {
+                let mut file = archive.by_index(index).unwrap();
+                let mut contents = String::new();
+                file.read_to_string(&mut contents).unwrap();
+                (file.name().to_string(), contents)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bundle_contains_mission_track_summary_and_hashed_manifest() {
+        let mission = mission();
+        let session = session(Some(mission.id));
+        let start = Utc::now();
+        let records = vec![
+            telemetry(&session, start + Duration::seconds(20), 40.0, 1600.0),
+            telemetry(&session, start, 40.0, 1600.0),
+            telemetry(&session, start + Duration::seconds(5), 40.0005, 1645.5),
+            telemetry(&session, start + Duration::seconds(10), 40.001, 1630.0),
+        ];
+        let request = ComplianceBundleRequest {
+            operator: OperatorConfig {
+                name: "Ada Pilot".to_string(),
+                organization: "Acme Ag".to_string(),
+                license_id: "FAA-107-12345".to_string(),
+                contact: "ops@example.com".to_string(),
+            },
+            mission: Some(mission),
+            incidents: Some(vec![ComplianceIncident {
+                timestamp: start + Duration::seconds(6),
+                drone_id: Some(session.drone_id),
+                kind: "AltitudeViolation".to_string(),
+                severity: "High".to_string(),
+                description: "exceeded 45 m ceiling".to_string(),
+            }]),
+        };
+
+        let bundle = ComplianceBundle::build(&session, &records, &request).unwrap();
+        let files = unzip(
+            bundle
+                .write_zip(Cursor::new(Vec::new()))
+                .unwrap()
+                .into_inner(),
+        );
+
+        let mut names: Vec<_> = files.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            vec![
+                MANIFEST_ARTIFACT,
+                MISSION_ARTIFACT,
+                SUMMARY_ARTIFACT,
+                TRACK_ARTIFACT
+            ]
+        );
+        assert_eq!(files[TRACK_ARTIFACT].matches("<trkpt ").count(), 4);
+
+        let summary = &files[SUMMARY_ARTIFACT];
+        assert!(summary.contains("<td>45.5 m</td>"));
+        assert!(summary.contains("AltitudeViolation"));
+        assert!(summary.contains("FAA-107-12345"));
+        assert_eq!(
+            bundle.manifest.summary.takeoff_time,
+            Some(start + Duration::seconds(5))
+        );
+        assert_eq!(
+            bundle.manifest.summary.landing_time,
+            Some(start + Duration::seconds(10))
+        );
+
+        let geojson: serde_json::Value = serde_json::from_str(&files[MISSION_ARTIFACT]).unwrap();
+        assert_eq!(
+            geojson["features"][1]["geometry"]["coordinates"][0][1],
+            40.0
+        );
+
+        let manifest: ComplianceManifest = serde_json::from_str(&files[MANIFEST_ARTIFACT]).unwrap();
+        assert!(manifest.missing.is_empty());
+        assert_eq!(manifest.artifacts.len(), 3);
+        for artifact in &manifest.artifacts {
+            assert_eq!(
+                artifact.sha256,
+                format!("{:x}", Sha256::digest(files[&artifact.name].as_bytes()))
+            );
+        }
+    }
+
+    #[test]
+    fn missing_mission_telemetry_and_operator_are_stated() {
+        let session = session(Some(Uuid::new_v4()));
+        let bundle =
+            ComplianceBundle::build(&session, &[], &ComplianceBundleRequest::default()).unwrap();
+
+        assert!(bundle.artifact(MISSION_ARTIFACT).is_none());
+        assert!(bundle.artifact(TRACK_ARTIFACT).is_none());
+        let missing = &bundle.manifest.missing;
+        assert!(missing
+            .iter()
+            .any(|item| item.starts_with(MISSION_ARTIFACT)));
+        assert!(missing.iter().any(|item| item.starts_with(TRACK_ARTIFACT)));
+        assert!(missing
+            .iter()
+            .any(|item| item == "operator name: not configured"));
+        assert!(missing.iter().any(|item| item.contains("no incident log")));
+
+        let summary =
+            String::from_utf8(bundle.artifact(SUMMARY_ARTIFACT).unwrap().to_vec()).unwrap();
+        assert!(summary.contains("Not included"));
+
+        let mismatched = ComplianceBundleRequest {
+            mission: Some(mission()),
+            ..ComplianceBundleRequest::default()
+        };
+        assert!(matches!(
+            ComplianceBundle::build(&session, &[], &mismatched),
+            Err(ComplianceExportError::MissionMismatch { .. })
+        ));
+    }
+}
@@ -105,13 +105,13 @@ impl DataExporter {
                     )
                 })
                 .unwrap_or_else(|| (String::new(), String::new(), String::new()));
-            let (position_lat, position_lon, position_alt) = match &record.payload {
-                crate::DataPayload::Telemetry { position, .. } => (
+            let (position_lat, position_lon, position_alt) = match record.payload.as_telemetry() {
+                Some(crate::TelemetryPayload { position, .. }) => (
                     position.0.to_string(),
                     position.1.to_string(),
                     position.2.to_string(),
                 ),
-                _ => (String::new(), String::new(), String::new()),
+                None => (String::new(), String::new(), String::new()),
             };
 
             writer.write_record(vec![
@@ -366,7 +366,7 @@ fn unsupported_export(format: &str) -> Box<dyn std::error::Error + Send + Sync>
     ))
 }
 
-fn xml_escape(value: &str) -> String {
+pub(crate) fn xml_escape(value: &str) -> String {
     value
         .replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -415,13 +415,13 @@ mod tests {
             drone_id: Uuid::new_v4(),
             timestamp: Utc::now(),
             data_type: DataType::Telemetry,
-            payload: crate::DataPayload::Telemetry {
+            payload: crate::DataPayload::Telemetry(crate::TelemetryPayload {
                 position: (0.0, 0.0, 0.0),
                 velocity: (1.0, 0.0, 0.0),
                 orientation: (0.0, 0.0, 0.0),
                 battery_level: 0.8,
                 signal_strength: 0.9,
-            },
+            }),
             sensor_id: "telemetry-01".to_string(),
             gps_coords: Some(GpsCoords {
                 latitude: 0.0,
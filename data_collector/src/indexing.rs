@@ -1,7 +1,11 @@
 use crate::{DataType, FlightDataRecord};
+use serde::Serialize;
 use std::collections::HashMap;
 use uuid::Uuid;
 
+/// Number of records re-indexed between progress callbacks during a rebuild.
+pub const REBUILD_PROGRESS_INTERVAL: usize = 1000;
+
 #[derive(Debug, Clone)]
 pub struct IndexConfig {
     pub index_path: std::path::PathBuf,
@@ -29,7 +33,16 @@ impl Default for IndexConfig {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Record filter used by [`DataIndexer::search`] and
+/// `DataCollectorService::search_data`.
+///
+/// Every populated field must match for a record to be returned (AND), while
+/// the values inside a single list field are alternatives (OR): a query with
+/// `data_types: [Telemetry, Image]` and `drone_ids: [a]` returns telemetry or
+/// image records from drone `a` only. `None` leaves a dimension
+/// unconstrained; an empty list matches nothing. The time range is inclusive
+/// at both ends. Results are ordered by timestamp before `limit` is applied.
+#[derive(Debug, Clone, Default)]
 pub struct SearchQuery {
     pub time_range: Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)>,
     pub spatial_bounds: Option<(f64, f64, f64, f64)>, // min_lat, min_lon, max_lat, max_lon
@@ -38,6 +51,84 @@ pub struct SearchQuery {
     pub limit: Option<usize>,
 }
 
+impl SearchQuery {
+    pub fn builder() -> SearchQueryBuilder {
+        SearchQueryBuilder::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum SearchQueryError {
+    #[error("search time range starts at {start} after it ends at {end}")]
+    InvertedTimeRange {
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    },
+    #[error("search bounds must have min <= max and finite coordinates")]
+    InvalidSpatialBounds,
+}
+
+/// Builds a [`SearchQuery`]. Repeated `data_type` or `drone` calls widen
+/// that dimension; `between` and `within_bounds` replace any earlier value.
+#[derive(Debug, Clone, Default)]
+pub struct SearchQueryBuilder {
+    query: SearchQuery,
+}
+
+impl SearchQueryBuilder {
+    pub fn data_type(mut self, data_type: DataType) -> Self {
+        self.query
+            .data_types
+            .get_or_insert_with(Vec::new)
+            .push(data_type);
+        self
+    }
+
+    pub fn drone(mut self, drone_id: Uuid) -> Self {
+        self.query
+            .drone_ids
+            .get_or_insert_with(Vec::new)
+            .push(drone_id);
+        self
+    }
+
+    pub fn between(
+        mut self,
+        start: chrono::DateTime<chrono::Utc>,
+        end: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        self.query.time_range = Some((start, end));
+        self
+    }
+
+    pub fn within_bounds(mut self, min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Self {
+        self.query.spatial_bounds = Some((min_lat, min_lon, max_lat, max_lon));
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.query.limit = Some(limit);
+        self
+    }
+
+    pub fn build(self) -> Result<SearchQuery, SearchQueryError> {
+        if let Some((start, end)) = self.query.time_range {
+            if start > end {
+                return Err(SearchQueryError::InvertedTimeRange { start, end });
+            }
+        }
+        if let Some((min_lat, min_lon, max_lat, max_lon)) = self.query.spatial_bounds {
+            let finite = [min_lat, min_lon, max_lat, max_lon]
+                .iter()
+                .all(|value| value.is_finite());
+            if !finite || min_lat > max_lat || min_lon > max_lon {
+                return Err(SearchQueryError::InvalidSpatialBounds);
+            }
+        }
+        Ok(self.query)
+    }
+}
+
 #[derive(Debug)]
 pub struct DataIndexer {
     config: IndexConfig,
@@ -96,6 +187,13 @@ impl DataIndexer {
         }
     }
 
+    pub fn index_records(&mut self, records: &[FlightDataRecord]) {
+        self.records.reserve(records.len());
+        for record in records {
+            self.index_record(record);
+        }
+    }
+
     pub fn find_by_location(&self, lat: f64, lon: f64, radius_deg: f64) -> Vec<Uuid> {
         if !self.config.enable_spatial_index {
             return Vec::new();
@@ -164,20 +262,37 @@ impl DataIndexer {
     }
 
     pub fn rebuild_indices(&mut self, records: &[FlightDataRecord]) {
+        self.reindex(records, |_| {});
+    }
+
+    fn reindex(
+        &mut self,
+        records: &[FlightDataRecord],
+        mut on_progress: impl FnMut(RebuildProgress),
+    ) {
         self.records.clear();
         self.spatial_index.clear();
         self.temporal_index.clear();
         self.type_index.clear();
 
-        for record in records {
+        let total = records.len();
+        for (position, record) in records.iter().enumerate() {
             self.index_record(record);
+            let processed = position + 1;
+            if processed % REBUILD_PROGRESS_INTERVAL == 0 && processed != total {
+                on_progress(RebuildProgress { processed, total });
+            }
         }
+        on_progress(RebuildProgress {
+            processed: total,
+            total,
+        });
     }
 
     fn get_spatial_key(&self, payload: &crate::DataPayload) -> SpatialKey {
         let grid_size = self.config.spatial_grid_size;
         match payload {
-            crate::DataPayload::Telemetry { position, .. } => SpatialKey {
+            crate::DataPayload::Telemetry(crate::TelemetryPayload { position, .. }) => SpatialKey {
                 lat_bucket: (position.0 / grid_size).floor() as i64,
                 lon_bucket: (position.1 / grid_size).floor() as i64,
             },
@@ -204,6 +319,23 @@ impl DataIndexer {
         }
     }
 
+    /// Snapshot of what the index currently holds, suitable for export.
+    pub fn stats(&self) -> IndexStats {
+        let mut records_by_type = HashMap::new();
+        for record in self.records.values() {
+            *records_by_type.entry(record.data_type.clone()).or_insert(0) += 1;
+        }
+        let earliest = self.records.values().map(|record| record.timestamp).min();
+        let latest = self.records.values().map(|record| record.timestamp).max();
+
+        IndexStats {
+            record_count: self.records.len(),
+            records_by_type,
+            spatial_cell_count: self.spatial_index.len(),
+            time_span: earliest.zip(latest),
+        }
+    }
+
     pub async fn index_session(&mut self, _session: &crate::FlightSession) -> anyhow::Result<()> {
         Ok(())
     }
@@ -213,11 +345,16 @@ impl DataIndexer {
         Ok(Self::filter_records(&records, &query))
     }
 
-    pub async fn rebuild(&mut self) -> anyhow::Result<()> {
-        self.records.clear();
-        self.spatial_index.clear();
-        self.temporal_index.clear();
-        self.type_index.clear();
+    /// Rebuilds every index from the records already held, reporting
+    /// progress every [`REBUILD_PROGRESS_INTERVAL`] records and once on
+    /// completion.
+    pub async fn rebuild(
+        &mut self,
+        on_progress: impl FnMut(RebuildProgress),
+    ) -> anyhow::Result<()> {
+        let mut records = self.records.values().cloned().collect::<Vec<_>>();
+        records.sort_by_key(|record| (record.timestamp, record.id));
+        self.reindex(&records, on_progress);
         Ok(())
     }
 
@@ -287,13 +424,28 @@ impl DataIndexer {
             return Some((coords.latitude, coords.longitude));
         }
 
-        match &record.payload {
-            crate::DataPayload::Telemetry { position, .. } => Some((position.0, position.1)),
-            _ => None,
-        }
+        record
+            .payload
+            .as_telemetry()
+            .map(|telemetry| (telemetry.position.0, telemetry.position.1))
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RebuildProgress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexStats {
+    pub record_count: usize,
+    pub records_by_type: HashMap<DataType, usize>,
+    pub spatial_cell_count: usize,
+    /// Earliest and latest record timestamps; `None` for an empty index.
+    pub time_span: Option<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)>,
+}
+
 #[derive(Debug, Clone)]
 pub struct IndexStatistics {
     pub spatial_buckets: usize,
@@ -323,13 +475,13 @@ mod tests {
             drone_id,
             flight_id: Uuid::new_v4(),
             data_type,
-            payload: crate::DataPayload::Telemetry {
+            payload: crate::DataPayload::Telemetry(crate::TelemetryPayload {
                 position: (latitude, longitude, 100.0),
                 velocity: (1.0, 0.0, 0.0),
                 orientation: (0.0, 0.0, 0.0),
                 battery_level: 0.8,
                 signal_strength: 0.9,
-            },
+            }),
             sensor_id: "telemetry-01".to_string(),
             gps_coords: Some(GpsCoords {
                 latitude,
@@ -355,13 +507,13 @@ mod tests {
             drone_id: Uuid::new_v4(),
             flight_id: Uuid::new_v4(),
             data_type: DataType::Telemetry,
-            payload: crate::DataPayload::Telemetry {
+            payload: crate::DataPayload::Telemetry(crate::TelemetryPayload {
                 position: (40.7128, -74.0060, 100.0), // NYC coordinates
                 velocity: (1.0, 0.0, 0.0),
                 orientation: (0.0, 0.0, 0.0),
                 battery_level: 0.8,
                 signal_strength: 0.9,
-            },
+            }),
             sensor_id: "telemetry-01".to_string(),
             gps_coords: Some(GpsCoords {
                 latitude: 40.7128,
@@ -394,13 +546,13 @@ mod tests {
             drone_id: Uuid::new_v4(),
             flight_id: Uuid::new_v4(),
             data_type: DataType::Telemetry,
-            payload: crate::DataPayload::Telemetry {
+            payload: crate::DataPayload::Telemetry(crate::TelemetryPayload {
                 position: (0.0, 0.0, 0.0),
                 velocity: (1.0, 0.0, 0.0),
                 orientation: (0.0, 0.0, 0.0),
                 battery_level: 0.8,
                 signal_strength: 0.9,
-            },
+            }),
             sensor_id: "telemetry-01".to_string(),
             gps_coords: Some(GpsCoords {
                 latitude: 0.0,
@@ -483,4 +635,115 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].id, rebuilt.id);
     }
+
+    #[tokio::test]
+    async fn builder_query_returns_intersection_of_type_time_and_drone() {
+        let mut indexer = DataIndexer::new(IndexConfig::default());
+        let drone_id = Uuid::new_v4();
+        let other_drone = Uuid::new_v4();
+        let start = Utc::now();
+        let end = start + chrono::Duration::minutes(10);
+        let inside = start + chrono::Duration::minutes(5);
+        let before = start - chrono::Duration::minutes(5);
+
+        let matching = test_record(DataType::Telemetry, drone_id, inside, 40.0, -105.0);
+        let on_boundary = test_record(DataType::Telemetry, drone_id, end, 40.0, -105.0);
+        let records = [
+            matching.clone(),
+            on_boundary.clone(),
+            test_record(DataType::Telemetry, drone_id, before, 40.0, -105.0),
+            test_record(DataType::Image, drone_id, inside, 40.0, -105.0),
+            test_record(DataType::Telemetry, other_drone, inside, 40.0, -105.0),
+            test_record(DataType::LidarScan, other_drone, before, 40.0, -105.0),
+        ];
+        for record in &records {
+            indexer.index_record(record);
+        }
+
+        let query = SearchQuery::builder()
+            .data_type(DataType::Telemetry)
+            .between(start, end)
+            .drone(drone_id)
+            .build()
+            .unwrap();
+        let results = indexer.search(query).await.unwrap();
+
+        assert_eq!(
+            results.iter().map(|record| record.id).collect::<Vec<_>>(),
+            vec![matching.id, on_boundary.id]
+        );
+
+        let widened = SearchQuery::builder()
+            .data_type(DataType::Telemetry)
+            .data_type(DataType::Image)
+            .drone(drone_id)
+            .between(start, end)
+            .limit(10)
+            .build()
+            .unwrap();
+        assert_eq!(indexer.search(widened).await.unwrap().len(), 3);
+
+        assert_eq!(
+            SearchQuery::builder()
+                .between(end, start)
+                .build()
+                .unwrap_err(),
+            SearchQueryError::InvertedTimeRange {
+                start: end,
+                end: start
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn rebuild_reports_progress_and_preserves_stats() {
+        let mut indexer = DataIndexer::new(IndexConfig::default());
+        let start = Utc::now();
+        let drone_id = Uuid::new_v4();
+        for index in 0..2_500 {
+            let data_type = if index % 5 == 0 {
+                DataType::Image
+            } else {
+                DataType::Telemetry
+            };
+            let record = test_record(
+                data_type,
+                drone_id,
+                start + chrono::Duration::seconds(index),
+                40.0 + (index % 10) as f64 * 0.01,
+                -105.0,
+            );
+            indexer.index_record(&record);
+        }
+
+        let before = indexer.stats();
+        assert_eq!(before.record_count, 2_500);
+        assert_eq!(before.records_by_type[&DataType::Image], 500);
+        assert_eq!(before.records_by_type[&DataType::Telemetry], 2_000);
+        assert_eq!(before.spatial_cell_count, 10);
+        assert_eq!(
+            before.time_span,
+            Some((start, start + chrono::Duration::seconds(2_499)))
+        );
+
+        let mut progress = Vec::new();
+        indexer
+            .rebuild(|update| progress.push(update))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            progress
+                .iter()
+                .map(|update| update.processed)
+                .collect::<Vec<_>>(),
+            vec![1_000, 2_000, 2_500]
+        );
+        assert!(progress.iter().all(|update| update.total == 2_500));
+        assert_eq!(indexer.stats(), before);
+        assert_eq!(
+            serde_json::to_value(indexer.stats()).unwrap()["records_by_type"]["Image"],
+            500
+        );
+    }
 }
@@ -1,34 +1,87 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use shared::data_quality::SessionQualityAssessment;
 use shared::schemas::GpsCoords;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+pub mod api;
+pub mod battery_health;
+pub mod compliance_export;
 pub mod export;
 pub mod indexing;
 pub mod multispectral;
+pub mod products;
+pub mod quality;
+pub mod replay;
 pub mod rplidar;
+#[cfg(feature = "s3")]
+pub mod s3_storage;
 pub mod simulated_capture;
 pub mod storage;
-
+pub mod storage_backend;
+pub mod time_sync;
+pub mod upload;
+pub mod upload_client;
+
+pub use api::{
+    BatterySessionAck, IngestApiState, IngestRequest, RecordIngestAck, RecordNotification,
+    SharedDataCollectorService,
+};
+pub use battery_health::{
+    fit_capacity_fade, infer_cycles, BatteryAlert, BatteryAlertKind, BatteryFleetReport,
+    BatteryHealthReport, BatteryHealthStatus, BatteryHistory, BatteryProfile, BatteryRegistry,
+    BatteryRegistryConfig, BatteryRegistryError, BatterySessionSummary, CapacityFade, ChargeCycle,
+    BATTERY_SERIAL_METADATA_KEY,
+};
+pub use compliance_export::{
+    ComplianceBundle, ComplianceBundleRequest, ComplianceExportError, ComplianceIncident,
+    ComplianceManifest, FlightSummary, ManifestArtifact,
+};
 pub use export::{DataExporter, ExportFormat};
-pub use indexing::{DataIndexer, IndexConfig, SearchQuery};
+pub use indexing::{
+    DataIndexer, IndexConfig, IndexStats, RebuildProgress, SearchQuery, SearchQueryBuilder,
+    SearchQueryError,
+};
 pub use multispectral::{
     multispectral_capture_to_record, validate_multispectral_capture, MultispectralBandCapture,
     MultispectralCaptureError, MultispectralCaptureManifest, MultispectralRecordError,
 };
+pub use products::{
+    parameters_hash, DerivedProduct, NewDerivedProduct, ProductDisposition, ProductProducer,
+    ProductRegistryError, SessionDeletion,
+};
+pub use quality::{
+    score_session_quality, QualityAssessmentConfig, QualityWeights, TELEMETRY_GAP_BUCKETS_SECONDS,
+};
+pub use replay::{
+    replay_message, GapPolicy, ReplayError, ReplayFrame, ReplayOptions, ReplayOutcome, ReplayPlan,
+};
 pub use rplidar::{
     lidar_scan_to_record, parse_rplidar_a3_measurements, LidarRecordError, RplidarParseError,
 };
+#[cfg(feature = "s3")]
+pub use s3_storage::{S3Storage, S3StorageConfig};
 pub use simulated_capture::{
     flight_sim_cpp_lidar_observation_from_json, flight_sim_cpp_multispectral_observation_from_json,
     simulated_capture_frame_to_batch, SimulatedCaptureBatch, SimulatedCaptureError,
     SimulatedCaptureFrame, SimulatedCapturePath, SimulatedCapturePathStep,
     SimulatedSensorObservation,
 };
-pub use storage::{StorageConfig, StorageEngine};
+pub use storage::{BatchLogSummary, StorageConfig, StorageEngine};
+pub use storage_backend::{LocalStorage, Storage, StoredObject};
+pub use time_sync::{
+    apply_time_offset, estimate_stream_offsets, OffsetEstimate, StreamOffsetEstimate,
+    TimeSyncConfig, TimeSyncReport, UndeterminedReason,
+};
+pub use upload::{
+    infer_data_type, AssembledUpload, UploadConfig, UploadError, UploadInitRequest, UploadManager,
+    UploadStatus, UploadTicket,
+};
+pub use upload_client::{file_sha256, UploadClient};
 
 /// Data collection and storage system
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -215,7 +268,15 @@ fn record_integrity_checksum(record: &FlightDataRecord) -> Result<String> {
     Ok(format!("{:016x}", fnv1a64(&encoded)))
 }
 
-fn fnv1a64(bytes: &[u8]) -> u64 {
+/// Identity used for duplicate detection: the same sensor reporting the same
+/// payload for the same instant is one observation, even if a flaky link
+/// delivered it twice under different record ids.
+fn record_content_hash(record: &FlightDataRecord) -> Result<String> {
+    let encoded = serde_json::to_vec(&(&record.sensor_id, &record.payload, record.timestamp))?;
+    Ok(format!("{:016x}", fnv1a64(&encoded)))
+}
+
+pub(crate) fn fnv1a64(bytes: &[u8]) -> u64 {
     let mut hash = 0xcbf2_9ce4_8422_2325u64;
     for byte in bytes {
         hash ^= u64::from(*byte);
@@ -229,14 +290,14 @@ fn apply_quality_mask(record: &mut FlightDataRecord) {
     record.metadata.remove(QA_REASON_KEY);
 
     let reason = match &record.payload {
-        DataPayload::PointCloud { point_count, .. } if *point_count < 3 => {
+        DataPayload::PointCloud(PointCloudPayload { point_count, .. }) if *point_count < 3 => {
             Some("sparse_point_cloud")
         }
-        DataPayload::SensorData {
+        DataPayload::SensorData(SensorDataPayload {
             sensor_type,
             values,
             ..
-        } if sensor_type.eq_ignore_ascii_case("multispectral")
+        }) if sensor_type.eq_ignore_ascii_case("multispectral")
             && values
                 .values()
                 .any(|value| !value.is_finite() || !(0.0..=1.0).contains(value)) =>
@@ -280,45 +341,259 @@ pub enum DataType {
     SystemLog,
 }
 
+/// What a record holds. Each variant wraps its own payload struct; in JSON a
+/// variant reads the same as the inline struct variants it replaced.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DataPayload {
-    Telemetry {
-        position: (f64, f64, f32),
-        velocity: (f32, f32, f32),
-        orientation: (f32, f32, f32),
-        battery_level: f32,
-        signal_strength: f32,
-    },
-    SensorData {
-        sensor_type: String,
-        values: HashMap<String, f64>,
-        calibration_info: Option<String>,
-    },
-    MediaFile {
-        file_type: String,
-        dimensions: Option<(u32, u32)>,
-        duration_seconds: Option<f32>,
-        compression: Option<String>,
-    },
-    PointCloud {
-        point_count: u32,
-        bounds: ((f32, f32, f32), (f32, f32, f32)),
-        format: String,
-        has_color: bool,
-        has_intensity: bool,
-    },
-    TrackLog {
-        waypoint_count: u32,
-        total_distance_m: f32,
-        duration_seconds: f32,
-        start_time: DateTime<Utc>,
-        end_time: DateTime<Utc>,
-    },
-    Raw {
-        format: String,
-        schema: Option<String>,
-        compression: Option<String>,
-    },
+    Telemetry(TelemetryPayload),
+    SensorData(SensorDataPayload),
+    MediaFile(MediaFilePayload),
+    PointCloud(PointCloudPayload),
+    TrackLog(TrackLogPayload),
+    Raw(RawPayload),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryPayload {
+    pub position: (f64, f64, f32),
+    pub velocity: (f32, f32, f32),
+    pub orientation: (f32, f32, f32),
+    pub battery_level: f32,
+    pub signal_strength: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorDataPayload {
+    pub sensor_type: String,
+    pub values: HashMap<String, f64>,
+    pub calibration_info: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaFilePayload {
+    pub file_type: String,
+    pub dimensions: Option<(u32, u32)>,
+    pub duration_seconds: Option<f32>,
+    pub compression: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PointCloudPayload {
+    pub point_count: u32,
+    pub bounds: ((f32, f32, f32), (f32, f32, f32)),
+    pub format: String,
+    pub has_color: bool,
+    pub has_intensity: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackLogPayload {
+    pub waypoint_count: u32,
+    pub total_distance_m: f32,
+    pub duration_seconds: f32,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawPayload {
+    pub format: String,
+    pub schema: Option<String>,
+    pub compression: Option<String>,
+}
+
+/// A [`DataPayload`] converted into a payload struct of another variant.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("expected a {expected} payload, found {found}")]
+pub struct PayloadVariantError {
+    pub expected: &'static str,
+    pub found: &'static str,
+    /// The payload that was passed in, handed back unchanged.
+    pub payload: DataPayload,
+}
+
+impl DataPayload {
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            DataPayload::Telemetry(_) => "Telemetry",
+            DataPayload::SensorData(_) => "SensorData",
+            DataPayload::MediaFile(_) => "MediaFile",
+            DataPayload::PointCloud(_) => "PointCloud",
+            DataPayload::TrackLog(_) => "TrackLog",
+            DataPayload::Raw(_) => "Raw",
+        }
+    }
+
+    /// The record type this payload describes when nothing more specific is
+    /// known. Records keep their own `data_type`, which can narrow it, such
+    /// as a thermal frame stored as a media file.
+    pub fn data_type(&self) -> DataType {
+        match self {
+            DataPayload::Telemetry(_) => DataType::Telemetry,
+            DataPayload::SensorData(_) => DataType::SensorReading,
+            DataPayload::MediaFile(media) if media.duration_seconds.is_some() => DataType::Video,
+            DataPayload::MediaFile(_) => DataType::Image,
+            DataPayload::PointCloud(_) => DataType::LidarScan,
+            DataPayload::TrackLog(_) => DataType::GPSTrack,
+            DataPayload::Raw(raw) => match raw.format.to_ascii_lowercase().as_str() {
+                "las" | "laz" | "ply" | "pcd" | "e57" => DataType::LidarScan,
+                "ulg" | "tlog" | "bin" => DataType::FlightLog,
+                _ => DataType::SystemLog,
+            },
+        }
+    }
+
+    pub fn as_telemetry(&self) -> Option<&TelemetryPayload> {
+        match self {
+            DataPayload::Telemetry(telemetry) => Some(telemetry),
+            _ => None,
+        }
+    }
+
+    pub fn as_sensor_data(&self) -> Option<&SensorDataPayload> {
+        match self {
+            DataPayload::SensorData(sensor_data) => Some(sensor_data),
+            _ => None,
+        }
+    }
+
+    pub fn as_media_file(&self) -> Option<&MediaFilePayload> {
+        match self {
+            DataPayload::MediaFile(media_file) => Some(media_file),
+            _ => None,
+        }
+    }
+
+    pub fn as_point_cloud(&self) -> Option<&PointCloudPayload> {
+        match self {
+            DataPayload::PointCloud(point_cloud) => Some(point_cloud),
+            _ => None,
+        }
+    }
+
+    pub fn as_track_log(&self) -> Option<&TrackLogPayload> {
+        match self {
+            DataPayload::TrackLog(track_log) => Some(track_log),
+            _ => None,
+        }
+    }
+
+    pub fn as_raw(&self) -> Option<&RawPayload> {
+        match self {
+            DataPayload::Raw(raw) => Some(raw),
+            _ => None,
+        }
+    }
+
+    fn variant_error(self, expected: &'static str) -> PayloadVariantError {
+        PayloadVariantError {
+            expected,
+            found: self.variant_name(),
+            payload: self,
+        }
+    }
+}
+
+impl From<TelemetryPayload> for DataPayload {
+    fn from(payload: TelemetryPayload) -> Self {
+        DataPayload::Telemetry(payload)
+    }
+}
+
+impl From<SensorDataPayload> for DataPayload {
+    fn from(payload: SensorDataPayload) -> Self {
+        DataPayload::SensorData(payload)
+    }
+}
+
+impl From<MediaFilePayload> for DataPayload {
+    fn from(payload: MediaFilePayload) -> Self {
+        DataPayload::MediaFile(payload)
+    }
+}
+
+impl From<PointCloudPayload> for DataPayload {
+    fn from(payload: PointCloudPayload) -> Self {
+        DataPayload::PointCloud(payload)
+    }
+}
+
+impl From<TrackLogPayload> for DataPayload {
+    fn from(payload: TrackLogPayload) -> Self {
+        DataPayload::TrackLog(payload)
+    }
+}
+
+impl From<RawPayload> for DataPayload {
+    fn from(payload: RawPayload) -> Self {
+        DataPayload::Raw(payload)
+    }
+}
+
+impl TryFrom<DataPayload> for TelemetryPayload {
+    type Error = PayloadVariantError;
+
+    fn try_from(payload: DataPayload) -> Result<Self, Self::Error> {
+        match payload {
+            DataPayload::Telemetry(telemetry) => Ok(telemetry),
+            other => Err(other.variant_error("Telemetry")),
+        }
+    }
+}
+
+impl TryFrom<DataPayload> for SensorDataPayload {
+    type Error = PayloadVariantError;
+
+    fn try_from(payload: DataPayload) -> Result<Self, Self::Error> {
+        match payload {
+            DataPayload::SensorData(sensor_data) => Ok(sensor_data),
+            other => Err(other.variant_error("SensorData")),
+        }
+    }
+}
+
+impl TryFrom<DataPayload> for MediaFilePayload {
+    type Error = PayloadVariantError;
+
+    fn try_from(payload: DataPayload) -> Result<Self, Self::Error> {
+        match payload {
+            DataPayload::MediaFile(media_file) => Ok(media_file),
+            other => Err(other.variant_error("MediaFile")),
+        }
+    }
+}
+
+impl TryFrom<DataPayload> for PointCloudPayload {
+    type Error = PayloadVariantError;
+
+    fn try_from(payload: DataPayload) -> Result<Self, Self::Error> {
+        match payload {
+            DataPayload::PointCloud(point_cloud) => Ok(point_cloud),
+            other => Err(other.variant_error("PointCloud")),
+        }
+    }
+}
+
+impl TryFrom<DataPayload> for TrackLogPayload {
+    type Error = PayloadVariantError;
+
+    fn try_from(payload: DataPayload) -> Result<Self, Self::Error> {
+        match payload {
+            DataPayload::TrackLog(track_log) => Ok(track_log),
+            other => Err(other.variant_error("TrackLog")),
+        }
+    }
+}
+
+impl TryFrom<DataPayload> for RawPayload {
+    type Error = PayloadVariantError;
+
+    fn try_from(payload: DataPayload) -> Result<Self, Self::Error> {
+        match payload {
+            DataPayload::Raw(raw) => Ok(raw),
+            other => Err(other.variant_error("Raw")),
+        }
+    }
 }
 
 fn default_link_id() -> Uuid {
@@ -505,6 +780,28 @@ pub enum SessionStatus {
     Collecting,
     Ended,
     Failed,
+    /// Closed by the collector rather than the flight: idle past the session
+    /// timeout, or replaced by a newer session on the same drone.
+    Aborted,
+}
+
+/// Result of handing a record to `DataCollectorService::collect_data`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollectOutcome {
+    /// Accepted into the pending batch; it is on disk once the batch flushes.
+    Stored { record_id: Uuid },
+    /// The session already holds a record with the same payload and
+    /// timestamp; nothing was written.
+    Duplicate {
+        record_id: Uuid,
+        content_hash: String,
+    },
+}
+
+impl CollectOutcome {
+    pub fn is_duplicate(&self) -> bool {
+        matches!(self, Self::Duplicate { .. })
+    }
 }
 
 #[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
@@ -517,6 +814,8 @@ pub enum SessionLifecycleError {
         from: SessionStatus,
         to: SessionStatus,
     },
+    #[error("drone {drone_id} already has capture session {session_id} in progress")]
+    DroneSessionActive { drone_id: Uuid, session_id: Uuid },
 }
 
 impl FlightSession {
@@ -564,8 +863,10 @@ impl FlightSession {
             (SessionStatus::Started, SessionStatus::Collecting)
                 | (SessionStatus::Started, SessionStatus::Ended)
                 | (SessionStatus::Started, SessionStatus::Failed)
+                | (SessionStatus::Started, SessionStatus::Aborted)
                 | (SessionStatus::Collecting, SessionStatus::Ended)
                 | (SessionStatus::Collecting, SessionStatus::Failed)
+                | (SessionStatus::Collecting, SessionStatus::Aborted)
         )
     }
 
@@ -907,6 +1208,17 @@ impl CollectionFailureRequest {
     }
 }
 
+/// One telemetry fix of a session's flight track.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTrackFix {
+    pub timestamp: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude_m: f32,
+    /// A media file was captured after this fix and before the next one.
+    pub capture_event: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionSummary {
     pub total_data_size_bytes: u64,
@@ -926,6 +1238,9 @@ pub struct SessionSummary {
     pub capture_health: CaptureHealth,
     #[serde(default)]
     pub aggregate_evidence: SessionAggregateEvidence,
+    /// Set when the session ends or by `assess_session_quality`.
+    #[serde(default)]
+    pub quality: Option<SessionQualityAssessment>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -957,6 +1272,10 @@ pub struct CaptureSessionListFilter {
     pub flight_id: Option<Uuid>,
     pub started_after: Option<DateTime<Utc>>,
     pub started_before: Option<DateTime<Utc>>,
+    /// Keeps sessions whose quality assessment scored at least this; sessions
+    /// not assessed yet are left out.
+    #[serde(default)]
+    pub min_quality_score: Option<u8>,
     pub offset: usize,
     pub limit: usize,
 }
@@ -968,6 +1287,7 @@ impl Default for CaptureSessionListFilter {
             flight_id: None,
             started_after: None,
             started_before: None,
+            min_quality_score: None,
             offset: 0,
             limit: 50,
         }
@@ -1008,6 +1328,17 @@ impl CaptureSessionListFilter {
             }
         }
 
+        if let Some(min_quality_score) = self.min_quality_score {
+            let score = session
+                .summary
+                .quality
+                .as_ref()
+                .map(|quality| quality.score);
+            if score.is_none_or(|score| score < min_quality_score) {
+                return false;
+            }
+        }
+
         true
     }
 }
@@ -1038,6 +1369,7 @@ pub struct CaptureSessionListItem {
     pub aggregate_evidence: SessionAggregateEvidence,
     pub capture_health: CaptureHealth,
     pub qa: CaptureQaSummary,
+    pub quality_score: Option<u8>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -1235,28 +1567,167 @@ pub struct CaptureSessionInspection {
     pub failures: Vec<CollectionFailure>,
 }
 
-/// Main data collector service
-pub struct DataCollectorService {
-    storage: StorageEngine,
+/// When collected records are written out: a pending batch is flushed once
+/// it holds `max_records` records or its oldest record has waited `max_age`.
+/// With `max_records` of 1 each record is written to its own file as soon as
+/// it is collected, bypassing the batch log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollectBatchConfig {
+    pub max_records: usize,
+    pub max_age: Duration,
+}
+
+impl CollectBatchConfig {
+    pub fn unbatched() -> Self {
+        Self {
+            max_records: 1,
+            max_age: Duration::ZERO,
+        }
+    }
+}
+
+impl Default for CollectBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_records: 50,
+            max_age: Duration::from_millis(500),
+        }
+    }
+}
+
+/// What starting a session does when its drone already has one in progress.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConcurrentSessionPolicy {
+    /// Refuse the new session with [`SessionLifecycleError::DroneSessionActive`].
+    #[default]
+    Reject,
+    /// Abort the session in progress, then start the new one.
+    AbortExisting,
+}
+
+/// How the collector keeps sessions exclusive per drone and closes the ones
+/// a drone stopped feeding: a session with no collected record for
+/// `idle_timeout` is aborted by [`DataCollectorService::sweep_idle_sessions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionPolicy {
+    pub concurrent: ConcurrentSessionPolicy,
+    pub idle_timeout: Duration,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Self {
+            concurrent: ConcurrentSessionPolicy::default(),
+            idle_timeout: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+/// Main data collector service, storing through any [`Storage`] backend.
+pub struct DataCollectorService<S = LocalStorage> {
+    storage: StorageEngine<S>,
     active_sessions: HashMap<Uuid, FlightSession>,
     linkage_catalog: CaptureLinkageCatalog,
     indexer: DataIndexer,
+    session_content_hashes: HashMap<Uuid, HashSet<String>>,
+    batch_config: CollectBatchConfig,
+    pending_records: Vec<FlightDataRecord>,
+    pending_since: Option<Instant>,
+    quality_config: QualityAssessmentConfig,
+    session_policy: SessionPolicy,
+    /// Last time each active session was touched by a collected record.
+    session_heartbeats: HashMap<Uuid, DateTime<Utc>>,
     auto_export: bool,
     retention_days: u32,
 }
 
 impl DataCollectorService {
+    /// Starts on an empty view of `data_root`: sessions a previous run left
+    /// open are not taken back. Use [`Self::open`] when resuming a store.
     pub fn new(data_root: PathBuf) -> Result<Self> {
-        let storage_config = StorageConfig {
-            base_path: data_root.clone(),
+        let storage = StorageEngine::new(Self::storage_config(&data_root))?;
+        Ok(Self::from_storage_engine(data_root, storage))
+    }
+
+    /// Opens the store under `data_root` and takes back the sessions a
+    /// previous run left open, like [`Self::with_storage`].
+    pub async fn open(data_root: PathBuf) -> Result<Self> {
+        let mut service = Self::new(data_root)?;
+        service.restore_open_sessions().await?;
+        Ok(service)
+    }
+}
+
+impl<S: Storage> DataCollectorService<S> {
+    /// Keeps records and sessions in `storage` instead of under `data_root`,
+    /// which then only holds the search index. Sessions a previous run left
+    /// open are taken back.
+    pub async fn with_storage(data_root: PathBuf, storage: S) -> Result<Self> {
+        let storage = StorageEngine::open(Self::storage_config(&data_root), storage).await?;
+        let mut service = Self::from_storage_engine(data_root, storage);
+        service.restore_open_sessions().await?;
+        Ok(service)
+    }
+
+    /// Makes the stored sessions still Started or Collecting active again,
+    /// so they keep their drone exclusive and are swept once idle. Each
+    /// one's heartbeat resumes from its last record, or its start, and its
+    /// duplicate detection from the content hashes of its stored records.
+    async fn restore_open_sessions(&mut self) -> Result<usize> {
+        let open = self
+            .storage
+            .list_sessions(None, None)
+            .await?
+            .into_iter()
+            .filter(|session| {
+                matches!(
+                    session.status,
+                    SessionStatus::Started | SessionStatus::Collecting
+                )
+            })
+            .collect::<Vec<_>>();
+        for session in &open {
+            let last_seen = session
+                .summary
+                .freshness
+                .last_record_at
+                .map_or(session.start_time, |last| last.max(session.start_time));
+            self.session_heartbeats.insert(session.id, last_seen);
+            let mut content_hashes = HashSet::with_capacity(session.data_records.len());
+            for record_id in &session.data_records {
+                match self.load_record(record_id).await? {
+                    Some(record) => {
+                        content_hashes.insert(record_content_hash(&record)?);
+                    }
+                    None => tracing::warn!(
+                        session_id = %session.id,
+                        %record_id,
+                        "record listed in restored session was not found"
+                    ),
+                }
+            }
+            self.session_content_hashes
+                .insert(session.id, content_hashes);
+            tracing::info!(session_id = %session.id, drone_id = %session.drone_id, "restored open capture session");
+        }
+        let restored = open.len();
+        self.active_sessions
+            .extend(open.into_iter().map(|session| (session.id, session)));
+        Ok(restored)
+    }
+
+    fn storage_config(data_root: &Path) -> StorageConfig {
+        StorageConfig {
+            base_path: data_root.to_path_buf(),
             max_file_size_mb: 100,
             compression_enabled: true,
             encryption_enabled: false,
             backup_enabled: false,
             retention_days: 365,
-        };
-        let storage = StorageEngine::new(storage_config)?;
+        }
+    }
 
+    fn from_storage_engine(data_root: PathBuf, storage: StorageEngine<S>) -> Self {
         let index_config = IndexConfig {
             index_path: data_root.join("index"),
             spatial_resolution: 100.0,
@@ -1269,14 +1740,115 @@ impl DataCollectorService {
         };
         let indexer = DataIndexer::new(index_config);
 
-        Ok(Self {
+        Self {
             storage,
             active_sessions: HashMap::new(),
             linkage_catalog: CaptureLinkageCatalog::default(),
             indexer,
+            session_content_hashes: HashMap::new(),
+            batch_config: CollectBatchConfig::default(),
+            pending_records: Vec::new(),
+            pending_since: None,
+            quality_config: QualityAssessmentConfig::default(),
+            session_policy: SessionPolicy::default(),
+            session_heartbeats: HashMap::new(),
             auto_export: false,
             retention_days: 365,
-        })
+        }
+    }
+
+    pub fn with_batch_config(mut self, batch_config: CollectBatchConfig) -> Self {
+        self.batch_config = batch_config;
+        self
+    }
+
+    pub fn batch_config(&self) -> CollectBatchConfig {
+        self.batch_config
+    }
+
+    pub fn with_quality_config(mut self, quality_config: QualityAssessmentConfig) -> Self {
+        self.quality_config = quality_config;
+        self
+    }
+
+    pub fn quality_config(&self) -> &QualityAssessmentConfig {
+        &self.quality_config
+    }
+
+    pub fn with_session_policy(mut self, session_policy: SessionPolicy) -> Self {
+        self.session_policy = session_policy;
+        self
+    }
+
+    pub fn session_policy(&self) -> SessionPolicy {
+        self.session_policy
+    }
+
+    /// Records accepted by `collect_data` that are not written yet.
+    pub fn pending_record_count(&self) -> usize {
+        self.pending_records.len()
+    }
+
+    /// Writes the pending records as one storage batch, then persists the
+    /// sessions they belong to and indexes them. Returns how many records
+    /// were written; on failure the records stay pending.
+    pub async fn flush_batch(&mut self) -> Result<usize> {
+        if self.pending_records.is_empty() {
+            return Ok(0);
+        }
+
+        let records = std::mem::take(&mut self.pending_records);
+        let stored = match self.store_pending(&records).await {
+            Ok(stored) => stored,
+            Err(error) => {
+                self.pending_records = records;
+                return Err(error);
+            }
+        };
+        self.pending_since = None;
+
+        let session_ids = stored
+            .iter()
+            .map(|record| record.session_id)
+            .collect::<BTreeSet<_>>();
+        for session_id in session_ids {
+            if let Some(session) = self.active_sessions.get(&session_id) {
+                self.storage.store_session(session).await?;
+            }
+        }
+        self.indexer.index_records(&stored);
+
+        Ok(stored.len())
+    }
+
+    async fn store_pending(&self, records: &[FlightDataRecord]) -> Result<Vec<FlightDataRecord>> {
+        match records {
+            [record] if self.batch_config.max_records <= 1 => {
+                Ok(vec![self.storage.store_data(record).await?])
+            }
+            _ => self.storage.store_batch(records).await,
+        }
+    }
+
+    /// Flushes the pending batch if it is full or has waited past
+    /// `max_age`; meant to be polled by a timer between collections.
+    pub async fn flush_due_batch(&mut self) -> Result<usize> {
+        let due = self.pending_records.len() >= self.batch_config.max_records
+            || self
+                .pending_since
+                .is_some_and(|since| since.elapsed() >= self.batch_config.max_age);
+        if due {
+            self.flush_batch().await
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Writes out anything still pending before the service goes away.
+    pub async fn shutdown(&mut self) -> Result<()> {
+        let flushed = self.flush_batch().await?;
+        tracing::info!(flushed, "data collector shut down");
+        Ok(())
     }
 
     pub fn register_capture_linkage(
@@ -1315,11 +1887,33 @@ impl DataCollectorService {
 
     pub async fn start_capture_session(&mut self, request: CaptureSessionRequest) -> Result<Uuid> {
         self.validate_capture_linkage(&request)?;
+        let in_progress = self
+            .active_sessions
+            .values()
+            .find(|session| session.drone_id == request.drone_id)
+            .map(|session| session.id);
+        if let Some(existing_id) = in_progress {
+            match self.session_policy.concurrent {
+                ConcurrentSessionPolicy::Reject => {
+                    return Err(SessionLifecycleError::DroneSessionActive {
+                        drone_id: request.drone_id,
+                        session_id: existing_id,
+                    }
+                    .into());
+                }
+                ConcurrentSessionPolicy::AbortExisting => {
+                    self.abort_session(&existing_id).await?;
+                }
+            }
+        }
+
         let session = FlightSession::new(request);
 
         let session_id = session.id;
 
         self.storage.store_session(&session).await?;
+        self.session_heartbeats
+            .insert(session_id, session.start_time);
         self.active_sessions.insert(session_id, session);
 
         tracing::info!("Started data collection session: {}", session_id);
@@ -1327,6 +1921,7 @@ impl DataCollectorService {
     }
 
     pub async fn end_session(&mut self, session_id: &Uuid) -> Result<FlightSession> {
+        self.flush_batch().await?;
         let current_status = self
             .active_sessions
             .get(session_id)
@@ -1351,11 +1946,14 @@ impl DataCollectorService {
                 session_id: *session_id,
             },
         )?;
+        self.session_content_hashes.remove(session_id);
+        self.session_heartbeats.remove(session_id);
         session.transition_status(SessionStatus::Ended)?;
         session.end_time = Some(Utc::now());
 
         // Calculate final summary
         session.summary = self.calculate_session_summary(&session).await?;
+        session.summary.quality = Some(self.score_quality(&session).await?);
 
         // Store session metadata
         self.storage.store_session(&session).await?;
@@ -1368,6 +1966,61 @@ impl DataCollectorService {
     }
 
     pub async fn fail_session(&mut self, session_id: &Uuid) -> Result<FlightSession> {
+        let session = self
+            .close_session(session_id, SessionStatus::Failed)
+            .await?;
+        tracing::warn!("Failed data collection session: {}", session_id);
+        Ok(session)
+    }
+
+    /// Closes a session the drone did not end itself, keeping everything
+    /// collected so far.
+    pub async fn abort_session(&mut self, session_id: &Uuid) -> Result<FlightSession> {
+        let session = self
+            .close_session(session_id, SessionStatus::Aborted)
+            .await?;
+        tracing::warn!(%session_id, drone_id = %session.drone_id, "aborted data collection session");
+        Ok(session)
+    }
+
+    /// Marks a session as still alive without collecting a record, e.g. when
+    /// the drone reports in between captures.
+    pub fn touch_session(&mut self, session_id: &Uuid) -> Result<()> {
+        let heartbeat = self.session_heartbeats.get_mut(session_id).ok_or(
+            SessionLifecycleError::SessionNotFound {
+                session_id: *session_id,
+            },
+        )?;
+        *heartbeat = Utc::now();
+        Ok(())
+    }
+
+    /// Aborts every active session whose last heartbeat is at least the
+    /// policy's idle timeout before `now`, returning the aborted sessions.
+    pub async fn sweep_idle_sessions(&mut self, now: DateTime<Utc>) -> Result<Vec<FlightSession>> {
+        let idle_timeout = chrono::Duration::from_std(self.session_policy.idle_timeout)
+            .unwrap_or(chrono::Duration::MAX);
+        let mut idle = self
+            .session_heartbeats
+            .iter()
+            .filter(|(_, last_seen)| now.signed_duration_since(**last_seen) >= idle_timeout)
+            .map(|(session_id, _)| *session_id)
+            .collect::<Vec<_>>();
+        idle.sort();
+
+        let mut aborted = Vec::with_capacity(idle.len());
+        for session_id in idle {
+            aborted.push(self.abort_session(&session_id).await?);
+        }
+        Ok(aborted)
+    }
+
+    async fn close_session(
+        &mut self,
+        session_id: &Uuid,
+        status: SessionStatus,
+    ) -> Result<FlightSession> {
+        self.flush_batch().await?;
         let current_status = self
             .active_sessions
             .get(session_id)
@@ -1376,13 +2029,11 @@ impl DataCollectorService {
             })?
             .status;
 
-        if !FlightSession::is_valid_transition(current_status, SessionStatus::Failed)
-            && current_status != SessionStatus::Failed
-        {
+        if !FlightSession::is_valid_transition(current_status, status) && current_status != status {
             return Err(SessionLifecycleError::InvalidStatusTransition {
                 session_id: *session_id,
                 from: current_status,
-                to: SessionStatus::Failed,
+                to: status,
             }
             .into());
         }
@@ -1392,12 +2043,12 @@ impl DataCollectorService {
                 session_id: *session_id,
             },
         )?;
-        session.transition_status(SessionStatus::Failed)?;
+        self.session_content_hashes.remove(session_id);
+        self.session_heartbeats.remove(session_id);
+        session.transition_status(status)?;
         session.end_time = Some(Utc::now());
 
         self.storage.store_session(&session).await?;
-
-        tracing::warn!("Failed data collection session: {}", session_id);
         Ok(session)
     }
 
@@ -1406,6 +2057,7 @@ impl DataCollectorService {
         session_id: &Uuid,
         failure: CollectionFailureRequest,
     ) -> Result<CollectionFailure> {
+        self.flush_batch().await?;
         let failure = failure.into_failure();
 
         let session_snapshot = {
@@ -1418,7 +2070,7 @@ impl DataCollectorService {
             match session.status {
                 SessionStatus::Started => session.transition_status(SessionStatus::Collecting)?,
                 SessionStatus::Collecting => {}
-                SessionStatus::Ended | SessionStatus::Failed => {
+                SessionStatus::Ended | SessionStatus::Failed | SessionStatus::Aborted => {
                     return Err(SessionLifecycleError::InvalidStatusTransition {
                         session_id: *session_id,
                         from: session.status,
@@ -1520,6 +2172,7 @@ impl DataCollectorService {
         retry_backoff_ms: Option<u64>,
         exhausted: bool,
     ) -> Result<CaptureHealth> {
+        self.flush_batch().await?;
         let session_snapshot = {
             let session = self.active_sessions.get_mut(session_id).ok_or(
                 SessionLifecycleError::SessionNotFound {
@@ -1530,7 +2183,7 @@ impl DataCollectorService {
             match session.status {
                 SessionStatus::Started => session.transition_status(SessionStatus::Collecting)?,
                 SessionStatus::Collecting => {}
-                SessionStatus::Ended | SessionStatus::Failed => {
+                SessionStatus::Ended | SessionStatus::Failed | SessionStatus::Aborted => {
                     return Err(SessionLifecycleError::InvalidStatusTransition {
                         session_id: *session_id,
                         from: session.status,
@@ -1560,8 +2213,12 @@ impl DataCollectorService {
             })
     }
 
-    pub async fn collect_data(&mut self, session_id: &Uuid, data: FlightDataRecord) -> Result<()> {
-        data.validate_provenance()?;
+    pub async fn collect_data(
+        &mut self,
+        session_id: &Uuid,
+        data: FlightDataRecord,
+    ) -> Result<CollectOutcome> {
+        data.validate_provenance()?;
         if data.session_id != *session_id {
             return Err(FlightDataProvenanceError::SessionMismatch {
                 expected_session_id: *session_id,
@@ -1591,11 +2248,36 @@ impl DataCollectorService {
             }
         };
 
-        // Store the data
-        let stored_data = self.storage.store_data(&data).await?;
+        if let Some(heartbeat) = self.session_heartbeats.get_mut(session_id) {
+            *heartbeat = Utc::now();
+        }
+
+        let content_hash = record_content_hash(&data)?;
+        if self
+            .session_content_hashes
+            .get(session_id)
+            .is_some_and(|hashes| hashes.contains(&content_hash))
+        {
+            tracing::debug!(
+                %session_id,
+                record_id = %data.id,
+                %content_hash,
+                "skipping duplicate record"
+            );
+            return Ok(CollectOutcome::Duplicate {
+                record_id: data.id,
+                content_hash,
+            });
+        }
+
+        let stored_data = prepare_record_for_storage(&data)?;
+        self.session_content_hashes
+            .entry(*session_id)
+            .or_default()
+            .insert(content_hash);
 
         // Update session
-        let session_snapshot = {
+        {
             let session = self.active_sessions.get_mut(session_id).ok_or(
                 SessionLifecycleError::SessionNotFound {
                     session_id: *session_id,
@@ -1631,15 +2313,15 @@ impl DataCollectorService {
             } else {
                 session.record_successful_capture(&stored_data, Utc::now());
             }
-            session.clone()
-        };
-
-        self.storage.store_session(&session_snapshot).await?;
+        }
 
-        // Update index
-        self.indexer.index_record(&stored_data);
+        // The record, its session and the index are written with the batch.
+        let record_id = stored_data.id;
+        self.pending_records.push(stored_data);
+        self.pending_since.get_or_insert_with(Instant::now);
+        self.flush_due_batch().await?;
 
-        Ok(())
+        Ok(CollectOutcome::Stored { record_id })
     }
 
     pub async fn collect_simulated_capture_frame(
@@ -1717,8 +2399,142 @@ impl DataCollectorService {
         if let Some(session) = self.active_sessions.get(session_id) {
             Ok(Some(session.clone()))
         } else {
-            self.storage.load_session(session_id).await
+            match self.storage.load_session(session_id).await? {
+                Some(session) => Ok(Some(self.refresh_stale_summary(session).await?)),
+                None => Ok(None),
+            }
+        }
+    }
+
+    /// Rebuilds a session's summary from its stored records and persists it.
+    /// `end_session` is the only other place the summary is recalculated, so
+    /// this covers records added out of band or a restart mid-session.
+    pub async fn recompute_summary(&mut self, session_id: &Uuid) -> Result<SessionSummary> {
+        self.flush_batch().await?;
+        let session = match self.active_sessions.get(session_id) {
+            Some(session) => session.clone(),
+            None => self.storage.load_session(session_id).await?.ok_or(
+                SessionLifecycleError::SessionNotFound {
+                    session_id: *session_id,
+                },
+            )?,
+        };
+        let session = self.store_recomputed_summary(session).await?;
+        if let Some(active) = self.active_sessions.get_mut(session_id) {
+            active.summary = session.summary.clone();
+        }
+        Ok(session.summary)
+    }
+
+    /// Re-runs the data-quality pass over a session's current records and
+    /// stores the result in its summary. `end_session` runs the same pass.
+    pub async fn assess_session_quality(
+        &mut self,
+        session_id: &Uuid,
+    ) -> Result<SessionQualityAssessment> {
+        self.flush_batch().await?;
+        let mut session = match self.active_sessions.get(session_id) {
+            Some(session) => session.clone(),
+            None => self.storage.load_session(session_id).await?.ok_or(
+                SessionLifecycleError::SessionNotFound {
+                    session_id: *session_id,
+                },
+            )?,
+        };
+        let assessment = self.score_quality(&session).await?;
+        session.summary.quality = Some(assessment.clone());
+        self.storage.store_session(&session).await?;
+        if let Some(active) = self.active_sessions.get_mut(session_id) {
+            active.summary.quality = Some(assessment.clone());
+        }
+        Ok(assessment)
+    }
+
+    async fn score_quality(&self, session: &FlightSession) -> Result<SessionQualityAssessment> {
+        let mut records = Vec::with_capacity(session.data_records.len());
+        for record_id in &session.data_records {
+            records.extend(self.load_record(record_id).await?);
+        }
+        Ok(score_session_quality(
+            session,
+            &records,
+            &self.quality_config,
+        ))
+    }
+
+    /// Estimates each sensor stream's clock offset against the session's
+    /// telemetry. Nothing is changed; see `apply_time_sync_correction`.
+    pub async fn audit_time_sync(
+        &mut self,
+        session_id: &Uuid,
+        config: &TimeSyncConfig,
+    ) -> Result<TimeSyncReport> {
+        self.flush_batch().await?;
+        let session = self.require_session(session_id).await?;
+        let records = self.load_session_records(&session).await?;
+        Ok(estimate_stream_offsets(session.id, &records, config))
+    }
+
+    /// Rewrites the timestamps of every stream `report` estimated an offset
+    /// for, keeping the sensor's own timestamp in the record metadata, and
+    /// reindexes. Returns how many records were rewritten.
+    pub async fn apply_time_sync_correction(&mut self, report: &TimeSyncReport) -> Result<usize> {
+        self.flush_batch().await?;
+        let offsets = report
+            .streams
+            .iter()
+            .filter_map(|stream| {
+                let offset = stream.estimate.offset_seconds()?;
+                Some((stream.sensor_id.as_str(), offset))
+            })
+            .collect::<HashMap<_, _>>();
+        if offsets.is_empty() {
+            return Ok(0);
+        }
+
+        let session = self.require_session(&report.session_id).await?;
+        let mut corrected = Vec::new();
+        for mut record in self.load_session_records(&session).await? {
+            if record.data_type == DataType::Telemetry {
+                continue;
+            }
+            if let Some(offset) = offsets.get(record.sensor_id.as_str()) {
+                apply_time_offset(&mut record, *offset);
+                corrected.push(prepare_record_for_storage(&record)?);
+            }
+        }
+        self.storage.replace_records(&corrected).await?;
+
+        let persisted_records = self.storage.load_all_data().await?;
+        self.indexer
+            .rebuild_from_records(&persisted_records)
+            .await?;
+        tracing::info!(
+            session_id = %report.session_id,
+            records = corrected.len(),
+            "applied sensor clock corrections"
+        );
+        Ok(corrected.len())
+    }
+
+    async fn refresh_stale_summary(&self, session: FlightSession) -> Result<FlightSession> {
+        let stored_count = session.data_records.len();
+        if session.summary.record_count as usize == stored_count {
+            return Ok(session);
         }
+        tracing::warn!(
+            session_id = %session.id,
+            summary_record_count = session.summary.record_count,
+            stored_count,
+            "stored session summary is stale; recomputing"
+        );
+        self.store_recomputed_summary(session).await
+    }
+
+    async fn store_recomputed_summary(&self, mut session: FlightSession) -> Result<FlightSession> {
+        session.summary = self.calculate_session_summary(&session).await?;
+        self.storage.store_session(&session).await?;
+        Ok(session)
     }
 
     pub async fn list_sessions(
@@ -1726,7 +2542,10 @@ impl DataCollectorService {
         drone_id: Option<Uuid>,
         limit: Option<u32>,
     ) -> Result<Vec<FlightSession>> {
-        let mut sessions = self.storage.list_sessions(drone_id, limit).await?;
+        let mut sessions = Vec::new();
+        for session in self.storage.list_sessions(drone_id, limit).await? {
+            sessions.push(self.refresh_stale_summary(session).await?);
+        }
 
         // Add active sessions
         for session in self.active_sessions.values() {
@@ -1741,6 +2560,68 @@ impl DataCollectorService {
         Ok(sessions)
     }
 
+    /// Adds `tags` to a session and persists it. Blank tags and tags the
+    /// session already has under any casing are skipped.
+    pub async fn add_session_tags(
+        &mut self,
+        session_id: &Uuid,
+        tags: &[String],
+    ) -> Result<FlightSession> {
+        self.update_session_tags(session_id, |session_tags| {
+            for tag in tags.iter().map(|tag| tag.trim()) {
+                if !tag.is_empty()
+                    && !session_tags
+                        .iter()
+                        .any(|existing| tags_match(existing, tag))
+                {
+                    session_tags.push(tag.to_string());
+                }
+            }
+        })
+        .await
+    }
+
+    /// Removes the session's tags matching any of `tags`, ignoring case, and
+    /// persists it.
+    pub async fn remove_session_tags(
+        &mut self,
+        session_id: &Uuid,
+        tags: &[String],
+    ) -> Result<FlightSession> {
+        self.update_session_tags(session_id, |session_tags| {
+            session_tags
+                .retain(|existing| !tags.iter().any(|tag| tags_match(existing, tag.trim())));
+        })
+        .await
+    }
+
+    async fn update_session_tags(
+        &mut self,
+        session_id: &Uuid,
+        update: impl FnOnce(&mut Vec<String>),
+    ) -> Result<FlightSession> {
+        let mut session = self.require_session(session_id).await?;
+        update(&mut session.tags);
+        self.storage.store_session(&session).await?;
+        if let Some(active) = self.active_sessions.get_mut(session_id) {
+            active.tags = session.tags.clone();
+        }
+        Ok(session)
+    }
+
+    /// Stored and active sessions tagged `tag` under any casing, newest first.
+    pub async fn find_sessions_by_tag(&self, tag: &str) -> Result<Vec<FlightSession>> {
+        let tag = tag.trim();
+        let mut sessions = self.sessions_for_inspection().await?;
+        sessions.retain(|session| {
+            session
+                .tags
+                .iter()
+                .any(|existing| tags_match(existing, tag))
+        });
+        Ok(sessions)
+    }
+
     pub async fn list_capture_sessions(
         &self,
         filter: CaptureSessionListFilter,
@@ -1798,7 +2679,7 @@ impl DataCollectorService {
                 })?;
         let mut records = Vec::new();
         for record_id in &session.data_records {
-            if let Some(record) = self.storage.load_data(record_id).await? {
+            if let Some(record) = self.load_record(record_id).await? {
                 records.push(record);
             }
         }
@@ -1807,13 +2688,11 @@ impl DataCollectorService {
     }
 
     async fn sessions_for_inspection(&self) -> Result<Vec<FlightSession>> {
-        let mut sessions_by_id = self
-            .storage
-            .list_sessions(None, None)
-            .await?
-            .into_iter()
-            .map(|session| (session.id, session))
-            .collect::<HashMap<_, _>>();
+        let mut sessions_by_id = HashMap::new();
+        for session in self.storage.list_sessions(None, None).await? {
+            let session = self.refresh_stale_summary(session).await?;
+            sessions_by_id.insert(session.id, session);
+        }
 
         for session in self.active_sessions.values() {
             sessions_by_id.insert(session.id, session.clone());
@@ -1830,12 +2709,15 @@ impl DataCollectorService {
     }
 
     pub async fn search_data(&self, query: SearchQuery) -> Result<Vec<FlightDataRecord>> {
-        let persisted_records = self.storage.load_all_data().await?;
-        if persisted_records.is_empty() {
+        let mut records = self.storage.load_all_data().await?;
+        for pending in &self.pending_records {
+            records.push(verify_record_integrity(pending)?);
+        }
+        if records.is_empty() {
             return self.indexer.search(query).await;
         }
 
-        Ok(DataIndexer::filter_records(&persisted_records, &query))
+        Ok(DataIndexer::filter_records(&records, &query))
     }
 
     pub async fn export_session(
@@ -1849,16 +2731,185 @@ impl DataCollectorService {
             .await?
             .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
 
+        let format_name = format!("{format:?}").to_lowercase();
         let export_config = export::ExportConfig {
             format,
             include_metadata: true,
             compress: false,
         };
         let exporter = DataExporter::new(export_config);
+        let session_records = self.load_session_records(&session).await?;
+
+        if let Err(e) = exporter
+            .export_session(&session, &session_records, output_path)
+            .await
+        {
+            return Err(anyhow::anyhow!("Export failed: {}", e));
+        }
+
+        self.register_product(
+            session_id,
+            NewDerivedProduct::new(
+                ProductProducer::SessionExport,
+                format!("session_export_{format_name}"),
+                output_path,
+            )
+            .with_parameters(&serde_json::json!({
+                "format": format_name,
+                "include_metadata": true,
+            })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Writes the regulatory compliance zip for a session to `output_path`.
+    pub async fn export_compliance_bundle(
+        &self,
+        session_id: &Uuid,
+        request: &ComplianceBundleRequest,
+        output_path: &Path,
+    ) -> Result<ComplianceManifest> {
+        let session = self
+            .get_session(session_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
+        let session_records = self.load_session_records(&session).await?;
+
+        let bundle = ComplianceBundle::build(&session, &session_records, request)?;
+        bundle.write_zip(std::fs::File::create(output_path)?)?;
+        self.register_product(
+            session_id,
+            NewDerivedProduct::new(
+                ProductProducer::ComplianceExport,
+                "compliance_bundle",
+                output_path,
+            )
+            .with_parameters(&serde_json::to_value(request)?),
+        )
+        .await?;
+        Ok(bundle.manifest)
+    }
+
+    /// Records an artifact derived from `session_id`. Parents must already be
+    /// registered for the same session.
+    pub async fn register_product(
+        &self,
+        session_id: &Uuid,
+        product: NewDerivedProduct,
+    ) -> Result<DerivedProduct> {
+        self.require_session(session_id).await?;
+        let mut products = self.storage.load_products(session_id).await?;
+        let product = products::new_product(*session_id, product, &products, Utc::now())?;
+        products.push(product.clone());
+        self.storage.store_products(session_id, &products).await?;
+        Ok(product)
+    }
+
+    /// Products registered for a session, optionally only those of one type.
+    pub async fn list_products(
+        &self,
+        session_id: &Uuid,
+        product_type: Option<&str>,
+    ) -> Result<Vec<DerivedProduct>> {
+        self.require_session(session_id).await?;
+        let mut products = self.storage.load_products(session_id).await?;
+        if let Some(product_type) = product_type {
+            products.retain(|product| product.product_type == product_type);
+        }
+        Ok(products)
+    }
+
+    /// Everything `product_id` was derived from, nearest inputs first.
+    pub async fn product_inputs(
+        &self,
+        session_id: &Uuid,
+        product_id: &Uuid,
+    ) -> Result<Vec<DerivedProduct>> {
+        let products = self.list_products(session_id, None).await?;
+        Ok(products::product_inputs(
+            *session_id,
+            &products,
+            *product_id,
+        )?)
+    }
+
+    /// Everything derived from `product_id`, nearest outputs first.
+    pub async fn product_outputs(
+        &self,
+        session_id: &Uuid,
+        product_id: &Uuid,
+    ) -> Result<Vec<DerivedProduct>> {
+        let products = self.list_products(session_id, None).await?;
+        Ok(products::product_outputs(
+            *session_id,
+            &products,
+            *product_id,
+        )?)
+    }
+
+    /// Deletes a finished session, its records and, depending on
+    /// `disposition`, its derived products.
+    pub async fn delete_session(
+        &mut self,
+        session_id: &Uuid,
+        disposition: ProductDisposition,
+    ) -> Result<SessionDeletion> {
+        let session = self.require_session(session_id).await?;
+        if matches!(
+            session.status,
+            SessionStatus::Started | SessionStatus::Collecting
+        ) {
+            return Err(ProductRegistryError::SessionActive {
+                session_id: *session_id,
+            }
+            .into());
+        }
 
+        let deletion = self.storage.delete_session(&session, disposition).await?;
+        self.active_sessions.remove(session_id);
+        self.session_content_hashes.remove(session_id);
+        let persisted_records = self.storage.load_all_data().await?;
+        self.indexer
+            .rebuild_from_records(&persisted_records)
+            .await?;
+        Ok(deletion)
+    }
+
+    /// A collected record, whether it is still pending or already stored.
+    async fn load_record(&self, record_id: &Uuid) -> Result<Option<FlightDataRecord>> {
+        if let Some(pending) = self
+            .pending_records
+            .iter()
+            .find(|record| record.id == *record_id)
+        {
+            return verify_record_integrity(pending).map(Some);
+        }
+        self.storage.load_data(record_id).await
+    }
+
+    async fn require_session(&self, session_id: &Uuid) -> Result<FlightSession> {
+        self.get_session(session_id).await?.ok_or_else(|| {
+            SessionLifecycleError::SessionNotFound {
+                session_id: *session_id,
+            }
+            .into()
+        })
+    }
+
+    /// Loads every record listed in a session, in the order they were stored.
+    pub async fn session_records(&self, session_id: &Uuid) -> Result<Vec<FlightDataRecord>> {
+        let session = self
+            .get_session(session_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Session not found"))?;
+        self.load_session_records(&session).await
+    }
+
+    async fn load_session_records(&self, session: &FlightSession) -> Result<Vec<FlightDataRecord>> {
         let mut session_records = Vec::with_capacity(session.data_records.len());
         for record_id in &session.data_records {
-            let record = self.storage.load_data(record_id).await?.ok_or_else(|| {
+            let record = self.load_record(record_id).await?.ok_or_else(|| {
                 anyhow::anyhow!(
                     "record {} listed in session {} was not found",
                     record_id,
@@ -1867,14 +2918,40 @@ impl DataCollectorService {
             })?;
             session_records.push(record);
         }
+        Ok(session_records)
+    }
 
-        match exporter
-            .export_session(&session, &session_records, output_path)
-            .await
-        {
-            Ok(_) => Ok(()),
-            Err(e) => Err(anyhow::anyhow!("Export failed: {}", e)),
+    /// The telemetry fixes of a session in time order, or `None` for an
+    /// unknown session.
+    pub async fn session_track(&self, session_id: &Uuid) -> Result<Option<Vec<SessionTrackFix>>> {
+        let Some(session) = self.get_session(session_id).await? else {
+            return Ok(None);
+        };
+        let mut records = self.load_session_records(&session).await?;
+        records.sort_by_key(|record| record.timestamp);
+
+        let mut track: Vec<SessionTrackFix> = Vec::new();
+        for record in &records {
+            match &record.payload {
+                DataPayload::Telemetry(telemetry) => {
+                    let (latitude, longitude, altitude_m) = telemetry.position;
+                    track.push(SessionTrackFix {
+                        timestamp: record.timestamp,
+                        latitude,
+                        longitude,
+                        altitude_m,
+                        capture_event: false,
+                    });
+                }
+                DataPayload::MediaFile(_) => {
+                    if let Some(fix) = track.last_mut() {
+                        fix.capture_event = true;
+                    }
+                }
+                _ => {}
+            }
         }
+        Ok(Some(track))
     }
 
     pub async fn cleanup_old_data(&mut self) -> Result<u32> {
@@ -1901,13 +2978,14 @@ impl DataCollectorService {
             coverage: session.summary.coverage.clone(),
             collection_failures: session.summary.collection_failures.clone(),
             capture_health: session.summary.capture_health.clone(),
+            quality: session.summary.quality.clone(),
             ..SessionSummary::default()
         };
         let mut loaded_record_count = 0u32;
 
         // Calculate from stored records
         for record_id in &session.data_records {
-            if let Some(record) = self.storage.load_data(record_id).await? {
+            if let Some(record) = self.load_record(record_id).await? {
                 loaded_record_count += 1;
                 summary.record_count += 1;
                 summary.total_data_size_bytes += record.size_bytes;
@@ -1947,9 +3025,9 @@ impl DataCollectorService {
     ) -> Result<Vec<FlightDataRecord>> {
         let mut records = Vec::new();
         for record_id in &session.data_records {
-            if let Some(record) = self.storage.load_data(record_id).await? {
+            if let Some(record) = self.load_record(record_id).await? {
                 if record.data_type == DataType::Telemetry
-                    && matches!(record.payload, DataPayload::Telemetry { .. })
+                    && matches!(record.payload, DataPayload::Telemetry(_))
                 {
                     records.push(record);
                 }
@@ -2005,6 +3083,10 @@ impl DataCollectorService {
     }
 }
 
+fn tags_match(left: &str, right: &str) -> bool {
+    left.to_lowercase() == right.to_lowercase()
+}
+
 fn capture_session_list_item(session: &FlightSession) -> CaptureSessionListItem {
     CaptureSessionListItem {
         session_id: session.id,
@@ -2022,6 +3104,11 @@ fn capture_session_list_item(session: &FlightSession) -> CaptureSessionListItem
         aggregate_evidence: session.summary.aggregate_evidence.clone(),
         capture_health: session.summary.capture_health.clone(),
         qa: CaptureQaSummary::from_summary(&session.summary),
+        quality_score: session
+            .summary
+            .quality
+            .as_ref()
+            .map(|quality| quality.score),
     }
 }
 
@@ -2043,11 +3130,11 @@ fn telemetry_samples(records: &[FlightDataRecord]) -> Vec<TelemetryAggregateSamp
 
 fn telemetry_sample(record: &FlightDataRecord) -> Option<TelemetryAggregateSample> {
     match record.payload {
-        DataPayload::Telemetry {
+        DataPayload::Telemetry(TelemetryPayload {
             position,
             battery_level,
             ..
-        } => Some(TelemetryAggregateSample {
+        }) => Some(TelemetryAggregateSample {
             latitude: position.0,
             longitude: position.1,
             altitude_m: f64::from(position.2),
@@ -2071,7 +3158,7 @@ fn distance_between_samples(
     horizontal.hypot(altitude_delta)
 }
 
-fn haversine_distance_m(
+pub(crate) fn haversine_distance_m(
     left_latitude: f64,
     left_longitude: f64,
     right_latitude: f64,
@@ -2180,6 +3267,7 @@ impl Default for SessionSummary {
             collection_failures: Vec::new(),
             capture_health: CaptureHealth::default(),
             aggregate_evidence: SessionAggregateEvidence::default(),
+            quality: None,
         }
     }
 }
@@ -2241,13 +3329,13 @@ mod tests {
             session.flight_id,
             session.drone_id,
             DataType::Telemetry,
-            DataPayload::Telemetry {
+            DataPayload::Telemetry(TelemetryPayload {
                 position: (40.0, -105.0, 30.0),
                 velocity: (1.0, 0.0, 0.0),
                 orientation: (0.0, 0.0, 0.0),
                 battery_level: 0.9,
                 signal_strength: 0.95,
-            },
+            }),
             provenance(session),
             256,
         )
@@ -2265,13 +3353,13 @@ mod tests {
             session.flight_id,
             session.drone_id,
             DataType::Telemetry,
-            DataPayload::Telemetry {
+            DataPayload::Telemetry(TelemetryPayload {
                 position: (latitude, longitude, 30.0),
                 velocity: (1.0, 0.0, 0.0),
                 orientation: (0.0, 0.0, 0.0),
                 battery_level,
                 signal_strength: 0.95,
-            },
+            }),
             FlightDataProvenance::complete(
                 session.id,
                 "telemetry-track-01".to_string(),
@@ -2289,13 +3377,13 @@ mod tests {
             session.flight_id,
             session.drone_id,
             DataType::LidarScan,
-            DataPayload::PointCloud {
+            DataPayload::PointCloud(PointCloudPayload {
                 point_count: 1,
                 bounds: ((0.0, 0.0, 0.0), (0.0, 0.0, 0.0)),
                 format: "ply".to_string(),
                 has_color: false,
                 has_intensity: true,
-            },
+            }),
             FlightDataProvenance::complete(
                 session.id,
                 "lidar-qa-01".to_string(),
@@ -2328,43 +3416,43 @@ mod tests {
     fn all_payloads() -> Vec<DataPayload> {
         let now = Utc::now();
         vec![
-            DataPayload::Telemetry {
+            DataPayload::Telemetry(TelemetryPayload {
                 position: (40.0, -105.0, 30.0),
                 velocity: (1.0, 0.0, 0.0),
                 orientation: (0.0, 0.0, 0.0),
                 battery_level: 0.9,
                 signal_strength: 0.95,
-            },
-            DataPayload::SensorData {
+            }),
+            DataPayload::SensorData(SensorDataPayload {
                 sensor_type: "multispectral".to_string(),
                 values: HashMap::from([("nir".to_string(), 0.72)]),
                 calibration_info: Some("calibration-2026-06".to_string()),
-            },
-            DataPayload::MediaFile {
+            }),
+            DataPayload::MediaFile(MediaFilePayload {
                 file_type: "image/tiff".to_string(),
                 dimensions: Some((1024, 1024)),
                 duration_seconds: None,
                 compression: Some("none".to_string()),
-            },
-            DataPayload::PointCloud {
+            }),
+            DataPayload::PointCloud(PointCloudPayload {
                 point_count: 42,
                 bounds: ((0.0, 0.0, 0.0), (1.0, 1.0, 1.0)),
                 format: "ply".to_string(),
                 has_color: false,
                 has_intensity: true,
-            },
-            DataPayload::TrackLog {
+            }),
+            DataPayload::TrackLog(TrackLogPayload {
                 waypoint_count: 2,
                 total_distance_m: 10.0,
                 duration_seconds: 5.0,
                 start_time: now,
                 end_time: now + chrono::Duration::seconds(5),
-            },
-            DataPayload::Raw {
+            }),
+            DataPayload::Raw(RawPayload {
                 format: "json".to_string(),
                 schema: Some("agbot.raw.v1".to_string()),
                 compression: None,
-            },
+            }),
         ]
     }
 
@@ -2406,8 +3494,8 @@ mod tests {
         }
     }
 
-    async fn start_linked_capture_session(
-        service: &mut DataCollectorService,
+    async fn start_linked_capture_session<S: Storage>(
+        service: &mut DataCollectorService<S>,
         request: CaptureSessionRequest,
     ) -> Uuid {
         service
@@ -2417,28 +3505,347 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_service_creation() {
+    async fn test_service_creation() {
+        let temp_dir = tempdir().unwrap();
+        let service = DataCollectorService::new(temp_dir.path().to_path_buf());
+        assert!(service.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+
+        let request = capture_request();
+        let drone_id = request.drone_id;
+        let session_id = start_linked_capture_session(&mut service, request).await;
+
+        let session = service.get_session(&session_id).await.unwrap().unwrap();
+        assert_eq!(session.drone_id, drone_id);
+        assert_eq!(session.status, SessionStatus::Started);
+
+        let ended_session = service.end_session(&session_id).await.unwrap();
+        assert_eq!(ended_session.status, SessionStatus::Ended);
+        assert!(ended_session.end_time.is_some());
+    }
+
+    #[tokio::test]
+    async fn sessions_are_found_by_tag_ignoring_case() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let north = start_linked_capture_session(
+            &mut service,
+            capture_request().with_tags(vec!["North Field".to_string()]),
+        )
+        .await;
+        let south = start_linked_capture_session(&mut service, capture_request()).await;
+        let untagged = start_linked_capture_session(&mut service, capture_request()).await;
+        service.end_session(&north).await.unwrap();
+
+        let tagged = service
+            .add_session_tags(
+                &north,
+                &["Irrigation".to_string(), "north field".to_string()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(tagged.tags, vec!["North Field", "Irrigation"]);
+        service
+            .add_session_tags(&south, &["irrigation".to_string(), " ".to_string()])
+            .await
+            .unwrap();
+
+        let mut found = service
+            .find_sessions_by_tag("IRRIGATION")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|session| session.id)
+            .collect::<Vec<_>>();
+        found.sort();
+        let mut expected = vec![north, south];
+        expected.sort();
+        assert_eq!(found, expected);
+        assert!(!found.contains(&untagged));
+
+        let restarted = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let stored = restarted.get_session(&north).await.unwrap().unwrap();
+        assert_eq!(stored.tags, vec!["North Field", "Irrigation"]);
+
+        let retagged = service
+            .remove_session_tags(&north, &["IRRIGATION".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(retagged.tags, vec!["North Field"]);
+        let found = service.find_sessions_by_tag("irrigation").await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, south);
+    }
+
+    #[tokio::test]
+    async fn end_session_flushes_pending_records() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let session_id = start_linked_capture_session(&mut service, capture_request()).await;
+        let session = service.get_session(&session_id).await.unwrap().unwrap();
+        let start = Utc::now();
+
+        for index in 0..3 {
+            let record = telemetry_record_at(
+                &session,
+                start + chrono::Duration::seconds(index),
+                40.0 + index as f64 * 0.0001,
+                -105.0,
+                0.9,
+            );
+            service.collect_data(&session_id, record).await.unwrap();
+        }
+        assert_eq!(service.pending_record_count(), 3);
+        assert!(service.storage.load_all_data().await.unwrap().is_empty());
+        assert_eq!(service.session_records(&session_id).await.unwrap().len(), 3);
+
+        let ended = service.end_session(&session_id).await.unwrap();
+
+        assert_eq!(service.pending_record_count(), 0);
+        assert_eq!(ended.summary.record_count, 3);
+        assert_eq!(service.storage.load_all_data().await.unwrap().len(), 3);
+        let restarted = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        assert_eq!(
+            restarted.session_records(&session_id).await.unwrap().len(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn second_session_on_a_busy_drone_is_rejected_by_default() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let first = capture_request();
+        let drone_id = first.drone_id;
+        let first_id = start_linked_capture_session(&mut service, first).await;
+
+        let mut second = capture_request();
+        second.drone_id = drone_id;
+        service
+            .register_capture_linkage(CaptureLinkageReference::from_request(&second))
+            .unwrap();
+        let error = service.start_capture_session(second).await.unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<SessionLifecycleError>(),
+            Some(&SessionLifecycleError::DroneSessionActive {
+                drone_id,
+                session_id: first_id,
+            })
+        );
+        let first = service.get_session(&first_id).await.unwrap().unwrap();
+        assert_eq!(first.status, SessionStatus::Started);
+    }
+
+    #[tokio::test]
+    async fn new_session_aborts_the_one_in_progress_when_configured() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_session_policy(SessionPolicy {
+                concurrent: ConcurrentSessionPolicy::AbortExisting,
+                ..SessionPolicy::default()
+            });
+        let first = capture_request();
+        let drone_id = first.drone_id;
+        let first_id = start_linked_capture_session(&mut service, first).await;
+
+        let mut second = capture_request();
+        second.drone_id = drone_id;
+        let second_id = start_linked_capture_session(&mut service, second).await;
+
+        let first = service.get_session(&first_id).await.unwrap().unwrap();
+        assert_eq!(first.status, SessionStatus::Aborted);
+        assert!(first.end_time.is_some());
+        let second = service.get_session(&second_id).await.unwrap().unwrap();
+        assert_eq!(second.status, SessionStatus::Started);
+    }
+
+    #[tokio::test]
+    async fn sweeper_aborts_sessions_idle_past_the_timeout() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_batch_config(CollectBatchConfig::unbatched())
+            .with_session_policy(SessionPolicy {
+                idle_timeout: Duration::from_secs(60),
+                ..SessionPolicy::default()
+            });
+        let idle_id = start_linked_capture_session(&mut service, capture_request()).await;
+        let busy_id = start_linked_capture_session(&mut service, capture_request()).await;
+        let long_ago = Utc::now() - chrono::Duration::seconds(61);
+        service.session_heartbeats.insert(idle_id, long_ago);
+        service.session_heartbeats.insert(busy_id, long_ago);
+
+        let busy = service.get_session(&busy_id).await.unwrap().unwrap();
+        service
+            .collect_data(&busy_id, telemetry_record(&busy))
+            .await
+            .unwrap();
+        let aborted = service.sweep_idle_sessions(Utc::now()).await.unwrap();
+
+        assert_eq!(aborted.len(), 1);
+        assert_eq!(aborted[0].id, idle_id);
+        assert_eq!(aborted[0].status, SessionStatus::Aborted);
+        let stored = service.get_session(&idle_id).await.unwrap().unwrap();
+        assert_eq!(stored.status, SessionStatus::Aborted);
+        let busy = service.get_session(&busy_id).await.unwrap().unwrap();
+        assert_eq!(busy.status, SessionStatus::Collecting);
+    }
+
+    #[tokio::test]
+    async fn sessions_left_open_by_a_crash_are_restored_and_swept() {
+        use crate::storage_backend::memory::MemoryStorage;
+
         let temp_dir = tempdir().unwrap();
-        let service = DataCollectorService::new(temp_dir.path().to_path_buf());
-        assert!(service.is_ok());
+        let backend = MemoryStorage::default();
+        let mut crashed =
+            DataCollectorService::with_storage(temp_dir.path().to_path_buf(), backend.clone())
+                .await
+                .unwrap();
+        let first = capture_request();
+        let drone_id = first.drone_id;
+        let open_id = start_linked_capture_session(&mut crashed, first).await;
+        let ended_id = start_linked_capture_session(&mut crashed, capture_request()).await;
+        crashed.end_session(&ended_id).await.unwrap();
+        drop(crashed);
+
+        let mut restarted =
+            DataCollectorService::with_storage(temp_dir.path().to_path_buf(), backend)
+                .await
+                .unwrap()
+                .with_session_policy(SessionPolicy {
+                    idle_timeout: Duration::from_secs(60),
+                    ..SessionPolicy::default()
+                });
+        assert_eq!(
+            restarted.session_heartbeats.keys().collect::<Vec<_>>(),
+            vec![&open_id]
+        );
+        let mut second = capture_request();
+        second.drone_id = drone_id;
+        restarted
+            .register_capture_linkage(CaptureLinkageReference::from_request(&second))
+            .unwrap();
+        let error = restarted
+            .start_capture_session(second.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<SessionLifecycleError>(),
+            Some(&SessionLifecycleError::DroneSessionActive {
+                drone_id,
+                session_id: open_id,
+            })
+        );
+
+        let aborted = restarted
+            .sweep_idle_sessions(Utc::now() + chrono::Duration::seconds(61))
+            .await
+            .unwrap();
+        assert_eq!(
+            aborted.iter().map(|session| session.id).collect::<Vec<_>>(),
+            vec![open_id]
+        );
+        let stored = restarted.get_session(&open_id).await.unwrap().unwrap();
+        assert_eq!(stored.status, SessionStatus::Aborted);
+        restarted.start_capture_session(second).await.unwrap();
     }
 
     #[tokio::test]
-    async fn test_session_lifecycle() {
+    async fn sessions_and_records_round_trip_through_an_in_memory_backend() {
+        use crate::storage_backend::memory::MemoryStorage;
+
         let temp_dir = tempdir().unwrap();
-        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let backend = MemoryStorage::default();
+        let mut service =
+            DataCollectorService::with_storage(temp_dir.path().to_path_buf(), backend.clone())
+                .await
+                .unwrap();
+        let session_id = start_linked_capture_session(&mut service, capture_request()).await;
+        let session = service.get_session(&session_id).await.unwrap().unwrap();
+        let start = Utc::now();
+        for index in 0..3 {
+            let record = telemetry_record_at(
+                &session,
+                start + chrono::Duration::seconds(index),
+                40.0 + index as f64 * 0.0001,
+                -105.0,
+                0.9,
+            );
+            service.collect_data(&session_id, record).await.unwrap();
+        }
+        let ended = service.end_session(&session_id).await.unwrap();
+        assert_eq!(ended.summary.record_count, 3);
 
-        let request = capture_request();
-        let drone_id = request.drone_id;
-        let session_id = start_linked_capture_session(&mut service, request).await;
+        assert!(backend.keys().contains(&format!(
+            "sessions/{}/{}/{session_id}/session.json",
+            session.drone_id,
+            session.start_time.format("%Y-%m-%d")
+        )));
+        assert!(backend
+            .keys()
+            .iter()
+            .any(|key| key.starts_with("batches/") && key.ends_with(".log")));
+        assert!(!temp_dir.path().join("sessions").exists());
+        assert!(!temp_dir.path().join("batches").exists());
+
+        let restarted = DataCollectorService::with_storage(temp_dir.path().to_path_buf(), backend)
+            .await
+            .unwrap();
+        let records = restarted.session_records(&session_id).await.unwrap();
+        assert_eq!(
+            records.iter().map(|record| record.id).collect::<Vec<_>>(),
+            ended.data_records
+        );
+        assert_eq!(
+            restarted.get_storage_stats().await.unwrap().total_records,
+            3
+        );
+    }
 
+    #[tokio::test]
+    async fn ten_thousand_records_collect_through_batches_without_loss() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_batch_config(CollectBatchConfig {
+                max_records: 50,
+                max_age: Duration::from_secs(3600),
+            });
+        let session_id = start_linked_capture_session(&mut service, capture_request()).await;
         let session = service.get_session(&session_id).await.unwrap().unwrap();
-        assert_eq!(session.drone_id, drone_id);
-        assert_eq!(session.status, SessionStatus::Started);
+        let start = Utc::now();
 
-        let ended_session = service.end_session(&session_id).await.unwrap();
-        assert_eq!(ended_session.status, SessionStatus::Ended);
-        assert!(ended_session.end_time.is_some());
+        for index in 0..10_000 {
+            let record = telemetry_record_at(
+                &session,
+                start + chrono::Duration::milliseconds(50 * index),
+                40.0 + (index % 100) as f64 * 0.0001,
+                -105.0 + (index / 100) as f64 * 0.0001,
+                0.9,
+            );
+            service.collect_data(&session_id, record).await.unwrap();
+        }
+        assert_eq!(service.pending_record_count(), 0);
+        let ended = service.end_session(&session_id).await.unwrap();
+        assert_eq!(ended.summary.record_count, 10_000);
+
+        let restarted = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let summary = restarted.storage.batch_log_summary().await;
+        assert_eq!(summary.batches, 200);
+        assert_eq!(summary.records, 10_000);
+        let records = restarted.session_records(&session_id).await.unwrap();
+        assert_eq!(records.len(), 10_000);
+        assert!(records
+            .iter()
+            .zip(&ended.data_records)
+            .all(|(record, record_id)| record.id == *record_id));
     }
 
     #[tokio::test]
@@ -2462,10 +3869,9 @@ mod tests {
         assert_eq!(session.status, SessionStatus::Started);
         assert!(session.end_time.is_none());
 
-        let stored_path = temp_dir
-            .path()
-            .join("sessions")
-            .join(session_id.to_string())
+        let stored_path = service
+            .storage
+            .get_session_path(&session)
             .join("session.json");
         let stored_json = tokio::fs::read(&stored_path).await.unwrap();
         let stored_session: FlightSession = serde_json::from_slice(&stored_json).unwrap();
@@ -2522,7 +3928,9 @@ mod tests {
     #[tokio::test]
     async fn test_collect_data_transitions_started_session_to_collecting() {
         let temp_dir = tempdir().unwrap();
-        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_batch_config(CollectBatchConfig::unbatched());
 
         let session_id = start_linked_capture_session(&mut service, capture_request()).await;
         let session = service.get_session(&session_id).await.unwrap().unwrap();
@@ -2620,11 +4028,119 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn stored_record_checksum_verifies_and_detects_tamper() {
+    async fn duplicate_record_is_skipped_within_session() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let session_id = start_linked_capture_session(&mut service, capture_request()).await;
+        let session = service.get_session(&session_id).await.unwrap().unwrap();
+        let record = telemetry_record(&session);
+        let mut retransmitted = record.clone();
+        retransmitted.id = Uuid::new_v4();
+
+        let first = service
+            .collect_data(&session_id, record.clone())
+            .await
+            .unwrap();
+        let second = service
+            .collect_data(&session_id, retransmitted)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            first,
+            CollectOutcome::Stored {
+                record_id: record.id
+            }
+        );
+        assert!(second.is_duplicate());
+        let session = service.get_session(&session_id).await.unwrap().unwrap();
+        assert_eq!(session.summary.record_count, 1);
+        assert_eq!(session.data_records, vec![record.id]);
+        service.flush_batch().await.unwrap();
+        assert_eq!(service.storage.load_all_data().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn duplicate_record_is_skipped_after_a_restart() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::open(temp_dir.path().to_path_buf())
+            .await
+            .unwrap();
+        let session_id = start_linked_capture_session(&mut service, capture_request()).await;
+        let session = service.get_session(&session_id).await.unwrap().unwrap();
+        let record = telemetry_record(&session);
+        service
+            .collect_data(&session_id, record.clone())
+            .await
+            .unwrap();
+        service.shutdown().await.unwrap();
+        drop(service);
+
+        let mut restarted = DataCollectorService::open(temp_dir.path().to_path_buf())
+            .await
+            .unwrap();
+        let mut retransmitted = record.clone();
+        retransmitted.id = Uuid::new_v4();
+        let outcome = restarted
+            .collect_data(&session_id, retransmitted)
+            .await
+            .unwrap();
+
+        assert!(outcome.is_duplicate());
+        let session = restarted.get_session(&session_id).await.unwrap().unwrap();
+        assert_eq!(session.data_records, vec![record.id]);
+    }
+
+    #[tokio::test]
+    async fn stale_stored_summary_is_recomputed_on_load() {
         let temp_dir = tempdir().unwrap();
         let mut service = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
         let session_id = start_linked_capture_session(&mut service, capture_request()).await;
         let session = service.get_session(&session_id).await.unwrap().unwrap();
+        for _ in 0..2 {
+            service
+                .collect_data(&session_id, telemetry_record(&session))
+                .await
+                .unwrap();
+        }
+        let mut corrupted = service.end_session(&session_id).await.unwrap();
+        corrupted.summary.record_count = 7;
+        corrupted.summary.total_data_size_bytes = 0;
+        corrupted.summary.data_types.clear();
+        service.storage.store_session(&corrupted).await.unwrap();
+
+        let loaded = service.get_session(&session_id).await.unwrap().unwrap();
+
+        assert_eq!(loaded.summary.record_count, 2);
+        assert_eq!(loaded.summary.total_data_size_bytes, 512);
+        assert_eq!(
+            loaded.summary.data_types.get(&DataType::Telemetry),
+            Some(&2)
+        );
+        let persisted = service
+            .storage
+            .load_session(&session_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(persisted.summary.record_count, 2);
+
+        corrupted.summary.record_count = 2;
+        corrupted.summary.data_types.clear();
+        service.storage.store_session(&corrupted).await.unwrap();
+        let recomputed = service.recompute_summary(&session_id).await.unwrap();
+        assert_eq!(recomputed.data_types.get(&DataType::Telemetry), Some(&2));
+        assert_eq!(recomputed.total_data_size_bytes, 512);
+    }
+
+    #[tokio::test]
+    async fn stored_record_checksum_verifies_and_detects_tamper() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf())
+            .unwrap()
+            .with_batch_config(CollectBatchConfig::unbatched());
+        let session_id = start_linked_capture_session(&mut service, capture_request()).await;
+        let session = service.get_session(&session_id).await.unwrap().unwrap();
         let record = telemetry_record(&session);
         let record_id = record.id;
         let record_timestamp = record.timestamp;
@@ -2672,6 +4188,7 @@ mod tests {
         let record_id = record.id;
 
         service.collect_data(&session_id, record).await.unwrap();
+        service.flush_batch().await.unwrap();
 
         let session = service.get_session(&session_id).await.unwrap().unwrap();
         let loaded = service
@@ -3029,10 +4546,9 @@ mod tests {
         );
         assert!((session.summary.coverage.captured_fraction - 0.5).abs() < f32::EPSILON);
 
-        let stored_path = temp_dir
-            .path()
-            .join("sessions")
-            .join(session_id.to_string())
+        let stored_path = service
+            .storage
+            .get_session_path(&session)
             .join("session.json");
         let stored_json = tokio::fs::read(&stored_path).await.unwrap();
         let stored_session: FlightSession = serde_json::from_slice(&stored_json).unwrap();
@@ -3088,10 +4604,9 @@ mod tests {
         assert_eq!(stored_session.summary.capture_health, report.health);
         assert!(stored_session.summary.collection_failures.is_empty());
 
-        let stored_path = temp_dir
-            .path()
-            .join("sessions")
-            .join(session_id.to_string())
+        let stored_path = service
+            .storage
+            .get_session_path(&session)
             .join("session.json");
         let stored_json = tokio::fs::read(&stored_path).await.unwrap();
         let stored_session: FlightSession = serde_json::from_slice(&stored_json).unwrap();
@@ -3187,6 +4702,7 @@ mod tests {
         let timestamp = record.timestamp;
 
         service.collect_data(&session_id, record).await.unwrap();
+        service.shutdown().await.unwrap();
         drop(service);
 
         let restarted = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
@@ -3262,6 +4778,7 @@ mod tests {
                 flight_id: Some(request.flight_id),
                 started_after: Some(window_start),
                 started_before: Some(Utc::now() + chrono::Duration::minutes(1)),
+                min_quality_score: None,
                 offset: 0,
                 limit: 1,
             })
@@ -3287,6 +4804,185 @@ mod tests {
         assert!(page.items[0].qa.passed);
     }
 
+    #[tokio::test]
+    async fn ended_sessions_carry_a_quality_assessment_that_filters_listing() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let request = capture_request();
+        let base_time = Utc.timestamp_opt(1_800_000_000, 0).unwrap();
+
+        let steady_session_id = start_linked_capture_session(&mut service, request.clone()).await;
+        let steady_session = service
+            .get_session(&steady_session_id)
+            .await
+            .unwrap()
+            .unwrap();
+        for second in 0..10 {
+            service
+                .collect_data(
+                    &steady_session_id,
+                    telemetry_record_at(
+                        &steady_session,
+                        base_time + chrono::Duration::seconds(second),
+                        40.0,
+                        -105.0 + second as f64 * 0.0001,
+                        0.9,
+                    ),
+                )
+                .await
+                .unwrap();
+        }
+        let ended = service.end_session(&steady_session_id).await.unwrap();
+        assert_eq!(ended.summary.quality.as_ref().unwrap().score, 100);
+
+        let blind_session_id = start_linked_capture_session(&mut service, request.clone()).await;
+        let blind_session = service
+            .get_session(&blind_session_id)
+            .await
+            .unwrap()
+            .unwrap();
+        service
+            .collect_data(&blind_session_id, sparse_point_cloud_record(&blind_session))
+            .await
+            .unwrap();
+        let assessment = service
+            .assess_session_quality(&blind_session_id)
+            .await
+            .unwrap();
+        assert_eq!(
+            assessment
+                .flags_of(shared::data_quality::QualityFlagType::NoTelemetry)
+                .count(),
+            1
+        );
+        assert_eq!(assessment.score, 0);
+        let active = service
+            .get_session(&blind_session_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(active.summary.quality, Some(assessment));
+
+        let page = service
+            .list_capture_sessions(CaptureSessionListFilter {
+                field_id: Some(request.field_id),
+                min_quality_score: Some(80),
+                ..CaptureSessionListFilter::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(page.total_count, 1);
+        assert_eq!(page.items[0].session_id, steady_session_id);
+        assert_eq!(page.items[0].quality_score, Some(100));
+    }
+
+    #[tokio::test]
+    async fn time_sync_audit_corrects_a_lagging_thermal_clock() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let session_id = start_linked_capture_session(&mut service, capture_request()).await;
+        let session = service.get_session(&session_id).await.unwrap().unwrap();
+        let base_time = Utc.timestamp_opt(1_800_000_000, 0).unwrap();
+        let at =
+            |seconds: f64| base_time + chrono::Duration::milliseconds((seconds * 1000.0) as i64);
+        // East-bound pass whose speed swings between 2 and 8 m/s.
+        let east_m = |seconds: f64| {
+            5.0 * seconds
+                - 30.0 / std::f64::consts::PI * (std::f64::consts::PI * seconds / 10.0).cos()
+        };
+        // Rounded to 1e-7 degrees so coordinates survive the JSON round trip
+        // the integrity checksum is computed over.
+        let longitude = |seconds: f64| {
+            let degrees = -105.0 + east_m(seconds) / (111_320.0 * 40f64.to_radians().cos());
+            (degrees * 1e7).round() / 1e7
+        };
+
+        for tick in 0..=300 {
+            let seconds = f64::from(tick) / 5.0;
+            service
+                .collect_data(
+                    &session_id,
+                    telemetry_record_at(&session, at(seconds), 40.0, longitude(seconds), 0.9),
+                )
+                .await
+                .unwrap();
+        }
+        for second in 15..=45 {
+            let seconds = f64::from(second);
+            let mut frame = FlightDataRecord::new(
+                session.flight_id,
+                session.drone_id,
+                DataType::ThermalImage,
+                DataPayload::MediaFile(MediaFilePayload {
+                    file_type: "image/tiff".to_string(),
+                    dimensions: Some((640, 512)),
+                    duration_seconds: None,
+                    compression: None,
+                }),
+                FlightDataProvenance::complete(
+                    session.id,
+                    "thermal-01".to_string(),
+                    gps_coords_at(40.0, longitude(seconds), 30.0),
+                    at(seconds + 3.0),
+                    "thermal-calibration-v1".to_string(),
+                ),
+                512,
+            )
+            .unwrap();
+            let shift = east_m(seconds) - east_m(seconds - 1.0);
+            frame.metadata.insert(
+                time_sync::FOOTPRINT_OVERLAP_KEY.to_string(),
+                (1.0 - shift / 50.0).to_string(),
+            );
+            frame.metadata.insert(
+                time_sync::FOOTPRINT_LENGTH_KEY.to_string(),
+                "50".to_string(),
+            );
+            service.collect_data(&session_id, frame).await.unwrap();
+        }
+
+        let report = service
+            .audit_time_sync(&session_id, &TimeSyncConfig::default())
+            .await
+            .unwrap();
+        let offset = report
+            .stream("thermal-01")
+            .unwrap()
+            .estimate
+            .offset_seconds()
+            .unwrap();
+        assert!((offset + 3.0).abs() <= 0.2, "{offset}");
+
+        let corrected = service.apply_time_sync_correction(&report).await.unwrap();
+        assert_eq!(corrected, 31);
+
+        let records = service.session_records(&session_id).await.unwrap();
+        assert_eq!(records.len(), 301 + 31);
+        let first_frame = records
+            .iter()
+            .filter(|record| record.sensor_id == "thermal-01")
+            .min_by_key(|record| record.timestamp)
+            .unwrap();
+        assert!((first_frame.timestamp - at(15.0)).num_milliseconds().abs() <= 200);
+        assert_eq!(
+            first_frame.metadata[time_sync::ORIGINAL_TIMESTAMP_KEY],
+            "2027-01-15T08:00:18.000Z"
+        );
+
+        let rechecked = service
+            .audit_time_sync(&session_id, &TimeSyncConfig::default())
+            .await
+            .unwrap();
+        let residual = rechecked
+            .stream("thermal-01")
+            .unwrap()
+            .estimate
+            .offset_seconds()
+            .unwrap();
+        assert!(residual.abs() <= 0.05, "{residual}");
+    }
+
     #[tokio::test]
     async fn test_capture_session_inspection_surfaces_failed_capture_evidence() {
         let temp_dir = tempdir().unwrap();
@@ -3400,6 +5096,124 @@ mod tests {
         let exported_json = tokio::fs::read(&output_path).await.unwrap();
         let exported: serde_json::Value = serde_json::from_slice(&exported_json).unwrap();
         assert_eq!(exported["records"].as_array().unwrap().len(), 0);
+
+        let products = service.list_products(&session_id, None).await.unwrap();
+        assert_eq!(products.len(), 1);
+        assert_eq!(products[0].producer, ProductProducer::SessionExport);
+        assert_eq!(products[0].product_type, "session_export_json");
+        assert_eq!(products[0].file_path, output_path);
+    }
+
+    #[tokio::test]
+    async fn derived_product_lineage_and_session_delete_cascade_or_orphan() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let outputs = temp_dir.path().join("outputs");
+        std::fs::create_dir_all(&outputs).unwrap();
+
+        let mut chains = Vec::new();
+        for _ in 0..2 {
+            let session_id = start_linked_capture_session(&mut service, capture_request()).await;
+            let mut parents = Vec::new();
+            let mut chain = Vec::new();
+            for (producer, product_type) in [
+                (ProductProducer::NdviAnalysis, "ndvi"),
+                (ProductProducer::SensorOverlay, "overlay"),
+                (ProductProducer::Report, "report"),
+            ] {
+                let file_path = outputs.join(format!("{session_id}-{product_type}.bin"));
+                std::fs::write(&file_path, product_type).unwrap();
+                let product = service
+                    .register_product(
+                        &session_id,
+                        NewDerivedProduct::new(producer, product_type, &file_path)
+                            .with_parameters(&serde_json::json!({ "stage": product_type }))
+                            .with_parents(parents),
+                    )
+                    .await
+                    .unwrap();
+                parents = vec![product.id];
+                chain.push(product);
+            }
+            service.end_session(&session_id).await.unwrap();
+            chains.push((session_id, chain));
+        }
+
+        let (session_id, chain) = &chains[0];
+        let ids = |products: Vec<DerivedProduct>| {
+            products
+                .into_iter()
+                .map(|product| product.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(service.list_products(session_id, None).await.unwrap()),
+            ids(chain.clone())
+        );
+        assert_eq!(
+            ids(service
+                .list_products(session_id, Some("overlay"))
+                .await
+                .unwrap()),
+            vec![chain[1].id]
+        );
+        assert_eq!(
+            ids(service
+                .product_inputs(session_id, &chain[2].id)
+                .await
+                .unwrap()),
+            vec![chain[1].id, chain[0].id]
+        );
+        assert_eq!(
+            ids(service
+                .product_outputs(session_id, &chain[0].id)
+                .await
+                .unwrap()),
+            vec![chain[1].id, chain[2].id]
+        );
+        let foreign_parent = service
+            .register_product(
+                session_id,
+                NewDerivedProduct::new(ProductProducer::Report, "report", "x.pdf")
+                    .with_parents(vec![chains[1].1[0].id]),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            foreign_parent.downcast_ref::<ProductRegistryError>(),
+            Some(ProductRegistryError::UnknownParent { .. })
+        ));
+
+        let cascade = service
+            .delete_session(session_id, ProductDisposition::Cascade)
+            .await
+            .unwrap();
+        assert_eq!(cascade.removed_products, 3);
+        assert!(chain.iter().all(|product| !product.file_path.exists()));
+        assert!(service.get_session(session_id).await.unwrap().is_none());
+        assert!(service.list_products(session_id, None).await.is_err());
+
+        let (orphan_session_id, orphan_chain) = &chains[1];
+        let orphan = service
+            .delete_session(orphan_session_id, ProductDisposition::Orphan)
+            .await
+            .unwrap();
+        assert_eq!(orphan.orphaned_products, 3);
+        assert_eq!(orphan.removed_products, 0);
+        assert!(orphan_chain
+            .iter()
+            .all(|product| product.file_path.exists()));
+        let orphaned: Vec<DerivedProduct> = serde_json::from_slice(
+            &std::fs::read(
+                temp_dir
+                    .path()
+                    .join("orphaned_products")
+                    .join(format!("{orphan_session_id}.json")),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(orphaned, *orphan_chain);
     }
 
     #[tokio::test]
@@ -3488,6 +5302,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn payload_accessors_return_only_their_own_variant() {
+        for payload in all_payloads() {
+            let accessors = [
+                payload.as_telemetry().is_some(),
+                payload.as_sensor_data().is_some(),
+                payload.as_media_file().is_some(),
+                payload.as_point_cloud().is_some(),
+                payload.as_track_log().is_some(),
+                payload.as_raw().is_some(),
+            ];
+            assert_eq!(
+                accessors.iter().filter(|matched| **matched).count(),
+                1,
+                "{}",
+                payload.variant_name()
+            );
+        }
+
+        let payloads = all_payloads();
+        assert_eq!(payloads[0].as_telemetry().unwrap().battery_level, 0.9);
+        assert!(payloads[0].as_point_cloud().is_none());
+        assert_eq!(payloads[3].as_point_cloud().unwrap().point_count, 42);
+        assert!(payloads[3].as_telemetry().is_none());
+        assert_eq!(
+            payloads
+                .iter()
+                .map(DataPayload::data_type)
+                .collect::<Vec<_>>(),
+            vec![
+                DataType::Telemetry,
+                DataType::SensorReading,
+                DataType::Image,
+                DataType::LidarScan,
+                DataType::GPSTrack,
+                DataType::SystemLog,
+            ]
+        );
+    }
+
+    #[test]
+    fn payload_converts_into_its_struct_and_keeps_its_json_layout() {
+        let sensor_data = SensorDataPayload::try_from(all_payloads().remove(1)).unwrap();
+        assert_eq!(sensor_data.values["nir"], 0.72);
+
+        let err = TelemetryPayload::try_from(all_payloads().remove(1)).unwrap_err();
+        assert_eq!((err.expected, err.found), ("Telemetry", "SensorData"));
+        assert!(err.payload.as_sensor_data().is_some());
+
+        // Records written while the variants held their fields inline still load.
+        let stored = serde_json::json!({
+            "SensorData": {
+                "sensor_type": "multispectral",
+                "values": { "nir": 0.72 },
+                "calibration_info": null
+            }
+        });
+        let payload: DataPayload = serde_json::from_value(stored.clone()).unwrap();
+        assert_eq!(
+            payload.as_sensor_data().unwrap().sensor_type,
+            "multispectral"
+        );
+        assert_eq!(serde_json::to_value(&payload).unwrap(), stored);
+    }
+
     #[tokio::test]
     async fn test_missing_gps_or_timestamp_is_rejected_as_provenance_error() {
         let temp_dir = tempdir().unwrap();
@@ -3501,11 +5380,11 @@ mod tests {
             session.flight_id,
             session.drone_id,
             DataType::Telemetry,
-            DataPayload::Raw {
+            DataPayload::Raw(RawPayload {
                 format: "json".to_string(),
                 schema: None,
                 compression: None,
-            },
+            }),
             missing_gps,
             128,
         )
@@ -3518,11 +5397,11 @@ mod tests {
             session.flight_id,
             session.drone_id,
             DataType::Telemetry,
-            DataPayload::Raw {
+            DataPayload::Raw(RawPayload {
                 format: "json".to_string(),
                 schema: None,
                 compression: None,
-            },
+            }),
             missing_timestamp,
             128,
         )
@@ -0,0 +1,401 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use data_collector::api::{router, IngestApiState};
+use data_collector::upload::{UploadConfig, UploadManager};
+use data_collector::{
+    file_sha256, DataCollectorService, GapPolicy, ReplayOptions, ReplayPlan, UploadClient,
+    UploadInitRequest,
+};
+use futures_util::SinkExt;
+use shared::config::CorsConfig;
+use shared::http_client::HttpClientConfig;
+use shared::schemas::{GpsCoords, WebSocketMessage};
+use shared::webhooks::{WebhookConfig, WebhookDispatcher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch, Mutex};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{client::IntoClientRequest, protocol::Message},
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Messages buffered between the pacing loop and a slow sink.
+const REPLAY_SINK_CAPACITY: usize = 64;
+/// How long `serve` waits at shutdown for queued webhook deliveries.
+const WEBHOOK_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Parser, Debug)]
+#[command(name = "data_collector")]
+#[command(about = "Flight data collection tools for agrodrone")]
+struct Args {
+    #[arg(
+        long,
+        default_value = "/tmp/agrodrone/data",
+        help = "Data root directory"
+    )]
+    data_root: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Re-emit a recorded session's telemetry and sensor records as live messages
+    Replay {
+        #[arg(long)]
+        session: Uuid,
+        #[arg(long, default_value_t = 1.0, help = "Playback speed multiplier")]
+        speed: f64,
+        #[arg(
+            long,
+            default_value = "stdout",
+            help = "`stdout` or a mission control ingest URL such as ws://localhost:8080/ws/ingest"
+        )]
+        target: String,
+        #[arg(
+            long,
+            help = "Control token for the ingest URL; defaults to the CONTROL_TOKEN environment variable"
+        )]
+        token: Option<String>,
+        #[arg(long, default_value_t = 0.0, help = "Seconds of flight to skip")]
+        start_offset_secs: f64,
+        #[arg(long, value_enum, default_value_t = Gaps::Dwell)]
+        gaps: Gaps,
+        #[arg(
+            long,
+            default_value_t = 5.0,
+            help = "Gap between records, in seconds, that --gaps applies to"
+        )]
+        max_gap_secs: f64,
+    },
+    /// Upload a capture file to a running collector (see `serve`) in chunks; pass
+    /// --upload-id to resume an interrupted upload
+    Upload {
+        #[arg(
+            long,
+            help = "Prefix the ingest API is mounted under, e.g. http://collector:8080/api"
+        )]
+        collector_url: String,
+        #[arg(long)]
+        session: Uuid,
+        #[arg(long)]
+        file: PathBuf,
+        #[arg(long)]
+        sensor_id: String,
+        #[arg(long)]
+        calibration_ref: String,
+        #[arg(long, allow_negative_numbers = true)]
+        latitude: f64,
+        #[arg(long, allow_negative_numbers = true)]
+        longitude: f64,
+        #[arg(long, allow_negative_numbers = true)]
+        altitude: f64,
+        #[arg(
+            long,
+            help = "RFC 3339 capture time; defaults to the file's modification time"
+        )]
+        captured_at: Option<DateTime<Utc>>,
+        #[arg(long)]
+        mime_type: Option<String>,
+        #[arg(long, help = "Resume this upload instead of starting a new one")]
+        upload_id: Option<Uuid>,
+        #[arg(long, default_value_t = 120, help = "Timeout for each chunk request")]
+        timeout_secs: u64,
+    },
+    /// Serve the ingest API under /api until interrupted
+    Serve {
+        #[arg(long, default_value = "0.0.0.0:8082")]
+        bind: String,
+        #[arg(
+            long,
+            help = "Webhook configuration (JSON); subscribed endpoints receive record.stored and battery.alert events"
+        )]
+        webhooks: Option<PathBuf>,
+        #[arg(
+            long,
+            default_value_t = 60,
+            help = "How often idle capture sessions are swept"
+        )]
+        session_sweep_secs: u64,
+        #[arg(
+            long,
+            default_value_t = 3600,
+            help = "How often expired partial uploads are removed"
+        )]
+        upload_gc_secs: u64,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Gaps {
+    Dwell,
+    Skip,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    match args.command {
+        Command::Replay {
+            session,
+            speed,
+            target,
+            token,
+            start_offset_secs,
+            gaps,
+            max_gap_secs,
+        } => {
+            // Keep stdout for the replayed messages themselves.
+            if target != "stdout" {
+                shared::init_logging()?;
+            }
+            let options = ReplayOptions {
+                speed,
+                start_offset: Duration::try_from_secs_f64(start_offset_secs)
+                    .context("--start-offset-secs must be a non-negative number")?,
+                gap_policy: match gaps {
+                    Gaps::Dwell => GapPolicy::Dwell,
+                    Gaps::Skip => GapPolicy::Skip,
+                },
+                max_gap: Duration::try_from_secs_f64(max_gap_secs)
+                    .context("--max-gap-secs must be a non-negative number")?,
+            };
+            let token = token.or_else(|| std::env::var("CONTROL_TOKEN").ok());
+            replay(args.data_root, session, options, &target, token).await
+        }
+        Command::Upload {
+            collector_url,
+            session,
+            file,
+            sensor_id,
+            calibration_ref,
+            latitude,
+            longitude,
+            altitude,
+            captured_at,
+            mime_type,
+            upload_id,
+            timeout_secs,
+        } => {
+            shared::init_logging()?;
+            let client = UploadClient::new(
+                collector_url,
+                HttpClientConfig {
+                    timeout_ms: timeout_secs.saturating_mul(1000),
+                    ..HttpClientConfig::default()
+                },
+            )?;
+            let upload_id = match upload_id {
+                Some(upload_id) => upload_id,
+                None => {
+                    let metadata = tokio::fs::metadata(&file)
+                        .await
+                        .with_context(|| format!("failed to read {}", file.display()))?;
+                    let captured_at = match captured_at {
+                        Some(captured_at) => captured_at,
+                        None => DateTime::from(metadata.modified()?),
+                    };
+                    let request = UploadInitRequest {
+                        file_name: file
+                            .file_name()
+                            .and_then(|name| name.to_str())
+                            .with_context(|| format!("{} has no file name", file.display()))?
+                            .to_string(),
+                        mime_type,
+                        size_bytes: metadata.len(),
+                        sha256: file_sha256(&file).await?,
+                        sensor_id,
+                        gps_coords: GpsCoords {
+                            latitude,
+                            longitude,
+                            altitude,
+                        },
+                        captured_at,
+                        calibration_ref,
+                    };
+                    let ticket = client.initiate(session, &request).await?;
+                    info!(
+                        "Started upload {} of {} in {} chunks",
+                        ticket.upload_id,
+                        file.display(),
+                        ticket.chunk_count
+                    );
+                    ticket.upload_id
+                }
+            };
+            let ack = client
+                .upload(upload_id, &file)
+                .await
+                .with_context(|| format!("upload {upload_id} did not finish; rerun with --upload-id {upload_id} to resume"))?;
+            println!("{}", serde_json::to_string(&ack)?);
+            Ok(())
+        }
+        Command::Serve {
+            bind,
+            webhooks,
+            session_sweep_secs,
+            upload_gc_secs,
+        } => {
+            shared::init_logging()?;
+            serve(
+                args.data_root,
+                &bind,
+                webhooks.as_deref(),
+                Duration::from_secs(session_sweep_secs.max(1)),
+                Duration::from_secs(upload_gc_secs.max(1)),
+            )
+            .await
+        }
+    }
+}
+
+async fn serve(
+    data_root: PathBuf,
+    bind: &str,
+    webhooks: Option<&Path>,
+    session_sweep: Duration,
+    upload_gc: Duration,
+) -> Result<()> {
+    let cors = CorsConfig::from_env()?;
+    cors.validate()?;
+    let uploads = Arc::new(UploadManager::open(
+        data_root.join("uploads"),
+        UploadConfig::default(),
+    )?);
+    let service = Arc::new(Mutex::new(DataCollectorService::open(data_root).await?));
+    let state = IngestApiState::new(Arc::clone(&service), Arc::clone(&uploads));
+
+    let mut tasks = vec![
+        state.spawn_batch_flusher(),
+        state.spawn_idle_session_sweeper(session_sweep),
+        uploads.spawn_garbage_collector(upload_gc),
+    ];
+    let webhooks = match webhooks {
+        Some(path) => {
+            let (webhooks, _worker) = WebhookDispatcher::spawn(WebhookConfig::load(path)?)?;
+            tasks.push(state.spawn_record_webhook(webhooks.clone()));
+            tasks.push(state.spawn_battery_alert_webhook(webhooks.clone()));
+            Some(webhooks)
+        }
+        None => None,
+    };
+
+    let app = axum::Router::new()
+        .nest("/api", router(state))
+        .layer(cors.layer());
+    let listener = tokio::net::TcpListener::bind(bind)
+        .await
+        .with_context(|| format!("failed to bind {bind}"))?;
+    info!("Ingest API listening on {}/api", bind);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            if let Err(error) = tokio::signal::ctrl_c().await {
+                warn!(%error, "failed to listen for Ctrl+C");
+            }
+        })
+        .await?;
+
+    for task in tasks {
+        task.abort();
+    }
+    let shutdown = service.lock().await.shutdown().await;
+    if let Some(webhooks) = webhooks {
+        if tokio::time::timeout(WEBHOOK_DRAIN_TIMEOUT, webhooks.flush())
+            .await
+            .is_err()
+        {
+            warn!("webhook deliveries still pending at shutdown were abandoned");
+        }
+    }
+    shutdown
+}
+
+async fn replay(
+    data_root: PathBuf,
+    session_id: Uuid,
+    options: ReplayOptions,
+    target: &str,
+    token: Option<String>,
+) -> Result<()> {
+    let service = DataCollectorService::new(data_root)?;
+    let records = service.session_records(&session_id).await?;
+    let plan = ReplayPlan::build(&records, &options)?;
+    info!(
+        "Replaying {} messages from session {} over {:?} ({} records have no live message)",
+        plan.frames().len(),
+        session_id,
+        plan.duration(),
+        plan.unreplayable_records()
+    );
+
+    let (shutdown_tx, shutdown) = watch::channel(false);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let _ = shutdown_tx.send(true);
+        }
+    });
+
+    let (tx, rx) = mpsc::channel(REPLAY_SINK_CAPACITY);
+    let sink = if target == "stdout" {
+        tokio::spawn(write_stdout(rx))
+    } else {
+        let mut request = target
+            .into_client_request()
+            .with_context(|| format!("invalid replay target {target}"))?;
+        if let Some(token) = token {
+            request.headers_mut().insert(
+                "authorization",
+                format!("Bearer {token}")
+                    .parse()
+                    .context("control token is not a valid header value")?,
+            );
+        }
+        let (socket, _) = connect_async(request)
+            .await
+            .with_context(|| format!("failed to connect to {target}"))?;
+        tokio::spawn(write_websocket(socket, rx))
+    };
+
+    let outcome = plan.play(tx, shutdown).await;
+    sink.await??;
+    if outcome.interrupted {
+        warn!(
+            "Replay stopped after {} of {} messages",
+            outcome.sent,
+            plan.frames().len()
+        );
+    } else {
+        info!("Replay finished: {} messages sent", outcome.sent);
+    }
+    Ok(())
+}
+
+async fn write_stdout(mut rx: mpsc::Receiver<WebSocketMessage>) -> Result<()> {
+    while let Some(message) = rx.recv().await {
+        let mut stdout = std::io::stdout().lock();
+        serde_json::to_writer(&mut stdout, &message)?;
+        writeln!(stdout)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
+async fn write_websocket<S>(mut socket: S, mut rx: mpsc::Receiver<WebSocketMessage>) -> Result<()>
+where
+    S: SinkExt<Message> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    while let Some(message) = rx.recv().await {
+        socket
+            .send(Message::Text(serde_json::to_string(&message)?))
+            .await?;
+    }
+    socket.close().await?;
+    Ok(())
+}
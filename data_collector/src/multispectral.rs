@@ -1,6 +1,6 @@
 use crate::{
     CollectionFailureKind, CollectionFailureRequest, DataPayload, DataType, FlightDataProvenance,
-    FlightDataProvenanceError, FlightDataRecord,
+    FlightDataProvenanceError, FlightDataRecord, MediaFilePayload,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -170,12 +170,12 @@ pub fn multispectral_capture_to_record(
         flight_id,
         drone_id,
         DataType::MultispectralImage,
-        DataPayload::MediaFile {
+        DataPayload::MediaFile(MediaFilePayload {
             file_type: "multispectral/tiff-stack".to_string(),
             dimensions,
             duration_seconds: None,
             compression: Some("tiff".to_string()),
-        },
+        }),
         provenance,
         size_bytes,
     )?;
@@ -285,11 +285,11 @@ mod tests {
             Some(&"panel-cal-2026-05-10".to_string())
         );
         match record.payload {
-            DataPayload::MediaFile {
+            DataPayload::MediaFile(MediaFilePayload {
                 file_type,
                 dimensions,
                 ..
-            } => {
+            }) => {
                 assert_eq!(file_type, "multispectral/tiff-stack");
                 assert_eq!(dimensions, Some((1024, 768)));
             }
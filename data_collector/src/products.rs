@@ -0,0 +1,265 @@
+use crate::upload::sha256_hex;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Pipeline stage that wrote a derived product. Session and compliance
+/// exports register themselves; the analysis stages register through
+/// `POST /sessions/{id}/products`, which post_processor does for its NDVI,
+/// thermal and LiDAR jobs. The other variants are for stages that register
+/// by hand, as imagery_processor, lidar_mapper and sensor_overlay_engine
+/// runs do not know their capture session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductProducer {
+    SessionExport,
+    ComplianceExport,
+    Orthomosaic,
+    NdviAnalysis,
+    ThermalAnalysis,
+    LidarMapping,
+    SensorOverlay,
+    Report,
+}
+
+/// An artifact produced from a capture session's records or from other
+/// products of the same session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DerivedProduct {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub producer: ProductProducer,
+    pub product_type: String,
+    pub file_path: PathBuf,
+    /// sha256 of the stage parameters, so reruns with other settings can be
+    /// told apart; see [`parameters_hash`].
+    pub parameters_hash: String,
+    pub created_at: DateTime<Utc>,
+    /// Products of the same session this one was computed from; empty when it
+    /// was derived straight from the raw records.
+    #[serde(default)]
+    pub parent_product_ids: Vec<Uuid>,
+}
+
+/// Registration request; the registry assigns the id and timestamp.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewDerivedProduct {
+    pub producer: ProductProducer,
+    pub product_type: String,
+    pub file_path: PathBuf,
+    #[serde(default)]
+    pub parameters_hash: String,
+    #[serde(default)]
+    pub parent_product_ids: Vec<Uuid>,
+}
+
+impl NewDerivedProduct {
+    pub fn new(
+        producer: ProductProducer,
+        product_type: impl Into<String>,
+        file_path: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            producer,
+            product_type: product_type.into(),
+            file_path: file_path.into(),
+            parameters_hash: parameters_hash(&serde_json::Value::Null),
+            parent_product_ids: Vec::new(),
+        }
+    }
+
+    pub fn with_parameters(mut self, parameters: &serde_json::Value) -> Self {
+        self.parameters_hash = parameters_hash(parameters);
+        self
+    }
+
+    pub fn with_parents(mut self, parent_product_ids: Vec<Uuid>) -> Self {
+        self.parent_product_ids = parent_product_ids;
+        self
+    }
+}
+
+/// What happens to a session's products when the session is deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProductDisposition {
+    /// Delete the product files and their registry entries with the session.
+    Cascade,
+    /// Keep the files and move the registry entries to the orphan registry.
+    Orphan,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionDeletion {
+    pub session_id: Uuid,
+    pub removed_records: u32,
+    pub removed_products: usize,
+    pub orphaned_products: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProductRegistryError {
+    #[error("product_type must not be empty")]
+    EmptyProductType,
+    #[error("product {product_id} is not registered for session {session_id}")]
+    UnknownProduct { session_id: Uuid, product_id: Uuid },
+    #[error("parent product {parent_id} is not registered for session {session_id}")]
+    UnknownParent { session_id: Uuid, parent_id: Uuid },
+    #[error("capture session {session_id} is still active and cannot be deleted")]
+    SessionActive { session_id: Uuid },
+}
+
+/// sha256 of the JSON encoding of a stage's parameters.
+pub fn parameters_hash(parameters: &serde_json::Value) -> String {
+    sha256_hex(parameters.to_string().as_bytes())
+}
+
+/// Builds the product for `session_id`, checking that every parent is one of
+/// `existing`.
+pub(crate) fn new_product(
+    session_id: Uuid,
+    request: NewDerivedProduct,
+    existing: &[DerivedProduct],
+    created_at: DateTime<Utc>,
+) -> Result<DerivedProduct, ProductRegistryError> {
+    if request.product_type.trim().is_empty() {
+        return Err(ProductRegistryError::EmptyProductType);
+    }
+    if let Some(parent_id) = request
+        .parent_product_ids
+        .iter()
+        .find(|parent_id| !existing.iter().any(|product| product.id == **parent_id))
+    {
+        return Err(ProductRegistryError::UnknownParent {
+            session_id,
+            parent_id: *parent_id,
+        });
+    }
+
+    Ok(DerivedProduct {
+        id: Uuid::new_v4(),
+        session_id,
+        producer: request.producer,
+        product_type: request.product_type,
+        file_path: request.file_path,
+        parameters_hash: request.parameters_hash,
+        created_at,
+        parent_product_ids: request.parent_product_ids,
+    })
+}
+
+/// Every product `product_id` was computed from, nearest inputs first.
+pub(crate) fn product_inputs(
+    session_id: Uuid,
+    products: &[DerivedProduct],
+    product_id: Uuid,
+) -> Result<Vec<DerivedProduct>, ProductRegistryError> {
+    walk_lineage(session_id, products, product_id, |product| {
+        product.parent_product_ids.clone()
+    })
+}
+
+/// Every product computed from `product_id`, directly or indirectly, nearest
+/// outputs first.
+pub(crate) fn product_outputs(
+    session_id: Uuid,
+    products: &[DerivedProduct],
+    product_id: Uuid,
+) -> Result<Vec<DerivedProduct>, ProductRegistryError> {
+    walk_lineage(session_id, products, product_id, |product| {
+        products
+            .iter()
+            .filter(|candidate| candidate.parent_product_ids.contains(&product.id))
+            .map(|candidate| candidate.id)
+            .collect()
+    })
+}
+
+fn walk_lineage(
+    session_id: Uuid,
+    products: &[DerivedProduct],
+    product_id: Uuid,
+    next: impl Fn(&DerivedProduct) -> Vec<Uuid>,
+) -> Result<Vec<DerivedProduct>, ProductRegistryError> {
+    let find = |id: Uuid| products.iter().find(|product| product.id == id);
+    let start = find(product_id).ok_or(ProductRegistryError::UnknownProduct {
+        session_id,
+        product_id,
+    })?;
+
+    let mut visited = HashSet::from([product_id]);
+    let mut queue = VecDeque::from(next(start));
+    let mut lineage = Vec::new();
+    while let Some(id) = queue.pop_front() {
+        if !visited.insert(id) {
+            continue;
+        }
+        if let Some(product) = find(id) {
+            queue.extend(next(product));
+            lineage.push(product.clone());
+        }
+    }
+    Ok(lineage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lineage_walks_diamond_without_repeating_products() {
+        let session_id = Uuid::new_v4();
+        let now = Utc::now();
+        let mut products = Vec::new();
+        let mut register = |request: NewDerivedProduct| {
+            let product = new_product(session_id, request, &products, now).unwrap();
+            products.push(product.clone());
+            product.id
+        };
+        let ortho = register(NewDerivedProduct::new(
+            ProductProducer::Orthomosaic,
+            "orthomosaic",
+            "ortho.tif",
+        ));
+        let ndvi = register(
+            NewDerivedProduct::new(ProductProducer::NdviAnalysis, "ndvi", "ndvi.tif")
+                .with_parents(vec![ortho]),
+        );
+        let thermal = register(
+            NewDerivedProduct::new(ProductProducer::ThermalAnalysis, "thermal", "thermal.tif")
+                .with_parents(vec![ortho]),
+        );
+        let report = register(
+            NewDerivedProduct::new(ProductProducer::Report, "report", "report.pdf")
+                .with_parents(vec![ndvi, thermal]),
+        );
+
+        let inputs = product_inputs(session_id, &products, report).unwrap();
+        let input_ids = inputs.iter().map(|product| product.id).collect::<Vec<_>>();
+        assert_eq!(input_ids, vec![ndvi, thermal, ortho]);
+
+        let outputs = product_outputs(session_id, &products, ortho).unwrap();
+        assert_eq!(outputs.len(), 3);
+        assert_eq!(outputs.last().unwrap().id, report);
+
+        assert_eq!(
+            new_product(
+                session_id,
+                NewDerivedProduct::new(ProductProducer::Report, "report", "r.pdf")
+                    .with_parents(vec![Uuid::nil()]),
+                &products,
+                now,
+            ),
+            Err(ProductRegistryError::UnknownParent {
+                session_id,
+                parent_id: Uuid::nil(),
+            })
+        );
+        assert_eq!(
+            parameters_hash(&serde_json::json!({"band": "nir"})),
+            parameters_hash(&serde_json::json!({"band": "nir"}))
+        );
+    }
+}
@@ -0,0 +1,910 @@
+//! Data-quality pass over a capture session.
+//!
+//! Each kind of captured data gets a coverage metric and a 0.0-1.0 component
+//! score: telemetry by the share of the track lost to gaps, imagery by the
+//! captures taken against the expected interval, multispectral captures by
+//! the share carrying every required band, and LiDAR by how steady the scan
+//! rate stays. The components roll up into a 0-100 session score, and every
+//! problem found becomes a [`QualityFlag`] naming the affected time range and
+//! strip of the field so the grower knows what to re-fly.
+
+use crate::{CollectionFailureKind, DataPayload, DataType, FlightDataRecord, FlightSession};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::data_quality::{
+    BandCompletenessMetrics, FieldStrip, GapHistogramBucket, ImageSpacingMetrics,
+    LidarStabilityMetrics, QualityFlag, QualityFlagType, QualitySeverity, SessionQualityAssessment,
+    TelemetryQualityMetrics,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Upper bounds of the telemetry gap histogram buckets; a final open-ended
+/// bucket holds everything longer.
+pub const TELEMETRY_GAP_BUCKETS_SECONDS: [f64; 5] = [1.0, 2.0, 5.0, 10.0, 30.0];
+/// A gap this many times longer than its flag threshold is critical.
+const CRITICAL_GAP_MULTIPLIER: f64 = 5.0;
+/// LiDAR dropouts longer than this are critical whatever the scan rate.
+const CRITICAL_LIDAR_DROPOUT_SECONDS: f64 = 60.0;
+/// Share of the track's radius around its centre that counts as the centre
+/// strip rather than one of the edges.
+const CENTER_STRIP_FRACTION: f64 = 0.25;
+const METERS_PER_DEGREE_LAT: f64 = 111_320.0;
+
+/// Relative weight of each component in the session score. Components the
+/// session has no data for are left out and the rest are renormalised.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QualityWeights {
+    pub telemetry: f64,
+    pub image_spacing: f64,
+    pub band_completeness: f64,
+    pub lidar_stability: f64,
+}
+
+impl Default for QualityWeights {
+    fn default() -> Self {
+        Self {
+            telemetry: 0.35,
+            image_spacing: 0.2,
+            band_completeness: 0.3,
+            lidar_stability: 0.15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QualityAssessmentConfig {
+    pub weights: QualityWeights,
+    /// Telemetry gaps longer than this are flagged; the time past it counts
+    /// as lost track.
+    pub telemetry_gap_threshold_seconds: f64,
+    pub expected_image_interval_seconds: f64,
+    /// Image intervals longer than this multiple of the expected interval
+    /// are flagged.
+    pub image_gap_factor: f64,
+    /// Bands every multispectral capture must carry, matched ignoring case.
+    pub required_bands: Vec<String>,
+    /// LiDAR intervals longer than this multiple of the median interval are
+    /// dropouts.
+    pub lidar_dropout_factor: f64,
+}
+
+impl Default for QualityAssessmentConfig {
+    fn default() -> Self {
+        Self {
+            weights: QualityWeights::default(),
+            telemetry_gap_threshold_seconds: 5.0,
+            expected_image_interval_seconds: 2.0,
+            image_gap_factor: 2.0,
+            required_bands: vec!["Red".to_string(), "NIR".to_string()],
+            lidar_dropout_factor: 3.0,
+        }
+    }
+}
+
+/// Assesses `records`, the records of `session`. Multispectral captures
+/// rejected for missing bands are taken from the session's collection
+/// failures and count as incomplete captures.
+pub fn score_session_quality(
+    session: &FlightSession,
+    records: &[FlightDataRecord],
+    config: &QualityAssessmentConfig,
+) -> SessionQualityAssessment {
+    let locator = TrackLocator::new(records);
+    let mut flags = Vec::new();
+
+    let telemetry = telemetry_metrics(session, records, config, &locator, &mut flags);
+    let image_spacing = image_spacing_metrics(records, config, &locator, &mut flags);
+    let band_completeness =
+        band_completeness_metrics(session, records, config, &locator, &mut flags);
+    let lidar_stability = lidar_stability_metrics(records, config, &locator, &mut flags);
+
+    let weights = &config.weights;
+    let score = weighted_score(&[
+        (weights.telemetry, Some(telemetry.score)),
+        (
+            weights.image_spacing,
+            image_spacing.as_ref().map(|metrics| metrics.score),
+        ),
+        (
+            weights.band_completeness,
+            band_completeness.as_ref().map(|metrics| metrics.score),
+        ),
+        (
+            weights.lidar_stability,
+            lidar_stability.as_ref().map(|metrics| metrics.score),
+        ),
+    ]);
+    flags.sort_by_key(|flag| (flag.start, flag.end));
+
+    SessionQualityAssessment {
+        session_id: session.id,
+        assessed_at: Utc::now(),
+        score,
+        telemetry,
+        image_spacing,
+        band_completeness,
+        lidar_stability,
+        flags,
+    }
+}
+
+fn weighted_score(components: &[(f64, Option<f64>)]) -> u8 {
+    let applicable = components
+        .iter()
+        .filter_map(|(weight, score)| {
+            let weight = if weight.is_finite() {
+                weight.max(0.0)
+            } else {
+                0.0
+            };
+            score.map(|score| (weight, score.clamp(0.0, 1.0)))
+        })
+        .collect::<Vec<_>>();
+    if applicable.is_empty() {
+        return 0;
+    }
+    let total_weight = applicable.iter().map(|(weight, _)| weight).sum::<f64>();
+    let value = if total_weight > 0.0 {
+        applicable
+            .iter()
+            .map(|(weight, score)| weight * score)
+            .sum::<f64>()
+            / total_weight
+    } else {
+        applicable.iter().map(|(_, score)| score).sum::<f64>() / applicable.len() as f64
+    };
+    (value * 100.0).round().clamp(0.0, 100.0) as u8
+}
+
+fn telemetry_metrics(
+    session: &FlightSession,
+    records: &[FlightDataRecord],
+    config: &QualityAssessmentConfig,
+    locator: &TrackLocator,
+    flags: &mut Vec<QualityFlag>,
+) -> TelemetryQualityMetrics {
+    let samples = sorted_records(records, |record| {
+        record.data_type == DataType::Telemetry
+            && matches!(record.payload, DataPayload::Telemetry(_))
+    });
+    let mut gap_histogram = TELEMETRY_GAP_BUCKETS_SECONDS
+        .iter()
+        .map(|max_seconds| GapHistogramBucket {
+            max_seconds: Some(*max_seconds),
+            count: 0,
+        })
+        .chain(std::iter::once(GapHistogramBucket {
+            max_seconds: None,
+            count: 0,
+        }))
+        .collect::<Vec<_>>();
+
+    if samples.is_empty() {
+        let (start, end) = session_window(session, records);
+        flags.push(QualityFlag {
+            flag_type: QualityFlagType::NoTelemetry,
+            severity: QualitySeverity::Critical,
+            start,
+            end,
+            area: None,
+            message: "session has no telemetry track".to_string(),
+            suggestion: "check the telemetry link and re-fly the mission".to_string(),
+        });
+        return TelemetryQualityMetrics {
+            sample_count: 0,
+            max_gap_seconds: 0.0,
+            gap_histogram,
+            score: 0.0,
+        };
+    }
+
+    let threshold = config.telemetry_gap_threshold_seconds;
+    let mut max_gap_seconds = 0.0f64;
+    let mut lost_seconds = 0.0;
+    for pair in samples.windows(2) {
+        let gap = seconds_between(pair[0].timestamp, pair[1].timestamp);
+        let bucket = TELEMETRY_GAP_BUCKETS_SECONDS
+            .iter()
+            .position(|max_seconds| gap <= *max_seconds)
+            .unwrap_or(TELEMETRY_GAP_BUCKETS_SECONDS.len());
+        gap_histogram[bucket].count += 1;
+        max_gap_seconds = max_gap_seconds.max(gap);
+
+        if gap > threshold {
+            lost_seconds += gap - threshold;
+            let area = locator.strip(pair.iter().copied().filter_map(record_position));
+            flags.push(QualityFlag {
+                flag_type: QualityFlagType::TelemetryGap,
+                severity: gap_severity(gap, threshold),
+                start: pair[0].timestamp,
+                end: pair[1].timestamp,
+                area,
+                message: format!("no telemetry for {gap:.0} s"),
+                suggestion: refly(area),
+            });
+        }
+    }
+
+    TelemetryQualityMetrics {
+        sample_count: samples.len(),
+        max_gap_seconds,
+        gap_histogram,
+        score: covered_fraction(&samples, lost_seconds),
+    }
+}
+
+fn image_spacing_metrics(
+    records: &[FlightDataRecord],
+    config: &QualityAssessmentConfig,
+    locator: &TrackLocator,
+    flags: &mut Vec<QualityFlag>,
+) -> Option<ImageSpacingMetrics> {
+    let mut by_sensor = BTreeMap::<&str, Vec<&FlightDataRecord>>::new();
+    for record in sorted_records(records, |record| {
+        matches!(
+            record.data_type,
+            DataType::Image | DataType::MultispectralImage | DataType::ThermalImage
+        )
+    }) {
+        by_sensor
+            .entry(record.sensor_id.as_str())
+            .or_default()
+            .push(record);
+    }
+    if by_sensor.is_empty() {
+        return None;
+    }
+
+    let expected = config.expected_image_interval_seconds;
+    let late_after = expected * config.image_gap_factor;
+    let mut image_count = 0;
+    let mut expected_count = 0.0;
+    let mut intervals = Vec::new();
+    let mut late_interval_count = 0;
+    for (sensor_id, images) in &by_sensor {
+        image_count += images.len();
+        let span = seconds_between(images[0].timestamp, images[images.len() - 1].timestamp);
+        expected_count += if expected > 0.0 {
+            (span / expected).round() + 1.0
+        } else {
+            images.len() as f64
+        };
+
+        for pair in images.windows(2) {
+            let interval = seconds_between(pair[0].timestamp, pair[1].timestamp);
+            intervals.push(interval);
+            if interval > late_after {
+                late_interval_count += 1;
+                let area = locator.strip(pair.iter().copied().filter_map(record_position));
+                flags.push(QualityFlag {
+                    flag_type: QualityFlagType::ImageSpacingGap,
+                    severity: gap_severity(interval, late_after),
+                    start: pair[0].timestamp,
+                    end: pair[1].timestamp,
+                    area,
+                    message: format!(
+                        "{sensor_id}: {interval:.0} s between images, expected {expected:.0} s"
+                    ),
+                    suggestion: refly(area),
+                });
+            }
+        }
+    }
+
+    let mean_interval_seconds = if intervals.is_empty() {
+        0.0
+    } else {
+        intervals.iter().sum::<f64>() / intervals.len() as f64
+    };
+    Some(ImageSpacingMetrics {
+        image_count,
+        expected_interval_seconds: expected,
+        mean_interval_seconds,
+        max_interval_seconds: intervals.iter().copied().fold(0.0, f64::max),
+        late_interval_count,
+        score: (image_count as f64 / expected_count).min(1.0),
+    })
+}
+
+struct BandCapture<'a> {
+    timestamp: DateTime<Utc>,
+    record: Option<&'a FlightDataRecord>,
+    /// Required bands the capture lacks; empty for a rejected capture, whose
+    /// bands are not known.
+    missing: Vec<&'a str>,
+    complete: bool,
+}
+
+fn band_completeness_metrics(
+    session: &FlightSession,
+    records: &[FlightDataRecord],
+    config: &QualityAssessmentConfig,
+    locator: &TrackLocator,
+    flags: &mut Vec<QualityFlag>,
+) -> Option<BandCompletenessMetrics> {
+    let required = config
+        .required_bands
+        .iter()
+        .map(|band| band.trim())
+        .filter(|band| !band.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut captures = records
+        .iter()
+        .filter(|record| record.data_type == DataType::MultispectralImage)
+        .map(|record| {
+            let bands = record
+                .metadata
+                .get("bands")
+                .map(|bands| bands.split(',').map(str::trim).collect::<Vec<_>>())
+                .unwrap_or_default();
+            let missing = required
+                .iter()
+                .copied()
+                .filter(|band| !bands.iter().any(|have| have.eq_ignore_ascii_case(band)))
+                .collect::<Vec<_>>();
+            BandCapture {
+                timestamp: record.timestamp,
+                record: Some(record),
+                complete: missing.is_empty(),
+                missing,
+            }
+        })
+        .chain(
+            session
+                .summary
+                .collection_failures
+                .iter()
+                .filter(|failure| failure.kind == CollectionFailureKind::MissingBand)
+                .map(|failure| BandCapture {
+                    timestamp: failure.occurred_at,
+                    record: None,
+                    missing: Vec::new(),
+                    complete: false,
+                }),
+        )
+        .collect::<Vec<_>>();
+    if captures.is_empty() {
+        return None;
+    }
+    captures.sort_by_key(|capture| capture.timestamp);
+
+    let complete_count = captures.iter().filter(|capture| capture.complete).count();
+    let score = complete_count as f64 / captures.len() as f64;
+    let severity = if score < 0.5 {
+        QualitySeverity::Critical
+    } else {
+        QualitySeverity::Warning
+    };
+    let missing_bands = captures
+        .iter()
+        .flat_map(|capture| capture.missing.iter().copied())
+        .collect::<BTreeSet<_>>();
+
+    for run in captures
+        .split(|capture| capture.complete)
+        .filter(|run| !run.is_empty())
+    {
+        let bands = run
+            .iter()
+            .flat_map(|capture| capture.missing.iter().copied())
+            .collect::<BTreeSet<_>>();
+        let bands = if bands.is_empty() {
+            "required bands".to_string()
+        } else {
+            bands.into_iter().collect::<Vec<_>>().join(", ")
+        };
+        let area = locator.strip(
+            run.iter()
+                .filter_map(|capture| capture.record.and_then(record_position)),
+        );
+        flags.push(QualityFlag {
+            flag_type: QualityFlagType::MissingBands,
+            severity,
+            start: run[0].timestamp,
+            end: run[run.len() - 1].timestamp,
+            area,
+            message: format!("{} multispectral captures missing {bands}", run.len()),
+            suggestion: format!("check the {bands} channel and {}", refly(area)),
+        });
+    }
+
+    Some(BandCompletenessMetrics {
+        capture_count: captures.len(),
+        complete_count,
+        required_bands: required.iter().map(|band| band.to_string()).collect(),
+        missing_bands: missing_bands
+            .into_iter()
+            .map(|band| band.to_string())
+            .collect(),
+        score,
+    })
+}
+
+fn lidar_stability_metrics(
+    records: &[FlightDataRecord],
+    config: &QualityAssessmentConfig,
+    locator: &TrackLocator,
+    flags: &mut Vec<QualityFlag>,
+) -> Option<LidarStabilityMetrics> {
+    let scans = sorted_records(records, |record| record.data_type == DataType::LidarScan);
+    let (first, last) = (scans.first()?.timestamp, scans.last()?.timestamp);
+    let span = seconds_between(first, last);
+    if span <= 0.0 {
+        return None;
+    }
+
+    let intervals = scans
+        .windows(2)
+        .map(|pair| seconds_between(pair[0].timestamp, pair[1].timestamp))
+        .collect::<Vec<_>>();
+    let mut sorted_intervals = intervals.clone();
+    sorted_intervals.sort_by(f64::total_cmp);
+    let median_interval = sorted_intervals[sorted_intervals.len() / 2];
+    let dropout_after = median_interval * config.lidar_dropout_factor;
+    let mut lost_seconds = 0.0;
+    for (pair, interval) in scans.windows(2).zip(&intervals) {
+        if *interval <= dropout_after {
+            continue;
+        }
+        lost_seconds += interval - median_interval;
+        let area = locator.strip(pair.iter().copied().filter_map(record_position));
+        flags.push(QualityFlag {
+            flag_type: QualityFlagType::LidarDropout,
+            severity: if *interval > CRITICAL_LIDAR_DROPOUT_SECONDS {
+                QualitySeverity::Critical
+            } else {
+                QualitySeverity::Warning
+            },
+            start: pair[0].timestamp,
+            end: pair[1].timestamp,
+            area,
+            message: format!("LiDAR scans stopped for {interval:.0} s"),
+            suggestion: refly(area),
+        });
+    }
+
+    // Only whole minutes are binned so a trailing partial minute does not
+    // read as a rate drop.
+    let full_minutes = (span / 60.0).floor() as usize;
+    let rates = if full_minutes == 0 {
+        vec![scans.len() as f64 * 60.0 / span]
+    } else {
+        let mut counts = vec![0.0; full_minutes];
+        for scan in &scans {
+            let minute = (seconds_between(first, scan.timestamp) / 60.0).floor() as usize;
+            if let Some(count) = counts.get_mut(minute) {
+                *count += 1.0;
+            }
+        }
+        counts
+    };
+    let mean = rates.iter().sum::<f64>() / rates.len() as f64;
+    let variance = rates.iter().map(|rate| (rate - mean).powi(2)).sum::<f64>() / rates.len() as f64;
+    let coefficient_of_variation = if mean > 0.0 {
+        variance.sqrt() / mean
+    } else {
+        0.0
+    };
+    let covered = (1.0 - lost_seconds / span).clamp(0.0, 1.0);
+
+    Some(LidarStabilityMetrics {
+        scan_count: scans.len(),
+        mean_scans_per_minute: mean,
+        min_scans_per_minute: rates.iter().copied().fold(f64::INFINITY, f64::min),
+        max_scans_per_minute: rates.iter().copied().fold(0.0, f64::max),
+        coefficient_of_variation,
+        score: covered.min(1.0 - coefficient_of_variation).clamp(0.0, 1.0),
+    })
+}
+
+fn sorted_records(
+    records: &[FlightDataRecord],
+    include: impl Fn(&FlightDataRecord) -> bool,
+) -> Vec<&FlightDataRecord> {
+    let mut selected = records
+        .iter()
+        .filter(|record| include(record))
+        .collect::<Vec<_>>();
+    selected.sort_by_key(|record| (record.timestamp, record.id));
+    selected
+}
+
+fn seconds_between(start: DateTime<Utc>, end: DateTime<Utc>) -> f64 {
+    end.signed_duration_since(start).num_milliseconds().max(0) as f64 / 1000.0
+}
+
+fn covered_fraction(samples: &[&FlightDataRecord], lost_seconds: f64) -> f64 {
+    let span = match (samples.first(), samples.last()) {
+        (Some(first), Some(last)) => seconds_between(first.timestamp, last.timestamp),
+        _ => 0.0,
+    };
+    if span > 0.0 {
+        (1.0 - lost_seconds / span).clamp(0.0, 1.0)
+    } else {
+        1.0
+    }
+}
+
+fn gap_severity(gap: f64, threshold: f64) -> QualitySeverity {
+    if gap > threshold * CRITICAL_GAP_MULTIPLIER {
+        QualitySeverity::Critical
+    } else {
+        QualitySeverity::Warning
+    }
+}
+
+fn session_window(
+    session: &FlightSession,
+    records: &[FlightDataRecord],
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    let end = session
+        .end_time
+        .or_else(|| records.iter().map(|record| record.timestamp).max())
+        .unwrap_or(session.start_time);
+    (session.start_time, end.max(session.start_time))
+}
+
+fn refly(area: Option<FieldStrip>) -> String {
+    match area {
+        Some(area) => format!("re-fly {} strip", area.as_str()),
+        None => "re-fly the affected window".to_string(),
+    }
+}
+
+/// Latitude and longitude a record was captured at, from the telemetry
+/// payload or the record's GPS fix.
+fn record_position(record: &FlightDataRecord) -> Option<(f64, f64)> {
+    match record.payload.as_telemetry() {
+        Some(telemetry) => Some((telemetry.position.0, telemetry.position.1)),
+        None => record
+            .gps_coords
+            .as_ref()
+            .map(|coords| (coords.latitude, coords.longitude)),
+    }
+}
+
+/// Names the strip of the field a set of positions falls in, relative to the
+/// centre of every position the session recorded.
+struct TrackLocator {
+    /// `None` when no record carries a position.
+    center: Option<(f64, f64)>,
+    meters_per_degree_lon: f64,
+    radius_m: f64,
+}
+
+impl TrackLocator {
+    fn new(records: &[FlightDataRecord]) -> Self {
+        let positions = records
+            .iter()
+            .filter_map(record_position)
+            .collect::<Vec<_>>();
+        let mut locator = Self {
+            center: mean_position(&positions),
+            meters_per_degree_lon: METERS_PER_DEGREE_LAT,
+            radius_m: 0.0,
+        };
+        if let Some(center) = locator.center {
+            locator.meters_per_degree_lon = METERS_PER_DEGREE_LAT * center.0.to_radians().cos();
+            locator.radius_m = positions
+                .iter()
+                .filter_map(|position| locator.offset(*position))
+                .map(|(east, north)| east.hypot(north))
+                .fold(0.0, f64::max);
+        }
+        locator
+    }
+
+    fn offset(&self, position: (f64, f64)) -> Option<(f64, f64)> {
+        let center = self.center?;
+        Some((
+            (position.1 - center.1) * self.meters_per_degree_lon,
+            (position.0 - center.0) * METERS_PER_DEGREE_LAT,
+        ))
+    }
+
+    fn strip(&self, positions: impl Iterator<Item = (f64, f64)>) -> Option<FieldStrip> {
+        let positions = positions.collect::<Vec<_>>();
+        let (east, north) = self.offset(mean_position(&positions)?)?;
+        if east.hypot(north) <= self.radius_m * CENTER_STRIP_FRACTION {
+            return Some(FieldStrip::Center);
+        }
+        Some(if east.abs() >= north.abs() {
+            if east > 0.0 {
+                FieldStrip::East
+            } else {
+                FieldStrip::West
+            }
+        } else if north > 0.0 {
+            FieldStrip::North
+        } else {
+            FieldStrip::South
+        })
+    }
+}
+
+fn mean_position(positions: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if positions.is_empty() {
+        return None;
+    }
+    let count = positions.len() as f64;
+    Some((
+        positions.iter().map(|position| position.0).sum::<f64>() / count,
+        positions.iter().map(|position| position.1).sum::<f64>() / count,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CaptureSessionRequest, CollectionFailure, DataPayload, FlightDataProvenance,
+        MediaFilePayload, PointCloudPayload, TelemetryPayload,
+    };
+    use chrono::TimeZone;
+    use shared::schemas::GpsCoords;
+    use uuid::Uuid;
+
+    fn session() -> FlightSession {
+        FlightSession::new(CaptureSessionRequest::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "grower-ops".to_string(),
+        ))
+    }
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_800_000_000 + seconds, 0).unwrap()
+    }
+
+    /// Position along an east-bound pass, one ten-thousandth of a degree of
+    /// longitude per second.
+    fn longitude_at(seconds: i64) -> f64 {
+        -105.0 + seconds as f64 * 0.0001
+    }
+
+    fn record(
+        session: &FlightSession,
+        seconds: i64,
+        data_type: DataType,
+        payload: DataPayload,
+        sensor_id: &str,
+    ) -> FlightDataRecord {
+        FlightDataRecord::new(
+            session.flight_id,
+            session.drone_id,
+            data_type,
+            payload,
+            FlightDataProvenance::complete(
+                session.id,
+                sensor_id.to_string(),
+                GpsCoords {
+                    latitude: 40.0,
+                    longitude: longitude_at(seconds),
+                    altitude: 30.0,
+                },
+                at(seconds),
+                "calibration-v1".to_string(),
+            ),
+            256,
+        )
+        .unwrap()
+    }
+
+    fn telemetry(session: &FlightSession, seconds: i64) -> FlightDataRecord {
+        record(
+            session,
+            seconds,
+            DataType::Telemetry,
+            DataPayload::Telemetry(TelemetryPayload {
+                position: (40.0, longitude_at(seconds), 30.0),
+                velocity: (8.5, 0.0, 0.0),
+                orientation: (0.0, 0.0, 0.0),
+                battery_level: 0.9,
+                signal_strength: 0.95,
+            }),
+            "telemetry-01",
+        )
+    }
+
+    fn multispectral(session: &FlightSession, seconds: i64, bands: &str) -> FlightDataRecord {
+        let mut record = record(
+            session,
+            seconds,
+            DataType::MultispectralImage,
+            DataPayload::MediaFile(MediaFilePayload {
+                file_type: "multispectral/tiff-stack".to_string(),
+                dimensions: Some((1280, 960)),
+                duration_seconds: None,
+                compression: Some("tiff".to_string()),
+            }),
+            "multispectral-01",
+        );
+        record
+            .metadata
+            .insert("bands".to_string(), bands.to_string());
+        record
+    }
+
+    fn lidar(session: &FlightSession, seconds: i64) -> FlightDataRecord {
+        record(
+            session,
+            seconds,
+            DataType::LidarScan,
+            DataPayload::PointCloud(PointCloudPayload {
+                point_count: 4_000,
+                bounds: ((0.0, 0.0, 0.0), (10.0, 10.0, 2.0)),
+                format: "rplidar".to_string(),
+                has_color: false,
+                has_intensity: true,
+            }),
+            "lidar-01",
+        )
+    }
+
+    #[test]
+    fn complete_session_scores_full_marks_without_flags() {
+        let session = session();
+        let mut records = (0..=60).map(|t| telemetry(&session, t)).collect::<Vec<_>>();
+        records.extend(
+            (0..=60)
+                .step_by(2)
+                .map(|t| multispectral(&session, t, "Red,NIR")),
+        );
+        records.extend((0..=60).map(|t| lidar(&session, t)));
+
+        let assessment =
+            score_session_quality(&session, &records, &QualityAssessmentConfig::default());
+
+        assert_eq!(assessment.score, 100);
+        assert!(assessment.flags.is_empty(), "{:?}", assessment.flags);
+        assert_eq!(assessment.telemetry.max_gap_seconds, 1.0);
+        assert_eq!(assessment.band_completeness.unwrap().complete_count, 31);
+        assert_eq!(
+            assessment.lidar_stability.unwrap().coefficient_of_variation,
+            0.0
+        );
+    }
+
+    #[test]
+    fn telemetry_gap_is_flagged_on_the_strip_it_left_uncovered() {
+        let session = session();
+        let records = (0..=120)
+            .filter(|t| !(81..110).contains(t))
+            .map(|t| telemetry(&session, t))
+            .collect::<Vec<_>>();
+
+        let assessment =
+            score_session_quality(&session, &records, &QualityAssessmentConfig::default());
+
+        assert_eq!(assessment.telemetry.max_gap_seconds, 30.0);
+        let histogram = &assessment.telemetry.gap_histogram;
+        assert_eq!(histogram[0].count, 90);
+        assert_eq!(histogram[4].max_seconds, Some(30.0));
+        assert_eq!(histogram[4].count, 1);
+        assert_eq!(histogram[5].count, 0);
+
+        let flags = assessment
+            .flags_of(QualityFlagType::TelemetryGap)
+            .collect::<Vec<_>>();
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].severity, QualitySeverity::Critical);
+        assert_eq!((flags[0].start, flags[0].end), (at(80), at(110)));
+        assert_eq!(flags[0].area, Some(FieldStrip::East));
+        assert_eq!(flags[0].suggestion, "re-fly east strip");
+        // 25 s of the 120 s track is lost past the 5 s threshold.
+        assert_eq!(assessment.score, 79);
+    }
+
+    #[test]
+    fn captures_missing_a_band_are_flagged_and_lower_the_score() {
+        let mut session = session();
+        let mut records = (0..=40).map(|t| telemetry(&session, t)).collect::<Vec<_>>();
+        records.extend((0..=40).step_by(2).map(|t| {
+            let bands = if (20..40).contains(&t) {
+                "Blue,Green,Red,RedEdge"
+            } else {
+                "Blue,Green,red,nir,RedEdge"
+            };
+            multispectral(&session, t, bands)
+        }));
+        session.summary.collection_failures.push(CollectionFailure {
+            id: Uuid::new_v4(),
+            occurred_at: at(39),
+            sensor_id: "multispectral-01".to_string(),
+            data_type: DataType::MultispectralImage,
+            kind: CollectionFailureKind::MissingBand,
+            message: "multispectral capture is missing required bands".to_string(),
+        });
+
+        let assessment =
+            score_session_quality(&session, &records, &QualityAssessmentConfig::default());
+
+        let bands = assessment.band_completeness.as_ref().unwrap();
+        assert_eq!(bands.capture_count, 22);
+        assert_eq!(bands.complete_count, 11);
+        assert_eq!(bands.missing_bands, vec!["NIR".to_string()]);
+        assert_eq!(bands.score, 0.5);
+
+        let flags = assessment
+            .flags_of(QualityFlagType::MissingBands)
+            .collect::<Vec<_>>();
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].severity, QualitySeverity::Warning);
+        assert_eq!((flags[0].start, flags[0].end), (at(20), at(39)));
+        assert_eq!(flags[0].message, "11 multispectral captures missing NIR");
+        assert_eq!(flags[0].area, Some(FieldStrip::East));
+        assert_eq!(
+            flags[0].suggestion,
+            "check the NIR channel and re-fly east strip"
+        );
+        assert!(
+            (75..=85).contains(&assessment.score),
+            "score {}",
+            assessment.score
+        );
+    }
+
+    #[test]
+    fn lidar_dropout_and_missing_telemetry_sink_the_score() {
+        let session = session();
+        let records = (0..=180)
+            .filter(|t| !(61..120).contains(t))
+            .map(|t| lidar(&session, t))
+            .collect::<Vec<_>>();
+
+        let assessment =
+            score_session_quality(&session, &records, &QualityAssessmentConfig::default());
+
+        let dropouts = assessment
+            .flags_of(QualityFlagType::LidarDropout)
+            .collect::<Vec<_>>();
+        assert_eq!(dropouts.len(), 1);
+        assert_eq!((dropouts[0].start, dropouts[0].end), (at(60), at(120)));
+        assert_eq!(dropouts[0].severity, QualitySeverity::Warning);
+        let lidar = assessment.lidar_stability.as_ref().unwrap();
+        assert_eq!(lidar.min_scans_per_minute, 1.0);
+        assert_eq!(lidar.max_scans_per_minute, 60.0);
+        assert!(lidar.score < 0.5, "lidar score {}", lidar.score);
+
+        assert_eq!(assessment.worst_severity(), Some(QualitySeverity::Critical));
+        assert_eq!(assessment.flags_of(QualityFlagType::NoTelemetry).count(), 1);
+        assert!(assessment.score < 20, "score {}", assessment.score);
+    }
+
+    #[test]
+    fn weights_only_count_components_the_session_has_data_for() {
+        let session = session();
+        let records = (0..=20)
+            .filter(|t| *t != 10)
+            .map(|t| multispectral(&session, t, "Red,NIR"))
+            .chain((0..=20).map(|t| telemetry(&session, t)))
+            .collect::<Vec<_>>();
+        let config = QualityAssessmentConfig {
+            weights: QualityWeights {
+                telemetry: 1.0,
+                image_spacing: 0.0,
+                band_completeness: 0.0,
+                lidar_stability: 5.0,
+            },
+            expected_image_interval_seconds: 1.0,
+            ..QualityAssessmentConfig::default()
+        };
+
+        let assessment = score_session_quality(&session, &records, &config);
+
+        assert!(assessment.lidar_stability.is_none());
+        assert_eq!(assessment.image_spacing.as_ref().unwrap().image_count, 20);
+        assert_eq!(
+            assessment
+                .flags_of(QualityFlagType::ImageSpacingGap)
+                .count(),
+            0
+        );
+        assert_eq!(assessment.score, 100);
+    }
+}
@@ -0,0 +1,556 @@
+//! Paced replay of a recorded session as live ground-station messages.
+//!
+//! A [`ReplayPlan`] orders a session's telemetry and sensor records by capture
+//! time, converts each into the `WebSocketMessage` mission control would have
+//! broadcast live, and assigns it a due time scaled by the replay speed. Playing
+//! the plan pushes the messages into a channel on that schedule so any sink
+//! (stdout, a mission control WebSocket) sees the flight as if it were live.
+
+use crate::{DataPayload, DataType, FlightDataRecord, MediaFilePayload, TelemetryPayload};
+use chrono::{DateTime, Utc};
+use shared::schemas::{
+    GpsCoords, ImageMetadata, LidarScan, MultispectralImage, Telemetry, WebSocketMessage,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::{mpsc, watch};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+const REPLAY_FLIGHT_MODE: &str = "REPLAY";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ReplayError {
+    #[error("replay speed must be a positive, finite factor, got {0}")]
+    InvalidSpeed(f64),
+    #[error("start offset {offset_secs}s is past the end of the {duration_secs}s session")]
+    StartOffsetPastEnd {
+        offset_secs: f64,
+        duration_secs: f64,
+    },
+}
+
+/// What to do when consecutive records are further apart than `max_gap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapPolicy {
+    /// Wait out the gap, scaled by the replay speed like any other interval.
+    Dwell,
+    /// Emit the record after the gap immediately.
+    Skip,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayOptions {
+    /// Playback speed multiplier; `4.0` plays a minute of flight in 15 seconds.
+    pub speed: f64,
+    /// Flight time to seek past before the first message is emitted, measured
+    /// from the session's first replayable record.
+    pub start_offset: Duration,
+    pub gap_policy: GapPolicy,
+    pub max_gap: Duration,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            start_offset: Duration::ZERO,
+            gap_policy: GapPolicy::Dwell,
+            max_gap: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayFrame {
+    pub record_id: Uuid,
+    pub recorded_at: DateTime<Utc>,
+    /// Wall-clock delay from the start of playback.
+    pub due: Duration,
+    pub message: WebSocketMessage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayOutcome {
+    pub sent: usize,
+    /// Playback stopped early on a shutdown signal or because the sink closed.
+    pub interrupted: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplayPlan {
+    frames: Vec<ReplayFrame>,
+    unreplayable_records: usize,
+}
+
+impl ReplayPlan {
+    pub fn build(
+        records: &[FlightDataRecord],
+        options: &ReplayOptions,
+    ) -> Result<Self, ReplayError> {
+        if !options.speed.is_finite() || options.speed <= 0.0 {
+            return Err(ReplayError::InvalidSpeed(options.speed));
+        }
+
+        let mut replayable = Vec::with_capacity(records.len());
+        for record in records {
+            if let Some(message) = replay_message(record) {
+                replayable.push((record, message));
+            }
+        }
+        let unreplayable_records = records.len() - replayable.len();
+        // Stable, so records sharing a timestamp keep their stored order.
+        replayable.sort_by_key(|(record, _)| record.timestamp);
+
+        let Some(first) = replayable.first().map(|(record, _)| record.timestamp) else {
+            return Ok(Self {
+                frames: Vec::new(),
+                unreplayable_records,
+            });
+        };
+        let last = replayable
+            .last()
+            .map(|(record, _)| record.timestamp)
+            .unwrap_or(first);
+        let seek_to = match chrono::Duration::from_std(options.start_offset)
+            .ok()
+            .and_then(|offset| first.checked_add_signed(offset))
+        {
+            Some(seek_to) if seek_to <= last => seek_to,
+            _ => {
+                return Err(ReplayError::StartOffsetPastEnd {
+                    offset_secs: options.start_offset.as_secs_f64(),
+                    duration_secs: (last - first).to_std().unwrap_or_default().as_secs_f64(),
+                })
+            }
+        };
+
+        let mut frames = Vec::with_capacity(replayable.len());
+        let mut due = Duration::ZERO;
+        let mut previous: Option<DateTime<Utc>> = None;
+        for (record, message) in replayable {
+            if record.timestamp < seek_to {
+                continue;
+            }
+            if let Some(previous) = previous {
+                let gap = (record.timestamp - previous).to_std().unwrap_or_default();
+                if options.gap_policy == GapPolicy::Dwell || gap <= options.max_gap {
+                    due += gap.div_f64(options.speed);
+                }
+            }
+            previous = Some(record.timestamp);
+            frames.push(ReplayFrame {
+                record_id: record.id,
+                recorded_at: record.timestamp,
+                due,
+                message,
+            });
+        }
+
+        Ok(Self {
+            frames,
+            unreplayable_records,
+        })
+    }
+
+    pub fn frames(&self) -> &[ReplayFrame] {
+        &self.frames
+    }
+
+    /// Records with no live message equivalent (logs, weather, ...).
+    pub fn unreplayable_records(&self) -> usize {
+        self.unreplayable_records
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.frames
+            .last()
+            .map(|frame| frame.due)
+            .unwrap_or_default()
+    }
+
+    /// Sends each frame's message into `sink` at its due time. Setting
+    /// `shutdown` to `true` stops playback before the next message.
+    pub async fn play(
+        &self,
+        sink: mpsc::Sender<WebSocketMessage>,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> ReplayOutcome {
+        let started = Instant::now();
+        let mut sent = 0;
+        for frame in &self.frames {
+            if wait_until(started + frame.due, &mut shutdown).await
+                || sink.send(frame.message.clone()).await.is_err()
+            {
+                return ReplayOutcome {
+                    sent,
+                    interrupted: true,
+                };
+            }
+            sent += 1;
+        }
+        ReplayOutcome {
+            sent,
+            interrupted: false,
+        }
+    }
+}
+
+/// Returns `true` if shutdown was requested before `deadline`.
+async fn wait_until(deadline: Instant, shutdown: &mut watch::Receiver<bool>) -> bool {
+    loop {
+        if *shutdown.borrow_and_update() {
+            return true;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => return false,
+            changed = shutdown.changed() => {
+                if changed.is_err() {
+                    // Nobody can request shutdown any more.
+                    tokio::time::sleep_until(deadline).await;
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// The message mission control would have broadcast when `record` was captured,
+/// or `None` for record types the live stream has no message for.
+pub fn replay_message(record: &FlightDataRecord) -> Option<WebSocketMessage> {
+    match (&record.data_type, &record.payload) {
+        (
+            DataType::Telemetry,
+            DataPayload::Telemetry(TelemetryPayload {
+                position,
+                velocity,
+                battery_level,
+                ..
+            }),
+        ) => {
+            let ground_speed = velocity.0.hypot(velocity.1);
+            Some(WebSocketMessage::Telemetry {
+                data: Telemetry {
+                    timestamp: record.timestamp,
+                    position: GpsCoords {
+                        latitude: position.0,
+                        longitude: position.1,
+                        altitude: f64::from(position.2),
+                    },
+                    // Recorded telemetry keeps the charge fraction, not the pack voltage.
+                    battery_voltage: 0.0,
+                    battery_percentage: (battery_level * 100.0).round().clamp(0.0, 100.0) as u8,
+                    armed: true,
+                    mode: REPLAY_FLIGHT_MODE.to_string(),
+                    ground_speed,
+                    air_speed: ground_speed,
+                    heading: velocity.1.atan2(velocity.0).to_degrees().rem_euclid(360.0),
+                    altitude_relative: position.2,
+                },
+            })
+        }
+        (
+            DataType::MultispectralImage | DataType::ThermalImage | DataType::Image,
+            DataPayload::MediaFile(MediaFilePayload { dimensions, .. }),
+        ) => {
+            let file_paths = image_file_paths(record);
+            let (width, height) = dimensions.unwrap_or_default();
+            Some(WebSocketMessage::ImageCaptured {
+                image: MultispectralImage {
+                    metadata: ImageMetadata {
+                        timestamp: record.timestamp,
+                        gps_position: record.gps_coords.clone(),
+                        bands: file_paths.keys().cloned().collect(),
+                        exposure_time: 0.0,
+                        gain: 0.0,
+                        width,
+                        height,
+                        spatial_ref: None,
+                    },
+                    file_paths,
+                    image_id: record.id,
+                },
+            })
+        }
+        (DataType::LidarScan, DataPayload::PointCloud(_)) => Some(WebSocketMessage::LidarUpdate {
+            scan: stored_lidar_scan(record).unwrap_or_else(|| LidarScan {
+                timestamp: record.timestamp,
+                points: Vec::new(),
+                scan_id: record
+                    .metadata
+                    .get("scan_id")
+                    .and_then(|scan_id| scan_id.parse().ok())
+                    .unwrap_or(record.id),
+            }),
+            sensor_id: Some(record.sensor_id.clone()),
+        }),
+        _ => None,
+    }
+}
+
+/// Band files recorded by `multispectral_capture_to_record`, falling back to the
+/// record's own file under its sensor id.
+fn image_file_paths(record: &FlightDataRecord) -> HashMap<String, String> {
+    if let Some(band_files) = record
+        .metadata
+        .get("band_files")
+        .and_then(|band_files| serde_json::from_str::<Vec<(String, String)>>(band_files).ok())
+    {
+        return band_files.into_iter().collect();
+    }
+    record
+        .file_path
+        .iter()
+        .map(|path| (record.sensor_id.clone(), path.to_string_lossy().to_string()))
+        .collect()
+}
+
+/// The full scan, when the record's file is the serialized `LidarScan`.
+fn stored_lidar_scan(record: &FlightDataRecord) -> Option<LidarScan> {
+    let contents = std::fs::read(record.file_path.as_ref()?).ok()?;
+    serde_json::from_slice(&contents).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PointCloudPayload, RawPayload};
+    use chrono::TimeZone;
+
+    const TIMING_TOLERANCE: Duration = Duration::from_millis(40);
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 6, 1, 10, 0, 0).unwrap()
+    }
+
+    fn record(data_type: DataType, payload: DataPayload, offset_ms: i64) -> FlightDataRecord {
+        FlightDataRecord {
+            id: Uuid::new_v4(),
+            session_id: Uuid::new_v4(),
+            flight_id: Uuid::new_v4(),
+            drone_id: Uuid::new_v4(),
+            timestamp: start() + chrono::Duration::milliseconds(offset_ms),
+            data_type,
+            payload,
+            sensor_id: "sensor-01".to_string(),
+            gps_coords: None,
+            calibration_ref: "calibration-2026-06".to_string(),
+            metadata: HashMap::new(),
+            file_path: None,
+            size_bytes: 64,
+        }
+    }
+
+    fn telemetry(offset_ms: i64) -> FlightDataRecord {
+        record(
+            DataType::Telemetry,
+            DataPayload::Telemetry(TelemetryPayload {
+                position: (40.0, -105.0, 30.0),
+                velocity: (3.0, 4.0, 0.0),
+                orientation: (0.0, 0.0, 0.0),
+                battery_level: 0.82,
+                signal_strength: 0.9,
+            }),
+            offset_ms,
+        )
+    }
+
+    fn image(offset_ms: i64) -> FlightDataRecord {
+        record(
+            DataType::MultispectralImage,
+            DataPayload::MediaFile(MediaFilePayload {
+                file_type: "multispectral/tiff-stack".to_string(),
+                dimensions: Some((640, 480)),
+                duration_seconds: None,
+                compression: None,
+            }),
+            offset_ms,
+        )
+    }
+
+    fn lidar(offset_ms: i64) -> FlightDataRecord {
+        record(
+            DataType::LidarScan,
+            DataPayload::PointCloud(PointCloudPayload {
+                point_count: 0,
+                bounds: ((0.0, 0.0, 0.0), (0.0, 0.0, 0.0)),
+                format: "rplidar-a3-q2".to_string(),
+                has_color: false,
+                has_intensity: true,
+            }),
+            offset_ms,
+        )
+    }
+
+    fn kind(message: &WebSocketMessage) -> &'static str {
+        match message {
+            WebSocketMessage::Telemetry { .. } => "telemetry",
+            WebSocketMessage::ImageCaptured { .. } => "image",
+            WebSocketMessage::LidarUpdate { .. } => "lidar",
+            _ => "other",
+        }
+    }
+
+    /// Plays `plan` into a receiver that timestamps each arrival.
+    async fn play_and_time(plan: &ReplayPlan) -> (ReplayOutcome, Vec<(Duration, &'static str)>) {
+        let (tx, mut rx) = mpsc::channel(16);
+        let (_shutdown_tx, shutdown) = watch::channel(false);
+        let started = Instant::now();
+        let receiver = tokio::spawn(async move {
+            let mut arrivals = Vec::new();
+            while let Some(message) = rx.recv().await {
+                arrivals.push((started.elapsed(), kind(&message)));
+            }
+            arrivals
+        });
+        let outcome = plan.play(tx, shutdown).await;
+        (outcome, receiver.await.unwrap())
+    }
+
+    #[test]
+    fn interleaved_records_are_ordered_by_capture_time() {
+        let mut system_log = telemetry(150);
+        system_log.data_type = DataType::SystemLog;
+        system_log.payload = DataPayload::Raw(RawPayload {
+            format: "text".to_string(),
+            schema: None,
+            compression: None,
+        });
+        let records = vec![
+            lidar(300),
+            telemetry(200),
+            image(100),
+            system_log,
+            telemetry(0),
+            image(200),
+        ];
+
+        let plan = ReplayPlan::build(&records, &ReplayOptions::default()).unwrap();
+
+        let kinds: Vec<_> = plan
+            .frames()
+            .iter()
+            .map(|frame| kind(&frame.message))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec!["telemetry", "image", "telemetry", "image", "lidar"]
+        );
+        assert_eq!(plan.unreplayable_records(), 1);
+        assert!(plan
+            .frames()
+            .windows(2)
+            .all(|pair| pair[0].recorded_at <= pair[1].recorded_at));
+
+        let WebSocketMessage::Telemetry { data } = &plan.frames()[0].message else {
+            panic!("expected telemetry first");
+        };
+        assert_eq!(data.battery_percentage, 82);
+        assert!((data.ground_speed - 5.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn playback_spacing_scales_with_speed() {
+        let records = vec![telemetry(0), image(1_000), telemetry(2_000)];
+
+        for speed in [10.0, 20.0] {
+            let options = ReplayOptions {
+                speed,
+                ..ReplayOptions::default()
+            };
+            let plan = ReplayPlan::build(&records, &options).unwrap();
+            let expected_step = Duration::from_secs(1).div_f64(speed);
+            assert_eq!(plan.duration(), expected_step * 2);
+
+            let (outcome, arrivals) = play_and_time(&plan).await;
+
+            assert_eq!(outcome.sent, 3);
+            assert_eq!(
+                arrivals.iter().map(|(_, kind)| *kind).collect::<Vec<_>>(),
+                vec!["telemetry", "image", "telemetry"]
+            );
+            for pair in arrivals.windows(2) {
+                let spacing = pair[1].0 - pair[0].0;
+                assert!(
+                    spacing + TIMING_TOLERANCE >= expected_step
+                        && spacing <= expected_step + TIMING_TOLERANCE,
+                    "speed {speed}: spacing {spacing:?}, expected {expected_step:?}"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn gaps_are_skipped_and_start_offset_seeks() {
+        let records = vec![
+            telemetry(0),
+            telemetry(100),
+            telemetry(60_100),
+            lidar(60_200),
+        ];
+        let options = ReplayOptions {
+            speed: 1.0,
+            start_offset: Duration::from_millis(50),
+            gap_policy: GapPolicy::Skip,
+            max_gap: Duration::from_secs(5),
+        };
+
+        let plan = ReplayPlan::build(&records, &options).unwrap();
+
+        let dues: Vec<_> = plan.frames().iter().map(|frame| frame.due).collect();
+        assert_eq!(
+            dues,
+            vec![Duration::ZERO, Duration::ZERO, Duration::from_millis(100)]
+        );
+        let (outcome, arrivals) = play_and_time(&plan).await;
+        assert_eq!(outcome.sent, 3);
+        assert!(arrivals[2].0 < Duration::from_millis(100) + TIMING_TOLERANCE);
+
+        let dwell = ReplayPlan::build(
+            &records,
+            &ReplayOptions {
+                gap_policy: GapPolicy::Dwell,
+                ..options.clone()
+            },
+        )
+        .unwrap();
+        assert_eq!(dwell.duration(), Duration::from_millis(60_100));
+
+        assert!(matches!(
+            ReplayPlan::build(
+                &records,
+                &ReplayOptions {
+                    start_offset: Duration::from_secs(120),
+                    ..options
+                },
+            ),
+            Err(ReplayError::StartOffsetPastEnd { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_playback_mid_replay() {
+        let records = vec![telemetry(0), telemetry(10_000), telemetry(20_000)];
+        let plan = ReplayPlan::build(&records, &ReplayOptions::default()).unwrap();
+        let (tx, mut rx) = mpsc::channel(16);
+        let (shutdown_tx, shutdown) = watch::channel(false);
+
+        let playback = tokio::spawn(async move { plan.play(tx, shutdown).await });
+        assert!(rx.recv().await.is_some());
+        shutdown_tx.send(true).unwrap();
+
+        let outcome = tokio::time::timeout(Duration::from_secs(1), playback)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            outcome,
+            ReplayOutcome {
+                sent: 1,
+                interrupted: true
+            }
+        );
+        assert!(rx.recv().await.is_none());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     CollectionFailureKind, CollectionFailureRequest, DataPayload, DataType, FlightDataProvenance,
-    FlightDataProvenanceError, FlightDataRecord,
+    FlightDataProvenanceError, FlightDataRecord, PointCloudPayload,
 };
 use chrono::{DateTime, Utc};
 use shared::schemas::{LidarPoint, LidarScan};
@@ -114,13 +114,13 @@ pub fn lidar_scan_to_record(
         flight_id,
         drone_id,
         DataType::LidarScan,
-        DataPayload::PointCloud {
+        DataPayload::PointCloud(PointCloudPayload {
             point_count: scan.points.len() as u32,
             bounds,
             format: "rplidar-a3-q2".to_string(),
             has_color: false,
             has_intensity: true,
-        },
+        }),
         provenance,
         size_bytes,
     )?;
@@ -247,13 +247,13 @@ mod tests {
             Some(&scan.scan_id.to_string())
         );
         match record.payload {
-            DataPayload::PointCloud {
+            DataPayload::PointCloud(PointCloudPayload {
                 point_count,
                 bounds,
                 format,
                 has_intensity,
                 ..
-            } => {
+            }) => {
                 assert_eq!(point_count, 3);
                 assert_eq!(format, "rplidar-a3-q2");
                 assert!(has_intensity);
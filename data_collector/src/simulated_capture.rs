@@ -2,7 +2,7 @@ use crate::{
     lidar_scan_to_record, multispectral_capture_to_record, CollectionFailureKind,
     CollectionFailureRequest, DataPayload, DataType, FlightDataProvenance,
     FlightDataProvenanceError, FlightDataRecord, LidarRecordError, MultispectralBandCapture,
-    MultispectralCaptureManifest, MultispectralRecordError,
+    MultispectralCaptureManifest, MultispectralRecordError, TelemetryPayload,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -259,7 +259,7 @@ pub fn simulated_capture_frame_to_batch(
                     flight_id,
                     drone_id,
                     DataType::Telemetry,
-                    DataPayload::Telemetry {
+                    DataPayload::Telemetry(TelemetryPayload {
                         position: (
                             position.latitude,
                             position.longitude,
@@ -269,7 +269,7 @@ pub fn simulated_capture_frame_to_batch(
                         orientation,
                         battery_level,
                         signal_strength,
-                    },
+                    }),
                     provenance,
                     128,
                 )?;
@@ -1,18 +1,111 @@
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use std::sync::Arc;
+use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use crate::products::{DerivedProduct, ProductDisposition, SessionDeletion};
+use crate::storage_backend::{LocalStorage, Storage, StoredObject};
 use crate::{DataType, FlightDataRecord as DataRecord, FlightSession as CollectionSession};
 
-/// Storage engine for collected data
+const PRODUCTS_FILE: &str = "products.json";
+const SESSION_FILE: &str = "session.json";
+const RETENTION_AUDIT_FILE: &str = "audit/retention.jsonl";
+/// `session_index/<session_id>` holds the prefix a session in the drone and
+/// date layout lives under, so it can be found by id alone.
+const SESSION_INDEX_PREFIX: &str = "session_index/";
+
+/// Records written through [`StorageEngine::store_batch`] live under this
+/// prefix, one `<sequence>.log` object per batch. The append-only
+/// `records.log` that older versions wrote every batch to is read the same
+/// way, and compacted rather than appended to.
+const BATCH_PREFIX: &str = "batches/";
+const BATCH_MAGIC: &[u8; 4] = b"AGB1";
+/// Magic, record count (u32), payload length (u64) and payload checksum (u64).
+const BATCH_HEADER_LEN: usize = 24;
+/// Record id followed by the encoded record length (u32).
+const BATCH_ENTRY_HEADER_LEN: usize = 20;
+
+/// Storage engine for collected data, keeping every object in a [`Storage`]
+/// backend: the local filesystem unless opened with [`Self::open`].
 #[derive(Debug, Clone)]
-pub struct StorageEngine {
+pub struct StorageEngine<S = LocalStorage> {
     pub config: StorageConfig,
+    storage: Arc<S>,
+    batch_log: Arc<Mutex<BatchLog>>,
+}
+
+/// In-memory view of the batch objects: which object and byte range each
+/// batched record lives at, and the sequence the next batch is written under.
+#[derive(Debug, Default)]
+struct BatchLog {
+    /// Batch objects with the number of batches each holds; only the legacy
+    /// log holds more than one.
+    objects: BTreeMap<String, usize>,
+    records: HashMap<Uuid, BatchedRecord>,
+    next_sequence: u64,
+    truncated_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+struct BatchedRecord {
+    object: Arc<str>,
+    offset: u64,
+    len: u32,
+}
+
+impl BatchedRecord {
+    fn range(&self) -> Range<usize> {
+        self.offset as usize..self.offset as usize + self.len as usize
+    }
+}
+
+impl BatchLog {
+    /// Indexes the complete batches held by the object `key`, returning how
+    /// many leading bytes they cover; anything after them is counted as
+    /// truncated.
+    fn index_object(&mut self, key: &str, bytes: &[u8]) -> usize {
+        if let Some(sequence) = batch_sequence(key) {
+            self.next_sequence = self.next_sequence.max(sequence + 1);
+        }
+        let scanned = scan_batch_object(bytes);
+        let truncated_bytes = (bytes.len() - scanned.committed_len) as u64;
+        if truncated_bytes > 0 {
+            tracing::warn!(
+                key,
+                truncated_bytes,
+                "truncating incomplete batch in record log"
+            );
+            self.truncated_bytes += truncated_bytes;
+        }
+        if scanned.batches > 0 {
+            let object: Arc<str> = key.into();
+            for (record_id, offset, len) in scanned.entries {
+                self.records.insert(
+                    record_id,
+                    BatchedRecord {
+                        object: object.clone(),
+                        offset,
+                        len,
+                    },
+                );
+            }
+            self.objects.insert(key.to_string(), scanned.batches);
+        }
+        scanned.committed_len
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchLogSummary {
+    pub batches: usize,
+    pub records: usize,
+    /// Bytes of incomplete batches cut off when the store was opened.
+    pub truncated_bytes: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,19 +132,122 @@ impl Default for StorageConfig {
 }
 
 impl StorageEngine {
+    /// Opens the store under `config.base_path`, indexing the batch objects.
+    /// A batch that was only partly written when the process died is
+    /// truncated away, and files staged by writes it interrupted are removed.
     pub fn new(config: StorageConfig) -> Result<Self> {
-        // TODO: Add validation logic if needed
-        Ok(Self { config })
+        let storage = LocalStorage::new(config.base_path.clone());
+        storage.remove_staged_files()?;
+        let batch_log = recover_local_batch_log(&config.base_path)?;
+        Ok(Self {
+            storage: Arc::new(storage),
+            config,
+            batch_log: Arc::new(Mutex::new(batch_log)),
+        })
+    }
+}
+
+impl<S: Storage> StorageEngine<S> {
+    /// Opens the store held by `storage`, cleaning up after interrupted
+    /// writes like [`StorageEngine::new`]. `config.base_path` is not used to
+    /// locate objects.
+    pub async fn open(config: StorageConfig, storage: S) -> Result<Self> {
+        storage.remove_interrupted_writes().await?;
+        let mut batch_log = BatchLog::default();
+        let mut keys = storage
+            .list(BATCH_PREFIX)
+            .await?
+            .into_iter()
+            .map(|object| object.key)
+            .filter(|key| key.ends_with(".log"))
+            .collect::<Vec<_>>();
+        keys.sort();
+        for key in keys {
+            let Some(bytes) = storage.load_bytes(&key).await? else {
+                continue;
+            };
+            let committed_len = batch_log.index_object(&key, &bytes);
+            if committed_len == 0 {
+                storage.delete(&key).await?;
+            } else if committed_len < bytes.len() {
+                storage
+                    .store_bytes(&key, bytes[..committed_len].to_vec())
+                    .await?;
+            }
+        }
+        Ok(Self {
+            config,
+            storage: Arc::new(storage),
+            batch_log: Arc::new(Mutex::new(batch_log)),
+        })
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Directory a new session's objects are written to:
+    /// `<base_path>/sessions/<drone_id>/<start date>/<session_id>`.
+    pub fn get_session_path(&self, session: &CollectionSession) -> PathBuf {
+        self.config.base_path.join(Self::session_prefix(session))
     }
 
-    /// Store a data record to persistent storage
-    pub async fn store_record(&self, record: &DataRecord) -> Result<PathBuf> {
-        let storage_path = self.get_storage_path(record)?;
+    /// Batches and records held in the batch objects.
+    pub async fn batch_log_summary(&self) -> BatchLogSummary {
+        let log = self.batch_log.lock().await;
+        BatchLogSummary {
+            batches: log.objects.values().sum(),
+            records: log.records.len(),
+            truncated_bytes: log.truncated_bytes,
+        }
+    }
 
-        // Ensure directory exists
-        if let Some(parent) = storage_path.parent() {
-            fs::create_dir_all(parent).await?;
+    /// Stores `records` as one checksummed batch written to its own object,
+    /// with a single write for the whole batch. Returns the records as
+    /// stored, like [`Self::store_data`].
+    pub async fn store_batch(&self, records: &[DataRecord]) -> Result<Vec<DataRecord>> {
+        if records.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let prepared = records
+            .iter()
+            .map(crate::prepare_record_for_storage)
+            .collect::<Result<Vec<_>>>()?;
+        let mut payload = Vec::new();
+        let mut entries = Vec::with_capacity(prepared.len());
+        for record in &prepared {
+            let encoded = serde_json::to_vec(record)?;
+            payload.extend_from_slice(record.id.as_bytes());
+            payload.extend_from_slice(&(encoded.len() as u32).to_le_bytes());
+            entries.push((record.id, payload.len() as u64, encoded.len() as u32));
+            payload.extend_from_slice(&encoded);
+        }
+        let frame = batch_frame(prepared.len() as u32, &payload);
+
+        let mut log = self.batch_log.lock().await;
+        let key = batch_key(log.next_sequence);
+        self.storage.store_bytes(&key, frame).await?;
+        log.next_sequence += 1;
+
+        let object: Arc<str> = key.as_str().into();
+        for (record_id, offset, len) in entries {
+            log.records.insert(
+                record_id,
+                BatchedRecord {
+                    object: object.clone(),
+                    offset: BATCH_HEADER_LEN as u64 + offset,
+                    len,
+                },
+            );
         }
+        log.objects.insert(key, 1);
+        Ok(prepared)
+    }
+
+    /// Store a data record to persistent storage, returning its key
+    pub async fn store_record(&self, record: &DataRecord) -> Result<String> {
+        let key = self.record_key(record);
 
         // Serialize and write the record
         let data = if self.config.compression_enabled {
@@ -60,31 +256,42 @@ impl StorageEngine {
             serde_json::to_vec_pretty(record)?
         };
 
-        fs::write(&storage_path, data).await?;
+        self.storage.store_bytes(&key, data.clone()).await?;
 
         // Create backup if enabled
         if self.config.backup_enabled {
-            self.create_backup(&storage_path, record).await?;
+            self.create_backup(record, data).await?;
         }
 
-        Ok(storage_path)
+        Ok(key)
     }
 
     /// Retrieve a data record by ID
     pub async fn retrieve_record(&self, record_id: &Uuid) -> Result<Option<DataRecord>> {
-        let direct_path = self.get_record_path(record_id)?;
-        let storage_path = if direct_path.exists() {
-            Some(direct_path)
-        } else {
-            self.find_record_path(record_id).await?
-        };
+        {
+            let log = self.batch_log.lock().await;
+            if let Some(location) = log.records.get(record_id) {
+                return self.read_batched_record(location).await.map(Some);
+            }
+        }
 
-        let Some(storage_path) = storage_path else {
-            return Ok(None);
+        let data = match self
+            .storage
+            .load_bytes(&Self::record_id_key(record_id))
+            .await?
+        {
+            Some(data) => data,
+            None => {
+                let Some(record) = self.find_record_object(record_id).await? else {
+                    return Ok(None);
+                };
+                self.storage
+                    .load_bytes(&record.key)
+                    .await?
+                    .ok_or_else(|| anyhow!("record {record_id} vanished while loading"))?
+            }
         };
 
-        let data = fs::read(&storage_path).await?;
-
         let record = if self.config.compression_enabled {
             self.decompress_data(&data).await?
         } else {
@@ -94,27 +301,30 @@ impl StorageEngine {
         Ok(Some(record))
     }
 
-    /// Store a complete collection session
-    pub async fn store_session(&self, session: &CollectionSession) -> Result<PathBuf> {
-        let session_path = self.get_session_path(&session.id)?;
-
-        // Create session directory
-        fs::create_dir_all(&session_path).await?;
+    /// Store a complete collection session, returning the key prefix its
+    /// objects live under. A session already stored under the legacy flat
+    /// layout is rewritten in place.
+    pub async fn store_session(&self, session: &CollectionSession) -> Result<String> {
+        let session_prefix = self.session_prefix_for(session).await?;
+        let index_key = Self::session_index_key(&session.id);
+        if session_prefix != Self::legacy_session_prefix(&session.id)
+            && self.storage.load_bytes(&index_key).await?.as_deref()
+                != Some(session_prefix.as_bytes())
+        {
+            self.storage
+                .store_bytes(&index_key, session_prefix.clone().into_bytes())
+                .await?;
+        }
 
         // Store session metadata
-        let metadata_path = session_path.join("session.json");
         let metadata = serde_json::to_vec_pretty(session)?;
-        fs::write(&metadata_path, metadata).await?;
+        self.storage
+            .store_bytes(&format!("{session_prefix}{SESSION_FILE}"), metadata)
+            .await?;
 
-        // Store individual records
-        for record_id in &session.data_records {
-            let _record_path = session_path.join(format!("{}.json", record_id));
-            // Note: This would need to load the actual record data
-            // For now, we'll skip individual record storage in session export
-            // Individual records are stored separately via store_data()
-        }
+        // Individual records are stored separately via store_data()
 
-        Ok(session_path)
+        Ok(session_prefix)
     }
 
     /// List all stored sessions
@@ -125,8 +335,10 @@ impl StorageEngine {
     ) -> Result<Vec<crate::FlightSession>> {
         let mut sessions = Vec::new();
 
-        for metadata_path in self.session_metadata_paths()? {
-            let metadata = fs::read(&metadata_path).await?;
+        for metadata_key in self.session_metadata_keys().await? {
+            let Some(metadata) = self.storage.load_bytes(&metadata_key).await? else {
+                continue;
+            };
             let session: CollectionSession = serde_json::from_slice(&metadata)?;
             if drone_id.is_none() || drone_id == Some(session.drone_id) {
                 sessions.push(session);
@@ -145,9 +357,11 @@ impl StorageEngine {
     pub async fn get_storage_stats(&self) -> Result<StorageStatistics> {
         let mut stats = StorageStatistics::default();
 
-        // Calculate total size and count files
-        self.calculate_directory_stats(&self.config.base_path, &mut stats)
-            .await?;
+        // Calculate total size and count objects
+        for object in self.storage.list("").await? {
+            stats.total_files += 1;
+            stats.total_size_bytes += object.size_bytes;
+        }
 
         // Get data type breakdown
         stats.data_type_breakdown = self.get_data_type_breakdown().await?;
@@ -163,18 +377,8 @@ impl StorageEngine {
         let sessions = self.list_sessions(None, None).await?;
 
         for session in sessions {
-            let session_path = self.get_session_path(&session.id)?;
-            let metadata_path = session_path.join("session.json");
-
-            if metadata_path.exists() {
-                let metadata = fs::read(&metadata_path).await?;
-                if let Ok(session) = serde_json::from_slice::<CollectionSession>(&metadata) {
-                    if session.start_time < cutoff_date {
-                        let size = self.get_directory_size(&session_path).await?;
-                        fs::remove_dir_all(&session_path).await?;
-                        cleaned_bytes += size;
-                    }
-                }
+            if session.start_time < cutoff_date {
+                cleaned_bytes += self.remove_session_objects(&session).await?;
             }
         }
 
@@ -183,208 +387,284 @@ impl StorageEngine {
 
     // Private helper methods
 
-    fn get_storage_path(&self, record: &DataRecord) -> Result<PathBuf> {
+    fn record_key(&self, record: &DataRecord) -> String {
         let date_path = record.timestamp.format("%Y/%m/%d").to_string();
-        let filename = format!(
-            "{}_{}.json",
+        format!(
+            "records/{}/{}_{}.json",
+            date_path,
             record.data_type.to_string().to_lowercase(),
             record.id
-        );
-
-        Ok(self
-            .config
-            .base_path
-            .join("records")
-            .join(&date_path)
-            .join(&filename))
+        )
     }
 
-    fn get_record_path(&self, record_id: &Uuid) -> Result<PathBuf> {
+    fn record_id_key(record_id: &Uuid) -> String {
         // For retrieval, we need to search for the record
         // This is a simplified implementation
-        Ok(self
-            .config
-            .base_path
-            .join("records")
-            .join(format!("{}.json", record_id)))
+        format!("records/{}.json", record_id)
     }
 
-    fn record_json_paths(&self) -> Result<Vec<PathBuf>> {
-        Self::json_paths_under(&self.config.base_path.join("records"))
+    async fn record_json_objects(&self) -> Result<Vec<StoredObject>> {
+        Ok(self
+            .storage
+            .list("records/")
+            .await?
+            .into_iter()
+            .filter(|object| object.key.ends_with(".json"))
+            .collect())
     }
 
-    fn session_metadata_paths(&self) -> Result<Vec<PathBuf>> {
-        let mut paths = Vec::new();
-        let sessions_path = self.config.base_path.join("sessions");
-        if !sessions_path.exists() {
-            return Ok(paths);
-        }
+    async fn session_metadata_keys(&self) -> Result<Vec<String>> {
+        Ok(self
+            .storage
+            .list("sessions/")
+            .await?
+            .into_iter()
+            .filter(|object| object.key.rsplit('/').next() == Some(SESSION_FILE))
+            .map(|object| object.key)
+            .collect())
+    }
 
-        for entry in walkdir::WalkDir::new(sessions_path) {
-            let entry = entry?;
-            if entry.file_type().is_file() && entry.file_name() == "session.json" {
-                paths.push(entry.path().to_path_buf());
-            }
-        }
+    async fn find_record_object(&self, record_id: &Uuid) -> Result<Option<StoredObject>> {
+        let record_id = record_id.to_string();
+        Ok(self
+            .record_json_objects()
+            .await?
+            .into_iter()
+            .find(|object| {
+                object
+                    .key
+                    .rsplit('/')
+                    .next()
+                    .is_some_and(|name| name.contains(&record_id))
+            }))
+    }
 
-        Ok(paths)
+    fn session_prefix(session: &CollectionSession) -> String {
+        format!(
+            "sessions/{}/{}/{}/",
+            session.drone_id,
+            session.start_time.format("%Y-%m-%d"),
+            session.id
+        )
     }
 
-    fn json_paths_under(root: &Path) -> Result<Vec<PathBuf>> {
-        let mut paths = Vec::new();
-        if !root.exists() {
-            return Ok(paths);
-        }
+    /// Sessions written before drone and date directories were introduced
+    /// live directly under `sessions/<session_id>/`.
+    fn legacy_session_prefix(session_id: &Uuid) -> String {
+        format!("sessions/{session_id}/")
+    }
 
-        for entry in walkdir::WalkDir::new(root) {
-            let entry = entry?;
-            if entry.file_type().is_file()
-                && entry.path().extension().map_or(false, |ext| ext == "json")
-            {
-                paths.push(entry.path().to_path_buf());
-            }
-        }
+    fn session_index_key(session_id: &Uuid) -> String {
+        format!("{SESSION_INDEX_PREFIX}{session_id}")
+    }
 
-        Ok(paths)
+    async fn has_legacy_metadata(&self, session_id: &Uuid) -> Result<bool> {
+        let key = format!("{}{SESSION_FILE}", Self::legacy_session_prefix(session_id));
+        Ok(self.storage.load_bytes(&key).await?.is_some())
     }
 
-    async fn find_record_path(&self, record_id: &Uuid) -> Result<Option<PathBuf>> {
-        let record_id = record_id.to_string();
-        for path in self.record_json_paths()? {
-            if path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .map_or(false, |name| name.contains(&record_id))
-            {
-                return Ok(Some(path));
-            }
+    /// Prefix `session` is stored under: the legacy one if its metadata is
+    /// already there, the drone and date one otherwise.
+    async fn session_prefix_for(&self, session: &CollectionSession) -> Result<String> {
+        if self.has_legacy_metadata(&session.id).await? {
+            Ok(Self::legacy_session_prefix(&session.id))
+        } else {
+            Ok(Self::session_prefix(session))
         }
-
-        Ok(None)
     }
 
-    fn get_session_path(&self, session_id: &Uuid) -> Result<PathBuf> {
+    /// Prefix the session's metadata is actually stored under, in either
+    /// layout, probing the legacy key and the session index directly.
+    async fn stored_session_prefix(&self, session_id: &Uuid) -> Result<Option<String>> {
+        if self.has_legacy_metadata(session_id).await? {
+            return Ok(Some(Self::legacy_session_prefix(session_id)));
+        }
         Ok(self
-            .config
-            .base_path
-            .join("sessions")
-            .join(session_id.to_string()))
+            .storage
+            .load_bytes(&Self::session_index_key(session_id))
+            .await?
+            .and_then(|prefix| String::from_utf8(prefix).ok()))
     }
 
-    async fn compress_data(&self, record: &DataRecord) -> Result<Vec<u8>> {
-        // Simplified compression - in practice, use a proper compression library
-        let json_data = serde_json::to_vec(record)?;
-        Ok(json_data)
+    /// Deletes the session's metadata and auxiliary objects along with its
+    /// index entry, returning the bytes freed.
+    async fn remove_session_objects(&self, session: &CollectionSession) -> Result<u64> {
+        let removed_bytes = self
+            .remove_prefix(&self.session_prefix_for(session).await?)
+            .await?;
+        self.storage
+            .delete(&Self::session_index_key(&session.id))
+            .await?;
+        Ok(removed_bytes)
     }
 
-    async fn decompress_data(&self, data: &[u8]) -> Result<DataRecord> {
-        // Simplified decompression
-        let record = serde_json::from_slice(data)?;
-        Ok(record)
+    /// Prefix for a session's auxiliary objects, falling back to the legacy
+    /// layout for sessions whose metadata has not been stored.
+    async fn session_objects_prefix(&self, session_id: &Uuid) -> Result<String> {
+        Ok(self
+            .stored_session_prefix(session_id)
+            .await?
+            .unwrap_or_else(|| Self::legacy_session_prefix(session_id)))
     }
 
-    async fn create_backup(&self, original_path: &Path, record: &DataRecord) -> Result<()> {
-        let backup_path = self
-            .config
-            .base_path
-            .join("backups")
-            .join(format!("{}.backup", record.id));
-
-        if let Some(parent) = backup_path.parent() {
-            fs::create_dir_all(parent).await?;
+    /// Deletes every object under `prefix`, returning the bytes freed.
+    async fn remove_prefix(&self, prefix: &str) -> Result<u64> {
+        let mut removed_bytes = 0;
+        for object in self.storage.list(prefix).await? {
+            self.storage.delete(&object.key).await?;
+            removed_bytes += object.size_bytes;
         }
+        Ok(removed_bytes)
+    }
 
-        fs::copy(original_path, &backup_path).await?;
-        Ok(())
+    /// Callers hold the batch log lock so compaction cannot move the record.
+    async fn read_batched_record(&self, location: &BatchedRecord) -> Result<DataRecord> {
+        let encoded = self
+            .storage
+            .load_range(
+                &location.object,
+                location.offset..location.offset + u64::from(location.len),
+            )
+            .await?;
+        Ok(serde_json::from_slice(&encoded)?)
     }
 
-    async fn calculate_directory_stats(
-        &self,
-        path: &Path,
-        stats: &mut StorageStatistics,
-    ) -> Result<()> {
-        if !path.exists() {
-            return Ok(());
+    /// Every batched record with its encoded size, loading each batch object
+    /// once.
+    async fn load_batched_records(&self) -> Result<Vec<(DataRecord, u64)>> {
+        let log = self.batch_log.lock().await;
+        let mut by_object = BTreeMap::<&str, Vec<&BatchedRecord>>::new();
+        for location in log.records.values() {
+            by_object
+                .entry(&location.object)
+                .or_default()
+                .push(location);
         }
 
-        let mut entries = fs::read_dir(path).await?;
-
-        while let Some(entry) = entries.next_entry().await? {
-            let metadata = entry.metadata().await?;
-
-            if metadata.is_file() {
-                stats.total_files += 1;
-                stats.total_size_bytes += metadata.len();
-            } else if metadata.is_dir() {
-                Box::pin(self.calculate_directory_stats(&entry.path(), stats)).await?;
+        let mut records = Vec::with_capacity(log.records.len());
+        for (object, locations) in by_object {
+            let bytes = self.storage.load_bytes(object).await?.unwrap_or_default();
+            for location in locations {
+                let encoded = bytes.get(location.range()).ok_or_else(|| {
+                    anyhow!(
+                        "batch object {object} is shorter than its index; restart to recover it"
+                    )
+                })?;
+                records.push((serde_json::from_slice(encoded)?, u64::from(location.len)));
             }
         }
-
-        Ok(())
+        Ok(records)
     }
 
-    async fn get_data_type_breakdown(&self) -> Result<HashMap<DataType, u32>> {
-        let mut breakdown = HashMap::new();
-
-        // Simplified implementation - scan all records
-        let records_path = self.config.base_path.join("records");
-        if records_path.exists() {
-            self.scan_data_types(&records_path, &mut breakdown).await?;
+    /// Drops `record_ids` from the batch objects holding them. Each affected
+    /// object is rewritten with its surviving records, or deleted when none
+    /// survive; other batches are left untouched.
+    async fn remove_batched_records(&self, record_ids: &HashSet<Uuid>) -> Result<(u32, u64)> {
+        let mut log = self.batch_log.lock().await;
+        let affected = record_ids
+            .iter()
+            .filter_map(|id| log.records.get(id))
+            .map(|location| location.object.clone())
+            .collect::<BTreeSet<_>>();
+        if affected.is_empty() {
+            return Ok((0, 0));
         }
 
-        Ok(breakdown)
-    }
-
-    async fn scan_data_types(
-        &self,
-        path: &Path,
-        breakdown: &mut HashMap<DataType, u32>,
-    ) -> Result<()> {
-        if !path.exists() {
-            return Ok(());
+        let mut survivors_by_object = BTreeMap::<Arc<str>, Vec<(Uuid, BatchedRecord)>>::new();
+        let mut removed_records = 0u32;
+        let mut removed_bytes = 0u64;
+        for (record_id, location) in &log.records {
+            if !affected.contains(&location.object) {
+                continue;
+            }
+            if record_ids.contains(record_id) {
+                removed_records += 1;
+                removed_bytes += u64::from(location.len);
+            } else {
+                survivors_by_object
+                    .entry(location.object.clone())
+                    .or_default()
+                    .push((*record_id, location.clone()));
+            }
         }
 
-        let mut entries = fs::read_dir(path).await?;
+        for object in affected {
+            let mut survivors = survivors_by_object.remove(&object).unwrap_or_default();
+            if survivors.is_empty() {
+                self.storage.delete(&object).await?;
+                log.records.retain(|_, location| location.object != object);
+                log.objects.remove(&*object);
+                continue;
+            }
 
-        while let Some(entry) = entries.next_entry().await? {
-            if entry.file_type().await?.is_file() {
-                if let Some(extension) = entry.path().extension() {
-                    if extension == "json" {
-                        if let Ok(data) = fs::read(entry.path()).await {
-                            if let Ok(record) = serde_json::from_slice::<DataRecord>(&data) {
-                                *breakdown.entry(record.data_type).or_insert(0) += 1;
-                            }
-                        }
-                    }
-                }
-            } else if entry.file_type().await?.is_dir() {
-                Box::pin(self.scan_data_types(&entry.path(), breakdown)).await?;
+            survivors.sort_by_key(|(_, location)| location.offset);
+            let bytes = self.storage.load_bytes(&object).await?.unwrap_or_default();
+            let mut payload = Vec::new();
+            let mut relocated = Vec::with_capacity(survivors.len());
+            for (record_id, location) in &survivors {
+                let encoded = bytes
+                    .get(location.range())
+                    .ok_or_else(|| anyhow!("batch object {object} is shorter than its index"))?;
+                payload.extend_from_slice(record_id.as_bytes());
+                payload.extend_from_slice(&location.len.to_le_bytes());
+                relocated.push((
+                    *record_id,
+                    (BATCH_HEADER_LEN + payload.len()) as u64,
+                    location.len,
+                ));
+                payload.extend_from_slice(encoded);
             }
+            let frame = batch_frame(survivors.len() as u32, &payload);
+            self.storage.store_bytes(&object, frame).await?;
+
+            log.records.retain(|_, location| location.object != object);
+            for (record_id, offset, len) in relocated {
+                log.records.insert(
+                    record_id,
+                    BatchedRecord {
+                        object: object.clone(),
+                        offset,
+                        len,
+                    },
+                );
+            }
+            log.objects.insert(object.to_string(), 1);
         }
 
-        Ok(())
+        Ok((removed_records, removed_bytes))
     }
 
-    async fn get_directory_size(&self, path: &Path) -> Result<u64> {
-        let mut total_size = 0u64;
+    async fn compress_data(&self, record: &DataRecord) -> Result<Vec<u8>> {
+        // Simplified compression - in practice, use a proper compression library
+        let json_data = serde_json::to_vec(record)?;
+        Ok(json_data)
+    }
 
-        if !path.exists() {
-            return Ok(0);
-        }
+    async fn decompress_data(&self, data: &[u8]) -> Result<DataRecord> {
+        // Simplified decompression
+        let record = serde_json::from_slice(data)?;
+        Ok(record)
+    }
 
-        let mut entries = fs::read_dir(path).await?;
+    async fn create_backup(&self, record: &DataRecord, data: Vec<u8>) -> Result<()> {
+        self.storage
+            .store_bytes(&format!("backups/{}.backup", record.id), data)
+            .await
+    }
 
-        while let Some(entry) = entries.next_entry().await? {
-            let metadata = entry.metadata().await?;
+    async fn get_data_type_breakdown(&self) -> Result<HashMap<DataType, u32>> {
+        let mut breakdown = HashMap::new();
 
-            if metadata.is_file() {
-                total_size += metadata.len();
-            } else if metadata.is_dir() {
-                total_size += Box::pin(self.get_directory_size(&entry.path())).await?;
+        // Simplified implementation - scan all records
+        for object in self.record_json_objects().await? {
+            if let Ok(Some(data)) = self.storage.load_bytes(&object.key).await {
+                if let Ok(record) = serde_json::from_slice::<DataRecord>(&data) {
+                    *breakdown.entry(record.data_type).or_insert(0) += 1;
+                }
             }
         }
 
-        Ok(total_size)
+        Ok(breakdown)
     }
 
     pub async fn store_data(
@@ -392,18 +672,34 @@ impl StorageEngine {
         data: &crate::FlightDataRecord,
     ) -> Result<crate::FlightDataRecord> {
         let prepared = crate::prepare_record_for_storage(data)?;
-        let _storage_path = self.store_record(&prepared).await?;
+        let _storage_key = self.store_record(&prepared).await?;
         Ok(prepared)
     }
 
-    pub async fn load_session(&self, session_id: &Uuid) -> Result<Option<crate::FlightSession>> {
-        let session_path = self.get_session_path(session_id)?.join("session.json");
-
-        if !session_path.exists() {
-            return Ok(None);
+    /// Rewrites records that are already stored. The new copies go to the
+    /// batch log, which takes precedence over individual record objects;
+    /// any such object is removed so the record is not listed twice.
+    pub async fn replace_records(&self, records: &[DataRecord]) -> Result<Vec<DataRecord>> {
+        let stored = self.store_batch(records).await?;
+        for record in records {
+            if let Some(object) = self.find_record_object(&record.id).await? {
+                self.storage.delete(&object.key).await?;
+            }
         }
+        Ok(stored)
+    }
 
-        let metadata = fs::read(&session_path).await?;
+    pub async fn load_session(&self, session_id: &Uuid) -> Result<Option<crate::FlightSession>> {
+        let Some(prefix) = self.stored_session_prefix(session_id).await? else {
+            return Ok(None);
+        };
+        let Some(metadata) = self
+            .storage
+            .load_bytes(&format!("{prefix}{SESSION_FILE}"))
+            .await?
+        else {
+            return Ok(None);
+        };
         let session = serde_json::from_slice(&metadata)?;
         Ok(Some(session))
     }
@@ -432,24 +728,10 @@ impl StorageEngine {
 
         let mut cleaned_bytes = 0u64;
         for session in expired_sessions {
-            let mut session_removed_bytes = 0u64;
-            let mut removed_records = 0u32;
-
-            for record_id in &session.data_records {
-                if let Some(record_path) = self.find_record_path(record_id).await? {
-                    let size = fs::metadata(&record_path).await?.len();
-                    fs::remove_file(&record_path).await?;
-                    session_removed_bytes += size;
-                    removed_records += 1;
-                }
-            }
-
-            let session_path = self.get_session_path(&session.id)?;
-            if session_path.exists() {
-                let session_dir_size = self.get_directory_size(&session_path).await?;
-                fs::remove_dir_all(&session_path).await?;
-                session_removed_bytes += session_dir_size;
-            }
+            // Retention never deletes derived outputs; keep them findable.
+            self.orphan_products(&session.id).await?;
+            let (removed_records, session_removed_bytes) =
+                self.remove_session_files(&session).await?;
 
             self.append_retention_audit(
                 &session,
@@ -464,6 +746,105 @@ impl StorageEngine {
         Ok(cleaned_bytes)
     }
 
+    /// Removes a finished session's records and metadata, handling its
+    /// derived products according to `disposition`.
+    pub async fn delete_session(
+        &self,
+        session: &CollectionSession,
+        disposition: ProductDisposition,
+    ) -> Result<SessionDeletion> {
+        let (removed_products, orphaned_products) = match disposition {
+            ProductDisposition::Cascade => {
+                let products = self.load_products(&session.id).await?;
+                for product in &products {
+                    match tokio::fs::remove_file(&product.file_path).await {
+                        Ok(()) => {}
+                        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(error) => return Err(error.into()),
+                    }
+                }
+                (products.len(), 0)
+            }
+            ProductDisposition::Orphan => (0, self.orphan_products(&session.id).await?),
+        };
+        let (removed_records, _) = self.remove_session_files(session).await?;
+
+        Ok(SessionDeletion {
+            session_id: session.id,
+            removed_records,
+            removed_products,
+            orphaned_products,
+        })
+    }
+
+    /// Derived products registered for a session, in registration order.
+    pub async fn load_products(&self, session_id: &Uuid) -> Result<Vec<DerivedProduct>> {
+        let key = format!(
+            "{}{PRODUCTS_FILE}",
+            self.session_objects_prefix(session_id).await?
+        );
+        match self.storage.load_bytes(&key).await? {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn store_products(
+        &self,
+        session_id: &Uuid,
+        products: &[DerivedProduct],
+    ) -> Result<()> {
+        self.storage
+            .store_bytes(
+                &format!(
+                    "{}{PRODUCTS_FILE}",
+                    self.session_objects_prefix(session_id).await?
+                ),
+                serde_json::to_vec_pretty(products)?,
+            )
+            .await
+    }
+
+    /// Moves a session's product registry under `orphaned_products/` so the
+    /// files stay traceable after the session's objects are removed.
+    async fn orphan_products(&self, session_id: &Uuid) -> Result<usize> {
+        let products = self.load_products(session_id).await?;
+        if products.is_empty() {
+            return Ok(0);
+        }
+
+        self.storage
+            .store_bytes(
+                &format!("orphaned_products/{session_id}.json"),
+                serde_json::to_vec_pretty(&products)?,
+            )
+            .await?;
+        tracing::warn!(
+            %session_id,
+            product_count = products.len(),
+            "session removed; its derived products are now orphaned"
+        );
+        Ok(products.len())
+    }
+
+    async fn remove_session_files(&self, session: &CollectionSession) -> Result<(u32, u64)> {
+        let (mut removed_records, mut removed_bytes) = self
+            .remove_batched_records(&session.data_records.iter().copied().collect())
+            .await?;
+
+        for record_id in &session.data_records {
+            if let Some(object) = self.find_record_object(record_id).await? {
+                self.storage.delete(&object.key).await?;
+                removed_bytes += object.size_bytes;
+                removed_records += 1;
+            }
+        }
+
+        removed_bytes += self.remove_session_objects(session).await?;
+
+        Ok((removed_records, removed_bytes))
+    }
+
     pub async fn get_stats(&self) -> Result<crate::StorageStats> {
         let mut stats = crate::StorageStats {
             total_records: 0,
@@ -474,13 +855,19 @@ impl StorageEngine {
             data_type_breakdown: std::collections::HashMap::new(),
         };
 
-        for record_path in self.record_json_paths()? {
-            let data = fs::read(&record_path).await?;
+        let mut records = Vec::new();
+        for object in self.record_json_objects().await? {
+            let Some(data) = self.storage.load_bytes(&object.key).await? else {
+                continue;
+            };
             let record: DataRecord = serde_json::from_slice(&data)?;
-            let metadata = fs::metadata(&record_path).await?;
+            records.push((record, object.size_bytes));
+        }
+        records.extend(self.load_batched_records().await?);
 
+        for (record, size_bytes) in records {
             stats.total_records += 1;
-            stats.total_size_bytes += metadata.len();
+            stats.total_size_bytes += size_bytes;
             stats.oldest_record = Some(
                 stats
                     .oldest_record
@@ -513,11 +900,16 @@ impl StorageEngine {
     pub async fn load_all_data(&self) -> Result<Vec<crate::FlightDataRecord>> {
         let mut records = Vec::new();
 
-        for record_path in self.record_json_paths()? {
-            let data = fs::read(&record_path).await?;
+        for object in self.record_json_objects().await? {
+            let Some(data) = self.storage.load_bytes(&object.key).await? else {
+                continue;
+            };
             let record = serde_json::from_slice::<DataRecord>(&data)?;
             records.push(crate::verify_record_integrity(&record)?);
         }
+        for (record, _) in self.load_batched_records().await? {
+            records.push(crate::verify_record_integrity(&record)?);
+        }
 
         records.sort_by(|a, b| {
             a.timestamp
@@ -535,11 +927,6 @@ impl StorageEngine {
         removed_records: u32,
         removed_bytes: u64,
     ) -> Result<()> {
-        let audit_path = self.config.base_path.join("audit").join("retention.jsonl");
-        if let Some(parent) = audit_path.parent() {
-            fs::create_dir_all(parent).await?;
-        }
-
         let event = serde_json::json!({
             "event": "retention_cleanup",
             "session_id": session.id,
@@ -549,15 +936,123 @@ impl StorageEngine {
             "removed_records": removed_records,
             "removed_bytes": removed_bytes,
         });
-        let mut file = fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(audit_path)
-            .await?;
-        file.write_all(event.to_string().as_bytes()).await?;
-        file.write_all(b"\n").await?;
-        Ok(())
+        let mut line = event.to_string().into_bytes();
+        line.push(b'\n');
+        self.storage.append_bytes(RETENTION_AUDIT_FILE, line).await
+    }
+}
+
+fn batch_frame(record_count: u32, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(BATCH_HEADER_LEN + payload.len());
+    frame.extend_from_slice(BATCH_MAGIC);
+    frame.extend_from_slice(&record_count.to_le_bytes());
+    frame.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    frame.extend_from_slice(&crate::fnv1a64(payload).to_le_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+fn batch_key(sequence: u64) -> String {
+    format!("{BATCH_PREFIX}{sequence:020}.log")
+}
+
+fn batch_sequence(key: &str) -> Option<u64> {
+    key.strip_prefix(BATCH_PREFIX)?
+        .strip_suffix(".log")?
+        .parse()
+        .ok()
+}
+
+/// Complete batches found at the start of a batch object.
+#[derive(Debug, Default)]
+struct ScannedBatches {
+    entries: Vec<(Uuid, u64, u32)>,
+    batches: usize,
+    committed_len: usize,
+}
+
+/// Indexes the complete batches at the start of `bytes`, stopping at the
+/// first batch that is cut short, fails its checksum or is malformed.
+fn scan_batch_object(bytes: &[u8]) -> ScannedBatches {
+    let mut scanned = ScannedBatches::default();
+    let mut position = 0usize;
+    while let Some(header) = bytes.get(position..position + BATCH_HEADER_LEN) {
+        if &header[..4] != BATCH_MAGIC {
+            break;
+        }
+        let record_count = u32::from_le_bytes(header[4..8].try_into().expect("4 bytes"));
+        let payload_len = u64::from_le_bytes(header[8..16].try_into().expect("8 bytes"));
+        let checksum = u64::from_le_bytes(header[16..24].try_into().expect("8 bytes"));
+        let payload_start = position + BATCH_HEADER_LEN;
+        let Some(payload) = usize::try_from(payload_len)
+            .ok()
+            .and_then(|len| bytes.get(payload_start..payload_start.checked_add(len)?))
+        else {
+            break;
+        };
+        if crate::fnv1a64(payload) != checksum {
+            break;
+        }
+        let Some(entries) = batch_entries(payload, record_count) else {
+            break;
+        };
+
+        scanned.entries.extend(
+            entries
+                .into_iter()
+                .map(|(record_id, offset, len)| (record_id, (payload_start + offset) as u64, len)),
+        );
+        position = payload_start + payload.len();
+        scanned.committed_len = position;
+        scanned.batches += 1;
     }
+    scanned
+}
+
+fn batch_entries(payload: &[u8], record_count: u32) -> Option<Vec<(Uuid, usize, u32)>> {
+    let mut entries = Vec::with_capacity(record_count as usize);
+    let mut position = 0usize;
+    while position < payload.len() {
+        let header = payload.get(position..position + BATCH_ENTRY_HEADER_LEN)?;
+        let record_id = Uuid::from_slice(&header[..16]).ok()?;
+        let len = u32::from_le_bytes(header[16..20].try_into().ok()?);
+        let offset = position + BATCH_ENTRY_HEADER_LEN;
+        payload.get(offset..offset + len as usize)?;
+        entries.push((record_id, offset, len));
+        position = offset + len as usize;
+    }
+    (entries.len() == record_count as usize).then_some(entries)
+}
+
+/// Indexes the batch objects under `base_path`, cutting incomplete batches
+/// off in place and removing objects left holding none.
+fn recover_local_batch_log(base_path: &Path) -> Result<BatchLog> {
+    let mut log = BatchLog::default();
+    let entries = match std::fs::read_dir(base_path.join(BATCH_PREFIX)) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(log),
+        Err(error) => return Err(error.into()),
+    };
+    let mut names = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.ends_with(".log"))
+        .collect::<Vec<_>>();
+    names.sort();
+
+    for name in names {
+        let key = format!("{BATCH_PREFIX}{name}");
+        let path = base_path.join(&key);
+        let bytes = std::fs::read(&path)?;
+        let committed_len = log.index_object(&key, &bytes);
+        if committed_len == 0 {
+            std::fs::remove_file(&path)?;
+        } else if committed_len < bytes.len() {
+            let file = std::fs::OpenOptions::new().write(true).open(&path)?;
+            file.set_len(committed_len as u64)?;
+            file.sync_all()?;
+        }
+    }
+    Ok(log)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -591,6 +1086,7 @@ impl DataType {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
     use shared::schemas::GpsCoords;
     use tempfile::tempdir;
 
@@ -637,13 +1133,13 @@ mod tests {
             drone_id: session.drone_id,
             data_type: DataType::Telemetry,
             timestamp,
-            payload: crate::DataPayload::Telemetry {
+            payload: crate::DataPayload::Telemetry(crate::TelemetryPayload {
                 position: (40.0, -105.0, 30.0),
                 velocity: (1.0, 0.0, 0.0),
                 orientation: (0.0, 0.0, 0.0),
                 battery_level: 0.8,
                 signal_strength: 0.9,
-            },
+            }),
             sensor_id: "telemetry-01".to_string(),
             gps_coords: Some(GpsCoords {
                 latitude: 40.0,
@@ -679,11 +1175,11 @@ mod tests {
             drone_id: Uuid::new_v4(),
             data_type: DataType::Image,
             timestamp: Utc::now(),
-            payload: crate::DataPayload::Raw {
+            payload: crate::DataPayload::Raw(crate::RawPayload {
                 format: "test".to_string(),
                 schema: None,
                 compression: None,
-            },
+            }),
             sensor_id: "camera-rgb-01".to_string(),
             gps_coords: Some(GpsCoords {
                 latitude: 40.0,
@@ -696,9 +1192,9 @@ mod tests {
             size_bytes: 1024,
         };
 
-        let path = engine.get_storage_path(&record).unwrap();
-        assert!(path.to_string_lossy().contains("image"));
-        assert!(path.to_string_lossy().contains(&record.id.to_string()));
+        let key = engine.record_key(&record);
+        assert!(key.contains("image"));
+        assert!(key.contains(&record.id.to_string()));
     }
 
     #[tokio::test]
@@ -735,6 +1231,254 @@ mod tests {
         assert_eq!(stats.newest_record, Some(record.timestamp));
     }
 
+    #[tokio::test]
+    async fn sessions_are_stored_by_drone_and_date() {
+        let temp_dir = tempdir().unwrap();
+        let engine = StorageEngine::new(test_config(temp_dir.path().to_path_buf())).unwrap();
+        let start_time = Utc.with_ymd_and_hms(2026, 6, 14, 9, 30, 0).unwrap();
+        let session = test_session(Uuid::new_v4(), crate::SessionStatus::Ended, start_time);
+
+        let prefix = engine.store_session(&session).await.unwrap();
+
+        assert_eq!(
+            prefix,
+            format!("sessions/{}/2026-06-14/{}/", session.drone_id, session.id)
+        );
+        let session_path = engine.get_session_path(&session);
+        assert_eq!(
+            session_path,
+            temp_dir
+                .path()
+                .join("sessions")
+                .join(session.drone_id.to_string())
+                .join("2026-06-14")
+                .join(session.id.to_string())
+        );
+        assert!(session_path.join(SESSION_FILE).exists());
+        assert_eq!(
+            engine
+                .storage()
+                .load_bytes(&StorageEngine::<LocalStorage>::session_index_key(
+                    &session.id
+                ))
+                .await
+                .unwrap(),
+            Some(prefix.into_bytes())
+        );
+        assert_eq!(
+            engine.load_session(&session.id).await.unwrap().unwrap().id,
+            session.id
+        );
+    }
+
+    #[tokio::test]
+    async fn sessions_in_the_legacy_flat_layout_still_load() {
+        let temp_dir = tempdir().unwrap();
+        let engine = StorageEngine::new(test_config(temp_dir.path().to_path_buf())).unwrap();
+        let mut session = test_session(Uuid::new_v4(), crate::SessionStatus::Ended, Utc::now());
+        let legacy_prefix = format!("sessions/{}/", session.id);
+        engine
+            .storage()
+            .store_bytes(
+                &format!("{legacy_prefix}{SESSION_FILE}"),
+                serde_json::to_vec_pretty(&session).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let loaded = engine.load_session(&session.id).await.unwrap().unwrap();
+        assert_eq!(loaded.id, session.id);
+        assert_eq!(
+            engine
+                .list_sessions(Some(session.drone_id), None)
+                .await
+                .unwrap()[0]
+                .id,
+            session.id
+        );
+
+        session.tags.push("reprocessed".to_string());
+        assert_eq!(engine.store_session(&session).await.unwrap(), legacy_prefix);
+        let reloaded = engine.load_session(&session.id).await.unwrap().unwrap();
+        assert_eq!(reloaded.tags, vec!["reprocessed".to_string()]);
+        assert!(!engine.get_session_path(&session).exists());
+    }
+
+    #[tokio::test]
+    async fn torn_final_batch_is_truncated_when_the_store_reopens() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path().to_path_buf());
+        let engine = StorageEngine::new(config.clone()).unwrap();
+        let session = test_session(Uuid::new_v4(), crate::SessionStatus::Collecting, Utc::now());
+        let batch = |count: usize| {
+            (0..count)
+                .map(|_| test_record(&session, Utc::now()))
+                .collect::<Vec<_>>()
+        };
+
+        let first = batch(3);
+        let second = batch(2);
+        engine.store_batch(&first).await.unwrap();
+        engine.store_batch(&second).await.unwrap();
+        engine.store_batch(&batch(4)).await.unwrap();
+        let batch_paths = (0..3)
+            .map(|sequence| temp_dir.path().join(batch_key(sequence)))
+            .collect::<Vec<_>>();
+        assert!(batch_paths.iter().all(|path| path.exists()));
+        let full_len = std::fs::metadata(&batch_paths[2]).unwrap().len();
+        let torn = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&batch_paths[2])
+            .unwrap();
+        torn.set_len(full_len - 7).unwrap();
+        drop(torn);
+
+        let reopened = StorageEngine::new(config.clone()).unwrap();
+        assert_eq!(
+            reopened.batch_log_summary().await,
+            BatchLogSummary {
+                batches: 2,
+                records: 5,
+                truncated_bytes: full_len - 7,
+            }
+        );
+        assert!(!batch_paths[2].exists());
+        let loaded = reopened.load_all_data().await.unwrap();
+        assert_eq!(loaded.len(), 5);
+        for record in first.iter().chain(&second) {
+            assert!(reopened.load_data(&record.id).await.unwrap().is_some());
+        }
+
+        let after = batch(1);
+        reopened.store_batch(&after).await.unwrap();
+        let reopened_again = StorageEngine::new(config).unwrap();
+        assert_eq!(reopened_again.batch_log_summary().await.records, 6);
+        assert_eq!(
+            reopened_again
+                .load_data(&after[0].id)
+                .await
+                .unwrap()
+                .unwrap()
+                .id,
+            after[0].id
+        );
+    }
+
+    #[tokio::test]
+    async fn removing_records_rewrites_only_the_batches_holding_them() {
+        use crate::storage_backend::memory::MemoryStorage;
+
+        let backend = MemoryStorage::default();
+        let session = test_session(Uuid::new_v4(), crate::SessionStatus::Collecting, Utc::now());
+        let encode = |records: &[DataRecord]| {
+            let mut payload = Vec::new();
+            for record in records {
+                let encoded = serde_json::to_vec(record).unwrap();
+                payload.extend_from_slice(record.id.as_bytes());
+                payload.extend_from_slice(&(encoded.len() as u32).to_le_bytes());
+                payload.extend_from_slice(&encoded);
+            }
+            batch_frame(records.len() as u32, &payload)
+        };
+        let legacy = (0..3)
+            .map(|_| crate::prepare_record_for_storage(&test_record(&session, Utc::now())).unwrap())
+            .collect::<Vec<_>>();
+        let mut legacy_log = encode(&legacy[..2]);
+        legacy_log.extend(encode(&legacy[2..]));
+        legacy_log.extend_from_slice(b"AGB1torn");
+        backend
+            .store_bytes("batches/records.log", legacy_log)
+            .await
+            .unwrap();
+
+        let engine = StorageEngine::open(test_config(PathBuf::from("unused")), backend.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            engine.batch_log_summary().await,
+            BatchLogSummary {
+                batches: 2,
+                records: 3,
+                truncated_bytes: 8,
+            }
+        );
+        let first = vec![test_record(&session, Utc::now())];
+        let second = vec![test_record(&session, Utc::now())];
+        engine.store_batch(&first).await.unwrap();
+        engine.store_batch(&second).await.unwrap();
+        assert_eq!(
+            backend.keys(),
+            vec![
+                batch_key(0),
+                batch_key(1),
+                "batches/records.log".to_string()
+            ]
+        );
+        let untouched = backend.load_bytes(&batch_key(1)).await.unwrap();
+
+        let removed = HashSet::from([legacy[0].id, first[0].id]);
+        let (removed_records, _) = engine.remove_batched_records(&removed).await.unwrap();
+
+        assert_eq!(removed_records, 2);
+        assert_eq!(
+            backend.keys(),
+            vec![batch_key(1), "batches/records.log".to_string()]
+        );
+        assert_eq!(backend.load_bytes(&batch_key(1)).await.unwrap(), untouched);
+        let reopened = StorageEngine::open(test_config(PathBuf::from("unused")), backend)
+            .await
+            .unwrap();
+        assert_eq!(
+            reopened.batch_log_summary().await,
+            BatchLogSummary {
+                batches: 2,
+                records: 3,
+                truncated_bytes: 0,
+            }
+        );
+        for record in legacy[1..].iter().chain(&second) {
+            assert!(reopened.load_data(&record.id).await.unwrap().is_some());
+        }
+        reopened.store_batch(&first).await.unwrap();
+        assert_eq!(reopened.batch_log_summary().await.batches, 3);
+        assert!(reopened.storage().keys().contains(&batch_key(2)));
+    }
+
+    #[tokio::test]
+    async fn staged_writes_left_by_a_crash_are_removed_when_the_store_reopens() {
+        let temp_dir = tempdir().unwrap();
+        let config = test_config(temp_dir.path().to_path_buf());
+        let engine = StorageEngine::new(config.clone()).unwrap();
+        let session = test_session(Uuid::new_v4(), crate::SessionStatus::Collecting, Utc::now());
+        let record = engine
+            .store_data(&test_record(&session, Utc::now()))
+            .await
+            .unwrap();
+        let record_path = engine.storage().path_for(&engine.record_key(&record));
+        let contents = std::fs::read(&record_path).unwrap();
+
+        // A rewrite of the record and a new record, both cut off before
+        // their rename.
+        let mut staged_rewrite = record_path.clone().into_os_string();
+        staged_rewrite.push(format!(".{}.partial", Uuid::new_v4()));
+        std::fs::write(&staged_rewrite, &contents[..contents.len() / 2]).unwrap();
+        let staged_new = record_path.with_file_name(format!(
+            "telemetry_{}.json.{}.partial",
+            Uuid::new_v4(),
+            Uuid::new_v4()
+        ));
+        std::fs::write(&staged_new, b"{\"id\":").unwrap();
+        assert_eq!(engine.load_all_data().await.unwrap().len(), 1);
+
+        let reopened = StorageEngine::new(config).unwrap();
+
+        assert!(!PathBuf::from(&staged_rewrite).exists());
+        assert!(!staged_new.exists());
+        assert_eq!(std::fs::read(&record_path).unwrap(), contents);
+        let loaded = reopened.load_data(&record.id).await.unwrap().unwrap();
+        assert_eq!(loaded.id, record.id);
+    }
+
     #[tokio::test]
     async fn test_cleanup_before_date_removes_old_completed_sessions_and_audits() {
         let temp_dir = tempdir().unwrap();
@@ -777,6 +1521,34 @@ mod tests {
         assert!(audit.contains("\"removed_records\":1"));
     }
 
+    #[tokio::test]
+    async fn cleanup_removes_expired_objects_from_any_backend() {
+        use crate::storage_backend::memory::MemoryStorage;
+
+        let backend = MemoryStorage::default();
+        let engine = StorageEngine::open(test_config(PathBuf::from("unused")), backend.clone())
+            .await
+            .unwrap();
+        let cutoff = Utc::now() - chrono::Duration::days(1);
+        let old_time = cutoff - chrono::Duration::hours(1);
+        let mut old_session = test_session(Uuid::new_v4(), crate::SessionStatus::Ended, old_time);
+        let stored_record = test_record(&old_session, old_time);
+        let batched_record = test_record(&old_session, old_time);
+        old_session
+            .data_records
+            .extend([stored_record.id, batched_record.id]);
+        engine.store_data(&stored_record).await.unwrap();
+        engine.store_batch(&[batched_record]).await.unwrap();
+        engine.store_session(&old_session).await.unwrap();
+        assert_eq!(engine.load_all_data().await.unwrap().len(), 2);
+
+        let removed_bytes = engine.cleanup_before_date(cutoff).await.unwrap();
+
+        assert!(removed_bytes > 0);
+        assert!(engine.load_all_data().await.unwrap().is_empty());
+        assert_eq!(backend.keys(), vec![RETENTION_AUDIT_FILE.to_string()]);
+    }
+
     #[tokio::test]
     async fn test_cleanup_before_date_refuses_active_session() {
         let temp_dir = tempdir().unwrap();
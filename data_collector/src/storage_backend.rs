@@ -0,0 +1,309 @@
+use anyhow::{anyhow, Result};
+use std::future::Future;
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use uuid::Uuid;
+
+/// Suffix of the files [`LocalStorage`] stages writes in. One left on disk
+/// is from a write that never finished.
+const STAGED_SUFFIX: &str = ".partial";
+
+/// One object held by a [`Storage`] backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredObject {
+    pub key: String,
+    pub size_bytes: u64,
+}
+
+/// Byte store behind [`crate::StorageEngine`]. Keys are `/`-separated
+/// paths relative to the store root, such as `sessions/<id>/session.json`.
+pub trait Storage: Send + Sync + 'static {
+    /// Writes `bytes` under `key`, replacing any previous object whole.
+    fn store_bytes(&self, key: &str, bytes: Vec<u8>) -> impl Future<Output = Result<()>> + Send;
+
+    /// The object under `key`, or `None` when there is none.
+    fn load_bytes(&self, key: &str) -> impl Future<Output = Result<Option<Vec<u8>>>> + Send;
+
+    /// Objects whose key starts with `prefix`, in key order.
+    fn list(&self, prefix: &str) -> impl Future<Output = Result<Vec<StoredObject>>> + Send;
+
+    /// Removes the object under `key`; a missing object is not an error.
+    fn delete(&self, key: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// Bytes `range` of the object under `key`. The default loads the whole
+    /// object; backends with ranged reads override it.
+    fn load_range(
+        &self,
+        key: &str,
+        range: Range<u64>,
+    ) -> impl Future<Output = Result<Vec<u8>>> + Send {
+        async move {
+            let bytes = self
+                .load_bytes(key)
+                .await?
+                .ok_or_else(|| anyhow!("object {key} not found"))?;
+            bytes
+                .get(range.start as usize..range.end as usize)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| anyhow!("object {key} is shorter than {range:?}"))
+        }
+    }
+
+    /// Appends `bytes` to the object under `key`, creating it when missing.
+    /// Either all of `bytes` land or the object is left as it was. The
+    /// default rewrites the whole object; backends that can append in place
+    /// override it.
+    fn append_bytes(&self, key: &str, bytes: Vec<u8>) -> impl Future<Output = Result<()>> + Send {
+        async move {
+            let mut object = self.load_bytes(key).await?.unwrap_or_default();
+            object.extend_from_slice(&bytes);
+            self.store_bytes(key, object).await
+        }
+    }
+
+    /// Removes whatever writes interrupted by a crash left behind, returning
+    /// how many leftovers were removed. Backends whose writes cannot be seen
+    /// half done have nothing to remove.
+    fn remove_interrupted_writes(&self) -> impl Future<Output = Result<usize>> + Send {
+        async { Ok(0) }
+    }
+}
+
+/// Stores objects as files under a root directory, one file per key.
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// File holding the object under `key`.
+    pub fn path_for(&self, key: &str) -> PathBuf {
+        key.split('/')
+            .filter(|part| !part.is_empty())
+            .fold(self.root.clone(), |path, part| path.join(part))
+    }
+
+    fn key_for(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let parts = relative
+            .components()
+            .map(|component| component.as_os_str().to_str())
+            .collect::<Option<Vec<_>>>()?;
+        Some(parts.join("/"))
+    }
+
+    /// Deletes staged files that never made it into place, returning how
+    /// many there were.
+    pub fn remove_staged_files(&self) -> Result<usize> {
+        if !self.root.exists() {
+            return Ok(0);
+        }
+        let mut removed = 0;
+        for entry in walkdir::WalkDir::new(&self.root) {
+            let entry = entry?;
+            if entry.file_type().is_file() && is_staged(entry.path()) {
+                std::fs::remove_file(entry.path())?;
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            tracing::warn!(
+                root = %self.root.display(),
+                removed,
+                "removed staged files left by interrupted writes"
+            );
+        }
+        Ok(removed)
+    }
+
+    /// Removes directories emptied by a delete, up to the root.
+    async fn prune_empty_parents(&self, path: &Path) {
+        let mut directory = path.parent();
+        while let Some(current) = directory.filter(|current| *current != self.root) {
+            if fs::remove_dir(current).await.is_err() {
+                break;
+            }
+            directory = current.parent();
+        }
+    }
+}
+
+impl Storage for LocalStorage {
+    /// Writes to a staged file in the same directory and renames it into
+    /// place, so readers see either the old object or the whole new one.
+    async fn store_bytes(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        let path = self.path_for(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut staged_name = path.file_name().unwrap_or_default().to_os_string();
+        staged_name.push(format!(".{}{STAGED_SUFFIX}", Uuid::new_v4()));
+        let staged_path = path.with_file_name(staged_name);
+
+        let staged = async {
+            let mut staged = fs::File::create(&staged_path).await?;
+            staged.write_all(&bytes).await?;
+            staged.sync_all().await?;
+            fs::rename(&staged_path, &path).await
+        }
+        .await;
+        if let Err(error) = staged {
+            let _ = fs::remove_file(&staged_path).await;
+            return Err(error.into());
+        }
+        Ok(())
+    }
+
+    async fn load_bytes(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.path_for(key)).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<StoredObject>> {
+        // Only the directory named by the prefix can hold matching keys.
+        let directory = prefix
+            .rsplit_once('/')
+            .map_or("", |(directory, _)| directory);
+        let walk_root = self.path_for(directory);
+        if !walk_root.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut objects = Vec::new();
+        for entry in walkdir::WalkDir::new(walk_root) {
+            let entry = entry?;
+            if !entry.file_type().is_file() || is_staged(entry.path()) {
+                continue;
+            }
+            let Some(key) = self.key_for(entry.path()) else {
+                continue;
+            };
+            if key.starts_with(prefix) {
+                objects.push(StoredObject {
+                    key,
+                    size_bytes: entry.metadata()?.len(),
+                });
+            }
+        }
+        objects.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(objects)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let path = self.path_for(key);
+        match fs::remove_file(&path).await {
+            Ok(()) => {}
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error.into()),
+        }
+        self.prune_empty_parents(&path).await;
+        Ok(())
+    }
+
+    async fn load_range(&self, key: &str, range: Range<u64>) -> Result<Vec<u8>> {
+        let mut file = fs::File::open(self.path_for(key)).await?;
+        file.seek(SeekFrom::Start(range.start)).await?;
+        let mut bytes = vec![0; (range.end - range.start) as usize];
+        file.read_exact(&mut bytes).await?;
+        Ok(bytes)
+    }
+
+    /// Appends in place with one write and sync, cutting the file back to
+    /// its old length if either fails.
+    async fn append_bytes(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        let path = self.path_for(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await?;
+        let original_len = file.metadata().await?.len();
+        let written = async {
+            file.write_all(&bytes).await?;
+            file.sync_data().await
+        }
+        .await;
+        if let Err(error) = written {
+            let _ = file.set_len(original_len).await;
+            return Err(error.into());
+        }
+        Ok(())
+    }
+
+    async fn remove_interrupted_writes(&self) -> Result<usize> {
+        self.remove_staged_files()
+    }
+}
+
+fn is_staged(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(STAGED_SUFFIX))
+}
+
+#[cfg(test)]
+pub(crate) mod memory {
+    use super::{Storage, StoredObject};
+    use anyhow::Result;
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    /// Keeps objects in a map; clones share the same objects.
+    #[derive(Debug, Clone, Default)]
+    pub(crate) struct MemoryStorage {
+        objects: Arc<Mutex<BTreeMap<String, Vec<u8>>>>,
+    }
+
+    impl MemoryStorage {
+        pub(crate) fn keys(&self) -> Vec<String> {
+            self.objects.lock().unwrap().keys().cloned().collect()
+        }
+    }
+
+    impl Storage for MemoryStorage {
+        async fn store_bytes(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+            self.objects.lock().unwrap().insert(key.to_string(), bytes);
+            Ok(())
+        }
+
+        async fn load_bytes(&self, key: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.objects.lock().unwrap().get(key).cloned())
+        }
+
+        async fn list(&self, prefix: &str) -> Result<Vec<StoredObject>> {
+            Ok(self
+                .objects
+                .lock()
+                .unwrap()
+                .range(prefix.to_string()..)
+                .take_while(|(key, _)| key.starts_with(prefix))
+                .map(|(key, bytes)| StoredObject {
+                    key: key.clone(),
+                    size_bytes: bytes.len() as u64,
+                })
+                .collect())
+        }
+
+        async fn delete(&self, key: &str) -> Result<()> {
+            self.objects.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+}
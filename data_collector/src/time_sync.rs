@@ -0,0 +1,665 @@
+//! Clock synchronisation audit between a session's sensor streams.
+//!
+//! Telemetry is the time reference. Every other stream that measures its
+//! own motion — the along-track shift between consecutive image footprints,
+//! or the shift a LiDAR scan matcher found between consecutive scans — is
+//! compared with the displacement the telemetry track shows between the
+//! same two timestamps. The constant offset that best lines the two up is
+//! the stream's clock error. A stream flown at constant speed looks the same
+//! at every offset, so it is reported as undetermined rather than guessed.
+
+use crate::{DataPayload, DataType, FlightDataRecord, TelemetryPayload};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Forward overlap (0-1) between an image's footprint and the previous
+/// image's from the same sensor.
+pub const FOOTPRINT_OVERLAP_KEY: &str = "footprint_overlap";
+/// Along-track length of an image's ground footprint, in metres.
+pub const FOOTPRINT_LENGTH_KEY: &str = "footprint_length_m";
+/// Horizontal shift a scan matcher found since the previous scan from the
+/// same sensor, in metres.
+pub const SCAN_SHIFT_KEY: &str = "scan_shift_m";
+/// Capture timestamp as tagged by the sensor, kept when a correction
+/// rewrites the record's timestamp.
+pub const ORIGINAL_TIMESTAMP_KEY: &str = "original_timestamp";
+/// Total correction applied to the record's timestamp, in seconds.
+pub const TIME_OFFSET_KEY: &str = "time_offset_seconds";
+
+const METERS_PER_DEGREE_LAT: f64 = 111_320.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeSyncConfig {
+    /// Largest clock error searched for, either way.
+    pub max_offset_seconds: f64,
+    pub coarse_step_seconds: f64,
+    pub fine_step_seconds: f64,
+    /// Displacement pairs that must fall inside the telemetry track at an
+    /// offset for it to be considered.
+    pub min_overlap_samples: usize,
+    /// Standard deviation the telemetry speed must reach over the stream's
+    /// window; below it the track carries no timing information.
+    pub min_speed_variation_mps: f64,
+    /// Estimates below this confidence are reported as undetermined.
+    pub min_confidence: f64,
+}
+
+impl Default for TimeSyncConfig {
+    fn default() -> Self {
+        Self {
+            max_offset_seconds: 10.0,
+            coarse_step_seconds: 0.1,
+            fine_step_seconds: 0.01,
+            min_overlap_samples: 8,
+            min_speed_variation_mps: 0.2,
+            min_confidence: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeSyncReport {
+    pub session_id: Uuid,
+    pub generated_at: DateTime<Utc>,
+    pub telemetry_samples: usize,
+    pub streams: Vec<StreamOffsetEstimate>,
+}
+
+impl TimeSyncReport {
+    pub fn stream(&self, sensor_id: &str) -> Option<&StreamOffsetEstimate> {
+        self.streams
+            .iter()
+            .find(|stream| stream.sensor_id == sensor_id)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamOffsetEstimate {
+    pub sensor_id: String,
+    pub data_type: DataType,
+    /// Records in the stream carrying a motion measurement.
+    pub sample_count: usize,
+    pub estimate: OffsetEstimate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum OffsetEstimate {
+    /// Adding `offset_seconds` to the stream's timestamps aligns it with
+    /// telemetry.
+    Estimated {
+        offset_seconds: f64,
+        /// How much better the fit is at the offset than across the search
+        /// range: 0 is no better, 1 is a perfect fit against a poor range.
+        confidence: f64,
+        residual_rms_m: f64,
+    },
+    Undetermined {
+        reason: UndeterminedReason,
+    },
+}
+
+impl OffsetEstimate {
+    pub fn offset_seconds(&self) -> Option<f64> {
+        match self {
+            Self::Estimated { offset_seconds, .. } => Some(*offset_seconds),
+            Self::Undetermined { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UndeterminedReason {
+    NoTelemetry,
+    InsufficientOverlap,
+    InsufficientMotion,
+    LowConfidence,
+}
+
+/// Estimates a constant clock offset for every non-telemetry stream in
+/// `records` that carries motion measurements, keyed by sensor id.
+pub fn estimate_stream_offsets(
+    session_id: Uuid,
+    records: &[FlightDataRecord],
+    config: &TimeSyncConfig,
+) -> TimeSyncReport {
+    let track = TelemetryTrack::new(records);
+    let mut streams = BTreeMap::<&str, (DataType, Vec<&FlightDataRecord>)>::new();
+    for record in records {
+        if record.data_type != DataType::Telemetry && measured_step_m(record).is_some() {
+            streams
+                .entry(record.sensor_id.as_str())
+                .or_insert_with(|| (record.data_type.clone(), Vec::new()))
+                .1
+                .push(record);
+        }
+    }
+
+    let streams = streams
+        .into_iter()
+        .map(|(sensor_id, (data_type, mut stream))| {
+            stream.sort_by_key(|record| (record.timestamp, record.id));
+            let steps = stream
+                .windows(2)
+                .filter_map(|pair| {
+                    Some(MotionStep {
+                        from: pair[0].timestamp,
+                        to: pair[1].timestamp,
+                        measured_m: measured_step_m(pair[1])?,
+                    })
+                })
+                .collect::<Vec<_>>();
+            StreamOffsetEstimate {
+                sensor_id: sensor_id.to_string(),
+                data_type,
+                sample_count: stream.len(),
+                estimate: estimate_offset(&track, &steps, config),
+            }
+        })
+        .collect();
+
+    TimeSyncReport {
+        session_id,
+        generated_at: Utc::now(),
+        telemetry_samples: track.samples.len(),
+        streams,
+    }
+}
+
+/// Shifts `record`'s timestamp by `offset_seconds`. The sensor's own
+/// timestamp is kept under [`ORIGINAL_TIMESTAMP_KEY`] the first time and the
+/// running total under [`TIME_OFFSET_KEY`], so repeated corrections stack.
+pub fn apply_time_offset(record: &mut FlightDataRecord, offset_seconds: f64) {
+    let previous = record
+        .metadata
+        .get(TIME_OFFSET_KEY)
+        .and_then(|offset| offset.parse::<f64>().ok())
+        .unwrap_or(0.0);
+    record
+        .metadata
+        .entry(ORIGINAL_TIMESTAMP_KEY.to_string())
+        .or_insert_with(|| {
+            record
+                .timestamp
+                .to_rfc3339_opts(SecondsFormat::Millis, true)
+        });
+    record.timestamp += chrono::Duration::milliseconds((offset_seconds * 1000.0).round() as i64);
+    record.metadata.insert(
+        TIME_OFFSET_KEY.to_string(),
+        format!("{:.3}", previous + offset_seconds),
+    );
+}
+
+fn measured_step_m(record: &FlightDataRecord) -> Option<f64> {
+    let metadata_value = |key: &str| {
+        record
+            .metadata
+            .get(key)
+            .and_then(|value| value.parse::<f64>().ok())
+            .filter(|value| value.is_finite())
+    };
+    if let Some(shift) = metadata_value(SCAN_SHIFT_KEY) {
+        return Some(shift.abs());
+    }
+    let overlap = metadata_value(FOOTPRINT_OVERLAP_KEY)?;
+    let length = metadata_value(FOOTPRINT_LENGTH_KEY)?;
+    Some(length * (1.0 - overlap.clamp(0.0, 1.0)))
+}
+
+struct MotionStep {
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    measured_m: f64,
+}
+
+struct TrackSample {
+    seconds: f64,
+    east_m: f64,
+    north_m: f64,
+}
+
+/// Telemetry positions in a local east/north frame, timed in seconds from
+/// the first sample.
+struct TelemetryTrack {
+    origin: Option<DateTime<Utc>>,
+    samples: Vec<TrackSample>,
+}
+
+impl TelemetryTrack {
+    fn new(records: &[FlightDataRecord]) -> Self {
+        let mut fixes = records
+            .iter()
+            .filter(|record| record.data_type == DataType::Telemetry)
+            .filter_map(|record| match record.payload {
+                DataPayload::Telemetry(TelemetryPayload { position, .. }) => {
+                    Some((record.timestamp, position.0, position.1))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        fixes.sort_by_key(|(timestamp, _, _)| *timestamp);
+        fixes.dedup_by_key(|(timestamp, _, _)| *timestamp);
+
+        let Some(&(origin, origin_lat, origin_lon)) = fixes.first() else {
+            return Self {
+                origin: None,
+                samples: Vec::new(),
+            };
+        };
+        let meters_per_degree_lon = METERS_PER_DEGREE_LAT * origin_lat.to_radians().cos();
+        let samples = fixes
+            .into_iter()
+            .map(|(timestamp, latitude, longitude)| TrackSample {
+                seconds: seconds_since(origin, timestamp),
+                east_m: (longitude - origin_lon) * meters_per_degree_lon,
+                north_m: (latitude - origin_lat) * METERS_PER_DEGREE_LAT,
+            })
+            .collect();
+        Self {
+            origin: Some(origin),
+            samples,
+        }
+    }
+
+    fn seconds(&self, timestamp: DateTime<Utc>) -> f64 {
+        self.origin
+            .map_or(0.0, |origin| seconds_since(origin, timestamp))
+    }
+
+    fn position_at(&self, seconds: f64) -> Option<(f64, f64)> {
+        let first = self.samples.first()?;
+        let last = self.samples.last()?;
+        if seconds < first.seconds || seconds > last.seconds {
+            return None;
+        }
+        let after = self
+            .samples
+            .partition_point(|sample| sample.seconds < seconds)
+            .min(self.samples.len() - 1);
+        let end = &self.samples[after];
+        let start = &self.samples[after.saturating_sub(1)];
+        let span = end.seconds - start.seconds;
+        let fraction = if span > 0.0 {
+            (seconds - start.seconds) / span
+        } else {
+            0.0
+        };
+        Some((
+            start.east_m + (end.east_m - start.east_m) * fraction,
+            start.north_m + (end.north_m - start.north_m) * fraction,
+        ))
+    }
+
+    fn displacement_m(&self, from: f64, to: f64) -> Option<f64> {
+        let start = self.position_at(from)?;
+        let end = self.position_at(to)?;
+        Some((end.0 - start.0).hypot(end.1 - start.1))
+    }
+
+    /// Standard deviation of the sample-to-sample speed within a window.
+    fn speed_variation_mps(&self, from: f64, to: f64) -> f64 {
+        let speeds = self
+            .samples
+            .windows(2)
+            .filter(|pair| pair[0].seconds >= from && pair[1].seconds <= to)
+            .filter_map(|pair| {
+                let dt = pair[1].seconds - pair[0].seconds;
+                (dt > 0.0).then(|| {
+                    (pair[1].east_m - pair[0].east_m).hypot(pair[1].north_m - pair[0].north_m) / dt
+                })
+            })
+            .collect::<Vec<_>>();
+        if speeds.len() < 2 {
+            return 0.0;
+        }
+        let mean = speeds.iter().sum::<f64>() / speeds.len() as f64;
+        (speeds
+            .iter()
+            .map(|speed| (speed - mean).powi(2))
+            .sum::<f64>()
+            / speeds.len() as f64)
+            .sqrt()
+    }
+}
+
+fn estimate_offset(
+    track: &TelemetryTrack,
+    steps: &[MotionStep],
+    config: &TimeSyncConfig,
+) -> OffsetEstimate {
+    let undetermined = |reason| OffsetEstimate::Undetermined { reason };
+    if track.samples.len() < 2 {
+        return undetermined(UndeterminedReason::NoTelemetry);
+    }
+    let steps = steps
+        .iter()
+        .map(|step| {
+            (
+                track.seconds(step.from),
+                track.seconds(step.to),
+                step.measured_m,
+            )
+        })
+        .collect::<Vec<_>>();
+    if steps.len() < config.min_overlap_samples {
+        return undetermined(UndeterminedReason::InsufficientOverlap);
+    }
+
+    // Mean squared residual between measured and telemetry displacement,
+    // or None when too few steps land inside the track at this offset.
+    let cost = |offset: f64| {
+        let residuals = steps
+            .iter()
+            .filter_map(|(from, to, measured)| {
+                let predicted = track.displacement_m(from + offset, to + offset)?;
+                Some((measured - predicted).powi(2))
+            })
+            .collect::<Vec<_>>();
+        (residuals.len() >= config.min_overlap_samples)
+            .then(|| residuals.iter().sum::<f64>() / residuals.len() as f64)
+    };
+    let candidates = |from: f64, to: f64, step: f64| {
+        let count = ((to - from) / step).round() as i64;
+        (0..=count)
+            .map(move |index| from + index as f64 * step)
+            .filter_map(|offset| cost(offset).map(|cost| (offset, cost)))
+            .collect::<Vec<_>>()
+    };
+
+    let max_offset = config.max_offset_seconds.abs();
+    let coarse = candidates(-max_offset, max_offset, config.coarse_step_seconds);
+    let Some(&(coarse_best, _)) = coarse
+        .iter()
+        .min_by(|left, right| left.1.total_cmp(&right.1))
+    else {
+        return undetermined(UndeterminedReason::InsufficientOverlap);
+    };
+
+    let window = (
+        steps.first().map_or(0.0, |step| step.0) + coarse_best,
+        steps.last().map_or(0.0, |step| step.1) + coarse_best,
+    );
+    if track.speed_variation_mps(window.0, window.1) < config.min_speed_variation_mps {
+        return undetermined(UndeterminedReason::InsufficientMotion);
+    }
+
+    let fine = candidates(
+        coarse_best - config.coarse_step_seconds,
+        coarse_best + config.coarse_step_seconds,
+        config.fine_step_seconds,
+    );
+    let Some(&(offset_seconds, best_cost)) =
+        fine.iter().min_by(|left, right| left.1.total_cmp(&right.1))
+    else {
+        return undetermined(UndeterminedReason::InsufficientOverlap);
+    };
+
+    let mut costs = coarse.iter().map(|(_, cost)| *cost).collect::<Vec<_>>();
+    costs.sort_by(f64::total_cmp);
+    let median_cost = costs[costs.len() / 2];
+    let confidence = if median_cost > 0.0 {
+        (1.0 - best_cost / median_cost).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    if confidence < config.min_confidence {
+        return undetermined(UndeterminedReason::LowConfidence);
+    }
+
+    OffsetEstimate::Estimated {
+        offset_seconds: (offset_seconds * 1000.0).round() / 1000.0,
+        confidence,
+        residual_rms_m: best_cost.sqrt(),
+    }
+}
+
+fn seconds_since(origin: DateTime<Utc>, timestamp: DateTime<Utc>) -> f64 {
+    timestamp.signed_duration_since(origin).num_milliseconds() as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CaptureSessionRequest, FlightDataProvenance, FlightSession, MediaFilePayload,
+        PointCloudPayload,
+    };
+    use chrono::TimeZone;
+    use shared::schemas::GpsCoords;
+
+    const LATITUDE: f64 = 40.0;
+    const FOOTPRINT_LENGTH_M: f64 = 60.0;
+
+    fn session() -> FlightSession {
+        FlightSession::new(CaptureSessionRequest::new(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            "grower-ops".to_string(),
+        ))
+    }
+
+    fn at(seconds: f64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_800_000_000, 0).unwrap()
+            + chrono::Duration::milliseconds((seconds * 1000.0).round() as i64)
+    }
+
+    /// Distance flown east by `seconds`, at a speed swinging between 2 and
+    /// 8 m/s over a 20 s period.
+    fn surging_east_m(seconds: f64) -> f64 {
+        let period = 20.0;
+        5.0 * seconds
+            - 3.0 * period / std::f64::consts::TAU
+                * (std::f64::consts::TAU * seconds / period).cos()
+    }
+
+    fn steady_east_m(seconds: f64) -> f64 {
+        5.0 * seconds
+    }
+
+    fn longitude(east_m: f64) -> f64 {
+        -105.0 + east_m / (METERS_PER_DEGREE_LAT * LATITUDE.to_radians().cos())
+    }
+
+    fn record(
+        session: &FlightSession,
+        sensor_id: &str,
+        data_type: DataType,
+        payload: DataPayload,
+        seconds: f64,
+        east_m: f64,
+    ) -> FlightDataRecord {
+        FlightDataRecord::new(
+            session.flight_id,
+            session.drone_id,
+            data_type,
+            payload,
+            FlightDataProvenance::complete(
+                session.id,
+                sensor_id.to_string(),
+                GpsCoords {
+                    latitude: LATITUDE,
+                    longitude: longitude(east_m),
+                    altitude: 30.0,
+                },
+                at(seconds),
+                "calibration-v1".to_string(),
+            ),
+            256,
+        )
+        .unwrap()
+    }
+
+    fn telemetry(session: &FlightSession, track: fn(f64) -> f64) -> Vec<FlightDataRecord> {
+        (0..=1200)
+            .map(|tick| {
+                let seconds = f64::from(tick) / 10.0;
+                record(
+                    session,
+                    "autopilot",
+                    DataType::Telemetry,
+                    DataPayload::Telemetry(TelemetryPayload {
+                        position: (LATITUDE, longitude(track(seconds)), 30.0),
+                        velocity: (5.0, 0.0, 0.0),
+                        orientation: (0.0, 0.0, 0.0),
+                        battery_level: 0.9,
+                        signal_strength: 0.95,
+                    }),
+                    seconds,
+                    track(seconds),
+                )
+            })
+            .collect()
+    }
+
+    /// Thermal frames captured every second between `from` and `to` but
+    /// tagged `clock_error` seconds late, each carrying the footprint
+    /// overlap with the previous frame.
+    fn thermal_frames(
+        session: &FlightSession,
+        track: fn(f64) -> f64,
+        from: u32,
+        to: u32,
+        clock_error: f64,
+    ) -> Vec<FlightDataRecord> {
+        (from..=to)
+            .map(|second| {
+                let seconds = f64::from(second);
+                let shift = track(seconds) - track(seconds - 1.0);
+                let mut frame = record(
+                    session,
+                    "thermal-01",
+                    DataType::ThermalImage,
+                    DataPayload::MediaFile(MediaFilePayload {
+                        file_type: "image/tiff".to_string(),
+                        dimensions: Some((640, 512)),
+                        duration_seconds: None,
+                        compression: None,
+                    }),
+                    seconds + clock_error,
+                    track(seconds),
+                );
+                frame.metadata.insert(
+                    FOOTPRINT_OVERLAP_KEY.to_string(),
+                    (1.0 - shift / FOOTPRINT_LENGTH_M).to_string(),
+                );
+                frame.metadata.insert(
+                    FOOTPRINT_LENGTH_KEY.to_string(),
+                    FOOTPRINT_LENGTH_M.to_string(),
+                );
+                frame
+            })
+            .collect()
+    }
+
+    fn lidar_scans(session: &FlightSession, from: u32, to: u32) -> Vec<FlightDataRecord> {
+        (from..=to)
+            .step_by(2)
+            .map(|second| {
+                let seconds = f64::from(second);
+                let mut scan = record(
+                    session,
+                    "lidar-01",
+                    DataType::LidarScan,
+                    DataPayload::PointCloud(PointCloudPayload {
+                        point_count: 4_000,
+                        bounds: ((0.0, 0.0, 0.0), (10.0, 10.0, 2.0)),
+                        format: "rplidar".to_string(),
+                        has_color: false,
+                        has_intensity: true,
+                    }),
+                    seconds,
+                    surging_east_m(seconds),
+                );
+                scan.metadata.insert(
+                    SCAN_SHIFT_KEY.to_string(),
+                    (surging_east_m(seconds) - surging_east_m(seconds - 2.0)).to_string(),
+                );
+                scan
+            })
+            .collect()
+    }
+
+    #[test]
+    fn injected_clock_offset_is_recovered_per_stream() {
+        let session = session();
+        let mut records = telemetry(&session, surging_east_m);
+        records.extend(thermal_frames(&session, surging_east_m, 10, 100, 2.5));
+        records.extend(lidar_scans(&session, 10, 100));
+
+        let report = estimate_stream_offsets(session.id, &records, &TimeSyncConfig::default());
+
+        assert_eq!(report.telemetry_samples, 1201);
+        let thermal = report.stream("thermal-01").unwrap();
+        assert_eq!(thermal.data_type, DataType::ThermalImage);
+        assert_eq!(thermal.sample_count, 91);
+        let OffsetEstimate::Estimated {
+            offset_seconds,
+            confidence,
+            ..
+        } = thermal.estimate
+        else {
+            panic!("thermal offset should be estimated: {:?}", thermal.estimate);
+        };
+        assert!((offset_seconds + 2.5).abs() <= 0.2, "{offset_seconds}");
+        assert!(confidence > 0.9, "{confidence}");
+
+        let lidar = report.stream("lidar-01").unwrap().estimate.offset_seconds();
+        assert!(lidar.unwrap().abs() <= 0.2, "{lidar:?}");
+    }
+
+    #[test]
+    fn constant_speed_or_short_streams_are_undetermined() {
+        let session = session();
+        let mut records = telemetry(&session, steady_east_m);
+        records.extend(thermal_frames(&session, steady_east_m, 10, 100, 2.5));
+
+        let report = estimate_stream_offsets(session.id, &records, &TimeSyncConfig::default());
+        assert_eq!(
+            report.stream("thermal-01").unwrap().estimate,
+            OffsetEstimate::Undetermined {
+                reason: UndeterminedReason::InsufficientMotion
+            }
+        );
+
+        let mut records = telemetry(&session, surging_east_m);
+        records.extend(thermal_frames(&session, surging_east_m, 10, 14, 2.5));
+        let report = estimate_stream_offsets(session.id, &records, &TimeSyncConfig::default());
+        assert_eq!(
+            report.stream("thermal-01").unwrap().estimate,
+            OffsetEstimate::Undetermined {
+                reason: UndeterminedReason::InsufficientOverlap
+            }
+        );
+
+        let records = thermal_frames(&session, surging_east_m, 10, 100, 2.5);
+        let report = estimate_stream_offsets(session.id, &records, &TimeSyncConfig::default());
+        assert_eq!(
+            report.stream("thermal-01").unwrap().estimate,
+            OffsetEstimate::Undetermined {
+                reason: UndeterminedReason::NoTelemetry
+            }
+        );
+    }
+
+    #[test]
+    fn applied_offsets_keep_the_sensor_timestamp_and_stack() {
+        let session = session();
+        let mut frame = thermal_frames(&session, surging_east_m, 10, 10, 2.5).remove(0);
+
+        apply_time_offset(&mut frame, -2.0);
+        apply_time_offset(&mut frame, -0.5);
+
+        assert_eq!(frame.timestamp, at(10.0));
+        assert_eq!(
+            frame.metadata[ORIGINAL_TIMESTAMP_KEY],
+            "2027-01-15T08:00:12.500Z"
+        );
+        assert_eq!(frame.metadata[TIME_OFFSET_KEY], "-2.500");
+    }
+}
@@ -0,0 +1,710 @@
+use crate::{
+    DataPayload, DataType, FlightDataProvenance, FlightDataRecord, MediaFilePayload, RawPayload,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared::schemas::GpsCoords;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+const DEFAULT_CHUNK_SIZE_BYTES: u64 = 8 * 1024 * 1024;
+const DEFAULT_MAX_FILE_SIZE_BYTES: u64 = 64 * 1024 * 1024 * 1024;
+const DEFAULT_UPLOAD_TTL_HOURS: i64 = 24;
+const MANIFEST_FILE: &str = "upload.json";
+const PARTIAL_DIR: &str = "partial";
+const COMPLETE_DIR: &str = "complete";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UploadConfig {
+    pub chunk_size_bytes: u64,
+    /// Largest file an upload may declare.
+    pub max_file_size_bytes: u64,
+    /// Partial uploads with no chunk activity for this long are discarded.
+    pub ttl: Duration,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size_bytes: DEFAULT_CHUNK_SIZE_BYTES,
+            max_file_size_bytes: DEFAULT_MAX_FILE_SIZE_BYTES,
+            ttl: Duration::hours(DEFAULT_UPLOAD_TTL_HOURS),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UploadError {
+    #[error("upload {upload_id} not found")]
+    NotFound { upload_id: Uuid },
+    #[error("invalid upload request: {reason}")]
+    InvalidRequest { reason: String },
+    #[error("file of {size_bytes} bytes exceeds the {max_bytes} byte upload limit")]
+    TooLarge { size_bytes: u64, max_bytes: u64 },
+    #[error("cannot infer a data type for {file_name} (mime type {mime_type:?})")]
+    UnsupportedFileType {
+        file_name: String,
+        mime_type: Option<String>,
+    },
+    #[error("chunk {index} is out of range; upload has {chunk_count} chunks")]
+    ChunkOutOfRange { index: u32, chunk_count: u32 },
+    #[error("chunk {index} is {actual} bytes; expected {expected}")]
+    ChunkSizeMismatch {
+        index: u32,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("chunk {index} content hash does not match the declared sha256")]
+    ChunkHashMismatch { index: u32 },
+    #[error("upload is missing {} chunk(s): {missing:?}", missing.len())]
+    Incomplete { missing: Vec<u32> },
+    #[error("assembled file sha256 {actual} does not match declared {expected}")]
+    FileHashMismatch { expected: String, actual: String },
+    #[error("upload storage error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("upload manifest error: {0}")]
+    Manifest(#[from] serde_json::Error),
+}
+
+/// Declared properties of a file about to be uploaded. Provenance fields
+/// are required up front so the assembled file can become a record without
+/// a second round trip.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadInitRequest {
+    pub file_name: String,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    pub size_bytes: u64,
+    pub sha256: String,
+    pub sensor_id: String,
+    pub gps_coords: GpsCoords,
+    pub captured_at: DateTime<Utc>,
+    pub calibration_ref: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadTicket {
+    pub upload_id: Uuid,
+    pub session_id: Uuid,
+    pub data_type: DataType,
+    pub chunk_size_bytes: u64,
+    pub chunk_count: u32,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadStatus {
+    pub upload_id: Uuid,
+    pub session_id: Uuid,
+    pub file_name: String,
+    pub size_bytes: u64,
+    pub chunk_size_bytes: u64,
+    pub chunk_count: u32,
+    /// One character per chunk, `1` when received, so clients can resume.
+    pub received_bitmap: String,
+    pub missing_chunks: Vec<u32>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl UploadStatus {
+    pub fn is_complete(&self) -> bool {
+        self.missing_chunks.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingUpload {
+    upload_id: Uuid,
+    session_id: Uuid,
+    request: UploadInitRequest,
+    data_type: DataType,
+    payload: DataPayload,
+    chunk_size_bytes: u64,
+    chunk_count: u32,
+    /// Indices of the chunks stored so far.
+    received: BTreeSet<u32>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl PendingUpload {
+    fn expected_chunk_len(&self, index: u32) -> u64 {
+        let offset = u64::from(index) * self.chunk_size_bytes;
+        (self.request.size_bytes - offset).min(self.chunk_size_bytes)
+    }
+
+    fn missing_chunks(&self) -> Vec<u32> {
+        (0..self.chunk_count)
+            .filter(|index| !self.received.contains(index))
+            .collect()
+    }
+
+    fn status(&self, ttl: Duration) -> UploadStatus {
+        UploadStatus {
+            upload_id: self.upload_id,
+            session_id: self.session_id,
+            file_name: self.request.file_name.clone(),
+            size_bytes: self.request.size_bytes,
+            chunk_size_bytes: self.chunk_size_bytes,
+            chunk_count: self.chunk_count,
+            received_bitmap: (0..self.chunk_count)
+                .map(|index| {
+                    if self.received.contains(&index) {
+                        '1'
+                    } else {
+                        '0'
+                    }
+                })
+                .collect(),
+            missing_chunks: self.missing_chunks(),
+            expires_at: self.updated_at + ttl,
+        }
+    }
+}
+
+/// A fully received, hash-verified file ready to be registered as a record.
+#[derive(Debug, Clone)]
+pub struct AssembledUpload {
+    pub upload_id: Uuid,
+    pub session_id: Uuid,
+    pub file_path: PathBuf,
+    pub data_type: DataType,
+    request: UploadInitRequest,
+    payload: DataPayload,
+}
+
+impl AssembledUpload {
+    pub fn into_record(
+        self,
+        flight_id: Uuid,
+        drone_id: Uuid,
+    ) -> Result<FlightDataRecord, crate::FlightDataProvenanceError> {
+        let mut record = FlightDataRecord::new(
+            flight_id,
+            drone_id,
+            self.data_type,
+            self.payload,
+            FlightDataProvenance::complete(
+                self.session_id,
+                self.request.sensor_id,
+                self.request.gps_coords,
+                self.request.captured_at,
+                self.request.calibration_ref,
+            ),
+            self.request.size_bytes,
+        )?;
+        record.file_path = Some(self.file_path);
+        record
+            .metadata
+            .insert("source_file_name".to_string(), self.request.file_name);
+        record
+            .metadata
+            .insert("sha256".to_string(), self.request.sha256);
+        record
+            .metadata
+            .insert("upload_id".to_string(), self.upload_id.to_string());
+        if let Some(mime_type) = self.request.mime_type {
+            record.metadata.insert("mime_type".to_string(), mime_type);
+        }
+        Ok(record)
+    }
+}
+
+/// Resumable chunked uploads staged on disk under `root`. Each partial
+/// upload keeps its manifest next to its chunks so uploads survive a
+/// collector restart.
+pub struct UploadManager {
+    root: PathBuf,
+    config: UploadConfig,
+    uploads: Mutex<HashMap<Uuid, PendingUpload>>,
+}
+
+impl UploadManager {
+    pub fn open(root: impl Into<PathBuf>, config: UploadConfig) -> Result<Self, UploadError> {
+        if config.chunk_size_bytes == 0 || config.max_file_size_bytes == 0 {
+            return Err(UploadError::InvalidRequest {
+                reason: "chunk size and maximum file size must be positive".to_string(),
+            });
+        }
+        let root = root.into();
+        let partial_dir = root.join(PARTIAL_DIR);
+        std::fs::create_dir_all(&partial_dir)?;
+        std::fs::create_dir_all(root.join(COMPLETE_DIR))?;
+
+        let mut uploads = HashMap::new();
+        for entry in std::fs::read_dir(&partial_dir)? {
+            let manifest_path = entry?.path().join(MANIFEST_FILE);
+            if !manifest_path.is_file() {
+                continue;
+            }
+            let upload: PendingUpload = serde_json::from_slice(&std::fs::read(&manifest_path)?)?;
+            uploads.insert(upload.upload_id, upload);
+        }
+
+        Ok(Self {
+            root,
+            config,
+            uploads: Mutex::new(uploads),
+        })
+    }
+
+    pub fn config(&self) -> UploadConfig {
+        self.config
+    }
+
+    pub async fn initiate(
+        &self,
+        session_id: Uuid,
+        request: UploadInitRequest,
+        now: DateTime<Utc>,
+    ) -> Result<UploadTicket, UploadError> {
+        let file_name = sanitized_file_name(&request.file_name)?;
+        if request.size_bytes == 0 {
+            return Err(UploadError::InvalidRequest {
+                reason: "size_bytes must be positive".to_string(),
+            });
+        }
+        if request.size_bytes > self.config.max_file_size_bytes {
+            return Err(UploadError::TooLarge {
+                size_bytes: request.size_bytes,
+                max_bytes: self.config.max_file_size_bytes,
+            });
+        }
+        if !is_sha256_hex(&request.sha256) {
+            return Err(UploadError::InvalidRequest {
+                reason: "sha256 must be 64 hex characters".to_string(),
+            });
+        }
+        let (data_type, payload) = infer_data_type(&file_name, request.mime_type.as_deref())
+            .ok_or_else(|| UploadError::UnsupportedFileType {
+                file_name: file_name.clone(),
+                mime_type: request.mime_type.clone(),
+            })?;
+        let chunk_count = u32::try_from(request.size_bytes.div_ceil(self.config.chunk_size_bytes))
+            .map_err(|_| UploadError::InvalidRequest {
+                reason: "file needs more chunks than an upload can track".to_string(),
+            })?;
+
+        let upload = PendingUpload {
+            upload_id: Uuid::new_v4(),
+            session_id,
+            request: UploadInitRequest {
+                file_name,
+                sha256: request.sha256.to_ascii_lowercase(),
+                ..request
+            },
+            data_type: data_type.clone(),
+            payload,
+            chunk_size_bytes: self.config.chunk_size_bytes,
+            chunk_count,
+            received: BTreeSet::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        fs::create_dir_all(self.upload_dir(upload.upload_id)).await?;
+        self.write_manifest(&upload).await?;
+
+        let ticket = UploadTicket {
+            upload_id: upload.upload_id,
+            session_id,
+            data_type,
+            chunk_size_bytes: upload.chunk_size_bytes,
+            chunk_count,
+            expires_at: now + self.config.ttl,
+        };
+        self.uploads.lock().await.insert(upload.upload_id, upload);
+        Ok(ticket)
+    }
+
+    /// Stores one chunk after checking its length and hash. Re-sending a
+    /// chunk that was already received simply replaces it.
+    pub async fn receive_chunk(
+        &self,
+        upload_id: Uuid,
+        index: u32,
+        declared_sha256: &str,
+        bytes: &[u8],
+        now: DateTime<Utc>,
+    ) -> Result<UploadStatus, UploadError> {
+        let mut uploads = self.uploads.lock().await;
+        let upload = uploads
+            .get_mut(&upload_id)
+            .ok_or(UploadError::NotFound { upload_id })?;
+        if index >= upload.chunk_count {
+            return Err(UploadError::ChunkOutOfRange {
+                index,
+                chunk_count: upload.chunk_count,
+            });
+        }
+        let expected = upload.expected_chunk_len(index);
+        if bytes.len() as u64 != expected {
+            return Err(UploadError::ChunkSizeMismatch {
+                index,
+                expected,
+                actual: bytes.len() as u64,
+            });
+        }
+        if !sha256_hex(bytes).eq_ignore_ascii_case(declared_sha256.trim()) {
+            return Err(UploadError::ChunkHashMismatch { index });
+        }
+
+        let chunk_path = self.chunk_path(upload_id, index);
+        let staging_path = chunk_path.with_extension("part");
+        fs::write(&staging_path, bytes).await?;
+        fs::rename(&staging_path, &chunk_path).await?;
+
+        upload.received.insert(index);
+        upload.updated_at = now;
+        let upload = upload.clone();
+        drop(uploads);
+        self.write_manifest(&upload).await?;
+        Ok(upload.status(self.config.ttl))
+    }
+
+    pub async fn status(&self, upload_id: Uuid) -> Result<UploadStatus, UploadError> {
+        self.uploads
+            .lock()
+            .await
+            .get(&upload_id)
+            .map(|upload| upload.status(self.config.ttl))
+            .ok_or(UploadError::NotFound { upload_id })
+    }
+
+    /// Concatenates the chunks into the final file and verifies the declared
+    /// whole-file hash. The partial upload is kept on a hash mismatch so the
+    /// client can re-send the offending chunks.
+    pub async fn assemble(&self, upload_id: Uuid) -> Result<AssembledUpload, UploadError> {
+        let upload = self
+            .uploads
+            .lock()
+            .await
+            .get(&upload_id)
+            .cloned()
+            .ok_or(UploadError::NotFound { upload_id })?;
+        let missing = upload.missing_chunks();
+        if !missing.is_empty() {
+            return Err(UploadError::Incomplete { missing });
+        }
+
+        let session_dir = self
+            .root
+            .join(COMPLETE_DIR)
+            .join(upload.session_id.to_string());
+        fs::create_dir_all(&session_dir).await?;
+        let file_path = session_dir.join(format!("{upload_id}-{}", upload.request.file_name));
+        let staging_path = file_path.with_extension("assembling");
+
+        let mut hasher = Sha256::new();
+        let mut output = fs::File::create(&staging_path).await?;
+        for index in 0..upload.chunk_count {
+            let bytes = fs::read(self.chunk_path(upload_id, index)).await?;
+            hasher.update(&bytes);
+            output.write_all(&bytes).await?;
+        }
+        output.flush().await?;
+        drop(output);
+
+        let actual = lowercase_hex(&hasher.finalize());
+        if actual != upload.request.sha256 {
+            fs::remove_file(&staging_path).await?;
+            return Err(UploadError::FileHashMismatch {
+                expected: upload.request.sha256,
+                actual,
+            });
+        }
+        fs::rename(&staging_path, &file_path).await?;
+
+        Ok(AssembledUpload {
+            upload_id,
+            session_id: upload.session_id,
+            file_path,
+            data_type: upload.data_type,
+            request: upload.request,
+            payload: upload.payload,
+        })
+    }
+
+    /// Drops a partial upload and its chunks; called once the assembled file
+    /// has been registered.
+    pub async fn discard(&self, upload_id: Uuid) -> Result<(), UploadError> {
+        self.uploads.lock().await.remove(&upload_id);
+        match fs::remove_dir_all(self.upload_dir(upload_id)).await {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Removes partial uploads idle for longer than the configured TTL.
+    pub async fn collect_expired(&self, now: DateTime<Utc>) -> Result<Vec<Uuid>, UploadError> {
+        let expired = self
+            .uploads
+            .lock()
+            .await
+            .values()
+            .filter(|upload| upload.updated_at + self.config.ttl <= now)
+            .map(|upload| upload.upload_id)
+            .collect::<Vec<_>>();
+        for upload_id in &expired {
+            self.discard(*upload_id).await?;
+            tracing::info!(%upload_id, "discarded expired partial upload");
+        }
+        Ok(expired)
+    }
+
+    pub fn spawn_garbage_collector(
+        self: Arc<Self>,
+        interval: std::time::Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(error) = self.collect_expired(Utc::now()).await {
+                    tracing::warn!(%error, "partial upload garbage collection failed");
+                }
+            }
+        })
+    }
+
+    fn upload_dir(&self, upload_id: Uuid) -> PathBuf {
+        self.root.join(PARTIAL_DIR).join(upload_id.to_string())
+    }
+
+    fn chunk_path(&self, upload_id: Uuid, index: u32) -> PathBuf {
+        self.upload_dir(upload_id).join(format!("chunk-{index:08}"))
+    }
+
+    async fn write_manifest(&self, upload: &PendingUpload) -> Result<(), UploadError> {
+        let manifest_path = self.upload_dir(upload.upload_id).join(MANIFEST_FILE);
+        let staging_path = manifest_path.with_extension("json.tmp");
+        fs::write(&staging_path, serde_json::to_vec_pretty(upload)?).await?;
+        fs::rename(&staging_path, &manifest_path).await?;
+        Ok(())
+    }
+}
+
+/// Maps a declared MIME type, falling back to the file extension, onto the
+/// record type and payload descriptor used for the assembled file.
+pub fn infer_data_type(
+    file_name: &str,
+    mime_type: Option<&str>,
+) -> Option<(DataType, DataPayload)> {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    let mime_type = mime_type.map(str::to_ascii_lowercase).unwrap_or_default();
+    let media = |file_type: &str| {
+        DataPayload::MediaFile(MediaFilePayload {
+            file_type: file_type.to_string(),
+            dimensions: None,
+            duration_seconds: None,
+            compression: None,
+        })
+    };
+    let raw = |format: &str, compression: Option<&str>| {
+        DataPayload::Raw(RawPayload {
+            format: format.to_string(),
+            schema: None,
+            compression: compression.map(str::to_string),
+        })
+    };
+
+    match mime_type.as_str() {
+        "application/vnd.las" => return Some((DataType::LidarScan, raw("las", None))),
+        "application/vnd.laszip" => {
+            return Some((DataType::LidarScan, raw("laz", Some("laszip"))));
+        }
+        mime if mime.starts_with("video/") => {
+            let subtype = mime.trim_start_matches("video/");
+            return Some((DataType::Video, media(subtype)));
+        }
+        mime if mime.starts_with("image/") => {
+            let subtype = mime.trim_start_matches("image/");
+            return Some((DataType::Image, media(subtype)));
+        }
+        _ => {}
+    }
+
+    match extension.as_str() {
+        "mp4" | "mov" | "mkv" | "avi" => Some((DataType::Video, media(&extension))),
+        "jpg" | "jpeg" | "png" | "tif" | "tiff" | "dng" => {
+            Some((DataType::Image, media(&extension)))
+        }
+        "las" | "ply" | "pcd" | "e57" => Some((DataType::LidarScan, raw(&extension, None))),
+        "laz" => Some((DataType::LidarScan, raw("laz", Some("laszip")))),
+        "ulg" | "tlog" | "bin" => Some((DataType::FlightLog, raw(&extension, None))),
+        _ => None,
+    }
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    lowercase_hex(&Sha256::digest(bytes))
+}
+
+pub(crate) fn lowercase_hex(bytes: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut value = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        value.push(HEX[(byte >> 4) as usize] as char);
+        value.push(HEX[(byte & 0x0f) as usize] as char);
+    }
+    value
+}
+
+fn is_sha256_hex(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|character| character.is_ascii_hexdigit())
+}
+
+fn sanitized_file_name(file_name: &str) -> Result<String, UploadError> {
+    Path::new(file_name)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| !name.trim().is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| UploadError::InvalidRequest {
+            reason: format!("file name {file_name:?} is not usable"),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn init_request(contents: &[u8], file_name: &str) -> UploadInitRequest {
+        UploadInitRequest {
+            file_name: file_name.to_string(),
+            mime_type: None,
+            size_bytes: contents.len() as u64,
+            sha256: sha256_hex(contents),
+            sensor_id: "lidar-01".to_string(),
+            gps_coords: GpsCoords {
+                latitude: 40.0,
+                longitude: -105.0,
+                altitude: 30.0,
+            },
+            captured_at: Utc::now(),
+            calibration_ref: "lidar-calibration-v1".to_string(),
+        }
+    }
+
+    #[test]
+    fn data_type_is_inferred_from_mime_then_extension() {
+        assert!(matches!(
+            infer_data_type("clip.bin", Some("video/mp4")),
+            Some((DataType::Video, _))
+        ));
+        assert!(matches!(
+            infer_data_type("north_field.LAZ", None),
+            Some((DataType::LidarScan, DataPayload::Raw(_)))
+        ));
+        assert!(matches!(
+            infer_data_type("frame_0001.tif", Some("application/octet-stream")),
+            Some((DataType::Image, _))
+        ));
+        assert!(infer_data_type("notes.txt", None).is_none());
+    }
+
+    #[tokio::test]
+    async fn expired_partial_uploads_are_collected() {
+        let temp_dir = tempdir().unwrap();
+        let manager = UploadManager::open(
+            temp_dir.path(),
+            UploadConfig {
+                chunk_size_bytes: 4,
+                ttl: Duration::minutes(30),
+                ..UploadConfig::default()
+            },
+        )
+        .unwrap();
+        let started = Utc::now();
+        let contents = b"scan-bytes";
+        let stale = manager
+            .initiate(Uuid::new_v4(), init_request(contents, "stale.las"), started)
+            .await
+            .unwrap();
+        let fresh = manager
+            .initiate(Uuid::new_v4(), init_request(contents, "fresh.las"), started)
+            .await
+            .unwrap();
+        manager
+            .receive_chunk(
+                fresh.upload_id,
+                0,
+                &sha256_hex(b"scan"),
+                b"scan",
+                started + Duration::minutes(20),
+            )
+            .await
+            .unwrap();
+
+        let collected = manager
+            .collect_expired(started + Duration::minutes(40))
+            .await
+            .unwrap();
+
+        assert_eq!(collected, vec![stale.upload_id]);
+        assert!(matches!(
+            manager.status(stale.upload_id).await,
+            Err(UploadError::NotFound { .. })
+        ));
+        assert!(!temp_dir
+            .path()
+            .join(PARTIAL_DIR)
+            .join(stale.upload_id.to_string())
+            .exists());
+        let manifest: serde_json::Value = serde_json::from_slice(
+            &std::fs::read(
+                temp_dir
+                    .path()
+                    .join(PARTIAL_DIR)
+                    .join(fresh.upload_id.to_string())
+                    .join(MANIFEST_FILE),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(manifest["received"], serde_json::json!([0]));
+        let reopened = UploadManager::open(temp_dir.path(), manager.config()).unwrap();
+        let status = reopened.status(fresh.upload_id).await.unwrap();
+        assert_eq!(status.received_bitmap, "100");
+    }
+
+    #[tokio::test]
+    async fn oversized_uploads_are_refused_before_anything_is_staged() {
+        let temp_dir = tempdir().unwrap();
+        let manager = UploadManager::open(
+            temp_dir.path(),
+            UploadConfig {
+                chunk_size_bytes: 4,
+                max_file_size_bytes: 16,
+                ..UploadConfig::default()
+            },
+        )
+        .unwrap();
+        let mut request = init_request(b"scan-bytes", "huge.las");
+        request.size_bytes = u64::from(u32::MAX) * 8 * 1024 * 1024;
+
+        let error = manager
+            .initiate(Uuid::new_v4(), request, Utc::now())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, UploadError::TooLarge { max_bytes: 16, .. }));
+        assert_eq!(
+            std::fs::read_dir(temp_dir.path().join(PARTIAL_DIR))
+                .unwrap()
+                .count(),
+            0
+        );
+    }
+}
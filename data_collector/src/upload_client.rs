@@ -0,0 +1,211 @@
+use crate::api::{IngestRequest, RecordIngestAck};
+use crate::upload::{lowercase_hex, sha256_hex, UploadInitRequest, UploadStatus, UploadTicket};
+use anyhow::{Context, Result};
+use reqwest::Method;
+use sha2::{Digest, Sha256};
+use shared::http_client::{HttpClient, HttpClientConfig};
+use std::io::SeekFrom;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use uuid::Uuid;
+
+pub const CHUNK_SHA256_HEADER: &str = "x-chunk-sha256";
+
+/// Buffer size used when hashing a local file.
+const HASH_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// Minimal client for the chunked upload routes. `upload` sends only the
+/// chunks the collector reports as missing, so calling it again with the
+/// same upload id resumes an interrupted transfer.
+#[derive(Debug, Clone)]
+pub struct UploadClient {
+    http: HttpClient,
+    base_url: String,
+}
+
+impl UploadClient {
+    /// `base_url` is the prefix the ingest router is mounted under, for
+    /// example `http://collector:8080/api`. Chunk PUTs are retried under
+    /// `config`'s retry policy; the POSTs that start and complete an upload
+    /// are sent once.
+    pub fn new(base_url: impl Into<String>, config: HttpClientConfig) -> Result<Self> {
+        Ok(Self {
+            http: HttpClient::new(config)?,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        })
+    }
+
+    pub async fn initiate(
+        &self,
+        session_id: Uuid,
+        request: &UploadInitRequest,
+    ) -> Result<UploadTicket> {
+        let url = format!("{}/sessions/{session_id}/records", self.base_url);
+        let response = self
+            .http
+            .send(
+                self.http
+                    .post(url)
+                    .json(&IngestRequest::Upload(request.clone())),
+            )
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn status(&self, upload_id: Uuid) -> Result<UploadStatus> {
+        let url = format!("{}/uploads/{upload_id}", self.base_url);
+        let response = self.http.send(self.http.get(url)).await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn send_chunk(
+        &self,
+        upload_id: Uuid,
+        index: u32,
+        chunk: &[u8],
+    ) -> Result<UploadStatus> {
+        let url = format!("{}/uploads/{upload_id}/chunks/{index}", self.base_url);
+        let response = self
+            .http
+            .send(
+                self.http
+                    .request(Method::PUT, url)
+                    .header(CHUNK_SHA256_HEADER, sha256_hex(chunk))
+                    .body(chunk.to_vec()),
+            )
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    pub async fn complete(&self, upload_id: Uuid) -> Result<RecordIngestAck> {
+        let url = format!("{}/uploads/{upload_id}/complete", self.base_url);
+        let response = self.http.send(self.http.post(url)).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Uploads the file at `path` under an existing upload id, skipping
+    /// chunks the collector already holds, then completes the upload. Only
+    /// one chunk is held in memory at a time.
+    pub async fn upload(&self, upload_id: Uuid, path: &Path) -> Result<RecordIngestAck> {
+        let status = self.status(upload_id).await?;
+        let mut file = File::open(path)
+            .await
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        let chunk_size = status.chunk_size_bytes;
+        let mut chunk = Vec::with_capacity(
+            usize::try_from(chunk_size)
+                .context("chunk size does not fit in memory on this platform")?,
+        );
+        for index in status.missing_chunks {
+            file.seek(SeekFrom::Start(u64::from(index) * chunk_size))
+                .await?;
+            chunk.clear();
+            (&mut file).take(chunk_size).read_to_end(&mut chunk).await?;
+            if chunk.is_empty() {
+                anyhow::bail!("chunk {index} is outside {}", path.display());
+            }
+            self.send_chunk(upload_id, index, &chunk).await?;
+        }
+        self.complete(upload_id).await
+    }
+}
+
+/// sha256 of the file at `path` in the lowercase hex form uploads declare,
+/// read in fixed-size blocks rather than all at once.
+pub async fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = File::open(path)
+        .await
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; HASH_BUFFER_BYTES];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(lowercase_hex(&hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{router, IngestApiState};
+    use crate::upload::{UploadConfig, UploadManager};
+    use crate::{DataCollectorService, DataType, GpsCoords};
+    use chrono::Utc;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+    use tokio::sync::Mutex;
+
+    #[tokio::test]
+    async fn client_resumes_partial_upload_and_completes_it() {
+        let temp_dir = tempdir().unwrap();
+        let service = Arc::new(Mutex::new(
+            DataCollectorService::new(temp_dir.path().to_path_buf()).unwrap(),
+        ));
+        let session_id = service
+            .lock()
+            .await
+            .start_session(Uuid::new_v4(), None)
+            .await
+            .unwrap();
+        let uploads = Arc::new(
+            UploadManager::open(
+                temp_dir.path().join("uploads"),
+                UploadConfig {
+                    chunk_size_bytes: 5,
+                    ..UploadConfig::default()
+                },
+            )
+            .unwrap(),
+        );
+        let app = axum::Router::new().nest("/api", router(IngestApiState::new(service, uploads)));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let contents = b"flight-log-0042.ulg-bytes";
+        let client = UploadClient::new(
+            format!("http://{address}/api/"),
+            HttpClientConfig::default(),
+        )
+        .unwrap();
+        let ticket = client
+            .initiate(
+                session_id,
+                &UploadInitRequest {
+                    file_name: "flight-0042.ulg".to_string(),
+                    mime_type: None,
+                    size_bytes: contents.len() as u64,
+                    sha256: sha256_hex(contents),
+                    sensor_id: "autopilot".to_string(),
+                    gps_coords: GpsCoords {
+                        latitude: 40.0,
+                        longitude: -105.0,
+                        altitude: 30.0,
+                    },
+                    captured_at: Utc::now(),
+                    calibration_ref: "fc-params-v3".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        let partial = client
+            .send_chunk(ticket.upload_id, 3, &contents[15..20])
+            .await
+            .unwrap();
+        assert_eq!(partial.received_bitmap, "00010");
+
+        let file_path = temp_dir.path().join("flight-0042.ulg");
+        std::fs::write(&file_path, contents).unwrap();
+        assert_eq!(file_sha256(&file_path).await.unwrap(), sha256_hex(contents));
+        let ack = client.upload(ticket.upload_id, &file_path).await.unwrap();
+
+        assert_eq!(ack.data_type, DataType::FlightLog);
+        assert_eq!(ack.record_count, 1);
+        assert!(client.status(ticket.upload_id).await.is_err());
+    }
+}
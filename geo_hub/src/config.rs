@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use shared::config::CorsConfig;
 use std::fmt;
 use std::path::{Path, PathBuf};
 
@@ -79,6 +80,7 @@ pub struct HubConfig {
     pub database_url: String,
     pub data_root: PathBuf,
     pub landsat: LandsatConfig,
+    pub cors: CorsConfig,
 }
 
 impl Default for HubConfig {
@@ -89,6 +91,7 @@ impl Default for HubConfig {
             database_url: "sqlite://geo_hub.db".to_string(),
             data_root: PathBuf::from("data/geo_hub"),
             landsat: LandsatConfig::default(),
+            cors: CorsConfig::default(),
         }
     }
 }
@@ -121,6 +124,7 @@ impl HubConfig {
             // Enable WAL mode for better concurrency
             config.database_url.push_str("?mode=rwc");
         }
+        config.cors.validate()?;
         Ok(config)
     }
 
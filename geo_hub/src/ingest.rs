@@ -3,6 +3,7 @@ use anyhow::{anyhow, Result};
 use clap::Args;
 use imagery_processor::{IndexKind, IndicesArgs, OutputFormat, Processor, SensorPreset};
 use serde::{Deserialize, Serialize};
+use shared::artifact_schema::{from_artifact_str, to_artifact_string};
 use shared::schemas::{
     assert_raster_spatial_ref, MultispectralImage, RasterSpatialRef, DEFAULT_RECORD_OWNER,
 };
@@ -477,7 +478,7 @@ async fn ingest_landsat_inner(
     let metadata_json_original = fs::read_to_string(&metadata_path)
         .await
         .map_err(|err| ingest_step_error("download_error", err))?;
-    let mut image: MultispectralImage = serde_json::from_str(&metadata_json_original)
+    let mut image: MultispectralImage = from_artifact_str(&metadata_json_original)
         .map_err(|err| ingest_step_error("metadata_error", err))?;
     let spatial_ref = assert_raster_spatial_ref(
         image.metadata.spatial_ref.as_ref(),
@@ -512,11 +513,14 @@ async fn ingest_landsat_inner(
         .file_name()
         .map(|f| f.to_owned())
         .unwrap_or_else(|| std::ffi::OsString::from("metadata_ingested.json"));
-    let metadata_json = serde_json::to_string_pretty(&image)
-        .map_err(|err| ingest_step_error("metadata_error", err))?;
-    fs::write(scene_dir.join(&metadata_filename), &metadata_json)
+    let metadata_artifact =
+        to_artifact_string(&image).map_err(|err| ingest_step_error("metadata_error", err))?;
+    fs::write(scene_dir.join(&metadata_filename), &metadata_artifact)
         .await
         .map_err(|err| ingest_step_error("processing_error", err))?;
+    // The scenes table keeps the bare struct; only the file is an artifact.
+    let metadata_json = serde_json::to_string_pretty(&image)
+        .map_err(|err| ingest_step_error("metadata_error", err))?;
 
     let summary = SceneMetadataSummary {
         scene_id: args.scene_id.clone(),
@@ -881,6 +885,9 @@ pub async fn ensure_product(pool: &DbPool, scene_id: &str, kind: &str) -> Result
         out_format: OutputFormat::Png,
         sensor,
         mask: None,
+        band_scale_factor: None,
+        float_geotiff: false,
+        resample: None,
     };
 
     let processor = Processor::new().await?;
@@ -94,6 +94,7 @@ use provenance::{
     ProvenanceParameters,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use shared::artifact_schema::from_artifact_str;
 use shared::plugin_extensions::ExtensionPointKind;
 use shared::schemas::{
     aggregate_content_engagement, aggregate_marketplace_ratings, append_content_version,
@@ -21478,7 +21479,7 @@ async fn load_scene_metadata(
         let metadata_json = fs::read_to_string(&path)
             .await
             .map_err(|err| AppError::Anyhow(err.into()))?;
-        let image = serde_json::from_str::<MultispectralImage>(&metadata_json).map_err(|err| {
+        let image = from_artifact_str::<MultispectralImage>(&metadata_json).map_err(|err| {
             AppError::Anyhow(anyhow::Error::new(err).context(format!(
                 "failed to decode scene metadata at {}",
                 path.display()
@@ -817,7 +817,7 @@ pub async fn serve(config: HubConfig, pool: crate::db::DbPool) -> Result<()> {
         scene_search_cache: Default::default(),
     };
 
-    let router = build_router(state);
+    let router = build_router(state).layer(shared_config.cors.layer());
 
     let listener = TcpListener::bind(addr).await?;
     info!(%addr, "geo_hub listening");
@@ -1,8 +1,8 @@
 use crate::{
-    ActionAckStatus, MissionControlActionAck, MissionControlActionClient,
-    MissionControlActionRequest, OperatorActionAuditRecord, OperatorActionError,
-    OperatorActionKind, SharedLinkState, SharedMessageDispatchState, SharedOperatorActionAuditLog,
-    SharedOperatorActionState, SharedOperatorSessionRegistry,
+    lidar_readout_lines, render_braille, ActionAckStatus, MissionControlActionAck,
+    MissionControlActionClient, MissionControlActionRequest, OperatorActionAuditRecord,
+    OperatorActionError, OperatorActionKind, SharedLinkState, SharedMessageDispatchState,
+    SharedOperatorActionAuditLog, SharedOperatorActionState, SharedOperatorSessionRegistry,
 };
 use serde::Serialize;
 use tokio::io::{self, AsyncBufReadExt, BufReader};
@@ -68,6 +68,30 @@ pub async fn cli_status_snapshot(
     CliStatusSnapshot { lines }
 }
 
+const CLI_LIDAR_PLOT_CELLS: (usize, usize) = (40, 20);
+const CLI_LIDAR_RANGE_M: f32 = 10.0;
+
+pub async fn cli_lidar_snapshot(dispatch_state: SharedMessageDispatchState) -> CliStatusSnapshot {
+    let dispatch = dispatch_state.read().await;
+    if dispatch.lidar_scans.is_empty() {
+        return CliStatusSnapshot {
+            lines: vec!["LiDAR: no scans received".to_string()],
+        };
+    }
+
+    let mut lines = vec![format!("LiDAR (plot range {CLI_LIDAR_RANGE_M:.0} m):")];
+    for view in dispatch.lidar_scans.values() {
+        let (width_cells, height_cells) = CLI_LIDAR_PLOT_CELLS;
+        lines.extend(
+            render_braille(view, width_cells, height_cells, CLI_LIDAR_RANGE_M)
+                .into_iter()
+                .map(|row| format!("  {row}")),
+        );
+        lines.extend(lidar_readout_lines(view));
+    }
+    CliStatusSnapshot { lines }
+}
+
 pub async fn submit_cli_operator_action(
     session_token: Option<&str>,
     action: OperatorActionKind,
@@ -168,7 +192,7 @@ pub async fn run_cli_interface(
     dispatch_state: SharedMessageDispatchState,
 ) {
     info!("CLI Ground Station Interface");
-    info!("Commands: help, status, quit");
+    info!("Commands: help, status, lidar, quit");
 
     let stdin = io::stdin();
     let reader = BufReader::new(stdin);
@@ -185,6 +209,7 @@ pub async fn run_cli_interface(
                     println!("Available commands:");
                     println!("  help   - Show this help message");
                     println!("  status - Show system status");
+                    println!("  lidar  - Show the latest LiDAR scan of each sensor");
                     println!("  quit   - Exit the application");
                 }
                 "status" => {
@@ -195,6 +220,11 @@ pub async fn run_cli_interface(
                         println!("{line}");
                     }
                 }
+                "lidar" => {
+                    for line in cli_lidar_snapshot(dispatch_state.clone()).await.lines {
+                        println!("{line}");
+                    }
+                }
                 "quit" | "exit" => {
                     println!("Goodbye!");
                     break;
@@ -4,12 +4,14 @@ use shared::{
     schemas::{Telemetry, WebSocketMessage},
     AgroResult,
 };
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::watch;
 use tracing::{error, info};
 
 pub mod cli_interface;
 pub mod fleet_operations;
+pub mod lidar_view;
 pub mod link_client;
 pub mod map_state;
 pub mod message_dispatch;
@@ -19,12 +21,18 @@ pub mod operator_session;
 pub mod web_server;
 
 pub use cli_interface::{
-    cli_status_snapshot, submit_cli_operator_action, CliCommandOutcome, CliStatusSnapshot,
+    cli_lidar_snapshot, cli_status_snapshot, submit_cli_operator_action, CliCommandOutcome,
+    CliStatusSnapshot,
 };
 pub use fleet_operations::{
     build_fleet_status_overview, summarize_fleet_operations_feed, FleetOperationsConsoleSummary,
     FleetOverviewAircraftStatus, FleetOverviewLinkState, FleetStatusOverview,
 };
+pub use lidar_view::{
+    decode_scan, downsample_polar, lidar_readout_lines, nearest_obstacle, render_braille,
+    sector_blockage, LidarScanView, LidarViewConfig, NearestObstacle, PolarPoint, SectorBlockage,
+    DEFAULT_LIDAR_SENSOR_ID, LIDAR_RENDER_INTERVAL,
+};
 pub use link_client::{
     run_websocket_client_until, run_websocket_client_with_dispatch_until,
     run_websocket_client_with_handler_until, shared_link_state, ConnectionState, LinkStateMachine,
@@ -72,6 +80,16 @@ pub struct Args {
 
     #[arg(long, help = "Mission control WebSocket URL")]
     pub ws_url: Option<String>,
+
+    #[arg(long, help = "Configuration file path")]
+    pub config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = 2.0,
+        help = "LiDAR returns closer than this many metres count as obstacles"
+    )]
+    pub lidar_obstacle_threshold_m: f32,
 }
 
 pub struct GroundStationUI {
@@ -82,18 +100,26 @@ pub struct GroundStationUI {
 
 impl GroundStationUI {
     pub async fn new() -> AgroResult<Self> {
-        let config = Arc::new(AgroConfig::load()?);
-        let link_state = shared_link_state(ReconnectPolicy::default());
-        let dispatch_state = shared_message_dispatch_state();
-        Ok(Self {
-            config,
-            link_state,
-            dispatch_state,
-        })
+        Ok(Self::with_config(AgroConfig::load()?))
+    }
+
+    pub fn with_config(config: AgroConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            link_state: shared_link_state(ReconnectPolicy::default()),
+            dispatch_state: shared_message_dispatch_state(),
+        }
     }
 
     pub async fn run(&self) -> AgroResult<()> {
         let args = Args::parse();
+        self.dispatch_state
+            .write()
+            .await
+            .set_lidar_view_config(LidarViewConfig {
+                obstacle_threshold_m: args.lidar_obstacle_threshold_m,
+                ..LidarViewConfig::default()
+            });
 
         if args.web {
             self.run_web_server(&args).await
@@ -189,7 +215,7 @@ impl GroundStationUI {
             WebSocketMessage::MissionStatus { mission_id, status } => {
                 info!("Mission {} status: {}", mission_id, status);
             }
-            WebSocketMessage::LidarUpdate { scan } => {
+            WebSocketMessage::LidarUpdate { scan, .. } => {
                 info!("LiDAR scan received: {} points", scan.points.len());
             }
             WebSocketMessage::ImageCaptured { image } => {
@@ -201,9 +227,32 @@ impl GroundStationUI {
                     result.mean_ndvi, result.vegetation_percentage
                 );
             }
+            WebSocketMessage::OverlayGenerated { overlay } => {
+                info!(
+                    "Overlay generated ({}): {}",
+                    overlay.overlay_types.join(" + "),
+                    overlay.composite_path
+                );
+            }
             WebSocketMessage::SystemStatus { status, message } => {
                 info!("System {}: {}", status, message);
             }
+            WebSocketMessage::HistorySnapshot { messages } => {
+                info!("History snapshot received: {} messages", messages.len());
+                for message in messages {
+                    Self::handle_websocket_message(message);
+                }
+            }
+            WebSocketMessage::MissionSync { .. } => {}
+            WebSocketMessage::MissionSyncProgress { progress } => {
+                info!(
+                    "Mission {} sync {:?}: {}/{} items",
+                    progress.mission_id,
+                    progress.phase,
+                    progress.items_confirmed,
+                    progress.items_total
+                );
+            }
         }
     }
 
@@ -0,0 +1,413 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use shared::schemas::LidarScan;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Sensor key for scans that arrive without a sensor id.
+pub const DEFAULT_LIDAR_SENSOR_ID: &str = "lidar";
+/// Fastest the live LiDAR views are redrawn or pushed, however fast scans
+/// arrive.
+pub const LIDAR_RENDER_INTERVAL: Duration = Duration::from_millis(250);
+
+const BRAILLE_BASE: u32 = 0x2800;
+/// Braille dot bits by row (0-3) and column (0-1) within a character cell.
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct LidarViewConfig {
+    /// Returns closer than this count as blocking their sector.
+    pub obstacle_threshold_m: f32,
+    pub sector_count: usize,
+    /// Most points kept for plotting, one per equal bearing bin.
+    pub max_plot_points: usize,
+    /// Returns below this quality are dropped.
+    pub min_quality: u8,
+}
+
+impl Default for LidarViewConfig {
+    fn default() -> Self {
+        Self {
+            obstacle_threshold_m: 2.0,
+            sector_count: 8,
+            max_plot_points: 360,
+            min_quality: 0,
+        }
+    }
+}
+
+/// One return as seen from the sensor: bearing clockwise from its forward
+/// axis in [0, 360) degrees and range in metres.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PolarPoint {
+    pub bearing_deg: f32,
+    pub distance_m: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct NearestObstacle {
+    pub distance_m: f32,
+    pub bearing_deg: f32,
+}
+
+/// Returns in one sector; sector `i` of `n` spans bearings
+/// `[i * 360 / n, (i + 1) * 360 / n)`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SectorBlockage {
+    pub start_deg: f32,
+    pub end_deg: f32,
+    pub point_count: usize,
+    pub blocked_count: usize,
+    /// Share of the sector's returns inside the obstacle threshold; 0 for a
+    /// sector with no returns.
+    pub blocked_percent: f32,
+}
+
+/// Latest scan of one sensor, decoded for display.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LidarScanView {
+    pub sensor_id: String,
+    pub scan_id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub point_count: usize,
+    pub dropped_point_count: usize,
+    pub nearest: Option<NearestObstacle>,
+    pub obstacle_threshold_m: f32,
+    pub sectors: Vec<SectorBlockage>,
+    pub plot_points: Vec<PolarPoint>,
+}
+
+impl LidarScanView {
+    pub fn from_scan(sensor_id: &str, scan: &LidarScan, config: &LidarViewConfig) -> Self {
+        let points = decode_scan(scan, config.min_quality);
+        Self {
+            sensor_id: sensor_id.to_string(),
+            scan_id: scan.scan_id,
+            timestamp: scan.timestamp,
+            point_count: points.len(),
+            dropped_point_count: scan.points.len() - points.len(),
+            nearest: nearest_obstacle(&points),
+            obstacle_threshold_m: config.obstacle_threshold_m,
+            sectors: sector_blockage(&points, config.sector_count, config.obstacle_threshold_m),
+            plot_points: downsample_polar(&points, config.max_plot_points),
+        }
+    }
+}
+
+/// Keeps returns with a finite, positive range and at least `min_quality`,
+/// with bearings wrapped into [0, 360).
+pub fn decode_scan(scan: &LidarScan, min_quality: u8) -> Vec<PolarPoint> {
+    scan.points
+        .iter()
+        .filter(|point| {
+            point.quality >= min_quality
+                && point.angle.is_finite()
+                && point.distance.is_finite()
+                && point.distance > 0.0
+        })
+        .map(|point| PolarPoint {
+            bearing_deg: wrap_bearing(point.angle),
+            distance_m: point.distance,
+        })
+        .collect()
+}
+
+/// Closest return; equal ranges resolve to the smaller bearing.
+pub fn nearest_obstacle(points: &[PolarPoint]) -> Option<NearestObstacle> {
+    points
+        .iter()
+        .min_by(|a, b| {
+            a.distance_m
+                .total_cmp(&b.distance_m)
+                .then(a.bearing_deg.total_cmp(&b.bearing_deg))
+        })
+        .map(|point| NearestObstacle {
+            distance_m: point.distance_m,
+            bearing_deg: point.bearing_deg,
+        })
+}
+
+pub fn sector_blockage(
+    points: &[PolarPoint],
+    sector_count: usize,
+    threshold_m: f32,
+) -> Vec<SectorBlockage> {
+    let sector_count = sector_count.max(1);
+    let width = 360.0 / sector_count as f32;
+    let mut sectors: Vec<SectorBlockage> = (0..sector_count)
+        .map(|index| SectorBlockage {
+            start_deg: index as f32 * width,
+            end_deg: (index + 1) as f32 * width,
+            point_count: 0,
+            blocked_count: 0,
+            blocked_percent: 0.0,
+        })
+        .collect();
+    for point in points {
+        let sector = &mut sectors[bin_index(point.bearing_deg, sector_count)];
+        sector.point_count += 1;
+        if point.distance_m <= threshold_m {
+            sector.blocked_count += 1;
+        }
+    }
+    for sector in &mut sectors {
+        if sector.point_count > 0 {
+            sector.blocked_percent =
+                sector.blocked_count as f32 / sector.point_count as f32 * 100.0;
+        }
+    }
+    sectors
+}
+
+/// Keeps the nearest return in each of `max_points` equal bearing bins, in
+/// bearing order, so obstacles survive downsampling and the result does not
+/// depend on the order returns arrived in.
+pub fn downsample_polar(points: &[PolarPoint], max_points: usize) -> Vec<PolarPoint> {
+    let bin_count = max_points.max(1);
+    let mut bins: Vec<Option<PolarPoint>> = vec![None; bin_count];
+    for point in points {
+        let slot = &mut bins[bin_index(point.bearing_deg, bin_count)];
+        let closer = slot.is_none_or(|kept| {
+            point
+                .distance_m
+                .total_cmp(&kept.distance_m)
+                .then(point.bearing_deg.total_cmp(&kept.bearing_deg))
+                .is_lt()
+        });
+        if closer {
+            *slot = Some(*point);
+        }
+    }
+    bins.into_iter().flatten().collect()
+}
+
+/// Top-down plot of `view` as `height_cells` lines of braille, each
+/// character 2x4 dots. The sensor sits at the centre with its forward axis
+/// up; returns beyond `range_m` are left off.
+pub fn render_braille(
+    view: &LidarScanView,
+    width_cells: usize,
+    height_cells: usize,
+    range_m: f32,
+) -> Vec<String> {
+    let (width_dots, height_dots) = (width_cells * 2, height_cells * 4);
+    let mut cells = vec![vec![0u8; width_cells]; height_cells];
+    if width_cells == 0 || height_cells == 0 || range_m <= 0.0 {
+        return Vec::new();
+    }
+    let (center_x, center_y) = (width_dots as f32 / 2.0, height_dots as f32 / 2.0);
+    let scale = center_x.min(center_y) / range_m;
+    let mut plot = |x: f32, y: f32| {
+        let (column, row) = (x.floor(), y.floor());
+        if column < 0.0 || row < 0.0 {
+            return;
+        }
+        let (column, row) = (column as usize, row as usize);
+        if column < width_dots && row < height_dots {
+            cells[row / 4][column / 2] |= BRAILLE_DOTS[row % 4][column % 2];
+        }
+    };
+
+    plot(center_x, center_y);
+    for point in view
+        .plot_points
+        .iter()
+        .filter(|point| point.distance_m <= range_m)
+    {
+        let bearing = point.bearing_deg.to_radians();
+        plot(
+            center_x + point.distance_m * bearing.sin() * scale,
+            center_y - point.distance_m * bearing.cos() * scale,
+        );
+    }
+
+    cells
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|bits| char::from_u32(BRAILLE_BASE + u32::from(bits)).unwrap_or(' '))
+                .collect()
+        })
+        .collect()
+}
+
+/// Nearest-obstacle and sector readouts for one view.
+pub fn lidar_readout_lines(view: &LidarScanView) -> Vec<String> {
+    let mut lines = vec![format!(
+        "  {}: {} points ({} dropped) at {}",
+        view.sensor_id,
+        view.point_count,
+        view.dropped_point_count,
+        view.timestamp.format("%H:%M:%S")
+    )];
+    lines.push(match view.nearest {
+        Some(nearest) => format!(
+            "  Nearest obstacle: {:.2} m at {:.0}°",
+            nearest.distance_m, nearest.bearing_deg
+        ),
+        None => "  Nearest obstacle: none".to_string(),
+    });
+    let blocked = view
+        .sectors
+        .iter()
+        .map(|sector| {
+            format!(
+                "{:.0}-{:.0}° {:.0}%",
+                sector.start_deg, sector.end_deg, sector.blocked_percent
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    lines.push(format!(
+        "  Blocked within {:.1} m: {blocked}",
+        view.obstacle_threshold_m
+    ));
+    lines
+}
+
+fn wrap_bearing(angle_deg: f32) -> f32 {
+    let wrapped = angle_deg.rem_euclid(360.0);
+    // rem_euclid can round up to exactly 360 for tiny negative angles.
+    if wrapped >= 360.0 {
+        0.0
+    } else {
+        wrapped
+    }
+}
+
+fn bin_index(bearing_deg: f32, bin_count: usize) -> usize {
+    ((bearing_deg / 360.0 * bin_count as f32) as usize).min(bin_count - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::schemas::LidarPoint;
+
+    fn scan(points: &[(f32, f32, u8)]) -> LidarScan {
+        let timestamp = Utc::now();
+        LidarScan {
+            timestamp,
+            points: points
+                .iter()
+                .map(|&(angle, distance, quality)| LidarPoint {
+                    timestamp,
+                    angle,
+                    distance,
+                    quality,
+                })
+                .collect(),
+            scan_id: Uuid::new_v4(),
+        }
+    }
+
+    #[test]
+    fn nearest_obstacle_skips_invalid_returns_and_wraps_bearings() {
+        let scan = scan(&[
+            (10.0, 4.0, 40),
+            (-90.0, 1.5, 40),
+            (200.0, 0.0, 40),
+            (30.0, f32::NAN, 40),
+            (120.0, 0.5, 2),
+            (450.0, 1.5, 40),
+        ]);
+        let config = LidarViewConfig {
+            min_quality: 10,
+            ..LidarViewConfig::default()
+        };
+
+        let view = LidarScanView::from_scan(DEFAULT_LIDAR_SENSOR_ID, &scan, &config);
+
+        assert_eq!(view.point_count, 3);
+        assert_eq!(view.dropped_point_count, 3);
+        // -90° and 450° tie on range; the smaller wrapped bearing wins.
+        assert_eq!(
+            view.nearest,
+            Some(NearestObstacle {
+                distance_m: 1.5,
+                bearing_deg: 90.0
+            })
+        );
+        assert_eq!(nearest_obstacle(&[]), None);
+    }
+
+    #[test]
+    fn sectors_report_the_share_of_returns_inside_the_threshold() {
+        let points = decode_scan(
+            &scan(&[
+                (0.0, 1.0, 1),
+                (45.0, 3.0, 1),
+                (89.9, 2.0, 1),
+                (90.0, 5.0, 1),
+                (359.9, 0.5, 1),
+            ]),
+            0,
+        );
+
+        let sectors = sector_blockage(&points, 4, 2.0);
+
+        assert_eq!(sectors.len(), 4);
+        assert_eq!((sectors[0].start_deg, sectors[0].end_deg), (0.0, 90.0));
+        assert_eq!((sectors[0].point_count, sectors[0].blocked_count), (3, 2));
+        assert!((sectors[0].blocked_percent - 200.0 / 3.0).abs() < 1e-4);
+        assert_eq!(
+            (sectors[1].point_count, sectors[1].blocked_percent),
+            (1, 0.0)
+        );
+        assert_eq!(
+            (sectors[2].point_count, sectors[2].blocked_percent),
+            (0, 0.0)
+        );
+        assert_eq!(
+            (sectors[3].point_count, sectors[3].blocked_percent),
+            (1, 100.0)
+        );
+    }
+
+    #[test]
+    fn downsampling_keeps_the_nearest_return_per_bin_whatever_the_input_order() {
+        let points: Vec<PolarPoint> = (0..720)
+            .map(|step| PolarPoint {
+                bearing_deg: step as f32 * 0.5,
+                distance_m: 2.0 + (step % 7) as f32,
+            })
+            .collect();
+        let mut reversed = points.clone();
+        reversed.reverse();
+
+        let downsampled = downsample_polar(&points, 90);
+
+        assert_eq!(downsampled.len(), 90);
+        assert_eq!(downsampled, downsample_polar(&reversed, 90));
+        // Bin 0 spans 0-4°: steps 0-7, whose nearest is step 0 or 7 at 2 m.
+        assert_eq!(downsampled[0].bearing_deg, 0.0);
+        assert_eq!(downsampled[0].distance_m, 2.0);
+        assert!(downsampled
+            .windows(2)
+            .all(|pair| pair[0].bearing_deg < pair[1].bearing_deg));
+    }
+
+    #[test]
+    fn braille_plot_puts_forward_returns_above_the_sensor() {
+        let view = LidarScanView::from_scan(
+            DEFAULT_LIDAR_SENSOR_ID,
+            &scan(&[(0.0, 4.0, 1), (90.0, 20.0, 1)]),
+            &LidarViewConfig::default(),
+        );
+
+        let lines = render_braille(&view, 4, 2, 4.0);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.chars().count() == 4));
+        // The sensor dot sits at the centre, the 4 m return on the top edge;
+        // the 20 m return is out of range.
+        assert_eq!(lines[0].chars().nth(2), Some('\u{2801}'));
+        assert_eq!(lines[1].chars().nth(2), Some('\u{2801}'));
+        let lit: usize = lines
+            .iter()
+            .flat_map(|line| line.chars())
+            .map(|c| (c as u32 - BRAILLE_BASE).count_ones() as usize)
+            .sum();
+        assert_eq!(lit, 2);
+    }
+}
@@ -1,17 +1,18 @@
 use anyhow::Result;
 use clap::Parser;
 use ground_station_ui::{Args, GroundStationUI};
-use shared::init_logging;
+use shared::{config::AgroConfig, init_logging};
 use tracing::info;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     init_logging()?;
 
-    let _args = Args::parse();
+    let args = Args::parse();
     info!("Starting Ground Station UI");
 
-    let ui = GroundStationUI::new().await?;
+    let config = AgroConfig::load_with_override(args.config.as_deref())?;
+    let ui = GroundStationUI::with_config(config);
     ui.run().await?;
 
     Ok(())
@@ -1,9 +1,11 @@
 use crate::{
-    CaptureEventInput, FlightPathSample, MapRenderState, MissionOverlayInput,
-    DEFAULT_FLIGHT_PATH_LIMIT,
+    CaptureEventInput, FlightPathSample, LidarScanView, LidarViewConfig, MapRenderState,
+    MissionOverlayInput, DEFAULT_FLIGHT_PATH_LIMIT, DEFAULT_LIDAR_SENSOR_ID,
 };
 use serde::Serialize;
+use shared::mission_sync::MissionSyncProgress;
 use shared::schemas::{GpsCoords, Telemetry, WebSocketMessage};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
@@ -20,7 +22,11 @@ pub enum MessageRoute {
     LidarUpdate,
     ImageCaptured,
     NdviProcessed,
+    OverlayGenerated,
     SystemStatus,
+    HistorySnapshot,
+    MissionSync,
+    MissionSyncProgress,
 }
 
 #[derive(Debug, Clone)]
@@ -93,6 +99,7 @@ pub enum CaptureEventKind {
     Lidar,
     ImageCaptured,
     NdviProcessed,
+    OverlayGenerated,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize)]
@@ -154,7 +161,11 @@ pub struct MessageDispatchState {
     pub latest_telemetry: Option<TelemetryTileValues>,
     pub latest_telemetry_updated_at: Option<chrono::DateTime<chrono::Utc>>,
     pub mission_statuses: Vec<MissionStatusSnapshot>,
+    /// Latest sync progress of each mission being sent to mission control.
+    pub mission_syncs: BTreeMap<Uuid, MissionSyncProgress>,
     pub lidar_scan_point_counts: Vec<usize>,
+    /// Latest decoded scan of each LiDAR sensor.
+    pub lidar_scans: BTreeMap<String, LidarScanView>,
     pub captured_image_ids: Vec<Uuid>,
     pub ndvi_means: Vec<f32>,
     pub capture_events: Vec<CaptureEvent>,
@@ -166,6 +177,8 @@ pub struct MessageDispatchState {
     capture_event_limit: usize,
     #[serde(skip)]
     flight_path_limit: usize,
+    #[serde(skip)]
+    lidar_view_config: LidarViewConfig,
 }
 
 pub type SharedMessageDispatchState = Arc<RwLock<MessageDispatchState>>;
@@ -184,7 +197,9 @@ impl MessageDispatchState {
             latest_telemetry: None,
             latest_telemetry_updated_at: None,
             mission_statuses: Vec::new(),
+            mission_syncs: BTreeMap::new(),
             lidar_scan_point_counts: Vec::new(),
+            lidar_scans: BTreeMap::new(),
             captured_image_ids: Vec::new(),
             ndvi_means: Vec::new(),
             capture_events: Vec::new(),
@@ -194,6 +209,7 @@ impl MessageDispatchState {
             malformed_frames: 0,
             capture_event_limit: capture_event_limit.max(1),
             flight_path_limit: DEFAULT_FLIGHT_PATH_LIMIT,
+            lidar_view_config: LidarViewConfig::default(),
         }
     }
 
@@ -231,8 +247,13 @@ impl MessageDispatchState {
                 });
                 MessageRoute::MissionStatus
             }
-            WebSocketMessage::LidarUpdate { scan } => {
+            WebSocketMessage::LidarUpdate { scan, sensor_id } => {
                 self.lidar_scan_point_counts.push(scan.points.len());
+                let sensor_id = sensor_id.as_deref().unwrap_or(DEFAULT_LIDAR_SENSOR_ID);
+                self.lidar_scans.insert(
+                    sensor_id.to_string(),
+                    LidarScanView::from_scan(sensor_id, scan, &self.lidar_view_config),
+                );
                 self.append_capture_event(CaptureEvent {
                     capture_event_id: format!("lidar:{}", scan.scan_id),
                     event_type: CaptureEventKind::Lidar,
@@ -267,6 +288,16 @@ impl MessageDispatchState {
                 });
                 MessageRoute::NdviProcessed
             }
+            WebSocketMessage::OverlayGenerated { overlay } => {
+                self.append_capture_event(CaptureEvent {
+                    capture_event_id: format!("overlay:{}", overlay.job_id),
+                    event_type: CaptureEventKind::OverlayGenerated,
+                    timestamp: overlay.captured_at,
+                    summary: format!("Overlay generated: {}", overlay.overlay_types.join(" + ")),
+                    position: overlay.position.clone(),
+                });
+                MessageRoute::OverlayGenerated
+            }
             WebSocketMessage::SystemStatus { status, message } => {
                 self.system_statuses.push(SystemStatusSnapshot {
                     status: status.clone(),
@@ -277,6 +308,19 @@ impl MessageDispatchState {
                 });
                 MessageRoute::SystemStatus
             }
+            WebSocketMessage::HistorySnapshot { messages } => {
+                for message in messages {
+                    self.dispatch_message_at(message, received_at);
+                }
+                MessageRoute::HistorySnapshot
+            }
+            // Sync traffic is addressed to the planner; only progress shows.
+            WebSocketMessage::MissionSync { .. } => MessageRoute::MissionSync,
+            WebSocketMessage::MissionSyncProgress { progress } => {
+                self.mission_syncs
+                    .insert(progress.mission_id, progress.clone());
+                MessageRoute::MissionSyncProgress
+            }
         }
     }
 
@@ -394,6 +438,11 @@ impl MessageDispatchState {
         self.mission_overlay = Some(mission_overlay);
     }
 
+    /// Applies to scans received from now on.
+    pub fn set_lidar_view_config(&mut self, config: LidarViewConfig) {
+        self.lidar_view_config = config;
+    }
+
     fn append_capture_event(&mut self, event: CaptureEvent) {
         self.capture_events.push(event);
         self.capture_events.sort_by(|left, right| {
@@ -468,9 +517,10 @@ mod tests {
         MapRenderError, MapRenderState, MissionOverlayInput, MissionPolygonInput,
         MissionWaypointInput, WEB_MERCATOR_CRS, WGS84_CRS,
     };
+    use shared::mission_sync::MissionSyncPhase;
     use shared::schemas::{
-        GpsCoords, ImageMetadata, LidarPoint, LidarScan, MultispectralImage, NdviResult, Telemetry,
-        WebSocketMessage,
+        GpsCoords, ImageMetadata, LidarPoint, LidarScan, MultispectralImage, NdviResult,
+        OverlayNotification, Telemetry, WebSocketMessage,
     };
     use std::{collections::HashMap, time::Duration};
     use uuid::Uuid;
@@ -497,6 +547,7 @@ mod tests {
             (
                 WebSocketMessage::LidarUpdate {
                     scan: sample_lidar_scan(),
+                    sensor_id: None,
                 },
                 MessageRoute::LidarUpdate,
             ),
@@ -512,6 +563,20 @@ mod tests {
                 },
                 MessageRoute::NdviProcessed,
             ),
+            (
+                WebSocketMessage::OverlayGenerated {
+                    overlay: OverlayNotification {
+                        job_id: Uuid::new_v4(),
+                        completed_at: chrono::Utc::now(),
+                        captured_at: chrono::Utc::now(),
+                        overlay_types: vec!["ndvi".to_string(), "thermal".to_string()],
+                        source_sensor_ids: vec!["ms-1".to_string(), "th-1".to_string()],
+                        composite_path: "/overlays/composite_overlay.png".to_string(),
+                        position: None,
+                    },
+                },
+                MessageRoute::OverlayGenerated,
+            ),
             (
                 WebSocketMessage::SystemStatus {
                     status: "warn".to_string(),
@@ -519,6 +584,20 @@ mod tests {
                 },
                 MessageRoute::SystemStatus,
             ),
+            (
+                WebSocketMessage::MissionSyncProgress {
+                    progress: MissionSyncProgress {
+                        mission_id,
+                        phase: MissionSyncPhase::Transferring,
+                        chunks_total: 4,
+                        chunks_confirmed: 1,
+                        items_total: 100,
+                        items_confirmed: 32,
+                        chunks_reused: 0,
+                    },
+                },
+                MessageRoute::MissionSyncProgress,
+            ),
         ];
 
         for (message, route) in cases {
@@ -536,9 +615,37 @@ mod tests {
         assert_eq!(state.ndvi_means, vec![0.42]);
         assert_eq!(state.system_statuses[0].status, "warn");
         assert_eq!(state.system_statuses[0].message, "wind increasing");
+        assert_eq!(state.mission_syncs[&mission_id].items_confirmed, 32);
         assert_eq!(state.malformed_frames, 0);
     }
 
+    #[test]
+    fn history_snapshot_dispatches_buffered_messages_in_order() {
+        let mut state = MessageDispatchState::default();
+        let frame = serde_json::to_string(&WebSocketMessage::HistorySnapshot {
+            messages: vec![
+                WebSocketMessage::Telemetry {
+                    data: sample_telemetry("AUTO", 80),
+                },
+                WebSocketMessage::SystemStatus {
+                    status: "warn".to_string(),
+                    message: "wind increasing".to_string(),
+                },
+                WebSocketMessage::Telemetry {
+                    data: sample_telemetry("RTL", 64),
+                },
+            ],
+        })
+        .unwrap();
+
+        let dispatched = state.dispatch_frame(&frame).unwrap();
+
+        assert_eq!(dispatched.route, MessageRoute::HistorySnapshot);
+        assert_eq!(state.latest_telemetry_mode.as_deref(), Some("RTL"));
+        assert_eq!(state.latest_telemetry_battery_percentage, Some(64));
+        assert_eq!(state.system_statuses.len(), 1);
+    }
+
     #[test]
     fn malformed_frame_is_counted_and_preserves_prior_state() {
         let mut state = MessageDispatchState::default();
@@ -811,7 +918,10 @@ mod tests {
         let lidar_id = lidar.scan_id;
 
         state.dispatch_message(&WebSocketMessage::ImageCaptured { image });
-        state.dispatch_message(&WebSocketMessage::LidarUpdate { scan: lidar });
+        state.dispatch_message(&WebSocketMessage::LidarUpdate {
+            scan: lidar,
+            sensor_id: None,
+        });
 
         let timeline = state.capture_events(None);
         assert_eq!(timeline.len(), 2);
@@ -823,6 +933,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn lidar_updates_keep_the_latest_scan_per_sensor() {
+        let mut state = MessageDispatchState::default();
+        let first = sample_lidar_scan();
+        let latest = sample_lidar_scan();
+        let latest_id = latest.scan_id;
+
+        for (scan, sensor_id) in [
+            (first, Some("front".to_string())),
+            (latest, Some("front".to_string())),
+            (sample_lidar_scan(), None),
+        ] {
+            state.dispatch_message(&WebSocketMessage::LidarUpdate { scan, sensor_id });
+        }
+
+        assert_eq!(state.lidar_scan_point_counts, vec![2, 2, 2]);
+        assert_eq!(
+            state.lidar_scans.keys().collect::<Vec<_>>(),
+            vec!["front", DEFAULT_LIDAR_SENSOR_ID]
+        );
+        let front = &state.lidar_scans["front"];
+        assert_eq!(front.scan_id, latest_id);
+        assert_eq!(front.nearest.as_ref().unwrap().distance_m, 2.0);
+    }
+
     #[test]
     fn capture_timeline_orders_filters_and_evicts_oldest_events() {
         let image_id = Uuid::new_v4();
@@ -836,7 +971,10 @@ mod tests {
 
         state.dispatch_message(&WebSocketMessage::ImageCaptured { image });
         state.dispatch_message(&WebSocketMessage::NdviProcessed { result: ndvi });
-        state.dispatch_message(&WebSocketMessage::LidarUpdate { scan: lidar });
+        state.dispatch_message(&WebSocketMessage::LidarUpdate {
+            scan: lidar,
+            sensor_id: None,
+        });
 
         let timeline = state.capture_events(None);
         assert_eq!(timeline.len(), 2);
@@ -11,24 +11,30 @@ use crate::{
         OperatorSession, OperatorSessionError, OperatorSessionRegistry,
         SharedOperatorSessionRegistry,
     },
-    CaptureEvent, LinkStateSnapshot, MapRenderState, SharedLinkState, SharedMessageDispatchState,
-    TelemetryFreshnessSnapshot, TelemetryTileSnapshot,
+    CaptureEvent, LidarScanView, LinkStateSnapshot, MapRenderState, SharedLinkState,
+    SharedMessageDispatchState, TelemetryFreshnessSnapshot, TelemetryTileSnapshot,
+    LIDAR_RENDER_INTERVAL,
 };
 use axum::{
-    extract::{Path, State},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
     http::{header, HeaderMap, StatusCode},
-    response::Html,
+    response::{Html, Response},
     routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use shared::control_plane::MembershipRole;
 use shared::{config::AgroConfig, AgroResult};
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use tracing::info;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info};
+use uuid::Uuid;
 
 pub struct WebServer {
-    #[allow(dead_code)]
     config: Arc<AgroConfig>,
     link_state: SharedLinkState,
     dispatch_state: SharedMessageDispatchState,
@@ -163,7 +169,8 @@ impl WebServer {
             operator_action_state: self.operator_action_state.clone(),
             mission_control_actions: self.mission_control_actions.clone(),
             operator_action_audit_log: self.operator_action_audit_log.clone(),
-        });
+        })
+        .layer(self.config.cors.layer());
 
         let bind_addr = "0.0.0.0:8081"; // Different port from mission control
         let listener = tokio::net::TcpListener::bind(bind_addr).await?;
@@ -183,6 +190,8 @@ fn build_router_with_state(state: WebServerState) -> Router {
         .route("/api/link-state", get(link_state))
         .route("/api/dispatch-state", get(dispatch_state))
         .route("/api/map-state", get(map_state))
+        .route("/api/lidar", get(lidar_scans))
+        .route("/ws/lidar", get(lidar_stream))
         .route("/api/operator/login", post(operator_login))
         .route(
             "/api/operator/actions/session-check",
@@ -195,6 +204,7 @@ fn build_router_with_state(state: WebServerState) -> Router {
         )
         .route("/telemetry", get(telemetry_page))
         .route("/maps", get(maps_page))
+        .route("/lidar", get(lidar_page))
         .nest_service("/static", ServeDir::new("static"))
         .with_state(state)
 }
@@ -217,6 +227,50 @@ async fn map_state(State(state): State<WebServerState>) -> Json<MapRenderState>
     Json(state.dispatch_state.read().await.map_render_state())
 }
 
+async fn lidar_scans(State(state): State<WebServerState>) -> Json<Vec<LidarScanView>> {
+    let dispatch = state.dispatch_state.read().await;
+    Json(dispatch.lidar_scans.values().cloned().collect())
+}
+
+async fn lidar_stream(ws: WebSocketUpgrade, State(state): State<WebServerState>) -> Response {
+    ws.on_upgrade(move |socket| stream_lidar_scans(socket, state.dispatch_state))
+}
+
+/// Pushes each sensor's latest scan at most once per render interval, so a
+/// fast scanner cannot flood the browser; scans that arrive in between are
+/// superseded rather than queued.
+async fn stream_lidar_scans(mut socket: WebSocket, dispatch_state: SharedMessageDispatchState) {
+    let mut interval = tokio::time::interval(LIDAR_RENDER_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut sent_scan_ids: BTreeMap<String, Uuid> = BTreeMap::new();
+
+    loop {
+        interval.tick().await;
+        let changed: Vec<LidarScanView> = dispatch_state
+            .read()
+            .await
+            .lidar_scans
+            .values()
+            .filter(|view| sent_scan_ids.get(&view.sensor_id) != Some(&view.scan_id))
+            .cloned()
+            .collect();
+
+        for view in changed {
+            let payload = match serde_json::to_string(&view) {
+                Ok(payload) => payload,
+                Err(error) => {
+                    debug!("Skipping LiDAR view that failed to encode: {}", error);
+                    continue;
+                }
+            };
+            if socket.send(Message::Text(payload)).await.is_err() {
+                return;
+            }
+            sent_scan_ids.insert(view.sensor_id, view.scan_id);
+        }
+    }
+}
+
 async fn operator_login(
     State(state): State<WebServerState>,
     Json(request): Json<OperatorLoginRequest>,
@@ -448,6 +502,7 @@ async fn dashboard_page() -> Html<&'static str> {
             <a href="/">Dashboard</a>
             <a href="/telemetry">Telemetry</a>
             <a href="/maps">Maps</a>
+            <a href="/lidar">LiDAR</a>
         </div>
 
         <div class="panel">
@@ -542,6 +597,9 @@ async fn dashboard_page() -> Html<&'static str> {
                 case 'NdviProcessed':
                     updateActivity(`NDVI processed: ${msg.result.mean_ndvi.toFixed(3)} mean`);
                     break;
+                case 'OverlayGenerated':
+                    updateActivity(`Overlay ready: ${msg.overlay.overlay_types.join(' + ')}`);
+                    break;
                 default:
                     updateActivity(`System: ${msg.status || 'Unknown event'}`);
             }
@@ -691,6 +749,7 @@ async fn telemetry_page() -> Html<&'static str> {
             <a href="/">Dashboard</a>
             <a href="/telemetry">Telemetry</a>
             <a href="/maps">Maps</a>
+            <a href="/lidar">LiDAR</a>
         </div>
 
         <div class="panel">
@@ -781,6 +840,7 @@ async fn maps_page() -> Html<&'static str> {
             <a href="/">Dashboard</a>
             <a href="/telemetry">Telemetry</a>
             <a href="/maps">Maps</a>
+            <a href="/lidar">LiDAR</a>
         </div>
 
         <div class="panel">
@@ -1001,6 +1061,222 @@ async fn maps_page() -> Html<&'static str> {
     )
 }
 
+async fn lidar_page() -> Html<&'static str> {
+    Html(
+        r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <title>LiDAR - Ground Station</title>
+    <style>
+        body { font-family: Arial, sans-serif; margin: 20px; background: #f0f0f0; color: #17202a; }
+        .container { max-width: 1200px; margin: 0 auto; }
+        .header { background: #2c3e50; color: white; padding: 20px; border-radius: 5px; margin-bottom: 20px; }
+        .panel { background: white; padding: 20px; border-radius: 5px; margin-bottom: 20px; box-shadow: 0 2px 5px rgba(0,0,0,0.1); }
+        .nav { margin-bottom: 20px; }
+        .nav a { margin-right: 20px; text-decoration: none; color: #3498db; }
+        .lidar-toolbar { display: flex; gap: 12px; align-items: center; flex-wrap: wrap; margin-bottom: 14px; }
+        .badge { background: #edf2f7; border: 1px solid #d5dde6; border-radius: 4px; padding: 6px 10px; font-size: 14px; }
+        .badge.ok { background: #e8f6ef; border-color: #a9dfbf; color: #145a32; }
+        .badge.error { background: #fdecea; border-color: #f5b7b1; color: #922b21; }
+        .lidar-frame { border: 1px solid #b8c2cc; background: #10161d; display: flex; justify-content: center; }
+        #lidar-plot { display: block; }
+        .lidar-readout { display: grid; grid-template-columns: repeat(auto-fit, minmax(180px, 1fr)); gap: 8px; margin-top: 12px; }
+        .readout-item { background: #f8fafc; border: 1px solid #e1e7ef; border-radius: 4px; padding: 8px; }
+        .readout-item strong { display: block; font-size: 12px; color: #536271; margin-bottom: 4px; text-transform: uppercase; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>LiDAR</h1>
+        </div>
+
+        <div class="nav">
+            <a href="/">Dashboard</a>
+            <a href="/telemetry">Telemetry</a>
+            <a href="/maps">Maps</a>
+            <a href="/lidar">LiDAR</a>
+        </div>
+
+        <div class="panel">
+            <div class="lidar-toolbar">
+                <span id="lidar-status" class="badge">Connecting</span>
+                <label>Sensor <select id="lidar-sensor"></select></label>
+                <label>Range <select id="lidar-range">
+                    <option value="5">5 m</option>
+                    <option value="10" selected>10 m</option>
+                    <option value="25">25 m</option>
+                </select></label>
+            </div>
+            <div class="lidar-frame">
+                <canvas id="lidar-plot" width="560" height="560"></canvas>
+            </div>
+            <div class="lidar-readout">
+                <div class="readout-item">
+                    <strong>Nearest obstacle</strong>
+                    <span id="nearest-readout">No data</span>
+                </div>
+                <div class="readout-item">
+                    <strong>Points</strong>
+                    <span id="points-readout">No data</span>
+                </div>
+                <div class="readout-item">
+                    <strong>Most blocked sector</strong>
+                    <span id="sector-readout">No data</span>
+                </div>
+                <div class="readout-item">
+                    <strong>Updated</strong>
+                    <span id="timestamp-readout">No data</span>
+                </div>
+            </div>
+        </div>
+    </div>
+    <script>
+        const canvas = document.getElementById('lidar-plot');
+        const ctx = canvas.getContext('2d');
+        const sensorSelect = document.getElementById('lidar-sensor');
+        const rangeSelect = document.getElementById('lidar-range');
+        const views = {};
+
+        function setStatus(text, kind) {
+            const status = document.getElementById('lidar-status');
+            status.textContent = text;
+            status.className = kind ? `badge ${kind}` : 'badge';
+        }
+
+        // Bearing 0 points up and increases clockwise, matching the scanner.
+        function toCanvas(bearingDeg, distanceM, scale) {
+            const radians = bearingDeg * Math.PI / 180;
+            return [
+                canvas.width / 2 + Math.sin(radians) * distanceM * scale,
+                canvas.height / 2 - Math.cos(radians) * distanceM * scale
+            ];
+        }
+
+        function drawSectors(view, rangeM, scale) {
+            view.sectors.forEach((sector) => {
+                if (sector.blocked_count === 0) {
+                    return;
+                }
+                const start = (sector.start_deg - 90) * Math.PI / 180;
+                const end = (sector.end_deg - 90) * Math.PI / 180;
+                ctx.beginPath();
+                ctx.moveTo(canvas.width / 2, canvas.height / 2);
+                ctx.arc(canvas.width / 2, canvas.height / 2, rangeM * scale, start, end);
+                ctx.closePath();
+                ctx.fillStyle = `rgba(231, 76, 60, ${0.08 + 0.4 * sector.blocked_percent / 100})`;
+                ctx.fill();
+            });
+        }
+
+        function drawRings(view, rangeM, scale) {
+            ctx.strokeStyle = '#2e3b48';
+            ctx.lineWidth = 1;
+            ctx.fillStyle = '#7f8c99';
+            ctx.font = '11px Arial';
+            const step = rangeM / 5;
+            for (let ring = 1; ring <= 5; ring++) {
+                ctx.beginPath();
+                ctx.arc(canvas.width / 2, canvas.height / 2, ring * step * scale, 0, 2 * Math.PI);
+                ctx.stroke();
+                ctx.fillText(`${(ring * step).toFixed(1)} m`, canvas.width / 2 + 4, canvas.height / 2 - ring * step * scale + 12);
+            }
+
+            ctx.strokeStyle = '#e67e22';
+            ctx.setLineDash([4, 4]);
+            ctx.beginPath();
+            ctx.arc(canvas.width / 2, canvas.height / 2, Math.min(view.obstacle_threshold_m, rangeM) * scale, 0, 2 * Math.PI);
+            ctx.stroke();
+            ctx.setLineDash([]);
+        }
+
+        function render() {
+            const rangeM = Number(rangeSelect.value);
+            const scale = (canvas.width / 2 - 10) / rangeM;
+            ctx.fillStyle = '#10161d';
+            ctx.fillRect(0, 0, canvas.width, canvas.height);
+
+            const view = views[sensorSelect.value];
+            if (!view) {
+                return;
+            }
+
+            drawSectors(view, rangeM, scale);
+            drawRings(view, rangeM, scale);
+
+            view.plot_points.forEach((point) => {
+                if (point.distance_m > rangeM) {
+                    return;
+                }
+                const [x, y] = toCanvas(point.bearing_deg, point.distance_m, scale);
+                ctx.fillStyle = point.distance_m <= view.obstacle_threshold_m ? '#e74c3c' : '#2ecc71';
+                ctx.fillRect(x - 1.5, y - 1.5, 3, 3);
+            });
+
+            ctx.fillStyle = '#ecf0f1';
+            ctx.beginPath();
+            ctx.moveTo(canvas.width / 2, canvas.height / 2 - 8);
+            ctx.lineTo(canvas.width / 2 - 5, canvas.height / 2 + 6);
+            ctx.lineTo(canvas.width / 2 + 5, canvas.height / 2 + 6);
+            ctx.closePath();
+            ctx.fill();
+
+            const nearest = view.nearest;
+            document.getElementById('nearest-readout').textContent = nearest
+                ? `${nearest.distance_m.toFixed(2)} m at ${nearest.bearing_deg.toFixed(0)}°`
+                : 'No returns';
+            document.getElementById('points-readout').textContent =
+                `${view.point_count} kept, ${view.dropped_point_count} dropped`;
+            const worst = view.sectors.reduce(
+                (best, sector) => (!best || sector.blocked_percent > best.blocked_percent ? sector : best),
+                null
+            );
+            document.getElementById('sector-readout').textContent = worst && worst.blocked_count > 0
+                ? `${worst.start_deg.toFixed(0)}–${worst.end_deg.toFixed(0)}°: ${worst.blocked_percent.toFixed(0)}% blocked`
+                : 'Clear';
+            document.getElementById('timestamp-readout').textContent =
+                new Date(view.timestamp).toLocaleTimeString();
+        }
+
+        function acceptView(view) {
+            if (!views[view.sensor_id]) {
+                const option = document.createElement('option');
+                option.value = view.sensor_id;
+                option.textContent = view.sensor_id;
+                sensorSelect.appendChild(option);
+            }
+            views[view.sensor_id] = view;
+            if (!sensorSelect.value) {
+                sensorSelect.value = view.sensor_id;
+            }
+            if (view.sensor_id === sensorSelect.value) {
+                render();
+            }
+        }
+
+        function connect() {
+            const scheme = window.location.protocol === 'https:' ? 'wss' : 'ws';
+            const socket = new WebSocket(`${scheme}://${window.location.host}/ws/lidar`);
+            socket.onopen = () => setStatus('Live', 'ok');
+            socket.onmessage = (event) => acceptView(JSON.parse(event.data));
+            socket.onclose = () => {
+                setStatus('Reconnecting', 'error');
+                setTimeout(connect, 2000);
+            };
+        }
+
+        sensorSelect.addEventListener('change', render);
+        rangeSelect.addEventListener('change', render);
+        render();
+        connect();
+    </script>
+</body>
+</html>
+"#,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1018,6 +1294,7 @@ mod tests {
     };
     use serde_json::json;
     use shared::control_plane::{MembershipRole, TenantPrincipal};
+    use shared::schemas::{LidarPoint, LidarScan, WebSocketMessage};
     use std::sync::{Arc, Mutex};
     use tower::ServiceExt;
     use uuid::Uuid;
@@ -1422,6 +1699,92 @@ mod tests {
         assert_eq!(client.recorded_requests().len(), 1);
     }
 
+    #[tokio::test]
+    async fn cors_preflight_allows_listed_origin_and_rejects_others() {
+        let (state, _) = test_state(MembershipRole::Operator, "secret", 15);
+        let cors = shared::config::CorsConfig {
+            allowed_origins: vec!["https://ops.example.com".to_string()],
+            ..Default::default()
+        };
+        let app = build_router_with_state(state).layer(cors.layer());
+
+        let preflight = |origin: &'static str| {
+            Request::builder()
+                .method("OPTIONS")
+                .uri("/api/link-state")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .body(Body::empty())
+                .expect("request should build")
+        };
+
+        let allowed = app
+            .clone()
+            .oneshot(preflight("https://ops.example.com"))
+            .await
+            .expect("router should handle preflight");
+        assert_eq!(
+            allowed
+                .headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .and_then(|value| value.to_str().ok()),
+            Some("https://ops.example.com")
+        );
+
+        let rejected = app
+            .oneshot(preflight("https://evil.example.com"))
+            .await
+            .expect("router should handle preflight");
+        assert!(rejected
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn lidar_endpoint_returns_the_latest_view_per_sensor() {
+        let (state, _) = test_state(MembershipRole::Operator, "secret", 15);
+        let timestamp = chrono::Utc::now();
+        let scan = LidarScan {
+            timestamp,
+            points: vec![LidarPoint {
+                timestamp,
+                angle: 90.0,
+                distance: 1.5,
+                quality: 80,
+            }],
+            scan_id: Uuid::new_v4(),
+        };
+        state
+            .dispatch_state
+            .write()
+            .await
+            .dispatch_message(&WebSocketMessage::LidarUpdate {
+                scan,
+                sensor_id: Some("front".to_string()),
+            });
+        let app = build_router_with_state(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/lidar")
+                    .body(Body::empty())
+                    .expect("request should build"),
+            )
+            .await
+            .expect("router should handle lidar request");
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), 64 * 1024)
+            .await
+            .expect("body should read");
+        let views: serde_json::Value =
+            serde_json::from_slice(&body).expect("lidar response should decode");
+        assert_eq!(views[0]["sensor_id"], "front");
+        assert_eq!(views[0]["nearest"]["bearing_deg"], 90.0);
+        assert_eq!(views[0]["nearest"]["distance_m"], 1.5);
+    }
+
     fn test_state(
         role: MembershipRole,
         credential: &str,
@@ -6,6 +6,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use shared::{
+    artifact_schema::from_artifact_str,
     error::AgroError,
     schemas::{
         assert_raster_spatial_ref, GeoBounds, MultispectralImage, RasterResolution,
@@ -176,6 +177,96 @@ pub async fn write_geotiff_spatial_sidecar(
     Ok(sidecar_path)
 }
 
+/// Writes `values` as a single-band 32-bit float GeoTIFF without GDAL,
+/// georeferenced from the transform and EPSG code in `spatial_ref`.
+pub fn write_float_geotiff(
+    product_path: &Path,
+    values: &[f32],
+    width: u32,
+    height: u32,
+    nodata: f32,
+    spatial_ref: &RasterSpatialRef,
+) -> AgroResult<()> {
+    use tiff::encoder::{colortype::Gray32Float, TiffEncoder};
+    use tiff::tags::Tag;
+
+    let tiff_error = |err: tiff::TiffError| AgroError::Processing(format!("Write GeoTIFF: {err}"));
+    let sidecar = GeoTiffSpatialSidecar::from_spatial_ref(spatial_ref)?;
+    let [origin_x, pixel_x, row_rotation, origin_y, column_rotation, pixel_y] =
+        sidecar.geo_transform;
+
+    let file = std::io::BufWriter::new(std::fs::File::create(product_path)?);
+    let mut encoder = TiffEncoder::new(file).map_err(tiff_error)?;
+    let mut image = encoder
+        .new_image::<Gray32Float>(width, height)
+        .map_err(tiff_error)?;
+    let directory = image.encoder();
+    if row_rotation == 0.0 && column_rotation == 0.0 {
+        directory
+            .write_tag(Tag::ModelPixelScaleTag, &[pixel_x, -pixel_y, 0.0][..])
+            .map_err(tiff_error)?;
+        directory
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0, 0.0, 0.0, origin_x, origin_y, 0.0][..],
+            )
+            .map_err(tiff_error)?;
+    } else {
+        // ModelTransformationTag, for rotated grids.
+        #[rustfmt::skip]
+        let transformation = [
+            pixel_x, row_rotation, 0.0, origin_x,
+            column_rotation, pixel_y, 0.0, origin_y,
+            0.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ];
+        directory
+            .write_tag(Tag::Unknown(34264), &transformation[..])
+            .map_err(tiff_error)?;
+    }
+    directory
+        .write_tag(
+            Tag::GeoKeyDirectoryTag,
+            &geo_key_directory(&sidecar.crs)[..],
+        )
+        .map_err(tiff_error)?;
+    directory
+        .write_tag(Tag::GdalNodata, nodata.to_string().as_str())
+        .map_err(tiff_error)?;
+    image.write_data(values).map_err(tiff_error)?;
+    Ok(())
+}
+
+/// GeoKey directory for an `EPSG:<code>` CRS. Codes 4000-4999 are taken as
+/// geographic, everything else as projected; a CRS without an EPSG code only
+/// gets the raster type, and the spatial sidecar carries the full CRS.
+fn geo_key_directory(crs: &str) -> Vec<u16> {
+    const MODEL_TYPE: u16 = 1024;
+    const RASTER_TYPE: u16 = 1025;
+    const GEOGRAPHIC_TYPE: u16 = 2048;
+    const PROJECTED_CS_TYPE: u16 = 3072;
+    const PIXEL_IS_AREA: u16 = 1;
+
+    let mut keys = vec![[RASTER_TYPE, 0, 1, PIXEL_IS_AREA]];
+    let epsg = crs
+        .strip_prefix("EPSG:")
+        .and_then(|code| code.parse::<u16>().ok());
+    if let Some(code) = epsg {
+        if (4000..5000).contains(&code) {
+            keys.push([MODEL_TYPE, 0, 1, 2]);
+            keys.push([GEOGRAPHIC_TYPE, 0, 1, code]);
+        } else {
+            keys.push([MODEL_TYPE, 0, 1, 1]);
+            keys.push([PROJECTED_CS_TYPE, 0, 1, code]);
+        }
+    }
+    keys.sort();
+
+    let mut directory = vec![1, 1, 0, keys.len() as u16];
+    directory.extend(keys.into_iter().flatten());
+    directory
+}
+
 fn geotiff_spatial_ref_error(message: &str) -> AgroError {
     AgroError::Processing(format!("GeoTIFF spatial sidecar error: {message}"))
 }
@@ -376,6 +467,76 @@ pub enum BandIngestError {
     EvidenceWrite { path: PathBuf, message: String },
 }
 
+impl From<BandIngestError> for AgroError {
+    fn from(error: BandIngestError) -> Self {
+        match error {
+            BandIngestError::MissingRequiredBand { band_name } => AgroError::MissingBand {
+                role: "required".to_string(),
+                band: band_name,
+            },
+            BandIngestError::DimensionMismatch {
+                band_name,
+                expected_width,
+                expected_height,
+                actual_width,
+                actual_height,
+            } => AgroError::dimension_mismatch(
+                format!("band '{band_name}'"),
+                (expected_width, expected_height),
+                (actual_width, actual_height),
+            ),
+            BandIngestError::MetadataParse { path, message } => AgroError::DecodeError {
+                path: path.display().to_string(),
+                reason: message,
+            },
+            BandIngestError::RasterInspect {
+                band_name,
+                path,
+                message,
+            } => AgroError::DecodeError {
+                path: path.display().to_string(),
+                reason: format!("band '{band_name}': {message}"),
+            },
+            error @ (BandIngestError::MetadataRead { .. }
+            | BandIngestError::EvidenceWrite { .. }) => {
+                AgroError::Io(std::io::Error::other(error.to_string()))
+            }
+            error @ BandIngestError::SpatialRefInvalid { .. } => {
+                AgroError::Processing(error.to_string())
+            }
+        }
+    }
+}
+
+/// An image that could not be opened: an I/O failure, or a file the decoder
+/// rejected.
+pub fn image_open_error(path: &str, error: image::ImageError) -> AgroError {
+    match error {
+        image::ImageError::IoError(error) => AgroError::Io(error),
+        other => AgroError::DecodeError {
+            path: path.to_string(),
+            reason: other.to_string(),
+        },
+    }
+}
+
+/// The error for a batch run from its per-metadata-file failures. A single
+/// failure is returned as is so callers can still match on its variant.
+pub fn batch_failure(mut failures: Vec<(PathBuf, AgroError)>) -> Option<AgroError> {
+    if failures.len() <= 1 {
+        return failures.pop().map(|(_, error)| error);
+    }
+    let details = failures
+        .iter()
+        .map(|(path, error)| format!("{}: {error}", path.display()))
+        .collect::<Vec<_>>();
+    Some(AgroError::Processing(format!(
+        "{} metadata file(s) failed: {}",
+        failures.len(),
+        details.join("; ")
+    )))
+}
+
 pub async fn load_multispectral_metadata(
     metadata_path: &Path,
 ) -> Result<MultispectralImage, BandIngestError> {
@@ -386,7 +547,7 @@ pub async fn load_multispectral_metadata(
             message: err.to_string(),
         })?;
 
-    serde_json::from_str(&metadata_content).map_err(|err| BandIngestError::MetadataParse {
+    from_artifact_str(&metadata_content).map_err(|err| BandIngestError::MetadataParse {
         path: metadata_path.to_path_buf(),
         message: err.to_string(),
     })
@@ -457,13 +618,17 @@ pub fn resolve_band_ingest_evidence(
         resolved_bands.insert("red_edge".to_string(), red_edge);
     }
 
-    resolve_band_ingest_evidence_for_resolved_bands(image, sensor, resolved_bands)
+    resolve_band_ingest_evidence_for_resolved_bands(image, sensor, resolved_bands, false)
 }
 
+/// Ingest evidence for already-resolved band names. Bands whose size differs
+/// from the image are rejected unless `allow_mismatched_bands` is set, in
+/// which case their actual size is recorded for the caller to resample.
 pub fn resolve_band_ingest_evidence_for_resolved_bands(
     image: &MultispectralImage,
     sensor: Option<SensorPreset>,
     resolved_bands: BTreeMap<String, String>,
+    allow_mismatched_bands: bool,
 ) -> Result<BandIngestEvidence, BandIngestError> {
     let mut band_index_to_name: BTreeMap<usize, String> = image
         .metadata
@@ -488,8 +653,14 @@ pub fn resolve_band_ingest_evidence_for_resolved_bands(
         }
     }
 
-    let band_grids = inspect_band_grids(&image, image.metadata.width, image.metadata.height)?;
-    let radiometric_calibration = radiometric_calibration_evidence(sensor, &band_index_to_name);
+    let band_grids = inspect_band_grids(
+        image,
+        image.metadata.width,
+        image.metadata.height,
+        allow_mismatched_bands,
+    )?;
+    let radiometric_calibration =
+        radiometric_calibration_evidence(sensor, &band_index_to_name, &band_grids);
     let spatial_ref = assert_raster_spatial_ref(
         image.metadata.spatial_ref.as_ref(),
         image.metadata.width,
@@ -498,8 +669,12 @@ pub fn resolve_band_ingest_evidence_for_resolved_bands(
     .map_err(|err| BandIngestError::SpatialRefInvalid {
         message: err.to_string(),
     })?;
-    let ingest_quality =
-        build_ingest_quality_evidence(image, DEFAULT_VALID_PIXEL_FLOOR, Utc::now())?;
+    let ingest_quality = build_ingest_quality_evidence(
+        image,
+        DEFAULT_VALID_PIXEL_FLOOR,
+        Utc::now(),
+        allow_mismatched_bands,
+    )?;
 
     Ok(BandIngestEvidence {
         image_id: image.image_id,
@@ -519,8 +694,10 @@ fn build_ingest_quality_evidence(
     image: &MultispectralImage,
     coverage_floor: f32,
     ingested_at: DateTime<Utc>,
+    allow_mismatched_bands: bool,
 ) -> Result<ImageIngestQualityEvidence, BandIngestError> {
-    let (valid_pixel_count, total_pixel_count) = count_valid_image_pixels(image)?;
+    let (valid_pixel_count, total_pixel_count) =
+        count_valid_image_pixels(image, allow_mismatched_bands)?;
     let valid_pixel_fraction = if total_pixel_count == 0 {
         0.0
     } else {
@@ -557,7 +734,11 @@ fn normalize_coverage_floor(coverage_floor: f32) -> f32 {
     }
 }
 
-fn count_valid_image_pixels(image: &MultispectralImage) -> Result<(usize, usize), BandIngestError> {
+fn count_valid_image_pixels(
+    image: &MultispectralImage,
+    allow_mismatched_bands: bool,
+) -> Result<(usize, usize), BandIngestError> {
+    let (width, height) = (image.metadata.width, image.metadata.height);
     let total_pixel_count = (image.metadata.width * image.metadata.height) as usize;
     let mut valid_pixels = vec![false; total_pixel_count];
     let mut band_names = if image.metadata.bands.is_empty() {
@@ -581,22 +762,35 @@ fn count_valid_image_pixels(image: &MultispectralImage) -> Result<(usize, usize)
         })?;
         let actual_width = band.width();
         let actual_height = band.height();
-        if actual_width != image.metadata.width || actual_height != image.metadata.height {
+        if (actual_width, actual_height) == (width, height) {
+            let gray = band.to_luma16();
+            for (index, pixel) in gray.pixels().enumerate() {
+                if pixel[0] != 0 {
+                    valid_pixels[index] = true;
+                }
+            }
+        } else if allow_mismatched_bands {
+            // Count coverage on the image grid by nearest source pixel.
+            let gray = band.to_luma16();
+            for y in 0..height {
+                let source_y = (u64::from(y) * u64::from(actual_height) / u64::from(height)) as u32;
+                for x in 0..width {
+                    let source_x =
+                        (u64::from(x) * u64::from(actual_width) / u64::from(width)) as u32;
+                    if gray.get_pixel(source_x, source_y)[0] != 0 {
+                        valid_pixels[(y * width + x) as usize] = true;
+                    }
+                }
+            }
+        } else {
             return Err(BandIngestError::DimensionMismatch {
                 band_name: band_name.clone(),
-                expected_width: image.metadata.width,
-                expected_height: image.metadata.height,
+                expected_width: width,
+                expected_height: height,
                 actual_width,
                 actual_height,
             });
         }
-
-        let gray = band.to_luma16();
-        for (index, pixel) in gray.pixels().enumerate() {
-            if pixel[0] != 0 {
-                valid_pixels[index] = true;
-            }
-        }
     }
 
     let valid_pixel_count = valid_pixels.iter().filter(|is_valid| **is_valid).count();
@@ -606,6 +800,7 @@ fn count_valid_image_pixels(image: &MultispectralImage) -> Result<(usize, usize)
 fn radiometric_calibration_evidence(
     sensor: Option<SensorPreset>,
     band_index_to_name: &BTreeMap<usize, String>,
+    band_grids: &BTreeMap<String, BandGridEvidence>,
 ) -> RadiometricCalibrationEvidence {
     match sensor {
         Some(SensorPreset::Sentinel2) | Some(SensorPreset::Landsat8) => {
@@ -615,7 +810,7 @@ fn radiometric_calibration_evidence(
                     (
                         band_name.clone(),
                         BandCalibrationCoefficients {
-                            gain: 1.0 / 255.0,
+                            gain: 1.0 / full_scale_dn(band_grids.get(band_name)),
                             offset: 0.0,
                             output_min: 0.0,
                             output_max: 1.0,
@@ -636,10 +831,19 @@ fn radiometric_calibration_evidence(
     }
 }
 
+/// Largest digital number a band of the inspected type can hold.
+fn full_scale_dn(grid: Option<&BandGridEvidence>) -> f32 {
+    match grid.map(|grid| grid.dtype.as_str()) {
+        Some("L16") => f32::from(u16::MAX),
+        _ => f32::from(u8::MAX),
+    }
+}
+
 fn inspect_band_grids(
     image: &MultispectralImage,
     expected_width: u32,
     expected_height: u32,
+    allow_mismatched_bands: bool,
 ) -> Result<BTreeMap<String, BandGridEvidence>, BandIngestError> {
     let mut band_names = if image.metadata.bands.is_empty() {
         image.file_paths.keys().cloned().collect::<Vec<_>>()
@@ -663,7 +867,9 @@ fn inspect_band_grids(
         })?;
         let actual_width = band.width();
         let actual_height = band.height();
-        if actual_width != expected_width || actual_height != expected_height {
+        if !allow_mismatched_bands
+            && (actual_width != expected_width || actual_height != expected_height)
+        {
             return Err(BandIngestError::DimensionMismatch {
                 band_name: band_name.clone(),
                 expected_width,
@@ -972,7 +1178,7 @@ mod tests {
         let image = load_multispectral_metadata(&metadata_path).await.unwrap();
         let ingested_at = chrono::Utc.with_ymd_and_hms(2026, 1, 1, 0, 5, 0).unwrap();
 
-        let quality = build_ingest_quality_evidence(&image, 0.95, ingested_at).unwrap();
+        let quality = build_ingest_quality_evidence(&image, 0.95, ingested_at, false).unwrap();
 
         assert_eq!(quality.capture_time, image.metadata.timestamp);
         assert_eq!(quality.ingested_at, ingested_at);
@@ -1003,7 +1209,7 @@ mod tests {
         let image = load_multispectral_metadata(&metadata_path).await.unwrap();
         let ingested_at = chrono::Utc.with_ymd_and_hms(2026, 1, 1, 0, 10, 0).unwrap();
 
-        let quality = build_ingest_quality_evidence(&image, 0.5, ingested_at).unwrap();
+        let quality = build_ingest_quality_evidence(&image, 0.5, ingested_at, false).unwrap();
 
         assert_eq!(quality.total_pixel_count, 4);
         assert_eq!(quality.valid_pixel_count, 1);
@@ -74,6 +74,17 @@ pub struct IndicesArgs {
     /// Optional mask image path (non-zero = valid). Applied before stats.
     #[arg(long)]
     pub mask: Option<PathBuf>,
+    /// Multiply raw band values by this before calibration, for sensors
+    /// that store reflectance as scaled integers (e.g. 0.0001)
+    #[arg(long)]
+    pub band_scale_factor: Option<f32>,
+    /// Also write the index values as a 32-bit float GeoTIFF for analysis
+    #[arg(long)]
+    pub float_geotiff: bool,
+    /// Resample bands whose size differs from the image's onto its grid with
+    /// this method instead of failing
+    #[arg(long, value_enum)]
+    pub resample: Option<ResampleMethod>,
 }
 
 #[derive(ClapArgs, Debug)]
@@ -492,6 +503,12 @@ pub enum OutputFormat {
     Geotiff,
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, ValueEnum, Debug)]
+pub enum ResampleMethod {
+    Nearest,
+    Bilinear,
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, ValueEnum, Debug)]
 pub enum TemperatureUnit {
     Kelvin,
@@ -626,6 +643,9 @@ pub struct IndexResultMeta {
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub source_images: Vec<uuid::Uuid>,
     pub output_path: String,
+    /// The 32-bit float GeoTIFF, when one was requested.
+    #[serde(default)]
+    pub float_geotiff_path: Option<String>,
     pub index: String,
     pub min: f32,
     pub max: f32,
@@ -1,19 +1,20 @@
 use anyhow::Context;
-use image::GrayImage;
-use shared::{error::AgroError, schemas::MultispectralImage, AgroResult};
+use image::{DynamicImage, GrayImage};
+use shared::{error::AgroError, schemas::MultispectralImage, AgroResult, OutputManifest};
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 use tracing::{error, info};
 
 use crate::{
     IndexBandRole, IndexBandValues, IndexPixelValue, IndexResultMeta, IndexStatisticsOutcome,
-    IndicesArgs, OutputFormat,
+    IndicesArgs, OutputFormat, ResampleMethod,
 };
 
 const NODATA_F32: f32 = -9999.0;
 
 pub async fn run_indices(args: &IndicesArgs) -> AgroResult<()> {
     tokio::fs::create_dir_all(&args.output_dir).await?;
+    OutputManifest::remove(&args.output_dir)?;
 
     let mut metadata_files = Vec::new();
     for entry in walkdir::WalkDir::new(&args.input_dir) {
@@ -28,21 +29,43 @@ pub async fn run_indices(args: &IndicesArgs) -> AgroResult<()> {
     info!(count = metadata_files.len(), index = ?args.index, "Found metadata files");
 
     let mut failures = Vec::new();
+    let mut processed = Vec::new();
     for mf in metadata_files {
-        if let Err(e) = process_one(&mf, args).await {
-            error!(file=%mf.display(), error=%e, "Failed processing");
-            failures.push(format!("{}: {}", mf.display(), e));
+        match process_one(&mf, args).await {
+            Ok(inputs) => processed.push(inputs),
+            Err(e) => {
+                error!(file=%mf.display(), error=%e, "Failed processing");
+                failures.push((mf, e));
+            }
         }
     }
 
-    if !failures.is_empty() {
-        return Err(AgroError::Processing(format!(
-            "{} metadata file(s) failed: {}",
-            failures.len(),
-            failures.join("; ")
-        )));
+    if let Some(err) = crate::io::batch_failure(failures) {
+        return Err(err);
     }
 
+    write_output_manifest(args, &processed)?;
+    Ok(())
+}
+
+/// The files one metadata document's outputs were computed from, and the
+/// files written for it.
+struct ImageInputs {
+    source_inputs: Vec<String>,
+    outputs: Vec<PathBuf>,
+}
+
+/// Lists the files this run wrote in `manifest.json`.
+fn write_output_manifest(args: &IndicesArgs, processed: &[ImageInputs]) -> AgroResult<()> {
+    let written = processed.iter().flat_map(|inputs| {
+        inputs
+            .outputs
+            .iter()
+            .map(|path| (path.clone(), inputs.source_inputs.clone()))
+    });
+    let manifest = OutputManifest::from_written("imagery_processor", &args.output_dir, written)?;
+    let manifest_path = manifest.write(&args.output_dir)?;
+    info!(files = manifest.files.len(), manifest = %manifest_path.display(), "Wrote output manifest");
     Ok(())
 }
 
@@ -59,29 +82,115 @@ fn require_band_path<'a>(
         .file_paths
         .get(band_name)
         .map(String::as_str)
-        .ok_or_else(|| processing_error(format!("{role} band '{band_name}' not found")))
+        .ok_or_else(|| AgroError::MissingBand {
+            role: role.to_string(),
+            band: band_name.to_string(),
+        })
 }
 
-fn load_luma_band(
+/// Raw values of a single-channel band at its native bit depth, resampled
+/// onto `expected_dimensions` when `resample` is set and the sizes differ.
+fn load_band_samples(
     image: &MultispectralImage,
     band_name: &str,
     role: &str,
     expected_dimensions: (u32, u32),
-) -> AgroResult<GrayImage> {
+    resample: Option<ResampleMethod>,
+) -> AgroResult<Vec<f32>> {
     let band_path = require_band_path(image, band_name, role)?;
-    let band = image::open(band_path)
-        .map_err(|e| processing_error(format!("Failed to load {role} band: {e}")))?
-        .to_luma8();
+    let band = image::open(band_path).map_err(|e| crate::io::image_open_error(band_path, e))?;
+
+    let dimensions = (band.width(), band.height());
+    let samples = match band {
+        DynamicImage::ImageLuma8(band) => band.pixels().map(|p| f32::from(p[0])).collect(),
+        DynamicImage::ImageLuma16(band) => band.pixels().map(|p| f32::from(p[0])).collect(),
+        other => {
+            return Err(AgroError::DecodeError {
+                path: band_path.to_string(),
+                reason: format!(
+                "{role} band '{band_name}' is {:?}; expected a single-channel 8- or 16-bit raster",
+                other.color()
+            ),
+            })
+        }
+    };
+    fit_band_to_grid(samples, role, dimensions, expected_dimensions, resample)
+}
 
-    if band.dimensions() != expected_dimensions {
-        return Err(processing_error(format!(
-            "{role} band dimensions mismatch: expected {:?}, got {:?}",
-            expected_dimensions,
-            band.dimensions()
-        )));
+/// `values` unchanged when the band already matches the image grid, else
+/// resampled onto it with `resample`, or a dimension mismatch without one.
+fn fit_band_to_grid(
+    values: Vec<f32>,
+    role: &str,
+    dimensions: (u32, u32),
+    expected_dimensions: (u32, u32),
+    resample: Option<ResampleMethod>,
+) -> AgroResult<Vec<f32>> {
+    if dimensions == expected_dimensions {
+        return Ok(values);
     }
+    let Some(method) = resample else {
+        return Err(AgroError::dimension_mismatch(
+            format!("{role} band"),
+            expected_dimensions,
+            dimensions,
+        ));
+    };
+    info!(
+        band = role,
+        from = %format!("{}x{}", dimensions.0, dimensions.1),
+        to = %format!("{}x{}", expected_dimensions.0, expected_dimensions.1),
+        factor_x = expected_dimensions.0 as f32 / dimensions.0 as f32,
+        factor_y = expected_dimensions.1 as f32 / dimensions.1 as f32,
+        ?method,
+        "Resampled band onto the image grid"
+    );
+    Ok(resample_band(
+        &values,
+        dimensions,
+        expected_dimensions,
+        method,
+    ))
+}
 
-    Ok(band)
+/// Samples `values`, a `from`-sized raster, at the pixel centres of a
+/// `to`-sized raster covering the same extent.
+fn resample_band(
+    values: &[f32],
+    from: (u32, u32),
+    to: (u32, u32),
+    method: ResampleMethod,
+) -> Vec<f32> {
+    let (from_width, from_height) = (from.0 as usize, from.1 as usize);
+    let source_coordinate = |target: u32, target_len: u32, source_len: usize| {
+        (f64::from(target) + 0.5) * source_len as f64 / f64::from(target_len) - 0.5
+    };
+    let at = |x: usize, y: usize| values[y * from_width + x];
+
+    let mut resampled = Vec::with_capacity(to.0 as usize * to.1 as usize);
+    for y in 0..to.1 {
+        let source_y = source_coordinate(y, to.1, from_height).clamp(0.0, (from_height - 1) as f64);
+        for x in 0..to.0 {
+            let source_x =
+                source_coordinate(x, to.0, from_width).clamp(0.0, (from_width - 1) as f64);
+            resampled.push(match method {
+                ResampleMethod::Nearest => at(source_x.round() as usize, source_y.round() as usize),
+                ResampleMethod::Bilinear => {
+                    let (left, top) = (source_x.floor() as usize, source_y.floor() as usize);
+                    let right = (left + 1).min(from_width - 1);
+                    let bottom = (top + 1).min(from_height - 1);
+                    let (dx, dy) = (
+                        (source_x - left as f64) as f32,
+                        (source_y - top as f64) as f32,
+                    );
+                    let upper = at(left, top) * (1.0 - dx) + at(right, top) * dx;
+                    let lower = at(left, bottom) * (1.0 - dx) + at(right, bottom) * dx;
+                    upper * (1.0 - dy) + lower * dy
+                }
+            });
+        }
+    }
+    resampled
 }
 
 fn valid_mask_at(mask_img: &Option<GrayImage>, x: u32, y: u32) -> bool {
@@ -96,9 +205,11 @@ fn record_invalid_pixel(reasons: &mut BTreeMap<String, usize>, reason: &'static
 
 fn calibrated_band_value(
     raw_value: f32,
+    scale_factor: Option<f32>,
     band_name: &str,
     calibration: &crate::io::RadiometricCalibrationEvidence,
 ) -> f32 {
+    let raw_value = raw_value * scale_factor.unwrap_or(1.0);
     calibration
         .coefficients
         .get(band_name)
@@ -290,6 +401,8 @@ fn load_index_band(
     band_name: &str,
     role: IndexBandRole,
     expected_dimensions: (u32, u32),
+    scale_factor: Option<f32>,
+    resample: Option<ResampleMethod>,
     calibration: &crate::io::RadiometricCalibrationEvidence,
 ) -> AgroResult<LoadedIndexBand> {
     #[cfg(feature = "gdal-io")]
@@ -300,52 +413,61 @@ fn load_index_band(
                 crate::io::gdal_util::read_first_band_as_f32(band_path).map_err(|err| {
                     processing_error(format!("GDAL read {} failed: {err}", role.label()))
                 })?;
-            if (width as u32, height as u32) != expected_dimensions {
-                return Err(processing_error(format!(
-                    "{} band dimensions mismatch: expected {:?}, got {:?}",
-                    role.label(),
-                    expected_dimensions,
-                    (width as u32, height as u32)
-                )));
-            }
-
+            // Nodata becomes NaN before resampling so bilinear samples that
+            // touch a nodata pixel come out invalid rather than blended.
+            let raw_values = raw_values
+                .into_iter()
+                .map(|value| match nodata {
+                    Some(nodata) if value as f64 == nodata => f32::NAN,
+                    _ => value,
+                })
+                .collect();
+            let raw_values = fit_band_to_grid(
+                raw_values,
+                role.label(),
+                (width as u32, height as u32),
+                expected_dimensions,
+                resample,
+            )?;
             let valid = raw_values
                 .iter()
-                .map(|value| nodata.map_or(true, |nodata| (*value as f64) != nodata))
+                .map(|value| !value.is_nan())
                 .collect::<Vec<_>>();
             let values = raw_values
                 .into_iter()
-                .map(|value| calibrated_band_value(value, band_name, calibration))
+                .map(|value| calibrated_band_value(value, scale_factor, band_name, calibration))
                 .collect::<Vec<_>>();
 
             return Ok(LoadedIndexBand { values, valid });
         }
     }
 
-    let band = load_luma_band(image, band_name, role.label(), expected_dimensions)?;
-    let values = band
-        .pixels()
-        .map(|pixel| calibrated_band_value(pixel[0] as f32, band_name, calibration))
-        .collect::<Vec<_>>();
+    let values = load_band_samples(
+        image,
+        band_name,
+        role.label(),
+        expected_dimensions,
+        resample,
+    )?
+    .into_iter()
+    .map(|value| calibrated_band_value(value, scale_factor, band_name, calibration))
+    .collect::<Vec<_>>();
     let valid = vec![true; values.len()];
 
     Ok(LoadedIndexBand { values, valid })
 }
 
-async fn process_one(metadata_file: &PathBuf, args: &IndicesArgs) -> AgroResult<()> {
-    let image = crate::io::load_multispectral_metadata(metadata_file)
-        .await
-        .map_err(|err| processing_error(err.to_string()))?;
+async fn process_one(metadata_file: &PathBuf, args: &IndicesArgs) -> AgroResult<ImageInputs> {
+    let image = crate::io::load_multispectral_metadata(metadata_file).await?;
     let resolved_bands = resolved_index_band_names(args);
     let evidence = crate::io::resolve_band_ingest_evidence_for_resolved_bands(
         &image,
         args.sensor,
         resolved_bands,
-    )
-    .map_err(|err| processing_error(err.to_string()))?;
-    crate::io::write_band_ingest_evidence(&args.output_dir, &evidence)
-        .await
-        .map_err(|err| processing_error(err.to_string()))?;
+        args.resample.is_some(),
+    )?;
+    let mut outputs =
+        vec![crate::io::write_band_ingest_evidence(&args.output_dir, &evidence).await?];
 
     let (width, height) = (image.metadata.width, image.metadata.height);
     let mut loaded_bands = BTreeMap::new();
@@ -366,6 +488,8 @@ async fn process_one(metadata_file: &PathBuf, args: &IndicesArgs) -> AgroResult<
             &band_name,
             *role,
             (width, height),
+            args.band_scale_factor,
+            args.resample,
             &evidence.radiometric_calibration,
         )?;
         loaded_bands.insert(*role, band);
@@ -381,14 +505,14 @@ async fn process_one(metadata_file: &PathBuf, args: &IndicesArgs) -> AgroResult<
     // Optional mask: non-zero means valid pixel
     let mask_img = if let Some(mask_path) = &args.mask {
         let mask = image::open(mask_path)
-            .map_err(|e| processing_error(format!("Failed to load mask: {}", e)))?
+            .map_err(|e| crate::io::image_open_error(&mask_path.to_string_lossy(), e))?
             .to_luma8();
         if mask.dimensions() != (width, height) {
-            return Err(processing_error(format!(
-                "Mask dimensions mismatch: expected {:?}, got {:?}",
+            return Err(AgroError::dimension_mismatch(
+                "Mask",
                 (width, height),
-                mask.dimensions()
-            )));
+                mask.dimensions(),
+            ));
         }
         Some(mask)
     } else {
@@ -463,7 +587,8 @@ async fn process_one(metadata_file: &PathBuf, args: &IndicesArgs) -> AgroResult<
             out.save(&p).map_err(|e| {
                 shared::error::AgroError::Processing(format!("Failed to save index image: {}", e))
             })?;
-            crate::io::write_png_spatial_sidecar(&p, Some(&evidence.spatial_ref)).await?;
+            outputs
+                .push(crate::io::write_png_spatial_sidecar(&p, Some(&evidence.spatial_ref)).await?);
             p
         }
         OutputFormat::Geotiff => {
@@ -507,7 +632,7 @@ async fn process_one(metadata_file: &PathBuf, args: &IndicesArgs) -> AgroResult<
                 }
                 crate::io::gdal_util::apply_spatial_ref(
                     p.to_string_lossy().as_ref(),
-                    &ingest.evidence.spatial_ref,
+                    &evidence.spatial_ref,
                 )
                 .map_err(|e| {
                     shared::error::AgroError::Processing(format!(
@@ -515,7 +640,9 @@ async fn process_one(metadata_file: &PathBuf, args: &IndicesArgs) -> AgroResult<
                         e
                     ))
                 })?;
-                crate::io::write_geotiff_spatial_sidecar(&p, &ingest.evidence.spatial_ref).await?;
+                outputs.push(
+                    crate::io::write_geotiff_spatial_sidecar(&p, &evidence.spatial_ref).await?,
+                );
                 p
             }
             #[cfg(not(feature = "gdal-io"))]
@@ -527,11 +654,43 @@ async fn process_one(metadata_file: &PathBuf, args: &IndicesArgs) -> AgroResult<
         }
     };
 
+    let float_geotiff_path = match (&out_f32, args.float_geotiff) {
+        (Some(values), true) => {
+            let out_name = format!(
+                "{}_{}_{}_f32.tif",
+                image.metadata.timestamp.format("%Y%m%d_%H%M%S"),
+                image.image_id,
+                format!("{:?}", args.index).to_lowercase()
+            );
+            let p = args.output_dir.join(out_name);
+            crate::io::write_float_geotiff(
+                &p,
+                values,
+                width,
+                height,
+                NODATA_F32,
+                &evidence.spatial_ref,
+            )?;
+            outputs
+                .push(crate::io::write_geotiff_spatial_sidecar(&p, &evidence.spatial_ref).await?);
+            Some(p)
+        }
+        _ => None,
+    };
+
+    outputs.push(out_path.clone());
+    outputs.extend(float_geotiff_path.clone());
     let mut output_hashes = BTreeMap::new();
     output_hashes.insert(
         "product".to_string(),
         crate::io::file_output_hash(&out_path).await?,
     );
+    if let Some(path) = &float_geotiff_path {
+        output_hashes.insert(
+            "float_geotiff".to_string(),
+            crate::io::file_output_hash(path).await?,
+        );
+    }
     let mask_ref = args
         .mask
         .as_ref()
@@ -555,6 +714,8 @@ async fn process_one(metadata_file: &PathBuf, args: &IndicesArgs) -> AgroResult<
             "sensor": args.sensor.map(|sensor| format!("{:?}", sensor).to_lowercase()),
             "resolved_bands": evidence.resolved_bands.clone(),
             "band_overrides": band_overrides,
+            "band_scale_factor": args.band_scale_factor,
+            "float_geotiff": args.float_geotiff,
         }),
         Some(evidence.radiometric_calibration.clone()),
         mask_ref,
@@ -579,6 +740,7 @@ async fn process_one(metadata_file: &PathBuf, args: &IndicesArgs) -> AgroResult<
         timestamp: chrono::Utc::now(),
         source_images: vec![image.image_id],
         output_path: out_path.to_string_lossy().to_string(),
+        float_geotiff_path: float_geotiff_path.map(|path| path.to_string_lossy().to_string()),
         index: format!("{:?}", args.index).to_lowercase(),
         min: stats.min,
         max: stats.max,
@@ -602,9 +764,21 @@ async fn process_one(metadata_file: &PathBuf, args: &IndicesArgs) -> AgroResult<
         format!("{:?}", args.index).to_lowercase()
     );
     let meta_path = args.output_dir.join(meta_name);
-    tokio::fs::write(meta_path, serde_json::to_string_pretty(&meta)?).await?;
+    tokio::fs::write(&meta_path, serde_json::to_string_pretty(&meta)?).await?;
+    outputs.push(meta_path);
 
-    Ok(())
+    let mut source_inputs = vec![metadata_file.to_string_lossy().to_string()];
+    source_inputs.extend(
+        evidence
+            .resolved_bands
+            .values()
+            .filter_map(|band_name| image.file_paths.get(band_name).cloned()),
+    );
+    source_inputs.extend(meta.reproducibility.mask_ref.clone());
+    Ok(ImageInputs {
+        source_inputs,
+        outputs,
+    })
 }
 
 #[cfg(test)]
@@ -619,6 +793,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn resampled_bands_sample_the_source_at_target_pixel_centres() {
+        let source = [0.0, 8.0, 16.0, 24.0];
+        assert_eq!(
+            resample_band(&source, (2, 2), (4, 1), ResampleMethod::Nearest),
+            [16.0, 16.0, 24.0, 24.0]
+        );
+        assert_eq!(
+            resample_band(&source, (2, 2), (4, 1), ResampleMethod::Bilinear),
+            [8.0, 10.0, 14.0, 16.0]
+        );
+        assert_eq!(
+            resample_band(&source, (2, 2), (1, 1), ResampleMethod::Bilinear),
+            [12.0]
+        );
+    }
+
     #[test]
     fn masked_index_statistics_exclude_cloud_and_nodata_pixels() {
         let stats = summarize_masked_index_values(
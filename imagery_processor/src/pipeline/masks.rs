@@ -1,5 +1,6 @@
 use anyhow::Context;
 use shared::{
+    artifact_schema::from_artifact_str,
     schemas::{assert_raster_spatial_ref, MultispectralImage},
     AgroResult,
 };
@@ -42,16 +43,12 @@ pub async fn run_masks(args: &MasksArgs) -> AgroResult<()> {
     for mf in metadata_files {
         if let Err(e) = process_one(&mf, args).await {
             error!(file=%mf.display(), error=%e, "Failed masks processing");
-            failures.push(format!("{}: {}", mf.display(), e));
+            failures.push((mf, e));
         }
     }
 
-    if !failures.is_empty() {
-        return Err(shared::error::AgroError::Processing(format!(
-            "{} metadata file(s) failed: {}",
-            failures.len(),
-            failures.join("; ")
-        )));
+    if let Some(err) = crate::io::batch_failure(failures) {
+        return Err(err);
     }
 
     Ok(())
@@ -70,7 +67,7 @@ struct MaskEvidence {
 
 async fn process_one(metadata_file: &PathBuf, args: &MasksArgs) -> AgroResult<()> {
     let metadata_content = tokio::fs::read_to_string(metadata_file).await?;
-    let image: MultispectralImage = serde_json::from_str(&metadata_content)?;
+    let image: MultispectralImage = from_artifact_str(&metadata_content)?;
 
     let qa_path = image.file_paths.get(&args.qa_band).ok_or_else(|| {
         shared::error::AgroError::Processing(format!("QA band '{}' not found", args.qa_band))
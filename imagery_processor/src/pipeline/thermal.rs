@@ -1,5 +1,6 @@
 use anyhow::Context;
 use shared::{
+    artifact_schema::from_artifact_str,
     error::AgroError,
     schemas::{assert_raster_spatial_ref, MultispectralImage},
     AgroResult,
@@ -35,16 +36,12 @@ pub async fn run_thermal(args: &ThermalArgs) -> AgroResult<()> {
     for mf in metadata_files {
         if let Err(e) = process_one(&mf, args).await {
             error!(file=%mf.display(), error=%e, "Failed thermal processing");
-            failures.push(format!("{}: {}", mf.display(), e));
+            failures.push((mf, e));
         }
     }
 
-    if !failures.is_empty() {
-        return Err(AgroError::Processing(format!(
-            "{} metadata file(s) failed: {}",
-            failures.len(),
-            failures.join("; ")
-        )));
+    if let Some(err) = crate::io::batch_failure(failures) {
+        return Err(err);
     }
 
     Ok(())
@@ -190,7 +187,10 @@ fn require_band_path<'a>(
         .file_paths
         .get(band_name)
         .map(String::as_str)
-        .ok_or_else(|| processing_error(format!("{role} band '{band_name}' not found")))
+        .ok_or_else(|| AgroError::MissingBand {
+            role: role.to_string(),
+            band: band_name.to_string(),
+        })
 }
 
 fn load_gray_values(
@@ -198,8 +198,7 @@ fn load_gray_values(
     expected_dimensions: (u32, u32),
     role: &str,
 ) -> AgroResult<(Vec<f32>, bool)> {
-    let dyn_img = image::open(path)
-        .map_err(|e| processing_error(format!("Failed to load {role} image: {e}")))?;
+    let dyn_img = image::open(path).map_err(|e| crate::io::image_open_error(path, e))?;
     let is_u16 = matches!(
         dyn_img.color(),
         image::ColorType::L16
@@ -211,21 +210,21 @@ fn load_gray_values(
     if is_u16 {
         let image = dyn_img.to_luma16();
         if image.dimensions() != expected_dimensions {
-            return Err(processing_error(format!(
-                "{role} dimensions mismatch: expected {:?}, got {:?}",
+            return Err(AgroError::dimension_mismatch(
+                role,
                 expected_dimensions,
-                image.dimensions()
-            )));
+                image.dimensions(),
+            ));
         }
         Ok((image.pixels().map(|pixel| pixel[0] as f32).collect(), true))
     } else {
         let image = dyn_img.to_luma8();
         if image.dimensions() != expected_dimensions {
-            return Err(processing_error(format!(
-                "{role} dimensions mismatch: expected {:?}, got {:?}",
+            return Err(AgroError::dimension_mismatch(
+                role,
                 expected_dimensions,
-                image.dimensions()
-            )));
+                image.dimensions(),
+            ));
         }
         Ok((image.pixels().map(|pixel| pixel[0] as f32).collect(), false))
     }
@@ -253,14 +252,14 @@ fn load_ndvi_image_emissivity(
     expected_dimensions: (u32, u32),
 ) -> AgroResult<Vec<f32>> {
     let image = image::open(path)
-        .map_err(|e| processing_error(format!("Failed to load NDVI image: {e}")))?
+        .map_err(|e| crate::io::image_open_error(&path.to_string_lossy(), e))?
         .to_luma8();
     if image.dimensions() != expected_dimensions {
-        return Err(processing_error(format!(
-            "NDVI image dimensions mismatch: expected {:?}, got {:?}",
+        return Err(AgroError::dimension_mismatch(
+            "NDVI image",
             expected_dimensions,
-            image.dimensions()
-        )));
+            image.dimensions(),
+        ));
     }
 
     Ok(image
@@ -349,7 +348,7 @@ fn stats_json(stats: RunningStats) -> serde_json::Value {
 
 async fn process_one(metadata_file: &PathBuf, args: &ThermalArgs) -> AgroResult<()> {
     let metadata_content = tokio::fs::read_to_string(metadata_file).await?;
-    let image: MultispectralImage = serde_json::from_str(&metadata_content)?;
+    let image: MultispectralImage = from_artifact_str(&metadata_content)?;
     let spatial_ref = assert_raster_spatial_ref(
         image.metadata.spatial_ref.as_ref(),
         image.metadata.width,
@@ -407,9 +406,7 @@ async fn process_one(metadata_file: &PathBuf, args: &ThermalArgs) -> AgroResult<
     let mask_img = if let Some(mask_path) = &args.mask {
         Some(
             image::open(mask_path)
-                .map_err(|e| {
-                    shared::error::AgroError::Processing(format!("Failed to load mask: {}", e))
-                })?
+                .map_err(|e| crate::io::image_open_error(&mask_path.to_string_lossy(), e))?
                 .to_luma8(),
         )
     } else {
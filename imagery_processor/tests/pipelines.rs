@@ -12,10 +12,12 @@ use imagery_processor::{
         thermal::run_thermal,
     },
     BandOverrideSpec, ClassifyArgs, ExportArgs, IndexBandRole, IndexKind, IndicesArgs, MasksArgs,
-    OutputFormat, SensorPreset, TemperatureUnit, ThermalArgs, ThermalProduct,
+    OutputFormat, ResampleMethod, SensorPreset, TemperatureUnit, ThermalArgs, ThermalProduct,
 };
 use serde_json::Value;
+use shared::error::AgroError;
 use shared::schemas::{assert_raster_spatial_ref, RasterSpatialRef};
+use shared::{OutputManifest, OUTPUT_MANIFEST_FILE_NAME};
 use std::{
     fs,
     path::{Path, PathBuf},
@@ -154,6 +156,9 @@ fn base_indices_args(input_dir: PathBuf, output_dir: PathBuf) -> IndicesArgs {
         out_format: OutputFormat::Png,
         sensor: None,
         mask: None,
+        band_scale_factor: None,
+        float_geotiff: false,
+        resample: None,
     }
 }
 
@@ -684,6 +689,79 @@ async fn indices_png_writes_matching_spatial_sidecar() {
     assert_eq!(sidecar.geo_transform, expected.geo_transform);
 }
 
+#[tokio::test]
+async fn indices_write_a_manifest_listing_every_output_with_its_checksum() {
+    let root = temp_test_dir("indices_output_manifest");
+    let input_dir = root.join("input");
+    let output_dir = root.join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    let red_path = input_dir.join("red.png");
+    let nir_path = input_dir.join("nir.png");
+    write_gray_image(&red_path, 2, 1, &[10, 20]);
+    write_gray_image(&nir_path, 2, 1, &[30, 40]);
+    write_metadata(
+        &input_dir,
+        2,
+        1,
+        &[("Red", red_path.as_path()), ("NIR", nir_path.as_path())],
+    );
+
+    fs::create_dir_all(&output_dir).unwrap();
+    fs::write(output_dir.join("earlier_run_ndvi.png"), b"old").unwrap();
+
+    let mut args = base_indices_args(input_dir.clone(), output_dir.clone());
+    args.float_geotiff = true;
+    run_indices(&args).await.unwrap();
+
+    let manifest = OutputManifest::read(&output_dir).unwrap();
+    assert_eq!(manifest.producer, "imagery_processor");
+    let mut written = fs::read_dir(&output_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|name| name != OUTPUT_MANIFEST_FILE_NAME && name != "earlier_run_ndvi.png")
+        .collect::<Vec<_>>();
+    written.sort();
+    let listed = manifest
+        .files
+        .iter()
+        .map(|entry| entry.path.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(listed, written);
+    assert_eq!(listed.len(), 6);
+    for entry in &manifest.files {
+        let contents = fs::read(output_dir.join(&entry.path)).unwrap();
+        assert_eq!(entry.size_bytes, contents.len() as u64);
+        assert_eq!(
+            entry.sha256,
+            file_output_hash(&output_dir.join(&entry.path))
+                .await
+                .unwrap()
+                .value
+        );
+        assert_eq!(
+            entry.source_inputs,
+            [
+                input_dir.join("metadata_test.json").to_string_lossy(),
+                nir_path.to_string_lossy(),
+                red_path.to_string_lossy(),
+            ]
+        );
+    }
+    let file_types = manifest
+        .files
+        .iter()
+        .map(|entry| entry.file_type.as_str())
+        .collect::<std::collections::BTreeSet<_>>();
+    assert_eq!(file_types, ["json", "png", "tif"].into());
+    assert!(manifest.verify(&output_dir).is_empty());
+
+    // A rerun that fails leaves no manifest behind to vouch for its outputs.
+    fs::remove_file(&red_path).unwrap();
+    assert!(run_indices(&args).await.is_err());
+    assert!(!output_dir.join(OUTPUT_MANIFEST_FILE_NAME).exists());
+}
+
 #[tokio::test]
 async fn export_geotiff_product_writes_schema_stats_csv_and_sidecar() {
     let root = temp_test_dir("export_geotiff_product");
@@ -979,6 +1057,98 @@ async fn indices_reject_missing_spatial_ref() {
     assert!(error.contains("missing spatial_ref"));
 }
 
+#[tokio::test]
+async fn indices_report_a_missing_nir_band_as_missing_band() {
+    let root = temp_test_dir("indices_missing_nir");
+    let input_dir = root.join("input");
+    let output_dir = root.join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    let red_path = input_dir.join("red.png");
+    write_gray_image(&red_path, 1, 1, &[10]);
+    write_metadata(&input_dir, 1, 1, &[("Red", red_path.as_path())]);
+
+    let args = base_indices_args(input_dir, output_dir);
+
+    let error = run_indices(&args).await.unwrap_err();
+    assert!(
+        matches!(&error, AgroError::MissingBand { band, .. } if band == "NIR"),
+        "{error:?}"
+    );
+    assert!(!error.is_retryable());
+}
+
+#[tokio::test]
+async fn indices_report_mismatched_red_and_nir_sizes_as_dimension_mismatch() {
+    let root = temp_test_dir("indices_mismatched_red_nir");
+    let input_dir = root.join("input");
+    let output_dir = root.join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    let red_path = input_dir.join("red.png");
+    let nir_path = input_dir.join("nir.png");
+    write_gray_image(&red_path, 2, 1, &[10, 20]);
+    write_gray_image(&nir_path, 1, 1, &[30]);
+    write_metadata(
+        &input_dir,
+        2,
+        1,
+        &[("Red", red_path.as_path()), ("NIR", nir_path.as_path())],
+    );
+
+    let args = base_indices_args(input_dir, output_dir);
+
+    let error = run_indices(&args).await.unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            AgroError::DimensionMismatch { expected, actual, .. }
+                if expected == "2x1" && actual == "1x1"
+        ),
+        "{error:?}"
+    );
+    assert!(error.to_string().contains("NIR"));
+}
+
+#[tokio::test]
+async fn indices_resample_a_smaller_nir_band_onto_the_red_grid_when_asked() {
+    let root = temp_test_dir("indices_resampled_nir");
+    let input_dir = root.join("input");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    let red_path = input_dir.join("red.png");
+    let nir_path = input_dir.join("nir.png");
+    write_gray_image(&red_path, 4, 2, &[10; 8]);
+    write_gray_image(&nir_path, 2, 1, &[30, 90]);
+    write_metadata(
+        &input_dir,
+        4,
+        2,
+        &[("Red", red_path.as_path()), ("NIR", nir_path.as_path())],
+    );
+
+    let strict = base_indices_args(input_dir.clone(), root.join("strict"));
+    assert!(matches!(
+        run_indices(&strict).await.unwrap_err(),
+        AgroError::DimensionMismatch { .. }
+    ));
+
+    let mut nearest = base_indices_args(input_dir.clone(), root.join("nearest"));
+    nearest.resample = Some(ResampleMethod::Nearest);
+    run_indices(&nearest).await.unwrap();
+    let meta = read_result_meta(&nearest.output_dir);
+    assert_eq!(meta["valid_pixel_count"].as_u64().unwrap(), 8);
+    assert!((meta["min"].as_f64().unwrap() - 0.5).abs() < 1e-6);
+    assert!((meta["max"].as_f64().unwrap() - 0.8).abs() < 1e-6);
+
+    let mut bilinear = base_indices_args(input_dir, root.join("bilinear"));
+    bilinear.resample = Some(ResampleMethod::Bilinear);
+    run_indices(&bilinear).await.unwrap();
+    let meta = read_result_meta(&bilinear.output_dir);
+    // The inner columns blend NIR to 45 and 75, between the nearest results.
+    assert!((meta["mean"].as_f64().unwrap() - 0.675267).abs() < 1e-5);
+}
+
 #[tokio::test]
 async fn indices_reject_zero_resolution_spatial_ref() {
     let root = temp_test_dir("indices_zero_resolution_spatial_ref");
@@ -1085,6 +1255,128 @@ async fn indices_geotiff_fails_without_gdal_feature() {
     assert_eq!(outputs, 1, "only band ingest evidence should be written");
 }
 
+fn read_float_tiff(path: &Path) -> (Vec<f32>, Vec<f64>) {
+    let mut decoder = tiff::decoder::Decoder::new(fs::File::open(path).unwrap()).unwrap();
+    let tiepoint = decoder
+        .get_tag_f64_vec(tiff::tags::Tag::ModelTiepointTag)
+        .unwrap();
+    match decoder.read_image().unwrap() {
+        tiff::decoder::DecodingResult::F32(values) => (values, tiepoint),
+        _ => panic!("expected 32-bit float samples"),
+    }
+}
+
+#[tokio::test]
+async fn ndvi_reads_16bit_tiff_bands_at_full_precision() {
+    let root = temp_test_dir("ndvi_16bit_tiff");
+    let input_dir = root.join("input");
+    let output_dir = root.join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    // Squashed to 8 bits both pixels would read as NDVI 0.5 and 0.
+    let red_path = input_dir.join("red.tif");
+    let nir_path = input_dir.join("nir.tif");
+    write_gray16_image(&red_path, 2, 1, &[300, 256]);
+    write_gray16_image(&nir_path, 2, 1, &[700, 257]);
+    write_metadata(
+        &input_dir,
+        2,
+        1,
+        &[("Red", red_path.as_path()), ("NIR", nir_path.as_path())],
+    );
+
+    let mut args = base_indices_args(input_dir, output_dir.clone());
+    args.float_geotiff = true;
+
+    run_indices(&args).await.unwrap();
+
+    let meta = read_result_meta(&output_dir);
+    let expected = [0.4, 1.0 / 513.0];
+    assert!((meta["max"].as_f64().unwrap() - expected[0]).abs() < 1e-6);
+    assert!((meta["min"].as_f64().unwrap() - expected[1]).abs() < 1e-6);
+
+    let float_path = PathBuf::from(meta["float_geotiff_path"].as_str().unwrap());
+    let (values, tiepoint) = read_float_tiff(&float_path);
+    assert_eq!(values.len(), 2);
+    for (value, expected) in values.iter().zip(expected) {
+        assert!((f64::from(*value) - expected).abs() < 1e-6);
+    }
+    assert_eq!(tiepoint, vec![0.0, 0.0, 0.0, -74.1, 40.8, 0.0]);
+    assert!(geotiff_spatial_sidecar_path(&float_path).exists());
+    assert!(meta["reproducibility"]["output_hashes"]
+        .get("float_geotiff")
+        .is_some());
+
+    let preview = image::open(meta["output_path"].as_str().unwrap()).unwrap();
+    assert_eq!(preview.color(), image::ColorType::L8);
+}
+
+#[tokio::test]
+async fn ndvi_reads_8bit_png_bands_and_applies_the_band_scale_factor() {
+    let root = temp_test_dir("ndvi_8bit_png_scaled");
+    let input_dir = root.join("input");
+    let output_dir = root.join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    let red_path = input_dir.join("red.png");
+    let nir_path = input_dir.join("nir.png");
+    write_gray_image(&red_path, 1, 1, &[51]);
+    write_gray_image(&nir_path, 1, 1, &[204]);
+    write_metadata(
+        &input_dir,
+        1,
+        1,
+        &[("Red", red_path.as_path()), ("NIR", nir_path.as_path())],
+    );
+
+    let mut args = base_indices_args(input_dir, output_dir.clone());
+    args.band_scale_factor = Some(0.0001);
+    args.float_geotiff = true;
+
+    run_indices(&args).await.unwrap();
+
+    let meta = read_result_meta(&output_dir);
+    let (values, _) = read_float_tiff(Path::new(meta["float_geotiff_path"].as_str().unwrap()));
+    assert!((values[0] - 0.6).abs() < 1e-6);
+    assert_eq!(
+        meta["reproducibility"]["parameters"]["band_scale_factor"]
+            .as_f64()
+            .map(|factor| (factor - 0.0001).abs() < 1e-9),
+        Some(true)
+    );
+}
+
+#[tokio::test]
+async fn indices_reject_multi_channel_band_files() {
+    let root = temp_test_dir("indices_rgba_band");
+    let input_dir = root.join("input");
+    let output_dir = root.join("output");
+    fs::create_dir_all(&input_dir).unwrap();
+
+    let red_path = input_dir.join("red.png");
+    let nir_path = input_dir.join("nir.png");
+    image::RgbaImage::from_pixel(1, 1, image::Rgba([10, 10, 10, 255]))
+        .save(&red_path)
+        .unwrap();
+    write_gray_image(&nir_path, 1, 1, &[30]);
+    write_metadata(
+        &input_dir,
+        1,
+        1,
+        &[("Red", red_path.as_path()), ("NIR", nir_path.as_path())],
+    );
+
+    let args = base_indices_args(input_dir, output_dir);
+
+    let error = run_indices(&args).await.unwrap_err().to_string();
+
+    assert!(error.contains("Rgba8"), "{error}");
+    assert!(
+        error.contains("expected a single-channel 8- or 16-bit raster"),
+        "{error}"
+    );
+}
+
 #[tokio::test]
 async fn sentinel2_indices_record_radiometric_calibration_evidence() {
     let root = temp_test_dir("sentinel2_calibration_evidence");
@@ -0,0 +1,119 @@
+use crate::simulation::{SimulationEvent, SimulationOutcome};
+use anyhow::Result;
+use data_collector::{
+    CollectionFailureKind, CollectionFailureRequest, DataCollectorService, DataType,
+    FlightDataRecord, FlightSession, SimulatedCapturePath,
+};
+use std::path::Path;
+use tempfile::TempDir;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Sensor the battery failsafe is recorded against.
+pub const FLIGHT_CONTROLLER_SENSOR_ID: &str = "flight_controller";
+
+/// A flight session as the collector left it once the simulation finished.
+#[derive(Debug, Clone)]
+pub struct CollectedFlight {
+    /// Ended, or failed when the flight was aborted.
+    pub session: FlightSession,
+    pub records: Vec<FlightDataRecord>,
+    pub outcome: SimulationOutcome,
+}
+
+/// A real [`DataCollectorService`] over a data root that is removed when the
+/// harness is dropped.
+pub struct CollectorHarness {
+    service: DataCollectorService,
+    data_root: TempDir,
+}
+
+impl CollectorHarness {
+    pub fn new() -> Result<Self> {
+        let data_root = tempfile::tempdir()?;
+        let service = DataCollectorService::new(data_root.path().to_path_buf())?;
+        Ok(Self { service, data_root })
+    }
+
+    pub fn data_root(&self) -> &Path {
+        self.data_root.path()
+    }
+
+    pub fn service(&self) -> &DataCollectorService {
+        &self.service
+    }
+
+    pub fn service_mut(&mut self) -> &mut DataCollectorService {
+        &mut self.service
+    }
+
+    /// Opens a session for `drone_id` on `mission_id` and feeds it the steps
+    /// received from `events` until the flight finishes. A completed flight
+    /// or one that ran out of ticks ends the session; a battery failsafe is
+    /// recorded as a collection failure and fails it.
+    pub async fn collect(
+        &mut self,
+        drone_id: Uuid,
+        mission_id: Uuid,
+        mut events: mpsc::Receiver<SimulationEvent>,
+    ) -> Result<CollectedFlight> {
+        let session_id = self
+            .service
+            .start_session(drone_id, Some(mission_id))
+            .await?;
+
+        let outcome = loop {
+            let Some(event) = events.recv().await else {
+                self.service.fail_session(&session_id).await?;
+                anyhow::bail!("simulation stopped without finishing session {session_id}");
+            };
+            match event {
+                SimulationEvent::Step { step, .. } => {
+                    let path = SimulatedCapturePath {
+                        session_id,
+                        flight_id: mission_id,
+                        drone_id,
+                        simulation_mission_id: mission_id,
+                        steps: vec![step],
+                    };
+                    self.service.collect_simulated_capture_path(path).await?;
+                }
+                SimulationEvent::Finished(outcome) => break outcome,
+            }
+        };
+
+        let session = match &outcome {
+            SimulationOutcome::BatteryFailsafe {
+                tick,
+                at,
+                battery_level,
+                ..
+            } => {
+                let failure = CollectionFailureRequest {
+                    occurred_at: Some(*at),
+                    sensor_id: FLIGHT_CONTROLLER_SENSOR_ID.to_string(),
+                    data_type: DataType::Telemetry,
+                    kind: CollectionFailureKind::Unknown,
+                    message: format!(
+                        "battery failsafe at {:.0}% on tick {tick}; flight aborted",
+                        battery_level * 100.0
+                    ),
+                };
+                self.service
+                    .record_collection_failure(&session_id, failure)
+                    .await?;
+                self.service.fail_session(&session_id).await?
+            }
+            SimulationOutcome::Completed { .. } | SimulationOutcome::TickLimitReached { .. } => {
+                self.service.end_session(&session_id).await?
+            }
+        };
+        let records = self.service.session_records(&session_id).await?;
+
+        Ok(CollectedFlight {
+            session,
+            records,
+            outcome,
+        })
+    }
+}
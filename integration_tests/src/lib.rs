@@ -0,0 +1,23 @@
+//! In-process harnesses for exercising the flight → collection → processing
+//! → report path without docker, network or the C++ flight simulator.
+//!
+//! - [`SimulationHarness`] flies a deterministic drone along waypoints and
+//!   emits the capture steps `flight_sim_cpp` would produce.
+//! - [`CollectorHarness`] runs a real `DataCollectorService` over a temporary
+//!   data root and turns those steps into a stored flight session.
+//! - [`ProcessorHarness`] runs mock analyzers with a configurable duration
+//!   and failure over the collected flight and renders the report.
+//! - [`run_pipeline`] wires the three together with in-memory channels.
+//!
+//! Downstream crates can use the harnesses on their own, e.g. feeding a
+//! hand-built event stream into a [`CollectorHarness`].
+
+pub mod collector;
+pub mod pipeline;
+pub mod processor;
+pub mod simulation;
+
+pub use collector::{CollectedFlight, CollectorHarness};
+pub use pipeline::{run_pipeline, PipelineRun};
+pub use processor::{AnalyzerFailure, MockAnalyzer, ProcessedFlight, ProcessorHarness};
+pub use simulation::{SimulationConfig, SimulationEvent, SimulationHarness, SimulationOutcome};
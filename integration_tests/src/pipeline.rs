@@ -0,0 +1,34 @@
+use crate::collector::{CollectedFlight, CollectorHarness};
+use crate::processor::{ProcessedFlight, ProcessorHarness};
+use crate::simulation::SimulationHarness;
+use anyhow::Result;
+use tokio::sync::mpsc;
+
+/// Simulation events buffered between the engine and the collector.
+const EVENT_CHANNEL_CAPACITY: usize = 16;
+
+/// Everything one flight produced on its way from simulator to report.
+#[derive(Debug, Clone)]
+pub struct PipelineRun {
+    pub flight: CollectedFlight,
+    pub processed: ProcessedFlight,
+}
+
+/// Flies `simulation` for at most `ticks` ticks while `collector` ingests
+/// its events over an in-memory channel, then hands the collected flight to
+/// `processor`.
+pub async fn run_pipeline(
+    simulation: &SimulationHarness,
+    ticks: u32,
+    collector: &mut CollectorHarness,
+    processor: &mut ProcessorHarness,
+) -> Result<PipelineRun> {
+    let (events_tx, events_rx) = mpsc::channel(EVENT_CHANNEL_CAPACITY);
+    let (_, flight) = tokio::try_join!(
+        simulation.stream(ticks, events_tx),
+        collector.collect(simulation.drone_id(), simulation.mission_id(), events_rx),
+    )?;
+    let processed = processor.process(&flight).await?;
+
+    Ok(PipelineRun { flight, processed })
+}
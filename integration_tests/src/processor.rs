@@ -0,0 +1,290 @@
+use crate::collector::CollectedFlight;
+use crate::simulation::SimulationOutcome;
+use anyhow::Result;
+use chrono::Utc;
+use data_collector::DataPayload;
+use post_processor::report_generator::{
+    CompanyInfo, DeliveryOptions, GeneratedReport, OutputFormat, ReportConfig, ReportDataContext,
+    ReportRequest,
+};
+use post_processor::{
+    compute_statistics, AnalysisResult, Priority, Recommendation, RecommendationCategory,
+    ReportGenerator, ResultData, ResultType, DEFAULT_PERCENTILES,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+use uuid::Uuid;
+
+const REPORT_TEMPLATE: &str = "agricultural_comprehensive";
+
+/// Stand-in for a post-processing analyzer. It takes `duration` to run and
+/// then either fails with the configured message or reports `value` at
+/// every telemetry fix of the flight.
+#[derive(Debug, Clone)]
+pub struct MockAnalyzer {
+    name: String,
+    result_type: ResultType,
+    duration: Duration,
+    value: f32,
+    failure: Option<String>,
+}
+
+/// An analyzer that did not produce a result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalyzerFailure {
+    pub analyzer: String,
+    pub message: String,
+}
+
+impl MockAnalyzer {
+    pub fn new(name: impl Into<String>, result_type: ResultType) -> Self {
+        Self {
+            name: name.into(),
+            result_type,
+            duration: Duration::ZERO,
+            value: 0.5,
+            failure: None,
+        }
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn with_value(mut self, value: f32) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn failing_with(mut self, message: impl Into<String>) -> Self {
+        self.failure = Some(message.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub async fn analyze(
+        &self,
+        flight: &CollectedFlight,
+    ) -> std::result::Result<AnalysisResult, AnalyzerFailure> {
+        tokio::time::sleep(self.duration).await;
+        if let Some(message) = &self.failure {
+            return Err(AnalyzerFailure {
+                analyzer: self.name.clone(),
+                message: message.clone(),
+            });
+        }
+
+        let points = flight
+            .records
+            .iter()
+            .filter_map(|record| match &record.payload {
+                DataPayload::Telemetry(telemetry) => {
+                    Some((telemetry.position.0, telemetry.position.1, self.value))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let values = points
+            .iter()
+            .map(|(_, _, value)| *value)
+            .collect::<Vec<_>>();
+
+        Ok(AnalysisResult {
+            id: Uuid::new_v4(),
+            job_id: flight.session.id,
+            result_type: self.result_type.clone(),
+            statistics: compute_statistics(&values, &DEFAULT_PERCENTILES),
+            data: ResultData::PointData {
+                points,
+                attributes: HashMap::new(),
+            },
+            visualizations: Vec::new(),
+            recommendations: Vec::new(),
+            evidence_refs: flight
+                .records
+                .iter()
+                .map(|record| record.id.to_string())
+                .collect(),
+            uncertainty: None,
+            created_at: Utc::now(),
+        })
+    }
+}
+
+/// What processing made of a collected flight.
+#[derive(Debug, Clone)]
+pub struct ProcessedFlight {
+    pub results: Vec<AnalysisResult>,
+    pub failures: Vec<AnalyzerFailure>,
+    pub report: GeneratedReport,
+    /// The rendered HTML report. The file it was read from is removed.
+    pub report_html: String,
+}
+
+/// Runs mock analyzers over a collected flight and renders its report with
+/// the real [`ReportGenerator`]. An aborted flight and each failed analyzer
+/// become recommendations in the report.
+pub struct ProcessorHarness {
+    analyzers: Vec<MockAnalyzer>,
+    report_generator: ReportGenerator,
+}
+
+impl Default for ProcessorHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessorHarness {
+    pub fn new() -> Self {
+        Self {
+            analyzers: Vec::new(),
+            report_generator: ReportGenerator::new(ReportConfig {
+                output_formats: vec![OutputFormat::HTML],
+                default_template: REPORT_TEMPLATE.to_string(),
+                include_raw_data: false,
+                include_visualizations: false,
+                enable_comparative_analysis: false,
+                logo_path: None,
+                company_info: CompanyInfo {
+                    name: "Integration Tests".to_string(),
+                    address: "In-process".to_string(),
+                    contact_email: "integration@example.com".to_string(),
+                    website: None,
+                    certification_info: None,
+                },
+            }),
+        }
+    }
+
+    pub fn with_analyzer(mut self, analyzer: MockAnalyzer) -> Self {
+        self.analyzers.push(analyzer);
+        self
+    }
+
+    pub fn analyzers(&self) -> &[MockAnalyzer] {
+        &self.analyzers
+    }
+
+    /// Runs every analyzer in turn, then renders the flight's report whether
+    /// or not they all succeeded.
+    pub async fn process(&mut self, flight: &CollectedFlight) -> Result<ProcessedFlight> {
+        let mut results = Vec::new();
+        let mut failures = Vec::new();
+        for analyzer in &self.analyzers {
+            match analyzer.analyze(flight).await {
+                Ok(result) => results.push(result),
+                Err(failure) => {
+                    tracing::warn!(
+                        "Analyzer {} failed for session {}: {}",
+                        failure.analyzer,
+                        flight.session.id,
+                        failure.message
+                    );
+                    failures.push(failure);
+                }
+            }
+        }
+
+        let mut recommendations = flight_recommendations(flight);
+        recommendations.extend(failures.iter().map(failure_recommendation));
+        recommendations.extend(
+            results
+                .iter()
+                .flat_map(|result| result.recommendations.iter().cloned()),
+        );
+
+        let session = &flight.session;
+        let request = ReportRequest {
+            id: Uuid::new_v4(),
+            title: format!("Flight report for session {}", session.id),
+            template_id: REPORT_TEMPLATE.to_string(),
+            data_context: ReportDataContext {
+                mission_ids: session.mission_id.into_iter().collect(),
+                flight_session_ids: vec![session.id],
+                date_range: (
+                    session.start_time,
+                    session.end_time.unwrap_or_else(Utc::now),
+                ),
+                geographical_bounds: None,
+                analysis_parameters: HashMap::new(),
+                include_historical_data: false,
+                comparative_missions: Vec::new(),
+                recommendations,
+                data_quality: session.summary.quality.clone().into_iter().collect(),
+                work_orders: Vec::new(),
+            },
+            custom_sections: Vec::new(),
+            output_formats: vec![OutputFormat::HTML],
+            delivery_options: DeliveryOptions {
+                email_recipients: Vec::new(),
+                storage_location: None,
+                auto_archive: false,
+                retention_days: 1,
+                access_permissions: Vec::new(),
+            },
+            requested_by: "integration_tests".to_string(),
+            requested_at: Utc::now(),
+            locale: None,
+        };
+        let report = self.report_generator.generate_report(request).await?;
+
+        let report_path = &report.file_paths[&OutputFormat::HTML];
+        let report_html = tokio::fs::read_to_string(report_path).await?;
+        tokio::fs::remove_file(report_path).await?;
+
+        Ok(ProcessedFlight {
+            results,
+            failures,
+            report,
+            report_html,
+        })
+    }
+}
+
+/// A re-fly recommendation when the flight did not finish its mission.
+fn flight_recommendations(flight: &CollectedFlight) -> Vec<Recommendation> {
+    let (title, description) = match &flight.outcome {
+        SimulationOutcome::Completed { .. } => return Vec::new(),
+        SimulationOutcome::BatteryFailsafe {
+            tick,
+            battery_level,
+            ..
+        } => (
+            "Flight aborted by battery failsafe".to_string(),
+            format!(
+                "Battery reached {:.0}% on tick {tick}; the rest of the mission was not flown.",
+                battery_level * 100.0
+            ),
+        ),
+        SimulationOutcome::TickLimitReached { ticks } => (
+            "Flight stopped before the last waypoint".to_string(),
+            format!("The mission was still in progress after {ticks} ticks."),
+        ),
+    };
+    vec![Recommendation {
+        category: RecommendationCategory::General,
+        priority: Priority::High,
+        title,
+        description,
+        action_items: vec!["Re-fly the part of the mission that was not covered".to_string()],
+        affected_areas: Vec::new(),
+        confidence_score: 1.0,
+    }]
+}
+
+fn failure_recommendation(failure: &AnalyzerFailure) -> Recommendation {
+    Recommendation {
+        category: RecommendationCategory::General,
+        priority: Priority::Medium,
+        title: format!("{} analysis failed", failure.analyzer),
+        description: failure.message.clone(),
+        action_items: vec![format!("Re-run the {} analysis", failure.analyzer)],
+        affected_areas: Vec::new(),
+        confidence_score: 1.0,
+    }
+}
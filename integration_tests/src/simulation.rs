@@ -0,0 +1,326 @@
+use anyhow::Result;
+use chrono::{DateTime, TimeZone, Utc};
+use data_collector::{
+    MultispectralBandCapture, MultispectralCaptureManifest, SimulatedCapturePathStep,
+    SimulatedSensorObservation,
+};
+use shared::schemas::GpsCoords;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+const METRES_PER_DEGREE: f64 = 111_320.0;
+const TELEMETRY_SENSOR_ID: &str = "sim-autopilot";
+const MULTISPECTRAL_SENSOR_ID: &str = "sim-multispectral";
+const SIGNAL_STRENGTH: f32 = 0.9;
+const BANDS: [&str; 4] = ["Red", "Green", "Blue", "NIR"];
+
+/// Flight the [`SimulationHarness`] flies. Every run of the same config
+/// produces the same steps, ids and timestamps.
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// Seeds the drone, mission and capture ids.
+    pub seed: u64,
+    pub start_time: DateTime<Utc>,
+    pub tick_interval: chrono::Duration,
+    /// Flown in order from the first; the flight completes at the last.
+    pub waypoints: Vec<GpsCoords>,
+    pub ground_speed_m_s: f64,
+    /// Battery level 0–1 at take-off.
+    pub initial_battery: f32,
+    pub battery_drain_per_tick: f32,
+    /// The flight is aborted once the battery is at or below this level.
+    pub failsafe_battery: f32,
+    /// Ticks between multispectral captures; 0 captures none.
+    pub capture_interval_ticks: u32,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            start_time: Utc.with_ymd_and_hms(2024, 6, 1, 8, 0, 0).unwrap(),
+            tick_interval: chrono::Duration::seconds(1),
+            waypoints: vec![
+                waypoint(40.0, -96.0),
+                waypoint(40.0009, -96.0),
+                waypoint(40.0009, -95.999),
+                waypoint(40.0, -95.999),
+            ],
+            ground_speed_m_s: 10.0,
+            initial_battery: 0.95,
+            battery_drain_per_tick: 0.005,
+            failsafe_battery: 0.2,
+            capture_interval_ticks: 5,
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_waypoints(mut self, waypoints: Vec<GpsCoords>) -> Self {
+        self.waypoints = waypoints;
+        self
+    }
+
+    pub fn with_battery(mut self, initial_battery: f32, drain_per_tick: f32) -> Self {
+        self.initial_battery = initial_battery;
+        self.battery_drain_per_tick = drain_per_tick;
+        self
+    }
+
+    pub fn with_failsafe_battery(mut self, failsafe_battery: f32) -> Self {
+        self.failsafe_battery = failsafe_battery;
+        self
+    }
+
+    pub fn with_capture_interval(mut self, ticks: u32) -> Self {
+        self.capture_interval_ticks = ticks;
+        self
+    }
+}
+
+/// One event of a simulated flight, in the order the engine produced it.
+#[derive(Debug, Clone)]
+pub enum SimulationEvent {
+    Step {
+        tick: u32,
+        step: SimulatedCapturePathStep,
+    },
+    /// Always the last event of a run.
+    Finished(SimulationOutcome),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationOutcome {
+    /// The drone reached the last waypoint.
+    Completed { ticks: u32, at: DateTime<Utc> },
+    /// The battery fell to the failsafe level and the flight was aborted.
+    BatteryFailsafe {
+        tick: u32,
+        at: DateTime<Utc>,
+        battery_level: f32,
+        position: GpsCoords,
+    },
+    /// The run stopped after its tick budget with the mission unfinished.
+    TickLimitReached { ticks: u32 },
+}
+
+/// Deterministic stand-in for the C++ flight simulator: flies straight legs
+/// between the configured waypoints at a constant ground speed and drains the
+/// battery by a fixed amount per tick.
+#[derive(Debug, Clone)]
+pub struct SimulationHarness {
+    config: SimulationConfig,
+}
+
+impl SimulationHarness {
+    pub fn new(config: SimulationConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    pub fn drone_id(&self) -> Uuid {
+        self.seeded_id(1)
+    }
+
+    pub fn mission_id(&self) -> Uuid {
+        self.seeded_id(2)
+    }
+
+    /// Runs the engine for at most `ticks` ticks and returns every event.
+    pub fn run(&self, ticks: u32) -> Vec<SimulationEvent> {
+        let mut events = Vec::new();
+        let mut flight = FlightState::new(&self.config);
+        for tick in 1..=ticks {
+            let at = self.config.start_time + self.config.tick_interval * tick as i32;
+            let tick_seconds = self.config.tick_interval.num_milliseconds() as f64 / 1000.0;
+            let heading = flight.advance(self.config.ground_speed_m_s * tick_seconds);
+            flight.battery = (flight.battery - self.config.battery_drain_per_tick).clamp(0.0, 1.0);
+            events.push(SimulationEvent::Step {
+                tick,
+                step: self.fix(tick, at, &flight, heading),
+            });
+
+            if flight.battery <= self.config.failsafe_battery {
+                events.push(SimulationEvent::Finished(
+                    SimulationOutcome::BatteryFailsafe {
+                        tick,
+                        at,
+                        battery_level: flight.battery,
+                        position: flight.reported_position(),
+                    },
+                ));
+                return events;
+            }
+            if flight.arrived() {
+                events.push(SimulationEvent::Finished(SimulationOutcome::Completed {
+                    ticks: tick,
+                    at,
+                }));
+                return events;
+            }
+        }
+        events.push(SimulationEvent::Finished(
+            SimulationOutcome::TickLimitReached { ticks },
+        ));
+        events
+    }
+
+    /// Runs the engine for at most `ticks` ticks, sending each event to
+    /// `events` as it is produced.
+    pub async fn stream(
+        &self,
+        ticks: u32,
+        events: mpsc::Sender<SimulationEvent>,
+    ) -> Result<SimulationOutcome> {
+        let mut outcome = SimulationOutcome::TickLimitReached { ticks };
+        for event in self.run(ticks) {
+            if let SimulationEvent::Finished(finished) = &event {
+                outcome = finished.clone();
+            }
+            events
+                .send(event)
+                .await
+                .map_err(|_| anyhow::anyhow!("simulation event receiver was dropped"))?;
+        }
+        Ok(outcome)
+    }
+
+    fn fix(
+        &self,
+        tick: u32,
+        at: DateTime<Utc>,
+        flight: &FlightState,
+        heading: Option<(f64, f64)>,
+    ) -> SimulatedCapturePathStep {
+        let (east, north) = heading.unwrap_or((0.0, 0.0));
+        let speed = self.config.ground_speed_m_s;
+        let mut observations = vec![SimulatedSensorObservation::Telemetry {
+            sensor_id: TELEMETRY_SENSOR_ID.to_string(),
+            calibration_ref: "sim-autopilot-v1".to_string(),
+            velocity: ((east * speed) as f32, (north * speed) as f32, 0.0),
+            orientation: (
+                0.0,
+                0.0,
+                east.atan2(north).to_degrees().rem_euclid(360.0) as f32,
+            ),
+            battery_level: flight.battery,
+            signal_strength: SIGNAL_STRENGTH,
+        }];
+        let interval = self.config.capture_interval_ticks;
+        if interval > 0 && tick.is_multiple_of(interval) {
+            observations.push(SimulatedSensorObservation::Multispectral {
+                sensor_id: MULTISPECTRAL_SENSOR_ID.to_string(),
+                calibration_ref: "sim-multispectral-v1".to_string(),
+                manifest: self.capture_manifest(tick),
+            });
+        }
+        SimulatedCapturePathStep::Fix {
+            observed_at: at,
+            position: flight.reported_position(),
+            observations,
+        }
+    }
+
+    fn capture_manifest(&self, tick: u32) -> MultispectralCaptureManifest {
+        let capture_id = self.seeded_id(0x1000 + u64::from(tick));
+        MultispectralCaptureManifest {
+            expected_bands: BANDS.iter().map(|band| band.to_string()).collect(),
+            bands: BANDS
+                .iter()
+                .map(|band| MultispectralBandCapture {
+                    name: band.to_string(),
+                    file_path: PathBuf::from(format!("sim://{capture_id}/{band}.tif")),
+                    width: 16,
+                    height: 12,
+                    exposure_time_ms: 8,
+                    gain: 1.0,
+                })
+                .collect(),
+        }
+    }
+
+    fn seeded_id(&self, n: u64) -> Uuid {
+        Uuid::from_u128((u128::from(self.config.seed) << 64) | u128::from(n))
+    }
+}
+
+struct FlightState {
+    position: GpsCoords,
+    remaining: Vec<GpsCoords>,
+    battery: f32,
+}
+
+impl FlightState {
+    fn new(config: &SimulationConfig) -> Self {
+        let mut waypoints = config.waypoints.iter().cloned();
+        let position = waypoints.next().unwrap_or_else(|| waypoint(0.0, 0.0));
+        Self {
+            position,
+            remaining: waypoints.collect(),
+            battery: config.initial_battery,
+        }
+    }
+
+    /// Position as an autopilot reports it, in whole 1e-7 degrees.
+    fn reported_position(&self) -> GpsCoords {
+        let to_deg_e7 = |degrees: f64| (degrees * 1e7).round() / 1e7;
+        GpsCoords {
+            latitude: to_deg_e7(self.position.latitude),
+            longitude: to_deg_e7(self.position.longitude),
+            altitude: (self.position.altitude * 1e3).round() / 1e3,
+        }
+    }
+
+    fn arrived(&self) -> bool {
+        self.remaining.is_empty()
+    }
+
+    /// Moves `distance_m` along the remaining legs and returns the unit
+    /// (east, north) heading of the last leg flown, if any.
+    fn advance(&mut self, mut distance_m: f64) -> Option<(f64, f64)> {
+        let mut heading = None;
+        while distance_m > 0.0 && !self.remaining.is_empty() {
+            let target = &self.remaining[0];
+            let metres_per_lon = METRES_PER_DEGREE * self.position.latitude.to_radians().cos();
+            let north = (target.latitude - self.position.latitude) * METRES_PER_DEGREE;
+            let east = (target.longitude - self.position.longitude) * metres_per_lon;
+            let leg = north.hypot(east);
+            if leg > 0.0 {
+                heading = Some((east / leg, north / leg));
+            }
+            if leg <= distance_m {
+                distance_m -= leg;
+                self.position = self.remaining.remove(0);
+                continue;
+            }
+            let fraction = distance_m / leg;
+            self.position = GpsCoords {
+                latitude: self.position.latitude
+                    + (target.latitude - self.position.latitude) * fraction,
+                longitude: self.position.longitude
+                    + (target.longitude - self.position.longitude) * fraction,
+                altitude: self.position.altitude
+                    + (target.altitude - self.position.altitude) * fraction,
+            };
+            distance_m = 0.0;
+        }
+        heading
+    }
+}
+
+fn waypoint(latitude: f64, longitude: f64) -> GpsCoords {
+    GpsCoords {
+        latitude,
+        longitude,
+        altitude: 402.0,
+    }
+}
@@ -0,0 +1,136 @@
+use data_collector::{DataType, SessionStatus};
+use integration_tests::{
+    run_pipeline, CollectorHarness, MockAnalyzer, ProcessorHarness, SimulationConfig,
+    SimulationHarness, SimulationOutcome,
+};
+use post_processor::report_generator::ReportStatus;
+use post_processor::ResultType;
+use std::time::Duration;
+
+fn processor() -> ProcessorHarness {
+    ProcessorHarness::new()
+        .with_analyzer(
+            MockAnalyzer::new("NDVI", ResultType::NdviMap)
+                .with_duration(Duration::from_millis(20))
+                .with_value(0.72),
+        )
+        .with_analyzer(MockAnalyzer::new("Thermal", ResultType::ThermalMap).with_value(0.1))
+}
+
+#[tokio::test]
+async fn a_completed_flight_is_collected_processed_and_reported() {
+    let simulation = SimulationHarness::new(SimulationConfig::default());
+    let mut collector = CollectorHarness::new().unwrap();
+    let mut processor = processor();
+
+    let run = run_pipeline(&simulation, 200, &mut collector, &mut processor)
+        .await
+        .unwrap();
+
+    let SimulationOutcome::Completed { ticks, .. } = run.flight.outcome else {
+        panic!("flight did not complete: {:?}", run.flight.outcome);
+    };
+    let session = &run.flight.session;
+    assert_eq!(session.status, SessionStatus::Ended);
+    assert_eq!(session.drone_id, simulation.drone_id());
+    assert_eq!(session.mission_id, Some(simulation.mission_id()));
+    assert!(session.summary.collection_failures.is_empty());
+    let telemetry = run
+        .flight
+        .records
+        .iter()
+        .filter(|record| record.data_type == DataType::Telemetry)
+        .count();
+    assert_eq!(telemetry, ticks as usize);
+    assert_eq!(
+        run.flight.records.len(),
+        ticks as usize + ticks as usize / 5
+    );
+
+    let processed = &run.processed;
+    assert!(processed.failures.is_empty());
+    assert_eq!(processed.results.len(), 2);
+    assert_eq!(processed.results[0].statistics.mean_value, 0.72);
+    assert_eq!(processed.results[0].statistics.valid_pixel_count, ticks);
+    assert_eq!(processed.report.status, ReportStatus::Completed);
+    assert!(processed
+        .report_html
+        .contains("<section id=\"data_quality\">"));
+    assert!(!processed.report_html.contains("analysis failed"));
+    assert!(!processed.report_html.contains("Flight aborted"));
+
+    // The engine is deterministic: the same config flies the same flight.
+    let rerun = SimulationHarness::new(SimulationConfig::default()).run(200);
+    assert_eq!(format!("{rerun:?}"), format!("{:?}", simulation.run(200)));
+}
+
+#[tokio::test]
+async fn a_battery_failsafe_fails_the_session_and_notes_the_abort_in_the_report() {
+    let simulation = SimulationHarness::new(
+        SimulationConfig::default()
+            .with_battery(0.5, 0.05)
+            .with_failsafe_battery(0.27),
+    );
+    let mut collector = CollectorHarness::new().unwrap();
+    let mut processor = processor();
+
+    let run = run_pipeline(&simulation, 200, &mut collector, &mut processor)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        run.flight.outcome,
+        SimulationOutcome::BatteryFailsafe { tick: 5, .. }
+    ));
+    let session = &run.flight.session;
+    assert_eq!(session.status, SessionStatus::Failed);
+    assert!(session.end_time.is_some());
+    let failures = &session.summary.collection_failures;
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].sensor_id, "flight_controller");
+    assert_eq!(
+        failures[0].message,
+        "battery failsafe at 25% on tick 5; flight aborted"
+    );
+    assert_eq!(run.flight.records.len(), 6);
+
+    let html = &run.processed.report_html;
+    assert_eq!(run.processed.report.status, ReportStatus::Completed);
+    assert!(html.contains("<p>Flight aborted by battery failsafe</p>"));
+    assert!(html.contains("Battery reached 25% on tick 5"));
+    assert!(html.contains("<p>Priority: High</p>"));
+}
+
+#[tokio::test]
+async fn a_failed_analyzer_is_recorded_in_a_report_that_is_still_produced() {
+    let simulation = SimulationHarness::new(SimulationConfig::default().with_seed(7));
+    let mut collector = CollectorHarness::new().unwrap();
+    let mut processor = ProcessorHarness::new()
+        .with_analyzer(MockAnalyzer::new("NDVI", ResultType::NdviMap))
+        .with_analyzer(
+            MockAnalyzer::new("Thermal", ResultType::ThermalMap)
+                .with_duration(Duration::from_millis(20))
+                .failing_with("thermal band missing from every capture"),
+        );
+
+    let run = run_pipeline(&simulation, 200, &mut collector, &mut processor)
+        .await
+        .unwrap();
+
+    assert_eq!(run.flight.session.status, SessionStatus::Ended);
+    let processed = &run.processed;
+    assert_eq!(processed.results.len(), 1);
+    assert_eq!(processed.results[0].result_type, ResultType::NdviMap);
+    assert_eq!(processed.failures.len(), 1);
+    assert_eq!(processed.failures[0].analyzer, "Thermal");
+    assert_eq!(processed.report.status, ReportStatus::Completed);
+    assert!(processed
+        .report_html
+        .contains("<p>Thermal analysis failed</p>"));
+    assert!(processed
+        .report_html
+        .contains("<p>thermal band missing from every capture</p>"));
+    assert!(processed
+        .report_html
+        .contains("<p>- Re-run the Thermal analysis</p>"));
+}
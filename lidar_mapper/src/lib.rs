@@ -5,12 +5,15 @@ use nalgebra::{Matrix3, SymmetricEigen, Vector3};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use shared::{
+    artifact_schema::from_artifact_str,
     config::AgroConfig,
+    legend::{continuous_legend, legend_path_for},
+    palette::{Palette, CIVIDIS},
     schemas::{
         assert_raster_spatial_ref, GeoBounds, LidarPoint, LidarScan, RasterResolution,
         RasterSpatialRef,
     },
-    AgroResult,
+    AgroResult, OutputManifest,
 };
 use std::{
     collections::HashMap,
@@ -24,7 +27,10 @@ use uuid::Uuid;
 #[command(name = "lidar_mapper")]
 #[command(about = "LiDAR Mapper for point cloud processing")]
 pub struct Args {
-    #[arg(long, help = "Input directory containing LiDAR scan files")]
+    #[arg(
+        long,
+        help = "Input directory containing LiDAR scan files (JSON scans or .las/.laz clouds)"
+    )]
     pub input_dir: PathBuf,
 
     #[arg(long, help = "Output directory for maps and visualizations")]
@@ -38,6 +44,8 @@ pub struct Args {
     pub occupancy_threshold: Option<f32>,
     #[arg(long, help = "Flip Y axis in output images")]
     pub flip_y: Option<bool>,
+    #[arg(long, help = "Configuration file path")]
+    pub config: Option<PathBuf>,
 }
 
 pub struct LidarMapper {
@@ -78,6 +86,9 @@ pub const OCCUPANCY_GRID_LOCAL_CRS: &str = "LOCAL_LIDAR_METERS";
 const OUTLIER_DISTANCE_EPSILON_METERS: f64 = 1.0e-9;
 const GRID_COORDINATE_EPSILON: f64 = 1.0e-6;
 const DEFAULT_LIDAR_COVERAGE_FLOOR: f32 = 0.80;
+/// Obstacle density runs from the low to the high end of this palette.
+const OBSTACLE_HEATMAP_PALETTE: Palette = CIVIDIS;
+const OBSTACLE_HEATMAP_LEGEND_TICKS: usize = 5;
 const POINT_CLOUD_FRAME_CRS_NOTE: &str =
     "LOCAL_LIDAR_METERS: x/y are derived from polar LiDAR angle and distance in meters; z=0 for 2D scans";
 
@@ -135,6 +146,9 @@ pub struct LidarCoverageDensityEvidence {
 pub struct LidarObstacleHeatmapEvidence {
     pub occupancy: LidarOccupancyGridEvidence,
     pub max_obstacle_count: usize,
+    /// Shared palette the heatmap and its legend are drawn with.
+    #[serde(default)]
+    pub palette: String,
     pub spatial_ref: RasterSpatialRef,
     pub width: u32,
     pub height: u32,
@@ -570,7 +584,7 @@ impl LidarMapper {
     /// Create a new mapper, loading config and applying CLI overrides
     pub async fn new(args: &Args) -> AgroResult<Self> {
         // Load base config
-        let mut config = AgroConfig::load()?;
+        let mut config = AgroConfig::load_with_override(args.config.as_deref())?;
         // Apply CLI overrides
         if let Some(d) = args.distance_threshold {
             config.processing.lidar_obstacle_distance_threshold = d;
@@ -596,38 +610,68 @@ impl LidarMapper {
     ) -> AgroResult<()> {
         info!("Processing LiDAR scans in: {:?}", input_dir);
 
+        OutputManifest::remove(output_dir)?;
         let ingest = self.ingest_scans(input_dir, output_dir).await?;
+        let mut written = vec![Self::scan_ingest_summary_path(output_dir)];
         let cleaned = self.clean_scans(&ingest.scans)?;
-        self.save_outlier_removal_evidence(&cleaned.evidence, output_dir)
-            .await?;
+        written.push(
+            self.save_outlier_removal_evidence(&cleaned.evidence, output_dir)
+                .await?,
+        );
         let cleaning_params = cleaned.evidence.params;
         let all_scans = cleaned.scans;
 
         // Create occupancy grid
         let grid = self.build_occupancy_grid(&all_scans)?;
-        self.save_occupancy_spatial_ref(&grid, output_dir).await?;
-        self.save_occupancy_grid_evidence(&grid.evidence, output_dir)
-            .await?;
+        written.push(self.save_occupancy_spatial_ref(&grid, output_dir).await?);
+        written.push(
+            self.save_occupancy_grid_evidence(&grid.evidence, output_dir)
+                .await?,
+        );
         let scan_ids = all_scans.iter().map(|scan| scan.scan_id).collect();
         let product_evidence =
             self.occupancy_grid_reproducibility_evidence(&grid, scan_ids, Some(cleaning_params))?;
-        self.save_lidar_product_reproducibility_evidence(&product_evidence, output_dir)
-            .await?;
+        written.push(
+            self.save_lidar_product_reproducibility_evidence(&product_evidence, output_dir)
+                .await?,
+        );
         let coverage_evidence =
             self.coverage_density_evidence(&grid, DEFAULT_LIDAR_COVERAGE_FLOOR)?;
-        self.save_coverage_density_evidence(&coverage_evidence, output_dir)
-            .await?;
+        written.push(
+            self.save_coverage_density_evidence(&coverage_evidence, output_dir)
+                .await?,
+        );
 
         // Save grid as image
-        self.save_grid_image(&grid.cells, output_dir).await?;
+        written.push(self.save_grid_image(&grid.cells, output_dir).await?);
 
         // Save point cloud
-        self.save_point_cloud(&all_scans, output_dir).await?;
+        written.extend(self.save_point_cloud(&all_scans, output_dir).await?);
 
         // Generate obstacle heatmap
-        self.save_obstacle_heatmap(&grid, output_dir).await?;
+        written.extend(self.save_obstacle_heatmap(&grid, output_dir).await?);
 
-        info!("LiDAR mapping completed");
+        // Every product is derived from the whole set of loaded scans, and
+        // only the files written above are listed.
+        let source_inputs: Vec<String> = ingest
+            .summary
+            .records
+            .iter()
+            .map(|record| record.path.clone())
+            .collect();
+        let manifest = OutputManifest::from_written(
+            "lidar_mapper",
+            output_dir,
+            written
+                .into_iter()
+                .map(|path| (path, source_inputs.clone())),
+        )?;
+        let manifest_path = manifest.write(output_dir)?;
+        info!(
+            "LiDAR mapping completed, {} outputs listed in {:?}",
+            manifest.files.len(),
+            manifest_path
+        );
         Ok(())
     }
 
@@ -713,21 +757,22 @@ impl LidarMapper {
         );
 
         if summary.loaded_count == 0 {
-            return Err(shared::error::AgroError::Processing(
-                "No LiDAR scans were processed successfully".into(),
+            return Err(shared::error::AgroError::EmptyInput(
+                "no LiDAR scans were processed successfully".into(),
             ));
         }
 
         Ok(LidarScanIngest { scans, summary })
     }
 
+    /// JSON scans named `*scan_*.json`, plus every `.las`/`.laz` point cloud.
     fn scan_files(input_dir: &Path) -> AgroResult<Vec<PathBuf>> {
         let mut scan_files = Vec::new();
         for entry in walkdir::WalkDir::new(input_dir) {
             let entry = entry.map_err(|e| shared::error::AgroError::Io(e.into()))?;
-            if entry.file_name().to_string_lossy().contains("scan_")
-                && entry.path().extension().map_or(false, |ext| ext == "json")
-            {
+            let json_scan = entry.file_name().to_string_lossy().contains("scan_")
+                && entry.path().extension().map_or(false, |ext| ext == "json");
+            if json_scan || Self::is_point_cloud_file(entry.path()) {
                 scan_files.push(entry.path().to_path_buf());
             }
         }
@@ -765,12 +810,62 @@ impl LidarMapper {
         360.0 - largest_gap.max(wrap_gap)
     }
 
+    fn is_point_cloud_file(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("las") || ext.eq_ignore_ascii_case("laz"))
+    }
+
     async fn load_scan(scan_file: &Path) -> AgroResult<LidarScan> {
+        if Self::is_point_cloud_file(scan_file) {
+            let path = scan_file.to_path_buf();
+            return tokio::task::spawn_blocking(move || Self::load_point_cloud_scan(&path))
+                .await
+                .map_err(|e| shared::error::AgroError::Processing(e.to_string()))?;
+        }
         let content = tokio::fs::read_to_string(scan_file).await?;
-        let scan: LidarScan = serde_json::from_str(&content)?;
+        let scan: LidarScan =
+            from_artifact_str(&content).map_err(|err| shared::error::AgroError::DecodeError {
+                path: scan_file.display().to_string(),
+                reason: err.to_string(),
+            })?;
         Ok(scan)
     }
 
+    /// Reads a LAS/LAZ cloud as one planar scan around the cloud's origin:
+    /// each point's x/y becomes an angle in degrees and a distance in
+    /// millimetres, and its 16-bit intensity the quality. The scan id is
+    /// derived from the path so reruns report the same id, and the capture
+    /// time is the file's modification time.
+    fn load_point_cloud_scan(path: &Path) -> AgroResult<LidarScan> {
+        let points = sensor_overlay_engine::read_las(path).map_err(|err| {
+            shared::error::AgroError::DecodeError {
+                path: path.display().to_string(),
+                reason: format!("{err:#}"),
+            }
+        })?;
+        let timestamp = std::fs::metadata(path)?
+            .modified()
+            .map(DateTime::<Utc>::from)
+            .unwrap_or_else(|_| Utc::now());
+        let digest = Sha256::digest(path.to_string_lossy().as_bytes());
+        let scan_id = Uuid::from_slice(&digest[..16]).expect("sha256 holds 16 bytes");
+        let points = points
+            .iter()
+            .map(|point| LidarPoint {
+                timestamp,
+                angle: point.y.atan2(point.x).to_degrees().rem_euclid(360.0),
+                distance: point.x.hypot(point.y) * 1000.0,
+                quality: (point.intensity / 257.0).round().clamp(0.0, 255.0) as u8,
+            })
+            .collect();
+        Ok(LidarScan {
+            timestamp,
+            points,
+            scan_id,
+        })
+    }
+
     pub fn clean_scans(&self, scans: &[LidarScan]) -> AgroResult<CleanedLidarScans> {
         self.remove_statistical_outliers(scans, LidarOutlierRemovalParams::default())
     }
@@ -928,12 +1023,12 @@ impl LidarMapper {
         &self,
         evidence: &LidarOutlierRemovalEvidence,
         output_dir: &PathBuf,
-    ) -> AgroResult<()> {
+    ) -> AgroResult<PathBuf> {
         let output_path = output_dir.join("lidar_outlier_removal_evidence.json");
         let content = serde_json::to_vec_pretty(evidence)?;
         tokio::fs::write(&output_path, content).await?;
         info!("Saved LiDAR outlier removal evidence to: {:?}", output_path);
-        Ok(())
+        Ok(output_path)
     }
 
     pub fn estimate_surface_normals(
@@ -1070,11 +1165,11 @@ impl LidarMapper {
     ) -> AgroResult<LidarGroundSegmentationResult> {
         Self::validate_ground_segmentation_params(params)?;
         if points.len() != normals.len() {
-            return Err(shared::error::AgroError::Processing(format!(
-                "LiDAR ground segmentation expected {} normals for {} points",
-                points.len(),
-                normals.len()
-            )));
+            return Err(shared::error::AgroError::DimensionMismatch {
+                subject: "LiDAR ground segmentation normals".into(),
+                expected: points.len().to_string(),
+                actual: normals.len().to_string(),
+            });
         }
 
         let min_ground_normal_z = params.max_ground_tilt_degrees.to_radians().cos();
@@ -1144,11 +1239,11 @@ impl LidarMapper {
     ) -> AgroResult<LidarObjectClusteringResult> {
         Self::validate_object_clustering_params(params)?;
         if points.len() != classifications.len() {
-            return Err(shared::error::AgroError::Processing(format!(
-                "LiDAR object clustering expected {} classifications for {} points",
-                points.len(),
-                classifications.len()
-            )));
+            return Err(shared::error::AgroError::DimensionMismatch {
+                subject: "LiDAR object clustering classifications".into(),
+                expected: points.len().to_string(),
+                actual: classifications.len().to_string(),
+            });
         }
         for point in points {
             if !point.x.is_finite() || !point.y.is_finite() || !point.z.is_finite() {
@@ -1288,16 +1383,16 @@ impl LidarMapper {
     ) -> AgroResult<LidarElevationProducts> {
         Self::validate_elevation_params(params)?;
         if points.is_empty() {
-            return Err(shared::error::AgroError::Processing(
+            return Err(shared::error::AgroError::EmptyInput(
                 "LiDAR elevation products require at least one point".into(),
             ));
         }
         if points.len() != classifications.len() {
-            return Err(shared::error::AgroError::Processing(format!(
-                "LiDAR elevation products expected {} classifications for {} points",
-                points.len(),
-                classifications.len()
-            )));
+            return Err(shared::error::AgroError::DimensionMismatch {
+                subject: "LiDAR elevation classifications".into(),
+                expected: points.len().to_string(),
+                actual: classifications.len().to_string(),
+            });
         }
 
         let cells = Self::elevation_cell_bounds(points, params.resolution_m)?;
@@ -1379,19 +1474,25 @@ impl LidarMapper {
     ) -> AgroResult<LidarCanopyHeightRaster> {
         let expected_values = (dsm.width * dsm.height) as usize;
         if expected_values == 0 {
-            return Err(shared::error::AgroError::Processing(
+            return Err(shared::error::AgroError::EmptyInput(
                 "LiDAR canopy-height products require non-empty raster dimensions".into(),
             ));
         }
         if dsm.width != dtm.width || dsm.height != dtm.height {
-            return Err(shared::error::AgroError::Processing(
-                "LiDAR canopy-height products require aligned DSM and DTM extents".into(),
+            return Err(shared::error::AgroError::dimension_mismatch(
+                "LiDAR canopy-height DTM extent",
+                (dsm.width, dsm.height),
+                (dtm.width, dtm.height),
             ));
         }
-        if dsm.values.len() != expected_values || dtm.values.len() != expected_values {
-            return Err(shared::error::AgroError::Processing(
-                "LiDAR canopy-height products require aligned DSM and DTM raster sizes".into(),
-            ));
+        for raster in [dsm, dtm] {
+            if raster.values.len() != expected_values {
+                return Err(shared::error::AgroError::DimensionMismatch {
+                    subject: format!("LiDAR canopy-height {} value count", raster.kind),
+                    expected: expected_values.to_string(),
+                    actual: raster.values.len().to_string(),
+                });
+            }
         }
 
         let dsm_spatial_ref =
@@ -1506,13 +1607,14 @@ impl LidarMapper {
     ) -> AgroResult<LidarTerrainMesh> {
         let expected_values = (dsm.width * dsm.height) as usize;
         if dsm.values.len() != expected_values {
-            return Err(shared::error::AgroError::Processing(format!(
-                "LiDAR terrain mesh expected {} DSM values for {}x{}, got {}",
-                expected_values,
-                dsm.width,
-                dsm.height,
-                dsm.values.len()
-            )));
+            return Err(shared::error::AgroError::DimensionMismatch {
+                subject: format!(
+                    "LiDAR terrain mesh DSM value count for {}x{}",
+                    dsm.width, dsm.height
+                ),
+                expected: expected_values.to_string(),
+                actual: dsm.values.len().to_string(),
+            });
         }
         let spatial_ref = assert_raster_spatial_ref(Some(&dsm.spatial_ref), dsm.width, dsm.height)
             .map_err(|err| {
@@ -1673,13 +1775,14 @@ impl LidarMapper {
     ) -> AgroResult<RasterSpatialRef> {
         let expected_values = (raster.width * raster.height) as usize;
         if raster.values.len() != expected_values {
-            return Err(shared::error::AgroError::Processing(format!(
-                "LiDAR elevation export expected {} values for {}x{}, got {}",
-                expected_values,
-                raster.width,
-                raster.height,
-                raster.values.len()
-            )));
+            return Err(shared::error::AgroError::DimensionMismatch {
+                subject: format!(
+                    "LiDAR elevation export value count for {}x{}",
+                    raster.width, raster.height
+                ),
+                expected: expected_values.to_string(),
+                actual: raster.values.len().to_string(),
+            });
         }
         if !raster.nodata.is_finite() {
             return Err(shared::error::AgroError::Processing(
@@ -2129,7 +2232,7 @@ impl LidarMapper {
         &self,
         grid: &LidarOccupancyGrid,
         output_dir: &PathBuf,
-    ) -> AgroResult<()> {
+    ) -> AgroResult<PathBuf> {
         let output_path = output_dir.join("occupancy_grid_spatial_ref.json");
         let content = serde_json::to_vec_pretty(&grid.spatial_ref)?;
         tokio::fs::write(&output_path, content).await?;
@@ -2137,26 +2240,26 @@ impl LidarMapper {
             "Saved occupancy grid spatial reference to: {:?}",
             output_path
         );
-        Ok(())
+        Ok(output_path)
     }
 
     async fn save_occupancy_grid_evidence(
         &self,
         evidence: &LidarOccupancyGridEvidence,
         output_dir: &PathBuf,
-    ) -> AgroResult<()> {
+    ) -> AgroResult<PathBuf> {
         let output_path = output_dir.join("lidar_occupancy_grid_evidence.json");
         let content = serde_json::to_vec_pretty(evidence)?;
         tokio::fs::write(&output_path, content).await?;
         info!("Saved LiDAR occupancy grid evidence to: {:?}", output_path);
-        Ok(())
+        Ok(output_path)
     }
 
     async fn save_lidar_product_reproducibility_evidence(
         &self,
         evidence: &LidarProductReproducibilityEvidence,
         output_dir: &PathBuf,
-    ) -> AgroResult<()> {
+    ) -> AgroResult<PathBuf> {
         let output_path = output_dir.join("lidar_occupancy_grid_reproducibility.json");
         let content = serde_json::to_vec_pretty(evidence)?;
         tokio::fs::write(&output_path, content).await?;
@@ -2164,7 +2267,7 @@ impl LidarMapper {
             "Saved LiDAR product reproducibility evidence to: {:?}",
             output_path
         );
-        Ok(())
+        Ok(output_path)
     }
 
     pub fn coverage_density_evidence(
@@ -2248,7 +2351,7 @@ impl LidarMapper {
         &self,
         evidence: &LidarCoverageDensityEvidence,
         output_dir: &PathBuf,
-    ) -> AgroResult<()> {
+    ) -> AgroResult<PathBuf> {
         let output_path = output_dir.join("lidar_coverage_density_evidence.json");
         let content = serde_json::to_vec_pretty(evidence)?;
         tokio::fs::write(&output_path, content).await?;
@@ -2256,14 +2359,14 @@ impl LidarMapper {
             "Saved LiDAR coverage density evidence to: {:?}",
             output_path
         );
-        Ok(())
+        Ok(output_path)
     }
 
     async fn save_grid_image(
         &self,
         grid: &HashMap<(i32, i32), GridCell>,
         output_dir: &PathBuf,
-    ) -> AgroResult<()> {
+    ) -> AgroResult<PathBuf> {
         // Find grid bounds
         let min_x = grid.keys().map(|(x, _)| *x).min().unwrap_or(0);
         let max_x = grid.keys().map(|(x, _)| *x).max().unwrap_or(0);
@@ -2300,10 +2403,14 @@ impl LidarMapper {
         })?;
 
         info!("Saved occupancy grid to: {:?}", output_path);
-        Ok(())
+        Ok(output_path)
     }
 
-    async fn save_point_cloud(&self, scans: &[LidarScan], output_dir: &PathBuf) -> AgroResult<()> {
+    async fn save_point_cloud(
+        &self,
+        scans: &[LidarScan],
+        output_dir: &PathBuf,
+    ) -> AgroResult<[PathBuf; 2]> {
         let mut points = Vec::new();
         let provenance = Self::point_cloud_provenance(scans);
 
@@ -2340,7 +2447,8 @@ impl LidarMapper {
 
         let output_path = output_dir.join("point_cloud.pcd");
         tokio::fs::write(&output_path, pcd_content).await?;
-        self.save_point_cloud_provenance(&provenance, output_dir)
+        let provenance_path = self
+            .save_point_cloud_provenance(&provenance, output_dir)
             .await?;
 
         info!(
@@ -2348,7 +2456,7 @@ impl LidarMapper {
             points.len(),
             output_path
         );
-        Ok(())
+        Ok([output_path, provenance_path])
     }
 
     fn point_cloud_provenance(scans: &[LidarScan]) -> LidarPointCloudProvenance {
@@ -2364,19 +2472,19 @@ impl LidarMapper {
         &self,
         provenance: &LidarPointCloudProvenance,
         output_dir: &PathBuf,
-    ) -> AgroResult<()> {
+    ) -> AgroResult<PathBuf> {
         let output_path = output_dir.join("point_cloud_provenance.json");
         let content = serde_json::to_vec_pretty(provenance)?;
         tokio::fs::write(&output_path, content).await?;
         info!("Saved point cloud provenance to: {:?}", output_path);
-        Ok(())
+        Ok(output_path)
     }
 
     async fn save_obstacle_heatmap(
         &self,
         grid: &LidarOccupancyGrid,
         output_dir: &PathBuf,
-    ) -> AgroResult<()> {
+    ) -> AgroResult<[PathBuf; 3]> {
         let width = grid.width.max(1);
         let height = grid.height.max(1);
         let max_obstacle_count = grid
@@ -2410,21 +2518,28 @@ impl LidarMapper {
         })?;
 
         info!("Saved obstacle heatmap to: {:?}", output_path);
-        self.save_obstacle_heatmap_evidence(
-            &Self::obstacle_heatmap_evidence(grid, max_obstacle_count),
-            output_dir,
-        )
-        .await?;
-        Ok(())
+        let legend = continuous_legend(
+            &OBSTACLE_HEATMAP_PALETTE,
+            (0.0, max_obstacle_count as f32),
+            (max_obstacle_count + 1).min(OBSTACLE_HEATMAP_LEGEND_TICKS),
+        );
+        let legend_path = legend_path_for(&output_path);
+        legend.write_png(&legend_path)?;
+        let evidence_path = self
+            .save_obstacle_heatmap_evidence(
+                &Self::obstacle_heatmap_evidence(grid, max_obstacle_count),
+                output_dir,
+            )
+            .await?;
+        Ok([output_path, legend_path, evidence_path])
     }
 
     fn obstacle_heatmap_color(obstacle_count: usize, max_obstacle_count: usize) -> [u8; 3] {
         if max_obstacle_count == 0 {
-            return [0, 0, 255];
+            return OBSTACLE_HEATMAP_PALETTE.color_at(0.0);
         }
 
-        let intensity = (obstacle_count as f32 / max_obstacle_count as f32 * 255.0).round() as u8;
-        [intensity, 0, 255u8.saturating_sub(intensity)]
+        OBSTACLE_HEATMAP_PALETTE.color_at(obstacle_count as f32 / max_obstacle_count as f32)
     }
 
     fn obstacle_heatmap_evidence(
@@ -2434,6 +2549,7 @@ impl LidarMapper {
         LidarObstacleHeatmapEvidence {
             occupancy: grid.evidence,
             max_obstacle_count,
+            palette: OBSTACLE_HEATMAP_PALETTE.name.to_string(),
             spatial_ref: grid.spatial_ref.clone(),
             width: grid.width,
             height: grid.height,
@@ -2444,12 +2560,12 @@ impl LidarMapper {
         &self,
         evidence: &LidarObstacleHeatmapEvidence,
         output_dir: &PathBuf,
-    ) -> AgroResult<()> {
+    ) -> AgroResult<PathBuf> {
         let output_path = output_dir.join("obstacle_heatmap_evidence.json");
         let content = serde_json::to_vec_pretty(evidence)?;
         tokio::fs::write(&output_path, content).await?;
         info!("Saved obstacle heatmap evidence to: {:?}", output_path);
-        Ok(())
+        Ok(output_path)
     }
 }
 
@@ -2625,6 +2741,49 @@ mod tests {
         assert_eq!(LidarMapper::angular_coverage_degrees(&wraparound), 20.0);
     }
 
+    #[tokio::test]
+    async fn process_directory_writes_a_manifest_of_every_output() {
+        let mapper = test_mapper();
+        let input_dir = temp_dir("manifest_input");
+        let output_dir = temp_dir("manifest_output");
+        let scan = scan_with_angles(Uuid::new_v4(), Utc::now(), &[0.0, 90.0, 180.0, 270.0]);
+        let scan_path = input_dir.join("scan_0001.json");
+        fs::write(&scan_path, serde_json::to_string(&scan).unwrap()).unwrap();
+        fs::write(output_dir.join("earlier_run.tif"), b"old").unwrap();
+        fs::write(output_dir.join(shared::OUTPUT_MANIFEST_FILE_NAME), b"stale").unwrap();
+
+        mapper
+            .process_directory(&input_dir, &output_dir)
+            .await
+            .unwrap();
+
+        let manifest = OutputManifest::read(&output_dir).unwrap();
+        let mut written: Vec<String> = fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name != shared::OUTPUT_MANIFEST_FILE_NAME && name != "earlier_run.tif")
+            .collect();
+        written.sort();
+        let listed: Vec<String> = manifest
+            .files
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect();
+        assert_eq!(listed, written);
+        for file_type in ["json", "png", "pcd"] {
+            assert!(manifest
+                .files
+                .iter()
+                .any(|entry| entry.file_type == file_type));
+        }
+        for entry in &manifest.files {
+            let contents = fs::read(output_dir.join(&entry.path)).unwrap();
+            assert_eq!(entry.size_bytes, contents.len() as u64);
+            assert_eq!(entry.sha256, format!("{:x}", Sha256::digest(&contents)));
+            assert_eq!(entry.source_inputs, [scan_path.to_string_lossy()]);
+        }
+    }
+
     #[tokio::test]
     async fn ingest_scans_records_summary_and_skips_malformed() {
         let mapper = test_mapper();
@@ -2676,6 +2835,43 @@ mod tests {
         assert_eq!(persisted, ingest.summary);
     }
 
+    #[tokio::test]
+    async fn ingest_scans_reads_las_point_clouds_as_planar_scans() {
+        let mapper = test_mapper();
+        let input_dir = temp_dir("las_input");
+        let output_dir = temp_dir("las_output");
+        let point = |x: f32, y: f32, intensity: f32| sensor_overlay_engine::LidarPoint {
+            x,
+            y,
+            z: 0.5,
+            intensity,
+            return_number: 1,
+            classification: 2,
+        };
+        let cloud_path = input_dir.join("field.las");
+        sensor_overlay_engine::write_las(
+            &cloud_path,
+            &[point(1.0, 0.0, 65535.0), point(0.0, 2.0, 0.0)],
+        )
+        .unwrap();
+
+        let ingest = mapper.ingest_scans(&input_dir, &output_dir).await.unwrap();
+
+        assert_eq!(ingest.summary.loaded_count, 1);
+        assert!(ingest.summary.records[0].path.ends_with("field.las"));
+        let points = &ingest.scans[0].points;
+        assert_eq!(points.len(), 2);
+        assert!((points[0].angle - 0.0).abs() < 0.1);
+        assert!((points[0].distance - 1000.0).abs() < 1.0);
+        assert_eq!(points[0].quality, 255);
+        assert!((points[1].angle - 90.0).abs() < 0.1);
+        assert!((points[1].distance - 2000.0).abs() < 1.0);
+        assert_eq!(points[1].quality, 0);
+
+        let rerun = mapper.ingest_scans(&input_dir, &output_dir).await.unwrap();
+        assert_eq!(rerun.scans[0].scan_id, ingest.scans[0].scan_id);
+    }
+
     #[test]
     fn build_occupancy_grid_asserts_spatial_ref_from_cell_extent() {
         let mapper = test_mapper_with_resolution(1.0);
@@ -3056,8 +3252,13 @@ mod tests {
             .unwrap()
             .to_rgb8();
         assert_eq!(image.dimensions(), (2, 1));
-        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255]);
-        assert_eq!(image.get_pixel(1, 0).0, [255, 0, 0]);
+        assert_eq!(image.get_pixel(0, 0).0, [0, 32, 77]);
+        assert_eq!(image.get_pixel(1, 0).0, [255, 234, 70]);
+        let legend = fs::read(output_dir.join("obstacle_heatmap_legend.png")).unwrap();
+        assert_eq!(
+            shared::legend::legend_labels(&legend).unwrap(),
+            ["0", "1", "2", "3", "4"]
+        );
 
         let evidence: LidarObstacleHeatmapEvidence = serde_json::from_str(
             &fs::read_to_string(output_dir.join("obstacle_heatmap_evidence.json")).unwrap(),
@@ -3065,6 +3266,7 @@ mod tests {
         .unwrap();
         assert_eq!(evidence.occupancy, grid.evidence);
         assert_eq!(evidence.max_obstacle_count, 4);
+        assert_eq!(evidence.palette, "cividis");
         assert_eq!(evidence.width, 2);
         assert_eq!(evidence.height, 1);
         assert_eq!(evidence.spatial_ref.bbox, grid.spatial_ref.bbox);
@@ -3085,7 +3287,7 @@ mod tests {
             .unwrap()
             .to_rgb8();
         assert_eq!(image.dimensions(), (1, 1));
-        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255]);
+        assert_eq!(image.get_pixel(0, 0).0, [0, 32, 77]);
 
         let evidence: LidarObstacleHeatmapEvidence = serde_json::from_str(
             &fs::read_to_string(output_dir.join("obstacle_heatmap_evidence.json")).unwrap(),
@@ -3578,7 +3780,11 @@ mod tests {
         };
 
         let err = mapper.build_canopy_height_raster(&dsm, &dtm).unwrap_err();
-        assert!(err.to_string().contains("aligned DSM and DTM extents"));
+        assert!(matches!(
+            err,
+            shared::error::AgroError::DimensionMismatch { ref expected, ref actual, .. }
+                if expected == "2x2" && actual == "2x3"
+        ));
     }
 
     #[test]
@@ -0,0 +1,527 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::{
+    error::AgroError,
+    schemas::{Telemetry, WebSocketMessage},
+    AgroResult,
+};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// Telemetry fields a rule can watch, as dotted paths into `Telemetry`.
+/// `armed` reads as 1 when armed and 0 otherwise.
+pub const TELEMETRY_METRICS: &[&str] = &[
+    "position.latitude",
+    "position.longitude",
+    "position.altitude",
+    "battery_voltage",
+    "battery_percentage",
+    "armed",
+    "ground_speed",
+    "air_speed",
+    "heading",
+    "altitude_relative",
+];
+
+fn metric_value(telemetry: &Telemetry, metric: &str) -> Option<f64> {
+    let value = match metric {
+        "position.latitude" => telemetry.position.latitude,
+        "position.longitude" => telemetry.position.longitude,
+        "position.altitude" => telemetry.position.altitude,
+        "battery_voltage" => f64::from(telemetry.battery_voltage),
+        "battery_percentage" => f64::from(telemetry.battery_percentage),
+        "armed" => f64::from(u8::from(telemetry.armed)),
+        "ground_speed" => f64::from(telemetry.ground_speed),
+        "air_speed" => f64::from(telemetry.air_speed),
+        "heading" => f64::from(telemetry.heading),
+        "altitude_relative" => f64::from(telemetry.altitude_relative),
+        _ => return None,
+    };
+    Some(value)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertComparison {
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+}
+
+impl AlertComparison {
+    pub fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Self::GreaterThan => value > threshold,
+            Self::GreaterThanOrEqual => value >= threshold,
+            Self::LessThan => value < threshold,
+            Self::LessThanOrEqual => value <= threshold,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::GreaterThan => ">",
+            Self::GreaterThanOrEqual => ">=",
+            Self::LessThan => "<",
+            Self::LessThanOrEqual => "<=",
+        }
+    }
+}
+
+/// Severity of a triggered alert, sent as the `SystemStatus` status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Info,
+    Warn,
+    Critical,
+}
+
+impl AlertSeverity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// An operator-defined condition on one telemetry metric.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub id: String,
+    /// One of [`TELEMETRY_METRICS`].
+    pub metric: String,
+    pub comparison: AlertComparison,
+    pub threshold: f64,
+    /// When set, the rule compares the metric's change over this many
+    /// seconds instead of its value, so `battery_percentage`, `less_than`,
+    /// `-1` with a 10 s window fires when the battery drops faster than 1%
+    /// per 10 s.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_window_secs: Option<f64>,
+    pub severity: AlertSeverity,
+    /// Minimum time between a rule's trigger and clear notifications for
+    /// one vehicle; changes inside it are held back until it passes.
+    #[serde(default)]
+    pub cooldown_secs: f64,
+}
+
+impl AlertRule {
+    pub fn validate(&self) -> Result<(), AlertRuleError> {
+        let invalid = |reason: &str| AlertRuleError::InvalidRule {
+            rule_id: self.id.clone(),
+            reason: reason.to_string(),
+        };
+        if self.id.trim().is_empty() {
+            return Err(invalid("id is empty"));
+        }
+        if !TELEMETRY_METRICS.contains(&self.metric.as_str()) {
+            return Err(AlertRuleError::UnknownMetric {
+                rule_id: self.id.clone(),
+                metric: self.metric.clone(),
+                valid: TELEMETRY_METRICS.join(", "),
+            });
+        }
+        if !self.threshold.is_finite() {
+            return Err(invalid("threshold is not a finite number"));
+        }
+        if self
+            .rate_window_secs
+            .is_some_and(|window| !window.is_finite() || window <= 0.0)
+        {
+            return Err(invalid("rate window must be a positive number of seconds"));
+        }
+        if !self.cooldown_secs.is_finite() || self.cooldown_secs < 0.0 {
+            return Err(invalid("cooldown must be zero or more seconds"));
+        }
+        Ok(())
+    }
+
+    fn describe_value(&self, value: f64) -> String {
+        match self.rate_window_secs {
+            Some(window) => format!("{} changed by {:.2} per {}s", self.metric, value, window),
+            None => format!("{} is {:.2}", self.metric, value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum AlertRuleError {
+    #[error("alert rule {rule_id} watches unknown metric `{metric}`; valid metrics are {valid}")]
+    UnknownMetric {
+        rule_id: String,
+        metric: String,
+        valid: String,
+    },
+    #[error("invalid alert rule {rule_id}: {reason}")]
+    InvalidRule { rule_id: String, reason: String },
+    #[error("alert rule {0} already exists")]
+    DuplicateRule(String),
+    #[error("alert rule {0} not found")]
+    RuleNotFound(String),
+}
+
+/// A rule starting or stopping to hold for a vehicle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertEvent {
+    pub rule_id: String,
+    pub vehicle_id: String,
+    pub severity: AlertSeverity,
+    pub triggered: bool,
+    pub value: f64,
+    pub message: String,
+}
+
+impl AlertEvent {
+    /// Triggers carry the rule's severity; clears go out as `ok`.
+    pub fn to_system_status(&self) -> WebSocketMessage {
+        let status = if self.triggered {
+            self.severity.as_str()
+        } else {
+            "ok"
+        };
+        WebSocketMessage::SystemStatus {
+            status: status.to_string(),
+            message: self.message.clone(),
+        }
+    }
+}
+
+/// One rule's progress on one vehicle.
+#[derive(Debug, Default)]
+struct RuleState {
+    active: bool,
+    last_change: Option<DateTime<Utc>>,
+    /// Samples covering the rate window, oldest first.
+    samples: VecDeque<(DateTime<Utc>, f64)>,
+}
+
+impl RuleState {
+    /// Change over `window` seconds, or `None` until the samples span it.
+    fn rate(&mut self, at: DateTime<Utc>, value: f64, window: f64) -> Option<f64> {
+        self.samples.push_back((at, value));
+        let window_start = at - chrono::Duration::milliseconds((window * 1000.0) as i64);
+        // Keep the newest sample at or before the window start as the base.
+        while self.samples.len() > 2 && self.samples[1].0 <= window_start {
+            self.samples.pop_front();
+        }
+        let (base_at, base_value) = *self.samples.front()?;
+        if base_at > window_start {
+            return None;
+        }
+        let elapsed = (at - base_at).num_milliseconds() as f64 / 1000.0;
+        Some((value - base_value) / elapsed * window)
+    }
+}
+
+#[derive(Debug, Default)]
+struct AlertEngineState {
+    rules: Vec<AlertRule>,
+    /// Keyed by vehicle and rule id.
+    progress: HashMap<(String, String), RuleState>,
+}
+
+/// Evaluates operator-defined [`AlertRule`]s against each vehicle's
+/// telemetry, reporting a rule once when it starts to hold and once when it
+/// stops.
+#[derive(Debug, Default)]
+pub struct AlertEngine {
+    state: Mutex<AlertEngineState>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Result<Self, AlertRuleError> {
+        let engine = Self::default();
+        for rule in rules {
+            engine.add_rule(rule)?;
+        }
+        Ok(engine)
+    }
+
+    /// Reads a JSON array of rules from `path`.
+    pub fn load(path: &Path) -> AgroResult<Self> {
+        let document = std::fs::read_to_string(path).map_err(|error| {
+            AgroError::ConfigValidation(format!(
+                "failed to read alert rules `{}`: {error}",
+                path.display()
+            ))
+        })?;
+        let rules: Vec<AlertRule> = serde_json::from_str(&document)?;
+        Self::new(rules).map_err(|error| AgroError::ConfigValidation(error.to_string()))
+    }
+
+    pub fn rules(&self) -> Vec<AlertRule> {
+        self.lock().rules.clone()
+    }
+
+    pub fn add_rule(&self, rule: AlertRule) -> Result<(), AlertRuleError> {
+        rule.validate()?;
+        let mut state = self.lock();
+        if state.rules.iter().any(|existing| existing.id == rule.id) {
+            return Err(AlertRuleError::DuplicateRule(rule.id));
+        }
+        state.rules.push(rule);
+        Ok(())
+    }
+
+    /// Removes a rule and forgets its progress on every vehicle.
+    pub fn remove_rule(&self, rule_id: &str) -> Result<AlertRule, AlertRuleError> {
+        let mut state = self.lock();
+        let index = state
+            .rules
+            .iter()
+            .position(|rule| rule.id == rule_id)
+            .ok_or_else(|| AlertRuleError::RuleNotFound(rule_id.to_string()))?;
+        state.progress.retain(|(_, id), _| id != rule_id);
+        Ok(state.rules.remove(index))
+    }
+
+    /// Runs every rule over one telemetry sample from `vehicle_id`, using the
+    /// sample's timestamp as the clock.
+    pub fn evaluate(&self, vehicle_id: &str, telemetry: &Telemetry) -> Vec<AlertEvent> {
+        let at = telemetry.timestamp;
+        let mut guard = self.lock();
+        let AlertEngineState { rules, progress } = &mut *guard;
+
+        let mut events = Vec::new();
+        for rule in rules.iter() {
+            let Some(value) = metric_value(telemetry, &rule.metric) else {
+                continue;
+            };
+            let rule_state = progress
+                .entry((vehicle_id.to_string(), rule.id.clone()))
+                .or_default();
+            let observed = match rule.rate_window_secs {
+                Some(window) => match rule_state.rate(at, value, window) {
+                    Some(rate) => rate,
+                    None => continue,
+                },
+                None => value,
+            };
+
+            let holds = rule.comparison.holds(observed, rule.threshold);
+            if holds == rule_state.active {
+                continue;
+            }
+            let cooling_down = rule_state.last_change.is_some_and(|changed| {
+                ((at - changed).num_milliseconds() as f64) < rule.cooldown_secs * 1000.0
+            });
+            if cooling_down {
+                continue;
+            }
+
+            rule_state.active = holds;
+            rule_state.last_change = Some(at);
+            let message = if holds {
+                format!(
+                    "Alert {} on vehicle {}: {} ({} {})",
+                    rule.id,
+                    vehicle_id,
+                    rule.describe_value(observed),
+                    rule.comparison.symbol(),
+                    rule.threshold
+                )
+            } else {
+                format!(
+                    "Alert {} on vehicle {} cleared: {}",
+                    rule.id,
+                    vehicle_id,
+                    rule.describe_value(observed)
+                )
+            };
+            events.push(AlertEvent {
+                rule_id: rule.id.clone(),
+                vehicle_id: vehicle_id.to_string(),
+                severity: rule.severity,
+                triggered: holds,
+                value: observed,
+                message,
+            });
+        }
+        events
+    }
+
+    /// Evaluates telemetry on `event_rx` as coming from `vehicle_id` and
+    /// publishes alert changes on `event_tx` until the bus closes.
+    pub async fn run(
+        &self,
+        vehicle_id: &str,
+        mut event_rx: broadcast::Receiver<WebSocketMessage>,
+        event_tx: broadcast::Sender<WebSocketMessage>,
+    ) -> AgroResult<()> {
+        loop {
+            match event_rx.recv().await {
+                Ok(WebSocketMessage::Telemetry { data }) => {
+                    for event in self.evaluate(vehicle_id, &data) {
+                        if let Err(e) = event_tx.send(event.to_system_status()) {
+                            warn!("Failed to send alert {}: {}", event.rule_id, e);
+                        }
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Alert rules skipped {} lagged messages", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, AlertEngineState> {
+        // Every update leaves the state whole, so a poisoned lock is still usable.
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::schemas::GpsCoords;
+
+    fn telemetry(seconds: i64, ground_speed: f32, battery_percentage: u8) -> Telemetry {
+        Telemetry {
+            timestamp: DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+            position: GpsCoords {
+                latitude: 41.0,
+                longitude: -96.0,
+                altitude: 350.0,
+            },
+            battery_voltage: 12.4,
+            battery_percentage,
+            armed: true,
+            mode: "AUTO".to_string(),
+            ground_speed,
+            air_speed: ground_speed,
+            heading: 90.0,
+            altitude_relative: 40.0,
+        }
+    }
+
+    fn speed_rule(cooldown_secs: f64) -> AlertRule {
+        AlertRule {
+            id: "overspeed".to_string(),
+            metric: "ground_speed".to_string(),
+            comparison: AlertComparison::GreaterThan,
+            threshold: 18.0,
+            rate_window_secs: None,
+            severity: AlertSeverity::Warn,
+            cooldown_secs,
+        }
+    }
+
+    #[test]
+    fn threshold_rules_trigger_once_hold_through_cooldown_and_clear() {
+        let engine = AlertEngine::new(vec![speed_rule(10.0)]).unwrap();
+        let script = [
+            (0, 12.0),
+            (1, 19.0),
+            (2, 20.0),
+            (4, 15.0),
+            (8, 19.5),
+            (12, 14.0),
+        ];
+
+        let events: Vec<(i64, AlertEvent)> = script
+            .iter()
+            .flat_map(|&(second, speed)| {
+                engine
+                    .evaluate("1", &telemetry(second, speed, 80))
+                    .into_iter()
+                    .map(move |event| (second, event))
+            })
+            .collect();
+
+        // The dip at 4 s falls inside the cooldown and is never reported.
+        assert_eq!(events.len(), 2);
+        let (at, trigger) = &events[0];
+        assert_eq!(*at, 1);
+        assert!(trigger.triggered);
+        assert!(matches!(
+            trigger.to_system_status(),
+            WebSocketMessage::SystemStatus { status, message }
+                if status == "warn"
+                    && message == "Alert overspeed on vehicle 1: ground_speed is 19.00 (> 18)"
+        ));
+        let (at, clear) = &events[1];
+        assert_eq!(*at, 12);
+        assert!(!clear.triggered);
+        assert!(matches!(
+            clear.to_system_status(),
+            WebSocketMessage::SystemStatus { status, .. } if status == "ok"
+        ));
+    }
+
+    #[test]
+    fn rules_are_tracked_separately_per_vehicle() {
+        let engine = AlertEngine::new(vec![speed_rule(0.0)]).unwrap();
+
+        assert_eq!(engine.evaluate("1", &telemetry(0, 19.0, 80)).len(), 1);
+        assert!(engine.evaluate("2", &telemetry(0, 12.0, 80)).is_empty());
+        assert_eq!(engine.evaluate("2", &telemetry(1, 19.0, 80)).len(), 1);
+        assert!(engine.evaluate("1", &telemetry(1, 19.0, 80)).is_empty());
+    }
+
+    #[test]
+    fn rate_rules_compare_the_change_over_their_window() {
+        let engine = AlertEngine::new(vec![AlertRule {
+            id: "battery_drain".to_string(),
+            metric: "battery_percentage".to_string(),
+            comparison: AlertComparison::LessThan,
+            threshold: -1.0,
+            rate_window_secs: Some(10.0),
+            severity: AlertSeverity::Critical,
+            cooldown_secs: 0.0,
+        }])
+        .unwrap();
+
+        // 1% per 10 s is on the threshold, not past it; nothing is reported
+        // before the samples span a whole window.
+        for (second, battery) in [(0, 90), (5, 90), (10, 89), (20, 88)] {
+            assert!(engine
+                .evaluate("1", &telemetry(second, 10.0, battery))
+                .is_empty());
+        }
+
+        // The newest sample at or before 15 s is 89% at 10 s, so the change
+        // is (85 - 89) / 15 s, scaled to the 10 s window.
+        let events = engine.evaluate("1", &telemetry(25, 10.0, 85));
+        assert_eq!(events.len(), 1);
+        assert!(events[0].triggered);
+        assert!((events[0].value - (85.0 - 89.0) / 15.0 * 10.0).abs() < 1e-9);
+        assert!(events[0].message.contains("changed by -2.67 per 10s"));
+
+        let events = engine.evaluate("1", &telemetry(40, 10.0, 85));
+        assert_eq!(events.len(), 1);
+        assert!(!events[0].triggered);
+        assert_eq!(events[0].value, 0.0);
+    }
+
+    #[test]
+    fn unknown_metrics_are_rejected_with_the_valid_paths() {
+        let error = AlertEngine::new(vec![AlertRule {
+            metric: "groundspeed".to_string(),
+            ..speed_rule(0.0)
+        }])
+        .unwrap_err();
+
+        let message = error.to_string();
+        assert!(message.contains("`groundspeed`"));
+        assert!(message.contains("ground_speed, air_speed, heading, altitude_relative"));
+
+        let engine = AlertEngine::new(vec![speed_rule(0.0)]).unwrap();
+        assert_eq!(
+            engine.add_rule(speed_rule(5.0)),
+            Err(AlertRuleError::DuplicateRule("overspeed".to_string()))
+        );
+        assert!(engine.remove_rule("overspeed").is_ok());
+        assert!(engine.rules().is_empty());
+    }
+}
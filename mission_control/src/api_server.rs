@@ -1,41 +1,96 @@
+use crate::alert_rules::{AlertEngine, AlertRule, AlertRuleError};
+use crate::control_auth::require_control_token;
+use crate::mavlink_client::MavlinkClient;
+use crate::vehicle_mission::VehicleMission;
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
     response::Json as ResponseJson,
-    routing::{get, post},
+    routing::{delete, get, post},
     Router,
 };
+use mavlink::common::MavMissionResult;
+use serde::Deserialize;
 use shared::{
     config::AgroConfig,
-    schemas::{Mission, WebSocketMessage},
-    AgroResult,
+    schemas::{Mission, OverlayNotification, WebSocketMessage},
+    AgroResult, SupervisionReport, TaskSupervisor,
 };
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::broadcast;
 use tracing::{info, warn};
 
+/// Upper bound for a whole mission transfer with the vehicle.
+pub(crate) const VEHICLE_MISSION_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct ApiServer {
     config: Arc<AgroConfig>,
     event_tx: broadcast::Sender<WebSocketMessage>,
+    supervisor: TaskSupervisor,
+    vehicle: Option<Arc<MavlinkClient>>,
+    alerts: Arc<AlertEngine>,
 }
 
 impl ApiServer {
-    pub fn new(config: Arc<AgroConfig>, event_tx: broadcast::Sender<WebSocketMessage>) -> Self {
-        Self { config, event_tx }
+    pub fn new(
+        config: Arc<AgroConfig>,
+        event_tx: broadcast::Sender<WebSocketMessage>,
+        supervisor: TaskSupervisor,
+    ) -> Self {
+        Self {
+            config,
+            event_tx,
+            supervisor,
+            vehicle: None,
+            alerts: Arc::new(AlertEngine::default()),
+        }
     }
 
-    pub async fn run(&self) -> AgroResult<()> {
+    /// Manages `alerts`' rules through `/api/alerts/rules`.
+    pub fn with_alert_engine(mut self, alerts: Arc<AlertEngine>) -> Self {
+        self.alerts = alerts;
+        self
+    }
+
+    /// Serves `/api/vehicle/mission` through `vehicle`; without a link those
+    /// routes answer 503. Either way they need the control token.
+    pub fn with_vehicle(mut self, vehicle: Arc<MavlinkClient>) -> Self {
+        self.vehicle = Some(vehicle);
+        self
+    }
+
+    pub fn router(&self) -> Router {
         let app_state = ApiState {
             config: self.config.clone(),
             event_tx: self.event_tx.clone(),
+            supervisor: self.supervisor.clone(),
+            vehicle: self.vehicle.clone(),
+            alerts: self.alerts.clone(),
         };
 
-        let app = Router::new()
+        Router::new()
             .route("/health", get(health_check))
+            .route("/ready", get(readiness))
             .route("/missions", post(upload_mission))
             .route("/missions", get(list_missions))
             .route("/telemetry", get(get_current_telemetry))
-            .with_state(app_state);
+            .route("/overlays/completed", post(overlay_completed))
+            .route(
+                "/api/vehicle/mission",
+                get(download_vehicle_mission).post(upload_vehicle_mission),
+            )
+            .route(
+                "/api/alerts/rules",
+                get(list_alert_rules).post(create_alert_rule),
+            )
+            .route("/api/alerts/rules/:rule_id", delete(delete_alert_rule))
+            .with_state(app_state)
+            .layer(self.config.cors.layer())
+    }
+
+    pub async fn run(&self) -> AgroResult<()> {
+        let app = self.router();
 
         let listener = tokio::net::TcpListener::bind(&self.config.server.api_bind_address).await?;
         info!(
@@ -53,12 +108,41 @@ impl ApiServer {
 struct ApiState {
     config: Arc<AgroConfig>,
     event_tx: broadcast::Sender<WebSocketMessage>,
+    supervisor: TaskSupervisor,
+    vehicle: Option<Arc<MavlinkClient>>,
+    alerts: Arc<AlertEngine>,
+}
+
+type VehicleResponse<T> = Result<ResponseJson<T>, (StatusCode, ResponseJson<serde_json::Value>)>;
+
+#[derive(Debug, Deserialize)]
+struct VehicleTarget {
+    #[serde(default = "default_vehicle_id")]
+    target_system: u8,
+    #[serde(default = "default_vehicle_id")]
+    target_component: u8,
+}
+
+fn default_vehicle_id() -> u8 {
+    1
 }
 
 async fn health_check() -> &'static str {
     "OK"
 }
 
+/// Supervised task states and restart counts; 503 once a task has exhausted
+/// its restart budget.
+async fn readiness(State(state): State<ApiState>) -> (StatusCode, ResponseJson<SupervisionReport>) {
+    let report = state.supervisor.report();
+    let status = if report.healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, ResponseJson(report))
+}
+
 async fn upload_mission(
     State(state): State<ApiState>,
     Json(mission): Json<Mission>,
@@ -96,6 +180,27 @@ async fn upload_mission(
     })))
 }
 
+/// Completion callback from the overlay engine's live mode; forwarded to
+/// ground stations as-is.
+async fn overlay_completed(
+    State(state): State<ApiState>,
+    Json(overlay): Json<OverlayNotification>,
+) -> StatusCode {
+    info!(
+        "Overlay {} generated at {}",
+        overlay.job_id, overlay.composite_path
+    );
+
+    if let Err(e) = state
+        .event_tx
+        .send(WebSocketMessage::OverlayGenerated { overlay })
+    {
+        warn!("Failed to forward overlay notification: {}", e);
+    }
+
+    StatusCode::ACCEPTED
+}
+
 async fn list_missions(
     State(state): State<ApiState>,
 ) -> Result<ResponseJson<Vec<Mission>>, StatusCode> {
@@ -135,3 +240,349 @@ async fn get_current_telemetry(
         "message": "Use WebSocket connection for real-time telemetry"
     })))
 }
+
+/// Pushes a mission in the planner's MAVLink JSON layout to the connected
+/// vehicle. Upload progress goes out to WebSocket clients as status messages.
+async fn upload_vehicle_mission(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(mission): Json<VehicleMission>,
+) -> VehicleResponse<serde_json::Value> {
+    let vehicle = authorized_vehicle(&state, &headers)?;
+    let items = mission
+        .to_mission_items()
+        .map_err(|e| json_error(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    let item_count = items.len();
+    info!("Uploading {} mission items to the vehicle", item_count);
+
+    match vehicle.upload_mission(items, VEHICLE_MISSION_TIMEOUT).await {
+        Ok(MavMissionResult::MAV_MISSION_ACCEPTED) => Ok(ResponseJson(serde_json::json!({
+            "success": true,
+            "items_uploaded": item_count,
+            "message": "Mission uploaded to vehicle"
+        }))),
+        Ok(result) => Err(json_error(
+            StatusCode::CONFLICT,
+            format!("Vehicle rejected the mission: {:?}", result),
+        )),
+        Err(e) => {
+            warn!("Mission upload to vehicle failed: {}", e);
+            Err(json_error(StatusCode::BAD_GATEWAY, e.to_string()))
+        }
+    }
+}
+
+/// Reads back the mission currently stored on the vehicle.
+async fn download_vehicle_mission(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Query(target): Query<VehicleTarget>,
+) -> VehicleResponse<VehicleMission> {
+    let vehicle = authorized_vehicle(&state, &headers)?;
+    let items = vehicle
+        .download_mission(
+            target.target_system,
+            target.target_component,
+            VEHICLE_MISSION_TIMEOUT,
+        )
+        .await
+        .map_err(|e| {
+            warn!("Mission download from vehicle failed: {}", e);
+            json_error(StatusCode::BAD_GATEWAY, e.to_string())
+        })?;
+
+    Ok(ResponseJson(VehicleMission::from_mission_items(
+        target.target_system,
+        target.target_component,
+        &items,
+    )))
+}
+
+async fn list_alert_rules(State(state): State<ApiState>) -> ResponseJson<Vec<AlertRule>> {
+    ResponseJson(state.alerts.rules())
+}
+
+/// Adds a rule; 422 names the valid metric paths when the rule's is unknown.
+async fn create_alert_rule(
+    State(state): State<ApiState>,
+    Json(rule): Json<AlertRule>,
+) -> Result<(StatusCode, ResponseJson<AlertRule>), (StatusCode, ResponseJson<serde_json::Value>)> {
+    state
+        .alerts
+        .add_rule(rule.clone())
+        .map_err(alert_rule_error)?;
+    info!("Added alert rule {}", rule.id);
+    Ok((StatusCode::CREATED, ResponseJson(rule)))
+}
+
+async fn delete_alert_rule(
+    State(state): State<ApiState>,
+    Path(rule_id): Path<String>,
+) -> Result<ResponseJson<AlertRule>, (StatusCode, ResponseJson<serde_json::Value>)> {
+    let rule = state
+        .alerts
+        .remove_rule(&rule_id)
+        .map_err(alert_rule_error)?;
+    info!("Removed alert rule {}", rule.id);
+    Ok(ResponseJson(rule))
+}
+
+fn alert_rule_error(error: AlertRuleError) -> (StatusCode, ResponseJson<serde_json::Value>) {
+    let status = match error {
+        AlertRuleError::UnknownMetric { .. } | AlertRuleError::InvalidRule { .. } => {
+            StatusCode::UNPROCESSABLE_ENTITY
+        }
+        AlertRuleError::DuplicateRule(_) => StatusCode::CONFLICT,
+        AlertRuleError::RuleNotFound(_) => StatusCode::NOT_FOUND,
+    };
+    json_error(status, error.to_string())
+}
+
+/// The vehicle link, once the caller has shown the control token.
+fn authorized_vehicle<'a>(
+    state: &'a ApiState,
+    headers: &HeaderMap,
+) -> Result<&'a Arc<MavlinkClient>, (StatusCode, ResponseJson<serde_json::Value>)> {
+    require_control_token(&state.config, headers)
+        .map_err(|(status, message)| json_error(status, message.to_string()))?;
+    state.vehicle.as_ref().ok_or_else(|| {
+        json_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No vehicle link; mission transfer needs flight mode".to_string(),
+        )
+    })
+}
+
+fn json_error(
+    status: StatusCode,
+    message: String,
+) -> (StatusCode, ResponseJson<serde_json::Value>) {
+    (
+        status,
+        ResponseJson(serde_json::json!({
+            "success": false,
+            "message": message
+        })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mavlink_client::mock_vehicle;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn mission_json(item_count: u16) -> serde_json::Value {
+        let items = (0..item_count)
+            .map(|seq| {
+                serde_json::json!({
+                    "seq": seq,
+                    "frame": 3,
+                    "command": 16,
+                    "current": u8::from(seq == 0),
+                    "autocontinue": 1,
+                    "param1": 0.0,
+                    "param2": 0.0,
+                    "param3": 0.0,
+                    "param4": 0.0,
+                    "x": 41.1 + f32::from(seq) * 0.001,
+                    "y": -96.1,
+                    "z": 30.0,
+                    "mission_type": 0
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({
+            "target_system": 1,
+            "target_component": 1,
+            "count": item_count,
+            "items": items
+        })
+    }
+
+    fn config_with_token() -> Arc<AgroConfig> {
+        let mut config = AgroConfig::default();
+        config.server.control_token = Some("s3cret".to_string());
+        Arc::new(config)
+    }
+
+    async fn json_body(response: axum::response::Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn vehicle_mission_is_uploaded_with_progress_and_read_back() {
+        let (vehicle, mut events, stored) =
+            mock_vehicle::connect(Some(mavlink::common::MavResult::MAV_RESULT_ACCEPTED)).await;
+        let (event_tx, _) = broadcast::channel(16);
+        let router = ApiServer::new(config_with_token(), event_tx, TaskSupervisor::new())
+            .with_vehicle(vehicle)
+            .router();
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::post("/api/vehicle/mission")
+                    .header("content-type", "application/json")
+                    .header("authorization", "Bearer s3cret")
+                    .body(Body::from(mission_json(4).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(json_body(response).await["items_uploaded"], 4);
+        assert_eq!(stored.lock().unwrap().len(), 4);
+        let mut progress = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let WebSocketMessage::SystemStatus { message, .. } = event {
+                progress.push(message);
+            }
+        }
+        assert_eq!(
+            progress.first().unwrap(),
+            "Mission upload: sent item 1 of 4"
+        );
+        assert_eq!(progress.last().unwrap(), "Mission upload accepted");
+
+        let response = router
+            .oneshot(
+                Request::get("/api/vehicle/mission")
+                    .header("authorization", "Bearer s3cret")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let mission = json_body(response).await;
+        assert_eq!(mission["count"], 4);
+        assert_eq!(mission["items"][3]["seq"], 3);
+    }
+
+    #[tokio::test]
+    async fn vehicle_mission_routes_need_a_vehicle_link() {
+        let (event_tx, _) = broadcast::channel(16);
+        let router = ApiServer::new(config_with_token(), event_tx, TaskSupervisor::new()).router();
+
+        let response = router
+            .oneshot(
+                Request::post("/api/vehicle/mission")
+                    .header("content-type", "application/json")
+                    .header("authorization", "Bearer s3cret")
+                    .body(Body::from(mission_json(2).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json_body(response).await["success"], false);
+    }
+
+    #[tokio::test]
+    async fn vehicle_mission_routes_need_the_control_token() {
+        let (vehicle, _events, stored) =
+            mock_vehicle::connect(Some(mavlink::common::MavResult::MAV_RESULT_ACCEPTED)).await;
+        let (event_tx, _) = broadcast::channel(16);
+        let router = ApiServer::new(config_with_token(), event_tx, TaskSupervisor::new())
+            .with_vehicle(vehicle)
+            .router();
+
+        for token in [None, Some("Bearer wrong")] {
+            let mut request =
+                Request::post("/api/vehicle/mission").header("content-type", "application/json");
+            if let Some(token) = token {
+                request = request.header("authorization", token);
+            }
+            let response = router
+                .clone()
+                .oneshot(
+                    request
+                        .body(Body::from(mission_json(2).to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        assert!(stored.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn alert_rules_are_managed_and_unknown_metrics_rejected() {
+        let (event_tx, _) = broadcast::channel(16);
+        let alerts = Arc::new(AlertEngine::default());
+        let router = ApiServer::new(
+            Arc::new(AgroConfig::default()),
+            event_tx,
+            TaskSupervisor::new(),
+        )
+        .with_alert_engine(alerts.clone())
+        .router();
+        let post_rule = |metric: &str| {
+            Request::post("/api/alerts/rules")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    serde_json::json!({
+                        "id": "high_altitude",
+                        "metric": metric,
+                        "comparison": "greater_than",
+                        "threshold": 115.0,
+                        "severity": "critical",
+                        "cooldown_secs": 30.0
+                    })
+                    .to_string(),
+                ))
+                .unwrap()
+        };
+
+        let response = router.clone().oneshot(post_rule("altitude")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let message = json_body(response).await["message"].to_string();
+        assert!(message.contains("altitude_relative"));
+
+        let response = router
+            .clone()
+            .oneshot(post_rule("altitude_relative"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(alerts.rules().len(), 1);
+
+        let response = router
+            .clone()
+            .oneshot(post_rule("altitude_relative"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = router
+            .clone()
+            .oneshot(
+                Request::get("/api/alerts/rules")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(json_body(response).await[0]["metric"], "altitude_relative");
+
+        let delete = || {
+            Request::delete("/api/alerts/rules/high_altitude")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = router.clone().oneshot(delete()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router.oneshot(delete()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,40 @@
+//! Bearer-token check for the paths that publish events or command the
+//! vehicle, guarded by `ServerConfig::control_token`.
+
+use axum::http::{header, HeaderMap, StatusCode};
+use shared::config::AgroConfig;
+
+/// Whether `headers` carry `Authorization: Bearer <control_token>`. Always
+/// false while no token is configured.
+pub fn is_authorized(config: &AgroConfig, headers: &HeaderMap) -> bool {
+    let Some(expected) = config.server.control_token.as_deref() else {
+        return false;
+    };
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.trim().as_bytes(), expected.as_bytes()))
+}
+
+/// Rejects a request without the control token: 503 while none is
+/// configured, 401 otherwise.
+pub fn require_control_token(
+    config: &AgroConfig,
+    headers: &HeaderMap,
+) -> Result<(), (StatusCode, &'static str)> {
+    if config.server.control_token.is_none() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No control token configured; set CONTROL_TOKEN to enable this route",
+        ));
+    }
+    if !is_authorized(config, headers) {
+        return Err((StatusCode::UNAUTHORIZED, "Missing or invalid control token"));
+    }
+    Ok(())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -1,32 +1,68 @@
 use clap::Parser;
-use shared::{config::AgroConfig, AgroResult, RuntimeMode};
+use shared::{
+    config::AgroConfig, error::AgroError, AgroResult, RuntimeMode, SupervisedTaskState,
+    SupervisionPolicy, TaskSupervisor,
+};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::broadcast;
 use tracing::{info, warn};
 
+pub mod alert_rules;
 pub mod api_server;
+pub mod control_auth;
 pub mod mavlink_client;
+pub mod telemetry_history;
+pub mod vehicle_mission;
 pub mod websocket_server;
+pub mod ws_encoding;
 
 #[derive(Parser, Debug)]
 #[command(name = "mission_control")]
 #[command(about = "Mission Control Service for agrodrone")]
 pub struct Args {
     #[arg(long, help = "Configuration file path")]
-    pub config: Option<String>,
+    pub config: Option<PathBuf>,
+
+    #[arg(long, help = "JSON file with alert rules to evaluate on telemetry")]
+    pub alert_rules: Option<PathBuf>,
 }
 
+/// Vehicle name alert messages use for the vehicle on this service's link.
+const LINK_VEHICLE_ID: &str = "1";
+
 pub struct MissionControlService {
     config: Arc<AgroConfig>,
     event_tx: broadcast::Sender<shared::schemas::WebSocketMessage>,
+    history: Arc<telemetry_history::TelemetryHistory>,
+    alerts: Arc<alert_rules::AlertEngine>,
+    supervisor: TaskSupervisor,
 }
 
 impl MissionControlService {
     pub async fn new() -> AgroResult<Self> {
-        let config = Arc::new(AgroConfig::load()?);
+        Ok(Self::with_config(AgroConfig::load()?))
+    }
+
+    pub fn with_config(config: AgroConfig) -> Self {
         let (event_tx, _) = broadcast::channel(1000);
+        let history = Arc::new(telemetry_history::TelemetryHistory::new(
+            config.server.ws_history_size,
+        ));
+
+        Self {
+            config: Arc::new(config),
+            event_tx,
+            history,
+            alerts: Arc::new(alert_rules::AlertEngine::default()),
+            supervisor: TaskSupervisor::new(),
+        }
+    }
 
-        Ok(Self { config, event_tx })
+    /// Starts with `alerts` in place of an empty rule set.
+    pub fn with_alert_engine(mut self, alerts: alert_rules::AlertEngine) -> Self {
+        self.alerts = Arc::new(alerts);
+        self
     }
 
     pub async fn run(&self) -> AgroResult<()> {
@@ -39,60 +75,125 @@ impl MissionControlService {
         tokio::fs::create_dir_all(&self.config.storage.data_root_path).await?;
         tokio::fs::create_dir_all(&self.config.storage.mission_data_path).await?;
 
+        // Record recent telemetry for WebSocket clients that connect later
+        let history = self.history.clone();
+        let history_tx = self.event_tx.clone();
+        self.supervisor.spawn_supervised(
+            "telemetry_history",
+            move || {
+                let history = history.clone();
+                let event_rx = history_tx.subscribe();
+                async move { history.run(event_rx).await }
+            },
+            SupervisionPolicy::default(),
+        );
+
+        // Evaluate operator alert rules on the telemetry stream
+        let alerts = self.alerts.clone();
+        let alerts_tx = self.event_tx.clone();
+        self.supervisor.spawn_supervised(
+            "alert_rules",
+            move || {
+                let alerts = alerts.clone();
+                let event_tx = alerts_tx.clone();
+                let event_rx = alerts_tx.subscribe();
+                async move { alerts.run(LINK_VEHICLE_ID, event_rx, event_tx).await }
+            },
+            SupervisionPolicy::default(),
+        );
+
         // Start MAVLink client
+        let mut vehicle = None;
         let mavlink_handle = match self.config.runtime_mode {
             RuntimeMode::Flight => {
                 info!("Starting MAVLink client for flight controller");
-                let client =
+                let client = Arc::new(
                     mavlink_client::MavlinkClient::new(self.config.clone(), self.event_tx.clone())
-                        .await?;
-                Some(tokio::spawn(async move {
-                    if let Err(e) = client.run().await {
-                        tracing::error!("MAVLink client error: {}", e);
-                    }
-                }))
+                        .await?,
+                );
+                vehicle = Some(client.clone());
+                self.supervisor.spawn_supervised(
+                    "mavlink_client",
+                    move || {
+                        let client = client.clone();
+                        async move { client.run().await }
+                    },
+                    SupervisionPolicy::default(),
+                )
             }
             RuntimeMode::Simulation => {
                 warn!("Running in simulation mode - MAVLink client disabled");
-                let client = mavlink_client::SimulatedMavlinkClient::new(
+                let client = Arc::new(mavlink_client::SimulatedMavlinkClient::new(
                     self.config.clone(),
                     self.event_tx.clone(),
-                );
-                Some(tokio::spawn(async move {
-                    if let Err(e) = client.run().await {
-                        tracing::error!("Simulated MAVLink client error: {}", e);
-                    }
-                }))
+                ));
+                self.supervisor.spawn_supervised(
+                    "simulated_mavlink_client",
+                    move || {
+                        let client = client.clone();
+                        async move { client.run().await }
+                    },
+                    SupervisionPolicy::default(),
+                )
             }
         };
 
         // Start WebSocket server
-        let ws_server =
-            websocket_server::WebSocketServer::new(self.config.clone(), self.event_tx.subscribe());
-        let ws_handle = tokio::spawn(async move {
-            if let Err(e) = ws_server.run().await {
-                tracing::error!("WebSocket server error: {}", e);
-            }
-        });
+        let mut ws_server = websocket_server::WebSocketServer::new(
+            self.config.clone(),
+            self.event_tx.clone(),
+            self.history.clone(),
+        );
+        if let Some(vehicle) = &vehicle {
+            ws_server = ws_server.with_vehicle(vehicle.clone());
+        }
+        let ws_server = Arc::new(ws_server);
+        let ws_handle = self.supervisor.spawn_supervised(
+            "websocket_server",
+            move || {
+                let ws_server = ws_server.clone();
+                async move { ws_server.run().await }
+            },
+            SupervisionPolicy::default(),
+        );
 
         // Start API server
-        let api_server = api_server::ApiServer::new(self.config.clone(), self.event_tx.clone());
-        let api_handle = tokio::spawn(async move {
-            if let Err(e) = api_server.run().await {
-                tracing::error!("API server error: {}", e);
-            }
-        });
-
-        // Wait for all services
-        tokio::select! {
-            _ = ws_handle => info!("WebSocket server finished"),
-            _ = api_handle => info!("API server finished"),
-            _ = async {
-                if let Some(handle) = mavlink_handle {
-                    handle.await.ok();
-                }
-            } => info!("MAVLink client finished"),
+        let mut api_server = api_server::ApiServer::new(
+            self.config.clone(),
+            self.event_tx.clone(),
+            self.supervisor.clone(),
+        )
+        .with_alert_engine(self.alerts.clone());
+        if let Some(vehicle) = vehicle {
+            api_server = api_server.with_vehicle(vehicle);
+        }
+        let api_server = Arc::new(api_server);
+        let api_handle = self.supervisor.spawn_supervised(
+            "api_server",
+            move || {
+                let api_server = api_server.clone();
+                async move { api_server.run().await }
+            },
+            SupervisionPolicy::default(),
+        );
+
+        // A task only finishes here once it completed or ran out of restarts.
+        let finished = tokio::select! {
+            status = ws_handle => status,
+            status = api_handle => status,
+            status = mavlink_handle => status,
+        }
+        .map_err(|error| anyhow::anyhow!("supervisor task aborted: {error}"))?;
+
+        if finished.state == SupervisedTaskState::Failed {
+            return Err(AgroError::Other(anyhow::anyhow!(
+                "{} failed after {} restarts: {}",
+                finished.name,
+                finished.restart_count,
+                finished.last_error.unwrap_or_default()
+            )));
         }
+        info!("{} finished", finished.name);
 
         Ok(())
     }
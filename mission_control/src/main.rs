@@ -1,17 +1,23 @@
 use anyhow::Result;
 use clap::Parser;
-use mission_control::{Args, MissionControlService};
-use shared::init_logging;
+use mission_control::{alert_rules::AlertEngine, Args, MissionControlService};
+use shared::{config::AgroConfig, init_logging};
 use tracing::info;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     init_logging()?;
 
-    let _args = Args::parse();
+    let args = Args::parse();
     info!("Starting Mission Control Service");
 
-    let service = MissionControlService::new().await?;
+    let config = AgroConfig::load_with_override(args.config.as_deref())?;
+    let mut service = MissionControlService::with_config(config);
+    if let Some(path) = &args.alert_rules {
+        let alerts = AlertEngine::load(path)?;
+        info!("Loaded {} alert rules", alerts.rules().len());
+        service = service.with_alert_engine(alerts);
+    }
     service.run().await?;
 
     Ok(())
@@ -1,16 +1,54 @@
+use mavlink::common::{
+    MavMessage, MavMissionResult, MavMissionType, MavResult, COMMAND_ACK_DATA, COMMAND_LONG_DATA,
+    MISSION_ACK_DATA, MISSION_COUNT_DATA, MISSION_ITEM_INT_DATA, MISSION_REQUEST_INT_DATA,
+    MISSION_REQUEST_LIST_DATA,
+};
 use shared::{
     config::AgroConfig,
+    error::AgroError,
     schemas::{GpsCoords, Telemetry, WebSocketMessage},
     AgroResult,
 };
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::{broadcast, mpsc, oneshot};
 use tokio_serial::SerialPortBuilderExt;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
+
+/// Commands waiting for the flight controller to acknowledge them, keyed by
+/// command id; MAVLink acknowledgements carry nothing more specific.
+type PendingCommands = HashMap<u32, oneshot::Sender<MavResult>>;
+
+/// Mission or fence upload waiting on the flight controller, which requests
+/// `items` one by one and ends the exchange with a `MISSION_ACK`.
+struct MissionUpload {
+    mission_type: MavMissionType,
+    items: Vec<MISSION_ITEM_INT_DATA>,
+    done: oneshot::Sender<MavMissionResult>,
+}
+
+/// Mission read back from the flight controller: items received so far, out
+/// of `count` once the flight controller has announced it.
+struct MissionDownload {
+    target_system: u8,
+    target_component: u8,
+    count: Option<u16>,
+    items: Vec<MISSION_ITEM_INT_DATA>,
+    done: oneshot::Sender<Vec<MISSION_ITEM_INT_DATA>>,
+}
 
 pub struct MavlinkClient {
     config: Arc<AgroConfig>,
     event_tx: broadcast::Sender<WebSocketMessage>,
+    pending_commands: Mutex<PendingCommands>,
+    /// The mission protocol runs one transfer in each direction at a time.
+    mission_upload: Mutex<Option<MissionUpload>>,
+    mission_download: Mutex<Option<MissionDownload>>,
+    command_tx: mpsc::Sender<MavMessage>,
+    /// Held by the running link loop; a restarted loop picks it up again.
+    command_rx: tokio::sync::Mutex<mpsc::Receiver<MavMessage>>,
 }
 
 impl MavlinkClient {
@@ -18,7 +56,16 @@ impl MavlinkClient {
         config: Arc<AgroConfig>,
         event_tx: broadcast::Sender<WebSocketMessage>,
     ) -> AgroResult<Self> {
-        Ok(Self { config, event_tx })
+        let (command_tx, command_rx) = mpsc::channel(32);
+        Ok(Self {
+            config,
+            event_tx,
+            pending_commands: Mutex::new(HashMap::new()),
+            mission_upload: Mutex::new(None),
+            mission_download: Mutex::new(None),
+            command_tx,
+            command_rx: tokio::sync::Mutex::new(command_rx),
+        })
     }
 
     pub async fn run(&self) -> AgroResult<()> {
@@ -27,27 +74,39 @@ impl MavlinkClient {
             self.config.mavlink.serial_port
         );
 
-        let mut port = tokio_serial::new(
+        let port = tokio_serial::new(
             &self.config.mavlink.serial_port,
             self.config.mavlink.baud_rate,
         )
         .open_native_async()
-        .map_err(|e| {
-            shared::error::AgroError::Mavlink(format!("Failed to open serial port: {}", e))
-        })?;
+        .map_err(|e| AgroError::Mavlink(format!("Failed to open serial port: {}", e)))?;
+
+        self.run_with_port(port).await
+    }
 
-        let mut heartbeat_interval = tokio::time::interval(std::time::Duration::from_millis(
+    /// Drives the link over `port`: heartbeats, queued commands, and incoming
+    /// frames. Returns once the flight controller closes the link.
+    pub async fn run_with_port<P>(&self, mut port: P) -> AgroResult<()>
+    where
+        P: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut command_rx = self.command_rx.lock().await;
+        let mut heartbeat_interval = tokio::time::interval(Duration::from_millis(
             self.config.mavlink.heartbeat_interval_ms,
         ));
 
         let mut telemetry_buffer = Vec::new();
+        let mut inbound = Vec::new();
 
         loop {
             tokio::select! {
                 _ = heartbeat_interval.tick() => {
                     self.send_heartbeat(&mut port).await?;
                 }
-                result = self.read_telemetry(&mut port) => {
+                Some(command) = command_rx.recv() => {
+                    write_message(&mut port, &command).await?;
+                }
+                result = self.read_telemetry(&mut port, &mut inbound) => {
                     match result {
                         Ok(telemetry) => {
                             telemetry_buffer.push(telemetry.clone());
@@ -58,6 +117,9 @@ impl MavlinkClient {
                                 warn!("Failed to send telemetry update: {}", e);
                             }
                         }
+                        Err(AgroError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                            return Err(AgroError::Mavlink("Flight controller closed the link".to_string()));
+                        }
                         Err(e) => {
                             error!("Failed to read telemetry: {}", e);
                         }
@@ -67,7 +129,366 @@ impl MavlinkClient {
         }
     }
 
-    async fn send_heartbeat(&self, port: &mut tokio_serial::SerialStream) -> AgroResult<()> {
+    /// Sends `command` and waits up to `timeout` for its `COMMAND_ACK`. A
+    /// rejection is still `Ok`, carrying the flight controller's result; only
+    /// a missing acknowledgement is an error.
+    pub async fn send_command_await_ack(
+        &self,
+        command: COMMAND_LONG_DATA,
+        timeout: Duration,
+    ) -> AgroResult<MavResult> {
+        let command_id = command.command;
+        let (ack_tx, ack_rx) = oneshot::channel();
+        {
+            let mut pending = lock(&self.pending_commands);
+            if pending.contains_key(&(command_id as u32)) {
+                return Err(AgroError::Mavlink(format!(
+                    "{:?} is already awaiting acknowledgement",
+                    command_id
+                )));
+            }
+            pending.insert(command_id as u32, ack_tx);
+        }
+
+        let sent = self
+            .command_tx
+            .send(MavMessage::COMMAND_LONG(command))
+            .await
+            .map_err(|_| AgroError::Mavlink("MAVLink link is not running".to_string()));
+        let result = match sent {
+            Ok(()) => tokio::time::timeout(timeout, ack_rx).await,
+            Err(e) => {
+                lock(&self.pending_commands).remove(&(command_id as u32));
+                return Err(e);
+            }
+        };
+        match result {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(_)) | Err(_) => {
+                lock(&self.pending_commands).remove(&(command_id as u32));
+                Err(AgroError::Mavlink(format!(
+                    "{:?} was not acknowledged within {} ms",
+                    command_id,
+                    timeout.as_millis()
+                )))
+            }
+        }
+    }
+
+    /// Uploads inclusion-fence `items` through the MAVLink mission protocol
+    /// and waits up to `timeout` for the flight controller to acknowledge the
+    /// whole fence. A rejection is still `Ok`, carrying its result.
+    pub async fn upload_geofence(
+        &self,
+        items: Vec<MISSION_ITEM_INT_DATA>,
+        timeout: Duration,
+    ) -> AgroResult<MavMissionResult> {
+        if items
+            .iter()
+            .any(|item| item.mission_type != MavMissionType::MAV_MISSION_TYPE_FENCE)
+        {
+            return Err(AgroError::Mavlink(
+                "Geofence upload only accepts fence items".to_string(),
+            ));
+        }
+        self.upload_mission(items, timeout).await
+    }
+
+    /// Uploads `items`, all of one mission type, through the MAVLink mission
+    /// protocol and waits up to `timeout` for the flight controller to
+    /// acknowledge them. Each item sent is reported to WebSocket clients. A
+    /// rejection is still `Ok`, carrying its result.
+    pub async fn upload_mission(
+        &self,
+        items: Vec<MISSION_ITEM_INT_DATA>,
+        timeout: Duration,
+    ) -> AgroResult<MavMissionResult> {
+        let Some(first) = items.first() else {
+            return Err(AgroError::Mavlink(
+                "Mission upload needs at least one item".to_string(),
+            ));
+        };
+        let mission_type = first.mission_type;
+        let label = transfer_label(mission_type);
+        if items.iter().any(|item| item.mission_type != mission_type) {
+            return Err(AgroError::Mavlink(format!(
+                "{} upload mixes mission types",
+                label
+            )));
+        }
+        let count = u16::try_from(items.len()).map_err(|_| {
+            AgroError::Mavlink(format!("{} has too many items: {}", label, items.len()))
+        })?;
+        let announce = MavMessage::MISSION_COUNT(MISSION_COUNT_DATA {
+            count,
+            target_system: first.target_system,
+            target_component: first.target_component,
+            mission_type,
+        });
+
+        let (done_tx, done_rx) = oneshot::channel();
+        {
+            let mut upload = lock(&self.mission_upload);
+            if upload.is_some() {
+                return Err(AgroError::Mavlink(
+                    "A mission upload is already in progress".to_string(),
+                ));
+            }
+            *upload = Some(MissionUpload {
+                mission_type,
+                items,
+                done: done_tx,
+            });
+        }
+
+        if self.command_tx.send(announce).await.is_err() {
+            lock(&self.mission_upload).take();
+            return Err(AgroError::Mavlink(
+                "MAVLink link is not running".to_string(),
+            ));
+        }
+        match tokio::time::timeout(timeout, done_rx).await {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(_)) | Err(_) => {
+                lock(&self.mission_upload).take();
+                Err(AgroError::Mavlink(format!(
+                    "{} upload was not acknowledged within {} ms",
+                    label,
+                    timeout.as_millis()
+                )))
+            }
+        }
+    }
+
+    /// Reads the flight controller's current mission through the MAVLink
+    /// mission protocol, waiting up to `timeout` for the whole transfer.
+    pub async fn download_mission(
+        &self,
+        target_system: u8,
+        target_component: u8,
+        timeout: Duration,
+    ) -> AgroResult<Vec<MISSION_ITEM_INT_DATA>> {
+        let (done_tx, done_rx) = oneshot::channel();
+        {
+            let mut download = lock(&self.mission_download);
+            if download.is_some() {
+                return Err(AgroError::Mavlink(
+                    "A mission download is already in progress".to_string(),
+                ));
+            }
+            *download = Some(MissionDownload {
+                target_system,
+                target_component,
+                count: None,
+                items: Vec::new(),
+                done: done_tx,
+            });
+        }
+
+        let request = MavMessage::MISSION_REQUEST_LIST(MISSION_REQUEST_LIST_DATA {
+            target_system,
+            target_component,
+            mission_type: MavMissionType::MAV_MISSION_TYPE_MISSION,
+        });
+        if self.command_tx.send(request).await.is_err() {
+            lock(&self.mission_download).take();
+            return Err(AgroError::Mavlink(
+                "MAVLink link is not running".to_string(),
+            ));
+        }
+        match tokio::time::timeout(timeout, done_rx).await {
+            Ok(Ok(items)) => Ok(items),
+            Ok(Err(_)) | Err(_) => {
+                lock(&self.mission_download).take();
+                Err(AgroError::Mavlink(format!(
+                    "Mission download did not complete within {} ms",
+                    timeout.as_millis()
+                )))
+            }
+        }
+    }
+
+    /// Queues the upload item the flight controller asked for next and
+    /// reports the progress.
+    fn handle_mission_request(&self, seq: u16, mission_type: MavMissionType) {
+        let (item, total) = {
+            let upload = lock(&self.mission_upload);
+            match upload
+                .as_ref()
+                .filter(|upload| upload.mission_type == mission_type)
+            {
+                Some(upload) => (upload.items.get(seq as usize).cloned(), upload.items.len()),
+                None => (None, 0),
+            }
+        };
+        let Some(item) = item else {
+            debug!(
+                "Ignoring request for {:?} item {} outside an upload",
+                mission_type, seq
+            );
+            return;
+        };
+        if let Err(e) = self.command_tx.try_send(MavMessage::MISSION_ITEM_INT(item)) {
+            warn!("Failed to queue mission item {}: {}", seq, e);
+            return;
+        }
+
+        let progress = WebSocketMessage::SystemStatus {
+            status: "info".to_string(),
+            message: format!(
+                "{} upload: sent item {} of {}",
+                transfer_label(mission_type),
+                seq + 1,
+                total
+            ),
+        };
+        if let Err(e) = self.event_tx.send(progress) {
+            warn!("Failed to send mission upload progress: {}", e);
+        }
+    }
+
+    fn handle_mission_ack(&self, ack: MISSION_ACK_DATA) {
+        let upload = {
+            let mut upload = lock(&self.mission_upload);
+            if upload
+                .as_ref()
+                .is_some_and(|upload| upload.mission_type == ack.mission_type)
+            {
+                upload.take()
+            } else {
+                None
+            }
+        };
+        let Some(upload) = upload else {
+            debug!(
+                "Ignoring {:?} acknowledgement outside an upload",
+                ack.mission_type
+            );
+            return;
+        };
+
+        let label = transfer_label(upload.mission_type);
+        let accepted = ack.mavtype == MavMissionResult::MAV_MISSION_ACCEPTED;
+        let status = WebSocketMessage::SystemStatus {
+            status: if accepted { "info" } else { "warn" }.to_string(),
+            message: if accepted {
+                format!("{} upload accepted", label)
+            } else {
+                format!("{} upload rejected: {:?}", label, ack.mavtype)
+            },
+        };
+        if let Err(e) = self.event_tx.send(status) {
+            warn!("Failed to send mission upload status: {}", e);
+        }
+        let _ = upload.done.send(ack.mavtype);
+    }
+
+    /// Starts pulling items once the flight controller says how many there are.
+    fn handle_mission_count(&self, count: MISSION_COUNT_DATA) {
+        if count.mission_type != MavMissionType::MAV_MISSION_TYPE_MISSION {
+            return;
+        }
+        let mut download = lock(&self.mission_download);
+        let Some(pending) = download.as_mut().filter(|pending| pending.count.is_none()) else {
+            debug!("Ignoring mission count outside a download");
+            return;
+        };
+        pending.count = Some(count.count);
+        if count.count == 0 {
+            if let Some(finished) = download.take() {
+                let _ = finished.done.send(Vec::new());
+            }
+            return;
+        }
+        self.request_mission_item(pending, 0);
+    }
+
+    /// Keeps the next item in sequence and requests the one after it; once
+    /// all have arrived the transfer is acknowledged and handed to the caller.
+    fn handle_mission_item(&self, item: MISSION_ITEM_INT_DATA) {
+        if item.mission_type != MavMissionType::MAV_MISSION_TYPE_MISSION {
+            return;
+        }
+        let mut download = lock(&self.mission_download);
+        let Some(pending) = download.as_mut() else {
+            debug!("Ignoring mission item {} outside a download", item.seq);
+            return;
+        };
+        let Some(count) = pending.count else {
+            return;
+        };
+        if usize::from(item.seq) != pending.items.len() {
+            debug!(
+                "Ignoring mission item {} while expecting {}",
+                item.seq,
+                pending.items.len()
+            );
+            return;
+        }
+
+        pending.items.push(item);
+        if pending.items.len() < usize::from(count) {
+            let next = pending.items.len() as u16;
+            self.request_mission_item(pending, next);
+            return;
+        }
+
+        let Some(finished) = download.take() else {
+            return;
+        };
+        let ack = MavMessage::MISSION_ACK(MISSION_ACK_DATA {
+            target_system: finished.target_system,
+            target_component: finished.target_component,
+            mavtype: MavMissionResult::MAV_MISSION_ACCEPTED,
+            mission_type: MavMissionType::MAV_MISSION_TYPE_MISSION,
+        });
+        if let Err(e) = self.command_tx.try_send(ack) {
+            warn!("Failed to queue mission download acknowledgement: {}", e);
+        }
+        let _ = finished.done.send(finished.items);
+    }
+
+    fn request_mission_item(&self, download: &MissionDownload, seq: u16) {
+        let request = MavMessage::MISSION_REQUEST_INT(MISSION_REQUEST_INT_DATA {
+            seq,
+            target_system: download.target_system,
+            target_component: download.target_component,
+            mission_type: MavMissionType::MAV_MISSION_TYPE_MISSION,
+        });
+        if let Err(e) = self.command_tx.try_send(request) {
+            warn!("Failed to request mission item {}: {}", seq, e);
+        }
+    }
+
+    /// Resolves the waiting caller and reports the outcome to WebSocket
+    /// clients. Acknowledgements for commands sent by someone else are ignored.
+    fn handle_command_ack(&self, ack: COMMAND_ACK_DATA) {
+        if ack.result == MavResult::MAV_RESULT_IN_PROGRESS {
+            debug!("{:?} in progress", ack.command);
+            return;
+        }
+        let Some(waiter) = lock(&self.pending_commands).remove(&(ack.command as u32)) else {
+            debug!("Ignoring unsolicited acknowledgement for {:?}", ack.command);
+            return;
+        };
+
+        let accepted = ack.result == MavResult::MAV_RESULT_ACCEPTED;
+        let status = WebSocketMessage::SystemStatus {
+            status: if accepted { "info" } else { "warn" }.to_string(),
+            message: if accepted {
+                format!("{:?} accepted", ack.command)
+            } else {
+                format!("{:?} rejected: {:?}", ack.command, ack.result)
+            },
+        };
+        if let Err(e) = self.event_tx.send(status) {
+            warn!("Failed to send command acknowledgement status: {}", e);
+        }
+        // The caller may have timed out in the meantime.
+        let _ = waiter.send(ack.result);
+    }
+
+    async fn send_heartbeat<P: AsyncWrite + Unpin>(&self, port: &mut P) -> AgroResult<()> {
         use mavlink::common::*;
 
         let heartbeat = MavMessage::HEARTBEAT(HEARTBEAT_DATA {
@@ -79,39 +500,42 @@ impl MavlinkClient {
             mavlink_version: 3,
         });
 
-        let header = mavlink::MavHeader {
-            system_id: 255,
-            component_id: 0,
-            sequence: 0,
-        };
-
-        let mut buf = Vec::new();
-        mavlink::write_versioned_msg(&mut buf, mavlink::MavlinkVersion::V2, header, &heartbeat)
-            .map_err(|e| {
-                shared::error::AgroError::Mavlink(format!("Failed to serialize heartbeat: {}", e))
-            })?;
-
-        tokio::io::AsyncWriteExt::write_all(port, &buf)
-            .await
-            .map_err(|e| {
-                shared::error::AgroError::Mavlink(format!("Failed to send heartbeat: {}", e))
-            })?;
-
-        Ok(())
+        write_message(port, &heartbeat).await
     }
 
-    async fn read_telemetry(&self, port: &mut tokio_serial::SerialStream) -> AgroResult<Telemetry> {
+    async fn read_telemetry<P: AsyncRead + Unpin>(
+        &self,
+        port: &mut P,
+        inbound: &mut Vec<u8>,
+    ) -> AgroResult<Telemetry> {
         use tokio::io::AsyncReadExt;
 
         let mut buf = [0u8; 1024];
-        let _n = port.read(&mut buf).await.map_err(|e| {
+        let n = port.read(&mut buf).await.map_err(|e| {
             shared::error::AgroError::Mavlink(format!("Failed to read from port: {}", e))
         })?;
+        if n == 0 {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        inbound.extend_from_slice(&buf[..n]);
 
-        // Parse MAVLink messages (simplified)
-        // In a real implementation, you'd use proper MAVLink parsing
+        for message in drain_messages(inbound) {
+            match message {
+                MavMessage::COMMAND_ACK(ack) => self.handle_command_ack(ack),
+                MavMessage::MISSION_REQUEST_INT(request) => {
+                    self.handle_mission_request(request.seq, request.mission_type)
+                }
+                MavMessage::MISSION_REQUEST(request) => {
+                    self.handle_mission_request(request.seq, request.mission_type)
+                }
+                MavMessage::MISSION_ACK(ack) => self.handle_mission_ack(ack),
+                MavMessage::MISSION_COUNT(count) => self.handle_mission_count(count),
+                MavMessage::MISSION_ITEM_INT(item) => self.handle_mission_item(item),
+                _ => {}
+            }
+        }
 
-        // For now, return mock telemetry
+        // Telemetry decoding is still simplified; report mock telemetry
         Ok(Telemetry {
             timestamp: chrono::Utc::now(),
             position: GpsCoords {
@@ -131,6 +555,72 @@ impl MavlinkClient {
     }
 }
 
+fn transfer_label(mission_type: MavMissionType) -> &'static str {
+    match mission_type {
+        MavMissionType::MAV_MISSION_TYPE_FENCE => "Geofence",
+        MavMissionType::MAV_MISSION_TYPE_RALLY => "Rally point",
+        _ => "Mission",
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+async fn write_message<P: AsyncWrite + Unpin>(
+    port: &mut P,
+    message: &MavMessage,
+) -> AgroResult<()> {
+    let header = mavlink::MavHeader {
+        system_id: 255,
+        component_id: 0,
+        sequence: 0,
+    };
+
+    let mut buf = Vec::new();
+    mavlink::write_versioned_msg(&mut buf, mavlink::MavlinkVersion::V2, header, message)
+        .map_err(|e| AgroError::Mavlink(format!("Failed to serialize MAVLink message: {}", e)))?;
+
+    tokio::io::AsyncWriteExt::write_all(port, &buf)
+        .await
+        .map_err(|e| AgroError::Mavlink(format!("Failed to send MAVLink message: {}", e)))
+}
+
+/// Parses every complete MAVLink 2 frame in `inbound`, leaving a trailing
+/// partial frame for the next read. Noise between frames, and frames with a
+/// bad checksum or an unknown id, are dropped.
+fn drain_messages(inbound: &mut Vec<u8>) -> Vec<MavMessage> {
+    let mut messages = Vec::new();
+    let mut consumed = 0;
+    while let Some(start) = inbound[consumed..]
+        .iter()
+        .position(|&byte| byte == mavlink::MAV_STX_V2)
+    {
+        consumed += start;
+        let mut reader = std::io::Cursor::new(&inbound[consumed..]);
+        match mavlink::read_v2_msg::<MavMessage, _>(&mut reader) {
+            Ok((_, message)) => {
+                consumed += reader.position() as usize;
+                messages.push(message);
+            }
+            Err(mavlink::error::MessageReadError::Io(e))
+                if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                break;
+            }
+            // Skip this start marker and look for the next frame.
+            Err(_) => consumed += 1,
+        }
+    }
+    if !inbound[consumed..].contains(&mavlink::MAV_STX_V2) {
+        consumed = inbound.len();
+    }
+    inbound.drain(..consumed);
+    messages
+}
+
 pub struct SimulatedMavlinkClient {
     config: Arc<AgroConfig>,
     event_tx: broadcast::Sender<WebSocketMessage>,
@@ -186,3 +676,316 @@ impl SimulatedMavlinkClient {
         }
     }
 }
+
+/// Scripted flight controller for tests, reached over an in-memory link.
+#[cfg(test)]
+pub(crate) mod mock_vehicle {
+    use super::*;
+    use tokio::io::{AsyncReadExt, DuplexStream};
+
+    /// Mission held by the mock: the last accepted upload, served to downloads.
+    pub(crate) type StoredMission = Arc<Mutex<Vec<MISSION_ITEM_INT_DATA>>>;
+
+    /// Answers every `COMMAND_LONG` with `result` and runs both directions of
+    /// the mission protocol; stays silent for `None`.
+    async fn run(mut stream: DuplexStream, result: Option<MavResult>, mission: StoredMission) {
+        let mut inbound = Vec::new();
+        let mut buf = [0u8; 1024];
+        let mut upload: Option<(MavMissionType, usize)> = None;
+        let mut received = Vec::new();
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            if n == 0 {
+                return;
+            }
+            inbound.extend_from_slice(&buf[..n]);
+            for message in drain_messages(&mut inbound) {
+                let Some(result) = result else {
+                    continue;
+                };
+                let reply = match message {
+                    MavMessage::COMMAND_LONG(command) => {
+                        MavMessage::COMMAND_ACK(COMMAND_ACK_DATA {
+                            command: command.command,
+                            result,
+                            ..COMMAND_ACK_DATA::default()
+                        })
+                    }
+                    MavMessage::MISSION_COUNT(count) => {
+                        upload = Some((count.mission_type, usize::from(count.count)));
+                        received.clear();
+                        request(0, count.mission_type)
+                    }
+                    MavMessage::MISSION_ITEM_INT(item) => {
+                        let Some((mission_type, count)) = upload else {
+                            continue;
+                        };
+                        let seq = item.seq;
+                        let in_sequence = usize::from(seq) == received.len();
+                        received.push(item);
+                        if in_sequence && received.len() < count {
+                            request(seq + 1, mission_type)
+                        } else {
+                            upload = None;
+                            let accepted = in_sequence && received.len() == count;
+                            if accepted && mission_type == MavMissionType::MAV_MISSION_TYPE_MISSION
+                            {
+                                *lock(&mission) = std::mem::take(&mut received);
+                            }
+                            MavMessage::MISSION_ACK(MISSION_ACK_DATA {
+                                mavtype: if accepted {
+                                    MavMissionResult::MAV_MISSION_ACCEPTED
+                                } else {
+                                    MavMissionResult::MAV_MISSION_INVALID_SEQUENCE
+                                },
+                                mission_type,
+                                ..MISSION_ACK_DATA::default()
+                            })
+                        }
+                    }
+                    MavMessage::MISSION_REQUEST_LIST(_) => {
+                        MavMessage::MISSION_COUNT(MISSION_COUNT_DATA {
+                            count: lock(&mission).len() as u16,
+                            mission_type: MavMissionType::MAV_MISSION_TYPE_MISSION,
+                            ..MISSION_COUNT_DATA::default()
+                        })
+                    }
+                    MavMessage::MISSION_REQUEST_INT(request) => {
+                        match lock(&mission).get(usize::from(request.seq)).cloned() {
+                            Some(item) => MavMessage::MISSION_ITEM_INT(item),
+                            None => continue,
+                        }
+                    }
+                    _ => continue,
+                };
+                write_message(&mut stream, &reply).await.unwrap();
+            }
+        }
+    }
+
+    fn request(seq: u16, mission_type: MavMissionType) -> MavMessage {
+        MavMessage::MISSION_REQUEST_INT(MISSION_REQUEST_INT_DATA {
+            seq,
+            mission_type,
+            ..MISSION_REQUEST_INT_DATA::default()
+        })
+    }
+
+    /// Client whose link is driven by a mock flight controller answering
+    /// commands with `result`.
+    pub(crate) async fn connect(
+        result: Option<MavResult>,
+    ) -> (
+        Arc<MavlinkClient>,
+        broadcast::Receiver<WebSocketMessage>,
+        StoredMission,
+    ) {
+        let (event_tx, event_rx) = broadcast::channel(64);
+        let client = Arc::new(
+            MavlinkClient::new(Arc::new(AgroConfig::default()), event_tx)
+                .await
+                .unwrap(),
+        );
+        let mission = StoredMission::default();
+        let (client_end, peer_end) = tokio::io::duplex(4096);
+        tokio::spawn(run(peer_end, result, mission.clone()));
+        let link = client.clone();
+        tokio::spawn(async move { link.run_with_port(client_end).await });
+        (client, event_rx, mission)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock_vehicle::connect;
+    use super::*;
+    use mavlink::common::MavCmd;
+
+    fn fence_vertex(seq: u16, lat: f64, lon: f64) -> MISSION_ITEM_INT_DATA {
+        MISSION_ITEM_INT_DATA {
+            param1: 4.0,
+            x: (lat * 1e7) as i32,
+            y: (lon * 1e7) as i32,
+            z: 120.0,
+            seq,
+            command: MavCmd::MAV_CMD_NAV_FENCE_POLYGON_VERTEX_INCLUSION,
+            target_system: 1,
+            target_component: 1,
+            frame: mavlink::common::MavFrame::MAV_FRAME_GLOBAL_RELATIVE_ALT,
+            autocontinue: 1,
+            mission_type: MavMissionType::MAV_MISSION_TYPE_FENCE,
+            ..MISSION_ITEM_INT_DATA::default()
+        }
+    }
+
+    fn arm() -> COMMAND_LONG_DATA {
+        COMMAND_LONG_DATA {
+            command: MavCmd::MAV_CMD_COMPONENT_ARM_DISARM,
+            param1: 1.0,
+            target_system: 1,
+            target_component: 1,
+            ..COMMAND_LONG_DATA::default()
+        }
+    }
+
+    async fn next_system_status(
+        events: &mut broadcast::Receiver<WebSocketMessage>,
+    ) -> (String, String) {
+        loop {
+            if let WebSocketMessage::SystemStatus { status, message } = events.recv().await.unwrap()
+            {
+                return (status, message);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn accepted_commands_resolve_and_are_reported() {
+        let (client, mut events, _) = connect(Some(MavResult::MAV_RESULT_ACCEPTED)).await;
+
+        let result = client
+            .send_command_await_ack(arm(), Duration::from_secs(2))
+            .await
+            .unwrap();
+
+        assert_eq!(result, MavResult::MAV_RESULT_ACCEPTED);
+        let (status, message) = next_system_status(&mut events).await;
+        assert_eq!(status, "info");
+        assert_eq!(message, "MAV_CMD_COMPONENT_ARM_DISARM accepted");
+    }
+
+    #[tokio::test]
+    async fn rejected_commands_return_the_flight_controller_result() {
+        let (client, mut events, _) = connect(Some(MavResult::MAV_RESULT_DENIED)).await;
+
+        let result = client
+            .send_command_await_ack(arm(), Duration::from_secs(2))
+            .await
+            .unwrap();
+
+        assert_eq!(result, MavResult::MAV_RESULT_DENIED);
+        let (status, message) = next_system_status(&mut events).await;
+        assert_eq!(status, "warn");
+        assert_eq!(
+            message,
+            "MAV_CMD_COMPONENT_ARM_DISARM rejected: MAV_RESULT_DENIED"
+        );
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_commands_time_out_and_can_be_resent() {
+        let (client, _events, _) = connect(None).await;
+
+        let error = client
+            .send_command_await_ack(arm(), Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("not acknowledged within 100 ms"));
+
+        // The timed-out command no longer blocks a retry of the same command.
+        let error = client
+            .send_command_await_ack(arm(), Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert!(!error.to_string().contains("already awaiting"));
+    }
+
+    #[tokio::test]
+    async fn geofence_items_are_uploaded_on_request_until_acknowledged() {
+        let (client, mut events, _) = connect(Some(MavResult::MAV_RESULT_ACCEPTED)).await;
+        let fence = vec![
+            fence_vertex(0, 41.1, -96.1),
+            fence_vertex(1, 41.1, -96.0),
+            fence_vertex(2, 41.2, -96.0),
+            fence_vertex(3, 41.2, -96.1),
+        ];
+
+        let result = client
+            .upload_geofence(fence, Duration::from_secs(2))
+            .await
+            .unwrap();
+
+        assert_eq!(result, MavMissionResult::MAV_MISSION_ACCEPTED);
+        for sent in 1..=4 {
+            let (status, message) = next_system_status(&mut events).await;
+            assert_eq!(status, "info");
+            assert_eq!(message, format!("Geofence upload: sent item {sent} of 4"));
+        }
+        let (status, message) = next_system_status(&mut events).await;
+        assert_eq!(status, "info");
+        assert_eq!(message, "Geofence upload accepted");
+    }
+
+    #[tokio::test]
+    async fn uploaded_missions_can_be_downloaded_again() {
+        let (client, _events, stored) = connect(Some(MavResult::MAV_RESULT_ACCEPTED)).await;
+        let mission = (0..3)
+            .map(|seq| MISSION_ITEM_INT_DATA {
+                seq,
+                command: MavCmd::MAV_CMD_NAV_WAYPOINT,
+                mission_type: MavMissionType::MAV_MISSION_TYPE_MISSION,
+                ..fence_vertex(seq, 41.1 + f64::from(seq) * 0.001, -96.1)
+            })
+            .collect::<Vec<_>>();
+
+        let result = client
+            .upload_mission(mission.clone(), Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(result, MavMissionResult::MAV_MISSION_ACCEPTED);
+        assert_eq!(stored.lock().unwrap().len(), 3);
+
+        let downloaded = client
+            .download_mission(1, 1, Duration::from_secs(2))
+            .await
+            .unwrap();
+        assert_eq!(downloaded, mission);
+    }
+
+    #[tokio::test]
+    async fn geofence_uploads_reject_mission_items_and_time_out_without_an_ack() {
+        let (client, _events, _) = connect(Some(MavResult::MAV_RESULT_ACCEPTED)).await;
+        let mut waypoint = fence_vertex(0, 41.1, -96.1);
+        waypoint.mission_type = MavMissionType::MAV_MISSION_TYPE_MISSION;
+        assert!(client
+            .upload_geofence(vec![waypoint], Duration::from_secs(2))
+            .await
+            .is_err());
+        let (client, _events, _) = connect(None).await;
+        let error = client
+            .upload_geofence(
+                vec![fence_vertex(0, 41.1, -96.1)],
+                Duration::from_millis(100),
+            )
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("not acknowledged within 100 ms"));
+    }
+
+    #[test]
+    fn partial_frames_wait_for_the_rest_and_noise_is_dropped() {
+        let ack = MavMessage::COMMAND_ACK(COMMAND_ACK_DATA {
+            command: MavCmd::MAV_CMD_NAV_TAKEOFF,
+            result: MavResult::MAV_RESULT_ACCEPTED,
+            ..COMMAND_ACK_DATA::default()
+        });
+        let mut frame = Vec::new();
+        mavlink::write_versioned_msg(
+            &mut frame,
+            mavlink::MavlinkVersion::V2,
+            mavlink::MavHeader::default(),
+            &ack,
+        )
+        .unwrap();
+
+        let mut inbound = vec![0x00, 0x42];
+        inbound.extend_from_slice(&frame[..5]);
+        assert!(drain_messages(&mut inbound).is_empty());
+        assert_eq!(inbound, frame[..5]);
+
+        inbound.extend_from_slice(&frame[5..]);
+        inbound.extend_from_slice(&frame);
+        assert_eq!(drain_messages(&mut inbound), vec![ack.clone(), ack]);
+        assert!(inbound.is_empty());
+    }
+}
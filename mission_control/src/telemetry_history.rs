@@ -0,0 +1,73 @@
+use shared::{schemas::WebSocketMessage, AgroResult};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// The last `capacity` telemetry and status messages published on the event
+/// bus, so new WebSocket clients can draw the current state straight away.
+#[derive(Debug)]
+pub struct TelemetryHistory {
+    capacity: usize,
+    messages: Mutex<VecDeque<WebSocketMessage>>,
+}
+
+impl TelemetryHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            messages: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Keeps telemetry, mission status and system status messages, dropping
+    /// the oldest once full; other messages are ignored.
+    pub fn record(&self, message: &WebSocketMessage) {
+        if self.capacity == 0 || !Self::is_recorded(message) {
+            return;
+        }
+        let mut messages = self.lock();
+        if messages.len() == self.capacity {
+            messages.pop_front();
+        }
+        messages.push_back(message.clone());
+    }
+
+    /// Buffered messages, oldest first.
+    pub fn snapshot(&self) -> Vec<WebSocketMessage> {
+        self.lock().iter().cloned().collect()
+    }
+
+    /// Records every message on `event_rx` until the bus closes.
+    pub async fn run(&self, mut event_rx: broadcast::Receiver<WebSocketMessage>) -> AgroResult<()> {
+        loop {
+            match event_rx.recv().await {
+                Ok(message) => self.record(&message),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Telemetry history skipped {} lagged messages", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<WebSocketMessage>> {
+        // Every push leaves the buffer whole, so a poisoned lock is still usable.
+        self.messages
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn is_recorded(message: &WebSocketMessage) -> bool {
+        matches!(
+            message,
+            WebSocketMessage::Telemetry { .. }
+                | WebSocketMessage::MissionStatus { .. }
+                | WebSocketMessage::SystemStatus { .. }
+        )
+    }
+}
@@ -0,0 +1,209 @@
+use mavlink::common::{MavCmd, MavFrame, MavMissionType, MISSION_ITEM_INT_DATA};
+use num_traits::{FromPrimitive, ToPrimitive};
+use serde::{Deserialize, Serialize};
+use shared::{error::AgroError, AgroResult};
+
+/// `MAV_FRAME_MISSION`: x and y are plain parameters, not coordinates.
+const MAV_FRAME_MISSION: u8 = 2;
+
+/// A mission in the MAVLink JSON layout the mission planner exports, with
+/// coordinates in degrees.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VehicleMission {
+    pub target_system: u8,
+    pub target_component: u8,
+    pub count: u16,
+    pub items: Vec<VehicleMissionItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VehicleMissionItem {
+    pub seq: u16,
+    pub frame: u8,
+    pub command: u16,
+    pub current: u8,
+    pub autocontinue: u8,
+    pub param1: f32,
+    pub param2: f32,
+    pub param3: f32,
+    pub param4: f32,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub mission_type: u8,
+}
+
+impl VehicleMission {
+    /// Wire items for the MAVLink mission protocol. Items must be numbered
+    /// 0..n in order, match `count`, and use MAVLink command, frame and
+    /// mission type values.
+    pub fn to_mission_items(&self) -> AgroResult<Vec<MISSION_ITEM_INT_DATA>> {
+        if self.items.is_empty() {
+            return Err(AgroError::Mavlink(
+                "Mission has no items to upload".to_string(),
+            ));
+        }
+        if usize::from(self.count) != self.items.len() {
+            return Err(AgroError::Mavlink(format!(
+                "Mission count {} does not match its {} items",
+                self.count,
+                self.items.len()
+            )));
+        }
+
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                if usize::from(item.seq) != index {
+                    return Err(AgroError::Mavlink(format!(
+                        "Mission item {} is numbered {}",
+                        index, item.seq
+                    )));
+                }
+                item.to_mission_item_int(self.target_system, self.target_component)
+            })
+            .collect()
+    }
+
+    /// Mission read back from the vehicle, in the exported JSON layout.
+    pub fn from_mission_items(
+        target_system: u8,
+        target_component: u8,
+        items: &[MISSION_ITEM_INT_DATA],
+    ) -> Self {
+        Self {
+            target_system,
+            target_component,
+            count: items.len() as u16,
+            items: items
+                .iter()
+                .map(VehicleMissionItem::from_mission_item_int)
+                .collect(),
+        }
+    }
+}
+
+impl VehicleMissionItem {
+    fn to_mission_item_int(
+        &self,
+        target_system: u8,
+        target_component: u8,
+    ) -> AgroResult<MISSION_ITEM_INT_DATA> {
+        let invalid = |field: &str, value: u16| {
+            AgroError::Mavlink(format!(
+                "Mission item {} has unknown {} {}",
+                self.seq, field, value
+            ))
+        };
+        let scale = |degrees: f32| (f64::from(degrees) * 1e7).round() as i32;
+        let (x, y) = if self.frame == MAV_FRAME_MISSION {
+            (self.x as i32, self.y as i32)
+        } else {
+            (scale(self.x), scale(self.y))
+        };
+
+        Ok(MISSION_ITEM_INT_DATA {
+            param1: self.param1,
+            param2: self.param2,
+            param3: self.param3,
+            param4: self.param4,
+            x,
+            y,
+            z: self.z,
+            seq: self.seq,
+            command: MavCmd::from_u16(self.command)
+                .ok_or_else(|| invalid("command", self.command))?,
+            target_system,
+            target_component,
+            frame: MavFrame::from_u8(self.frame)
+                .ok_or_else(|| invalid("frame", u16::from(self.frame)))?,
+            current: self.current,
+            autocontinue: self.autocontinue,
+            mission_type: MavMissionType::from_u8(self.mission_type)
+                .ok_or_else(|| invalid("mission type", u16::from(self.mission_type)))?,
+        })
+    }
+
+    fn from_mission_item_int(item: &MISSION_ITEM_INT_DATA) -> Self {
+        let frame = item.frame.to_u8().unwrap_or_default();
+        let unscale = |degrees_e7: i32| (f64::from(degrees_e7) / 1e7) as f32;
+        let (x, y) = if frame == MAV_FRAME_MISSION {
+            (item.x as f32, item.y as f32)
+        } else {
+            (unscale(item.x), unscale(item.y))
+        };
+
+        Self {
+            seq: item.seq,
+            frame,
+            command: item.command.to_u16().unwrap_or_default(),
+            current: item.current,
+            autocontinue: item.autocontinue,
+            param1: item.param1,
+            param2: item.param2,
+            param3: item.param3,
+            param4: item.param4,
+            x,
+            y,
+            z: item.z,
+            mission_type: item.mission_type.to_u8().unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn waypoint(seq: u16) -> VehicleMissionItem {
+        VehicleMissionItem {
+            seq,
+            frame: 3,
+            command: 16,
+            current: u8::from(seq == 0),
+            autocontinue: 1,
+            param1: 0.0,
+            param2: 0.0,
+            param3: 0.0,
+            param4: 0.0,
+            x: 41.25,
+            y: -96.5,
+            z: 30.0,
+            mission_type: 0,
+        }
+    }
+
+    #[test]
+    fn exported_items_round_trip_through_the_wire_form() {
+        let mission = VehicleMission {
+            target_system: 1,
+            target_component: 1,
+            count: 2,
+            items: vec![waypoint(0), waypoint(1)],
+        };
+
+        let items = mission.to_mission_items().unwrap();
+        assert_eq!(items[1].x, 412_500_000);
+        assert_eq!(items[1].command, MavCmd::MAV_CMD_NAV_WAYPOINT);
+
+        assert_eq!(VehicleMission::from_mission_items(1, 1, &items), mission);
+    }
+
+    #[test]
+    fn out_of_order_or_unknown_items_are_rejected() {
+        let mut mission = VehicleMission {
+            target_system: 1,
+            target_component: 1,
+            count: 2,
+            items: vec![waypoint(0), waypoint(2)],
+        };
+        assert!(mission.to_mission_items().is_err());
+
+        mission.items[1] = VehicleMissionItem {
+            command: 65_000,
+            ..waypoint(1)
+        };
+        assert!(mission.to_mission_items().is_err());
+    }
+}
@@ -1,36 +1,85 @@
+use crate::api_server::VEHICLE_MISSION_TIMEOUT;
+use crate::control_auth::{is_authorized, require_control_token};
+use crate::mavlink_client::MavlinkClient;
+use crate::telemetry_history::TelemetryHistory;
+use crate::vehicle_mission::VehicleMission;
+use crate::ws_encoding::{EncodingQuery, FrameEncoding, SUBPROTOCOLS};
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Query, State,
     },
-    response::Response,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
     routing::get,
     Router,
 };
 use futures_util::{SinkExt, StreamExt};
-use shared::{config::AgroConfig, schemas::WebSocketMessage, AgroResult};
-use std::sync::Arc;
-use tokio::sync::broadcast;
-use tracing::{info, warn};
+use mavlink::common::MavMissionResult;
+use shared::{
+    config::AgroConfig,
+    mission_sync::{MissionSyncMessage, MissionSyncReceiver, SyncedMission},
+    schemas::WebSocketMessage,
+    AgroResult,
+};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, info, warn};
 
 pub struct WebSocketServer {
     config: Arc<AgroConfig>,
-    event_rx: broadcast::Receiver<WebSocketMessage>,
+    event_tx: broadcast::Sender<WebSocketMessage>,
+    history: Arc<TelemetryHistory>,
+    mission_sync: Arc<Mutex<MissionSyncReceiver>>,
+    vehicle: Option<Arc<MavlinkClient>>,
 }
 
 impl WebSocketServer {
-    pub fn new(config: Arc<AgroConfig>, event_rx: broadcast::Receiver<WebSocketMessage>) -> Self {
-        Self { config, event_rx }
+    pub fn new(
+        config: Arc<AgroConfig>,
+        event_tx: broadcast::Sender<WebSocketMessage>,
+        history: Arc<TelemetryHistory>,
+    ) -> Self {
+        Self {
+            config,
+            event_tx,
+            history,
+            mission_sync: Arc::new(Mutex::new(MissionSyncReceiver::new())),
+            vehicle: None,
+        }
     }
 
-    pub async fn run(&self) -> AgroResult<()> {
+    /// Uploads missions synced over `/ws` to `vehicle` once they verify, for
+    /// clients that connected with the control token.
+    pub fn with_vehicle(mut self, vehicle: Arc<MavlinkClient>) -> Self {
+        self.vehicle = Some(vehicle);
+        self
+    }
+
+    /// `/ws` sends each client a snapshot of `history`, then streams every
+    /// event, as text JSON unless the client negotiates a binary or
+    /// compressed encoding. Mission sync messages from a client are answered
+    /// on that connection only, and their progress is broadcast; any other
+    /// client message is ignored. `/ws/ingest` takes events to publish, such
+    /// as a replayed flight, from clients holding the control token.
+    pub fn router(&self) -> Router {
         let app_state = AppState {
-            event_tx: self.event_rx.resubscribe().into(),
+            config: self.config.clone(),
+            event_tx: self.event_tx.clone(),
+            history: self.history.clone(),
+            mission_sync: self.mission_sync.clone(),
+            vehicle: self.vehicle.clone(),
         };
 
-        let app = Router::new()
+        Router::new()
             .route("/ws", get(websocket_handler))
-            .with_state(app_state);
+            .route("/ws/ingest", get(ingest_handler))
+            .with_state(app_state)
+            .layer(self.config.cors.layer())
+    }
+
+    pub async fn run(&self) -> AgroResult<()> {
+        let app = self.router();
 
         let listener = tokio::net::TcpListener::bind(&self.config.server.ws_bind_address).await?;
         info!(
@@ -46,27 +95,114 @@ impl WebSocketServer {
 
 #[derive(Clone)]
 struct AppState {
-    event_tx: Arc<broadcast::Receiver<WebSocketMessage>>,
+    config: Arc<AgroConfig>,
+    event_tx: broadcast::Sender<WebSocketMessage>,
+    history: Arc<TelemetryHistory>,
+    /// Shared by every connection so a planner can resume after reconnecting.
+    mission_sync: Arc<Mutex<MissionSyncReceiver>>,
+    vehicle: Option<Arc<MavlinkClient>>,
+}
+
+async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(query): Query<EncodingQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    // Watching is open to anyone; commanding the vehicle is not.
+    let authorized = is_authorized(&state.config, &headers);
+    ws.protocols(SUBPROTOCOLS).on_upgrade(move |socket| {
+        let subprotocol = socket
+            .protocol()
+            .and_then(|protocol| protocol.to_str().ok());
+        let encoding = query.resolve(subprotocol);
+        handle_socket(socket, state, encoding, authorized)
+    })
+}
+
+async fn ingest_handler(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(query): Query<EncodingQuery>,
+    State(state): State<AppState>,
+) -> Response {
+    if let Err(rejection) = require_control_token(&state.config, &headers) {
+        return rejection.into_response();
+    }
+    ws.protocols(SUBPROTOCOLS).on_upgrade(move |socket| {
+        let subprotocol = socket
+            .protocol()
+            .and_then(|protocol| protocol.to_str().ok());
+        let encoding = query.resolve(subprotocol);
+        handle_ingest_socket(socket, state, encoding)
+    })
 }
 
-async fn websocket_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+/// Publishes every event an authorized client sends to all subscribers, as
+/// if it were live. Point-to-point and server-generated message types are
+/// dropped.
+async fn handle_ingest_socket(mut socket: WebSocket, state: AppState, encoding: FrameEncoding) {
+    info!("WebSocket ingest connection established");
+    while let Some(msg) = socket.recv().await {
+        let event = match msg {
+            Ok(Message::Text(text)) => {
+                serde_json::from_str::<WebSocketMessage>(&text).map_err(|e| e.to_string())
+            }
+            Ok(Message::Binary(payload)) => {
+                encoding.decode_binary(&payload).map_err(|e| e.to_string())
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(Message::Ping(_)) | Ok(Message::Pong(_)) => continue,
+            Err(e) => {
+                warn!("WebSocket ingest error: {}", e);
+                break;
+            }
+        };
+        match event {
+            Ok(
+                WebSocketMessage::MissionSync { .. }
+                | WebSocketMessage::MissionSyncProgress { .. }
+                | WebSocketMessage::HistorySnapshot { .. },
+            ) => debug!("Dropping non-publishable message on the ingest socket"),
+            Ok(event) => {
+                let _ = state.event_tx.send(event);
+            }
+            Err(e) => info!("Received undecodable message on the ingest socket: {}", e),
+        }
+    }
+    info!("WebSocket ingest connection closed");
 }
 
-async fn handle_socket(socket: WebSocket, state: AppState) {
-    info!("New WebSocket connection established");
+async fn handle_socket(
+    socket: WebSocket,
+    state: AppState,
+    encoding: FrameEncoding,
+    authorized: bool,
+) {
+    info!(
+        "New WebSocket connection established ({:?}, {:?})",
+        encoding.format, encoding.compression
+    );
 
     let (mut sender, mut receiver) = socket.split();
-    let mut event_rx = state.event_tx.resubscribe();
+    // Subscribe before taking the snapshot so nothing published in between
+    // is lost; a message in that window may arrive twice instead.
+    let mut event_rx = state.event_tx.subscribe();
+    let history = state.history.snapshot();
+    let (reply_tx, mut reply_rx) = mpsc::unbounded_channel::<WebSocketMessage>();
 
     // Spawn task to handle incoming messages from client
     let recv_task = tokio::spawn(async move {
         while let Some(msg) = receiver.next().await {
             match msg {
-                Ok(Message::Text(_)) | Ok(Message::Binary(_)) => {
-                    // Handle incoming messages from client if needed
-                    info!("Received message from client: {:?}", msg);
-                }
+                Ok(Message::Text(text)) => match serde_json::from_str::<WebSocketMessage>(&text) {
+                    Ok(event) => route_client_event(event, &state, authorized, &reply_tx),
+                    Err(_) => info!("Received message from client: {}", text),
+                },
+                Ok(Message::Binary(payload)) => match encoding.decode_binary(&payload) {
+                    Ok(event) => route_client_event(event, &state, authorized, &reply_tx),
+                    Err(e) => info!("Received undecodable binary message from client: {}", e),
+                },
                 Ok(Message::Close(_)) => {
                     info!("WebSocket close message received");
                     break;
@@ -84,10 +220,28 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
 
     // Spawn task to send events to client
     let send_task = tokio::spawn(async move {
-        while let Ok(event) = event_rx.recv().await {
-            match serde_json::to_string(&event) {
-                Ok(json) => {
-                    if sender.send(Message::Text(json)).await.is_err() {
+        if !history.is_empty() {
+            let snapshot = WebSocketMessage::HistorySnapshot { messages: history };
+            match encoding.encode(&snapshot) {
+                Ok(frame) => {
+                    if sender.send(frame).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => warn!("Failed to serialize history snapshot: {}", e),
+            }
+        }
+        loop {
+            let event = tokio::select! {
+                event = event_rx.recv() => match event {
+                    Ok(event) => event,
+                    Err(_) => break,
+                },
+                Some(reply) = reply_rx.recv() => reply,
+            };
+            match encoding.encode(&event) {
+                Ok(frame) => {
+                    if sender.send(frame).await.is_err() {
                         break;
                     }
                 }
@@ -106,3 +260,518 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
 
     info!("WebSocket connection closed");
 }
+
+/// Answers a client's mission sync traffic through `reply_tx` to that client
+/// alone. Events a client sends are never republished here; publishing goes
+/// through `/ws/ingest`.
+fn route_client_event(
+    event: WebSocketMessage,
+    state: &AppState,
+    authorized: bool,
+    reply_tx: &mpsc::UnboundedSender<WebSocketMessage>,
+) {
+    match event {
+        WebSocketMessage::MissionSync { message } => {
+            handle_mission_sync(message, state, authorized, reply_tx)
+        }
+        _ => debug!("Ignoring client message; events are published through /ws/ingest"),
+    }
+}
+
+fn handle_mission_sync(
+    message: MissionSyncMessage,
+    state: &AppState,
+    authorized: bool,
+    reply_tx: &mpsc::UnboundedSender<WebSocketMessage>,
+) {
+    let step = state
+        .mission_sync
+        .lock()
+        // Every step leaves the receiver whole, so a poisoned lock is still usable.
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .handle(message);
+    if let Some(reply) = step.reply {
+        let _ = reply_tx.send(WebSocketMessage::MissionSync { message: reply });
+    }
+    if let Some(progress) = step.progress {
+        let _ = state
+            .event_tx
+            .send(WebSocketMessage::MissionSyncProgress { progress });
+    }
+    if let Some(mission) = step.committed {
+        tokio::spawn(activate_synced_mission(
+            mission,
+            state.vehicle.clone(),
+            authorized,
+            state.event_tx.clone(),
+        ));
+    }
+}
+
+/// Uploads a verified mission to the vehicle, or only records it as synced
+/// when no vehicle is connected, and reports the outcome as mission status.
+/// A sender without the control token never reaches the vehicle.
+async fn activate_synced_mission(
+    mission: SyncedMission,
+    vehicle: Option<Arc<MavlinkClient>>,
+    authorized: bool,
+    event_tx: broadcast::Sender<WebSocketMessage>,
+) {
+    let mission_id = mission.manifest.mission_id;
+    let items = serde_json::from_value::<VehicleMission>(mission.into_document("items"))
+        .map_err(|e| e.to_string())
+        .and_then(|mission| mission.to_mission_items().map_err(|e| e.to_string()));
+    let status = match (items, vehicle) {
+        (Err(e), _) => {
+            warn!(
+                "Synced mission {} is not a vehicle mission: {}",
+                mission_id, e
+            );
+            "Rejected"
+        }
+        (Ok(_), None) => {
+            info!("Mission {} synced; no vehicle connected", mission_id);
+            "Synced"
+        }
+        (Ok(_), Some(_)) if !authorized => {
+            warn!(
+                "Not uploading synced mission {}: sender lacks the control token",
+                mission_id
+            );
+            "Unauthorized"
+        }
+        (Ok(items), Some(vehicle)) => {
+            info!(
+                "Uploading synced mission {} ({} items) to the vehicle",
+                mission_id,
+                items.len()
+            );
+            match vehicle.upload_mission(items, VEHICLE_MISSION_TIMEOUT).await {
+                Ok(MavMissionResult::MAV_MISSION_ACCEPTED) => "Activated",
+                Ok(result) => {
+                    warn!(
+                        "Vehicle rejected synced mission {}: {:?}",
+                        mission_id, result
+                    );
+                    "Rejected"
+                }
+                Err(e) => {
+                    warn!("Synced mission {} upload failed: {}", mission_id, e);
+                    "UploadFailed"
+                }
+            }
+        }
+    };
+    let _ = event_tx.send(WebSocketMessage::MissionStatus {
+        mission_id,
+        status: status.to_string(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws_encoding::{FrameCompression, FrameFormat};
+    use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+
+    async fn serve() -> (String, broadcast::Sender<WebSocketMessage>) {
+        serve_with_history(Arc::new(TelemetryHistory::new(0))).await
+    }
+
+    async fn serve_with_history(
+        history: Arc<TelemetryHistory>,
+    ) -> (String, broadcast::Sender<WebSocketMessage>) {
+        serve_with(AgroConfig::default(), history).await
+    }
+
+    async fn serve_with(
+        config: AgroConfig,
+        history: Arc<TelemetryHistory>,
+    ) -> (String, broadcast::Sender<WebSocketMessage>) {
+        let (event_tx, _) = broadcast::channel(16);
+        let server = WebSocketServer::new(Arc::new(config), event_tx.clone(), history);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, server.router()).await.unwrap() });
+        (format!("ws://{address}/ws"), event_tx)
+    }
+
+    fn status_event() -> WebSocketMessage {
+        WebSocketMessage::SystemStatus {
+            status: "ok".to_string(),
+            message: "battery nominal ".repeat(20),
+        }
+    }
+
+    /// Publishes `event` once the client's subscription is live and returns
+    /// the first frame the client receives.
+    async fn first_frame(
+        request: tungstenite::handshake::client::Request,
+        event_tx: &broadcast::Sender<WebSocketMessage>,
+        event: &WebSocketMessage,
+    ) -> (tungstenite::Message, Option<String>) {
+        let (mut client, response) = tokio_tungstenite::connect_async(request).await.unwrap();
+        let protocol = response
+            .headers()
+            .get("sec-websocket-protocol")
+            .map(|value| value.to_str().unwrap().to_string());
+        while event_tx.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        event_tx.send(event.clone()).unwrap();
+        let frame = client.next().await.unwrap().unwrap();
+        (frame, protocol)
+    }
+
+    #[tokio::test]
+    async fn clients_get_the_encoding_they_negotiate() {
+        let (url, event_tx) = serve().await;
+        let event = status_event();
+        let expected = serde_json::to_value(&event).unwrap();
+
+        let (frame, _) = first_frame(
+            url.as_str().into_client_request().unwrap(),
+            &event_tx,
+            &event,
+        )
+        .await;
+        let tungstenite::Message::Text(text) = frame else {
+            panic!("plain clients should get text frames, got {frame:?}");
+        };
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&text).unwrap(),
+            expected
+        );
+
+        let (frame, _) = first_frame(
+            format!("{url}?encoding=msgpack")
+                .into_client_request()
+                .unwrap(),
+            &event_tx,
+            &event,
+        )
+        .await;
+        let tungstenite::Message::Binary(payload) = frame else {
+            panic!("msgpack clients should get binary frames, got {frame:?}");
+        };
+        let msgpack = FrameEncoding {
+            format: FrameFormat::Msgpack,
+            compression: FrameCompression::None,
+        };
+        let decoded = msgpack.decode_binary(&payload).unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), expected);
+        let mission = WebSocketMessage::MissionStatus {
+            mission_id: uuid::Uuid::new_v4(),
+            status: "Active".to_string(),
+        };
+        let Message::Binary(mission_payload) = msgpack.encode(&mission).unwrap() else {
+            panic!("msgpack frames should be binary");
+        };
+        assert_eq!(
+            serde_json::to_value(msgpack.decode_binary(&mission_payload).unwrap()).unwrap(),
+            serde_json::to_value(&mission).unwrap()
+        );
+
+        let mut request = url.as_str().into_client_request().unwrap();
+        request.headers_mut().insert(
+            "sec-websocket-protocol",
+            "agbot.msgpack+deflate, agbot.json".parse().unwrap(),
+        );
+        let (frame, protocol) = first_frame(request, &event_tx, &event).await;
+        assert_eq!(protocol.as_deref(), Some("agbot.msgpack+deflate"));
+        let tungstenite::Message::Binary(compressed) = frame else {
+            panic!("compressed clients should get binary frames, got {frame:?}");
+        };
+        assert!(compressed.len() < payload.len());
+        let decoded = FrameEncoding::from_subprotocol("agbot.msgpack+deflate")
+            .unwrap()
+            .decode_binary(&compressed)
+            .unwrap();
+        assert_eq!(serde_json::to_value(&decoded).unwrap(), expected);
+    }
+
+    fn telemetry_event(battery_percentage: u8) -> WebSocketMessage {
+        WebSocketMessage::Telemetry {
+            data: shared::schemas::Telemetry {
+                timestamp: chrono::Utc::now(),
+                position: shared::schemas::GpsCoords {
+                    latitude: 37.7749,
+                    longitude: -122.4194,
+                    altitude: 120.0,
+                },
+                battery_voltage: 12.4,
+                battery_percentage,
+                armed: true,
+                mode: "AUTO".to_string(),
+                ground_speed: 5.0,
+                air_speed: 5.5,
+                heading: 90.0,
+                altitude_relative: 20.0,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn late_clients_get_a_history_snapshot_before_live_messages() {
+        let history = Arc::new(TelemetryHistory::new(3));
+        let (url, event_tx) = serve_with_history(history.clone()).await;
+        let recorder = {
+            let history = history.clone();
+            let event_rx = event_tx.subscribe();
+            tokio::spawn(async move { history.run(event_rx).await })
+        };
+
+        let published: Vec<_> = (0..4u8)
+            .map(|step| telemetry_event(90 - step))
+            .chain([status_event()])
+            .collect();
+        for event in &published {
+            event_tx.send(event.clone()).unwrap();
+        }
+        event_tx
+            .send(WebSocketMessage::MissionStatus {
+                mission_id: uuid::Uuid::nil(),
+                status: "Active".to_string(),
+            })
+            .unwrap();
+        while history.snapshot().len() < 3
+            || !matches!(
+                history.snapshot().last(),
+                Some(WebSocketMessage::MissionStatus { .. })
+            )
+        {
+            tokio::task::yield_now().await;
+        }
+
+        let (mut client, _) = tokio_tungstenite::connect_async(url.as_str())
+            .await
+            .unwrap();
+        let tungstenite::Message::Text(text) = client.next().await.unwrap().unwrap() else {
+            panic!("snapshot should be a text frame for plain clients");
+        };
+        let WebSocketMessage::HistorySnapshot { messages } = serde_json::from_str(&text).unwrap()
+        else {
+            panic!("first frame should be the history snapshot, got {text}");
+        };
+        let expected: Vec<_> = [&published[3], &published[4]]
+            .into_iter()
+            .cloned()
+            .chain([WebSocketMessage::MissionStatus {
+                mission_id: uuid::Uuid::nil(),
+                status: "Active".to_string(),
+            }])
+            .map(|message| serde_json::to_value(message).unwrap())
+            .collect();
+        let received: Vec<_> = messages
+            .iter()
+            .map(|message| serde_json::to_value(message).unwrap())
+            .collect();
+        assert_eq!(received, expected);
+
+        let live = telemetry_event(42);
+        event_tx.send(live.clone()).unwrap();
+        let tungstenite::Message::Text(text) = client.next().await.unwrap().unwrap() else {
+            panic!("live messages should follow as text frames");
+        };
+        let received: WebSocketMessage = serde_json::from_str(&text).unwrap();
+        assert_eq!(
+            serde_json::to_value(received).unwrap(),
+            serde_json::to_value(&live).unwrap()
+        );
+        recorder.abort();
+    }
+
+    type Client = tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >;
+
+    /// Syncs a five-item vehicle mission over `client` until mission control
+    /// confirms the commit, returning the mission id.
+    async fn sync_test_mission(client: &mut Client) -> uuid::Uuid {
+        use shared::mission_sync::{chunk_mission_document, MissionSyncPhase, MissionSyncSender};
+
+        let items: Vec<_> = (0..5u16)
+            .map(|seq| {
+                serde_json::json!({
+                    "seq": seq, "frame": 3, "command": 16, "current": u8::from(seq == 0),
+                    "autocontinue": 1, "param1": 0.0, "param2": 0.0, "param3": 0.0,
+                    "param4": 0.0, "x": 41.0, "y": -96.0, "z": 40.0, "mission_type": 0,
+                })
+            })
+            .collect();
+        let document = serde_json::json!({
+            "target_system": 1, "target_component": 1, "count": 5, "items": items,
+        });
+        let (manifest, chunks) =
+            chunk_mission_document(uuid::Uuid::new_v4(), &document, "items", 2).unwrap();
+        let mission_id = manifest.mission_id;
+        let mut sync = MissionSyncSender::new(manifest, chunks);
+
+        let mut outgoing = vec![sync.offer()];
+        while !matches!(sync.progress().phase, MissionSyncPhase::Complete) {
+            for message in outgoing.drain(..) {
+                let frame =
+                    serde_json::to_string(&WebSocketMessage::MissionSync { message }).unwrap();
+                client
+                    .send(tungstenite::Message::Text(frame))
+                    .await
+                    .unwrap();
+            }
+            let tungstenite::Message::Text(text) = client.next().await.unwrap().unwrap() else {
+                panic!("plain clients should get text frames");
+            };
+            let WebSocketMessage::MissionSync { message } = serde_json::from_str(&text).unwrap()
+            else {
+                continue;
+            };
+            match message {
+                MissionSyncMessage::Have { chunk_hashes, .. } => {
+                    sync.on_have(&chunk_hashes);
+                    outgoing = sync
+                        .pending()
+                        .cloned()
+                        .map(|chunk| MissionSyncMessage::Chunk { mission_id, chunk })
+                        .collect();
+                    if sync.is_transferred() {
+                        outgoing = vec![sync.commit()];
+                    }
+                }
+                MissionSyncMessage::ChunkAck { index, hash, .. } => {
+                    sync.on_ack(index, &hash);
+                    if sync.is_transferred() {
+                        outgoing = vec![sync.commit()];
+                    }
+                }
+                MissionSyncMessage::Committed { .. } => sync.finish(MissionSyncPhase::Complete),
+                other => panic!("unexpected sync reply {other:?}"),
+            }
+        }
+        mission_id
+    }
+
+    #[tokio::test]
+    async fn mission_sync_replies_go_to_the_sender_and_progress_is_broadcast() {
+        let (url, event_tx) = serve().await;
+        let mut observer = event_tx.subscribe();
+        let (mut client, _) = tokio_tungstenite::connect_async(url.as_str())
+            .await
+            .unwrap();
+        let mission_id = sync_test_mission(&mut client).await;
+
+        let mut saw_progress = false;
+        loop {
+            match observer.recv().await.unwrap() {
+                WebSocketMessage::MissionSync { .. } => {
+                    panic!("sync traffic should not be broadcast")
+                }
+                WebSocketMessage::MissionSyncProgress { progress } => {
+                    assert_eq!(progress.mission_id, mission_id);
+                    saw_progress = true;
+                }
+                WebSocketMessage::MissionStatus { status, .. } => {
+                    assert_eq!(status, "Synced");
+                    break;
+                }
+                _ => {}
+            }
+        }
+        assert!(saw_progress);
+    }
+
+    #[tokio::test]
+    async fn only_clients_holding_the_control_token_can_publish_events() {
+        let mut config = AgroConfig::default();
+        config.server.control_token = Some("s3cret".to_string());
+        let (url, event_tx) = serve_with(config, Arc::new(TelemetryHistory::new(0))).await;
+        let mut observer = event_tx.subscribe();
+        let forged = serde_json::to_string(&telemetry_event(5)).unwrap();
+
+        let (mut client, _) = tokio_tungstenite::connect_async(url.as_str())
+            .await
+            .unwrap();
+        client
+            .send(tungstenite::Message::Text(forged.clone()))
+            .await
+            .unwrap();
+        client.close(None).await.unwrap();
+
+        let ingest_url = format!("{url}/ingest");
+        let rejected = tokio_tungstenite::connect_async(ingest_url.as_str()).await;
+        assert!(matches!(
+            rejected,
+            Err(tungstenite::Error::Http(response)) if response.status() == 401
+        ));
+
+        let mut request = ingest_url.as_str().into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("authorization", "Bearer s3cret".parse().unwrap());
+        let (mut publisher, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+        let replayed = status_event();
+        publisher
+            .send(tungstenite::Message::Text(
+                serde_json::to_string(&replayed).unwrap(),
+            ))
+            .await
+            .unwrap();
+
+        let received = observer.recv().await.unwrap();
+        assert_eq!(
+            serde_json::to_value(received).unwrap(),
+            serde_json::to_value(&replayed).unwrap(),
+            "the forged telemetry from the plain client must not be published"
+        );
+    }
+
+    #[tokio::test]
+    async fn synced_missions_reach_the_vehicle_only_with_the_control_token() {
+        let (vehicle, _events, stored) = crate::mavlink_client::mock_vehicle::connect(Some(
+            mavlink::common::MavResult::MAV_RESULT_ACCEPTED,
+        ))
+        .await;
+        let mut config = AgroConfig::default();
+        config.server.control_token = Some("s3cret".to_string());
+        let (event_tx, _) = broadcast::channel(64);
+        let server = WebSocketServer::new(
+            Arc::new(config),
+            event_tx.clone(),
+            Arc::new(TelemetryHistory::new(0)),
+        )
+        .with_vehicle(vehicle);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, server.router()).await.unwrap() });
+        let mut observer = event_tx.subscribe();
+
+        let mission_status = |observer: &mut broadcast::Receiver<WebSocketMessage>| {
+            let mut observer = observer.resubscribe();
+            async move {
+                loop {
+                    if let WebSocketMessage::MissionStatus { status, .. } =
+                        observer.recv().await.unwrap()
+                    {
+                        return status;
+                    }
+                }
+            }
+        };
+
+        let (mut anonymous, _) = tokio_tungstenite::connect_async(url.as_str())
+            .await
+            .unwrap();
+        let status = mission_status(&mut observer);
+        sync_test_mission(&mut anonymous).await;
+        assert_eq!(status.await, "Unauthorized");
+        assert!(stored.lock().unwrap().is_empty());
+
+        let mut request = url.as_str().into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("authorization", "Bearer s3cret".parse().unwrap());
+        let (mut operator, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+        let status = mission_status(&mut observer);
+        sync_test_mission(&mut operator).await;
+        assert_eq!(status.await, "Activated");
+        assert_eq!(stored.lock().unwrap().len(), 5);
+    }
+}
@@ -0,0 +1,122 @@
+use axum::extract::ws::Message;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::Deserialize;
+use shared::{error::AgroError, schemas::WebSocketMessage, AgroResult};
+use std::io::{Read, Write};
+
+/// Subprotocols `/ws` accepts, most compact first; the server picks the
+/// first one the client also offers.
+pub const SUBPROTOCOLS: [&str; 4] = [
+    "agbot.msgpack+deflate",
+    "agbot.msgpack",
+    "agbot.json+deflate",
+    "agbot.json",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameFormat {
+    #[default]
+    Json,
+    /// MessagePack with named fields, so optional fields may be left out.
+    Msgpack,
+}
+
+/// Compression of each message payload. The WebSocket stack has no
+/// permessage-deflate extension, so compressed payloads are raw deflate
+/// streams carried in binary frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameCompression {
+    #[default]
+    None,
+    Deflate,
+}
+
+/// How one connection's messages are framed. The default, text JSON, is
+/// what clients get unless they negotiate something else.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameEncoding {
+    pub format: FrameFormat,
+    pub compression: FrameCompression,
+}
+
+/// `/ws?encoding=msgpack&compression=deflate`; each parameter overrides the
+/// matching half of any negotiated subprotocol.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct EncodingQuery {
+    pub encoding: Option<FrameFormat>,
+    pub compression: Option<FrameCompression>,
+}
+
+impl EncodingQuery {
+    pub fn resolve(&self, subprotocol: Option<&str>) -> FrameEncoding {
+        let negotiated = subprotocol
+            .and_then(FrameEncoding::from_subprotocol)
+            .unwrap_or_default();
+        FrameEncoding {
+            format: self.encoding.unwrap_or(negotiated.format),
+            compression: self.compression.unwrap_or(negotiated.compression),
+        }
+    }
+}
+
+impl FrameEncoding {
+    pub fn from_subprotocol(protocol: &str) -> Option<Self> {
+        let (format, compression) = match protocol.trim() {
+            "agbot.json" => (FrameFormat::Json, FrameCompression::None),
+            "agbot.json+deflate" => (FrameFormat::Json, FrameCompression::Deflate),
+            "agbot.msgpack" => (FrameFormat::Msgpack, FrameCompression::None),
+            "agbot.msgpack+deflate" => (FrameFormat::Msgpack, FrameCompression::Deflate),
+            _ => return None,
+        };
+        Some(Self {
+            format,
+            compression,
+        })
+    }
+
+    /// Text frames for uncompressed JSON, binary frames otherwise.
+    pub fn encode(&self, message: &WebSocketMessage) -> AgroResult<Message> {
+        if *self == Self::default() {
+            return Ok(Message::Text(serde_json::to_string(message)?));
+        }
+        let payload = match self.format {
+            FrameFormat::Json => serde_json::to_vec(message)?,
+            FrameFormat::Msgpack => rmp_serde::to_vec_named(message)
+                .map_err(|error| frame_error("encode MessagePack", error))?,
+        };
+        let payload = match self.compression {
+            FrameCompression::None => payload,
+            FrameCompression::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&payload)?;
+                encoder.finish()?
+            }
+        };
+        Ok(Message::Binary(payload))
+    }
+
+    /// Decodes the payload of a binary frame sent in this encoding.
+    pub fn decode_binary(&self, payload: &[u8]) -> AgroResult<WebSocketMessage> {
+        let inflated;
+        let payload = match self.compression {
+            FrameCompression::None => payload,
+            FrameCompression::Deflate => {
+                let mut buffer = Vec::new();
+                DeflateDecoder::new(payload).read_to_end(&mut buffer)?;
+                inflated = buffer;
+                &inflated
+            }
+        };
+        match self.format {
+            FrameFormat::Json => Ok(serde_json::from_slice(payload)?),
+            FrameFormat::Msgpack => rmp_serde::from_slice(payload)
+                .map_err(|error| frame_error("decode MessagePack", error)),
+        }
+    }
+}
+
+fn frame_error(action: &str, error: impl std::fmt::Display) -> AgroError {
+    AgroError::Network(format!("failed to {action} WebSocket frame: {error}"))
+}
@@ -1,8 +1,9 @@
 import React from 'react';
-import { Mission } from '../types/mission';
+import { CostEstimate, Mission } from '../types/mission';
 
 interface MissionPanelProps {
   mission: Mission | null;
+  estimate: CostEstimate | null;
   missions: Mission[];
   onMissionSelect: (mission: Mission) => void;
   onNewMission: () => void;
@@ -13,6 +14,7 @@ interface MissionPanelProps {
 
 const MissionPanel: React.FC<MissionPanelProps> = ({
   mission,
+  estimate,
   missions,
   onMissionSelect,
   onNewMission,
@@ -75,6 +77,17 @@ const MissionPanel: React.FC<MissionPanelProps> = ({
             <p><strong>Est. Battery:</strong> {(mission.estimated_battery_usage * 100).toFixed(1)}%</p>
           </div>
           
+          {estimate && (
+            <div className="mission-estimate">
+              <h5>Cost Estimate</h5>
+              <p><strong>Flight Time:</strong> {(estimate.total_flight_time_seconds / 3600).toFixed(1)} h</p>
+              <p><strong>Sorties:</strong> {estimate.sortie_count} ({estimate.battery_swaps} battery swaps, limited by {estimate.limited_by === 'Battery' ? 'battery' : 'flight time'})</p>
+              <p><strong>Images:</strong> {estimate.image_count}</p>
+              <p><strong>Raw Data:</strong> {(estimate.total_data_bytes / 1e9).toFixed(1)} GB</p>
+              <p><strong>Processing:</strong> {estimate.total_processing_minutes.toFixed(0)} min</p>
+            </div>
+          )}
+
           <div className="waypoint-list">
             <h5>Waypoints</h5>
             {mission.waypoints.length === 0 ? (
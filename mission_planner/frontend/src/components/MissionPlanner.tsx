@@ -2,12 +2,13 @@ import React, { useState, useEffect, useCallback } from 'react';
 import MapComponent from './MapComponent';
 import MissionPanel from './MissionPanel';
 import ControlsPanel from './ControlsPanel';
-import { Mission, Waypoint, WaypointType } from '../types/mission';
+import { CostEstimate, Mission, Waypoint, WaypointType } from '../types/mission';
 import { MissionService } from '../services/missionService';
 import { WebSocketService } from '../services/websocketService';
 
 const MissionPlanner: React.FC = () => {
   const [currentMission, setCurrentMission] = useState<Mission | null>(null);
+  const [estimate, setEstimate] = useState<CostEstimate | null>(null);
   const [missions, setMissions] = useState<Mission[]>([]);
   const [isDrawing, setIsDrawing] = useState(false);
   const [selectedTool, setSelectedTool] = useState<'waypoint' | 'area' | 'path'>('waypoint');
@@ -29,6 +30,14 @@ const MissionPlanner: React.FC = () => {
     };
   }, []);
 
+  useEffect(() => {
+    setEstimate(null);
+    if (!currentMission) return;
+    missionService.getMissionEstimate(currentMission.id)
+      .then(setEstimate)
+      .catch(() => setEstimate(null));
+  }, [currentMission?.id, currentMission?.updated_at]);
+
   const loadMissions = async () => {
     try {
       const missionList = await missionService.getMissions();
@@ -143,6 +152,7 @@ const MissionPlanner: React.FC = () => {
         
         <MissionPanel
           mission={currentMission}
+          estimate={estimate}
           missions={missions}
           onMissionSelect={setCurrentMission}
           onNewMission={createNewMission}
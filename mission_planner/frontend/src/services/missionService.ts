@@ -1,5 +1,5 @@
 import axios from 'axios';
-import { Mission, MAVLinkMission, MAVLinkCommand, MAV_CMD, WaypointType } from '../types/mission';
+import { CostEstimate, Mission, MAVLinkMission, MAVLinkCommand, MAV_CMD, WaypointType } from '../types/mission';
 
 export class MissionService {
   private baseUrl = process.env.REACT_APP_API_URL || 'http://localhost:3000/api';
@@ -33,6 +33,11 @@ export class MissionService {
     return response.data;
   }
 
+  async getMissionEstimate(id: string): Promise<CostEstimate> {
+    const response = await axios.get(`${this.baseUrl}/missions/${id}/estimate`);
+    return response.data;
+  }
+
   async convertToMAVLink(mission: Mission): Promise<MAVLinkMission> {
     const items: MAVLinkCommand[] = [];
 
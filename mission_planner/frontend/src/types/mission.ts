@@ -67,6 +67,41 @@ export interface Mission {
   metadata: Record<string, string>;
 }
 
+export interface SortieEstimate {
+  index: number;
+  distance_m: number;
+  flight_time_seconds: number;
+  battery_draw_percent: number;
+  image_count: number;
+}
+
+export interface SensorDataVolume {
+  sensor: string;
+  image_count: number;
+  bytes: number;
+}
+
+export interface ProcessingEstimate {
+  stage: string;
+  minutes: number;
+}
+
+export interface CostEstimate {
+  mission_id: string | null;
+  area_hectares: number;
+  total_distance_m: number;
+  total_flight_time_seconds: number;
+  sortie_count: number;
+  battery_swaps: number;
+  limited_by: 'Battery' | 'FlightTime';
+  sorties: SortieEstimate[];
+  image_count: number;
+  data_volume: SensorDataVolume[];
+  total_data_bytes: number;
+  processing: ProcessingEstimate[];
+  total_processing_minutes: number;
+}
+
 export interface MAVLinkCommand {
   command: number;
   param1?: number;
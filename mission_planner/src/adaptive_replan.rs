@@ -288,8 +288,9 @@ mod tests {
             waypoints: vec![
                 Waypoint {
                     id: Uuid::new_v4(),
-                    position: point!(x: start_x, y: start_y),
+                    position: point!(x: start_x, y: start_y).into(),
                     altitude_m: 40.0,
+                    altitude_reference: shared::AltitudeReference::RelativeToHome,
                     waypoint_type: WaypointType::Takeoff,
                     actions: Vec::new(),
                     arrival_time: None,
@@ -298,8 +299,9 @@ mod tests {
                 },
                 Waypoint {
                     id: Uuid::new_v4(),
-                    position: point!(x: end_x, y: end_y),
+                    position: point!(x: end_x, y: end_y).into(),
                     altitude_m: 40.0,
+                    altitude_reference: shared::AltitudeReference::RelativeToHome,
                     waypoint_type: WaypointType::Landing,
                     actions: Vec::new(),
                     arrival_time: None,
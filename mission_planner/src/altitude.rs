@@ -0,0 +1,172 @@
+use crate::terrain::Dem;
+use crate::Waypoint;
+use geo::Point;
+use shared::geospatial::{AltitudeConversionError, AltitudeDatum, AltitudeReference};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AltitudeReferenceError {
+    /// Waypoints disagree on their altitude datum.
+    Mixed {
+        expected: AltitudeReference,
+        waypoint_index: usize,
+        found: AltitudeReference,
+    },
+    Conversion {
+        waypoint_index: usize,
+        source: AltitudeConversionError,
+    },
+}
+
+impl fmt::Display for AltitudeReferenceError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mixed {
+                expected,
+                waypoint_index,
+                found,
+            } => write!(
+                formatter,
+                "waypoint {waypoint_index} altitude is {found:?} but earlier waypoints use \
+                 {expected:?}; normalize the mission to one reference first"
+            ),
+            Self::Conversion {
+                waypoint_index,
+                source,
+            } => write!(formatter, "waypoint {waypoint_index}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for AltitudeReferenceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Conversion { source, .. } => Some(source),
+            Self::Mixed { .. } => None,
+        }
+    }
+}
+
+/// The single altitude reference shared by all `waypoints`, or `None` when
+/// there are none.
+pub fn common_altitude_reference(
+    waypoints: &[Waypoint],
+) -> Result<Option<AltitudeReference>, AltitudeReferenceError> {
+    let Some(expected) = waypoints
+        .first()
+        .map(|waypoint| waypoint.altitude_reference)
+    else {
+        return Ok(None);
+    };
+    match waypoints
+        .iter()
+        .position(|waypoint| waypoint.altitude_reference != expected)
+    {
+        Some(waypoint_index) => Err(AltitudeReferenceError::Mixed {
+            expected,
+            waypoint_index,
+            found: waypoints[waypoint_index].altitude_reference,
+        }),
+        None => Ok(Some(expected)),
+    }
+}
+
+/// Re-expresses every waypoint altitude against `target`. Home elevation is
+/// needed for relative-to-home altitudes and the DEM for AGL ones; the
+/// waypoints are left untouched if any of them cannot be converted.
+pub fn normalize_altitude_references(
+    waypoints: &mut [Waypoint],
+    target: AltitudeReference,
+    home_elevation_msl_m: Option<f64>,
+    dem: Option<&Dem>,
+) -> Result<(), AltitudeReferenceError> {
+    let converted = waypoints
+        .iter()
+        .enumerate()
+        .map(|(waypoint_index, waypoint)| {
+            let terrain_elevation_msl_m = dem
+                .and_then(|dem| dem.elevation_at(&Point::from(waypoint.position)))
+                .map(f64::from);
+            waypoint
+                .altitude_reference
+                .convert(
+                    f64::from(waypoint.altitude_m),
+                    target,
+                    &AltitudeDatum::new(home_elevation_msl_m, terrain_elevation_msl_m),
+                )
+                .map(|altitude_m| altitude_m as f32)
+                .map_err(|source| AltitudeReferenceError::Conversion {
+                    waypoint_index,
+                    source,
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for (waypoint, altitude_m) in waypoints.iter_mut().zip(converted) {
+        waypoint.altitude_m = altitude_m;
+        waypoint.altitude_reference = target;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WaypointType;
+    use shared::GeoPoint;
+
+    #[test]
+    fn mixed_references_are_rejected_until_normalized_against_home_and_terrain() {
+        let dem = Dem::new(Point::new(-96.1, 41.1), 0.1, 2, 2, vec![300.0; 4]).unwrap();
+        let mut waypoints = vec![
+            Waypoint::new(GeoPoint::new(41.15, -96.05), 40.0, WaypointType::Navigation),
+            Waypoint::new(GeoPoint::new(41.16, -96.05), 25.0, WaypointType::Survey)
+                .with_altitude_reference(AltitudeReference::Agl),
+            Waypoint::new(
+                GeoPoint::new(41.17, -96.05),
+                360.0,
+                WaypointType::Navigation,
+            )
+            .with_altitude_reference(AltitudeReference::Msl),
+        ];
+
+        assert_eq!(
+            common_altitude_reference(&waypoints),
+            Err(AltitudeReferenceError::Mixed {
+                expected: AltitudeReference::RelativeToHome,
+                waypoint_index: 1,
+                found: AltitudeReference::Agl,
+            })
+        );
+        assert!(matches!(
+            normalize_altitude_references(
+                &mut waypoints,
+                AltitudeReference::RelativeToHome,
+                Some(310.0),
+                None,
+            ),
+            Err(AltitudeReferenceError::Conversion {
+                waypoint_index: 1,
+                source: AltitudeConversionError::MissingTerrainElevation { .. },
+            })
+        ));
+        assert_eq!(waypoints[0].altitude_m, 40.0);
+
+        normalize_altitude_references(
+            &mut waypoints,
+            AltitudeReference::RelativeToHome,
+            Some(310.0),
+            Some(&dem),
+        )
+        .unwrap();
+        let altitudes = waypoints
+            .iter()
+            .map(|waypoint| waypoint.altitude_m)
+            .collect::<Vec<_>>();
+        assert_eq!(altitudes, vec![40.0, 15.0, 50.0]);
+        assert_eq!(
+            common_altitude_reference(&waypoints),
+            Ok(Some(AltitudeReference::RelativeToHome))
+        );
+    }
+}
@@ -6,15 +6,17 @@ use axum::{
     routing::{delete, get, post, put},
     Router,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::{
-    Mission, MissionLinkage, MissionListFilter, MissionPlannerService, MissionRevision,
-    MissionStats, MissionStatus, Waypoint,
+    estimate_mission_cost, mission_draft_from_session, CostEstimate, CostEstimateConfig, Mission,
+    MissionLinkage, MissionListFilter, MissionPlannerService, MissionRevision, MissionStats,
+    MissionStatus, ProviderFailure, TrackSimplificationConfig, Waypoint, WaypointValidationError,
+    WeatherData, WeatherIntegration,
 };
 
 /// REST API for mission planning
@@ -34,7 +36,12 @@ impl MissionApi {
             .route("/missions", get(list_missions))
             .route("/missions/search", get(search_missions))
             .route("/missions/stats", get(get_mission_stats))
+            .route(
+                "/missions/from-session/:session_id",
+                post(create_mission_from_session),
+            )
             .route("/missions/:id/history", get(get_mission_history))
+            .route("/missions/:id/estimate", get(get_mission_estimate))
             .route("/missions/:id", get(get_mission))
             .route("/missions/:id", put(update_mission))
             .route("/missions/:id", delete(delete_mission))
@@ -43,6 +50,17 @@ impl MissionApi {
     }
 }
 
+/// REST API for current conditions from the weather provider chain
+pub struct WeatherApi;
+
+impl WeatherApi {
+    pub fn router(weather: Arc<WeatherIntegration>) -> Router {
+        Router::new()
+            .route("/weather", get(get_weather))
+            .with_state(weather)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateMissionRequest {
     pub name: String,
@@ -85,9 +103,31 @@ pub struct SearchQuery {
     pub q: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct FromSessionQuery {
+    pub tolerance_m: Option<f64>,
+    pub vertical_threshold_m: Option<f32>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateMissionFromSessionRequest {
+    pub name: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MissionResponse {
     pub mission: Mission,
+    /// Cost estimate with the default estimate config, shown alongside the
+    /// mission; absent when the mission cannot be estimated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<CostEstimate>,
+}
+
+impl From<Mission> for MissionResponse {
+    fn from(mission: Mission) -> Self {
+        let estimate = estimate_mission_cost(&mission, &CostEstimateConfig::default()).ok();
+        Self { mission, estimate }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -110,6 +150,27 @@ pub struct CreateMissionResponse {
     pub message: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct WeatherQuery {
+    pub lat: f64,
+    pub lon: f64,
+    /// Hours of forecast to include; none when unset.
+    pub hours: Option<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WeatherResponse {
+    pub current: WeatherData,
+    pub provider: String,
+    pub observed_at: DateTime<Utc>,
+    pub age_seconds: i64,
+    /// True once the reading is older than the configured maximum age.
+    pub stale: bool,
+    pub failed_providers: Vec<ProviderFailure>,
+    pub forecast_provider: Option<String>,
+    pub hourly: Vec<WeatherData>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: String,
@@ -153,13 +214,101 @@ async fn create_mission(
             id,
             message: "Mission created successfully".to_string(),
         })),
-        Err(e) => Err((
+        Err(e) => Err(create_mission_error(e)),
+    }
+}
+
+/// Maps a failed create to 422 when the mission itself was rejected.
+fn create_mission_error(error: anyhow::Error) -> (StatusCode, Json<ErrorResponse>) {
+    match error.downcast_ref::<WaypointValidationError>() {
+        Some(validation) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ErrorResponse {
+                error: "INVALID_WAYPOINT_ACTIONS".to_string(),
+                message: validation
+                    .issues
+                    .iter()
+                    .map(|issue| match issue.waypoint_index {
+                        Some(index) => format!("waypoint {index}: {}", issue.message),
+                        None => issue.message.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            }),
+        ),
+        None => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
                 error: "CREATE_FAILED".to_string(),
-                message: e.to_string(),
+                message: error.to_string(),
             }),
-        )),
+        ),
+    }
+}
+
+/// Create a draft mission that re-flies the track the data collector
+/// recorded for a session
+async fn create_mission_from_session(
+    State(service): State<Arc<MissionPlannerService>>,
+    Path(session_id): Path<Uuid>,
+    Query(query): Query<FromSessionQuery>,
+    request: Option<Json<CreateMissionFromSessionRequest>>,
+) -> Result<(StatusCode, Json<MissionResponse>), (StatusCode, Json<ErrorResponse>)> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let source = service.session_tracks().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: "SESSION_TRACKS_UNAVAILABLE".to_string(),
+                message: "DATA_COLLECTOR_URL is not configured".to_string(),
+            }),
+        )
+    })?;
+    let track = match source.fetch(session_id).await {
+        Ok(Some(track)) => track,
+        Ok(None) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "SESSION_NOT_FOUND".to_string(),
+                    message: format!("Session {session_id} not found"),
+                }),
+            ))
+        }
+        Err(e) => {
+            return Err((
+                StatusCode::BAD_GATEWAY,
+                Json(ErrorResponse {
+                    error: "SESSION_TRACK_FETCH_FAILED".to_string(),
+                    message: format!("{e:#}"),
+                }),
+            ))
+        }
+    };
+    let defaults = TrackSimplificationConfig::default();
+    let config = TrackSimplificationConfig {
+        tolerance_m: query.tolerance_m.unwrap_or(defaults.tolerance_m),
+        vertical_threshold_m: query
+            .vertical_threshold_m
+            .unwrap_or(defaults.vertical_threshold_m),
+    };
+    let mut mission =
+        mission_draft_from_session(&session_id.to_string(), &track, config).map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ErrorResponse {
+                    error: "INVALID_SESSION_TRACK".to_string(),
+                    message: e.to_string(),
+                }),
+            )
+        })?;
+    if let Some(name) = request.name {
+        mission.name = name;
+    }
+
+    match service.create_mission(mission.clone()).await {
+        Ok(_) => Ok((StatusCode::CREATED, Json(MissionResponse::from(mission)))),
+        Err(e) => Err(create_mission_error(e)),
     }
 }
 
@@ -169,7 +318,7 @@ async fn get_mission(
     Path(id): Path<Uuid>,
 ) -> Result<Json<MissionResponse>, (StatusCode, Json<ErrorResponse>)> {
     match service.get_mission(&id).await {
-        Ok(Some(mission)) => Ok(Json(MissionResponse { mission })),
+        Ok(Some(mission)) => Ok(Json(MissionResponse::from(mission))),
         Ok(None) => Err((
             StatusCode::NOT_FOUND,
             Json(ErrorResponse {
@@ -187,6 +336,46 @@ async fn get_mission(
     }
 }
 
+/// Forecast flight time, battery swaps, data volume and processing time
+async fn get_mission_estimate(
+    State(service): State<Arc<MissionPlannerService>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CostEstimate>, (StatusCode, Json<ErrorResponse>)> {
+    let mission = match service.get_mission(&id).await {
+        Ok(Some(mission)) => mission,
+        Ok(None) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "NOT_FOUND".to_string(),
+                    message: "Mission not found".to_string(),
+                }),
+            ));
+        }
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: "GET_FAILED".to_string(),
+                    message: e.to_string(),
+                }),
+            ));
+        }
+    };
+
+    estimate_mission_cost(&mission, &CostEstimateConfig::default())
+        .map(Json)
+        .map_err(|e| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ErrorResponse {
+                    error: "ESTIMATE_FAILED".to_string(),
+                    message: e.to_string(),
+                }),
+            )
+        })
+}
+
 /// Update a mission
 async fn update_mission(
     State(service): State<Arc<MissionPlannerService>>,
@@ -248,7 +437,7 @@ async fn update_mission(
     mission.updated_at = Utc::now();
 
     match service.update_mission(mission).await {
-        Ok(mission) => Ok(Json(MissionResponse { mission })),
+        Ok(mission) => Ok(Json(MissionResponse::from(mission))),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
@@ -427,7 +616,7 @@ async fn optimize_mission(
 
     // Update the mission in the database
     match service.update_mission(mission).await {
-        Ok(mission) => Ok(Json(MissionResponse { mission })),
+        Ok(mission) => Ok(Json(MissionResponse::from(mission))),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
@@ -454,6 +643,48 @@ async fn get_mission_stats(
     }
 }
 
+/// Current conditions, with the provider that supplied them and their age
+async fn get_weather(
+    State(weather): State<Arc<WeatherIntegration>>,
+    Query(query): Query<WeatherQuery>,
+) -> Result<Json<WeatherResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let unavailable = |e: anyhow::Error| {
+        (
+            StatusCode::BAD_GATEWAY,
+            Json(ErrorResponse {
+                error: "WEATHER_UNAVAILABLE".to_string(),
+                message: format!("{e:#}"),
+            }),
+        )
+    };
+
+    let report = weather
+        .current_report(query.lat, query.lon)
+        .await
+        .map_err(unavailable)?;
+    let forecast = match query.hours.filter(|hours| *hours > 0) {
+        Some(hours) => Some(
+            weather
+                .hourly_forecast(query.lat, query.lon, hours)
+                .await
+                .map_err(unavailable)?,
+        ),
+        None => None,
+    };
+    let age = report.source.age(Utc::now());
+
+    Ok(Json(WeatherResponse {
+        current: report.data,
+        provider: report.source.provider,
+        observed_at: report.source.observed_at,
+        age_seconds: age.num_seconds(),
+        stale: age > weather.max_data_age(),
+        failed_providers: report.failed_providers,
+        forecast_provider: forecast.as_ref().map(|forecast| forecast.provider.clone()),
+        hourly: forecast.map(|forecast| forecast.hourly).unwrap_or_default(),
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -524,4 +755,41 @@ mod tests {
 
         assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
     }
+
+    #[tokio::test]
+    async fn test_weather_api_reports_provider_and_age() {
+        let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/manual_weather.json");
+        let weather = WeatherIntegration::with_providers(
+            vec![Box::new(crate::ManualProvider::new(fixture))],
+            std::time::Duration::from_secs(1),
+        );
+        let server = TestServer::new(WeatherApi::router(Arc::new(weather))).unwrap();
+
+        let response = server
+            .get("/weather")
+            .add_query_params([("lat", "11.0"), ("lon", "77.0"), ("hours", "1")])
+            .await;
+
+        assert_eq!(response.status_code(), StatusCode::OK);
+        let body: WeatherResponse = response.json();
+        assert_eq!(body.provider, "manual");
+        assert_eq!(body.forecast_provider.as_deref(), Some("manual"));
+        assert_eq!(body.hourly.len(), 1);
+        assert!(body.stale);
+        assert!(body.age_seconds > 0);
+        assert!(body.age_seconds <= (Utc::now() - body.observed_at).num_seconds());
+
+        let offline = WeatherIntegration::with_providers(
+            vec![Box::new(crate::ManualProvider::new(
+                "/nonexistent/weather.json",
+            ))],
+            std::time::Duration::from_secs(1),
+        );
+        let server = TestServer::new(WeatherApi::router(Arc::new(offline))).unwrap();
+        let response = server
+            .get("/weather")
+            .add_query_params([("lat", "11.0"), ("lon", "77.0")])
+            .await;
+        assert_eq!(response.status_code(), StatusCode::BAD_GATEWAY);
+    }
 }
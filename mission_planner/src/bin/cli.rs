@@ -5,6 +5,7 @@ use serde_json;
 use uuid::Uuid;
 
 use mission_planner::{Mission, MissionPlannerService, Waypoint, WaypointType};
+use shared::{AltitudeReference, GeoPoint};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -226,8 +227,9 @@ async fn main() -> Result<()> {
 
                 let waypoint = Waypoint {
                     id: Uuid::new_v4(),
-                    position: geo::Point::new(lon, lat),
+                    position: GeoPoint::new(lat, lon),
                     altitude_m: altitude,
+                    altitude_reference: AltitudeReference::RelativeToHome,
                     waypoint_type,
                     actions: Vec::new(),
                     arrival_time: None,
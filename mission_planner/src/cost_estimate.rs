@@ -0,0 +1,467 @@
+use crate::mission_optimizer::{
+    mission_action_time_seconds, mission_path_distance_m, validate_budget_config,
+};
+use crate::{Mission, MissionBudgetConfig};
+use geo::{Area, Polygon};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// Same degrees-to-metres approximation flight paths use for distances.
+const METERS_PER_DEGREE: f64 = 111_320.0;
+const SQUARE_METERS_PER_HECTARE: f64 = 10_000.0;
+
+/// Raw bytes one sensor writes per camera trigger.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorDataProfile {
+    pub sensor: String,
+    pub bytes_per_image: u64,
+}
+
+/// Post-processing throughput of one pipeline stage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessingThroughput {
+    pub stage: String,
+    pub minutes_per_hectare: f64,
+}
+
+/// Inputs for a mission cost estimate. Battery draw and speed come from the
+/// mission budget model; each sortie starts on a fresh battery and pays the
+/// base draw again. The per-image sizes and processing throughputs below are
+/// rough defaults and should be replaced with figures measured on the
+/// deployed sensors and pipeline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostEstimateConfig {
+    pub budget: MissionBudgetConfig,
+    /// Distance flown between camera triggers.
+    pub trigger_spacing_m: f32,
+    pub sensors: Vec<SensorDataProfile>,
+    pub processing: Vec<ProcessingThroughput>,
+}
+
+impl Default for CostEstimateConfig {
+    fn default() -> Self {
+        Self {
+            budget: MissionBudgetConfig::default(),
+            trigger_spacing_m: 20.0,
+            sensors: vec![
+                SensorDataProfile {
+                    sensor: "rgb".to_string(),
+                    bytes_per_image: 24_000_000,
+                },
+                SensorDataProfile {
+                    sensor: "multispectral".to_string(),
+                    bytes_per_image: 15_000_000,
+                },
+                SensorDataProfile {
+                    sensor: "thermal".to_string(),
+                    bytes_per_image: 1_300_000,
+                },
+            ],
+            processing: vec![
+                ProcessingThroughput {
+                    stage: "orthomosaic".to_string(),
+                    minutes_per_hectare: 2.0,
+                },
+                ProcessingThroughput {
+                    stage: "vegetation_indices".to_string(),
+                    minutes_per_hectare: 0.25,
+                },
+                ProcessingThroughput {
+                    stage: "report".to_string(),
+                    minutes_per_hectare: 0.05,
+                },
+            ],
+        }
+    }
+}
+
+/// Coverage of a field before any waypoints exist: the field boundary, in
+/// the same degree coordinates missions use, and the survey line spacing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoverageParameters {
+    pub area_of_interest: Polygon<f64>,
+    pub line_spacing_m: f64,
+}
+
+/// What ends a sortie: the usable battery or the maximum flight time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortieLimit {
+    Battery,
+    FlightTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SortieEstimate {
+    pub index: u32,
+    pub distance_m: f32,
+    pub flight_time_seconds: u32,
+    pub battery_draw_percent: f32,
+    pub image_count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorDataVolume {
+    pub sensor: String,
+    pub image_count: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessingEstimate {
+    pub stage: String,
+    pub minutes: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostEstimate {
+    /// `None` for estimates made from coverage parameters.
+    pub mission_id: Option<Uuid>,
+    pub area_hectares: f64,
+    pub total_distance_m: f32,
+    pub total_flight_time_seconds: u32,
+    pub sortie_count: u32,
+    pub battery_swaps: u32,
+    pub limited_by: SortieLimit,
+    pub sorties: Vec<SortieEstimate>,
+    pub image_count: u64,
+    pub data_volume: Vec<SensorDataVolume>,
+    pub total_data_bytes: u64,
+    pub processing: Vec<ProcessingEstimate>,
+    pub total_processing_minutes: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CostEstimateErrorCode {
+    InvalidConfig,
+    InvalidCoverage,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostEstimateError {
+    pub code: CostEstimateErrorCode,
+    pub message: String,
+}
+
+/// The flying a mission or coverage plan needs, before it is cut into
+/// sorties.
+struct FlightWork {
+    distance_m: f32,
+    action_seconds: u32,
+    waypoint_count: usize,
+    area_m2: f64,
+}
+
+/// Estimates flight time, sorties, data volume and processing time for a
+/// planned mission.
+pub fn estimate_mission_cost(
+    mission: &Mission,
+    config: &CostEstimateConfig,
+) -> Result<CostEstimate, CostEstimateError> {
+    let work = FlightWork {
+        distance_m: mission_path_distance_m(mission),
+        action_seconds: mission_action_time_seconds(mission),
+        waypoint_count: mission.waypoints.len(),
+        area_m2: polygon_area_m2(&mission.area_of_interest),
+    };
+    let mut estimate = estimate_cost(&work, config)?;
+    estimate.mission_id = Some(mission.id);
+    Ok(estimate)
+}
+
+/// Estimates a field survey before it is generated, taking the path as
+/// parallel lines `line_spacing_m` apart covering the whole field. Waypoint
+/// and action draw are unknown at this point and left out.
+pub fn estimate_coverage_cost(
+    coverage: &CoverageParameters,
+    config: &CostEstimateConfig,
+) -> Result<CostEstimate, CostEstimateError> {
+    if !coverage.line_spacing_m.is_finite() || coverage.line_spacing_m <= 0.0 {
+        return Err(CostEstimateError::new(
+            CostEstimateErrorCode::InvalidCoverage,
+            "line spacing must be finite and positive",
+        ));
+    }
+    let area_m2 = polygon_area_m2(&coverage.area_of_interest);
+    if !area_m2.is_finite() || area_m2 <= 0.0 {
+        return Err(CostEstimateError::new(
+            CostEstimateErrorCode::InvalidCoverage,
+            "coverage area must be a polygon with a non-zero area",
+        ));
+    }
+    let work = FlightWork {
+        distance_m: (area_m2 / coverage.line_spacing_m) as f32,
+        action_seconds: 0,
+        waypoint_count: 0,
+        area_m2,
+    };
+    estimate_cost(&work, config)
+}
+
+fn estimate_cost(
+    work: &FlightWork,
+    config: &CostEstimateConfig,
+) -> Result<CostEstimate, CostEstimateError> {
+    validate_cost_config(config)?;
+    let budget = config.budget;
+
+    // Draw and time that scale with the work; the base draw is paid per sortie.
+    let distance_m = f64::from(work.distance_m);
+    let work_draw_percent = distance_m * f64::from(budget.flight_draw_percent_per_meter)
+        + f64::from(work.action_seconds) * f64::from(budget.action_draw_percent_per_second)
+        + work.waypoint_count as f64 * f64::from(budget.waypoint_draw_percent);
+    let work_time_seconds =
+        distance_m / f64::from(budget.cruise_speed_ms) + f64::from(work.action_seconds);
+    let sortie_draw_percent = f64::from(
+        budget.battery_capacity_percent - budget.reserve_battery_percent - budget.base_draw_percent,
+    );
+    let sortie_time_seconds = f64::from(budget.max_flight_time_minutes) * 60.0;
+
+    // Share of the total work one full battery covers under each limit.
+    let battery_share = share_per_sortie(sortie_draw_percent, work_draw_percent);
+    let time_share = share_per_sortie(sortie_time_seconds, work_time_seconds);
+    let (share, limited_by) = if time_share < battery_share {
+        (time_share, SortieLimit::FlightTime)
+    } else {
+        (battery_share, SortieLimit::Battery)
+    };
+    let sortie_count = ((1.0 / share).ceil() as u32).max(1);
+
+    let image_count = if distance_m > 0.0 {
+        (distance_m / f64::from(config.trigger_spacing_m)).floor() as u64 + 1
+    } else {
+        0
+    };
+    let sorties = (0..sortie_count)
+        .map(|index| {
+            let start = (f64::from(index) * share).min(1.0);
+            let end = (f64::from(index + 1) * share).min(1.0);
+            let fraction = end - start;
+            let images_before = |at: f64| (at * image_count as f64).round() as u64;
+            SortieEstimate {
+                index: index + 1,
+                distance_m: (distance_m * fraction) as f32,
+                flight_time_seconds: (work_time_seconds * fraction).round() as u32,
+                battery_draw_percent: budget.base_draw_percent
+                    + (work_draw_percent * fraction) as f32,
+                image_count: images_before(end) - images_before(start),
+            }
+        })
+        .collect();
+
+    let data_volume: Vec<SensorDataVolume> = config
+        .sensors
+        .iter()
+        .map(|sensor| SensorDataVolume {
+            sensor: sensor.sensor.clone(),
+            image_count,
+            bytes: image_count * sensor.bytes_per_image,
+        })
+        .collect();
+    let area_hectares = work.area_m2 / SQUARE_METERS_PER_HECTARE;
+    let processing: Vec<ProcessingEstimate> = config
+        .processing
+        .iter()
+        .map(|stage| ProcessingEstimate {
+            stage: stage.stage.clone(),
+            minutes: area_hectares * stage.minutes_per_hectare,
+        })
+        .collect();
+
+    Ok(CostEstimate {
+        mission_id: None,
+        area_hectares,
+        total_distance_m: work.distance_m,
+        total_flight_time_seconds: (work.distance_m / budget.cruise_speed_ms).ceil() as u32
+            + work.action_seconds,
+        sortie_count,
+        battery_swaps: sortie_count - 1,
+        limited_by,
+        sorties,
+        image_count,
+        total_data_bytes: data_volume.iter().map(|volume| volume.bytes).sum(),
+        data_volume,
+        total_processing_minutes: processing.iter().map(|stage| stage.minutes).sum(),
+        processing,
+    })
+}
+
+fn share_per_sortie(per_sortie: f64, total: f64) -> f64 {
+    if total <= 0.0 {
+        1.0
+    } else {
+        (per_sortie / total).min(1.0)
+    }
+}
+
+fn validate_cost_config(config: &CostEstimateConfig) -> Result<(), CostEstimateError> {
+    validate_budget_config(config.budget).map_err(|error| {
+        CostEstimateError::new(CostEstimateErrorCode::InvalidConfig, error.message)
+    })?;
+    let budget = config.budget;
+    if budget.battery_capacity_percent - budget.reserve_battery_percent <= budget.base_draw_percent
+    {
+        return Err(CostEstimateError::new(
+            CostEstimateErrorCode::InvalidConfig,
+            "base draw leaves no usable battery for flying",
+        ));
+    }
+    if budget.max_flight_time_minutes == 0 {
+        return Err(CostEstimateError::new(
+            CostEstimateErrorCode::InvalidConfig,
+            "maximum flight time must be positive",
+        ));
+    }
+    if !config.trigger_spacing_m.is_finite() || config.trigger_spacing_m <= 0.0 {
+        return Err(CostEstimateError::new(
+            CostEstimateErrorCode::InvalidConfig,
+            "trigger spacing must be finite and positive",
+        ));
+    }
+    if let Some(stage) = config
+        .processing
+        .iter()
+        .find(|stage| !stage.minutes_per_hectare.is_finite() || stage.minutes_per_hectare < 0.0)
+    {
+        return Err(CostEstimateError::new(
+            CostEstimateErrorCode::InvalidConfig,
+            format!(
+                "processing stage '{}' needs a finite, non-negative throughput",
+                stage.stage
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn polygon_area_m2(polygon: &Polygon<f64>) -> f64 {
+    polygon.unsigned_area() * METERS_PER_DEGREE * METERS_PER_DEGREE
+}
+
+impl CostEstimateError {
+    fn new(code: CostEstimateErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for CostEstimateError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for CostEstimateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::polygon;
+
+    /// A 1000 m wide field `depth_m` deep, in degrees.
+    fn rectangular_field(depth_m: f64) -> CoverageParameters {
+        let width = 1_000.0 / METERS_PER_DEGREE;
+        let depth = depth_m / METERS_PER_DEGREE;
+        CoverageParameters {
+            area_of_interest: polygon![
+                (x: 0.0, y: 0.0),
+                (x: width, y: 0.0),
+                (x: width, y: depth),
+                (x: 0.0, y: depth),
+                (x: 0.0, y: 0.0),
+            ],
+            line_spacing_m: 5.0,
+        }
+    }
+
+    /// One battery flies exactly 50 km: 100% less 20% reserve and 30% base
+    /// draw, at 0.001% per metre. Flight time never binds.
+    fn battery_bound_config() -> CostEstimateConfig {
+        CostEstimateConfig {
+            budget: MissionBudgetConfig {
+                cruise_speed_ms: 10.0,
+                max_flight_time_minutes: 600,
+                battery_capacity_percent: 100.0,
+                reserve_battery_percent: 20.0,
+                base_draw_percent: 30.0,
+                flight_draw_percent_per_meter: 0.001,
+                action_draw_percent_per_second: 0.0,
+                waypoint_draw_percent: 0.0,
+            },
+            trigger_spacing_m: 50.0,
+            sensors: vec![
+                SensorDataProfile {
+                    sensor: "rgb".to_string(),
+                    bytes_per_image: 20_000_000,
+                },
+                SensorDataProfile {
+                    sensor: "thermal".to_string(),
+                    bytes_per_image: 1_000_000,
+                },
+            ],
+            processing: vec![ProcessingThroughput {
+                stage: "orthomosaic".to_string(),
+                minutes_per_hectare: 2.0,
+            }],
+        }
+    }
+
+    #[test]
+    fn a_field_just_under_one_battery_flies_in_one_sortie() {
+        // 24.5 ha at 5 m line spacing is 49 km of survey lines.
+        let estimate =
+            estimate_coverage_cost(&rectangular_field(245.0), &battery_bound_config()).unwrap();
+
+        assert!((estimate.area_hectares - 24.5).abs() < 1e-6);
+        assert!((estimate.total_distance_m - 49_000.0).abs() < 1.0);
+        assert_eq!(estimate.total_flight_time_seconds, 4_900);
+        assert_eq!(estimate.sortie_count, 1);
+        assert_eq!(estimate.battery_swaps, 0);
+        assert_eq!(estimate.limited_by, SortieLimit::Battery);
+        assert!((estimate.sorties[0].battery_draw_percent - 79.0).abs() < 0.01);
+
+        // A trigger every 50 m, including one at the start.
+        assert_eq!(estimate.image_count, 981);
+        assert_eq!(estimate.sorties[0].image_count, 981);
+        assert_eq!(estimate.data_volume[0].bytes, 19_620_000_000);
+        assert_eq!(estimate.data_volume[1].bytes, 981_000_000);
+        assert_eq!(estimate.total_data_bytes, 20_601_000_000);
+        assert!((estimate.total_processing_minutes - 49.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn a_field_just_over_one_battery_needs_a_second_sortie() {
+        // 25.5 ha is 51 km, so the second battery flies the last kilometre.
+        let estimate =
+            estimate_coverage_cost(&rectangular_field(255.0), &battery_bound_config()).unwrap();
+
+        assert_eq!(estimate.sortie_count, 2);
+        assert_eq!(estimate.battery_swaps, 1);
+        let [first, second] = estimate.sorties.as_slice() else {
+            panic!("expected two sorties, got {:?}", estimate.sorties);
+        };
+        assert!((first.distance_m - 50_000.0).abs() < 5.0);
+        assert!((first.battery_draw_percent - 80.0).abs() < 0.01);
+        assert!((second.distance_m - 1_000.0).abs() < 5.0);
+        assert!((second.battery_draw_percent - 31.0).abs() < 0.01);
+        assert_eq!(estimate.image_count, 1_021);
+        assert_eq!(first.image_count + second.image_count, 1_021);
+        assert_eq!(estimate.total_data_bytes, 1_021 * 21_000_000);
+    }
+
+    #[test]
+    fn flight_time_can_bound_sorties_before_the_battery() {
+        let mut config = battery_bound_config();
+        config.budget.max_flight_time_minutes = 25;
+
+        // 49 km at 10 m/s is 4900 s, four 1500 s sorties.
+        let estimate = estimate_coverage_cost(&rectangular_field(245.0), &config).unwrap();
+
+        assert_eq!(estimate.limited_by, SortieLimit::FlightTime);
+        assert_eq!(estimate.sortie_count, 4);
+        assert_eq!(estimate.sorties[0].flight_time_seconds, 1_500);
+        assert_eq!(estimate.sorties[3].flight_time_seconds, 400);
+    }
+}
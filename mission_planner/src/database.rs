@@ -2,6 +2,7 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json;
+use shared::geospatial::geo_point_xy;
 use sqlx::{postgres::PgRow, PgPool, Postgres, QueryBuilder, Row};
 use std::str::FromStr;
 use uuid::Uuid;
@@ -189,6 +190,12 @@ impl DatabaseService {
         .execute(&self.pool)
         .await?;
 
+        sqlx::query(
+            "ALTER TABLE waypoints ADD COLUMN IF NOT EXISTS altitude_reference TEXT NOT NULL DEFAULT '\"relative_to_home\"';",
+        )
+        .execute(&self.pool)
+        .await?;
+
         // Create flight_paths table
         sqlx::query(
             r#"
@@ -285,13 +292,17 @@ impl DatabaseService {
                 r#"
                 INSERT INTO waypoints (
                     id, mission_id, position, altitude_m, waypoint_type,
-                    actions, arrival_time, speed_ms, heading_degrees, sequence_order
-                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                    actions, arrival_time, speed_ms, heading_degrees, sequence_order,
+                    altitude_reference
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
                 "#,
             )
             .bind(waypoint.id)
             .bind(mission.id)
-            .bind(serde_json::to_value(&waypoint.position)?)
+            .bind(geo_point_xy::serialize(
+                &waypoint.position,
+                serde_json::value::Serializer,
+            )?)
             .bind(waypoint.altitude_m)
             .bind(serde_json::to_string(&waypoint.waypoint_type)?)
             .bind(serde_json::to_value(&waypoint.actions)?)
@@ -299,6 +310,7 @@ impl DatabaseService {
             .bind(waypoint.speed_ms)
             .bind(waypoint.heading_degrees)
             .bind(index as i32)
+            .bind(serde_json::to_string(&waypoint.altitude_reference)?)
             .execute(&mut *tx)
             .await?;
         }
@@ -342,8 +354,13 @@ impl DatabaseService {
             .map(|row| {
                 Ok(Waypoint {
                     id: row.get("id"),
-                    position: serde_json::from_value(row.get("position"))?,
+                    position: geo_point_xy::deserialize(
+                        row.get::<serde_json::Value, _>("position"),
+                    )?,
                     altitude_m: row.get("altitude_m"),
+                    altitude_reference: serde_json::from_str(
+                        &row.get::<String, _>("altitude_reference"),
+                    )?,
                     waypoint_type: serde_json::from_str(&row.get::<String, _>("waypoint_type"))?,
                     actions: serde_json::from_value(row.get("actions"))?,
                     arrival_time: row.get("arrival_time"),
@@ -369,6 +386,7 @@ impl DatabaseService {
                     estimated_duration_seconds: row.get::<i32, _>("estimated_duration_seconds")
                         as u32,
                     path_type: serde_json::from_value(row.get("path_type"))?,
+                    max_altitude_m: None,
                 })
             })
             .collect();
@@ -465,13 +483,17 @@ impl DatabaseService {
                 r#"
                 INSERT INTO waypoints (
                     id, mission_id, position, altitude_m, waypoint_type,
-                    actions, arrival_time, speed_ms, heading_degrees, sequence_order
-                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                    actions, arrival_time, speed_ms, heading_degrees, sequence_order,
+                    altitude_reference
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
                 "#,
             )
             .bind(waypoint.id)
             .bind(updated.id)
-            .bind(serde_json::to_value(&waypoint.position)?)
+            .bind(geo_point_xy::serialize(
+                &waypoint.position,
+                serde_json::value::Serializer,
+            )?)
             .bind(waypoint.altitude_m)
             .bind(serde_json::to_string(&waypoint.waypoint_type)?)
             .bind(serde_json::to_value(&waypoint.actions)?)
@@ -479,6 +501,7 @@ impl DatabaseService {
             .bind(waypoint.speed_ms)
             .bind(waypoint.heading_degrees)
             .bind(index as i32)
+            .bind(serde_json::to_string(&waypoint.altitude_reference)?)
             .execute(&mut *tx)
             .await?;
         }
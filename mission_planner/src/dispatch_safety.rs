@@ -200,8 +200,8 @@ pub fn evaluate_dispatch_safety_with_constraints(
     for zone in no_fly_zones {
         for (leg_index, pair) in mission.waypoints.windows(2).enumerate() {
             let leg = LineString::from(vec![
-                (pair[0].position.x(), pair[0].position.y()),
-                (pair[1].position.x(), pair[1].position.y()),
+                pair[0].position.to_lon_lat_tuple(),
+                pair[1].position.to_lon_lat_tuple(),
             ]);
             if zone.boundary.intersects(&leg) {
                 violations.push(SafetyViolation {
@@ -221,8 +221,8 @@ pub fn evaluate_dispatch_safety_with_constraints(
     for constraint in airspace_constraints {
         for (leg_index, pair) in mission.waypoints.windows(2).enumerate() {
             let leg = LineString::from(vec![
-                (pair[0].position.x(), pair[0].position.y()),
-                (pair[1].position.x(), pair[1].position.y()),
+                pair[0].position.to_lon_lat_tuple(),
+                pair[1].position.to_lon_lat_tuple(),
             ]);
             if constraint.boundary.intersects(&leg) {
                 violations.push(SafetyViolation {
@@ -1,6 +1,8 @@
+use crate::terrain::Dem;
 use crate::Waypoint;
 use geo::Point;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,12 +13,19 @@ pub struct FlightPath {
     pub total_distance_m: f32,
     pub estimated_duration_seconds: u32,
     pub path_type: PathType,
+    /// Ceiling that altitude adjustments such as terrain following clamp to.
+    #[serde(default)]
+    pub max_altitude_m: Option<f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PathSegment {
     pub start_waypoint_id: Uuid,
     pub end_waypoint_id: Uuid,
+    #[serde(default)]
+    pub start_position: Option<Point<f64>>,
+    #[serde(default)]
+    pub end_position: Option<Point<f64>>,
     pub distance_m: f32,
     pub bearing_degrees: f32,
     pub estimated_time_seconds: u32,
@@ -94,6 +103,7 @@ impl FlightPath {
             total_distance_m: 0.0,
             estimated_duration_seconds: 0,
             path_type,
+            max_altitude_m: None,
         }
     }
 
@@ -110,8 +120,10 @@ impl FlightPath {
             let start = &window[0];
             let end = &window[1];
 
-            let distance = calculate_distance(&start.position, &end.position);
-            let bearing = calculate_bearing(&start.position, &end.position);
+            let start_position = Point::from(start.position);
+            let end_position = Point::from(end.position);
+            let distance = calculate_distance(&start_position, &end_position);
+            let bearing = calculate_bearing(&start_position, &end_position);
 
             // Simple time estimation based on average speed
             let avg_speed = 10.0; // m/s
@@ -120,6 +132,8 @@ impl FlightPath {
             let segment = PathSegment {
                 start_waypoint_id: start.id,
                 end_waypoint_id: end.id,
+                start_position: Some(start_position),
+                end_position: Some(end_position),
                 distance_m: distance,
                 bearing_degrees: bearing,
                 estimated_time_seconds: time,
@@ -140,6 +154,55 @@ impl FlightPath {
 
         path
     }
+
+    /// Re-targets every waypoint to `agl_m` above the DEM elevation under it,
+    /// turning a constant-altitude path into one that follows the terrain.
+    /// Results are clamped to `max_altitude_m`; waypoints off the DEM, or
+    /// without a recorded position, keep their altitude.
+    pub fn apply_terrain_following(&mut self, dem: &Dem, agl_m: f32) {
+        let max_altitude_m = self.max_altitude_m;
+        let mut warned = HashSet::new();
+        let mut follow = |waypoint_id: Uuid, position: Option<Point<f64>>, altitude_m: &mut f32| {
+            let Some(ground_m) = position.and_then(|position| dem.elevation_at(&position)) else {
+                if warned.insert(waypoint_id) {
+                    tracing::warn!(
+                        "Waypoint {} is outside the DEM; keeping altitude {:.1} m",
+                        waypoint_id,
+                        altitude_m
+                    );
+                }
+                return;
+            };
+            let target_m = ground_m + agl_m;
+            *altitude_m = match max_altitude_m {
+                Some(ceiling_m) if target_m > ceiling_m => {
+                    if warned.insert(waypoint_id) {
+                        tracing::warn!(
+                            "Waypoint {} terrain-following altitude {:.1} m clamped to {:.1} m",
+                            waypoint_id,
+                            target_m,
+                            ceiling_m
+                        );
+                    }
+                    ceiling_m
+                }
+                _ => target_m,
+            };
+        };
+
+        for segment in &mut self.segments {
+            follow(
+                segment.start_waypoint_id,
+                segment.start_position,
+                &mut segment.altitude_profile.start_altitude_m,
+            );
+            follow(
+                segment.end_waypoint_id,
+                segment.end_position,
+                &mut segment.altitude_profile.end_altitude_m,
+            );
+        }
+    }
 }
 
 fn calculate_distance(start: &Point<f64>, end: &Point<f64>) -> f32 {
@@ -154,3 +217,81 @@ fn calculate_bearing(start: &Point<f64>, end: &Point<f64>) -> f32 {
     let dy = end.y() - start.y();
     dy.atan2(dx).to_degrees() as f32
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WaypointType;
+
+    fn waypoint(x: f64, altitude_m: f32) -> Waypoint {
+        Waypoint {
+            id: Uuid::new_v4(),
+            position: Point::new(x, 0.5).into(),
+            altitude_m,
+            altitude_reference: shared::AltitudeReference::RelativeToHome,
+            waypoint_type: WaypointType::Survey,
+            actions: vec![],
+            arrival_time: None,
+            speed_ms: None,
+            heading_degrees: None,
+        }
+    }
+
+    /// Ground rises 20 m per unit of x across a 0..4 by 0..1 extent.
+    fn sloped_dem() -> Dem {
+        let elevations = (0..2)
+            .flat_map(|_| (0..5).map(|column| 300.0 + 20.0 * column as f32))
+            .collect();
+        Dem::new(Point::new(0.0, 0.0), 1.0, 5, 2, elevations).unwrap()
+    }
+
+    #[test]
+    fn terrain_following_tracks_a_sloped_dem() {
+        let waypoints = [
+            waypoint(0.0, 350.0),
+            waypoint(1.5, 350.0),
+            waypoint(4.0, 350.0),
+            waypoint(6.0, 350.0),
+        ];
+        let mut path = FlightPath::from_waypoints(
+            "Hillside".to_string(),
+            &waypoints,
+            PathType::Survey {
+                pattern: SurveyPattern::Lawnmower,
+                overlap_percent: 70.0,
+            },
+        );
+
+        path.apply_terrain_following(&sloped_dem(), 40.0);
+
+        let altitudes: Vec<(f32, f32)> = path
+            .segments
+            .iter()
+            .map(|segment| {
+                (
+                    segment.altitude_profile.start_altitude_m,
+                    segment.altitude_profile.end_altitude_m,
+                )
+            })
+            .collect();
+        // The last waypoint is beyond the DEM and keeps its planned altitude.
+        assert_eq!(
+            altitudes,
+            vec![(340.0, 370.0), (370.0, 420.0), (420.0, 350.0)]
+        );
+    }
+
+    #[test]
+    fn terrain_following_clamps_to_the_path_ceiling() {
+        let waypoints = [waypoint(0.0, 100.0), waypoint(4.0, 100.0)];
+        let mut path =
+            FlightPath::from_waypoints("Ridge".to_string(), &waypoints, PathType::Direct);
+        path.max_altitude_m = Some(400.0);
+
+        path.apply_terrain_following(&sloped_dem(), 40.0);
+
+        let profile = &path.segments[0].altitude_profile;
+        assert_eq!(profile.start_altitude_m, 340.0);
+        assert_eq!(profile.end_altitude_m, 400.0);
+    }
+}
@@ -2,14 +2,17 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use geo::{Point, Polygon};
 use serde::{Deserialize, Serialize};
+use shared::AltitudeReference;
 use std::{collections::HashMap, fmt, str::FromStr};
 use uuid::Uuid;
 
 pub mod abort_recovery;
 pub mod adaptive_replan;
+pub mod altitude;
 pub mod api;
 pub mod automated_failsafe;
 pub mod autonomous_execution;
+pub mod cost_estimate;
 pub mod database;
 pub mod dispatch_safety;
 pub mod flight_path;
@@ -18,9 +21,12 @@ pub mod mavlink_integration;
 pub mod mission_audit;
 pub mod mission_export;
 pub mod mission_optimizer;
+pub mod mission_sync_client;
 pub mod preflight_checklist;
+pub mod session_track;
 pub mod survey_template;
 pub mod telemetry;
+pub mod terrain;
 pub mod waypoint;
 pub mod weather_integration;
 pub mod websocket_handler;
@@ -35,7 +41,8 @@ pub use adaptive_replan::{
     AdaptiveReplanErrorCode, AdaptiveReplanOutcome, AdaptiveReplanProposal, AdaptiveReplanRequest,
     AdaptiveReplanStatus,
 };
-pub use api::MissionApi;
+pub use altitude::AltitudeReferenceError;
+pub use api::{MissionApi, WeatherApi};
 pub use automated_failsafe::{
     assert_failsafe_ready_for_arming, evaluate_automated_failsafe, AutomatedFailsafeAuditEvent,
     AutomatedFailsafeAuditEventKind, AutomatedFailsafeConfig, AutomatedFailsafeError,
@@ -46,6 +53,11 @@ pub use autonomous_execution::{
     AutonomousExecutionErrorCode, AutonomousExecutionOutcome, AutonomousExecutionPlan,
     AutonomousOperatorApproval, AutonomousRuntimeMode,
 };
+pub use cost_estimate::{
+    estimate_coverage_cost, estimate_mission_cost, CostEstimate, CostEstimateConfig,
+    CostEstimateError, CostEstimateErrorCode, CoverageParameters, ProcessingEstimate,
+    ProcessingThroughput, SensorDataProfile, SensorDataVolume, SortieEstimate, SortieLimit,
+};
 pub use database::{DatabaseService, MissionStats};
 pub use dispatch_safety::{
     evaluate_dispatch_safety, evaluate_dispatch_safety_with_constraints, AirspaceConstraint,
@@ -73,11 +85,16 @@ pub use mission_optimizer::{
     assert_mission_budget_allows_arming, evaluate_mission_budget, MissionBudgetConfig,
     MissionBudgetError, MissionBudgetErrorCode, MissionBudgetReport, MissionOptimizer,
 };
+pub use mission_sync_client::{DeployRoute, MissionControlLink, DEFAULT_SYNC_RECONNECTS};
 pub use preflight_checklist::{
     evaluate_preflight_checklist, GpsFixStatus, GpsFixType, PreflightArmError, PreflightCheckName,
     PreflightCheckResult, PreflightCheckStatus, PreflightChecklistConfig,
     PreflightChecklistContext, PreflightChecklistReport,
 };
+pub use session_track::{
+    max_deviation_m, mission_draft_from_session, simplify_track, SessionTrackError,
+    SessionTrackErrorCode, SessionTrackPoint, SessionTrackSource, TrackSimplificationConfig,
+};
 pub use survey_template::{
     generate_survey_template, validate_plan_bounds, PlanBoundsConfig, PlanBoundsError,
     PlanBoundsIssue, PlanBoundsIssueCode, SurveyTemplateConfig, SurveyTemplateError,
@@ -90,11 +107,17 @@ pub use telemetry::{
     TelemetryGapEvent, TelemetryHistory, TelemetryLinkState, TelemetryRecordError,
     TelemetryRecordErrorCode,
 };
+pub use terrain::{Dem, DemError};
 pub use waypoint::{
-    validate_waypoint_sanity, Action, Waypoint, WaypointType, WaypointValidationCode,
-    WaypointValidationConfig, WaypointValidationError, WaypointValidationIssue,
+    validate_waypoint_actions, validate_waypoint_sanity, Action, Waypoint, WaypointType,
+    WaypointValidationCode, WaypointValidationConfig, WaypointValidationError,
+    WaypointValidationIssue,
+};
+pub use weather_integration::{
+    AlertSeverity, FlightConditionResult, HourlyForecast, ManualProvider, ManualWeatherFile,
+    ProviderFailure, WeatherAlert, WeatherData, WeatherIntegration, WeatherIntegrationConfig,
+    WeatherObservation, WeatherProvider, WeatherProviderConfig, WeatherReport, WeatherSource,
 };
-pub use weather_integration::{AlertSeverity, FlightConditionResult, WeatherAlert, WeatherData};
 
 /// Core mission planning structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -357,6 +380,36 @@ impl Mission {
         Self::new_linked(name, description, area, MissionLinkage::unassigned())
     }
 
+    /// GeoJSON FeatureCollection of the plan (area of interest, route and
+    /// waypoints) for web maps; coordinates follow the spec's `[lon, lat]`.
+    pub fn to_geojson(&self) -> serde_json::Value {
+        mission_export::mission_plan_geojson(self)
+    }
+
+    /// Altitude reference shared by every waypoint; errors when the plan mixes
+    /// references.
+    pub fn altitude_reference(
+        &self,
+    ) -> std::result::Result<Option<AltitudeReference>, AltitudeReferenceError> {
+        altitude::common_altitude_reference(&self.waypoints)
+    }
+
+    /// Converts all waypoint altitudes to `target` so the plan uses one
+    /// reference; see [`altitude::normalize_altitude_references`].
+    pub fn normalize_altitude_references(
+        &mut self,
+        target: AltitudeReference,
+        home_elevation_msl_m: Option<f64>,
+        dem: Option<&Dem>,
+    ) -> std::result::Result<(), AltitudeReferenceError> {
+        altitude::normalize_altitude_references(
+            &mut self.waypoints,
+            target,
+            home_elevation_msl_m,
+            dem,
+        )
+    }
+
     pub fn new_linked(
         name: String,
         description: String,
@@ -508,6 +561,7 @@ impl Default for WeatherConstraints {
 /// Mission planning service with PostgreSQL backend
 pub struct MissionPlannerService {
     db: DatabaseService,
+    session_tracks: Option<SessionTrackSource>,
 }
 
 impl MissionPlannerService {
@@ -515,16 +569,30 @@ impl MissionPlannerService {
     pub async fn new(database_url: &str) -> Result<Self> {
         let db = DatabaseService::connect(database_url).await?;
         db.initialize().await?;
-        Ok(Self { db })
+        Ok(Self::with_database(db))
     }
 
     /// Create new service with existing database service
     pub fn with_database(db: DatabaseService) -> Self {
-        Self { db }
+        Self {
+            db,
+            session_tracks: None,
+        }
+    }
+
+    /// Where draft missions from recorded sessions get their tracks.
+    pub fn with_session_tracks(mut self, session_tracks: SessionTrackSource) -> Self {
+        self.session_tracks = Some(session_tracks);
+        self
+    }
+
+    pub fn session_tracks(&self) -> Option<&SessionTrackSource> {
+        self.session_tracks.as_ref()
     }
 
     /// Create a new mission
     pub async fn create_mission(&self, mission: Mission) -> Result<Uuid> {
+        validate_waypoint_actions(&mission.waypoints)?;
         self.db.create_mission(&mission).await
     }
 
@@ -793,6 +861,67 @@ mod tests {
         assert_eq!(mission.status, MissionStatus::Draft);
     }
 
+    #[test]
+    fn test_out_of_range_actions_are_rejected_per_waypoint() {
+        let takeoff = Waypoint::new(geo::point!(x: 0.0, y: 0.0), 10.0, WaypointType::Takeoff)
+            .with_action(Action::SetGimbal {
+                pitch_degrees: -90.0,
+                yaw_degrees: 0.0,
+            });
+        let survey = Waypoint::new(geo::point!(x: 5.0, y: 5.0), 20.0, WaypointType::Survey)
+            .with_action(Action::SetGimbal {
+                pitch_degrees: -120.0,
+                yaw_degrees: 0.0,
+            })
+            .with_action(Action::Hover {
+                duration_seconds: 5,
+            })
+            .with_action(Action::SetSpeed { speed_ms: -2.0 });
+        let landing = Waypoint::new(geo::point!(x: 10.0, y: 10.0), 0.0, WaypointType::Landing)
+            .with_action(Action::SetSpeed { speed_ms: 3.0 });
+        let waypoints = vec![takeoff, survey, landing];
+
+        let error = validate_waypoint_actions(&waypoints).expect_err("invalid actions must fail");
+        let rejected: Vec<_> = error
+            .issues
+            .iter()
+            .map(|issue| (issue.waypoint_index, issue.code, issue.message.as_str()))
+            .collect();
+        assert_eq!(rejected.len(), 2);
+        assert_eq!(rejected[0].0, Some(1));
+        assert_eq!(rejected[0].1, WaypointValidationCode::InvalidAction);
+        assert!(rejected[0].2.starts_with("action 0: gimbal pitch"));
+        assert_eq!(rejected[1].0, Some(1));
+        assert!(rejected[1].2.starts_with("action 2: speed -2"));
+
+        let mut mission = Mission::new(
+            "Gimbal Mission".to_string(),
+            "Bad actions".to_string(),
+            polygon![
+                (x: 0.0, y: 0.0),
+                (x: 10.0, y: 0.0),
+                (x: 10.0, y: 10.0),
+                (x: 0.0, y: 0.0),
+            ],
+        );
+        mission.waypoints = waypoints;
+        match mission.validate() {
+            Err(MissionValidationError::Waypoint(validation)) => {
+                assert_eq!(
+                    validation.primary_code(),
+                    Some(WaypointValidationCode::InvalidAction)
+                );
+            }
+            other => panic!("expected waypoint validation error, got {other:?}"),
+        }
+        assert!(Action::SetGimbal {
+            pitch_degrees: 45.0,
+            yaw_degrees: 181.0,
+        }
+        .validate()
+        .is_err());
+    }
+
     #[tokio::test]
     #[ignore] // Requires PostgreSQL database
     async fn test_mission_service() {
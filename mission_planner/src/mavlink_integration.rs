@@ -1,7 +1,10 @@
 use crate::{Mission, WaypointType};
 use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
+use mavlink::common::{MavCmd, MavFrame, MavMissionType, MISSION_ITEM_INT_DATA};
+use num_traits::FromPrimitive;
 use serde::{Deserialize, Serialize};
+use shared::AltitudeReference;
 use std::{collections::HashMap, fmt};
 use uuid::Uuid;
 
@@ -23,6 +26,41 @@ pub struct MAVLinkMissionItem {
     pub mission_type: u8,
 }
 
+impl MAVLinkMissionItem {
+    /// Wire form for the MAVLink mission protocol, with coordinates of
+    /// navigation and fence items scaled to degrees * 1e7. `None` when the
+    /// command, frame or mission type is not a MAVLink value.
+    pub fn to_mission_item_int(
+        &self,
+        target_system: u8,
+        target_component: u8,
+    ) -> Option<MISSION_ITEM_INT_DATA> {
+        let scale = |degrees: f32| (f64::from(degrees) * 1e7).round() as i32;
+        let (x, y) = if self.frame == MAV_FRAME_MISSION {
+            (self.x as i32, self.y as i32)
+        } else {
+            (scale(self.x), scale(self.y))
+        };
+        Some(MISSION_ITEM_INT_DATA {
+            param1: self.param1,
+            param2: self.param2,
+            param3: self.param3,
+            param4: self.param4,
+            x,
+            y,
+            z: self.z,
+            seq: self.seq,
+            command: MavCmd::from_u16(self.command)?,
+            target_system,
+            target_component,
+            frame: MavFrame::from_u8(self.frame)?,
+            current: self.current,
+            autocontinue: self.autocontinue,
+            mission_type: MavMissionType::from_u8(self.mission_type)?,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MAVLinkMission {
     pub target_system: u8,
@@ -39,10 +77,78 @@ pub const MAV_CMD_DO_SET_SERVO: u16 = 183;
 pub const MAV_CMD_IMAGE_START_CAPTURE: u16 = 2000;
 pub const MAV_CMD_IMAGE_STOP_CAPTURE: u16 = 2001;
 pub const MAV_CMD_DO_DIGICAM_CONTROL: u16 = 203;
+pub const MAV_CMD_DO_MOUNT_CONTROL: u16 = 205;
+pub const MAV_CMD_NAV_FENCE_POLYGON_VERTEX_INCLUSION: u16 = 5001;
 
 // MAVLink frames
+pub const MAV_FRAME_GLOBAL: u8 = 0;
 pub const MAV_FRAME_GLOBAL_RELATIVE_ALT: u8 = 3;
 pub const MAV_FRAME_MISSION: u8 = 2;
+pub const MAV_FRAME_GLOBAL_TERRAIN_ALT: u8 = 10;
+
+// MAVLink mission types
+pub const MAV_MISSION_TYPE_MISSION: u8 = 0;
+pub const MAV_MISSION_TYPE_FENCE: u8 = 1;
+
+/// Most vertices accepted for one inclusion fence, within what common
+/// autopilots can store.
+pub const MAX_GEOFENCE_VERTICES: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeofenceError {
+    /// The last boundary point must repeat the first.
+    NotClosed,
+    TooFewVertices {
+        count: usize,
+    },
+    TooManyVertices {
+        count: usize,
+        max: usize,
+    },
+    InvalidVertex {
+        index: usize,
+    },
+    InvalidMaxAltitude {
+        max_alt: f32,
+    },
+}
+
+impl fmt::Display for GeofenceError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotClosed => write!(
+                formatter,
+                "geofence boundary is not closed; repeat the first vertex at the end"
+            ),
+            Self::TooFewVertices { count } => {
+                write!(formatter, "geofence needs at least 3 vertices, got {count}")
+            }
+            Self::TooManyVertices { count, max } => write!(
+                formatter,
+                "geofence has {count} vertices but at most {max} can be uploaded"
+            ),
+            Self::InvalidVertex { index } => write!(
+                formatter,
+                "geofence vertex {index} is not a valid latitude/longitude"
+            ),
+            Self::InvalidMaxAltitude { max_alt } => write!(
+                formatter,
+                "geofence altitude ceiling {max_alt} m must be positive"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GeofenceError {}
+
+/// Navigation frame whose `z` matches an altitude measured from `reference`.
+pub fn mav_frame_for(reference: AltitudeReference) -> u8 {
+    match reference {
+        AltitudeReference::Msl => MAV_FRAME_GLOBAL,
+        AltitudeReference::RelativeToHome => MAV_FRAME_GLOBAL_RELATIVE_ALT,
+        AltitudeReference::Agl => MAV_FRAME_GLOBAL_TERRAIN_ALT,
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct MAVLinkAckConfig {
@@ -223,7 +329,10 @@ impl std::error::Error for MAVLinkCommandAckError {}
 pub struct MAVLinkConverter;
 
 impl MAVLinkConverter {
+    /// Rejects plans that mix altitude references; normalize them with
+    /// [`Mission::normalize_altitude_references`] first.
     pub fn mission_to_mavlink(mission: &Mission) -> Result<MAVLinkMission> {
+        mission.altitude_reference()?;
         let mut items = Vec::new();
         let mut seq = 0u16;
 
@@ -231,7 +340,7 @@ impl MAVLinkConverter {
         if let Some(first_waypoint) = mission.waypoints.first() {
             items.push(MAVLinkMissionItem {
                 seq,
-                frame: MAV_FRAME_GLOBAL_RELATIVE_ALT,
+                frame: mav_frame_for(first_waypoint.altitude_reference),
                 command: MAV_CMD_NAV_TAKEOFF,
                 current: if seq == 0 { 1 } else { 0 },
                 autocontinue: 1,
@@ -239,8 +348,8 @@ impl MAVLinkConverter {
                 param2: 0.0, // Empty
                 param3: 0.0, // Empty
                 param4: 0.0, // Yaw angle
-                x: first_waypoint.position.x() as f32,
-                y: first_waypoint.position.y() as f32,
+                x: first_waypoint.position.lat as f32,
+                y: first_waypoint.position.lon as f32,
                 z: first_waypoint.altitude_m,
                 mission_type: 0,
             });
@@ -257,7 +366,7 @@ impl MAVLinkConverter {
 
             items.push(MAVLinkMissionItem {
                 seq,
-                frame: MAV_FRAME_GLOBAL_RELATIVE_ALT,
+                frame: mav_frame_for(waypoint.altitude_reference),
                 command,
                 current: 0,
                 autocontinue: 1,
@@ -265,8 +374,8 @@ impl MAVLinkConverter {
                 param2: 3.0, // Accept radius (meters)
                 param3: 0.0, // Pass radius
                 param4: 0.0, // Yaw
-                x: waypoint.position.x() as f32,
-                y: waypoint.position.y() as f32,
+                x: waypoint.position.lat as f32,
+                y: waypoint.position.lon as f32,
                 z: waypoint.altitude_m,
                 mission_type: 0,
             });
@@ -315,6 +424,26 @@ impl MAVLinkConverter {
                             mission_type: 0,
                         });
                     }
+                    crate::waypoint::Action::SetGimbal {
+                        pitch_degrees,
+                        yaw_degrees,
+                    } => {
+                        items.push(MAVLinkMissionItem {
+                            seq,
+                            frame: MAV_FRAME_MISSION,
+                            command: MAV_CMD_DO_MOUNT_CONTROL,
+                            current: 0,
+                            autocontinue: 1,
+                            param1: *pitch_degrees,
+                            param2: 0.0, // Roll
+                            param3: *yaw_degrees,
+                            param4: 0.0,
+                            x: 0.0,
+                            y: 0.0,
+                            z: 2.0, // MAV_MOUNT_MODE_MAVLINK_TARGETING
+                            mission_type: 0,
+                        });
+                    }
                     _ => {
                         // Skip unsupported actions for now
                         seq -= 1; // Don't increment seq for unsupported actions
@@ -331,7 +460,7 @@ impl MAVLinkConverter {
             if let Some(last_waypoint) = mission.waypoints.last() {
                 items.push(MAVLinkMissionItem {
                     seq,
-                    frame: MAV_FRAME_GLOBAL_RELATIVE_ALT,
+                    frame: mav_frame_for(last_waypoint.altitude_reference),
                     command: MAV_CMD_NAV_LAND,
                     current: 0,
                     autocontinue: 1,
@@ -339,8 +468,8 @@ impl MAVLinkConverter {
                     param2: 0.0, // Precision land mode
                     param3: 0.0, // Empty
                     param4: 0.0, // Yaw angle
-                    x: last_waypoint.position.x() as f32,
-                    y: last_waypoint.position.y() as f32,
+                    x: last_waypoint.position.lat as f32,
+                    y: last_waypoint.position.lon as f32,
                     z: 0.0, // Land altitude
                     mission_type: 0,
                 });
@@ -355,6 +484,63 @@ impl MAVLinkConverter {
         })
     }
 
+    /// Inclusion-fence items for a closed `boundary` of `(latitude,
+    /// longitude)` points, one per vertex without the closing repeat. Each
+    /// item carries the vertex count in `param1` as the fence protocol
+    /// requires, and `max_alt` (above home) in `z`.
+    pub fn geofence_to_mavlink(
+        boundary: &[(f64, f64)],
+        max_alt: f32,
+    ) -> std::result::Result<Vec<MAVLinkMissionItem>, GeofenceError> {
+        if !(max_alt.is_finite() && max_alt > 0.0) {
+            return Err(GeofenceError::InvalidMaxAltitude { max_alt });
+        }
+        let (Some(first), Some(last)) = (boundary.first(), boundary.last()) else {
+            return Err(GeofenceError::TooFewVertices { count: 0 });
+        };
+        if boundary.len() < 2 || first != last {
+            return Err(GeofenceError::NotClosed);
+        }
+        let vertices = &boundary[..boundary.len() - 1];
+        if vertices.len() < 3 {
+            return Err(GeofenceError::TooFewVertices {
+                count: vertices.len(),
+            });
+        }
+        if vertices.len() > MAX_GEOFENCE_VERTICES {
+            return Err(GeofenceError::TooManyVertices {
+                count: vertices.len(),
+                max: MAX_GEOFENCE_VERTICES,
+            });
+        }
+        if let Some(index) = vertices
+            .iter()
+            .position(|(lat, lon)| !(lat.abs() <= 90.0 && lon.abs() <= 180.0))
+        {
+            return Err(GeofenceError::InvalidVertex { index });
+        }
+
+        Ok(vertices
+            .iter()
+            .enumerate()
+            .map(|(seq, (lat, lon))| MAVLinkMissionItem {
+                seq: seq as u16,
+                frame: MAV_FRAME_GLOBAL_RELATIVE_ALT,
+                command: MAV_CMD_NAV_FENCE_POLYGON_VERTEX_INCLUSION,
+                current: 0,
+                autocontinue: 1,
+                param1: vertices.len() as f32, // Vertex count
+                param2: 0.0,                   // Inclusion group
+                param3: 0.0,
+                param4: 0.0,
+                x: *lat as f32,
+                y: *lon as f32,
+                z: max_alt,
+                mission_type: MAV_MISSION_TYPE_FENCE,
+            })
+            .collect())
+    }
+
     pub fn to_waypoint_file(mavlink_mission: &MAVLinkMission) -> String {
         let mut output = String::new();
         output.push_str("QGC WPL 110\n");
@@ -471,3 +657,130 @@ mod command_ack_tests {
             .contains("ack timeout"));
     }
 }
+
+#[cfg(test)]
+mod mission_item_tests {
+    use super::*;
+    use crate::{AltitudeReferenceError, Waypoint};
+    use geo::polygon;
+    use shared::GeoPoint;
+
+    #[test]
+    fn mission_items_put_latitude_in_x_and_longitude_in_y() {
+        let mut mission = Mission::new(
+            "Axis check".to_string(),
+            String::new(),
+            polygon![(x: -96.1, y: 41.1), (x: -96.0, y: 41.1), (x: -96.0, y: 41.2)],
+        );
+        mission.add_waypoint(Waypoint::new(
+            GeoPoint::new(41.15, -96.05),
+            30.0,
+            WaypointType::Navigation,
+        ));
+
+        let mavlink = MAVLinkConverter::mission_to_mavlink(&mission).unwrap();
+
+        let item = &mavlink.items[1];
+        assert_eq!((item.x, item.y), (41.15_f32, -96.05_f32));
+        let waypoint_json = serde_json::to_value(&mission.waypoints[0]).unwrap();
+        assert_eq!(
+            waypoint_json["position"],
+            serde_json::json!({"x": -96.05, "y": 41.15})
+        );
+    }
+
+    #[test]
+    fn closed_four_vertex_boundary_becomes_four_inclusion_fence_items() {
+        let boundary = [
+            (41.10, -96.10),
+            (41.10, -96.00),
+            (41.20, -96.00),
+            (41.20, -96.10),
+            (41.10, -96.10),
+        ];
+
+        let items = MAVLinkConverter::geofence_to_mavlink(&boundary, 120.0).unwrap();
+
+        assert_eq!(items.len(), 4);
+        for (seq, item) in items.iter().enumerate() {
+            assert_eq!(item.seq, seq as u16);
+            assert_eq!(item.command, MAV_CMD_NAV_FENCE_POLYGON_VERTEX_INCLUSION);
+            assert_eq!(item.mission_type, MAV_MISSION_TYPE_FENCE);
+            assert_eq!(item.param1, 4.0);
+            assert_eq!(item.z, 120.0);
+        }
+        assert_eq!((items[2].x, items[2].y), (41.20_f32, -96.00_f32));
+        let wire = items[2].to_mission_item_int(1, 1).unwrap();
+        // Items hold f32 degrees, so the scaled values are only exact to ~1e-6°.
+        assert!((wire.x - 412_000_000).abs() < 20);
+        assert!((wire.y + 960_000_000).abs() < 20);
+        assert_eq!(wire.mission_type, MavMissionType::MAV_MISSION_TYPE_FENCE);
+
+        assert_eq!(
+            MAVLinkConverter::geofence_to_mavlink(&boundary[..4], 120.0).unwrap_err(),
+            GeofenceError::NotClosed
+        );
+        let mut too_many: Vec<(f64, f64)> = (0..=MAX_GEOFENCE_VERTICES)
+            .map(|index| (41.0 + index as f64 * 1e-4, -96.0))
+            .collect();
+        too_many.push(too_many[0]);
+        assert_eq!(
+            MAVLinkConverter::geofence_to_mavlink(&too_many, 120.0).unwrap_err(),
+            GeofenceError::TooManyVertices {
+                count: MAX_GEOFENCE_VERTICES + 1,
+                max: MAX_GEOFENCE_VERTICES
+            }
+        );
+    }
+
+    #[test]
+    fn mission_items_use_the_frame_of_each_waypoint_altitude_reference() {
+        let area = polygon![(x: -96.1, y: 41.1), (x: -96.0, y: 41.1), (x: -96.0, y: 41.2)];
+        let frames = |reference: AltitudeReference| {
+            let mut mission = Mission::new("Frames".to_string(), String::new(), area.clone());
+            mission.add_waypoint(
+                Waypoint::new(GeoPoint::new(41.15, -96.05), 30.0, WaypointType::Navigation)
+                    .with_altitude_reference(reference),
+            );
+            MAVLinkConverter::mission_to_mavlink(&mission)
+                .unwrap()
+                .items
+                .iter()
+                .map(|item| item.frame)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            frames(AltitudeReference::RelativeToHome),
+            vec![MAV_FRAME_GLOBAL_RELATIVE_ALT; 3]
+        );
+        assert_eq!(frames(AltitudeReference::Msl), vec![MAV_FRAME_GLOBAL; 3]);
+        assert_eq!(
+            frames(AltitudeReference::Agl),
+            vec![MAV_FRAME_GLOBAL_TERRAIN_ALT; 3]
+        );
+
+        let mut mixed = Mission::new("Mixed".to_string(), String::new(), area);
+        mixed.add_waypoint(Waypoint::new(
+            GeoPoint::new(41.15, -96.05),
+            30.0,
+            WaypointType::Navigation,
+        ));
+        mixed.add_waypoint(
+            Waypoint::new(
+                GeoPoint::new(41.16, -96.05),
+                330.0,
+                WaypointType::Navigation,
+            )
+            .with_altitude_reference(AltitudeReference::Msl),
+        );
+        let error = MAVLinkConverter::mission_to_mavlink(&mixed).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<AltitudeReferenceError>(),
+            Some(AltitudeReferenceError::Mixed {
+                waypoint_index: 1,
+                ..
+            })
+        ));
+    }
+}
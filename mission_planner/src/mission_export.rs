@@ -153,8 +153,8 @@ fn mission_plan_csv(mission: &Mission) -> String {
             "{},{},{:.7},{:.7},{:.3},{},{},{},{},{}\n",
             index + 1,
             waypoint.id,
-            waypoint.position.x(),
-            waypoint.position.y(),
+            waypoint.position.lon,
+            waypoint.position.lat,
             waypoint.altitude_m,
             csv_escape(&format!("{:?}", waypoint.waypoint_type)),
             waypoint
@@ -226,6 +226,100 @@ fn telemetry_track_geojson(
     })
 }
 
+/// Plan-side GeoJSON for web maps: the area of interest, the waypoint route
+/// as a LineString, then one Point per waypoint. Coordinates are
+/// `[lon, lat]`, with altitude as a third ordinate on route and waypoints.
+pub(crate) fn mission_plan_geojson(mission: &Mission) -> Value {
+    let ring = |ring: &geo::LineString<f64>| {
+        ring.coords()
+            .map(|coord| json!([coord.x, coord.y]))
+            .collect::<Vec<_>>()
+    };
+    let area = &mission.area_of_interest;
+    let mut rings = vec![ring(area.exterior())];
+    rings.extend(area.interiors().iter().map(ring));
+
+    let mut features = vec![json!({
+        "type": "Feature",
+        "properties": {
+            "kind": "area_of_interest",
+            "mission_id": mission.id,
+            "name": mission.name,
+        },
+        "geometry": {
+            "type": "Polygon",
+            "coordinates": rings,
+        }
+    })];
+
+    if mission.waypoints.len() >= 2 {
+        let route = mission
+            .waypoints
+            .iter()
+            .map(|waypoint| {
+                json!([
+                    waypoint.position.lon,
+                    waypoint.position.lat,
+                    waypoint.altitude_m
+                ])
+            })
+            .collect::<Vec<_>>();
+        features.push(json!({
+            "type": "Feature",
+            "properties": {
+                "kind": "flight_path",
+                "mission_id": mission.id,
+                "waypoint_count": mission.waypoints.len(),
+            },
+            "geometry": {
+                "type": "LineString",
+                "coordinates": route,
+            }
+        }));
+    }
+
+    features.extend(
+        mission
+            .waypoints
+            .iter()
+            .enumerate()
+            .map(|(index, waypoint)| {
+                json!({
+                    "type": "Feature",
+                    "id": waypoint.id,
+                    "properties": {
+                        "kind": "waypoint",
+                        "sequence": index + 1,
+                        "waypoint_type": waypoint.waypoint_type,
+                        "altitude_m": waypoint.altitude_m,
+                        "speed_ms": waypoint.speed_ms,
+                        "heading_degrees": waypoint.heading_degrees,
+                        "actions": waypoint.actions,
+                    },
+                    "geometry": {
+                        "type": "Point",
+                        "coordinates": [
+                            waypoint.position.lon,
+                            waypoint.position.lat,
+                            waypoint.altitude_m
+                        ],
+                    }
+                })
+            }),
+    );
+
+    json!({
+        "type": "FeatureCollection",
+        "crs": {
+            "type": "name",
+            "properties": {
+                "name": MISSION_EXPORT_CRS,
+            }
+        },
+        "features": features,
+    })
+}
+
 fn telemetry_extent(telemetry: &[MissionTelemetrySample]) -> Option<MissionExportExtent> {
     let mut points = telemetry.iter();
     let first = points.next()?;
@@ -274,8 +368,9 @@ mod tests {
     use super::*;
     use crate::{Action, MissionStatus, Waypoint, WaypointType, WeatherConstraints};
     use chrono::{TimeZone, Utc};
-    use geo::{LineString, Point, Polygon};
+    use geo::{LineString, Polygon};
     use shared::schemas::{GpsCoords, Telemetry};
+    use shared::GeoPoint;
 
     #[test]
     fn completed_mission_exports_plan_csv_and_geojson_track() {
@@ -376,6 +471,49 @@ mod tests {
         assert_eq!(error.code, MissionExportErrorCode::TelemetryMissionMismatch);
     }
 
+    #[test]
+    fn mission_geojson_has_area_route_and_waypoints_in_lon_lat_order() {
+        let mission = sample_mission();
+
+        let geojson = mission.to_geojson();
+
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().expect("features");
+        assert_eq!(features.len(), 1 + 1 + mission.waypoints.len());
+        assert_eq!(features[0]["geometry"]["type"], "Polygon");
+        assert_eq!(
+            features[0]["geometry"]["coordinates"][0][1],
+            json!([-96.00, 41.00])
+        );
+        assert_eq!(features[1]["geometry"]["type"], "LineString");
+        assert_eq!(
+            features[1]["geometry"]["coordinates"],
+            json!([[-96.0, 41.0, 40.0], [-96.004, 41.002, 42.0]])
+        );
+
+        let takeoff = &features[2];
+        assert_eq!(takeoff["geometry"]["type"], "Point");
+        assert_eq!(
+            takeoff["geometry"]["coordinates"],
+            json!([-96.0, 41.0, 40.0])
+        );
+        assert_eq!(takeoff["properties"]["waypoint_type"], "Takeoff");
+        assert_eq!(
+            takeoff["properties"]["actions"],
+            json!([{"SetSpeed": {"speed_ms": 6.0}}])
+        );
+        for feature in features {
+            let first = match feature["geometry"]["type"].as_str() {
+                Some("Polygon") => &feature["geometry"]["coordinates"][0][0],
+                Some("LineString") => &feature["geometry"]["coordinates"][0],
+                _ => &feature["geometry"]["coordinates"],
+            };
+            // Longitudes here are west of -90, which no latitude can be.
+            assert!(first[0].as_f64().unwrap() < -90.0);
+            assert!((40.0..=42.0).contains(&first[1].as_f64().unwrap()));
+        }
+    }
+
     fn sample_mission() -> Mission {
         let id = Uuid::new_v4();
         Mission {
@@ -403,8 +541,9 @@ mod tests {
             waypoints: vec![
                 Waypoint {
                     id: Uuid::new_v4(),
-                    position: Point::new(-96.0, 41.0),
+                    position: GeoPoint::new(41.0, -96.0),
                     altitude_m: 40.0,
+                    altitude_reference: shared::AltitudeReference::RelativeToHome,
                     waypoint_type: WaypointType::Takeoff,
                     actions: vec![Action::SetSpeed { speed_ms: 6.0 }],
                     arrival_time: Some(Utc.timestamp_opt(100, 0).unwrap()),
@@ -413,8 +552,9 @@ mod tests {
                 },
                 Waypoint {
                     id: Uuid::new_v4(),
-                    position: Point::new(-96.004, 41.002),
+                    position: GeoPoint::new(41.002, -96.004),
                     altitude_m: 42.0,
+                    altitude_reference: shared::AltitudeReference::RelativeToHome,
                     waypoint_type: WaypointType::Landing,
                     actions: Vec::new(),
                     arrival_time: Some(Utc.timestamp_opt(110, 0).unwrap()),
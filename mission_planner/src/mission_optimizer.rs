@@ -1,8 +1,8 @@
 use crate::flight_path::{PathType, SurveyPattern};
 use crate::{FlightPath, Mission};
 use anyhow::Result;
-use geo::Point;
 use serde::{Deserialize, Serialize};
+use shared::GeoPoint;
 use std::fmt;
 use uuid::Uuid;
 
@@ -268,7 +268,7 @@ pub fn assert_mission_budget_allows_arming(
     Ok(())
 }
 
-fn validate_budget_config(
+pub(crate) fn validate_budget_config(
     config: MissionBudgetConfig,
 ) -> std::result::Result<(), MissionBudgetError> {
     let valid = config.cruise_speed_ms.is_finite()
@@ -295,7 +295,7 @@ fn validate_budget_config(
     Ok(())
 }
 
-fn mission_path_distance_m(mission: &Mission) -> f32 {
+pub(crate) fn mission_path_distance_m(mission: &Mission) -> f32 {
     if mission.flight_paths.is_empty() {
         return FlightPath::from_waypoints(
             "budget evaluation path".to_string(),
@@ -311,7 +311,7 @@ fn mission_path_distance_m(mission: &Mission) -> f32 {
         .sum()
 }
 
-fn mission_action_time_seconds(mission: &Mission) -> u32 {
+pub(crate) fn mission_action_time_seconds(mission: &Mission) -> u32 {
     mission
         .waypoints
         .iter()
@@ -341,6 +341,7 @@ fn estimate_action_time(action: &crate::waypoint::Action) -> u32 {
         Action::CollectMultispectral { .. } => 5,
         Action::Hover { duration_seconds } => *duration_seconds,
         Action::SetSpeed { .. } => 1,
+        Action::SetGimbal { .. } => 1,
         Action::Wait { duration_seconds } => *duration_seconds,
         Action::Custom { .. } => 10,
     }
@@ -354,9 +355,9 @@ impl fmt::Display for MissionBudgetError {
 
 impl std::error::Error for MissionBudgetError {}
 
-fn distance(a: &Point<f64>, b: &Point<f64>) -> f64 {
-    let dx = b.x() - a.x();
-    let dy = b.y() - a.y();
+fn distance(a: &GeoPoint, b: &GeoPoint) -> f64 {
+    let dx = b.lon - a.lon;
+    let dy = b.lat - a.lat;
     (dx * dx + dy * dy).sqrt()
 }
 
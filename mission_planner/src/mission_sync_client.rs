@@ -0,0 +1,393 @@
+//! Deploys converted missions to mission control.
+//!
+//! Missions that fit in one sync chunk go through mission control's
+//! `POST /api/vehicle/mission` in one request. Longer missions use the
+//! differential sync protocol from [`shared::mission_sync`] over mission
+//! control's `/ws` socket, so a dropped connection or an edit to part of
+//! the mission only costs the chunks mission control does not hold yet.
+
+use crate::mavlink_integration::MAVLinkMission;
+use anyhow::{Context, Result};
+use futures_util::{SinkExt, StreamExt};
+use shared::http_client::{HttpClient, HttpClientConfig};
+use shared::mission_sync::{
+    chunk_mission_document, sync_mission, MissionSyncError, MissionSyncMessage, MissionSyncPolicy,
+    MissionSyncProgress, MissionSyncSender, MissionSyncTransport, DEFAULT_SYNC_CHUNK_SIZE,
+};
+use shared::schemas::WebSocketMessage as MissionControlMessage;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    tungstenite::{client::IntoClientRequest, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Times a sync reconnects after its connection drops before giving up.
+pub const DEFAULT_SYNC_RECONNECTS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeployRoute {
+    /// The whole mission in one request.
+    FullUpload,
+    DifferentialSync,
+}
+
+/// Where and how to deploy missions to mission control.
+#[derive(Debug, Clone)]
+pub struct MissionControlLink {
+    /// Base URL of mission control's HTTP API, e.g. `http://localhost:3000`.
+    pub api_url: String,
+    /// Mission control's WebSocket endpoint, e.g. `ws://localhost:8080/ws`.
+    pub ws_url: String,
+    /// Missions with at most this many items use [`DeployRoute::FullUpload`].
+    pub full_upload_max_items: usize,
+    pub chunk_size: usize,
+    pub policy: MissionSyncPolicy,
+    pub max_reconnects: u32,
+    /// Mission control's `CONTROL_TOKEN`, sent as a bearer token on both
+    /// routes; mission control refuses vehicle uploads without it.
+    pub control_token: Option<String>,
+    /// Client for [`DeployRoute::FullUpload`], shared by every deploy.
+    pub http: HttpClient,
+}
+
+impl MissionControlLink {
+    /// Full uploads use the default [`HttpClientConfig`]; replace
+    /// [`Self::http`] for other timeouts or retries.
+    pub fn new(api_url: impl Into<String>, ws_url: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            api_url: api_url.into(),
+            ws_url: ws_url.into(),
+            full_upload_max_items: DEFAULT_SYNC_CHUNK_SIZE,
+            chunk_size: DEFAULT_SYNC_CHUNK_SIZE,
+            policy: MissionSyncPolicy::default(),
+            max_reconnects: DEFAULT_SYNC_RECONNECTS,
+            control_token: None,
+            http: HttpClient::new(HttpClientConfig::default())?,
+        })
+    }
+
+    /// Reads `MISSION_CONTROL_API_URL`, `MISSION_CONTROL_WS_URL` and
+    /// `CONTROL_TOKEN`; `None` unless both URLs are set.
+    pub fn from_env() -> Result<Option<Self>> {
+        let setting = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        let (Some(api_url), Some(ws_url)) = (
+            setting("MISSION_CONTROL_API_URL"),
+            setting("MISSION_CONTROL_WS_URL"),
+        ) else {
+            return Ok(None);
+        };
+        let mut link = Self::new(api_url, ws_url)?;
+        link.control_token = setting("CONTROL_TOKEN");
+        Ok(Some(link))
+    }
+
+    pub fn route_for(&self, mission: &MAVLinkMission) -> DeployRoute {
+        if mission.items.len() <= self.full_upload_max_items {
+            DeployRoute::FullUpload
+        } else {
+            DeployRoute::DifferentialSync
+        }
+    }
+
+    /// Sends `mission` to mission control by the route its size calls for,
+    /// reporting sync progress through `on_progress`.
+    pub async fn deploy<F>(
+        &self,
+        mission_id: Uuid,
+        mission: &MAVLinkMission,
+        on_progress: F,
+    ) -> Result<DeployRoute>
+    where
+        F: FnMut(&MissionSyncProgress),
+    {
+        let route = self.route_for(mission);
+        match route {
+            DeployRoute::FullUpload => self.upload_full(mission).await?,
+            DeployRoute::DifferentialSync => self.sync(mission_id, mission, on_progress).await?,
+        }
+        Ok(route)
+    }
+
+    async fn upload_full(&self, mission: &MAVLinkMission) -> Result<()> {
+        let url = format!("{}/api/vehicle/mission", self.api_url.trim_end_matches('/'));
+        let mut request = self.http.post(&url).json(mission);
+        if let Some(token) = &self.control_token {
+            request = request.bearer_auth(token);
+        }
+        // The upload replaces the vehicle's whole mission, so repeating it
+        // after a lost response leaves mission control in the same state.
+        self.http
+            .send_retryable(request)
+            .await
+            .with_context(|| format!("mission control did not accept the mission at {url}"))?;
+        Ok(())
+    }
+
+    async fn sync<F>(
+        &self,
+        mission_id: Uuid,
+        mission: &MAVLinkMission,
+        mut on_progress: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&MissionSyncProgress),
+    {
+        let (manifest, chunks) =
+            chunk_mission_document(mission_id, mission, "items", self.chunk_size)?;
+        info!(
+            "Syncing mission {} to mission control: {} items in {} chunks",
+            mission_id,
+            manifest.item_count,
+            manifest.chunk_count()
+        );
+        let mut sender = MissionSyncSender::new(manifest, chunks);
+
+        let mut attempt = 0;
+        loop {
+            let outcome =
+                match WebSocketSyncTransport::connect(&self.ws_url, self.control_token.as_deref())
+                    .await
+                {
+                    Ok(mut transport) => {
+                        sync_mission(&mut transport, &mut sender, self.policy, &mut on_progress)
+                            .await
+                    }
+                    Err(e) => Err(e),
+                };
+            match outcome {
+                Ok(()) => return Ok(()),
+                Err(e @ (MissionSyncError::TransportClosed | MissionSyncError::Transport(_)))
+                    if attempt < self.max_reconnects =>
+                {
+                    attempt += 1;
+                    warn!(
+                        "Mission {} sync interrupted ({}); reconnecting ({}/{})",
+                        mission_id, e, attempt, self.max_reconnects
+                    );
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// Carries sync messages over mission control's `/ws`, skipping the
+/// telemetry and status broadcasts it also streams.
+struct WebSocketSyncTransport {
+    stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl WebSocketSyncTransport {
+    async fn connect(url: &str, control_token: Option<&str>) -> Result<Self, MissionSyncError> {
+        let mut request = url
+            .into_client_request()
+            .map_err(|e| MissionSyncError::Transport(e.to_string()))?;
+        if let Some(token) = control_token {
+            let value = format!("Bearer {token}")
+                .parse()
+                .map_err(|_| MissionSyncError::Transport("invalid control token".to_string()))?;
+            request.headers_mut().insert("authorization", value);
+        }
+        let (stream, _) = tokio_tungstenite::connect_async(request)
+            .await
+            .map_err(|e| MissionSyncError::Transport(e.to_string()))?;
+        Ok(Self { stream })
+    }
+}
+
+impl MissionSyncTransport for WebSocketSyncTransport {
+    async fn send(&mut self, message: MissionSyncMessage) -> Result<(), MissionSyncError> {
+        let frame = serde_json::to_string(&MissionControlMessage::MissionSync { message })
+            .map_err(|e| MissionSyncError::Encode(e.to_string()))?;
+        self.stream
+            .send(Message::Text(frame))
+            .await
+            .map_err(|e| MissionSyncError::Transport(e.to_string()))
+    }
+
+    async fn recv(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<MissionSyncMessage>, MissionSyncError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let frame = match tokio::time::timeout_at(deadline, self.stream.next()).await {
+                Err(_) => return Ok(None),
+                Ok(None) | Ok(Some(Ok(Message::Close(_)))) => {
+                    return Err(MissionSyncError::TransportClosed)
+                }
+                Ok(Some(Err(e))) => return Err(MissionSyncError::Transport(e.to_string())),
+                Ok(Some(Ok(frame))) => frame,
+            };
+            let Message::Text(text) = frame else {
+                continue;
+            };
+            if let Ok(MissionControlMessage::MissionSync { message }) = serde_json::from_str(&text)
+            {
+                return Ok(Some(message));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mavlink_integration::{MAVLinkMissionItem, MAV_CMD_NAV_WAYPOINT};
+    use axum::extract::ws::{Message as AxumMessage, WebSocketUpgrade};
+    use axum::{routing::get, Router};
+    use shared::mission_sync::{MissionSyncPhase, MissionSyncReceiver};
+    use std::sync::{Arc, Mutex};
+
+    fn mission(items: u16) -> MAVLinkMission {
+        MAVLinkMission {
+            target_system: 1,
+            target_component: 1,
+            count: items,
+            items: (0..items)
+                .map(|seq| MAVLinkMissionItem {
+                    seq,
+                    frame: 3,
+                    command: MAV_CMD_NAV_WAYPOINT,
+                    current: u8::from(seq == 0),
+                    autocontinue: 1,
+                    param1: 0.0,
+                    param2: 0.0,
+                    param3: 0.0,
+                    param4: 0.0,
+                    x: 41.0 + f32::from(seq) * 1e-4,
+                    y: -96.0,
+                    z: 40.0,
+                    mission_type: 0,
+                })
+                .collect(),
+        }
+    }
+
+    /// A `/ws` endpoint that answers sync messages like mission control and
+    /// keeps every mission it verified.
+    async fn serve_receiver() -> (String, Arc<Mutex<Vec<serde_json::Value>>>) {
+        let receiver = Arc::new(Mutex::new(MissionSyncReceiver::new()));
+        let committed = Arc::new(Mutex::new(Vec::new()));
+        let stored = committed.clone();
+        let app = Router::new().route(
+            "/ws",
+            get(move |ws: WebSocketUpgrade| {
+                let receiver = receiver.clone();
+                let committed = committed.clone();
+                async move {
+                    ws.on_upgrade(move |mut socket| async move {
+                        while let Some(Ok(AxumMessage::Text(text))) = socket.recv().await {
+                            let Ok(MissionControlMessage::MissionSync { message }) =
+                                serde_json::from_str(&text)
+                            else {
+                                continue;
+                            };
+                            let step = receiver.lock().unwrap().handle(message);
+                            if let Some(mission) = step.committed {
+                                committed
+                                    .lock()
+                                    .unwrap()
+                                    .push(mission.into_document("items"));
+                            }
+                            if let Some(reply) = step.reply {
+                                let frame =
+                                    serde_json::to_string(&MissionControlMessage::MissionSync {
+                                        message: reply,
+                                    })
+                                    .unwrap();
+                                if socket.send(AxumMessage::Text(frame)).await.is_err() {
+                                    break;
+                                }
+                            }
+                        }
+                    })
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("ws://{address}/ws"), stored)
+    }
+
+    #[test]
+    fn small_missions_take_the_full_upload_route() {
+        let link =
+            MissionControlLink::new("http://localhost:3000", "ws://localhost:8080/ws").unwrap();
+        assert_eq!(link.route_for(&mission(5)), DeployRoute::FullUpload);
+        assert_eq!(
+            link.route_for(&mission(DEFAULT_SYNC_CHUNK_SIZE as u16 + 1)),
+            DeployRoute::DifferentialSync
+        );
+    }
+
+    #[tokio::test]
+    async fn long_missions_sync_over_the_websocket() {
+        let (ws_url, committed) = serve_receiver().await;
+        let mut link = MissionControlLink::new("http://127.0.0.1:9", ws_url).unwrap();
+        link.chunk_size = 8;
+        link.full_upload_max_items = 8;
+        let mission = mission(50);
+        let mut phases = Vec::new();
+
+        let route = link
+            .deploy(Uuid::new_v4(), &mission, |progress| {
+                phases.push(progress.phase)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(route, DeployRoute::DifferentialSync);
+        assert_eq!(phases.last(), Some(&MissionSyncPhase::Complete));
+        let committed = committed.lock().unwrap();
+        assert_eq!(committed.len(), 1);
+        assert_eq!(committed[0], serde_json::to_value(&mission).unwrap());
+    }
+
+    #[tokio::test]
+    async fn full_uploads_are_retried_after_a_failed_attempt() {
+        use axum::http::StatusCode;
+        use axum::routing::post;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = attempts.clone();
+        let app = Router::new().route(
+            "/api/vehicle/mission",
+            post(move || {
+                let counter = counter.clone();
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                        StatusCode::BAD_GATEWAY
+                    } else {
+                        StatusCode::OK
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let mut link =
+            MissionControlLink::new(format!("http://{address}"), "ws://127.0.0.1:9/ws").unwrap();
+        let mut config = HttpClientConfig::default();
+        config.retry.initial_backoff_ms = 1;
+        link.http = HttpClient::new(config).unwrap();
+
+        let route = link
+            .deploy(Uuid::new_v4(), &mission(5), |_| {})
+            .await
+            .unwrap();
+
+        assert_eq!(route, DeployRoute::FullUpload);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+}
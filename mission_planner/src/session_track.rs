@@ -0,0 +1,593 @@
+use crate::waypoint::CameraSettings;
+use crate::{Action, Mission, MissionLinkage, Waypoint, WaypointType};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use geo::{ConvexHull, MultiPoint, Point};
+use serde::{Deserialize, Serialize};
+use shared::http_client::{HttpClient, HttpClientConfig, HttpClientError};
+use std::fmt;
+use uuid::Uuid;
+
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+/// Positions closer than this are treated as the same spot when counting
+/// distinct track points, which absorbs GPS jitter during a hover.
+const DISTINCT_POSITION_EPSILON_M: f64 = 0.5;
+const MIN_DISTINCT_POINTS: usize = 3;
+const CAPTURE_CAMERA_ID: &str = "primary";
+
+/// One telemetry fix from a recorded flight session. `position` is
+/// longitude/latitude in degrees.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionTrackPoint {
+    pub timestamp: DateTime<Utc>,
+    pub position: Point<f64>,
+    pub altitude_m: f32,
+    #[serde(default)]
+    pub capture_event: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TrackSimplificationConfig {
+    /// Maximum horizontal deviation, in meters, between the original track
+    /// and the simplified polyline.
+    pub tolerance_m: f64,
+    /// Points whose altitude departs from the simplified profile by more than
+    /// this are kept even when they are horizontally redundant.
+    pub vertical_threshold_m: f32,
+}
+
+impl Default for TrackSimplificationConfig {
+    fn default() -> Self {
+        Self {
+            tolerance_m: 5.0,
+            vertical_threshold_m: 3.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SessionTrackErrorCode {
+    InvalidTolerance,
+    InvalidVerticalThreshold,
+    NonFiniteSample,
+    DegenerateTrack,
+    StationaryTrack,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionTrackError {
+    pub code: SessionTrackErrorCode,
+    pub message: String,
+}
+
+/// Reduces a recorded telemetry track to the points needed to reproduce it
+/// within `config.tolerance_m`, using Douglas–Peucker on a local metric
+/// projection. Capture events, the first and the last fix are always kept.
+pub fn simplify_track(
+    track: &[SessionTrackPoint],
+    config: TrackSimplificationConfig,
+) -> Result<Vec<SessionTrackPoint>, SessionTrackError> {
+    validate_track(track, config)?;
+
+    let projected = project(track);
+    let mut keep = vec![false; track.len()];
+    keep[0] = true;
+    keep[track.len() - 1] = true;
+    for (index, point) in track.iter().enumerate() {
+        if point.capture_event {
+            keep[index] = true;
+        }
+    }
+
+    let anchors: Vec<usize> = keep
+        .iter()
+        .enumerate()
+        .filter_map(|(index, kept)| kept.then_some(index))
+        .collect();
+    for span in anchors.windows(2) {
+        mark_significant(track, &projected, span[0], span[1], config, &mut keep);
+    }
+
+    Ok(track
+        .iter()
+        .zip(keep)
+        .filter(|(_, kept)| *kept)
+        .map(|(point, _)| point.clone())
+        .collect())
+}
+
+/// Largest horizontal distance, in meters, from any original fix to the
+/// simplified polyline.
+pub fn max_deviation_m(original: &[SessionTrackPoint], simplified: &[SessionTrackPoint]) -> f64 {
+    let Some(origin) = original.first().or(simplified.first()) else {
+        return 0.0;
+    };
+    let to_xy = |point: &SessionTrackPoint| project_point(origin.position, point.position);
+    let polyline: Vec<(f64, f64)> = simplified.iter().map(to_xy).collect();
+    if polyline.len() < 2 {
+        return original
+            .iter()
+            .map(|point| {
+                let (x, y) = to_xy(point);
+                let (ox, oy) = polyline.first().copied().unwrap_or((0.0, 0.0));
+                (x - ox).hypot(y - oy)
+            })
+            .fold(0.0, f64::max);
+    }
+
+    original
+        .iter()
+        .map(|point| {
+            let xy = to_xy(point);
+            polyline
+                .windows(2)
+                .map(|leg| distance_to_segment(xy, leg[0], leg[1]))
+                .fold(f64::INFINITY, f64::min)
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Turns a recorded session into a draft mission that re-flies it: the area
+/// of interest is the convex hull of the track, waypoints follow the
+/// simplified path and the duration estimate comes from the original flight.
+pub fn mission_draft_from_session(
+    session_id: &str,
+    track: &[SessionTrackPoint],
+    config: TrackSimplificationConfig,
+) -> Result<Mission, SessionTrackError> {
+    let simplified = simplify_track(track, config)?;
+
+    let hull = MultiPoint::from(track.iter().map(|point| point.position).collect::<Vec<_>>())
+        .convex_hull();
+    let linkage = MissionLinkage {
+        session_id: Some(session_id.to_string()),
+        ..MissionLinkage::unassigned()
+    };
+    let mut mission = Mission::new_linked(
+        format!("Re-fly of session {session_id}"),
+        format!(
+            "Draft generated from {} telemetry fixes simplified to {} waypoints",
+            track.len(),
+            simplified.len()
+        ),
+        hull,
+        linkage,
+    );
+
+    let last_index = simplified.len() - 1;
+    for (index, point) in simplified.iter().enumerate() {
+        let waypoint_type = if index == 0 {
+            WaypointType::Takeoff
+        } else if index == last_index {
+            WaypointType::Landing
+        } else if point.capture_event {
+            WaypointType::DataCollection
+        } else {
+            WaypointType::Navigation
+        };
+        let mut waypoint = Waypoint::new(point.position, point.altitude_m, waypoint_type);
+        if point.capture_event {
+            waypoint.add_action(Action::TakePhoto {
+                camera_id: CAPTURE_CAMERA_ID.to_string(),
+                settings: CameraSettings::default(),
+            });
+        }
+        mission.add_waypoint(waypoint);
+    }
+
+    let flight_seconds = (track[track.len() - 1].timestamp - track[0].timestamp)
+        .num_seconds()
+        .max(0);
+    mission.estimated_duration_minutes =
+        u32::try_from((flight_seconds + 59) / 60).unwrap_or(u32::MAX);
+    mission
+        .metadata
+        .insert("source".to_string(), "session_track".to_string());
+    mission
+        .metadata
+        .insert("source_point_count".to_string(), track.len().to_string());
+    mission.metadata.insert(
+        "simplification_tolerance_m".to_string(),
+        config.tolerance_m.to_string(),
+    );
+
+    Ok(mission)
+}
+
+/// Reads recorded session tracks from the data collector's
+/// `GET /sessions/{session_id}/track`.
+#[derive(Debug, Clone)]
+pub struct SessionTrackSource {
+    base_url: String,
+    http: HttpClient,
+}
+
+/// A fix as the data collector serves it.
+#[derive(Debug, Deserialize)]
+struct CollectorTrackFix {
+    timestamp: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+    altitude_m: f32,
+    capture_event: bool,
+}
+
+impl SessionTrackSource {
+    /// `base_url` is the data collector's ingest API root, e.g.
+    /// `http://localhost:8082/api`.
+    pub fn new(base_url: impl Into<String>, config: HttpClientConfig) -> Result<Self> {
+        Ok(Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: HttpClient::new(config)?,
+        })
+    }
+
+    /// Reads `DATA_COLLECTOR_URL`; `None` when it is unset. Requests use the
+    /// default [`HttpClientConfig`] timeout and retries.
+    pub fn from_env() -> Result<Option<Self>> {
+        std::env::var("DATA_COLLECTOR_URL")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(|url| Self::new(url, HttpClientConfig::default()))
+            .transpose()
+    }
+
+    /// The recorded track of `session_id`, or `None` when the data collector
+    /// does not know the session.
+    pub async fn fetch(&self, session_id: Uuid) -> Result<Option<Vec<SessionTrackPoint>>> {
+        let url = format!("{}/sessions/{session_id}/track", self.base_url);
+        let response = match self.http.send(self.http.get(&url)).await {
+            Err(HttpClientError::Status {
+                status: reqwest::StatusCode::NOT_FOUND,
+                ..
+            }) => return Ok(None),
+            response => response.with_context(|| format!("requesting {url}"))?,
+        };
+        let fixes: Vec<CollectorTrackFix> = response
+            .json()
+            .await
+            .with_context(|| format!("decoding the track from {url}"))?;
+        Ok(Some(
+            fixes
+                .into_iter()
+                .map(|fix| SessionTrackPoint {
+                    timestamp: fix.timestamp,
+                    position: Point::new(fix.longitude, fix.latitude),
+                    altitude_m: fix.altitude_m,
+                    capture_event: fix.capture_event,
+                })
+                .collect(),
+        ))
+    }
+}
+
+fn validate_track(
+    track: &[SessionTrackPoint],
+    config: TrackSimplificationConfig,
+) -> Result<(), SessionTrackError> {
+    if !config.tolerance_m.is_finite() || config.tolerance_m <= 0.0 {
+        return Err(SessionTrackError {
+            code: SessionTrackErrorCode::InvalidTolerance,
+            message: "simplification tolerance must be finite and positive".to_string(),
+        });
+    }
+    if !config.vertical_threshold_m.is_finite() || config.vertical_threshold_m <= 0.0 {
+        return Err(SessionTrackError {
+            code: SessionTrackErrorCode::InvalidVerticalThreshold,
+            message: "vertical threshold must be finite and positive".to_string(),
+        });
+    }
+    if let Some(index) = track.iter().position(|point| {
+        !point.position.x().is_finite()
+            || !point.position.y().is_finite()
+            || !point.altitude_m.is_finite()
+    }) {
+        return Err(SessionTrackError {
+            code: SessionTrackErrorCode::NonFiniteSample,
+            message: format!("track sample {index} has a non-finite position or altitude"),
+        });
+    }
+
+    let projected = project(track);
+    let mut distinct: Vec<(f64, f64)> = Vec::new();
+    for &xy in &projected {
+        if distinct
+            .last()
+            .is_none_or(|&last| (xy.0 - last.0).hypot(xy.1 - last.1) > DISTINCT_POSITION_EPSILON_M)
+        {
+            distinct.push(xy);
+        }
+    }
+    if distinct.len() < MIN_DISTINCT_POINTS {
+        return Err(SessionTrackError {
+            code: SessionTrackErrorCode::DegenerateTrack,
+            message: format!(
+                "track has {} distinct positions across {} samples; at least {MIN_DISTINCT_POINTS} are needed to derive waypoints",
+                distinct.len(),
+                track.len()
+            ),
+        });
+    }
+
+    let extent_m = projected
+        .iter()
+        .map(|&(x, y)| x.hypot(y))
+        .fold(0.0, f64::max);
+    if extent_m <= config.tolerance_m {
+        return Err(SessionTrackError {
+            code: SessionTrackErrorCode::StationaryTrack,
+            message: format!(
+                "track never leaves a {extent_m:.1} m radius of its first fix, which is within the {:.1} m tolerance; the session looks like a stationary hover",
+                config.tolerance_m
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+/// Recursive Douglas–Peucker over `track[first..=last]`. A point is kept when
+/// it is the worst offender and either its horizontal distance exceeds the
+/// tolerance or its altitude departs from the interpolated profile by more
+/// than the vertical threshold.
+fn mark_significant(
+    track: &[SessionTrackPoint],
+    projected: &[(f64, f64)],
+    first: usize,
+    last: usize,
+    config: TrackSimplificationConfig,
+    keep: &mut [bool],
+) {
+    if last <= first + 1 {
+        return;
+    }
+
+    let mut worst: Option<(usize, f64)> = None;
+    for index in first + 1..last {
+        let horizontal = distance_to_segment(projected[index], projected[first], projected[last]);
+        let vertical = f64::from(
+            (track[index].altitude_m - interpolated_altitude(track, first, last, index)).abs(),
+        );
+        let score = (horizontal / config.tolerance_m)
+            .max(vertical / f64::from(config.vertical_threshold_m));
+        if score > 1.0 && worst.is_none_or(|(_, best)| score > best) {
+            worst = Some((index, score));
+        }
+    }
+
+    if let Some((split, _)) = worst {
+        keep[split] = true;
+        mark_significant(track, projected, first, split, config, keep);
+        mark_significant(track, projected, split, last, config, keep);
+    }
+}
+
+fn interpolated_altitude(
+    track: &[SessionTrackPoint],
+    first: usize,
+    last: usize,
+    index: usize,
+) -> f32 {
+    let span = (track[last].timestamp - track[first].timestamp).num_milliseconds();
+    let fraction = if span > 0 {
+        (track[index].timestamp - track[first].timestamp).num_milliseconds() as f32 / span as f32
+    } else {
+        (index - first) as f32 / (last - first) as f32
+    };
+    let start = track[first].altitude_m;
+    start + (track[last].altitude_m - start) * fraction.clamp(0.0, 1.0)
+}
+
+fn project(track: &[SessionTrackPoint]) -> Vec<(f64, f64)> {
+    let Some(origin) = track.first() else {
+        return Vec::new();
+    };
+    track
+        .iter()
+        .map(|point| project_point(origin.position, point.position))
+        .collect()
+}
+
+/// Equirectangular projection around `origin`; accurate to well under the
+/// simplification tolerance over the few kilometers a survey flight covers.
+fn project_point(origin: Point<f64>, point: Point<f64>) -> (f64, f64) {
+    let meters_per_degree = EARTH_RADIUS_M.to_radians();
+    let x = (point.x() - origin.x()) * meters_per_degree * origin.y().to_radians().cos();
+    let y = (point.y() - origin.y()) * meters_per_degree;
+    (x, y)
+}
+
+fn distance_to_segment(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0.0 {
+        return (point.0 - start.0).hypot(point.1 - start.1);
+    }
+    let t =
+        (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_squared).clamp(0.0, 1.0);
+    (point.0 - (start.0 + t * dx)).hypot(point.1 - (start.1 + t * dy))
+}
+
+impl fmt::Display for SessionTrackError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for SessionTrackError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use geo::point;
+
+    const ORIGIN_LAT: f64 = 40.0;
+    const ORIGIN_LON: f64 = -105.0;
+
+    /// Builds a fix `east_m`/`north_m` meters from the test origin.
+    fn fix(seconds: i64, east_m: f64, north_m: f64, altitude_m: f32) -> SessionTrackPoint {
+        let meters_per_degree = EARTH_RADIUS_M.to_radians();
+        SessionTrackPoint {
+            timestamp: Utc.with_ymd_and_hms(2026, 6, 1, 9, 0, 0).unwrap()
+                + Duration::seconds(seconds),
+            position: point!(
+                x: ORIGIN_LON + east_m / (meters_per_degree * ORIGIN_LAT.to_radians().cos()),
+                y: ORIGIN_LAT + north_m / meters_per_degree
+            ),
+            altitude_m,
+            capture_event: false,
+        }
+    }
+
+    /// Four 100 m legs heading east, each sampled every 10 m, alternating
+    /// between 0 m and 20 m north so every turn is a 20 m excursion.
+    fn zig_zag_track() -> Vec<SessionTrackPoint> {
+        let mut track = Vec::new();
+        for step in 0..=40 {
+            let east = f64::from(step) * 10.0;
+            let leg_progress = f64::from(step % 10) / 10.0;
+            let north = if (step / 10) % 2 == 0 {
+                20.0 * leg_progress
+            } else {
+                20.0 * (1.0 - leg_progress)
+            };
+            track.push(fix(i64::from(step) * 5, east, north, 30.0));
+        }
+        track
+    }
+
+    #[test]
+    fn zig_zag_keeps_turns_at_tight_tolerance() {
+        let track = zig_zag_track();
+        let config = TrackSimplificationConfig {
+            tolerance_m: 1.0,
+            vertical_threshold_m: 3.0,
+        };
+
+        let simplified = simplify_track(&track, config).unwrap();
+
+        assert_eq!(simplified.len(), 5);
+        assert!(max_deviation_m(&track, &simplified) <= config.tolerance_m);
+    }
+
+    #[test]
+    fn zig_zag_collapses_to_a_line_at_loose_tolerance() {
+        let track = zig_zag_track();
+        let config = TrackSimplificationConfig {
+            tolerance_m: 25.0,
+            vertical_threshold_m: 3.0,
+        };
+
+        let simplified = simplify_track(&track, config).unwrap();
+
+        assert_eq!(simplified.len(), 2);
+        let deviation = max_deviation_m(&track, &simplified);
+        assert!(deviation <= config.tolerance_m);
+        assert!(deviation > 19.0);
+    }
+
+    #[test]
+    fn capture_points_and_climbs_are_preserved() {
+        let config = TrackSimplificationConfig {
+            tolerance_m: 25.0,
+            vertical_threshold_m: 5.0,
+        };
+        let mut track = zig_zag_track();
+        track[13].capture_event = true;
+        let level = simplify_track(&track, config).unwrap();
+        assert_eq!(level.len(), 3);
+        assert!(level.contains(&track[13]));
+
+        track[27].altitude_m = 45.0;
+        let with_climb = simplify_track(&track, config).unwrap();
+
+        assert!(with_climb.contains(&track[13]));
+        assert!(with_climb.contains(&track[27]));
+        assert!(with_climb.len() > level.len());
+    }
+
+    #[test]
+    fn mission_draft_uses_hull_waypoints_and_original_duration() {
+        let mut track = zig_zag_track();
+        track[13].capture_event = true;
+        let config = TrackSimplificationConfig {
+            tolerance_m: 25.0,
+            vertical_threshold_m: 3.0,
+        };
+
+        let mission = mission_draft_from_session("session-42", &track, config).unwrap();
+
+        assert_eq!(mission.session_id.as_deref(), Some("session-42"));
+        assert_eq!(mission.waypoints.len(), 3);
+        assert_eq!(mission.waypoints[0].waypoint_type, WaypointType::Takeoff);
+        assert_eq!(
+            mission.waypoints[1].waypoint_type,
+            WaypointType::DataCollection
+        );
+        assert!(matches!(
+            mission.waypoints[1].actions.as_slice(),
+            [Action::TakePhoto { .. }]
+        ));
+        assert_eq!(mission.waypoints[2].waypoint_type, WaypointType::Landing);
+        assert_eq!(mission.estimated_duration_minutes, 4);
+        assert!(mission.area_of_interest.exterior().0.len() >= 4);
+    }
+
+    #[test]
+    fn hover_and_short_tracks_are_rejected() {
+        let hover: Vec<_> = (0..20)
+            .map(|second| fix(second, 0.1 * (second % 3) as f64, 0.0, 12.0))
+            .collect();
+        let error = simplify_track(&hover, TrackSimplificationConfig::default()).unwrap_err();
+        assert_eq!(error.code, SessionTrackErrorCode::DegenerateTrack);
+
+        let drift: Vec<_> = (0..20)
+            .map(|second| fix(second, second as f64 * 0.2, 0.0, 12.0))
+            .collect();
+        let error = simplify_track(&drift, TrackSimplificationConfig::default()).unwrap_err();
+        assert_eq!(error.code, SessionTrackErrorCode::StationaryTrack);
+
+        let two_points = vec![fix(0, 0.0, 0.0, 10.0), fix(10, 50.0, 0.0, 10.0)];
+        let error = simplify_track(&two_points, TrackSimplificationConfig::default()).unwrap_err();
+        assert_eq!(error.code, SessionTrackErrorCode::DegenerateTrack);
+    }
+
+    #[tokio::test]
+    async fn session_tracks_are_read_from_the_data_collector() {
+        use axum::{extract::Path, http::StatusCode, routing::get, Json, Router};
+
+        let known = Uuid::new_v4();
+        let app = Router::new().route(
+            "/sessions/:session_id/track",
+            get(move |Path(session_id): Path<Uuid>| async move {
+                if session_id != known {
+                    return Err(StatusCode::NOT_FOUND);
+                }
+                Ok(Json(serde_json::json!([{
+                    "timestamp": "2026-06-01T09:00:00Z",
+                    "latitude": ORIGIN_LAT,
+                    "longitude": ORIGIN_LON,
+                    "altitude_m": 12.0,
+                    "capture_event": true,
+                }])))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let source =
+            SessionTrackSource::new(format!("http://{address}/"), HttpClientConfig::default())
+                .unwrap();
+
+        let track = source.fetch(known).await.unwrap().unwrap();
+        assert_eq!(
+            track,
+            vec![SessionTrackPoint {
+                capture_event: true,
+                ..fix(0, 0.0, 0.0, 12.0)
+            }]
+        );
+        assert!(source.fetch(Uuid::new_v4()).await.unwrap().is_none());
+    }
+}
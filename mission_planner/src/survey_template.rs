@@ -110,7 +110,7 @@ pub fn validate_plan_bounds(
             });
         }
 
-        if !point_is_inside_or_on_boundary(boundary, &waypoint.position) {
+        if !point_is_inside_or_on_boundary(boundary, &Point::from(waypoint.position)) {
             issues.push(PlanBoundsIssue {
                 waypoint_index: Some(index),
                 code: PlanBoundsIssueCode::OutsideGeofence,
@@ -0,0 +1,153 @@
+use geo::Point;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DemError {
+    TooSmall {
+        columns: usize,
+        rows: usize,
+    },
+    SizeMismatch {
+        columns: usize,
+        rows: usize,
+        expected: usize,
+        actual: usize,
+    },
+    InvalidSpacing {
+        spacing: f64,
+    },
+    NonFiniteElevation {
+        index: usize,
+    },
+}
+
+impl fmt::Display for DemError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooSmall { columns, rows } => write!(
+                formatter,
+                "DEM needs at least 2x2 elevation posts, got {columns}x{rows}"
+            ),
+            Self::SizeMismatch {
+                columns,
+                rows,
+                expected,
+                actual,
+            } => write!(
+                formatter,
+                "DEM has {actual} elevations but {columns}x{rows} posts need {expected}"
+            ),
+            Self::InvalidSpacing { spacing } => write!(
+                formatter,
+                "DEM post spacing must be positive and finite, got {spacing}"
+            ),
+            Self::NonFiniteElevation { index } => {
+                write!(formatter, "DEM elevation at index {index} is not finite")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DemError {}
+
+/// Digital elevation model sampled on a regular grid of posts in the same x/y
+/// frame as waypoint positions. Post `(column, row)` sits at
+/// `origin + (column, row) * spacing`, so row 0 is the southern edge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dem {
+    origin: Point<f64>,
+    spacing: f64,
+    columns: usize,
+    rows: usize,
+    elevations_m: Vec<f32>,
+}
+
+impl Dem {
+    /// `elevations_m` is row-major, starting at `origin`.
+    pub fn new(
+        origin: Point<f64>,
+        spacing: f64,
+        columns: usize,
+        rows: usize,
+        elevations_m: Vec<f32>,
+    ) -> Result<Self, DemError> {
+        if columns < 2 || rows < 2 {
+            return Err(DemError::TooSmall { columns, rows });
+        }
+        if elevations_m.len() != columns * rows {
+            return Err(DemError::SizeMismatch {
+                columns,
+                rows,
+                expected: columns * rows,
+                actual: elevations_m.len(),
+            });
+        }
+        if !spacing.is_finite() || spacing <= 0.0 {
+            return Err(DemError::InvalidSpacing { spacing });
+        }
+        if let Some(index) = elevations_m.iter().position(|value| !value.is_finite()) {
+            return Err(DemError::NonFiniteElevation { index });
+        }
+
+        Ok(Self {
+            origin,
+            spacing,
+            columns,
+            rows,
+            elevations_m,
+        })
+    }
+
+    /// Bilinearly interpolated elevation, or `None` outside the grid.
+    pub fn elevation_at(&self, position: &Point<f64>) -> Option<f32> {
+        let column = (position.x() - self.origin.x()) / self.spacing;
+        let row = (position.y() - self.origin.y()) / self.spacing;
+        let max_column = (self.columns - 1) as f64;
+        let max_row = (self.rows - 1) as f64;
+        if !(0.0..=max_column).contains(&column) || !(0.0..=max_row).contains(&row) {
+            return None;
+        }
+
+        // Step back from the far edge so the last cell still has four posts.
+        let left = (column.floor() as usize).min(self.columns - 2);
+        let bottom = (row.floor() as usize).min(self.rows - 2);
+        let fx = (column - left as f64) as f32;
+        let fy = (row - bottom as f64) as f32;
+        let post = |c: usize, r: usize| self.elevations_m[r * self.columns + c];
+
+        let south = post(left, bottom) * (1.0 - fx) + post(left + 1, bottom) * fx;
+        let north = post(left, bottom + 1) * (1.0 - fx) + post(left + 1, bottom + 1) * fx;
+        Some(south * (1.0 - fy) + north * fy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_between_posts_and_rejects_outside_points() {
+        let dem = Dem::new(
+            Point::new(10.0, 20.0),
+            1.0,
+            2,
+            2,
+            vec![100.0, 110.0, 120.0, 130.0],
+        )
+        .unwrap();
+
+        assert_eq!(dem.elevation_at(&Point::new(10.0, 20.0)), Some(100.0));
+        assert_eq!(dem.elevation_at(&Point::new(10.5, 20.5)), Some(115.0));
+        assert_eq!(dem.elevation_at(&Point::new(11.0, 21.0)), Some(130.0));
+        assert_eq!(dem.elevation_at(&Point::new(11.5, 20.5)), None);
+        assert_eq!(
+            Dem::new(Point::new(0.0, 0.0), 1.0, 2, 2, vec![0.0; 3]),
+            Err(DemError::SizeMismatch {
+                columns: 2,
+                rows: 2,
+                expected: 4,
+                actual: 3
+            })
+        );
+    }
+}
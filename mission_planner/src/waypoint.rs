@@ -1,14 +1,21 @@
 use chrono::{DateTime, Utc};
-use geo::Point;
 use serde::{Deserialize, Serialize};
+use shared::geospatial::{geo_point_xy, AltitudeReference, GeoPoint};
 use std::{collections::HashSet, fmt};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Waypoint {
     pub id: Uuid,
-    pub position: Point<f64>,
+    /// Stored and sent in the `{"x": lon, "y": lat}` layout of the former
+    /// `geo::Point` field.
+    #[serde(with = "geo_point_xy")]
+    pub position: GeoPoint,
     pub altitude_m: f32,
+    /// Datum `altitude_m` is measured from; missions stored before this field
+    /// existed were flown relative to home.
+    #[serde(default)]
+    pub altitude_reference: AltitudeReference,
     pub waypoint_type: WaypointType,
     pub actions: Vec<Action>,
     pub arrival_time: Option<DateTime<Utc>>,
@@ -49,6 +56,7 @@ pub enum WaypointValidationCode {
     ZeroLengthLeg,
     LegDistanceExceeded,
     AltitudeStepExceeded,
+    InvalidAction,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -90,6 +98,10 @@ pub enum Action {
     SetSpeed {
         speed_ms: f32,
     },
+    SetGimbal {
+        pitch_degrees: f32,
+        yaw_degrees: f32,
+    },
     Wait {
         duration_seconds: u32,
     },
@@ -99,6 +111,12 @@ pub enum Action {
     },
 }
 
+/// Fastest commanded ground speed accepted for a `SetSpeed` action.
+pub const MAX_ACTION_SPEED_MS: f32 = 30.0;
+/// Gimbal pitch is limited to straight down (-90) through straight up (+90).
+pub const MAX_GIMBAL_PITCH_DEGREES: f32 = 90.0;
+pub const MAX_GIMBAL_YAW_DEGREES: f32 = 180.0;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CameraSettings {
     pub iso: u32,
@@ -210,6 +228,7 @@ pub fn validate_waypoint_sanity(
     }
 
     let mut seen_ids = HashSet::new();
+    issues.extend(action_issues(waypoints));
     for (index, waypoint) in waypoints.iter().enumerate() {
         if !seen_ids.insert(waypoint.id) {
             issues.push(WaypointValidationIssue {
@@ -259,8 +278,8 @@ pub fn validate_waypoint_sanity(
     for (index, pair) in waypoints.windows(2).enumerate() {
         let from = &pair[0];
         let to = &pair[1];
-        let dx = to.position.x() - from.position.x();
-        let dy = to.position.y() - from.position.y();
+        let dx = to.position.lon - from.position.lon;
+        let dy = to.position.lat - from.position.lat;
         let horizontal_distance_m = (dx * dx + dy * dy).sqrt();
         let altitude_delta_m = (to.altitude_m - from.altitude_m).abs();
         let distance_3d_m =
@@ -302,12 +321,141 @@ pub fn validate_waypoint_sanity(
     }
 }
 
+/// Checks only the actions attached to each waypoint, so a mission can be
+/// rejected for nonsensical actions before it has a complete route.
+pub fn validate_waypoint_actions(waypoints: &[Waypoint]) -> Result<(), WaypointValidationError> {
+    let issues = action_issues(waypoints);
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(WaypointValidationError { issues })
+    }
+}
+
+fn action_issues(waypoints: &[Waypoint]) -> Vec<WaypointValidationIssue> {
+    waypoints
+        .iter()
+        .enumerate()
+        .flat_map(|(index, waypoint)| {
+            waypoint
+                .actions
+                .iter()
+                .enumerate()
+                .filter_map(move |(action_index, action)| {
+                    action
+                        .validate()
+                        .err()
+                        .map(|reason| WaypointValidationIssue {
+                            waypoint_index: Some(index),
+                            code: WaypointValidationCode::InvalidAction,
+                            message: format!("action {action_index}: {reason}"),
+                        })
+                })
+        })
+        .collect()
+}
+
+impl Action {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Action::TakePhoto {
+                camera_id,
+                settings,
+            } => {
+                require_camera_id(camera_id)?;
+                if settings.iso == 0 {
+                    return Err("photo ISO must be positive".to_string());
+                }
+                Ok(())
+            }
+            Action::StartVideo {
+                camera_id,
+                duration_seconds,
+            } => {
+                require_camera_id(camera_id)?;
+                require_duration("video", *duration_seconds)
+            }
+            Action::StopVideo { camera_id } => require_camera_id(camera_id),
+            Action::CollectLidar {
+                duration_seconds, ..
+            } => require_duration("LiDAR collection", *duration_seconds),
+            Action::CollectMultispectral {
+                bands,
+                exposure_settings,
+            } => {
+                if bands.is_empty() {
+                    return Err("multispectral collection needs at least one band".to_string());
+                }
+                if exposure_settings.exposure_time_ms == 0 {
+                    return Err("multispectral exposure time must be positive".to_string());
+                }
+                if !exposure_settings.gain.is_finite() || exposure_settings.gain <= 0.0 {
+                    return Err(format!(
+                        "multispectral gain {} must be positive and finite",
+                        exposure_settings.gain
+                    ));
+                }
+                Ok(())
+            }
+            Action::Hover { duration_seconds } => require_duration("hover", *duration_seconds),
+            Action::SetSpeed { speed_ms } => {
+                if !speed_ms.is_finite() || *speed_ms <= 0.0 || *speed_ms > MAX_ACTION_SPEED_MS {
+                    return Err(format!(
+                        "speed {speed_ms} m/s must be within (0, {MAX_ACTION_SPEED_MS}]"
+                    ));
+                }
+                Ok(())
+            }
+            Action::SetGimbal {
+                pitch_degrees,
+                yaw_degrees,
+            } => {
+                if !pitch_degrees.is_finite() || pitch_degrees.abs() > MAX_GIMBAL_PITCH_DEGREES {
+                    return Err(format!(
+                        "gimbal pitch {pitch_degrees} deg must be within ±{MAX_GIMBAL_PITCH_DEGREES}"
+                    ));
+                }
+                if !yaw_degrees.is_finite() || yaw_degrees.abs() > MAX_GIMBAL_YAW_DEGREES {
+                    return Err(format!(
+                        "gimbal yaw {yaw_degrees} deg must be within ±{MAX_GIMBAL_YAW_DEGREES}"
+                    ));
+                }
+                Ok(())
+            }
+            Action::Wait { .. } => Ok(()),
+            Action::Custom { action_type, .. } => {
+                if action_type.trim().is_empty() {
+                    return Err("custom action type must not be empty".to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn require_camera_id(camera_id: &str) -> Result<(), String> {
+    if camera_id.trim().is_empty() {
+        return Err("camera id must not be empty".to_string());
+    }
+    Ok(())
+}
+
+fn require_duration(action: &str, duration_seconds: u32) -> Result<(), String> {
+    if duration_seconds == 0 {
+        return Err(format!("{action} duration must be positive"));
+    }
+    Ok(())
+}
+
 impl Waypoint {
-    pub fn new(position: Point<f64>, altitude: f32, waypoint_type: WaypointType) -> Self {
+    /// Accepts a [`GeoPoint`] or a `geo::Point` (x = longitude, y = latitude);
+    /// bare coordinate tuples are rejected because their axis order is ambiguous.
+    pub fn new(position: impl Into<GeoPoint>, altitude: f32, waypoint_type: WaypointType) -> Self {
         Self {
             id: Uuid::new_v4(),
-            position,
+            position: position.into(),
             altitude_m: altitude,
+            altitude_reference: AltitudeReference::RelativeToHome,
             waypoint_type,
             actions: Vec::new(),
             arrival_time: None,
@@ -316,6 +464,11 @@ impl Waypoint {
         }
     }
 
+    pub fn with_altitude_reference(mut self, altitude_reference: AltitudeReference) -> Self {
+        self.altitude_reference = altitude_reference;
+        self
+    }
+
     pub fn with_action(mut self, action: Action) -> Self {
         self.actions.push(action);
         self
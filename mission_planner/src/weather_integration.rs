@@ -1,16 +1,31 @@
 use crate::WeatherConstraints;
-use anyhow::Result;
-use reqwest;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use futures_util::future::BoxFuture;
 use serde::{Deserialize, Serialize};
+use shared::{CircuitBreakerStatus, HttpClient, HttpClientConfig};
+use std::path::PathBuf;
+use std::time::Duration;
 
+pub const OPEN_WEATHER_URL: &str = "https://api.openweathermap.org/data/3.0/onecall";
+pub const OPEN_METEO_URL: &str = "https://api.open-meteo.com/v1/forecast";
+const OPEN_METEO_FIELDS: &str = "temperature_2m,relative_humidity_2m,wind_speed_10m,\
+wind_direction_10m,precipitation,visibility,pressure_msl,cloud_cover";
+/// OpenWeather leaves visibility out once it reaches its 10 km cap.
+const OPEN_WEATHER_MAX_VISIBILITY_M: f32 = 10_000.0;
+
+/// Conditions as every provider reports them once normalized; each field
+/// carries its unit in its name.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WeatherData {
     pub temperature_celsius: f32,
     pub humidity_percent: f32,
     pub wind_speed_ms: f32,
     pub wind_direction_degrees: f32,
+    /// Over the last hour.
     pub precipitation_mm: f32,
     pub visibility_m: f32,
+    /// Reduced to mean sea level.
     pub pressure_hpa: f32,
     pub cloud_cover_percent: f32,
 }
@@ -18,8 +33,11 @@ pub struct WeatherData {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WeatherForecast {
     pub current: WeatherData,
+    /// One entry per hour from the current hour.
     pub hourly: Vec<WeatherData>,
     pub alerts: Vec<WeatherAlert>,
+    pub source: WeatherSource,
+    pub forecast_provider: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,45 +55,401 @@ pub enum AlertSeverity {
     Critical,
 }
 
+/// Current conditions as one provider reported them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeatherObservation {
+    pub observed_at: DateTime<Utc>,
+    pub data: WeatherData,
+}
+
+/// Which provider supplied a reading, and when.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeatherSource {
+    pub provider: String,
+    pub observed_at: DateTime<Utc>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl WeatherSource {
+    pub fn age(&self, now: DateTime<Utc>) -> chrono::Duration {
+        now - self.observed_at
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderFailure {
+    pub provider: String,
+    pub error: String,
+}
+
+/// Current conditions from the first provider in the fallback chain that
+/// answered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeatherReport {
+    pub data: WeatherData,
+    pub source: WeatherSource,
+    /// Providers tried before `source.provider`, in order.
+    pub failed_providers: Vec<ProviderFailure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HourlyForecast {
+    pub provider: String,
+    /// One entry per hour from the current hour.
+    pub hourly: Vec<WeatherData>,
+}
+
+/// A source of current conditions and hourly forecasts.
+pub trait WeatherProvider: Send + Sync {
+    /// Name reported alongside the data; matches the config `kind`.
+    fn name(&self) -> &'static str;
+
+    fn get_current(&self, lat: f64, lon: f64) -> BoxFuture<'_, Result<WeatherObservation>>;
+
+    /// Up to `hours` hourly entries from the current hour.
+    fn get_hourly_forecast(
+        &self,
+        lat: f64,
+        lon: f64,
+        hours: u8,
+    ) -> BoxFuture<'_, Result<Vec<WeatherData>>>;
+}
+
+/// One provider in the fallback chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WeatherProviderConfig {
+    /// OpenWeather One Call API; needs an API key.
+    OpenWeather { api_key: String, base_url: String },
+    /// Open-Meteo forecast API; no key needed.
+    OpenMeteo { base_url: String },
+    /// A JSON file the operator edits by hand, for fields without internet.
+    Manual { path: PathBuf },
+    /// An on-farm weather station serving its latest reading over HTTP.
+    Station { url: String },
+    /// Random plausible conditions, for simulation only.
+    Simulated,
+}
+
+impl WeatherProviderConfig {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::OpenWeather { .. } => "open_weather",
+            Self::OpenMeteo { .. } => "open_meteo",
+            Self::Manual { .. } => "manual",
+            Self::Station { .. } => "station",
+            Self::Simulated => "simulated",
+        }
+    }
+
+    fn build(&self, client: &HttpClient) -> Box<dyn WeatherProvider> {
+        match self {
+            Self::OpenWeather { api_key, base_url } => Box::new(OpenWeatherProvider {
+                api_key: api_key.clone(),
+                base_url: base_url.clone(),
+                client: client.clone(),
+            }),
+            Self::OpenMeteo { base_url } => Box::new(OpenMeteoProvider {
+                base_url: base_url.clone(),
+                client: client.clone(),
+            }),
+            Self::Manual { path } => Box::new(ManualProvider { path: path.clone() }),
+            Self::Station { url } => Box::new(StationProvider {
+                url: url.clone(),
+                client: client.clone(),
+            }),
+            Self::Simulated => Box::new(SimulatedProvider),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeatherIntegrationConfig {
+    /// Tried in order until one answers.
+    pub providers: Vec<WeatherProviderConfig>,
+    /// Longest one provider may take before the next is tried.
+    pub provider_timeout_ms: u64,
+    /// Readings older than this draw a go/no-go warning.
+    pub max_data_age_secs: i64,
+    /// Retries and circuit breaking for the HTTP providers; each attempt is
+    /// also held to `provider_timeout_ms`.
+    #[serde(default)]
+    pub http: HttpClientConfig,
+}
+
+impl Default for WeatherIntegrationConfig {
+    fn default() -> Self {
+        Self {
+            providers: vec![WeatherProviderConfig::OpenMeteo {
+                base_url: OPEN_METEO_URL.to_string(),
+            }],
+            provider_timeout_ms: 5_000,
+            max_data_age_secs: 1_800,
+            http: HttpClientConfig::default(),
+        }
+    }
+}
+
+impl WeatherIntegrationConfig {
+    /// Reads `WEATHER_PROVIDERS` (comma-separated kinds in fallback order,
+    /// e.g. `station,open_meteo,manual`), `WEATHER_PROVIDER_TIMEOUT_MS` and
+    /// `WEATHER_MAX_AGE_SECS`. Each provider takes its settings from
+    /// `WEATHER_API_KEY` and `WEATHER_OPEN_WEATHER_URL`,
+    /// `WEATHER_OPEN_METEO_URL`, `WEATHER_MANUAL_PATH` or
+    /// `WEATHER_STATION_URL`.
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let env = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        let required = |key: &str, kind: &str| {
+            env(key).with_context(|| format!("{key} must be set for the {kind} weather provider"))
+        };
+
+        let providers = match env("WEATHER_PROVIDERS") {
+            Some(kinds) => kinds
+                .split(',')
+                .map(str::trim)
+                .filter(|kind| !kind.is_empty())
+                .map(|kind| {
+                    Ok(match kind {
+                        "open_weather" => WeatherProviderConfig::OpenWeather {
+                            api_key: required("WEATHER_API_KEY", kind)?,
+                            base_url: env("WEATHER_OPEN_WEATHER_URL")
+                                .unwrap_or_else(|| OPEN_WEATHER_URL.to_string()),
+                        },
+                        "open_meteo" => WeatherProviderConfig::OpenMeteo {
+                            base_url: env("WEATHER_OPEN_METEO_URL")
+                                .unwrap_or_else(|| OPEN_METEO_URL.to_string()),
+                        },
+                        "manual" => WeatherProviderConfig::Manual {
+                            path: required("WEATHER_MANUAL_PATH", kind)?.into(),
+                        },
+                        "station" => WeatherProviderConfig::Station {
+                            url: required("WEATHER_STATION_URL", kind)?,
+                        },
+                        "simulated" => WeatherProviderConfig::Simulated,
+                        other => bail!("unknown weather provider '{other}'"),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => defaults.providers,
+        };
+        let parse = |key: &str| -> Result<Option<i64>> {
+            env(key)
+                .map(|value| {
+                    value
+                        .trim()
+                        .parse::<i64>()
+                        .with_context(|| format!("{key} must be a whole number, got '{value}'"))
+                })
+                .transpose()
+        };
+
+        let config = Self {
+            providers,
+            provider_timeout_ms: match parse("WEATHER_PROVIDER_TIMEOUT_MS")? {
+                Some(value) => u64::try_from(value)
+                    .context("WEATHER_PROVIDER_TIMEOUT_MS must not be negative")?,
+                None => defaults.provider_timeout_ms,
+            },
+            max_data_age_secs: parse("WEATHER_MAX_AGE_SECS")?.unwrap_or(defaults.max_data_age_secs),
+            http: defaults.http,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.providers.is_empty() {
+            bail!("at least one weather provider must be configured");
+        }
+        if self.provider_timeout_ms == 0 {
+            bail!("weather provider timeout must be positive");
+        }
+        if self.max_data_age_secs <= 0 {
+            bail!("weather max data age must be positive");
+        }
+        self.http.validate()?;
+        Ok(())
+    }
+}
+
 pub struct WeatherIntegration {
-    api_key: Option<String>,
-    client: reqwest::Client,
+    providers: Vec<Box<dyn WeatherProvider>>,
+    provider_timeout: Duration,
+    max_data_age: chrono::Duration,
+    /// Shared by the configured HTTP providers; `None` for
+    /// [`Self::with_providers`].
+    http: Option<HttpClient>,
 }
 
 impl WeatherIntegration {
+    /// Open-Meteo, tried after OpenWeather when an API key is given.
     pub fn new(api_key: Option<String>) -> Self {
+        let mut config = WeatherIntegrationConfig::default();
+        if let Some(api_key) = api_key {
+            config.providers.insert(
+                0,
+                WeatherProviderConfig::OpenWeather {
+                    api_key,
+                    base_url: OPEN_WEATHER_URL.to_string(),
+                },
+            );
+        }
+        Self::from_config(&config).expect("default weather config should be valid")
+    }
+
+    pub fn from_config(config: &WeatherIntegrationConfig) -> Result<Self> {
+        config.validate()?;
+        let provider_timeout = Duration::from_millis(config.provider_timeout_ms);
+        // Avoid environment/system proxy discovery so tests remain deterministic.
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .build()
+            .context("weather client should build")?;
+        let mut http_config = config.http.clone();
+        http_config.timeout_ms = http_config.timeout_ms.min(config.provider_timeout_ms);
+        let http = HttpClient::with_client(client, http_config)?;
+        let providers = config
+            .providers
+            .iter()
+            .map(|provider| provider.build(&http))
+            .collect();
+        Ok(Self {
+            providers,
+            provider_timeout,
+            max_data_age: chrono::Duration::seconds(config.max_data_age_secs),
+            http: Some(http),
+        })
+    }
+
+    /// Uses `providers` as the fallback chain, in order.
+    pub fn with_providers(
+        providers: Vec<Box<dyn WeatherProvider>>,
+        provider_timeout: Duration,
+    ) -> Self {
+        let defaults = WeatherIntegrationConfig::default();
         Self {
-            api_key,
-            // Avoid environment/system proxy discovery so tests remain deterministic.
-            client: reqwest::Client::builder()
-                .no_proxy()
-                .build()
-                .expect("weather client should build"),
+            providers,
+            provider_timeout,
+            max_data_age: chrono::Duration::seconds(defaults.max_data_age_secs),
+            http: None,
         }
     }
 
+    pub fn with_max_data_age(mut self, max_data_age: chrono::Duration) -> Self {
+        self.max_data_age = max_data_age;
+        self
+    }
+
+    pub fn provider_names(&self) -> Vec<&'static str> {
+        self.providers
+            .iter()
+            .map(|provider| provider.name())
+            .collect()
+    }
+
+    pub fn max_data_age(&self) -> chrono::Duration {
+        self.max_data_age
+    }
+
+    /// Breaker state for each provider host called so far.
+    pub fn circuit_breakers(&self) -> Vec<CircuitBreakerStatus> {
+        self.http
+            .as_ref()
+            .map(HttpClient::circuit_breakers)
+            .unwrap_or_default()
+    }
+
+    /// Current conditions from the first provider that answers within the
+    /// provider timeout.
+    pub async fn current_report(&self, lat: f64, lon: f64) -> Result<WeatherReport> {
+        let mut failed_providers = Vec::new();
+        for provider in &self.providers {
+            match self.within_timeout(provider.get_current(lat, lon)).await {
+                Ok(observation) => {
+                    return Ok(WeatherReport {
+                        data: observation.data,
+                        source: WeatherSource {
+                            provider: provider.name().to_string(),
+                            observed_at: observation.observed_at,
+                            fetched_at: Utc::now(),
+                        },
+                        failed_providers,
+                    })
+                }
+                Err(error) => failed_providers.push(record_failure(provider.name(), error)),
+            }
+        }
+        Err(all_providers_failed(&failed_providers))
+    }
+
+    /// Hourly forecast from the first provider that answers within the
+    /// provider timeout.
+    pub async fn hourly_forecast(&self, lat: f64, lon: f64, hours: u8) -> Result<HourlyForecast> {
+        let mut failed_providers = Vec::new();
+        for provider in &self.providers {
+            match self
+                .within_timeout(provider.get_hourly_forecast(lat, lon, hours))
+                .await
+            {
+                Ok(hourly) => {
+                    return Ok(HourlyForecast {
+                        provider: provider.name().to_string(),
+                        hourly,
+                    })
+                }
+                Err(error) => failed_providers.push(record_failure(provider.name(), error)),
+            }
+        }
+        Err(all_providers_failed(&failed_providers))
+    }
+
     pub async fn get_current_weather(&self, lat: f64, lon: f64) -> Result<WeatherData> {
-        // In a real implementation, this would call a weather API
-        // For simulation, return mock data
-        Ok(self.generate_mock_weather())
+        Ok(self.current_report(lat, lon).await?.data)
     }
 
     pub async fn get_forecast(&self, lat: f64, lon: f64, hours: u8) -> Result<WeatherForecast> {
-        let current = self.get_current_weather(lat, lon).await?;
-
-        // Generate hourly forecast
-        let hourly = (0..hours).map(|_| self.generate_mock_weather()).collect();
+        let current = self.current_report(lat, lon).await?;
+        let forecast = self.hourly_forecast(lat, lon, hours).await?;
 
         // Check for weather alerts
-        let alerts = self.check_weather_alerts(&current);
+        let alerts = self.check_weather_alerts(&current.data);
 
         Ok(WeatherForecast {
-            current,
-            hourly,
+            current: current.data,
+            hourly: forecast.hourly,
             alerts,
+            source: current.source,
+            forecast_provider: forecast.provider,
         })
     }
 
+    /// Go/no-go on a provider report: the checks of
+    /// [`Self::check_flight_conditions`], plus the provider and data age, and
+    /// a warning once the reading is older than the configured maximum.
+    pub fn check_flight_report(
+        &self,
+        report: &WeatherReport,
+        constraints: &WeatherConstraints,
+    ) -> FlightConditionResult {
+        let mut result = self.check_flight_conditions(&report.data, constraints);
+        let age = report.source.age(Utc::now());
+        if age > self.max_data_age {
+            result.warnings.push(format!(
+                "Weather data from {} is {} minutes old",
+                report.source.provider,
+                age.num_minutes()
+            ));
+        }
+        result.source = Some(report.source.clone());
+        result.data_age_secs = Some(age.num_seconds());
+        result
+    }
+
     pub fn check_flight_conditions(
         &self,
         weather: &WeatherData,
@@ -132,23 +506,15 @@ impl WeatherIntegration {
             issues,
             warnings,
             weather_score: self.calculate_weather_score(weather, constraints),
+            source: None,
+            data_age_secs: None,
         }
     }
 
-    fn generate_mock_weather(&self) -> WeatherData {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-
-        WeatherData {
-            temperature_celsius: rng.gen_range(15.0..25.0),
-            humidity_percent: rng.gen_range(40.0..70.0),
-            wind_speed_ms: rng.gen_range(2.0..12.0),
-            wind_direction_degrees: rng.gen_range(0.0..360.0),
-            precipitation_mm: rng.gen_range(0.0..2.0),
-            visibility_m: rng.gen_range(5000.0..15000.0),
-            pressure_hpa: rng.gen_range(1010.0..1025.0),
-            cloud_cover_percent: rng.gen_range(10.0..80.0),
-        }
+    async fn within_timeout<T>(&self, call: BoxFuture<'_, Result<T>>) -> Result<T> {
+        tokio::time::timeout(self.provider_timeout, call)
+            .await
+            .map_err(|_| anyhow!("timed out after {} ms", self.provider_timeout.as_millis()))?
     }
 
     fn check_weather_alerts(&self, weather: &WeatherData) -> Vec<WeatherAlert> {
@@ -217,17 +583,446 @@ impl WeatherIntegration {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlightConditionResult {
     pub flight_safe: bool,
     pub issues: Vec<String>,
     pub warnings: Vec<String>,
     pub weather_score: f32,
+    /// Set when the check ran on a provider report.
+    pub source: Option<WeatherSource>,
+    pub data_age_secs: Option<i64>,
+}
+
+fn record_failure(provider: &'static str, error: anyhow::Error) -> ProviderFailure {
+    tracing::warn!("Weather provider {provider} failed: {error:#}");
+    ProviderFailure {
+        provider: provider.to_string(),
+        error: format!("{error:#}"),
+    }
+}
+
+fn all_providers_failed(failures: &[ProviderFailure]) -> anyhow::Error {
+    let tried = failures
+        .iter()
+        .map(|failure| format!("{}: {}", failure.provider, failure.error))
+        .collect::<Vec<_>>()
+        .join("; ");
+    anyhow!("no weather provider answered ({tried})")
+}
+
+fn unix_time(seconds: i64) -> Result<DateTime<Utc>> {
+    Utc.timestamp_opt(seconds, 0)
+        .single()
+        .with_context(|| format!("invalid timestamp {seconds}"))
+}
+
+/// OpenWeather One Call API, queried in metric units.
+pub struct OpenWeatherProvider {
+    api_key: String,
+    base_url: String,
+    client: HttpClient,
+}
+
+#[derive(Debug, Deserialize)]
+struct OneCallResponse {
+    current: OneCallEntry,
+    #[serde(default)]
+    hourly: Vec<OneCallEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OneCallEntry {
+    dt: i64,
+    temp: f32,
+    humidity: f32,
+    pressure: f32,
+    wind_speed: f32,
+    wind_deg: f32,
+    #[serde(default)]
+    clouds: f32,
+    visibility: Option<f32>,
+    rain: Option<OneCallPrecipitation>,
+    snow: Option<OneCallPrecipitation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OneCallPrecipitation {
+    #[serde(rename = "1h", default)]
+    last_hour_mm: f32,
+}
+
+impl OneCallEntry {
+    fn into_weather(self) -> WeatherData {
+        let precipitation =
+            |volume: Option<OneCallPrecipitation>| volume.map_or(0.0, |volume| volume.last_hour_mm);
+        WeatherData {
+            temperature_celsius: self.temp,
+            humidity_percent: self.humidity,
+            wind_speed_ms: self.wind_speed,
+            wind_direction_degrees: self.wind_deg,
+            precipitation_mm: precipitation(self.rain) + precipitation(self.snow),
+            visibility_m: self.visibility.unwrap_or(OPEN_WEATHER_MAX_VISIBILITY_M),
+            pressure_hpa: self.pressure,
+            cloud_cover_percent: self.clouds,
+        }
+    }
+}
+
+impl OpenWeatherProvider {
+    async fn fetch(&self, lat: f64, lon: f64, exclude: &str) -> Result<OneCallResponse> {
+        let request = self.client.get(&self.base_url).query(&[
+            ("lat", lat.to_string()),
+            ("lon", lon.to_string()),
+            ("appid", self.api_key.clone()),
+            ("units", "metric".to_string()),
+            ("exclude", exclude.to_string()),
+        ]);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+}
+
+impl WeatherProvider for OpenWeatherProvider {
+    fn name(&self) -> &'static str {
+        "open_weather"
+    }
+
+    fn get_current(&self, lat: f64, lon: f64) -> BoxFuture<'_, Result<WeatherObservation>> {
+        Box::pin(async move {
+            let current = self
+                .fetch(lat, lon, "minutely,hourly,daily,alerts")
+                .await?
+                .current;
+            Ok(WeatherObservation {
+                observed_at: unix_time(current.dt)?,
+                data: current.into_weather(),
+            })
+        })
+    }
+
+    fn get_hourly_forecast(
+        &self,
+        lat: f64,
+        lon: f64,
+        hours: u8,
+    ) -> BoxFuture<'_, Result<Vec<WeatherData>>> {
+        Box::pin(async move {
+            let response = self.fetch(lat, lon, "minutely,daily,alerts").await?;
+            Ok(response
+                .hourly
+                .into_iter()
+                .take(usize::from(hours))
+                .map(OneCallEntry::into_weather)
+                .collect())
+        })
+    }
+}
+
+/// Open-Meteo forecast API, asked for metres per second and GMT unix times.
+pub struct OpenMeteoProvider {
+    base_url: String,
+    client: HttpClient,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoResponse {
+    current: Option<OpenMeteoCurrent>,
+    hourly: Option<OpenMeteoHourly>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoCurrent {
+    time: i64,
+    temperature_2m: f32,
+    relative_humidity_2m: f32,
+    wind_speed_10m: f32,
+    wind_direction_10m: f32,
+    precipitation: f32,
+    visibility: f32,
+    pressure_msl: f32,
+    cloud_cover: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoHourly {
+    temperature_2m: Vec<f32>,
+    relative_humidity_2m: Vec<f32>,
+    wind_speed_10m: Vec<f32>,
+    wind_direction_10m: Vec<f32>,
+    precipitation: Vec<f32>,
+    visibility: Vec<f32>,
+    pressure_msl: Vec<f32>,
+    cloud_cover: Vec<f32>,
+}
+
+impl OpenMeteoProvider {
+    async fn fetch(
+        &self,
+        lat: f64,
+        lon: f64,
+        series: &str,
+        hours: u8,
+    ) -> Result<OpenMeteoResponse> {
+        let request = self.client.get(&self.base_url).query(&[
+            ("latitude", lat.to_string()),
+            ("longitude", lon.to_string()),
+            (series, OPEN_METEO_FIELDS.to_string()),
+            ("forecast_hours", hours.to_string()),
+            ("wind_speed_unit", "ms".to_string()),
+            ("timeformat", "unixtime".to_string()),
+            ("timezone", "GMT".to_string()),
+        ]);
+        Ok(self.client.send(request).await?.json().await?)
+    }
+}
+
+impl WeatherProvider for OpenMeteoProvider {
+    fn name(&self) -> &'static str {
+        "open_meteo"
+    }
+
+    fn get_current(&self, lat: f64, lon: f64) -> BoxFuture<'_, Result<WeatherObservation>> {
+        Box::pin(async move {
+            let current = self
+                .fetch(lat, lon, "current", 1)
+                .await?
+                .current
+                .context("Open-Meteo response has no current conditions")?;
+            Ok(WeatherObservation {
+                observed_at: unix_time(current.time)?,
+                data: WeatherData {
+                    temperature_celsius: current.temperature_2m,
+                    humidity_percent: current.relative_humidity_2m,
+                    wind_speed_ms: current.wind_speed_10m,
+                    wind_direction_degrees: current.wind_direction_10m,
+                    precipitation_mm: current.precipitation,
+                    visibility_m: current.visibility,
+                    pressure_hpa: current.pressure_msl,
+                    cloud_cover_percent: current.cloud_cover,
+                },
+            })
+        })
+    }
+
+    fn get_hourly_forecast(
+        &self,
+        lat: f64,
+        lon: f64,
+        hours: u8,
+    ) -> BoxFuture<'_, Result<Vec<WeatherData>>> {
+        Box::pin(async move {
+            let hourly = self
+                .fetch(lat, lon, "hourly", hours)
+                .await?
+                .hourly
+                .context("Open-Meteo response has no hourly forecast")?;
+            let count = [
+                hourly.temperature_2m.len(),
+                hourly.relative_humidity_2m.len(),
+                hourly.wind_speed_10m.len(),
+                hourly.wind_direction_10m.len(),
+                hourly.precipitation.len(),
+                hourly.visibility.len(),
+                hourly.pressure_msl.len(),
+                hourly.cloud_cover.len(),
+            ]
+            .into_iter()
+            .min()
+            .unwrap_or(0)
+            .min(usize::from(hours));
+            Ok((0..count)
+                .map(|hour| WeatherData {
+                    temperature_celsius: hourly.temperature_2m[hour],
+                    humidity_percent: hourly.relative_humidity_2m[hour],
+                    wind_speed_ms: hourly.wind_speed_10m[hour],
+                    wind_direction_degrees: hourly.wind_direction_10m[hour],
+                    precipitation_mm: hourly.precipitation[hour],
+                    visibility_m: hourly.visibility[hour],
+                    pressure_hpa: hourly.pressure_msl[hour],
+                    cloud_cover_percent: hourly.cloud_cover[hour],
+                })
+                .collect())
+        })
+    }
+}
+
+/// Layout of the file read by [`ManualProvider`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManualWeatherFile {
+    /// When the operator took the reading; reported as the data's age.
+    pub observed_at: DateTime<Utc>,
+    pub current: WeatherData,
+    /// One entry per hour from the current hour.
+    #[serde(default)]
+    pub hourly: Vec<WeatherData>,
+}
+
+/// Reads conditions from a local JSON file on every call, so edits take
+/// effect without a restart.
+pub struct ManualProvider {
+    path: PathBuf,
+}
+
+impl ManualProvider {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    async fn read(&self) -> Result<ManualWeatherFile> {
+        let contents = tokio::fs::read_to_string(&self.path)
+            .await
+            .with_context(|| format!("failed to read {}", self.path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse {}", self.path.display()))
+    }
+}
+
+impl WeatherProvider for ManualProvider {
+    fn name(&self) -> &'static str {
+        "manual"
+    }
+
+    fn get_current(&self, _lat: f64, _lon: f64) -> BoxFuture<'_, Result<WeatherObservation>> {
+        Box::pin(async move {
+            let file = self.read().await?;
+            Ok(WeatherObservation {
+                observed_at: file.observed_at,
+                data: file.current,
+            })
+        })
+    }
+
+    fn get_hourly_forecast(
+        &self,
+        _lat: f64,
+        _lon: f64,
+        hours: u8,
+    ) -> BoxFuture<'_, Result<Vec<WeatherData>>> {
+        Box::pin(async move {
+            let mut hourly = self.read().await?.hourly;
+            hourly.truncate(usize::from(hours));
+            Ok(hourly)
+        })
+    }
+}
+
+/// Latest reading served by a station; stations without a clock may leave
+/// out `observed_at`, in which case the fetch time is used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StationReading {
+    pub observed_at: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub data: WeatherData,
+}
+
+/// Polls a local weather station's HTTP endpoint. Stations only observe, so
+/// forecasts always fall through to the next provider.
+pub struct StationProvider {
+    url: String,
+    client: HttpClient,
+}
+
+impl WeatherProvider for StationProvider {
+    fn name(&self) -> &'static str {
+        "station"
+    }
+
+    fn get_current(&self, _lat: f64, _lon: f64) -> BoxFuture<'_, Result<WeatherObservation>> {
+        Box::pin(async move {
+            let reading: StationReading = self
+                .client
+                .send(self.client.get(&self.url))
+                .await?
+                .json()
+                .await?;
+            Ok(WeatherObservation {
+                observed_at: reading.observed_at.unwrap_or_else(Utc::now),
+                data: reading.data,
+            })
+        })
+    }
+
+    fn get_hourly_forecast(
+        &self,
+        _lat: f64,
+        _lon: f64,
+        _hours: u8,
+    ) -> BoxFuture<'_, Result<Vec<WeatherData>>> {
+        Box::pin(async { bail!("weather stations do not forecast") })
+    }
+}
+
+/// Random plausible conditions, for simulation runs.
+pub struct SimulatedProvider;
+
+impl SimulatedProvider {
+    fn generate(&self) -> WeatherData {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
+
+        WeatherData {
+            temperature_celsius: rng.gen_range(15.0..25.0),
+            humidity_percent: rng.gen_range(40.0..70.0),
+            wind_speed_ms: rng.gen_range(2.0..12.0),
+            wind_direction_degrees: rng.gen_range(0.0..360.0),
+            precipitation_mm: rng.gen_range(0.0..2.0),
+            visibility_m: rng.gen_range(5000.0..15000.0),
+            pressure_hpa: rng.gen_range(1010.0..1025.0),
+            cloud_cover_percent: rng.gen_range(10.0..80.0),
+        }
+    }
+}
+
+impl WeatherProvider for SimulatedProvider {
+    fn name(&self) -> &'static str {
+        "simulated"
+    }
+
+    fn get_current(&self, _lat: f64, _lon: f64) -> BoxFuture<'_, Result<WeatherObservation>> {
+        Box::pin(async move {
+            Ok(WeatherObservation {
+                observed_at: Utc::now(),
+                data: self.generate(),
+            })
+        })
+    }
+
+    fn get_hourly_forecast(
+        &self,
+        _lat: f64,
+        _lon: f64,
+        hours: u8,
+    ) -> BoxFuture<'_, Result<Vec<WeatherData>>> {
+        Box::pin(async move { Ok((0..hours).map(|_| self.generate()).collect()) })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use axum::{extract::Query, routing::get, Json, Router};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const MANUAL_FIXTURE: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/manual_weather.json");
+
+    async fn serve(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{address}")
+    }
+
+    fn integration(providers: Vec<WeatherProviderConfig>, timeout_ms: u64) -> WeatherIntegration {
+        WeatherIntegration::from_config(&WeatherIntegrationConfig {
+            providers,
+            provider_timeout_ms: timeout_ms,
+            ..WeatherIntegrationConfig::default()
+        })
+        .unwrap()
+    }
 
     #[test]
     fn test_weather_check() {
@@ -272,4 +1067,236 @@ mod tests {
         assert!(!result.issues.is_empty());
         assert!(result.weather_score < 50.0);
     }
+
+    #[tokio::test]
+    async fn api_providers_normalize_into_weather_data() {
+        let base = serve(
+            Router::new()
+                .route(
+                    "/onecall",
+                    get(|Query(query): Query<HashMap<String, String>>| async move {
+                        assert_eq!(query["appid"], "secret");
+                        assert_eq!(query["units"], "metric");
+                        Json(serde_json::json!({
+                            "current": {
+                                "dt": 1_746_340_200, "temp": 21.5, "humidity": 55,
+                                "pressure": 1012, "wind_speed": 4.2, "wind_deg": 90,
+                                "clouds": 20, "rain": { "1h": 0.3 }
+                            },
+                            "hourly": [
+                                { "dt": 1_746_342_000, "temp": 22.0, "humidity": 50,
+                                  "pressure": 1012, "wind_speed": 5.0, "wind_deg": 100,
+                                  "clouds": 10, "visibility": 8000 },
+                                { "dt": 1_746_345_600, "temp": 23.0, "humidity": 48,
+                                  "pressure": 1011, "wind_speed": 6.0, "wind_deg": 110,
+                                  "clouds": 5, "visibility": 9000 }
+                            ]
+                        }))
+                    }),
+                )
+                .route(
+                    "/forecast",
+                    get(|Query(query): Query<HashMap<String, String>>| async move {
+                        assert_eq!(query["wind_speed_unit"], "ms");
+                        Json(serde_json::json!({
+                            "current": {
+                                "time": 1_746_340_200, "temperature_2m": 19.0,
+                                "relative_humidity_2m": 61.0, "wind_speed_10m": 3.1,
+                                "wind_direction_10m": 270.0, "precipitation": 0.0,
+                                "visibility": 24000.0, "pressure_msl": 1018.2,
+                                "cloud_cover": 75.0
+                            },
+                            "hourly": {
+                                "time": [1_746_338_400, 1_746_342_000, 1_746_345_600],
+                                "temperature_2m": [19.0, 20.0, 21.0],
+                                "relative_humidity_2m": [60.0, 58.0, 55.0],
+                                "wind_speed_10m": [3.0, 3.5, 4.0],
+                                "wind_direction_10m": [270.0, 275.0, 280.0],
+                                "precipitation": [0.0, 0.2, 0.0],
+                                "visibility": [24000.0, 20000.0, 18000.0],
+                                "pressure_msl": [1018.0, 1017.5, 1017.0],
+                                "cloud_cover": [75.0, 80.0, 60.0]
+                            }
+                        }))
+                    }),
+                ),
+        )
+        .await;
+
+        let open_weather = integration(
+            vec![WeatherProviderConfig::OpenWeather {
+                api_key: "secret".to_string(),
+                base_url: format!("{base}/onecall"),
+            }],
+            2_000,
+        );
+        let report = open_weather.current_report(11.0, 77.0).await.unwrap();
+        assert_eq!(report.source.provider, "open_weather");
+        assert_eq!(report.source.observed_at, unix_time(1_746_340_200).unwrap());
+        assert_eq!(report.data.temperature_celsius, 21.5);
+        assert_eq!(report.data.precipitation_mm, 0.3);
+        // Visibility at or beyond the cap is left out of the response.
+        assert_eq!(report.data.visibility_m, OPEN_WEATHER_MAX_VISIBILITY_M);
+        let hourly = open_weather.hourly_forecast(11.0, 77.0, 1).await.unwrap();
+        assert_eq!(hourly.hourly.len(), 1);
+        assert_eq!(hourly.hourly[0].visibility_m, 8000.0);
+
+        let open_meteo = integration(
+            vec![WeatherProviderConfig::OpenMeteo {
+                base_url: format!("{base}/forecast"),
+            }],
+            2_000,
+        );
+        let forecast = open_meteo.get_forecast(11.0, 77.0, 2).await.unwrap();
+        assert_eq!(forecast.source.provider, "open_meteo");
+        assert_eq!(forecast.forecast_provider, "open_meteo");
+        assert_eq!(forecast.current.wind_speed_ms, 3.1);
+        assert_eq!(forecast.current.pressure_hpa, 1018.2);
+        assert_eq!(forecast.hourly.len(), 2);
+        assert_eq!(forecast.hourly[1].precipitation_mm, 0.2);
+    }
+
+    #[tokio::test]
+    async fn manual_provider_reads_the_operator_fixture() {
+        let integration = WeatherIntegration::with_providers(
+            vec![Box::new(ManualProvider::new(MANUAL_FIXTURE))],
+            Duration::from_secs(1),
+        );
+
+        let report = integration.current_report(11.0, 77.0).await.unwrap();
+        assert_eq!(report.source.provider, "manual");
+        assert_eq!(
+            report.source.observed_at,
+            "2025-05-04T06:30:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(report.data.wind_speed_ms, 3.4);
+        assert!(report.failed_providers.is_empty());
+
+        let hourly = integration.hourly_forecast(11.0, 77.0, 12).await.unwrap();
+        assert_eq!(hourly.hourly.len(), 2);
+        assert_eq!(hourly.hourly[1].precipitation_mm, 0.4);
+
+        let missing = WeatherIntegration::with_providers(
+            vec![Box::new(ManualProvider::new("/nonexistent/weather.json"))],
+            Duration::from_secs(1),
+        );
+        let error = missing.current_report(11.0, 77.0).await.unwrap_err();
+        assert!(error.to_string().contains("manual: failed to read"));
+    }
+
+    #[tokio::test]
+    async fn station_calls_are_retried_and_a_dead_station_trips_its_breaker() {
+        let failures_left = Arc::new(AtomicUsize::new(1));
+        let hits = Arc::new(AtomicUsize::new(0));
+        let base = serve(Router::new().route(
+            "/station",
+            get({
+                let (failures_left, hits) = (failures_left.clone(), hits.clone());
+                move || async move {
+                    hits.fetch_add(1, Ordering::SeqCst);
+                    let failing = failures_left
+                        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                            left.checked_sub(1)
+                        })
+                        .is_ok();
+                    if failing {
+                        return Err(axum::http::StatusCode::SERVICE_UNAVAILABLE);
+                    }
+                    Ok(Json(serde_json::json!({
+                        "temperature_celsius": 24.0, "humidity_percent": 40.0,
+                        "wind_speed_ms": 2.0, "wind_direction_degrees": 45.0,
+                        "precipitation_mm": 0.0, "visibility_m": 10000.0,
+                        "pressure_hpa": 1011.0, "cloud_cover_percent": 5.0
+                    })))
+                }
+            }),
+        ))
+        .await;
+        let integration = WeatherIntegration::from_config(&WeatherIntegrationConfig {
+            providers: vec![
+                WeatherProviderConfig::Station {
+                    url: format!("{base}/station"),
+                },
+                WeatherProviderConfig::Manual {
+                    path: MANUAL_FIXTURE.into(),
+                },
+            ],
+            provider_timeout_ms: 2_000,
+            http: HttpClientConfig {
+                retry: shared::RetryPolicy {
+                    max_attempts: 2,
+                    initial_backoff_ms: 1,
+                    ..shared::RetryPolicy::default()
+                },
+                circuit_breaker: shared::CircuitBreakerPolicy {
+                    failure_threshold: 2,
+                    open_ms: 60_000,
+                },
+                ..HttpClientConfig::default()
+            },
+            ..WeatherIntegrationConfig::default()
+        })
+        .unwrap();
+
+        let report = integration.current_report(11.0, 77.0).await.unwrap();
+        assert_eq!(report.source.provider, "station");
+        assert!(report.failed_providers.is_empty());
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        failures_left.store(usize::MAX, Ordering::SeqCst);
+        let report = integration.current_report(11.0, 77.0).await.unwrap();
+        assert_eq!(report.source.provider, "manual");
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+        let breakers = integration.circuit_breakers();
+        assert_eq!(breakers.len(), 1);
+        assert_eq!(breakers[0].state, shared::CircuitState::Open);
+
+        // The open breaker skips the station without calling it.
+        let report = integration.current_report(11.0, 77.0).await.unwrap();
+        assert_eq!(report.source.provider, "manual");
+        assert!(report.failed_providers[0].error.contains("circuit breaker"));
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn fallback_chain_skips_a_primary_that_times_out() {
+        let base = serve(Router::new().route(
+            "/station",
+            get(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                "{}"
+            }),
+        ))
+        .await;
+        let integration = integration(
+            vec![
+                WeatherProviderConfig::Station {
+                    url: format!("{base}/station"),
+                },
+                WeatherProviderConfig::Manual {
+                    path: MANUAL_FIXTURE.into(),
+                },
+            ],
+            200,
+        );
+
+        let report = integration.current_report(11.0, 77.0).await.unwrap();
+        assert_eq!(report.source.provider, "manual");
+        assert_eq!(report.failed_providers.len(), 1);
+        assert_eq!(report.failed_providers[0].provider, "station");
+
+        // The go/no-go check names the provider and flags the old reading.
+        let result = integration.check_flight_report(&report, &WeatherConstraints::default());
+        assert!(result.flight_safe);
+        assert_eq!(result.source, Some(report.source.clone()));
+        assert!(result.data_age_secs.unwrap() > integration.max_data_age().num_seconds());
+        assert!(result
+            .warnings
+            .iter()
+            .any(|warning| warning.starts_with("Weather data from manual")));
+
+        let forecast = integration.get_forecast(11.0, 77.0, 6).await.unwrap();
+        assert_eq!(forecast.forecast_provider, "manual");
+        assert_eq!(forecast.hourly.len(), 2);
+    }
 }
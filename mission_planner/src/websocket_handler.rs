@@ -8,12 +8,14 @@ use axum::{
 };
 use futures_util::{sink::SinkExt, stream::StreamExt};
 use serde::{Deserialize, Serialize};
+use shared::mission_sync::MissionSyncProgress;
 use std::sync::Arc;
 use tokio::sync::{broadcast, Mutex};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::mavlink_integration::MAVLinkConverter;
+use crate::mission_sync_client::MissionControlLink;
 use crate::{
     Mission, MissionPlannerService, MissionTelemetrySample, TelemetryFreshness, TelemetryHistory,
 };
@@ -47,6 +49,10 @@ pub enum WebSocketMessage {
         status: String,
         progress: f32,
     },
+    /// How far a long mission's sync to mission control has got.
+    MissionSyncProgress {
+        progress: MissionSyncProgress,
+    },
     DroneTelemetry {
         drone_id: String,
         telemetry: DroneTelemettry,
@@ -83,21 +89,30 @@ pub struct DroneTelemettry {
 }
 
 pub struct WebSocketHandler {
-    mission_service: Arc<Mutex<MissionPlannerService>>,
+    mission_service: Arc<MissionPlannerService>,
     telemetry_history: Arc<Mutex<TelemetryHistory>>,
     broadcast_tx: broadcast::Sender<WebSocketMessage>,
+    mission_control: Option<MissionControlLink>,
 }
 
 impl WebSocketHandler {
-    pub fn new(mission_service: Arc<Mutex<MissionPlannerService>>) -> Self {
+    pub fn new(mission_service: Arc<MissionPlannerService>) -> Self {
         let (broadcast_tx, _) = broadcast::channel(100);
         Self {
             mission_service,
             telemetry_history: Arc::new(Mutex::new(TelemetryHistory::default())),
             broadcast_tx,
+            mission_control: None,
         }
     }
 
+    /// Deploys missions to mission control through `link`; without one,
+    /// deployment is only simulated.
+    pub fn with_mission_control(mut self, link: MissionControlLink) -> Self {
+        self.mission_control = Some(link);
+        self
+    }
+
     pub async fn handle_upgrade(
         ws: WebSocketUpgrade,
         State(handler): State<Arc<WebSocketHandler>>,
@@ -245,34 +260,52 @@ impl WebSocketHandler {
         let waypoint_file = MAVLinkConverter::to_waypoint_file(&mavlink_mission);
 
         // Save mission
-        let service = self.mission_service.lock().await;
-        service.create_mission(mission.clone()).await?;
+        self.mission_service.create_mission(mission.clone()).await?;
 
-        // TODO: Send to actual drone system
         info!(
             "MAVLink waypoint file generated ({} items)",
             mavlink_mission.count
         );
         info!("Mission waypoints:\n{}", waypoint_file);
 
-        // Simulate deployment
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        let deployed = match &self.mission_control {
+            Some(link) => {
+                let broadcast_tx = self.broadcast_tx.clone();
+                link.deploy(mission.id, &mavlink_mission, |progress| {
+                    let _ = broadcast_tx.send(WebSocketMessage::MissionSyncProgress {
+                        progress: progress.clone(),
+                    });
+                })
+                .await
+                .map(|route| info!("Mission {} deployed by {:?}", mission.id, route))
+            }
+            None => {
+                // Simulate deployment
+                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+                Ok(())
+            }
+        };
+        if let Err(e) = &deployed {
+            error!("Failed to deploy mission {}: {:#}", mission.id, e);
+        }
 
         let response = WebSocketMessage::MissionDeployed {
             mission_id: mission.id,
-            success: true,
-            error: None,
+            success: deployed.is_ok(),
+            error: deployed.as_ref().err().map(|e| format!("{e:#}")),
         };
 
         let json_msg = serde_json::to_string(&response)?;
         sender.send(Message::Text(json_msg))?;
 
         // Broadcast mission status updates
-        let _ = self.broadcast_tx.send(WebSocketMessage::MissionStatus {
-            mission_id: mission.id,
-            status: "Deployed".to_string(),
-            progress: 0.0,
-        });
+        if deployed.is_ok() {
+            let _ = self.broadcast_tx.send(WebSocketMessage::MissionStatus {
+                mission_id: mission.id,
+                status: "Deployed".to_string(),
+                progress: 0.0,
+            });
+        }
 
         Ok(())
     }
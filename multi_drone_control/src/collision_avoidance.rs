@@ -5,6 +5,116 @@ use shared::Position3D;
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::{DroneStatus, SafetyViolation, Severity, ViolationType};
+
+/// Closest-approach distance below which a converging pair is a collision risk.
+pub const COLLISION_RISK_SEPARATION_M: f64 = 25.0;
+/// Approaches further ahead than this are not reported.
+pub const COLLISION_RISK_HORIZON_S: f64 = 30.0;
+
+/// Straight-line extrapolation of a pair's relative motion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClosestApproach {
+    pub time_s: f64,
+    pub distance_m: f64,
+}
+
+/// Time and distance of closest approach for two drones flying at constant
+/// velocity, in the local metric frame of [`DroneStatus`]. `None` unless the
+/// pair is converging, i.e. the closest approach is still ahead.
+pub fn closest_approach(first: &DroneStatus, second: &DroneStatus) -> Option<ClosestApproach> {
+    let relative_position = [
+        second.position.0 - first.position.0,
+        second.position.1 - first.position.1,
+        f64::from(second.position.2 - first.position.2),
+    ];
+    let relative_velocity = [
+        f64::from(second.velocity.0 - first.velocity.0),
+        f64::from(second.velocity.1 - first.velocity.1),
+        f64::from(second.velocity.2 - first.velocity.2),
+    ];
+    let closing = dot(&relative_position, &relative_velocity);
+    let speed_squared = dot(&relative_velocity, &relative_velocity);
+    if closing >= 0.0 || speed_squared <= f64::EPSILON {
+        return None;
+    }
+
+    let time_s = -closing / speed_squared;
+    let at_closest = [
+        relative_position[0] + relative_velocity[0] * time_s,
+        relative_position[1] + relative_velocity[1] * time_s,
+        relative_position[2] + relative_velocity[2] * time_s,
+    ];
+    Some(ClosestApproach {
+        time_s,
+        distance_m: dot(&at_closest, &at_closest).sqrt(),
+    })
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Severity from how soon the approach happens, raised one level when the
+/// predicted miss distance is under 40% of the separation minimum.
+pub fn collision_risk_severity(approach: &ClosestApproach) -> Severity {
+    let by_time = if approach.time_s < 5.0 {
+        Severity::Critical
+    } else if approach.time_s < 10.0 {
+        Severity::High
+    } else if approach.time_s < 20.0 {
+        Severity::Medium
+    } else {
+        Severity::Low
+    };
+    if approach.distance_m >= COLLISION_RISK_SEPARATION_M * 0.4 {
+        return by_time;
+    }
+    match by_time {
+        Severity::Low => Severity::Medium,
+        Severity::Medium => Severity::High,
+        Severity::High | Severity::Critical => Severity::Critical,
+    }
+}
+
+/// One `CollisionRisk` violation per converging pair whose closest approach
+/// falls inside [`COLLISION_RISK_HORIZON_S`] and [`COLLISION_RISK_SEPARATION_M`].
+/// The violation is attributed to the pair's lower drone id.
+pub fn collision_risk_violations(
+    statuses: &[&DroneStatus],
+    timestamp: DateTime<Utc>,
+) -> Vec<SafetyViolation> {
+    let mut drones = statuses.to_vec();
+    drones.sort_by_key(|status| status.id);
+
+    let mut violations = Vec::new();
+    for (index, first) in drones.iter().enumerate() {
+        for second in &drones[index + 1..] {
+            let Some(approach) = closest_approach(first, second) else {
+                continue;
+            };
+            if approach.time_s > COLLISION_RISK_HORIZON_S
+                || approach.distance_m >= COLLISION_RISK_SEPARATION_M
+            {
+                continue;
+            }
+            violations.push(SafetyViolation {
+                drone_id: first.id,
+                violation_type: ViolationType::CollisionRisk,
+                description: format!(
+                    "Converging with drone {}: closest approach {:.1}m in {:.1}s",
+                    second.id, approach.distance_m, approach.time_s
+                ),
+                severity: collision_risk_severity(&approach),
+                timestamp,
+                position: Some(first.position),
+                action_ref: None,
+            });
+        }
+    }
+    violations
+}
+
 /// 3D collision avoidance system for multi-drone operations
 pub struct CollisionAvoidanceSystem {
     tracked_drones: HashMap<Uuid, DroneTrackingInfo>,
@@ -780,6 +890,36 @@ pub struct CollisionAvoidanceStatus {
 mod tests {
     use super::*;
 
+    #[test]
+    fn collision_risk_severity_scales_with_time_and_miss_distance() {
+        let approach = |time_s, distance_m| ClosestApproach { time_s, distance_m };
+
+        assert_eq!(
+            collision_risk_severity(&approach(3.0, 20.0)),
+            Severity::Critical
+        );
+        assert_eq!(
+            collision_risk_severity(&approach(8.0, 20.0)),
+            Severity::High
+        );
+        assert_eq!(
+            collision_risk_severity(&approach(8.0, 2.0)),
+            Severity::Critical
+        );
+        assert_eq!(
+            collision_risk_severity(&approach(15.0, 20.0)),
+            Severity::Medium
+        );
+        assert_eq!(
+            collision_risk_severity(&approach(25.0, 20.0)),
+            Severity::Low
+        );
+        assert_eq!(
+            collision_risk_severity(&approach(25.0, 5.0)),
+            Severity::Medium
+        );
+    }
+
     #[tokio::test]
     async fn test_collision_avoidance_system() {
         let mut system = CollisionAvoidanceSystem::new();
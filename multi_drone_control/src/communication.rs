@@ -0,0 +1,344 @@
+use crate::{SafetyViolation, Severity, ViolationType};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use uuid::Uuid;
+
+pub const DEFAULT_MAX_RELAY_HOPS: u32 = 3;
+
+/// One end of a radio link: the ground control station or a drone.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(tag = "kind", content = "drone_id", rename_all = "snake_case")]
+pub enum LinkEndpoint {
+    Base,
+    Drone(Uuid),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConnectivityLink {
+    pub from: LinkEndpoint,
+    pub to: LinkEndpoint,
+    pub distance_m: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DroneConnectivity {
+    pub drone_id: Uuid,
+    pub position: (f64, f64, f32),
+    /// Links needed to reach the base; `Some(1)` is a direct link and `None`
+    /// means no relay path exists.
+    pub hop_count: Option<u32>,
+    /// Next endpoint on the shortest path towards the base.
+    pub next_hop: Option<LinkEndpoint>,
+}
+
+impl DroneConnectivity {
+    pub fn is_reachable(&self) -> bool {
+        self.hop_count.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConnectivityReport {
+    pub base_position: (f64, f64, f32),
+    pub communication_range_m: f64,
+    pub drones: Vec<DroneConnectivity>,
+    pub links: Vec<ConnectivityLink>,
+    pub unreachable_drone_ids: Vec<Uuid>,
+    /// Drones whose loss would cut at least one other drone off from the base.
+    pub articulation_drone_ids: Vec<Uuid>,
+    pub max_hop_count: u32,
+}
+
+impl ConnectivityReport {
+    pub fn drone(&self, drone_id: Uuid) -> Option<&DroneConnectivity> {
+        self.drones.iter().find(|drone| drone.drone_id == drone_id)
+    }
+
+    pub fn is_fully_connected(&self) -> bool {
+        self.unreachable_drone_ids.is_empty()
+    }
+}
+
+/// Builds the link graph for drones at `positions`, where two endpoints are
+/// linked when they are within `communication_range_m` of each other, and
+/// resolves multi-hop reachability from the base.
+pub fn build_connectivity_report(
+    base_position: (f64, f64, f32),
+    positions: &[(Uuid, (f64, f64, f32))],
+    communication_range_m: f64,
+) -> ConnectivityReport {
+    let mut positions = positions.to_vec();
+    positions.sort_by_key(|(drone_id, _)| *drone_id);
+
+    // Node 0 is the base; node i + 1 is positions[i].
+    let node_position = |node: usize| {
+        if node == 0 {
+            base_position
+        } else {
+            positions[node - 1].1
+        }
+    };
+    let endpoint = |node: usize| {
+        if node == 0 {
+            LinkEndpoint::Base
+        } else {
+            LinkEndpoint::Drone(positions[node - 1].0)
+        }
+    };
+    let node_count = positions.len() + 1;
+
+    let mut adjacency = vec![Vec::new(); node_count];
+    let mut links = Vec::new();
+    for left in 0..node_count {
+        for right in (left + 1)..node_count {
+            let distance_m = distance_3d(node_position(left), node_position(right));
+            if distance_m <= communication_range_m {
+                adjacency[left].push(right);
+                adjacency[right].push(left);
+                links.push(ConnectivityLink {
+                    from: endpoint(left),
+                    to: endpoint(right),
+                    distance_m,
+                });
+            }
+        }
+    }
+
+    let mut hops: Vec<Option<u32>> = vec![None; node_count];
+    let mut parent: Vec<Option<usize>> = vec![None; node_count];
+    hops[0] = Some(0);
+    let mut queue = VecDeque::from([0]);
+    while let Some(node) = queue.pop_front() {
+        let next_hops = hops[node].unwrap_or_default() + 1;
+        for &neighbor in &adjacency[node] {
+            if hops[neighbor].is_none() {
+                hops[neighbor] = Some(next_hops);
+                parent[neighbor] = Some(node);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    let drones = positions
+        .iter()
+        .enumerate()
+        .map(|(index, (drone_id, position))| DroneConnectivity {
+            drone_id: *drone_id,
+            position: *position,
+            hop_count: hops[index + 1],
+            next_hop: parent[index + 1].map(endpoint),
+        })
+        .collect::<Vec<_>>();
+    let unreachable_drone_ids = drones
+        .iter()
+        .filter(|drone| !drone.is_reachable())
+        .map(|drone| drone.drone_id)
+        .collect();
+    let articulation_drone_ids = articulation_nodes(&adjacency)
+        .into_iter()
+        .filter(|&node| node != 0)
+        .map(|node| positions[node - 1].0)
+        .collect();
+    let max_hop_count = drones
+        .iter()
+        .filter_map(|drone| drone.hop_count)
+        .max()
+        .unwrap_or(0);
+
+    ConnectivityReport {
+        base_position,
+        communication_range_m,
+        drones,
+        links,
+        unreachable_drone_ids,
+        articulation_drone_ids,
+        max_hop_count,
+    }
+}
+
+/// Emits a `CommunicationLoss` violation for every drone with no relay path
+/// to the base. A drone that cannot be reached is also a drone that cannot be
+/// told to return, so severity rises as its battery drains.
+pub fn communication_loss_violations(
+    report: &ConnectivityReport,
+    battery_levels: &HashMap<Uuid, f32>,
+    timestamp: DateTime<Utc>,
+) -> Vec<SafetyViolation> {
+    report
+        .drones
+        .iter()
+        .filter(|drone| !drone.is_reachable())
+        .map(|drone| {
+            let battery_level = battery_levels.get(&drone.drone_id).copied();
+            SafetyViolation {
+                drone_id: drone.drone_id,
+                violation_type: ViolationType::CommunicationLoss,
+                description: format!(
+                    "Drone has no direct or relay link to base within {:.0}m range{}",
+                    report.communication_range_m,
+                    battery_level
+                        .map(|level| format!(" (battery {:.0}%)", level * 100.0))
+                        .unwrap_or_default()
+                ),
+                severity: communication_loss_severity(battery_level),
+                timestamp,
+                position: Some(drone.position),
+                action_ref: None,
+            }
+        })
+        .collect()
+}
+
+fn communication_loss_severity(battery_level: Option<f32>) -> Severity {
+    match battery_level {
+        Some(level) if level < 0.2 => Severity::Critical,
+        Some(level) if level < 0.5 => Severity::High,
+        Some(_) => Severity::Medium,
+        None => Severity::High,
+    }
+}
+
+/// Tarjan's articulation-point search over the component containing the base.
+fn articulation_nodes(adjacency: &[Vec<usize>]) -> Vec<usize> {
+    let node_count = adjacency.len();
+    let mut discovery = vec![usize::MAX; node_count];
+    let mut low = vec![0; node_count];
+    let mut is_articulation = vec![false; node_count];
+    let mut timer = 0;
+
+    // Iterative DFS: (node, parent, next neighbor index).
+    let mut stack = vec![(0usize, usize::MAX, 0usize)];
+    discovery[0] = timer;
+    low[0] = timer;
+    timer += 1;
+    let mut root_children = 0;
+
+    while let Some(&mut (node, parent, ref mut next)) = stack.last_mut() {
+        if let Some(&neighbor) = adjacency[node].get(*next) {
+            *next += 1;
+            if discovery[neighbor] == usize::MAX {
+                discovery[neighbor] = timer;
+                low[neighbor] = timer;
+                timer += 1;
+                if node == 0 {
+                    root_children += 1;
+                }
+                stack.push((neighbor, node, 0));
+            } else if neighbor != parent {
+                low[node] = low[node].min(discovery[neighbor]);
+            }
+        } else {
+            stack.pop();
+            if parent != usize::MAX {
+                low[parent] = low[parent].min(low[node]);
+                if parent != 0 && low[node] >= discovery[parent] {
+                    is_articulation[parent] = true;
+                }
+            }
+        }
+    }
+    if root_children > 1 {
+        is_articulation[0] = true;
+    }
+
+    (0..node_count)
+        .filter(|&node| is_articulation[node])
+        .collect()
+}
+
+fn distance_3d(left: (f64, f64, f32), right: (f64, f64, f32)) -> f64 {
+    let altitude_delta = f64::from(left.2 - right.2);
+    (left.0 - right.0)
+        .hypot(left.1 - right.1)
+        .hypot(altitude_delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: (f64, f64, f32) = (0.0, 0.0, 0.0);
+
+    fn ids(count: usize) -> Vec<Uuid> {
+        let mut ids = (0..count).map(|_| Uuid::new_v4()).collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn chain_is_reachable_through_relays_and_middle_links_are_articulation_points() {
+        let drone_ids = ids(3);
+        let positions = vec![
+            (drone_ids[0], (800.0, 0.0, 0.0)),
+            (drone_ids[1], (1_600.0, 0.0, 0.0)),
+            (drone_ids[2], (2_400.0, 0.0, 0.0)),
+        ];
+
+        let report = build_connectivity_report(BASE, &positions, 1_000.0);
+
+        assert!(report.is_fully_connected());
+        assert_eq!(report.drone(drone_ids[0]).unwrap().hop_count, Some(1));
+        assert_eq!(report.drone(drone_ids[1]).unwrap().hop_count, Some(2));
+        assert_eq!(report.drone(drone_ids[2]).unwrap().hop_count, Some(3));
+        assert_eq!(
+            report.drone(drone_ids[2]).unwrap().next_hop,
+            Some(LinkEndpoint::Drone(drone_ids[1]))
+        );
+        assert_eq!(report.max_hop_count, 3);
+        assert_eq!(
+            report.articulation_drone_ids,
+            vec![drone_ids[0], drone_ids[1]]
+        );
+        assert_eq!(report.links.len(), 3);
+    }
+
+    #[test]
+    fn disconnected_island_is_unreachable_and_flagged_by_battery() {
+        let drone_ids = ids(4);
+        let positions = vec![
+            (drone_ids[0], (500.0, 0.0, 30.0)),
+            (drone_ids[1], (0.0, 600.0, 30.0)),
+            (drone_ids[2], (5_000.0, 5_000.0, 30.0)),
+            (drone_ids[3], (5_400.0, 5_000.0, 30.0)),
+        ];
+        let battery_levels = HashMap::from([(drone_ids[2], 0.1), (drone_ids[3], 0.8)]);
+
+        let report = build_connectivity_report(BASE, &positions, 1_000.0);
+        let violations = communication_loss_violations(&report, &battery_levels, Utc::now());
+
+        assert_eq!(
+            report.unreachable_drone_ids,
+            vec![drone_ids[2], drone_ids[3]]
+        );
+        assert!(report.articulation_drone_ids.is_empty());
+        assert_eq!(violations.len(), 2);
+        assert!(violations
+            .iter()
+            .all(|violation| violation.violation_type == ViolationType::CommunicationLoss));
+        let severity_of = |drone_id| {
+            violations
+                .iter()
+                .find(|violation| violation.drone_id == drone_id)
+                .map(|violation| violation.severity.clone())
+        };
+        assert_eq!(severity_of(drone_ids[2]), Some(Severity::Critical));
+        assert_eq!(severity_of(drone_ids[3]), Some(Severity::Medium));
+    }
+
+    #[test]
+    fn redundant_relay_paths_have_no_articulation_points() {
+        let drone_ids = ids(3);
+        let positions = vec![
+            (drone_ids[0], (700.0, 300.0, 0.0)),
+            (drone_ids[1], (700.0, -300.0, 0.0)),
+            (drone_ids[2], (1_400.0, 0.0, 0.0)),
+        ];
+
+        let report = build_connectivity_report(BASE, &positions, 1_000.0);
+
+        assert!(report.is_fully_connected());
+        assert_eq!(report.drone(drone_ids[2]).unwrap().hop_count, Some(2));
+        assert!(report.articulation_drone_ids.is_empty());
+    }
+}
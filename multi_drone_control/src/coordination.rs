@@ -2,7 +2,7 @@ use crate::{swarm::generate_formation_slots, Formation};
 use anyhow::{ensure, Result};
 use chrono::{DateTime, Duration as ChronoDuration, Utc};
 use serde::{Deserialize, Serialize};
-use shared::GeoCoordinate;
+use shared::{GeoCoordinate, LocalPoint};
 use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
@@ -174,13 +174,6 @@ pub struct FormationOptimizationReport {
     pub validated: bool,
 }
 
-#[derive(Debug, Clone, Copy)]
-struct LocalPoint {
-    east_m: f64,
-    north_m: f64,
-    altitude_m: f64,
-}
-
 #[derive(Debug, Clone)]
 struct FormationSlotTarget {
     slot_index: usize,
@@ -992,7 +985,7 @@ impl CoordinationEngine {
                 let local = LocalPoint {
                     east_m: slot.offset_m.0,
                     north_m: slot.offset_m.1,
-                    altitude_m: f64::from(slot.offset_m.2),
+                    up_m: f64::from(slot.offset_m.2),
                 };
                 FormationSlotTarget {
                     slot_index: slot.slot_index,
@@ -1186,7 +1179,7 @@ fn geo_to_local(origin: &GeoCoordinate, position: &GeoCoordinate) -> LocalPoint
             * EARTH_RADIUS_M
             * origin_lat_rad.cos(),
         north_m: (position.latitude - origin.latitude).to_radians() * EARTH_RADIUS_M,
-        altitude_m: f64::from(position.altitude_m - origin.altitude_m),
+        up_m: f64::from(position.altitude_m - origin.altitude_m),
     }
 }
 
@@ -1196,14 +1189,14 @@ fn local_to_geo(origin: &GeoCoordinate, local: LocalPoint) -> GeoCoordinate {
         latitude: origin.latitude + (local.north_m / EARTH_RADIUS_M).to_degrees(),
         longitude: origin.longitude
             + (local.east_m / (EARTH_RADIUS_M * origin_lat_rad.cos())).to_degrees(),
-        altitude_m: origin.altitude_m + local.altitude_m as f32,
+        altitude_m: origin.altitude_m + local.up_m as f32,
     }
 }
 
 fn local_distance(left: LocalPoint, right: LocalPoint) -> f64 {
     ((left.east_m - right.east_m).powi(2)
         + (left.north_m - right.north_m).powi(2)
-        + (left.altitude_m - right.altitude_m).powi(2))
+        + (left.up_m - right.up_m).powi(2))
     .sqrt()
 }
 
@@ -1238,25 +1231,24 @@ fn minimum_synchronous_path_distance(
     let relative_start = LocalPoint {
         east_m: left_start.east_m - right_start.east_m,
         north_m: left_start.north_m - right_start.north_m,
-        altitude_m: left_start.altitude_m - right_start.altitude_m,
+        up_m: left_start.up_m - right_start.up_m,
     };
     let relative_velocity = LocalPoint {
         east_m: (left_target.east_m - left_start.east_m)
             - (right_target.east_m - right_start.east_m),
         north_m: (left_target.north_m - left_start.north_m)
             - (right_target.north_m - right_start.north_m),
-        altitude_m: (left_target.altitude_m - left_start.altitude_m)
-            - (right_target.altitude_m - right_start.altitude_m),
+        up_m: (left_target.up_m - left_start.up_m) - (right_target.up_m - right_start.up_m),
     };
     let velocity_norm = relative_velocity.east_m.powi(2)
         + relative_velocity.north_m.powi(2)
-        + relative_velocity.altitude_m.powi(2);
+        + relative_velocity.up_m.powi(2);
     let closest_t = if velocity_norm <= GEOMETRY_EPSILON {
         0.0
     } else {
         -((relative_start.east_m * relative_velocity.east_m)
             + (relative_start.north_m * relative_velocity.north_m)
-            + (relative_start.altitude_m * relative_velocity.altitude_m))
+            + (relative_start.up_m * relative_velocity.up_m))
             / velocity_norm
     }
     .clamp(0.0, 1.0);
@@ -1264,14 +1256,14 @@ fn minimum_synchronous_path_distance(
     let closest = LocalPoint {
         east_m: relative_start.east_m + relative_velocity.east_m * closest_t,
         north_m: relative_start.north_m + relative_velocity.north_m * closest_t,
-        altitude_m: relative_start.altitude_m + relative_velocity.altitude_m * closest_t,
+        up_m: relative_start.up_m + relative_velocity.up_m * closest_t,
     };
     local_distance(
         closest,
         LocalPoint {
             east_m: 0.0,
             north_m: 0.0,
-            altitude_m: 0.0,
+            up_m: 0.0,
         },
     )
 }
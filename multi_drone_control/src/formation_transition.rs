@@ -0,0 +1,845 @@
+use anyhow::{ensure, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use shared::RuntimeMode;
+use uuid::Uuid;
+
+use crate::collision_avoidance::{COLLISION_RISK_HORIZON_S, COLLISION_RISK_SEPARATION_M};
+use crate::swarm::{generate_formation_slots, FormationSlot};
+use crate::{DroneStatus, Formation, MultiDroneControlService, SafetyViolation, ViolationType};
+
+const TIME_EPSILON_S: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct FormationTransitionConfig {
+    pub safety_radius_m: f64,
+    /// Predicted approaches further ahead than this do not count as conflicts.
+    pub warning_horizon_s: f64,
+    pub cruise_speed_mps: f64,
+    pub climb_rate_mps: f64,
+    /// Height between transit layers; must exceed the safety radius so that
+    /// drones cruising in different layers can never conflict.
+    pub altitude_layer_m: f64,
+    pub max_altitude_layers: usize,
+    pub stagger_interval_s: f64,
+    /// Phasing candidates tried before falling back to sequential moves.
+    pub max_search_nodes: usize,
+}
+
+impl Default for FormationTransitionConfig {
+    fn default() -> Self {
+        Self {
+            safety_radius_m: COLLISION_RISK_SEPARATION_M,
+            warning_horizon_s: COLLISION_RISK_HORIZON_S,
+            cruise_speed_mps: 5.0,
+            climb_rate_mps: 2.5,
+            altitude_layer_m: 30.0,
+            max_altitude_layers: 2,
+            stagger_interval_s: 10.0,
+            max_search_nodes: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, thiserror::Error, PartialEq)]
+pub enum FormationTransitionError {
+    #[error("formation transition requires at least one drone")]
+    NoDrones,
+    #[error("formation has {slot_count} slots for {drone_count} drones")]
+    SlotCountMismatch {
+        drone_count: usize,
+        slot_count: usize,
+    },
+    #[error("drone {drone_id} has a non-finite position")]
+    InvalidPosition { drone_id: Uuid },
+    #[error("formation transition parameter is invalid: {reason}")]
+    InvalidConfig { reason: String },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TransitionStrategy {
+    /// Every drone flies straight to its slot at once.
+    Parallel,
+    /// Drones start at staggered times and/or cruise on raised altitude layers.
+    Phased,
+    /// One drone at a time; used when the phasing search finds no plan.
+    Sequential,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TimedWaypoint {
+    pub time_s: f64,
+    pub position: (f64, f64, f32),
+}
+
+/// Timed path of one drone, in the local metric frame of [`DroneStatus`].
+/// The drone holds its first waypoint until the second one's time and holds
+/// its last waypoint once it arrives.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DroneTransitionScript {
+    pub drone_id: Uuid,
+    pub slot_index: usize,
+    pub start_time_s: f64,
+    pub altitude_layer: usize,
+    pub travel_distance_m: f64,
+    pub waypoints: Vec<TimedWaypoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormationTransitionPlan {
+    pub strategy: TransitionStrategy,
+    pub scripts: Vec<DroneTransitionScript>,
+    pub total_travel_m: f64,
+    pub duration_s: f64,
+    /// False only when even the sequential fallback has a conflicting pair.
+    pub conflict_free: bool,
+    pub search_nodes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormationTransitionSimulation {
+    pub steps: usize,
+    pub collision_warnings: Vec<SafetyViolation>,
+}
+
+impl DroneTransitionScript {
+    pub fn arrival_time_s(&self) -> f64 {
+        self.waypoints
+            .last()
+            .map_or(0.0, |waypoint| waypoint.time_s)
+    }
+
+    pub fn position_at(&self, time_s: f64) -> (f64, f64, f32) {
+        let Some(index) = self.segment_at(time_s) else {
+            let starts_later = self
+                .waypoints
+                .first()
+                .is_some_and(|first| time_s < first.time_s);
+            let waypoint = if starts_later {
+                self.waypoints.first()
+            } else {
+                self.waypoints.last()
+            };
+            return waypoint.map_or((0.0, 0.0, 0.0), |waypoint| waypoint.position);
+        };
+        let (from, to) = (&self.waypoints[index], &self.waypoints[index + 1]);
+        let fraction = (time_s - from.time_s) / (to.time_s - from.time_s);
+        from_vector(lerp(
+            to_vector(from.position),
+            to_vector(to.position),
+            fraction,
+        ))
+    }
+
+    /// Velocity of the leg flown from `time_s` on; zero while holding.
+    pub fn velocity_at(&self, time_s: f64) -> (f32, f32, f32) {
+        let velocity = self.velocity_vector_at(time_s);
+        (velocity[0] as f32, velocity[1] as f32, velocity[2] as f32)
+    }
+
+    fn velocity_vector_at(&self, time_s: f64) -> [f64; 3] {
+        let Some(index) = self.segment_at(time_s) else {
+            return [0.0; 3];
+        };
+        let (from, to) = (&self.waypoints[index], &self.waypoints[index + 1]);
+        let duration = to.time_s - from.time_s;
+        let delta = sub(to_vector(to.position), to_vector(from.position));
+        [
+            delta[0] / duration,
+            delta[1] / duration,
+            delta[2] / duration,
+        ]
+    }
+
+    fn segment_at(&self, time_s: f64) -> Option<usize> {
+        self.waypoints
+            .windows(2)
+            .position(|pair| pair[0].time_s <= time_s && time_s < pair[1].time_s)
+    }
+}
+
+impl FormationTransitionPlan {
+    fn new(
+        strategy: TransitionStrategy,
+        scripts: Vec<DroneTransitionScript>,
+        config: &FormationTransitionConfig,
+        search_nodes: usize,
+    ) -> Self {
+        let conflict_free = find_conflict(&scripts, config).is_none();
+        Self {
+            strategy,
+            total_travel_m: scripts.iter().map(|script| script.travel_distance_m).sum(),
+            duration_s: scripts
+                .iter()
+                .map(DroneTransitionScript::arrival_time_s)
+                .fold(0.0, f64::max),
+            scripts,
+            conflict_free,
+            search_nodes,
+        }
+    }
+}
+
+/// Plans the move from `current` positions to formation `slots` placed at
+/// `anchor`. Slots are assigned to minimise total travel, then the moves are
+/// phased so that no pair of drones conflicts (see [`find_conflict`]): all at
+/// once if possible, otherwise with staggered starts and raised transit
+/// layers, and one drone at a time if that search runs out of budget.
+pub fn plan_formation_transition(
+    current: &[(Uuid, (f64, f64, f32))],
+    slots: &[FormationSlot],
+    anchor: (f64, f64, f32),
+    config: FormationTransitionConfig,
+) -> std::result::Result<FormationTransitionPlan, FormationTransitionError> {
+    validate_transition(current, slots, &config)?;
+
+    let targets = slot_targets(slots, anchor);
+    let cost = current
+        .iter()
+        .map(|(_, position)| {
+            targets
+                .iter()
+                .map(|(_, target)| distance(to_vector(*position), *target))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let legs = minimum_cost_assignment(&cost)
+        .into_iter()
+        .zip(current)
+        .map(|(target_index, (drone_id, position))| TransitionLeg {
+            drone_id: *drone_id,
+            slot_index: targets[target_index].0,
+            start: to_vector(*position),
+            target: targets[target_index].1,
+        })
+        .collect::<Vec<_>>();
+
+    let mut order = (0..legs.len()).collect::<Vec<_>>();
+    order.sort_by(|left, right| legs[*right].distance().total_cmp(&legs[*left].distance()));
+
+    let mut search = PhaseSearch {
+        legs: &legs,
+        order: &order,
+        config: &config,
+        nodes: 0,
+        scripts: vec![None; legs.len()],
+    };
+    if search.place(0) {
+        let scripts = search
+            .scripts
+            .into_iter()
+            .map(|script| script.expect("every leg was placed"))
+            .collect::<Vec<_>>();
+        let strategy = if scripts
+            .iter()
+            .all(|script| script.start_time_s == 0.0 && script.altitude_layer == 0)
+        {
+            TransitionStrategy::Parallel
+        } else {
+            TransitionStrategy::Phased
+        };
+        return Ok(FormationTransitionPlan::new(
+            strategy,
+            scripts,
+            &config,
+            search.nodes,
+        ));
+    }
+
+    let nodes = search.nodes;
+    let layer = config.max_altitude_layers.min(1);
+    let mut start_time_s = 0.0;
+    let mut scripts = vec![None; legs.len()];
+    for index in order.iter().rev() {
+        let leg = &legs[*index];
+        if leg.distance() == 0.0 {
+            scripts[*index] = Some(leg.script(0.0, 0, &config));
+            continue;
+        }
+        let script = leg.script(start_time_s, layer, &config);
+        start_time_s = script.arrival_time_s();
+        scripts[*index] = Some(script);
+    }
+    Ok(FormationTransitionPlan::new(
+        TransitionStrategy::Sequential,
+        scripts.into_iter().flatten().collect(),
+        &config,
+        nodes,
+    ))
+}
+
+/// Baseline without planning: drone `i` flies straight to slot `i` and every
+/// drone leaves at once.
+pub fn direct_formation_transition(
+    current: &[(Uuid, (f64, f64, f32))],
+    slots: &[FormationSlot],
+    anchor: (f64, f64, f32),
+    config: FormationTransitionConfig,
+) -> std::result::Result<FormationTransitionPlan, FormationTransitionError> {
+    validate_transition(current, slots, &config)?;
+
+    let scripts = current
+        .iter()
+        .zip(slot_targets(slots, anchor))
+        .map(|((drone_id, position), (slot_index, target))| {
+            TransitionLeg {
+                drone_id: *drone_id,
+                slot_index,
+                start: to_vector(*position),
+                target,
+            }
+            .script(0.0, 0, &config)
+        })
+        .collect();
+    Ok(FormationTransitionPlan::new(
+        TransitionStrategy::Parallel,
+        scripts,
+        &config,
+        0,
+    ))
+}
+
+/// First pair of scripts that conflicts. A pair conflicts when the drones
+/// come within the safety radius, or when straight-line extrapolation of
+/// the legs they are flying predicts that within the warning horizon, which
+/// is the test the collision-warning system applies to live telemetry.
+pub fn find_conflict(
+    scripts: &[DroneTransitionScript],
+    config: &FormationTransitionConfig,
+) -> Option<(Uuid, Uuid)> {
+    for (index, first) in scripts.iter().enumerate() {
+        for second in &scripts[index + 1..] {
+            if scripts_conflict(first, second, config) {
+                return Some((first.drone_id, second.drone_id));
+            }
+        }
+    }
+    None
+}
+
+impl MultiDroneControlService {
+    /// Plans a transition of the swarm into `formation` at `anchor`, starting
+    /// from each member's last reported position.
+    pub async fn plan_formation_transition(
+        &self,
+        swarm_id: Uuid,
+        formation: &Formation,
+        anchor: (f64, f64, f32),
+        config: FormationTransitionConfig,
+    ) -> Result<FormationTransitionPlan> {
+        let drone_ids = {
+            let controller = self.controller.read().await;
+            controller
+                .get_swarm(&swarm_id)
+                .ok_or_else(|| anyhow::anyhow!("swarm {swarm_id} not found"))?
+                .drone_ids()
+        };
+        let current = {
+            let statuses = self.drone_statuses.read().await;
+            drone_ids
+                .iter()
+                .map(|drone_id| {
+                    statuses
+                        .get(drone_id)
+                        .map(|status| (*drone_id, status.position))
+                        .ok_or_else(|| anyhow::anyhow!("no telemetry for drone {drone_id}"))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+        let slots = generate_formation_slots(formation, current.len(), config.safety_radius_m)?;
+        Ok(plan_formation_transition(&current, &slots, anchor, config)?)
+    }
+
+    /// Replays `plan` as simulated telemetry every `step_s` seconds until the
+    /// last drone arrives, collecting the collision warnings raised on the way.
+    pub async fn simulate_formation_transition(
+        &self,
+        plan: &FormationTransitionPlan,
+        step_s: f64,
+    ) -> Result<FormationTransitionSimulation> {
+        ensure!(
+            self.autonomy_config.runtime_mode == RuntimeMode::Simulation,
+            "formation transition simulation requires runtime mode SIMULATION"
+        );
+        ensure!(
+            step_s.is_finite() && step_s > 0.0,
+            "simulation step must be finite and positive"
+        );
+
+        let mut collision_warnings = Vec::new();
+        let mut steps = 0;
+        loop {
+            let time_s = steps as f64 * step_s;
+            for script in &plan.scripts {
+                let previous = self.get_drone_status(&script.drone_id).await;
+                self.update_drone_status(DroneStatus {
+                    id: script.drone_id,
+                    position: script.position_at(time_s),
+                    velocity: script.velocity_at(time_s),
+                    battery_level: previous.as_ref().map_or(1.0, |status| status.battery_level),
+                    status: previous
+                        .as_ref()
+                        .map_or_else(|| "in_mission".to_string(), |status| status.status.clone()),
+                    assigned_mission: previous.and_then(|status| status.assigned_mission),
+                    last_update: Utc::now(),
+                })
+                .await;
+            }
+            collision_warnings.extend(
+                self.check_safety_violations()
+                    .await?
+                    .into_iter()
+                    .filter(|violation| violation.violation_type == ViolationType::CollisionRisk),
+            );
+            steps += 1;
+            if time_s >= plan.duration_s {
+                break;
+            }
+        }
+
+        Ok(FormationTransitionSimulation {
+            steps,
+            collision_warnings,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TransitionLeg {
+    drone_id: Uuid,
+    slot_index: usize,
+    start: [f64; 3],
+    target: [f64; 3],
+}
+
+impl TransitionLeg {
+    fn distance(&self) -> f64 {
+        distance(self.start, self.target)
+    }
+
+    /// Holds until `start_time_s`, then flies straight to the slot on layer
+    /// zero, or climbs to the layer, cruises level and descends into the slot.
+    fn script(
+        &self,
+        start_time_s: f64,
+        altitude_layer: usize,
+        config: &FormationTransitionConfig,
+    ) -> DroneTransitionScript {
+        let mut waypoints = vec![TimedWaypoint {
+            time_s: 0.0,
+            position: from_vector(self.start),
+        }];
+        let mut push = |time_s: f64, position: [f64; 3]| {
+            let last = waypoints.last().expect("script starts with a waypoint");
+            if time_s > last.time_s + TIME_EPSILON_S {
+                waypoints.push(TimedWaypoint {
+                    time_s,
+                    position: from_vector(position),
+                });
+            }
+        };
+
+        push(start_time_s, self.start);
+        let mut time_s = start_time_s;
+        let mut travel_distance_m = 0.0;
+        if altitude_layer == 0 {
+            travel_distance_m = self.distance();
+            time_s += travel_distance_m / config.cruise_speed_mps;
+            push(time_s, self.target);
+        } else {
+            let transit_altitude =
+                self.start[2].max(self.target[2]) + altitude_layer as f64 * config.altitude_layer_m;
+            let climb_top = [self.start[0], self.start[1], transit_altitude];
+            let descent_top = [self.target[0], self.target[1], transit_altitude];
+            for (from, to, speed) in [
+                (self.start, climb_top, config.climb_rate_mps),
+                (climb_top, descent_top, config.cruise_speed_mps),
+                (descent_top, self.target, config.climb_rate_mps),
+            ] {
+                let leg_m = distance(from, to);
+                travel_distance_m += leg_m;
+                time_s += leg_m / speed;
+                push(time_s, to);
+            }
+        }
+
+        DroneTransitionScript {
+            drone_id: self.drone_id,
+            slot_index: self.slot_index,
+            start_time_s,
+            altitude_layer,
+            travel_distance_m,
+            waypoints,
+        }
+    }
+}
+
+/// Depth-first search over start delays and transit layers, longest legs
+/// first, bounded by `max_search_nodes`.
+struct PhaseSearch<'a> {
+    legs: &'a [TransitionLeg],
+    order: &'a [usize],
+    config: &'a FormationTransitionConfig,
+    nodes: usize,
+    scripts: Vec<Option<DroneTransitionScript>>,
+}
+
+impl PhaseSearch<'_> {
+    fn place(&mut self, depth: usize) -> bool {
+        let Some(&index) = self.order.get(depth) else {
+            return true;
+        };
+        let leg = &self.legs[index];
+        let (delays, layers) = if leg.distance() == 0.0 {
+            (1, 0)
+        } else {
+            (self.legs.len(), self.config.max_altitude_layers)
+        };
+
+        for delay in 0..delays {
+            for layer in 0..=layers {
+                if self.nodes >= self.config.max_search_nodes {
+                    return false;
+                }
+                self.nodes += 1;
+
+                let script = leg.script(
+                    delay as f64 * self.config.stagger_interval_s,
+                    layer,
+                    self.config,
+                );
+                let conflicts = self
+                    .scripts
+                    .iter()
+                    .flatten()
+                    .any(|placed| scripts_conflict(placed, &script, self.config));
+                if conflicts {
+                    continue;
+                }
+                self.scripts[index] = Some(script);
+                if self.place(depth + 1) {
+                    return true;
+                }
+                self.scripts[index] = None;
+            }
+        }
+        false
+    }
+}
+
+fn scripts_conflict(
+    first: &DroneTransitionScript,
+    second: &DroneTransitionScript,
+    config: &FormationTransitionConfig,
+) -> bool {
+    let mut times = first
+        .waypoints
+        .iter()
+        .chain(&second.waypoints)
+        .map(|waypoint| waypoint.time_s)
+        .collect::<Vec<_>>();
+    times.sort_by(f64::total_cmp);
+    times.dedup();
+
+    for (index, start_s) in times.iter().copied().enumerate() {
+        let end_s = times.get(index + 1).copied().unwrap_or(f64::INFINITY);
+        let relative_position = sub(
+            to_vector(second.position_at(start_s)),
+            to_vector(first.position_at(start_s)),
+        );
+        if norm(relative_position) < config.safety_radius_m {
+            return true;
+        }
+
+        // Both drones fly straight legs until `end_s`, so the extrapolated
+        // closest approach is the same from anywhere inside the interval.
+        let relative_velocity = sub(
+            second.velocity_vector_at(start_s),
+            first.velocity_vector_at(start_s),
+        );
+        let closing = dot(relative_position, relative_velocity);
+        let speed_squared = dot(relative_velocity, relative_velocity);
+        if closing >= 0.0 || speed_squared <= f64::EPSILON {
+            continue;
+        }
+        let approach_s = -closing / speed_squared;
+        if start_s + approach_s - config.warning_horizon_s > end_s {
+            continue;
+        }
+        let at_closest = lerp(
+            relative_position,
+            add(relative_position, relative_velocity),
+            approach_s,
+        );
+        if norm(at_closest) < config.safety_radius_m {
+            return true;
+        }
+    }
+    false
+}
+
+fn validate_transition(
+    current: &[(Uuid, (f64, f64, f32))],
+    slots: &[FormationSlot],
+    config: &FormationTransitionConfig,
+) -> std::result::Result<(), FormationTransitionError> {
+    if current.is_empty() {
+        return Err(FormationTransitionError::NoDrones);
+    }
+    if current.len() != slots.len() {
+        return Err(FormationTransitionError::SlotCountMismatch {
+            drone_count: current.len(),
+            slot_count: slots.len(),
+        });
+    }
+    if let Some((drone_id, _)) = current
+        .iter()
+        .find(|(_, position)| !to_vector(*position).iter().all(|axis| axis.is_finite()))
+    {
+        return Err(FormationTransitionError::InvalidPosition {
+            drone_id: *drone_id,
+        });
+    }
+
+    let positive = [
+        ("safety radius", config.safety_radius_m),
+        ("warning horizon", config.warning_horizon_s),
+        ("cruise speed", config.cruise_speed_mps),
+        ("climb rate", config.climb_rate_mps),
+        ("stagger interval", config.stagger_interval_s),
+    ];
+    if let Some((label, _)) = positive
+        .iter()
+        .find(|(_, value)| !value.is_finite() || *value <= 0.0)
+    {
+        return Err(FormationTransitionError::InvalidConfig {
+            reason: format!("{label} must be finite and positive"),
+        });
+    }
+    if config.max_altitude_layers > 0
+        && (!config.altitude_layer_m.is_finite()
+            || config.altitude_layer_m <= config.safety_radius_m)
+    {
+        return Err(FormationTransitionError::InvalidConfig {
+            reason: "altitude layer spacing must exceed the safety radius".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn slot_targets(slots: &[FormationSlot], anchor: (f64, f64, f32)) -> Vec<(usize, [f64; 3])> {
+    let anchor = to_vector(anchor);
+    slots
+        .iter()
+        .map(|slot| (slot.slot_index, add(anchor, to_vector(slot.offset_m))))
+        .collect()
+}
+
+/// Hungarian algorithm on a square cost matrix; returns the column assigned
+/// to each row.
+fn minimum_cost_assignment(cost: &[Vec<f64>]) -> Vec<usize> {
+    let size = cost.len();
+    // One-based with a virtual column 0, as in the classic formulation.
+    let mut row_potential = vec![0.0; size + 1];
+    let mut column_potential = vec![0.0; size + 1];
+    let mut column_owner = vec![0usize; size + 1];
+    let mut previous_column = vec![0usize; size + 1];
+
+    for row in 1..=size {
+        column_owner[0] = row;
+        let mut column = 0;
+        let mut slack = vec![f64::INFINITY; size + 1];
+        let mut visited = vec![false; size + 1];
+        loop {
+            visited[column] = true;
+            let owner = column_owner[column];
+            let mut delta = f64::INFINITY;
+            let mut next_column = 0;
+            for candidate in 1..=size {
+                if visited[candidate] {
+                    continue;
+                }
+                let reduced = cost[owner - 1][candidate - 1]
+                    - row_potential[owner]
+                    - column_potential[candidate];
+                if reduced < slack[candidate] {
+                    slack[candidate] = reduced;
+                    previous_column[candidate] = column;
+                }
+                if slack[candidate] < delta {
+                    delta = slack[candidate];
+                    next_column = candidate;
+                }
+            }
+            for candidate in 0..=size {
+                if visited[candidate] {
+                    row_potential[column_owner[candidate]] += delta;
+                    column_potential[candidate] -= delta;
+                } else {
+                    slack[candidate] -= delta;
+                }
+            }
+            column = next_column;
+            if column_owner[column] == 0 {
+                break;
+            }
+        }
+        while column != 0 {
+            let previous = previous_column[column];
+            column_owner[column] = column_owner[previous];
+            column = previous;
+        }
+    }
+
+    let mut assignment = vec![0; size];
+    for column in 1..=size {
+        assignment[column_owner[column] - 1] = column - 1;
+    }
+    assignment
+}
+
+fn to_vector(position: (f64, f64, f32)) -> [f64; 3] {
+    [position.0, position.1, f64::from(position.2)]
+}
+
+fn from_vector(vector: [f64; 3]) -> (f64, f64, f32) {
+    (vector[0], vector[1], vector[2] as f32)
+}
+
+fn add(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn norm(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    norm(sub(b, a))
+}
+
+fn lerp(a: [f64; 3], b: [f64; 3], fraction: f64) -> [f64; 3] {
+    add(
+        a,
+        [
+            (b[0] - a[0]) * fraction,
+            (b[1] - a[1]) * fraction,
+            (b[2] - a[2]) * fraction,
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_of_four() -> Vec<(Uuid, (f64, f64, f32))> {
+        (0..4)
+            .map(|index| (Uuid::new_v4(), (30.0 * index as f64, 0.0, 30.0)))
+            .collect()
+    }
+
+    fn circle_slots() -> Vec<FormationSlot> {
+        generate_formation_slots(
+            &Formation::Circle {
+                radius_m: 40.0,
+                center: (45.0, 10.0),
+            },
+            4,
+            COLLISION_RISK_SEPARATION_M,
+        )
+        .expect("circle slots should generate")
+    }
+
+    #[test]
+    fn slot_assignment_minimises_total_cost_where_greedy_would_not() {
+        let cost = vec![
+            vec![1.0, 2.0, 9.0],
+            vec![2.0, 9.0, 9.0],
+            vec![9.0, 9.0, 1.0],
+        ];
+
+        assert_eq!(minimum_cost_assignment(&cost), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn line_to_circle_is_phased_where_direct_paths_conflict() {
+        let config = FormationTransitionConfig::default();
+        let line = line_of_four();
+        let slots = circle_slots();
+
+        let direct = direct_formation_transition(&line, &slots, (0.0, 0.0, 30.0), config)
+            .expect("direct transition should build");
+        let plan = plan_formation_transition(&line, &slots, (0.0, 0.0, 30.0), config)
+            .expect("transition should plan");
+
+        assert!(find_conflict(&direct.scripts, &config).is_some());
+        assert_eq!(plan.strategy, TransitionStrategy::Phased);
+        assert!(plan.conflict_free);
+        assert!(find_conflict(&plan.scripts, &config).is_none());
+        let mut slot_indices = plan
+            .scripts
+            .iter()
+            .map(|script| script.slot_index)
+            .collect::<Vec<_>>();
+        slot_indices.sort();
+        assert_eq!(slot_indices, vec![0, 1, 2, 3]);
+        for script in &plan.scripts {
+            let target = slots[script.slot_index].offset_m;
+            let arrived = script.position_at(plan.duration_s);
+            assert!((arrived.0 - target.0).abs() < 1e-6);
+            assert!((arrived.1 - target.1).abs() < 1e-6);
+            assert_eq!(script.velocity_at(plan.duration_s), (0.0, 0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn exhausted_search_falls_back_to_one_drone_at_a_time() {
+        let config = FormationTransitionConfig {
+            max_search_nodes: 0,
+            ..FormationTransitionConfig::default()
+        };
+
+        let plan =
+            plan_formation_transition(&line_of_four(), &circle_slots(), (0.0, 0.0, 30.0), config)
+                .expect("fallback should still plan");
+
+        assert_eq!(plan.strategy, TransitionStrategy::Sequential);
+        let mut windows = plan
+            .scripts
+            .iter()
+            .filter(|script| script.travel_distance_m > 0.0)
+            .map(|script| (script.start_time_s, script.arrival_time_s()))
+            .collect::<Vec<_>>();
+        windows.sort_by(|left, right| left.0.total_cmp(&right.0));
+        assert_eq!(windows.len(), 4);
+        assert!(windows.windows(2).all(|pair| pair[0].1 <= pair[1].0));
+    }
+
+    #[test]
+    fn mismatched_slot_count_is_rejected() {
+        let err = plan_formation_transition(
+            &line_of_four()[..3],
+            &circle_slots(),
+            (0.0, 0.0, 30.0),
+            FormationTransitionConfig::default(),
+        )
+        .expect_err("slot count mismatch should reject");
+
+        assert_eq!(
+            err,
+            FormationTransitionError::SlotCountMismatch {
+                drone_count: 3,
+                slot_count: 4,
+            }
+        );
+    }
+}
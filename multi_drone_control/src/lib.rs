@@ -2,7 +2,9 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use shared::{FlightParameters, Mission, SafetyConstraints};
-use shared::{GeoCoordinate, RuntimeMode};
+use shared::{GeoCoordinate, LocalPoint, LocalPolygon, RuntimeMode};
+use shared::{WebhookDispatcher, WebhookEvent, WebhookEventKind};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::env;
 use std::str::FromStr;
@@ -13,14 +15,24 @@ use tokio::time::sleep;
 use uuid::Uuid;
 
 pub mod collision_avoidance;
+pub mod communication;
 pub mod coordinated_approval;
 pub mod coordination;
+pub mod formation_transition;
 pub mod mission_assignment;
 pub mod swarm;
 pub mod swarm_command;
 pub mod synchronized_survey;
+pub mod timeline;
 
-pub use collision_avoidance::{AvoidanceManeuver, CollisionAvoidanceSystem};
+pub use collision_avoidance::{
+    closest_approach, collision_risk_violations, AvoidanceManeuver, ClosestApproach,
+    CollisionAvoidanceSystem, COLLISION_RISK_HORIZON_S, COLLISION_RISK_SEPARATION_M,
+};
+pub use communication::{
+    build_connectivity_report, communication_loss_violations, ConnectivityLink, ConnectivityReport,
+    DroneConnectivity, LinkEndpoint, DEFAULT_MAX_RELAY_HOPS,
+};
 pub use coordinated_approval::{
     authorize_coordinated_execution, dry_run_coordinated_execution, ApprovalAuditEvent,
     ApprovalGateConfig, ApprovalGateError, CoordinatedExecutionDecision,
@@ -34,6 +46,11 @@ pub use coordination::{
     SwarmTelemetryReport, SwarmTelemetryStatus,
 };
 use coordination::{DroneOperationStatus, DroneState};
+pub use formation_transition::{
+    direct_formation_transition, find_conflict, plan_formation_transition, DroneTransitionScript,
+    FormationTransitionConfig, FormationTransitionError, FormationTransitionPlan,
+    FormationTransitionSimulation, TimedWaypoint, TransitionStrategy,
+};
 pub use mission_assignment::{
     AssignmentAlgorithm, AssignmentBatchReport, AssignmentFailureReason, AvailabilityStatus,
     DroneAssignment, DroneCapabilities, MissionAssignmentEngine, MissionRequest,
@@ -91,6 +108,11 @@ pub use synchronized_survey::{
     SurveyLane, SurveyProgressReport, SurveySeparationSample, SynchronizedSurveyConfig,
     SynchronizedSurveyError, SynchronizedSurveyPlan,
 };
+pub use timeline::{
+    ChargingBooking, DeadlineMiss, DroneAvailability, DroneTimeline, FleetSchedule, FleetTimeline,
+    FleetTimelineApi, TimelineAssignment, TimelineBlock, TimelineBlockKind, TimelineConflict,
+    TimelineError, TimelineMission, TimelineReplan,
+};
 
 const AUTONOMOUS_SURVEY_ENV_VAR: &str = "AUTONOMOUS_SURVEY_ENABLED";
 
@@ -127,10 +149,22 @@ pub struct MultiDroneController {
     pub global_constraints: GlobalConstraints,
     #[serde(default)]
     pub swarm_constraints: HashMap<Uuid, GlobalConstraints>,
+    #[serde(default)]
+    pub drone_constraints: HashMap<Uuid, DroneConstraintOverride>,
     pub communication_range_m: f32,
+    /// Ground control station position in the same local frame as drone
+    /// positions; the root of the relay graph.
+    #[serde(default)]
+    pub base_position: (f64, f64, f32),
+    #[serde(default = "default_max_relay_hops")]
+    pub max_relay_hops: u32,
     pub created_at: DateTime<Utc>,
 }
 
+fn default_max_relay_hops() -> u32 {
+    DEFAULT_MAX_RELAY_HOPS
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SwarmRegistryEntry {
     pub swarm_id: Uuid,
@@ -164,17 +198,30 @@ pub enum SwarmRegistryError {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct GlobalConstraints {
     pub max_altitude_m: f32,
-    pub geofence_boundaries: Vec<(f64, f64)>,
+    pub geofence_boundaries: LocalPolygon,
     pub no_fly_zones: Vec<NoFlyZone>,
     pub max_concurrent_drones: u32,
-    pub emergency_landing_sites: Vec<(f64, f64)>,
+    pub emergency_landing_sites: Vec<LocalPoint>,
+}
+
+/// Per-drone limits for drones working their own plot. Each field that is
+/// set replaces the matching global value; no-fly zones always stay global.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DroneConstraintOverride {
+    #[serde(default)]
+    pub geofence_boundaries: Option<LocalPolygon>,
+    #[serde(default)]
+    pub max_altitude_m: Option<f32>,
+    /// Return-to-launch point; the controller's `base_position` otherwise.
+    #[serde(default)]
+    pub rtl_point: Option<(f64, f64, f32)>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct NoFlyZone {
     pub id: Uuid,
     pub name: String,
-    pub boundary: Vec<(f64, f64)>,
+    pub boundary: LocalPolygon,
     pub altitude_restriction: Option<(f32, f32)>,
     pub reason: String,
     pub active: bool,
@@ -196,6 +243,8 @@ pub enum GlobalConstraintValidationError {
     InvalidEmergencyLandingSite { index: usize },
     #[error("no-fly zone {zone_id} is invalid: {reason}")]
     InvalidNoFlyZone { zone_id: Uuid, reason: String },
+    #[error("return-to-launch point contains a non-finite coordinate")]
+    InvalidRtlPoint,
 }
 
 #[derive(Debug, Clone, thiserror::Error, PartialEq, Eq)]
@@ -374,8 +423,10 @@ pub struct MultiDroneControlService {
     autonomy_config: AutonomousSurveyConfig,
     active_autonomous_surveys: Arc<RwLock<HashMap<Uuid, AutonomousSurveySession>>>,
     retask_proposals: Arc<RwLock<HashMap<Uuid, SwarmRetaskProposal>>>,
+    fleet_schedule: Arc<RwLock<FleetSchedule>>,
     command_sender: mpsc::UnboundedSender<ControlCommand>,
     command_receiver: Arc<RwLock<mpsc::UnboundedReceiver<ControlCommand>>>,
+    webhooks: Option<WebhookDispatcher>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -393,7 +444,10 @@ impl MultiDroneController {
             swarms: HashMap::new(),
             global_constraints: GlobalConstraints::default(),
             swarm_constraints: HashMap::new(),
+            drone_constraints: HashMap::new(),
             communication_range_m: 1000.0,
+            base_position: (0.0, 0.0, 0.0),
+            max_relay_hops: DEFAULT_MAX_RELAY_HOPS,
             created_at: Utc::now(),
         }
     }
@@ -507,6 +561,14 @@ impl MultiDroneController {
         Ok(next_status)
     }
 
+    pub fn connectivity_report(&self, positions: &[(Uuid, (f64, f64, f32))]) -> ConnectivityReport {
+        build_connectivity_report(
+            self.base_position,
+            positions,
+            f64::from(self.communication_range_m),
+        )
+    }
+
     pub fn list_all_drones(&self) -> Vec<Uuid> {
         let mut drone_ids: Vec<Uuid> = self
             .swarms
@@ -560,6 +622,39 @@ impl MultiDroneController {
             .unwrap_or(&self.global_constraints)
     }
 
+    pub fn set_drone_constraints(
+        &mut self,
+        drone_id: Uuid,
+        overrides: DroneConstraintOverride,
+    ) -> std::result::Result<(), GlobalConstraintValidationError> {
+        overrides.validate()?;
+        self.drone_constraints.insert(drone_id, overrides);
+        Ok(())
+    }
+
+    pub fn clear_drone_constraints(&mut self, drone_id: Uuid) -> Option<DroneConstraintOverride> {
+        self.drone_constraints.remove(&drone_id)
+    }
+
+    pub fn get_drone_constraints(&self, drone_id: Uuid) -> Option<&DroneConstraintOverride> {
+        self.drone_constraints.get(&drone_id)
+    }
+
+    /// Global constraints with the drone's overrides, if any, applied on top.
+    pub fn effective_constraints_for_drone(&self, drone_id: Uuid) -> Cow<'_, GlobalConstraints> {
+        match self.drone_constraints.get(&drone_id) {
+            Some(overrides) => Cow::Owned(overrides.apply_to(&self.global_constraints)),
+            None => Cow::Borrowed(&self.global_constraints),
+        }
+    }
+
+    pub fn rtl_point_for_drone(&self, drone_id: Uuid) -> (f64, f64, f32) {
+        self.drone_constraints
+            .get(&drone_id)
+            .and_then(|overrides| overrides.rtl_point)
+            .unwrap_or(self.base_position)
+    }
+
     pub fn validate_swarm_action_targets(
         &self,
         action_ref: impl Into<String>,
@@ -715,42 +810,11 @@ impl MultiDroneController {
     }
 
     fn target_within_geofence(position: &(f64, f64, f32), constraints: &GlobalConstraints) -> bool {
-        if constraints.geofence_boundaries.len() < 3 {
-            return true;
-        }
-
-        Self::point_in_polygon(position.0, position.1, &constraints.geofence_boundaries)
+        constraints.geofence_contains(&LocalPoint::from_xyz(*position))
     }
 
     fn target_in_no_fly_zone(position: &(f64, f64, f32), zone: &NoFlyZone) -> bool {
-        if let Some((min_alt, max_alt)) = zone.altitude_restriction {
-            if position.2 < min_alt || position.2 > max_alt {
-                return false;
-            }
-        }
-
-        if zone.boundary.len() < 3 {
-            return false;
-        }
-
-        Self::point_in_polygon(position.0, position.1, &zone.boundary)
-    }
-
-    fn point_in_polygon(x: f64, y: f64, boundary: &[(f64, f64)]) -> bool {
-        let mut inside = false;
-        let mut j = boundary.len() - 1;
-
-        for i in 0..boundary.len() {
-            let (xi, yi) = boundary[i];
-            let (xj, yj) = boundary[j];
-
-            if ((yi > y) != (yj > y)) && (x < (xj - xi) * (y - yi) / (yj - yi) + xi) {
-                inside = !inside;
-            }
-            j = i;
-        }
-
-        inside
+        zone.contains(&LocalPoint::from_xyz(*position))
     }
 
     fn active_membership_conflict(
@@ -813,15 +877,19 @@ impl Default for GlobalConstraints {
     fn default() -> Self {
         Self {
             max_altitude_m: 400.0,
-            geofence_boundaries: vec![
+            geofence_boundaries: LocalPolygon::from_xy_points([
                 (-1000.0, -1000.0),
                 (1000.0, -1000.0),
                 (1000.0, 1000.0),
                 (-1000.0, 1000.0),
-            ],
+            ]),
             no_fly_zones: Vec::new(),
             max_concurrent_drones: 10,
-            emergency_landing_sites: vec![(0.0, 0.0), (500.0, 500.0), (-500.0, -500.0)],
+            emergency_landing_sites: vec![
+                LocalPoint::new(0.0, 0.0),
+                LocalPoint::new(500.0, 500.0),
+                LocalPoint::new(-500.0, -500.0),
+            ],
         }
     }
 }
@@ -838,8 +906,9 @@ impl GlobalConstraints {
 
         if self
             .geofence_boundaries
+            .vertices
             .iter()
-            .any(|(x, y)| !x.is_finite() || !y.is_finite())
+            .any(|point| !point.is_finite())
         {
             return Err(GlobalConstraintValidationError::InvalidGeofenceCoordinate);
         }
@@ -852,8 +921,8 @@ impl GlobalConstraints {
             return Err(GlobalConstraintValidationError::MissingEmergencyLandingSite);
         }
 
-        for (index, (x, y)) in self.emergency_landing_sites.iter().enumerate() {
-            if !x.is_finite() || !y.is_finite() {
+        for (index, site) in self.emergency_landing_sites.iter().enumerate() {
+            if !site.is_finite() {
                 return Err(GlobalConstraintValidationError::InvalidEmergencyLandingSite { index });
             }
         }
@@ -867,8 +936,9 @@ impl GlobalConstraints {
             }
             if zone
                 .boundary
+                .vertices
                 .iter()
-                .any(|(x, y)| !x.is_finite() || !y.is_finite())
+                .any(|point| !point.is_finite())
             {
                 return Err(GlobalConstraintValidationError::InvalidNoFlyZone {
                     zone_id: zone.id,
@@ -890,6 +960,64 @@ impl GlobalConstraints {
 
         Ok(())
     }
+
+    /// An unset geofence (fewer than three points) allows every position.
+    pub fn geofence_contains(&self, position: &LocalPoint) -> bool {
+        self.geofence_boundaries.len() < 3 || self.geofence_boundaries.contains(position)
+    }
+}
+
+impl NoFlyZone {
+    /// True when `position` is inside the boundary and, if the zone has one,
+    /// within its altitude band.
+    pub fn contains(&self, position: &LocalPoint) -> bool {
+        if let Some((min_alt, max_alt)) = self.altitude_restriction {
+            let altitude = position.up_m as f32;
+            if altitude < min_alt || altitude > max_alt {
+                return false;
+            }
+        }
+
+        self.boundary.contains(position)
+    }
+}
+
+impl DroneConstraintOverride {
+    pub fn validate(&self) -> std::result::Result<(), GlobalConstraintValidationError> {
+        if let Some(max_altitude_m) = self.max_altitude_m {
+            if !max_altitude_m.is_finite() || max_altitude_m <= 0.0 {
+                return Err(GlobalConstraintValidationError::InvalidMaxAltitude);
+            }
+        }
+
+        if let Some(boundary) = &self.geofence_boundaries {
+            if boundary.len() < 3 {
+                return Err(GlobalConstraintValidationError::EmptyGeofence);
+            }
+            if boundary.vertices.iter().any(|point| !point.is_finite()) {
+                return Err(GlobalConstraintValidationError::InvalidGeofenceCoordinate);
+            }
+        }
+
+        if let Some((x, y, z)) = self.rtl_point {
+            if !x.is_finite() || !y.is_finite() || !z.is_finite() {
+                return Err(GlobalConstraintValidationError::InvalidRtlPoint);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn apply_to(&self, global: &GlobalConstraints) -> GlobalConstraints {
+        let mut constraints = global.clone();
+        if let Some(boundary) = &self.geofence_boundaries {
+            constraints.geofence_boundaries = boundary.clone();
+        }
+        if let Some(max_altitude_m) = self.max_altitude_m {
+            constraints.max_altitude_m = max_altitude_m;
+        }
+        constraints
+    }
 }
 
 impl MultiDroneControlService {
@@ -916,11 +1044,30 @@ impl MultiDroneControlService {
             autonomy_config,
             active_autonomous_surveys: Arc::new(RwLock::new(HashMap::new())),
             retask_proposals: Arc::new(RwLock::new(HashMap::new())),
+            fleet_schedule: Arc::new(RwLock::new(FleetSchedule::default())),
             command_sender,
             command_receiver: Arc::new(RwLock::new(command_receiver)),
+            webhooks: None,
         }
     }
 
+    /// Publishes a `safety.violation` webhook for every audited violation.
+    pub fn with_webhooks(mut self, webhooks: WebhookDispatcher) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// Inputs to the fleet timeline; the planner writes them as the day's
+    /// plan changes.
+    pub fn fleet_schedule(&self) -> Arc<RwLock<FleetSchedule>> {
+        self.fleet_schedule.clone()
+    }
+
+    /// Serves `GET /api/fleet/timeline` over this service's schedule.
+    pub fn timeline_router(&self) -> axum::Router {
+        FleetTimelineApi::router(self.fleet_schedule.clone())
+    }
+
     pub async fn send_command(&self, command: ControlCommand) -> Result<()> {
         self.command_sender
             .send(command)
@@ -1056,7 +1203,7 @@ impl MultiDroneControlService {
     fn apply_coordination_rule_execution(
         statuses: &mut HashMap<Uuid, DroneStatus>,
         execution: &CoordinationRuleExecution,
-        emergency_sites: &[(f64, f64)],
+        emergency_sites: &[LocalPoint],
     ) {
         let Some(status) = statuses.get_mut(&execution.drone_id) else {
             return;
@@ -1071,12 +1218,12 @@ impl MultiDroneControlService {
                 status.position.2 = 0.0;
             }
             CoordinationRuleExecutionKind::LandAtNearestEmergencySite => {
-                if let Some((site_x, site_y)) = Self::nearest_emergency_site(
-                    (status.position.0, status.position.1),
+                if let Some(site) = Self::nearest_emergency_site(
+                    LocalPoint::from_xyz(status.position),
                     emergency_sites,
                 ) {
                     status.status = "emergency".to_string();
-                    status.position = (site_x, site_y, 0.0);
+                    status.position = (site.east_m, site.north_m, 0.0);
                 } else {
                     status.status = "emergency".to_string();
                     status.position.2 = 0.0;
@@ -1089,22 +1236,17 @@ impl MultiDroneControlService {
     }
 
     fn nearest_emergency_site(
-        position: (f64, f64),
-        emergency_sites: &[(f64, f64)],
-    ) -> Option<(f64, f64)> {
+        position: LocalPoint,
+        emergency_sites: &[LocalPoint],
+    ) -> Option<LocalPoint> {
         emergency_sites.iter().copied().min_by(|left, right| {
-            Self::distance_2d(position, *left)
-                .partial_cmp(&Self::distance_2d(position, *right))
+            position
+                .horizontal_distance_m(left)
+                .partial_cmp(&position.horizontal_distance_m(right))
                 .unwrap_or(std::cmp::Ordering::Equal)
         })
     }
 
-    fn distance_2d(left: (f64, f64), right: (f64, f64)) -> f64 {
-        let dx = left.0 - right.0;
-        let dy = left.1 - right.1;
-        (dx * dx + dy * dy).sqrt()
-    }
-
     pub async fn get_drone_status(&self, drone_id: &Uuid) -> Option<DroneStatus> {
         let statuses = self.drone_statuses.read().await;
         statuses.get(drone_id).cloned()
@@ -1115,6 +1257,18 @@ impl MultiDroneControlService {
         statuses.values().cloned().collect()
     }
 
+    /// Link graph, relay hop counts and single points of failure for the
+    /// drones currently reporting status.
+    pub async fn get_connectivity_report(&self) -> ConnectivityReport {
+        let statuses = self.drone_statuses.read().await;
+        let positions = statuses
+            .values()
+            .map(|status| (status.id, status.position))
+            .collect::<Vec<_>>();
+        drop(statuses);
+        self.controller.read().await.connectivity_report(&positions)
+    }
+
     pub async fn process_commands(&self) -> Result<()> {
         let mut receiver = self.command_receiver.write().await;
 
@@ -1470,9 +1624,15 @@ impl MultiDroneControlService {
                 }
             }
             ControlCommand::ReturnToBase { drone_ids } => {
+                let controller = self.controller.read().await;
                 for drone_id in drone_ids {
                     // Send return to base command
-                    tracing::info!("Return to base initiated for drone: {}", drone_id);
+                    let rtl_point = controller.rtl_point_for_drone(drone_id);
+                    tracing::info!(
+                        "Return to base initiated for drone: {} -> {:?}",
+                        drone_id,
+                        rtl_point
+                    );
                 }
             }
             ControlCommand::UpdateConstraints { constraints } => {
@@ -1491,7 +1651,16 @@ impl MultiDroneControlService {
     ) {
         let mut audit_log = self.safety_audit_log.write().await;
         for violation in violations {
-            audit_log.append(violation.clone(), recorded_at);
+            let record = audit_log.append(violation.clone(), recorded_at);
+            if let Some(webhooks) = &self.webhooks {
+                let payload = serde_json::to_value(&record).unwrap_or_default();
+                if let Err(error) = webhooks.publish(WebhookEvent::new(
+                    WebhookEventKind::SafetyViolation,
+                    payload,
+                )) {
+                    tracing::warn!("Dropping safety violation webhook: {}", error);
+                }
+            }
         }
     }
 
@@ -1787,14 +1956,16 @@ impl MultiDroneControlService {
         let mut violations = Vec::new();
 
         for status in statuses.values() {
+            let constraints = controller.effective_constraints_for_drone(status.id);
+
             // Check altitude violations
-            if status.position.2 > controller.global_constraints.max_altitude_m {
+            if status.position.2 > constraints.max_altitude_m {
                 violations.push(SafetyViolation {
                     drone_id: status.id,
                     violation_type: ViolationType::AltitudeExceeded,
                     description: format!(
                         "Altitude {:.1}m exceeds maximum {:.1}m",
-                        status.position.2, controller.global_constraints.max_altitude_m
+                        status.position.2, constraints.max_altitude_m
                     ),
                     severity: Severity::High,
                     timestamp: Utc::now(),
@@ -1804,7 +1975,7 @@ impl MultiDroneControlService {
             }
 
             // Check geofence violations
-            if !self.is_within_geofence(&status.position, &controller.global_constraints) {
+            if !self.is_within_geofence(&status.position, &constraints) {
                 violations.push(SafetyViolation {
                     drone_id: status.id,
                     violation_type: ViolationType::GeofenceViolation,
@@ -1817,7 +1988,7 @@ impl MultiDroneControlService {
             }
 
             // Check no-fly zone violations
-            for zone in &controller.global_constraints.no_fly_zones {
+            for zone in &constraints.no_fly_zones {
                 if zone.active && self.is_in_no_fly_zone(&status.position, zone) {
                     violations.push(SafetyViolation {
                         drone_id: status.id,
@@ -1832,6 +2003,25 @@ impl MultiDroneControlService {
             }
         }
 
+        violations.extend(collision_risk_violations(
+            &statuses.values().collect::<Vec<_>>(),
+            Utc::now(),
+        ));
+
+        let positions = statuses
+            .values()
+            .map(|status| (status.id, status.position))
+            .collect::<Vec<_>>();
+        let battery_levels = statuses
+            .values()
+            .map(|status| (status.id, status.battery_level))
+            .collect::<HashMap<_, _>>();
+        violations.extend(communication_loss_violations(
+            &controller.connectivity_report(&positions),
+            &battery_levels,
+            Utc::now(),
+        ));
+
         drop(controller);
         drop(statuses);
         self.audit_safety_violations(&violations, Utc::now()).await;
@@ -1844,60 +2034,11 @@ impl MultiDroneControlService {
         position: &(f64, f64, f32),
         constraints: &GlobalConstraints,
     ) -> bool {
-        // Simple point-in-polygon check (for convex polygons)
-        let (x, y, _) = *position;
-        let boundary = &constraints.geofence_boundaries;
-
-        if boundary.len() < 3 {
-            return true; // No geofence defined
-        }
-
-        let mut inside = false;
-        let mut j = boundary.len() - 1;
-
-        for i in 0..boundary.len() {
-            let (xi, yi) = boundary[i];
-            let (xj, yj) = boundary[j];
-
-            if ((yi > y) != (yj > y)) && (x < (xj - xi) * (y - yi) / (yj - yi) + xi) {
-                inside = !inside;
-            }
-            j = i;
-        }
-
-        inside
+        constraints.geofence_contains(&LocalPoint::from_xyz(*position))
     }
 
     fn is_in_no_fly_zone(&self, position: &(f64, f64, f32), zone: &NoFlyZone) -> bool {
-        let (x, y, z) = *position;
-
-        // Check altitude restriction if present
-        if let Some((min_alt, max_alt)) = zone.altitude_restriction {
-            if z < min_alt || z > max_alt {
-                return false;
-            }
-        }
-
-        // Check if point is in zone boundary (simple point-in-polygon)
-        let boundary = &zone.boundary;
-        if boundary.len() < 3 {
-            return false;
-        }
-
-        let mut inside = false;
-        let mut j = boundary.len() - 1;
-
-        for i in 0..boundary.len() {
-            let (xi, yi) = boundary[i];
-            let (xj, yj) = boundary[j];
-
-            if ((yi > y) != (yj > y)) && (x < (xj - xi) * (y - yi) / (yj - yi) + xi) {
-                inside = !inside;
-            }
-            j = i;
-        }
-
-        inside
+        zone.contains(&LocalPoint::from_xyz(*position))
     }
 }
 
@@ -2208,6 +2349,40 @@ mod tests {
         assert_eq!(restored.get_swarm_constraints(swarm_id), Some(&constraints));
     }
 
+    #[test]
+    fn constraints_keep_tuple_json_layout_for_geofence_and_zones() {
+        let zone_id = Uuid::from_u128(77);
+        let stored = serde_json::json!({
+            "max_altitude_m": 120.0,
+            "geofence_boundaries": [[-100.0, -100.0], [100.0, -100.0], [100.0, 100.0], [-100.0, 100.0]],
+            "no_fly_zones": [{
+                "id": zone_id,
+                "name": "Pump house",
+                "boundary": [[0.0, 0.0], [20.0, 0.0], [20.0, 20.0], [0.0, 20.0]],
+                "altitude_restriction": [0.0, 60.0],
+                "reason": "structure",
+                "active": true
+            }],
+            "max_concurrent_drones": 4,
+            "emergency_landing_sites": [[0.0, -80.0]]
+        });
+
+        let constraints: GlobalConstraints =
+            serde_json::from_value(stored.clone()).expect("legacy constraints deserialize");
+
+        assert_eq!(constraints.validate(), Ok(()));
+        assert_eq!(
+            constraints.emergency_landing_sites,
+            vec![LocalPoint::new(0.0, -80.0)]
+        );
+        assert!(constraints.geofence_contains(&LocalPoint::new(50.0, 50.0)));
+        assert!(!constraints.geofence_contains(&LocalPoint::new(150.0, 50.0)));
+        let zone = &constraints.no_fly_zones[0];
+        assert!(zone.contains(&LocalPoint::new(10.0, 10.0).with_up(30.0)));
+        assert!(!zone.contains(&LocalPoint::new(10.0, 10.0).with_up(90.0)));
+        assert_eq!(serde_json::to_value(&constraints).unwrap(), stored);
+    }
+
     #[test]
     fn swarm_constraints_reject_missing_emergency_landing_site() {
         let mut controller = MultiDroneController::new("Constraint Controller".to_string());
@@ -2435,6 +2610,245 @@ mod tests {
         assert_eq!(records[0].violation.position, Some((150.0, 10.0, 50.0)));
     }
 
+    #[tokio::test]
+    async fn audited_safety_violations_are_published_as_webhooks() {
+        let (webhooks, _worker) = WebhookDispatcher::spawn(shared::WebhookConfig {
+            endpoints: vec![shared::WebhookEndpoint {
+                id: "safety-desk".to_string(),
+                // Nothing listens on the discard port; the event is dead-lettered.
+                url: "http://127.0.0.1:9/hooks".to_string(),
+                secret: "secret".to_string(),
+                events: vec![WebhookEventKind::SafetyViolation],
+            }],
+            delivery: shared::WebhookDeliveryPolicy {
+                max_retries: 0,
+                ..Default::default()
+            },
+            queue_capacity: 4,
+        })
+        .unwrap();
+        let service = MultiDroneControlService::new("Test Service".to_string())
+            .with_webhooks(webhooks.clone());
+        let drone_id = Uuid::new_v4();
+        {
+            let mut controller = service.controller.write().await;
+            controller.global_constraints = constrained_controller().global_constraints;
+        }
+        service
+            .update_drone_status(DroneStatus {
+                id: drone_id,
+                position: (150.0, 10.0, 50.0),
+                velocity: (0.0, 0.0, 0.0),
+                battery_level: 0.9,
+                status: "in_mission".to_string(),
+                assigned_mission: None,
+                last_update: fixed_time(),
+            })
+            .await;
+
+        service.check_safety_violations().await.unwrap();
+        webhooks.flush().await;
+
+        let dead_letters = webhooks.dead_letters();
+        assert_eq!(dead_letters.len(), 1);
+        let event = &dead_letters[0].event;
+        assert_eq!(event.kind, WebhookEventKind::SafetyViolation);
+        assert_eq!(event.payload["audit_id"], "safety-violation-000001");
+        assert_eq!(event.payload["violation"]["drone_id"], drone_id.to_string());
+    }
+
+    #[tokio::test]
+    async fn drone_constraint_override_takes_precedence_over_global_geofence() {
+        let service = MultiDroneControlService::new("Test Service".to_string());
+        let plot_drone = Uuid::new_v4();
+        let fleet_drone = Uuid::new_v4();
+        {
+            let mut controller = service.controller.write().await;
+            controller.global_constraints = constrained_controller().global_constraints;
+            controller
+                .set_drone_constraints(
+                    plot_drone,
+                    DroneConstraintOverride {
+                        geofence_boundaries: Some(LocalPolygon::from_xy_points([
+                            (200.0, 200.0),
+                            (400.0, 200.0),
+                            (400.0, 400.0),
+                            (200.0, 400.0),
+                        ])),
+                        max_altitude_m: Some(60.0),
+                        rtl_point: Some((300.0, 210.0, 0.0)),
+                    },
+                )
+                .unwrap();
+            assert_eq!(
+                controller.rtl_point_for_drone(plot_drone),
+                (300.0, 210.0, 0.0)
+            );
+            assert_eq!(
+                controller.rtl_point_for_drone(fleet_drone),
+                controller.base_position
+            );
+            assert_eq!(
+                controller
+                    .effective_constraints_for_drone(plot_drone)
+                    .no_fly_zones,
+                controller.global_constraints.no_fly_zones
+            );
+        }
+        for drone_id in [plot_drone, fleet_drone] {
+            service
+                .update_drone_status(DroneStatus {
+                    id: drone_id,
+                    position: (300.0, 300.0, 50.0),
+                    velocity: (0.0, 0.0, 0.0),
+                    battery_level: 0.9,
+                    status: "in_mission".to_string(),
+                    assigned_mission: None,
+                    last_update: fixed_time(),
+                })
+                .await;
+        }
+
+        let violations = service.check_safety_violations().await.unwrap();
+
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].drone_id, fleet_drone);
+        assert_eq!(
+            violations[0].violation_type,
+            ViolationType::GeofenceViolation
+        );
+    }
+
+    #[test]
+    fn drone_constraint_override_rejects_degenerate_geofence() {
+        let mut controller = constrained_controller();
+        let drone_id = Uuid::new_v4();
+
+        let error = controller
+            .set_drone_constraints(
+                drone_id,
+                DroneConstraintOverride {
+                    geofence_boundaries: Some(LocalPolygon::from_xy_points([
+                        (0.0, 0.0),
+                        (1.0, 1.0),
+                    ])),
+                    ..DroneConstraintOverride::default()
+                },
+            )
+            .unwrap_err();
+
+        assert_eq!(error, GlobalConstraintValidationError::EmptyGeofence);
+        assert!(controller.get_drone_constraints(drone_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn service_check_safety_violations_flags_only_converging_pairs() {
+        let service = MultiDroneControlService::new("Test Service".to_string());
+        let status = |position, velocity| DroneStatus {
+            id: Uuid::new_v4(),
+            position,
+            velocity,
+            battery_level: 0.9,
+            status: "in_mission".to_string(),
+            assigned_mission: None,
+            last_update: fixed_time(),
+        };
+        // Head-on at 10 m/s each, 60 m apart: 3 s to a near miss.
+        let converging = [
+            status((-30.0, 100.0, 50.0), (10.0, 0.0, 0.0)),
+            status((30.0, 102.0, 50.0), (-10.0, 0.0, 0.0)),
+        ];
+        // Close together but flying apart.
+        let diverging = [
+            status((-10.0, -200.0, 50.0), (-8.0, 0.0, 0.0)),
+            status((10.0, -200.0, 50.0), (8.0, 0.0, 0.0)),
+        ];
+        for drone in converging.iter().chain(&diverging) {
+            service.update_drone_status(drone.clone()).await;
+        }
+
+        let violations = service.check_safety_violations().await.unwrap();
+
+        let collision_risks = violations
+            .iter()
+            .filter(|violation| violation.violation_type == ViolationType::CollisionRisk)
+            .collect::<Vec<_>>();
+        assert_eq!(collision_risks.len(), 1);
+        assert_eq!(collision_risks[0].severity, Severity::Critical);
+        let converging_ids = [converging[0].id, converging[1].id];
+        assert!(converging_ids.contains(&collision_risks[0].drone_id));
+    }
+
+    #[tokio::test]
+    async fn planned_formation_transition_raises_no_collision_warnings_unlike_direct_one() {
+        let service = MultiDroneControlService::new_with_config(
+            "Test Service".to_string(),
+            AutonomousSurveyConfig {
+                enabled: false,
+                runtime_mode: RuntimeMode::Simulation,
+            },
+        );
+        let line = (0..4)
+            .map(|index| (Uuid::new_v4(), (30.0 * index as f64, 0.0, 30.0)))
+            .collect::<Vec<_>>();
+        let swarm = DroneSwarm::new_owned(
+            "Transition".to_string(),
+            line.iter().map(|(drone_id, _)| *drone_id).collect(),
+            swarm::FormationType::Line,
+            "ops-team".to_string(),
+        );
+        let swarm_id = swarm.id;
+        service
+            .controller
+            .write()
+            .await
+            .register_swarm(swarm)
+            .unwrap();
+        let hover_in_line = || async {
+            for (drone_id, position) in &line {
+                service
+                    .update_drone_status(DroneStatus {
+                        id: *drone_id,
+                        position: *position,
+                        velocity: (0.0, 0.0, 0.0),
+                        battery_level: 0.9,
+                        status: "in_mission".to_string(),
+                        assigned_mission: None,
+                        last_update: Utc::now(),
+                    })
+                    .await;
+            }
+        };
+        let circle = Formation::Circle {
+            radius_m: 40.0,
+            center: (45.0, 10.0),
+        };
+        let config = FormationTransitionConfig::default();
+
+        hover_in_line().await;
+        let plan = service
+            .plan_formation_transition(swarm_id, &circle, (0.0, 0.0, 30.0), config)
+            .await
+            .unwrap();
+        let planned = service
+            .simulate_formation_transition(&plan, 0.5)
+            .await
+            .unwrap();
+
+        hover_in_line().await;
+        let slots = swarm::generate_formation_slots(&circle, line.len(), 25.0).unwrap();
+        let direct = direct_formation_transition(&line, &slots, (0.0, 0.0, 30.0), config).unwrap();
+        let unplanned = service
+            .simulate_formation_transition(&direct, 0.5)
+            .await
+            .unwrap();
+
+        assert!(plan.conflict_free);
+        assert!(planned.steps > 1);
+        assert!(planned.collision_warnings.is_empty());
+        assert!(!unplanned.collision_warnings.is_empty());
+    }
+
     #[test]
     fn safety_violation_taxonomy_has_six_types_and_four_severities() {
         assert_eq!(ViolationType::all().len(), 6);
@@ -2456,7 +2870,7 @@ mod tests {
         {
             let mut controller = service.controller.write().await;
             controller.global_constraints.emergency_landing_sites =
-                vec![(10.0, 10.0), (100.0, 100.0)];
+                vec![LocalPoint::new(10.0, 10.0), LocalPoint::new(100.0, 100.0)];
         }
         let drone_id = Uuid::new_v4();
         let stale_position = (15.0, 12.0, 120.0);
@@ -3123,22 +3537,27 @@ mod tests {
         let mut controller = MultiDroneController::new("Safety Controller".to_string());
         controller.global_constraints = GlobalConstraints {
             max_altitude_m: 120.0,
-            geofence_boundaries: vec![
+            geofence_boundaries: LocalPolygon::from_xy_points([
                 (-100.0, -100.0),
                 (100.0, -100.0),
                 (100.0, 100.0),
                 (-100.0, 100.0),
-            ],
+            ]),
             no_fly_zones: vec![NoFlyZone {
                 id: Uuid::new_v4(),
                 name: "Farmhouse".to_string(),
-                boundary: vec![(0.0, 0.0), (20.0, 0.0), (20.0, 20.0), (0.0, 20.0)],
+                boundary: LocalPolygon::from_xy_points([
+                    (0.0, 0.0),
+                    (20.0, 0.0),
+                    (20.0, 20.0),
+                    (0.0, 20.0),
+                ]),
                 altitude_restriction: Some((0.0, 120.0)),
                 reason: "people and structures".to_string(),
                 active: true,
             }],
             max_concurrent_drones: 4,
-            emergency_landing_sites: vec![(0.0, -80.0)],
+            emergency_landing_sites: vec![LocalPoint::new(0.0, -80.0)],
         };
         controller
     }
@@ -3,6 +3,7 @@ use crate::{
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use shared::LocalPoint;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -207,12 +208,12 @@ fn emergency_land_routes(
         .map(|drone_id| {
             let current_position = require_drone_position(controller, drone_id)?;
             let site = nearest_emergency_site(
-                (current_position.0, current_position.1),
+                LocalPoint::from_xyz(current_position),
                 &controller.global_constraints.emergency_landing_sites,
             );
             Ok(SwarmCommandRoute {
                 drone_id,
-                target_position: (site.0, site.1, 0.0),
+                target_position: (site.east_m, site.north_m, 0.0),
                 reason: "nearest_emergency_site".to_string(),
             })
         })
@@ -230,13 +231,14 @@ fn require_drone_position(
         .ok_or(SwarmCommandError::DroneNotFound { drone_id })
 }
 
-fn nearest_emergency_site(position: (f64, f64), sites: &[(f64, f64)]) -> (f64, f64) {
+fn nearest_emergency_site(position: LocalPoint, sites: &[LocalPoint]) -> LocalPoint {
     sites
         .iter()
         .copied()
         .min_by(|left, right| {
-            distance_2d(position, *left)
-                .partial_cmp(&distance_2d(position, *right))
+            position
+                .horizontal_distance_m(left)
+                .partial_cmp(&position.horizontal_distance_m(right))
                 .unwrap_or(std::cmp::Ordering::Equal)
         })
         .expect("emergency site list is non-empty")
@@ -341,10 +343,6 @@ fn sorted_drone_ids(drone_ids: &[Uuid]) -> Vec<Uuid> {
     ids
 }
 
-fn distance_2d(left: (f64, f64), right: (f64, f64)) -> f64 {
-    (left.0 - right.0).hypot(left.1 - right.1)
-}
-
 fn distance_3d(left: (f64, f64, f32), right: (f64, f64, f32)) -> f64 {
     let altitude_delta = f64::from(left.2 - right.2);
     (left.0 - right.0)
@@ -367,7 +365,7 @@ mod tests {
         let drone_b = Uuid::from_u128(202);
         let mut controller = MultiDroneController::new("command coordinator".to_string());
         controller.global_constraints = GlobalConstraints {
-            emergency_landing_sites: vec![(0.0, 0.0), (100.0, 0.0)],
+            emergency_landing_sites: vec![LocalPoint::new(0.0, 0.0), LocalPoint::new(100.0, 0.0)],
             ..GlobalConstraints::default()
         };
         let mut swarm = DroneSwarm::new_owned(
@@ -189,6 +189,7 @@ pub fn plan_synchronized_survey(
             checked_at,
         )
         .map_err(safety_error_to_survey_error)?;
+    let relay_note = check_lane_connectivity(controller, &lanes)?;
 
     let coverage = coverage_fraction(&lanes, bounds.area_m2());
     let progress = evaluate_synchronized_survey_progress(
@@ -226,6 +227,7 @@ pub fn plan_synchronized_survey(
     } else {
         "synchronized survey rejected by planned separation breach".to_string()
     };
+    let message = with_relay_note(message, relay_note);
     let status = progress.status.clone();
 
     Ok(SynchronizedSurveyPlan {
@@ -282,6 +284,7 @@ pub fn plan_coverage_optimization(
             checked_at,
         )
         .map_err(safety_error_to_survey_error)?;
+    let relay_note = check_lane_connectivity(controller, &lanes)?;
 
     let progress = evaluate_synchronized_survey_progress(
         &SynchronizedSurveyPlan {
@@ -322,6 +325,7 @@ pub fn plan_coverage_optimization(
             coverage * 100.0
         )
     };
+    let message = with_relay_note(message, relay_note);
 
     Ok(CoverageOptimizationPlan {
         swarm_id,
@@ -588,6 +592,71 @@ fn coverage_start_samples_by_pass(lanes: &[SurveyLane]) -> Vec<SurveySeparationS
         .collect()
 }
 
+/// Checks that every drone keeps a direct or relayed link to the base at the
+/// start and end of each synchronized pass. Partitions that strand a drone or
+/// need more relay hops than the controller allows are rejected; partitions
+/// that depend on relaying return a note for the audit trail.
+fn check_lane_connectivity(
+    controller: &MultiDroneController,
+    lanes: &[SurveyLane],
+) -> Result<Option<String>, SynchronizedSurveyError> {
+    let mut max_hop_count = 0;
+    for snapshot in lane_connectivity_snapshots(lanes) {
+        let report = controller.connectivity_report(&snapshot.positions);
+        if let Some(drone_id) = report.unreachable_drone_ids.first() {
+            return Err(SynchronizedSurveyError::SafetyRejected {
+                reason: format!(
+                    "survey partition leaves drone {drone_id} without a relay path to base within {:.0}m range",
+                    controller.communication_range_m
+                ),
+            });
+        }
+        if report.max_hop_count > controller.max_relay_hops {
+            return Err(SynchronizedSurveyError::SafetyRejected {
+                reason: format!(
+                    "farthest strip needs {} relay hops but at most {} are allowed",
+                    report.max_hop_count, controller.max_relay_hops
+                ),
+            });
+        }
+        max_hop_count = max_hop_count.max(report.max_hop_count);
+    }
+
+    Ok((max_hop_count > 1)
+        .then(|| format!("farthest strip relies on a {max_hop_count}-hop relay to base")))
+}
+
+fn lane_connectivity_snapshots(lanes: &[SurveyLane]) -> Vec<SurveySeparationSample> {
+    coverage_start_samples_by_pass(lanes)
+        .into_iter()
+        .flat_map(|start| {
+            let end = SurveySeparationSample {
+                elapsed_s: start.elapsed_s,
+                positions: lanes
+                    .iter()
+                    .filter(|lane| {
+                        (lane.synchronized_start_offset_s - start.elapsed_s).abs() <= f64::EPSILON
+                    })
+                    .map(|lane| {
+                        (
+                            lane.drone_id,
+                            (lane.end_xy.0, lane.end_xy.1, lane.planned_altitude_m),
+                        )
+                    })
+                    .collect(),
+            };
+            [start, end]
+        })
+        .collect()
+}
+
+fn with_relay_note(message: String, relay_note: Option<String>) -> String {
+    match relay_note {
+        Some(note) => format!("{message}; {note}"),
+        None => message,
+    }
+}
+
 fn coverage_lane_length(bounds: &BoundaryBounds) -> f64 {
     bounds.width_m().min(bounds.height_m())
 }
@@ -914,4 +983,62 @@ mod tests {
         );
         assert!(plan.audit[0].message.contains("multi-pass"));
     }
+
+    #[test]
+    fn synchronized_survey_notes_relay_dependence_and_rejects_excess_hops() {
+        let drone_ids = vec![
+            Uuid::from_u128(41),
+            Uuid::from_u128(42),
+            Uuid::from_u128(43),
+        ];
+        let (mut controller, swarm_id) = active_swarm_controller(drone_ids);
+        controller.communication_range_m = 100.0;
+        let checked_at = Utc.timestamp_opt(1_800_000_300, 0).unwrap();
+
+        let plan = plan_synchronized_survey(
+            &controller,
+            swarm_id,
+            rectangle_boundary_with_size(300.0, 80.0),
+            SynchronizedSurveyConfig::default(),
+            checked_at,
+        )
+        .expect("relayed survey should plan");
+        assert!(plan.audit[0].message.contains("3-hop relay"));
+
+        controller.max_relay_hops = 2;
+        let error = plan_synchronized_survey(
+            &controller,
+            swarm_id,
+            rectangle_boundary_with_size(300.0, 80.0),
+            SynchronizedSurveyConfig::default(),
+            checked_at,
+        )
+        .expect_err("farthest strip exceeds relay hop budget");
+        assert!(matches!(
+            error,
+            SynchronizedSurveyError::SafetyRejected { ref reason } if reason.contains("3 relay hops")
+        ));
+    }
+
+    #[test]
+    fn survey_partition_beyond_relay_range_is_rejected() {
+        let (mut controller, swarm_id) =
+            active_swarm_controller(vec![Uuid::from_u128(51), Uuid::from_u128(52)]);
+        controller.communication_range_m = 200.0;
+        let remote_field = vec![(600.0, 0.0), (720.0, 0.0), (720.0, 80.0), (600.0, 80.0)];
+
+        let error = plan_synchronized_survey(
+            &controller,
+            swarm_id,
+            remote_field,
+            SynchronizedSurveyConfig::default(),
+            Utc.timestamp_opt(1_800_000_400, 0).unwrap(),
+        )
+        .expect_err("remote field is out of relay range");
+
+        assert!(matches!(
+            error,
+            SynchronizedSurveyError::SafetyRejected { ref reason } if reason.contains("without a relay path")
+        ));
+    }
 }
@@ -0,0 +1,874 @@
+//! Fleet-wide timeline of the day's plan: which drone flies which mission
+//! when, with the transits, charging and idle time in between.
+//!
+//! A [`FleetSchedule`] holds the inputs (missions with their duration
+//! estimates, drone availability and charger bookings, and the assignment of
+//! missions to drones in flying order) and [`FleetSchedule::plan`] lays them
+//! out as a [`FleetTimeline`]. Missions float: a mission that runs long
+//! pushes everything after it on the same drone.
+
+use crate::mission_assignment::DroneAssignment;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Battery fraction a drone must still hold when it lands.
+pub const DEFAULT_BATTERY_RESERVE: f32 = 0.2;
+
+/// A mission to place on the timeline.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimelineMission {
+    pub mission_id: Uuid,
+    pub name: String,
+    /// Time on the mission area, from the mission cost estimate.
+    pub estimated_duration_s: f64,
+    /// Flight time between the launch site and the mission area, flown once
+    /// each way.
+    pub transit_duration_s: f64,
+    /// Battery fraction the mission uses, both transits included.
+    pub battery_required: f32,
+    /// Earliest the drone may take off for the mission.
+    pub earliest_departure: Option<DateTime<Utc>>,
+    /// When the work on the mission area has to be finished.
+    pub deadline: Option<DateTime<Utc>>,
+}
+
+/// A reserved slot on a charger. Drones only charge inside their bookings.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChargingBooking {
+    pub station_id: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DroneAvailability {
+    pub drone_id: Uuid,
+    pub available_from: DateTime<Utc>,
+    /// Battery fraction at `available_from`.
+    pub battery_level: f32,
+    /// Battery fraction gained per hour on a charger.
+    pub charge_rate_per_hour: f32,
+    pub charging_bookings: Vec<ChargingBooking>,
+}
+
+/// One mission given to one drone. A drone flies its missions in the order
+/// their assignments are listed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimelineAssignment {
+    pub drone_id: Uuid,
+    pub mission_id: Uuid,
+}
+
+impl From<&DroneAssignment> for TimelineAssignment {
+    fn from(assignment: &DroneAssignment) -> Self {
+        Self {
+            drone_id: assignment.drone_id,
+            mission_id: assignment.mission_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TimelineBlockKind {
+    Mission,
+    Transit,
+    Charging,
+    Idle,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimelineBlock {
+    pub kind: TimelineBlockKind,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// The mission a mission or transit block belongs to.
+    pub mission_id: Option<Uuid>,
+    /// The charger a charging block uses.
+    pub station_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DroneTimeline {
+    pub drone_id: Uuid,
+    pub blocks: Vec<TimelineBlock>,
+}
+
+impl DroneTimeline {
+    pub fn mission_block(&self, mission_id: Uuid) -> Option<&TimelineBlock> {
+        self.blocks.iter().find(|block| {
+            block.kind == TimelineBlockKind::Mission && block.mission_id == Some(mission_id)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeadlineMiss {
+    pub mission_id: Uuid,
+    pub drone_id: Uuid,
+    pub deadline: DateTime<Utc>,
+    pub finishes_at: DateTime<Utc>,
+    pub late_by_s: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimelineConflict {
+    /// Two drones are on the same charger at the same time.
+    OverlappingCharging {
+        station_id: String,
+        drone_ids: (Uuid, Uuid),
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+    DeadlineMissed(DeadlineMiss),
+    /// No booking lets the drone charge enough before the mission; it is
+    /// shown flying anyway.
+    InsufficientCharge {
+        drone_id: Uuid,
+        mission_id: Uuid,
+        battery_level: f32,
+        battery_needed: f32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FleetTimeline {
+    pub drones: Vec<DroneTimeline>,
+    pub conflicts: Vec<TimelineConflict>,
+    /// Missions no drone is assigned to.
+    pub unscheduled_missions: Vec<Uuid>,
+}
+
+impl FleetTimeline {
+    pub fn drone(&self, drone_id: Uuid) -> Option<&DroneTimeline> {
+        self.drones
+            .iter()
+            .find(|timeline| timeline.drone_id == drone_id)
+    }
+
+    pub fn deadline_misses(&self) -> impl Iterator<Item = &DeadlineMiss> {
+        self.conflicts.iter().filter_map(|conflict| match conflict {
+            TimelineConflict::DeadlineMissed(miss) => Some(miss),
+            _ => None,
+        })
+    }
+
+    /// Deadlines missed here that `baseline` still made.
+    pub fn deadlines_broken_since(&self, baseline: &FleetTimeline) -> Vec<DeadlineMiss> {
+        let already_missed = baseline
+            .deadline_misses()
+            .map(|miss| miss.mission_id)
+            .collect::<HashSet<_>>();
+        self.deadline_misses()
+            .filter(|miss| !already_missed.contains(&miss.mission_id))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The timeline after a mission ran long.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimelineReplan {
+    pub timeline: FleetTimeline,
+    pub broken_deadlines: Vec<DeadlineMiss>,
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum TimelineError {
+    #[error("mission {0} is not in the schedule")]
+    UnknownMission(Uuid),
+    #[error("drone {0} has no availability in the schedule")]
+    UnknownDrone(Uuid),
+    #[error("mission {0} is assigned more than once")]
+    DuplicateAssignment(Uuid),
+    #[error("mission {mission_id} is invalid: {reason}")]
+    InvalidMission { mission_id: Uuid, reason: String },
+}
+
+/// Inputs to the fleet timeline.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FleetSchedule {
+    pub missions: Vec<TimelineMission>,
+    pub drones: Vec<DroneAvailability>,
+    pub assignments: Vec<TimelineAssignment>,
+    pub battery_reserve: f32,
+    /// Seconds each mission has run beyond its estimate.
+    pub overruns: HashMap<Uuid, f64>,
+}
+
+impl Default for FleetSchedule {
+    fn default() -> Self {
+        Self::new(Vec::new(), Vec::new(), Vec::new())
+    }
+}
+
+impl FleetSchedule {
+    pub fn new(
+        missions: Vec<TimelineMission>,
+        drones: Vec<DroneAvailability>,
+        assignments: Vec<TimelineAssignment>,
+    ) -> Self {
+        Self {
+            missions,
+            drones,
+            assignments,
+            battery_reserve: DEFAULT_BATTERY_RESERVE,
+            overruns: HashMap::new(),
+        }
+    }
+
+    pub fn with_battery_reserve(mut self, battery_reserve: f32) -> Self {
+        self.battery_reserve = battery_reserve;
+        self
+    }
+
+    /// Adds `mission` at the end of `drone_id`'s day.
+    pub fn add_mission(&mut self, mission: TimelineMission, drone_id: Uuid) {
+        self.assignments.push(TimelineAssignment {
+            drone_id,
+            mission_id: mission.mission_id,
+        });
+        self.missions.push(mission);
+    }
+
+    /// Records that `mission_id` ran `overrun_s` seconds longer than
+    /// estimated.
+    pub fn record_overrun(&mut self, mission_id: Uuid, overrun_s: f64) {
+        *self.overruns.entry(mission_id).or_default() += overrun_s;
+    }
+
+    /// Plans the timeline as if `mission_id` had run `overrun_s` seconds
+    /// long, reporting the deadlines that breaks. The schedule is unchanged.
+    pub fn replan_after_overrun(
+        &self,
+        mission_id: Uuid,
+        overrun_s: f64,
+    ) -> Result<TimelineReplan, TimelineError> {
+        let baseline = self.plan()?;
+        let mut delayed = self.clone();
+        delayed.record_overrun(mission_id, overrun_s);
+        let timeline = delayed.plan()?;
+        Ok(TimelineReplan {
+            broken_deadlines: timeline.deadlines_broken_since(&baseline),
+            timeline,
+        })
+    }
+
+    pub fn plan(&self) -> Result<FleetTimeline, TimelineError> {
+        let missions = self
+            .missions
+            .iter()
+            .map(|mission| (mission.mission_id, mission))
+            .collect::<HashMap<_, _>>();
+        if let Some(overrun) = self
+            .overruns
+            .keys()
+            .find(|mission_id| !missions.contains_key(mission_id))
+        {
+            return Err(TimelineError::UnknownMission(*overrun));
+        }
+        for mission in &self.missions {
+            mission.validate()?;
+        }
+
+        let mut queues: HashMap<Uuid, Vec<&TimelineMission>> = HashMap::new();
+        let mut assigned = HashSet::new();
+        for assignment in &self.assignments {
+            let mission = missions
+                .get(&assignment.mission_id)
+                .ok_or(TimelineError::UnknownMission(assignment.mission_id))?;
+            if !self
+                .drones
+                .iter()
+                .any(|drone| drone.drone_id == assignment.drone_id)
+            {
+                return Err(TimelineError::UnknownDrone(assignment.drone_id));
+            }
+            if !assigned.insert(assignment.mission_id) {
+                return Err(TimelineError::DuplicateAssignment(assignment.mission_id));
+            }
+            queues.entry(assignment.drone_id).or_default().push(mission);
+        }
+
+        let mut conflicts = Vec::new();
+        let drones = self
+            .drones
+            .iter()
+            .map(|drone| {
+                let queue = queues.remove(&drone.drone_id).unwrap_or_default();
+                self.plan_drone(drone, &queue, &mut conflicts)
+            })
+            .collect::<Vec<_>>();
+        conflicts.extend(overlapping_charging(&drones));
+
+        Ok(FleetTimeline {
+            drones,
+            conflicts,
+            unscheduled_missions: self
+                .missions
+                .iter()
+                .map(|mission| mission.mission_id)
+                .filter(|mission_id| !assigned.contains(mission_id))
+                .collect(),
+        })
+    }
+
+    fn plan_drone(
+        &self,
+        drone: &DroneAvailability,
+        missions: &[&TimelineMission],
+        conflicts: &mut Vec<TimelineConflict>,
+    ) -> DroneTimeline {
+        let mut bookings = drone.charging_bookings.iter().collect::<Vec<_>>();
+        bookings.sort_by_key(|booking| booking.start);
+        let mut day = DroneDay {
+            blocks: Vec::new(),
+            cursor: drone.available_from,
+            battery: drone.battery_level,
+        };
+
+        for mission in missions {
+            let battery_needed = mission.battery_required + self.battery_reserve;
+            if day.battery < battery_needed {
+                day.charge(&bookings, drone.charge_rate_per_hour, battery_needed);
+                if day.battery < battery_needed {
+                    conflicts.push(TimelineConflict::InsufficientCharge {
+                        drone_id: drone.drone_id,
+                        mission_id: mission.mission_id,
+                        battery_level: day.battery,
+                        battery_needed,
+                    });
+                }
+            }
+
+            if let Some(earliest) = mission.earliest_departure {
+                day.wait_until(earliest);
+            }
+            let transit = seconds(mission.transit_duration_s);
+            let on_site = seconds(
+                mission.estimated_duration_s
+                    + self
+                        .overruns
+                        .get(&mission.mission_id)
+                        .copied()
+                        .unwrap_or_default(),
+            );
+            day.push(
+                TimelineBlockKind::Transit,
+                transit,
+                Some(mission.mission_id),
+            );
+            day.push(
+                TimelineBlockKind::Mission,
+                on_site,
+                Some(mission.mission_id),
+            );
+            let finishes_at = day.cursor;
+            day.push(
+                TimelineBlockKind::Transit,
+                transit,
+                Some(mission.mission_id),
+            );
+            day.battery = (day.battery - mission.battery_required).max(0.0);
+
+            if let Some(deadline) = mission.deadline.filter(|deadline| finishes_at > *deadline) {
+                conflicts.push(TimelineConflict::DeadlineMissed(DeadlineMiss {
+                    mission_id: mission.mission_id,
+                    drone_id: drone.drone_id,
+                    deadline,
+                    finishes_at,
+                    late_by_s: (finishes_at - deadline).num_milliseconds() as f64 / 1000.0,
+                }));
+            }
+        }
+
+        DroneTimeline {
+            drone_id: drone.drone_id,
+            blocks: day.blocks,
+        }
+    }
+}
+
+impl TimelineMission {
+    fn validate(&self) -> Result<(), TimelineError> {
+        let reason = if !(self.estimated_duration_s.is_finite() && self.estimated_duration_s >= 0.0)
+        {
+            "estimated duration must be a non-negative number of seconds"
+        } else if !(self.transit_duration_s.is_finite() && self.transit_duration_s >= 0.0) {
+            "transit duration must be a non-negative number of seconds"
+        } else if !(0.0..=1.0).contains(&self.battery_required) {
+            "battery required must be between 0 and 1"
+        } else {
+            return Ok(());
+        };
+        Err(TimelineError::InvalidMission {
+            mission_id: self.mission_id,
+            reason: reason.to_string(),
+        })
+    }
+}
+
+/// A drone's blocks as they are laid out, and where it stands at the end.
+struct DroneDay {
+    blocks: Vec<TimelineBlock>,
+    cursor: DateTime<Utc>,
+    battery: f32,
+}
+
+impl DroneDay {
+    fn push(
+        &mut self,
+        kind: TimelineBlockKind,
+        duration: chrono::Duration,
+        mission_id: Option<Uuid>,
+    ) {
+        let end = self.cursor + duration;
+        self.blocks.push(TimelineBlock {
+            kind,
+            start: self.cursor,
+            end,
+            mission_id,
+            station_id: None,
+        });
+        self.cursor = end;
+    }
+
+    fn wait_until(&mut self, at: DateTime<Utc>) {
+        if at > self.cursor {
+            self.push(TimelineBlockKind::Idle, at - self.cursor, None);
+        }
+    }
+
+    /// Charges in the bookings still ahead, each until the battery is full
+    /// or the booking ends, until the battery reaches `battery_needed`.
+    fn charge(&mut self, bookings: &[&ChargingBooking], rate_per_hour: f32, battery_needed: f32) {
+        if rate_per_hour <= 0.0 {
+            return;
+        }
+        for booking in bookings {
+            if self.battery >= battery_needed {
+                break;
+            }
+            if booking.end <= self.cursor {
+                continue;
+            }
+            self.wait_until(booking.start);
+            let hours_to_full = f64::from((1.0 - self.battery) / rate_per_hour);
+            let end = booking
+                .end
+                .min(self.cursor + seconds(hours_to_full * 3600.0));
+            let hours = (end - self.cursor).num_milliseconds() as f64 / 3_600_000.0;
+            self.push(TimelineBlockKind::Charging, end - self.cursor, None);
+            if let Some(block) = self.blocks.last_mut() {
+                block.station_id = Some(booking.station_id.clone());
+            }
+            self.battery = (self.battery + rate_per_hour * hours as f32).min(1.0);
+        }
+    }
+}
+
+fn seconds(seconds: f64) -> chrono::Duration {
+    chrono::Duration::milliseconds((seconds * 1000.0).round() as i64)
+}
+
+fn overlapping_charging(drones: &[DroneTimeline]) -> Vec<TimelineConflict> {
+    let charging = drones
+        .iter()
+        .flat_map(|timeline| {
+            timeline
+                .blocks
+                .iter()
+                .filter(|block| block.kind == TimelineBlockKind::Charging)
+                .map(move |block| (timeline.drone_id, block))
+        })
+        .collect::<Vec<_>>();
+
+    let mut conflicts = Vec::new();
+    for (index, (first_drone, first)) in charging.iter().enumerate() {
+        for (second_drone, second) in &charging[index + 1..] {
+            if first_drone != second_drone
+                && first.station_id == second.station_id
+                && first.start < second.end
+                && second.start < first.end
+            {
+                conflicts.push(TimelineConflict::OverlappingCharging {
+                    station_id: first.station_id.clone().unwrap_or_default(),
+                    drone_ids: (*first_drone, *second_drone),
+                    start: first.start.max(second.start),
+                    end: first.end.min(second.end),
+                });
+            }
+        }
+    }
+    conflicts
+}
+
+/// Read-only REST view of the fleet timeline.
+pub struct FleetTimelineApi;
+
+impl FleetTimelineApi {
+    pub fn router(schedule: Arc<RwLock<FleetSchedule>>) -> Router {
+        Router::new()
+            .route("/api/fleet/timeline", get(get_fleet_timeline))
+            .with_state(schedule)
+    }
+}
+
+/// What-if changes applied to a copy of the schedule for one response.
+#[derive(Debug, Default, Deserialize)]
+pub struct TimelineQuery {
+    /// Adds a hypothetical mission at the end of this drone's day.
+    pub add_mission_drone_id: Option<Uuid>,
+    pub add_mission_duration_s: Option<f64>,
+    pub add_mission_transit_s: Option<f64>,
+    pub add_mission_battery: Option<f32>,
+    pub add_mission_earliest_departure: Option<DateTime<Utc>>,
+    pub add_mission_deadline: Option<DateTime<Utc>>,
+    /// Treats this mission as having run `delay_s` seconds long.
+    pub delay_mission_id: Option<Uuid>,
+    pub delay_s: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FleetTimelineResponse {
+    #[serde(flatten)]
+    pub timeline: FleetTimeline,
+    /// Id given to the hypothetical mission, when one was added.
+    pub hypothetical_mission_id: Option<Uuid>,
+    /// Deadlines the what-if changes break.
+    pub broken_deadlines: Vec<DeadlineMiss>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub message: String,
+}
+
+async fn get_fleet_timeline(
+    State(schedule): State<Arc<RwLock<FleetSchedule>>>,
+    Query(query): Query<TimelineQuery>,
+) -> Result<Json<FleetTimelineResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let schedule = schedule.read().await.clone();
+    let baseline = schedule.plan().map_err(invalid_schedule)?;
+
+    let mut what_if = schedule;
+    let hypothetical_mission_id = match (query.add_mission_drone_id, query.add_mission_duration_s) {
+        (Some(drone_id), Some(duration_s)) => {
+            let mission_id = Uuid::new_v4();
+            what_if.add_mission(
+                TimelineMission {
+                    mission_id,
+                    name: "hypothetical".to_string(),
+                    estimated_duration_s: duration_s,
+                    transit_duration_s: query.add_mission_transit_s.unwrap_or_default(),
+                    battery_required: query.add_mission_battery.unwrap_or_default(),
+                    earliest_departure: query.add_mission_earliest_departure,
+                    deadline: query.add_mission_deadline,
+                },
+                drone_id,
+            );
+            Some(mission_id)
+        }
+        (None, None) => None,
+        _ => {
+            return Err(bad_request(
+                "add_mission_drone_id and add_mission_duration_s go together",
+            ))
+        }
+    };
+    match (query.delay_mission_id, query.delay_s) {
+        (Some(mission_id), Some(delay_s)) => what_if.record_overrun(mission_id, delay_s),
+        (None, None) => {}
+        _ => return Err(bad_request("delay_mission_id and delay_s go together")),
+    }
+
+    let timeline = what_if.plan().map_err(invalid_schedule)?;
+    Ok(Json(FleetTimelineResponse {
+        broken_deadlines: timeline.deadlines_broken_since(&baseline),
+        timeline,
+        hypothetical_mission_id,
+    }))
+}
+
+fn bad_request(message: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: "INVALID_QUERY".to_string(),
+            message: message.to_string(),
+        }),
+    )
+}
+
+fn invalid_schedule(error: TimelineError) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(ErrorResponse {
+            error: "INVALID_SCHEDULE".to_string(),
+            message: error.to_string(),
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use chrono::TimeZone;
+    use tower::ServiceExt;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 5, 1, hour, minute, 0).unwrap()
+    }
+
+    fn mission(
+        name: &str,
+        duration_min: f64,
+        transit_min: f64,
+        battery_required: f32,
+        deadline: DateTime<Utc>,
+    ) -> TimelineMission {
+        TimelineMission {
+            mission_id: Uuid::new_v4(),
+            name: name.to_string(),
+            estimated_duration_s: duration_min * 60.0,
+            transit_duration_s: transit_min * 60.0,
+            battery_required,
+            earliest_departure: None,
+            deadline: Some(deadline),
+        }
+    }
+
+    struct Scenario {
+        schedule: FleetSchedule,
+        drone_a: Uuid,
+        drone_b: Uuid,
+        missions: Vec<Uuid>,
+    }
+
+    /// Drone A flies two missions with a charge between them, which it can
+    /// only take in its 09:00-10:00 booking on pad-1. Drone B flies two
+    /// missions back to back, the first not before 08:30.
+    fn scenario() -> Scenario {
+        let drone_a = Uuid::new_v4();
+        let drone_b = Uuid::new_v4();
+        let north = mission("north", 30.0, 5.0, 0.5, at(9, 0));
+        let east = mission("east", 40.0, 5.0, 0.6, at(11, 0));
+        let mut south = mission("south", 60.0, 10.0, 0.5, at(10, 0));
+        south.earliest_departure = Some(at(8, 30));
+        let west = mission("west", 20.0, 10.0, 0.15, at(10, 30));
+        let missions = [&north, &east, &south, &west].map(|mission| mission.mission_id);
+        let assignments = [
+            (drone_a, &north),
+            (drone_a, &east),
+            (drone_b, &south),
+            (drone_b, &west),
+        ]
+        .map(|(drone_id, mission)| TimelineAssignment {
+            drone_id,
+            mission_id: mission.mission_id,
+        })
+        .to_vec();
+
+        Scenario {
+            schedule: FleetSchedule::new(
+                vec![north, east, south, west],
+                vec![
+                    DroneAvailability {
+                        drone_id: drone_a,
+                        available_from: at(8, 0),
+                        battery_level: 1.0,
+                        charge_rate_per_hour: 1.0,
+                        charging_bookings: vec![ChargingBooking {
+                            station_id: "pad-1".to_string(),
+                            start: at(9, 0),
+                            end: at(10, 0),
+                        }],
+                    },
+                    DroneAvailability {
+                        drone_id: drone_b,
+                        available_from: at(8, 0),
+                        battery_level: 0.9,
+                        charge_rate_per_hour: 1.0,
+                        charging_bookings: vec![],
+                    },
+                ],
+                assignments,
+            ),
+            drone_a,
+            drone_b,
+            missions: missions.to_vec(),
+        }
+    }
+
+    fn spans(timeline: &DroneTimeline) -> Vec<(TimelineBlockKind, DateTime<Utc>, DateTime<Utc>)> {
+        timeline
+            .blocks
+            .iter()
+            .map(|block| (block.kind, block.start, block.end))
+            .collect()
+    }
+
+    #[test]
+    fn drones_fly_their_missions_in_order_with_charging_and_idle_gaps() {
+        use TimelineBlockKind::*;
+        let scenario = scenario();
+        let timeline = scenario.schedule.plan().unwrap();
+
+        assert_eq!(
+            spans(timeline.drone(scenario.drone_a).unwrap()),
+            vec![
+                (Transit, at(8, 0), at(8, 5)),
+                (Mission, at(8, 5), at(8, 35)),
+                (Transit, at(8, 35), at(8, 40)),
+                (Idle, at(8, 40), at(9, 0)),
+                (Charging, at(9, 0), at(9, 30)),
+                (Transit, at(9, 30), at(9, 35)),
+                (Mission, at(9, 35), at(10, 15)),
+                (Transit, at(10, 15), at(10, 20)),
+            ]
+        );
+        assert_eq!(
+            timeline.drone(scenario.drone_a).unwrap().blocks[4].station_id,
+            Some("pad-1".to_string())
+        );
+        assert_eq!(
+            spans(timeline.drone(scenario.drone_b).unwrap()),
+            vec![
+                (Idle, at(8, 0), at(8, 30)),
+                (Transit, at(8, 30), at(8, 40)),
+                (Mission, at(8, 40), at(9, 40)),
+                (Transit, at(9, 40), at(9, 50)),
+                (Transit, at(9, 50), at(10, 0)),
+                (Mission, at(10, 0), at(10, 20)),
+                (Transit, at(10, 20), at(10, 30)),
+            ]
+        );
+        assert!(timeline.conflicts.is_empty());
+        assert!(timeline.unscheduled_missions.is_empty());
+    }
+
+    #[test]
+    fn an_overrun_shifts_later_blocks_and_reports_the_deadlines_it_breaks() {
+        let scenario = scenario();
+        let (south, west) = (scenario.missions[2], scenario.missions[3]);
+
+        let replan = scenario
+            .schedule
+            .replan_after_overrun(south, 20.0 * 60.0)
+            .unwrap();
+
+        let drone_b = replan.timeline.drone(scenario.drone_b).unwrap();
+        // South now finishes exactly on its 10:00 deadline; west is pushed
+        // ten minutes past its own.
+        assert_eq!(drone_b.mission_block(south).unwrap().end, at(10, 0));
+        let west_block = drone_b.mission_block(west).unwrap();
+        assert_eq!((west_block.start, west_block.end), (at(10, 20), at(10, 40)));
+        assert_eq!(
+            replan.broken_deadlines,
+            vec![DeadlineMiss {
+                mission_id: west,
+                drone_id: scenario.drone_b,
+                deadline: at(10, 30),
+                finishes_at: at(10, 40),
+                late_by_s: 600.0,
+            }]
+        );
+        // Drone A's day is untouched and the schedule itself is unchanged.
+        assert_eq!(
+            replan.timeline.drone(scenario.drone_a),
+            scenario.schedule.plan().unwrap().drone(scenario.drone_a)
+        );
+        assert!(scenario.schedule.overruns.is_empty());
+    }
+
+    #[test]
+    fn charging_shortfalls_and_shared_chargers_are_reported() {
+        let mut scenario = scenario();
+        // West now needs drone B to charge, but its five minutes on pad-1
+        // cannot top it up. North running long pushes drone A's charge into
+        // the same five minutes.
+        scenario.schedule.drones[1]
+            .charging_bookings
+            .push(ChargingBooking {
+                station_id: "pad-1".to_string(),
+                start: at(9, 50),
+                end: at(9, 55),
+            });
+        scenario.schedule.missions[3].battery_required = 0.3;
+        scenario
+            .schedule
+            .record_overrun(scenario.missions[0], 70.0 * 60.0);
+
+        let timeline = scenario.schedule.plan().unwrap();
+
+        assert!(timeline.conflicts.iter().any(|conflict| matches!(
+            conflict,
+            TimelineConflict::InsufficientCharge { drone_id, mission_id, .. }
+                if *drone_id == scenario.drone_b && *mission_id == scenario.missions[3]
+        )));
+        assert!(timeline
+            .conflicts
+            .contains(&TimelineConflict::OverlappingCharging {
+                station_id: "pad-1".to_string(),
+                drone_ids: (scenario.drone_a, scenario.drone_b),
+                start: at(9, 50),
+                end: at(9, 55),
+            }));
+    }
+
+    #[tokio::test]
+    async fn timeline_endpoint_applies_what_if_missions_without_keeping_them() {
+        let scenario = scenario();
+        let schedule = Arc::new(RwLock::new(scenario.schedule));
+        let app = FleetTimelineApi::router(schedule.clone());
+        let get = |uri: String| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let status = response.status();
+                let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (
+                    status,
+                    serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+                )
+            }
+        };
+
+        let (status, what_if) = get(format!(
+            "/api/fleet/timeline?add_mission_drone_id={}&add_mission_duration_s=1800&add_mission_deadline=2026-05-01T10:30:00Z",
+            scenario.drone_a
+        ))
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let hypothetical = what_if["hypothetical_mission_id"].as_str().unwrap();
+        assert_eq!(what_if["broken_deadlines"][0]["mission_id"], hypothetical);
+        assert_eq!(
+            what_if["broken_deadlines"][0]["finishes_at"],
+            "2026-05-01T10:50:00Z"
+        );
+
+        let (_, current) = get("/api/fleet/timeline".to_string()).await;
+        assert_eq!(current["hypothetical_mission_id"], serde_json::Value::Null);
+        assert_eq!(schedule.read().await.missions.len(), 4);
+
+        let (status, _) = get("/api/fleet/timeline?delay_s=60".to_string()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
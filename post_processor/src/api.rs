@@ -0,0 +1,1055 @@
+use crate::artifacts::ArtifactManifest;
+use crate::grid_store::{extract_window, GridWindow};
+use crate::preview::{stored_preview_path, PreviewSize};
+use crate::report_schedule::{
+    ReportSchedule, ReportScheduleError, ReportScheduleRequest, ReportScheduler,
+    ScheduleAuditEntry, ScheduleRun,
+};
+use crate::thumbnail::{
+    render_result_thumbnail, ThumbnailCache, ThumbnailError, DEFAULT_THUMBNAIL_SIZE,
+};
+use crate::work_orders::{
+    WorkOrder, WorkOrderError, WorkOrderQuery, WorkOrderRequest, WorkOrderTransitionRequest,
+    WorkOrderUpdate,
+};
+use crate::{PartialGridResult, PostProcessorService};
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use shared::webhooks::WebhookDeadLetter;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Browser cache lifetime for rendered thumbnails and previews; results never
+/// change once stored, so this only bounds how long a deleted result stays
+/// visible.
+const THUMBNAIL_CACHE_CONTROL: &str = "public, max-age=86400";
+
+/// Most cells a single window request returns; larger areas go through the
+/// previews.
+const MAX_WINDOW_CELLS: usize = 1024 * 1024;
+
+/// Shared handles served by the post_processor REST API.
+#[derive(Clone)]
+pub struct PostProcessorApiState {
+    pub report_scheduler: Arc<ReportScheduler>,
+    pub service: Arc<PostProcessorService>,
+    pub thumbnails: Arc<Mutex<ThumbnailCache>>,
+}
+
+/// The post_processor REST routes. The crate ships no server binary: the
+/// service embedding it mounts this router and starts the schedule loop with
+/// [`ReportScheduler::spawn`].
+pub fn router(state: PostProcessorApiState) -> Router {
+    Router::new()
+        .route(
+            "/report-schedules",
+            get(list_report_schedules).post(create_report_schedule),
+        )
+        .route(
+            "/report-schedules/:schedule_id",
+            get(get_report_schedule)
+                .put(update_report_schedule)
+                .delete(delete_report_schedule),
+        )
+        .route(
+            "/report-schedules/:schedule_id/runs",
+            get(list_report_schedule_runs),
+        )
+        .route(
+            "/report-schedules/:schedule_id/audit",
+            get(list_report_schedule_audit),
+        )
+        .route("/jobs/:job_id/artifacts", get(get_job_artifacts))
+        .route("/jobs/:job_id/partial-result", get(get_job_partial_result))
+        .route("/results/:result_id/thumbnail", get(get_result_thumbnail))
+        .route("/results/:result_id/preview", get(get_result_preview))
+        .route("/results/:result_id/window", get(get_result_window))
+        .route("/webhooks/dead-letters", get(list_webhook_dead_letters))
+        .route(
+            "/work-orders",
+            get(list_work_orders).post(create_work_order),
+        )
+        .route(
+            "/work-orders/:work_order_id",
+            get(get_work_order)
+                .put(update_work_order)
+                .delete(delete_work_order),
+        )
+        .route(
+            "/work-orders/:work_order_id/transition",
+            post(transition_work_order),
+        )
+        .with_state(state)
+}
+
+type ApiError = (StatusCode, String);
+
+fn schedule_error_response(error: ReportScheduleError) -> ApiError {
+    let status = match error {
+        ReportScheduleError::NotFound { .. } => StatusCode::NOT_FOUND,
+        ReportScheduleError::Persistence { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        ReportScheduleError::InvalidCron { .. }
+        | ReportScheduleError::InvalidInterval
+        | ReportScheduleError::EmptyOutputFormats
+        | ReportScheduleError::EmptyName => StatusCode::UNPROCESSABLE_ENTITY,
+    };
+    (status, error.to_string())
+}
+
+fn work_order_error_response(error: WorkOrderError) -> ApiError {
+    let status = match error {
+        WorkOrderError::NotFound { .. } | WorkOrderError::ResultNotFound { .. } => {
+            StatusCode::NOT_FOUND
+        }
+        WorkOrderError::IllegalTransition { .. } => StatusCode::CONFLICT,
+        WorkOrderError::Persistence { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        WorkOrderError::RecommendationNotFound { .. }
+        | WorkOrderError::NoZones { .. }
+        | WorkOrderError::UnknownField { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+    };
+    (status, error.to_string())
+}
+
+fn thumbnail_error_response(error: ThumbnailError) -> ApiError {
+    let status = match error {
+        ThumbnailError::NotGrid { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        ThumbnailError::InvalidSize { .. } => StatusCode::BAD_REQUEST,
+        ThumbnailError::InvalidGrid { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        ThumbnailError::Render { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, error.to_string())
+}
+
+#[derive(Debug, Deserialize)]
+struct ThumbnailQuery {
+    size: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PreviewQuery {
+    size: PreviewSize,
+}
+
+async fn list_report_schedules(
+    State(state): State<PostProcessorApiState>,
+) -> Json<Vec<ReportSchedule>> {
+    Json(state.report_scheduler.list_schedules().await)
+}
+
+async fn create_report_schedule(
+    State(state): State<PostProcessorApiState>,
+    Json(request): Json<ReportScheduleRequest>,
+) -> Result<(StatusCode, Json<ReportSchedule>), ApiError> {
+    state
+        .report_scheduler
+        .create_schedule(request)
+        .await
+        .map(|schedule| (StatusCode::CREATED, Json(schedule)))
+        .map_err(schedule_error_response)
+}
+
+async fn get_report_schedule(
+    State(state): State<PostProcessorApiState>,
+    Path(schedule_id): Path<Uuid>,
+) -> Result<Json<ReportSchedule>, ApiError> {
+    state
+        .report_scheduler
+        .get_schedule(schedule_id)
+        .await
+        .map(Json)
+        .ok_or_else(|| schedule_error_response(ReportScheduleError::NotFound { schedule_id }))
+}
+
+async fn update_report_schedule(
+    State(state): State<PostProcessorApiState>,
+    Path(schedule_id): Path<Uuid>,
+    Json(request): Json<ReportScheduleRequest>,
+) -> Result<Json<ReportSchedule>, ApiError> {
+    state
+        .report_scheduler
+        .update_schedule(schedule_id, request)
+        .await
+        .map(Json)
+        .map_err(schedule_error_response)
+}
+
+async fn delete_report_schedule(
+    State(state): State<PostProcessorApiState>,
+    Path(schedule_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .report_scheduler
+        .delete_schedule(schedule_id)
+        .await
+        .map(|()| StatusCode::NO_CONTENT)
+        .map_err(schedule_error_response)
+}
+
+async fn list_report_schedule_runs(
+    State(state): State<PostProcessorApiState>,
+    Path(schedule_id): Path<Uuid>,
+) -> Result<Json<Vec<ScheduleRun>>, ApiError> {
+    if state
+        .report_scheduler
+        .get_schedule(schedule_id)
+        .await
+        .is_none()
+    {
+        return Err(schedule_error_response(ReportScheduleError::NotFound {
+            schedule_id,
+        }));
+    }
+    Ok(Json(state.report_scheduler.runs_for(schedule_id).await))
+}
+
+async fn list_report_schedule_audit(
+    State(state): State<PostProcessorApiState>,
+    Path(schedule_id): Path<Uuid>,
+) -> Json<Vec<ScheduleAuditEntry>> {
+    Json(state.report_scheduler.audit_for(schedule_id).await)
+}
+
+async fn get_job_artifacts(
+    State(state): State<PostProcessorApiState>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<ArtifactManifest>, ApiError> {
+    if let Some(manifest) = state.service.job_artifacts(&job_id) {
+        return Ok(Json(manifest));
+    }
+    let message = match state.service.get_job_status(&job_id).await {
+        Some(_) => format!("job {job_id} has no artifacts"),
+        None => format!("job {job_id} not found"),
+    };
+    Err((StatusCode::NOT_FOUND, message))
+}
+
+/// Latest partial grid of a job run with prioritized ROIs, with how much of
+/// it is assembled.
+async fn get_job_partial_result(
+    State(state): State<PostProcessorApiState>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<PartialGridResult>, ApiError> {
+    if let Some(partial) = state.service.latest_partial_result(&job_id) {
+        return Ok(Json(partial));
+    }
+    let message = match state.service.get_job_status(&job_id).await {
+        Some(_) => format!("job {job_id} has no partial result"),
+        None => format!("job {job_id} not found"),
+    };
+    Err((StatusCode::NOT_FOUND, message))
+}
+
+async fn get_result_thumbnail(
+    State(state): State<PostProcessorApiState>,
+    Path(result_id): Path<Uuid>,
+    Query(query): Query<ThumbnailQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let size = query.size.unwrap_or(DEFAULT_THUMBNAIL_SIZE);
+    let Some(result) = state.service.get_result(&result_id).await else {
+        state.thumbnails.lock().await.evict_result(&result_id);
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("analysis result {result_id} not found"),
+        ));
+    };
+
+    let cached = state.thumbnails.lock().await.get(&result_id, size).cloned();
+    let png = match cached {
+        Some(png) => png,
+        None => {
+            // Rendered without holding the cache, so one slow render does not
+            // stall every other thumbnail request.
+            let png = tokio::task::spawn_blocking(move || render_result_thumbnail(&result, size))
+                .await
+                .map_err(|error| (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()))?
+                .map_err(thumbnail_error_response)?;
+            state
+                .thumbnails
+                .lock()
+                .await
+                .insert(result_id, size, png.clone());
+            png
+        }
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/png"),
+            (header::CACHE_CONTROL, THUMBNAIL_CACHE_CONTROL),
+        ],
+        png,
+    ))
+}
+
+async fn get_result_preview(
+    State(state): State<PostProcessorApiState>,
+    Path(result_id): Path<Uuid>,
+    Query(query): Query<PreviewQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let file_path = {
+        let result = state.service.get_result(&result_id).await.ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("analysis result {result_id} not found"),
+            )
+        })?;
+        stored_preview_path(&result, query.size)
+            .cloned()
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    format!(
+                        "analysis result {result_id} has no {} preview",
+                        query.size.as_str()
+                    ),
+                )
+            })?
+    };
+
+    let png = tokio::fs::read(&file_path).await.map_err(|error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("failed to read preview {}: {error}", file_path.display()),
+        )
+    })?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/png"),
+            (header::CACHE_CONTROL, THUMBNAIL_CACHE_CONTROL),
+        ],
+        png,
+    ))
+}
+
+/// Cells of a grid result window; masked cells are `null`.
+#[derive(Debug, Serialize)]
+struct GridWindowResponse {
+    result_id: Uuid,
+    window: GridWindow,
+    values: Vec<f32>,
+}
+
+async fn get_result_window(
+    State(state): State<PostProcessorApiState>,
+    Path(result_id): Path<Uuid>,
+    Query(window): Query<GridWindow>,
+) -> Result<Json<GridWindowResponse>, ApiError> {
+    if window.cell_count() > MAX_WINDOW_CELLS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "window of {} cells exceeds the {MAX_WINDOW_CELLS} cell limit",
+                window.cell_count()
+            ),
+        ));
+    }
+    let result = state.service.get_result(&result_id).await.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("analysis result {result_id} not found"),
+        )
+    })?;
+    let crate::ResultData::GridData { width, height, .. } = &result.data else {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("analysis result {result_id} is not a grid"),
+        ));
+    };
+    let values = extract_window(&result.data, window).ok_or_else(|| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("window does not lie inside the {width}x{height} grid"),
+        )
+    })?;
+    Ok(Json(GridWindowResponse {
+        result_id,
+        window,
+        values,
+    }))
+}
+
+async fn list_webhook_dead_letters(
+    State(state): State<PostProcessorApiState>,
+) -> Json<Vec<WebhookDeadLetter>> {
+    Json(state.service.webhook_dead_letters())
+}
+
+async fn list_work_orders(
+    State(state): State<PostProcessorApiState>,
+    Query(query): Query<WorkOrderQuery>,
+) -> Json<Vec<WorkOrder>> {
+    Json(state.service.work_orders(&query))
+}
+
+async fn create_work_order(
+    State(state): State<PostProcessorApiState>,
+    Json(request): Json<WorkOrderRequest>,
+) -> Result<(StatusCode, Json<WorkOrder>), ApiError> {
+    state
+        .service
+        .create_work_order(request)
+        .map(|work_order| (StatusCode::CREATED, Json(work_order)))
+        .map_err(work_order_error_response)
+}
+
+async fn get_work_order(
+    State(state): State<PostProcessorApiState>,
+    Path(work_order_id): Path<Uuid>,
+) -> Result<Json<WorkOrder>, ApiError> {
+    state
+        .service
+        .work_order(&work_order_id)
+        .map(Json)
+        .ok_or_else(|| work_order_error_response(WorkOrderError::NotFound { work_order_id }))
+}
+
+async fn update_work_order(
+    State(state): State<PostProcessorApiState>,
+    Path(work_order_id): Path<Uuid>,
+    Json(update): Json<WorkOrderUpdate>,
+) -> Result<Json<WorkOrder>, ApiError> {
+    state
+        .service
+        .update_work_order(work_order_id, update)
+        .map(Json)
+        .map_err(work_order_error_response)
+}
+
+async fn delete_work_order(
+    State(state): State<PostProcessorApiState>,
+    Path(work_order_id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .service
+        .delete_work_order(work_order_id)
+        .map(|()| StatusCode::NO_CONTENT)
+        .map_err(work_order_error_response)
+}
+
+async fn transition_work_order(
+    State(state): State<PostProcessorApiState>,
+    Path(work_order_id): Path<Uuid>,
+    Json(request): Json<WorkOrderTransitionRequest>,
+) -> Result<Json<WorkOrder>, ApiError> {
+    state
+        .service
+        .transition_work_order(work_order_id, request)
+        .map(Json)
+        .map_err(work_order_error_response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report_generator::{CompanyInfo, OutputFormat, ReportConfig, ReportGenerator};
+    use crate::report_schedule::{NoSessionSource, ReportScheduleStore, SystemScheduleClock};
+    use crate::{ndvi_analysis, ProcessingParameters};
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, Request},
+    };
+    use chrono::Utc;
+    use serde_json::json;
+    use tower::ServiceExt;
+
+    /// Runs a 3x2 NDVI image as a job with prioritized ROIs and returns its
+    /// result.
+    async fn prioritized_ndvi_result(
+        service: &PostProcessorService,
+        output_directory: &std::path::Path,
+    ) -> crate::AnalysisResult {
+        let request = ndvi_analysis::NdviAnalysisRequest {
+            id: Uuid::new_v4(),
+            red_band_data: vec![100, 120, 140, 160, 180, 200],
+            nir_band_data: vec![400; 6],
+            image_width: 3,
+            image_height: 2,
+            georeference_info: ndvi_analysis::GeoreferenceInfo {
+                top_left_lat: 0.002,
+                top_left_lon: 0.0,
+                bottom_right_lat: 0.0,
+                bottom_right_lon: 0.003,
+                pixel_size_m: 1.0,
+                coordinate_system: "EPSG:4326".to_string(),
+            },
+            capture_time: Utc::now(),
+            quality_mask: None,
+        };
+        let mut parameters = ProcessingParameters::default();
+        parameters.custom_parameters.extend([
+            (crate::PRIORITIZED_ROIS_KEY.to_string(), json!([])),
+            (
+                crate::NDVI_REQUEST_PAYLOAD_KEY.to_string(),
+                serde_json::to_value(&request).unwrap(),
+            ),
+        ]);
+        service
+            .submit_job(crate::ProcessingJob {
+                id: Uuid::nil(),
+                job_type: crate::JobType::NdviAnalysis,
+                input_files: vec![],
+                output_directory: output_directory.to_path_buf(),
+                parameters,
+                status: crate::JobStatus::Queued,
+                created_at: Utc::now(),
+                started_at: None,
+                completed_at: None,
+                error_message: None,
+            })
+            .await
+            .unwrap();
+        service.process_next_job().await.unwrap().unwrap()
+    }
+
+    fn test_router() -> Router {
+        let working_directory = std::env::temp_dir().join(format!("pp-api-{}", Uuid::new_v4()));
+        test_router_with(Arc::new(
+            PostProcessorService::new(working_directory).unwrap(),
+        ))
+    }
+
+    fn test_router_with(service: Arc<PostProcessorService>) -> Router {
+        let generator = ReportGenerator::new(ReportConfig {
+            output_formats: vec![OutputFormat::PDF],
+            default_template: "agricultural_comprehensive".to_string(),
+            include_raw_data: false,
+            include_visualizations: true,
+            enable_comparative_analysis: false,
+            logo_path: None,
+            company_info: CompanyInfo {
+                name: "Test Company".to_string(),
+                address: "123 Test St".to_string(),
+                contact_email: "test@example.com".to_string(),
+                website: None,
+                certification_info: None,
+            },
+        });
+        router(PostProcessorApiState {
+            report_scheduler: Arc::new(ReportScheduler::new(
+                ReportScheduleStore::in_memory(),
+                Arc::new(Mutex::new(generator)),
+                Arc::new(NoSessionSource),
+                Arc::new(SystemScheduleClock),
+            )),
+            service,
+            thumbnails: Arc::new(Mutex::new(ThumbnailCache::default())),
+        })
+    }
+
+    fn schedule_body(expression: &str) -> Body {
+        Body::from(
+            json!({
+                "name": "Weekly comparison",
+                "trigger": { "kind": "cron", "expression": expression },
+                "template_id": "agricultural_comprehensive",
+                "data_query": { "field_id": "north-80", "lookback_days": 7 },
+                "output_formats": ["PDF"],
+                "delivery_options": {
+                    "email_recipients": [],
+                    "storage_location": null,
+                    "auto_archive": false,
+                    "retention_days": 30,
+                    "access_permissions": []
+                },
+                "missed_run_policy": "run_once_on_startup"
+            })
+            .to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn report_schedule_crud_roundtrip() {
+        let app = test_router();
+
+        let created = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/report-schedules")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(schedule_body("0 6 * * 1"))
+                    .expect("request should build"),
+            )
+            .await
+            .expect("router should handle create");
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let body = to_bytes(created.into_body(), 64 * 1024).await.unwrap();
+        let schedule: ReportSchedule = serde_json::from_slice(&body).unwrap();
+        assert!(schedule.next_run_at.is_some());
+
+        let fetched = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/report-schedules/{}", schedule.id))
+                    .body(Body::empty())
+                    .expect("request should build"),
+            )
+            .await
+            .expect("router should handle get");
+        assert_eq!(fetched.status(), StatusCode::OK);
+
+        let deleted = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/report-schedules/{}", schedule.id))
+                    .body(Body::empty())
+                    .expect("request should build"),
+            )
+            .await
+            .expect("router should handle delete");
+        assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+
+        let missing = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/report-schedules/{}/runs", schedule.id))
+                    .body(Body::empty())
+                    .expect("request should build"),
+            )
+            .await
+            .expect("router should handle runs");
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn invalid_cron_expression_is_rejected() {
+        let response = test_router()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/report-schedules")
+                    .header(header::CONTENT_TYPE, "application/json")
+                    .body(schedule_body("every monday"))
+                    .expect("request should build"),
+            )
+            .await
+            .expect("router should handle create");
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn grid_result_thumbnail_is_a_cached_png_and_other_results_are_rejected() {
+        let working_directory = tempfile::tempdir().unwrap();
+        let service = PostProcessorService::new(working_directory.path().to_path_buf()).unwrap();
+        let grid = prioritized_ndvi_result(&service, working_directory.path()).await;
+        let mut zonal = grid.clone();
+        zonal.id = Uuid::new_v4();
+        zonal.data = crate::ResultData::ZonalData {
+            zones: vec![],
+            aggregated_values: Default::default(),
+        };
+        crate::write(&service.results_cache).insert(zonal.id, zonal.clone());
+        let app = test_router_with(Arc::new(service));
+
+        let thumbnail = |uri: String| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let response = thumbnail(format!("/results/{}/thumbnail?size=64", grid.id)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        assert!(response.headers().contains_key(header::CACHE_CONTROL));
+        let body = to_bytes(response.into_body(), 64 * 1024).await.unwrap();
+        assert!(body.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let decoded = image::load_from_memory(&body).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (64, 43));
+
+        let cached = thumbnail(format!("/results/{}/thumbnail?size=64", grid.id)).await;
+        let cached_body = to_bytes(cached.into_body(), 64 * 1024).await.unwrap();
+        assert_eq!(cached_body, body);
+
+        let rejected = thumbnail(format!("/results/{}/thumbnail", zonal.id)).await;
+        assert_eq!(rejected.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let missing = thumbnail(format!("/results/{}/thumbnail", Uuid::new_v4())).await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        let oversized = thumbnail(format!("/results/{}/thumbnail?size=5000", grid.id)).await;
+        assert_eq!(oversized.status(), StatusCode::BAD_REQUEST);
+        let unlisted = thumbnail(format!("/results/{}/thumbnail?size=30", grid.id)).await;
+        assert_eq!(unlisted.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn partial_results_of_prioritized_jobs_are_served() {
+        let working_directory = tempfile::tempdir().unwrap();
+        let service =
+            Arc::new(PostProcessorService::new(working_directory.path().to_path_buf()).unwrap());
+        let grid = prioritized_ndvi_result(&service, working_directory.path()).await;
+        let app = test_router_with(service);
+
+        let partial = |job_id: Uuid| {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    Request::builder()
+                        .uri(format!("/jobs/{job_id}/partial-result"))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        let response = partial(grid.job_id).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), 64 * 1024).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["completeness"], json!(1.0));
+        assert_eq!(body["completed_blocks"], body["total_blocks"]);
+        assert_eq!(body["rois_complete"], json!(true));
+
+        let missing = partial(Uuid::new_v4()).await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn grid_windows_are_served_from_mapped_results() {
+        let working_directory = tempfile::tempdir().unwrap();
+        let mut service =
+            PostProcessorService::new(working_directory.path().to_path_buf()).unwrap();
+        service.set_grid_store_config(crate::GridStoreConfig { spill_min_cells: 6 });
+        let grid = prioritized_ndvi_result(&service, working_directory.path()).await;
+        let crate::ResultData::GridData { values, .. } = &grid.data else {
+            panic!("NDVI result is a grid");
+        };
+        assert!(values.is_mapped());
+        let expected = vec![values[1], values[2], values[4], values[5]];
+        let app = test_router_with(Arc::new(service));
+
+        let window = |uri: String| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let response = window(format!(
+            "/results/{}/window?x=1&y=0&width=2&height=2",
+            grid.id
+        ))
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), 64 * 1024).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body["window"],
+            json!({"x": 1, "y": 0, "width": 2, "height": 2})
+        );
+        let cells: Vec<f32> = serde_json::from_value(body["values"].clone()).unwrap();
+        assert_eq!(cells, expected);
+
+        let outside = window(format!(
+            "/results/{}/window?x=2&y=0&width=2&height=1",
+            grid.id
+        ))
+        .await;
+        assert_eq!(outside.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let oversized = window(format!(
+            "/results/{}/window?x=0&y=0&width=4096&height=4096",
+            grid.id
+        ))
+        .await;
+        assert_eq!(oversized.status(), StatusCode::BAD_REQUEST);
+        let missing = window(format!(
+            "/results/{}/window?x=0&y=0&width=1&height=1",
+            Uuid::new_v4()
+        ))
+        .await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn large_grid_results_serve_stored_previews_by_size() {
+        let working_directory = tempfile::tempdir().unwrap();
+        let mut service =
+            PostProcessorService::new(working_directory.path().to_path_buf()).unwrap();
+        service.set_preview_config(crate::PreviewConfig {
+            min_grid_cells: 6,
+            thumbnail_max_dimension: 2,
+            ..Default::default()
+        });
+        let grid = prioritized_ndvi_result(&service, working_directory.path()).await;
+        assert_eq!(grid.visualizations.len(), 2);
+        let app = test_router_with(Arc::new(service));
+
+        let preview = |uri: String| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let response = preview(format!("/results/{}/preview?size=thumb", grid.id)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        assert!(response.headers().contains_key(header::CACHE_CONTROL));
+        let body = to_bytes(response.into_body(), 64 * 1024).await.unwrap();
+        assert_eq!(
+            body.as_ref(),
+            std::fs::read(&grid.visualizations[0].file_path).unwrap()
+        );
+        let decoded = image::load_from_memory(&body).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (2, 1));
+
+        let medium = preview(format!("/results/{}/preview?size=medium", grid.id)).await;
+        assert_eq!(medium.status(), StatusCode::OK);
+        let invalid = preview(format!("/results/{}/preview?size=huge", grid.id)).await;
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+        let missing = preview(format!("/results/{}/preview?size=thumb", Uuid::new_v4())).await;
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn job_lifecycle_webhooks_that_cannot_be_delivered_are_listed_as_dead_letters() {
+        use shared::webhooks::{
+            WebhookConfig, WebhookDeliveryPolicy, WebhookDispatcher, WebhookEndpoint,
+            WebhookEventKind,
+        };
+
+        let working_directory = tempfile::tempdir().unwrap();
+        let mut service =
+            PostProcessorService::new(working_directory.path().to_path_buf()).unwrap();
+        let (webhooks, _worker) = WebhookDispatcher::spawn(WebhookConfig {
+            endpoints: vec![WebhookEndpoint {
+                id: "farm-saas".to_string(),
+                // Nothing listens on the discard port, so every attempt fails.
+                url: "http://127.0.0.1:9/hooks".to_string(),
+                secret: "secret".to_string(),
+                events: vec![WebhookEventKind::JobCompleted, WebhookEventKind::JobFailed],
+            }],
+            delivery: WebhookDeliveryPolicy {
+                max_retries: 1,
+                initial_backoff_ms: 1,
+                ..Default::default()
+            },
+            queue_capacity: 4,
+        })
+        .unwrap();
+        service.set_webhooks(webhooks.clone());
+
+        let job = |job_type| crate::ProcessingJob {
+            id: Uuid::new_v4(),
+            job_type,
+            input_files: vec![],
+            output_directory: working_directory.path().to_path_buf(),
+            parameters: ProcessingParameters::default(),
+            status: crate::JobStatus::Queued,
+            created_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+            error_message: None,
+        };
+        let failed_job = service
+            .submit_job(job(crate::JobType::HealthAssessment))
+            .await
+            .unwrap();
+        assert!(service.process_next_job().await.unwrap().is_none());
+        service
+            .submit_job(job(crate::JobType::NdviAnalysis))
+            .await
+            .unwrap();
+        assert!(service.process_next_job().await.unwrap().is_some());
+        webhooks.flush().await;
+
+        let response = test_router_with(Arc::new(service))
+            .oneshot(
+                Request::builder()
+                    .uri("/webhooks/dead-letters")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), 64 * 1024).await.unwrap();
+        let dead_letters: Vec<WebhookDeadLetter> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(dead_letters.len(), 2);
+        assert!(dead_letters.iter().all(|letter| letter.attempts == 2));
+        let failed = dead_letters
+            .iter()
+            .find(|letter| letter.event.kind == WebhookEventKind::JobFailed)
+            .unwrap();
+        assert_eq!(failed.event.payload["job_id"], json!(failed_job));
+        assert!(dead_letters
+            .iter()
+            .any(|letter| letter.event.kind == WebhookEventKind::JobCompleted));
+    }
+
+    #[tokio::test]
+    async fn work_orders_are_created_from_recommendations_and_only_move_through_legal_states() {
+        let working_directory = tempfile::tempdir().unwrap();
+        let service = PostProcessorService::new(working_directory.path().to_path_buf()).unwrap();
+        let zone = crate::AnalysisZone {
+            id: "cluster-1".to_string(),
+            boundary: vec![
+                (0.0, 0.001),
+                (0.001, 0.001),
+                (0.001, 0.0),
+                (0.0, 0.0),
+                (0.0, 0.001),
+            ],
+            area_m2: 100.0,
+            values: Default::default(),
+            classification: None,
+        };
+        let result = crate::AnalysisResult {
+            id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+            result_type: crate::ResultType::NdviMap,
+            data: crate::ResultData::ZonalData {
+                zones: vec![zone.clone()],
+                aggregated_values: Default::default(),
+            },
+            statistics: Default::default(),
+            visualizations: vec![],
+            recommendations: vec![crate::Recommendation {
+                category: crate::RecommendationCategory::Irrigation,
+                priority: crate::Priority::High,
+                title: "Dry corner".to_string(),
+                description: "Check the drip line".to_string(),
+                action_items: vec![],
+                affected_areas: vec![zone.clone()],
+                confidence_score: 0.7,
+            }],
+            evidence_refs: vec![],
+            uncertainty: None,
+            created_at: Utc::now(),
+        };
+        crate::write(&service.results_cache).insert(result.id, result.clone());
+        let app = test_router_with(Arc::new(service));
+        let send = |method: &str, uri: String, body: serde_json::Value| {
+            let app = app.clone();
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            async move { app.oneshot(request).await.unwrap() }
+        };
+
+        let created = send(
+            "POST",
+            "/work-orders".to_string(),
+            json!({
+                "result_id": result.id,
+                "recommendation_index": 0,
+                "field_id": "north-80",
+                "assignee": "crew-a"
+            }),
+        )
+        .await;
+        assert_eq!(created.status(), StatusCode::CREATED);
+        let body = to_bytes(created.into_body(), 64 * 1024).await.unwrap();
+        let work_order: WorkOrder = serde_json::from_slice(&body).unwrap();
+        assert_eq!(work_order.zones[0].boundary, zone.boundary);
+
+        let transition = |status: &str| {
+            send(
+                "POST",
+                format!("/work-orders/{}/transition", work_order.id),
+                json!({ "status": status }),
+            )
+        };
+        assert_eq!(transition("verified").await.status(), StatusCode::CONFLICT);
+        assert_eq!(transition("in_progress").await.status(), StatusCode::OK);
+
+        let listed = send(
+            "GET",
+            "/work-orders?field_id=north-80&status=in_progress".to_string(),
+            json!(null),
+        )
+        .await;
+        let body = to_bytes(listed.into_body(), 64 * 1024).await.unwrap();
+        let listed: Vec<WorkOrder> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(listed.len(), 1);
+
+        let unknown = send(
+            "POST",
+            "/work-orders".to_string(),
+            json!({ "result_id": result.id, "recommendation_index": 4, "field_id": "north-80" }),
+        )
+        .await;
+        assert_eq!(unknown.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let deleted = send(
+            "DELETE",
+            format!("/work-orders/{}", work_order.id),
+            json!(null),
+        )
+        .await;
+        assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn job_artifacts_list_the_files_a_completed_job_wrote() {
+        let working_directory = tempfile::tempdir().unwrap();
+        let service = PostProcessorService::new(working_directory.path().to_path_buf()).unwrap();
+        let job_id = service
+            .submit_job(crate::ProcessingJob {
+                id: Uuid::new_v4(),
+                job_type: crate::JobType::ThermalAnalysis,
+                input_files: vec![],
+                output_directory: working_directory.path().join("outputs"),
+                parameters: ProcessingParameters::default(),
+                status: crate::JobStatus::Queued,
+                created_at: Utc::now(),
+                started_at: None,
+                completed_at: None,
+                error_message: None,
+            })
+            .await
+            .unwrap();
+        service.process_next_job().await.unwrap().unwrap();
+        let app = test_router_with(Arc::new(service));
+        let get = |uri: String| {
+            let app = app.clone();
+            async move {
+                app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let response = get(format!("/jobs/{job_id}/artifacts")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), 64 * 1024).await.unwrap();
+        let manifest: ArtifactManifest = serde_json::from_slice(&body).unwrap();
+        let job_directory = working_directory
+            .path()
+            .join("outputs")
+            .join(job_id.to_string());
+        assert_eq!(manifest.directory, job_directory);
+        let paths: Vec<_> = manifest.artifacts.iter().map(|a| a.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                job_directory.join("temperature_grid.csv"),
+                job_directory.join("statistics.json"),
+            ]
+        );
+        assert_eq!(manifest.artifacts[1].mime, "application/json");
+
+        let unknown = get(format!("/jobs/{}/artifacts", Uuid::new_v4())).await;
+        assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+    }
+}
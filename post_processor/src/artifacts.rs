@@ -0,0 +1,235 @@
+use crate::ResultData;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared::{OutputFileEntry, OutputManifest, OUTPUT_MANIFEST_FILE_NAME};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// What an analyzer output file holds. The kind fixes the file name, so a
+/// job writes at most one file per kind, at
+/// `{output_directory}/{job_id}/{kind}.{extension}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    NdviGrid,
+    ElevationGrid,
+    TemperatureGrid,
+    Statistics,
+}
+
+impl ArtifactKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NdviGrid => "ndvi_grid",
+            Self::ElevationGrid => "elevation_grid",
+            Self::TemperatureGrid => "temperature_grid",
+            Self::Statistics => "statistics",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::NdviGrid | Self::ElevationGrid | Self::TemperatureGrid => "csv",
+            Self::Statistics => "json",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            Self::NdviGrid | Self::ElevationGrid | Self::TemperatureGrid => "text/csv",
+            Self::Statistics => "application/json",
+        }
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{}.{}", self.as_str(), self.extension())
+    }
+}
+
+/// One file a job produced.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobArtifact {
+    pub kind: ArtifactKind,
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// `sha256:<hex>` of the file contents.
+    pub content_hash: String,
+    pub mime: String,
+}
+
+/// Every file a job's last successful attempt left in its directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactManifest {
+    pub job_id: Uuid,
+    pub directory: PathBuf,
+    pub artifacts: Vec<JobArtifact>,
+}
+
+/// Writes artifact files. Swappable so tests can fail a write part way.
+pub trait ArtifactSink: Send + Sync {
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+}
+
+/// Writes artifacts straight to the local filesystem.
+#[derive(Debug, Default)]
+pub struct FsArtifactSink;
+
+impl ArtifactSink for FsArtifactSink {
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+}
+
+/// Collects one attempt's artifacts in a staging directory next to the
+/// job's directory and swaps it in on [`Self::commit`], so the job directory
+/// only ever holds a complete set from one attempt, described by its
+/// `manifest.json`. An attempt dropped without committing removes its
+/// staging directory.
+pub struct ArtifactWriter {
+    job_id: Uuid,
+    directory: PathBuf,
+    staging: PathBuf,
+    sink: Arc<dyn ArtifactSink>,
+    artifacts: Vec<JobArtifact>,
+    source_inputs: Vec<String>,
+    committed: bool,
+}
+
+impl ArtifactWriter {
+    /// Starts an attempt for `job_id`, clearing staging directories that
+    /// earlier attempts left behind by crashing mid-write.
+    pub fn create(
+        output_directory: &Path,
+        job_id: Uuid,
+        sink: Arc<dyn ArtifactSink>,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(output_directory)?;
+        let staging_prefix = staging_prefix(job_id);
+        for entry in fs::read_dir(output_directory)? {
+            let entry = entry?;
+            if entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with(&staging_prefix))
+            {
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+
+        let staging = output_directory.join(format!("{staging_prefix}{}.partial", Uuid::new_v4()));
+        fs::create_dir(&staging)?;
+        Ok(Self {
+            job_id,
+            directory: output_directory.join(job_id.to_string()),
+            staging,
+            sink,
+            artifacts: Vec::new(),
+            source_inputs: Vec::new(),
+            committed: false,
+        })
+    }
+
+    /// Input files the job's artifacts are derived from, recorded for each
+    /// artifact in the manifest.
+    pub fn with_source_inputs(mut self, inputs: &[PathBuf]) -> Self {
+        self.source_inputs = inputs
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        self
+    }
+
+    /// Stages `contents` as the job's `kind` artifact, replacing an earlier
+    /// write of the same kind in this attempt.
+    pub fn write(&mut self, kind: ArtifactKind, contents: &[u8]) -> io::Result<()> {
+        let file_name = kind.file_name();
+        self.sink.write(&self.staging.join(&file_name), contents)?;
+        self.artifacts.retain(|artifact| artifact.kind != kind);
+        self.artifacts.push(JobArtifact {
+            kind,
+            path: self.directory.join(file_name),
+            bytes: contents.len() as u64,
+            content_hash: content_hash(contents),
+            mime: kind.mime().to_string(),
+        });
+        Ok(())
+    }
+
+    /// Writes the attempt's `manifest.json` and replaces the job's directory
+    /// with this attempt's files.
+    pub fn commit(mut self) -> io::Result<ArtifactManifest> {
+        let files = self
+            .artifacts
+            .iter()
+            .map(|artifact| OutputFileEntry {
+                path: artifact.kind.file_name(),
+                file_type: artifact.kind.extension().to_string(),
+                source_inputs: self.source_inputs.clone(),
+                size_bytes: artifact.bytes,
+                sha256: artifact
+                    .content_hash
+                    .trim_start_matches("sha256:")
+                    .to_string(),
+            })
+            .collect();
+        let manifest = OutputManifest::new("post_processor", files);
+        self.sink.write(
+            &self.staging.join(OUTPUT_MANIFEST_FILE_NAME),
+            &manifest.to_json_bytes()?,
+        )?;
+
+        if self.directory.exists() {
+            let retired = self.staging.with_extension("retired");
+            fs::rename(&self.directory, &retired)?;
+            fs::rename(&self.staging, &self.directory)?;
+            fs::remove_dir_all(&retired)?;
+        } else {
+            fs::rename(&self.staging, &self.directory)?;
+        }
+        self.committed = true;
+
+        Ok(ArtifactManifest {
+            job_id: self.job_id,
+            directory: self.directory.clone(),
+            artifacts: std::mem::take(&mut self.artifacts),
+        })
+    }
+}
+
+impl Drop for ArtifactWriter {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_dir_all(&self.staging);
+        }
+    }
+}
+
+fn staging_prefix(job_id: Uuid) -> String {
+    format!(".{job_id}.")
+}
+
+pub fn content_hash(contents: &[u8]) -> String {
+    let hex: String = Sha256::digest(contents)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("sha256:{hex}")
+}
+
+/// Grid values as CSV, one line per row from north to south; `ResultData`
+/// other than a grid yields an empty file.
+pub fn grid_csv(data: &ResultData) -> Vec<u8> {
+    let ResultData::GridData { width, values, .. } = data else {
+        return Vec::new();
+    };
+    let mut csv = String::new();
+    for row in values.chunks((*width as usize).max(1)) {
+        let line: Vec<String> = row.iter().map(f32::to_string).collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+    csv.into_bytes()
+}
@@ -0,0 +1,113 @@
+use crate::roi_processing::GridGeometry;
+use crate::zonal_statistics::{compute_statistics, DEFAULT_PERCENTILES};
+use crate::{AnalysisStatistics, ResultData};
+use std::io::Read;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum GridCsvError {
+    #[error("grid resolution must be positive and finite, got {resolution}")]
+    InvalidResolution { resolution: f64 },
+    #[error("grid bounds must have min < max on both axes, got {bounds:?}")]
+    InvalidBounds { bounds: (f64, f64, f64, f64) },
+    #[error("line {line}: `{field}` is not a number")]
+    InvalidCoordinate { line: u64, field: String },
+    #[error("line {line}: point ({x}, {y}) lies outside the grid bounds")]
+    OutOfBounds { line: u64, x: f64, y: f64 },
+    #[error("grid CSV could not be read: {reason}")]
+    Read { reason: String },
+}
+
+/// Reads `x,y,value` rows into a north-up grid covering `bounds`
+/// (`(min_x, min_y, max_x, max_y)`, as in `ResultData::GridData`) with square
+/// cells of `resolution` bound units. A leading header row is skipped. Cells
+/// without a row, and rows whose value is missing or not a number, stay NaN;
+/// a later row for the same cell replaces an earlier one.
+pub fn read_grid_csv(
+    reader: impl Read,
+    bounds: (f64, f64, f64, f64),
+    resolution: f64,
+    units: &str,
+) -> Result<ResultData, GridCsvError> {
+    if !resolution.is_finite() || resolution <= 0.0 {
+        return Err(GridCsvError::InvalidResolution { resolution });
+    }
+    let (min_x, min_y, max_x, max_y) = bounds;
+    if !(min_x < max_x && min_y < max_y) {
+        return Err(GridCsvError::InvalidBounds { bounds });
+    }
+    let width = (((max_x - min_x) / resolution).round() as u32).max(1);
+    let height = (((max_y - min_y) / resolution).round() as u32).max(1);
+    let mut values = vec![f32::NAN; width as usize * height as usize];
+
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    for (index, record) in csv_reader.records().enumerate() {
+        let record = record.map_err(|error| GridCsvError::Read {
+            reason: error.to_string(),
+        })?;
+        let line = record
+            .position()
+            .map(|position| position.line())
+            .unwrap_or(index as u64 + 1);
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let coordinate = |column: usize| {
+            let field = record.get(column).unwrap_or_default();
+            field
+                .parse::<f64>()
+                .map_err(|_| GridCsvError::InvalidCoordinate {
+                    line,
+                    field: field.to_string(),
+                })
+        };
+        let (x, y) = match (coordinate(0), coordinate(1)) {
+            (Ok(x), Ok(y)) => (x, y),
+            (Err(_), _) | (_, Err(_)) if index == 0 => continue,
+            (Err(error), _) | (_, Err(error)) => return Err(error),
+        };
+        if !(min_x..=max_x).contains(&x) || !(min_y..=max_y).contains(&y) {
+            return Err(GridCsvError::OutOfBounds { line, x, y });
+        }
+
+        let col = (((x - min_x) / resolution).floor() as u32).min(width - 1);
+        let row = (((max_y - y) / resolution).floor() as u32).min(height - 1);
+        values[(row * width + col) as usize] = record
+            .get(2)
+            .and_then(|value| value.parse::<f32>().ok())
+            .unwrap_or(f32::NAN);
+    }
+
+    Ok(ResultData::GridData {
+        width,
+        height,
+        values: values.into(),
+        bounds,
+        units: units.to_string(),
+    })
+}
+
+/// Statistics over the finite cells of a grid; coverage assumes geographic
+/// bounds.
+pub fn grid_statistics(data: &ResultData) -> AnalysisStatistics {
+    let ResultData::GridData {
+        width,
+        height,
+        values,
+        bounds,
+        ..
+    } = data
+    else {
+        return AnalysisStatistics::default();
+    };
+    let geometry =
+        GridGeometry::from_corners(*width, *height, (bounds.3, bounds.0), (bounds.1, bounds.2));
+    let statistics = compute_statistics(values, &DEFAULT_PERCENTILES);
+    AnalysisStatistics {
+        coverage_area_m2: statistics.valid_pixel_count as f32 * geometry.pixel_area_m2,
+        ..statistics
+    }
+}
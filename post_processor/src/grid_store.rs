@@ -0,0 +1,400 @@
+//! Grid values kept in memory or in a memory-mapped grid file.
+//!
+//! A grid file is a 16-byte header (the `AGGRID01` magic, then width and
+//! height as little-endian `u32`) followed by the row-major cells as
+//! little-endian `f32`. Large results are spilled to one so the results
+//! cache holds a mapping instead of the cells; pages are read from disk only
+//! when a cell on them is touched.
+
+use crate::ResultData;
+use memmap2::Mmap;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::fs;
+use std::io::{BufWriter, Read, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const GRID_FILE_MAGIC: [u8; 8] = *b"AGGRID01";
+const GRID_FILE_HEADER_LEN: usize = 16;
+const CELL_BYTES: usize = std::mem::size_of::<f32>();
+
+/// When grid results are spilled to grid files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridStoreConfig {
+    /// Grids with at least this many cells are written to a grid file and
+    /// mapped; smaller grids stay inline.
+    pub spill_min_cells: usize,
+}
+
+impl Default for GridStoreConfig {
+    fn default() -> Self {
+        Self {
+            spill_min_cells: 4_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GridStoreError {
+    #[error("grid file {path}: {message}")]
+    Io { path: PathBuf, message: String },
+    #[error("grid file {path} is not a valid grid: {reason}")]
+    InvalidFile { path: PathBuf, reason: String },
+    #[error("grid has {cells} cells, expected {width}x{height}")]
+    DimensionMismatch {
+        cells: usize,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// The cells of a [`ResultData::GridData`], row-major. Derefs to `[f32]`
+/// either way; a mapped grid serializes as a reference to its file rather
+/// than its cells.
+#[derive(Clone)]
+pub enum GridValues {
+    Inline(Vec<f32>),
+    Mapped(MappedGrid),
+}
+
+/// A read-only mapping of a grid file.
+#[derive(Clone)]
+pub struct MappedGrid {
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    map: Arc<Mmap>,
+}
+
+impl GridValues {
+    /// Writes `values` to a grid file at `path` and maps it.
+    pub fn spill(
+        path: &Path,
+        width: u32,
+        height: u32,
+        values: &[f32],
+    ) -> Result<Self, GridStoreError> {
+        if values.len() != width as usize * height as usize {
+            return Err(GridStoreError::DimensionMismatch {
+                cells: values.len(),
+                width,
+                height,
+            });
+        }
+        let io_error = |error: std::io::Error| GridStoreError::Io {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        let mut writer = BufWriter::new(fs::File::create(path).map_err(io_error)?);
+        writer.write_all(&GRID_FILE_MAGIC).map_err(io_error)?;
+        writer.write_all(&width.to_le_bytes()).map_err(io_error)?;
+        writer.write_all(&height.to_le_bytes()).map_err(io_error)?;
+        for value in values {
+            writer.write_all(&value.to_le_bytes()).map_err(io_error)?;
+        }
+        writer
+            .into_inner()
+            .map_err(|error| io_error(error.into_error()))?
+            .sync_all()
+            .map_err(io_error)?;
+        Self::open(path)
+    }
+
+    /// Maps an existing grid file. On big-endian targets the cells are
+    /// decoded into memory instead.
+    pub fn open(path: &Path) -> Result<Self, GridStoreError> {
+        let io_error = |error: std::io::Error| GridStoreError::Io {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        };
+        let invalid = |reason: String| GridStoreError::InvalidFile {
+            path: path.to_path_buf(),
+            reason,
+        };
+
+        let mut file = fs::File::open(path).map_err(io_error)?;
+        let mut header = [0u8; GRID_FILE_HEADER_LEN];
+        file.read_exact(&mut header)
+            .map_err(|_| invalid("shorter than its header".to_string()))?;
+        if header[..8] != GRID_FILE_MAGIC {
+            return Err(invalid("missing AGGRID01 magic".to_string()));
+        }
+        let width = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        let height = u32::from_le_bytes(header[12..16].try_into().unwrap());
+        let expected_len = GRID_FILE_HEADER_LEN + width as usize * height as usize * CELL_BYTES;
+        let file_len = file.metadata().map_err(io_error)?.len();
+        if file_len != expected_len as u64 {
+            return Err(invalid(format!(
+                "{file_len} bytes, expected {expected_len} for {width}x{height}"
+            )));
+        }
+
+        // SAFETY: grid files are written once by `spill` and never modified
+        // in place; removing one unlinks it without invalidating the map.
+        let map = unsafe { Mmap::map(&file) }.map_err(io_error)?;
+        let cells = &map[GRID_FILE_HEADER_LEN..];
+        if cfg!(target_endian = "big") {
+            return Ok(Self::Inline(
+                cells
+                    .chunks_exact(CELL_BYTES)
+                    .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+                    .collect(),
+            ));
+        }
+        // The map is page aligned and the header a multiple of four bytes.
+        bytemuck::try_cast_slice::<u8, f32>(cells)
+            .map_err(|error| invalid(format!("cells are not addressable as f32: {error}")))?;
+
+        Ok(Self::Mapped(MappedGrid {
+            path: path.to_path_buf(),
+            width,
+            height,
+            map: Arc::new(map),
+        }))
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self, Self::Mapped(_))
+    }
+
+    /// The grid file backing a mapped grid.
+    pub fn file_path(&self) -> Option<&Path> {
+        match self {
+            Self::Inline(_) => None,
+            Self::Mapped(mapped) => Some(&mapped.path),
+        }
+    }
+}
+
+impl MappedGrid {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+}
+
+impl Deref for GridValues {
+    type Target = [f32];
+
+    fn deref(&self) -> &[f32] {
+        match self {
+            Self::Inline(values) => values,
+            Self::Mapped(mapped) => bytemuck::cast_slice(&mapped.map[GRID_FILE_HEADER_LEN..]),
+        }
+    }
+}
+
+impl From<Vec<f32>> for GridValues {
+    fn from(values: Vec<f32>) -> Self {
+        Self::Inline(values)
+    }
+}
+
+impl fmt::Debug for GridValues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inline(values) => values.fmt(f),
+            Self::Mapped(mapped) => f
+                .debug_struct("Mapped")
+                .field("path", &mapped.path)
+                .field("width", &mapped.width)
+                .field("height", &mapped.height)
+                .finish(),
+        }
+    }
+}
+
+/// How a mapped grid appears in JSON.
+#[derive(Serialize, Deserialize)]
+struct StoredGrid {
+    grid_file: PathBuf,
+}
+
+impl Serialize for GridValues {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Inline(values) => values.serialize(serializer),
+            Self::Mapped(mapped) => StoredGrid {
+                grid_file: mapped.path.clone(),
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+/// Reads the cells of an inline grid (NaN serializes as `null`) or maps the
+/// grid file a stored grid names.
+struct GridValuesVisitor;
+
+impl<'de> Visitor<'de> for GridValuesVisitor {
+    type Value = GridValues;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an array of grid cells or a grid_file reference")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<GridValues, A::Error> {
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element::<Option<f32>>()? {
+            values.push(value.unwrap_or(f32::NAN));
+        }
+        Ok(GridValues::Inline(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<GridValues, A::Error> {
+        let stored = StoredGrid::deserialize(de::value::MapAccessDeserializer::new(map))?;
+        GridValues::open(&stored.grid_file).map_err(de::Error::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for GridValues {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(GridValuesVisitor)
+    }
+}
+
+/// A rectangle of grid cells, in cells from the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GridWindow {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl GridWindow {
+    pub fn cell_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+}
+
+/// The cell at column `x`, row `y` of a grid result. `None` for other
+/// results and cells outside the grid.
+pub fn value_at(data: &ResultData, x: u32, y: u32) -> Option<f32> {
+    let ResultData::GridData {
+        width,
+        height,
+        values,
+        ..
+    } = data
+    else {
+        return None;
+    };
+    if x >= *width || y >= *height {
+        return None;
+    }
+    values
+        .get(y as usize * *width as usize + x as usize)
+        .copied()
+}
+
+/// The cells of `window`, row-major, reading only the rows it covers. `None`
+/// for other results and windows that do not lie inside the grid.
+pub fn extract_window(data: &ResultData, window: GridWindow) -> Option<Vec<f32>> {
+    let ResultData::GridData {
+        width,
+        height,
+        values,
+        ..
+    } = data
+    else {
+        return None;
+    };
+    let fits = |start: u32, span: u32, extent: u32| {
+        span > 0 && start.checked_add(span).is_some_and(|end| end <= extent)
+    };
+    if !fits(window.x, window.width, *width)
+        || !fits(window.y, window.height, *height)
+        || values.len() != *width as usize * *height as usize
+    {
+        return None;
+    }
+
+    let mut cells = Vec::with_capacity(window.cell_count());
+    for row in window.y..window.y + window.height {
+        let start = row as usize * *width as usize + window.x as usize;
+        cells.extend_from_slice(&values[start..start + window.width as usize]);
+    }
+    Some(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(values: GridValues) -> ResultData {
+        ResultData::GridData {
+            width: 3,
+            height: 2,
+            values,
+            bounds: (0.0, 0.0, 3.0, 2.0),
+            units: "NDVI".to_string(),
+        }
+    }
+
+    #[test]
+    fn spilled_grids_read_like_inline_ones_and_serialize_as_a_file_reference() {
+        let dir = tempfile::tempdir().unwrap();
+        let cells = vec![0.1, 0.2, 0.3, 0.4, f32::NAN, 0.6];
+        let path = dir.path().join("grid.bin");
+        let mapped = GridValues::spill(&path, 3, 2, &cells).unwrap();
+        assert!(mapped.is_mapped());
+        assert_eq!(mapped.len(), 6);
+
+        let data = grid(mapped.clone());
+        assert_eq!(value_at(&data, 2, 1), Some(0.6));
+        assert_eq!(value_at(&data, 3, 0), None);
+        let window = GridWindow {
+            x: 1,
+            y: 0,
+            width: 2,
+            height: 2,
+        };
+        let extracted = extract_window(&data, window).unwrap();
+        assert_eq!(extracted.len(), 4);
+        assert_eq!(&extracted[..2], &[0.2, 0.3]);
+        assert!(extracted[2].is_nan());
+        assert_eq!(extracted[3], 0.6);
+        assert_eq!(extract_window(&data, GridWindow { x: 2, ..window }), None);
+
+        let json = serde_json::to_value(&data).unwrap();
+        assert_eq!(
+            json["GridData"]["values"],
+            serde_json::json!({ "grid_file": path })
+        );
+        let reloaded: ResultData = serde_json::from_value(json).unwrap();
+        assert_eq!(value_at(&reloaded, 0, 1), Some(0.4));
+
+        let inline = serde_json::to_value(grid(cells.into())).unwrap();
+        assert!(inline["GridData"]["values"].is_array());
+        let reloaded: ResultData = serde_json::from_value(inline).unwrap();
+        assert!(value_at(&reloaded, 1, 1).unwrap().is_nan());
+    }
+
+    #[test]
+    fn truncated_grid_files_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("grid.bin");
+        GridValues::spill(&path, 3, 2, &[0.0; 6]).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+
+        assert!(matches!(
+            GridValues::open(&path),
+            Err(GridStoreError::InvalidFile { .. })
+        ));
+        assert!(matches!(
+            GridValues::spill(&path, 3, 3, &[0.0; 6]),
+            Err(GridStoreError::DimensionMismatch { cells: 6, .. })
+        ));
+    }
+}
@@ -143,7 +143,7 @@ fn grower_report_lines(request: &GrowerReportRequest) -> Vec<String> {
     lines
 }
 
-fn render_pdf_lines(lines: &[String]) -> Vec<u8> {
+pub(crate) fn render_pdf_lines(lines: &[String]) -> Vec<u8> {
     let mut stream = String::from("BT\n/F1 10 Tf\n72 760 Td\n14 TL\n");
     for line in lines {
         stream.push_str(&format!("({}) Tj\nT*\n", escape_pdf_text(line)));
@@ -154,7 +154,7 @@ fn render_pdf_lines(lines: &[String]) -> Vec<u8> {
         "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
         "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
         "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Resources << /Font << /F1 4 0 R >> >> /Contents 5 0 R >>".to_string(),
-        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
         format!("<< /Length {} >>\nstream\n{}endstream", stream.len(), stream),
     ];
 
@@ -193,11 +193,22 @@ fn nonempty_values(values: &[String]) -> Vec<String> {
         .collect::<Vec<_>>()
 }
 
+/// Escapes string delimiters and writes Latin-1 characters as octal codes
+/// of the WinAnsi font encoding; anything beyond Latin-1 becomes `?`.
 fn escape_pdf_text(value: &str) -> String {
-    value
-        .replace('\\', "\\\\")
-        .replace('(', "\\(")
-        .replace(')', "\\)")
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '\\' | '(' | ')' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            ' '..='~' => escaped.push(character),
+            '\u{a0}'..='\u{ff}' => escaped.push_str(&format!("\\{:03o}", character as u32)),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
 }
 
 fn reason_code_str(reason: ProductAnomalyReasonCode) -> &'static str {
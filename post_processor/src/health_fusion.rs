@@ -0,0 +1,160 @@
+//! Per-cell crop health index fused from co-registered NDVI, thermal stress
+//! and canopy height grids.
+
+/// NDVI grid; 0.2 and below scores 0, 0.8 and above scores 1.
+pub const NDVI_LAYER: &str = "ndvi";
+/// Thermal stress index grid, 0 for unstressed and 1 for fully stressed.
+pub const THERMAL_STRESS_LAYER: &str = "thermal_stress";
+/// Canopy height grid in metres, scored against the field's own tall canopy.
+pub const CANOPY_HEIGHT_LAYER: &str = "canopy_height";
+
+const NDVI_STRESSED: f32 = 0.2;
+const NDVI_HEALTHY: f32 = 0.8;
+/// Percentile of a canopy height grid that scores as full height, so a few
+/// tall outliers do not pull the rest of the field down.
+const CANOPY_REFERENCE_PERCENTILE: f32 = 0.95;
+
+/// Combines `layers` of `(name, values, weight)` into a health index per
+/// cell, 0 for poor and 1 for healthy, as the weighted mean of each layer's
+/// score. Layers are scored by name (see [`NDVI_LAYER`],
+/// [`THERMAL_STRESS_LAYER`] and [`CANOPY_HEIGHT_LAYER`]); a layer with any
+/// other name is taken as a 0–1 health score already.
+///
+/// A layer missing from a cell (a non-finite value) or from the whole grid
+/// (a length other than `width * height`) drops out and the remaining
+/// weights are renormalized. Cells no layer covers are NaN.
+pub fn fuse_health_index(layers: &[(&str, &[f32], f32)], width: u32, height: u32) -> Vec<f32> {
+    let cells = width as usize * height as usize;
+    let scored = layers
+        .iter()
+        .filter(|(name, values, weight)| {
+            if values.len() != cells {
+                tracing::warn!(
+                    "Health layer {} has {} cells, expected {}; leaving it out",
+                    name,
+                    values.len(),
+                    cells
+                );
+                return false;
+            }
+            weight.is_finite() && *weight > 0.0
+        })
+        .map(|(name, values, weight)| (layer_scores(name, values), *weight))
+        .collect::<Vec<_>>();
+
+    (0..cells)
+        .map(|cell| {
+            let (weighted, total_weight) = scored
+                .iter()
+                .filter_map(|(scores, weight)| {
+                    let score = scores[cell];
+                    score.is_finite().then_some((score * weight, *weight))
+                })
+                .fold((0.0, 0.0), |(sum, total), (value, weight)| {
+                    (sum + value, total + weight)
+                });
+            if total_weight > 0.0 {
+                (weighted / total_weight).clamp(0.0, 1.0)
+            } else {
+                f32::NAN
+            }
+        })
+        .collect()
+}
+
+/// Scores each cell of a layer 0–1, leaving missing cells non-finite.
+fn layer_scores(name: &str, values: &[f32]) -> Vec<f32> {
+    match name.to_ascii_lowercase().as_str() {
+        NDVI_LAYER => values
+            .iter()
+            .map(|ndvi| ((ndvi - NDVI_STRESSED) / (NDVI_HEALTHY - NDVI_STRESSED)).clamp(0.0, 1.0))
+            .collect(),
+        THERMAL_STRESS_LAYER => values
+            .iter()
+            .map(|stress| 1.0 - stress.clamp(0.0, 1.0))
+            .collect(),
+        CANOPY_HEIGHT_LAYER => {
+            let reference = canopy_reference_height(values);
+            values
+                .iter()
+                .map(|height| match reference {
+                    Some(reference) => (height / reference).clamp(0.0, 1.0),
+                    None => f32::NAN,
+                })
+                .collect()
+        }
+        _ => values.iter().map(|score| score.clamp(0.0, 1.0)).collect(),
+    }
+}
+
+fn canopy_reference_height(values: &[f32]) -> Option<f32> {
+    let mut heights = values
+        .iter()
+        .copied()
+        .filter(|height| height.is_finite() && *height > 0.0)
+        .collect::<Vec<_>>();
+    if heights.is_empty() {
+        return None;
+    }
+    heights.sort_by(f32::total_cmp);
+    let index = ((heights.len() - 1) as f32 * CANOPY_REFERENCE_PERCENTILE).round() as usize;
+    Some(heights[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_stressed_region_drops_the_index_where_healthy_cells_stay_near_one() {
+        let (width, height) = (4, 4);
+        let mut ndvi = vec![0.85; 16];
+        let mut stress = vec![0.0; 16];
+        let mut canopy = vec![1.2; 16];
+        // The top-left 2x2 block is stressed; one healthy cell has no
+        // thermal reading.
+        for cell in [0, 1, 4, 5] {
+            ndvi[cell] = 0.3;
+            stress[cell] = 0.8;
+            canopy[cell] = 0.4;
+        }
+        stress[15] = f32::NAN;
+
+        let index = fuse_health_index(
+            &[
+                (NDVI_LAYER, &ndvi, 0.5),
+                (THERMAL_STRESS_LAYER, &stress, 0.3),
+                (CANOPY_HEIGHT_LAYER, &canopy, 0.2),
+            ],
+            width,
+            height,
+        );
+
+        assert_eq!(index.len(), 16);
+        for cell in [2, 3, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15] {
+            assert!(
+                (index[cell] - 1.0).abs() < 1e-3,
+                "cell {cell}: {}",
+                index[cell]
+            );
+        }
+        for cell in [0, 1, 4, 5] {
+            assert!(index[cell] < 0.3, "cell {cell}: {}", index[cell]);
+        }
+    }
+
+    #[test]
+    fn layers_that_do_not_fit_the_grid_are_left_out() {
+        let index = fuse_health_index(
+            &[
+                (NDVI_LAYER, &[0.8, 0.2], 1.0),
+                (THERMAL_STRESS_LAYER, &[0.0], 1.0),
+            ],
+            2,
+            1,
+        );
+        assert_eq!(index, vec![1.0, 0.0]);
+
+        assert!(fuse_health_index(&[], 1, 1)[0].is_nan());
+    }
+}
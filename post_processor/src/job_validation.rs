@@ -0,0 +1,724 @@
+//! Pre-flight checks a job passes before it is queued, so trivial input
+//! problems surface at submission instead of deep inside an analyzer.
+
+use crate::ndvi_analysis::NdviAnalysisRequest;
+use crate::thermal_analysis::ThermalAnalysisRequest;
+use crate::{
+    IndexAnomalyRequest, IndexTrendRequest, IndexVegetationTypeClassificationRequest, JobType,
+    LidarChangeRequest, NdviChangeRequest, ProcessingJob, ProcessingParameters,
+    INDEX_ANOMALY_PAYLOAD_KEY, INDEX_TREND_PAYLOAD_KEY,
+    INDEX_VEGETATION_CLASSIFICATION_PAYLOAD_KEY, LIDAR_CHANGE_PAYLOAD_KEY, NDVI_CHANGE_PAYLOAD_KEY,
+    NDVI_REQUEST_PAYLOAD_KEY, PRIORITIZED_ROIS_KEY, SESSION_ID_KEY, THERMAL_REQUEST_PAYLOAD_KEY,
+};
+use image::codecs::{png::PngDecoder, tiff::TiffDecoder};
+use image::{ColorType, ImageDecoder, ImageFormat};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use shared::artifact_schema::from_artifact_str;
+use shared::schemas::{LidarScan, MultispectralImage};
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Seek};
+use std::path::Path;
+use uuid::Uuid;
+
+/// Custom parameters read as on/off switches when a job runs.
+const BOOLEAN_PARAMETER_KEYS: [&str; 8] = [
+    crate::HEALTH_FEATURE_FLAG_KEY,
+    crate::HEALTH_APPROVAL_KEY,
+    crate::HEALTH_STALE_KEY,
+    crate::YIELD_FEATURE_FLAG_KEY,
+    crate::INDEX_ANOMALY_FEATURE_FLAG_KEY,
+    crate::INDEX_TREND_FEATURE_FLAG_KEY,
+    crate::INDEX_VEGETATION_CLASSIFICATION_FEATURE_FLAG_KEY,
+    crate::LIDAR_CHANGE_FEATURE_FLAG_KEY,
+];
+
+/// Total input size above which a job needs `force` to be queued.
+pub const DEFAULT_MAX_INPUT_BYTES: u64 = 8 * 1024 * 1024 * 1024;
+
+/// Largest JSON input parsed in full; bigger ones only have their header
+/// sniffed.
+pub const MAX_PARSED_JSON_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Bytes read to sniff whether an input holds a JSON object.
+const JSON_HEADER_BYTES: usize = 4096;
+
+/// What happens to a job that fails validation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidJobPolicy {
+    /// The submission is refused and nothing is queued.
+    #[default]
+    Reject,
+    /// The job is recorded as [`crate::JobStatus::Failed`] without running,
+    /// so it shows up in job listings with its problems.
+    RecordFailed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobValidationConfig {
+    pub max_input_bytes: u64,
+    pub invalid_job_policy: InvalidJobPolicy,
+}
+
+impl Default for JobValidationConfig {
+    fn default() -> Self {
+        Self {
+            max_input_bytes: DEFAULT_MAX_INPUT_BYTES,
+            invalid_job_policy: InvalidJobPolicy::Reject,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobProblemKind {
+    MissingInput,
+    UnreadableInput,
+    EmptyInput,
+    UnexpectedInputKind,
+    UnexpectedExtension,
+    MissingParameter,
+    InvalidParameter,
+    OutputNotWritable,
+    InputTooLarge,
+}
+
+impl JobProblemKind {
+    /// Warnings can be bypassed with `force`; everything else stops the job.
+    pub fn severity(self) -> ProblemSeverity {
+        match self {
+            Self::UnexpectedExtension | Self::InputTooLarge => ProblemSeverity::Warning,
+            _ => ProblemSeverity::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemSeverity {
+    Error,
+    Warning,
+}
+
+/// One thing wrong with a job. `subject` names the offending input file,
+/// custom parameter or output directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobProblem {
+    pub kind: JobProblemKind,
+    pub severity: ProblemSeverity,
+    pub subject: String,
+    pub message: String,
+}
+
+impl JobProblem {
+    fn new(kind: JobProblemKind, subject: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            severity: kind.severity(),
+            subject: subject.into(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobValidationReport {
+    pub problems: Vec<JobProblem>,
+    /// Combined size of the input files that could be inspected.
+    pub input_bytes: u64,
+}
+
+impl JobValidationReport {
+    /// Whether the job may be queued; `force` lets warnings through.
+    pub fn passes(&self, force: bool) -> bool {
+        self.problems
+            .iter()
+            .all(|problem| force && problem.severity == ProblemSeverity::Warning)
+    }
+
+    /// Problems that stop the job, given `force`.
+    pub fn blocking(&self, force: bool) -> Vec<JobProblem> {
+        self.problems
+            .iter()
+            .filter(|problem| !force || problem.severity == ProblemSeverity::Error)
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("job failed validation: {}", summarize(problems))]
+pub struct JobValidationError {
+    pub problems: Vec<JobProblem>,
+}
+
+fn summarize(problems: &[JobProblem]) -> String {
+    problems
+        .iter()
+        .map(|problem| format!("{}: {}", problem.subject, problem.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Content each input file of a job type must hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputKind {
+    MultispectralMetadata,
+    LidarScan,
+    ThermalRaster,
+}
+
+impl InputKind {
+    fn for_job(job_type: &JobType) -> Option<Self> {
+        match job_type {
+            JobType::NdviAnalysis => Some(Self::MultispectralMetadata),
+            JobType::LidarProcessing => Some(Self::LidarScan),
+            JobType::ThermalAnalysis => Some(Self::ThermalRaster),
+            _ => None,
+        }
+    }
+
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::MultispectralMetadata | Self::LidarScan => &["json"],
+            Self::ThermalRaster => &["tif", "tiff", "png"],
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::MultispectralMetadata => "multispectral metadata JSON",
+            Self::LidarScan => "LiDAR scan JSON",
+            Self::ThermalRaster => "16-bit single-channel raster",
+        }
+    }
+}
+
+/// Checks `job`'s inputs, parameters and output directory. Blocks on file
+/// reads, so async callers run it on a blocking thread. Input sizes are
+/// totalled first; JSON inputs are only parsed in full while the total is
+/// within [`JobValidationConfig::max_input_bytes`] and each document is at
+/// most [`MAX_PARSED_JSON_BYTES`], otherwise only their header is sniffed.
+pub fn validate_job(job: &ProcessingJob, config: &JobValidationConfig) -> JobValidationReport {
+    let mut report = JobValidationReport::default();
+    let input_kind = InputKind::for_job(&job.job_type);
+    let inputs = job
+        .input_files
+        .iter()
+        .map(|input| (input, input_size(input)))
+        .collect::<Vec<_>>();
+    report.input_bytes = inputs
+        .iter()
+        .filter_map(|(_, size)| size.as_ref().ok())
+        .sum();
+    let too_large = report.input_bytes > config.max_input_bytes;
+    for (input, size) in inputs {
+        let subject = input.display().to_string();
+        let checked = size.and_then(|bytes| {
+            let parse_json = !too_large && bytes <= MAX_PARSED_JSON_BYTES;
+            check_input(input, input_kind, parse_json)
+        });
+        match checked {
+            Ok(problems) => report.problems.extend(problems),
+            Err(problem) => report.problems.push(problem.into_problem(subject)),
+        }
+    }
+    if too_large {
+        report.problems.push(JobProblem::new(
+            JobProblemKind::InputTooLarge,
+            "input_files",
+            format!(
+                "inputs total {} bytes, over the {} byte limit",
+                report.input_bytes, config.max_input_bytes
+            ),
+        ));
+    }
+    report
+        .problems
+        .extend(check_parameters(&job.job_type, &job.parameters));
+    if let Err(message) = check_output_writable(&job.output_directory) {
+        report.problems.push(JobProblem::new(
+            JobProblemKind::OutputNotWritable,
+            job.output_directory.display().to_string(),
+            message,
+        ));
+    }
+    report
+}
+
+/// A problem found before the file's subject is attached.
+struct InputProblem(JobProblemKind, String);
+
+impl InputProblem {
+    fn into_problem(self, subject: String) -> JobProblem {
+        JobProblem::new(self.0, subject, self.1)
+    }
+}
+
+/// Size of the regular, non-empty file at `path`.
+fn input_size(path: &Path) -> Result<u64, InputProblem> {
+    let metadata = fs::metadata(path).map_err(|error| match error.kind() {
+        std::io::ErrorKind::NotFound => InputProblem(
+            JobProblemKind::MissingInput,
+            "input file does not exist".into(),
+        ),
+        _ => InputProblem(JobProblemKind::UnreadableInput, error.to_string()),
+    })?;
+    if !metadata.is_file() {
+        return Err(InputProblem(
+            JobProblemKind::UnreadableInput,
+            "input is not a regular file".into(),
+        ));
+    }
+    if metadata.len() == 0 {
+        return Err(InputProblem(
+            JobProblemKind::EmptyInput,
+            "input file is empty".into(),
+        ));
+    }
+    Ok(metadata.len())
+}
+
+fn check_input(
+    path: &Path,
+    kind: Option<InputKind>,
+    parse_json: bool,
+) -> Result<Vec<JobProblem>, InputProblem> {
+    let file = File::open(path)
+        .map_err(|e| InputProblem(JobProblemKind::UnreadableInput, e.to_string()))?;
+
+    let mut problems = Vec::new();
+    let Some(kind) = kind else {
+        return Ok(problems);
+    };
+    check_content(file, kind, parse_json).map_err(|message| {
+        InputProblem(
+            JobProblemKind::UnexpectedInputKind,
+            format!("expected {}: {message}", kind.label()),
+        )
+    })?;
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    if !extension
+        .as_deref()
+        .is_some_and(|extension| kind.extensions().contains(&extension))
+    {
+        problems.push(JobProblem::new(
+            JobProblemKind::UnexpectedExtension,
+            path.display().to_string(),
+            format!(
+                "{} usually has extension {}",
+                kind.label(),
+                kind.extensions().join(", ")
+            ),
+        ));
+    }
+    Ok(problems)
+}
+
+fn check_content(mut file: File, kind: InputKind, parse_json: bool) -> Result<(), String> {
+    match kind {
+        InputKind::MultispectralMetadata | InputKind::LidarScan => {
+            let mut header = Vec::with_capacity(JSON_HEADER_BYTES);
+            file.by_ref()
+                .take(JSON_HEADER_BYTES as u64)
+                .read_to_end(&mut header)
+                .map_err(|error| error.to_string())?;
+            if header.trim_ascii_start().first() != Some(&b'{') {
+                return Err("file is not a JSON object".to_string());
+            }
+            if !parse_json {
+                return Ok(());
+            }
+            file.rewind().map_err(|error| error.to_string())?;
+            let mut json = String::new();
+            file.read_to_string(&mut json)
+                .map_err(|error| error.to_string())?;
+            match kind {
+                InputKind::LidarScan => from_artifact_str::<LidarScan>(&json).map(drop),
+                _ => from_artifact_str::<MultispectralImage>(&json).map(drop),
+            }
+            .map_err(|error| error.to_string())
+        }
+        InputKind::ThermalRaster => {
+            let mut magic = [0u8; 8];
+            file.read_exact(&mut magic)
+                .and_then(|()| file.rewind())
+                .map_err(|_| "file is too short for a raster header".to_string())?;
+            // Only the header is decoded, for the pixel layout.
+            let reader = BufReader::new(file);
+            let color = match image::guess_format(&magic) {
+                Ok(ImageFormat::Png) => PngDecoder::new(reader).map(|decoder| decoder.color_type()),
+                Ok(ImageFormat::Tiff) => {
+                    TiffDecoder::new(reader).map(|decoder| decoder.color_type())
+                }
+                _ => return Err("file is neither a TIFF nor a PNG raster".to_string()),
+            }
+            .map_err(|error| error.to_string())?;
+            if color != ColorType::L16 {
+                return Err(format!("raster holds {color:?} pixels"));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Payload key each job type needs in `custom_parameters`, checked by
+/// deserializing it as that type's request. NDVI and thermal jobs need one
+/// only when they set prioritized ROIs.
+fn check_parameters(job_type: &JobType, parameters: &ProcessingParameters) -> Vec<JobProblem> {
+    let prioritized = parameters
+        .custom_parameters
+        .contains_key(PRIORITIZED_ROIS_KEY);
+    let payload_problem = match job_type {
+        JobType::NdviAnalysis if prioritized => {
+            check_payload::<NdviAnalysisRequest>(parameters, NDVI_REQUEST_PAYLOAD_KEY)
+        }
+        JobType::ThermalAnalysis if prioritized => {
+            check_payload::<ThermalAnalysisRequest>(parameters, THERMAL_REQUEST_PAYLOAD_KEY)
+        }
+        JobType::IndexAnomalyDetection => {
+            check_payload::<IndexAnomalyRequest>(parameters, INDEX_ANOMALY_PAYLOAD_KEY)
+        }
+        JobType::LidarChangeAdvisory => {
+            check_payload::<LidarChangeRequest>(parameters, LIDAR_CHANGE_PAYLOAD_KEY)
+        }
+        JobType::IndexTrendAdvisory => {
+            check_payload::<IndexTrendRequest>(parameters, INDEX_TREND_PAYLOAD_KEY)
+        }
+        JobType::IndexVegetationTypeClassification => {
+            check_payload::<IndexVegetationTypeClassificationRequest>(
+                parameters,
+                INDEX_VEGETATION_CLASSIFICATION_PAYLOAD_KEY,
+            )
+        }
+        JobType::NdviChangeDetection => {
+            check_payload::<NdviChangeRequest>(parameters, NDVI_CHANGE_PAYLOAD_KEY)
+        }
+        _ => None,
+    };
+    let mut problems = payload_problem.into_iter().collect::<Vec<_>>();
+    if parameters.custom_parameters.contains_key(SESSION_ID_KEY) {
+        problems.extend(check_payload::<Uuid>(parameters, SESSION_ID_KEY));
+    }
+
+    if let Some(locale) = parameters
+        .custom_parameters
+        .get(crate::RECOMMENDATION_LOCALE_KEY)
+    {
+        if !locale.is_string() {
+            problems.push(JobProblem::new(
+                JobProblemKind::InvalidParameter,
+                crate::RECOMMENDATION_LOCALE_KEY,
+                "expected a locale code string",
+            ));
+        }
+    }
+    for key in BOOLEAN_PARAMETER_KEYS {
+        if let Some(value) = parameters.custom_parameters.get(key) {
+            if !value.is_boolean() {
+                problems.push(JobProblem::new(
+                    JobProblemKind::InvalidParameter,
+                    key,
+                    "expected true or false",
+                ));
+            }
+        }
+    }
+    problems
+}
+
+fn check_payload<T: DeserializeOwned>(
+    parameters: &ProcessingParameters,
+    key: &str,
+) -> Option<JobProblem> {
+    let Some(payload) = parameters.custom_parameters.get(key) else {
+        return Some(JobProblem::new(
+            JobProblemKind::MissingParameter,
+            key,
+            "required payload is missing",
+        ));
+    };
+    serde_json::from_value::<T>(payload.clone())
+        .err()
+        .map(|error| JobProblem::new(JobProblemKind::InvalidParameter, key, error.to_string()))
+}
+
+/// Probes the nearest existing ancestor of `directory` with a scratch file;
+/// nothing is created when the directory itself does not exist yet.
+fn check_output_writable(directory: &Path) -> Result<(), String> {
+    let existing = directory
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .ok_or_else(|| "no existing parent directory".to_string())?;
+    if !existing.is_dir() {
+        return Err(format!("{} is not a directory", existing.display()));
+    }
+    let probe = existing.join(format!(".preflight-{}", Uuid::new_v4()));
+    File::create(&probe).map_err(|error| format!("cannot create files: {error}"))?;
+    let _ = fs::remove_file(probe);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JobStatus;
+    use chrono::Utc;
+    use image::{ImageBuffer, Luma};
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn job(job_type: JobType, input_files: Vec<PathBuf>, output_directory: &Path) -> ProcessingJob {
+        ProcessingJob {
+            id: Uuid::nil(),
+            job_type,
+            input_files,
+            output_directory: output_directory.to_path_buf(),
+            parameters: ProcessingParameters::default(),
+            status: JobStatus::Queued,
+            created_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+            error_message: None,
+        }
+    }
+
+    fn kinds(report: &JobValidationReport) -> Vec<(JobProblemKind, String)> {
+        report
+            .problems
+            .iter()
+            .map(|problem| (problem.kind, problem.subject.clone()))
+            .collect()
+    }
+
+    fn write_thermal(path: &Path) {
+        ImageBuffer::<Luma<u16>, _>::from_pixel(2, 2, Luma([30_000]))
+            .save(path)
+            .unwrap();
+    }
+
+    fn metadata_json() -> String {
+        json!({
+            "metadata": {
+                "timestamp": "2026-05-01T10:00:00Z",
+                "gps_position": null,
+                "bands": ["Red", "NIR"],
+                "exposure_time": 0.01,
+                "gain": 1.0,
+                "width": 2,
+                "height": 2
+            },
+            "file_paths": { "Red": "red.tif", "NIR": "nir.tif" },
+            "image_id": Uuid::nil()
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn well_formed_inputs_pass() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = dir.path().join("image.json");
+        fs::write(&metadata, metadata_json()).unwrap();
+        let thermal = dir.path().join("thermal.tif");
+        write_thermal(&thermal);
+        let config = JobValidationConfig::default();
+
+        let ndvi = validate_job(
+            &job(JobType::NdviAnalysis, vec![metadata], dir.path()),
+            &config,
+        );
+        assert!(ndvi.problems.is_empty(), "{:?}", ndvi.problems);
+        let thermal = validate_job(
+            &job(
+                JobType::ThermalAnalysis,
+                vec![thermal],
+                &dir.path().join("out"),
+            ),
+            &config,
+        );
+        assert!(thermal.passes(false), "{:?}", thermal.problems);
+        assert!(!dir.path().join("out").exists());
+    }
+
+    #[test]
+    fn oversized_inputs_are_only_sniffed_before_the_size_check_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let mismatched = dir.path().join("scan.json");
+        fs::write(&mismatched, b"{\"points\": 3}").unwrap();
+        let not_json = dir.path().join("notes.json");
+        fs::write(&not_json, b"plain text").unwrap();
+        let config = JobValidationConfig {
+            max_input_bytes: 8,
+            ..JobValidationConfig::default()
+        };
+
+        let report = validate_job(
+            &job(
+                JobType::LidarProcessing,
+                vec![mismatched, not_json.clone()],
+                dir.path(),
+            ),
+            &config,
+        );
+        assert_eq!(
+            kinds(&report),
+            [
+                (
+                    JobProblemKind::UnexpectedInputKind,
+                    not_json.display().to_string()
+                ),
+                (JobProblemKind::InputTooLarge, "input_files".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_empty_and_wrong_kind_inputs_name_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.json");
+        let empty = dir.path().join("empty.json");
+        fs::write(&empty, b"").unwrap();
+        let garbage = dir.path().join("scan.json");
+        fs::write(&garbage, b"{\"points\": 3}").unwrap();
+        let eight_bit = dir.path().join("thermal.png");
+        ImageBuffer::<Luma<u8>, _>::from_pixel(2, 2, Luma([9]))
+            .save(&eight_bit)
+            .unwrap();
+        let config = JobValidationConfig::default();
+
+        let lidar = validate_job(
+            &job(
+                JobType::LidarProcessing,
+                vec![missing.clone(), empty.clone(), garbage.clone()],
+                dir.path(),
+            ),
+            &config,
+        );
+        assert_eq!(
+            kinds(&lidar),
+            [
+                (JobProblemKind::MissingInput, missing.display().to_string()),
+                (JobProblemKind::EmptyInput, empty.display().to_string()),
+                (
+                    JobProblemKind::UnexpectedInputKind,
+                    garbage.display().to_string()
+                ),
+            ]
+        );
+        assert!(!lidar.passes(true));
+
+        let thermal = validate_job(
+            &job(
+                JobType::ThermalAnalysis,
+                vec![eight_bit.clone()],
+                dir.path(),
+            ),
+            &config,
+        );
+        assert_eq!(thermal.problems[0].subject, eight_bit.display().to_string());
+        assert!(thermal.problems[0].message.contains("L8"));
+    }
+
+    #[test]
+    fn extension_and_size_warnings_can_be_forced() {
+        let dir = tempfile::tempdir().unwrap();
+        let thermal = dir.path().join("thermal.raw");
+        write_thermal(&dir.path().join("thermal.png"));
+        fs::rename(dir.path().join("thermal.png"), &thermal).unwrap();
+        let config = JobValidationConfig {
+            max_input_bytes: 4,
+            ..JobValidationConfig::default()
+        };
+
+        let report = validate_job(
+            &job(JobType::ThermalAnalysis, vec![thermal.clone()], dir.path()),
+            &config,
+        );
+        assert_eq!(
+            kinds(&report),
+            [
+                (
+                    JobProblemKind::UnexpectedExtension,
+                    thermal.display().to_string()
+                ),
+                (JobProblemKind::InputTooLarge, "input_files".to_string()),
+            ]
+        );
+        assert!(!report.passes(false));
+        assert!(report.passes(true));
+        assert!(report.blocking(true).is_empty());
+    }
+
+    #[test]
+    fn payloads_and_flags_are_checked_by_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = JobValidationConfig::default();
+
+        let missing = validate_job(
+            &job(JobType::NdviChangeDetection, vec![], dir.path()),
+            &config,
+        );
+        assert_eq!(
+            kinds(&missing),
+            [(
+                JobProblemKind::MissingParameter,
+                NDVI_CHANGE_PAYLOAD_KEY.to_string()
+            )]
+        );
+
+        let mut mistyped = job(JobType::IndexTrendAdvisory, vec![], dir.path());
+        mistyped
+            .parameters
+            .custom_parameters
+            .insert(INDEX_TREND_PAYLOAD_KEY.to_string(), json!({ "scenes": 3 }));
+        mistyped.parameters.custom_parameters.insert(
+            crate::INDEX_TREND_FEATURE_FLAG_KEY.to_string(),
+            json!("yes"),
+        );
+        mistyped
+            .parameters
+            .custom_parameters
+            .insert(crate::RECOMMENDATION_LOCALE_KEY.to_string(), json!(7));
+        assert_eq!(
+            kinds(&validate_job(&mistyped, &config)),
+            [
+                (
+                    JobProblemKind::InvalidParameter,
+                    INDEX_TREND_PAYLOAD_KEY.to_string()
+                ),
+                (
+                    JobProblemKind::InvalidParameter,
+                    crate::RECOMMENDATION_LOCALE_KEY.to_string()
+                ),
+                (
+                    JobProblemKind::InvalidParameter,
+                    crate::INDEX_TREND_FEATURE_FLAG_KEY.to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn output_directory_under_a_file_is_not_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("occupied");
+        fs::write(&file, b"x").unwrap();
+        let output = file.join("outputs");
+
+        let report = validate_job(
+            &job(JobType::CompositeReport, vec![], &output),
+            &JobValidationConfig::default(),
+        );
+        assert_eq!(
+            kinds(&report),
+            [(
+                JobProblemKind::OutputNotWritable,
+                output.display().to_string()
+            )]
+        );
+    }
+}
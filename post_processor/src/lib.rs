@@ -2,37 +2,67 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use shared::schemas::FarmFieldRegistry;
-use std::collections::{BTreeSet, HashMap};
+use shared::webhooks::{WebhookDeadLetter, WebhookDispatcher, WebhookEvent, WebhookEventKind};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
+pub mod api;
+pub mod artifacts;
 pub mod evidence;
 pub mod findings_export;
+pub mod grid_import;
+pub mod grid_store;
 pub mod grower_report;
+pub mod health_fusion;
 pub mod index_anomaly;
 pub mod index_trend;
 pub mod index_vegetation_classification;
+pub mod job_validation;
 pub mod lidar_analysis;
 pub mod lidar_change;
+pub mod localization;
 pub mod ndvi_analysis;
+pub mod ndvi_change;
+pub mod prescription;
+pub mod preview;
+pub mod problem_clusters;
 pub mod product_anomalies;
+pub mod product_registration;
+pub mod progress;
+pub mod recommendation_rules;
 pub mod report_generator;
+pub mod report_schedule;
+pub mod report_sections;
+pub mod roi_processing;
 pub mod thermal_analysis;
 pub mod thermal_spots;
+pub mod thumbnail;
 pub mod vegetation_summary;
+pub mod work_orders;
 pub mod zonal_statistics;
 pub mod zone_delineation;
 pub mod zone_recommendations;
 
+pub use artifacts::{
+    ArtifactKind, ArtifactManifest, ArtifactSink, ArtifactWriter, FsArtifactSink, JobArtifact,
+};
 pub use findings_export::{
     export_findings_csv, export_findings_geojson, export_findings_shapefile, FindingExportRecord,
     FindingsExportError, FINDINGS_CSV_HEADER,
 };
+pub use grid_import::{grid_statistics, read_grid_csv, GridCsvError};
+pub use grid_store::{
+    extract_window, value_at, GridStoreConfig, GridStoreError, GridValues, GridWindow,
+};
 pub use grower_report::{
     render_grower_ready_pdf, FieldReportMetadata, GrowerReportError, GrowerReportRequest,
     SceneReportMetadata,
 };
+pub use health_fusion::{fuse_health_index, CANOPY_HEIGHT_LAYER, NDVI_LAYER, THERMAL_STRESS_LAYER};
 pub use index_anomaly::{
     analyze_index_anomalies, IndexAnomalyDecision, IndexAnomalyError, IndexAnomalyRequest,
     INDEX_ANOMALY_FEATURE_FLAG_KEY, INDEX_ANOMALY_PAYLOAD_KEY,
@@ -49,28 +79,79 @@ pub use index_vegetation_classification::{
     VegetationTypeSignature, INDEX_VEGETATION_CLASSIFICATION_FEATURE_FLAG_KEY,
     INDEX_VEGETATION_CLASSIFICATION_PAYLOAD_KEY,
 };
+pub use job_validation::{
+    validate_job, InvalidJobPolicy, JobProblem, JobProblemKind, JobValidationConfig,
+    JobValidationError, JobValidationReport, ProblemSeverity, DEFAULT_MAX_INPUT_BYTES,
+};
 pub use lidar_analysis::{LidarAnalysisConfig, LidarAnalysisProcessor};
 pub use lidar_change::{
     analyze_lidar_change, LidarChangeDecision, LidarChangeError, LidarChangeRequest,
     LIDAR_CHANGE_FEATURE_FLAG_KEY, LIDAR_CHANGE_PAYLOAD_KEY,
 };
+pub use localization::{
+    LocaleCatalog, LocalizationError, LocalizationWarning, Localizer, MessageArg, MessageFormatter,
+    DEFAULT_LOCALE,
+};
 pub use ndvi_analysis::{NdviAnalysisConfig, NdviAnalysisProcessor};
+pub use ndvi_change::{
+    change_classification_legend, detect_ndvi_change, render_change_classification,
+    NdviChangeClass, NdviChangeError, NdviChangeRequest, NdviChangeResult, NdviChangeShares,
+    NdviChangeZone, NdviChangeZoneSummary, DEFAULT_NDVI_CHANGE_DEGRADED_ZONE_PCT,
+    NDVI_CHANGE_HIGH_PRIORITY_PCT, NDVI_CHANGE_PAYLOAD_KEY,
+};
+pub use prescription::{
+    export_prescription, generate_prescription, prescription_summary_csv, PrescribedZone,
+    PrescriptionConfig, PrescriptionError, PrescriptionExport, PrescriptionPlan,
+    PrescriptionRateRule, PrescriptionTarget, PrescriptionZoneFlag,
+    PRESCRIPTION_SUMMARY_CSV_HEADER,
+};
+pub use preview::{PreviewConfig, PreviewError, PreviewSize};
+pub use problem_clusters::{
+    cluster_problem_zones, recommend_problem_clusters, ProblemClusterError, ProblemClusterRule,
+    ThresholdDirection, LOW_NDVI_THRESHOLD,
+};
 pub use product_anomalies::{
     flag_product_anomalies, AnomalyDetectionConfig, AnomalyDetectionError, ProductAnomaly,
     ProductAnomalyReasonCode,
 };
+pub use product_registration::{ProductRegistration, ProductRegistryClient, SESSION_ID_KEY};
+pub use progress::{JobProgress, ProgressCallback};
+pub use recommendation_rules::{
+    RecommendationRule, RecommendationRuleError, RecommendationRuleSet, RecommendationTemplate,
+    RuleComparator,
+};
 pub use report_generator::ReportGenerator;
-pub use thermal_analysis::{ThermalAnalysisConfig, ThermalAnalysisProcessor};
+pub use report_schedule::{
+    CronExpression, MissedRunPolicy, ReportDataQuery, ReportSchedule, ReportScheduleError,
+    ReportScheduleRequest, ReportScheduleStore, ReportScheduleTrigger, ReportScheduler,
+    ReportSessionSource, ScheduleClock, ScheduleRun, ScheduleRunOutcome,
+};
+pub use report_sections::{
+    ChartRenderer, SectionCache, SectionFragment, SvgChartRenderer, DEFAULT_SECTION_CACHE_BYTES,
+};
+pub use roi_processing::{
+    BlockSchedule, BlockWindow, GridAssembler, GridGeometry, PartialGridResult, PrioritizedRoi,
+    RoiProcessingError, NDVI_REQUEST_PAYLOAD_KEY, PRIORITIZED_ROIS_KEY, ROI_BLOCK_SIZE_KEY,
+    THERMAL_REQUEST_PAYLOAD_KEY,
+};
+pub use thermal_analysis::{ThermalAnalysisConfig, ThermalAnalysisProcessor, ThermalCacheStats};
 pub use thermal_spots::{
     detect_thermal_spots, ThermalSpot, ThermalSpotError, ThermalSpotRequest, ThermalSpotSummary,
     ThermalSpotType,
 };
+pub use thumbnail::{render_result_thumbnail, ThumbnailCache, ThumbnailError};
 pub use vegetation_summary::{
     summarize_vegetation, VegetationSourceProduct, VegetationSummary, VegetationSummaryError,
     VegetationSummaryInput, VegetationTrend, DEFAULT_LOW_VIGOR_NDVI_THRESHOLD,
 };
+pub use work_orders::{
+    WorkOrder, WorkOrderError, WorkOrderFollowUpPolicy, WorkOrderQuery, WorkOrderRequest,
+    WorkOrderStatus, WorkOrderStore, WorkOrderSuggestion, WorkOrderTransition,
+    WorkOrderTransitionRequest, WorkOrderUpdate, DEFAULT_VERIFY_MIN_NDVI_GAIN,
+};
 pub use zonal_statistics::{
-    compute_zonal_statistics, ProductGrid, ProductGridStatistics, ZonalStatisticsError,
+    compute_statistics, compute_zonal_statistics, ProductGrid, ProductGridStatistics,
+    ZonalStatisticsError, DEFAULT_PERCENTILES,
 };
 pub use zone_delineation::{
     delineate_anomaly_zones, AnomalyZone, AnomalyZonePolygon, ZoneDelineationError,
@@ -84,8 +165,12 @@ const HEALTH_FEATURE_FLAG_KEY: &str = "crop_health_feature_enabled";
 const HEALTH_APPROVAL_KEY: &str = "crop_health_approval_granted";
 const HEALTH_STALE_KEY: &str = "crop_health_products_stale";
 const HEALTH_EVIDENCE_KEY: &str = "evidence_refs";
+/// Locale code for recommendation text; English when absent.
+const RECOMMENDATION_LOCALE_KEY: &str = "locale";
 const YIELD_FEATURE_FLAG_KEY: &str = "crop_yield_feature_enabled";
 const YIELD_EVIDENCE_KEY: &str = "yield_evidence_refs";
+/// Partial results buffered for slow subscribers before they start lagging.
+const PARTIAL_RESULT_CHANNEL_CAPACITY: usize = 64;
 
 /// Post-processing pipeline for agricultural drone data
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -135,6 +220,12 @@ pub enum AnalysisJobError {
     JobNotFound { job_id: Uuid },
     #[error("analysis job queue rejected request: {reason}")]
     QueueRejected { reason: String },
+    #[error("analysis job {job_id} already finished as {status:?}")]
+    AlreadyFinished { job_id: Uuid, status: JobStatus },
+    #[error("analysis job {job_id} is {status:?} and cannot be retried")]
+    NotRetryable { job_id: Uuid, status: JobStatus },
+    #[error(transparent)]
+    Invalid(#[from] JobValidationError),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -150,6 +241,7 @@ pub enum JobType {
     LidarChangeAdvisory,
     IndexTrendAdvisory,
     IndexVegetationTypeClassification,
+    NdviChangeDetection,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -245,7 +337,9 @@ pub enum ResultData {
     GridData {
         width: u32,
         height: u32,
-        values: Vec<f32>,
+        /// Inline, or mapped from a grid file once above
+        /// [`GridStoreConfig::spill_min_cells`].
+        values: GridValues,
         bounds: (f64, f64, f64, f64),
         units: String,
     },
@@ -336,18 +430,69 @@ pub enum Priority {
     Critical,
 }
 
-/// Main post-processing service
+/// Progress event for a prioritized analysis job.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobPartialResult {
+    pub job_id: Uuid,
+    pub partial: PartialGridResult,
+}
+
+/// Jobs by lifecycle stage. Cancelling a running job only flags it; the
+/// worker drops its result when it finishes.
+#[derive(Debug, Default)]
+struct JobBook {
+    queue: Vec<ProcessingJob>,
+    running: HashMap<Uuid, ProcessingJob>,
+    completed: HashMap<Uuid, ProcessingJob>,
+    cancel_requested: HashSet<Uuid>,
+}
+
+impl JobBook {
+    fn find(&self, job_id: &Uuid) -> Option<&ProcessingJob> {
+        self.queue
+            .iter()
+            .find(|job| job.id == *job_id)
+            .or_else(|| self.running.get(job_id))
+            .or_else(|| self.completed.get(job_id))
+    }
+}
+
+/// Main post-processing service.
+///
+/// Every operation takes `&self`, so one instance can be shared through an
+/// `Arc` by the REST layer and any number of workers. State is split into
+/// independently locked parts and no lock is held across an `.await` or
+/// while a job runs, so submitting, cancelling and querying never wait for
+/// a job in progress. Analyzers and configuration are immutable once the
+/// service is shared; the `set_*` methods are for setting it up.
 pub struct PostProcessorService {
-    job_queue: Vec<ProcessingJob>,
-    completed_jobs: HashMap<Uuid, ProcessingJob>,
-    results_cache: HashMap<Uuid, AnalysisResult>,
-    result_records: HashMap<Uuid, RetainedAnalysisResult>,
-    analysis_job_identities: HashMap<Uuid, AnalysisJobIdentity>,
+    jobs: Mutex<JobBook>,
+    results_cache: RwLock<HashMap<Uuid, AnalysisResult>>,
+    partial_results: Mutex<HashMap<Uuid, PartialGridResult>>,
+    partial_result_events: broadcast::Sender<JobPartialResult>,
+    result_records: RwLock<HashMap<Uuid, RetainedAnalysisResult>>,
+    analysis_job_identities: RwLock<HashMap<Uuid, AnalysisJobIdentity>>,
+    artifact_manifests: RwLock<HashMap<Uuid, ArtifactManifest>>,
+    artifact_sink: Arc<dyn ArtifactSink>,
     working_directory: PathBuf,
     ndvi_analyzer: NdviAnalysisProcessor,
     lidar_analyzer: LidarAnalysisProcessor,
     thermal_analyzer: ThermalAnalysisProcessor,
     report_generator: ReportGenerator,
+    recommendation_rules: RecommendationRuleSet,
+    localizer: Localizer,
+    preview_config: PreviewConfig,
+    grid_store_config: GridStoreConfig,
+    job_validation: JobValidationConfig,
+    webhooks: Option<WebhookDispatcher>,
+    product_registry: Option<ProductRegistryClient>,
+    work_orders: Mutex<WorkOrderStore>,
+    work_order_policy: WorkOrderFollowUpPolicy,
+    progress_callback: Option<ProgressCallback>,
+    /// Holds each job at the start of processing until the test adds a
+    /// permit, so tests can keep a job in flight.
+    #[cfg(test)]
+    processing_gate: Option<std::sync::Arc<tokio::sync::Semaphore>>,
 }
 
 impl PostProcessorService {
@@ -361,13 +506,23 @@ impl PostProcessorService {
             .values()
             .map(|record| (record.identity.job_id, record.identity.clone()))
             .collect();
+        let recommendation_rules = RecommendationRuleSet::load(&working_directory)?;
+        let localizer = Localizer::load(&working_directory)?;
+        let work_orders = WorkOrderStore::load(&working_directory)?;
+        let section_cache = SectionCache::new(
+            working_directory.join("report_sections"),
+            DEFAULT_SECTION_CACHE_BYTES,
+        );
 
         Ok(Self {
-            job_queue: Vec::new(),
-            completed_jobs: HashMap::new(),
-            results_cache,
-            result_records,
-            analysis_job_identities,
+            jobs: Mutex::new(JobBook::default()),
+            results_cache: RwLock::new(results_cache),
+            partial_results: Mutex::new(HashMap::new()),
+            partial_result_events: broadcast::channel(PARTIAL_RESULT_CHANNEL_CAPACITY).0,
+            result_records: RwLock::new(result_records),
+            analysis_job_identities: RwLock::new(analysis_job_identities),
+            artifact_manifests: RwLock::new(HashMap::new()),
+            artifact_sink: Arc::new(FsArtifactSink),
             working_directory,
             ndvi_analyzer: NdviAnalysisProcessor::new(NdviAnalysisConfig::default()),
             lidar_analyzer: LidarAnalysisProcessor::new(LidarAnalysisConfig::default()),
@@ -389,28 +544,182 @@ impl PostProcessorService {
                     website: Some("https://agrodrone.com".to_string()),
                     certification_info: None,
                 },
-            }),
+            })
+            .with_localizer(localizer.clone())
+            .with_section_cache(section_cache),
+            recommendation_rules,
+            localizer,
+            preview_config: PreviewConfig::default(),
+            grid_store_config: GridStoreConfig::default(),
+            job_validation: JobValidationConfig::default(),
+            webhooks: None,
+            product_registry: None,
+            work_orders: Mutex::new(work_orders),
+            work_order_policy: WorkOrderFollowUpPolicy::default(),
+            progress_callback: None,
+            #[cfg(test)]
+            processing_gate: None,
         })
     }
 
-    pub async fn submit_job(&mut self, mut job: ProcessingJob) -> Result<Uuid> {
+    pub fn recommendation_rules(&self) -> &RecommendationRuleSet {
+        &self.recommendation_rules
+    }
+
+    pub fn set_recommendation_rules(&mut self, rules: RecommendationRuleSet) {
+        self.recommendation_rules = rules;
+    }
+
+    /// Message catalogs for recommendation and report text.
+    pub fn set_localizer(&mut self, localizer: Localizer) {
+        self.report_generator.set_localizer(localizer.clone());
+        self.localizer = localizer;
+    }
+
+    pub fn set_preview_config(&mut self, config: PreviewConfig) {
+        self.preview_config = config;
+    }
+
+    pub fn set_grid_store_config(&mut self, config: GridStoreConfig) {
+        self.grid_store_config = config;
+    }
+
+    pub fn set_job_validation_config(&mut self, config: JobValidationConfig) {
+        self.job_validation = config;
+    }
+
+    pub fn set_work_order_follow_up_policy(&mut self, policy: WorkOrderFollowUpPolicy) {
+        self.work_order_policy = policy;
+    }
+
+    /// Where analyzers write their artifact files; the local filesystem
+    /// unless replaced.
+    pub fn set_artifact_sink(&mut self, sink: Arc<dyn ArtifactSink>) {
+        self.artifact_sink = sink;
+    }
+
+    /// Called as jobs run with the share of each job done, ending at 1 for
+    /// every job that completes.
+    pub fn set_progress_callback(&mut self, callback: ProgressCallback) {
+        self.progress_callback = Some(callback);
+    }
+
+    /// Publishes `job.completed` and `job.failed` events through `webhooks`.
+    pub fn set_webhooks(&mut self, webhooks: WebhookDispatcher) {
+        self.webhooks = Some(webhooks);
+    }
+
+    /// Registers the artifacts of jobs carrying a [`SESSION_ID_KEY`] as
+    /// products of that session.
+    pub fn set_product_registry(&mut self, registry: ProductRegistryClient) {
+        self.product_registry = Some(registry);
+    }
+
+    /// Webhook deliveries that exhausted their retries; empty when webhooks
+    /// are not configured.
+    pub fn webhook_dead_letters(&self) -> Vec<WebhookDeadLetter> {
+        self.webhooks
+            .as_ref()
+            .map(WebhookDispatcher::dead_letters)
+            .unwrap_or_default()
+    }
+
+    fn publish_webhook(&self, kind: WebhookEventKind, payload: serde_json::Value) {
+        let Some(webhooks) = &self.webhooks else {
+            return;
+        };
+        if let Err(error) = webhooks.publish(WebhookEvent::new(kind, payload)) {
+            tracing::warn!("Dropping {} webhook: {}", kind.as_str(), error);
+        }
+    }
+
+    /// Runs the pre-flight checks [`Self::submit_job`] applies, without
+    /// queueing anything. The checks read input files, so they run on a
+    /// blocking thread.
+    pub async fn validate_job(&self, job: &ProcessingJob) -> JobValidationReport {
+        let job = job.clone();
+        let config = self.job_validation.clone();
+        tokio::task::spawn_blocking(move || validate_job(&job, &config))
+            .await
+            .unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic()))
+    }
+
+    /// Queues `job` once it passes validation. A job that fails is refused
+    /// with a [`JobValidationError`], or recorded as failed, depending on
+    /// [`JobValidationConfig::invalid_job_policy`].
+    pub async fn submit_job(&self, job: ProcessingJob) -> Result<Uuid> {
+        self.submit_job_checked(job, false).await
+    }
+
+    /// Like [`Self::submit_job`], but validation warnings do not stop the job.
+    pub async fn submit_job_forced(&self, job: ProcessingJob) -> Result<Uuid> {
+        self.submit_job_checked(job, true).await
+    }
+
+    async fn submit_job_checked(&self, mut job: ProcessingJob, force: bool) -> Result<Uuid> {
         job.id = Uuid::new_v4();
         job.status = JobStatus::Queued;
         job.created_at = Utc::now();
+        match self.preflight(&job, force).await {
+            Ok(()) => Ok(self.enqueue_job(job)),
+            Err(error) => match self.job_validation.invalid_job_policy {
+                InvalidJobPolicy::Reject => Err(error.into()),
+                InvalidJobPolicy::RecordFailed => Ok(self.record_invalid_job(job, &error)),
+            },
+        }
+    }
 
+    async fn preflight(
+        &self,
+        job: &ProcessingJob,
+        force: bool,
+    ) -> std::result::Result<(), JobValidationError> {
+        let report = self.validate_job(job).await;
+        if report.passes(force) {
+            return Ok(());
+        }
+        Err(JobValidationError {
+            problems: report.blocking(force),
+        })
+    }
+
+    /// Files a job that failed validation as [`JobStatus::Failed`] without
+    /// queueing it.
+    fn record_invalid_job(&self, mut job: ProcessingJob, error: &JobValidationError) -> Uuid {
         let job_id = job.id;
-        self.job_queue.push(job);
+        job.status = JobStatus::Failed;
+        job.error_message = Some(error.to_string());
+        job.completed_at = Some(Utc::now());
+        self.sync_analysis_job_identity(&job);
+        lock(&self.jobs).completed.insert(job_id, job.clone());
+
+        tracing::warn!("Job {} failed validation: {}", job_id, error);
+        self.publish_webhook(
+            WebhookEventKind::JobFailed,
+            serde_json::json!({
+                "job_id": job_id,
+                "job_type": job.job_type,
+                "error": error.to_string(),
+            }),
+        );
+        job_id
+    }
+
+    fn enqueue_job(&self, job: ProcessingJob) -> Uuid {
+        let job_id = job.id;
+        let mut jobs = lock(&self.jobs);
+        jobs.queue.push(job);
 
         // Sort by priority and creation time
-        self.job_queue
-            .sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        jobs.queue.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        drop(jobs);
 
         tracing::info!("Submitted processing job: {}", job_id);
-        Ok(job_id)
+        job_id
     }
 
     pub async fn submit_analysis_job(
-        &mut self,
+        &self,
         scene_catalog: &FarmFieldRegistry,
         request: AnalysisJobRequest,
     ) -> std::result::Result<Uuid, AnalysisJobError> {
@@ -424,12 +733,8 @@ impl PostProcessorService {
             });
         }
 
-        let scene_id = request.scene_id;
-        let field_id = request.field_id;
-        let season_id = request.season_id;
-        let product_refs = request.product_refs;
         let job = ProcessingJob {
-            id: Uuid::nil(),
+            id: Uuid::new_v4(),
             job_type: request.job_type,
             input_files: request.input_files,
             output_directory: request.output_directory,
@@ -440,87 +745,239 @@ impl PostProcessorService {
             completed_at: None,
             error_message: None,
         };
-        let job_id =
-            self.submit_job(job)
-                .await
-                .map_err(|error| AnalysisJobError::QueueRejected {
-                    reason: error.to_string(),
-                })?;
-        let created_at = self
-            .get_job_status(&job_id)
-            .await
-            .map(|job| job.created_at)
-            .unwrap_or_else(Utc::now);
-
-        self.analysis_job_identities.insert(
-            job_id,
+        // Registered before the job is queued so a worker that picks it up
+        // straight away still retains its result.
+        write(&self.analysis_job_identities).insert(
+            job.id,
             AnalysisJobIdentity {
-                job_id,
-                scene_id,
-                field_id,
-                season_id,
-                product_refs,
-                created_at,
+                job_id: job.id,
+                scene_id: request.scene_id,
+                field_id: request.field_id,
+                season_id: request.season_id,
+                product_refs: request.product_refs,
+                created_at: job.created_at,
                 status: JobStatus::Queued,
                 failure_reason: None,
             },
         );
 
-        Ok(job_id)
+        let validated = self.preflight(&job, false).await;
+        match (validated, self.job_validation.invalid_job_policy) {
+            (Ok(()), _) => Ok(self.enqueue_job(job)),
+            (Err(error), InvalidJobPolicy::Reject) => {
+                write(&self.analysis_job_identities).remove(&job.id);
+                Err(error.into())
+            }
+            (Err(error), InvalidJobPolicy::RecordFailed) => {
+                Ok(self.record_invalid_job(job, &error))
+            }
+        }
     }
 
-    pub async fn process_next_job(&mut self) -> Result<Option<AnalysisResult>> {
-        if let Some(mut job) = self.job_queue.pop() {
-            job.status = JobStatus::Processing;
-            job.started_at = Some(Utc::now());
-            self.sync_analysis_job_identity(&job);
-
-            tracing::info!("Processing job: {} (type: {:?})", job.id, job.job_type);
+    pub async fn process_next_job(&self) -> Result<Option<AnalysisResult>> {
+        let Some(mut job) = ({
+            let mut jobs = lock(&self.jobs);
+            let next = jobs.queue.pop();
+            if let Some(job) = &next {
+                let mut running = job.clone();
+                running.status = JobStatus::Processing;
+                running.started_at = Some(Utc::now());
+                jobs.running.insert(job.id, running);
+            }
+            next
+        }) else {
+            return Ok(None);
+        };
+        job.status = JobStatus::Processing;
+        job.started_at = Some(Utc::now());
+        self.sync_analysis_job_identity(&job);
+
+        tracing::info!("Processing job: {} (type: {:?})", job.id, job.job_type);
+
+        let outcome = self.process_job(&job).await;
+        let cancelled = lock(&self.jobs).cancel_requested.remove(&job.id);
+        let result = match outcome {
+            _ if cancelled => {
+                self.remove_artifacts(&job.id).await;
+                job.status = JobStatus::Cancelled;
+                job.completed_at = Some(Utc::now());
+                tracing::info!("Job {} cancelled while processing", job.id);
+                None
+            }
+            Ok(mut result) => {
+                result.job_id = job.id;
+                job.status = JobStatus::Completed;
+                job.completed_at = Some(Utc::now());
+                self.attach_previews(&mut result);
+                self.spill_large_grid(&mut result);
+                write(&self.results_cache).insert(result.id, result.clone());
+                self.register_session_products(&job).await;
+                self.publish_webhook(
+                    WebhookEventKind::JobCompleted,
+                    serde_json::json!({
+                        "job_id": job.id,
+                        "job_type": job.job_type,
+                        "result_id": result.id,
+                        "result_type": result.result_type,
+                    }),
+                );
+                Some(result)
+            }
+            Err(e) => {
+                job.status = JobStatus::Failed;
+                job.error_message = Some(e.to_string());
+                job.completed_at = Some(Utc::now());
+                tracing::error!("Job {} failed: {}", job.id, e);
+                self.publish_webhook(
+                    WebhookEventKind::JobFailed,
+                    serde_json::json!({
+                        "job_id": job.id,
+                        "job_type": job.job_type,
+                        "error": e.to_string(),
+                    }),
+                );
+                None
+            }
+        };
 
-            let result = match self.process_job(&job).await {
-                Ok(mut result) => {
-                    result.job_id = job.id;
-                    job.status = JobStatus::Completed;
-                    job.completed_at = Some(Utc::now());
-                    self.results_cache.insert(result.id, result.clone());
-                    Some(result)
-                }
-                Err(e) => {
-                    job.status = JobStatus::Failed;
-                    job.error_message = Some(e.to_string());
-                    job.completed_at = Some(Utc::now());
-                    tracing::error!("Job {} failed: {}", job.id, e);
-                    None
-                }
-            };
+        self.sync_analysis_job_identity(&job);
+        let retained = match result.as_ref() {
+            Some(result) => self.retain_analysis_result(result).await,
+            None => Ok(()),
+        };
+        let mut jobs = lock(&self.jobs);
+        jobs.running.remove(&job.id);
+        jobs.completed.insert(job.id, job);
+        drop(jobs);
+        retained?;
+        Ok(result)
+    }
 
+    /// Cancels a queued or running job. A queued job never runs; a running
+    /// one finishes its current step but its result is discarded. Either
+    /// way the job ends up [`JobStatus::Cancelled`].
+    pub fn cancel_job(&self, job_id: &Uuid) -> std::result::Result<(), AnalysisJobError> {
+        let mut jobs = lock(&self.jobs);
+        if let Some(queue_index) = jobs.queue.iter().position(|job| job.id == *job_id) {
+            let mut job = jobs.queue.remove(queue_index);
+            job.status = JobStatus::Cancelled;
+            job.completed_at = Some(Utc::now());
+            jobs.completed.insert(*job_id, job.clone());
+            drop(jobs);
             self.sync_analysis_job_identity(&job);
-            if let Some(result) = result.as_ref() {
-                self.retain_analysis_result(result).await?;
+            return Ok(());
+        }
+        if jobs.running.contains_key(job_id) {
+            jobs.cancel_requested.insert(*job_id);
+            return Ok(());
+        }
+        match jobs.completed.get(job_id) {
+            Some(job) => Err(AnalysisJobError::AlreadyFinished {
+                job_id: *job_id,
+                status: job.status,
+            }),
+            None => Err(AnalysisJobError::JobNotFound { job_id: *job_id }),
+        }
+    }
+
+    /// Queues a failed or cancelled job again under the same id. The retry
+    /// replaces whatever artifacts the job has only once it succeeds.
+    pub fn retry_job(&self, job_id: &Uuid) -> std::result::Result<(), AnalysisJobError> {
+        let mut jobs = lock(&self.jobs);
+        let mut job = match jobs.completed.remove(job_id) {
+            Some(job) if matches!(job.status, JobStatus::Failed | JobStatus::Cancelled) => job,
+            Some(job) => {
+                let status = job.status;
+                jobs.completed.insert(*job_id, job);
+                return Err(AnalysisJobError::NotRetryable {
+                    job_id: *job_id,
+                    status,
+                });
             }
-            self.completed_jobs.insert(job.id, job);
-            Ok(result)
-        } else {
-            Ok(None)
+            None => {
+                return Err(match jobs.find(job_id) {
+                    Some(job) => AnalysisJobError::NotRetryable {
+                        job_id: *job_id,
+                        status: job.status,
+                    },
+                    None => AnalysisJobError::JobNotFound { job_id: *job_id },
+                })
+            }
+        };
+        drop(jobs);
+
+        job.status = JobStatus::Queued;
+        job.started_at = None;
+        job.completed_at = None;
+        job.error_message = None;
+        self.sync_analysis_job_identity(&job);
+        self.enqueue_job(job);
+        Ok(())
+    }
+
+    async fn process_job(&self, job: &ProcessingJob) -> Result<AnalysisResult> {
+        #[cfg(test)]
+        if let Some(gate) = &self.processing_gate {
+            gate.acquire().await?.forget();
+        }
+
+        let progress = JobProgress::new(job.id, self.progress_callback.clone());
+        progress.report(0.0);
+        let result = self.run_job(job, &progress).await;
+        if result.is_ok() {
+            progress.finish();
         }
+        result
     }
 
-    async fn process_job(&mut self, job: &ProcessingJob) -> Result<AnalysisResult> {
+    async fn run_job(&self, job: &ProcessingJob, progress: &JobProgress) -> Result<AnalysisResult> {
+        let prioritized = job
+            .parameters
+            .custom_parameters
+            .contains_key(PRIORITIZED_ROIS_KEY);
         match job.job_type {
+            JobType::NdviAnalysis if prioritized => self.run_prioritized_ndvi(job, progress).await,
+            JobType::ThermalAnalysis if prioritized => {
+                self.run_prioritized_thermal(job, progress).await
+            }
             JobType::NdviAnalysis => {
-                self.ndvi_analyzer
-                    .analyze(&job.input_files, &job.parameters)
-                    .await
+                let (result, manifest) = self
+                    .ndvi_analyzer
+                    .analyze(
+                        &job.input_files,
+                        &job.parameters,
+                        self.artifact_writer(job)?,
+                        progress,
+                    )
+                    .await?;
+                self.record_artifacts(manifest);
+                Ok(result)
             }
             JobType::LidarProcessing => {
-                self.lidar_analyzer
-                    .analyze(&job.input_files, &job.parameters)
-                    .await
+                let (result, manifest) = self
+                    .lidar_analyzer
+                    .analyze(
+                        &job.input_files,
+                        &job.parameters,
+                        self.artifact_writer(job)?,
+                        progress,
+                    )
+                    .await?;
+                self.record_artifacts(manifest);
+                Ok(result)
             }
             JobType::ThermalAnalysis => {
-                self.thermal_analyzer
-                    .analyze(&job.input_files, &job.parameters)
-                    .await
+                let (result, manifest) = self
+                    .thermal_analyzer
+                    .analyze(
+                        &job.input_files,
+                        &job.parameters,
+                        self.artifact_writer(job)?,
+                        progress,
+                    )
+                    .await?;
+                self.record_artifacts(manifest);
+                Ok(result)
             }
             JobType::MultiSpectralAnalysis => self.process_multispectral(job).await,
             JobType::CompositeReport => self.generate_composite_report(job).await,
@@ -532,6 +989,63 @@ impl PostProcessorService {
             JobType::IndexVegetationTypeClassification => {
                 self.analyze_index_vegetation_type_classification(job).await
             }
+            JobType::NdviChangeDetection => self.detect_ndvi_change(job).await,
+        }
+    }
+
+    fn artifact_writer(&self, job: &ProcessingJob) -> Result<ArtifactWriter> {
+        Ok(
+            ArtifactWriter::create(&job.output_directory, job.id, self.artifact_sink.clone())?
+                .with_source_inputs(&job.input_files),
+        )
+    }
+
+    /// Best effort: a registry that cannot be reached leaves the job
+    /// completed and is only logged.
+    async fn register_session_products(&self, job: &ProcessingJob) {
+        let Some(registry) = &self.product_registry else {
+            return;
+        };
+        let session_id = match product_registration::job_session_id(job) {
+            Ok(Some(session_id)) => session_id,
+            Ok(None) => return,
+            Err(error) => {
+                tracing::warn!("Not registering products of job {}: {:#}", job.id, error);
+                return;
+            }
+        };
+        let Some(manifest) = self.job_artifacts(&job.id) else {
+            return;
+        };
+        for registration in product_registration::product_registrations(job, &manifest) {
+            if let Err(error) = registry.register(session_id, &registration).await {
+                tracing::warn!(
+                    "Failed to register {} of job {} with session {}: {:#}",
+                    registration.product_type,
+                    job.id,
+                    session_id,
+                    error
+                );
+            }
+        }
+    }
+
+    fn record_artifacts(&self, manifest: ArtifactManifest) {
+        write(&self.artifact_manifests).insert(manifest.job_id, manifest);
+    }
+
+    /// Forgets a job's manifest and deletes its artifact directory.
+    async fn remove_artifacts(&self, job_id: &Uuid) {
+        let manifest = write(&self.artifact_manifests).remove(job_id);
+        if let Some(manifest) = manifest {
+            if let Err(error) = tokio::fs::remove_dir_all(&manifest.directory).await {
+                tracing::warn!(
+                    "Failed to remove artifacts of job {} at {}: {}",
+                    job_id,
+                    manifest.directory.display(),
+                    error
+                );
+            }
         }
     }
 
@@ -544,7 +1058,7 @@ impl PostProcessorService {
             data: ResultData::GridData {
                 width: 100,
                 height: 100,
-                values: vec![0.8; 10000], // Dummy data
+                values: vec![0.8; 10000].into(), // Dummy data
                 bounds: (0.0, 0.0, 100.0, 100.0),
                 units: "health_index".to_string(),
             },
@@ -652,24 +1166,19 @@ impl PostProcessorService {
             classification: Some(self.classify_health_zone(health_score)),
         });
 
-        let recommendations = if health_score < 0.55 {
-            vec![Recommendation {
-                category: RecommendationCategory::Irrigation,
-                priority: Priority::Medium,
-                title: "Review potential crop-stress signal".to_string(),
-                description: "Deterministic health indicators indicate review is advised."
-                    .to_string(),
-                action_items: vec![
-                    "Prioritize inspection of lower scoring zones".to_string(),
-                    "Confirm recent irrigation and irrigation scheduling logs are complete"
-                        .to_string(),
-                ],
-                affected_areas: zones.clone(),
-                confidence_score: 1.0 - uncertainty,
-            }]
-        } else {
-            Vec::new()
-        };
+        let locale = job
+            .parameters
+            .custom_parameters
+            .get(RECOMMENDATION_LOCALE_KEY)
+            .and_then(serde_json::Value::as_str)
+            .unwrap_or(DEFAULT_LOCALE);
+        let mut formatter = self.localizer.formatter(locale);
+        let recommendations =
+            self.recommendation_rules
+                .evaluate_localized(&zones, 1.0 - uncertainty, &mut formatter);
+        for warning in formatter.warnings() {
+            tracing::warn!("Job {} recommendation text: {}", job.id, warning);
+        }
 
         Ok(AnalysisResult {
             id: Uuid::new_v4(),
@@ -683,15 +1192,15 @@ impl PostProcessorService {
                     ("upper_uncertainty".to_string(), health_score + uncertainty),
                 ]),
             },
+            // The ends of the uncertainty band, so the statistics span it.
             statistics: AnalysisStatistics {
-                min_value: health_score - uncertainty,
-                max_value: health_score + uncertainty,
-                mean_value: health_score,
-                std_deviation: uncertainty,
-                percentiles: HashMap::new(),
                 coverage_area_m2: 10000.0,
                 valid_pixel_count: 1,
                 total_pixel_count: 1,
+                ..compute_statistics(
+                    &[health_score - uncertainty, health_score + uncertainty],
+                    &DEFAULT_PERCENTILES,
+                )
             },
             visualizations: Vec::new(),
             recommendations,
@@ -721,26 +1230,16 @@ impl PostProcessorService {
             data: ResultData::GridData {
                 width: 1,
                 height: 1,
-                values: vec![yield_estimate],
+                values: vec![yield_estimate].into(),
                 bounds: (0.0, 0.0, 1.0, 1.0),
                 units: "tons_per_hectare".to_string(),
             },
+            // The ends of the uncertainty band, as for crop health.
             statistics: AnalysisStatistics {
-                min_value: lower_yield,
-                max_value: upper_yield,
-                mean_value: yield_estimate,
-                std_deviation: uncertainty_span / 2.0,
-                percentiles: [
-                    ("25".to_string(), lower_yield),
-                    ("50".to_string(), yield_estimate),
-                    ("75".to_string(), upper_yield),
-                ]
-                .iter()
-                .cloned()
-                .collect(),
                 coverage_area_m2: 1.0,
                 valid_pixel_count: 1,
                 total_pixel_count: 1,
+                ..compute_statistics(&[lower_yield, upper_yield], &DEFAULT_PERCENTILES)
             },
             visualizations: Vec::new(),
             recommendations: Vec::new(),
@@ -769,7 +1268,7 @@ impl PostProcessorService {
                     ResultData::GridData {
                         width: trend.width,
                         height: trend.height,
-                        values: trend.delta_values.unwrap_or_default(),
+                        values: trend.delta_values.unwrap_or_default().into(),
                         bounds: (
                             trend.common_extent.min_lon,
                             trend.common_extent.min_lat,
@@ -843,7 +1342,7 @@ impl PostProcessorService {
                     ResultData::GridData {
                         width: result.width,
                         height: result.height,
-                        values: result.obstacle_change_values.unwrap_or_default(),
+                        values: result.obstacle_change_values.unwrap_or_default().into(),
                         bounds: (
                             result.common_extent.min_lon,
                             result.common_extent.min_lat,
@@ -915,6 +1414,123 @@ impl PostProcessorService {
         })
     }
 
+    async fn detect_ndvi_change(&self, job: &ProcessingJob) -> Result<AnalysisResult> {
+        let payload = job
+            .parameters
+            .custom_parameters
+            .get(NDVI_CHANGE_PAYLOAD_KEY)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "ndvi change detection requires '{}' payload",
+                    NDVI_CHANGE_PAYLOAD_KEY
+                )
+            })?;
+        let request: NdviChangeRequest = serde_json::from_value(payload.clone())
+            .map_err(|error| anyhow::anyhow!("invalid ndvi change payload: {error}"))?;
+        let change = detect_ndvi_change(&request).map_err(|error| anyhow::anyhow!("{error}"))?;
+        let layer_ref = format!("ndvi-change:{}", job.id);
+        let statistics = compute_zonal_statistics(&change.delta_grid(), &layer_ref)
+            .map_err(|error| anyhow::anyhow!("{error}"))?
+            .statistics;
+
+        fs::create_dir_all(&job.output_directory)?;
+        let overlay_path = job
+            .output_directory
+            .join(format!("ndvi_change_{}.png", job.id));
+        render_change_classification(&change).save(&overlay_path)?;
+        let legend_path = shared::legend::legend_path_for(&overlay_path);
+        change_classification_legend().write_png(&legend_path)?;
+
+        let cell_area_m2 = (change.resolution.x * change.resolution.y) as f32;
+        let zones = request
+            .zones
+            .iter()
+            .zip(&change.zones)
+            .map(|(zone, summary)| AnalysisZone {
+                id: summary.zone_id.clone(),
+                boundary: zone.boundary.clone(),
+                area_m2: summary.shares.compared_cell_count as f32 * cell_area_m2,
+                values: HashMap::from([
+                    ("improved_pct".to_string(), summary.shares.improved_pct),
+                    ("stable_pct".to_string(), summary.shares.stable_pct),
+                    ("degraded_pct".to_string(), summary.shares.degraded_pct),
+                ]),
+                classification: Some(
+                    if summary.shares.is_degraded(request.degraded_zone_pct) {
+                        "degraded"
+                    } else {
+                        "stable_or_improved"
+                    }
+                    .to_string(),
+                ),
+            })
+            .collect::<Vec<_>>();
+        let worst_degraded_pct = change
+            .zones
+            .iter()
+            .map(|zone| zone.shares.degraded_pct)
+            .fold(change.summary.degraded_pct, f32::max);
+
+        Ok(AnalysisResult {
+            id: Uuid::new_v4(),
+            job_id: job.id,
+            result_type: ResultType::NdviMap,
+            data: ResultData::GridData {
+                width: change.width,
+                height: change.height,
+                values: change.deltas.clone().into(),
+                bounds: (
+                    change.extent.min_lon,
+                    change.extent.min_lat,
+                    change.extent.max_lon,
+                    change.extent.max_lat,
+                ),
+                units: thumbnail::NDVI_DELTA_UNITS.to_string(),
+            },
+            statistics,
+            visualizations: vec![VisualizationOutput {
+                id: Uuid::new_v4(),
+                visualization_type: VisualizationType::ClassificationMap,
+                file_path: overlay_path,
+                format: "png".to_string(),
+                description: "NDVI change classified as improved, stable or degraded".to_string(),
+                parameters: HashMap::from([
+                    ("dead_band".to_string(), request.dead_band.to_string()),
+                    (
+                        "overlap_fraction".to_string(),
+                        format!("{:.3}", change.overlap_fraction),
+                    ),
+                    ("legend_path".to_string(), legend_path.display().to_string()),
+                ]),
+            }],
+            recommendations: vec![Recommendation {
+                category: RecommendationCategory::General,
+                // Bands start at the zone threshold, so a zone labelled
+                // degraded always raises the recommendation above low.
+                priority: if worst_degraded_pct
+                    >= NDVI_CHANGE_HIGH_PRIORITY_PCT.max(request.degraded_zone_pct)
+                {
+                    Priority::High
+                } else if worst_degraded_pct >= request.degraded_zone_pct {
+                    Priority::Medium
+                } else {
+                    Priority::Low
+                },
+                title: "NDVI change between flights".to_string(),
+                description: format!(
+                    "{:.1}% of compared cells degraded, {:.1}% improved.",
+                    change.summary.degraded_pct, change.summary.improved_pct
+                ),
+                action_items: Vec::new(),
+                affected_areas: zones,
+                confidence_score: change.overlap_fraction.min(1.0) as f32,
+            }],
+            evidence_refs: Vec::new(),
+            uncertainty: None,
+            created_at: Utc::now(),
+        })
+    }
+
     async fn analyze_index_anomaly(&self, job: &ProcessingJob) -> Result<AnalysisResult> {
         self.ensure_index_anomaly_feature_enabled(job)?;
         let request = self.resolve_index_anomaly_request(job)?;
@@ -1100,8 +1716,7 @@ impl PostProcessorService {
 
     fn resolve_yield_product_refs(&self, job: &ProcessingJob) -> Result<Vec<String>> {
         let identity = self
-            .analysis_job_identities
-            .get(&job.id)
+            .analysis_job_identity(&job.id)
             .ok_or_else(|| anyhow::anyhow!("missing analysis identity for yield prediction"))?;
 
         let mut references = BTreeSet::new();
@@ -1148,7 +1763,7 @@ impl PostProcessorService {
     }
 
     fn resolve_index_anomaly_request(&self, job: &ProcessingJob) -> Result<IndexAnomalyRequest> {
-        let _identity = self.analysis_job_identities.get(&job.id).ok_or_else(|| {
+        let _identity = self.analysis_job_identity(&job.id).ok_or_else(|| {
             anyhow::anyhow!("missing analysis identity for index anomaly detection")
         })?;
 
@@ -1185,7 +1800,7 @@ impl PostProcessorService {
         &self,
         job: &ProcessingJob,
     ) -> Result<IndexVegetationTypeClassificationRequest> {
-        let _identity = self.analysis_job_identities.get(&job.id).ok_or_else(|| {
+        let _identity = self.analysis_job_identity(&job.id).ok_or_else(|| {
             anyhow::anyhow!("missing analysis identity for index vegetation classification")
         })?;
 
@@ -1223,8 +1838,7 @@ impl PostProcessorService {
 
     fn resolve_index_trend_request(&self, job: &ProcessingJob) -> Result<IndexTrendRequest> {
         let _identity = self
-            .analysis_job_identities
-            .get(&job.id)
+            .analysis_job_identity(&job.id)
             .ok_or_else(|| anyhow::anyhow!("missing analysis identity for index trend advisory"))?;
 
         let payload = job
@@ -1242,7 +1856,7 @@ impl PostProcessorService {
     }
 
     fn resolve_lidar_change_request(&self, job: &ProcessingJob) -> Result<LidarChangeRequest> {
-        let _identity = self.analysis_job_identities.get(&job.id).ok_or_else(|| {
+        let _identity = self.analysis_job_identity(&job.id).ok_or_else(|| {
             anyhow::anyhow!("missing analysis identity for lidar change advisory")
         })?;
 
@@ -1312,8 +1926,7 @@ impl PostProcessorService {
 
     fn resolve_health_product_refs(&self, job: &ProcessingJob) -> Result<Vec<String>> {
         let identity = self
-            .analysis_job_identities
-            .get(&job.id)
+            .analysis_job_identity(&job.id)
             .ok_or_else(|| anyhow::anyhow!("missing analysis identity for health assessment"))?;
 
         let mut references = BTreeSet::new();
@@ -1423,18 +2036,179 @@ impl PostProcessorService {
         }
     }
 
-    pub async fn get_job_status(&self, job_id: &Uuid) -> Option<&ProcessingJob> {
-        // Check active queue first
-        if let Some(job) = self.job_queue.iter().find(|j| j.id == *job_id) {
-            return Some(job);
+    pub async fn get_job_status(&self, job_id: &Uuid) -> Option<ProcessingJob> {
+        lock(&self.jobs).find(job_id).cloned()
+    }
+
+    /// Files the job's last successful attempt produced; `None` until one
+    /// has, or for job types that write no artifacts.
+    pub fn job_artifacts(&self, job_id: &Uuid) -> Option<ArtifactManifest> {
+        read(&self.artifact_manifests).get(job_id).cloned()
+    }
+
+    pub async fn get_result(&self, result_id: &Uuid) -> Option<AnalysisResult> {
+        read(&self.results_cache).get(result_id).cloned()
+    }
+
+    /// Most recent partial grid of a job run with `prioritized_rois`,
+    /// complete once it finishes.
+    pub fn latest_partial_result(&self, job_id: &Uuid) -> Option<PartialGridResult> {
+        lock(&self.partial_results).get(job_id).cloned()
+    }
+
+    pub fn subscribe_partial_results(&self) -> broadcast::Receiver<JobPartialResult> {
+        self.partial_result_events.subscribe()
+    }
+
+    /// Runs an NDVI job that sets `prioritized_rois` on the request in its
+    /// payload: blocks touching the ROIs are analyzed first and a partial
+    /// result is published after every block.
+    async fn run_prioritized_ndvi(
+        &self,
+        job: &ProcessingJob,
+        progress: &JobProgress,
+    ) -> Result<AnalysisResult> {
+        let request: ndvi_analysis::NdviAnalysisRequest =
+            job_payload(job, NDVI_REQUEST_PAYLOAD_KEY)?;
+        let complete =
+            self.ndvi_analyzer
+                .process_ndvi_prioritized(&request, &job.parameters, |partial| {
+                    self.publish_partial(job.id, partial, progress)
+                })?;
+        self.finish_prioritized_job(job, ResultType::NdviMap, ArtifactKind::NdviGrid, complete)
+    }
+
+    /// Thermal counterpart of [`Self::run_prioritized_ndvi`].
+    async fn run_prioritized_thermal(
+        &self,
+        job: &ProcessingJob,
+        progress: &JobProgress,
+    ) -> Result<AnalysisResult> {
+        let request: thermal_analysis::ThermalAnalysisRequest =
+            job_payload(job, THERMAL_REQUEST_PAYLOAD_KEY)?;
+        let complete = self.thermal_analyzer.process_thermal_prioritized(
+            &request,
+            &job.parameters,
+            |partial| self.publish_partial(job.id, partial, progress),
+        )?;
+        self.finish_prioritized_job(
+            job,
+            ResultType::ThermalMap,
+            ArtifactKind::TemperatureGrid,
+            complete,
+        )
+    }
+
+    fn publish_partial(&self, job_id: Uuid, partial: &PartialGridResult, progress: &JobProgress) {
+        // Assembly is most of the work; writing artifacts takes the rest.
+        progress.report(partial.completeness * 0.9);
+        publish_partial_result(
+            &self.partial_results,
+            &self.partial_result_events,
+            job_id,
+            partial,
+        );
+    }
+
+    fn finish_prioritized_job(
+        &self,
+        job: &ProcessingJob,
+        result_type: ResultType,
+        grid_kind: ArtifactKind,
+        complete: PartialGridResult,
+    ) -> Result<AnalysisResult> {
+        let recommendations = grid_recommendations(&result_type, &complete.data);
+        let result = AnalysisResult {
+            id: Uuid::new_v4(),
+            job_id: job.id,
+            result_type,
+            data: complete.data,
+            statistics: complete.statistics,
+            visualizations: vec![],
+            recommendations,
+            evidence_refs: vec![],
+            uncertainty: None,
+            created_at: Utc::now(),
+        };
+        let mut artifacts = self.artifact_writer(job)?;
+        artifacts.write(grid_kind, &artifacts::grid_csv(&result.data))?;
+        artifacts.write(
+            ArtifactKind::Statistics,
+            &serde_json::to_vec_pretty(&result.statistics)?,
+        )?;
+        self.record_artifacts(artifacts.commit()?);
+        Ok(result)
+    }
+
+    /// Imports an NDVI grid computed outside the pipeline from an `x,y,value`
+    /// CSV (see [`read_grid_csv`]) and caches it like any other result, with
+    /// statistics, low-NDVI cluster recommendations and previews. The result
+    /// gets a fresh job id since no job produced it.
+    pub fn import_grid_csv(
+        &self,
+        path: &Path,
+        bounds: (f64, f64, f64, f64),
+        resolution: f64,
+    ) -> Result<AnalysisResult> {
+        let file = fs::File::open(path)?;
+        let data = read_grid_csv(file, bounds, resolution, "NDVI")?;
+        let mut result = AnalysisResult {
+            id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+            result_type: ResultType::NdviMap,
+            statistics: grid_statistics(&data),
+            recommendations: grid_recommendations(&ResultType::NdviMap, &data),
+            data,
+            visualizations: Vec::new(),
+            evidence_refs: Vec::new(),
+            uncertainty: None,
+            created_at: Utc::now(),
+        };
+        self.attach_previews(&mut result);
+        self.spill_large_grid(&mut result);
+        write(&self.results_cache).insert(result.id, result.clone());
+        Ok(result)
+    }
+
+    /// Stores downsampled previews next to large grid results so clients do
+    /// not have to fetch the full raster; failures only cost the previews.
+    fn attach_previews(&self, result: &mut AnalysisResult) {
+        if !self.preview_config.wants_previews(result) {
+            return;
+        }
+        let output_dir = self.working_directory.join("previews");
+        match preview::write_previews(result, &self.preview_config, &output_dir) {
+            Ok(previews) => result.visualizations.extend(previews),
+            Err(error) => tracing::warn!("Skipping previews for result {}: {}", result.id, error),
         }
+    }
 
-        // Check completed jobs
-        self.completed_jobs.get(job_id)
+    /// Moves the cells of a large grid result into a grid file under
+    /// `grids/` and keeps only the mapping; on failure the grid stays inline.
+    fn spill_large_grid(&self, result: &mut AnalysisResult) {
+        let ResultData::GridData {
+            width,
+            height,
+            values,
+            ..
+        } = &mut result.data
+        else {
+            return;
+        };
+        if values.is_mapped() || values.len() < self.grid_store_config.spill_min_cells {
+            return;
+        }
+        let path = Self::grid_file_path_for(&self.working_directory, &result.id);
+        match GridValues::spill(&path, *width, *height, values) {
+            Ok(mapped) => *values = mapped,
+            Err(error) => tracing::warn!("Keeping grid of result {} inline: {}", result.id, error),
+        }
     }
 
-    pub async fn get_result(&self, result_id: &Uuid) -> Option<&AnalysisResult> {
-        self.results_cache.get(result_id)
+    fn grid_file_path_for(working_directory: &Path, result_id: &Uuid) -> PathBuf {
+        working_directory
+            .join("grids")
+            .join(format!("{result_id}.grid"))
     }
 
     pub async fn list_analysis_results(
@@ -1447,8 +2221,7 @@ impl PostProcessorService {
         } else {
             query.page_size.min(250)
         };
-        let mut items: Vec<RetainedAnalysisResult> = self
-            .result_records
+        let mut items: Vec<RetainedAnalysisResult> = read(&self.result_records)
             .values()
             .filter(|record| analysis_result_matches_query(record, &query))
             .cloned()
@@ -1473,59 +2246,111 @@ impl PostProcessorService {
         }
     }
 
-    pub fn analysis_job_identity(&self, job_id: &Uuid) -> Option<&AnalysisJobIdentity> {
-        self.analysis_job_identities.get(job_id)
+    /// Opens a work order from one recommendation of a cached result, filed
+    /// under the field of the job that produced it.
+    pub fn create_work_order(
+        &self,
+        request: WorkOrderRequest,
+    ) -> std::result::Result<WorkOrder, WorkOrderError> {
+        let result = read(&self.results_cache)
+            .get(&request.result_id)
+            .cloned()
+            .ok_or(WorkOrderError::ResultNotFound {
+                result_id: request.result_id,
+            })?;
+        let field_id = self
+            .analysis_job_identity(&result.job_id)
+            .map(|identity| identity.field_id);
+        lock(&self.work_orders).create(&result, field_id, request, Utc::now())
+    }
+
+    pub fn work_order(&self, work_order_id: &Uuid) -> Option<WorkOrder> {
+        lock(&self.work_orders).work_order(work_order_id).cloned()
+    }
+
+    pub fn work_orders(&self, query: &WorkOrderQuery) -> Vec<WorkOrder> {
+        lock(&self.work_orders).work_orders(query)
+    }
+
+    pub fn update_work_order(
+        &self,
+        work_order_id: Uuid,
+        update: WorkOrderUpdate,
+    ) -> std::result::Result<WorkOrder, WorkOrderError> {
+        lock(&self.work_orders).update(work_order_id, update, Utc::now())
+    }
+
+    pub fn delete_work_order(
+        &self,
+        work_order_id: Uuid,
+    ) -> std::result::Result<(), WorkOrderError> {
+        lock(&self.work_orders).delete(work_order_id)
+    }
+
+    pub fn transition_work_order(
+        &self,
+        work_order_id: Uuid,
+        request: WorkOrderTransitionRequest,
+    ) -> std::result::Result<WorkOrder, WorkOrderError> {
+        lock(&self.work_orders).transition(work_order_id, request, Utc::now())
+    }
+
+    pub fn analysis_job_identity(&self, job_id: &Uuid) -> Option<AnalysisJobIdentity> {
+        read(&self.analysis_job_identities).get(job_id).cloned()
     }
 
     pub fn mark_analysis_job_failed(
-        &mut self,
+        &self,
         job_id: &Uuid,
         reason_code: impl Into<String>,
     ) -> std::result::Result<(), AnalysisJobError> {
         let reason_code = reason_code.into();
-        if let Some(queue_index) = self.job_queue.iter().position(|job| job.id == *job_id) {
-            let mut job = self.job_queue.remove(queue_index);
+        let mut jobs = lock(&self.jobs);
+        let job = if let Some(queue_index) = jobs.queue.iter().position(|job| job.id == *job_id) {
+            let mut job = jobs.queue.remove(queue_index);
             job.status = JobStatus::Failed;
             job.completed_at = Some(Utc::now());
-            job.error_message = Some(reason_code.clone());
-            self.sync_analysis_job_identity(&job);
-            self.completed_jobs.insert(*job_id, job);
-            return Ok(());
-        }
-
-        if let Some(job) = self.completed_jobs.get_mut(job_id) {
+            job.error_message = Some(reason_code);
+            jobs.completed.insert(*job_id, job.clone());
+            job
+        } else if let Some(job) = jobs.completed.get_mut(job_id) {
             job.status = JobStatus::Failed;
             job.completed_at = Some(Utc::now());
             job.error_message = Some(reason_code);
-            let job = job.clone();
-            self.sync_analysis_job_identity(&job);
-            return Ok(());
-        }
-
-        Err(AnalysisJobError::JobNotFound { job_id: *job_id })
+            job.clone()
+        } else {
+            return Err(AnalysisJobError::JobNotFound { job_id: *job_id });
+        };
+        drop(jobs);
+        self.sync_analysis_job_identity(&job);
+        Ok(())
     }
 
-    pub async fn list_jobs(&self, status_filter: Option<JobStatus>) -> Vec<&ProcessingJob> {
-        let mut jobs: Vec<&ProcessingJob> = self.job_queue.iter().collect();
-        jobs.extend(self.completed_jobs.values());
-
-        if let Some(status) = status_filter {
-            jobs.retain(|job| job.status == status);
-        }
+    pub async fn list_jobs(&self, status_filter: Option<JobStatus>) -> Vec<ProcessingJob> {
+        let book = lock(&self.jobs);
+        let mut jobs: Vec<ProcessingJob> = book
+            .queue
+            .iter()
+            .chain(book.running.values())
+            .chain(book.completed.values())
+            .filter(|job| status_filter.is_none_or(|status| job.status == status))
+            .cloned()
+            .collect();
+        drop(book);
 
         jobs.sort_by(|a, b| b.created_at.cmp(&a.created_at));
         jobs
     }
 
-    fn sync_analysis_job_identity(&mut self, job: &ProcessingJob) {
-        if let Some(identity) = self.analysis_job_identities.get_mut(&job.id) {
+    fn sync_analysis_job_identity(&self, job: &ProcessingJob) {
+        if let Some(identity) = write(&self.analysis_job_identities).get_mut(&job.id) {
             identity.status = job.status;
             identity.failure_reason = job.error_message.clone();
         }
     }
 
-    async fn retain_analysis_result(&mut self, result: &AnalysisResult) -> Result<()> {
-        let Some(identity) = self.analysis_job_identities.get(&result.job_id).cloned() else {
+    async fn retain_analysis_result(&self, result: &AnalysisResult) -> Result<()> {
+        let Some(identity) = self.analysis_job_identity(&result.job_id) else {
             return Ok(());
         };
         let record = RetainedAnalysisResult {
@@ -1537,7 +2362,14 @@ impl PostProcessorService {
         let output_path = output_dir.join(format!("{}.json", result.id));
         let content = serde_json::to_vec_pretty(&record)?;
         tokio::fs::write(output_path, content).await?;
-        self.result_records.insert(result.id, record);
+        let field_id = record.identity.field_id.clone();
+        write(&self.result_records).insert(result.id, record);
+        lock(&self.work_orders).record_follow_up(
+            &field_id,
+            result,
+            &self.work_order_policy,
+            Utc::now(),
+        )?;
         Ok(())
     }
 
@@ -1567,34 +2399,42 @@ impl PostProcessorService {
         working_directory.join("analysis_results")
     }
 
-    pub async fn cleanup_old_results(&mut self, older_than_days: u32) -> Result<u32> {
+    pub async fn cleanup_old_results(&self, older_than_days: u32) -> Result<u32> {
         let cutoff_date = Utc::now() - chrono::Duration::days(older_than_days as i64);
         let mut removed_count = 0;
 
         // Remove old completed jobs
-        self.completed_jobs.retain(|_, job| {
+        let mut old_job_ids = Vec::new();
+        lock(&self.jobs).completed.retain(|job_id, job| {
             if job.completed_at.unwrap_or(job.created_at) > cutoff_date {
                 true
             } else {
                 removed_count += 1;
+                old_job_ids.push(*job_id);
                 false
             }
         });
 
         // Remove old results and their retained listing records.
-        let old_result_ids: Vec<Uuid> = self
-            .results_cache
+        let old_result_ids: Vec<Uuid> = read(&self.results_cache)
             .iter()
             .filter_map(|(result_id, result)| {
                 (result.created_at <= cutoff_date).then_some(*result_id)
             })
             .collect();
         for result_id in old_result_ids {
-            self.results_cache.remove(&result_id);
-            self.result_records.remove(&result_id);
+            if let Some(result) = write(&self.results_cache).remove(&result_id) {
+                old_job_ids.push(result.job_id);
+            }
+            write(&self.result_records).remove(&result_id);
             let result_path = Self::analysis_results_dir_for(&self.working_directory)
                 .join(format!("{result_id}.json"));
             let _ = tokio::fs::remove_file(result_path).await;
+            let grid_path = Self::grid_file_path_for(&self.working_directory, &result_id);
+            let _ = tokio::fs::remove_file(grid_path).await;
+        }
+        for job_id in old_job_ids {
+            self.remove_artifacts(&job_id).await;
         }
 
         tracing::info!("Cleaned up {} old processing jobs", removed_count);
@@ -1617,6 +2457,58 @@ impl Default for AnalysisStatistics {
     }
 }
 
+fn grid_recommendations(result_type: &ResultType, data: &ResultData) -> Vec<Recommendation> {
+    match result_type {
+        ResultType::NdviMap => recommend_problem_clusters(data, &ProblemClusterRule::low_ndvi())
+            .unwrap_or_else(|error| {
+                tracing::warn!("Skipping low-NDVI cluster recommendations: {}", error);
+                Vec::new()
+            }),
+        _ => Vec::new(),
+    }
+}
+
+/// Deserializes the request a job carries under `key` in its custom parameters.
+fn job_payload<T: serde::de::DeserializeOwned>(job: &ProcessingJob, key: &str) -> Result<T> {
+    let payload = job
+        .parameters
+        .custom_parameters
+        .get(key)
+        .ok_or_else(|| anyhow::anyhow!("{:?} job requires '{key}' payload", job.job_type))?;
+    serde_json::from_value(payload.clone())
+        .map_err(|error| anyhow::anyhow!("invalid '{key}' payload: {error}"))
+}
+
+fn publish_partial_result(
+    partial_results: &Mutex<HashMap<Uuid, PartialGridResult>>,
+    events: &broadcast::Sender<JobPartialResult>,
+    job_id: Uuid,
+    partial: &PartialGridResult,
+) {
+    lock(partial_results).insert(job_id, partial.clone());
+    // Nobody listening is fine; the latest partial stays queryable.
+    let _ = events.send(JobPartialResult {
+        job_id,
+        partial: partial.clone(),
+    });
+}
+
+// Updates never leave a map half-written, so a poisoned lock is still usable.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
 fn analysis_result_matches_query(
     record: &RetainedAnalysisResult,
     query: &AnalysisResultListQuery,
@@ -1685,7 +2577,7 @@ mod tests {
     use serde_json::json;
     use shared::schemas::{
         FarmFieldEntityStatus, FarmFieldRegistry, FarmRecord, FieldBoundary, FieldRecord,
-        GeoBounds, GeoPoint, RasterResolution, SceneRecord, SeasonRecord,
+        GeoBounds, GeoPoint, RasterResolution, RasterSpatialRef, SceneRecord, SeasonRecord,
     };
     use tempfile::tempdir;
 
@@ -1699,7 +2591,7 @@ mod tests {
     #[tokio::test]
     async fn test_job_submission() {
         let temp_dir = tempdir().unwrap();
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
 
         let job = ProcessingJob {
             id: Uuid::new_v4(),
@@ -1720,9 +2612,88 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn analysis_job_submission_links_scene_field_and_season() {
+    async fn invalid_jobs_are_rejected_or_recorded_failed_by_policy() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let missing = temp_dir.path().join("missing.json");
+        let mut job = ndvi_job(temp_dir.path());
+        job.input_files = vec![missing.clone()];
+
+        let error = service.submit_job(job.clone()).await.unwrap_err();
+        let error = error.downcast::<JobValidationError>().unwrap();
+        assert_eq!(error.problems.len(), 1);
+        assert_eq!(error.problems[0].kind, JobProblemKind::MissingInput);
+        assert_eq!(error.problems[0].subject, missing.display().to_string());
+        assert!(service.process_next_job().await.unwrap().is_none());
+
+        service.set_job_validation_config(JobValidationConfig {
+            invalid_job_policy: InvalidJobPolicy::RecordFailed,
+            ..JobValidationConfig::default()
+        });
+        let job_id = service.submit_job(job).await.unwrap();
+        let status = service.get_job_status(&job_id).await.unwrap();
+        assert_eq!(status.status, JobStatus::Failed);
+        assert!(status
+            .error_message
+            .is_some_and(|message| message.contains("missing.json")));
+        assert!(service.process_next_job().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn forced_submission_bypasses_validation_warnings_only() {
+        let temp_dir = tempdir().unwrap();
+        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        service.set_job_validation_config(JobValidationConfig {
+            max_input_bytes: 1,
+            ..JobValidationConfig::default()
+        });
+        let mut job = ndvi_job(temp_dir.path());
+        job.input_files = vec![write_image_metadata(&temp_dir.path().join("image.json"))];
+
+        let report = service.validate_job(&job).await;
+        assert_eq!(report.problems[0].kind, JobProblemKind::InputTooLarge);
+        assert!(service.submit_job(job.clone()).await.is_err());
+        let job_id = service.submit_job_forced(job.clone()).await.unwrap();
+        let status = service.get_job_status(&job_id).await.unwrap();
+        assert_eq!(status.status, JobStatus::Queued);
+
+        job.input_files.push(temp_dir.path().join("missing.json"));
+        assert!(service.submit_job_forced(job).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn progress_callback_sees_each_job_climb_to_one() {
         let temp_dir = tempdir().unwrap();
         let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        service.set_progress_callback({
+            let reports = reports.clone();
+            Arc::new(move |job_id, fraction| reports.lock().unwrap().push((job_id, fraction)))
+        });
+        let mut job = ndvi_job(temp_dir.path());
+        job.input_files = (0..3)
+            .map(|index| write_image_metadata(&temp_dir.path().join(format!("image-{index}.json"))))
+            .collect();
+        let job_id = service.submit_job(job).await.unwrap();
+
+        service.process_next_job().await.unwrap().unwrap();
+
+        let reports = reports.lock().unwrap();
+        assert!(reports.iter().all(|(id, _)| *id == job_id));
+        let fractions: Vec<f32> = reports.iter().map(|(_, fraction)| *fraction).collect();
+        assert!(
+            fractions.len() > 3,
+            "expected per-file reports, got {fractions:?}"
+        );
+        assert!(fractions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(fractions.first(), Some(&0.0));
+        assert_eq!(fractions.last(), Some(&1.0));
+    }
+
+    #[tokio::test]
+    async fn analysis_job_submission_links_scene_field_and_season() {
+        let temp_dir = tempdir().unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let catalog = analysis_catalog();
 
         let job_id = service
@@ -1749,7 +2720,7 @@ mod tests {
     #[tokio::test]
     async fn analysis_job_submission_rejects_unknown_scene_without_queueing() {
         let temp_dir = tempdir().unwrap();
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let catalog = analysis_catalog();
         let mut request = analysis_job_request(temp_dir.path());
         request.scene_id = "missing-scene".to_string();
@@ -1771,7 +2742,7 @@ mod tests {
     #[tokio::test]
     async fn health_assessment_requires_feature_flag_and_approval() {
         let temp_dir = tempdir().unwrap();
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let catalog = analysis_catalog();
         let mut request = analysis_job_request(temp_dir.path());
         request.job_type = JobType::HealthAssessment;
@@ -1808,7 +2779,7 @@ mod tests {
     #[tokio::test]
     async fn health_assessment_requires_non_stale_deterministic_products() {
         let temp_dir = tempdir().unwrap();
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let catalog = analysis_catalog();
         let mut request = analysis_job_request(temp_dir.path());
         request.job_type = JobType::HealthAssessment;
@@ -1871,7 +2842,7 @@ mod tests {
             .insert(HEALTH_STALE_KEY.to_string(), json!(false));
 
         let first_result = {
-            let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+            let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
             let mut request = request.clone();
             request.job_type = JobType::HealthAssessment;
 
@@ -1888,7 +2859,7 @@ mod tests {
 
         let request = request;
         let second_result = {
-            let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+            let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
 
             let _ = service
                 .submit_analysis_job(&catalog, request.clone())
@@ -1922,7 +2893,7 @@ mod tests {
         let catalog = analysis_catalog();
         let mut request = analysis_job_request(temp_dir.path());
         request.job_type = JobType::YieldPrediction;
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
 
         let job_id = service
             .submit_analysis_job(&catalog, request)
@@ -1962,7 +2933,7 @@ mod tests {
             .insert(YIELD_FEATURE_FLAG_KEY.to_string(), json!(true));
 
         let first_result = {
-            let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+            let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
             let mut request = request.clone();
             request.job_type = JobType::YieldPrediction;
 
@@ -1978,7 +2949,7 @@ mod tests {
         };
 
         let second_result = {
-            let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+            let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
 
             let _ = service
                 .submit_analysis_job(&catalog, request.clone())
@@ -2050,7 +3021,7 @@ mod tests {
             .custom_parameters
             .insert(INDEX_TREND_FEATURE_FLAG_KEY.to_string(), json!(false));
 
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let job_id = service
             .submit_analysis_job(&catalog, request)
             .await
@@ -2087,7 +3058,7 @@ mod tests {
             .custom_parameters
             .insert(INDEX_TREND_FEATURE_FLAG_KEY.to_string(), json!(true));
 
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let _ = service
             .submit_analysis_job(&catalog, request)
             .await
@@ -2121,7 +3092,7 @@ mod tests {
             .custom_parameters
             .insert(INDEX_TREND_FEATURE_FLAG_KEY.to_string(), json!(true));
 
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let _ = service
             .submit_analysis_job(&catalog, request)
             .await
@@ -2151,7 +3122,7 @@ mod tests {
             .custom_parameters
             .insert(LIDAR_CHANGE_FEATURE_FLAG_KEY.to_string(), json!(false));
 
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let job_id = service
             .submit_analysis_job(&catalog, request)
             .await
@@ -2188,7 +3159,7 @@ mod tests {
             .custom_parameters
             .insert(LIDAR_CHANGE_FEATURE_FLAG_KEY.to_string(), json!(true));
 
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let _ = service
             .submit_analysis_job(&catalog, request)
             .await
@@ -2224,7 +3195,7 @@ mod tests {
             .custom_parameters
             .insert(LIDAR_CHANGE_FEATURE_FLAG_KEY.to_string(), json!(true));
 
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let _ = service
             .submit_analysis_job(&catalog, job_request)
             .await
@@ -2239,6 +3210,71 @@ mod tests {
         assert!(result.uncertainty.is_some());
     }
 
+    #[tokio::test]
+    async fn ndvi_change_detection_emits_delta_grid_and_classification_overlay() {
+        let temp_dir = tempdir().unwrap();
+        let catalog = analysis_catalog();
+        let mut current_values = vec![0.6; 4];
+        current_values[0] = 0.3;
+        let mut request = analysis_job_request(temp_dir.path());
+        request.job_type = JobType::NdviChangeDetection;
+        request.parameters.custom_parameters.insert(
+            NDVI_CHANGE_PAYLOAD_KEY.to_string(),
+            serde_json::to_value(NdviChangeRequest {
+                previous: ndvi_change_grid(0.0, vec![0.6; 4]),
+                current: ndvi_change_grid(10.0, current_values),
+                dead_band: 0.05,
+                min_overlap_fraction: 0.25,
+                degraded_zone_pct: DEFAULT_NDVI_CHANGE_DEGRADED_ZONE_PCT,
+                zones: vec![NdviChangeZone {
+                    id: "zone-east".to_string(),
+                    boundary: vec![
+                        (500010.0, 4500000.0),
+                        (500020.0, 4500000.0),
+                        (500020.0, 4500020.0),
+                        (500010.0, 4500020.0),
+                    ],
+                }],
+            })
+            .expect("valid request"),
+        );
+
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        service
+            .submit_analysis_job(&catalog, request)
+            .await
+            .expect("ndvi change request is accepted");
+        let result = service
+            .process_next_job()
+            .await
+            .expect("processing attempted")
+            .expect("ndvi change result produced");
+
+        let ResultData::GridData {
+            width,
+            height,
+            values,
+            ..
+        } = &result.data
+        else {
+            panic!("ndvi change should produce grid data");
+        };
+        assert_eq!((*width, *height), (3, 2));
+        assert!(values[0].is_nan());
+        assert!((values[1] + 0.3).abs() < 1e-6);
+        assert_eq!(result.statistics.valid_pixel_count, 2);
+        assert!(result.visualizations[0].file_path.exists());
+        let legend = fs::read(&result.visualizations[0].parameters["legend_path"]).unwrap();
+        assert_eq!(
+            shared::legend::legend_labels(&legend).unwrap(),
+            ["Improved", "Stable", "Degraded"]
+        );
+        let zone = &result.recommendations[0].affected_areas[0];
+        assert_eq!(zone.values["degraded_pct"], 50.0);
+        assert_eq!(zone.classification.as_deref(), Some("degraded"));
+        assert!(matches!(result.recommendations[0].priority, Priority::High));
+    }
+
     #[tokio::test]
     async fn index_anomaly_detection_requires_feature_flag() {
         let temp_dir = tempdir().unwrap();
@@ -2254,7 +3290,7 @@ mod tests {
             .custom_parameters
             .insert(INDEX_ANOMALY_FEATURE_FLAG_KEY.to_string(), json!(false));
 
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let job_id = service
             .submit_analysis_job(&catalog, request)
             .await
@@ -2295,7 +3331,7 @@ mod tests {
             .custom_parameters
             .insert(INDEX_ANOMALY_FEATURE_FLAG_KEY.to_string(), json!(true));
 
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let _ = service
             .submit_analysis_job(&catalog, job_request)
             .await
@@ -2328,7 +3364,7 @@ mod tests {
             .insert(INDEX_ANOMALY_FEATURE_FLAG_KEY.to_string(), json!(true));
 
         let mut first_result = {
-            let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+            let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
             let _ = service
                 .submit_analysis_job(&catalog, request.clone())
                 .await
@@ -2341,7 +3377,7 @@ mod tests {
         };
 
         let mut second_result = {
-            let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+            let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
             let _ = service
                 .submit_analysis_job(&catalog, request)
                 .await
@@ -2379,7 +3415,7 @@ mod tests {
             json!(false),
         );
 
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let job_id = service
             .submit_analysis_job(&catalog, request)
             .await
@@ -2416,7 +3452,7 @@ mod tests {
             json!(true),
         );
 
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let _ = service
             .submit_analysis_job(&catalog, request)
             .await
@@ -2451,7 +3487,7 @@ mod tests {
             json!(true),
         );
 
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let _ = service
             .submit_analysis_job(&catalog, request)
             .await
@@ -2481,7 +3517,7 @@ mod tests {
         );
 
         let mut first = {
-            let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+            let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
             let _ = service
                 .submit_analysis_job(&catalog, request.clone())
                 .await
@@ -2494,7 +3530,7 @@ mod tests {
         };
 
         let mut second = {
-            let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+            let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
             let _ = service
                 .submit_analysis_job(&catalog, request)
                 .await
@@ -2514,7 +3550,7 @@ mod tests {
     #[tokio::test]
     async fn analysis_job_failure_records_reason_code() {
         let temp_dir = tempdir().unwrap();
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let catalog = analysis_catalog();
         let job_id = service
             .submit_analysis_job(&catalog, analysis_job_request(temp_dir.path()))
@@ -2536,7 +3572,7 @@ mod tests {
     #[tokio::test]
     async fn completed_analysis_job_keeps_stable_job_id() {
         let temp_dir = tempdir().unwrap();
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let catalog = analysis_catalog();
         let mut request = analysis_job_request(temp_dir.path());
         request.job_type = JobType::MultiSpectralAnalysis;
@@ -2559,7 +3595,7 @@ mod tests {
     #[tokio::test]
     async fn completed_results_are_listed_with_filters_and_pagination() {
         let temp_dir = tempdir().unwrap();
-        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
         let catalog = analysis_catalog();
         let started_at = Utc::now() - chrono::Duration::seconds(1);
 
@@ -2613,7 +3649,7 @@ mod tests {
         let temp_dir = tempdir().unwrap();
         let catalog = analysis_catalog();
         let (result_id, job_id) = {
-            let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+            let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
             let mut request = analysis_job_request(temp_dir.path());
             request.job_type = JobType::MultiSpectralAnalysis;
             let job_id = service
@@ -2648,6 +3684,525 @@ mod tests {
         assert_eq!(page.items[0].result.id, result_id);
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn large_grid_results_are_mapped_across_restarts_without_loading_them() {
+        let temp_dir = tempdir().unwrap();
+        let (width, height) = (8192_u32, 4096_u32);
+        let grid_bytes = width as u64 * height as u64 * 4;
+        let result_id = {
+            let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+            service.set_grid_store_config(GridStoreConfig {
+                spill_min_cells: 1_000_000,
+            });
+            let job_id = Uuid::new_v4();
+            write(&service.analysis_job_identities).insert(
+                job_id,
+                AnalysisJobIdentity {
+                    job_id,
+                    scene_id: "scene-1".to_string(),
+                    field_id: "field-a".to_string(),
+                    season_id: "season-2026".to_string(),
+                    product_refs: Vec::new(),
+                    created_at: Utc::now(),
+                    status: JobStatus::Completed,
+                    failure_reason: None,
+                },
+            );
+            let values = (0..height)
+                .flat_map(|row| (0..width).map(move |col| (row * width + col) as f32))
+                .collect::<Vec<_>>();
+            let mut result = AnalysisResult {
+                id: Uuid::new_v4(),
+                job_id,
+                result_type: ResultType::NdviMap,
+                data: ResultData::GridData {
+                    width,
+                    height,
+                    values: values.into(),
+                    bounds: (0.0, 0.0, 1.0, 1.0),
+                    units: "NDVI".to_string(),
+                },
+                statistics: AnalysisStatistics::default(),
+                visualizations: Vec::new(),
+                recommendations: Vec::new(),
+                evidence_refs: Vec::new(),
+                uncertainty: None,
+                created_at: Utc::now(),
+            };
+            service.spill_large_grid(&mut result);
+            service.retain_analysis_result(&result).await.unwrap();
+            result.id
+        };
+
+        // The retained record points at the grid file instead of inlining it.
+        let record_path = temp_dir
+            .path()
+            .join("analysis_results")
+            .join(format!("{result_id}.json"));
+        let grid_path = temp_dir
+            .path()
+            .join("grids")
+            .join(format!("{result_id}.grid"));
+        assert!(fs::metadata(&record_path).unwrap().len() < 4096);
+        assert_eq!(fs::metadata(&grid_path).unwrap().len(), 16 + grid_bytes);
+
+        let restarted = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let result = restarted.get_result(&result_id).await.unwrap();
+        let ResultData::GridData {
+            values: GridValues::Mapped(mapped),
+            ..
+        } = &result.data
+        else {
+            panic!("retained result is a mapped grid");
+        };
+        assert_eq!(mapped.path(), grid_path);
+        assert_eq!(value_at(&result.data, 0, 0), Some(0.0));
+        assert_eq!(
+            value_at(&result.data, width - 1, height - 1),
+            Some((width * height - 1) as f32)
+        );
+        assert_eq!(value_at(&result.data, width, 0), None);
+        for (x, y) in [(0, 0), (4000, 2000), (width - 256, height - 256)] {
+            let window = GridWindow {
+                x,
+                y,
+                width: 256,
+                height: 256,
+            };
+            let cells = extract_window(&result.data, window).unwrap();
+            assert_eq!(cells.len(), 256 * 256);
+            assert_eq!(cells[0], (y * width + x) as f32);
+            assert_eq!(cells[256 * 256 - 1], ((y + 255) * width + x + 255) as f32);
+        }
+        // Windows read through the mapping, so a cell rewritten in the grid
+        // file shows up without reloading the result.
+        use std::os::unix::fs::FileExt;
+        let (x, y) = (4000, 2000);
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&grid_path)
+            .unwrap()
+            .write_all_at(&(-1.0_f32).to_le_bytes(), 16 + u64::from(y * width + x) * 4)
+            .unwrap();
+        let window = GridWindow {
+            x,
+            y,
+            width: 1,
+            height: 1,
+        };
+        assert_eq!(extract_window(&result.data, window), Some(vec![-1.0]));
+
+        assert_eq!(restarted.cleanup_old_results(0).await.unwrap(), 0);
+        assert!(!grid_path.exists());
+    }
+
+    #[tokio::test]
+    async fn prioritized_ndvi_jobs_publish_partials_before_the_final_result() {
+        let temp_dir = tempdir().unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let mut events = service.subscribe_partial_results();
+        let request = ndvi_analysis::NdviAnalysisRequest {
+            id: Uuid::new_v4(),
+            red_band_data: vec![100; 16],
+            nir_band_data: vec![400; 16],
+            image_width: 4,
+            image_height: 4,
+            georeference_info: ndvi_analysis::GeoreferenceInfo {
+                top_left_lat: 0.004,
+                top_left_lon: 0.0,
+                bottom_right_lat: 0.0,
+                bottom_right_lon: 0.004,
+                pixel_size_m: 2.0,
+                coordinate_system: "EPSG:4326".to_string(),
+            },
+            capture_time: Utc::now(),
+            quality_mask: None,
+        };
+        let mut job = ndvi_job(temp_dir.path());
+        job.parameters.custom_parameters.extend([
+            (ROI_BLOCK_SIZE_KEY.to_string(), json!(2)),
+            (
+                PRIORITIZED_ROIS_KEY.to_string(),
+                json!([{
+                    "id": "corner",
+                    "boundary": [[0.0031, 0.0], [0.004, 0.0], [0.004, 0.0009]],
+                }]),
+            ),
+            (
+                NDVI_REQUEST_PAYLOAD_KEY.to_string(),
+                serde_json::to_value(&request).unwrap(),
+            ),
+        ]);
+        let job_id = service.submit_job(job).await.unwrap();
+
+        let result = service
+            .process_next_job()
+            .await
+            .expect("prioritized NDVI runs")
+            .expect("a job was queued");
+
+        let partials: Vec<PartialGridResult> = std::iter::from_fn(|| events.try_recv().ok())
+            .inspect(|event| assert_eq!(event.job_id, job_id))
+            .map(|event| event.partial)
+            .collect();
+        assert_eq!(
+            partials
+                .iter()
+                .map(|partial| partial.completeness)
+                .collect::<Vec<_>>(),
+            vec![0.25, 0.5, 0.75, 1.0]
+        );
+        assert!(partials[0].rois_complete);
+        assert!(service
+            .latest_partial_result(&job_id)
+            .expect("latest partial is kept")
+            .is_complete());
+        assert_eq!(result.job_id, job_id);
+        assert_eq!(result.statistics.valid_pixel_count, 16);
+        assert_eq!(result.statistics.coverage_area_m2, 64.0);
+        assert!(service.get_result(&result.id).await.is_some());
+        assert_eq!(service.job_artifacts(&job_id).unwrap().artifacts.len(), 2);
+
+        let mut missing_payload = ndvi_job(temp_dir.path());
+        missing_payload
+            .parameters
+            .custom_parameters
+            .insert(PRIORITIZED_ROIS_KEY.to_string(), json!([]));
+        let error = service.submit_job(missing_payload).await.unwrap_err();
+        assert!(error.to_string().contains(NDVI_REQUEST_PAYLOAD_KEY));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn submissions_stay_fast_while_a_slow_job_is_processing() {
+        let temp_dir = tempdir().unwrap();
+        let gate = std::sync::Arc::new(tokio::sync::Semaphore::new(0));
+        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        service.processing_gate = Some(gate.clone());
+        let service = std::sync::Arc::new(service);
+
+        let slow_job = service.submit_job(ndvi_job(temp_dir.path())).await.unwrap();
+        let worker = tokio::spawn({
+            let service = service.clone();
+            async move { service.process_next_job().await }
+        });
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while service.get_job_status(&slow_job).await.unwrap().status != JobStatus::Processing {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("slow job never started");
+
+        let started = std::time::Instant::now();
+        for _ in 0..100 {
+            service.submit_job(ndvi_job(temp_dir.path())).await.unwrap();
+        }
+        let elapsed = started.elapsed();
+
+        assert!(
+            elapsed < std::time::Duration::from_secs(1),
+            "100 submissions took {elapsed:?} behind a running job"
+        );
+        assert!(!worker.is_finished());
+        assert_eq!(service.list_jobs(Some(JobStatus::Queued)).await.len(), 100);
+        assert_eq!(
+            service.get_job_status(&slow_job).await.unwrap().status,
+            JobStatus::Processing
+        );
+
+        gate.add_permits(1);
+        let result = worker.await.unwrap().unwrap().unwrap();
+        assert_eq!(result.job_id, slow_job);
+        assert_eq!(
+            service.get_job_status(&slow_job).await.unwrap().status,
+            JobStatus::Completed
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_submit_cancel_and_status_calls_do_not_deadlock() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let temp_dir = tempdir().unwrap();
+        let output_directory = temp_dir.path().to_path_buf();
+        let service = Arc::new(PostProcessorService::new(output_directory.clone()).unwrap());
+        let clients_done = Arc::new(AtomicBool::new(false));
+
+        let stress = async {
+            let workers: Vec<_> = (0..3)
+                .map(|_| {
+                    let service = service.clone();
+                    let clients_done = clients_done.clone();
+                    tokio::spawn(async move {
+                        loop {
+                            if service.process_next_job().await.unwrap().is_none() {
+                                if clients_done.load(Ordering::SeqCst)
+                                    && service.list_jobs(Some(JobStatus::Queued)).await.is_empty()
+                                {
+                                    break;
+                                }
+                                tokio::task::yield_now().await;
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            let clients: Vec<_> = (0..8)
+                .map(|client| {
+                    let service = service.clone();
+                    let output_directory = output_directory.clone();
+                    tokio::spawn(async move {
+                        let mut submitted = Vec::new();
+                        let mut cancelled = Vec::new();
+                        for round in 0..50 {
+                            let job_id = service
+                                .submit_job(ndvi_job(&output_directory))
+                                .await
+                                .unwrap();
+                            submitted.push(job_id);
+                            assert!(service.get_job_status(&job_id).await.is_some());
+                            // Cancel a fresh job and one submitted a few rounds
+                            // ago, which a worker may be running by now.
+                            for candidate in [Some(job_id), submitted.iter().rev().nth(3).copied()]
+                                .into_iter()
+                                .flatten()
+                                .filter(|_| (client + round) % 3 == 0)
+                            {
+                                match service.cancel_job(&candidate) {
+                                    Ok(()) => cancelled.push(candidate),
+                                    Err(AnalysisJobError::AlreadyFinished { .. }) => {}
+                                    Err(error) => panic!("unexpected cancel error: {error}"),
+                                }
+                            }
+                            service.list_jobs(None).await;
+                            tokio::task::yield_now().await;
+                        }
+                        (submitted, cancelled)
+                    })
+                })
+                .collect();
+
+            let mut submitted = Vec::new();
+            let mut cancelled = Vec::new();
+            for client in clients {
+                let (client_submitted, client_cancelled) = client.await.unwrap();
+                submitted.extend(client_submitted);
+                cancelled.extend(client_cancelled);
+            }
+            // A job still running three rounds later accepts a second cancel.
+            cancelled.sort();
+            cancelled.dedup();
+            clients_done.store(true, Ordering::SeqCst);
+            for worker in workers {
+                worker.await.unwrap();
+            }
+            (submitted, cancelled)
+        };
+        let (submitted, cancelled) =
+            tokio::time::timeout(std::time::Duration::from_secs(30), stress)
+                .await
+                .expect("submit/cancel/status stress deadlocked");
+
+        assert_eq!(submitted.len(), 400);
+        assert!(!cancelled.is_empty());
+        let mut completed = 0;
+        for job_id in &submitted {
+            match service.get_job_status(job_id).await.unwrap().status {
+                JobStatus::Completed => completed += 1,
+                JobStatus::Cancelled => {}
+                status => panic!("job {job_id} ended as {status:?}"),
+            }
+        }
+        for job_id in &cancelled {
+            assert_eq!(
+                service.get_job_status(job_id).await.unwrap().status,
+                JobStatus::Cancelled
+            );
+        }
+        assert_eq!(completed + cancelled.len(), submitted.len());
+        assert_eq!(read(&service.results_cache).len(), completed);
+        let jobs = lock(&service.jobs);
+        assert!(jobs.queue.is_empty() && jobs.running.is_empty());
+        assert!(jobs.cancel_requested.is_empty());
+    }
+
+    /// Writes half of the first `failures` statistics files it sees, then
+    /// fails as a full disk would.
+    struct FailingStatisticsSink {
+        failures: std::sync::atomic::AtomicUsize,
+    }
+
+    impl ArtifactSink for FailingStatisticsSink {
+        fn write(&self, path: &Path, contents: &[u8]) -> std::io::Result<()> {
+            use std::sync::atomic::Ordering;
+
+            let is_statistics = path.file_name() == Some("statistics.json".as_ref());
+            if is_statistics
+                && self
+                    .failures
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                        left.checked_sub(1)
+                    })
+                    .is_ok()
+            {
+                fs::write(path, &contents[..contents.len() / 2])?;
+                return Err(std::io::Error::other("no space left on device"));
+            }
+            fs::write(path, contents)
+        }
+    }
+
+    fn files_under(directory: &Path) -> BTreeSet<PathBuf> {
+        let mut files = BTreeSet::new();
+        for entry in fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                files.extend(files_under(&path));
+            } else {
+                files.insert(path);
+            }
+        }
+        files
+    }
+
+    #[tokio::test]
+    async fn completed_job_writes_an_output_manifest_with_checksums() {
+        let temp_dir = tempdir().unwrap();
+        let output_directory = temp_dir.path().join("outputs");
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let mut job = ndvi_job(&output_directory);
+        job.input_files = ["north.json", "south.json"]
+            .map(|name| write_image_metadata(&temp_dir.path().join("flight").join(name)))
+            .to_vec();
+        let sources = job
+            .input_files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+        let job_id = service.submit_job(job).await.unwrap();
+        service.process_next_job().await.unwrap().unwrap();
+
+        let job_directory = output_directory.join(job_id.to_string());
+        let manifest = shared::OutputManifest::read(&job_directory).unwrap();
+        assert_eq!(manifest.producer, "post_processor");
+        let written: Vec<String> = files_under(&job_directory)
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .filter(|name| name != shared::OUTPUT_MANIFEST_FILE_NAME)
+            .collect();
+        let listed: Vec<String> = manifest
+            .files
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect();
+        assert_eq!(listed, written);
+        assert_eq!(listed, ["ndvi_grid.csv", "statistics.json"]);
+        for entry in &manifest.files {
+            let contents = fs::read(job_directory.join(&entry.path)).unwrap();
+            assert_eq!(entry.size_bytes, contents.len() as u64);
+            assert_eq!(
+                format!("sha256:{}", entry.sha256),
+                artifacts::content_hash(&contents)
+            );
+            assert_eq!(entry.source_inputs, sources);
+        }
+        assert!(manifest.verify(&job_directory).is_empty());
+    }
+
+    #[tokio::test]
+    async fn retried_job_leaves_exactly_its_manifest_on_disk() {
+        let temp_dir = tempdir().unwrap();
+        let output_directory = temp_dir.path().join("outputs");
+        let mut service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        service.set_artifact_sink(Arc::new(FailingStatisticsSink {
+            failures: std::sync::atomic::AtomicUsize::new(1),
+        }));
+
+        let job_id = service
+            .submit_job(ndvi_job(&output_directory))
+            .await
+            .unwrap();
+        assert!(service.process_next_job().await.unwrap().is_none());
+        assert_eq!(
+            service.get_job_status(&job_id).await.unwrap().status,
+            JobStatus::Failed
+        );
+        assert!(service.job_artifacts(&job_id).is_none());
+        assert!(files_under(&output_directory).is_empty());
+        assert_eq!(fs::read_dir(&output_directory).unwrap().count(), 0);
+
+        service.retry_job(&job_id).unwrap();
+        let result = service.process_next_job().await.unwrap().unwrap();
+        assert_eq!(result.job_id, job_id);
+        let manifest = service.job_artifacts(&job_id).unwrap();
+        assert_eq!(
+            manifest.directory,
+            output_directory.join(job_id.to_string())
+        );
+        let mut manifest_files: BTreeSet<PathBuf> = manifest
+            .artifacts
+            .iter()
+            .map(|artifact| artifact.path.clone())
+            .collect();
+        manifest_files.insert(manifest.directory.join(shared::OUTPUT_MANIFEST_FILE_NAME));
+        assert_eq!(files_under(&output_directory), manifest_files);
+        assert_eq!(fs::read_dir(&output_directory).unwrap().count(), 1);
+        for artifact in &manifest.artifacts {
+            let contents = fs::read(&artifact.path).unwrap();
+            assert_eq!(artifact.bytes, contents.len() as u64);
+            assert_eq!(artifact.content_hash, artifacts::content_hash(&contents));
+        }
+        assert!(matches!(
+            service.retry_job(&job_id),
+            Err(AnalysisJobError::NotRetryable {
+                status: JobStatus::Completed,
+                ..
+            })
+        ));
+
+        service.cleanup_old_results(0).await.unwrap();
+        assert!(service.job_artifacts(&job_id).is_none());
+        assert!(!manifest.directory.exists());
+    }
+
+    /// Writes multispectral image metadata, the input of an NDVI job.
+    fn write_image_metadata(path: &Path) -> PathBuf {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let metadata = json!({
+            "metadata": {
+                "timestamp": "2026-05-01T10:00:00Z",
+                "gps_position": null,
+                "bands": ["Red", "NIR"],
+                "exposure_time": 0.01,
+                "gain": 1.0,
+                "width": 2,
+                "height": 2
+            },
+            "file_paths": { "Red": "red.tif", "NIR": "nir.tif" },
+            "image_id": Uuid::new_v4()
+        });
+        fs::write(path, metadata.to_string()).unwrap();
+        path.to_path_buf()
+    }
+
+    fn ndvi_job(output_directory: &Path) -> ProcessingJob {
+        ProcessingJob {
+            id: Uuid::nil(),
+            job_type: JobType::NdviAnalysis,
+            input_files: vec![],
+            output_directory: output_directory.to_path_buf(),
+            parameters: ProcessingParameters::default(),
+            status: JobStatus::Queued,
+            created_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+            error_message: None,
+        }
+    }
+
     fn analysis_job_request(output_directory: &std::path::Path) -> AnalysisJobRequest {
         AnalysisJobRequest {
             org_id: "org-a".to_string(),
@@ -2662,6 +4217,28 @@ mod tests {
         }
     }
 
+    fn ndvi_change_grid(offset_x: f64, values: Vec<f32>) -> ProductGrid {
+        let min_x = 500000.0 + offset_x;
+        ProductGrid {
+            width: 2,
+            height: 2,
+            nodata_mask: vec![false; values.len()],
+            values,
+            spatial_ref: RasterSpatialRef {
+                georeferenced: true,
+                crs: Some("EPSG:32614".to_string()),
+                bbox: Some(GeoBounds {
+                    min_lon: min_x,
+                    min_lat: 4500000.0,
+                    max_lon: min_x + 20.0,
+                    max_lat: 4500020.0,
+                }),
+                geo_transform: Some([min_x, 10.0, 0.0, 4500020.0, 0.0, -10.0]),
+                resolution: Some(RasterResolution { x: 10.0, y: 10.0 }),
+            },
+        }
+    }
+
     fn trend_request() -> IndexTrendRequest {
         IndexTrendRequest {
             snapshots: vec![
@@ -3003,4 +4580,55 @@ mod tests {
             .expect("scene persists");
         catalog
     }
+
+    #[test]
+    fn imported_grid_csv_fills_missing_and_ragged_rows_with_nan() {
+        let temp_dir = tempdir().unwrap();
+        let service = PostProcessorService::new(temp_dir.path().to_path_buf()).unwrap();
+        let csv_path = temp_dir.path().join("ndvi.csv");
+        fs::write(
+            &csv_path,
+            "x,y,value\n\
+             0.5,1.5,0.2\n\
+             1.5,1.5,0.4\n\
+             2.5,1.5,0.6\n\
+             0.5,0.5,0.8\n\
+             1.5,0.5\n",
+        )
+        .unwrap();
+
+        let result = service
+            .import_grid_csv(&csv_path, (0.0, 0.0, 3.0, 2.0), 1.0)
+            .unwrap();
+
+        let ResultData::GridData {
+            width,
+            height,
+            values,
+            ..
+        } = &result.data
+        else {
+            panic!("imported CSV should be grid data");
+        };
+        assert_eq!((*width, *height), (3, 2));
+        assert_eq!(&values[..4], &[0.2, 0.4, 0.6, 0.8]);
+        assert!(values[4].is_nan() && values[5].is_nan());
+        assert!((result.statistics.mean_value - 0.5).abs() < 1e-6);
+        assert_eq!(result.statistics.valid_pixel_count, 4);
+        assert_eq!(result.statistics.total_pixel_count, 6);
+        assert!(read(&service.results_cache).contains_key(&result.id));
+
+        fs::write(&csv_path, "0.5,1.5,0.2\n9.0,1.5,0.4\n").unwrap();
+        let error = service
+            .import_grid_csv(&csv_path, (0.0, 0.0, 3.0, 2.0), 1.0)
+            .unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<GridCsvError>(),
+            Some(&GridCsvError::OutOfBounds {
+                line: 2,
+                x: 9.0,
+                y: 1.5
+            })
+        );
+    }
 }
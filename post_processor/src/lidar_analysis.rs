@@ -1,3 +1,4 @@
+use crate::zonal_statistics::{compute_statistics, DEFAULT_PERCENTILES};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -459,54 +460,60 @@ impl LidarAnalysisProcessor {
 
     // Compatibility method for the post processor service
     pub async fn analyze(
-        &mut self,
+        &self,
         input_files: &[std::path::PathBuf],
         _parameters: &super::ProcessingParameters,
-    ) -> anyhow::Result<super::AnalysisResult> {
+        mut artifacts: super::ArtifactWriter,
+        progress: &super::JobProgress,
+    ) -> anyhow::Result<(super::AnalysisResult, super::ArtifactManifest)> {
         use chrono::Utc;
-        use std::collections::HashMap;
         use uuid::Uuid;
 
         // Convert input files to strings
-        let _file_paths: Vec<String> = input_files
-            .iter()
-            .map(|p| p.to_string_lossy().to_string())
-            .collect();
+        let mut _file_paths = Vec::with_capacity(input_files.len());
+        for (index, path) in input_files.iter().enumerate() {
+            _file_paths.push(path.to_string_lossy().to_string());
+            progress.report_steps(index + 1, input_files.len(), 0.0..0.6);
+        }
 
-        // Create mock percentiles
-        let mut percentiles = HashMap::new();
-        percentiles.insert("25".to_string(), 2.5);
-        percentiles.insert("50".to_string(), 5.0);
-        percentiles.insert("75".to_string(), 7.5);
+        let values = vec![5.0; 10000]; // Mock elevation values
+        let statistics = super::AnalysisStatistics {
+            coverage_area_m2: 10000.0,
+            ..compute_statistics(&values, &DEFAULT_PERCENTILES)
+        };
 
         // Create a basic analysis result
-        Ok(super::AnalysisResult {
+        let result = super::AnalysisResult {
             id: Uuid::new_v4(),
             job_id: Uuid::new_v4(),
             result_type: super::ResultType::ElevationModel,
             data: super::ResultData::GridData {
                 width: 100,
                 height: 100,
-                values: vec![5.0; 10000], // Mock elevation values
+                values: values.into(),
                 bounds: (-74.0, 40.0, -73.9, 40.1),
                 units: "meters".to_string(),
             },
-            statistics: super::AnalysisStatistics {
-                min_value: 0.0,
-                max_value: 10.0,
-                mean_value: 5.0,
-                std_deviation: 2.0,
-                percentiles,
-                coverage_area_m2: 10000.0,
-                valid_pixel_count: 10000,
-                total_pixel_count: 10000,
-            },
+            statistics,
             visualizations: vec![],
             recommendations: vec![],
             evidence_refs: vec![],
             uncertainty: None,
             created_at: Utc::now(),
-        })
+        };
+
+        artifacts.write(
+            super::ArtifactKind::ElevationGrid,
+            &super::artifacts::grid_csv(&result.data),
+        )?;
+        progress.report(0.8);
+        artifacts.write(
+            super::ArtifactKind::Statistics,
+            &serde_json::to_vec_pretty(&result.statistics)?,
+        )?;
+        let manifest = artifacts.commit()?;
+        progress.finish();
+        Ok((result, manifest))
     }
 }
 
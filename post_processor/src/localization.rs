@@ -0,0 +1,428 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Locale every message falls back to; its catalog must hold every key.
+pub const DEFAULT_LOCALE: &str = "en";
+/// Directory under the service working directory whose `*.json` catalogs
+/// extend or override the built-in ones.
+pub const LOCALES_DIR: &str = "locales";
+
+const BUILTIN_CATALOGS: [&str; 2] = [
+    include_str!("../locales/en.json"),
+    include_str!("../locales/es.json"),
+];
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum LocalizationError {
+    #[error("catalog has an empty locale code")]
+    EmptyLocale,
+    #[error("catalog `{locale}` needs a decimal separator distinct from its grouping separator")]
+    InvalidSeparators { locale: String },
+    #[error("failed to load message catalog: {reason}")]
+    Load { reason: String },
+}
+
+/// Messages for one locale. Templates name their arguments in braces, e.g.
+/// `"Low vigor in zone {zone_id}"`; unknown placeholders are kept verbatim.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocaleCatalog {
+    pub locale: String,
+    pub decimal_separator: String,
+    /// Inserted between groups of three integer digits; empty disables
+    /// grouping.
+    #[serde(default)]
+    pub grouping_separator: String,
+    #[serde(default)]
+    pub messages: BTreeMap<String, String>,
+}
+
+impl LocaleCatalog {
+    pub fn from_json(content: &[u8]) -> Result<Self, LocalizationError> {
+        let catalog = serde_json::from_slice::<Self>(content).map_err(load_error)?;
+        catalog.validate()?;
+        Ok(catalog)
+    }
+
+    pub fn validate(&self) -> Result<(), LocalizationError> {
+        if self.locale.trim().is_empty() {
+            return Err(LocalizationError::EmptyLocale);
+        }
+        if self.decimal_separator.is_empty() || self.decimal_separator == self.grouping_separator {
+            return Err(LocalizationError::InvalidSeparators {
+                locale: self.locale.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// `value` rounded to `decimals` places with this locale's separators.
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let formatted = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = formatted
+            .split_once('.')
+            .unwrap_or((formatted.as_str(), ""));
+
+        let mut rendered = String::with_capacity(formatted.len() + integer.len() / 3);
+        if value.is_sign_negative() && formatted.bytes().any(|digit| matches!(digit, b'1'..=b'9')) {
+            rendered.push('-');
+        }
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index).is_multiple_of(3) {
+                rendered.push_str(&self.grouping_separator);
+            }
+            rendered.push(digit);
+        }
+        if !fraction.is_empty() {
+            rendered.push_str(&self.decimal_separator);
+            rendered.push_str(fraction);
+        }
+        rendered
+    }
+}
+
+/// Value substituted for a named placeholder.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessageArg {
+    Text(String),
+    /// Formatted with the separators of the locale being rendered.
+    Number {
+        value: f64,
+        decimals: usize,
+    },
+}
+
+impl MessageArg {
+    pub fn number(value: impl Into<f64>, decimals: usize) -> Self {
+        Self::Number {
+            value: value.into(),
+            decimals,
+        }
+    }
+
+    /// Locale-neutral rendering, used where no catalog is involved.
+    pub fn plain(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Number { value, decimals } => format!("{:.*}", decimals, value),
+        }
+    }
+}
+
+/// Why a message was not rendered from the requested locale.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LocalizationWarning {
+    /// No catalog for the locale; everything was rendered in English.
+    MissingLocale { locale: String },
+    /// The locale's catalog lacks the key; the English text was used.
+    MissingKey { locale: String, key: String },
+}
+
+impl fmt::Display for LocalizationWarning {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingLocale { locale } => {
+                write!(formatter, "no message catalog for locale `{locale}`")
+            }
+            Self::MissingKey { locale, key } => {
+                write!(formatter, "locale `{locale}` has no message `{key}`")
+            }
+        }
+    }
+}
+
+/// Message catalogs by locale code.
+#[derive(Debug, Clone)]
+pub struct Localizer {
+    catalogs: HashMap<String, LocaleCatalog>,
+}
+
+impl Default for Localizer {
+    /// The English and Spanish catalogs shipped with the crate.
+    fn default() -> Self {
+        let mut localizer = Self {
+            catalogs: HashMap::new(),
+        };
+        for content in BUILTIN_CATALOGS {
+            let catalog =
+                LocaleCatalog::from_json(content.as_bytes()).expect("built-in catalog is valid");
+            localizer.add_catalog(catalog);
+        }
+        localizer
+    }
+}
+
+impl Localizer {
+    /// Built-in catalogs extended by every `*.json` file in
+    /// `directory/`[`LOCALES_DIR`]; the directory is optional.
+    pub fn load(directory: &Path) -> Result<Self, LocalizationError> {
+        let mut localizer = Self::default();
+        let locales_dir = directory.join(LOCALES_DIR);
+        if !locales_dir.is_dir() {
+            return Ok(localizer);
+        }
+        let mut paths = fs::read_dir(&locales_dir)
+            .map_err(load_error)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(load_error)?;
+        paths.retain(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        });
+        paths.sort();
+        for path in paths {
+            let content = fs::read(&path).map_err(load_error)?;
+            let catalog = LocaleCatalog::from_json(&content).map_err(|error| match error {
+                LocalizationError::Load { reason } => LocalizationError::Load {
+                    reason: format!("{}: {reason}", path.display()),
+                },
+                other => other,
+            })?;
+            localizer.add_catalog(catalog);
+        }
+        Ok(localizer)
+    }
+
+    /// Adds `catalog`, or merges it into the catalog already held for its
+    /// locale: its messages and separators win.
+    pub fn add_catalog(&mut self, catalog: LocaleCatalog) {
+        match self.catalogs.get_mut(&catalog.locale) {
+            Some(existing) => {
+                existing.decimal_separator = catalog.decimal_separator;
+                existing.grouping_separator = catalog.grouping_separator;
+                existing.messages.extend(catalog.messages);
+            }
+            None => {
+                self.catalogs.insert(catalog.locale.clone(), catalog);
+            }
+        }
+    }
+
+    pub fn locales(&self) -> Vec<&str> {
+        let mut locales = self.catalogs.keys().map(String::as_str).collect::<Vec<_>>();
+        locales.sort_unstable();
+        locales
+    }
+
+    /// Renders messages for `locale`, falling back to English (with a
+    /// recorded warning) when the locale is unknown.
+    pub fn formatter(&self, locale: &str) -> MessageFormatter<'_> {
+        let mut warnings = Vec::new();
+        let catalog = match self.catalogs.get(locale) {
+            Some(catalog) => catalog,
+            None => {
+                warnings.push(LocalizationWarning::MissingLocale {
+                    locale: locale.to_string(),
+                });
+                self.default_catalog()
+            }
+        };
+        MessageFormatter {
+            localizer: self,
+            catalog,
+            warnings,
+        }
+    }
+
+    /// Catalogs a formatter for `locale` reads from: the locale's own, when
+    /// there is one, and the English fallback.
+    pub fn catalogs_for(&self, locale: &str) -> Vec<&LocaleCatalog> {
+        let mut catalogs = self.catalogs.get(locale).into_iter().collect::<Vec<_>>();
+        if locale != DEFAULT_LOCALE {
+            catalogs.push(self.default_catalog());
+        }
+        catalogs
+    }
+
+    fn default_catalog(&self) -> &LocaleCatalog {
+        self.catalogs
+            .get(DEFAULT_LOCALE)
+            .expect("the default locale catalog is built in")
+    }
+}
+
+/// Renders messages for one locale and collects the fallbacks it took.
+#[derive(Debug)]
+pub struct MessageFormatter<'a> {
+    localizer: &'a Localizer,
+    catalog: &'a LocaleCatalog,
+    warnings: Vec<LocalizationWarning>,
+}
+
+impl MessageFormatter<'_> {
+    /// Locale the messages are rendered in after any locale fallback.
+    pub fn locale(&self) -> &str {
+        &self.catalog.locale
+    }
+
+    /// Whether the default catalog defines `key`, i.e. whether it is a
+    /// message key at all rather than free text.
+    pub fn knows(&self, key: &str) -> bool {
+        self.localizer.default_catalog().messages.contains_key(key)
+    }
+
+    /// `key` rendered with `args`. A key missing from the locale falls back
+    /// to the English text and records a warning; `None` when English lacks
+    /// it too.
+    pub fn message(
+        &mut self,
+        key: &str,
+        args: impl Fn(&str) -> Option<MessageArg>,
+    ) -> Option<String> {
+        let template = match self.catalog.messages.get(key) {
+            Some(template) => template,
+            None => {
+                let warning = LocalizationWarning::MissingKey {
+                    locale: self.catalog.locale.clone(),
+                    key: key.to_string(),
+                };
+                if !self.warnings.contains(&warning) {
+                    self.warnings.push(warning);
+                }
+                self.localizer.default_catalog().messages.get(key)?
+            }
+        };
+        Some(fill_placeholders(template, |name| {
+            args(name).map(|arg| match arg {
+                MessageArg::Text(text) => text,
+                MessageArg::Number { value, decimals } => {
+                    self.catalog.format_number(value, decimals)
+                }
+            })
+        }))
+    }
+
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        self.catalog.format_number(value, decimals)
+    }
+
+    pub fn warnings(&self) -> &[LocalizationWarning] {
+        &self.warnings
+    }
+
+    pub fn into_warnings(self) -> Vec<LocalizationWarning> {
+        self.warnings
+    }
+}
+
+/// Replaces every `{name}` in `text` for which `value` returns a string;
+/// other placeholders and an unterminated `{` are kept verbatim.
+pub(crate) fn fill_placeholders(text: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let Some(length) = rest[start + 1..].find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let name = &rest[start + 1..start + 1 + length];
+        match value(name) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[start..start + length + 2]),
+        }
+        rest = &rest[start + length + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+fn load_error(error: impl fmt::Display) -> LocalizationError {
+    LocalizationError::Load {
+        reason: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_use_the_locale_separators() {
+        let localizer = Localizer::default();
+        let english = localizer.formatter("en");
+        let spanish = localizer.formatter("es");
+
+        assert_eq!(english.format_number(1_234_567.891, 2), "1,234,567.89");
+        assert_eq!(spanish.format_number(1_234_567.891, 2), "1.234.567,89");
+        assert_eq!(english.format_number(12_500.0, 0), "12,500");
+        assert_eq!(spanish.format_number(0.325, 3), "0,325");
+        assert_eq!(english.format_number(-999.6, 0), "-1,000");
+        assert_eq!(english.format_number(-0.0001, 2), "0.00");
+        assert_eq!(english.format_number(f64::NAN, 2), "NaN");
+    }
+
+    #[test]
+    fn missing_keys_and_locales_fall_back_to_english_with_warnings() {
+        let mut localizer = Localizer::default();
+        localizer.add_catalog(LocaleCatalog {
+            locale: "fr".to_string(),
+            decimal_separator: ",".to_string(),
+            grouping_separator: " ".to_string(),
+            messages: BTreeMap::from([("priority.high".to_string(), "Haute".to_string())]),
+        });
+
+        let mut french = localizer.formatter("fr");
+        let area = |name: &str| (name == "area_m2").then(|| MessageArg::number(12_500.0, 0));
+        assert_eq!(french.message("priority.high", area).unwrap(), "Haute");
+        assert_eq!(
+            french
+                .message("report.recommendation.affected_area", area)
+                .unwrap(),
+            "Affected area: 12 500 m²"
+        );
+        assert_eq!(french.message("no.such.key", area), None);
+        assert_eq!(
+            french.into_warnings(),
+            vec![
+                LocalizationWarning::MissingKey {
+                    locale: "fr".to_string(),
+                    key: "report.recommendation.affected_area".to_string(),
+                },
+                LocalizationWarning::MissingKey {
+                    locale: "fr".to_string(),
+                    key: "no.such.key".to_string(),
+                },
+            ]
+        );
+
+        let mut german = localizer.formatter("de");
+        assert_eq!(german.locale(), "en");
+        assert_eq!(german.message("priority.low", area).unwrap(), "Low");
+        assert_eq!(
+            german.warnings(),
+            [LocalizationWarning::MissingLocale {
+                locale: "de".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn catalogs_in_the_working_directory_override_built_in_messages() {
+        let directory = tempfile::tempdir().unwrap();
+        fs::create_dir(directory.path().join(LOCALES_DIR)).unwrap();
+        fs::write(
+            directory.path().join(LOCALES_DIR).join("es.json"),
+            r#"{"locale": "es", "decimal_separator": ",", "grouping_separator": " ",
+                "messages": {"priority.high": "Urgente"}}"#,
+        )
+        .unwrap();
+
+        let localizer = Localizer::load(directory.path()).unwrap();
+        let mut spanish = localizer.formatter("es");
+        assert_eq!(
+            spanish.message("priority.high", |_| None).unwrap(),
+            "Urgente"
+        );
+        assert_eq!(spanish.message("priority.low", |_| None).unwrap(), "Baja");
+        assert_eq!(spanish.format_number(12_500.0, 0), "12 500");
+        assert!(spanish.warnings().is_empty());
+    }
+}
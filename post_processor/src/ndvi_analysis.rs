@@ -1,3 +1,7 @@
+use crate::roi_processing::{
+    process_prioritized, BlockSchedule, BlockWindow, GridGeometry, PartialGridResult,
+};
+use crate::zonal_statistics::{compute_statistics, DEFAULT_PERCENTILES};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -280,6 +284,55 @@ impl NdviAnalysisProcessor {
         Ok(())
     }
 
+    /// Computes the filtered NDVI map block by block, ROI blocks first, and
+    /// reports each partial grid. The finished grid equals the one produced by
+    /// `process_ndvi_request`, since every step is per-pixel.
+    pub fn process_ndvi_prioritized(
+        &self,
+        request: &NdviAnalysisRequest,
+        parameters: &super::ProcessingParameters,
+        on_partial: impl FnMut(&PartialGridResult),
+    ) -> Result<PartialGridResult> {
+        self.validate_input_data(request)?;
+        let georeference = &request.georeference_info;
+        let geometry = GridGeometry::from_corners(
+            request.image_width,
+            request.image_height,
+            (georeference.top_left_lat, georeference.top_left_lon),
+            (georeference.bottom_right_lat, georeference.bottom_right_lon),
+        )
+        .with_pixel_size_m(georeference.pixel_size_m);
+        let schedule = BlockSchedule::from_parameters(geometry, parameters)?;
+
+        process_prioritized(
+            schedule,
+            "NDVI",
+            |value| value >= 0.0,
+            |window| self.ndvi_block(request, window),
+            on_partial,
+        )
+    }
+
+    fn ndvi_block(&self, request: &NdviAnalysisRequest, window: BlockWindow) -> Result<Vec<f32>> {
+        let indices: Vec<usize> = window.pixel_indices(request.image_width).collect();
+        let red: Vec<u16> = indices.iter().map(|&i| request.red_band_data[i]).collect();
+        let nir: Vec<u16> = indices.iter().map(|&i| request.nir_band_data[i]).collect();
+        let mut ndvi = self.calculate_ndvi(&red, &nir)?;
+
+        if let Some(quality_mask) = &request.quality_mask {
+            for (value, index) in ndvi.iter_mut().zip(&indices) {
+                if quality_mask.get(*index) == Some(&0) {
+                    *value = -1.0; // Mark as invalid
+                }
+            }
+        }
+        if self.config.enable_shadow_detection {
+            self.filter_shadows(&mut ndvi)?;
+        }
+
+        Ok(ndvi)
+    }
+
     fn extract_vegetation_indices(
         &self,
         ndvi_map: &[f32],
@@ -513,54 +566,60 @@ impl NdviAnalysisProcessor {
 
     // Compatibility method for the post processor service
     pub async fn analyze(
-        &mut self,
+        &self,
         input_files: &[std::path::PathBuf],
         parameters: &super::ProcessingParameters,
-    ) -> anyhow::Result<super::AnalysisResult> {
+        mut artifacts: super::ArtifactWriter,
+        progress: &super::JobProgress,
+    ) -> anyhow::Result<(super::AnalysisResult, super::ArtifactManifest)> {
         use chrono::Utc;
-        use std::collections::HashMap;
         use uuid::Uuid;
 
         // Convert input files to strings
-        let _file_paths: Vec<String> = input_files
-            .iter()
-            .map(|p| p.to_string_lossy().to_string())
-            .collect();
+        let mut _file_paths = Vec::with_capacity(input_files.len());
+        for (index, path) in input_files.iter().enumerate() {
+            _file_paths.push(path.to_string_lossy().to_string());
+            progress.report_steps(index + 1, input_files.len(), 0.0..0.6);
+        }
 
-        // Create mock percentiles
-        let mut percentiles = HashMap::new();
-        percentiles.insert("25".to_string(), 0.25);
-        percentiles.insert("50".to_string(), 0.50);
-        percentiles.insert("75".to_string(), 0.75);
+        let values = vec![0.5; 10000]; // Mock NDVI values
+        let statistics = super::AnalysisStatistics {
+            coverage_area_m2: 10000.0,
+            ..compute_statistics(&values, &DEFAULT_PERCENTILES)
+        };
 
         // Create a basic analysis result
-        Ok(super::AnalysisResult {
+        let result = super::AnalysisResult {
             id: Uuid::new_v4(),
             job_id: Uuid::new_v4(), // This should be passed from the caller
             result_type: super::ResultType::NdviMap,
             data: super::ResultData::GridData {
                 width: 100,
                 height: 100,
-                values: vec![0.5; 10000],           // Mock NDVI values
+                values: values.into(),
                 bounds: (-74.0, 40.0, -73.9, 40.1), // Mock bounds
                 units: "NDVI".to_string(),
             },
-            statistics: super::AnalysisStatistics {
-                min_value: 0.0,
-                max_value: 1.0,
-                mean_value: 0.5,
-                std_deviation: 0.2,
-                percentiles,
-                coverage_area_m2: 10000.0,
-                valid_pixel_count: 10000,
-                total_pixel_count: 10000,
-            },
+            statistics,
             visualizations: vec![],
             recommendations: vec![],
             evidence_refs: vec![],
             uncertainty: None,
             created_at: Utc::now(),
-        })
+        };
+
+        artifacts.write(
+            super::ArtifactKind::NdviGrid,
+            &super::artifacts::grid_csv(&result.data),
+        )?;
+        progress.report(0.8);
+        artifacts.write(
+            super::ArtifactKind::Statistics,
+            &serde_json::to_vec_pretty(&result.statistics)?,
+        )?;
+        let manifest = artifacts.commit()?;
+        progress.finish();
+        Ok((result, manifest))
     }
 }
 
@@ -580,7 +639,7 @@ mod tests {
             output_resolution: 1.0,
         };
 
-        let mut processor = NdviAnalysisProcessor::new(config);
+        let processor = NdviAnalysisProcessor::new(config);
 
         // Test NDVI calculation with sample data
         let red_data = vec![100, 150, 200]; // Lower values for healthy vegetation
@@ -0,0 +1,597 @@
+use crate::zonal_statistics::ProductGrid;
+use image::RgbaImage;
+use sensor_overlay_engine::utils::{create_heatmap_image_rgba, HeatmapClassBreaks, HeatmapOptions};
+use sensor_overlay_engine::RgbColor;
+use serde::{Deserialize, Serialize};
+use shared::geospatial::{LocalPoint, LocalPolygon};
+use shared::legend::{categorical_legend, Legend};
+use shared::palette::{okabe_ito, Rgb8};
+use shared::schemas::{
+    assert_raster_spatial_ref, GeoBounds, RasterResolution, RasterSpatialRef, RasterSpatialRefError,
+};
+
+pub const NDVI_CHANGE_PAYLOAD_KEY: &str = "ndvi_change_detection_payload";
+pub const DEFAULT_NDVI_CHANGE_DEAD_BAND: f32 = 0.05;
+pub const DEFAULT_NDVI_CHANGE_MIN_OVERLAP_FRACTION: f64 = 0.25;
+/// Degraded share, in percent of compared cells, at which a zone is labelled
+/// degraded and the change recommendation becomes medium priority.
+pub const DEFAULT_NDVI_CHANGE_DEGRADED_ZONE_PCT: f32 = 20.0;
+/// Degraded share at which the change recommendation becomes high priority,
+/// unless the zone threshold is set above it.
+pub const NDVI_CHANGE_HIGH_PRIORITY_PCT: f32 = 50.0;
+
+const IMPROVED_COLOR: Rgb8 = okabe_ito::BLUE;
+const STABLE_COLOR: Rgb8 = [190, 190, 190];
+const DEGRADED_COLOR: Rgb8 = okabe_ito::VERMILLION;
+
+/// Two NDVI products of the same area from different flights. The grids may
+/// differ in extent and resolution but must share a CRS.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NdviChangeRequest {
+    pub previous: ProductGrid,
+    pub current: ProductGrid,
+    /// Deltas within `±dead_band` are classified as stable.
+    #[serde(default = "default_dead_band")]
+    pub dead_band: f32,
+    /// Required intersection area as a fraction of the smaller flight's
+    /// footprint.
+    #[serde(default = "default_min_overlap_fraction")]
+    pub min_overlap_fraction: f64,
+    /// Percent of a zone's compared cells that must have degraded for the
+    /// zone to be labelled degraded; see
+    /// [`DEFAULT_NDVI_CHANGE_DEGRADED_ZONE_PCT`].
+    #[serde(default = "default_degraded_zone_pct")]
+    pub degraded_zone_pct: f32,
+    #[serde(default)]
+    pub zones: Vec<NdviChangeZone>,
+}
+
+/// Management zone outline in the grids' CRS, as `(x, y)` vertices.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NdviChangeZone {
+    pub id: String,
+    pub boundary: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NdviChangeClass {
+    Improved,
+    Stable,
+    Degraded,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct NdviChangeShares {
+    pub compared_cell_count: u32,
+    pub improved_pct: f32,
+    pub stable_pct: f32,
+    pub degraded_pct: f32,
+}
+
+impl NdviChangeShares {
+    /// Whether at least `threshold_pct` percent of the compared cells
+    /// degraded.
+    pub fn is_degraded(&self, threshold_pct: f32) -> bool {
+        self.compared_cell_count > 0 && self.degraded_pct >= threshold_pct
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NdviChangeZoneSummary {
+    pub zone_id: String,
+    pub shares: NdviChangeShares,
+}
+
+/// Per-cell NDVI deltas (`current - previous`) on the common grid. Cells
+/// seen by only one flight, or masked in either, hold `NaN` and no class.
+#[derive(Debug, Clone)]
+pub struct NdviChangeResult {
+    pub width: u32,
+    pub height: u32,
+    pub crs: String,
+    pub extent: GeoBounds,
+    pub resolution: RasterResolution,
+    pub deltas: Vec<f32>,
+    pub classes: Vec<Option<NdviChangeClass>>,
+    pub overlap_fraction: f64,
+    pub summary: NdviChangeShares,
+    pub zones: Vec<NdviChangeZoneSummary>,
+}
+
+impl NdviChangeResult {
+    pub fn class_at(&self, column: u32, row: u32) -> Option<NdviChangeClass> {
+        self.classes[(row * self.width + column) as usize]
+    }
+
+    pub fn delta_at(&self, column: u32, row: u32) -> f32 {
+        self.deltas[(row * self.width + column) as usize]
+    }
+
+    /// Delta grid with uncompared cells masked, ready for zonal statistics.
+    pub fn delta_grid(&self) -> ProductGrid {
+        ProductGrid {
+            width: self.width,
+            height: self.height,
+            values: self.deltas.clone(),
+            nodata_mask: self.deltas.iter().map(|delta| delta.is_nan()).collect(),
+            spatial_ref: RasterSpatialRef {
+                georeferenced: true,
+                crs: Some(self.crs.clone()),
+                bbox: Some(self.extent.clone()),
+                geo_transform: Some([
+                    self.extent.min_lon,
+                    self.resolution.x,
+                    0.0,
+                    self.extent.max_lat,
+                    0.0,
+                    -self.resolution.y,
+                ]),
+                resolution: Some(self.resolution),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum NdviChangeError {
+    #[error("{grid} grid has {values} values and {mask} mask entries; expected {expected}")]
+    DimensionMismatch {
+        grid: &'static str,
+        expected: usize,
+        values: usize,
+        mask: usize,
+    },
+    #[error("{grid} grid has invalid spatial reference: {reason}")]
+    SpatialReference {
+        grid: &'static str,
+        reason: RasterSpatialRefError,
+    },
+    #[error("{grid} grid is rotated; only north-up grids can be resampled")]
+    RotatedGrid { grid: &'static str },
+    #[error("flights use different CRS: previous {previous}, current {current}")]
+    CrsMismatch { previous: String, current: String },
+    #[error(
+        "flights overlap by {:.1}% of the smaller footprint; at least {:.1}% is required",
+        overlap_fraction * 100.0,
+        required * 100.0
+    )]
+    InsufficientOverlap {
+        overlap_fraction: f64,
+        required: f64,
+    },
+    #[error("no cell has valid NDVI from both flights")]
+    NoComparableCells,
+    #[error("invalid ndvi change parameter: {reason}")]
+    InvalidParameter { reason: String },
+}
+
+struct AlignedGrid<'a> {
+    grid: &'a ProductGrid,
+    transform: [f64; 6],
+    bbox: GeoBounds,
+    resolution: RasterResolution,
+    crs: String,
+}
+
+impl AlignedGrid<'_> {
+    /// Nearest-cell sample at `(x, y)`; `None` outside the grid or on nodata.
+    fn sample(&self, x: f64, y: f64) -> Option<f32> {
+        let column = ((x - self.transform[0]) / self.transform[1]).floor();
+        let row = ((y - self.transform[3]) / self.transform[5]).floor();
+        if column < 0.0
+            || row < 0.0
+            || column >= f64::from(self.grid.width)
+            || row >= f64::from(self.grid.height)
+        {
+            return None;
+        }
+        let index = row as usize * self.grid.width as usize + column as usize;
+        let value = self.grid.values[index];
+        (!self.grid.nodata_mask[index] && value.is_finite()).then_some(value)
+    }
+
+    fn area(&self) -> f64 {
+        (self.bbox.max_lon - self.bbox.min_lon) * (self.bbox.max_lat - self.bbox.min_lat)
+    }
+}
+
+/// Resamples both flights onto a common north-up grid and classifies the
+/// per-cell change.
+///
+/// The common grid spans the union of both footprints at the coarser of the
+/// two resolutions, anchored at the union's north-west corner, and each
+/// flight is sampled at cell centres with nearest-neighbour lookup so no
+/// NDVI values are invented by interpolation.
+pub fn detect_ndvi_change(
+    request: &NdviChangeRequest,
+) -> Result<NdviChangeResult, NdviChangeError> {
+    if !request.dead_band.is_finite() || request.dead_band < 0.0 {
+        return Err(NdviChangeError::InvalidParameter {
+            reason: "dead_band must be a non-negative number".to_string(),
+        });
+    }
+    if !(0.0..=1.0).contains(&request.min_overlap_fraction) {
+        return Err(NdviChangeError::InvalidParameter {
+            reason: "min_overlap_fraction must be between 0 and 1".to_string(),
+        });
+    }
+    if !(request.degraded_zone_pct > 0.0 && request.degraded_zone_pct <= 100.0) {
+        return Err(NdviChangeError::InvalidParameter {
+            reason: "degraded_zone_pct must be above 0 and at most 100".to_string(),
+        });
+    }
+
+    let previous = aligned_grid(&request.previous, "previous")?;
+    let current = aligned_grid(&request.current, "current")?;
+    if !previous.crs.eq_ignore_ascii_case(&current.crs) {
+        return Err(NdviChangeError::CrsMismatch {
+            previous: previous.crs,
+            current: current.crs,
+        });
+    }
+
+    let overlap_width = previous.bbox.max_lon.min(current.bbox.max_lon)
+        - previous.bbox.min_lon.max(current.bbox.min_lon);
+    let overlap_height = previous.bbox.max_lat.min(current.bbox.max_lat)
+        - previous.bbox.min_lat.max(current.bbox.min_lat);
+    let overlap_fraction = if overlap_width > 0.0 && overlap_height > 0.0 {
+        overlap_width * overlap_height / previous.area().min(current.area())
+    } else {
+        0.0
+    };
+    if overlap_fraction <= 0.0 || overlap_fraction < request.min_overlap_fraction {
+        return Err(NdviChangeError::InsufficientOverlap {
+            overlap_fraction,
+            required: request.min_overlap_fraction,
+        });
+    }
+
+    let resolution = RasterResolution {
+        x: previous.resolution.x.max(current.resolution.x),
+        y: previous.resolution.y.max(current.resolution.y),
+    };
+    let min_x = previous.bbox.min_lon.min(current.bbox.min_lon);
+    let max_x = previous.bbox.max_lon.max(current.bbox.max_lon);
+    let min_y = previous.bbox.min_lat.min(current.bbox.min_lat);
+    let max_y = previous.bbox.max_lat.max(current.bbox.max_lat);
+    // Shave a little off before rounding up so extents that are exact
+    // multiples of the resolution do not gain a spurious extra cell.
+    let width = ((max_x - min_x) / resolution.x - 1.0e-9).ceil().max(1.0) as u32;
+    let height = ((max_y - min_y) / resolution.y - 1.0e-9).ceil().max(1.0) as u32;
+    let extent = GeoBounds {
+        min_lon: min_x,
+        min_lat: max_y - f64::from(height) * resolution.y,
+        max_lon: min_x + f64::from(width) * resolution.x,
+        max_lat: max_y,
+    };
+
+    let cell_count = width as usize * height as usize;
+    let mut deltas = Vec::with_capacity(cell_count);
+    let mut classes = Vec::with_capacity(cell_count);
+    let mut centres = Vec::with_capacity(cell_count);
+    for row in 0..height {
+        for column in 0..width {
+            let x = extent.min_lon + (f64::from(column) + 0.5) * resolution.x;
+            let y = extent.max_lat - (f64::from(row) + 0.5) * resolution.y;
+            centres.push((x, y));
+            match (previous.sample(x, y), current.sample(x, y)) {
+                (Some(before), Some(after)) => {
+                    let delta = after - before;
+                    deltas.push(delta);
+                    classes.push(Some(classify(delta, request.dead_band)));
+                }
+                _ => {
+                    deltas.push(f32::NAN);
+                    classes.push(None);
+                }
+            }
+        }
+    }
+
+    let summary = shares(classes.iter().flatten().copied());
+    if summary.compared_cell_count == 0 {
+        return Err(NdviChangeError::NoComparableCells);
+    }
+    let zones = request
+        .zones
+        .iter()
+        .map(|zone| {
+            let boundary = LocalPolygon::from_xy_points(zone.boundary.iter().copied());
+            NdviChangeZoneSummary {
+                zone_id: zone.id.clone(),
+                shares: shares(
+                    centres
+                        .iter()
+                        .zip(&classes)
+                        .filter(|(&centre, _)| boundary.contains(&LocalPoint::from_xy(centre)))
+                        .filter_map(|(_, class)| *class),
+                ),
+            }
+        })
+        .collect();
+
+    Ok(NdviChangeResult {
+        width,
+        height,
+        crs: previous.crs,
+        extent,
+        resolution,
+        deltas,
+        classes,
+        overlap_fraction,
+        summary,
+        zones,
+    })
+}
+
+/// Colours improved cells blue, stable grey and degraded vermillion, which
+/// stay apart under red-green colour blindness; cells without a comparison
+/// are transparent so the overlay can sit on a basemap.
+pub fn render_change_classification(result: &NdviChangeResult) -> RgbaImage {
+    // Class codes 0, 1, 2 fall either side of breaks at 0.5 and 1.5.
+    let codes: Vec<f32> = (0..result.height)
+        .flat_map(|row| (0..result.width).map(move |column| (column, row)))
+        .map(|(column, row)| match result.class_at(column, row) {
+            Some(NdviChangeClass::Degraded) => 0.0,
+            Some(NdviChangeClass::Stable) => 1.0,
+            Some(NdviChangeClass::Improved) => 2.0,
+            None => f32::NAN,
+        })
+        .collect();
+    let options = HeatmapOptions {
+        class_breaks: Some(HeatmapClassBreaks {
+            breaks: vec![0.5, 1.5],
+            colors: [DEGRADED_COLOR, STABLE_COLOR, IMPROVED_COLOR]
+                .map(|[r, g, b]| RgbColor { r, g, b })
+                .to_vec(),
+        }),
+        transparent_nodata: true,
+        ..HeatmapOptions::default()
+    };
+    create_heatmap_image_rgba(&codes, result.width, result.height, "viridis", &options)
+        .expect("class codes match the grid and the breaks match the colors")
+}
+
+/// Key to the colours of [`render_change_classification`].
+pub fn change_classification_legend() -> Legend {
+    categorical_legend([
+        ("Improved", IMPROVED_COLOR),
+        ("Stable", STABLE_COLOR),
+        ("Degraded", DEGRADED_COLOR),
+    ])
+}
+
+fn default_dead_band() -> f32 {
+    DEFAULT_NDVI_CHANGE_DEAD_BAND
+}
+
+fn default_min_overlap_fraction() -> f64 {
+    DEFAULT_NDVI_CHANGE_MIN_OVERLAP_FRACTION
+}
+
+fn default_degraded_zone_pct() -> f32 {
+    DEFAULT_NDVI_CHANGE_DEGRADED_ZONE_PCT
+}
+
+fn classify(delta: f32, dead_band: f32) -> NdviChangeClass {
+    if delta > dead_band {
+        NdviChangeClass::Improved
+    } else if delta < -dead_band {
+        NdviChangeClass::Degraded
+    } else {
+        NdviChangeClass::Stable
+    }
+}
+
+fn shares(classes: impl Iterator<Item = NdviChangeClass>) -> NdviChangeShares {
+    let (mut improved, mut stable, mut degraded) = (0u32, 0u32, 0u32);
+    for class in classes {
+        match class {
+            NdviChangeClass::Improved => improved += 1,
+            NdviChangeClass::Stable => stable += 1,
+            NdviChangeClass::Degraded => degraded += 1,
+        }
+    }
+    let total = improved + stable + degraded;
+    if total == 0 {
+        return NdviChangeShares::default();
+    }
+    let pct = |count: u32| count as f32 * 100.0 / total as f32;
+    NdviChangeShares {
+        compared_cell_count: total,
+        improved_pct: pct(improved),
+        stable_pct: pct(stable),
+        degraded_pct: pct(degraded),
+    }
+}
+
+fn aligned_grid<'a>(
+    grid: &'a ProductGrid,
+    name: &'static str,
+) -> Result<AlignedGrid<'a>, NdviChangeError> {
+    let expected = grid.width as usize * grid.height as usize;
+    if grid.values.len() != expected || grid.nodata_mask.len() != expected {
+        return Err(NdviChangeError::DimensionMismatch {
+            grid: name,
+            expected,
+            values: grid.values.len(),
+            mask: grid.nodata_mask.len(),
+        });
+    }
+    let spatial_ref = assert_raster_spatial_ref(Some(&grid.spatial_ref), grid.width, grid.height)
+        .map_err(|reason| NdviChangeError::SpatialReference { grid: name, reason })?;
+    let missing = |reason| NdviChangeError::SpatialReference { grid: name, reason };
+    let transform = spatial_ref
+        .geo_transform
+        .ok_or_else(|| missing(RasterSpatialRefError::MissingTransform))?;
+    if transform[2] != 0.0 || transform[4] != 0.0 {
+        return Err(NdviChangeError::RotatedGrid { grid: name });
+    }
+    Ok(AlignedGrid {
+        grid,
+        transform,
+        bbox: spatial_ref
+            .bbox
+            .ok_or_else(|| missing(RasterSpatialRefError::MissingBbox))?,
+        resolution: RasterResolution {
+            x: transform[1].abs(),
+            y: transform[5].abs(),
+        },
+        crs: spatial_ref
+            .crs
+            .ok_or_else(|| missing(RasterSpatialRefError::MissingCrs))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGIN_X: f64 = 500_000.0;
+    const ORIGIN_Y: f64 = 4_500_000.0;
+    const CELL: f64 = 10.0;
+
+    /// A 4x4 grid whose north-west corner sits `offset_x` metres east of the
+    /// shared origin.
+    fn grid(offset_x: f64, values: Vec<f32>) -> ProductGrid {
+        let min_x = ORIGIN_X + offset_x;
+        ProductGrid {
+            width: 4,
+            height: 4,
+            nodata_mask: vec![false; values.len()],
+            values,
+            spatial_ref: RasterSpatialRef {
+                georeferenced: true,
+                crs: Some("EPSG:32614".to_string()),
+                bbox: Some(GeoBounds {
+                    min_lon: min_x,
+                    min_lat: ORIGIN_Y,
+                    max_lon: min_x + 4.0 * CELL,
+                    max_lat: ORIGIN_Y + 4.0 * CELL,
+                }),
+                geo_transform: Some([min_x, CELL, 0.0, ORIGIN_Y + 4.0 * CELL, 0.0, -CELL]),
+                resolution: Some(RasterResolution { x: CELL, y: CELL }),
+            },
+        }
+    }
+
+    fn column_zone(id: &str, column: u32) -> NdviChangeZone {
+        let min_x = ORIGIN_X + f64::from(column) * CELL;
+        NdviChangeZone {
+            id: id.to_string(),
+            boundary: vec![
+                (min_x, ORIGIN_Y),
+                (min_x + CELL, ORIGIN_Y),
+                (min_x + CELL, ORIGIN_Y + 4.0 * CELL),
+                (min_x, ORIGIN_Y + 4.0 * CELL),
+            ],
+        }
+    }
+
+    /// The current flight is shifted one cell east. On the ground, column 1
+    /// row 0 improves, column 2 row 1 degrades and column 3 row 2 moves by
+    /// less than the dead band.
+    fn offset_request() -> NdviChangeRequest {
+        let previous = grid(0.0, vec![0.5; 16]);
+        let mut current_values = vec![0.5; 16];
+        // Current column = ground column - 1.
+        current_values[0] = 0.7;
+        current_values[4 + 1] = 0.3;
+        current_values[8 + 2] = 0.52;
+        NdviChangeRequest {
+            previous,
+            current: grid(CELL, current_values),
+            dead_band: 0.05,
+            min_overlap_fraction: 0.5,
+            degraded_zone_pct: DEFAULT_NDVI_CHANGE_DEGRADED_ZONE_PCT,
+            zones: vec![column_zone("west", 1), column_zone("middle", 2)],
+        }
+    }
+
+    #[test]
+    fn zones_are_degraded_from_the_configured_share() {
+        let result = detect_ndvi_change(&offset_request()).expect("flights overlap");
+        let (west, middle) = (&result.zones[0].shares, &result.zones[1].shares);
+        assert!(!west.is_degraded(DEFAULT_NDVI_CHANGE_DEGRADED_ZONE_PCT));
+        assert!(middle.is_degraded(DEFAULT_NDVI_CHANGE_DEGRADED_ZONE_PCT));
+        assert!(!middle.is_degraded(30.0));
+        assert!(!NdviChangeShares::default().is_degraded(0.0));
+
+        let mut request = offset_request();
+        request.degraded_zone_pct = 0.0;
+        assert!(matches!(
+            detect_ndvi_change(&request),
+            Err(NdviChangeError::InvalidParameter { .. })
+        ));
+    }
+
+    #[test]
+    fn offset_flights_are_aligned_and_classified_per_cell() {
+        let result = detect_ndvi_change(&offset_request()).expect("flights overlap");
+
+        assert_eq!((result.width, result.height), (5, 4));
+        assert!((result.overlap_fraction - 0.75).abs() < 1e-9);
+        assert_eq!(result.extent.max_lon, ORIGIN_X + 5.0 * CELL);
+        for row in 0..4 {
+            assert!(
+                result.delta_at(0, row).is_nan(),
+                "only previous covers column 0"
+            );
+            assert!(
+                result.delta_at(4, row).is_nan(),
+                "only current covers column 4"
+            );
+            assert_eq!(result.class_at(0, row), None);
+        }
+        assert_eq!(result.class_at(1, 0), Some(NdviChangeClass::Improved));
+        assert!((result.delta_at(1, 0) - 0.2).abs() < 1e-6);
+        assert_eq!(result.class_at(2, 1), Some(NdviChangeClass::Degraded));
+        assert_eq!(result.class_at(3, 2), Some(NdviChangeClass::Stable));
+        assert_eq!(result.class_at(3, 3), Some(NdviChangeClass::Stable));
+
+        assert_eq!(result.summary.compared_cell_count, 12);
+        assert!((result.summary.degraded_pct - 100.0 / 12.0).abs() < 1e-4);
+        let west = &result.zones[0];
+        assert_eq!(west.zone_id, "west");
+        assert_eq!(west.shares.compared_cell_count, 4);
+        assert_eq!(west.shares.improved_pct, 25.0);
+        assert_eq!(west.shares.degraded_pct, 0.0);
+        assert_eq!(result.zones[1].shares.degraded_pct, 25.0);
+
+        let overlay = render_change_classification(&result);
+        assert_eq!(overlay.get_pixel(1, 0).0, [0, 114, 178, 255]);
+        assert_eq!(overlay.get_pixel(2, 1).0, [213, 94, 0, 255]);
+        assert_eq!(overlay.get_pixel(3, 2).0, [190, 190, 190, 255]);
+        assert_eq!(overlay.get_pixel(0, 0)[3], 0);
+        assert_eq!(
+            change_classification_legend().labels(),
+            ["Improved", "Stable", "Degraded"]
+        );
+    }
+
+    #[test]
+    fn barely_intersecting_flights_fail_fast() {
+        let mut request = offset_request();
+        request.current = grid(3.5 * CELL, vec![0.5; 16]);
+
+        let error = detect_ndvi_change(&request).expect_err("only 12.5% overlap");
+
+        assert!(matches!(
+            error,
+            NdviChangeError::InsufficientOverlap { required, .. } if required == 0.5
+        ));
+        assert!(error.to_string().contains("12.5%"));
+    }
+
+    #[test]
+    fn mismatched_crs_is_rejected() {
+        let mut request = offset_request();
+        request.current.spatial_ref.crs = Some("EPSG:4326".to_string());
+
+        assert!(matches!(
+            detect_ndvi_change(&request),
+            Err(NdviChangeError::CrsMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,675 @@
+use crate::{AnalysisZone, ResultData};
+use interop::{
+    export_prescription_shapefile, InteropCoordinate, InteropError, PrescriptionField,
+    PrescriptionShapefileReport, PrescriptionShapefileRequest, PrescriptionZone,
+};
+use sensor_overlay_engine::{
+    write_geotiff, GeoTiffBand, GeoTiffOptions, SpatialBounds, GEOTIFF_NODATA,
+};
+use serde::{Deserialize, Serialize};
+use shared::geospatial::{LocalPoint, LocalPolygon};
+use std::path::{Path, PathBuf};
+
+pub const PRESCRIPTION_SUMMARY_CSV_HEADER: &str =
+    "zone_id,classification,area_ha,rate,unit,product_total,flag";
+
+const SQUARE_METRES_PER_HECTARE: f64 = 10_000.0;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PrescriptionError {
+    #[error("prescriptions need zonal data")]
+    NotZonal,
+    #[error("zonal result has no zones to prescribe")]
+    NoZones,
+    #[error("invalid prescription config: {0}")]
+    InvalidConfig(String),
+    #[error("CRS {0} is not an EPSG code")]
+    UnsupportedCrs(String),
+    #[error("interop export failed: {0}")]
+    Interop(#[from] InteropError),
+    #[error("rate map GeoTIFF failed: {0}")]
+    RateMap(String),
+    #[error("csv writer failed: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("prescription file write failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("csv output was not valid UTF-8: {0}")]
+    Utf8(#[from] std::string::FromUtf8Error),
+}
+
+/// How zone health maps to an application rate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrescriptionConfig {
+    /// Rate unit written to every export, e.g. "l_ha" or "kg_ha".
+    pub unit: String,
+    /// Zone value the value-range rules read, e.g. "ndvi_mean".
+    pub value_key: String,
+    /// Checked in order; the first rule matching a zone sets its rate.
+    pub rules: Vec<PrescriptionRateRule>,
+    /// Rate for zones no rule matches, including zones without data.
+    pub default_rate: f64,
+    pub min_rate: f64,
+    pub max_rate: f64,
+    /// Total product (L or kg) the prescription may use. When the matched
+    /// rates need more, every rate is scaled down by the same factor, with
+    /// zones that would drop below `min_rate` held there.
+    #[serde(default)]
+    pub product_budget: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "match", rename_all = "snake_case")]
+pub enum PrescriptionRateRule {
+    /// Zones whose classification equals `classification`, ignoring case.
+    Classification { classification: String, rate: f64 },
+    /// Zones whose `value_key` value lies in `[min, max)`; an open end
+    /// matches everything on that side.
+    ValueRange {
+        min: Option<f32>,
+        max: Option<f32>,
+        rate: f64,
+    },
+}
+
+impl PrescriptionRateRule {
+    fn rate_for(&self, zone: &AnalysisZone, value_key: &str) -> Option<f64> {
+        match self {
+            PrescriptionRateRule::Classification {
+                classification,
+                rate,
+            } => zone
+                .classification
+                .as_deref()
+                .is_some_and(|class| class.trim().eq_ignore_ascii_case(classification.trim()))
+                .then_some(*rate),
+            PrescriptionRateRule::ValueRange { min, max, rate } => {
+                let value = zone_value(zone, value_key)?;
+                (min.is_none_or(|min| value >= min) && max.is_none_or(|max| value < max))
+                    .then_some(*rate)
+            }
+        }
+    }
+}
+
+/// Field the prescription is written for. Zones must tile `field_boundary`
+/// for the shapefile export.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrescriptionTarget {
+    pub prescription_id: String,
+    pub field_id: String,
+    /// `EPSG:<code>` of the field boundary and zone polygons.
+    pub crs: String,
+    pub field_boundary: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrescriptionZoneFlag {
+    /// The zone has neither a classification nor a finite `value_key` value.
+    MissingData,
+    /// The zone has data but no rule covers it.
+    NoMatchingRule,
+}
+
+impl PrescriptionZoneFlag {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PrescriptionZoneFlag::MissingData => "missing_data",
+            PrescriptionZoneFlag::NoMatchingRule => "no_matching_rule",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrescribedZone {
+    pub zone_id: String,
+    pub classification: Option<String>,
+    pub boundary: Vec<(f64, f64)>,
+    pub area_ha: f64,
+    /// Matched (or default) rate after the min/max clamp, before the budget.
+    pub planned_rate: f64,
+    pub rate: f64,
+    /// `rate` times `area_ha`, in L or kg.
+    pub product_total: f64,
+    pub flag: Option<PrescriptionZoneFlag>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrescriptionPlan {
+    pub target: PrescriptionTarget,
+    pub unit: String,
+    pub zones: Vec<PrescribedZone>,
+    /// Product the planned rates would use.
+    pub planned_product: f64,
+    pub total_product: f64,
+    /// Whether the budget forced rates down.
+    pub budget_applied: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrescriptionExport {
+    pub rate_map_path: PathBuf,
+    pub rate_map_resolution: (u32, u32),
+    /// `.shp`, `.shx`, `.dbf` and `.prj`, in that order.
+    pub shapefile_paths: Vec<PathBuf>,
+    pub shapefile: PrescriptionShapefileReport,
+    pub summary_csv_path: PathBuf,
+}
+
+/// Turns the zones of a zonal health result into per-zone application rates.
+pub fn generate_prescription(
+    data: &ResultData,
+    target: PrescriptionTarget,
+    config: &PrescriptionConfig,
+) -> Result<PrescriptionPlan, PrescriptionError> {
+    let ResultData::ZonalData { zones, .. } = data else {
+        return Err(PrescriptionError::NotZonal);
+    };
+    if zones.is_empty() {
+        return Err(PrescriptionError::NoZones);
+    }
+    validate_config(config)?;
+
+    let mut prescribed = zones
+        .iter()
+        .map(|zone| {
+            let matched = config
+                .rules
+                .iter()
+                .find_map(|rule| rule.rate_for(zone, &config.value_key));
+            let flag = match matched {
+                Some(_) => None,
+                None if zone.classification.is_none()
+                    && zone_value(zone, &config.value_key).is_none() =>
+                {
+                    Some(PrescriptionZoneFlag::MissingData)
+                }
+                None => Some(PrescriptionZoneFlag::NoMatchingRule),
+            };
+            let planned_rate = matched
+                .unwrap_or(config.default_rate)
+                .clamp(config.min_rate, config.max_rate);
+            PrescribedZone {
+                zone_id: zone.id.clone(),
+                classification: zone.classification.clone(),
+                boundary: zone.boundary.clone(),
+                area_ha: f64::from(zone.area_m2) / SQUARE_METRES_PER_HECTARE,
+                planned_rate,
+                rate: planned_rate,
+                product_total: 0.0,
+                flag,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let planned_product = prescribed
+        .iter()
+        .map(|zone| zone.planned_rate * zone.area_ha)
+        .sum::<f64>();
+    let budget_applied = config
+        .product_budget
+        .is_some_and(|budget| planned_product > budget);
+    if let (true, Some(budget)) = (budget_applied, config.product_budget) {
+        fit_rates_to_budget(&mut prescribed, budget, config.min_rate);
+    }
+    for zone in &mut prescribed {
+        zone.product_total = zone.rate * zone.area_ha;
+    }
+
+    Ok(PrescriptionPlan {
+        target,
+        unit: config.unit.trim().to_string(),
+        total_product: prescribed.iter().map(|zone| zone.product_total).sum(),
+        zones: prescribed,
+        planned_product,
+        budget_applied,
+    })
+}
+
+/// Writes the plan to `output_dir` as `<id>_rate.tif` (one float32 band of
+/// rates, `grid_cell_size` CRS units per cell, cells outside every zone set
+/// to nodata), `<id>.shp` with its `.shx`, `.dbf` and `.prj` (a RATE and
+/// UNIT attribute per zone) and `<id>_summary.csv`.
+pub fn export_prescription(
+    plan: &PrescriptionPlan,
+    grid_cell_size: f64,
+    output_dir: &Path,
+) -> Result<PrescriptionExport, PrescriptionError> {
+    if !(grid_cell_size.is_finite() && grid_cell_size > 0.0) {
+        return Err(PrescriptionError::InvalidConfig(
+            "grid cell size must be positive".to_string(),
+        ));
+    }
+    let epsg = epsg_code(&plan.target.crs)?;
+    let shapefile = export_prescription_shapefile(shapefile_request(plan))?;
+    let id = &shapefile.prescription_id;
+
+    let (bounds, width, height) = rate_map_grid(&plan.target.field_boundary, grid_cell_size)?;
+    let zones: Vec<(LocalPolygon, f32)> = plan
+        .zones
+        .iter()
+        .map(|zone| {
+            (
+                LocalPolygon::from_xy_points(zone.boundary.iter().copied()),
+                zone.rate as f32,
+            )
+        })
+        .collect();
+    let rates = (0..height)
+        .flat_map(|row| (0..width).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            let x = bounds.min_x + (f64::from(column) + 0.5) * grid_cell_size;
+            let y = bounds.max_y - (f64::from(row) + 0.5) * grid_cell_size;
+            let centre = LocalPoint::new(x, y);
+            zones
+                .iter()
+                .find(|(boundary, _)| boundary.contains(&centre))
+                .map_or(GEOTIFF_NODATA, |&(_, rate)| rate)
+        })
+        .collect();
+    let rate_map_path = output_dir.join(format!("{id}_rate.tif"));
+    write_geotiff(
+        &rate_map_path,
+        width,
+        height,
+        &[GeoTiffBand {
+            description: format!("Application rate ({})", plan.unit),
+            nodata: GEOTIFF_NODATA,
+            values: rates,
+        }],
+        &bounds,
+        epsg,
+        &GeoTiffOptions::default(),
+    )
+    .map_err(|error| PrescriptionError::RateMap(format!("{error:#}")))?;
+
+    let mut shapefile_paths = Vec::with_capacity(4);
+    for (extension, bytes) in [
+        ("shp", &shapefile.files.shp),
+        ("shx", &shapefile.files.shx),
+        ("dbf", &shapefile.files.dbf),
+        ("prj", &shapefile.files.prj),
+    ] {
+        let path = output_dir.join(format!("{id}.{extension}"));
+        std::fs::write(&path, bytes)?;
+        shapefile_paths.push(path);
+    }
+
+    let summary_csv_path = output_dir.join(format!("{id}_summary.csv"));
+    std::fs::write(&summary_csv_path, prescription_summary_csv(plan)?)?;
+
+    Ok(PrescriptionExport {
+        rate_map_path,
+        rate_map_resolution: (width, height),
+        shapefile_paths,
+        shapefile,
+        summary_csv_path,
+    })
+}
+
+/// Per-zone area, rate and product, followed by a `total` row.
+pub fn prescription_summary_csv(plan: &PrescriptionPlan) -> Result<String, PrescriptionError> {
+    let mut buffer = Vec::new();
+    {
+        let mut writer = csv::Writer::from_writer(&mut buffer);
+        writer.write_record(PRESCRIPTION_SUMMARY_CSV_HEADER.split(','))?;
+        for zone in &plan.zones {
+            writer.write_record([
+                zone.zone_id.as_str(),
+                zone.classification.as_deref().unwrap_or(""),
+                &format!("{:.4}", zone.area_ha),
+                &format!("{:.2}", zone.rate),
+                plan.unit.as_str(),
+                &format!("{:.2}", zone.product_total),
+                zone.flag.map_or("", PrescriptionZoneFlag::as_str),
+            ])?;
+        }
+        writer.write_record([
+            "total",
+            "",
+            &format!(
+                "{:.4}",
+                plan.zones.iter().map(|zone| zone.area_ha).sum::<f64>()
+            ),
+            "",
+            plan.unit.as_str(),
+            &format!("{:.2}", plan.total_product),
+            "",
+        ])?;
+        writer.flush()?;
+    }
+
+    String::from_utf8(buffer).map_err(PrescriptionError::from)
+}
+
+fn validate_config(config: &PrescriptionConfig) -> Result<(), PrescriptionError> {
+    let invalid = |reason: &str| Err(PrescriptionError::InvalidConfig(reason.to_string()));
+    if config.unit.trim().is_empty() {
+        return invalid("rate unit is empty");
+    }
+    if !(config.min_rate.is_finite()
+        && config.max_rate.is_finite()
+        && 0.0 <= config.min_rate
+        && config.min_rate <= config.max_rate)
+    {
+        return invalid("min/max rates must be finite with 0 <= min <= max");
+    }
+    let rule_rates = config.rules.iter().map(|rule| match rule {
+        PrescriptionRateRule::Classification { rate, .. }
+        | PrescriptionRateRule::ValueRange { rate, .. } => *rate,
+    });
+    if !std::iter::once(config.default_rate)
+        .chain(rule_rates)
+        .all(|rate| rate.is_finite() && rate >= 0.0)
+    {
+        return invalid("rates must be finite and non-negative");
+    }
+    if config
+        .product_budget
+        .is_some_and(|budget| !(budget.is_finite() && budget > 0.0))
+    {
+        return invalid("product budget must be positive");
+    }
+    Ok(())
+}
+
+/// Scales every rate by one factor so the total meets `budget`. A zone the
+/// factor would take below `min_rate` is held at `min_rate` and the rest are
+/// rescaled against what remains, until no further zone hits the floor.
+fn fit_rates_to_budget(zones: &mut [PrescribedZone], budget: f64, min_rate: f64) {
+    let mut floored = vec![false; zones.len()];
+    loop {
+        let floored_product = zones
+            .iter()
+            .zip(&floored)
+            .filter(|(_, floored)| **floored)
+            .map(|(zone, _)| min_rate * zone.area_ha)
+            .sum::<f64>();
+        let free_product = zones
+            .iter()
+            .zip(&floored)
+            .filter(|(_, floored)| !**floored)
+            .map(|(zone, _)| zone.planned_rate * zone.area_ha)
+            .sum::<f64>();
+        let factor = if free_product > 0.0 {
+            ((budget - floored_product) / free_product).max(0.0)
+        } else {
+            0.0
+        };
+
+        let mut newly_floored = false;
+        for (zone, floored) in zones.iter_mut().zip(floored.iter_mut()) {
+            if *floored {
+                zone.rate = min_rate;
+                continue;
+            }
+            zone.rate = zone.planned_rate * factor;
+            if zone.rate < min_rate {
+                zone.rate = min_rate;
+                *floored = true;
+                newly_floored = true;
+            }
+        }
+        if !newly_floored {
+            return;
+        }
+    }
+}
+
+fn zone_value(zone: &AnalysisZone, value_key: &str) -> Option<f32> {
+    zone.values
+        .get(value_key)
+        .copied()
+        .filter(|value| value.is_finite())
+}
+
+fn epsg_code(crs: &str) -> Result<u16, PrescriptionError> {
+    let crs = crs.trim();
+    crs.get(..5)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("EPSG:"))
+        .and_then(|_| crs[5..].trim().parse().ok())
+        .ok_or_else(|| PrescriptionError::UnsupportedCrs(crs.to_string()))
+}
+
+/// North-up grid over the field boundary's bounding box, extended to whole
+/// cells.
+fn rate_map_grid(
+    field_boundary: &[(f64, f64)],
+    cell_size: f64,
+) -> Result<(SpatialBounds, u32, u32), PrescriptionError> {
+    let (min_x, min_y, max_x, max_y) = field_boundary.iter().fold(
+        (
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        ),
+        |(min_x, min_y, max_x, max_y), &(x, y)| {
+            (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+        },
+    );
+    if !(max_x > min_x && max_y > min_y) {
+        return Err(PrescriptionError::InvalidConfig(
+            "field boundary has no area".to_string(),
+        ));
+    }
+    let width = ((max_x - min_x) / cell_size).ceil() as u32;
+    let height = ((max_y - min_y) / cell_size).ceil() as u32;
+    let bounds = SpatialBounds::new(
+        min_x,
+        max_y - f64::from(height) * cell_size,
+        min_x + f64::from(width) * cell_size,
+        max_y,
+    );
+    Ok((bounds, width, height))
+}
+
+fn shapefile_request(plan: &PrescriptionPlan) -> PrescriptionShapefileRequest {
+    let coordinates = |points: &[(f64, f64)]| {
+        points
+            .iter()
+            .map(|&(x, y)| InteropCoordinate { x, y })
+            .collect::<Vec<_>>()
+    };
+    PrescriptionShapefileRequest {
+        prescription_id: plan.target.prescription_id.clone(),
+        field: PrescriptionField {
+            field_id: plan.target.field_id.clone(),
+            crs: plan.target.crs.clone(),
+            boundary: coordinates(&plan.target.field_boundary),
+        },
+        zones: plan
+            .zones
+            .iter()
+            .map(|zone| PrescriptionZone {
+                zone_id: zone.zone_id.clone(),
+                polygon: coordinates(&zone.boundary),
+                crs: plan.target.crs.clone(),
+                rate: zone.rate,
+                unit: plan.unit.clone(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn rectangle(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Vec<(f64, f64)> {
+        vec![
+            (min_x, max_y),
+            (max_x, max_y),
+            (max_x, min_y),
+            (min_x, min_y),
+            (min_x, max_y),
+        ]
+    }
+
+    fn zone(
+        id: &str,
+        min_x: f64,
+        max_x: f64,
+        classification: Option<&str>,
+        ndvi: Option<f32>,
+    ) -> AnalysisZone {
+        AnalysisZone {
+            id: id.to_string(),
+            boundary: rectangle(
+                500_000.0 + min_x,
+                4_500_000.0,
+                500_000.0 + max_x,
+                4_500_200.0,
+            ),
+            area_m2: ((max_x - min_x) * 200.0) as f32,
+            values: ndvi
+                .map(|ndvi| HashMap::from([("ndvi_mean".to_string(), ndvi)]))
+                .unwrap_or_default(),
+            classification: classification.map(str::to_string),
+        }
+    }
+
+    /// Stressed (2 ha), healthy (3 ha) and an unscanned strip (1 ha).
+    fn three_zone_result() -> ResultData {
+        ResultData::ZonalData {
+            zones: vec![
+                zone("stressed", 0.0, 100.0, Some("Stressed"), Some(0.31)),
+                zone("healthy", 100.0, 250.0, None, Some(0.78)),
+                zone("unscanned", 250.0, 300.0, None, None),
+            ],
+            aggregated_values: HashMap::new(),
+        }
+    }
+
+    fn target() -> PrescriptionTarget {
+        PrescriptionTarget {
+            prescription_id: "rx-2026-06".to_string(),
+            field_id: "field-7".to_string(),
+            crs: "EPSG:32614".to_string(),
+            field_boundary: rectangle(500_000.0, 4_500_000.0, 500_300.0, 4_500_200.0),
+        }
+    }
+
+    fn config(product_budget: Option<f64>) -> PrescriptionConfig {
+        PrescriptionConfig {
+            unit: "l_ha".to_string(),
+            value_key: "ndvi_mean".to_string(),
+            rules: vec![
+                PrescriptionRateRule::Classification {
+                    classification: "stressed".to_string(),
+                    rate: 120.0,
+                },
+                PrescriptionRateRule::ValueRange {
+                    min: Some(0.6),
+                    max: None,
+                    rate: 60.0,
+                },
+            ],
+            default_rate: 80.0,
+            min_rate: 50.0,
+            max_rate: 150.0,
+            product_budget,
+        }
+    }
+
+    fn rates(plan: &PrescriptionPlan) -> Vec<(&str, f64)> {
+        plan.zones
+            .iter()
+            .map(|zone| (zone.zone_id.as_str(), zone.rate))
+            .collect()
+    }
+
+    fn dbf_rates(dbf: &[u8]) -> Vec<f64> {
+        let header_len = u16::from_le_bytes([dbf[8], dbf[9]]) as usize;
+        let record_len = u16::from_le_bytes([dbf[10], dbf[11]]) as usize;
+        let mut offset = 1;
+        let mut rate_field = None;
+        for descriptor in dbf[32..header_len - 1].chunks(32) {
+            let name = String::from_utf8_lossy(&descriptor[..11]);
+            let width = descriptor[16] as usize;
+            if name.trim_end_matches('\0') == "RATE" {
+                rate_field = Some((offset, width));
+            }
+            offset += width;
+        }
+        let (start, width) = rate_field.expect("RATE attribute");
+        dbf[header_len..dbf.len() - 1]
+            .chunks(record_len)
+            .map(|record| {
+                String::from_utf8_lossy(&record[start..start + width])
+                    .trim()
+                    .parse()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rules_set_rates_and_missing_zones_get_the_flagged_default() {
+        let plan = generate_prescription(&three_zone_result(), target(), &config(None)).unwrap();
+
+        assert_eq!(
+            rates(&plan),
+            vec![("stressed", 120.0), ("healthy", 60.0), ("unscanned", 80.0)]
+        );
+        assert_eq!(
+            plan.zones.iter().map(|zone| zone.flag).collect::<Vec<_>>(),
+            vec![None, None, Some(PrescriptionZoneFlag::MissingData)]
+        );
+        assert!((plan.total_product - 500.0).abs() < 1e-9);
+        assert!(!plan.budget_applied);
+    }
+
+    #[test]
+    fn budget_rescales_rates_proportionally_and_holds_the_floor() {
+        let plan =
+            generate_prescription(&three_zone_result(), target(), &config(Some(400.0))).unwrap();
+
+        // 500 L planned against 400 L: a flat 0.8 would take the healthy
+        // zone to 48 L/ha, under the 50 L/ha floor, so it is held at 50 and
+        // the other 250 L is shared by the remaining 320 L of plan (x0.78125).
+        assert!(plan.budget_applied);
+        assert!((plan.planned_product - 500.0).abs() < 1e-9);
+        for ((zone_id, rate), (expected_id, expected)) in rates(&plan).into_iter().zip([
+            ("stressed", 93.75),
+            ("healthy", 50.0),
+            ("unscanned", 62.5),
+        ]) {
+            assert_eq!(zone_id, expected_id);
+            assert!((rate - expected).abs() < 1e-9, "{zone_id}: {rate}");
+        }
+        assert!((plan.total_product - 400.0).abs() < 1e-9);
+        assert!((plan.zones[0].product_total - 187.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn export_writes_rate_map_shapefile_and_summary() {
+        let plan =
+            generate_prescription(&three_zone_result(), target(), &config(Some(400.0))).unwrap();
+        let directory = tempfile::tempdir().unwrap();
+
+        let export = export_prescription(&plan, 10.0, directory.path()).unwrap();
+
+        assert_eq!(export.rate_map_resolution, (30, 20));
+        assert!(export.rate_map_path.exists());
+        assert_eq!(export.shapefile.rate_attribute, "RATE");
+        assert_eq!(export.shapefile.zone_count, 3);
+        let dbf = std::fs::read(&export.shapefile_paths[2]).unwrap();
+        assert_eq!(dbf_rates(&dbf), vec![93.75, 50.0, 62.5]);
+
+        let summary = std::fs::read_to_string(&export.summary_csv_path).unwrap();
+        let mut lines = summary.lines();
+        assert_eq!(lines.next(), Some(PRESCRIPTION_SUMMARY_CSV_HEADER));
+        assert_eq!(
+            lines.next(),
+            Some("stressed,Stressed,2.0000,93.75,l_ha,187.50,")
+        );
+        assert_eq!(lines.next(), Some("healthy,,3.0000,50.00,l_ha,150.00,"));
+        assert_eq!(
+            lines.next(),
+            Some("unscanned,,1.0000,62.50,l_ha,62.50,missing_data")
+        );
+        assert_eq!(lines.next(), Some("total,,6.0000,,l_ha,400.00,"));
+    }
+}
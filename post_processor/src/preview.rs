@@ -0,0 +1,448 @@
+use crate::roi_processing::METERS_PER_DEGREE;
+use crate::thumbnail::{is_ndvi_delta, thumbnail_colormap, NDVI_DELTA_RANGE};
+use crate::{AnalysisResult, ResultData, VisualizationOutput, VisualizationType};
+use image::{DynamicImage, ImageOutputFormat, Rgba, RgbaImage};
+use sensor_overlay_engine::utils::render_value_overlay;
+use sensor_overlay_engine::{SpatialBounds, KNOWN_COLORMAPS};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Visualization parameter naming the preview size of a stored preview.
+pub const PREVIEW_SIZE_PARAMETER: &str = "preview_size";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreviewSize {
+    Thumb,
+    Medium,
+}
+
+impl PreviewSize {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Thumb => "thumb",
+            Self::Medium => "medium",
+        }
+    }
+}
+
+/// When previews are generated for grid results and how large they are.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreviewConfig {
+    /// Grids with fewer cells than this are served as-is and get no previews.
+    pub min_grid_cells: usize,
+    pub thumbnail_max_dimension: u32,
+    pub medium_max_dimension: u32,
+    /// Overrides the per-result colormap; one of the overlay engine's known
+    /// colormaps.
+    pub colormap: Option<String>,
+}
+
+impl Default for PreviewConfig {
+    fn default() -> Self {
+        Self {
+            min_grid_cells: 1_000_000,
+            thumbnail_max_dimension: 256,
+            medium_max_dimension: 1024,
+            colormap: None,
+        }
+    }
+}
+
+impl PreviewConfig {
+    pub fn max_dimension(&self, size: PreviewSize) -> u32 {
+        match size {
+            PreviewSize::Thumb => self.thumbnail_max_dimension,
+            PreviewSize::Medium => self.medium_max_dimension,
+        }
+    }
+
+    /// True for grid results large enough to need previews.
+    pub fn wants_previews(&self, result: &AnalysisResult) -> bool {
+        matches!(
+            &result.data,
+            ResultData::GridData { values, .. } if values.len() >= self.min_grid_cells
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PreviewError {
+    #[error("result {result_id} is not a grid result")]
+    NotGrid { result_id: Uuid },
+    #[error("result {result_id} grid is empty or does not match its dimensions")]
+    InvalidGrid { result_id: Uuid },
+    #[error("preview dimension must be at least 1")]
+    InvalidDimension,
+    #[error("unknown preview colormap `{colormap}`")]
+    UnknownColormap { colormap: String },
+    #[error("failed to render {size} preview for result {result_id}: {reason}")]
+    Render {
+        result_id: Uuid,
+        size: &'static str,
+        reason: String,
+    },
+}
+
+/// Averages `values` (row-major, `width` x `height`) into a
+/// `target_width` x `target_height` grid. Each output cell is the mean of the
+/// finite source cells it covers, and NaN only when none of them is finite.
+pub fn downsample_grid(
+    values: &[f32],
+    width: u32,
+    height: u32,
+    target_width: u32,
+    target_height: u32,
+) -> Vec<f32> {
+    let (width, height) = (width as usize, height as usize);
+    let (target_width, target_height) = (target_width as usize, target_height as usize);
+    let span = |index: usize, source: usize, target: usize| {
+        let start = index * source / target;
+        let end = ((index + 1) * source / target).max(start + 1);
+        start..end.min(source)
+    };
+
+    let mut downsampled = Vec::with_capacity(target_width * target_height);
+    for target_row in 0..target_height {
+        let rows = span(target_row, height, target_height);
+        for target_col in 0..target_width {
+            let cols = span(target_col, width, target_width);
+            let (mut sum, mut count) = (0.0_f64, 0_u32);
+            for row in rows.clone() {
+                for value in &values[row * width + cols.start..row * width + cols.end] {
+                    if value.is_finite() {
+                        sum += f64::from(*value);
+                        count += 1;
+                    }
+                }
+            }
+            downsampled.push(if count == 0 {
+                f32::NAN
+            } else {
+                (sum / f64::from(count)) as f32
+            });
+        }
+    }
+    downsampled
+}
+
+/// Output dimensions keeping the grid's aspect ratio with the longer side at
+/// most `max_dimension`. Grids are never upsampled.
+pub fn preview_dimensions(width: u32, height: u32, max_dimension: u32) -> (u32, u32) {
+    let longest = width.max(height);
+    if longest <= max_dimension {
+        return (width, height);
+    }
+    let scale = f64::from(max_dimension) / f64::from(longest);
+    (
+        ((f64::from(width) * scale).round() as u32).clamp(1, max_dimension),
+        ((f64::from(height) * scale).round() as u32).clamp(1, max_dimension),
+    )
+}
+
+/// Writes thumbnail and medium PNG previews for `result` into `output_dir`
+/// and returns their visualization entries. NaN cells are fully transparent;
+/// the medium preview also carries a scale bar in the bottom-left corner when
+/// the grid bounds give a ground width.
+pub fn write_previews(
+    result: &AnalysisResult,
+    config: &PreviewConfig,
+    output_dir: &Path,
+) -> Result<Vec<VisualizationOutput>, PreviewError> {
+    [PreviewSize::Thumb, PreviewSize::Medium]
+        .into_iter()
+        .map(|size| {
+            let render_error = |reason: String| PreviewError::Render {
+                result_id: result.id,
+                size: size.as_str(),
+                reason,
+            };
+            let (image, scale_bar_m) = render_preview_image(result, size, config)?;
+            let png = encode_png(&image).map_err(render_error)?;
+            let file_path = output_dir.join(format!("{}_{}.png", result.id, size.as_str()));
+            fs::create_dir_all(output_dir)
+                .and_then(|()| fs::write(&file_path, png))
+                .map_err(|error| render_error(error.to_string()))?;
+
+            let mut parameters = HashMap::from([
+                (
+                    PREVIEW_SIZE_PARAMETER.to_string(),
+                    size.as_str().to_string(),
+                ),
+                ("width".to_string(), image.width().to_string()),
+                ("height".to_string(), image.height().to_string()),
+                ("colormap".to_string(), preview_colormap(result, config)),
+            ]);
+            if let Some(scale_bar_m) = scale_bar_m {
+                parameters.insert("scale_bar_m".to_string(), scale_bar_m.to_string());
+            }
+            Ok(VisualizationOutput {
+                id: Uuid::new_v4(),
+                visualization_type: VisualizationType::Heatmap,
+                file_path,
+                format: "png".to_string(),
+                description: format!("{} preview of result {}", size.as_str(), result.id),
+                parameters,
+            })
+        })
+        .collect()
+}
+
+/// Path of the stored `size` preview among the result's visualizations.
+pub fn stored_preview_path(result: &AnalysisResult, size: PreviewSize) -> Option<&PathBuf> {
+    result
+        .visualizations
+        .iter()
+        .find(|visualization| {
+            visualization
+                .parameters
+                .get(PREVIEW_SIZE_PARAMETER)
+                .is_some_and(|value| value == size.as_str())
+        })
+        .map(|visualization| &visualization.file_path)
+}
+
+fn preview_colormap(result: &AnalysisResult, config: &PreviewConfig) -> String {
+    config.colormap.clone().unwrap_or_else(|| {
+        if is_ndvi_delta(result) {
+            "rdbu".to_string()
+        } else {
+            thumbnail_colormap(&result.result_type).to_string()
+        }
+    })
+}
+
+fn render_preview_image(
+    result: &AnalysisResult,
+    size: PreviewSize,
+    config: &PreviewConfig,
+) -> Result<(RgbaImage, Option<f64>), PreviewError> {
+    let ResultData::GridData {
+        width,
+        height,
+        values,
+        bounds,
+        ..
+    } = &result.data
+    else {
+        return Err(PreviewError::NotGrid {
+            result_id: result.id,
+        });
+    };
+    let (width, height) = (*width, *height);
+    if width == 0 || height == 0 || values.len() != width as usize * height as usize {
+        return Err(PreviewError::InvalidGrid {
+            result_id: result.id,
+        });
+    }
+    let max_dimension = config.max_dimension(size);
+    if max_dimension == 0 {
+        return Err(PreviewError::InvalidDimension);
+    }
+    let colormap = preview_colormap(result, config);
+    if !KNOWN_COLORMAPS.contains(&colormap.as_str()) {
+        return Err(PreviewError::UnknownColormap { colormap });
+    }
+
+    let (preview_width, preview_height) = preview_dimensions(width, height, max_dimension);
+    let downsampled = downsample_grid(values, width, height, preview_width, preview_height);
+    let spatial_bounds = SpatialBounds {
+        min_x: bounds.0,
+        min_y: bounds.1,
+        max_x: bounds.2,
+        max_y: bounds.3,
+        min_z: None,
+        max_z: None,
+    };
+    let mut image = render_value_overlay(
+        &downsampled,
+        preview_width,
+        preview_height,
+        &spatial_bounds,
+        &colormap,
+        // Symmetric, so no change sits mid-ramp.
+        is_ndvi_delta(result).then_some(NDVI_DELTA_RANGE),
+        0,
+    )
+    .map_err(|error| PreviewError::Render {
+        result_id: result.id,
+        size: size.as_str(),
+        reason: error.to_string(),
+    })?
+    .image;
+
+    let scale_bar_m = match size {
+        PreviewSize::Thumb => None,
+        PreviewSize::Medium => draw_scale_bar(&mut image, *bounds),
+    };
+    Ok((image, scale_bar_m))
+}
+
+/// Draws a black bar with end ticks on a white backing, about a quarter of
+/// the image wide and rounded to 1, 2 or 5 times a power of ten metres.
+/// Returns the bar length, or `None` when the bounds give no ground width.
+fn draw_scale_bar(image: &mut RgbaImage, bounds: (f64, f64, f64, f64)) -> Option<f64> {
+    let (min_lon, min_lat, max_lon, max_lat) = bounds;
+    let mid_lat = ((min_lat + max_lat) / 2.0).to_radians();
+    let ground_width_m = (max_lon - min_lon).abs() * METERS_PER_DEGREE * mid_lat.cos();
+    let (width, height) = image.dimensions();
+    if !ground_width_m.is_finite() || ground_width_m <= 0.0 || width < 40 || height < 20 {
+        return None;
+    }
+
+    let target_m = ground_width_m / 4.0;
+    let magnitude = 10_f64.powf(target_m.log10().floor());
+    let bar_m = [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|step| step * magnitude)
+        .find(|length| *length <= target_m)
+        .unwrap_or(magnitude);
+    let bar_px = ((bar_m / ground_width_m) * f64::from(width)).round() as u32;
+    if bar_px < 2 {
+        return None;
+    }
+
+    let margin = 8;
+    let (left, bottom) = (margin, height - margin);
+    fill(
+        image,
+        left - 3,
+        bottom - 11,
+        bar_px + 6,
+        14,
+        [255, 255, 255, 255],
+    );
+    fill(image, left, bottom - 4, bar_px, 3, [0, 0, 0, 255]);
+    fill(image, left, bottom - 9, 1, 8, [0, 0, 0, 255]);
+    fill(image, left + bar_px - 1, bottom - 9, 1, 8, [0, 0, 0, 255]);
+    Some(bar_m)
+}
+
+fn fill(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: [u8; 4]) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, Rgba(color));
+        }
+    }
+}
+
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(image.clone())
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .map_err(|error| error.to_string())?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AnalysisStatistics, ResultType};
+    use chrono::Utc;
+
+    fn large_grid_with_hole() -> AnalysisResult {
+        let (width, height) = (2000_u32, 2000_u32);
+        let values = (0..width * height)
+            .map(|index| {
+                let (row, col) = (index / width, index % width);
+                if (800..1200).contains(&row) && (800..1200).contains(&col) {
+                    f32::NAN
+                } else {
+                    col as f32 / width as f32
+                }
+            })
+            .collect::<Vec<_>>();
+        AnalysisResult {
+            id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+            result_type: ResultType::NdviMap,
+            data: ResultData::GridData {
+                width,
+                height,
+                values: values.into(),
+                bounds: (-96.02, 41.0, -96.0, 41.02),
+                units: "NDVI".to_string(),
+            },
+            statistics: AnalysisStatistics::default(),
+            visualizations: Vec::new(),
+            recommendations: Vec::new(),
+            evidence_refs: Vec::new(),
+            uncertainty: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn downsampling_ignores_nan_unless_every_contributing_cell_is_nan() {
+        let values = [
+            1.0,
+            f32::NAN,
+            f32::NAN,
+            f32::NAN,
+            3.0,
+            8.0,
+            f32::NAN,
+            f32::NAN,
+        ];
+        assert_eq!(downsample_grid(&values, 4, 2, 2, 1)[0], 4.0);
+        assert!(downsample_grid(&values, 4, 2, 2, 1)[1].is_nan());
+        assert_eq!(downsample_grid(&values, 4, 2, 1, 1), vec![4.0]);
+    }
+
+    #[test]
+    fn large_grid_previews_are_downsampled_with_a_transparent_hole() {
+        let result = large_grid_with_hole();
+        let config = PreviewConfig::default();
+        assert!(config.wants_previews(&result));
+        let output_dir = tempfile::tempdir().unwrap();
+
+        let previews = write_previews(&result, &config, output_dir.path()).unwrap();
+        assert_eq!(previews.len(), 2);
+
+        let thumb = image::open(&previews[0].file_path).unwrap().to_rgba8();
+        assert_eq!(thumb.dimensions(), (256, 256));
+        assert_eq!(thumb.get_pixel(128, 128).0[3], 0);
+        assert_eq!(thumb.get_pixel(10, 10).0[3], 255);
+
+        let medium = image::open(&previews[1].file_path).unwrap().to_rgba8();
+        assert_eq!(medium.dimensions(), (1024, 1024));
+        assert_eq!(medium.get_pixel(512, 512).0[3], 0);
+        assert_eq!(previews[1].parameters["scale_bar_m"], "200");
+        assert_eq!(medium.get_pixel(8, 1024 - 8 - 4).0, [0, 0, 0, 255]);
+        assert_eq!(
+            stored_preview_path(
+                &AnalysisResult {
+                    visualizations: previews.clone(),
+                    ..result
+                },
+                PreviewSize::Medium
+            ),
+            Some(&previews[1].file_path)
+        );
+    }
+
+    #[test]
+    fn ndvi_delta_previews_centre_no_change_on_white() {
+        let mut result = large_grid_with_hole();
+        result.data = ResultData::GridData {
+            width: 4,
+            height: 1,
+            // Only slight gains; a min/max scale would paint 0.0 dark red.
+            values: vec![0.0, 0.1, 0.05, f32::NAN].into(),
+            bounds: (-96.02, 41.0, -96.0, 41.02),
+            units: crate::thumbnail::NDVI_DELTA_UNITS.to_string(),
+        };
+
+        let (image, _) =
+            render_preview_image(&result, PreviewSize::Thumb, &PreviewConfig::default()).unwrap();
+
+        assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        let gain = image.get_pixel(1, 0).0;
+        assert!(gain[2] > gain[0], "gains lean blue, got {gain:?}");
+        assert_eq!(image.get_pixel(3, 0).0[3], 0);
+    }
+}
@@ -0,0 +1,296 @@
+use crate::roi_processing::GridGeometry;
+use crate::{AnalysisZone, Priority, Recommendation, RecommendationCategory, ResultData};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// NDVI below which a cell counts as low vigour for cluster recommendations.
+pub const LOW_NDVI_THRESHOLD: f32 = 0.3;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProblemClusterError {
+    #[error("problem clusters need grid data")]
+    NotGrid,
+    #[error("grid is {width}x{height} but has {values} values")]
+    DimensionMismatch {
+        width: u32,
+        height: u32,
+        values: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdDirection {
+    Below,
+    Above,
+}
+
+/// Which cells are a problem and how the resulting clusters are reported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProblemClusterRule {
+    pub category: RecommendationCategory,
+    /// Short name of the condition, used in titles, e.g. "low NDVI".
+    pub label: String,
+    pub threshold: f32,
+    pub direction: ThresholdDirection,
+    /// Clusters with fewer cells are treated as noise.
+    pub min_cells: usize,
+    /// Distance past the threshold at which a cluster counts as fully severe.
+    pub severity_span: f32,
+}
+
+impl ProblemClusterRule {
+    pub fn low_ndvi() -> Self {
+        Self {
+            category: RecommendationCategory::General,
+            label: "low NDVI".to_string(),
+            threshold: LOW_NDVI_THRESHOLD,
+            direction: ThresholdDirection::Below,
+            min_cells: 2,
+            severity_span: 0.2,
+        }
+    }
+
+    pub fn is_problem(&self, value: f32) -> bool {
+        match self.direction {
+            ThresholdDirection::Below => value < self.threshold,
+            ThresholdDirection::Above => value > self.threshold,
+        }
+    }
+
+    /// 0..=1 measure of how far `value` lies past the threshold.
+    fn severity(&self, value: f32) -> f32 {
+        let excess = match self.direction {
+            ThresholdDirection::Below => self.threshold - value,
+            ThresholdDirection::Above => value - self.threshold,
+        };
+        (excess / self.severity_span.max(f32::EPSILON)).clamp(0.0, 1.0)
+    }
+}
+
+/// Groups the grid cells matching `predicate` into 4-connected clusters, one
+/// zone per cluster. Zones carry their bounding box (row 0 is the northern
+/// edge) and `cell_count`, `mean_value`, `min_value` and `max_value`.
+/// Non-finite cells never join a cluster.
+pub fn cluster_problem_zones(
+    data: &ResultData,
+    predicate: impl Fn(f32) -> bool,
+) -> Result<Vec<AnalysisZone>, ProblemClusterError> {
+    let ResultData::GridData {
+        width,
+        height,
+        values,
+        bounds,
+        ..
+    } = data
+    else {
+        return Err(ProblemClusterError::NotGrid);
+    };
+    let (width, height) = (*width, *height);
+    if values.len() != width as usize * height as usize {
+        return Err(ProblemClusterError::DimensionMismatch {
+            width,
+            height,
+            values: values.len(),
+        });
+    }
+    if values.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let geometry =
+        GridGeometry::from_corners(width, height, (bounds.3, bounds.0), (bounds.1, bounds.2));
+    let flagged = values
+        .iter()
+        .map(|value| value.is_finite() && predicate(*value))
+        .collect::<Vec<_>>();
+    let mut visited = vec![false; values.len()];
+    let mut zones = Vec::new();
+
+    for start in 0..values.len() {
+        if !flagged[start] || visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut queue = VecDeque::from([start]);
+        let mut cells = Vec::new();
+        while let Some(index) = queue.pop_front() {
+            cells.push(index);
+            for neighbor in grid_neighbors(index, width as usize, height as usize) {
+                if flagged[neighbor] && !visited[neighbor] {
+                    visited[neighbor] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        zones.push(zone_from_cells(zones.len() + 1, &geometry, values, &cells));
+    }
+
+    Ok(zones)
+}
+
+/// One recommendation per cluster of `rule` problem cells with at least
+/// `rule.min_cells` cells, most confident first. Confidence grows with cluster
+/// size and with how far its mean lies past the threshold.
+pub fn recommend_problem_clusters(
+    data: &ResultData,
+    rule: &ProblemClusterRule,
+) -> Result<Vec<Recommendation>, ProblemClusterError> {
+    let mut recommendations = cluster_problem_zones(data, |value| rule.is_problem(value))?
+        .into_iter()
+        .filter(|zone| zone_cell_count(zone) >= rule.min_cells)
+        .map(|zone| {
+            let cell_count = zone_cell_count(&zone);
+            let mean_value = zone
+                .values
+                .get("mean_value")
+                .copied()
+                .unwrap_or(rule.threshold);
+            let severity = rule.severity(mean_value);
+            // Saturates towards 1 as clusters grow past a handful of cells.
+            let size_factor = cell_count as f32 / (cell_count as f32 + 4.0);
+            let confidence_score = (0.5 * size_factor + 0.5 * severity).clamp(0.0, 1.0);
+            let priority = match severity {
+                s if s >= 0.75 => Priority::High,
+                s if s >= 0.25 => Priority::Medium,
+                _ => Priority::Low,
+            };
+            Recommendation {
+                category: rule.category.clone(),
+                priority,
+                title: format!("Inspect {} cluster {}", rule.label, zone.id),
+                description: format!(
+                    "{} contiguous cells ({:.0} m2) with mean {:.3} against a threshold of {:.3}.",
+                    cell_count, zone.area_m2, mean_value, rule.threshold
+                ),
+                action_items: vec![
+                    format!("Scout the {} area to confirm the cause", rule.label),
+                    "Compare with the previous survey of the same zone".to_string(),
+                ],
+                affected_areas: vec![zone],
+                confidence_score,
+            }
+        })
+        .collect::<Vec<_>>();
+    recommendations.sort_by(|a, b| b.confidence_score.total_cmp(&a.confidence_score));
+    Ok(recommendations)
+}
+
+fn zone_cell_count(zone: &AnalysisZone) -> usize {
+    zone.values.get("cell_count").copied().unwrap_or(0.0) as usize
+}
+
+fn grid_neighbors(index: usize, width: usize, height: usize) -> impl Iterator<Item = usize> {
+    let row = index / width;
+    let col = index % width;
+    [
+        (col > 0).then(|| index - 1),
+        (col + 1 < width).then(|| index + 1),
+        (row > 0).then(|| index - width),
+        (row + 1 < height).then(|| index + width),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+fn zone_from_cells(
+    zone_number: usize,
+    geometry: &GridGeometry,
+    values: &[f32],
+    cells: &[usize],
+) -> AnalysisZone {
+    let width = geometry.width as usize;
+    let (mut min_col, mut max_col) = (usize::MAX, 0);
+    let (mut min_row, mut max_row) = (usize::MAX, 0);
+    let (mut sum, mut min_value, mut max_value) = (0.0_f64, f32::INFINITY, f32::NEG_INFINITY);
+    for &index in cells {
+        let (row, col) = (index / width, index % width);
+        min_col = min_col.min(col);
+        max_col = max_col.max(col);
+        min_row = min_row.min(row);
+        max_row = max_row.max(row);
+        sum += f64::from(values[index]);
+        min_value = min_value.min(values[index]);
+        max_value = max_value.max(values[index]);
+    }
+
+    let (min_lon, min_lat, max_lon, max_lat) = geometry.bounds;
+    let lon_step = (max_lon - min_lon) / geometry.width as f64;
+    let lat_step = (max_lat - min_lat) / geometry.height as f64;
+    let west = min_lon + min_col as f64 * lon_step;
+    let east = min_lon + (max_col + 1) as f64 * lon_step;
+    let north = max_lat - min_row as f64 * lat_step;
+    let south = max_lat - (max_row + 1) as f64 * lat_step;
+
+    AnalysisZone {
+        id: format!("cluster-{zone_number}"),
+        boundary: vec![
+            (west, north),
+            (east, north),
+            (east, south),
+            (west, south),
+            (west, north),
+        ],
+        area_m2: cells.len() as f32 * geometry.pixel_area_m2,
+        values: HashMap::from([
+            ("cell_count".to_string(), cells.len() as f32),
+            ("mean_value".to_string(), (sum / cells.len() as f64) as f32),
+            ("min_value".to_string(), min_value),
+            ("max_value".to_string(), max_value),
+        ]),
+        classification: Some("problem_cluster".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_low_ndvi_blobs_become_two_recommendations() {
+        #[rustfmt::skip]
+        let values = vec![
+            0.10, 0.12, 0.70, 0.70, 0.70, 0.70,
+            0.15, 0.70, 0.70, 0.70, 0.25, 0.28,
+            0.70, 0.70, 0.70, 0.70, 0.26, 0.70,
+            0.70, 0.05, 0.70, 0.70, 0.70, 0.70,
+        ];
+        let grid = ResultData::GridData {
+            width: 6,
+            height: 4,
+            values: values.into(),
+            bounds: (-96.006, 41.0, -96.0, 41.004),
+            units: "NDVI".to_string(),
+        };
+
+        let zones = cluster_problem_zones(&grid, |value| value < LOW_NDVI_THRESHOLD).unwrap();
+        assert_eq!(zones.len(), 3);
+        assert_eq!(zones[0].values["cell_count"], 3.0);
+        let (east, south) = zones[0].boundary[2];
+        assert!((east - -96.004).abs() < 1e-9 && (south - 41.002).abs() < 1e-9);
+
+        let recommendations =
+            recommend_problem_clusters(&grid, &ProblemClusterRule::low_ndvi()).unwrap();
+        assert_eq!(recommendations.len(), 2);
+        let severe = &recommendations[0];
+        let mild = &recommendations[1];
+        assert_eq!(severe.affected_areas[0].values["cell_count"], 3.0);
+        assert_eq!(mild.affected_areas[0].values["cell_count"], 3.0);
+        assert!(severe.affected_areas[0].values["mean_value"] < 0.2);
+        assert!(severe.confidence_score > mild.confidence_score);
+        assert!(matches!(severe.priority, Priority::High));
+        assert!(matches!(mild.priority, Priority::Low));
+
+        assert_eq!(
+            cluster_problem_zones(
+                &ResultData::TimeSeriesData {
+                    timestamps: vec![],
+                    values: HashMap::new(),
+                },
+                |_| true,
+            )
+            .unwrap_err(),
+            ProblemClusterError::NotGrid
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Records the artifacts of analysis jobs that belong to a capture session
+//! in the data collector's product registry
+//! (`POST /sessions/{session_id}/products`).
+
+use crate::artifacts::ArtifactManifest;
+use crate::{JobType, ProcessingJob};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use shared::http_client::{HttpClient, HttpClientConfig};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Custom parameter holding the id of the capture session a job's inputs
+/// came from. Jobs without it are not registered anywhere.
+pub const SESSION_ID_KEY: &str = "session_id";
+
+/// Body of a product registration, in the registry's wire format.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProductRegistration {
+    pub producer: &'static str,
+    pub product_type: String,
+    pub file_path: PathBuf,
+    /// sha256 of the JSON encoding of the job parameters.
+    pub parameters_hash: String,
+}
+
+/// Client for the registry of one data collector.
+pub struct ProductRegistryClient {
+    http: HttpClient,
+    base_url: String,
+}
+
+impl ProductRegistryClient {
+    /// `base_url` is the data collector's ingest API root, e.g.
+    /// `http://collector:8082/api`.
+    pub fn new(base_url: impl Into<String>, config: HttpClientConfig) -> Result<Self> {
+        Ok(Self {
+            http: HttpClient::new(config)?,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        })
+    }
+
+    pub async fn register(
+        &self,
+        session_id: Uuid,
+        registration: &ProductRegistration,
+    ) -> Result<()> {
+        let url = format!("{}/sessions/{session_id}/products", self.base_url);
+        let response = self
+            .http
+            .send(self.http.post(&url).json(registration))
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("{url} answered {status}: {body}"));
+        }
+        Ok(())
+    }
+}
+
+/// The session `job` was submitted for, if any.
+pub fn job_session_id(job: &ProcessingJob) -> Result<Option<Uuid>> {
+    job.parameters
+        .custom_parameters
+        .get(SESSION_ID_KEY)
+        .map(|value| {
+            serde_json::from_value(value.clone())
+                .with_context(|| format!("{SESSION_ID_KEY} is not a session id"))
+        })
+        .transpose()
+}
+
+/// One registration per artifact in `manifest`; empty for job types the
+/// registry has no producer for.
+pub fn product_registrations(
+    job: &ProcessingJob,
+    manifest: &ArtifactManifest,
+) -> Vec<ProductRegistration> {
+    let producer = match job.job_type {
+        JobType::NdviAnalysis => "ndvi_analysis",
+        JobType::ThermalAnalysis => "thermal_analysis",
+        JobType::LidarProcessing => "lidar_mapping",
+        _ => return Vec::new(),
+    };
+    let parameters = serde_json::to_value(&job.parameters)
+        .map(|value| value.to_string())
+        .unwrap_or_default();
+    let parameters_hash: String = Sha256::digest(parameters.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    manifest
+        .artifacts
+        .iter()
+        .map(|artifact| ProductRegistration {
+            producer,
+            product_type: artifact.kind.as_str().to_string(),
+            file_path: artifact.path.clone(),
+            parameters_hash: parameters_hash.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JobStatus, PostProcessorService, ProcessingParameters};
+    use axum::extract::{Path, State};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use chrono::Utc;
+    use std::sync::{Arc, Mutex};
+
+    type Received = Arc<Mutex<Vec<(Uuid, serde_json::Value)>>>;
+
+    async fn registry_server() -> (String, Received) {
+        async fn register(
+            State(received): State<Received>,
+            Path(session_id): Path<Uuid>,
+            Json(body): Json<serde_json::Value>,
+        ) -> axum::http::StatusCode {
+            received.lock().unwrap().push((session_id, body));
+            axum::http::StatusCode::CREATED
+        }
+        let received = Received::default();
+        let app = Router::new()
+            .route("/sessions/:session_id/products", post(register))
+            .with_state(received.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{address}/"), received)
+    }
+
+    fn ndvi_job(output_directory: &std::path::Path, session_id: Option<Uuid>) -> ProcessingJob {
+        let mut parameters = ProcessingParameters::default();
+        if let Some(session_id) = session_id {
+            parameters
+                .custom_parameters
+                .insert(SESSION_ID_KEY.to_string(), serde_json::json!(session_id));
+        }
+        ProcessingJob {
+            id: Uuid::new_v4(),
+            job_type: JobType::NdviAnalysis,
+            input_files: vec![],
+            output_directory: output_directory.to_path_buf(),
+            parameters,
+            status: JobStatus::Queued,
+            created_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+            error_message: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn artifacts_of_session_jobs_are_registered_as_session_products() {
+        let (base_url, received) = registry_server().await;
+        let working_directory = tempfile::tempdir().unwrap();
+        let mut service =
+            PostProcessorService::new(working_directory.path().to_path_buf()).unwrap();
+        service.set_product_registry(
+            ProductRegistryClient::new(base_url, HttpClientConfig::default()).unwrap(),
+        );
+
+        let session_id = Uuid::new_v4();
+        let job_id = service
+            .submit_job(ndvi_job(working_directory.path(), Some(session_id)))
+            .await
+            .unwrap();
+        service.process_next_job().await.unwrap().unwrap();
+        let manifest = service.job_artifacts(&job_id).unwrap();
+        assert!(!manifest.artifacts.is_empty());
+        {
+            let received = received.lock().unwrap();
+            assert_eq!(received.len(), manifest.artifacts.len());
+            for (artifact, (registered_session, body)) in
+                manifest.artifacts.iter().zip(received.iter())
+            {
+                assert_eq!(*registered_session, session_id);
+                assert_eq!(body["producer"], "ndvi_analysis");
+                assert_eq!(body["product_type"], artifact.kind.as_str());
+                assert_eq!(body["file_path"], serde_json::json!(artifact.path));
+                assert_eq!(body["parameters_hash"].as_str().unwrap().len(), 64);
+            }
+        }
+
+        service
+            .submit_job(ndvi_job(working_directory.path(), None))
+            .await
+            .unwrap();
+        service.process_next_job().await.unwrap().unwrap();
+        assert_eq!(received.lock().unwrap().len(), manifest.artifacts.len());
+
+        let mut invalid = ndvi_job(working_directory.path(), None);
+        invalid
+            .parameters
+            .custom_parameters
+            .insert(SESSION_ID_KEY.to_string(), serde_json::json!("field-7"));
+        assert!(service.submit_job(invalid).await.is_err());
+    }
+}
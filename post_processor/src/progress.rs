@@ -0,0 +1,113 @@
+//! Job progress reporting for long-running analyses.
+
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Called with a job's id and the share of it done, from 0 to 1.
+pub type ProgressCallback = Arc<dyn Fn(Uuid, f32) + Send + Sync>;
+
+/// Reports one job's progress to an optional [`ProgressCallback`].
+///
+/// Reports never go backwards: a fraction at or below the last one reported
+/// is dropped, and fractions are clamped to 0..=1.
+#[derive(Clone)]
+pub struct JobProgress {
+    job_id: Uuid,
+    callback: Option<ProgressCallback>,
+    last: Arc<Mutex<Option<f32>>>,
+}
+
+impl JobProgress {
+    pub fn new(job_id: Uuid, callback: Option<ProgressCallback>) -> Self {
+        Self {
+            job_id,
+            callback,
+            last: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Reports nowhere, for analyzers run outside the service.
+    pub fn disabled() -> Self {
+        Self::new(Uuid::nil(), None)
+    }
+
+    pub fn report(&self, fraction: f32) {
+        let Some(callback) = &self.callback else {
+            return;
+        };
+        if fraction.is_nan() {
+            return;
+        }
+        let fraction = fraction.clamp(0.0, 1.0);
+        {
+            let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+            if last.is_some_and(|last| fraction <= last) {
+                return;
+            }
+            *last = Some(fraction);
+        }
+        callback(self.job_id, fraction);
+    }
+
+    /// Reports `done` of `total` steps, scaled into `span`, e.g. the loading
+    /// share of a job that still has outputs to write.
+    pub fn report_steps(&self, done: usize, total: usize, span: std::ops::Range<f32>) {
+        let share = if total == 0 {
+            1.0
+        } else {
+            done as f32 / total as f32
+        };
+        self.report(span.start + (span.end - span.start) * share);
+    }
+
+    pub fn finish(&self) {
+        self.report(1.0);
+    }
+}
+
+impl std::fmt::Debug for JobProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobProgress")
+            .field("job_id", &self.job_id)
+            .field("enabled", &self.callback.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stands in for an analyzer: reads tiles one by one, then writes its
+    /// outputs, reporting as it goes, sometimes out of step.
+    fn fake_analyzer(tiles: usize, progress: &JobProgress) {
+        progress.report(0.0);
+        for tile in 0..tiles {
+            progress.report_steps(tile + 1, tiles, 0.0..0.8);
+            // A late, stale report from a worker must not move progress back.
+            progress.report_steps(tile, tiles, 0.0..0.8);
+        }
+        progress.report(0.9);
+        progress.finish();
+    }
+
+    #[test]
+    fn fake_analyzer_progress_increases_to_one() {
+        let job_id = Uuid::new_v4();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let callback: ProgressCallback = {
+            let seen = seen.clone();
+            Arc::new(move |id, fraction| {
+                assert_eq!(id, job_id);
+                seen.lock().unwrap().push(fraction);
+            })
+        };
+
+        fake_analyzer(5, &JobProgress::new(job_id, Some(callback)));
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 8);
+        assert!(seen.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(seen.last(), Some(&1.0));
+    }
+}
@@ -0,0 +1,432 @@
+use crate::localization::{fill_placeholders, MessageArg, MessageFormatter};
+use crate::{AnalysisZone, Priority, Recommendation, RecommendationCategory};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Rules file read from the service working directory; the built-in rules
+/// apply when it is absent.
+pub const RECOMMENDATION_RULES_FILE: &str = "recommendation_rules.json";
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RecommendationRuleError {
+    #[error("rule {index} has an empty metric")]
+    EmptyMetric { index: usize },
+    #[error("rule {index} threshold must be finite, got {threshold}")]
+    InvalidThreshold { index: usize, threshold: f32 },
+    #[error("rule {index} confidence must be between 0 and 1, got {confidence}")]
+    InvalidConfidence { index: usize, confidence: f32 },
+    #[error("rule {index} has an empty title template")]
+    EmptyTitle { index: usize },
+    #[error("failed to load recommendation rules: {reason}")]
+    Load { reason: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleComparator {
+    #[serde(rename = "<")]
+    LessThan,
+    #[serde(rename = "<=")]
+    LessOrEqual,
+    #[serde(rename = ">")]
+    GreaterThan,
+    #[serde(rename = ">=")]
+    GreaterOrEqual,
+    #[serde(rename = "==")]
+    Equal,
+}
+
+impl RuleComparator {
+    pub fn matches(self, value: f32, threshold: f32) -> bool {
+        match self {
+            Self::LessThan => value < threshold,
+            Self::LessOrEqual => value <= threshold,
+            Self::GreaterThan => value > threshold,
+            Self::GreaterOrEqual => value >= threshold,
+            Self::Equal => (value - threshold).abs() <= f32::EPSILON,
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::LessThan => "<",
+            Self::LessOrEqual => "<=",
+            Self::GreaterThan => ">",
+            Self::GreaterOrEqual => ">=",
+            Self::Equal => "==",
+        }
+    }
+}
+
+/// Recommendation text. `{zone_id}`, `{metric}`, `{value}`, `{threshold}`,
+/// `{comparator}`, `{area_m2}` and any other value name of the zone are
+/// replaced when the rule fires; unknown placeholders are kept verbatim.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecommendationTemplate {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub action_items: Vec<String>,
+    /// Catalog key for localized text: `<key>.title`, `<key>.description`
+    /// and `<key>.actions` (one action per line) replace the fields above
+    /// when evaluated for a locale.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_key: Option<String>,
+}
+
+/// Fires once for every zone whose `metric` value satisfies
+/// `comparator threshold`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecommendationRule {
+    pub metric: String,
+    pub comparator: RuleComparator,
+    pub threshold: f32,
+    pub category: RecommendationCategory,
+    pub priority: Priority,
+    pub template: RecommendationTemplate,
+    /// Fixed confidence for the recommendation; the caller's confidence is
+    /// used when unset.
+    #[serde(default)]
+    pub confidence: Option<f32>,
+}
+
+impl RecommendationRule {
+    pub fn fires_on(&self, zone: &AnalysisZone) -> bool {
+        zone.values.get(&self.metric).is_some_and(|value| {
+            value.is_finite() && self.comparator.matches(*value, self.threshold)
+        })
+    }
+
+    fn recommend(&self, zone: &AnalysisZone, default_confidence: f32) -> Recommendation {
+        let render = |text: &str| {
+            fill_placeholders(text, |name| {
+                placeholder_arg(name, self, zone).map(|arg| arg.plain())
+            })
+        };
+        self.recommendation(
+            zone,
+            default_confidence,
+            render(&self.template.title),
+            render(&self.template.description),
+            self.template
+                .action_items
+                .iter()
+                .map(|item| render(item))
+                .collect(),
+        )
+    }
+
+    fn recommend_localized(
+        &self,
+        zone: &AnalysisZone,
+        default_confidence: f32,
+        formatter: &mut MessageFormatter<'_>,
+    ) -> Recommendation {
+        let Some(key) = &self.template.message_key else {
+            return self.recommend(zone, default_confidence);
+        };
+        let fallback = self.recommend(zone, default_confidence);
+        let args = |name: &str| placeholder_arg(name, self, zone);
+        let title = formatter
+            .message(&format!("{key}.title"), args)
+            .unwrap_or(fallback.title);
+        let description = formatter
+            .message(&format!("{key}.description"), args)
+            .unwrap_or(fallback.description);
+        let action_items = formatter
+            .message(&format!("{key}.actions"), args)
+            .map(|actions| actions.lines().map(str::to_string).collect())
+            .unwrap_or(fallback.action_items);
+        self.recommendation(zone, default_confidence, title, description, action_items)
+    }
+
+    fn recommendation(
+        &self,
+        zone: &AnalysisZone,
+        default_confidence: f32,
+        title: String,
+        description: String,
+        action_items: Vec<String>,
+    ) -> Recommendation {
+        Recommendation {
+            category: self.category.clone(),
+            priority: self.priority.clone(),
+            title,
+            description,
+            action_items,
+            affected_areas: vec![zone.clone()],
+            confidence_score: self
+                .confidence
+                .unwrap_or(default_confidence)
+                .clamp(0.0, 1.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecommendationRuleSet {
+    pub rules: Vec<RecommendationRule>,
+}
+
+impl Default for RecommendationRuleSet {
+    /// The crop-health rule the service shipped with before rules were
+    /// configurable.
+    fn default() -> Self {
+        Self {
+            rules: vec![RecommendationRule {
+                metric: "health_score".to_string(),
+                comparator: RuleComparator::LessThan,
+                threshold: 0.55,
+                category: RecommendationCategory::Irrigation,
+                priority: Priority::Medium,
+                template: RecommendationTemplate {
+                    title: "Review potential crop-stress signal".to_string(),
+                    description: "Deterministic health indicators indicate review is advised."
+                        .to_string(),
+                    action_items: vec![
+                        "Prioritize inspection of lower scoring zones".to_string(),
+                        "Confirm recent irrigation and irrigation scheduling logs are complete"
+                            .to_string(),
+                    ],
+                    message_key: Some("crop_stress_review".to_string()),
+                },
+                confidence: None,
+            }],
+        }
+    }
+}
+
+impl RecommendationRuleSet {
+    pub fn new(rules: Vec<RecommendationRule>) -> Result<Self, RecommendationRuleError> {
+        let rule_set = Self { rules };
+        rule_set.validate()?;
+        Ok(rule_set)
+    }
+
+    /// Reads [`RECOMMENDATION_RULES_FILE`] from `directory`, falling back to
+    /// the built-in rules when the file does not exist.
+    pub fn load(directory: &Path) -> Result<Self, RecommendationRuleError> {
+        let path = directory.join(RECOMMENDATION_RULES_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read(&path).map_err(load_error)?;
+        Self::from_json(&content)
+    }
+
+    pub fn from_json(content: &[u8]) -> Result<Self, RecommendationRuleError> {
+        let rule_set = serde_json::from_slice::<Self>(content).map_err(load_error)?;
+        rule_set.validate()?;
+        Ok(rule_set)
+    }
+
+    pub fn validate(&self) -> Result<(), RecommendationRuleError> {
+        for (index, rule) in self.rules.iter().enumerate() {
+            if rule.metric.trim().is_empty() {
+                return Err(RecommendationRuleError::EmptyMetric { index });
+            }
+            if !rule.threshold.is_finite() {
+                return Err(RecommendationRuleError::InvalidThreshold {
+                    index,
+                    threshold: rule.threshold,
+                });
+            }
+            if let Some(confidence) = rule.confidence {
+                if !(0.0..=1.0).contains(&confidence) {
+                    return Err(RecommendationRuleError::InvalidConfidence { index, confidence });
+                }
+            }
+            if rule.template.title.trim().is_empty() {
+                return Err(RecommendationRuleError::EmptyTitle { index });
+            }
+        }
+        Ok(())
+    }
+
+    /// Recommendations for every rule/zone pair that fires, in rule order.
+    /// Zones without the rule's metric never match it.
+    pub fn evaluate(&self, zones: &[AnalysisZone], default_confidence: f32) -> Vec<Recommendation> {
+        self.rules
+            .iter()
+            .flat_map(|rule| {
+                zones
+                    .iter()
+                    .filter(|zone| rule.fires_on(zone))
+                    .map(move |zone| rule.recommend(zone, default_confidence))
+            })
+            .collect()
+    }
+
+    /// [`Self::evaluate`] with the text of rules that carry a message key
+    /// rendered in the formatter's locale; numbers use its separators.
+    pub fn evaluate_localized(
+        &self,
+        zones: &[AnalysisZone],
+        default_confidence: f32,
+        formatter: &mut MessageFormatter<'_>,
+    ) -> Vec<Recommendation> {
+        let mut recommendations = Vec::new();
+        for rule in &self.rules {
+            for zone in zones.iter().filter(|zone| rule.fires_on(zone)) {
+                recommendations.push(rule.recommend_localized(zone, default_confidence, formatter));
+            }
+        }
+        recommendations
+    }
+}
+
+fn load_error(error: impl std::fmt::Display) -> RecommendationRuleError {
+    RecommendationRuleError::Load {
+        reason: error.to_string(),
+    }
+}
+
+fn placeholder_arg(
+    name: &str,
+    rule: &RecommendationRule,
+    zone: &AnalysisZone,
+) -> Option<MessageArg> {
+    match name {
+        "zone_id" => Some(MessageArg::Text(zone.id.clone())),
+        "metric" => Some(MessageArg::Text(rule.metric.clone())),
+        "value" => zone
+            .values
+            .get(&rule.metric)
+            .map(|value| MessageArg::number(*value, 3)),
+        "threshold" => Some(MessageArg::number(rule.threshold, 3)),
+        "comparator" => Some(MessageArg::Text(rule.comparator.symbol().to_string())),
+        "area_m2" => Some(MessageArg::number(zone.area_m2, 0)),
+        other => zone
+            .values
+            .get(other)
+            .map(|value| MessageArg::number(*value, 3)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::localization::Localizer;
+    use std::collections::HashMap;
+
+    fn zone(id: &str, ndvi_mean: f32) -> AnalysisZone {
+        AnalysisZone {
+            id: id.to_string(),
+            boundary: Vec::new(),
+            area_m2: 2500.0,
+            values: HashMap::from([("ndvi_mean".to_string(), ndvi_mean)]),
+            classification: None,
+        }
+    }
+
+    #[test]
+    fn low_ndvi_rule_fires_on_the_stressed_zone_only() {
+        let rules = RecommendationRuleSet::from_json(
+            br#"{"rules": [{
+                "metric": "ndvi_mean",
+                "comparator": "<",
+                "threshold": 0.4,
+                "category": "Irrigation",
+                "priority": "High",
+                "template": {
+                    "title": "Irrigate zone {zone_id}",
+                    "description": "{metric} is {value} ({comparator} {threshold}) over {area_m2} m2.",
+                    "action_items": ["Check emitters in {zone_id}", "Keep {unknown} as is"]
+                },
+                "confidence": 0.8
+            }]}"#,
+        )
+        .unwrap();
+
+        let recommendations = rules.evaluate(&[zone("north", 0.32), zone("south", 0.71)], 0.5);
+        assert_eq!(recommendations.len(), 1);
+        let recommendation = &recommendations[0];
+        assert!(matches!(
+            recommendation.category,
+            RecommendationCategory::Irrigation
+        ));
+        assert!(matches!(recommendation.priority, Priority::High));
+        assert_eq!(recommendation.title, "Irrigate zone north");
+        assert_eq!(
+            recommendation.description,
+            "ndvi_mean is 0.320 (< 0.400) over 2500 m2."
+        );
+        assert_eq!(
+            recommendation.action_items,
+            vec!["Check emitters in north", "Keep {unknown} as is"]
+        );
+        assert_eq!(recommendation.affected_areas[0].id, "north");
+        assert_eq!(recommendation.confidence_score, 0.8);
+
+        assert!(rules.evaluate(&[zone("south", 0.71)], 0.5).is_empty());
+        let mut blank_metric = rules.rules[0].clone();
+        blank_metric.metric = " ".to_string();
+        assert_eq!(
+            RecommendationRuleSet::new(vec![blank_metric]).unwrap_err(),
+            RecommendationRuleError::EmptyMetric { index: 0 }
+        );
+    }
+
+    #[test]
+    fn keyed_rules_render_in_the_requested_locale() {
+        let rules = RecommendationRuleSet::from_json(
+            br#"{"rules": [{
+                "metric": "ndvi_mean",
+                "comparator": "<",
+                "threshold": 0.4,
+                "category": "Fertilization",
+                "priority": "High",
+                "template": {"title": "Low vigor in {zone_id}", "message_key": "low_vigor"}
+            }]}"#,
+        )
+        .unwrap();
+        let mut zones = vec![zone("north", 0.325)];
+        zones[0].area_m2 = 12_500.0;
+        let localizer = Localizer::default();
+
+        let mut english = localizer.formatter("en");
+        let recommendations = rules.evaluate_localized(&zones, 0.5, &mut english);
+        assert_eq!(recommendations[0].title, "Low vigor in zone north");
+        assert_eq!(
+            recommendations[0].description,
+            "ndvi_mean is 0.325 against a threshold of 0.400 over 12,500 m²."
+        );
+        assert_eq!(recommendations[0].action_items.len(), 2);
+        assert!(english.warnings().is_empty());
+
+        let mut spanish = localizer.formatter("es");
+        let recommendations = rules.evaluate_localized(&zones, 0.5, &mut spanish);
+        assert_eq!(recommendations[0].title, "Bajo vigor en la zona north");
+        assert_eq!(
+            recommendations[0].description,
+            "ndvi_mean es 0,325 frente a un umbral de 0,400 en 12.500 m²."
+        );
+        assert_eq!(
+            recommendations[0].action_items[1],
+            "Comparar north con el vuelo anterior antes de tratar"
+        );
+        assert!(spanish.warnings().is_empty());
+
+        // Rules without a key, and the unlocalized path, keep the template.
+        assert_eq!(rules.evaluate(&zones, 0.5)[0].title, "Low vigor in north");
+    }
+
+    #[test]
+    fn built_in_rule_text_matches_its_english_catalog_entry() {
+        let zone = AnalysisZone {
+            values: HashMap::from([("health_score".to_string(), 0.3)]),
+            ..zone("field", 0.3)
+        };
+        let rules = RecommendationRuleSet::default();
+        let localizer = Localizer::default();
+        let mut english = localizer.formatter("en");
+
+        let plain = rules.evaluate(std::slice::from_ref(&zone), 0.7);
+        let localized = rules.evaluate_localized(&[zone], 0.7, &mut english);
+        assert_eq!(plain[0].title, localized[0].title);
+        assert_eq!(plain[0].description, localized[0].description);
+        assert_eq!(plain[0].action_items, localized[0].action_items);
+        assert!(english.warnings().is_empty());
+    }
+}
@@ -1,14 +1,36 @@
+use crate::grower_report::render_pdf_lines;
+use crate::localization::{
+    LocalizationWarning, Localizer, MessageArg, MessageFormatter, DEFAULT_LOCALE,
+};
+use crate::report_sections::{
+    ChartRenderer, RenderedChart, SectionCache, SectionFragment, SvgChartRenderer,
+};
+use crate::work_orders::{WorkOrder, WorkOrderStatus};
+use crate::{Priority, Recommendation};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use shared::data_quality::{
+    FieldStrip, QualityFlagType, QualitySeverity, SessionQualityAssessment,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 use uuid::Uuid;
 
+/// Sections rendered at once unless set with
+/// [`ReportGenerator::with_render_parallelism`].
+const DEFAULT_RENDER_PARALLELISM: usize = 4;
+
 /// Report generation system for agricultural drone data analysis
 pub struct ReportGenerator {
     config: ReportConfig,
     template_cache: HashMap<String, ReportTemplate>,
     generated_reports: HashMap<Uuid, GeneratedReport>,
+    localizer: Localizer,
+    chart_renderer: Arc<dyn ChartRenderer>,
+    section_cache: Option<SectionCache>,
+    render_parallelism: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,6 +93,8 @@ pub enum SectionType {
     SensorData,
     Analysis,
     Recommendations,
+    WorkOrders,
+    DataQuality,
     RawData,
     Appendix,
     Maps,
@@ -97,6 +121,7 @@ pub enum DataSource {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VisualizationConfig {
     pub chart_type: ChartType,
+    /// Name of a palette in [`shared::palette`].
     pub color_scheme: String,
     pub dimensions: (u32, u32), // width, height
     pub include_legend: bool,
@@ -156,6 +181,9 @@ pub struct ReportRequest {
     pub delivery_options: DeliveryOptions,
     pub requested_by: String,
     pub requested_at: DateTime<Utc>,
+    /// Locale for section titles and recommendation text; English when unset.
+    #[serde(default)]
+    pub locale: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -167,6 +195,17 @@ pub struct ReportDataContext {
     pub analysis_parameters: HashMap<String, serde_json::Value>,
     pub include_historical_data: bool,
     pub comparative_missions: Vec<Uuid>,
+    /// Rendered into the recommendations section.
+    #[serde(default)]
+    pub recommendations: Vec<Recommendation>,
+    /// Quality assessments of the flight sessions, rendered into the
+    /// data-quality section.
+    #[serde(default)]
+    pub data_quality: Vec<SessionQualityAssessment>,
+    /// Work orders of the reported fields; the open ones are listed per
+    /// field.
+    #[serde(default)]
+    pub work_orders: Vec<WorkOrder>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -229,6 +268,10 @@ pub struct GeneratedReport {
     pub metadata: ReportMetadata,
     pub status: ReportStatus,
     pub error_messages: Vec<String>,
+    /// Locale the report text was rendered in after any fallback.
+    pub locale: String,
+    #[serde(default)]
+    pub localization_warnings: Vec<LocalizationWarning>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -240,6 +283,9 @@ pub struct ReportMetadata {
     pub quality_score: f32,
     pub sections_included: Vec<String>,
     pub visualizations_count: u32,
+    /// Ids of the sections reused from the section cache, in report order.
+    #[serde(default)]
+    pub cached_sections: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -257,6 +303,10 @@ impl ReportGenerator {
             config,
             template_cache: HashMap::new(),
             generated_reports: HashMap::new(),
+            localizer: Localizer::default(),
+            chart_renderer: Arc::new(SvgChartRenderer),
+            section_cache: None,
+            render_parallelism: DEFAULT_RENDER_PARALLELISM,
         };
 
         // Load default templates
@@ -264,6 +314,33 @@ impl ReportGenerator {
         generator
     }
 
+    pub fn with_localizer(mut self, localizer: Localizer) -> Self {
+        self.set_localizer(localizer);
+        self
+    }
+
+    pub fn set_localizer(&mut self, localizer: Localizer) {
+        self.localizer = localizer;
+    }
+
+    /// Reuses sections rendered by earlier reports from `cache` when their
+    /// input is unchanged.
+    pub fn with_section_cache(mut self, cache: SectionCache) -> Self {
+        self.section_cache = Some(cache);
+        self
+    }
+
+    pub fn with_chart_renderer(mut self, renderer: Arc<dyn ChartRenderer>) -> Self {
+        self.chart_renderer = renderer;
+        self
+    }
+
+    /// Renders at most `parallelism` sections at once; 0 is taken as 1.
+    pub fn with_render_parallelism(mut self, parallelism: usize) -> Self {
+        self.render_parallelism = parallelism.max(1);
+        self
+    }
+
     fn load_default_templates(&mut self) {
         // Create a comprehensive agricultural report template
         let agricultural_template = ReportTemplate {
@@ -289,7 +366,7 @@ impl ReportGenerator {
                     data_sources: vec![DataSource::FlightLogs, DataSource::MissionPlanning],
                     visualization_config: Some(VisualizationConfig {
                         chart_type: ChartType::GeospatialMap,
-                        color_scheme: "terrain".to_string(),
+                        color_scheme: "cividis".to_string(),
                         dimensions: (800, 600),
                         include_legend: true,
                         title: Some("Flight Path and Coverage Area".to_string()),
@@ -305,7 +382,7 @@ impl ReportGenerator {
                     data_sources: vec![DataSource::NDVIAnalysis, DataSource::ImageAnalysis],
                     visualization_config: Some(VisualizationConfig {
                         chart_type: ChartType::Heatmap,
-                        color_scheme: "vegetation".to_string(),
+                        color_scheme: "viridis".to_string(),
                         dimensions: (800, 600),
                         include_legend: true,
                         title: Some("NDVI Distribution".to_string()),
@@ -321,7 +398,7 @@ impl ReportGenerator {
                     data_sources: vec![DataSource::ThermalAnalysis],
                     visualization_config: Some(VisualizationConfig {
                         chart_type: ChartType::Heatmap,
-                        color_scheme: "thermal".to_string(),
+                        color_scheme: "cividis".to_string(),
                         dimensions: (800, 600),
                         include_legend: true,
                         title: Some("Temperature Distribution".to_string()),
@@ -337,6 +414,24 @@ impl ReportGenerator {
                     data_sources: vec![DataSource::Analysis],
                     visualization_config: None,
                 },
+                ReportSection {
+                    section_id: "work_orders".to_string(),
+                    title: "Open Work Orders".to_string(),
+                    section_type: SectionType::WorkOrders,
+                    order: 6,
+                    required: false,
+                    data_sources: vec![DataSource::Analysis],
+                    visualization_config: None,
+                },
+                ReportSection {
+                    section_id: "data_quality".to_string(),
+                    title: "Data Quality".to_string(),
+                    section_type: SectionType::DataQuality,
+                    order: 7,
+                    required: false,
+                    data_sources: vec![DataSource::FlightLogs, DataSource::SensorReadings],
+                    visualization_config: None,
+                },
             ],
             styling: ReportStyling {
                 color_primary: "#2E7D32".to_string(),   // Green
@@ -388,7 +483,7 @@ impl ReportGenerator {
                     data_sources: vec![DataSource::SensorReadings, DataSource::Analysis],
                     visualization_config: Some(VisualizationConfig {
                         chart_type: ChartType::BarChart,
-                        color_scheme: "default".to_string(),
+                        color_scheme: "okabe-ito".to_string(),
                         dimensions: (600, 400),
                         include_legend: true,
                         title: Some("Mission Metrics Overview".to_string()),
@@ -440,7 +535,7 @@ impl ReportGenerator {
         let collected_data = self.collect_report_data(&request).await?;
 
         // Generate report content
-        let report_content = self
+        let (report_content, cached_sections) = self
             .generate_report_content(&template, &collected_data, &request)
             .await?;
 
@@ -457,6 +552,9 @@ impl ReportGenerator {
         }
 
         let processing_time = start_time.elapsed().as_millis() as u64;
+        for warning in &report_content.localization_warnings {
+            tracing::warn!("Report {} text: {}", request.id, warning);
+        }
 
         let generated_report = GeneratedReport {
             id: Uuid::new_v4(),
@@ -476,15 +574,22 @@ impl ReportGenerator {
                     .collect(),
                 processing_time_ms: processing_time,
                 quality_score: self.calculate_report_quality(&report_content),
-                sections_included: template.sections.iter().map(|s| s.title.clone()).collect(),
+                sections_included: report_content
+                    .sections
+                    .iter()
+                    .map(|s| s.title.clone())
+                    .collect(),
                 visualizations_count: template
                     .sections
                     .iter()
                     .filter(|s| s.visualization_config.is_some())
                     .count() as u32,
+                cached_sections,
             },
             status: ReportStatus::Completed,
             error_messages: vec![],
+            locale: report_content.locale.clone(),
+            localization_warnings: report_content.localization_warnings.clone(),
         };
 
         // Handle delivery options
@@ -535,30 +640,99 @@ impl ReportGenerator {
         })
     }
 
+    /// Renders the template's sections in order. Sections do not depend on
+    /// each other, so up to `render_parallelism` of them render at once, and
+    /// a section whose resolved input is already in the section cache is
+    /// reused. Also returns the ids of the reused sections.
     async fn generate_report_content(
         &self,
-        _template: &ReportTemplate,
-        _data: &ReportData,
-        _request: &ReportRequest,
-    ) -> Result<ReportContent> {
-        // TODO: Implement report content generation
-        // This would process the template and data to create structured content
-        Ok(ReportContent {
-            sections: vec![],
-            visualizations: HashMap::new(),
-            metadata: HashMap::new(),
-        })
+        template: &ReportTemplate,
+        data: &ReportData,
+        request: &ReportRequest,
+    ) -> Result<(ReportContent, Vec<String>)> {
+        // TODO: Fill analysis sections from the collected data
+        let requested_locale = request.locale.as_deref().unwrap_or(DEFAULT_LOCALE);
+        let formatter = self.localizer.formatter(requested_locale);
+        let locale = formatter.locale().to_string();
+        let mut localization_warnings = formatter.into_warnings();
+        let catalogs = SectionCache::input_hash(&serde_json::to_value(
+            self.localizer.catalogs_for(requested_locale),
+        )?)?;
+
+        let mut sections = template.sections.clone();
+        sections.sort_by_key(|section| section.order);
+
+        let localizer = Arc::new(self.localizer.clone());
+        let context = Arc::new(request.data_context.clone());
+        let semaphore = Arc::new(Semaphore::new(self.render_parallelism));
+        let mut jobs = Vec::with_capacity(sections.len());
+        for section in sections {
+            let job = SectionJob {
+                input: section_input(
+                    &section,
+                    data,
+                    &context,
+                    requested_locale,
+                    &catalogs,
+                    self.config.include_visualizations,
+                )?,
+                section,
+                context: context.clone(),
+                locale: requested_locale.to_string(),
+                include_visualizations: self.config.include_visualizations,
+            };
+            let permit = semaphore.clone().acquire_owned().await?;
+            let localizer = localizer.clone();
+            let renderer = self.chart_renderer.clone();
+            let cache = self.section_cache.clone();
+            jobs.push(tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                job.run(&localizer, renderer.as_ref(), cache.as_ref())
+            }));
+        }
+
+        let mut fragments = Vec::with_capacity(jobs.len());
+        let mut cached_sections = Vec::new();
+        for job in jobs {
+            let (fragment, cached) = job.await??;
+            if cached {
+                cached_sections.push(fragment.section_id.clone());
+            }
+            for warning in &fragment.localization_warnings {
+                if !localization_warnings.contains(warning) {
+                    localization_warnings.push(warning.clone());
+                }
+            }
+            fragments.push(fragment);
+        }
+
+        let content = ReportContent {
+            sections: fragments,
+            locale,
+            localization_warnings,
+        };
+        Ok((content, cached_sections))
     }
 
     async fn export_report_format(
         &self,
-        _content: &ReportContent,
+        content: &ReportContent,
         format: &OutputFormat,
         request: &ReportRequest,
     ) -> Result<(String, u64)> {
-        // TODO: Implement format-specific export logic
-        let file_path = format!("/tmp/report_{}_{:?}.ext", request.id, format);
-        let file_size = 1024; // Placeholder
+        let (file_path, file_size) = match format {
+            OutputFormat::HTML | OutputFormat::PDF => {
+                let (extension, bytes) = match format {
+                    OutputFormat::HTML => ("html", render_html(content, &request.title)),
+                    _ => ("pdf", render_pdf(content, &request.title)),
+                };
+                let path = std::env::temp_dir().join(format!("report_{}.{extension}", request.id));
+                tokio::fs::write(&path, &bytes).await?;
+                (path.display().to_string(), bytes.len() as u64)
+            }
+            // TODO: Implement the remaining format-specific exports
+            _ => (format!("/tmp/report_{}_{:?}.ext", request.id, format), 1024),
+        };
 
         tracing::info!(
             "Exported report to {} format: {}",
@@ -632,39 +806,413 @@ struct ReportData {
 
 #[derive(Debug, Clone)]
 struct ReportContent {
-    sections: Vec<SectionContent>,
-    visualizations: HashMap<String, VisualizationData>,
-    metadata: HashMap<String, String>,
+    sections: Vec<SectionFragment>,
+    locale: String,
+    localization_warnings: Vec<LocalizationWarning>,
 }
 
-#[derive(Debug, Clone)]
-struct SectionContent {
-    section_id: String,
-    title: String,
-    content: String,
-    tables: Vec<TableData>,
-    charts: Vec<ChartData>,
+/// One section to render, with the input it is cached under.
+struct SectionJob {
+    section: ReportSection,
+    input: serde_json::Value,
+    context: Arc<ReportDataContext>,
+    locale: String,
+    include_visualizations: bool,
 }
 
-#[derive(Debug, Clone)]
-struct TableData {
-    headers: Vec<String>,
-    rows: Vec<Vec<String>>,
-    title: Option<String>,
+impl SectionJob {
+    /// The section's fragment, and whether it was taken from `cache`.
+    fn run(
+        self,
+        localizer: &Localizer,
+        renderer: &dyn ChartRenderer,
+        cache: Option<&SectionCache>,
+    ) -> Result<(SectionFragment, bool)> {
+        let input_hash = SectionCache::input_hash(&self.input)?;
+        if let Some(fragment) =
+            cache.and_then(|cache| cache.get(&self.section.section_id, &input_hash))
+        {
+            return Ok((fragment, true));
+        }
+
+        let fragment = self.render(localizer, renderer)?;
+        if let Some(cache) = cache {
+            if let Err(error) = cache.put(&input_hash, &fragment) {
+                tracing::warn!(
+                    "Failed to cache report section {}: {}",
+                    fragment.section_id,
+                    error
+                );
+            }
+        }
+        Ok((fragment, false))
+    }
+
+    fn render(
+        &self,
+        localizer: &Localizer,
+        renderer: &dyn ChartRenderer,
+    ) -> Result<SectionFragment> {
+        let section = &self.section;
+        let mut formatter = localizer.formatter(&self.locale);
+        let key = format!("report.section.{}", section.section_id);
+        let title = if formatter.knows(&key) {
+            formatter.message(&key, |_| None)
+        } else {
+            None
+        }
+        .unwrap_or_else(|| section.title.clone());
+        let content = match section.section_type {
+            SectionType::Recommendations => {
+                recommendation_lines(&self.context.recommendations, &mut formatter).join("\n")
+            }
+            SectionType::WorkOrders => {
+                work_order_lines(&self.context.work_orders, &mut formatter).join("\n")
+            }
+            SectionType::DataQuality => {
+                data_quality_lines(&self.context.data_quality, &mut formatter).join("\n")
+            }
+            _ => String::new(),
+        };
+
+        let mut charts = Vec::new();
+        if let Some(config) = section
+            .visualization_config
+            .as_ref()
+            .filter(|_| self.include_visualizations)
+        {
+            let chart_data = serde_json::json!({
+                "data": self.input["data"],
+                "parameters": self.input["parameters"],
+            });
+            charts.push(RenderedChart {
+                alt_text: config.title.clone().unwrap_or_else(|| title.clone()),
+                svg: renderer.render(&section.section_id, config, &chart_data)?,
+            });
+        }
+
+        Ok(SectionFragment {
+            section_id: section.section_id.clone(),
+            title,
+            content,
+            tables: vec![],
+            charts,
+            localization_warnings: formatter.into_warnings(),
+        })
+    }
 }
 
-#[derive(Debug, Clone)]
-struct ChartData {
-    chart_type: ChartType,
-    data: serde_json::Value,
-    config: VisualizationConfig,
+/// Everything `section` renders from: its template entry, the collected
+/// data of its sources, the request data its type lists, and the
+/// `analysis_parameters` entry under its id. Equal inputs render equal
+/// fragments.
+fn section_input(
+    section: &ReportSection,
+    data: &ReportData,
+    context: &ReportDataContext,
+    locale: &str,
+    catalogs: &str,
+    include_visualizations: bool,
+) -> Result<serde_json::Value> {
+    let mut sources = serde_json::Map::new();
+    for source in &section.data_sources {
+        let collected = match source {
+            DataSource::FlightLogs => &data.flight_data,
+            DataSource::SensorReadings => &data.sensor_data,
+            DataSource::WeatherData | DataSource::MissionPlanning | DataSource::Custom(_) => {
+                &data.metadata
+            }
+            _ => &data.analysis_results,
+        };
+        sources.insert(format!("{source:?}"), serde_json::to_value(collected)?);
+    }
+    let listed = match section.section_type {
+        SectionType::Recommendations => serde_json::to_value(&context.recommendations)?,
+        SectionType::WorkOrders => serde_json::to_value(&context.work_orders)?,
+        SectionType::DataQuality => serde_json::to_value(&context.data_quality)?,
+        _ => serde_json::Value::Null,
+    };
+
+    Ok(serde_json::json!({
+        "section": section,
+        "locale": locale,
+        "catalogs": catalogs,
+        "include_visualizations": include_visualizations,
+        "data": sources,
+        "listed": listed,
+        "parameters": context.analysis_parameters.get(&section.section_id),
+    }))
 }
 
-#[derive(Debug, Clone)]
-struct VisualizationData {
-    image_path: String,
-    alt_text: String,
-    caption: Option<String>,
+/// One line per fact of each recommendation, labelled in the formatter's
+/// locale.
+fn recommendation_lines(
+    recommendations: &[Recommendation],
+    formatter: &mut MessageFormatter<'_>,
+) -> Vec<String> {
+    if recommendations.is_empty() {
+        return formatter
+            .message("report.recommendations.empty", |_| None)
+            .into_iter()
+            .collect();
+    }
+
+    let mut lines = Vec::new();
+    for recommendation in recommendations {
+        lines.push(recommendation.title.clone());
+        if !recommendation.description.is_empty() {
+            lines.push(recommendation.description.clone());
+        }
+        let priority = formatter
+            .message(priority_key(&recommendation.priority), |_| None)
+            .unwrap_or_default();
+        lines.extend(formatter.message("report.recommendation.priority", |name| {
+            (name == "priority").then(|| MessageArg::Text(priority.clone()))
+        }));
+        lines.extend(
+            formatter.message("report.recommendation.confidence", |name| {
+                (name == "confidence")
+                    .then(|| MessageArg::number(recommendation.confidence_score * 100.0, 0))
+            }),
+        );
+        let area_m2 = recommendation
+            .affected_areas
+            .iter()
+            .map(|zone| f64::from(zone.area_m2))
+            .sum::<f64>();
+        if area_m2 > 0.0 {
+            lines.extend(
+                formatter.message("report.recommendation.affected_area", |name| {
+                    (name == "area_m2").then(|| MessageArg::number(area_m2, 0))
+                }),
+            );
+        }
+        lines.extend(
+            recommendation
+                .action_items
+                .iter()
+                .map(|action| format!("- {action}")),
+        );
+    }
+    lines
+}
+
+/// Open work orders grouped by field, soonest due first.
+fn work_order_lines(
+    work_orders: &[WorkOrder],
+    formatter: &mut MessageFormatter<'_>,
+) -> Vec<String> {
+    let mut by_field: BTreeMap<&str, Vec<&WorkOrder>> = BTreeMap::new();
+    for work_order in work_orders
+        .iter()
+        .filter(|work_order| work_order.status.is_open())
+    {
+        by_field
+            .entry(work_order.field_id.as_str())
+            .or_default()
+            .push(work_order);
+    }
+    if by_field.is_empty() {
+        return formatter
+            .message("report.work_orders.empty", |_| None)
+            .into_iter()
+            .collect();
+    }
+
+    let unscheduled = formatter
+        .message("report.work_orders.no_due_date", |_| None)
+        .unwrap_or_default();
+    let unassigned = formatter
+        .message("report.work_orders.unassigned", |_| None)
+        .unwrap_or_default();
+    let mut lines = Vec::new();
+    for (field_id, mut orders) in by_field {
+        orders.sort_by_key(|work_order| (work_order.due_date.is_none(), work_order.due_date));
+        lines.extend(
+            formatter.message("report.work_orders.field", |name| match name {
+                "field_id" => Some(MessageArg::Text(field_id.to_string())),
+                "count" => Some(MessageArg::number(orders.len() as f64, 0)),
+                _ => None,
+            }),
+        );
+        for work_order in orders {
+            let status = formatter
+                .message(work_order_status_key(work_order.status), |_| None)
+                .unwrap_or_default();
+            let priority = formatter
+                .message(priority_key(&work_order.priority), |_| None)
+                .unwrap_or_default();
+            let due_date = work_order
+                .due_date
+                .map(|due| due.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| unscheduled.clone());
+            let assignee = work_order
+                .assignee
+                .clone()
+                .unwrap_or_else(|| unassigned.clone());
+            lines.extend(
+                formatter.message("report.work_orders.item", |name| match name {
+                    "title" => Some(MessageArg::Text(work_order.title.clone())),
+                    "status" => Some(MessageArg::Text(status.clone())),
+                    "priority" => Some(MessageArg::Text(priority.clone())),
+                    "due_date" => Some(MessageArg::Text(due_date.clone())),
+                    "assignee" => Some(MessageArg::Text(assignee.clone())),
+                    _ => None,
+                }),
+            );
+        }
+    }
+    lines
+}
+
+fn data_quality_lines(
+    assessments: &[SessionQualityAssessment],
+    formatter: &mut MessageFormatter<'_>,
+) -> Vec<String> {
+    if assessments.is_empty() {
+        return formatter
+            .message("report.data_quality.empty", |_| None)
+            .into_iter()
+            .collect();
+    }
+
+    let mut lines = Vec::new();
+    for assessment in assessments {
+        lines.extend(
+            formatter.message("report.data_quality.session", |name| match name {
+                "session_id" => Some(MessageArg::Text(assessment.session_id.to_string())),
+                "score" => Some(MessageArg::number(assessment.score, 0)),
+                _ => None,
+            }),
+        );
+        for flag in &assessment.flags {
+            let severity = formatter
+                .message(severity_key(flag.severity), |_| None)
+                .unwrap_or_default();
+            let flag_name = formatter
+                .message(flag_type_key(flag.flag_type), |_| None)
+                .unwrap_or_default();
+            let action = match flag.area {
+                Some(area) => {
+                    let strip = formatter
+                        .message(strip_key(area), |_| None)
+                        .unwrap_or_default();
+                    formatter.message("report.data_quality.refly", |name| {
+                        (name == "strip").then(|| MessageArg::Text(strip.clone()))
+                    })
+                }
+                None => formatter.message("report.data_quality.suggestion", |name| {
+                    (name == "suggestion").then(|| MessageArg::Text(flag.suggestion.clone()))
+                }),
+            }
+            .unwrap_or_default();
+            lines.extend(
+                formatter.message("report.data_quality.flag", |name| match name {
+                    "severity" => Some(MessageArg::Text(severity.clone())),
+                    "flag" => Some(MessageArg::Text(flag_name.clone())),
+                    "start" => Some(MessageArg::Text(flag.start.format("%H:%M:%S").to_string())),
+                    "end" => Some(MessageArg::Text(flag.end.format("%H:%M:%S").to_string())),
+                    "action" => Some(MessageArg::Text(action.clone())),
+                    _ => None,
+                }),
+            );
+        }
+    }
+    lines
+}
+
+fn severity_key(severity: QualitySeverity) -> &'static str {
+    match severity {
+        QualitySeverity::Info => "quality_severity.info",
+        QualitySeverity::Warning => "quality_severity.warning",
+        QualitySeverity::Critical => "quality_severity.critical",
+    }
+}
+
+fn flag_type_key(flag_type: QualityFlagType) -> &'static str {
+    match flag_type {
+        QualityFlagType::NoTelemetry => "quality_flag.no_telemetry",
+        QualityFlagType::TelemetryGap => "quality_flag.telemetry_gap",
+        QualityFlagType::ImageSpacingGap => "quality_flag.image_spacing_gap",
+        QualityFlagType::MissingBands => "quality_flag.missing_bands",
+        QualityFlagType::LidarDropout => "quality_flag.lidar_dropout",
+    }
+}
+
+fn strip_key(area: FieldStrip) -> &'static str {
+    match area {
+        FieldStrip::North => "field_strip.north",
+        FieldStrip::South => "field_strip.south",
+        FieldStrip::East => "field_strip.east",
+        FieldStrip::West => "field_strip.west",
+        FieldStrip::Center => "field_strip.center",
+    }
+}
+
+fn work_order_status_key(status: WorkOrderStatus) -> &'static str {
+    match status {
+        WorkOrderStatus::Open => "work_order_status.open",
+        WorkOrderStatus::InProgress => "work_order_status.in_progress",
+        WorkOrderStatus::Done => "work_order_status.done",
+        WorkOrderStatus::Verified => "work_order_status.verified",
+        WorkOrderStatus::Dismissed => "work_order_status.dismissed",
+    }
+}
+
+fn priority_key(priority: &Priority) -> &'static str {
+    match priority {
+        Priority::Low => "priority.low",
+        Priority::Medium => "priority.medium",
+        Priority::High => "priority.high",
+        Priority::Critical => "priority.critical",
+    }
+}
+
+fn render_html(content: &ReportContent, title: &str) -> Vec<u8> {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head><meta charset=\"utf-8\"><title>{}</title></head>\n<body>\n<h1>{}</h1>\n",
+        escape_html(&content.locale),
+        escape_html(title),
+        escape_html(title)
+    );
+    for section in &content.sections {
+        html.push_str(&format!(
+            "<section id=\"{}\">\n<h2>{}</h2>\n",
+            escape_html(&section.section_id),
+            escape_html(&section.title)
+        ));
+        for line in section.content.lines() {
+            html.push_str(&format!("<p>{}</p>\n", escape_html(line)));
+        }
+        for chart in &section.charts {
+            html.push_str(&format!(
+                "<figure>\n{}\n<figcaption>{}</figcaption>\n</figure>\n",
+                chart.svg,
+                escape_html(&chart.alt_text)
+            ));
+        }
+        html.push_str("</section>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html.into_bytes()
+}
+
+fn render_pdf(content: &ReportContent, title: &str) -> Vec<u8> {
+    let mut lines = vec![title.to_string(), String::new()];
+    for section in &content.sections {
+        lines.push(section.title.clone());
+        lines.extend(section.content.lines().map(str::to_string));
+        lines.push(String::new());
+    }
+    render_pdf_lines(&lines)
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 fn format_name(format: &OutputFormat) -> &str {
@@ -714,6 +1262,9 @@ mod tests {
                 analysis_parameters: HashMap::new(),
                 include_historical_data: false,
                 comparative_missions: vec![],
+                recommendations: vec![],
+                data_quality: vec![],
+                work_orders: vec![],
             },
             custom_sections: vec![],
             output_formats: vec![OutputFormat::PDF],
@@ -726,6 +1277,7 @@ mod tests {
             },
             requested_by: "test_user".to_string(),
             requested_at: Utc::now(),
+            locale: None,
         };
 
         let result = generator.generate_report(request).await.unwrap();
@@ -733,6 +1285,471 @@ mod tests {
         assert!(!result.file_paths.is_empty());
     }
 
+    /// Records the section of every chart it draws.
+    #[derive(Default)]
+    struct CountingRenderer {
+        rendered: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl ChartRenderer for CountingRenderer {
+        fn render(
+            &self,
+            section_id: &str,
+            config: &VisualizationConfig,
+            data: &serde_json::Value,
+        ) -> Result<String> {
+            self.rendered.lock().unwrap().push(section_id.to_string());
+            SvgChartRenderer.render(section_id, config, data)
+        }
+    }
+
+    #[tokio::test]
+    async fn unchanged_sections_are_reused_from_the_section_cache() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let cold_cache_dir = tempfile::tempdir().unwrap();
+        let config = ReportConfig {
+            output_formats: vec![OutputFormat::HTML],
+            default_template: "agricultural_comprehensive".to_string(),
+            include_raw_data: false,
+            include_visualizations: true,
+            enable_comparative_analysis: false,
+            logo_path: None,
+            company_info: CompanyInfo {
+                name: "Test Company".to_string(),
+                address: "123 Test St".to_string(),
+                contact_email: "test@example.com".to_string(),
+                website: None,
+                certification_info: None,
+            },
+        };
+        let generator = |cache_dir: &std::path::Path, renderer: Arc<CountingRenderer>| {
+            ReportGenerator::new(config.clone())
+                .with_chart_renderer(renderer)
+                .with_section_cache(SectionCache::new(cache_dir, 1024 * 1024))
+                .with_render_parallelism(2)
+        };
+        let request = |thermal_threshold: f64| ReportRequest {
+            id: Uuid::new_v4(),
+            title: "Field report".to_string(),
+            template_id: "agricultural_comprehensive".to_string(),
+            data_context: ReportDataContext {
+                mission_ids: vec![],
+                flight_session_ids: vec![],
+                date_range: (Utc::now() - chrono::Duration::days(1), Utc::now()),
+                geographical_bounds: None,
+                analysis_parameters: HashMap::from([
+                    (
+                        "vegetation_analysis".to_string(),
+                        serde_json::json!({ "mean_ndvi": 0.62 }),
+                    ),
+                    (
+                        "thermal_analysis".to_string(),
+                        serde_json::json!({ "hot_spot_c": thermal_threshold }),
+                    ),
+                ]),
+                include_historical_data: false,
+                comparative_missions: vec![],
+                recommendations: vec![],
+                data_quality: vec![],
+                work_orders: vec![],
+            },
+            custom_sections: vec![],
+            output_formats: vec![OutputFormat::HTML],
+            delivery_options: DeliveryOptions {
+                email_recipients: vec![],
+                storage_location: None,
+                auto_archive: false,
+                retention_days: 30,
+                access_permissions: vec![],
+            },
+            requested_by: "test_user".to_string(),
+            requested_at: Utc::now(),
+            locale: None,
+        };
+        let renderer = Arc::new(CountingRenderer::default());
+        let mut warm = generator(cache_dir.path(), renderer.clone());
+        let mut section_ids = warm.template_cache["agricultural_comprehensive"]
+            .sections
+            .clone();
+        section_ids.sort_by_key(|section| section.order);
+        let section_ids: Vec<String> = section_ids
+            .into_iter()
+            .map(|section| section.section_id)
+            .collect();
+
+        let first = warm.generate_report(request(32.0)).await.unwrap();
+        assert!(first.metadata.cached_sections.is_empty());
+        let mut charted = renderer.rendered.lock().unwrap().clone();
+        charted.sort();
+        assert_eq!(
+            charted,
+            vec![
+                "mission_overview",
+                "thermal_analysis",
+                "vegetation_analysis"
+            ]
+        );
+
+        renderer.rendered.lock().unwrap().clear();
+        let changed = request(35.0);
+        let second = warm.generate_report(changed.clone()).await.unwrap();
+        assert_eq!(*renderer.rendered.lock().unwrap(), vec!["thermal_analysis"]);
+        assert_eq!(
+            second.metadata.cached_sections,
+            section_ids
+                .iter()
+                .filter(|id| *id != "thermal_analysis")
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+        let warm_html = std::fs::read(&second.file_paths[&OutputFormat::HTML]).unwrap();
+
+        let mut cold = generator(cold_cache_dir.path(), Arc::new(CountingRenderer::default()));
+        let cold_report = cold.generate_report(changed).await.unwrap();
+        assert!(cold_report.metadata.cached_sections.is_empty());
+        let cold_html = std::fs::read(&cold_report.file_paths[&OutputFormat::HTML]).unwrap();
+        assert_eq!(warm_html, cold_html);
+        assert!(String::from_utf8_lossy(&cold_html).contains("<figure>"));
+        assert_eq!(
+            cold_report.metadata.sections_included,
+            second.metadata.sections_included
+        );
+
+        for report in [first, second, cold_report] {
+            for path in report.file_paths.values() {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn report_renders_recommendations_in_the_requested_locale() {
+        let mut generator = ReportGenerator::new(ReportConfig {
+            output_formats: vec![OutputFormat::HTML],
+            default_template: "agricultural_comprehensive".to_string(),
+            include_raw_data: false,
+            include_visualizations: false,
+            enable_comparative_analysis: false,
+            logo_path: None,
+            company_info: CompanyInfo {
+                name: "Test Company".to_string(),
+                address: "123 Test St".to_string(),
+                contact_email: "test@example.com".to_string(),
+                website: None,
+                certification_info: None,
+            },
+        });
+        let recommendation = Recommendation {
+            category: crate::RecommendationCategory::Fertilization,
+            priority: Priority::High,
+            title: "Bajo vigor en la zona north".to_string(),
+            description: String::new(),
+            action_items: vec!["Inspeccionar la zona north".to_string()],
+            affected_areas: vec![crate::AnalysisZone {
+                id: "north".to_string(),
+                boundary: vec![],
+                area_m2: 12_500.0,
+                values: HashMap::new(),
+                classification: None,
+            }],
+            confidence_score: 0.85,
+        };
+        let request = |locale: &str| ReportRequest {
+            id: Uuid::new_v4(),
+            title: "Informe de campo".to_string(),
+            template_id: "agricultural_comprehensive".to_string(),
+            data_context: ReportDataContext {
+                mission_ids: vec![],
+                flight_session_ids: vec![],
+                date_range: (Utc::now() - chrono::Duration::days(1), Utc::now()),
+                geographical_bounds: None,
+                analysis_parameters: HashMap::new(),
+                include_historical_data: false,
+                comparative_missions: vec![],
+                recommendations: vec![recommendation.clone()],
+                data_quality: vec![],
+                work_orders: vec![],
+            },
+            custom_sections: vec![],
+            output_formats: vec![OutputFormat::HTML, OutputFormat::PDF],
+            delivery_options: DeliveryOptions {
+                email_recipients: vec![],
+                storage_location: None,
+                auto_archive: false,
+                retention_days: 30,
+                access_permissions: vec![],
+            },
+            requested_by: "test_user".to_string(),
+            requested_at: Utc::now(),
+            locale: Some(locale.to_string()),
+        };
+
+        let report = generator.generate_report(request("es")).await.unwrap();
+        assert_eq!(report.locale, "es");
+        assert!(report.localization_warnings.is_empty());
+        assert!(report
+            .metadata
+            .sections_included
+            .contains(&"Recomendaciones y acciones".to_string()));
+        let html = std::fs::read_to_string(&report.file_paths[&OutputFormat::HTML]).unwrap();
+        assert!(html.contains("<html lang=\"es\">"));
+        assert!(html.contains("<h2>Análisis térmico</h2>"));
+        assert!(html.contains("<p>Prioridad: Alta</p>"));
+        assert!(html.contains("<p>Confianza: 85 %</p>"));
+        assert!(html.contains("<p>Superficie afectada: 12.500 m²</p>"));
+        let pdf = std::fs::read(&report.file_paths[&OutputFormat::PDF]).unwrap();
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(String::from_utf8_lossy(&pdf).contains("(An\\341lisis t\\351rmico) Tj"));
+
+        let report = generator.generate_report(request("pt")).await.unwrap();
+        assert_eq!(report.locale, "en");
+        assert_eq!(
+            report.localization_warnings,
+            vec![LocalizationWarning::MissingLocale {
+                locale: "pt".to_string()
+            }]
+        );
+        let html = std::fs::read_to_string(&report.file_paths[&OutputFormat::HTML]).unwrap();
+        assert!(html.contains("<p>Priority: High</p>"));
+        assert!(html.contains("<p>Affected area: 12,500 m²</p>"));
+        for path in generator
+            .generated_reports
+            .values()
+            .flat_map(|report| report.file_paths.values())
+        {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[tokio::test]
+    async fn report_includes_a_data_quality_section_with_refly_actions() {
+        use chrono::TimeZone;
+        use shared::data_quality::{QualityFlag, TelemetryQualityMetrics};
+
+        let mut generator = ReportGenerator::new(ReportConfig {
+            output_formats: vec![OutputFormat::HTML],
+            default_template: "agricultural_comprehensive".to_string(),
+            include_raw_data: false,
+            include_visualizations: false,
+            enable_comparative_analysis: false,
+            logo_path: None,
+            company_info: CompanyInfo {
+                name: "Test Company".to_string(),
+                address: "123 Test St".to_string(),
+                contact_email: "test@example.com".to_string(),
+                website: None,
+                certification_info: None,
+            },
+        });
+        let at = |hour, minute| Utc.with_ymd_and_hms(2026, 6, 1, hour, minute, 0).unwrap();
+        let session_id = Uuid::new_v4();
+        let assessment = SessionQualityAssessment {
+            session_id,
+            assessed_at: at(11, 0),
+            score: 62,
+            telemetry: TelemetryQualityMetrics {
+                sample_count: 600,
+                max_gap_seconds: 45.0,
+                gap_histogram: vec![],
+                score: 0.9,
+            },
+            image_spacing: None,
+            band_completeness: None,
+            lidar_stability: None,
+            flags: vec![
+                QualityFlag {
+                    flag_type: QualityFlagType::MissingBands,
+                    severity: QualitySeverity::Critical,
+                    start: at(10, 2),
+                    end: at(10, 9),
+                    area: Some(FieldStrip::East),
+                    message: "40 multispectral captures missing NIR".to_string(),
+                    suggestion: "check the NIR channel and re-fly east strip".to_string(),
+                },
+                QualityFlag {
+                    flag_type: QualityFlagType::LidarDropout,
+                    severity: QualitySeverity::Warning,
+                    start: at(10, 15),
+                    end: at(10, 16),
+                    area: None,
+                    message: "LiDAR scans stopped for 60 s".to_string(),
+                    suggestion: "re-fly the affected window".to_string(),
+                },
+            ],
+        };
+        let request = |locale: &str, data_quality: Vec<SessionQualityAssessment>| ReportRequest {
+            id: Uuid::new_v4(),
+            title: "Field report".to_string(),
+            template_id: "agricultural_comprehensive".to_string(),
+            data_context: ReportDataContext {
+                mission_ids: vec![],
+                flight_session_ids: vec![session_id],
+                date_range: (at(0, 0), at(23, 0)),
+                geographical_bounds: None,
+                analysis_parameters: HashMap::new(),
+                include_historical_data: false,
+                comparative_missions: vec![],
+                recommendations: vec![],
+                data_quality,
+                work_orders: vec![],
+            },
+            custom_sections: vec![],
+            output_formats: vec![OutputFormat::HTML],
+            delivery_options: DeliveryOptions {
+                email_recipients: vec![],
+                storage_location: None,
+                auto_archive: false,
+                retention_days: 30,
+                access_permissions: vec![],
+            },
+            requested_by: "test_user".to_string(),
+            requested_at: Utc::now(),
+            locale: Some(locale.to_string()),
+        };
+
+        let report = generator
+            .generate_report(request("en", vec![assessment.clone()]))
+            .await
+            .unwrap();
+        assert!(report
+            .metadata
+            .sections_included
+            .contains(&"Data Quality".to_string()));
+        let html = std::fs::read_to_string(&report.file_paths[&OutputFormat::HTML]).unwrap();
+        assert!(html.contains(&format!(
+            "<p>Session {session_id}: quality score 62/100</p>"
+        )));
+        assert!(html.contains(
+            "<p>- Critical: Missing bands, 10:02:00–10:09:00 UTC. Re-fly the east strip.</p>"
+        ));
+        assert!(html.contains(
+            "<p>- Warning: LiDAR dropout, 10:15:00–10:16:00 UTC. Suggested action: re-fly the affected window.</p>"
+        ));
+
+        let report = generator
+            .generate_report(request("es", vec![assessment]))
+            .await
+            .unwrap();
+        assert!(report.localization_warnings.is_empty());
+        let html = std::fs::read_to_string(&report.file_paths[&OutputFormat::HTML]).unwrap();
+        assert!(html.contains("<h2>Calidad de los datos</h2>"));
+        assert!(html.contains(
+            "<p>- Crítico: Bandas faltantes, 10:02:00–10:09:00 UTC. Volver a volar la franja este.</p>"
+        ));
+
+        let report = generator
+            .generate_report(request("en", vec![]))
+            .await
+            .unwrap();
+        let html = std::fs::read_to_string(&report.file_paths[&OutputFormat::HTML]).unwrap();
+        assert!(
+            html.contains("<p>No data-quality assessment is available for this report period.</p>")
+        );
+        for path in generator
+            .generated_reports
+            .values()
+            .flat_map(|report| report.file_paths.values())
+        {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[tokio::test]
+    async fn report_lists_open_work_orders_per_field() {
+        use chrono::TimeZone;
+
+        let mut generator = ReportGenerator::new(ReportConfig {
+            output_formats: vec![OutputFormat::HTML],
+            default_template: "agricultural_comprehensive".to_string(),
+            include_raw_data: false,
+            include_visualizations: false,
+            enable_comparative_analysis: false,
+            logo_path: None,
+            company_info: CompanyInfo {
+                name: "Test Company".to_string(),
+                address: "123 Test St".to_string(),
+                contact_email: "test@example.com".to_string(),
+                website: None,
+                certification_info: None,
+            },
+        });
+        let at = Utc.with_ymd_and_hms(2026, 6, 1, 9, 0, 0).unwrap();
+        let work_order = |field_id: &str, title: &str, status, due_date| WorkOrder {
+            id: Uuid::new_v4(),
+            result_id: Uuid::new_v4(),
+            recommendation_index: 0,
+            field_id: field_id.to_string(),
+            zones: vec![],
+            category: crate::RecommendationCategory::Irrigation,
+            priority: Priority::High,
+            title: title.to_string(),
+            description: String::new(),
+            action_items: vec![],
+            due_date,
+            assignee: None,
+            status,
+            history: vec![],
+            suggestion: None,
+            created_at: at,
+            updated_at: at,
+        };
+        let request = ReportRequest {
+            id: Uuid::new_v4(),
+            title: "Field report".to_string(),
+            template_id: "agricultural_comprehensive".to_string(),
+            data_context: ReportDataContext {
+                mission_ids: vec![],
+                flight_session_ids: vec![],
+                date_range: (at, at),
+                geographical_bounds: None,
+                analysis_parameters: HashMap::new(),
+                include_historical_data: false,
+                comparative_missions: vec![],
+                recommendations: vec![],
+                data_quality: vec![],
+                work_orders: vec![
+                    work_order("south-40", "Flush emitters", WorkOrderStatus::Open, None),
+                    work_order(
+                        "north-80",
+                        "Repair drip line",
+                        WorkOrderStatus::InProgress,
+                        Some(at),
+                    ),
+                    work_order("north-80", "Resample soil", WorkOrderStatus::Verified, None),
+                ],
+            },
+            custom_sections: vec![],
+            output_formats: vec![OutputFormat::HTML],
+            delivery_options: DeliveryOptions {
+                email_recipients: vec![],
+                storage_location: None,
+                auto_archive: false,
+                retention_days: 30,
+                access_permissions: vec![],
+            },
+            requested_by: "test_user".to_string(),
+            requested_at: at,
+            locale: Some("en".to_string()),
+        };
+
+        let report = generator.generate_report(request).await.unwrap();
+        let html = std::fs::read_to_string(&report.file_paths[&OutputFormat::HTML]).unwrap();
+        let _ = std::fs::remove_file(&report.file_paths[&OutputFormat::HTML]);
+        assert!(html.contains("<h2>Open Work Orders</h2>"));
+        let north = html
+            .find("<p>Field north-80: 1 open work orders</p>")
+            .unwrap();
+        let south = html
+            .find("<p>Field south-40: 1 open work orders</p>")
+            .unwrap();
+        assert!(north < south);
+        assert!(html.contains(
+            "<p>- Repair drip line (In progress, priority High): due 2026-06-01, assignee unassigned</p>"
+        ));
+        assert!(html.contains("due not set"));
+        assert!(!html.contains("Resample soil"));
+    }
+
     #[test]
     fn test_template_loading() {
         let config = ReportConfig {
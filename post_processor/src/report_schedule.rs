@@ -0,0 +1,1062 @@
+use crate::report_generator::{
+    DeliveryOptions, GeographicalBounds, OutputFormat, ReportDataContext, ReportGenerator,
+    ReportRequest,
+};
+use crate::work_orders::WorkOrder;
+use chrono::{DateTime, Duration, Utc};
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use shared::data_quality::SessionQualityAssessment;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+const SCHEDULE_STORE_FILE: &str = "report_schedules.json";
+const STAGED_SUFFIX: &str = ".partial";
+/// Runs, and separately audit entries, kept per schedule; older ones are
+/// dropped as new ones are recorded.
+const MAX_HISTORY_PER_SCHEDULE: usize = 200;
+const DEFAULT_MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ReportScheduleError {
+    #[error("invalid cron expression `{expression}`: {reason}")]
+    InvalidCron { expression: String, reason: String },
+    #[error("interval trigger must be at least one minute")]
+    InvalidInterval,
+    #[error("schedule must request at least one output format")]
+    EmptyOutputFormats,
+    #[error("schedule name cannot be empty")]
+    EmptyName,
+    #[error("report schedule not found: {schedule_id}")]
+    NotFound { schedule_id: Uuid },
+    #[error("report schedule persistence failed: {reason}")]
+    Persistence { reason: String },
+}
+
+/// Five-field cron expression (`minute hour day-of-month month day-of-week`),
+/// evaluated in UTC. When both day fields are restricted, either may match.
+#[derive(Debug, Clone)]
+pub struct CronExpression {
+    cron: Cron,
+}
+
+impl CronExpression {
+    pub fn parse(expression: &str) -> Result<Self, ReportScheduleError> {
+        Cron::new(expression)
+            .parse()
+            .map(|cron| Self { cron })
+            .map_err(|error| ReportScheduleError::InvalidCron {
+                expression: expression.to_string(),
+                reason: error.to_string(),
+            })
+    }
+
+    /// Returns the first matching minute strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.cron.find_next_occurrence(&after, false).ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReportScheduleTrigger {
+    Cron { expression: String },
+    Interval { every_minutes: u32 },
+}
+
+impl ReportScheduleTrigger {
+    pub fn validate(&self) -> Result<(), ReportScheduleError> {
+        match self {
+            Self::Cron { expression } => CronExpression::parse(expression).map(|_| ()),
+            Self::Interval { every_minutes } if *every_minutes == 0 => {
+                Err(ReportScheduleError::InvalidInterval)
+            }
+            Self::Interval { .. } => Ok(()),
+        }
+    }
+
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Cron { expression } => CronExpression::parse(expression).ok()?.next_after(after),
+            Self::Interval { every_minutes } => {
+                Some(after + Duration::minutes(i64::from(*every_minutes)))
+            }
+        }
+    }
+}
+
+/// How a schedule treats fire times that passed while the service was down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedRunPolicy {
+    #[default]
+    Skip,
+    RunOnceOnStartup,
+}
+
+/// Data-context query resolved at run time, e.g. "last 7 days of sessions for field X".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportDataQuery {
+    pub field_id: Option<String>,
+    pub lookback_days: u32,
+    #[serde(default)]
+    pub mission_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub flight_session_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub geographical_bounds: Option<GeographicalBounds>,
+    #[serde(default)]
+    pub analysis_parameters: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub include_historical_data: bool,
+}
+
+/// Looks up flight sessions recorded for a field inside a date range.
+pub trait ReportSessionSource: Send + Sync {
+    fn sessions_in_range(
+        &self,
+        field_id: Option<&str>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Vec<Uuid>;
+
+    /// Data-quality assessment of a session, for the report's data-quality
+    /// section.
+    fn session_quality(&self, _session_id: Uuid) -> Option<SessionQualityAssessment> {
+        None
+    }
+
+    /// Work orders of the field (all fields when `None`), for the report's
+    /// open-work-orders section.
+    fn work_orders(&self, _field_id: Option<&str>) -> Vec<WorkOrder> {
+        Vec::new()
+    }
+}
+
+/// Session source that contributes nothing beyond the schedule's explicit ids.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoSessionSource;
+
+impl ReportSessionSource for NoSessionSource {
+    fn sessions_in_range(
+        &self,
+        _field_id: Option<&str>,
+        _start: DateTime<Utc>,
+        _end: DateTime<Utc>,
+    ) -> Vec<Uuid> {
+        Vec::new()
+    }
+}
+
+impl ReportDataQuery {
+    pub fn resolve(
+        &self,
+        at: DateTime<Utc>,
+        sessions: &dyn ReportSessionSource,
+    ) -> ReportDataContext {
+        let start = at - Duration::days(i64::from(self.lookback_days));
+        let mut flight_session_ids = self.flight_session_ids.clone();
+        for session_id in sessions.sessions_in_range(self.field_id.as_deref(), start, at) {
+            if !flight_session_ids.contains(&session_id) {
+                flight_session_ids.push(session_id);
+            }
+        }
+        let mut analysis_parameters = self.analysis_parameters.clone();
+        if let Some(field_id) = &self.field_id {
+            analysis_parameters.insert("field_id".to_string(), serde_json::json!(field_id));
+        }
+
+        ReportDataContext {
+            mission_ids: self.mission_ids.clone(),
+            date_range: (start, at),
+            geographical_bounds: self.geographical_bounds.clone(),
+            analysis_parameters,
+            include_historical_data: self.include_historical_data,
+            comparative_missions: Vec::new(),
+            recommendations: Vec::new(),
+            data_quality: flight_session_ids
+                .iter()
+                .filter_map(|session_id| sessions.session_quality(*session_id))
+                .collect(),
+            work_orders: sessions.work_orders(self.field_id.as_deref()),
+            flight_session_ids,
+        }
+    }
+}
+
+/// User-supplied schedule definition accepted by create and update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportScheduleRequest {
+    pub name: String,
+    pub trigger: ReportScheduleTrigger,
+    pub template_id: String,
+    pub data_query: ReportDataQuery,
+    pub output_formats: Vec<OutputFormat>,
+    pub delivery_options: DeliveryOptions,
+    #[serde(default)]
+    pub missed_run_policy: MissedRunPolicy,
+    #[serde(default)]
+    pub max_consecutive_failures: Option<u32>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ReportScheduleRequest {
+    fn validate(&self) -> Result<(), ReportScheduleError> {
+        if self.name.trim().is_empty() {
+            return Err(ReportScheduleError::EmptyName);
+        }
+        if self.output_formats.is_empty() {
+            return Err(ReportScheduleError::EmptyOutputFormats);
+        }
+        self.trigger.validate()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportSchedule {
+    pub id: Uuid,
+    pub name: String,
+    pub trigger: ReportScheduleTrigger,
+    pub template_id: String,
+    pub data_query: ReportDataQuery,
+    pub output_formats: Vec<OutputFormat>,
+    pub delivery_options: DeliveryOptions,
+    pub missed_run_policy: MissedRunPolicy,
+    pub max_consecutive_failures: u32,
+    pub enabled: bool,
+    pub consecutive_failures: u32,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ReportSchedule {
+    fn from_request(request: ReportScheduleRequest, now: DateTime<Utc>) -> Self {
+        let next_run_at = request.trigger.next_after(now);
+        Self {
+            id: Uuid::new_v4(),
+            name: request.name,
+            trigger: request.trigger,
+            template_id: request.template_id,
+            data_query: request.data_query,
+            output_formats: request.output_formats,
+            delivery_options: request.delivery_options,
+            missed_run_policy: request.missed_run_policy,
+            max_consecutive_failures: request
+                .max_consecutive_failures
+                .unwrap_or(DEFAULT_MAX_CONSECUTIVE_FAILURES)
+                .max(1),
+            enabled: request.enabled,
+            consecutive_failures: 0,
+            next_run_at,
+            last_run_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn apply_request(&mut self, request: ReportScheduleRequest, now: DateTime<Utc>) {
+        self.name = request.name;
+        self.trigger = request.trigger;
+        self.template_id = request.template_id;
+        self.data_query = request.data_query;
+        self.output_formats = request.output_formats;
+        self.delivery_options = request.delivery_options;
+        self.missed_run_policy = request.missed_run_policy;
+        self.max_consecutive_failures = request
+            .max_consecutive_failures
+            .unwrap_or(DEFAULT_MAX_CONSECUTIVE_FAILURES)
+            .max(1);
+        self.enabled = request.enabled;
+        self.consecutive_failures = 0;
+        self.next_run_at = self.trigger.next_after(now);
+        self.updated_at = now;
+    }
+
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.enabled && self.next_run_at.is_some_and(|next| next <= now)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ScheduleRunOutcome {
+    Succeeded { report_id: Uuid },
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    pub id: Uuid,
+    pub schedule_id: Uuid,
+    pub scheduled_for: DateTime<Utc>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub date_range: (DateTime<Utc>, DateTime<Utc>),
+    pub outcome: ScheduleRunOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleAuditEvent {
+    Created,
+    Updated,
+    Deleted,
+    MissedRunSkipped,
+    MissedRunRecovered,
+    DisabledAfterFailures,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleAuditEntry {
+    pub at: DateTime<Utc>,
+    pub schedule_id: Uuid,
+    pub event: ScheduleAuditEvent,
+    pub detail: String,
+}
+
+/// JSON-file backed store for schedules, their recent run history, and audit
+/// entries.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReportScheduleStore {
+    schedules: HashMap<Uuid, ReportSchedule>,
+    runs: Vec<ScheduleRun>,
+    audit: Vec<ScheduleAuditEntry>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl ReportScheduleStore {
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn load(directory: &Path) -> Result<Self, ReportScheduleError> {
+        let path = directory.join(SCHEDULE_STORE_FILE);
+        let mut store = if path.exists() {
+            let content = fs::read(&path).map_err(persistence_error)?;
+            serde_json::from_slice::<Self>(&content).map_err(persistence_error)?
+        } else {
+            Self::default()
+        };
+        store.path = Some(path);
+        Ok(store)
+    }
+
+    /// Writes the store to a staged file next to it and renames that into
+    /// place, so a crash mid-write leaves the previous store intact.
+    fn persist(&self) -> Result<(), ReportScheduleError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(persistence_error)?;
+        }
+        let content = serde_json::to_vec_pretty(self).map_err(persistence_error)?;
+        let mut staged_name = path.file_name().unwrap_or_default().to_os_string();
+        staged_name.push(format!(".{}{STAGED_SUFFIX}", Uuid::new_v4()));
+        let staged_path = path.with_file_name(staged_name);
+
+        let staged = (|| {
+            let mut staged = fs::File::create(&staged_path)?;
+            staged.write_all(&content)?;
+            staged.sync_all()?;
+            fs::rename(&staged_path, path)
+        })();
+        if let Err(error) = staged {
+            let _ = fs::remove_file(&staged_path);
+            return Err(persistence_error(error));
+        }
+        Ok(())
+    }
+
+    fn audit(
+        &mut self,
+        at: DateTime<Utc>,
+        schedule_id: Uuid,
+        event: ScheduleAuditEvent,
+        detail: String,
+    ) {
+        self.audit.push(ScheduleAuditEntry {
+            at,
+            schedule_id,
+            event,
+            detail,
+        });
+        drop_oldest(&mut self.audit, |entry| entry.schedule_id == schedule_id);
+    }
+
+    fn record_run(&mut self, run: ScheduleRun) {
+        let schedule_id = run.schedule_id;
+        self.runs.push(run);
+        drop_oldest(&mut self.runs, |run| run.schedule_id == schedule_id);
+    }
+
+    pub fn schedules(&self) -> Vec<ReportSchedule> {
+        let mut schedules: Vec<ReportSchedule> = self.schedules.values().cloned().collect();
+        schedules.sort_by(|left, right| {
+            left.created_at
+                .cmp(&right.created_at)
+                .then_with(|| left.id.cmp(&right.id))
+        });
+        schedules
+    }
+
+    pub fn schedule(&self, schedule_id: &Uuid) -> Option<&ReportSchedule> {
+        self.schedules.get(schedule_id)
+    }
+
+    pub fn runs_for(&self, schedule_id: &Uuid) -> Vec<ScheduleRun> {
+        self.runs
+            .iter()
+            .filter(|run| run.schedule_id == *schedule_id)
+            .cloned()
+            .collect()
+    }
+
+    pub fn audit_for(&self, schedule_id: &Uuid) -> Vec<ScheduleAuditEntry> {
+        self.audit
+            .iter()
+            .filter(|entry| entry.schedule_id == *schedule_id)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Removes the oldest entries matching `belongs` beyond
+/// [`MAX_HISTORY_PER_SCHEDULE`]; `entries` is in the order they were recorded.
+fn drop_oldest<T>(entries: &mut Vec<T>, belongs: impl Fn(&T) -> bool) {
+    let excess = entries
+        .iter()
+        .filter(|entry| belongs(entry))
+        .count()
+        .saturating_sub(MAX_HISTORY_PER_SCHEDULE);
+    let mut dropped = 0;
+    entries.retain(|entry| {
+        if dropped < excess && belongs(entry) {
+            dropped += 1;
+            false
+        } else {
+            true
+        }
+    });
+}
+
+fn persistence_error(error: impl std::fmt::Display) -> ReportScheduleError {
+    ReportScheduleError::Persistence {
+        reason: error.to_string(),
+    }
+}
+
+/// Source of "now" for schedule evaluation, injectable so tests can fire schedules.
+pub trait ScheduleClock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemScheduleClock;
+
+impl ScheduleClock for SystemScheduleClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Evaluates due schedules and generates their reports through [`ReportGenerator`].
+pub struct ReportScheduler {
+    store: Mutex<ReportScheduleStore>,
+    generator: Arc<Mutex<ReportGenerator>>,
+    sessions: Arc<dyn ReportSessionSource>,
+    clock: Arc<dyn ScheduleClock>,
+}
+
+impl ReportScheduler {
+    pub fn new(
+        store: ReportScheduleStore,
+        generator: Arc<Mutex<ReportGenerator>>,
+        sessions: Arc<dyn ReportSessionSource>,
+        clock: Arc<dyn ScheduleClock>,
+    ) -> Self {
+        Self {
+            store: Mutex::new(store),
+            generator,
+            sessions,
+            clock,
+        }
+    }
+
+    pub fn generator(&self) -> Arc<Mutex<ReportGenerator>> {
+        Arc::clone(&self.generator)
+    }
+
+    pub async fn create_schedule(
+        &self,
+        request: ReportScheduleRequest,
+    ) -> Result<ReportSchedule, ReportScheduleError> {
+        request.validate()?;
+        let now = self.clock.now();
+        let schedule = ReportSchedule::from_request(request, now);
+
+        let mut store = self.store.lock().await;
+        store.schedules.insert(schedule.id, schedule.clone());
+        store.audit(
+            now,
+            schedule.id,
+            ScheduleAuditEvent::Created,
+            format!("schedule `{}` created", schedule.name),
+        );
+        store.persist()?;
+        Ok(schedule)
+    }
+
+    pub async fn update_schedule(
+        &self,
+        schedule_id: Uuid,
+        request: ReportScheduleRequest,
+    ) -> Result<ReportSchedule, ReportScheduleError> {
+        request.validate()?;
+        let now = self.clock.now();
+        let mut store = self.store.lock().await;
+        let schedule = store
+            .schedules
+            .get_mut(&schedule_id)
+            .ok_or(ReportScheduleError::NotFound { schedule_id })?;
+        schedule.apply_request(request, now);
+        let schedule = schedule.clone();
+        store.audit(
+            now,
+            schedule_id,
+            ScheduleAuditEvent::Updated,
+            format!("schedule `{}` updated", schedule.name),
+        );
+        store.persist()?;
+        Ok(schedule)
+    }
+
+    pub async fn delete_schedule(&self, schedule_id: Uuid) -> Result<(), ReportScheduleError> {
+        let now = self.clock.now();
+        let mut store = self.store.lock().await;
+        let schedule = store
+            .schedules
+            .remove(&schedule_id)
+            .ok_or(ReportScheduleError::NotFound { schedule_id })?;
+        store.audit(
+            now,
+            schedule_id,
+            ScheduleAuditEvent::Deleted,
+            format!("schedule `{}` deleted", schedule.name),
+        );
+        store.persist()
+    }
+
+    pub async fn get_schedule(&self, schedule_id: Uuid) -> Option<ReportSchedule> {
+        self.store.lock().await.schedule(&schedule_id).cloned()
+    }
+
+    pub async fn list_schedules(&self) -> Vec<ReportSchedule> {
+        self.store.lock().await.schedules()
+    }
+
+    pub async fn runs_for(&self, schedule_id: Uuid) -> Vec<ScheduleRun> {
+        self.store.lock().await.runs_for(&schedule_id)
+    }
+
+    pub async fn audit_for(&self, schedule_id: Uuid) -> Vec<ScheduleAuditEntry> {
+        self.store.lock().await.audit_for(&schedule_id)
+    }
+
+    /// Applies each schedule's [`MissedRunPolicy`] to fire times that passed
+    /// while the service was not running. Call once before the first tick.
+    pub async fn recover_missed_runs(&self) -> Result<Vec<ScheduleRun>, ReportScheduleError> {
+        let now = self.clock.now();
+        let missed: Vec<ReportSchedule> = {
+            let store = self.store.lock().await;
+            store
+                .schedules()
+                .into_iter()
+                .filter(|schedule| schedule.is_due(now))
+                .collect()
+        };
+
+        let mut runs = Vec::new();
+        for schedule in missed {
+            let missed_at = schedule.next_run_at.unwrap_or(now);
+            match schedule.missed_run_policy {
+                MissedRunPolicy::Skip => {
+                    let mut store = self.store.lock().await;
+                    if let Some(stored) = store.schedules.get_mut(&schedule.id) {
+                        stored.next_run_at = stored.trigger.next_after(now);
+                    }
+                    store.audit(
+                        now,
+                        schedule.id,
+                        ScheduleAuditEvent::MissedRunSkipped,
+                        format!("skipped run missed at {}", missed_at.to_rfc3339()),
+                    );
+                    store.persist()?;
+                }
+                MissedRunPolicy::RunOnceOnStartup => {
+                    {
+                        let mut store = self.store.lock().await;
+                        store.audit(
+                            now,
+                            schedule.id,
+                            ScheduleAuditEvent::MissedRunRecovered,
+                            format!("running once for run missed at {}", missed_at.to_rfc3339()),
+                        );
+                    }
+                    runs.push(self.execute(schedule, now).await?);
+                }
+            }
+        }
+        Ok(runs)
+    }
+
+    /// Runs every enabled schedule whose next fire time has passed. Several
+    /// elapsed fire times for one schedule collapse into a single run.
+    pub async fn run_due(&self) -> Result<Vec<ScheduleRun>, ReportScheduleError> {
+        let now = self.clock.now();
+        let due: Vec<ReportSchedule> = {
+            let store = self.store.lock().await;
+            store
+                .schedules()
+                .into_iter()
+                .filter(|schedule| schedule.is_due(now))
+                .collect()
+        };
+
+        let mut runs = Vec::with_capacity(due.len());
+        for schedule in due {
+            runs.push(self.execute(schedule, now).await?);
+        }
+        Ok(runs)
+    }
+
+    async fn execute(
+        &self,
+        schedule: ReportSchedule,
+        now: DateTime<Utc>,
+    ) -> Result<ScheduleRun, ReportScheduleError> {
+        let scheduled_for = schedule.next_run_at.unwrap_or(now);
+        let data_context = schedule.data_query.resolve(now, self.sessions.as_ref());
+        let date_range = data_context.date_range;
+        let request = ReportRequest {
+            id: Uuid::new_v4(),
+            title: format!(
+                "{} ({} to {})",
+                schedule.name,
+                date_range.0.format("%Y-%m-%d"),
+                date_range.1.format("%Y-%m-%d")
+            ),
+            template_id: schedule.template_id.clone(),
+            data_context,
+            custom_sections: Vec::new(),
+            output_formats: schedule.output_formats.clone(),
+            delivery_options: schedule.delivery_options.clone(),
+            requested_by: format!("schedule:{}", schedule.id),
+            requested_at: now,
+            locale: None,
+        };
+
+        let generated = self.generator.lock().await.generate_report(request).await;
+        let outcome = match generated {
+            Ok(report) => ScheduleRunOutcome::Succeeded {
+                report_id: report.id,
+            },
+            Err(error) => {
+                tracing::warn!(schedule_id = %schedule.id, "scheduled report failed: {error}");
+                ScheduleRunOutcome::Failed {
+                    error: error.to_string(),
+                }
+            }
+        };
+        let run = ScheduleRun {
+            id: Uuid::new_v4(),
+            schedule_id: schedule.id,
+            scheduled_for,
+            started_at: now,
+            finished_at: self.clock.now(),
+            date_range,
+            outcome,
+        };
+
+        let mut store = self.store.lock().await;
+        let mut disabled_after = None;
+        if let Some(stored) = store.schedules.get_mut(&schedule.id) {
+            stored.last_run_at = Some(now);
+            stored.next_run_at = stored.trigger.next_after(now);
+            match run.outcome {
+                ScheduleRunOutcome::Succeeded { .. } => stored.consecutive_failures = 0,
+                ScheduleRunOutcome::Failed { .. } => {
+                    stored.consecutive_failures += 1;
+                    if stored.consecutive_failures >= stored.max_consecutive_failures {
+                        stored.enabled = false;
+                        disabled_after = Some(stored.consecutive_failures);
+                    }
+                }
+            }
+        }
+        if let Some(failures) = disabled_after {
+            store.audit(
+                now,
+                schedule.id,
+                ScheduleAuditEvent::DisabledAfterFailures,
+                format!("disabled after {failures} consecutive failures"),
+            );
+        }
+        store.record_run(run.clone());
+        store.persist()?;
+        Ok(run)
+    }
+
+    /// Spawns the background evaluator: recovers missed runs once, then checks
+    /// for due schedules every `tick`.
+    pub fn spawn(self: Arc<Self>, tick: std::time::Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            if let Err(error) = self.recover_missed_runs().await {
+                tracing::error!("report schedule recovery failed: {error}");
+            }
+            let mut interval = tokio::time::interval(tick);
+            loop {
+                interval.tick().await;
+                if let Err(error) = self.run_due().await {
+                    tracing::error!("report schedule evaluation failed: {error}");
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report_generator::{CompanyInfo, ReportConfig};
+    use chrono::TimeZone;
+    use std::sync::Mutex as StdMutex;
+
+    struct ManualClock(StdMutex<DateTime<Utc>>);
+
+    impl ManualClock {
+        fn at(at: DateTime<Utc>) -> Arc<Self> {
+            Arc::new(Self(StdMutex::new(at)))
+        }
+
+        fn set(&self, at: DateTime<Utc>) {
+            *self.0.lock().unwrap() = at;
+        }
+    }
+
+    impl ScheduleClock for ManualClock {
+        fn now(&self) -> DateTime<Utc> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    struct FieldSessions {
+        field_id: &'static str,
+        sessions: Vec<(DateTime<Utc>, Uuid)>,
+    }
+
+    impl ReportSessionSource for FieldSessions {
+        fn sessions_in_range(
+            &self,
+            field_id: Option<&str>,
+            start: DateTime<Utc>,
+            end: DateTime<Utc>,
+        ) -> Vec<Uuid> {
+            if field_id != Some(self.field_id) {
+                return Vec::new();
+            }
+            self.sessions
+                .iter()
+                .filter(|(at, _)| *at >= start && *at <= end)
+                .map(|(_, id)| *id)
+                .collect()
+        }
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .single()
+            .unwrap()
+    }
+
+    fn generator() -> Arc<Mutex<ReportGenerator>> {
+        Arc::new(Mutex::new(ReportGenerator::new(ReportConfig {
+            output_formats: vec![OutputFormat::PDF],
+            default_template: "agricultural_comprehensive".to_string(),
+            include_raw_data: false,
+            include_visualizations: true,
+            enable_comparative_analysis: true,
+            logo_path: None,
+            company_info: CompanyInfo {
+                name: "Test Company".to_string(),
+                address: "123 Test St".to_string(),
+                contact_email: "test@example.com".to_string(),
+                website: None,
+                certification_info: None,
+            },
+        })))
+    }
+
+    fn weekly_request(template_id: &str) -> ReportScheduleRequest {
+        ReportScheduleRequest {
+            name: "Weekly comparison".to_string(),
+            trigger: ReportScheduleTrigger::Cron {
+                expression: "0 6 * * 1".to_string(),
+            },
+            template_id: template_id.to_string(),
+            data_query: ReportDataQuery {
+                field_id: Some("north-80".to_string()),
+                lookback_days: 7,
+                mission_ids: Vec::new(),
+                flight_session_ids: Vec::new(),
+                geographical_bounds: None,
+                analysis_parameters: HashMap::new(),
+                include_historical_data: false,
+            },
+            output_formats: vec![OutputFormat::PDF],
+            delivery_options: DeliveryOptions {
+                email_recipients: Vec::new(),
+                storage_location: None,
+                auto_archive: false,
+                retention_days: 30,
+                access_permissions: Vec::new(),
+            },
+            missed_run_policy: MissedRunPolicy::Skip,
+            max_consecutive_failures: Some(2),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn cron_expression_finds_next_monday_morning() {
+        let cron = CronExpression::parse("0 6 * * 1").unwrap();
+
+        // 2026-10-14 is a Wednesday.
+        let next = cron.next_after(utc(2026, 10, 14, 12, 0)).unwrap();
+
+        assert_eq!(next, utc(2026, 10, 19, 6, 0));
+        assert_eq!(
+            cron.next_after(next).unwrap(),
+            utc(2026, 10, 26, 6, 0),
+            "a fire time is never returned twice"
+        );
+    }
+
+    #[test]
+    fn cron_expression_supports_steps_lists_and_ranges() {
+        let cron = CronExpression::parse("*/15 8-9 1,15 * *").unwrap();
+
+        assert_eq!(
+            cron.next_after(utc(2026, 10, 1, 9, 50)).unwrap(),
+            utc(2026, 10, 15, 8, 0)
+        );
+        assert!(CronExpression::parse("61 * * * *").is_err());
+        assert!(CronExpression::parse("0 6 * *").is_err());
+        assert!(CronExpression::parse("*/0 * * * *").is_err());
+    }
+
+    #[tokio::test]
+    async fn due_schedule_generates_report_for_resolved_date_range() {
+        let clock = ManualClock::at(utc(2026, 10, 14, 12, 0));
+        let recent_session = Uuid::new_v4();
+        let stale_session = Uuid::new_v4();
+        let sessions = Arc::new(FieldSessions {
+            field_id: "north-80",
+            sessions: vec![
+                (utc(2026, 10, 16, 9, 0), recent_session),
+                (utc(2026, 10, 1, 9, 0), stale_session),
+            ],
+        });
+        let generator = generator();
+        let scheduler = ReportScheduler::new(
+            ReportScheduleStore::in_memory(),
+            Arc::clone(&generator),
+            sessions,
+            clock.clone(),
+        );
+        let schedule = scheduler
+            .create_schedule(weekly_request("agricultural_comprehensive"))
+            .await
+            .unwrap();
+        assert_eq!(schedule.next_run_at, Some(utc(2026, 10, 19, 6, 0)));
+
+        assert!(scheduler.run_due().await.unwrap().is_empty());
+
+        clock.set(utc(2026, 10, 19, 6, 0));
+        let runs = scheduler.run_due().await.unwrap();
+
+        assert_eq!(runs.len(), 1);
+        let run = &runs[0];
+        assert_eq!(
+            run.date_range,
+            (utc(2026, 10, 12, 6, 0), utc(2026, 10, 19, 6, 0))
+        );
+        let ScheduleRunOutcome::Succeeded { report_id } = run.outcome else {
+            panic!("scheduled run should succeed: {:?}", run.outcome);
+        };
+        let generator = generator.lock().await;
+        let report = generator
+            .get_report(report_id)
+            .await
+            .expect("report is linked");
+        assert_eq!(report.title, "Weekly comparison (2026-10-12 to 2026-10-19)");
+        assert_eq!(report.generated_by, format!("schedule:{}", schedule.id));
+
+        let stored = scheduler.get_schedule(schedule.id).await.unwrap();
+        assert_eq!(stored.last_run_at, Some(utc(2026, 10, 19, 6, 0)));
+        assert_eq!(stored.next_run_at, Some(utc(2026, 10, 26, 6, 0)));
+
+        let context = stored.data_query.resolve(
+            utc(2026, 10, 19, 6, 0),
+            &FieldSessions {
+                field_id: "north-80",
+                sessions: vec![
+                    (utc(2026, 10, 16, 9, 0), recent_session),
+                    (utc(2026, 10, 1, 9, 0), stale_session),
+                ],
+            },
+        );
+        assert_eq!(context.flight_session_ids, vec![recent_session]);
+    }
+
+    #[tokio::test]
+    async fn consecutive_failures_disable_schedule_with_audit_entry() {
+        let clock = ManualClock::at(utc(2026, 10, 14, 12, 0));
+        let scheduler = ReportScheduler::new(
+            ReportScheduleStore::in_memory(),
+            generator(),
+            Arc::new(NoSessionSource),
+            clock.clone(),
+        );
+        let schedule = scheduler
+            .create_schedule(weekly_request("missing_template"))
+            .await
+            .unwrap();
+
+        clock.set(utc(2026, 10, 19, 6, 0));
+        let first = scheduler.run_due().await.unwrap();
+        assert!(matches!(
+            first[0].outcome,
+            ScheduleRunOutcome::Failed { .. }
+        ));
+        assert!(scheduler.get_schedule(schedule.id).await.unwrap().enabled);
+
+        clock.set(utc(2026, 10, 26, 6, 0));
+        scheduler.run_due().await.unwrap();
+
+        let stored = scheduler.get_schedule(schedule.id).await.unwrap();
+        assert!(!stored.enabled);
+        assert_eq!(stored.consecutive_failures, 2);
+        let audit = scheduler.audit_for(schedule.id).await;
+        assert!(audit
+            .iter()
+            .any(|entry| entry.event == ScheduleAuditEvent::DisabledAfterFailures));
+
+        clock.set(utc(2026, 11, 2, 6, 0));
+        assert!(scheduler.run_due().await.unwrap().is_empty());
+        assert_eq!(scheduler.runs_for(schedule.id).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn missed_runs_follow_schedule_policy_after_restart() {
+        let tmp = tempfile::tempdir().unwrap();
+        let clock = ManualClock::at(utc(2026, 10, 14, 12, 0));
+        let scheduler = ReportScheduler::new(
+            ReportScheduleStore::load(tmp.path()).unwrap(),
+            generator(),
+            Arc::new(NoSessionSource),
+            clock.clone(),
+        );
+        let skipped = scheduler
+            .create_schedule(weekly_request("agricultural_comprehensive"))
+            .await
+            .unwrap();
+        let mut catch_up_request = weekly_request("agricultural_comprehensive");
+        catch_up_request.missed_run_policy = MissedRunPolicy::RunOnceOnStartup;
+        let catch_up = scheduler.create_schedule(catch_up_request).await.unwrap();
+        drop(scheduler);
+
+        // Service comes back two missed Mondays later.
+        clock.set(utc(2026, 10, 27, 9, 0));
+        let restarted = ReportScheduler::new(
+            ReportScheduleStore::load(tmp.path()).unwrap(),
+            generator(),
+            Arc::new(NoSessionSource),
+            clock.clone(),
+        );
+        let runs = restarted.recover_missed_runs().await.unwrap();
+
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].schedule_id, catch_up.id);
+        assert!(restarted.runs_for(skipped.id).await.is_empty());
+        assert!(restarted
+            .audit_for(skipped.id)
+            .await
+            .iter()
+            .any(|entry| entry.event == ScheduleAuditEvent::MissedRunSkipped));
+        for schedule in restarted.list_schedules().await {
+            assert_eq!(schedule.next_run_at, Some(utc(2026, 11, 2, 6, 0)));
+        }
+    }
+
+    #[test]
+    fn stored_history_is_capped_per_schedule_and_written_in_place() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut store = ReportScheduleStore::load(tmp.path()).unwrap();
+        let busy = Uuid::new_v4();
+        let quiet = Uuid::new_v4();
+        let run = |schedule_id, minute| {
+            let at = utc(2026, 10, 19, 6, 0) + Duration::minutes(minute);
+            ScheduleRun {
+                id: Uuid::new_v4(),
+                schedule_id,
+                scheduled_for: at,
+                started_at: at,
+                finished_at: at,
+                date_range: (at - Duration::days(7), at),
+                outcome: ScheduleRunOutcome::Failed {
+                    error: "no data".to_string(),
+                },
+            }
+        };
+        store.record_run(run(quiet, 0));
+        for minute in 0..MAX_HISTORY_PER_SCHEDULE as i64 + 5 {
+            store.record_run(run(busy, minute));
+            store.audit(
+                utc(2026, 10, 19, 6, 0) + Duration::minutes(minute),
+                busy,
+                ScheduleAuditEvent::Updated,
+                format!("update {minute}"),
+            );
+        }
+        store.persist().unwrap();
+
+        let reloaded = ReportScheduleStore::load(tmp.path()).unwrap();
+        let runs = reloaded.runs_for(&busy);
+        assert_eq!(runs.len(), MAX_HISTORY_PER_SCHEDULE);
+        assert_eq!(runs[0].started_at, utc(2026, 10, 19, 6, 5));
+        assert_eq!(reloaded.runs_for(&quiet).len(), 1);
+        let audit = reloaded.audit_for(&busy);
+        assert_eq!(audit.len(), MAX_HISTORY_PER_SCHEDULE);
+        assert_eq!(audit[0].detail, "update 5");
+
+        let files: Vec<_> = fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(files, vec![std::ffi::OsString::from(SCHEDULE_STORE_FILE)]);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::artifacts::content_hash;
+use crate::localization::LocalizationWarning;
+use crate::report_generator::VisualizationConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use shared::palette::{hex, palette, PaletteKind};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Default cap on the bytes a [`SectionCache`] keeps on disk.
+pub const DEFAULT_SECTION_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Draws a section's chart. Swappable so tests can count renders.
+pub trait ChartRenderer: Send + Sync {
+    /// SVG markup for `config` drawn over the section's resolved input.
+    fn render(
+        &self,
+        section_id: &str,
+        config: &VisualizationConfig,
+        data: &serde_json::Value,
+    ) -> Result<String>;
+}
+
+/// Draws a bar per numeric value found in the section's input, coloured
+/// from the shared palette named by the config's `color_scheme`: by height
+/// for a continuous palette, or per value key for a categorical one.
+#[derive(Debug, Default)]
+pub struct SvgChartRenderer;
+
+impl ChartRenderer for SvgChartRenderer {
+    fn render(
+        &self,
+        section_id: &str,
+        config: &VisualizationConfig,
+        data: &serde_json::Value,
+    ) -> Result<String> {
+        let palette = palette(&config.color_scheme)?;
+        let (width, height) = config.dimensions;
+        let mut leaves = Vec::new();
+        numeric_leaves(data, String::new(), &mut leaves);
+        let max = leaves
+            .iter()
+            .fold(0.0_f64, |max, (_, value)| max.max(value.abs()));
+        let class_colors = match palette.kind {
+            PaletteKind::Categorical => {
+                palette.assign_class_colors(leaves.iter().map(|(key, _)| key.as_str()))
+            }
+            PaletteKind::Continuous => BTreeMap::new(),
+        };
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" data-section=\"{section_id}\">"
+        );
+        if let Some(title) = &config.title {
+            svg.push_str(&format!("<title>{title}</title>"));
+        }
+        if max > 0.0 {
+            let bar_width = f64::from(width) / leaves.len() as f64;
+            for (index, (key, value)) in leaves.iter().enumerate() {
+                let bar_height = value.abs() / max * f64::from(height);
+                let color = class_colors
+                    .get(key)
+                    .copied()
+                    .unwrap_or_else(|| palette.color_at((value.abs() / max) as f32));
+                svg.push_str(&format!(
+                    "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>",
+                    index as f64 * bar_width,
+                    f64::from(height) - bar_height,
+                    bar_width,
+                    bar_height,
+                    hex(color)
+                ));
+            }
+        }
+        svg.push_str("</svg>");
+        Ok(svg)
+    }
+}
+
+/// Numeric values under `value` with their `.`-joined key paths.
+fn numeric_leaves(value: &serde_json::Value, path: String, leaves: &mut Vec<(String, f64)>) {
+    let child_path = |key: &dyn std::fmt::Display| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+    match value {
+        serde_json::Value::Number(number) => {
+            if let Some(number) = number.as_f64() {
+                leaves.push((path, number));
+            }
+        }
+        serde_json::Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .for_each(|(index, item)| numeric_leaves(item, child_path(&index), leaves)),
+        serde_json::Value::Object(fields) => fields
+            .iter()
+            .for_each(|(key, field)| numeric_leaves(field, child_path(key), leaves)),
+        _ => {}
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableData {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderedChart {
+    pub alt_text: String,
+    /// Kept in its own file next to the section's cache entry.
+    #[serde(skip)]
+    pub svg: String,
+}
+
+/// One section of a report, rendered from its resolved input.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionFragment {
+    pub section_id: String,
+    pub title: String,
+    pub content: String,
+    pub tables: Vec<TableData>,
+    pub charts: Vec<RenderedChart>,
+    /// Fallbacks taken while rendering the section's text.
+    pub localization_warnings: Vec<LocalizationWarning>,
+}
+
+/// Rendered report sections on disk, keyed by section id and a hash of the
+/// section's resolved input, so unchanged sections are reused across report
+/// runs. Each entry is a `{key}.json` fragment plus a `{key}.{n}.svg` file
+/// per chart. Past `max_bytes` the least recently used entries are removed.
+#[derive(Debug, Clone)]
+pub struct SectionCache {
+    directory: PathBuf,
+    max_bytes: u64,
+}
+
+impl SectionCache {
+    pub fn new(directory: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        Self {
+            directory: directory.into(),
+            max_bytes,
+        }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Hash of a section's resolved input, as used in its cache key.
+    pub fn input_hash(input: &serde_json::Value) -> Result<String> {
+        let hash = content_hash(&serde_json::to_vec(input)?);
+        Ok(hash.trim_start_matches("sha256:").to_string())
+    }
+
+    /// The cached fragment, or `None` on a miss. A hit counts as a use for
+    /// eviction; an unreadable entry is treated as a miss.
+    pub fn get(&self, section_id: &str, input_hash: &str) -> Option<SectionFragment> {
+        let key = entry_key(section_id, input_hash);
+        let fragment_path = self.directory.join(format!("{key}.json"));
+        let mut fragment: SectionFragment =
+            serde_json::from_slice(&fs::read(&fragment_path).ok()?).ok()?;
+        for (index, chart) in fragment.charts.iter_mut().enumerate() {
+            chart.svg =
+                fs::read_to_string(self.directory.join(format!("{key}.{index}.svg"))).ok()?;
+        }
+        if let Ok(file) = fs::File::options().write(true).open(&fragment_path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(fragment)
+    }
+
+    /// Stores `fragment`, then evicts older entries past the size cap.
+    pub fn put(&self, input_hash: &str, fragment: &SectionFragment) -> Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        let key = entry_key(&fragment.section_id, input_hash);
+        for (index, chart) in fragment.charts.iter().enumerate() {
+            fs::write(
+                self.directory.join(format!("{key}.{index}.svg")),
+                &chart.svg,
+            )?;
+        }
+        // The fragment goes last: an entry without one is never read.
+        fs::write(
+            self.directory.join(format!("{key}.json")),
+            serde_json::to_vec(fragment)?,
+        )?;
+        self.evict(&key)
+    }
+
+    /// Removes least recently used entries other than `keep` until the cache
+    /// fits in `max_bytes`.
+    fn evict(&self, keep: &str) -> Result<()> {
+        struct Entry {
+            key: String,
+            used_at: SystemTime,
+            bytes: u64,
+            paths: Vec<PathBuf>,
+        }
+
+        let mut entries: Vec<Entry> = Vec::new();
+        for file in fs::read_dir(&self.directory)? {
+            let file = file?;
+            let name = file.file_name().to_string_lossy().into_owned();
+            let Some(key) = name.split('.').next().map(str::to_string) else {
+                continue;
+            };
+            let metadata = file.metadata()?;
+            let index = match entries.iter().position(|entry| entry.key == key) {
+                Some(index) => index,
+                None => {
+                    entries.push(Entry {
+                        key,
+                        used_at: SystemTime::UNIX_EPOCH,
+                        bytes: 0,
+                        paths: Vec::new(),
+                    });
+                    entries.len() - 1
+                }
+            };
+            let entry = &mut entries[index];
+            entry.bytes += metadata.len();
+            if name.ends_with(".json") {
+                entry.used_at = metadata.modified()?;
+            }
+            entry.paths.push(file.path());
+        }
+
+        let mut total: u64 = entries.iter().map(|entry| entry.bytes).sum();
+        entries.sort_by_key(|entry| entry.used_at);
+        for entry in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if entry.key == keep {
+                continue;
+            }
+            for path in &entry.paths {
+                fs::remove_file(path)?;
+            }
+            total -= entry.bytes;
+        }
+        Ok(())
+    }
+}
+
+/// File stem of a cache entry; section ids are reduced to characters that
+/// are safe in file names.
+fn entry_key(section_id: &str, input_hash: &str) -> String {
+    let section: String = section_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{section}-{input_hash}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::palette::OKABE_ITO;
+
+    fn fragment(section_id: &str, content_len: usize) -> SectionFragment {
+        SectionFragment {
+            section_id: section_id.to_string(),
+            title: section_id.to_string(),
+            content: "x".repeat(content_len),
+            tables: vec![],
+            charts: vec![RenderedChart {
+                alt_text: "chart".to_string(),
+                svg: "<svg/>".to_string(),
+            }],
+            localization_warnings: vec![],
+        }
+    }
+
+    fn chart_config(color_scheme: &str) -> VisualizationConfig {
+        VisualizationConfig {
+            chart_type: crate::report_generator::ChartType::BarChart,
+            color_scheme: color_scheme.to_string(),
+            dimensions: (100, 50),
+            include_legend: false,
+            title: None,
+            custom_parameters: Default::default(),
+        }
+    }
+
+    #[test]
+    fn chart_bars_take_their_fill_from_the_named_palette() {
+        let data = serde_json::json!({ "area": 2.0, "coverage": { "flown": 4.0 } });
+
+        let svg = SvgChartRenderer
+            .render("metrics", &chart_config("viridis"), &data)
+            .unwrap();
+        assert!(svg.contains("fill=\"#21918c\""), "{svg}");
+        assert!(svg.contains("fill=\"#fde725\""), "{svg}");
+
+        let categorical = SvgChartRenderer
+            .render("metrics", &chart_config("okabe-ito"), &data)
+            .unwrap();
+        let colors = OKABE_ITO.assign_class_colors(["area", "coverage.flown"]);
+        for color in colors.values() {
+            assert!(categorical.contains(&format!("fill=\"{}\"", hex(*color))));
+        }
+        assert_eq!(
+            SvgChartRenderer
+                .render("metrics", &chart_config("okabe-ito"), &data)
+                .unwrap(),
+            categorical
+        );
+
+        let error = SvgChartRenderer
+            .render("metrics", &chart_config("terrain"), &data)
+            .unwrap_err();
+        assert!(error.to_string().contains("available palettes: viridis"));
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted_past_the_size_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = SectionCache::new(dir.path(), 2_500);
+        let epoch = SystemTime::now() - std::time::Duration::from_secs(60);
+
+        cache.put("a", &fragment("first", 1_000)).unwrap();
+        cache.put("b", &fragment("second", 1_000)).unwrap();
+        // Backdate both so the read below is clearly the latest use.
+        for key in ["first-a", "second-b"] {
+            let file = fs::File::options()
+                .write(true)
+                .open(dir.path().join(format!("{key}.json")))
+                .unwrap();
+            file.set_modified(epoch).unwrap();
+        }
+        assert_eq!(cache.get("first", "a").unwrap().charts[0].svg, "<svg/>");
+
+        cache.put("c", &fragment("third", 1_000)).unwrap();
+
+        assert!(cache.get("first", "a").is_some());
+        assert!(cache.get("second", "b").is_none());
+        assert!(!dir.path().join("second-b.0.svg").exists());
+        assert!(cache.get("third", "c").is_some());
+    }
+}
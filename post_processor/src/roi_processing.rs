@@ -0,0 +1,765 @@
+use crate::zonal_statistics::{compute_statistics, DEFAULT_PERCENTILES};
+use crate::{AnalysisStatistics, ProcessingParameters, ResultData};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shared::geospatial::{GeoPoint, GeoPolygon};
+use thiserror::Error;
+
+/// `custom_parameters` key holding the prioritized ROI polygons.
+pub const PRIORITIZED_ROIS_KEY: &str = "prioritized_rois";
+/// `custom_parameters` key holding the
+/// [`NdviAnalysisRequest`](crate::ndvi_analysis::NdviAnalysisRequest) an NDVI
+/// job with prioritized ROIs analyzes.
+pub const NDVI_REQUEST_PAYLOAD_KEY: &str = "ndvi_request_payload";
+/// `custom_parameters` key holding the
+/// [`ThermalAnalysisRequest`](crate::thermal_analysis::ThermalAnalysisRequest)
+/// a thermal job with prioritized ROIs analyzes.
+pub const THERMAL_REQUEST_PAYLOAD_KEY: &str = "thermal_request_payload";
+/// `custom_parameters` key overriding the processing block edge, in pixels.
+pub const ROI_BLOCK_SIZE_KEY: &str = "roi_block_size_px";
+pub const DEFAULT_ROI_BLOCK_SIZE_PX: u32 = 64;
+
+pub(crate) const METERS_PER_DEGREE: f64 = 111_320.0;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RoiProcessingError {
+    #[error("invalid `{PRIORITIZED_ROIS_KEY}` parameter: {reason}")]
+    InvalidRois { reason: String },
+    #[error("ROI {roi_id} needs at least three boundary vertices")]
+    DegenerateRoi { roi_id: String },
+    #[error("`{ROI_BLOCK_SIZE_KEY}` must be a positive integer, got {value}")]
+    InvalidBlockSize { value: Value },
+    #[error("grid is empty ({width}x{height})")]
+    EmptyGrid { width: u32, height: u32 },
+    #[error("block {width}x{height} at ({x}, {y}) is not part of the processing schedule")]
+    UnknownBlock {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    #[error("block at ({x}, {y}) expected {expected} values, got {actual}")]
+    BlockSizeMismatch {
+        x: u32,
+        y: u32,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("block at ({x}, {y}) was already assembled")]
+    DuplicateBlock { x: u32, y: u32 },
+}
+
+/// Area the grower wants analyzed before the rest of the image.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrioritizedRoi {
+    pub id: String,
+    /// Outline as `(lon, lat)` vertices.
+    pub boundary: Vec<(f64, f64)>,
+    /// Higher priorities are processed first.
+    #[serde(default)]
+    pub priority: u32,
+}
+
+/// Pixel window of one processing block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockWindow {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BlockWindow {
+    pub fn pixel_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Row-major indices of the window's pixels in a grid `grid_width` wide.
+    pub fn pixel_indices(&self, grid_width: u32) -> impl Iterator<Item = usize> {
+        let window = *self;
+        (window.y..window.y + window.height).flat_map(move |row| {
+            (window.x..window.x + window.width)
+                .map(move |col| row as usize * grid_width as usize + col as usize)
+        })
+    }
+}
+
+/// Raster extent being tiled; row 0 is the northern edge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridGeometry {
+    pub width: u32,
+    pub height: u32,
+    /// `(min_lon, min_lat, max_lon, max_lat)`, matching `ResultData::GridData`.
+    pub bounds: (f64, f64, f64, f64),
+    pub pixel_area_m2: f32,
+}
+
+impl GridGeometry {
+    /// Builds the geometry from image corners given as `(lat, lon)`, deriving
+    /// the pixel footprint from the extent.
+    pub fn from_corners(
+        width: u32,
+        height: u32,
+        top_left: (f64, f64),
+        bottom_right: (f64, f64),
+    ) -> Self {
+        let bounds = (
+            top_left.1.min(bottom_right.1),
+            top_left.0.min(bottom_right.0),
+            top_left.1.max(bottom_right.1),
+            top_left.0.max(bottom_right.0),
+        );
+        let mid_lat = ((bounds.1 + bounds.3) / 2.0).to_radians();
+        let pixel_width_m =
+            (bounds.2 - bounds.0) * METERS_PER_DEGREE * mid_lat.cos() / width.max(1) as f64;
+        let pixel_height_m = (bounds.3 - bounds.1) * METERS_PER_DEGREE / height.max(1) as f64;
+        Self {
+            width,
+            height,
+            bounds,
+            pixel_area_m2: (pixel_width_m * pixel_height_m) as f32,
+        }
+    }
+
+    /// Uses a known ground sample distance instead of the extent-derived one.
+    pub fn with_pixel_size_m(mut self, pixel_size_m: f32) -> Self {
+        self.pixel_area_m2 = pixel_size_m * pixel_size_m;
+        self
+    }
+
+    fn pixel_size_degrees(&self) -> (f64, f64) {
+        (
+            (self.bounds.2 - self.bounds.0) / self.width as f64,
+            (self.bounds.3 - self.bounds.1) / self.height as f64,
+        )
+    }
+
+    fn pixel_centre(&self, col: u32, row: u32) -> (f64, f64) {
+        let (lon_step, lat_step) = self.pixel_size_degrees();
+        (
+            self.bounds.0 + (col as f64 + 0.5) * lon_step,
+            self.bounds.3 - (row as f64 + 0.5) * lat_step,
+        )
+    }
+
+    fn intersects(&self, window: &BlockWindow, roi: &PrioritizedRoi) -> bool {
+        let (lon_step, lat_step) = self.pixel_size_degrees();
+        let min_lon = self.bounds.0 + window.x as f64 * lon_step;
+        let max_lon = min_lon + window.width as f64 * lon_step;
+        let max_lat = self.bounds.3 - window.y as f64 * lat_step;
+        let min_lat = max_lat - window.height as f64 * lat_step;
+        // Vertex check catches ROIs smaller than a pixel that no centre falls in.
+        if roi.boundary.iter().any(|&(lon, lat)| {
+            (min_lon..=max_lon).contains(&lon) && (min_lat..=max_lat).contains(&lat)
+        }) {
+            return true;
+        }
+        let boundary = GeoPolygon::new(
+            roi.boundary
+                .iter()
+                .copied()
+                .map(GeoPoint::from_lon_lat_tuple)
+                .collect(),
+        );
+        (window.y..window.y + window.height).any(|row| {
+            (window.x..window.x + window.width).any(|col| {
+                let centre = GeoPoint::from_lon_lat_tuple(self.pixel_centre(col, row));
+                boundary.contains(&centre)
+            })
+        })
+    }
+}
+
+/// Order in which an analysis visits its blocks: every block touching a
+/// prioritized ROI (highest priority first), then the rest in row-major order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockSchedule {
+    pub geometry: GridGeometry,
+    pub block_size: u32,
+    pub blocks: Vec<BlockWindow>,
+    /// Number of leading `blocks` that intersect an ROI.
+    pub roi_block_count: usize,
+}
+
+impl BlockSchedule {
+    pub fn new(
+        geometry: GridGeometry,
+        block_size: u32,
+        rois: &[PrioritizedRoi],
+    ) -> Result<Self, RoiProcessingError> {
+        if geometry.width == 0 || geometry.height == 0 {
+            return Err(RoiProcessingError::EmptyGrid {
+                width: geometry.width,
+                height: geometry.height,
+            });
+        }
+        if block_size == 0 {
+            return Err(RoiProcessingError::InvalidBlockSize {
+                value: Value::from(block_size),
+            });
+        }
+
+        let mut roi_blocks = Vec::new();
+        let mut remaining = Vec::new();
+        for y in (0..geometry.height).step_by(block_size as usize) {
+            for x in (0..geometry.width).step_by(block_size as usize) {
+                let window = BlockWindow {
+                    x,
+                    y,
+                    width: block_size.min(geometry.width - x),
+                    height: block_size.min(geometry.height - y),
+                };
+                match rois
+                    .iter()
+                    .filter(|roi| geometry.intersects(&window, roi))
+                    .map(|roi| roi.priority)
+                    .max()
+                {
+                    Some(priority) => roi_blocks.push((priority, window)),
+                    None => remaining.push(window),
+                }
+            }
+        }
+        // Stable sort keeps row-major order within a priority.
+        roi_blocks.sort_by_key(|(priority, _)| std::cmp::Reverse(*priority));
+
+        let roi_block_count = roi_blocks.len();
+        let blocks = roi_blocks
+            .into_iter()
+            .map(|(_, window)| window)
+            .chain(remaining)
+            .collect();
+        Ok(Self {
+            geometry,
+            block_size,
+            blocks,
+            roi_block_count,
+        })
+    }
+
+    /// Reads the ROIs and block size from the job's `custom_parameters`.
+    /// Without ROIs the schedule is plain row-major tiling.
+    pub fn from_parameters(
+        geometry: GridGeometry,
+        parameters: &ProcessingParameters,
+    ) -> Result<Self, RoiProcessingError> {
+        Self::new(
+            geometry,
+            roi_block_size(parameters)?,
+            &prioritized_rois(parameters)?,
+        )
+    }
+
+    fn blocks_per_row(&self) -> usize {
+        self.geometry.width.div_ceil(self.block_size) as usize
+    }
+
+    fn block_slot(&self, window: &BlockWindow) -> Option<usize> {
+        if !window.x.is_multiple_of(self.block_size) || !window.y.is_multiple_of(self.block_size) {
+            return None;
+        }
+        let slot = (window.y / self.block_size) as usize * self.blocks_per_row()
+            + (window.x / self.block_size) as usize;
+        let expected = BlockWindow {
+            x: window.x,
+            y: window.y,
+            width: self
+                .block_size
+                .min(self.geometry.width.checked_sub(window.x)?),
+            height: self
+                .block_size
+                .min(self.geometry.height.checked_sub(window.y)?),
+        };
+        (expected == *window && expected.width > 0 && expected.height > 0).then_some(slot)
+    }
+}
+
+pub fn prioritized_rois(
+    parameters: &ProcessingParameters,
+) -> Result<Vec<PrioritizedRoi>, RoiProcessingError> {
+    let Some(value) = parameters.custom_parameters.get(PRIORITIZED_ROIS_KEY) else {
+        return Ok(Vec::new());
+    };
+    let rois: Vec<PrioritizedRoi> =
+        serde_json::from_value(value.clone()).map_err(|error| RoiProcessingError::InvalidRois {
+            reason: error.to_string(),
+        })?;
+    if let Some(roi) = rois.iter().find(|roi| roi.boundary.len() < 3) {
+        return Err(RoiProcessingError::DegenerateRoi {
+            roi_id: roi.id.clone(),
+        });
+    }
+    Ok(rois)
+}
+
+pub fn roi_block_size(parameters: &ProcessingParameters) -> Result<u32, RoiProcessingError> {
+    match parameters.custom_parameters.get(ROI_BLOCK_SIZE_KEY) {
+        None => Ok(DEFAULT_ROI_BLOCK_SIZE_PX),
+        Some(value) => value
+            .as_u64()
+            .and_then(|size| u32::try_from(size).ok())
+            .filter(|size| *size > 0)
+            .ok_or_else(|| RoiProcessingError::InvalidBlockSize {
+                value: value.clone(),
+            }),
+    }
+}
+
+/// Snapshot of a prioritized analysis after some of its blocks completed.
+#[derive(Debug, Clone, Serialize)]
+pub struct PartialGridResult {
+    pub completed_blocks: usize,
+    pub total_blocks: usize,
+    /// Fraction of grid pixels assembled so far.
+    pub completeness: f32,
+    /// Whether every block touching a prioritized ROI is assembled.
+    pub rois_complete: bool,
+    /// Full-size grid; pixels not yet assembled are NaN.
+    pub data: ResultData,
+    /// Statistics over the assembled pixels only.
+    pub statistics: AnalysisStatistics,
+}
+
+impl PartialGridResult {
+    pub fn is_complete(&self) -> bool {
+        self.completed_blocks == self.total_blocks
+    }
+}
+
+/// Collects block results in whatever order they finish.
+#[derive(Debug, Clone)]
+pub struct GridAssembler {
+    schedule: BlockSchedule,
+    units: String,
+    is_valid: fn(f32) -> bool,
+    values: Vec<f32>,
+    assembled_pixels: Vec<bool>,
+    assembled_blocks: Vec<bool>,
+    roi_slots: Vec<usize>,
+    completed_blocks: usize,
+    completed_pixels: usize,
+}
+
+impl GridAssembler {
+    /// `is_valid` decides which assembled values count towards statistics,
+    /// e.g. NDVI's `-1.0` no-data marker.
+    pub fn new(
+        schedule: BlockSchedule,
+        units: impl Into<String>,
+        is_valid: fn(f32) -> bool,
+    ) -> Self {
+        let pixel_count = schedule.geometry.width as usize * schedule.geometry.height as usize;
+        let roi_slots = schedule.blocks[..schedule.roi_block_count]
+            .iter()
+            .filter_map(|window| schedule.block_slot(window))
+            .collect();
+        Self {
+            values: vec![f32::NAN; pixel_count],
+            assembled_pixels: vec![false; pixel_count],
+            assembled_blocks: vec![false; schedule.blocks.len()],
+            roi_slots,
+            completed_blocks: 0,
+            completed_pixels: 0,
+            units: units.into(),
+            is_valid,
+            schedule,
+        }
+    }
+
+    pub fn insert_block(
+        &mut self,
+        window: BlockWindow,
+        values: &[f32],
+    ) -> Result<(), RoiProcessingError> {
+        let slot = self
+            .schedule
+            .block_slot(&window)
+            .ok_or(RoiProcessingError::UnknownBlock {
+                x: window.x,
+                y: window.y,
+                width: window.width,
+                height: window.height,
+            })?;
+        if values.len() != window.pixel_count() {
+            return Err(RoiProcessingError::BlockSizeMismatch {
+                x: window.x,
+                y: window.y,
+                expected: window.pixel_count(),
+                actual: values.len(),
+            });
+        }
+        if self.assembled_blocks[slot] {
+            return Err(RoiProcessingError::DuplicateBlock {
+                x: window.x,
+                y: window.y,
+            });
+        }
+
+        for (index, value) in window
+            .pixel_indices(self.schedule.geometry.width)
+            .zip(values)
+        {
+            self.values[index] = *value;
+            self.assembled_pixels[index] = true;
+        }
+        self.assembled_blocks[slot] = true;
+        self.completed_blocks += 1;
+        self.completed_pixels += window.pixel_count();
+        Ok(())
+    }
+
+    /// Current grid with statistics recomputed over everything assembled.
+    pub fn snapshot(&self) -> PartialGridResult {
+        let geometry = &self.schedule.geometry;
+        PartialGridResult {
+            completed_blocks: self.completed_blocks,
+            total_blocks: self.schedule.blocks.len(),
+            completeness: self.completed_pixels as f32 / self.values.len() as f32,
+            rois_complete: self
+                .roi_slots
+                .iter()
+                .all(|slot| self.assembled_blocks[*slot]),
+            data: ResultData::GridData {
+                width: geometry.width,
+                height: geometry.height,
+                values: self.values.clone().into(),
+                bounds: geometry.bounds,
+                units: self.units.clone(),
+            },
+            statistics: self.statistics(),
+        }
+    }
+
+    fn statistics(&self) -> AnalysisStatistics {
+        let valid_values: Vec<f32> = self
+            .values
+            .iter()
+            .zip(&self.assembled_pixels)
+            .filter(|(value, assembled)| **assembled && (self.is_valid)(**value))
+            .map(|(value, _)| *value)
+            .collect();
+        let statistics = compute_statistics(&valid_values, &DEFAULT_PERCENTILES);
+        AnalysisStatistics {
+            coverage_area_m2: statistics.valid_pixel_count as f32
+                * self.schedule.geometry.pixel_area_m2,
+            total_pixel_count: self.completed_pixels as u32,
+            ..statistics
+        }
+    }
+}
+
+/// Runs `compute_block` over the schedule, handing a fresh snapshot to
+/// `on_partial` after every block, and returns the completed grid.
+pub fn process_prioritized<F, P>(
+    schedule: BlockSchedule,
+    units: &str,
+    is_valid: fn(f32) -> bool,
+    mut compute_block: F,
+    mut on_partial: P,
+) -> anyhow::Result<PartialGridResult>
+where
+    F: FnMut(BlockWindow) -> anyhow::Result<Vec<f32>>,
+    P: FnMut(&PartialGridResult),
+{
+    let blocks = schedule.blocks.clone();
+    let mut assembler = GridAssembler::new(schedule, units, is_valid);
+    let mut latest = assembler.snapshot();
+    for window in blocks {
+        let values = compute_block(window)?;
+        assembler.insert_block(window, &values)?;
+        latest = assembler.snapshot();
+        on_partial(&latest);
+    }
+    Ok(latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ndvi_analysis::{
+        GeoreferenceInfo as NdviGeoreference, NdviAnalysisConfig, NdviAnalysisProcessor,
+        NdviAnalysisRequest,
+    };
+    use crate::thermal_analysis::{
+        EnvironmentalConditions, GeoreferenceInfo as ThermalGeoreference, TemperatureUnit,
+        ThermalAnalysisConfig, ThermalAnalysisParameters, ThermalAnalysisProcessor,
+        ThermalAnalysisRequest,
+    };
+    use chrono::Utc;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    const SIZE: u32 = 12;
+    const BLOCK: u32 = 4;
+
+    /// Polygon around the centres of pixels 9..=10 in both axes, which all
+    /// fall in the bottom-right 4x4 block of the 12x12 test grid.
+    fn bottom_right_roi() -> serde_json::Value {
+        json!([{
+            "id": "wet-corner",
+            "boundary": [[0.0085, 0.0015], [0.011, 0.0015], [0.011, 0.0035], [0.0085, 0.0035]],
+            "priority": 5
+        }])
+    }
+
+    fn parameters(custom: &[(&str, serde_json::Value)]) -> ProcessingParameters {
+        ProcessingParameters {
+            analysis_type: "ndvi".to_string(),
+            quality_threshold: 0.5,
+            spatial_resolution_m: 1.0,
+            temporal_aggregation: None,
+            output_formats: Vec::new(),
+            custom_parameters: custom
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn ndvi_request() -> NdviAnalysisRequest {
+        let pixels = (SIZE * SIZE) as usize;
+        NdviAnalysisRequest {
+            id: Uuid::new_v4(),
+            red_band_data: (0..pixels).map(|i| 200 + (i * 37 % 300) as u16).collect(),
+            nir_band_data: (0..pixels).map(|i| 300 + (i * 91 % 900) as u16).collect(),
+            image_width: SIZE,
+            image_height: SIZE,
+            georeference_info: NdviGeoreference {
+                top_left_lat: 0.012,
+                top_left_lon: 0.0,
+                bottom_right_lat: 0.0,
+                bottom_right_lon: 0.012,
+                pixel_size_m: 1.0,
+                coordinate_system: "EPSG:4326".to_string(),
+            },
+            capture_time: Utc::now(),
+            quality_mask: Some((0..pixels).map(|i| u8::from(i % 17 != 0)).collect()),
+        }
+    }
+
+    fn grid_values(result: &PartialGridResult) -> &[f32] {
+        match &result.data {
+            ResultData::GridData { values, .. } => values,
+            other => panic!("expected grid data, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn roi_blocks_are_scheduled_first() {
+        let geometry = GridGeometry::from_corners(SIZE, SIZE, (0.012, 0.0), (0.0, 0.012));
+        let schedule = BlockSchedule::from_parameters(
+            geometry,
+            &parameters(&[
+                (PRIORITIZED_ROIS_KEY, bottom_right_roi()),
+                (ROI_BLOCK_SIZE_KEY, json!(BLOCK)),
+            ]),
+        )
+        .expect("schedule builds");
+
+        assert_eq!(schedule.blocks.len(), 9);
+        assert_eq!(schedule.roi_block_count, 1);
+        assert_eq!(
+            schedule.blocks[0],
+            BlockWindow {
+                x: 8,
+                y: 8,
+                width: 4,
+                height: 4
+            }
+        );
+        assert_eq!(schedule.blocks[1].x, 0);
+        assert_eq!(schedule.blocks[1].y, 0);
+    }
+
+    #[tokio::test]
+    async fn ndvi_partials_cover_roi_first_and_final_matches_full_image() {
+        let mut processor = NdviAnalysisProcessor::new(NdviAnalysisConfig::default());
+        let request = ndvi_request();
+        let params = parameters(&[
+            (PRIORITIZED_ROIS_KEY, bottom_right_roi()),
+            (ROI_BLOCK_SIZE_KEY, json!(BLOCK)),
+        ]);
+
+        let mut partials = Vec::new();
+        let prioritized = processor
+            .process_ndvi_prioritized(&request, &params, |partial| partials.push(partial.clone()))
+            .expect("prioritized NDVI runs");
+        let whole_image = processor
+            .process_ndvi_prioritized(
+                &request,
+                &parameters(&[(ROI_BLOCK_SIZE_KEY, json!(SIZE))]),
+                |_| {},
+            )
+            .expect("single-block NDVI runs");
+        let reference = processor
+            .process_ndvi_request(request.clone())
+            .await
+            .expect("full-image NDVI runs");
+
+        assert_eq!(partials.len(), 9);
+        let first = &partials[0];
+        assert!(first.rois_complete);
+        assert_eq!(first.completed_blocks, 1);
+        assert!((first.completeness - 16.0 / 144.0).abs() < f32::EPSILON);
+        for (index, value) in grid_values(first).iter().enumerate() {
+            let (col, row) = (index as u32 % SIZE, index as u32 / SIZE);
+            let in_roi_block = col >= 8 && row >= 8;
+            assert_eq!(!value.is_nan(), in_roi_block, "pixel ({col}, {row})");
+            if in_roi_block {
+                assert_eq!(value.to_bits(), reference.ndvi_map[index].to_bits());
+            }
+        }
+        assert_eq!(first.statistics.total_pixel_count, 16);
+
+        assert!(prioritized.is_complete());
+        assert_eq!(prioritized.completeness, 1.0);
+        let final_bits: Vec<u32> = grid_values(&prioritized)
+            .iter()
+            .map(|v| v.to_bits())
+            .collect();
+        let reference_bits: Vec<u32> = reference.ndvi_map.iter().map(|v| v.to_bits()).collect();
+        assert_eq!(final_bits, reference_bits);
+        assert_eq!(prioritized.statistics, whole_image.statistics);
+        assert_eq!(prioritized.statistics.total_pixel_count, SIZE * SIZE);
+    }
+
+    #[tokio::test]
+    async fn thermal_blocks_match_full_image_noise_reduction() {
+        let mut processor = ThermalAnalysisProcessor::new(ThermalAnalysisConfig {
+            temperature_unit: TemperatureUnit::Celsius,
+            emissivity_default: 0.95,
+            ambient_temp_default: 20.0,
+            enable_noise_reduction: true,
+            enable_temperature_mapping: true,
+            thermal_threshold_high: 50.0,
+            thermal_threshold_low: 0.0,
+            region_emissivity: Vec::new(),
+            max_cached_results: 0,
+        });
+        let request = ThermalAnalysisRequest {
+            id: Uuid::new_v4(),
+            thermal_image_data: (0..SIZE * SIZE)
+                .map(|i| 2000 + (i * 7919 % 1500) as u16)
+                .collect(),
+            image_width: SIZE,
+            image_height: SIZE,
+            capture_time: Utc::now(),
+            georeference_info: ThermalGeoreference {
+                top_left_lat: 0.012,
+                top_left_lon: 0.0,
+                bottom_right_lat: 0.0,
+                bottom_right_lon: 0.012,
+                altitude: 60.0,
+                altitude_reference: shared::AltitudeReference::RelativeToHome,
+                camera_angle: 0.0,
+            },
+            environmental_conditions: EnvironmentalConditions {
+                ambient_temperature: 20.0,
+                humidity: 50.0,
+                wind_speed: 3.0,
+                atmospheric_pressure: 1013.25,
+                solar_irradiance: 800.0,
+            },
+            analysis_parameters: ThermalAnalysisParameters {
+                emissivity: 0.95,
+                distance_to_target: 60.0,
+                atmospheric_temperature: 20.0,
+                relative_humidity: 50.0,
+                analysis_regions: vec![],
+            },
+        };
+        let params = parameters(&[
+            (PRIORITIZED_ROIS_KEY, bottom_right_roi()),
+            (ROI_BLOCK_SIZE_KEY, json!(5)),
+        ]);
+
+        let mut first_window = None;
+        let prioritized = processor
+            .process_thermal_prioritized(&request, &params, |partial| {
+                if first_window.is_none() {
+                    first_window = Some(partial.completed_blocks);
+                }
+            })
+            .expect("prioritized thermal runs");
+        let reference = processor
+            .process_thermal_request(request)
+            .await
+            .expect("full-image thermal runs");
+
+        assert_eq!(first_window, Some(1));
+        let final_bits: Vec<u32> = grid_values(&prioritized)
+            .iter()
+            .map(|v| v.to_bits())
+            .collect();
+        let reference_bits: Vec<u32> = reference
+            .temperature_map
+            .iter()
+            .map(|v| v.to_bits())
+            .collect();
+        assert_eq!(final_bits, reference_bits);
+    }
+
+    #[test]
+    fn assembler_accepts_blocks_out_of_order_and_rejects_duplicates() {
+        let geometry = GridGeometry::from_corners(4, 2, (1.0, 0.0), (0.0, 1.0));
+        let schedule = BlockSchedule::new(geometry, 2, &[]).expect("schedule builds");
+        let mut assembler = GridAssembler::new(schedule.clone(), "ndvi", |value| value >= 0.0);
+
+        assembler
+            .insert_block(schedule.blocks[1], &[0.4, 0.6, 0.8, -1.0])
+            .expect("second block first");
+        let partial = assembler.snapshot();
+        assert_eq!(partial.completed_blocks, 1);
+        assert_eq!(partial.completeness, 0.5);
+        assert_eq!(partial.statistics.valid_pixel_count, 3);
+        assert_eq!(partial.statistics.max_value, 0.8);
+
+        assembler
+            .insert_block(schedule.blocks[0], &[0.1, 0.2, 0.3, 0.4])
+            .expect("first block second");
+        let complete = assembler.snapshot();
+        assert!(complete.is_complete());
+        assert_eq!(
+            grid_values(&complete),
+            &[0.1, 0.2, 0.4, 0.6, 0.3, 0.4, 0.8, -1.0]
+        );
+        assert_eq!(complete.statistics.min_value, 0.1);
+        assert_eq!(complete.statistics.valid_pixel_count, 7);
+
+        assert_eq!(
+            assembler.insert_block(schedule.blocks[0], &[0.0; 4]),
+            Err(RoiProcessingError::DuplicateBlock { x: 0, y: 0 })
+        );
+        assert!(matches!(
+            assembler.insert_block(
+                BlockWindow {
+                    x: 1,
+                    y: 0,
+                    width: 2,
+                    height: 2
+                },
+                &[0.0; 4]
+            ),
+            Err(RoiProcessingError::UnknownBlock { .. })
+        ));
+    }
+
+    #[test]
+    fn malformed_roi_parameters_are_rejected() {
+        let degenerate = parameters(&[(
+            PRIORITIZED_ROIS_KEY,
+            json!([{ "id": "line", "boundary": [[0.0, 0.0], [1.0, 1.0]] }]),
+        )]);
+        assert_eq!(
+            prioritized_rois(&degenerate),
+            Err(RoiProcessingError::DegenerateRoi {
+                roi_id: "line".to_string()
+            })
+        );
+        assert!(matches!(
+            roi_block_size(&parameters(&[(ROI_BLOCK_SIZE_KEY, json!(0))])),
+            Err(RoiProcessingError::InvalidBlockSize { .. })
+        ));
+    }
+}
@@ -1,16 +1,76 @@
+use crate::roi_processing::{
+    process_prioritized, BlockSchedule, BlockWindow, GridGeometry, PartialGridResult,
+};
+use crate::zonal_statistics::{compute_statistics, DEFAULT_PERCENTILES};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use shared::AltitudeReference;
+use std::collections::{HashMap, VecDeque};
 use uuid::Uuid;
 
 /// Thermal imaging analysis and processing system
 pub struct ThermalAnalysisProcessor {
     config: ThermalAnalysisConfig,
-    thermal_cache: HashMap<Uuid, ThermalAnalysisResult>,
+    thermal_cache: ThermalResultCache,
     calibration_data: ThermalCalibration,
 }
 
+/// Processed results kept for [`ThermalAnalysisProcessor::get_cached_result`],
+/// evicting the least recently processed or read result past `capacity`.
+struct ThermalResultCache {
+    capacity: usize,
+    results: HashMap<Uuid, ThermalAnalysisResult>,
+    /// Request ids from least to most recently used.
+    access_order: VecDeque<Uuid>,
+}
+
+impl ThermalResultCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            results: HashMap::new(),
+            access_order: VecDeque::new(),
+        }
+    }
+
+    fn insert(&mut self, result: ThermalAnalysisResult) {
+        if self.capacity == 0 {
+            return;
+        }
+        let request_id = result.request_id;
+        self.results.insert(request_id, result);
+        self.touch(request_id);
+        while self.results.len() > self.capacity {
+            let Some(evicted) = self.access_order.pop_front() else {
+                break;
+            };
+            self.results.remove(&evicted);
+        }
+    }
+
+    fn get(&mut self, request_id: Uuid) -> Option<&ThermalAnalysisResult> {
+        if self.results.contains_key(&request_id) {
+            self.touch(request_id);
+        }
+        self.results.get(&request_id)
+    }
+
+    fn touch(&mut self, request_id: Uuid) {
+        self.access_order.retain(|id| *id != request_id);
+        self.access_order.push_back(request_id);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThermalCacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    /// Struct sizes plus the temperature map and per-detection vectors; the
+    /// strings inside detections are not counted.
+    pub estimated_bytes: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThermalAnalysisConfig {
     pub temperature_unit: TemperatureUnit,
@@ -20,6 +80,51 @@ pub struct ThermalAnalysisConfig {
     pub enable_temperature_mapping: bool,
     pub thermal_threshold_high: f32,
     pub thermal_threshold_low: f32,
+    /// Emissivity used inside regions of each type that do not set their
+    /// own; types not listed fall back to the request's emissivity.
+    #[serde(default = "default_region_emissivity")]
+    pub region_emissivity: Vec<RegionEmissivity>,
+    /// Processed results kept in memory; 0 disables the cache.
+    #[serde(default = "default_max_cached_results")]
+    pub max_cached_results: usize,
+}
+
+fn default_max_cached_results() -> usize {
+    32
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RegionEmissivity {
+    pub region_type: RegionType,
+    pub emissivity: f32,
+}
+
+/// Typical broadband emissivities. Equipment assumes painted metal; bare
+/// metal is far lower and should be set on the region itself.
+pub fn default_region_emissivity() -> Vec<RegionEmissivity> {
+    [
+        (RegionType::Vegetation, 0.98),
+        (RegionType::Soil, 0.93),
+        (RegionType::Water, 0.99),
+        (RegionType::Infrastructure, 0.92),
+        (RegionType::Livestock, 0.98),
+        (RegionType::Equipment, 0.90),
+    ]
+    .into_iter()
+    .map(|(region_type, emissivity)| RegionEmissivity {
+        region_type,
+        emissivity,
+    })
+    .collect()
+}
+
+impl ThermalAnalysisConfig {
+    fn emissivity_for(&self, region_type: &RegionType) -> Option<f32> {
+        self.region_emissivity
+            .iter()
+            .find(|entry| entry.region_type == *region_type)
+            .map(|entry| entry.emissivity)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -60,6 +165,8 @@ impl Default for ThermalAnalysisConfig {
             enable_temperature_mapping: true,
             thermal_threshold_high: 50.0,
             thermal_threshold_low: 0.0,
+            region_emissivity: default_region_emissivity(),
+            max_cached_results: default_max_cached_results(),
         }
     }
 }
@@ -83,6 +190,10 @@ pub struct GeoreferenceInfo {
     pub bottom_right_lat: f64,
     pub bottom_right_lon: f64,
     pub altitude: f32,
+    /// Datum `altitude` is measured from; flight logs report it relative to
+    /// home unless stated otherwise.
+    #[serde(default)]
+    pub altitude_reference: AltitudeReference,
     pub camera_angle: f32,
 }
 
@@ -111,6 +222,9 @@ pub struct AnalysisRegion {
     pub polygon: Vec<(u32, u32)>, // Pixel coordinates
     pub region_type: RegionType,
     pub expected_temperature_range: Option<(f32, f32)>,
+    /// Overrides the configured emissivity for this region's type.
+    #[serde(default)]
+    pub emissivity: Option<f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -147,6 +261,11 @@ pub struct ThermalStatistics {
     pub temperature_distribution: Vec<(f32, u32)>, // (temp_bin, count)
     pub hot_pixel_count: u32,
     pub cold_pixel_count: u32,
+    /// Pixels with a finite temperature; the rest were masked out.
+    #[serde(default)]
+    pub valid_pixel_count: u32,
+    #[serde(default)]
+    pub total_pixel_count: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -233,8 +352,8 @@ pub enum AnomalyType {
 impl ThermalAnalysisProcessor {
     pub fn new(config: ThermalAnalysisConfig) -> Self {
         Self {
+            thermal_cache: ThermalResultCache::new(config.max_cached_results),
             config,
-            thermal_cache: HashMap::new(),
             calibration_data: ThermalCalibration::default(),
         }
     }
@@ -281,7 +400,7 @@ impl ThermalAnalysisProcessor {
         };
 
         // Cache result
-        self.thermal_cache.insert(request.id, result.clone());
+        self.thermal_cache.insert(result.clone());
 
         tracing::info!(
             "Processed thermal analysis for request {} in {}ms",
@@ -306,10 +425,12 @@ impl ThermalAnalysisProcessor {
 
     fn calibrate_thermal_data(&self, request: &ThermalAnalysisRequest) -> Result<Vec<f32>> {
         let mut temperatures = Vec::with_capacity(request.thermal_image_data.len());
+        let emissivity = self.emissivity_map(request)?;
 
-        for &raw_value in &request.thermal_image_data {
+        for (&raw_value, &emissivity) in request.thermal_image_data.iter().zip(&emissivity) {
             let temperature = self.raw_to_temperature(
                 raw_value,
+                emissivity,
                 &request.analysis_parameters,
                 &request.environmental_conditions,
             )?;
@@ -324,9 +445,47 @@ impl ThermalAnalysisProcessor {
         Ok(temperatures)
     }
 
+    /// Emissivity of every pixel. The first region containing a pixel
+    /// decides, using its own emissivity or else the configured one for its
+    /// type; every other pixel uses the request's emissivity.
+    fn emissivity_map(&self, request: &ThermalAnalysisRequest) -> Result<Vec<f32>> {
+        let (width, height) = (request.image_width, request.image_height);
+        let mut emissivity_map =
+            vec![request.analysis_parameters.emissivity; request.thermal_image_data.len()];
+
+        for region in request.analysis_parameters.analysis_regions.iter().rev() {
+            let Some(emissivity) = region
+                .emissivity
+                .or_else(|| self.config.emissivity_for(&region.region_type))
+            else {
+                continue;
+            };
+            anyhow::ensure!(
+                emissivity > 0.0 && emissivity <= 1.0,
+                "Region '{}' emissivity {} is outside (0, 1]",
+                region.name,
+                emissivity
+            );
+            let Some((min_x, min_y, max_x, max_y)) = self.get_polygon_bounds(&region.polygon)
+            else {
+                continue;
+            };
+            for y in min_y..=max_y.min(height.saturating_sub(1)) {
+                for x in min_x..=max_x.min(width.saturating_sub(1)) {
+                    if self.point_in_polygon(x, y, &region.polygon) {
+                        emissivity_map[y as usize * width as usize + x as usize] = emissivity;
+                    }
+                }
+            }
+        }
+
+        Ok(emissivity_map)
+    }
+
     fn raw_to_temperature(
         &self,
         raw_value: u16,
+        emissivity: f32,
         params: &ThermalAnalysisParameters,
         env: &EnvironmentalConditions,
     ) -> Result<f32> {
@@ -335,7 +494,7 @@ impl ThermalAnalysisProcessor {
         let base_temp = raw_value as f32 * 0.01; // Basic scaling
 
         // Apply emissivity correction
-        let emissivity_corrected = base_temp / params.emissivity;
+        let emissivity_corrected = base_temp / emissivity;
 
         // Apply atmospheric correction
         let atmospheric_transmission = 0.98 - (params.distance_to_target * 0.001);
@@ -376,37 +535,139 @@ impl ThermalAnalysisProcessor {
         Ok(())
     }
 
+    /// Computes the calibrated temperature map block by block, ROI blocks
+    /// first, and reports each partial grid. The finished grid equals the one
+    /// produced by `process_thermal_request`.
+    pub fn process_thermal_prioritized(
+        &self,
+        request: &ThermalAnalysisRequest,
+        parameters: &super::ProcessingParameters,
+        on_partial: impl FnMut(&PartialGridResult),
+    ) -> Result<PartialGridResult> {
+        self.validate_thermal_request(request)?;
+        let georeference = &request.georeference_info;
+        let geometry = GridGeometry::from_corners(
+            request.image_width,
+            request.image_height,
+            (georeference.top_left_lat, georeference.top_left_lon),
+            (georeference.bottom_right_lat, georeference.bottom_right_lon),
+        );
+        let schedule = BlockSchedule::from_parameters(geometry, parameters)?;
+        let emissivity = self.emissivity_map(request)?;
+        let units = match self.config.temperature_unit {
+            TemperatureUnit::Celsius => "celsius",
+            TemperatureUnit::Fahrenheit => "fahrenheit",
+            TemperatureUnit::Kelvin => "kelvin",
+        };
+
+        process_prioritized(
+            schedule,
+            units,
+            f32::is_finite,
+            |window| self.temperature_block(request, &emissivity, window),
+            on_partial,
+        )
+    }
+
+    fn temperature_block(
+        &self,
+        request: &ThermalAnalysisRequest,
+        emissivity: &[f32],
+        window: BlockWindow,
+    ) -> Result<Vec<f32>> {
+        let (width, height) = (request.image_width, request.image_height);
+        // Calibrate a one-pixel halo so the median filter sees the same
+        // neighbours it would in a whole-image pass.
+        let halo_x = window.x.saturating_sub(1);
+        let halo_y = window.y.saturating_sub(1);
+        let halo_width = (window.x + window.width + 1).min(width) - halo_x;
+        let halo_height = (window.y + window.height + 1).min(height) - halo_y;
+        let halo_window = BlockWindow {
+            x: halo_x,
+            y: halo_y,
+            width: halo_width,
+            height: halo_height,
+        };
+        let halo = halo_window
+            .pixel_indices(width)
+            .map(|index| {
+                self.raw_to_temperature(
+                    request.thermal_image_data[index],
+                    emissivity[index],
+                    &request.analysis_parameters,
+                    &request.environmental_conditions,
+                )
+            })
+            .collect::<Result<Vec<f32>>>()?;
+        let halo_at =
+            |col: u32, row: u32| halo[((row - halo_y) * halo_width + (col - halo_x)) as usize];
+
+        let mut block = Vec::with_capacity(window.pixel_count());
+        for row in window.y..window.y + window.height {
+            for col in window.x..window.x + window.width {
+                let interior = row > 0 && col > 0 && row + 1 < height && col + 1 < width;
+                if self.config.enable_noise_reduction && interior {
+                    let mut neighbors = Vec::with_capacity(9);
+                    for neighbor_row in row - 1..=row + 1 {
+                        for neighbor_col in col - 1..=col + 1 {
+                            neighbors.push(halo_at(neighbor_col, neighbor_row));
+                        }
+                    }
+                    neighbors.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                    block.push(neighbors[neighbors.len() / 2]);
+                } else {
+                    block.push(halo_at(col, row));
+                }
+            }
+        }
+
+        Ok(block)
+    }
+
+    /// Statistics over the finite temperatures; masked (NaN) and infinite
+    /// pixels only count towards `total_pixel_count`.
     fn calculate_thermal_statistics(&self, temperature_map: &[f32]) -> Result<ThermalStatistics> {
         if temperature_map.is_empty() {
             return Err(anyhow::anyhow!("Empty temperature map"));
         }
 
-        let mut sorted_temps = temperature_map.to_vec();
-        sorted_temps.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut sorted_temps = temperature_map
+            .iter()
+            .copied()
+            .filter(|temp| temp.is_finite())
+            .collect::<Vec<_>>();
+        if sorted_temps.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Temperature map has no valid pixels out of {}",
+                temperature_map.len()
+            ));
+        }
+        sorted_temps.sort_by(f32::total_cmp);
 
-        let mean_temperature = temperature_map.iter().sum::<f32>() / temperature_map.len() as f32;
-        let median_temperature = sorted_temps[sorted_temps.len() / 2];
+        let valid_pixel_count = sorted_temps.len();
+        let mean_temperature = sorted_temps.iter().sum::<f32>() / valid_pixel_count as f32;
+        let median_temperature = sorted_temps[valid_pixel_count / 2];
         let min_temperature = sorted_temps[0];
-        let max_temperature = sorted_temps[sorted_temps.len() - 1];
+        let max_temperature = sorted_temps[valid_pixel_count - 1];
 
-        let variance = temperature_map
+        let variance = sorted_temps
             .iter()
             .map(|t| (t - mean_temperature).powi(2))
             .sum::<f32>()
-            / temperature_map.len() as f32;
+            / valid_pixel_count as f32;
         let temperature_std_dev = variance.sqrt();
 
-        let hot_pixel_count = temperature_map
+        let hot_pixel_count = sorted_temps
             .iter()
             .filter(|&&t| t > self.config.thermal_threshold_high)
             .count() as u32;
 
-        let cold_pixel_count = temperature_map
+        let cold_pixel_count = sorted_temps
             .iter()
             .filter(|&&t| t < self.config.thermal_threshold_low)
             .count() as u32;
 
-        let temperature_distribution = self.calculate_temperature_distribution(temperature_map);
+        let temperature_distribution = self.calculate_temperature_distribution(&sorted_temps);
 
         Ok(ThermalStatistics {
             mean_temperature,
@@ -417,15 +678,19 @@ impl ThermalAnalysisProcessor {
             temperature_distribution,
             hot_pixel_count,
             cold_pixel_count,
+            valid_pixel_count: valid_pixel_count as u32,
+            total_pixel_count: temperature_map.len() as u32,
         })
     }
 
+    /// Bins the finite temperatures into 20 equal-width bins.
     fn calculate_temperature_distribution(&self, temperatures: &[f32]) -> Vec<(f32, u32)> {
         let mut distribution = Vec::new();
-        let min_temp = temperatures.iter().fold(f32::INFINITY, |a, &b| a.min(b));
-        let max_temp = temperatures
-            .iter()
-            .fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+        let finite = temperatures.iter().copied().filter(|temp| temp.is_finite());
+        let (min_temp, max_temp) = finite
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), temp| {
+                (min.min(temp), max.max(temp))
+            });
 
         let bin_size = (max_temp - min_temp) / 20.0; // 20 bins
 
@@ -480,6 +745,74 @@ impl ThermalAnalysisProcessor {
         Ok(hotspots)
     }
 
+    /// Counts animals as 8-connected blobs of pixels inside the inclusive
+    /// body-temperature `range`; blobs smaller than `min_blob_pixels` are
+    /// dropped as noise. There is no georeference here, so each detection's
+    /// `center_location` holds its sub-pixel centroid as (x, y) and
+    /// `pixel_location` the nearest pixel; detections are in the raster order
+    /// of their first pixel. Confidence grows with blob size and
+    /// saturates at twice the minimum.
+    pub fn count_livestock(
+        &self,
+        temperature_map: &[f32],
+        width: u32,
+        range: (f32, f32),
+        min_blob_pixels: u32,
+    ) -> Vec<ThermalHotspot> {
+        let width = width as usize;
+        if width == 0 {
+            return Vec::new();
+        }
+        let height = temperature_map.len() / width;
+        let (low, high) = range;
+        let in_range = |idx: usize| (low..=high).contains(&temperature_map[idx]);
+        let mut visited = vec![false; width * height];
+        let mut animals = Vec::new();
+
+        for start in 0..width * height {
+            if visited[start] || !in_range(start) {
+                continue;
+            }
+            visited[start] = true;
+            let mut stack = vec![start];
+            let (mut area, mut sum_x, mut sum_y) = (0u32, 0.0f64, 0.0f64);
+            let mut peak = f32::MIN;
+            while let Some(idx) = stack.pop() {
+                let (x, y) = (idx % width, idx / width);
+                area += 1;
+                sum_x += x as f64;
+                sum_y += y as f64;
+                peak = peak.max(temperature_map[idx]);
+                for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+                    for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+                        let neighbor = ny * width + nx;
+                        if !visited[neighbor] && in_range(neighbor) {
+                            visited[neighbor] = true;
+                            stack.push(neighbor);
+                        }
+                    }
+                }
+            }
+            if area < min_blob_pixels.max(1) {
+                continue;
+            }
+
+            let (centroid_x, centroid_y) = (sum_x / area as f64, sum_y / area as f64);
+            animals.push(ThermalHotspot {
+                id: Uuid::new_v4(),
+                center_location: (centroid_x, centroid_y),
+                pixel_location: (centroid_x.round() as u32, centroid_y.round() as u32),
+                peak_temperature: peak,
+                area_pixels: area,
+                intensity: peak - low,
+                confidence_level: (area as f32 / (2 * min_blob_pixels.max(1)) as f32).min(1.0),
+                hotspot_type: HotspotType::Animal,
+            });
+        }
+
+        animals
+    }
+
     fn is_local_maximum(
         &self,
         temp_map: &[f32],
@@ -619,7 +952,11 @@ impl ThermalAnalysisProcessor {
             let (xi, yi) = polygon[i];
             let (xj, yj) = polygon[j];
 
-            if ((yi > y) != (yj > y)) && (x < (xj - xi) * (y - yi) / (yj - yi) + xi) {
+            // Signed arithmetic: edges run in either direction.
+            let crossing_x = (f64::from(xj) - f64::from(xi)) * (f64::from(y) - f64::from(yi))
+                / (f64::from(yj) - f64::from(yi))
+                + f64::from(xi);
+            if ((yi > y) != (yj > y)) && f64::from(x) < crossing_x {
                 inside = !inside;
             }
             j = i;
@@ -709,63 +1046,90 @@ impl ThermalAnalysisProcessor {
         Ok(0.85)
     }
 
-    pub async fn get_cached_result(&self, request_id: Uuid) -> Option<&ThermalAnalysisResult> {
-        self.thermal_cache.get(&request_id)
+    /// Reading a result counts as a use for eviction.
+    pub async fn get_cached_result(&mut self, request_id: Uuid) -> Option<&ThermalAnalysisResult> {
+        self.thermal_cache.get(request_id)
+    }
+
+    pub fn cache_stats(&self) -> ThermalCacheStats {
+        let cache = &self.thermal_cache;
+        ThermalCacheStats {
+            entries: cache.results.len(),
+            capacity: cache.capacity,
+            estimated_bytes: cache.results.values().map(estimated_result_bytes).sum(),
+        }
     }
 
     // Compatibility method for the post processor service
     pub async fn analyze(
-        &mut self,
+        &self,
         input_files: &[std::path::PathBuf],
         _parameters: &super::ProcessingParameters,
-    ) -> anyhow::Result<super::AnalysisResult> {
+        mut artifacts: super::ArtifactWriter,
+        progress: &super::JobProgress,
+    ) -> anyhow::Result<(super::AnalysisResult, super::ArtifactManifest)> {
         use chrono::Utc;
-        use std::collections::HashMap;
         use uuid::Uuid;
 
         // Convert input files to strings
-        let _file_paths: Vec<String> = input_files
-            .iter()
-            .map(|p| p.to_string_lossy().to_string())
-            .collect();
+        let mut _file_paths = Vec::with_capacity(input_files.len());
+        for (index, path) in input_files.iter().enumerate() {
+            _file_paths.push(path.to_string_lossy().to_string());
+            progress.report_steps(index + 1, input_files.len(), 0.0..0.6);
+        }
 
-        // Create mock percentiles
-        let mut percentiles = HashMap::new();
-        percentiles.insert("25".to_string(), 22.5);
-        percentiles.insert("50".to_string(), 25.0);
-        percentiles.insert("75".to_string(), 27.5);
+        let values = vec![25.0; 10000]; // Mock temperature values
+        let statistics = super::AnalysisStatistics {
+            coverage_area_m2: 10000.0,
+            ..compute_statistics(&values, &DEFAULT_PERCENTILES)
+        };
 
         // Create a basic analysis result
-        Ok(super::AnalysisResult {
+        let result = super::AnalysisResult {
             id: Uuid::new_v4(),
             job_id: Uuid::new_v4(),
             result_type: super::ResultType::ThermalMap,
             data: super::ResultData::GridData {
                 width: 100,
                 height: 100,
-                values: vec![25.0; 10000], // Mock temperature values
+                values: values.into(),
                 bounds: (-74.0, 40.0, -73.9, 40.1),
                 units: "celsius".to_string(),
             },
-            statistics: super::AnalysisStatistics {
-                min_value: 20.0,
-                max_value: 30.0,
-                mean_value: 25.0,
-                std_deviation: 2.5,
-                percentiles,
-                coverage_area_m2: 10000.0,
-                valid_pixel_count: 10000,
-                total_pixel_count: 10000,
-            },
+            statistics,
             visualizations: vec![],
             recommendations: vec![],
             evidence_refs: vec![],
             uncertainty: None,
             created_at: Utc::now(),
-        })
+        };
+
+        artifacts.write(
+            super::ArtifactKind::TemperatureGrid,
+            &super::artifacts::grid_csv(&result.data),
+        )?;
+        progress.report(0.8);
+        artifacts.write(
+            super::ArtifactKind::Statistics,
+            &serde_json::to_vec_pretty(&result.statistics)?,
+        )?;
+        let manifest = artifacts.commit()?;
+        progress.finish();
+        Ok((result, manifest))
     }
 }
 
+fn estimated_result_bytes(result: &ThermalAnalysisResult) -> usize {
+    use std::mem::size_of;
+
+    size_of::<ThermalAnalysisResult>()
+        + result.temperature_map.len() * size_of::<f32>()
+        + result.thermal_statistics.temperature_distribution.len() * size_of::<(f32, u32)>()
+        + result.hotspot_detections.len() * size_of::<ThermalHotspot>()
+        + result.region_analyses.len() * size_of::<RegionAnalysis>()
+        + result.anomaly_detections.len() * size_of::<ThermalAnomaly>()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -780,6 +1144,8 @@ mod tests {
             enable_temperature_mapping: true,
             thermal_threshold_high: 50.0,
             thermal_threshold_low: 0.0,
+            region_emissivity: default_region_emissivity(),
+            max_cached_results: 8,
         };
 
         let mut processor = ThermalAnalysisProcessor::new(config);
@@ -796,6 +1162,7 @@ mod tests {
                 bottom_right_lat: 39.9,
                 bottom_right_lon: -73.9,
                 altitude: 100.0,
+                altitude_reference: AltitudeReference::RelativeToHome,
                 camera_angle: 0.0,
             },
             environmental_conditions: EnvironmentalConditions {
@@ -829,6 +1196,8 @@ mod tests {
             enable_temperature_mapping: true,
             thermal_threshold_high: 50.0,
             thermal_threshold_low: 0.0,
+            region_emissivity: default_region_emissivity(),
+            max_cached_results: 8,
         };
 
         let processor = ThermalAnalysisProcessor::new(config);
@@ -849,8 +1218,172 @@ mod tests {
             solar_irradiance: 1000.0,
         };
 
-        let temp = processor.raw_to_temperature(1000, &params, &env).unwrap();
+        let temp = processor
+            .raw_to_temperature(1000, params.emissivity, &params, &env)
+            .unwrap();
         assert!(temp > 0.0);
         assert!(temp < 100.0); // Reasonable temperature range
     }
+
+    fn small_request() -> ThermalAnalysisRequest {
+        ThermalAnalysisRequest {
+            id: Uuid::new_v4(),
+            thermal_image_data: vec![1000, 1500, 2000, 2500],
+            image_width: 2,
+            image_height: 2,
+            capture_time: Utc::now(),
+            georeference_info: GeoreferenceInfo {
+                top_left_lat: 40.0,
+                top_left_lon: -74.0,
+                bottom_right_lat: 39.9,
+                bottom_right_lon: -73.9,
+                altitude: 100.0,
+                altitude_reference: AltitudeReference::RelativeToHome,
+                camera_angle: 0.0,
+            },
+            environmental_conditions: EnvironmentalConditions {
+                ambient_temperature: 20.0,
+                humidity: 50.0,
+                wind_speed: 5.0,
+                atmospheric_pressure: 1013.25,
+                solar_irradiance: 1000.0,
+            },
+            analysis_parameters: ThermalAnalysisParameters {
+                emissivity: 0.95,
+                distance_to_target: 100.0,
+                atmospheric_temperature: 20.0,
+                relative_humidity: 50.0,
+                analysis_regions: vec![],
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn cache_evicts_the_least_recently_used_result_past_its_cap() {
+        let mut processor = ThermalAnalysisProcessor::new(ThermalAnalysisConfig {
+            max_cached_results: 2,
+            ..ThermalAnalysisConfig::default()
+        });
+        let (first, second, third) = (small_request(), small_request(), small_request());
+        let (first_id, second_id, third_id) = (first.id, second.id, third.id);
+
+        processor.process_thermal_request(first).await.unwrap();
+        processor.process_thermal_request(second).await.unwrap();
+        // Reading the older result makes the second one least recently used.
+        assert!(processor.get_cached_result(first_id).await.is_some());
+        processor.process_thermal_request(third).await.unwrap();
+
+        assert!(processor.get_cached_result(second_id).await.is_none());
+        assert!(processor.get_cached_result(first_id).await.is_some());
+        assert!(processor.get_cached_result(third_id).await.is_some());
+        let stats = processor.cache_stats();
+        assert_eq!((stats.entries, stats.capacity), (2, 2));
+        assert!(stats.estimated_bytes >= 2 * (size_of::<ThermalAnalysisResult>() + 4 * 4));
+    }
+
+    #[test]
+    fn livestock_counting_skips_blobs_below_the_minimum_size() {
+        let processor = ThermalAnalysisProcessor::new(ThermalAnalysisConfig::default());
+        let width = 10;
+        let mut temperatures = vec![15.0; 60];
+        let mut warm = |x: usize, y: usize, temperature: f32| {
+            temperatures[y * width + x] = temperature;
+        };
+        // A 2x2 cow, a diagonal 3-pixel calf and a single warm pixel.
+        for (x, y) in [(1, 1), (2, 1), (1, 2), (2, 2)] {
+            warm(x, y, 38.5);
+        }
+        warm(6, 0, 38.0);
+        warm(7, 1, 39.0);
+        warm(8, 2, 38.0);
+        warm(5, 4, 38.2);
+        // Too hot for an animal; breaks nothing up.
+        warm(0, 5, 70.0);
+
+        let animals = processor.count_livestock(&temperatures, width as u32, (36.0, 41.0), 3);
+
+        assert_eq!(animals.len(), 2);
+        assert!(animals
+            .iter()
+            .all(|animal| animal.hotspot_type == HotspotType::Animal));
+        // Blobs come out in the raster order of their first pixel.
+        assert_eq!(animals[0].center_location, (7.0, 1.0));
+        assert_eq!(animals[0].pixel_location, (7, 1));
+        assert_eq!(animals[0].peak_temperature, 39.0);
+        assert_eq!(animals[1].center_location, (1.5, 1.5));
+        assert_eq!(animals[1].area_pixels, 4);
+    }
+
+    #[test]
+    fn regions_calibrate_the_same_raw_value_with_their_own_emissivity() {
+        let processor = ThermalAnalysisProcessor::new(ThermalAnalysisConfig {
+            enable_noise_reduction: false,
+            ..ThermalAnalysisConfig::default()
+        });
+        let region = |name: &str, left: u32, region_type, emissivity| AnalysisRegion {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            polygon: vec![(left, 0), (left + 2, 0), (left + 2, 2), (left, 2)],
+            region_type,
+            expected_temperature_range: None,
+            emissivity,
+        };
+        let request = ThermalAnalysisRequest {
+            id: Uuid::new_v4(),
+            thermal_image_data: vec![3000; 12],
+            image_width: 6,
+            image_height: 2,
+            capture_time: Utc::now(),
+            georeference_info: GeoreferenceInfo {
+                top_left_lat: 40.0,
+                top_left_lon: -74.0,
+                bottom_right_lat: 39.9,
+                bottom_right_lon: -73.9,
+                altitude: 100.0,
+                altitude_reference: AltitudeReference::RelativeToHome,
+                camera_angle: 0.0,
+            },
+            environmental_conditions: EnvironmentalConditions {
+                ambient_temperature: 20.0,
+                humidity: 50.0,
+                wind_speed: 5.0,
+                atmospheric_pressure: 1013.25,
+                solar_irradiance: 1000.0,
+            },
+            analysis_parameters: ThermalAnalysisParameters {
+                emissivity: 0.95,
+                distance_to_target: 100.0,
+                atmospheric_temperature: 20.0,
+                relative_humidity: 50.0,
+                // Columns 0-1 take the table value for vegetation, columns 2-3
+                // their own bare-metal value, columns 4-5 the default.
+                analysis_regions: vec![
+                    region("canopy", 0, RegionType::Vegetation, None),
+                    region("trailer", 2, RegionType::Equipment, Some(0.3)),
+                ],
+            },
+        };
+        let expected = |emissivity| {
+            processor
+                .raw_to_temperature(
+                    3000,
+                    emissivity,
+                    &request.analysis_parameters,
+                    &request.environmental_conditions,
+                )
+                .unwrap()
+        };
+
+        let temperatures = processor.calibrate_thermal_data(&request).unwrap();
+
+        for row in [0, 6] {
+            assert_eq!(temperatures[row], expected(0.98));
+            assert_eq!(temperatures[row + 1], expected(0.98));
+            assert_eq!(temperatures[row + 2], expected(0.3));
+            assert_eq!(temperatures[row + 3], expected(0.3));
+            assert_eq!(temperatures[row + 4], expected(0.95));
+            assert_eq!(temperatures[row + 5], expected(0.95));
+        }
+        assert!(temperatures[2] > temperatures[0]);
+    }
 }
@@ -0,0 +1,253 @@
+use crate::{AnalysisResult, ResultData, ResultType};
+use image::{imageops, DynamicImage, ImageOutputFormat};
+use sensor_overlay_engine::utils::{create_heatmap_image_rgba, HeatmapOptions};
+use std::collections::HashMap;
+use std::io::Cursor;
+use uuid::Uuid;
+
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 128;
+/// Longer-side pixel sizes a thumbnail can be rendered at, so a result has
+/// a handful of cache entries at most.
+pub const THUMBNAIL_SIZES: [u32; 4] = [64, 128, 256, 512];
+/// Default cap on the PNG bytes a [`ThumbnailCache`] keeps.
+pub const DEFAULT_THUMBNAIL_CACHE_BYTES: usize = 32 * 1024 * 1024;
+/// Units of the NDVI change grids written by change detection.
+pub const NDVI_DELTA_UNITS: &str = "ndvi_delta";
+/// Fixed scale for NDVI change maps so separately rendered ones compare
+/// directly; larger changes clamp to the ends.
+pub const NDVI_DELTA_RANGE: (f32, f32) = (-0.5, 0.5);
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ThumbnailError {
+    #[error("result {result_id} holds {kind} data, only grid results have thumbnails")]
+    NotGrid { result_id: Uuid, kind: &'static str },
+    #[error("thumbnail size must be one of {THUMBNAIL_SIZES:?}, got {size}")]
+    InvalidSize { size: u32 },
+    #[error("result {result_id} grid is empty or does not match its dimensions")]
+    InvalidGrid { result_id: Uuid },
+    #[error("failed to render thumbnail for result {result_id}: {reason}")]
+    Render { result_id: Uuid, reason: String },
+}
+
+/// Colormap used for a result's grid; thermal grids get the heat ramp, every
+/// other index the viridis ramp.
+pub fn thumbnail_colormap(result_type: &ResultType) -> &'static str {
+    match result_type {
+        ResultType::ThermalMap => "hot",
+        _ => "viridis",
+    }
+}
+
+/// Whether `result` is an NDVI change grid rather than an index map.
+pub fn is_ndvi_delta(result: &AnalysisResult) -> bool {
+    matches!(&result.data, ResultData::GridData { units, .. } if units == NDVI_DELTA_UNITS)
+}
+
+/// Colormap and scale for rendering a result's grid. NDVI change grids use
+/// the diverging ramp over [`NDVI_DELTA_RANGE`], white at no change, with
+/// cells lacking a comparison left transparent; every other grid uses
+/// [`thumbnail_colormap`] over its own min/max.
+pub fn result_heatmap_style(result: &AnalysisResult) -> (&'static str, HeatmapOptions) {
+    if is_ndvi_delta(result) {
+        let options = HeatmapOptions {
+            value_range: Some(NDVI_DELTA_RANGE),
+            center: Some(0.0),
+            transparent_nodata: true,
+            ..HeatmapOptions::default()
+        };
+        return ("rdbu", options);
+    }
+    (
+        thumbnail_colormap(&result.result_type),
+        HeatmapOptions::default(),
+    )
+}
+
+/// Renders a grid result as a PNG whose longer side is `size` pixels. Cells
+/// are scaled with nearest-neighbour sampling so class edges stay sharp.
+pub fn render_result_thumbnail(
+    result: &AnalysisResult,
+    size: u32,
+) -> Result<Vec<u8>, ThumbnailError> {
+    if !THUMBNAIL_SIZES.contains(&size) {
+        return Err(ThumbnailError::InvalidSize { size });
+    }
+    let (width, height, values) = match &result.data {
+        ResultData::GridData {
+            width,
+            height,
+            values,
+            ..
+        } => (*width, *height, values),
+        other => {
+            return Err(ThumbnailError::NotGrid {
+                result_id: result.id,
+                kind: result_data_kind(other),
+            })
+        }
+    };
+    if width == 0 || height == 0 || values.len() != (width as usize) * (height as usize) {
+        return Err(ThumbnailError::InvalidGrid {
+            result_id: result.id,
+        });
+    }
+
+    let (colormap, options) = result_heatmap_style(result);
+    let heatmap =
+        create_heatmap_image_rgba(values, width, height, colormap, &options).map_err(|error| {
+            ThumbnailError::Render {
+                result_id: result.id,
+                reason: error.to_string(),
+            }
+        })?;
+    let scale = size as f64 / width.max(height) as f64;
+    let thumbnail_width = ((width as f64 * scale).round() as u32).max(1);
+    let thumbnail_height = ((height as f64 * scale).round() as u32).max(1);
+    let thumbnail = imageops::resize(
+        &heatmap,
+        thumbnail_width,
+        thumbnail_height,
+        imageops::FilterType::Nearest,
+    );
+
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(thumbnail)
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .map_err(|error| ThumbnailError::Render {
+            result_id: result.id,
+            reason: error.to_string(),
+        })?;
+    Ok(png)
+}
+
+fn result_data_kind(data: &ResultData) -> &'static str {
+    match data {
+        ResultData::GridData { .. } => "grid",
+        ResultData::PointData { .. } => "point",
+        ResultData::ZonalData { .. } => "zonal",
+        ResultData::TimeSeriesData { .. } => "time series",
+    }
+}
+
+/// Rendered thumbnails keyed by result and size. Results are immutable once
+/// stored, so entries stay valid until [`ThumbnailCache::evict_result`];
+/// past `max_bytes` of PNG data the least recently used entries are dropped.
+#[derive(Debug)]
+pub struct ThumbnailCache {
+    entries: HashMap<(Uuid, u32), CachedThumbnail>,
+    max_bytes: usize,
+    bytes: usize,
+    /// Bumped on every hit or insert; orders entries by last use.
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct CachedThumbnail {
+    png: Vec<u8>,
+    used_at: u64,
+}
+
+impl Default for ThumbnailCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_THUMBNAIL_CACHE_BYTES)
+    }
+}
+
+impl ThumbnailCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_bytes,
+            bytes: 0,
+            clock: 0,
+        }
+    }
+
+    /// The cached PNG; a hit counts as a use for eviction.
+    pub fn get(&mut self, result_id: &Uuid, size: u32) -> Option<&Vec<u8>> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(&(*result_id, size))?;
+        entry.used_at = self.clock;
+        Some(&entry.png)
+    }
+
+    /// Stores `png`, then evicts least recently used entries until the cache
+    /// fits. A PNG larger than the whole cache is not kept.
+    pub fn insert(&mut self, result_id: Uuid, size: u32, png: Vec<u8>) {
+        if png.len() > self.max_bytes {
+            return;
+        }
+        self.clock += 1;
+        self.bytes += png.len();
+        let entry = CachedThumbnail {
+            png,
+            used_at: self.clock,
+        };
+        if let Some(replaced) = self.entries.insert((result_id, size), entry) {
+            self.bytes -= replaced.png.len();
+        }
+        while self.bytes > self.max_bytes {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.used_at)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.bytes -= evicted.png.len();
+            }
+        }
+    }
+
+    pub fn evict_result(&mut self, result_id: &Uuid) {
+        let mut freed = 0;
+        self.entries.retain(|(id, _), entry| {
+            let keep = id != result_id;
+            if !keep {
+                freed += entry.png.len();
+            }
+            keep
+        });
+        self.bytes -= freed;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// PNG bytes currently held.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_drops_least_recently_used_thumbnails_past_its_byte_cap() {
+        let mut cache = ThumbnailCache::new(10);
+        let (first, second, third) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        cache.insert(first, 64, vec![1; 4]);
+        cache.insert(second, 64, vec![2; 4]);
+        assert!(cache.get(&first, 64).is_some());
+        cache.insert(third, 64, vec![3; 4]);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.bytes(), 8);
+        assert!(cache.get(&second, 64).is_none());
+        assert!(cache.get(&first, 64).is_some());
+
+        cache.insert(first, 128, vec![4; 11]);
+        assert!(cache.get(&first, 128).is_none());
+        cache.evict_result(&first);
+        assert_eq!((cache.len(), cache.bytes()), (1, 4));
+    }
+}
@@ -0,0 +1,748 @@
+use crate::{
+    AnalysisResult, AnalysisZone, Priority, RecommendationCategory, ResultData, ResultType,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::geospatial::{GeoPoint, GeoPolygon};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+const WORK_ORDER_STORE_FILE: &str = "work_orders.json";
+/// Mean NDVI gain over a work order's zones that counts as the treatment
+/// having worked.
+pub const DEFAULT_VERIFY_MIN_NDVI_GAIN: f32 = 0.05;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WorkOrderError {
+    #[error("work order not found: {work_order_id}")]
+    NotFound { work_order_id: Uuid },
+    #[error("analysis result not found: {result_id}")]
+    ResultNotFound { result_id: Uuid },
+    #[error("analysis result {result_id} has no recommendation at index {index}")]
+    RecommendationNotFound { result_id: Uuid, index: usize },
+    #[error("recommendation {index} of analysis result {result_id} has no affected zones")]
+    NoZones { result_id: Uuid, index: usize },
+    #[error("no field is recorded for analysis result {result_id}")]
+    UnknownField { result_id: Uuid },
+    #[error("work order cannot move from {from} to {to}")]
+    IllegalTransition {
+        from: WorkOrderStatus,
+        to: WorkOrderStatus,
+    },
+    #[error("work order persistence failed: {reason}")]
+    Persistence { reason: String },
+}
+
+/// Lifecycle of a work order. `Done` means the crew finished the work;
+/// `Verified` means a later flight confirmed it helped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkOrderStatus {
+    Open,
+    InProgress,
+    Done,
+    Verified,
+    Dismissed,
+}
+
+impl WorkOrderStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::InProgress => "in_progress",
+            Self::Done => "done",
+            Self::Verified => "verified",
+            Self::Dismissed => "dismissed",
+        }
+    }
+
+    /// Legal moves: work starts and finishes, finished work is verified or
+    /// reopened, and anything not yet verified can be dismissed.
+    pub fn can_transition_to(self, to: Self) -> bool {
+        matches!(
+            (self, to),
+            (Self::Open, Self::InProgress)
+                | (Self::Open, Self::Dismissed)
+                | (Self::InProgress, Self::Done)
+                | (Self::InProgress, Self::Dismissed)
+                | (Self::Done, Self::Verified)
+                | (Self::Done, Self::Open)
+                | (Self::Done, Self::Dismissed)
+        )
+    }
+
+    /// Work still waiting on the field crew.
+    pub fn is_open(self) -> bool {
+        matches!(self, Self::Open | Self::InProgress)
+    }
+}
+
+impl fmt::Display for WorkOrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkOrderTransition {
+    pub from: WorkOrderStatus,
+    pub to: WorkOrderStatus,
+    pub at: DateTime<Utc>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Status a later analysis of the same zones suggests for a finished work
+/// order; applied only through an explicit transition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkOrderSuggestion {
+    pub work_order_id: Uuid,
+    pub result_id: Uuid,
+    pub suggested_status: WorkOrderStatus,
+    pub baseline_ndvi: f32,
+    pub follow_up_ndvi: f32,
+    pub suggested_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkOrder {
+    pub id: Uuid,
+    pub result_id: Uuid,
+    pub recommendation_index: usize,
+    pub field_id: String,
+    pub zones: Vec<AnalysisZone>,
+    pub category: RecommendationCategory,
+    pub priority: Priority,
+    pub title: String,
+    pub description: String,
+    pub action_items: Vec<String>,
+    pub due_date: Option<DateTime<Utc>>,
+    pub assignee: Option<String>,
+    pub status: WorkOrderStatus,
+    pub history: Vec<WorkOrderTransition>,
+    #[serde(default)]
+    pub suggestion: Option<WorkOrderSuggestion>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WorkOrder {
+    /// When the order last entered [`WorkOrderStatus::Done`]; only analyses
+    /// captured after this can judge the work.
+    fn done_at(&self) -> Option<DateTime<Utc>> {
+        self.history
+            .iter()
+            .rev()
+            .find(|transition| transition.to == WorkOrderStatus::Done)
+            .map(|transition| transition.at)
+    }
+}
+
+/// Creates a work order from one recommendation of an analysis result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkOrderRequest {
+    pub result_id: Uuid,
+    pub recommendation_index: usize,
+    /// Used when the result has no recorded job identity.
+    #[serde(default)]
+    pub field_id: Option<String>,
+    /// Replaces the recommendation's description when set.
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub due_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub assignee: Option<String>,
+}
+
+/// Editable fields of a work order; replaces all of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkOrderUpdate {
+    pub description: String,
+    pub priority: Priority,
+    #[serde(default)]
+    pub due_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub assignee: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkOrderTransitionRequest {
+    pub status: WorkOrderStatus,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkOrderQuery {
+    pub field_id: Option<String>,
+    pub status: Option<WorkOrderStatus>,
+}
+
+/// How a follow-up analysis is judged against a finished work order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorkOrderFollowUpPolicy {
+    pub verify_min_ndvi_gain: f32,
+}
+
+impl Default for WorkOrderFollowUpPolicy {
+    fn default() -> Self {
+        Self {
+            verify_min_ndvi_gain: DEFAULT_VERIFY_MIN_NDVI_GAIN,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WorkOrderStore {
+    work_orders: HashMap<Uuid, WorkOrder>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl WorkOrderStore {
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    pub fn load(directory: &Path) -> Result<Self, WorkOrderError> {
+        let path = directory.join(WORK_ORDER_STORE_FILE);
+        let mut store = if path.exists() {
+            let content = fs::read(&path).map_err(persistence_error)?;
+            serde_json::from_slice::<Self>(&content).map_err(persistence_error)?
+        } else {
+            Self::default()
+        };
+        store.path = Some(path);
+        Ok(store)
+    }
+
+    fn persist(&self) -> Result<(), WorkOrderError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(persistence_error)?;
+        }
+        let content = serde_json::to_vec_pretty(self).map_err(persistence_error)?;
+        fs::write(path, content).map_err(persistence_error)
+    }
+
+    /// Opens a work order for the zones of `result`'s recommendation.
+    /// `field_id` comes from the result's job identity and wins over the
+    /// request's.
+    pub fn create(
+        &mut self,
+        result: &AnalysisResult,
+        field_id: Option<String>,
+        request: WorkOrderRequest,
+        now: DateTime<Utc>,
+    ) -> Result<WorkOrder, WorkOrderError> {
+        let index = request.recommendation_index;
+        let recommendation =
+            result
+                .recommendations
+                .get(index)
+                .ok_or(WorkOrderError::RecommendationNotFound {
+                    result_id: result.id,
+                    index,
+                })?;
+        if recommendation.affected_areas.is_empty() {
+            return Err(WorkOrderError::NoZones {
+                result_id: result.id,
+                index,
+            });
+        }
+        let field_id = field_id
+            .or(request.field_id)
+            .ok_or(WorkOrderError::UnknownField {
+                result_id: result.id,
+            })?;
+
+        let work_order = WorkOrder {
+            id: Uuid::new_v4(),
+            result_id: result.id,
+            recommendation_index: index,
+            field_id,
+            zones: recommendation.affected_areas.clone(),
+            category: recommendation.category.clone(),
+            priority: recommendation.priority.clone(),
+            title: recommendation.title.clone(),
+            description: request
+                .description
+                .unwrap_or_else(|| recommendation.description.clone()),
+            action_items: recommendation.action_items.clone(),
+            due_date: request.due_date,
+            assignee: request.assignee,
+            status: WorkOrderStatus::Open,
+            history: Vec::new(),
+            suggestion: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.work_orders.insert(work_order.id, work_order.clone());
+        self.persist()?;
+        Ok(work_order)
+    }
+
+    pub fn work_order(&self, work_order_id: &Uuid) -> Option<&WorkOrder> {
+        self.work_orders.get(work_order_id)
+    }
+
+    pub fn work_orders(&self, query: &WorkOrderQuery) -> Vec<WorkOrder> {
+        let mut work_orders: Vec<WorkOrder> = self
+            .work_orders
+            .values()
+            .filter(|order| {
+                query
+                    .field_id
+                    .as_ref()
+                    .is_none_or(|field_id| order.field_id == *field_id)
+                    && query.status.is_none_or(|status| order.status == status)
+            })
+            .cloned()
+            .collect();
+        work_orders.sort_by(|left, right| {
+            left.created_at
+                .cmp(&right.created_at)
+                .then_with(|| left.id.cmp(&right.id))
+        });
+        work_orders
+    }
+
+    pub fn update(
+        &mut self,
+        work_order_id: Uuid,
+        update: WorkOrderUpdate,
+        now: DateTime<Utc>,
+    ) -> Result<WorkOrder, WorkOrderError> {
+        let work_order = self
+            .work_orders
+            .get_mut(&work_order_id)
+            .ok_or(WorkOrderError::NotFound { work_order_id })?;
+        work_order.description = update.description;
+        work_order.priority = update.priority;
+        work_order.due_date = update.due_date;
+        work_order.assignee = update.assignee;
+        work_order.updated_at = now;
+        let work_order = work_order.clone();
+        self.persist()?;
+        Ok(work_order)
+    }
+
+    pub fn delete(&mut self, work_order_id: Uuid) -> Result<(), WorkOrderError> {
+        self.work_orders
+            .remove(&work_order_id)
+            .ok_or(WorkOrderError::NotFound { work_order_id })?;
+        self.persist()
+    }
+
+    /// Moves a work order to `request.status` if the lifecycle allows it,
+    /// recording the move and dropping any pending suggestion.
+    pub fn transition(
+        &mut self,
+        work_order_id: Uuid,
+        request: WorkOrderTransitionRequest,
+        now: DateTime<Utc>,
+    ) -> Result<WorkOrder, WorkOrderError> {
+        let work_order = self
+            .work_orders
+            .get_mut(&work_order_id)
+            .ok_or(WorkOrderError::NotFound { work_order_id })?;
+        let from = work_order.status;
+        if !from.can_transition_to(request.status) {
+            return Err(WorkOrderError::IllegalTransition {
+                from,
+                to: request.status,
+            });
+        }
+        work_order.history.push(WorkOrderTransition {
+            from,
+            to: request.status,
+            at: now,
+            note: request.note,
+        });
+        work_order.status = request.status;
+        work_order.suggestion = None;
+        work_order.updated_at = now;
+        let work_order = work_order.clone();
+        self.persist()?;
+        Ok(work_order)
+    }
+
+    /// Compares a later NDVI analysis of `field_id` against each finished
+    /// work order's zones and suggests `Verified` when the mean NDVI rose by
+    /// at least the policy's gain, or reopening otherwise. Orders whose zones
+    /// the result does not cover are left alone.
+    pub fn record_follow_up(
+        &mut self,
+        field_id: &str,
+        result: &AnalysisResult,
+        policy: &WorkOrderFollowUpPolicy,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<WorkOrderSuggestion>, WorkOrderError> {
+        if result.result_type != ResultType::NdviMap {
+            return Ok(Vec::new());
+        }
+
+        let mut suggestions = Vec::new();
+        for work_order in self.work_orders.values_mut() {
+            if work_order.field_id != field_id
+                || work_order.status != WorkOrderStatus::Done
+                || work_order.result_id == result.id
+                || work_order
+                    .done_at()
+                    .is_some_and(|done_at| result.created_at <= done_at)
+            {
+                continue;
+            }
+            let Some((baseline_ndvi, follow_up_ndvi)) =
+                compare_zone_ndvi(&work_order.zones, result)
+            else {
+                continue;
+            };
+            let suggested_status = if follow_up_ndvi - baseline_ndvi >= policy.verify_min_ndvi_gain
+            {
+                WorkOrderStatus::Verified
+            } else {
+                WorkOrderStatus::Open
+            };
+            let suggestion = WorkOrderSuggestion {
+                work_order_id: work_order.id,
+                result_id: result.id,
+                suggested_status,
+                baseline_ndvi,
+                follow_up_ndvi,
+                suggested_at: now,
+            };
+            work_order.suggestion = Some(suggestion.clone());
+            suggestions.push(suggestion);
+        }
+
+        if !suggestions.is_empty() {
+            self.persist()?;
+        }
+        Ok(suggestions)
+    }
+}
+
+fn persistence_error(error: impl std::fmt::Display) -> WorkOrderError {
+    WorkOrderError::Persistence {
+        reason: error.to_string(),
+    }
+}
+
+/// Area-weighted mean NDVI of `zones` at creation and in `result`, over the
+/// zones measured by both.
+fn compare_zone_ndvi(zones: &[AnalysisZone], result: &AnalysisResult) -> Option<(f32, f32)> {
+    let (mut weight, mut baseline, mut follow_up) = (0.0_f64, 0.0_f64, 0.0_f64);
+    for zone in zones {
+        let (Some(before), Some(after)) = (zone_ndvi(zone), follow_up_zone_ndvi(zone, result))
+        else {
+            continue;
+        };
+        let zone_weight = f64::from(zone.area_m2.max(1.0));
+        weight += zone_weight;
+        baseline += zone_weight * f64::from(before);
+        follow_up += zone_weight * f64::from(after);
+    }
+    (weight > 0.0).then(|| ((baseline / weight) as f32, (follow_up / weight) as f32))
+}
+
+/// Zonal NDVI results store `ndvi_mean`; low-NDVI problem clusters store
+/// `mean_value`.
+fn zone_ndvi(zone: &AnalysisZone) -> Option<f32> {
+    zone.values
+        .get("ndvi_mean")
+        .or_else(|| zone.values.get("mean_value"))
+        .copied()
+        .filter(|value| value.is_finite())
+}
+
+fn follow_up_zone_ndvi(zone: &AnalysisZone, result: &AnalysisResult) -> Option<f32> {
+    match &result.data {
+        ResultData::ZonalData { zones, .. } => zones
+            .iter()
+            .find(|candidate| candidate.id == zone.id)
+            .and_then(zone_ndvi),
+        ResultData::GridData {
+            width,
+            height,
+            values,
+            bounds,
+            ..
+        } => grid_mean_inside(*width, *height, values, *bounds, &zone.boundary),
+        _ => None,
+    }
+}
+
+/// Mean of the finite grid cells whose centres fall inside `boundary`. Row 0
+/// is the northern edge of `bounds`.
+fn grid_mean_inside(
+    width: u32,
+    height: u32,
+    values: &[f32],
+    bounds: (f64, f64, f64, f64),
+    boundary: &[(f64, f64)],
+) -> Option<f32> {
+    let (width, height) = (width as usize, height as usize);
+    if width == 0 || height == 0 || values.len() < width * height {
+        return None;
+    }
+    let (min_lon, min_lat, max_lon, max_lat) = bounds;
+    let lon_step = (max_lon - min_lon) / width as f64;
+    let lat_step = (max_lat - min_lat) / height as f64;
+    let boundary = GeoPolygon::new(
+        boundary
+            .iter()
+            .copied()
+            .map(GeoPoint::from_lon_lat_tuple)
+            .collect(),
+    );
+
+    let (mut sum, mut count) = (0.0_f64, 0_usize);
+    for row in 0..height {
+        let lat = max_lat - (row as f64 + 0.5) * lat_step;
+        for col in 0..width {
+            let value = values[row * width + col];
+            let lon = min_lon + (col as f64 + 0.5) * lon_step;
+            if value.is_finite() && boundary.contains(&GeoPoint::new(lat, lon)) {
+                sum += f64::from(value);
+                count += 1;
+            }
+        }
+    }
+    (count > 0).then(|| (sum / count as f64) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AnalysisStatistics, Recommendation};
+    use chrono::Duration;
+
+    fn square_zone(id: &str, ndvi: f32) -> AnalysisZone {
+        AnalysisZone {
+            id: id.to_string(),
+            boundary: vec![
+                (0.0, 0.002),
+                (0.002, 0.002),
+                (0.002, 0.0),
+                (0.0, 0.0),
+                (0.0, 0.002),
+            ],
+            area_m2: 400.0,
+            values: HashMap::from([("mean_value".to_string(), ndvi)]),
+            classification: Some("low_ndvi".to_string()),
+        }
+    }
+
+    fn result_with(data: ResultData, recommendations: Vec<Recommendation>) -> AnalysisResult {
+        AnalysisResult {
+            id: Uuid::new_v4(),
+            job_id: Uuid::new_v4(),
+            result_type: ResultType::NdviMap,
+            data,
+            statistics: AnalysisStatistics::default(),
+            visualizations: vec![],
+            recommendations,
+            evidence_refs: vec![],
+            uncertainty: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// A 4x4 grid of 0.001° cells whose north-west quarter, the square
+    /// zone, holds `zone_value` and the rest 0.8.
+    fn grid(zone_value: f32) -> ResultData {
+        let mut values = vec![0.8; 16];
+        for index in [0, 1, 4, 5] {
+            values[index] = zone_value;
+        }
+        ResultData::GridData {
+            width: 4,
+            height: 4,
+            values: values.into(),
+            bounds: (0.0, -0.002, 0.004, 0.002),
+            units: "NDVI".to_string(),
+        }
+    }
+
+    fn low_ndvi_result(zone: AnalysisZone) -> AnalysisResult {
+        result_with(
+            grid(0.3),
+            vec![Recommendation {
+                category: RecommendationCategory::Fertilization,
+                priority: Priority::High,
+                title: "Low NDVI cluster".to_string(),
+                description: "Scout the cluster".to_string(),
+                action_items: vec!["Take soil samples".to_string()],
+                affected_areas: vec![zone],
+                confidence_score: 0.8,
+            }],
+        )
+    }
+
+    fn request(result: &AnalysisResult) -> WorkOrderRequest {
+        WorkOrderRequest {
+            result_id: result.id,
+            recommendation_index: 0,
+            field_id: None,
+            description: None,
+            due_date: None,
+            assignee: Some("crew-a".to_string()),
+        }
+    }
+
+    fn transition(status: WorkOrderStatus) -> WorkOrderTransitionRequest {
+        WorkOrderTransitionRequest { status, note: None }
+    }
+
+    #[test]
+    fn creating_a_work_order_preserves_the_zone_geometry() {
+        let zone = square_zone("cluster-1", 0.3);
+        let result = low_ndvi_result(zone.clone());
+        let mut store = WorkOrderStore::in_memory();
+
+        let work_order = store
+            .create(
+                &result,
+                Some("north-80".to_string()),
+                request(&result),
+                Utc::now(),
+            )
+            .unwrap();
+
+        assert_eq!(work_order.status, WorkOrderStatus::Open);
+        assert_eq!(work_order.field_id, "north-80");
+        assert_eq!(work_order.zones.len(), 1);
+        assert_eq!(work_order.zones[0].id, zone.id);
+        assert_eq!(work_order.zones[0].boundary, zone.boundary);
+        assert_eq!(work_order.zones[0].area_m2, zone.area_m2);
+        assert_eq!(work_order.description, "Scout the cluster");
+
+        let mut missing = request(&result);
+        missing.recommendation_index = 3;
+        assert_eq!(
+            store
+                .create(&result, None, missing, Utc::now())
+                .unwrap_err(),
+            WorkOrderError::RecommendationNotFound {
+                result_id: result.id,
+                index: 3
+            }
+        );
+        assert_eq!(
+            store
+                .create(&result, None, request(&result), Utc::now())
+                .unwrap_err(),
+            WorkOrderError::UnknownField {
+                result_id: result.id
+            }
+        );
+    }
+
+    #[test]
+    fn illegal_transitions_are_rejected_and_legal_ones_recorded() {
+        let result = low_ndvi_result(square_zone("cluster-1", 0.3));
+        let mut store = WorkOrderStore::in_memory();
+        let now = Utc::now();
+        let id = store
+            .create(&result, Some("north-80".to_string()), request(&result), now)
+            .unwrap()
+            .id;
+
+        for status in [WorkOrderStatus::Done, WorkOrderStatus::Verified] {
+            assert_eq!(
+                store.transition(id, transition(status), now).unwrap_err(),
+                WorkOrderError::IllegalTransition {
+                    from: WorkOrderStatus::Open,
+                    to: status
+                }
+            );
+        }
+        store
+            .transition(id, transition(WorkOrderStatus::InProgress), now)
+            .unwrap();
+        store
+            .transition(id, transition(WorkOrderStatus::Done), now)
+            .unwrap();
+        let verified = store
+            .transition(id, transition(WorkOrderStatus::Verified), now)
+            .unwrap();
+        assert_eq!(verified.history.len(), 3);
+        assert_eq!(
+            store
+                .transition(id, transition(WorkOrderStatus::Open), now)
+                .unwrap_err(),
+            WorkOrderError::IllegalTransition {
+                from: WorkOrderStatus::Verified,
+                to: WorkOrderStatus::Open
+            }
+        );
+        assert_eq!(
+            store
+                .transition(Uuid::nil(), transition(WorkOrderStatus::Done), now)
+                .unwrap_err(),
+            WorkOrderError::NotFound {
+                work_order_id: Uuid::nil()
+            }
+        );
+    }
+
+    #[test]
+    fn a_later_improved_ndvi_analysis_suggests_verification_and_a_flat_one_reopening() {
+        let tmp = tempfile::tempdir().unwrap();
+        let result = low_ndvi_result(square_zone("cluster-1", 0.3));
+        let mut store = WorkOrderStore::load(tmp.path()).unwrap();
+        let now = result.created_at;
+        let id = store
+            .create(&result, Some("north-80".to_string()), request(&result), now)
+            .unwrap()
+            .id;
+        store
+            .transition(id, transition(WorkOrderStatus::InProgress), now)
+            .unwrap();
+        store
+            .transition(id, transition(WorkOrderStatus::Done), now)
+            .unwrap();
+        let policy = WorkOrderFollowUpPolicy::default();
+
+        let mut improved = result_with(grid(0.55), vec![]);
+        improved.created_at = now + Duration::days(14);
+        let other_field = store
+            .record_follow_up("south-40", &improved, &policy, now)
+            .unwrap();
+        assert!(other_field.is_empty());
+        let suggestions = store
+            .record_follow_up("north-80", &improved, &policy, now)
+            .unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].suggested_status, WorkOrderStatus::Verified);
+        assert!((suggestions[0].baseline_ndvi - 0.3).abs() < 1e-6);
+        assert!((suggestions[0].follow_up_ndvi - 0.55).abs() < 1e-6);
+
+        let reloaded = WorkOrderStore::load(tmp.path()).unwrap();
+        assert_eq!(
+            reloaded.work_order(&id).unwrap().suggestion,
+            Some(suggestions[0].clone())
+        );
+
+        let mut flat = result_with(grid(0.31), vec![]);
+        flat.created_at = now + Duration::days(21);
+        let suggestions = store
+            .record_follow_up("north-80", &flat, &policy, now)
+            .unwrap();
+        assert_eq!(suggestions[0].suggested_status, WorkOrderStatus::Open);
+
+        let mut stale = result_with(grid(0.9), vec![]);
+        stale.created_at = now - Duration::days(1);
+        assert!(store
+            .record_follow_up("north-80", &stale, &policy, now)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -79,21 +79,9 @@ pub fn compute_zonal_statistics(
         });
     }
 
-    valid_values.sort_by(|left, right| left.total_cmp(right));
     let valid_pixel_count = valid_values.len() as u32;
     let total_pixel_count = expected as u32;
     let nodata_pixel_count = total_pixel_count - valid_pixel_count;
-    let min_value = valid_values[0];
-    let max_value = *valid_values.last().expect("valid values are non-empty");
-    let mean_value = valid_values.iter().sum::<f32>() / valid_values.len() as f32;
-    let variance = valid_values
-        .iter()
-        .map(|value| {
-            let delta = *value - mean_value;
-            delta * delta
-        })
-        .sum::<f32>()
-        / valid_values.len() as f32;
     let evidence = make_analysis_evidence(
         layer_ref,
         "zonal_statistics_v1",
@@ -130,14 +118,9 @@ pub fn compute_zonal_statistics(
 
     Ok(ProductGridStatistics {
         statistics: AnalysisStatistics {
-            min_value,
-            max_value,
-            mean_value,
-            std_deviation: variance.sqrt(),
-            percentiles: percentiles(&valid_values),
             coverage_area_m2,
-            valid_pixel_count,
             total_pixel_count,
+            ..compute_statistics(&valid_values, &DEFAULT_PERCENTILES)
         },
         crs: spatial_ref
             .crs
@@ -153,15 +136,70 @@ pub fn compute_zonal_statistics(
     })
 }
 
-fn percentiles(sorted_values: &[f32]) -> HashMap<String, f32> {
-    [0_u32, 25, 50, 75, 100]
-        .into_iter()
-        .map(|percentile| {
-            let rank = ((percentile as f32 / 100.0) * sorted_values.len() as f32).floor() as usize;
-            let index = rank.min(sorted_values.len() - 1);
-            (percentile.to_string(), sorted_values[index])
-        })
-        .collect()
+/// Percentiles [`compute_statistics`] reports unless asked for others.
+pub const DEFAULT_PERCENTILES: [u8; 5] = [5, 25, 50, 75, 95];
+
+/// Min, max, mean, population standard deviation and the requested
+/// percentiles of `values`, skipping NaN and infinite values. Percentiles are
+/// linearly interpolated between the closest ranks and keyed by their number,
+/// e.g. `"50"`. The valid pixel count is the number of values used and the
+/// total pixel count the length of `values`; coverage area is left at zero
+/// for the caller.
+pub fn compute_statistics(values: &[f32], percentiles: &[u8]) -> AnalysisStatistics {
+    let mut sorted = values
+        .iter()
+        .copied()
+        .filter(|value| value.is_finite())
+        .collect::<Vec<_>>();
+    let total_pixel_count = values.len() as u32;
+    if sorted.is_empty() {
+        return AnalysisStatistics {
+            min_value: 0.0,
+            max_value: 0.0,
+            mean_value: 0.0,
+            std_deviation: 0.0,
+            percentiles: HashMap::new(),
+            coverage_area_m2: 0.0,
+            valid_pixel_count: 0,
+            total_pixel_count,
+        };
+    }
+
+    sorted.sort_by(f32::total_cmp);
+    let count = sorted.len() as f64;
+    let mean = sorted.iter().map(|value| f64::from(*value)).sum::<f64>() / count;
+    let variance = sorted
+        .iter()
+        .map(|value| (f64::from(*value) - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    AnalysisStatistics {
+        min_value: sorted[0],
+        max_value: sorted[sorted.len() - 1],
+        mean_value: mean as f32,
+        std_deviation: variance.sqrt() as f32,
+        percentiles: percentiles
+            .iter()
+            .map(|percentile| {
+                (
+                    percentile.to_string(),
+                    interpolated_percentile(&sorted, *percentile),
+                )
+            })
+            .collect(),
+        coverage_area_m2: 0.0,
+        valid_pixel_count: sorted.len() as u32,
+        total_pixel_count,
+    }
+}
+
+fn interpolated_percentile(sorted: &[f32], percentile: u8) -> f32 {
+    let rank = f64::from(percentile.min(100)) / 100.0 * (sorted.len() - 1) as f64;
+    let below = rank.floor() as usize;
+    let above = rank.ceil() as usize;
+    let fraction = rank - below as f64;
+    (f64::from(sorted[below]) + (f64::from(sorted[above]) - f64::from(sorted[below])) * fraction)
+        as f32
 }
 
 #[cfg(test)]
@@ -186,7 +224,7 @@ mod tests {
         assert_eq!(result.statistics.max_value, 0.9);
         assert!((result.statistics.mean_value - 0.45).abs() < 1.0e-6);
         assert!((result.statistics.std_deviation - 0.29580396).abs() < 1.0e-6);
-        assert_eq!(result.statistics.percentiles["50"], 0.5);
+        assert!((result.statistics.percentiles["50"] - 0.4).abs() < 1.0e-6);
         assert_eq!(result.statistics.valid_pixel_count, 4);
         assert_eq!(result.statistics.total_pixel_count, 4);
         assert_eq!(result.statistics.coverage_area_m2, 400.0);
@@ -204,6 +242,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn statistics_interpolate_percentiles_and_skip_nan() {
+        let values = [7.0, 1.0, 3.0, f32::NAN, 5.0, 9.0, 2.0, 8.0];
+
+        let statistics = compute_statistics(&values, &DEFAULT_PERCENTILES);
+
+        assert_eq!(statistics.min_value, 1.0);
+        assert_eq!(statistics.max_value, 9.0);
+        assert!((statistics.mean_value - 5.0).abs() < 1.0e-6);
+        assert_eq!(statistics.valid_pixel_count, 7);
+        assert_eq!(statistics.total_pixel_count, 8);
+        for (percentile, expected) in [
+            ("5", 1.3),
+            ("25", 2.5),
+            ("50", 5.0),
+            ("75", 7.5),
+            ("95", 8.7),
+        ] {
+            assert!(
+                (statistics.percentiles[percentile] - expected).abs() < 1.0e-5,
+                "p{percentile} was {}",
+                statistics.percentiles[percentile]
+            );
+        }
+    }
+
     #[test]
     fn reproducible_statistics_emit_same_evidence_hash_for_same_inputs() {
         let grid = ProductGrid {
@@ -1,19 +1,49 @@
 use shared::{
+    artifact_schema::to_artifact_string,
     config::AgroConfig,
     schemas::{GpsCoords, ImageMetadata, MultispectralImage},
     AgroResult,
 };
 use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use tokio::sync::{mpsc, oneshot, Mutex};
 use tracing::{error, info};
 
+/// Asks a running camera reader to capture one frame now. The reply carries
+/// the path of the frame's metadata file, which lists the band images.
+#[derive(Debug)]
+pub struct CaptureRequest {
+    pub reply: oneshot::Sender<AgroResult<PathBuf>>,
+}
+
+/// Receiving end of the capture trigger channel. It is shared so a reader
+/// restarted by its supervisor keeps serving the same channel.
+pub type CaptureRequests = Arc<Mutex<mpsc::Receiver<CaptureRequest>>>;
+
+async fn next_capture_request(requests: &Option<CaptureRequests>) -> Option<CaptureRequest> {
+    match requests {
+        Some(requests) => requests.lock().await.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 pub struct CameraReader {
     config: Arc<AgroConfig>,
     data_dir: PathBuf,
+    capture_requests: Option<CaptureRequests>,
 }
 
 impl CameraReader {
     pub async fn new(config: Arc<AgroConfig>, data_dir: PathBuf) -> AgroResult<Self> {
-        Ok(Self { config, data_dir })
+        Ok(Self {
+            config,
+            data_dir,
+            capture_requests: None,
+        })
+    }
+
+    pub fn with_capture_requests(mut self, requests: CaptureRequests) -> Self {
+        self.capture_requests = Some(requests);
+        self
     }
 
     pub async fn run(&self) -> AgroResult<()> {
@@ -27,15 +57,27 @@ impl CameraReader {
         ));
 
         loop {
-            capture_interval.tick().await;
-
-            match self.capture_image().await {
-                Ok(image) => {
-                    self.save_image(&image).await?;
-                    info!("Captured multispectral image: {}", image.image_id);
+            tokio::select! {
+                _ = capture_interval.tick() => {
+                    match self.capture_image().await {
+                        Ok(image) => {
+                            self.save_image(&image).await?;
+                            info!("Captured multispectral image: {}", image.image_id);
+                        }
+                        Err(e) => {
+                            error!("Failed to capture image: {}", e);
+                        }
+                    }
                 }
-                Err(e) => {
-                    error!("Failed to capture image: {}", e);
+                Some(request) = next_capture_request(&self.capture_requests) => {
+                    let captured = match self.capture_image().await {
+                        Ok(image) => {
+                            info!("Captured triggered multispectral image: {}", image.image_id);
+                            self.save_image(&image).await
+                        }
+                        Err(e) => Err(e),
+                    };
+                    let _ = request.reply.send(captured);
                 }
             }
         }
@@ -118,7 +160,7 @@ impl CameraReader {
         Ok(())
     }
 
-    async fn save_image(&self, image: &MultispectralImage) -> AgroResult<()> {
+    async fn save_image(&self, image: &MultispectralImage) -> AgroResult<PathBuf> {
         let filename = format!(
             "metadata_{}_{}.json",
             image.metadata.timestamp.format("%Y%m%d_%H%M%S"),
@@ -126,21 +168,31 @@ impl CameraReader {
         );
         let filepath = self.data_dir.join(filename);
 
-        let json = serde_json::to_string_pretty(image)?;
-        tokio::fs::write(filepath, json).await?;
+        let json = to_artifact_string(image)?;
+        tokio::fs::write(&filepath, json).await?;
 
-        Ok(())
+        Ok(filepath)
     }
 }
 
 pub struct SimulatedCameraReader {
     config: Arc<AgroConfig>,
     data_dir: PathBuf,
+    capture_requests: Option<CaptureRequests>,
 }
 
 impl SimulatedCameraReader {
     pub fn new(config: Arc<AgroConfig>, data_dir: PathBuf) -> Self {
-        Self { config, data_dir }
+        Self {
+            config,
+            data_dir,
+            capture_requests: None,
+        }
+    }
+
+    pub fn with_capture_requests(mut self, requests: CaptureRequests) -> Self {
+        self.capture_requests = Some(requests);
+        self
     }
 
     pub async fn run(&self) -> AgroResult<()> {
@@ -151,17 +203,33 @@ impl SimulatedCameraReader {
         ));
 
         loop {
-            capture_interval.tick().await;
-
-            let image = self.generate_simulated_image().await?;
-            self.save_image(&image).await?;
-            info!(
-                "Generated simulated multispectral image: {}",
-                image.image_id
-            );
+            tokio::select! {
+                _ = capture_interval.tick() => {
+                    let image = self.generate_simulated_image().await?;
+                    self.save_image(&image).await?;
+                    info!(
+                        "Generated simulated multispectral image: {}",
+                        image.image_id
+                    );
+                }
+                Some(request) = next_capture_request(&self.capture_requests) => {
+                    let captured = self.capture_now().await;
+                    let _ = request.reply.send(captured);
+                }
+            }
         }
     }
 
+    /// Synthesizes and stores one frame immediately.
+    pub async fn capture_now(&self) -> AgroResult<PathBuf> {
+        let image = self.generate_simulated_image().await?;
+        info!(
+            "Generated triggered simulated multispectral image: {}",
+            image.image_id
+        );
+        self.save_image(&image).await
+    }
+
     async fn generate_simulated_image(&self) -> AgroResult<MultispectralImage> {
         let timestamp = chrono::Utc::now();
         let image_id = uuid::Uuid::new_v4();
@@ -264,7 +332,7 @@ impl SimulatedCameraReader {
         Ok(())
     }
 
-    async fn save_image(&self, image: &MultispectralImage) -> AgroResult<()> {
+    async fn save_image(&self, image: &MultispectralImage) -> AgroResult<PathBuf> {
         let filename = format!(
             "sim_metadata_{}_{}.json",
             image.metadata.timestamp.format("%Y%m%d_%H%M%S"),
@@ -272,9 +340,9 @@ impl SimulatedCameraReader {
         );
         let filepath = self.data_dir.join(filename);
 
-        let json = serde_json::to_string_pretty(image)?;
-        tokio::fs::write(filepath, json).await?;
+        let json = to_artifact_string(image)?;
+        tokio::fs::write(&filepath, json).await?;
 
-        Ok(())
+        Ok(filepath)
     }
 }
@@ -1,6 +1,12 @@
 use clap::Parser;
-use shared::{config::AgroConfig, AgroResult, RuntimeMode};
+use shared::{
+    config::AgroConfig, error::AgroError, AgroResult, RuntimeMode, SupervisedTaskState,
+    SupervisionPolicy, SupervisionReport, TaskSupervisor,
+};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Mutex};
 use tracing::{info, warn};
 
 pub mod camera_reader;
@@ -11,17 +17,57 @@ pub mod lidar_reader;
 #[command(about = "Sensor Collector Service for agrodrone")]
 pub struct Args {
     #[arg(long, help = "Configuration file path")]
-    pub config: Option<String>,
+    pub config: Option<PathBuf>,
 }
 
+/// How long `trigger_capture` waits for the camera reader to store a frame.
+const CAPTURE_TRIGGER_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct SensorCollectorService {
     config: Arc<AgroConfig>,
+    supervisor: TaskSupervisor,
+    capture_trigger: mpsc::Sender<camera_reader::CaptureRequest>,
+    capture_requests: camera_reader::CaptureRequests,
 }
 
 impl SensorCollectorService {
     pub async fn new() -> AgroResult<Self> {
-        let config = Arc::new(AgroConfig::load()?);
-        Ok(Self { config })
+        Ok(Self::with_config(AgroConfig::load()?))
+    }
+
+    pub fn with_config(config: AgroConfig) -> Self {
+        let (capture_trigger, capture_requests) = mpsc::channel(8);
+        Self {
+            config: Arc::new(config),
+            supervisor: TaskSupervisor::new(),
+            capture_trigger,
+            capture_requests: Arc::new(Mutex::new(capture_requests)),
+        }
+    }
+
+    /// Has the running camera reader capture one frame now and returns the
+    /// path of its metadata file. Requires `run` to be active.
+    pub async fn trigger_capture(&self) -> AgroResult<PathBuf> {
+        let (reply, captured) = oneshot::channel();
+        self.capture_trigger
+            .send(camera_reader::CaptureRequest { reply })
+            .await
+            .map_err(|_| AgroError::Sensor("camera reader is not accepting captures".into()))?;
+        match tokio::time::timeout(CAPTURE_TRIGGER_TIMEOUT, captured).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(AgroError::Sensor(
+                "camera reader stopped before capturing".into(),
+            )),
+            Err(_) => Err(AgroError::Sensor(format!(
+                "camera capture did not complete within {:?}",
+                CAPTURE_TRIGGER_TIMEOUT
+            ))),
+        }
+    }
+
+    /// Reader task states and restart counts.
+    pub fn supervision_report(&self) -> SupervisionReport {
+        self.supervisor.report()
     }
 
     pub async fn run(&self) -> AgroResult<()> {
@@ -43,22 +89,31 @@ impl SensorCollectorService {
         let lidar_handle = match self.config.runtime_mode {
             RuntimeMode::Flight => {
                 info!("Starting LiDAR reader for RPLIDAR A3");
-                let reader = lidar_reader::LidarReader::new(self.config.clone(), lidar_dir).await?;
-                Some(tokio::spawn(async move {
-                    if let Err(e) = reader.run().await {
-                        tracing::error!("LiDAR reader error: {}", e);
-                    }
-                }))
+                let reader =
+                    Arc::new(lidar_reader::LidarReader::new(self.config.clone(), lidar_dir).await?);
+                self.supervisor.spawn_supervised(
+                    "lidar_reader",
+                    move || {
+                        let reader = reader.clone();
+                        async move { reader.run().await }
+                    },
+                    SupervisionPolicy::default(),
+                )
             }
             RuntimeMode::Simulation => {
                 warn!("Running in simulation mode - starting simulated LiDAR");
-                let reader =
-                    lidar_reader::SimulatedLidarReader::new(self.config.clone(), lidar_dir);
-                Some(tokio::spawn(async move {
-                    if let Err(e) = reader.run().await {
-                        tracing::error!("Simulated LiDAR reader error: {}", e);
-                    }
-                }))
+                let reader = Arc::new(lidar_reader::SimulatedLidarReader::new(
+                    self.config.clone(),
+                    lidar_dir,
+                ));
+                self.supervisor.spawn_supervised(
+                    "simulated_lidar_reader",
+                    move || {
+                        let reader = reader.clone();
+                        async move { reader.run().await }
+                    },
+                    SupervisionPolicy::default(),
+                )
             }
         };
 
@@ -66,40 +121,87 @@ impl SensorCollectorService {
         let camera_handle = match self.config.runtime_mode {
             RuntimeMode::Flight => {
                 info!("Starting multispectral camera reader");
-                let reader =
-                    camera_reader::CameraReader::new(self.config.clone(), camera_dir).await?;
-                Some(tokio::spawn(async move {
-                    if let Err(e) = reader.run().await {
-                        tracing::error!("Camera reader error: {}", e);
-                    }
-                }))
+                let reader = Arc::new(
+                    camera_reader::CameraReader::new(self.config.clone(), camera_dir)
+                        .await?
+                        .with_capture_requests(self.capture_requests.clone()),
+                );
+                self.supervisor.spawn_supervised(
+                    "camera_reader",
+                    move || {
+                        let reader = reader.clone();
+                        async move { reader.run().await }
+                    },
+                    SupervisionPolicy::default(),
+                )
             }
             RuntimeMode::Simulation => {
                 warn!("Running in simulation mode - starting simulated camera");
-                let reader =
-                    camera_reader::SimulatedCameraReader::new(self.config.clone(), camera_dir);
-                Some(tokio::spawn(async move {
-                    if let Err(e) = reader.run().await {
-                        tracing::error!("Simulated camera reader error: {}", e);
-                    }
-                }))
+                let reader = Arc::new(
+                    camera_reader::SimulatedCameraReader::new(self.config.clone(), camera_dir)
+                        .with_capture_requests(self.capture_requests.clone()),
+                );
+                self.supervisor.spawn_supervised(
+                    "simulated_camera_reader",
+                    move || {
+                        let reader = reader.clone();
+                        async move { reader.run().await }
+                    },
+                    SupervisionPolicy::default(),
+                )
             }
         };
 
-        // Wait for all readers
-        tokio::select! {
-            _ = async {
-                if let Some(handle) = lidar_handle {
-                    handle.await.ok();
-                }
-            } => info!("LiDAR reader finished"),
-            _ = async {
-                if let Some(handle) = camera_handle {
-                    handle.await.ok();
-                }
-            } => info!("Camera reader finished"),
+        // A reader only finishes here once it completed or ran out of restarts.
+        let finished = tokio::select! {
+            status = lidar_handle => status,
+            status = camera_handle => status,
         }
+        .map_err(|error| anyhow::anyhow!("supervisor task aborted: {error}"))?;
+
+        if finished.state == SupervisedTaskState::Failed {
+            return Err(AgroError::Other(anyhow::anyhow!(
+                "{} failed after {} restarts: {}",
+                finished.name,
+                finished.restart_count,
+                finished.last_error.unwrap_or_default()
+            )));
+        }
+        info!("{} finished", finished.name);
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn triggered_capture_in_simulation_writes_a_frame() {
+        let data_root =
+            std::env::temp_dir().join(format!("sensor_collector_{}", uuid::Uuid::new_v4()));
+        let mut config = AgroConfig::load().unwrap();
+        config.runtime_mode = RuntimeMode::Simulation;
+        config.storage.data_root_path = data_root.clone();
+        config.camera.capture_interval_ms = 3_600_000;
+        let service = Arc::new(SensorCollectorService::with_config(config));
+
+        let running = tokio::spawn({
+            let service = service.clone();
+            async move { service.run().await }
+        });
+        let metadata_path = service.trigger_capture().await.unwrap();
+        running.abort();
+
+        assert!(metadata_path.starts_with(data_root.join("camera")));
+        let metadata: shared::schemas::MultispectralImage =
+            shared::from_artifact_str(&std::fs::read_to_string(&metadata_path).unwrap()).unwrap();
+        assert!(!metadata.file_paths.is_empty());
+        assert!(metadata
+            .file_paths
+            .values()
+            .all(|band_path| std::path::Path::new(band_path).exists()));
+        let _ = std::fs::remove_dir_all(data_root);
+    }
+}
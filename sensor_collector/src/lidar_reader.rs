@@ -1,5 +1,6 @@
 use shared::{
-    config::AgroConfig,
+    artifact_schema::to_artifact_string,
+    config::{AgroConfig, SimulatedObstacle},
     schemas::{LidarPoint, LidarScan},
     AgroResult,
 };
@@ -93,7 +94,7 @@ impl LidarReader {
         );
         let filepath = self.data_dir.join(filename);
 
-        let json = serde_json::to_string_pretty(scan)?;
+        let json = to_artifact_string(scan)?;
         tokio::fs::write(filepath, json).await?;
 
         Ok(())
@@ -103,56 +104,87 @@ impl LidarReader {
 pub struct SimulatedLidarReader {
     config: Arc<AgroConfig>,
     data_dir: PathBuf,
+    scan_limit: Option<u64>,
 }
 
+/// Quality the simulated reader reports for a return; rays that hit no
+/// configured obstacle are reported with quality 0 and distance 0.
+const SIMULATED_RETURN_QUALITY: u8 = 47;
+
 impl SimulatedLidarReader {
     pub fn new(config: Arc<AgroConfig>, data_dir: PathBuf) -> Self {
-        Self { config, data_dir }
+        Self {
+            config,
+            data_dir,
+            scan_limit: None,
+        }
+    }
+
+    /// Stops `run` after this many scans instead of scanning forever.
+    pub fn with_scan_limit(mut self, scans: u64) -> Self {
+        self.scan_limit = Some(scans);
+        self
     }
 
     pub async fn run(&self) -> AgroResult<()> {
-        info!("Starting simulated LiDAR reader");
+        info!(
+            "Starting simulated LiDAR reader at {} scans/s with {} points per scan",
+            self.config.lidar.scan_frequency, self.config.lidar.points_per_scan
+        );
 
         let mut scan_interval = tokio::time::interval(std::time::Duration::from_secs_f32(
             1.0 / self.config.lidar.scan_frequency,
         ));
 
-        loop {
+        let mut scans = 0u64;
+        while self.scan_limit.is_none_or(|limit| scans < limit) {
             scan_interval.tick().await;
 
             let scan = self.generate_simulated_scan();
             self.save_scan(&scan).await?;
+            scans += 1;
             info!(
                 "Generated simulated LiDAR scan with {} points",
                 scan.points.len()
             );
         }
+
+        Ok(())
     }
 
     fn generate_simulated_scan(&self) -> LidarScan {
         let timestamp = chrono::Utc::now();
-        let mut points = Vec::new();
-
-        // Generate simulated scan points with some obstacles
-        for i in 0..360 {
-            let angle = i as f32;
-            let mut distance = 2000.0; // Base distance
-
-            // Add some simulated obstacles
-            if (angle >= 45.0 && angle <= 75.0) || (angle >= 285.0 && angle <= 315.0) {
-                distance = 800.0 + (angle * 0.01745).sin() * 200.0;
-            }
-
-            // Add noise
-            distance += (rand::random::<f32>() - 0.5) * 100.0;
-
-            points.push(LidarPoint {
-                timestamp,
-                angle,
-                distance,
-                quality: 47,
-            });
-        }
+        let lidar = &self.config.lidar;
+        let point_count = lidar.points_per_scan.max(1);
+        let step = 360.0 / point_count as f32;
+
+        let points = (0..point_count)
+            .map(|i| {
+                let angle = i as f32 * step;
+                let distance = if lidar.simulated_obstacles.is_empty() {
+                    Some(builtin_obstacle_distance(angle))
+                } else {
+                    nearest_obstacle_distance(&lidar.simulated_obstacles, angle)
+                        .map(|metres| metres * 1000.0)
+                };
+
+                match distance {
+                    // Add noise
+                    Some(distance) => LidarPoint {
+                        timestamp,
+                        angle,
+                        distance: distance + (rand::random::<f32>() - 0.5) * 100.0,
+                        quality: SIMULATED_RETURN_QUALITY,
+                    },
+                    None => LidarPoint {
+                        timestamp,
+                        angle,
+                        distance: 0.0,
+                        quality: 0,
+                    },
+                }
+            })
+            .collect();
 
         LidarScan {
             timestamp,
@@ -169,9 +201,81 @@ impl SimulatedLidarReader {
         );
         let filepath = self.data_dir.join(filename);
 
-        let json = serde_json::to_string_pretty(scan)?;
+        let json = to_artifact_string(scan)?;
         tokio::fs::write(filepath, json).await?;
 
         Ok(())
     }
 }
+
+/// Distance in millimetres for the built-in scene: open space at 2 m with
+/// obstacles in two sectors.
+fn builtin_obstacle_distance(angle: f32) -> f32 {
+    if (45.0..=75.0).contains(&angle) || (285.0..=315.0).contains(&angle) {
+        800.0 + (angle * 0.01745).sin() * 200.0
+    } else {
+        2000.0
+    }
+}
+
+/// Distance in metres to the closest obstacle hit by the ray at `angle`
+/// degrees.
+fn nearest_obstacle_distance(obstacles: &[SimulatedObstacle], angle: f32) -> Option<f32> {
+    let radians = angle.to_radians();
+    let direction = (radians.cos(), radians.sin());
+    obstacles
+        .iter()
+        .filter_map(|obstacle| obstacle.ray_distance(direction))
+        .min_by(f32::total_cmp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::artifact_schema::from_artifact_str;
+
+    #[tokio::test]
+    async fn simulated_reader_writes_one_file_per_scan_at_the_configured_rate() {
+        let data_dir = std::env::temp_dir().join(format!("lidar_sim_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let mut config = AgroConfig::load().unwrap();
+        config.lidar.scan_frequency = 50.0;
+        config.lidar.points_per_scan = 720;
+        // A square room 4 m across with a post 1 m ahead.
+        let corners = [(2.0, 2.0), (-2.0, 2.0), (-2.0, -2.0), (2.0, -2.0)];
+        config.lidar.simulated_obstacles = (0..4)
+            .map(|i| SimulatedObstacle::Wall {
+                start: corners[i],
+                end: corners[(i + 1) % 4],
+            })
+            .chain([SimulatedObstacle::Circle {
+                center: (1.0, 0.0),
+                radius: 0.25,
+            }])
+            .collect();
+        let reader =
+            SimulatedLidarReader::new(Arc::new(config), data_dir.clone()).with_scan_limit(8);
+
+        let started = std::time::Instant::now();
+        reader.run().await.unwrap();
+
+        // The first scan is immediate; the rest wait one 20 ms period each.
+        assert!(started.elapsed() >= std::time::Duration::from_millis(140));
+        let scans = std::fs::read_dir(&data_dir)
+            .unwrap()
+            .map(|entry| {
+                let content = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+                from_artifact_str::<LidarScan>(&content).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(scans.len(), 8);
+        for scan in &scans {
+            assert_eq!(scan.points.len(), 720);
+            // Straight ahead hits the post, sideways hits the wall at 2 m.
+            assert!((scan.points[0].distance - 750.0).abs() <= 50.0);
+            assert!((scan.points[180].distance - 2000.0).abs() <= 50.0);
+            assert!(scan.points.iter().all(|point| point.quality > 0));
+        }
+        let _ = std::fs::remove_dir_all(data_dir);
+    }
+}
@@ -1,17 +1,18 @@
 use anyhow::Result;
 use clap::Parser;
 use sensor_collector::{Args, SensorCollectorService};
-use shared::init_logging;
+use shared::{config::AgroConfig, init_logging};
 use tracing::info;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     init_logging()?;
 
-    let _args = Args::parse();
+    let args = Args::parse();
     info!("Starting Sensor Collector Service");
 
-    let service = SensorCollectorService::new().await?;
+    let config = AgroConfig::load_with_override(args.config.as_deref())?;
+    let service = SensorCollectorService::with_config(config);
     service.run().await?;
 
     Ok(())
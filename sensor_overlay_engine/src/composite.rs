@@ -3,8 +3,14 @@ use image::{ImageBuffer, Rgba, RgbaImage};
 use nalgebra::Point3;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
-
-use crate::lidar_overlay::{LidarOverlayProcessor, LidarOverlayResult};
+use tokio_util::sync::CancellationToken;
+
+use crate::coregistration::{
+    align_thermal, CoregistrationConfig, IntensityFrame, ThermalAlignment,
+};
+use crate::geotiff::{write_geotiff, GeoTiffBand, GeoTiffOptions, GEOTIFF_NODATA};
+use crate::jobs::ensure_not_cancelled;
+use crate::lidar_overlay::{HeightMap, LidarOverlayProcessor, LidarOverlayResult};
 use crate::ndvi::{NdviOverlayResult, NdviProcessor};
 use crate::thermal::{ThermalOverlayResult, ThermalProcessor};
 
@@ -18,11 +24,23 @@ pub struct CompositeOverlayEngine {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CompositeConfig {
     pub overlay_types: Vec<OverlayType>,
     pub blending_mode: BlendingMode,
     pub opacity_settings: OpacitySettings,
     pub output_format: String,
+    /// Affine map `[a, b, c, d, e, f]` from thermal to RGB pixel
+    /// coordinates, `x' = a*x + b*y + c` and `y' = d*x + e*y + f`, measured
+    /// from the top-left image corner. When set, the thermal layer is warped
+    /// into the RGB frame before compositing; see
+    /// [`affine_from_control_points`] to derive one.
+    #[serde(default)]
+    pub thermal_to_rgb_transform: Option<[f32; 6]>,
+    /// How the thermal layer is aligned to the RGB or NIR frame; overrides
+    /// `thermal_to_rgb_transform` unless its mode is `Off`.
+    #[serde(default)]
+    pub coregistration: CoregistrationConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -43,6 +61,7 @@ pub enum BlendingMode {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct OpacitySettings {
     pub ndvi_opacity: f32,
     pub thermal_opacity: f32,
@@ -80,6 +99,108 @@ pub struct CompositeProductOverlay {
     pub metadata: CompositeBlendMetadata,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MultibandGeoTiffBand {
+    pub description: String,
+    pub nodata: f32,
+    /// Resolution of the overlay before it was resampled onto the shared grid.
+    pub source_resolution: (u32, u32),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MultibandGeoTiff {
+    pub path: std::path::PathBuf,
+    pub epsg: u16,
+    pub resolution: (u32, u32),
+    pub geo_transform: [f64; 6],
+    pub bands: Vec<MultibandGeoTiffBand>,
+}
+
+/// Settings for the TVDI soil-moisture proxy.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SoilMoistureConfig {
+    /// Equal-width NDVI bins the dry and wet edges are fitted over.
+    pub ndvi_bin_count: usize,
+    /// Pixels a bin needs before its extremes count towards an edge.
+    pub min_pixels_per_bin: usize,
+    /// Populated bins needed to fit an edge.
+    pub min_edge_bins: usize,
+    /// Narrowest NDVI spread across the scene that edges are fitted over.
+    pub min_ndvi_range: f32,
+}
+
+impl Default for SoilMoistureConfig {
+    fn default() -> Self {
+        Self {
+            ndvi_bin_count: 10,
+            min_pixels_per_bin: 3,
+            min_edge_bins: 4,
+            min_ndvi_range: 0.2,
+        }
+    }
+}
+
+/// `temperature = intercept + slope * ndvi`, with the fit's coefficient of
+/// determination over the bin extremes it was fitted to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct TemperatureEdge {
+    pub intercept: f64,
+    pub slope: f64,
+    pub r_squared: f64,
+    pub bin_count: usize,
+}
+
+impl TemperatureEdge {
+    pub fn temperature_at(&self, ndvi: f64) -> f64 {
+        self.intercept + self.slope * ndvi
+    }
+}
+
+/// A ground measurement of volumetric soil moisture (m3/m3).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct MoistureSample {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub volumetric_moisture: f64,
+}
+
+/// Linear correction `moisture = intercept + slope * tvdi` fitted to ground
+/// samples, and how well it reproduces them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MoistureCalibration {
+    pub intercept: f64,
+    pub slope: f64,
+    pub rmse: f64,
+    pub samples_used: usize,
+    /// Samples outside the scene or on pixels without an index value.
+    pub samples_skipped: usize,
+}
+
+/// NDVI and surface temperature on one north-up grid covering `bounds`.
+#[derive(Debug, Clone)]
+pub struct NdviTemperatureGrid {
+    pub width: u32,
+    pub height: u32,
+    pub ndvi: Vec<f32>,
+    pub temperature: Vec<f32>,
+    pub bounds: crate::SpatialBounds,
+}
+
+/// TVDI layer, or calibrated volumetric moisture when ground samples were
+/// supplied, with the edges and calibration it was derived from.
+#[derive(Debug, Clone)]
+pub struct SoilMoistureProduct {
+    pub overlay: crate::SensorOverlay,
+    /// Uncalibrated index per pixel: 0 on the wet edge, 1 on the dry edge,
+    /// NaN where NDVI or temperature is missing.
+    pub tvdi: Vec<f32>,
+    pub dry_edge: TemperatureEdge,
+    pub wet_edge: TemperatureEdge,
+    pub ndvi_range: (f32, f32),
+    pub calibration: Option<MoistureCalibration>,
+}
+
 impl Default for CompositeConfig {
     fn default() -> Self {
         Self {
@@ -92,6 +213,8 @@ impl Default for CompositeConfig {
                 rgb_opacity: 1.0,
             },
             output_format: "PNG".to_string(),
+            thermal_to_rgb_transform: None,
+            coregistration: CoregistrationConfig::default(),
         }
     }
 }
@@ -155,10 +278,23 @@ impl CompositeOverlayEngine {
         &self,
         scan_data: &CompositeScanData,
         output_dir: &Path,
+    ) -> Result<CompositeOverlayResult> {
+        self.process_field_scan_cancellable(scan_data, output_dir, &CancellationToken::new())
+            .await
+    }
+
+    /// [`process_field_scan`](Self::process_field_scan) that stops between
+    /// the NDVI, thermal, LiDAR and composite stages once `cancel` fires.
+    pub async fn process_field_scan_cancellable(
+        &self,
+        scan_data: &CompositeScanData,
+        output_dir: &Path,
+        cancel: &CancellationToken,
     ) -> Result<CompositeOverlayResult> {
         let mut overlay_results = Vec::new();
 
         // Process NDVI if available and requested
+        ensure_not_cancelled(cancel)?;
         if self.config.overlay_types.contains(&OverlayType::Ndvi) && scan_data.ndvi_data.is_some() {
             let ndvi_output = output_dir.join("ndvi_overlay.png");
             let ndvi_result = self
@@ -169,6 +305,7 @@ impl CompositeOverlayEngine {
         }
 
         // Process Thermal if available and requested
+        ensure_not_cancelled(cancel)?;
         if self.config.overlay_types.contains(&OverlayType::Thermal)
             && scan_data.thermal_data.is_some()
         {
@@ -181,6 +318,7 @@ impl CompositeOverlayEngine {
         }
 
         // Process LiDAR if available and requested
+        ensure_not_cancelled(cancel)?;
         if self.config.overlay_types.contains(&OverlayType::Lidar) && scan_data.lidar_data.is_some()
         {
             let lidar_output = output_dir.join("lidar_overlay.png");
@@ -191,8 +329,32 @@ impl CompositeOverlayEngine {
             overlay_results.push(IndividualOverlayResult::Lidar(lidar_result));
         }
 
+        let scan_bounds = crate::SpatialBounds::enclosing(
+            scan_data
+                .ndvi_data
+                .iter()
+                .flat_map(|ndvi| &ndvi.gps_coordinates)
+                .chain(
+                    scan_data
+                        .thermal_data
+                        .iter()
+                        .flat_map(|thermal| &thermal.gps_coordinates),
+                ),
+        );
+
+        // Align the thermal layer to the composite frame
+        let thermal_alignment = match overlay_results.iter().find_map(|result| match result {
+            IndividualOverlayResult::Thermal(thermal_result) => Some(thermal_result),
+            _ => None,
+        }) {
+            Some(thermal_result) => self.align_thermal_layer(thermal_result, scan_data)?,
+            None => None,
+        };
+
         // Create composite overlay
-        let composite_image = self.create_composite_overlay(&overlay_results, scan_data)?;
+        ensure_not_cancelled(cancel)?;
+        let composite_image =
+            self.create_composite_overlay(&overlay_results, scan_data, thermal_alignment.as_ref())?;
         let composite_output = output_dir.join("composite_overlay.png");
         composite_image.save(&composite_output)?;
 
@@ -202,16 +364,217 @@ impl CompositeOverlayEngine {
         Ok(CompositeOverlayResult {
             individual_overlays: overlay_results,
             composite_image_path: composite_output,
+            scan_bounds,
+            thermal_alignment,
             analysis,
             timestamp: chrono::Utc::now(),
         })
     }
 
+    /// Works out where `thermal_result` lands on the composite frame, using
+    /// the RGB base's luminance or else the NIR band as the reference.
+    fn align_thermal_layer(
+        &self,
+        thermal_result: &ThermalOverlayResult,
+        scan_data: &CompositeScanData,
+    ) -> Result<Option<ThermalAlignment>> {
+        let composite_size = self.determine_composite_dimensions(scan_data);
+        let reference_values = if let Some(rgb) = &scan_data.rgb_image {
+            Some(
+                rgb.data
+                    .iter()
+                    .map(|[r, g, b]| {
+                        0.299 * f32::from(*r) + 0.587 * f32::from(*g) + 0.114 * f32::from(*b)
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            scan_data
+                .ndvi_data
+                .as_ref()
+                .map(|ndvi| ndvi.nir_band.clone())
+        };
+        let reference = reference_values
+            .as_deref()
+            .filter(|values| values.len() == composite_size.0 as usize * composite_size.1 as usize)
+            .map(|values| IntensityFrame {
+                values,
+                width: composite_size.0,
+                height: composite_size.1,
+            });
+        let thermal = IntensityFrame {
+            values: &thermal_result.temperatures,
+            width: thermal_result.width,
+            height: thermal_result.height,
+        };
+        anyhow::ensure!(
+            thermal.values.len() == thermal.width as usize * thermal.height as usize,
+            "thermal overlay has {} temperatures for {}x{}",
+            thermal.values.len(),
+            thermal.width,
+            thermal.height
+        );
+        align_thermal(
+            &thermal,
+            reference.as_ref(),
+            composite_size,
+            &self.config.coregistration,
+            self.config.thermal_to_rgb_transform.as_ref(),
+        )
+    }
+
+    /// Stacks the NDVI, thermal and elevation overlays of `result` into one
+    /// float32 GeoTIFF in `epsg`, georeferenced to the scan bounds. Every
+    /// overlay is taken to cover the whole scan and is bilinearly resampled
+    /// onto the finest overlay's grid; cells without data are written as
+    /// [`GEOTIFF_NODATA`]. `options` selects a cloud-optimized layout.
+    pub fn export_multiband_geotiff(
+        &self,
+        result: &CompositeOverlayResult,
+        path: &Path,
+        epsg: u16,
+        options: &GeoTiffOptions,
+    ) -> Result<MultibandGeoTiff> {
+        let bounds = result.scan_bounds.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("composite result has no scan bounds to georeference")
+        })?;
+        let mut sources = Vec::new();
+        for overlay in &result.individual_overlays {
+            match overlay {
+                IndividualOverlayResult::Ndvi(ndvi) => sources.push(BandSource {
+                    description: "NDVI",
+                    width: ndvi.width,
+                    height: ndvi.height,
+                    values: ndvi.ndvi_values.clone(),
+                }),
+                IndividualOverlayResult::Thermal(thermal) => sources.push(BandSource {
+                    description: "Temperature above ambient (degC)",
+                    width: thermal.width,
+                    height: thermal.height,
+                    values: thermal.temperatures.clone(),
+                }),
+                IndividualOverlayResult::Lidar(lidar) if !lidar.height_map.data.is_empty() => {
+                    sources.push(elevation_band_source(&lidar.height_map));
+                }
+                IndividualOverlayResult::Lidar(_) => {}
+            }
+        }
+        anyhow::ensure!(
+            !sources.is_empty(),
+            "composite result has no NDVI, thermal or elevation overlay to export"
+        );
+        for source in &sources {
+            anyhow::ensure!(
+                source.values.len() == source.width as usize * source.height as usize,
+                "{} overlay has {} values for a {}x{} grid",
+                source.description,
+                source.values.len(),
+                source.width,
+                source.height
+            );
+        }
+
+        let width = sources.iter().map(|source| source.width).max().unwrap_or(0);
+        let height = sources
+            .iter()
+            .map(|source| source.height)
+            .max()
+            .unwrap_or(0);
+        let bands = sources
+            .iter()
+            .map(|source| GeoTiffBand {
+                description: source.description.to_string(),
+                nodata: GEOTIFF_NODATA,
+                values: resample_bilinear(source, width, height)
+                    .into_iter()
+                    .map(|value| {
+                        if value.is_finite() {
+                            value
+                        } else {
+                            GEOTIFF_NODATA
+                        }
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        let geo_transform = write_geotiff(path, width, height, &bands, bounds, epsg, options)?;
+
+        Ok(MultibandGeoTiff {
+            path: path.to_path_buf(),
+            epsg,
+            resolution: (width, height),
+            geo_transform,
+            bands: sources
+                .iter()
+                .map(|source| MultibandGeoTiffBand {
+                    description: source.description.to_string(),
+                    nodata: GEOTIFF_NODATA,
+                    source_resolution: (source.width, source.height),
+                })
+                .collect(),
+        })
+    }
+
+    /// Soil-moisture proxy from the NDVI and thermal overlays of `result`,
+    /// both resampled onto the finer of their grids over the scan bounds.
+    /// See [`compute_soil_moisture_index`].
+    pub fn soil_moisture_index(
+        &self,
+        result: &CompositeOverlayResult,
+        samples: &[MoistureSample],
+        config: &SoilMoistureConfig,
+    ) -> Result<SoilMoistureProduct> {
+        let bounds = result.scan_bounds.clone().ok_or_else(|| {
+            anyhow::anyhow!("composite result has no scan bounds to georeference")
+        })?;
+        let mut ndvi = None;
+        let mut temperature = None;
+        for overlay in &result.individual_overlays {
+            match overlay {
+                IndividualOverlayResult::Ndvi(overlay) => {
+                    ndvi = Some(BandSource {
+                        description: "NDVI",
+                        width: overlay.width,
+                        height: overlay.height,
+                        values: overlay.ndvi_values.clone(),
+                    })
+                }
+                IndividualOverlayResult::Thermal(overlay) => {
+                    temperature = Some(BandSource {
+                        description: "Temperature above ambient (degC)",
+                        width: overlay.width,
+                        height: overlay.height,
+                        values: overlay.temperatures.clone(),
+                    })
+                }
+                IndividualOverlayResult::Lidar(_) => {}
+            }
+        }
+        let (Some(ndvi), Some(temperature)) = (ndvi, temperature) else {
+            anyhow::bail!("soil moisture index needs both an NDVI and a thermal overlay");
+        };
+        let width = ndvi.width.max(temperature.width);
+        let height = ndvi.height.max(temperature.height);
+
+        compute_soil_moisture_index(
+            &NdviTemperatureGrid {
+                width,
+                height,
+                ndvi: resample_bilinear(&ndvi, width, height),
+                temperature: resample_bilinear(&temperature, width, height),
+                bounds,
+            },
+            samples,
+            config,
+        )
+    }
+
     /// Create a composite overlay by blending multiple sensor data types
     fn create_composite_overlay(
         &self,
         overlay_results: &[IndividualOverlayResult],
         scan_data: &CompositeScanData,
+        thermal_alignment: Option<&ThermalAlignment>,
     ) -> Result<RgbaImage> {
         // Determine output image dimensions
         let (width, height) = self.determine_composite_dimensions(scan_data);
@@ -234,7 +597,11 @@ impl CompositeOverlayEngine {
                     self.blend_ndvi_overlay(&mut composite, ndvi_result)?;
                 }
                 IndividualOverlayResult::Thermal(thermal_result) => {
-                    self.blend_thermal_overlay(&mut composite, thermal_result)?;
+                    self.blend_thermal_overlay(
+                        &mut composite,
+                        thermal_result,
+                        thermal_alignment.map(|alignment| &alignment.transform),
+                    )?;
                 }
                 IndividualOverlayResult::Lidar(lidar_result) => {
                     self.blend_lidar_overlay(&mut composite, lidar_result)?;
@@ -303,14 +670,29 @@ impl CompositeOverlayEngine {
         Ok(())
     }
 
-    /// Blend thermal overlay into composite
+    /// Blend thermal overlay into composite, warped through `transform` when
+    /// it has been aligned
     fn blend_thermal_overlay(
         &self,
         composite: &mut RgbaImage,
         thermal_result: &ThermalOverlayResult,
+        transform: Option<&[f32; 6]>,
     ) -> Result<()> {
         let overlay_image = image::open(&thermal_result.output_path)?;
-        let overlay_rgba = overlay_image.to_rgba8();
+        let mut overlay_rgba = overlay_image.to_rgba8();
+        if let Some(transform) = transform {
+            let warped = warp_affine(
+                overlay_rgba.as_raw().chunks_exact(4),
+                (overlay_rgba.width(), overlay_rgba.height()),
+                transform,
+                (composite.width(), composite.height()),
+            )?;
+            overlay_rgba = ImageBuffer::from_fn(composite.width(), composite.height(), |x, y| {
+                warped[(y * composite.width() + x) as usize].map_or(Rgba([0, 0, 0, 0]), |pixel| {
+                    Rgba([pixel[0], pixel[1], pixel[2], pixel[3]])
+                })
+            });
+        }
 
         for (x, y, overlay_pixel) in overlay_rgba.enumerate_pixels() {
             if x < composite.width() && y < composite.height() {
@@ -494,7 +876,8 @@ impl CompositeOverlayEngine {
     fn assess_thermal_stress(&self, thermal_stats: &crate::thermal::ThermalStatistics) -> f32 {
         // Higher standard deviation and more hot spots indicate more stress
         let temp_variation = thermal_stats.std_dev / 10.0; // Normalize by expected variation
-        let hot_spot_factor = thermal_stats.hot_spots as f32 / thermal_stats.total_pixels as f32;
+        let hot_spot_factor =
+            thermal_stats.hot_spots as f32 / thermal_stats.valid_pixel_count.max(1) as f32;
 
         (temp_variation + hot_spot_factor * 2.0).min(1.0) * 100.0
     }
@@ -578,6 +961,179 @@ fn assert_composite_layer_extent(
     Ok(())
 }
 
+/// A thermal pixel position and where the same ground feature appears in the
+/// RGB image, both in pixel coordinates from the top-left corner.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ControlPointPair {
+    pub thermal: (f32, f32),
+    pub rgb: (f32, f32),
+}
+
+/// Least-squares affine thermal-to-RGB transform through `pairs`, in the
+/// layout of [`CompositeConfig::thermal_to_rgb_transform`]. Needs at least
+/// three pairs that are not all on one line.
+pub fn affine_from_control_points(pairs: &[ControlPointPair]) -> Result<[f32; 6]> {
+    anyhow::ensure!(
+        pairs.len() >= 3,
+        "affine alignment needs at least 3 control points, got {}",
+        pairs.len()
+    );
+    // Normal equations shared by both output rows: (A^T A) p = A^T b with
+    // rows [x, y, 1] of A.
+    let mut normal = nalgebra::Matrix3::<f64>::zeros();
+    let mut rhs_x = nalgebra::Vector3::<f64>::zeros();
+    let mut rhs_y = nalgebra::Vector3::<f64>::zeros();
+    for pair in pairs {
+        let row = nalgebra::Vector3::new(f64::from(pair.thermal.0), f64::from(pair.thermal.1), 1.0);
+        normal += row * row.transpose();
+        rhs_x += row * f64::from(pair.rgb.0);
+        rhs_y += row * f64::from(pair.rgb.1);
+    }
+    let inverse = normal
+        .try_inverse()
+        .filter(|_| normal.determinant().abs() > 1e-9)
+        .ok_or_else(|| anyhow::anyhow!("control points are collinear"))?;
+    let (x, y) = (inverse * rhs_x, inverse * rhs_y);
+    Ok([x[0], x[1], x[2], y[0], y[1], y[2]].map(|value| value as f32))
+}
+
+/// Inverse of an affine transform in the
+/// [`CompositeConfig::thermal_to_rgb_transform`] layout, or `None` when it
+/// collapses the image onto a line.
+pub fn invert_affine(transform: &[f32; 6]) -> Option<[f64; 6]> {
+    let [a, b, c, d, e, f] = transform.map(f64::from);
+    let determinant = a * e - b * d;
+    if !determinant.is_normal() {
+        return None;
+    }
+    Some([
+        e / determinant,
+        -b / determinant,
+        (b * f - c * e) / determinant,
+        -d / determinant,
+        a / determinant,
+        (c * d - a * f) / determinant,
+    ])
+}
+
+/// Resamples a thermal grid into a `target` sized RGB frame through
+/// `transform`, nearest neighbour; cells that fall outside the thermal image
+/// are NaN.
+pub fn warp_thermal_grid(
+    values: &[f32],
+    size: (u32, u32),
+    transform: &[f32; 6],
+    target: (u32, u32),
+) -> Result<Vec<f32>> {
+    Ok(
+        warp_affine(values.iter().copied(), size, transform, target)?
+            .into_iter()
+            .map(|value| value.unwrap_or(f32::NAN))
+            .collect(),
+    )
+}
+
+/// Samples the row-major `source` grid at the inverse-mapped centre of each
+/// `target` pixel; `None` where that lands outside the source.
+fn warp_affine<T: Clone>(
+    source: impl IntoIterator<Item = T>,
+    size: (u32, u32),
+    transform: &[f32; 6],
+    target: (u32, u32),
+) -> Result<Vec<Option<T>>> {
+    let source = source.into_iter().collect::<Vec<_>>();
+    anyhow::ensure!(
+        source.len() == size.0 as usize * size.1 as usize,
+        "grid has {} cells for {}x{}",
+        source.len(),
+        size.0,
+        size.1
+    );
+    let [a, b, c, d, e, f] = invert_affine(transform)
+        .ok_or_else(|| anyhow::anyhow!("thermal-to-RGB transform is not invertible"))?;
+    let mut warped = Vec::with_capacity(target.0 as usize * target.1 as usize);
+    for row in 0..target.1 {
+        let v = f64::from(row) + 0.5;
+        for column in 0..target.0 {
+            let u = f64::from(column) + 0.5;
+            let (x, y) = ((a * u + b * v + c).floor(), (d * u + e * v + f).floor());
+            let inside = x >= 0.0 && y >= 0.0 && x < f64::from(size.0) && y < f64::from(size.1);
+            warped.push(inside.then(|| source[y as usize * size.0 as usize + x as usize].clone()));
+        }
+    }
+    Ok(warped)
+}
+
+/// A single-band overlay on its native grid; non-finite values are missing.
+struct BandSource {
+    description: &'static str,
+    width: u32,
+    height: u32,
+    values: Vec<f32>,
+}
+
+/// Lays the height map out north-up, with NaN for cells no point fell in.
+fn elevation_band_source(height_map: &HeightMap) -> BandSource {
+    let bounds = &height_map.bounds;
+    let width = (bounds.max_x - bounds.min_x + 1) as u32;
+    let height = (bounds.max_y - bounds.min_y + 1) as u32;
+    let mut values = vec![f32::NAN; width as usize * height as usize];
+    for (&(x, y), &elevation) in &height_map.data {
+        let column = (x - bounds.min_x) as usize;
+        let row = (bounds.max_y - y) as usize;
+        values[row * width as usize + column] = elevation;
+    }
+    BandSource {
+        description: "Elevation (m)",
+        width,
+        height,
+        values,
+    }
+}
+
+/// Samples `source` at the cell centres of a `width` x `height` grid over the
+/// same extent, weighting only the neighbours that hold data.
+fn resample_bilinear(source: &BandSource, width: u32, height: u32) -> Vec<f32> {
+    if (source.width, source.height) == (width, height) {
+        return source.values.clone();
+    }
+    let source_coordinate = |index: u32, target: u32, native: u32| {
+        ((f64::from(index) + 0.5) * f64::from(native) / f64::from(target) - 0.5)
+            .clamp(0.0, f64::from(native - 1))
+    };
+    let mut resampled = Vec::with_capacity(width as usize * height as usize);
+    for row in 0..height {
+        let y = source_coordinate(row, height, source.height);
+        let (top, fy) = (y.floor() as u32, y.fract());
+        let bottom = (top + 1).min(source.height - 1);
+        for column in 0..width {
+            let x = source_coordinate(column, width, source.width);
+            let (left, fx) = (x.floor() as u32, x.fract());
+            let right = (left + 1).min(source.width - 1);
+            let mut weighted = 0.0;
+            let mut total_weight = 0.0;
+            for (sx, sy, weight) in [
+                (left, top, (1.0 - fx) * (1.0 - fy)),
+                (right, top, fx * (1.0 - fy)),
+                (left, bottom, (1.0 - fx) * fy),
+                (right, bottom, fx * fy),
+            ] {
+                let value = source.values[(sy * source.width + sx) as usize];
+                if value.is_finite() && weight > 0.0 {
+                    weighted += f64::from(value) * weight;
+                    total_weight += weight;
+                }
+            }
+            resampled.push(if total_weight > 0.0 {
+                (weighted / total_weight) as f32
+            } else {
+                f32::NAN
+            });
+        }
+    }
+    resampled
+}
+
 fn spatial_bounds_match(left: &crate::SpatialBounds, right: &crate::SpatialBounds) -> bool {
     const GEO_TOLERANCE: f64 = 1e-9;
     (left.min_x - right.min_x).abs() <= GEO_TOLERANCE
@@ -586,6 +1142,309 @@ fn spatial_bounds_match(left: &crate::SpatialBounds, right: &crate::SpatialBound
         && (left.max_y - right.max_y).abs() <= GEO_TOLERANCE
 }
 
+/// Temperature-Vegetation Dryness Index over `grid`.
+///
+/// NDVI is split into equal-width bins; the hottest and coolest pixel of each
+/// populated bin are regressed against their NDVI to give the dry and wet
+/// edges. Each pixel's temperature is then placed between the two edges at
+/// its NDVI, clamped to [0, 1]. With `samples`, a linear correction from TVDI
+/// to volumetric moisture is fitted at the samples' pixels and applied to the
+/// output grid; otherwise the grid holds TVDI itself. Scenes whose NDVI spread
+/// or populated bins fall short of `config` are rejected rather than fitted.
+pub fn compute_soil_moisture_index(
+    grid: &NdviTemperatureGrid,
+    samples: &[MoistureSample],
+    config: &SoilMoistureConfig,
+) -> Result<SoilMoistureProduct> {
+    let cell_count = grid.width as usize * grid.height as usize;
+    anyhow::ensure!(
+        grid.ndvi.len() == cell_count && grid.temperature.len() == cell_count,
+        "soil moisture grid is {}x{} but has {} NDVI and {} temperature values",
+        grid.width,
+        grid.height,
+        grid.ndvi.len(),
+        grid.temperature.len()
+    );
+    anyhow::ensure!(
+        config.ndvi_bin_count > 0 && config.min_edge_bins >= 2,
+        "soil moisture config needs at least one NDVI bin and two edge bins"
+    );
+
+    let pixels: Vec<(usize, f64, f64)> = grid
+        .ndvi
+        .iter()
+        .zip(&grid.temperature)
+        .enumerate()
+        .filter(|(_, (ndvi, temperature))| ndvi.is_finite() && temperature.is_finite())
+        .map(|(index, (&ndvi, &temperature))| (index, f64::from(ndvi), f64::from(temperature)))
+        .collect();
+    let (ndvi_min, ndvi_max) = pixels
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), pixel| {
+            (low.min(pixel.1), high.max(pixel.1))
+        });
+    anyhow::ensure!(
+        !pixels.is_empty() && ndvi_max - ndvi_min >= f64::from(config.min_ndvi_range),
+        "insufficient NDVI range for dry/wet edge fitting: scene spans {:.3}..{:.3} \
+         over {} valid pixels, need a spread of at least {:.3}",
+        ndvi_min,
+        ndvi_max,
+        pixels.len(),
+        config.min_ndvi_range
+    );
+
+    let bin_width = (ndvi_max - ndvi_min) / config.ndvi_bin_count as f64;
+    let mut bins: Vec<Option<NdviBin>> = vec![None; config.ndvi_bin_count];
+    for &(_, ndvi, temperature) in &pixels {
+        let bin = (((ndvi - ndvi_min) / bin_width) as usize).min(config.ndvi_bin_count - 1);
+        let entry = bins[bin].get_or_insert(NdviBin {
+            hottest: (ndvi, temperature),
+            coolest: (ndvi, temperature),
+            pixels: 0,
+        });
+        if temperature > entry.hottest.1 {
+            entry.hottest = (ndvi, temperature);
+        }
+        if temperature < entry.coolest.1 {
+            entry.coolest = (ndvi, temperature);
+        }
+        entry.pixels += 1;
+    }
+    let populated: Vec<NdviBin> = bins
+        .into_iter()
+        .flatten()
+        .filter(|bin| bin.pixels >= config.min_pixels_per_bin)
+        .collect();
+    anyhow::ensure!(
+        populated.len() >= config.min_edge_bins,
+        "insufficient NDVI bins for dry/wet edge fitting: {} of {} bins hold at least {} \
+         pixels, need {}",
+        populated.len(),
+        config.ndvi_bin_count,
+        config.min_pixels_per_bin,
+        config.min_edge_bins
+    );
+    let dry_points: Vec<_> = populated.iter().map(|bin| bin.hottest).collect();
+    let wet_points: Vec<_> = populated.iter().map(|bin| bin.coolest).collect();
+    let dry_edge = fit_temperature_edge(&dry_points)
+        .ok_or_else(|| anyhow::anyhow!("dry edge fit is degenerate"))?;
+    let wet_edge = fit_temperature_edge(&wet_points)
+        .ok_or_else(|| anyhow::anyhow!("wet edge fit is degenerate"))?;
+    for ndvi in [ndvi_min, ndvi_max] {
+        anyhow::ensure!(
+            dry_edge.temperature_at(ndvi) > wet_edge.temperature_at(ndvi),
+            "dry edge does not lie above the wet edge at NDVI {ndvi:.3}; the scene has too \
+             little temperature contrast for a dryness index"
+        );
+    }
+
+    let mut tvdi = vec![f32::NAN; cell_count];
+    for &(index, ndvi, temperature) in &pixels {
+        let wet = wet_edge.temperature_at(ndvi);
+        let dry = dry_edge.temperature_at(ndvi);
+        if dry > wet {
+            tvdi[index] = ((temperature - wet) / (dry - wet)).clamp(0.0, 1.0) as f32;
+        }
+    }
+
+    let calibration = if samples.is_empty() {
+        None
+    } else {
+        Some(calibrate_moisture(grid, &tvdi, samples)?)
+    };
+    let values: Vec<f32> = match &calibration {
+        Some(calibration) => tvdi
+            .iter()
+            .map(|&index| (calibration.intercept + calibration.slope * f64::from(index)) as f32)
+            .collect(),
+        None => tvdi.clone(),
+    };
+    let (min_value, max_value) = values
+        .iter()
+        .filter(|value| value.is_finite())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), &value| {
+            (low.min(value), high.max(value))
+        });
+
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert(
+        "value".to_string(),
+        if calibration.is_some() {
+            "volumetric_moisture_m3_per_m3"
+        } else {
+            "tvdi"
+        }
+        .to_string(),
+    );
+    for (name, edge) in [("dry_edge", &dry_edge), ("wet_edge", &wet_edge)] {
+        metadata.insert(format!("{name}_intercept"), edge.intercept.to_string());
+        metadata.insert(format!("{name}_slope"), edge.slope.to_string());
+        metadata.insert(format!("{name}_r_squared"), edge.r_squared.to_string());
+        metadata.insert(format!("{name}_bins"), edge.bin_count.to_string());
+    }
+    metadata.insert("ndvi_min".to_string(), ndvi_min.to_string());
+    metadata.insert("ndvi_max".to_string(), ndvi_max.to_string());
+    if let Some(calibration) = &calibration {
+        metadata.insert(
+            "calibration_intercept".to_string(),
+            calibration.intercept.to_string(),
+        );
+        metadata.insert(
+            "calibration_slope".to_string(),
+            calibration.slope.to_string(),
+        );
+        metadata.insert("calibration_rmse".to_string(), calibration.rmse.to_string());
+        metadata.insert(
+            "calibration_samples".to_string(),
+            calibration.samples_used.to_string(),
+        );
+    }
+
+    Ok(SoilMoistureProduct {
+        overlay: crate::SensorOverlay {
+            id: uuid::Uuid::new_v4(),
+            overlay_type: crate::OverlayType::Custom("soil_moisture_tvdi".to_string()),
+            timestamp: chrono::Utc::now(),
+            spatial_bounds: grid.bounds.clone(),
+            resolution: (grid.width, grid.height),
+            data: crate::OverlayData::Grid {
+                width: grid.width,
+                height: grid.height,
+                values,
+                min_value,
+                max_value,
+            },
+            metadata,
+        },
+        tvdi,
+        dry_edge,
+        wet_edge,
+        ndvi_range: (ndvi_min as f32, ndvi_max as f32),
+        calibration,
+    })
+}
+
+/// Reads ground samples from CSV rows of `lat,lon,volumetric_moisture`; a
+/// header row and blank lines are skipped.
+pub fn parse_moisture_samples_csv(csv: &str) -> Result<Vec<MoistureSample>> {
+    let mut samples = Vec::new();
+    for (line_index, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let parsed: Option<Vec<f64>> = fields.iter().map(|field| field.parse().ok()).collect();
+        match parsed.as_deref() {
+            Some(&[latitude, longitude, volumetric_moisture]) => samples.push(MoistureSample {
+                latitude,
+                longitude,
+                volumetric_moisture,
+            }),
+            None if samples.is_empty() && line_index == 0 => continue,
+            _ => anyhow::bail!(
+                "moisture sample line {} is not `lat,lon,volumetric_moisture`: {line}",
+                line_index + 1
+            ),
+        }
+    }
+    Ok(samples)
+}
+
+pub fn read_moisture_samples_csv(path: &Path) -> Result<Vec<MoistureSample>> {
+    let csv = std::fs::read_to_string(path)
+        .map_err(|error| anyhow::anyhow!("failed to read {}: {error}", path.display()))?;
+    parse_moisture_samples_csv(&csv)
+}
+
+/// Hottest and coolest `(ndvi, temperature)` pixel of one NDVI bin.
+#[derive(Debug, Clone, Copy)]
+struct NdviBin {
+    hottest: (f64, f64),
+    coolest: (f64, f64),
+    pixels: usize,
+}
+
+fn fit_temperature_edge(points: &[(f64, f64)]) -> Option<TemperatureEdge> {
+    let (intercept, slope, r_squared) = fit_line(points)?;
+    Some(TemperatureEdge {
+        intercept,
+        slope,
+        r_squared,
+        bin_count: points.len(),
+    })
+}
+
+/// Least-squares `y = intercept + slope * x` and its R²; `None` when every
+/// `x` is the same.
+fn fit_line(points: &[(f64, f64)]) -> Option<(f64, f64, f64)> {
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|point| point.0).sum::<f64>() / count;
+    let mean_y = points.iter().map(|point| point.1).sum::<f64>() / count;
+    let (sxx, sxy, syy) = points
+        .iter()
+        .fold((0.0, 0.0, 0.0), |(sxx, sxy, syy), point| {
+            let (dx, dy) = (point.0 - mean_x, point.1 - mean_y);
+            (sxx + dx * dx, sxy + dx * dy, syy + dy * dy)
+        });
+    if points.len() < 2 || sxx <= f64::EPSILON {
+        return None;
+    }
+    let slope = sxy / sxx;
+    let r_squared = if syy <= f64::EPSILON {
+        1.0
+    } else {
+        (sxy * sxy) / (sxx * syy)
+    };
+    Some((mean_y - slope * mean_x, slope, r_squared))
+}
+
+fn calibrate_moisture(
+    grid: &NdviTemperatureGrid,
+    tvdi: &[f32],
+    samples: &[MoistureSample],
+) -> Result<MoistureCalibration> {
+    let bounds = &grid.bounds;
+    let points: Vec<(f64, f64)> = samples
+        .iter()
+        .filter(|sample| bounds.contains_point(sample.longitude, sample.latitude))
+        .filter_map(|sample| {
+            let column = ((sample.longitude - bounds.min_x) / (bounds.max_x - bounds.min_x)
+                * f64::from(grid.width)) as u32;
+            let row = ((bounds.max_y - sample.latitude) / (bounds.max_y - bounds.min_y)
+                * f64::from(grid.height)) as u32;
+            let index = row.min(grid.height - 1) as usize * grid.width as usize
+                + column.min(grid.width - 1) as usize;
+            let value = tvdi[index];
+            value
+                .is_finite()
+                .then_some((f64::from(value), sample.volumetric_moisture))
+        })
+        .collect();
+    let (intercept, slope, _) = fit_line(&points).ok_or_else(|| {
+        anyhow::anyhow!(
+            "moisture calibration needs at least two samples inside the scene at different \
+             index values; {} of {} usable",
+            points.len(),
+            samples.len()
+        )
+    })?;
+    let rmse = (points
+        .iter()
+        .map(|&(index, moisture)| (intercept + slope * index - moisture).powi(2))
+        .sum::<f64>()
+        / points.len() as f64)
+        .sqrt();
+
+    Ok(MoistureCalibration {
+        intercept,
+        slope,
+        rmse,
+        samples_used: points.len(),
+        samples_skipped: samples.len() - points.len(),
+    })
+}
+
 // Data structures
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -616,6 +1475,11 @@ pub enum IndividualOverlayResult {
 pub struct CompositeOverlayResult {
     pub individual_overlays: Vec<IndividualOverlayResult>,
     pub composite_image_path: std::path::PathBuf,
+    /// Extent of the NDVI and thermal capture positions, when there were any.
+    pub scan_bounds: Option<crate::SpatialBounds>,
+    /// Where the thermal layer was placed, when it was aligned.
+    #[serde(default)]
+    pub thermal_alignment: Option<ThermalAlignment>,
     pub analysis: CompositeAnalysis,
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
@@ -739,6 +1603,135 @@ mod tests {
         assert!(error.contains("extent-mismatch"));
     }
 
+    #[tokio::test]
+    async fn multiband_geotiff_stacks_overlays_on_the_finest_grid() {
+        let engine = CompositeOverlayEngine::new(
+            CompositeConfig::default(),
+            NdviProcessor::new(NdviConfig::default()),
+            ThermalProcessor::new(ThermalConfig::default()),
+            LidarOverlayProcessor::new(LidarConfig::default()),
+        );
+        let timestamp = chrono::Utc::now();
+        let corners = vec![
+            Point3::new(-105.001, 40.0, 0.0),
+            Point3::new(-105.0, 40.001, 0.0),
+        ];
+        let scan = CompositeScanData {
+            ndvi_data: Some(crate::ndvi::FieldScanData {
+                red_band: vec![0.1; 12],
+                nir_band: vec![0.5; 12],
+                width: 4,
+                height: 3,
+                gps_coordinates: corners.clone(),
+                timestamp,
+            }),
+            thermal_data: Some(crate::thermal::ThermalScanData {
+                raw_thermal_data: vec![3_000; 4],
+                width: 2,
+                height: 2,
+                gps_coordinates: corners,
+                timestamp,
+            }),
+            lidar_data: Some(crate::lidar_overlay::PointCloudData {
+                points: (0..48)
+                    .map(|index| {
+                        Point3::new((index % 8) as f32 * 0.1, (index / 8) as f32 * 0.1, 1.5)
+                    })
+                    .collect(),
+                intensities: vec![100.0; 48],
+                gps_origin: Point3::new(-105.001, 40.0, 1_600.0),
+                timestamp,
+            }),
+            rgb_image: None,
+            gps_reference: Point3::new(-105.0005, 40.0005, 1_600.0),
+            timestamp,
+        };
+        let directory = tempfile::tempdir().unwrap();
+        let result = engine
+            .process_field_scan(&scan, directory.path())
+            .await
+            .unwrap();
+        let path = directory.path().join("stack.tif");
+
+        let export = engine
+            .export_multiband_geotiff(&result, &path, 4326, &GeoTiffOptions::default())
+            .unwrap();
+
+        assert_eq!(export.resolution, (8, 6));
+        assert_eq!(
+            export
+                .bands
+                .iter()
+                .map(|band| (band.description.as_str(), band.source_resolution))
+                .collect::<Vec<_>>(),
+            vec![
+                ("NDVI", (4, 3)),
+                ("Temperature above ambient (degC)", (2, 2)),
+                ("Elevation (m)", (8, 6)),
+            ]
+        );
+        assert!((export.geo_transform[0] + 105.001).abs() < 1e-9);
+        assert!((export.geo_transform[3] - 40.001).abs() < 1e-9);
+        let mut decoder = tiff::decoder::Decoder::new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(decoder.dimensions().unwrap(), (8, 6));
+        assert_eq!(
+            decoder
+                .get_tag_u32(tiff::tags::Tag::SamplesPerPixel)
+                .unwrap(),
+            3
+        );
+        assert_eq!(
+            decoder
+                .get_tag_u32_vec(tiff::tags::Tag::StripOffsets)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn thermal_grid_warps_by_a_known_translation() {
+        let mut thermal = vec![20.0; 8 * 6];
+        thermal[4 * 8 + 3] = 45.0;
+        let shift = [1.0, 0.0, 5.0, 0.0, 1.0, 2.0];
+
+        let warped = warp_thermal_grid(&thermal, (8, 6), &shift, (16, 12)).unwrap();
+        let hottest = warped
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| value.is_finite())
+            .max_by(|left, right| left.1.total_cmp(right.1))
+            .map(|(index, _)| (index % 16, index / 16))
+            .unwrap();
+        assert_eq!(hottest, (8, 6));
+        // The thermal frame covers RGB columns 5..13 and rows 2..8.
+        assert!(warped[16 + 6].is_nan());
+        assert!(warped[2 * 16 + 4].is_nan());
+        assert_eq!(warped[7 * 16 + 12], 20.0);
+        assert!(warped[8 * 16 + 12].is_nan());
+    }
+
+    #[test]
+    fn control_points_recover_a_scaled_and_offset_transform() {
+        let expected = [4.0, 0.0, -3.0, 0.0, 4.0, 10.0];
+        let pairs =
+            [(0.0, 0.0), (10.0, 0.0), (0.0, 8.0), (10.0, 8.0)].map(|(x, y)| ControlPointPair {
+                thermal: (x, y),
+                rgb: (4.0 * x - 3.0, 4.0 * y + 10.0),
+            });
+
+        let fitted = affine_from_control_points(&pairs).unwrap();
+        for (fitted, expected) in fitted.iter().zip(expected) {
+            assert!((fitted - expected).abs() < 1e-4, "{fitted:?}");
+        }
+        let collinear = [(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)].map(|(x, y)| ControlPointPair {
+            thermal: (x, y),
+            rgb: (x, y),
+        });
+        assert!(affine_from_control_points(&collinear).is_err());
+        assert!(invert_affine(&[1.0, 2.0, 0.0, 2.0, 4.0, 0.0]).is_none());
+    }
+
     #[test]
     fn test_vegetation_health_score() {
         let config = CompositeConfig::default();
@@ -759,4 +1752,110 @@ mod tests {
         let health_score = engine.calculate_vegetation_health_score(&ndvi_stats);
         assert!(health_score > 50.0 && health_score < 90.0);
     }
+
+    /// 20x10 scene: NDVI rises 0.1..0.8 across columns, and each row sits a
+    /// fixed fraction of the way from the wet edge to the dry edge.
+    fn synthetic_tvdi_scene() -> NdviTemperatureGrid {
+        let (width, height) = (20u32, 10u32);
+        let mut ndvi = Vec::new();
+        let mut temperature = Vec::new();
+        for row in 0..height {
+            let dryness = row as f32 / (height - 1) as f32;
+            for column in 0..width {
+                let value = 0.1 + 0.7 * column as f32 / (width - 1) as f32;
+                let dry = 45.0 - 20.0 * value;
+                let wet = 22.0 - 2.0 * value;
+                ndvi.push(value);
+                temperature.push(wet + dryness * (dry - wet));
+            }
+        }
+        NdviTemperatureGrid {
+            width,
+            height,
+            ndvi,
+            temperature,
+            bounds: crate::SpatialBounds::new(0.0, 0.0, 20.0, 10.0),
+        }
+    }
+
+    #[test]
+    fn test_soil_moisture_index_recovers_edges_on_synthetic_scene() {
+        let scene = synthetic_tvdi_scene();
+        let product =
+            compute_soil_moisture_index(&scene, &[], &SoilMoistureConfig::default()).unwrap();
+
+        assert!((product.dry_edge.intercept - 45.0).abs() < 1e-3);
+        assert!((product.dry_edge.slope + 20.0).abs() < 1e-3);
+        assert!((product.wet_edge.intercept - 22.0).abs() < 1e-3);
+        assert!((product.wet_edge.slope + 2.0).abs() < 1e-3);
+        assert!(product.dry_edge.r_squared > 0.999);
+        assert!(product.calibration.is_none());
+
+        for row in 0..10 {
+            for column in 0..20 {
+                let value = product.tvdi[row * 20 + column];
+                assert!(
+                    (value - row as f32 / 9.0).abs() < 1e-3,
+                    "row {row}: {value}"
+                );
+            }
+        }
+        assert_eq!(
+            product.overlay.overlay_type,
+            crate::OverlayType::Custom("soil_moisture_tvdi".to_string())
+        );
+        assert_eq!(product.overlay.metadata["value"], "tvdi");
+        assert!(product.overlay.metadata.contains_key("dry_edge_slope"));
+    }
+
+    #[test]
+    fn test_soil_moisture_index_rejects_narrow_ndvi_range() {
+        let mut scene = synthetic_tvdi_scene();
+        for value in &mut scene.ndvi {
+            *value = 0.5 + (*value - 0.1) * 0.1;
+        }
+
+        let error = compute_soil_moisture_index(&scene, &[], &SoilMoistureConfig::default())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("insufficient NDVI range"), "{error}");
+    }
+
+    #[test]
+    fn test_soil_moisture_calibration_from_ground_points() {
+        let samples = parse_moisture_samples_csv(
+            "lat,lon,volumetric_moisture\n\
+             9.5,10.5,0.40\n\
+             6.5,4.5,0.30\n\
+             0.5,15.5,0.10\n\
+             45.0,45.0,0.99\n",
+        )
+        .unwrap();
+        assert_eq!(samples.len(), 4);
+
+        let product = compute_soil_moisture_index(
+            &synthetic_tvdi_scene(),
+            &samples,
+            &SoilMoistureConfig::default(),
+        )
+        .unwrap();
+        let calibration = product.calibration.unwrap();
+        assert_eq!(calibration.samples_used, 3);
+        assert_eq!(calibration.samples_skipped, 1);
+        assert!((calibration.intercept - 0.40).abs() < 1e-3);
+        assert!((calibration.slope + 0.30).abs() < 1e-3);
+        assert!(calibration.rmse < 1e-3);
+
+        let crate::OverlayData::Grid { values, .. } = &product.overlay.data else {
+            panic!("soil moisture overlay should be a grid");
+        };
+        assert!((values[0] - 0.40).abs() < 1e-3);
+        assert!((values[9 * 20] - 0.10).abs() < 1e-3);
+        assert_eq!(
+            product.overlay.metadata["value"],
+            "volumetric_moisture_m3_per_m3"
+        );
+
+        assert!(parse_moisture_samples_csv("1.0,2.0,0.3\n1.0,oops,0.3\n").is_err());
+    }
 }
@@ -0,0 +1,676 @@
+use crate::composite::CompositeConfig;
+use crate::coregistration::CoregistrationMode;
+use crate::formula::{CustomOverlaySpec, FormulaOverlayProcessor};
+use crate::lidar_overlay::LidarConfig;
+use crate::ndvi::NdviConfig;
+use crate::thermal::ThermalConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Colormap names understood by `utils::render_value_overlay`: the shared
+/// continuous palettes.
+pub const KNOWN_COLORMAPS: &[&str] = shared::palette::CONTINUOUS_PALETTE_NAMES;
+
+/// Everything the `process` subcommand can be configured with. Fields
+/// without a serde default are required and unknown fields are rejected, so
+/// a typo cannot silently fall back to a default; only the colour tables
+/// accept extra entries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OverlayEngineConfig {
+    pub composite: CompositeConfig,
+    pub ndvi: NdviConfig,
+    pub thermal: ThermalConfig,
+    pub lidar: LidarConfig,
+    pub colormaps: ColormapSettings,
+    /// Band-math overlays registered as `OverlayType::Custom` at startup.
+    pub custom_overlays: Vec<CustomOverlaySpec>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColormapSettings {
+    pub ndvi: String,
+    pub thermal: String,
+}
+
+impl Default for OverlayEngineConfig {
+    fn default() -> Self {
+        Self {
+            composite: CompositeConfig::default(),
+            ndvi: NdviConfig::default(),
+            thermal: ThermalConfig::default(),
+            lidar: LidarConfig::default(),
+            colormaps: ColormapSettings {
+                ndvi: "viridis".to_string(),
+                thermal: "hot".to_string(),
+            },
+            custom_overlays: Vec::new(),
+        }
+    }
+}
+
+/// Fields serde fills in when a document leaves them out.
+const DEFAULTED_FIELDS: &[&str] = &[
+    "$.composite.thermal_to_rgb_transform",
+    "$.composite.coregistration",
+];
+
+/// Keyed tables whose entries are not checked against the known keys; the
+/// known keys are still required.
+const OPEN_FIELDS: &[&str] = &[
+    "$.ndvi.color_mapping",
+    "$.thermal.color_palette",
+    "$.lidar.height_color_mapping",
+];
+
+/// Description of each documented setting, keyed by JSON path. Objects whose
+/// members are all of one kind (colour tables) are documented as a whole.
+const FIELD_DOCS: &[(&str, &str)] = &[
+    (
+        "$.composite.overlay_types",
+        "Layers blended into the composite: any of Ndvi, Thermal, Lidar, Rgb",
+    ),
+    (
+        "$.composite.blending_mode",
+        "One of Alpha, Multiply, Overlay, Screen, HardLight",
+    ),
+    (
+        "$.composite.opacity_settings",
+        "Per-layer opacity, each between 0 and 1",
+    ),
+    ("$.composite.output_format", "Image format of the composite"),
+    (
+        "$.composite.thermal_to_rgb_transform",
+        "Affine [a, b, c, d, e, f] mapping thermal pixel (x, y) to RGB (ax+by+c, dx+ey+f); null if aligned",
+    ),
+    (
+        "$.composite.coregistration.mode",
+        "Thermal alignment: Off (use thermal_to_rgb_transform), Calibration or Automatic",
+    ),
+    (
+        "$.composite.coregistration.calibration_path",
+        "Rig calibration JSON from `calibrate`; required in Calibration mode",
+    ),
+    (
+        "$.composite.coregistration.max_residual",
+        "Alignments with 1 - cross-correlation above this fall back to GPS placement; 0 to 2",
+    ),
+    (
+        "$.composite.coregistration.max_shift_px",
+        "Largest offset from GPS placement searched in Automatic mode, in RGB pixels",
+    ),
+    (
+        "$.composite.coregistration.shift_step_px",
+        "Coarse offset search step in RGB pixels; must be > 0",
+    ),
+    (
+        "$.composite.coregistration.min_scale",
+        "Smallest thermal frame size searched, relative to GPS placement; must be > 0",
+    ),
+    (
+        "$.composite.coregistration.max_scale",
+        "Largest thermal frame size searched; must be >= min_scale",
+    ),
+    (
+        "$.composite.coregistration.scale_steps",
+        "Scales tried between min_scale and max_scale; must be > 0",
+    ),
+    ("$.ndvi.red_band_index", "Band index of the red channel"),
+    (
+        "$.ndvi.nir_band_index",
+        "Band index of the near-infrared channel; must differ from the red band",
+    ),
+    ("$.ndvi.output_format", "Image format of the NDVI overlay"),
+    (
+        "$.ndvi.color_mapping",
+        "RGB colours for each NDVI class, 0-255 per channel",
+    ),
+    (
+        "$.thermal.temperature_range",
+        "Temperatures mapped onto the palette; min must be below max",
+    ),
+    (
+        "$.thermal.color_palette",
+        "RGB colours from cold to hot, 0-255 per channel",
+    ),
+    (
+        "$.thermal.calibration.offset",
+        "Added to scaled raw counts, in °C",
+    ),
+    (
+        "$.thermal.calibration.scale",
+        "Multiplier applied to raw counts; must be non-zero",
+    ),
+    (
+        "$.thermal.calibration.ambient_temp",
+        "Ambient temperature used for anomaly detection, in °C",
+    ),
+    (
+        "$.lidar.point_cloud_resolution",
+        "Point cloud rasterisation cell size in metres; must be > 0",
+    ),
+    (
+        "$.lidar.height_color_mapping",
+        "RGBA colours for each height class, 0-255 per channel",
+    ),
+    (
+        "$.lidar.occupancy_grid_resolution",
+        "Occupancy grid cell size in metres; must be > 0",
+    ),
+    (
+        "$.lidar.max_range",
+        "Points farther than this many metres are ignored; must be > 0",
+    ),
+    (
+        "$.lidar.ground_filter.cell_size",
+        "Ground filter grid cell size in metres; must be > 0",
+    ),
+    (
+        "$.lidar.ground_filter.initial_window",
+        "First ground filter window in metres; doubles each pass; must be > 0",
+    ),
+    (
+        "$.lidar.ground_filter.max_window",
+        "Largest ground filter window in metres; must be >= initial_window",
+    ),
+    (
+        "$.lidar.ground_filter.ground_threshold",
+        "Height in metres above the ground surface still labelled ground; must be > 0",
+    ),
+    (
+        "$.lidar.ground_filter.slope",
+        "Terrain rise per metre of window growth; must be >= 0",
+    ),
+    (
+        "$.lidar.ground_filter.structure_height",
+        "Height in metres from which non-ground points are structures; must exceed ground_threshold",
+    ),
+    ("$.colormaps.ndvi", "Colormap for the NDVI value overlay"),
+    (
+        "$.colormaps.thermal",
+        "Colormap for the thermal value overlay",
+    ),
+    (
+        "$.custom_overlays",
+        "Band-math overlays, each {\"name\", \"formula\"} over band names, e.g. \"(b5 - b4) / (b5 + b4)\"",
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigFieldError {
+    /// JSON path of the offending field, e.g. `$.composite.opacity_settings`.
+    pub path: String,
+    pub message: String,
+}
+
+/// Every problem found in a configuration document, in document order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigValidationError {
+    pub errors: Vec<ConfigFieldError>,
+}
+
+impl ConfigValidationError {
+    fn single(path: &str, message: impl Into<String>) -> Self {
+        Self {
+            errors: vec![ConfigFieldError {
+                path: path.to_string(),
+                message: message.into(),
+            }],
+        }
+    }
+
+    pub fn has_error_at(&self, path: &str) -> bool {
+        self.errors.iter().any(|error| error.path == path)
+    }
+}
+
+impl fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid overlay engine configuration:")?;
+        for error in &self.errors {
+            write!(f, "\n  {}: {}", error.path, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigValidationError {}
+
+impl OverlayEngineConfig {
+    pub fn from_json_str(text: &str) -> Result<Self, ConfigValidationError> {
+        let value = serde_json::from_str::<Value>(text)
+            .map_err(|error| ConfigValidationError::single("$", error.to_string()))?;
+        Self::from_json_value(&value)
+    }
+
+    /// Parses and validates a configuration, collecting unknown and missing
+    /// fields, type errors and out-of-range values rather than stopping at
+    /// the first problem.
+    pub fn from_json_value(value: &Value) -> Result<Self, ConfigValidationError> {
+        let reference = serde_json::to_value(Self::default())
+            .expect("default overlay engine config serializes");
+        let mut errors = Vec::new();
+        check_shape(value, &reference, "$", &mut errors);
+
+        // Known fields are laid over the defaults so range checks still run
+        // on a document that also has structural problems.
+        let merged = merge_known_fields(value, &reference);
+        match serde_json::from_value::<Self>(merged) {
+            Ok(config) => {
+                errors.extend(config.range_errors());
+                if errors.is_empty() {
+                    return Ok(config);
+                }
+            }
+            Err(error) => errors.push(ConfigFieldError {
+                path: "$".to_string(),
+                message: error.to_string(),
+            }),
+        }
+        Err(ConfigValidationError { errors })
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigValidationError> {
+        let errors = self.range_errors();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigValidationError { errors })
+        }
+    }
+
+    /// Default configuration as pretty JSON, a starting point for `--config`.
+    pub fn example_json() -> String {
+        serde_json::to_string_pretty(&Self::default())
+            .expect("default overlay engine config serializes")
+    }
+
+    /// Markdown companion to [`Self::example_json`] describing each field,
+    /// its default and its constraints.
+    pub fn example_doc() -> String {
+        let reference = serde_json::to_value(Self::default())
+            .expect("default overlay engine config serializes");
+        let mut doc = String::from(
+            "# sensor-overlay-engine configuration\n\n\
+             Fields are required unless marked optional; unknown fields are \
+             rejected except in colour tables.\n\n\
+             | Field | Default | Description |\n|---|---|---|\n",
+        );
+        for (path, default) in documented_fields(&reference, "$") {
+            let description = field_doc(&path).unwrap_or("");
+            let description = if DEFAULTED_FIELDS.contains(&path.as_str()) {
+                format!("Optional. {description}")
+            } else {
+                description.to_string()
+            };
+            doc.push_str(&format!("| `{path}` | `{default}` | {description} |\n"));
+        }
+        doc
+    }
+
+    fn range_errors(&self) -> Vec<ConfigFieldError> {
+        let mut errors = Vec::new();
+        let mut push = |path: &str, message: String| {
+            errors.push(ConfigFieldError {
+                path: path.to_string(),
+                message,
+            })
+        };
+
+        let opacity = &self.composite.opacity_settings;
+        for (name, value) in [
+            ("ndvi_opacity", opacity.ndvi_opacity),
+            ("thermal_opacity", opacity.thermal_opacity),
+            ("lidar_opacity", opacity.lidar_opacity),
+            ("rgb_opacity", opacity.rgb_opacity),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                push(
+                    &format!("$.composite.opacity_settings.{name}"),
+                    format!("opacity must be between 0 and 1, got {value}"),
+                );
+            }
+        }
+        if let Some(transform) = &self.composite.thermal_to_rgb_transform {
+            if transform.iter().any(|value| !value.is_finite())
+                || crate::composite::invert_affine(transform).is_none()
+            {
+                push(
+                    "$.composite.thermal_to_rgb_transform",
+                    format!("must be a finite, invertible affine transform, got {transform:?}"),
+                );
+            }
+        }
+        let coregistration = &self.composite.coregistration;
+        if coregistration.mode == CoregistrationMode::Calibration
+            && coregistration.calibration_path.is_none()
+        {
+            push(
+                "$.composite.coregistration.calibration_path",
+                "required in Calibration mode".to_string(),
+            );
+        }
+        if !(0.0..=2.0).contains(&coregistration.max_residual) {
+            push(
+                "$.composite.coregistration.max_residual",
+                format!(
+                    "must be between 0 and 2, got {}",
+                    coregistration.max_residual
+                ),
+            );
+        }
+        for (name, value) in [
+            ("shift_step_px", coregistration.shift_step_px),
+            ("scale_steps", coregistration.scale_steps),
+        ] {
+            if value == 0 {
+                push(
+                    &format!("$.composite.coregistration.{name}"),
+                    "must be > 0".to_string(),
+                );
+            }
+        }
+        if coregistration.min_scale.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater) {
+            push(
+                "$.composite.coregistration.min_scale",
+                format!("must be > 0, got {}", coregistration.min_scale),
+            );
+        } else if coregistration
+            .max_scale
+            .partial_cmp(&coregistration.min_scale)
+            == Some(std::cmp::Ordering::Less)
+            || coregistration.max_scale.is_nan()
+        {
+            push(
+                "$.composite.coregistration.max_scale",
+                format!(
+                    "must be >= min_scale ({}), got {}",
+                    coregistration.min_scale, coregistration.max_scale
+                ),
+            );
+        }
+        if self.composite.overlay_types.is_empty() {
+            push(
+                "$.composite.overlay_types",
+                "at least one overlay type is required".to_string(),
+            );
+        }
+
+        if self.ndvi.red_band_index == self.ndvi.nir_band_index {
+            push(
+                "$.ndvi.nir_band_index",
+                "near-infrared band must differ from the red band".to_string(),
+            );
+        }
+
+        let range = &self.thermal.temperature_range;
+        if range.min_celsius.partial_cmp(&range.max_celsius) != Some(std::cmp::Ordering::Less) {
+            push(
+                "$.thermal.temperature_range",
+                format!(
+                    "min_celsius {} must be below max_celsius {}",
+                    range.min_celsius, range.max_celsius
+                ),
+            );
+        }
+        let scale = self.thermal.calibration.scale;
+        if !scale.is_finite() || scale == 0.0 {
+            push(
+                "$.thermal.calibration.scale",
+                format!("scale must be a non-zero number, got {scale}"),
+            );
+        }
+
+        let ground_filter = &self.lidar.ground_filter;
+        for (name, value) in [
+            ("point_cloud_resolution", self.lidar.point_cloud_resolution),
+            (
+                "occupancy_grid_resolution",
+                self.lidar.occupancy_grid_resolution,
+            ),
+            ("max_range", self.lidar.max_range),
+            ("ground_filter.cell_size", ground_filter.cell_size),
+            ("ground_filter.initial_window", ground_filter.initial_window),
+            (
+                "ground_filter.ground_threshold",
+                ground_filter.ground_threshold,
+            ),
+        ] {
+            if !(value > 0.0 && value.is_finite()) {
+                push(
+                    &format!("$.lidar.{name}"),
+                    format!("must be greater than 0, got {value}"),
+                );
+            }
+        }
+        if ground_filter
+            .max_window
+            .partial_cmp(&ground_filter.initial_window)
+            .is_none_or(|ordering| ordering == std::cmp::Ordering::Less)
+        {
+            push(
+                "$.lidar.ground_filter.max_window",
+                format!(
+                    "must be at least initial_window {}, got {}",
+                    ground_filter.initial_window, ground_filter.max_window
+                ),
+            );
+        }
+        if !(ground_filter.slope >= 0.0 && ground_filter.slope.is_finite()) {
+            push(
+                "$.lidar.ground_filter.slope",
+                format!("must be 0 or greater, got {}", ground_filter.slope),
+            );
+        }
+        if ground_filter
+            .structure_height
+            .partial_cmp(&ground_filter.ground_threshold)
+            != Some(std::cmp::Ordering::Greater)
+        {
+            push(
+                "$.lidar.ground_filter.structure_height",
+                format!(
+                    "must exceed ground_threshold {}, got {}",
+                    ground_filter.ground_threshold, ground_filter.structure_height
+                ),
+            );
+        }
+
+        for (name, colormap) in [
+            ("ndvi", &self.colormaps.ndvi),
+            ("thermal", &self.colormaps.thermal),
+        ] {
+            if !KNOWN_COLORMAPS.contains(&colormap.as_str()) {
+                push(
+                    &format!("$.colormaps.{name}"),
+                    format!(
+                        "unknown colormap '{colormap}'; expected one of {}",
+                        KNOWN_COLORMAPS.join(", ")
+                    ),
+                );
+            }
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for (index, spec) in self.custom_overlays.iter().enumerate() {
+            if let Err(error) = FormulaOverlayProcessor::from_spec(spec) {
+                push(&format!("$.custom_overlays[{index}]"), format!("{error:#}"));
+            } else if !names.insert(spec.name.as_str()) {
+                push(
+                    &format!("$.custom_overlays[{index}].name"),
+                    format!("duplicate custom overlay '{}'", spec.name),
+                );
+            }
+        }
+
+        errors
+    }
+}
+
+fn check_shape(value: &Value, reference: &Value, path: &str, errors: &mut Vec<ConfigFieldError>) {
+    let Value::Object(expected) = reference else {
+        return;
+    };
+    let Value::Object(actual) = value else {
+        errors.push(ConfigFieldError {
+            path: path.to_string(),
+            message: "expected an object".to_string(),
+        });
+        return;
+    };
+
+    let open = OPEN_FIELDS.contains(&path);
+    for key in actual.keys() {
+        if !open && !expected.contains_key(key) {
+            errors.push(ConfigFieldError {
+                path: format!("{path}.{key}"),
+                message: "unknown field".to_string(),
+            });
+        }
+    }
+    for (key, expected_value) in expected {
+        let field_path = format!("{path}.{key}");
+        match actual.get(key) {
+            Some(actual_value) => check_shape(actual_value, expected_value, &field_path, errors),
+            None if DEFAULTED_FIELDS.contains(&field_path.as_str()) => {}
+            None => errors.push(ConfigFieldError {
+                path: field_path,
+                message: "missing field".to_string(),
+            }),
+        }
+    }
+}
+
+fn merge_known_fields(value: &Value, reference: &Value) -> Value {
+    match (value, reference) {
+        (Value::Object(actual), Value::Object(expected)) => Value::Object(
+            expected
+                .iter()
+                .map(|(key, expected_value)| {
+                    let merged = actual
+                        .get(key)
+                        .map(|actual_value| merge_known_fields(actual_value, expected_value))
+                        .unwrap_or_else(|| expected_value.clone());
+                    (key.clone(), merged)
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        (_, Value::Object(_)) => reference.clone(),
+        _ => value.clone(),
+    }
+}
+
+fn field_doc(path: &str) -> Option<&'static str> {
+    FIELD_DOCS
+        .iter()
+        .find(|(documented, _)| *documented == path)
+        .map(|(_, description)| *description)
+}
+
+/// Leaf fields and documented objects of `reference`, paired with their
+/// compact default value.
+fn documented_fields(reference: &Value, path: &str) -> Vec<(String, String)> {
+    match reference {
+        Value::Object(fields) if field_doc(path).is_none() => fields
+            .iter()
+            .flat_map(|(key, value)| documented_fields(value, &format!("{path}.{key}")))
+            .collect(),
+        _ => vec![(path.to_string(), reference.to_string())],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn default_value() -> Value {
+        serde_json::to_value(OverlayEngineConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn default_and_example_round_trip() {
+        let config = OverlayEngineConfig::from_json_str(&OverlayEngineConfig::example_json())
+            .expect("example config is valid");
+        assert_eq!(config.colormaps.ndvi, "viridis");
+
+        let doc = OverlayEngineConfig::example_doc();
+        for (path, _) in documented_fields(&default_value(), "$") {
+            assert!(field_doc(&path).is_some(), "{path} is undocumented");
+            assert!(doc.contains(&format!("`{path}`")));
+        }
+    }
+
+    #[test]
+    fn unknown_missing_and_out_of_range_fields_are_all_reported_by_path() {
+        let mut value = default_value();
+        value["composite"]["opacity_settings"]["ndvi_opacty"] = json!(0.5);
+        value["composite"]["opacity_settings"]
+            .as_object_mut()
+            .unwrap()
+            .remove("ndvi_opacity");
+        value["composite"]["opacity_settings"]["thermal_opacity"] = json!(1.5);
+        value["colormaps"]["thermal"] = json!("rainbow");
+        value["lidar"]["max_range"] = json!(0.0);
+        value["composite"]["thermal_to_rgb_transform"] = json!([1.0, 2.0, 0.0, 2.0, 4.0, 0.0]);
+        value["composite"]["coregistration"]["scale_steps"] = json!(0);
+        value["custom_overlays"] = json!([
+            {"name": "ndre", "formula": "(b5 - b4) / (b5 + b4)"},
+            {"name": "ndre", "formula": "b5 / b4"},
+            {"name": "broken", "formula": "(b5 - b4"},
+        ]);
+
+        let error = OverlayEngineConfig::from_json_value(&value).unwrap_err();
+
+        assert!(error.has_error_at("$.composite.opacity_settings.ndvi_opacty"));
+        assert!(error.has_error_at("$.composite.opacity_settings.ndvi_opacity"));
+        assert!(error.has_error_at("$.composite.opacity_settings.thermal_opacity"));
+        assert!(error.has_error_at("$.colormaps.thermal"));
+        assert!(error.has_error_at("$.lidar.max_range"));
+        assert!(error.has_error_at("$.composite.thermal_to_rgb_transform"));
+        assert!(error.has_error_at("$.composite.coregistration.scale_steps"));
+        assert!(error.has_error_at("$.custom_overlays[1].name"));
+        assert!(error.has_error_at("$.custom_overlays[2]"));
+        assert_eq!(error.errors.len(), 9);
+        assert!(error.to_string().contains("unknown colormap 'rainbow'"));
+    }
+
+    #[test]
+    fn defaulted_fields_may_be_omitted_and_colour_tables_take_extra_entries() {
+        let mut value = default_value();
+        let composite = value["composite"].as_object_mut().unwrap();
+        composite.remove("thermal_to_rgb_transform");
+        composite.remove("coregistration");
+        value["ndvi"]["color_mapping"]["irrigated_pasture"] = json!([0, 160, 90]);
+        let config = OverlayEngineConfig::from_json_value(&value).unwrap();
+        let defaults = OverlayEngineConfig::default();
+        assert_eq!(
+            serde_json::to_value(&config.composite).unwrap(),
+            serde_json::to_value(&defaults.composite).unwrap()
+        );
+
+        value["ndvi"]["color_mapping"]
+            .as_object_mut()
+            .unwrap()
+            .remove("water");
+        let error = OverlayEngineConfig::from_json_value(&value).unwrap_err();
+        assert!(error.has_error_at("$.ndvi.color_mapping.water"));
+        assert_eq!(error.errors.len(), 1);
+    }
+
+    #[test]
+    fn type_errors_and_non_objects_are_rejected() {
+        let error = OverlayEngineConfig::from_json_str(r#"{"composite": []}"#).unwrap_err();
+        assert!(error.has_error_at("$.composite"));
+        assert!(error.has_error_at("$.ndvi"));
+
+        let mut value = default_value();
+        value["ndvi"]["red_band_index"] = json!("zero");
+        let error = OverlayEngineConfig::from_json_value(&value).unwrap_err();
+        assert!(error.has_error_at("$"));
+
+        assert!(OverlayEngineConfig::from_json_str("{").is_err());
+    }
+}
@@ -0,0 +1,595 @@
+//! Thermal-to-RGB co-registration.
+//!
+//! The thermal and RGB/multispectral cameras have different fields of view
+//! and sit a few centimetres apart, so a thermal frame stretched over the
+//! RGB frame (GPS-only placement) lands metres off. Alignment comes either
+//! from a per-rig [`RigCalibration`] fitted to hand-matched control points,
+//! or from a normalized cross-correlation search over the scale and offset
+//! of the thermal frame. Transforms use the layout of
+//! [`CompositeConfig::thermal_to_rgb_transform`](crate::composite::CompositeConfig::thermal_to_rgb_transform).
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::composite::{affine_from_control_points, invert_affine, ControlPointPair};
+
+/// Fewest reference samples that must land on the thermal frame for a
+/// candidate alignment to be scored.
+const MIN_OVERLAP_SAMPLES: usize = 64;
+
+/// Reference samples taken along the longer image side when scoring.
+const SAMPLES_PER_SIDE: u32 = 96;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CoregistrationMode {
+    /// Use [`CompositeConfig::thermal_to_rgb_transform`](crate::composite::CompositeConfig::thermal_to_rgb_transform)
+    /// as is, if set.
+    Off,
+    /// Load a [`RigCalibration`] from `calibration_path`.
+    Calibration,
+    /// Search for the alignment that best correlates the two frames.
+    Automatic,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CoregistrationConfig {
+    pub mode: CoregistrationMode,
+    /// Rig calibration written by `sensor-overlay-engine calibrate`.
+    pub calibration_path: Option<PathBuf>,
+    /// Alignments scoring a residual (1 - normalized cross-correlation)
+    /// above this fall back to GPS-only placement.
+    pub max_residual: f32,
+    /// Largest offset from GPS-only placement searched, in RGB pixels.
+    pub max_shift_px: u32,
+    /// Coarse search step, in RGB pixels; the best candidate is refined
+    /// below it.
+    pub shift_step_px: u32,
+    /// Thermal frame size relative to GPS-only placement searched.
+    pub min_scale: f32,
+    pub max_scale: f32,
+    pub scale_steps: u32,
+}
+
+impl Default for CoregistrationConfig {
+    fn default() -> Self {
+        Self {
+            mode: CoregistrationMode::Off,
+            calibration_path: None,
+            max_residual: 0.5,
+            max_shift_px: 32,
+            shift_step_px: 2,
+            min_scale: 0.8,
+            max_scale: 1.2,
+            scale_steps: 9,
+        }
+    }
+}
+
+/// The thermal-to-RGB alignment of one rig, fitted to control points.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RigCalibration {
+    pub rig_id: String,
+    pub thermal_to_rgb: [f32; 6],
+    pub control_points: Vec<ControlPointPair>,
+    /// Root-mean-square distance between the transformed thermal points and
+    /// their RGB matches, in RGB pixels.
+    pub residual_px: f32,
+}
+
+impl RigCalibration {
+    pub fn from_control_points(
+        rig_id: impl Into<String>,
+        control_points: Vec<ControlPointPair>,
+    ) -> Result<Self> {
+        let thermal_to_rgb = affine_from_control_points(&control_points)?;
+        let [a, b, c, d, e, f] = thermal_to_rgb.map(f64::from);
+        let squared_error: f64 = control_points
+            .iter()
+            .map(|pair| {
+                let (x, y) = (f64::from(pair.thermal.0), f64::from(pair.thermal.1));
+                let dx = a * x + b * y + c - f64::from(pair.rgb.0);
+                let dy = d * x + e * y + f - f64::from(pair.rgb.1);
+                dx * dx + dy * dy
+            })
+            .sum();
+        Ok(Self {
+            rig_id: rig_id.into(),
+            thermal_to_rgb,
+            residual_px: (squared_error / control_points.len() as f64).sqrt() as f32,
+            control_points,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read rig calibration {}", path.display()))?;
+        let calibration: Self = serde_json::from_str(&json)
+            .with_context(|| format!("invalid rig calibration {}", path.display()))?;
+        anyhow::ensure!(
+            invert_affine(&calibration.thermal_to_rgb).is_some(),
+            "rig calibration {} is not invertible",
+            path.display()
+        );
+        Ok(calibration)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write rig calibration {}", path.display()))
+    }
+}
+
+/// Reads control point pairs from CSV rows of
+/// `thermal_x,thermal_y,rgb_x,rgb_y`; a header row and blank lines are
+/// skipped.
+pub fn parse_control_points_csv(csv: &str) -> Result<Vec<ControlPointPair>> {
+    let mut pairs = Vec::new();
+    for (line_index, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let parsed: Option<Vec<f32>> = fields.iter().map(|field| field.parse().ok()).collect();
+        match parsed.as_deref() {
+            Some(&[thermal_x, thermal_y, rgb_x, rgb_y]) => pairs.push(ControlPointPair {
+                thermal: (thermal_x, thermal_y),
+                rgb: (rgb_x, rgb_y),
+            }),
+            None if pairs.is_empty() && line_index == 0 => continue,
+            _ => anyhow::bail!(
+                "control point line {} is not `thermal_x,thermal_y,rgb_x,rgb_y`: {line}",
+                line_index + 1
+            ),
+        }
+    }
+    Ok(pairs)
+}
+
+/// A single-band image, row-major from the top-left corner.
+#[derive(Debug, Clone, Copy)]
+pub struct IntensityFrame<'a> {
+    pub values: &'a [f32],
+    pub width: u32,
+    pub height: u32,
+}
+
+impl IntensityFrame<'_> {
+    /// Bilinear sample at pixel coordinates, `None` outside the frame or
+    /// next to a non-finite cell.
+    fn sample(&self, x: f64, y: f64) -> Option<f64> {
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (max_x, max_y) = (f64::from(self.width) - 1.0, f64::from(self.height) - 1.0);
+        if !(x >= 0.0 && y >= 0.0 && x <= max_x && y <= max_y) {
+            return None;
+        }
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = (
+            (x0 + 1).min(self.width as usize - 1),
+            (y0 + 1).min(self.height as usize - 1),
+        );
+        let (fx, fy) = (x - x0 as f64, y - y0 as f64);
+        let at =
+            |column: usize, row: usize| f64::from(self.values[row * self.width as usize + column]);
+        let value = (at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx) * (1.0 - fy)
+            + (at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx) * fy;
+        value.is_finite().then_some(value)
+    }
+}
+
+/// Stretches the thermal frame over the whole RGB frame, as if both cameras
+/// saw exactly the GPS footprint of the capture.
+pub fn gps_only_transform(thermal: (u32, u32), rgb: (u32, u32)) -> [f32; 6] {
+    [
+        rgb.0 as f32 / thermal.0 as f32,
+        0.0,
+        0.0,
+        0.0,
+        rgb.1 as f32 / thermal.1 as f32,
+        0.0,
+    ]
+}
+
+/// 1 - normalized cross-correlation between `reference` and `thermal`
+/// warped through `transform`, over a grid of reference samples; from 0 for
+/// a perfect match to 2. `None` when too few samples overlap the thermal
+/// frame or either side is flat.
+pub fn alignment_residual(
+    thermal: &IntensityFrame,
+    reference: &IntensityFrame,
+    transform: &[f32; 6],
+) -> Option<f32> {
+    let [a, b, c, d, e, f] = invert_affine(transform)?;
+    let stride = (reference.width.max(reference.height) / SAMPLES_PER_SIDE).max(1);
+    let (mut count, mut sum_t, mut sum_r) = (0usize, 0.0, 0.0);
+    let (mut sum_tt, mut sum_rr, mut sum_tr) = (0.0, 0.0, 0.0);
+    for row in (0..reference.height).step_by(stride as usize) {
+        let v = f64::from(row) + 0.5;
+        for column in (0..reference.width).step_by(stride as usize) {
+            let u = f64::from(column) + 0.5;
+            let r = f64::from(reference.values[(row * reference.width + column) as usize]);
+            if !r.is_finite() {
+                continue;
+            }
+            let Some(t) = thermal.sample(a * u + b * v + c, d * u + e * v + f) else {
+                continue;
+            };
+            count += 1;
+            sum_t += t;
+            sum_r += r;
+            sum_tt += t * t;
+            sum_rr += r * r;
+            sum_tr += t * r;
+        }
+    }
+    if count < MIN_OVERLAP_SAMPLES {
+        return None;
+    }
+    let n = count as f64;
+    let covariance = sum_tr - sum_t * sum_r / n;
+    let variance_t = sum_tt - sum_t * sum_t / n;
+    let variance_r = sum_rr - sum_r * sum_r / n;
+    let denominator = (variance_t * variance_r).sqrt();
+    if !denominator.is_normal() {
+        return None;
+    }
+    Some((1.0 - covariance / denominator) as f32)
+}
+
+/// A candidate alignment: the thermal frame scaled by `scale` about the
+/// RGB frame centre relative to GPS-only placement, then shifted.
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    scale: f64,
+    shift_x: f64,
+    shift_y: f64,
+}
+
+impl Candidate {
+    fn transform(&self, thermal: (u32, u32), rgb: (u32, u32)) -> [f32; 6] {
+        let stretch_x = f64::from(rgb.0) / f64::from(thermal.0);
+        let stretch_y = f64::from(rgb.1) / f64::from(thermal.1);
+        let (centre_x, centre_y) = (f64::from(rgb.0) / 2.0, f64::from(rgb.1) / 2.0);
+        [
+            self.scale * stretch_x,
+            0.0,
+            centre_x * (1.0 - self.scale) + self.shift_x,
+            0.0,
+            self.scale * stretch_y,
+            centre_y * (1.0 - self.scale) + self.shift_y,
+        ]
+        .map(|value| value as f32)
+    }
+}
+
+/// Searches scale and offset for the transform that maximises the
+/// normalized cross-correlation of `thermal` with `reference`: a coarse grid
+/// over the configured window, then a local refinement down to a quarter
+/// pixel. Returns the transform and its residual, or `None` when no
+/// candidate overlapped enough of the reference to be scored.
+pub fn estimate_alignment(
+    thermal: &IntensityFrame,
+    reference: &IntensityFrame,
+    config: &CoregistrationConfig,
+) -> Option<([f32; 6], f32)> {
+    let sizes = (
+        (thermal.width, thermal.height),
+        (reference.width, reference.height),
+    );
+    let score = |candidate: &Candidate| {
+        let transform = candidate.transform(sizes.0, sizes.1);
+        alignment_residual(thermal, reference, &transform)
+    };
+
+    let scale_steps = config.scale_steps.max(1);
+    let scale_step = if scale_steps > 1 {
+        f64::from(config.max_scale - config.min_scale) / f64::from(scale_steps - 1)
+    } else {
+        0.0
+    };
+    let shift_step = config.shift_step_px.max(1) as i64;
+    let max_shift = i64::from(config.max_shift_px);
+    let mut best: Option<(Candidate, f32)> = None;
+    for scale_index in 0..scale_steps {
+        let scale = f64::from(config.min_scale) + scale_step * f64::from(scale_index);
+        for shift_y in (-max_shift..=max_shift).step_by(shift_step as usize) {
+            for shift_x in (-max_shift..=max_shift).step_by(shift_step as usize) {
+                let candidate = Candidate {
+                    scale,
+                    shift_x: shift_x as f64,
+                    shift_y: shift_y as f64,
+                };
+                if let Some(residual) = score(&candidate) {
+                    if best.is_none_or(|(_, best_residual)| residual < best_residual) {
+                        best = Some((candidate, residual));
+                    }
+                }
+            }
+        }
+    }
+
+    let (mut candidate, mut residual) = best?;
+    let mut step_px = shift_step as f64 / 2.0;
+    let mut step_scale = scale_step / 2.0;
+    while step_px >= 0.25 {
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for (scale, shift_x, shift_y) in [
+                (step_scale, 0.0, 0.0),
+                (-step_scale, 0.0, 0.0),
+                (0.0, step_px, 0.0),
+                (0.0, -step_px, 0.0),
+                (0.0, 0.0, step_px),
+                (0.0, 0.0, -step_px),
+            ] {
+                let neighbour = Candidate {
+                    scale: candidate.scale + scale,
+                    shift_x: candidate.shift_x + shift_x,
+                    shift_y: candidate.shift_y + shift_y,
+                };
+                if neighbour.scale <= 0.0 {
+                    continue;
+                }
+                if let Some(neighbour_residual) = score(&neighbour) {
+                    if neighbour_residual < residual {
+                        (candidate, residual) = (neighbour, neighbour_residual);
+                        improved = true;
+                    }
+                }
+            }
+        }
+        step_px /= 2.0;
+        step_scale /= 2.0;
+    }
+    Some((candidate.transform(sizes.0, sizes.1), residual))
+}
+
+/// How a thermal layer ended up placed on the composite.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AlignmentMethod {
+    /// [`CompositeConfig::thermal_to_rgb_transform`](crate::composite::CompositeConfig::thermal_to_rgb_transform).
+    Configured,
+    Calibration,
+    Automatic,
+    GpsOnly,
+}
+
+/// Thermal alignment recorded with a composite.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ThermalAlignment {
+    pub method: AlignmentMethod,
+    pub transform: [f32; 6],
+    /// 1 - normalized cross-correlation of the aligned frames, when there
+    /// was a reference frame to score against.
+    pub residual: Option<f32>,
+    /// Why the configured method was abandoned for GPS-only placement.
+    pub fallback_reason: Option<String>,
+}
+
+impl ThermalAlignment {
+    fn gps_only(
+        thermal: (u32, u32),
+        rgb: (u32, u32),
+        residual: Option<f32>,
+        reason: String,
+    ) -> Self {
+        tracing::warn!("Placing thermal layer by GPS only: {reason}");
+        Self {
+            method: AlignmentMethod::GpsOnly,
+            transform: gps_only_transform(thermal, rgb),
+            residual,
+            fallback_reason: Some(reason),
+        }
+    }
+}
+
+/// Aligns `thermal` onto the composite frame per `config`. `reference` is
+/// the RGB or NIR intensity on the composite grid, if the scan has one.
+/// Returns `None` in [`CoregistrationMode::Off`] without a configured
+/// transform, where the thermal layer is blended pixel for pixel as before.
+pub fn align_thermal(
+    thermal: &IntensityFrame,
+    reference: Option<&IntensityFrame>,
+    composite: (u32, u32),
+    config: &CoregistrationConfig,
+    configured: Option<&[f32; 6]>,
+) -> Result<Option<ThermalAlignment>> {
+    let thermal_size = (thermal.width, thermal.height);
+    let (method, transform) = match config.mode {
+        CoregistrationMode::Off => match configured {
+            Some(transform) => (AlignmentMethod::Configured, *transform),
+            None => return Ok(None),
+        },
+        CoregistrationMode::Calibration => {
+            let path = config
+                .calibration_path
+                .as_deref()
+                .context("calibration co-registration needs a calibration_path")?;
+            (
+                AlignmentMethod::Calibration,
+                RigCalibration::load(path)?.thermal_to_rgb,
+            )
+        }
+        CoregistrationMode::Automatic => {
+            let Some(reference) = reference else {
+                return Ok(Some(ThermalAlignment::gps_only(
+                    thermal_size,
+                    composite,
+                    None,
+                    "no RGB or NIR frame to align against".to_string(),
+                )));
+            };
+            match estimate_alignment(thermal, reference, config) {
+                Some((transform, _)) => (AlignmentMethod::Automatic, transform),
+                None => {
+                    return Ok(Some(ThermalAlignment::gps_only(
+                        thermal_size,
+                        composite,
+                        None,
+                        "no candidate alignment overlapped the reference frame".to_string(),
+                    )))
+                }
+            }
+        }
+    };
+
+    let residual =
+        reference.and_then(|reference| alignment_residual(thermal, reference, &transform));
+    if let Some(residual) = residual.filter(|residual| *residual > config.max_residual) {
+        return Ok(Some(ThermalAlignment::gps_only(
+            thermal_size,
+            composite,
+            Some(residual),
+            format!(
+                "{method:?} alignment residual {residual:.3} exceeds {}",
+                config.max_residual
+            ),
+        )));
+    }
+    Ok(Some(ThermalAlignment {
+        method,
+        transform,
+        residual,
+        fallback_reason: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A smooth field with a few hot spots, so correlation has one clear
+    /// peak.
+    fn scene(x: f64, y: f64) -> f32 {
+        let blob = |cx: f64, cy: f64, radius: f64| {
+            (-((x - cx).powi(2) + (y - cy).powi(2)) / (2.0 * radius * radius)).exp()
+        };
+        (20.0 + 12.0 * blob(30.0, 40.0, 6.0) + 8.0 * blob(70.0, 25.0, 9.0)
+            - 6.0 * blob(55.0, 75.0, 7.0)
+            + 0.05 * x
+            + 0.02 * y) as f32
+    }
+
+    fn render(width: u32, height: u32, to_scene: impl Fn(f64, f64) -> (f64, f64)) -> Vec<f32> {
+        (0..height)
+            .flat_map(|row| (0..width).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let (x, y) = to_scene(f64::from(column) + 0.5, f64::from(row) + 0.5);
+                scene(x, y)
+            })
+            .collect()
+    }
+
+    /// A 100x100 RGB frame of the scene and a 50x50 thermal frame of a
+    /// narrower view: thermal pixel (x, y) sees RGB (1.6x + 14, 1.6y + 9).
+    fn synthetic_pair() -> (Vec<f32>, Vec<f32>, [f32; 6]) {
+        let expected = [1.6, 0.0, 14.0, 0.0, 1.6, 9.0];
+        let reference = render(100, 100, |x, y| (x, y));
+        let thermal = render(50, 50, |x, y| (1.6 * x + 14.0, 1.6 * y + 9.0));
+        (thermal, reference, expected)
+    }
+
+    #[test]
+    fn automatic_alignment_recovers_a_shifted_and_scaled_thermal_frame() {
+        let (thermal, reference, expected) = synthetic_pair();
+        let thermal = IntensityFrame {
+            values: &thermal,
+            width: 50,
+            height: 50,
+        };
+        let reference = IntensityFrame {
+            values: &reference,
+            width: 100,
+            height: 100,
+        };
+        let config = CoregistrationConfig {
+            mode: CoregistrationMode::Automatic,
+            max_shift_px: 16,
+            shift_step_px: 4,
+            min_scale: 0.7,
+            max_scale: 1.0,
+            scale_steps: 7,
+            ..CoregistrationConfig::default()
+        };
+
+        let alignment = align_thermal(&thermal, Some(&reference), (100, 100), &config, None)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(alignment.method, AlignmentMethod::Automatic);
+        assert!(alignment.residual.unwrap() < 0.01, "{alignment:?}");
+        for (index, (fitted, expected)) in alignment.transform.iter().zip(expected).enumerate() {
+            let tolerance = if index % 3 == 2 { 0.75 } else { 0.02 };
+            assert!(
+                (fitted - expected).abs() < tolerance,
+                "{:?} vs {expected:?}",
+                alignment.transform
+            );
+        }
+    }
+
+    #[test]
+    fn poorly_matching_calibration_falls_back_to_gps_placement() {
+        let (thermal, reference, expected) = synthetic_pair();
+        let thermal = IntensityFrame {
+            values: &thermal,
+            width: 50,
+            height: 50,
+        };
+        let reference = IntensityFrame {
+            values: &reference,
+            width: 100,
+            height: 100,
+        };
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("rig.json");
+        let pairs = [(0.0, 0.0), (50.0, 0.0), (0.0, 50.0), (50.0, 50.0)]
+            .map(|(x, y)| ControlPointPair {
+                thermal: (x, y),
+                rgb: (1.6 * x + 14.0, 1.6 * y + 9.0),
+            })
+            .to_vec();
+        let calibration = RigCalibration::from_control_points("rig-1", pairs).unwrap();
+        assert!(calibration.residual_px < 1e-3);
+        calibration.save(&path).unwrap();
+        let mut config = CoregistrationConfig {
+            mode: CoregistrationMode::Calibration,
+            calibration_path: Some(path.clone()),
+            ..CoregistrationConfig::default()
+        };
+
+        let aligned = align_thermal(&thermal, Some(&reference), (100, 100), &config, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(aligned.method, AlignmentMethod::Calibration);
+        for (fitted, expected) in aligned.transform.iter().zip(expected) {
+            assert!((fitted - expected).abs() < 1e-3);
+        }
+
+        // A calibration from another rig leaves the frames uncorrelated.
+        let mut other_rig = calibration.clone();
+        other_rig.thermal_to_rgb = [1.0, 0.0, 50.0, 0.0, 1.0, 50.0];
+        other_rig.save(&path).unwrap();
+        config.max_residual = 0.2;
+        let fallback = align_thermal(&thermal, Some(&reference), (100, 100), &config, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(fallback.method, AlignmentMethod::GpsOnly);
+        assert_eq!(fallback.transform, [2.0, 0.0, 0.0, 0.0, 2.0, 0.0]);
+        assert!(fallback.residual.unwrap() > 0.2);
+        assert!(fallback.fallback_reason.is_some());
+    }
+
+    #[test]
+    fn control_points_parse_from_csv() {
+        let pairs =
+            parse_control_points_csv("thermal_x,thermal_y,rgb_x,rgb_y\n1,2,3,4\n\n5, 6, 7, 8\n")
+                .unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[1].rgb, (7.0, 8.0));
+        assert!(parse_control_points_csv("1,2,3\n").is_err());
+    }
+}
@@ -0,0 +1,413 @@
+//! Band-math overlays declared in configuration.
+//!
+//! A [`CustomOverlaySpec`] names an overlay and gives a formula over the
+//! bands of a multispectral image, e.g. `(b5 - b4) / (b5 + b4)`. At startup
+//! each spec becomes a [`FormulaOverlayProcessor`] registered with the
+//! [`OverlayEngine`](crate::OverlayEngine) under
+//! [`OverlayType::Custom`].
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+use crate::live::{band, reflectance};
+use crate::{
+    OverlayData, OverlayProcessor, OverlayType, SensorInput, SensorInputData, SensorOverlay,
+    SpatialBounds,
+};
+
+/// A custom overlay: `formula` evaluated per pixel over the named bands of
+/// a multispectral image.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CustomOverlaySpec {
+    pub name: String,
+    pub formula: String,
+}
+
+/// A parsed band-math expression: numbers, band names, `+ - * /`, unary
+/// minus and parentheses, with the usual precedence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Formula {
+    Number(f32),
+    Band(String),
+    Negate(Box<Formula>),
+    Binary(Box<Formula>, Operator, Box<Formula>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Formula {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut parser = Parser { text, position: 0 };
+        let formula = parser.expression()?;
+        parser.skip_whitespace();
+        if parser.position < text.len() {
+            bail!(
+                "unexpected '{}' at column {} of formula `{text}`",
+                &text[parser.position..],
+                parser.position + 1
+            );
+        }
+        Ok(formula)
+    }
+
+    /// Band names the formula reads, sorted.
+    pub fn bands(&self) -> BTreeSet<&str> {
+        let mut bands = BTreeSet::new();
+        self.collect_bands(&mut bands);
+        bands
+    }
+
+    fn collect_bands<'a>(&'a self, bands: &mut BTreeSet<&'a str>) {
+        match self {
+            Formula::Number(_) => {}
+            Formula::Band(name) => {
+                bands.insert(name);
+            }
+            Formula::Negate(operand) => operand.collect_bands(bands),
+            Formula::Binary(left, _, right) => {
+                left.collect_bands(bands);
+                right.collect_bands(bands);
+            }
+        }
+    }
+
+    /// Value at one pixel, reading each band through `band_value`. Division
+    /// by zero and missing bands give NaN, which overlays treat as no data.
+    pub fn evaluate(&self, band_value: &impl Fn(&str) -> Option<f32>) -> f32 {
+        match self {
+            Formula::Number(value) => *value,
+            Formula::Band(name) => band_value(name).unwrap_or(f32::NAN),
+            Formula::Negate(operand) => -operand.evaluate(band_value),
+            Formula::Binary(left, operator, right) => {
+                let (left, right) = (left.evaluate(band_value), right.evaluate(band_value));
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide if right == 0.0 => f32::NAN,
+                    Operator::Divide => left / right,
+                }
+            }
+        }
+    }
+
+    /// Evaluates the formula over equally sized band grids, pixel by pixel.
+    pub fn evaluate_grid(&self, bands: &HashMap<String, Vec<f32>>) -> Result<Vec<f32>> {
+        let mut pixel_count = None;
+        for name in self.bands() {
+            let values = bands
+                .get(name)
+                .with_context(|| format!("formula reads band '{name}', which the input lacks"))?;
+            match pixel_count {
+                None => pixel_count = Some(values.len()),
+                Some(count) if count != values.len() => bail!(
+                    "band '{name}' has {} pixels, other bands have {count}",
+                    values.len()
+                ),
+                Some(_) => {}
+            }
+        }
+        let pixel_count = pixel_count.context("formula reads no bands")?;
+        Ok((0..pixel_count)
+            .map(|pixel| self.evaluate(&|name| bands.get(name).map(|values| values[pixel])))
+            .collect())
+    }
+}
+
+/// Recursive-descent parser:
+/// `expression = term (('+' | '-') term)*`,
+/// `term = factor (('*' | '/') factor)*`,
+/// `factor = '-' factor | number | band | '(' expression ')'`.
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.text[self.position..].chars().next()
+    }
+
+    fn expression(&mut self) -> Result<Formula> {
+        let mut formula = self.term()?;
+        while let Some(operator) = match self.peek() {
+            Some('+') => Some(Operator::Add),
+            Some('-') => Some(Operator::Subtract),
+            _ => None,
+        } {
+            self.position += 1;
+            formula = Formula::Binary(Box::new(formula), operator, Box::new(self.term()?));
+        }
+        Ok(formula)
+    }
+
+    fn term(&mut self) -> Result<Formula> {
+        let mut formula = self.factor()?;
+        while let Some(operator) = match self.peek() {
+            Some('*') => Some(Operator::Multiply),
+            Some('/') => Some(Operator::Divide),
+            _ => None,
+        } {
+            self.position += 1;
+            formula = Formula::Binary(Box::new(formula), operator, Box::new(self.factor()?));
+        }
+        Ok(formula)
+    }
+
+    fn factor(&mut self) -> Result<Formula> {
+        let next = self.peek();
+        let start = self.position;
+        match next {
+            Some('-') => {
+                self.position += 1;
+                Ok(Formula::Negate(Box::new(self.factor()?)))
+            }
+            Some('(') => {
+                self.position += 1;
+                let formula = self.expression()?;
+                if self.peek() != Some(')') {
+                    bail!(
+                        "unclosed '(' at column {} of formula `{}`",
+                        start + 1,
+                        self.text
+                    );
+                }
+                self.position += 1;
+                Ok(formula)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let token = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                token
+                    .parse()
+                    .map(Formula::Number)
+                    .with_context(|| format!("invalid number '{token}' in formula `{}`", self.text))
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => Ok(Formula::Band(
+                self.take_while(|c| c.is_ascii_alphanumeric() || c == '_')
+                    .to_string(),
+            )),
+            Some(c) => bail!(
+                "unexpected '{c}' at column {} of formula `{}`",
+                self.position + 1,
+                self.text
+            ),
+            None => bail!("formula `{}` ends early", self.text),
+        }
+    }
+
+    fn take_while(&mut self, accept: impl Fn(char) -> bool) -> &'a str {
+        let start = self.position;
+        let rest = &self.text[start..];
+        let end = rest.find(|c| !accept(c)).unwrap_or(rest.len());
+        self.position += end;
+        &self.text[start..self.position]
+    }
+}
+
+/// Builds a custom overlay from the first multispectral input that carries
+/// every band its formula reads. Bands are read as reflectance, 0 to 1.
+#[derive(Debug, Clone)]
+pub struct FormulaOverlayProcessor {
+    name: String,
+    source: String,
+    formula: Formula,
+}
+
+impl FormulaOverlayProcessor {
+    pub fn from_spec(spec: &CustomOverlaySpec) -> Result<Self> {
+        if spec.name.trim().is_empty() {
+            bail!("custom overlay needs a name");
+        }
+        let formula = Formula::parse(&spec.formula)
+            .with_context(|| format!("custom overlay '{}'", spec.name))?;
+        if formula.bands().is_empty() {
+            bail!(
+                "custom overlay '{}' formula `{}` reads no bands",
+                spec.name,
+                spec.formula
+            );
+        }
+        Ok(Self {
+            name: spec.name.clone(),
+            source: spec.formula.clone(),
+            formula,
+        })
+    }
+
+    pub fn formula(&self) -> &Formula {
+        &self.formula
+    }
+}
+
+impl OverlayProcessor for FormulaOverlayProcessor {
+    fn process(&self, inputs: &[SensorInput]) -> Result<SensorOverlay> {
+        let (input, bands) = inputs
+            .iter()
+            .find_map(|input| match &input.data {
+                SensorInputData::MultispectralImage { bands, .. }
+                    if self
+                        .formula
+                        .bands()
+                        .iter()
+                        .all(|name| band(bands, name).is_some()) =>
+                {
+                    Some((input, bands))
+                }
+                _ => None,
+            })
+            .with_context(|| {
+                format!(
+                    "no multispectral input has the bands {:?} for custom overlay '{}'",
+                    self.formula.bands(),
+                    self.name
+                )
+            })?;
+
+        let mut grids = HashMap::new();
+        let mut size = None;
+        for name in self.formula.bands() {
+            let image = band(bands, name).expect("checked above");
+            if size.is_some_and(|size| size != (image.width, image.height)) {
+                bail!("bands of custom overlay '{}' differ in size", self.name);
+            }
+            size = Some((image.width, image.height));
+            grids.insert(name.to_string(), reflectance(image)?);
+        }
+        let (width, height) = size.expect("formula reads at least one band");
+        let values = self.formula.evaluate_grid(&grids)?;
+        let (min_value, max_value) = values
+            .iter()
+            .filter(|value| value.is_finite())
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| {
+                (min.min(value), max.max(value))
+            });
+
+        Ok(SensorOverlay {
+            id: Uuid::new_v4(),
+            overlay_type: self.get_overlay_type(),
+            timestamp: Utc::now(),
+            spatial_bounds: SpatialBounds::new(
+                input.position.x,
+                input.position.y,
+                input.position.x,
+                input.position.y,
+            ),
+            resolution: (width, height),
+            data: OverlayData::Grid {
+                width,
+                height,
+                values,
+                min_value,
+                max_value,
+            },
+            metadata: HashMap::from([("formula".to_string(), self.source.clone())]),
+        })
+    }
+
+    fn can_process(&self, sensor_type: &str) -> bool {
+        sensor_type == "multispectral"
+    }
+
+    fn get_overlay_type(&self) -> OverlayType {
+        OverlayType::Custom(self.name.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ImageData, MultispectralCalibration};
+    use nalgebra::{Point3, Vector3};
+
+    fn band_image(values: &[u8]) -> ImageData {
+        ImageData {
+            width: 2,
+            height: 2,
+            channels: 1,
+            pixel_data: values.to_vec(),
+            format: "u8".to_string(),
+        }
+    }
+
+    #[test]
+    fn formula_respects_precedence_and_parentheses() {
+        let formula = Formula::parse("(b5 - b4) / (b5 + b4) * 2 + -1").unwrap();
+        assert_eq!(formula.bands(), BTreeSet::from(["b4", "b5"]));
+        let value = formula.evaluate(&|name| match name {
+            "b5" => Some(0.6),
+            "b4" => Some(0.2),
+            _ => None,
+        });
+        assert!((value - 0.0).abs() < 1e-6, "{value}");
+
+        assert!(Formula::parse("(b5 - b4").is_err());
+        assert!(Formula::parse("b5 $ b4").is_err());
+        assert!(Formula::parse("b5 -").is_err());
+    }
+
+    #[test]
+    fn formula_processor_evaluates_sample_bands() {
+        let processor = FormulaOverlayProcessor::from_spec(&CustomOverlaySpec {
+            name: "ndre".to_string(),
+            formula: "(b5 - b4) / (b5 + b4)".to_string(),
+        })
+        .unwrap();
+        let input = SensorInput {
+            sensor_id: "ms-1".to_string(),
+            sensor_type: "multispectral".to_string(),
+            timestamp: Utc::now(),
+            position: Point3::new(-96.0, 41.0, 120.0),
+            orientation: Vector3::zeros(),
+            data: SensorInputData::MultispectralImage {
+                bands: HashMap::from([
+                    ("b4".to_string(), band_image(&[51, 102, 0, 255])),
+                    ("B5".to_string(), band_image(&[153, 102, 0, 0])),
+                ]),
+                calibration: MultispectralCalibration {
+                    dark_current: HashMap::new(),
+                    gain: HashMap::new(),
+                    reflectance_panel: HashMap::new(),
+                },
+            },
+        };
+
+        let overlay = processor.process(&[input]).unwrap();
+
+        assert_eq!(
+            overlay.overlay_type,
+            OverlayType::Custom("ndre".to_string())
+        );
+        assert_eq!(overlay.resolution, (2, 2));
+        let OverlayData::Grid {
+            values,
+            min_value,
+            max_value,
+            ..
+        } = overlay.data
+        else {
+            panic!("expected a grid overlay");
+        };
+        assert!((values[0] - 0.5).abs() < 1e-6);
+        assert_eq!(values[1], 0.0);
+        assert!(values[2].is_nan(), "0 / 0 is no data");
+        assert!((values[3] + 1.0).abs() < 1e-6);
+        assert_eq!((min_value, max_value), (-1.0, values[0]));
+        assert_eq!(overlay.metadata["formula"], "(b5 - b4) / (b5 + b4)");
+    }
+}
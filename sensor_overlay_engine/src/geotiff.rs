@@ -0,0 +1,714 @@
+use crate::SpatialBounds;
+use anyhow::{ensure, Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tiff::encoder::TiffEncoder;
+use tiff::tags::Tag;
+
+/// Value written for cells a band has no data for.
+pub const GEOTIFF_NODATA: f32 = -9999.0;
+
+/// GDAL's private tag for per-band metadata (an XML document).
+const GDAL_METADATA_TAG: u16 = 42112;
+
+const GT_MODEL_TYPE_GEO_KEY: u16 = 1024;
+const GT_RASTER_TYPE_GEO_KEY: u16 = 1025;
+const GEOGRAPHIC_TYPE_GEO_KEY: u16 = 2048;
+const PROJECTED_CS_TYPE_GEO_KEY: u16 = 3072;
+const MODEL_TYPE_PROJECTED: u16 = 1;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+const RASTER_PIXEL_IS_AREA: u16 = 1;
+
+/// One float32 band of a GeoTIFF, row-major with row 0 at the north edge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoTiffBand {
+    pub description: String,
+    pub nodata: f32,
+    pub values: Vec<f32>,
+}
+
+/// Default tile edge of cloud-optimized GeoTIFFs, in pixels.
+pub const COG_TILE_SIZE: u32 = 256;
+
+/// TIFF `NewSubfileType` of a reduced-resolution copy of the image.
+const SUBFILE_REDUCED_RESOLUTION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeoTiffOptions {
+    /// Write a cloud-optimized GeoTIFF: square tiles and power-of-two
+    /// overviews, with every IFD ahead of the pixel data so a client can
+    /// read the layout with one range request.
+    pub cloud_optimized: bool,
+    /// Tile edge for cloud-optimized output; a multiple of 16.
+    pub tile_size: u32,
+}
+
+impl Default for GeoTiffOptions {
+    fn default() -> Self {
+        Self {
+            cloud_optimized: false,
+            tile_size: COG_TILE_SIZE,
+        }
+    }
+}
+
+impl GeoTiffOptions {
+    pub fn cloud_optimized() -> Self {
+        Self {
+            cloud_optimized: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Writes `bands` as one uncompressed float32 GeoTIFF covering `bounds` in
+/// `epsg`, planar configuration 2: one strip per band, or tiles and
+/// overviews per band when `options` asks for a cloud-optimized file. Band
+/// descriptions and nodata values go into the GDAL metadata tag; the GDAL
+/// nodata tag only holds one value, so it is written when every band agrees
+/// on it. EPSG codes 4000-4999 are written as geographic CRSs, everything
+/// else as projected. Returns the GDAL-ordered geotransform.
+pub fn write_geotiff(
+    path: &Path,
+    width: u32,
+    height: u32,
+    bands: &[GeoTiffBand],
+    bounds: &SpatialBounds,
+    epsg: u16,
+    options: &GeoTiffOptions,
+) -> Result<[f64; 6]> {
+    ensure!(!bands.is_empty(), "GeoTIFF needs at least one band");
+    ensure!(width > 0 && height > 0, "GeoTIFF raster is empty");
+    ensure!(
+        !options.cloud_optimized || (options.tile_size > 0 && options.tile_size.is_multiple_of(16)),
+        "GeoTIFF tile size {} is not a positive multiple of 16",
+        options.tile_size
+    );
+    ensure!(
+        bounds.max_x > bounds.min_x && bounds.max_y > bounds.min_y,
+        "GeoTIFF bounds {bounds:?} have no area"
+    );
+    let cell_count = width as usize * height as usize;
+    for band in bands {
+        ensure!(
+            band.values.len() == cell_count,
+            "band '{}' has {} values for a {width}x{height} raster",
+            band.description,
+            band.values.len()
+        );
+    }
+
+    let pixel_width = (bounds.max_x - bounds.min_x) / f64::from(width);
+    let pixel_height = (bounds.max_y - bounds.min_y) / f64::from(height);
+    let geo_keys = geo_key_directory(epsg);
+    let geo_tags = GeoTags {
+        pixel_scale: [pixel_width, pixel_height, 0.0],
+        tiepoint: [0.0, 0.0, 0.0, bounds.min_x, bounds.max_y, 0.0],
+        geo_keys: &geo_keys,
+    };
+    if options.cloud_optimized {
+        write_cloud_optimized(path, width, height, bands, &geo_tags, options.tile_size)?;
+    } else {
+        write_stripped(path, width, height, bands, &geo_tags)?;
+    }
+
+    Ok([
+        bounds.min_x,
+        pixel_width,
+        0.0,
+        bounds.max_y,
+        0.0,
+        -pixel_height,
+    ])
+}
+
+/// Georeferencing tags shared by every layout.
+struct GeoTags<'a> {
+    pixel_scale: [f64; 3],
+    tiepoint: [f64; 6],
+    geo_keys: &'a [u16],
+}
+
+/// EPSG codes 4000-4999 are geographic CRSs, everything else projected.
+fn geo_key_directory(epsg: u16) -> Vec<u16> {
+    let geographic = (4000..5000).contains(&epsg);
+    vec![
+        1,
+        1,
+        0,
+        3,
+        GT_MODEL_TYPE_GEO_KEY,
+        0,
+        1,
+        if geographic {
+            MODEL_TYPE_GEOGRAPHIC
+        } else {
+            MODEL_TYPE_PROJECTED
+        },
+        GT_RASTER_TYPE_GEO_KEY,
+        0,
+        1,
+        RASTER_PIXEL_IS_AREA,
+        if geographic {
+            GEOGRAPHIC_TYPE_GEO_KEY
+        } else {
+            PROJECTED_CS_TYPE_GEO_KEY
+        },
+        0,
+        1,
+        epsg,
+    ]
+}
+
+/// One strip per band (planar configuration 2).
+fn write_stripped(
+    path: &Path,
+    width: u32,
+    height: u32,
+    bands: &[GeoTiffBand],
+    geo_tags: &GeoTags,
+) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut encoder = TiffEncoder::new(BufWriter::new(file))?;
+    let mut directory = encoder.new_directory()?;
+    let mut strip_offsets = Vec::with_capacity(bands.len());
+    let mut strip_byte_counts = Vec::with_capacity(bands.len());
+    for band in bands {
+        let offset = directory.write_data(band.values.as_slice())?;
+        strip_offsets.push(u32::try_from(offset).context("GeoTIFF exceeds 4 GiB")?);
+        strip_byte_counts.push((band.values.len() * std::mem::size_of::<f32>()) as u32);
+    }
+
+    directory.write_tag(Tag::ImageWidth, width)?;
+    directory.write_tag(Tag::ImageLength, height)?;
+    directory.write_tag(Tag::BitsPerSample, vec![32u16; bands.len()].as_slice())?;
+    directory.write_tag(Tag::Compression, 1u16)?;
+    directory.write_tag(Tag::PhotometricInterpretation, 1u16)?;
+    directory.write_tag(Tag::StripOffsets, strip_offsets.as_slice())?;
+    directory.write_tag(Tag::SamplesPerPixel, bands.len() as u16)?;
+    directory.write_tag(Tag::RowsPerStrip, height)?;
+    directory.write_tag(Tag::StripByteCounts, strip_byte_counts.as_slice())?;
+    directory.write_tag(Tag::PlanarConfiguration, 2u16)?;
+    if bands.len() > 1 {
+        directory.write_tag(Tag::ExtraSamples, vec![0u16; bands.len() - 1].as_slice())?;
+    }
+    directory.write_tag(Tag::SampleFormat, vec![3u16; bands.len()].as_slice())?;
+    directory.write_tag(Tag::ModelPixelScaleTag, geo_tags.pixel_scale.as_slice())?;
+    directory.write_tag(Tag::ModelTiepointTag, geo_tags.tiepoint.as_slice())?;
+    directory.write_tag(Tag::GeoKeyDirectoryTag, geo_tags.geo_keys)?;
+    directory.write_tag(
+        Tag::Unknown(GDAL_METADATA_TAG),
+        gdal_metadata(bands).as_str(),
+    )?;
+    if bands.iter().all(|band| band.nodata == bands[0].nodata) {
+        directory.write_tag(Tag::GdalNodata, bands[0].nodata.to_string().as_str())?;
+    }
+    directory
+        .finish()
+        .with_context(|| format!("failed to finish GeoTIFF {}", path.display()))
+}
+
+/// A TIFF field value, in the types this writer uses.
+enum FieldValue {
+    Ascii(String),
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Double(Vec<f64>),
+}
+
+impl FieldValue {
+    fn type_code(&self) -> u16 {
+        match self {
+            FieldValue::Ascii(_) => 2,
+            FieldValue::Short(_) => 3,
+            FieldValue::Long(_) => 4,
+            FieldValue::Double(_) => 12,
+        }
+    }
+
+    fn count(&self) -> u32 {
+        match self {
+            FieldValue::Ascii(text) => text.len() as u32 + 1,
+            FieldValue::Short(values) => values.len() as u32,
+            FieldValue::Long(values) => values.len() as u32,
+            FieldValue::Double(values) => values.len() as u32,
+        }
+    }
+
+    fn to_le_bytes(&self) -> Vec<u8> {
+        match self {
+            FieldValue::Ascii(text) => text.bytes().chain([0]).collect(),
+            FieldValue::Short(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            FieldValue::Long(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            FieldValue::Double(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        }
+    }
+}
+
+/// One image file directory: its fields, sorted by tag when serialized.
+struct Ifd(Vec<(u16, FieldValue)>);
+
+impl Ifd {
+    /// Bytes taken by the directory and the values that do not fit inline,
+    /// each padded to a word boundary.
+    fn len(&self) -> u64 {
+        let overflow: u64 = self
+            .0
+            .iter()
+            .map(|(_, value)| value.to_le_bytes().len() as u64)
+            .filter(|&len| len > 4)
+            .map(|len| len + len % 2)
+            .sum();
+        2 + 12 * self.0.len() as u64 + 4 + overflow
+    }
+
+    /// Serializes the directory as if written at `offset`, linking to the
+    /// directory at `next` (0 for the last).
+    fn serialize(&self, offset: u64, next: u32) -> Result<Vec<u8>> {
+        let mut fields = self.0.iter().collect::<Vec<_>>();
+        fields.sort_by_key(|(tag, _)| *tag);
+        let mut entries = Vec::with_capacity(2 + 12 * self.0.len() + 4);
+        let mut overflow = Vec::new();
+        let overflow_start = offset + 2 + 12 * self.0.len() as u64 + 4;
+        entries.extend_from_slice(&(self.0.len() as u16).to_le_bytes());
+        for (tag, value) in fields {
+            entries.extend_from_slice(&tag.to_le_bytes());
+            entries.extend_from_slice(&value.type_code().to_le_bytes());
+            entries.extend_from_slice(&value.count().to_le_bytes());
+            let mut bytes = value.to_le_bytes();
+            if bytes.len() <= 4 {
+                bytes.resize(4, 0);
+                entries.extend_from_slice(&bytes);
+            } else {
+                let at = u32::try_from(overflow_start + overflow.len() as u64)
+                    .context("GeoTIFF exceeds 4 GiB")?;
+                entries.extend_from_slice(&at.to_le_bytes());
+                if bytes.len() % 2 == 1 {
+                    bytes.push(0);
+                }
+                overflow.extend_from_slice(&bytes);
+            }
+        }
+        entries.extend_from_slice(&next.to_le_bytes());
+        entries.extend_from_slice(&overflow);
+        Ok(entries)
+    }
+}
+
+/// The raster at one resolution, a value grid per band.
+struct Level {
+    width: u32,
+    height: u32,
+    bands: Vec<Vec<f32>>,
+}
+
+impl Level {
+    /// Halves the resolution, averaging each 2x2 block over its cells that
+    /// are not nodata.
+    fn downsample(&self, nodata: &[f32]) -> Level {
+        let width = self.width.div_ceil(2);
+        let height = self.height.div_ceil(2);
+        let bands = self
+            .bands
+            .iter()
+            .zip(nodata)
+            .map(|(values, &nodata)| {
+                let mut reduced = Vec::with_capacity(width as usize * height as usize);
+                for row in 0..height {
+                    for column in 0..width {
+                        let (mut sum, mut count) = (0.0f64, 0u32);
+                        for y in (2 * row)..(2 * row + 2).min(self.height) {
+                            for x in (2 * column)..(2 * column + 2).min(self.width) {
+                                let value = values[(y * self.width + x) as usize];
+                                if value.is_finite() && value != nodata {
+                                    sum += f64::from(value);
+                                    count += 1;
+                                }
+                            }
+                        }
+                        reduced.push(if count == 0 {
+                            nodata
+                        } else {
+                            (sum / f64::from(count)) as f32
+                        });
+                    }
+                }
+                reduced
+            })
+            .collect();
+        Level {
+            width,
+            height,
+            bands,
+        }
+    }
+
+    fn tiles_across(&self, tile_size: u32) -> u32 {
+        self.width.div_ceil(tile_size)
+    }
+
+    fn tiles_down(&self, tile_size: u32) -> u32 {
+        self.height.div_ceil(tile_size)
+    }
+
+    /// Writes every tile, band by band and row-major within a band, padding
+    /// the right and bottom edges with nodata.
+    fn write_tiles(&self, out: &mut impl Write, tile_size: u32, nodata: &[f32]) -> Result<()> {
+        let mut tile = Vec::with_capacity(tile_size as usize * tile_size as usize * 4);
+        for (values, &nodata) in self.bands.iter().zip(nodata) {
+            for tile_row in 0..self.tiles_down(tile_size) {
+                for tile_column in 0..self.tiles_across(tile_size) {
+                    tile.clear();
+                    for y in tile_row * tile_size..(tile_row + 1) * tile_size {
+                        for x in tile_column * tile_size..(tile_column + 1) * tile_size {
+                            let value = if x < self.width && y < self.height {
+                                values[(y * self.width + x) as usize]
+                            } else {
+                                nodata
+                            };
+                            tile.extend_from_slice(&value.to_le_bytes());
+                        }
+                    }
+                    out.write_all(&tile)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes a cloud-optimized layout: the header, then the IFDs of the full
+/// image and of each overview (halving until the image fits in one tile),
+/// then the overview tiles from the smallest up, then the full image's
+/// tiles. Georeferencing and band metadata go on the full image only, as
+/// GDAL reads them from there.
+fn write_cloud_optimized(
+    path: &Path,
+    width: u32,
+    height: u32,
+    bands: &[GeoTiffBand],
+    geo_tags: &GeoTags,
+    tile_size: u32,
+) -> Result<()> {
+    let nodata: Vec<f32> = bands.iter().map(|band| band.nodata).collect();
+    let mut levels = vec![Level {
+        width,
+        height,
+        bands: bands.iter().map(|band| band.values.clone()).collect(),
+    }];
+    while let Some(last) = levels
+        .last()
+        .filter(|last| last.width > tile_size || last.height > tile_size)
+    {
+        let reduced = last.downsample(&nodata);
+        levels.push(reduced);
+    }
+
+    let band_count = bands.len();
+    let tile_bytes = u32::try_from(u64::from(tile_size) * u64::from(tile_size) * 4)
+        .context("GeoTIFF tile exceeds 4 GiB")?;
+    let shared_nodata = bands
+        .iter()
+        .all(|band| band.nodata == bands[0].nodata)
+        .then(|| bands[0].nodata.to_string());
+    let mut ifds: Vec<Ifd> = levels
+        .iter()
+        .enumerate()
+        .map(|(index, level)| {
+            let tile_count = level.tiles_across(tile_size) as usize
+                * level.tiles_down(tile_size) as usize
+                * band_count;
+            let mut fields = vec![
+                (
+                    Tag::ImageWidth.to_u16(),
+                    FieldValue::Long(vec![level.width]),
+                ),
+                (
+                    Tag::ImageLength.to_u16(),
+                    FieldValue::Long(vec![level.height]),
+                ),
+                (
+                    Tag::BitsPerSample.to_u16(),
+                    FieldValue::Short(vec![32; band_count]),
+                ),
+                (Tag::Compression.to_u16(), FieldValue::Short(vec![1])),
+                (
+                    Tag::PhotometricInterpretation.to_u16(),
+                    FieldValue::Short(vec![1]),
+                ),
+                (
+                    Tag::SamplesPerPixel.to_u16(),
+                    FieldValue::Short(vec![band_count as u16]),
+                ),
+                (
+                    Tag::PlanarConfiguration.to_u16(),
+                    FieldValue::Short(vec![2]),
+                ),
+                (Tag::TileWidth.to_u16(), FieldValue::Long(vec![tile_size])),
+                (Tag::TileLength.to_u16(), FieldValue::Long(vec![tile_size])),
+                (
+                    Tag::TileOffsets.to_u16(),
+                    FieldValue::Long(vec![0; tile_count]),
+                ),
+                (
+                    Tag::TileByteCounts.to_u16(),
+                    FieldValue::Long(vec![tile_bytes; tile_count]),
+                ),
+                (
+                    Tag::SampleFormat.to_u16(),
+                    FieldValue::Short(vec![3; band_count]),
+                ),
+            ];
+            if band_count > 1 {
+                fields.push((
+                    Tag::ExtraSamples.to_u16(),
+                    FieldValue::Short(vec![0; band_count - 1]),
+                ));
+            }
+            if index == 0 {
+                fields.extend([
+                    (
+                        Tag::ModelPixelScaleTag.to_u16(),
+                        FieldValue::Double(geo_tags.pixel_scale.to_vec()),
+                    ),
+                    (
+                        Tag::ModelTiepointTag.to_u16(),
+                        FieldValue::Double(geo_tags.tiepoint.to_vec()),
+                    ),
+                    (
+                        Tag::GeoKeyDirectoryTag.to_u16(),
+                        FieldValue::Short(geo_tags.geo_keys.to_vec()),
+                    ),
+                    (GDAL_METADATA_TAG, FieldValue::Ascii(gdal_metadata(bands))),
+                ]);
+            } else {
+                fields.push((
+                    Tag::NewSubfileType.to_u16(),
+                    FieldValue::Long(vec![SUBFILE_REDUCED_RESOLUTION]),
+                ));
+            }
+            if let Some(nodata) = &shared_nodata {
+                fields.push((Tag::GdalNodata.to_u16(), FieldValue::Ascii(nodata.clone())));
+            }
+            Ifd(fields)
+        })
+        .collect();
+
+    // The IFD sizes do not depend on the tile offsets, so the whole layout
+    // can be fixed before anything is written.
+    let mut ifd_offsets = Vec::with_capacity(ifds.len());
+    let mut offset = 8u64;
+    for ifd in &ifds {
+        ifd_offsets.push(offset);
+        offset += ifd.len();
+    }
+    for (level, ifd) in levels.iter().zip(&mut ifds).rev() {
+        let tile_count = level.tiles_across(tile_size) as usize
+            * level.tiles_down(tile_size) as usize
+            * band_count;
+        let tile_offsets = (0..tile_count as u64)
+            .map(|tile| u32::try_from(offset + tile * u64::from(tile_bytes)))
+            .collect::<Result<Vec<_>, _>>()
+            .context("GeoTIFF exceeds 4 GiB")?;
+        offset += tile_count as u64 * u64::from(tile_bytes);
+        for (tag, value) in &mut ifd.0 {
+            if *tag == Tag::TileOffsets.to_u16() {
+                *value = FieldValue::Long(tile_offsets.clone());
+            }
+        }
+    }
+    ensure!(offset <= u64::from(u32::MAX), "GeoTIFF exceeds 4 GiB");
+
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    out.write_all(b"II")?;
+    out.write_all(&42u16.to_le_bytes())?;
+    out.write_all(&8u32.to_le_bytes())?;
+    for (index, ifd) in ifds.iter().enumerate() {
+        let next = ifd_offsets.get(index + 1).map_or(0, |&next| next as u32);
+        out.write_all(&ifd.serialize(ifd_offsets[index], next)?)?;
+    }
+    for level in levels.iter().rev() {
+        level.write_tiles(&mut out, tile_size, &nodata)?;
+    }
+    out.flush()
+        .with_context(|| format!("failed to finish GeoTIFF {}", path.display()))
+}
+
+fn gdal_metadata(bands: &[GeoTiffBand]) -> String {
+    let mut xml = String::from("<GDALMetadata>");
+    for (sample, band) in bands.iter().enumerate() {
+        xml.push_str(&format!(
+            "<Item name=\"DESCRIPTION\" sample=\"{sample}\" role=\"description\">{}</Item>\
+             <Item name=\"NODATA\" sample=\"{sample}\">{}</Item>",
+            xml_escape(&band.description),
+            band.nodata
+        ));
+    }
+    xml.push_str("</GDALMetadata>");
+    xml
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiff::decoder::Decoder;
+
+    #[test]
+    fn geotiff_carries_georeferencing_and_band_metadata() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("bands.tif");
+        let bounds = SpatialBounds::new(500_000.0, 4_400_000.0, 500_040.0, 4_400_020.0);
+        let bands = [
+            GeoTiffBand {
+                description: "NDVI".to_string(),
+                nodata: GEOTIFF_NODATA,
+                values: vec![0.5; 8],
+            },
+            GeoTiffBand {
+                description: "Canopy <2 m".to_string(),
+                nodata: GEOTIFF_NODATA,
+                values: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, GEOTIFF_NODATA],
+            },
+        ];
+
+        let transform = write_geotiff(
+            &path,
+            4,
+            2,
+            &bands,
+            &bounds,
+            32613,
+            &GeoTiffOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(transform, [500_000.0, 10.0, 0.0, 4_400_020.0, 0.0, -10.0]);
+        let mut decoder = Decoder::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(decoder.dimensions().unwrap(), (4, 2));
+        assert_eq!(decoder.get_tag_u32(Tag::SamplesPerPixel).unwrap(), 2);
+        assert_eq!(
+            decoder.get_tag_f64_vec(Tag::ModelTiepointTag).unwrap(),
+            vec![0.0, 0.0, 0.0, 500_000.0, 4_400_020.0, 0.0]
+        );
+        let geo_keys = decoder.get_tag_u16_vec(Tag::GeoKeyDirectoryTag).unwrap();
+        assert_eq!(&geo_keys[12..], &[PROJECTED_CS_TYPE_GEO_KEY, 0, 1, 32613]);
+        let metadata = decoder
+            .get_tag_ascii_string(Tag::Unknown(GDAL_METADATA_TAG))
+            .unwrap();
+        assert!(metadata.contains("sample=\"1\" role=\"description\">Canopy &lt;2 m<"));
+        assert_eq!(
+            decoder.get_tag_ascii_string(Tag::GdalNodata).unwrap(),
+            "-9999"
+        );
+    }
+
+    /// Offsets of every IFD in the chain, read straight from the file.
+    fn ifd_offsets(bytes: &[u8]) -> Vec<usize> {
+        let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize;
+        let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+        let mut offsets = Vec::new();
+        let mut next = u32_at(4);
+        while next != 0 {
+            offsets.push(next);
+            next = u32_at(next + 2 + 12 * u16_at(next));
+        }
+        offsets
+    }
+
+    #[test]
+    fn cloud_optimized_geotiff_is_tiled_with_overviews_ahead_of_the_data() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("cog.tif");
+        let bounds = SpatialBounds::new(500_000.0, 4_400_000.0, 500_200.0, 4_400_100.0);
+        let gradient = (0..100)
+            .flat_map(|row| (0..200).map(move |column| (row * 200 + column) as f32))
+            .collect::<Vec<_>>();
+        let bands = [
+            GeoTiffBand {
+                description: "Gradient".to_string(),
+                nodata: GEOTIFF_NODATA,
+                values: gradient,
+            },
+            GeoTiffBand {
+                description: "Flat".to_string(),
+                nodata: GEOTIFF_NODATA,
+                values: vec![2.5; 200 * 100],
+            },
+        ];
+        let options = GeoTiffOptions {
+            cloud_optimized: true,
+            tile_size: 64,
+        };
+
+        write_geotiff(&path, 200, 100, &bands, &bounds, 32613, &options).unwrap();
+
+        // 200x100 halves to 100x50 and then 50x25, which fits in one tile.
+        let mut decoder = Decoder::new(File::open(&path).unwrap()).unwrap();
+        let mut levels = Vec::new();
+        let mut tile_offsets = Vec::new();
+        loop {
+            let subfile = decoder.get_tag_u32(Tag::NewSubfileType).unwrap_or(0);
+            levels.push((
+                decoder.dimensions().unwrap(),
+                subfile,
+                decoder.get_tag_u32(Tag::TileWidth).unwrap(),
+                decoder.get_tag_u32(Tag::TileLength).unwrap(),
+            ));
+            tile_offsets.push(decoder.get_tag_u32_vec(Tag::TileOffsets).unwrap());
+            if !decoder.more_images() {
+                break;
+            }
+            decoder.next_image().unwrap();
+        }
+        assert_eq!(
+            levels,
+            vec![
+                ((200, 100), 0, 64, 64),
+                ((100, 50), SUBFILE_REDUCED_RESOLUTION, 64, 64),
+                ((50, 25), SUBFILE_REDUCED_RESOLUTION, 64, 64),
+            ]
+        );
+        let overview_count = levels.len() - 1;
+        assert_eq!(overview_count, 2);
+        // 4x2 tiles per band at full resolution, 2x1 and 1x1 in the overviews.
+        assert_eq!(
+            tile_offsets.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![16, 4, 2]
+        );
+
+        // Every IFD precedes all tile data, and the overview tiles come
+        // before the full image's, smallest overview first.
+        let bytes = std::fs::read(&path).unwrap();
+        let ifds = ifd_offsets(&bytes);
+        assert_eq!(ifds.len(), 3);
+        assert!(ifds.windows(2).all(|pair| pair[0] < pair[1]));
+        let first_tile = *tile_offsets.iter().flatten().min().unwrap() as usize;
+        assert!(ifds.iter().all(|&ifd| ifd < first_tile));
+        assert_eq!(tile_offsets[2][0] as usize, first_tile);
+        assert!(tile_offsets[2].last() < tile_offsets[1].first());
+        assert!(tile_offsets[1].last() < tile_offsets[0].first());
+        let tile_bytes = 64 * 64 * 4;
+        assert_eq!(bytes.len(), tile_offsets[0][15] as usize + tile_bytes);
+
+        let f32_at = |at: usize| f32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        // Second full-resolution tile of the first row, first pixel: (64, 0).
+        assert_eq!(f32_at(tile_offsets[0][1] as usize), 64.0);
+        // The right edge of the last gradient tile is padding.
+        assert_eq!(f32_at(tile_offsets[0][7] as usize + 63 * 4), GEOTIFF_NODATA);
+        // First overview cell averages full-resolution (0, 0), (1, 0),
+        // (0, 1) and (1, 1).
+        assert_eq!(f32_at(tile_offsets[1][0] as usize), 100.5);
+        assert_eq!(f32_at(tile_offsets[2][1] as usize), 2.5);
+    }
+}
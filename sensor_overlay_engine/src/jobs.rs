@@ -0,0 +1,165 @@
+use crate::{OverlayProcessor, ProcessingJob, SensorOverlay};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverlayJobStatus {
+    Queued,
+    Running,
+    Completed,
+    Cancelled,
+    Failed(String),
+}
+
+impl OverlayJobStatus {
+    fn is_finished(&self) -> bool {
+        !matches!(self, OverlayJobStatus::Queued | OverlayJobStatus::Running)
+    }
+}
+
+#[derive(Debug)]
+struct TrackedJob {
+    cancel: CancellationToken,
+    status: OverlayJobStatus,
+}
+
+/// Tracks overlay jobs and bounds how many run at once. Each job gets its own
+/// cancellation token; a job holds one worker slot only while its processor
+/// runs, so a job cancelled while waiting never takes one and a cancelled
+/// running job gives its slot back as soon as the processor notices. Clones
+/// share the same slots and jobs, so a clone can cancel jobs while the engine
+/// is busy running them.
+#[derive(Debug, Clone)]
+pub struct OverlayJobManager {
+    slots: Arc<Semaphore>,
+    worker_limit: usize,
+    jobs: Arc<Mutex<HashMap<Uuid, TrackedJob>>>,
+}
+
+impl OverlayJobManager {
+    pub fn new(worker_limit: usize) -> Self {
+        let worker_limit = worker_limit.max(1);
+        Self {
+            slots: Arc::new(Semaphore::new(worker_limit)),
+            worker_limit,
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn worker_limit(&self) -> usize {
+        self.worker_limit
+    }
+
+    /// Worker slots not held by a running processor.
+    pub fn available_slots(&self) -> usize {
+        self.slots.available_permits()
+    }
+
+    pub fn register(&self, job_id: Uuid) -> CancellationToken {
+        self.lock()
+            .entry(job_id)
+            .or_insert_with(|| TrackedJob {
+                cancel: CancellationToken::new(),
+                status: OverlayJobStatus::Queued,
+            })
+            .cancel
+            .clone()
+    }
+
+    /// Cancels a job that has not finished. Returns `false` for unknown or
+    /// finished jobs.
+    pub fn cancel(&self, job_id: &Uuid) -> bool {
+        let mut jobs = self.lock();
+        let Some(job) = jobs.get_mut(job_id) else {
+            return false;
+        };
+        if job.status.is_finished() {
+            return false;
+        }
+        job.cancel.cancel();
+        if job.status == OverlayJobStatus::Queued {
+            job.status = OverlayJobStatus::Cancelled;
+        }
+        true
+    }
+
+    pub fn status(&self, job_id: &Uuid) -> Option<OverlayJobStatus> {
+        self.lock().get(job_id).map(|job| job.status.clone())
+    }
+
+    /// Drops the bookkeeping of every finished job.
+    pub fn clear_finished(&self) {
+        self.lock().retain(|_, job| !job.status.is_finished());
+    }
+
+    /// Waits for a worker slot and runs `job` on the blocking pool. Returns
+    /// `None` when the job was cancelled before or while it ran; whatever a
+    /// cancelled processor produced is discarded.
+    pub(crate) async fn run(
+        &self,
+        job: ProcessingJob,
+        processor: Arc<dyn OverlayProcessor>,
+    ) -> Result<Option<SensorOverlay>> {
+        let job_id = job.id;
+        let cancel = self.register(job_id);
+        let permit = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => None,
+            permit = self.slots.clone().acquire_owned() => Some(permit?),
+        };
+        let Some(permit) = permit else {
+            self.set_status(job_id, OverlayJobStatus::Cancelled);
+            return Ok(None);
+        };
+        self.set_status(job_id, OverlayJobStatus::Running);
+
+        let worker_cancel = cancel.clone();
+        let outcome = tokio::task::spawn_blocking(move || {
+            let _slot = permit;
+            processor.process_cancellable(&job.inputs, &worker_cancel)
+        })
+        .await
+        .unwrap_or_else(|error| Err(anyhow::anyhow!("overlay worker panicked: {error}")));
+
+        match outcome {
+            _ if cancel.is_cancelled() => {
+                self.set_status(job_id, OverlayJobStatus::Cancelled);
+                Ok(None)
+            }
+            Ok(overlay) => {
+                self.set_status(job_id, OverlayJobStatus::Completed);
+                Ok(Some(overlay))
+            }
+            Err(error) => {
+                self.set_status(job_id, OverlayJobStatus::Failed(error.to_string()));
+                Err(error)
+            }
+        }
+    }
+
+    pub(crate) fn set_status(&self, job_id: Uuid, status: OverlayJobStatus) {
+        if let Some(job) = self.lock().get_mut(&job_id) {
+            job.status = status;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, TrackedJob>> {
+        self.jobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Fails once `cancel` has fired; processors call it between tiles and
+/// stages.
+pub fn ensure_not_cancelled(cancel: &CancellationToken) -> Result<()> {
+    if cancel.is_cancelled() {
+        anyhow::bail!("overlay job cancelled");
+    }
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use crate::LidarPoint;
+use anyhow::{Context, Result};
+use las::point::{Classification, Format};
+use las::{Builder, Point, Read as _, Reader, Transform, Vector, Write as _, Writer};
+use std::path::Path;
+
+/// Coordinate resolution of written files: millimetres.
+pub const LAS_COORDINATE_SCALE: f64 = 0.001;
+
+/// Loads every point of a `.las` or `.laz` file. Coordinates are narrowed to
+/// the `f32` of [`LidarPoint`], so georeferenced clouds should be shifted to a
+/// local origin before they need sub-centimetre precision; intensity keeps
+/// the raw 16-bit LAS value.
+pub fn read_las(path: &Path) -> Result<Vec<LidarPoint>> {
+    let mut reader = Reader::from_path(path)
+        .with_context(|| format!("failed to open LAS file {}", path.display()))?;
+    reader
+        .points()
+        .map(|point| {
+            let point =
+                point.with_context(|| format!("failed to read point from {}", path.display()))?;
+            Ok(LidarPoint {
+                x: point.x as f32,
+                y: point.y as f32,
+                z: point.z as f32,
+                intensity: f32::from(point.intensity),
+                return_number: point.return_number,
+                classification: u8::from(point.classification),
+            })
+        })
+        .collect()
+}
+
+/// Writes `points` as an uncompressed LAS 1.4 file (point format 6, which
+/// holds the full 0-255 classification range). Coordinates are stored at
+/// [`LAS_COORDINATE_SCALE`] relative to the cloud's minimum corner; intensity
+/// is rounded and clamped to 16 bits.
+pub fn write_las(path: &Path, points: &[LidarPoint]) -> Result<()> {
+    let origin = |coordinate: fn(&LidarPoint) -> f32| {
+        points
+            .iter()
+            .map(|point| f64::from(coordinate(point)))
+            .fold(f64::INFINITY, f64::min)
+            .floor()
+    };
+    let transform = |offset: f64| Transform {
+        scale: LAS_COORDINATE_SCALE,
+        offset: if offset.is_finite() { offset } else { 0.0 },
+    };
+
+    let mut builder = Builder::from((1, 4));
+    builder.point_format = Format::new(6)?;
+    builder.transforms = Vector {
+        x: transform(origin(|point| point.x)),
+        y: transform(origin(|point| point.y)),
+        z: transform(origin(|point| point.z)),
+    };
+    let header = builder.into_header()?;
+    let mut writer = Writer::from_path(path, header)
+        .with_context(|| format!("failed to create LAS file {}", path.display()))?;
+
+    for point in points {
+        writer.write(Point {
+            x: f64::from(point.x),
+            y: f64::from(point.y),
+            z: f64::from(point.z),
+            intensity: point.intensity.round().clamp(0.0, f32::from(u16::MAX)) as u16,
+            return_number: point.return_number,
+            number_of_returns: point.return_number,
+            classification: Classification::new(point.classification).with_context(|| {
+                format!(
+                    "classification {} cannot be written to LAS",
+                    point.classification
+                )
+            })?,
+            gps_time: Some(0.0),
+            ..Default::default()
+        })?;
+    }
+    writer
+        .close()
+        .with_context(|| format!("failed to finish LAS file {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_round_trip_through_a_las_file() {
+        let points = vec![
+            LidarPoint {
+                x: 12.345,
+                y: -7.5,
+                z: 101.25,
+                intensity: 812.0,
+                return_number: 1,
+                classification: 2,
+            },
+            LidarPoint {
+                x: 13.001,
+                y: -6.999,
+                z: 103.875,
+                intensity: 65_535.0,
+                return_number: 2,
+                classification: 5,
+            },
+            LidarPoint {
+                x: 14.5,
+                y: -8.25,
+                z: 99.0,
+                intensity: 0.0,
+                return_number: 1,
+                classification: 64,
+            },
+        ];
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("cloud.las");
+
+        write_las(&path, &points).unwrap();
+        let loaded = read_las(&path).unwrap();
+
+        assert_eq!(loaded.len(), points.len());
+        for (written, read) in points.iter().zip(&loaded) {
+            assert!((written.x - read.x).abs() < 1e-3, "{written:?} != {read:?}");
+            assert!((written.y - read.y).abs() < 1e-3, "{written:?} != {read:?}");
+            assert!((written.z - read.z).abs() < 1e-3, "{written:?} != {read:?}");
+            assert_eq!(written.intensity, read.intensity);
+            assert_eq!(written.return_number, read.return_number);
+            assert_eq!(written.classification, read.classification);
+        }
+    }
+}
@@ -4,19 +4,46 @@ use image::{ImageBuffer, Rgb, RgbImage, Rgba, RgbaImage};
 use nalgebra::{Point3, Vector3};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 use uuid::Uuid;
 
 pub mod composite;
+pub mod config;
+pub mod coregistration;
+pub mod formula;
+pub mod geotiff;
+pub mod jobs;
+pub mod las_io;
 pub mod lidar_overlay;
+pub mod live;
 pub mod ndvi;
 pub mod thermal;
 
-pub use composite::CompositeOverlayEngine;
+pub use composite::{
+    affine_from_control_points, compute_soil_moisture_index, invert_affine,
+    parse_moisture_samples_csv, read_moisture_samples_csv, warp_thermal_grid,
+    CompositeOverlayEngine, ControlPointPair, MoistureCalibration, MoistureSample,
+    NdviTemperatureGrid, SoilMoistureConfig, SoilMoistureProduct, TemperatureEdge,
+};
+pub use config::{ConfigFieldError, ConfigValidationError, OverlayEngineConfig, KNOWN_COLORMAPS};
+pub use coregistration::{
+    align_thermal, estimate_alignment, parse_control_points_csv, AlignmentMethod,
+    CoregistrationConfig, CoregistrationMode, RigCalibration, ThermalAlignment,
+};
+pub use formula::{CustomOverlaySpec, Formula, FormulaOverlayProcessor};
+pub use geotiff::{write_geotiff, GeoTiffBand, GeoTiffOptions, COG_TILE_SIZE, GEOTIFF_NODATA};
+pub use jobs::{ensure_not_cancelled, OverlayJobManager, OverlayJobStatus};
+pub use las_io::{read_las, write_las, LAS_COORDINATE_SCALE};
 pub use lidar_overlay::{
-    LidarOverlayProcessor, LidarProductOverlay, LidarRasterOverlayKind, LidarRasterOverlayProduct,
+    GroundFilterConfig, LidarClassificationResult, LidarOverlayProcessor, LidarPointClass,
+    LidarProductOverlay, LidarRasterOverlayKind, LidarRasterOverlayProduct,
+};
+pub use live::{
+    InputAccumulator, LiveClock, LiveOverlayService, MatchWindow, MatchedInputs, SystemLiveClock,
 };
 pub use ndvi::NdviProcessor;
 pub use thermal::ThermalProcessor;
+pub use tokio_util::sync::CancellationToken;
 
 /// Custom serializable RGB color type
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -60,6 +87,7 @@ pub enum OverlayType {
     Thermal,
     LidarElevation,
     LidarIntensity,
+    LidarClassification,
     Composite,
     Custom(String),
 }
@@ -172,13 +200,28 @@ pub struct CameraParameters {
 
 /// Main overlay processing engine
 pub struct OverlayEngine {
-    processors: HashMap<OverlayType, Box<dyn OverlayProcessor>>,
+    processors: HashMap<OverlayType, Arc<dyn OverlayProcessor>>,
     output_cache: HashMap<Uuid, SensorOverlay>,
     processing_queue: Vec<ProcessingJob>,
+    jobs: OverlayJobManager,
 }
 
 pub trait OverlayProcessor: Send + Sync {
     fn process(&self, inputs: &[SensorInput]) -> Result<SensorOverlay>;
+    /// Runs [`process`](Self::process) unless `cancel` fires first.
+    /// Processors that work in tiles or stages should override this and call
+    /// [`ensure_not_cancelled`] between them; the default can only check
+    /// before and after the whole run.
+    fn process_cancellable(
+        &self,
+        inputs: &[SensorInput],
+        cancel: &CancellationToken,
+    ) -> Result<SensorOverlay> {
+        ensure_not_cancelled(cancel)?;
+        let overlay = self.process(inputs)?;
+        ensure_not_cancelled(cancel)?;
+        Ok(overlay)
+    }
     fn can_process(&self, sensor_type: &str) -> bool;
     fn get_overlay_type(&self) -> OverlayType;
 }
@@ -194,20 +237,14 @@ pub struct ProcessingJob {
 
 impl OverlayEngine {
     pub fn new() -> Self {
-        let mut processors: HashMap<OverlayType, Box<dyn OverlayProcessor>> = HashMap::new();
+        let mut processors: HashMap<OverlayType, Arc<dyn OverlayProcessor>> = HashMap::new();
 
         // Create default configurations
         let ndvi_config = ndvi::NdviConfig {
             red_band_index: 0,
             nir_band_index: 1,
             output_format: "PNG".to_string(),
-            color_mapping: ndvi::ColorMapping {
-                low_vegetation: [255, 0, 0],
-                medium_vegetation: [255, 255, 0],
-                high_vegetation: [0, 255, 0],
-                water: [0, 0, 255],
-                soil: [139, 69, 19],
-            },
+            color_mapping: ndvi::ColorMapping::default(),
         };
 
         let thermal_config = thermal::ThermalConfig {
@@ -240,29 +277,54 @@ impl OverlayEngine {
             },
             occupancy_grid_resolution: 0.2,
             max_range: 100.0,
+            ground_filter: lidar_overlay::GroundFilterConfig::default(),
         };
 
         // Register default processors
-        processors.insert(OverlayType::NDVI, Box::new(NdviProcessor::new(ndvi_config)));
+        processors.insert(OverlayType::NDVI, Arc::new(NdviProcessor::new(ndvi_config)));
         processors.insert(
             OverlayType::Thermal,
-            Box::new(ThermalProcessor::new(thermal_config)),
+            Arc::new(ThermalProcessor::new(thermal_config)),
         );
         processors.insert(
             OverlayType::LidarElevation,
-            Box::new(LidarOverlayProcessor::new(lidar_config)),
+            Arc::new(LidarOverlayProcessor::new(lidar_config)),
         );
 
         Self {
             processors,
             output_cache: HashMap::new(),
             processing_queue: Vec::new(),
+            jobs: OverlayJobManager::new(
+                std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get),
+            ),
         }
     }
 
+    /// Caps how many jobs run at once; the default is one per CPU.
+    pub fn with_worker_limit(mut self, worker_limit: usize) -> Self {
+        self.jobs = OverlayJobManager::new(worker_limit);
+        self
+    }
+
+    /// Registers a [`FormulaOverlayProcessor`] for each spec, under
+    /// [`OverlayType::Custom`] with the spec's name.
+    pub fn with_custom_overlays(mut self, specs: &[CustomOverlaySpec]) -> Result<Self> {
+        for spec in specs {
+            self.register_processor(Box::new(FormulaOverlayProcessor::from_spec(spec)?));
+        }
+        Ok(self)
+    }
+
     pub fn register_processor(&mut self, processor: Box<dyn OverlayProcessor>) {
         let overlay_type = processor.get_overlay_type();
-        self.processors.insert(overlay_type, processor);
+        self.processors.insert(overlay_type, Arc::from(processor));
+    }
+
+    /// A handle sharing this engine's jobs, for cancelling or watching them
+    /// while the engine is busy processing.
+    pub fn job_manager(&self) -> OverlayJobManager {
+        self.jobs.clone()
     }
 
     pub async fn submit_job(
@@ -279,6 +341,7 @@ impl OverlayEngine {
         };
 
         let job_id = job.id;
+        self.jobs.register(job_id);
         self.processing_queue.push(job);
         self.processing_queue
             .sort_by(|a, b| b.priority.cmp(&a.priority));
@@ -286,33 +349,84 @@ impl OverlayEngine {
         Ok(job_id)
     }
 
+    /// Cancels a queued or running job. A queued job is dropped from the
+    /// queue along with its inputs; a running one stops at the processor's
+    /// next check and releases its worker slot. Neither produces an overlay.
+    pub fn cancel_job(&mut self, id: &Uuid) -> bool {
+        let cancelled = self.jobs.cancel(id);
+        self.processing_queue.retain(|job| job.id != *id);
+        cancelled
+    }
+
     pub async fn process_next_job(&mut self) -> Result<Option<SensorOverlay>> {
-        if let Some(job) = self.processing_queue.pop() {
-            if let Some(processor) = self.processors.get(&job.overlay_type) {
-                let overlay = processor.process(&job.inputs)?;
-                self.output_cache.insert(job.id, overlay.clone());
-                Ok(Some(overlay))
-            } else {
-                Err(anyhow::anyhow!(
-                    "No processor available for overlay type: {:?}",
-                    job.overlay_type
-                ))
+        while let Some(job) = self.processing_queue.pop() {
+            let job_id = job.id;
+            let processor = self.processor_for(&job)?;
+            if let Some(overlay) = self.jobs.run(job, processor).await? {
+                self.output_cache.insert(job_id, overlay.clone());
+                return Ok(Some(overlay));
             }
-        } else {
-            Ok(None)
         }
+        Ok(None)
     }
 
+    /// Runs every queued job on the worker pool, at most the worker limit at
+    /// a time. Cancelled jobs are left out of the results. If any job fails
+    /// the rest still run, their overlays are cached, and the first error is
+    /// returned.
     pub async fn process_all_pending(&mut self) -> Result<Vec<SensorOverlay>> {
-        let mut results = Vec::new();
+        let mut first_error = None;
+        let mut workers = Vec::new();
+
+        while let Some(job) = self.processing_queue.pop() {
+            let job_id = job.id;
+            match self.processor_for(&job) {
+                Ok(processor) => {
+                    let jobs = self.jobs.clone();
+                    workers.push((
+                        job_id,
+                        tokio::spawn(async move { jobs.run(job, processor).await }),
+                    ));
+                }
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
 
-        while !self.processing_queue.is_empty() {
-            if let Some(overlay) = self.process_next_job().await? {
-                results.push(overlay);
+        let mut results = Vec::new();
+        for (job_id, worker) in workers {
+            match worker.await.map_err(anyhow::Error::from).and_then(|r| r) {
+                Ok(Some(overlay)) => {
+                    self.output_cache.insert(job_id, overlay.clone());
+                    results.push(overlay);
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
             }
         }
 
-        Ok(results)
+        match first_error {
+            Some(error) => Err(error),
+            None => Ok(results),
+        }
+    }
+
+    fn processor_for(&self, job: &ProcessingJob) -> Result<Arc<dyn OverlayProcessor>> {
+        match self.processors.get(&job.overlay_type) {
+            Some(processor) => Ok(Arc::clone(processor)),
+            None => {
+                let error = format!(
+                    "No processor available for overlay type: {:?}",
+                    job.overlay_type
+                );
+                self.jobs
+                    .set_status(job.id, OverlayJobStatus::Failed(error.clone()));
+                Err(anyhow::anyhow!(error))
+            }
+        }
     }
 
     pub fn get_overlay(&self, id: &Uuid) -> Option<&SensorOverlay> {
@@ -325,6 +439,7 @@ impl OverlayEngine {
 
     pub fn clear_cache(&mut self) {
         self.output_cache.clear();
+        self.jobs.clear_finished();
     }
 
     pub fn get_queue_length(&self) -> usize {
@@ -344,6 +459,20 @@ impl SpatialBounds {
         }
     }
 
+    /// Smallest bounds enclosing every coordinate, or `None` when there are
+    /// none.
+    pub fn enclosing<'a>(coordinates: impl IntoIterator<Item = &'a Point3<f64>>) -> Option<Self> {
+        coordinates.into_iter().fold(None, |bounds, point| {
+            let bounds = bounds.unwrap_or_else(|| Self::new(point.x, point.y, point.x, point.y));
+            Some(Self::new(
+                bounds.min_x.min(point.x),
+                bounds.min_y.min(point.y),
+                bounds.max_x.max(point.x),
+                bounds.max_y.max(point.y),
+            ))
+        })
+    }
+
     pub fn with_elevation(mut self, min_z: f64, max_z: f64) -> Self {
         self.min_z = Some(min_z);
         self.max_z = Some(max_z);
@@ -368,6 +497,7 @@ impl Default for OverlayEngine {
 /// Utility functions for overlay processing
 pub mod utils {
     use super::*;
+    use shared::palette::{palette, Palette};
 
     #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
     pub struct OverlayValueRange {
@@ -428,19 +558,108 @@ pub mod utils {
         pub metadata: IdwInterpolationMetadata,
     }
 
+    /// Discrete classes for [`HeatmapOptions::class_breaks`]: values below
+    /// `breaks[0]` take `colors[0]`, values in `[breaks[i - 1], breaks[i])`
+    /// take `colors[i]`, and values at or above the last break take the last
+    /// colour, so there is one more colour than breaks.
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub struct HeatmapClassBreaks {
+        pub breaks: Vec<f32>,
+        pub colors: Vec<RgbColor>,
+    }
+
+    /// How the heatmap functions map values to colours. The default
+    /// reproduces [`create_heatmap_image`]: a continuous ramp over the data's
+    /// own finite min/max.
+    #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+    pub struct HeatmapOptions {
+        /// Fixed `(min, max)` the colormap spans; values outside it clamp to
+        /// the ends. Lets separately rendered maps share one scale.
+        pub value_range: Option<(f32, f32)>,
+        /// Value drawn at the middle of the colormap (white for `"rdbu"`),
+        /// with each side stretched over its own half of the range.
+        pub center: Option<f32>,
+        /// Colours by class instead of the colormap.
+        pub class_breaks: Option<HeatmapClassBreaks>,
+        /// Draws non-finite values fully transparent in
+        /// [`create_heatmap_image_rgba`]; otherwise they are opaque black.
+        pub transparent_nodata: bool,
+    }
+
     pub fn create_heatmap_image(
         values: &[f32],
         width: u32,
         height: u32,
         color_map: &str,
     ) -> Result<RgbImage> {
+        create_heatmap_image_with(values, width, height, color_map, &HeatmapOptions::default())
+    }
+
+    /// [`create_heatmap_image`] with explicit range, centre and class
+    /// options. Non-finite values are black; use
+    /// [`create_heatmap_image_rgba`] to make them transparent.
+    pub fn create_heatmap_image_with(
+        values: &[f32],
+        width: u32,
+        height: u32,
+        color_map: &str,
+        options: &HeatmapOptions,
+    ) -> Result<RgbImage> {
+        let rgba = create_heatmap_image_rgba(
+            values,
+            width,
+            height,
+            color_map,
+            &HeatmapOptions {
+                transparent_nodata: false,
+                ..options.clone()
+            },
+        )?;
+        Ok(ImageBuffer::from_fn(width, height, |x, y| {
+            let [r, g, b, _] = rgba.get_pixel(x, y).0;
+            Rgb([r, g, b])
+        }))
+    }
+
+    pub fn create_heatmap_image_rgba(
+        values: &[f32],
+        width: u32,
+        height: u32,
+        color_map: &str,
+        options: &HeatmapOptions,
+    ) -> Result<RgbaImage> {
         if values.len() != (width * height) as usize {
             return Err(anyhow::anyhow!("Values length doesn't match dimensions"));
         }
+        let palette = palette(color_map)?;
+        if let Some(classes) = &options.class_breaks {
+            if classes.colors.len() != classes.breaks.len() + 1 {
+                return Err(anyhow::anyhow!(
+                    "{} class breaks need {} colors, got {}",
+                    classes.breaks.len(),
+                    classes.breaks.len() + 1,
+                    classes.colors.len()
+                ));
+            }
+            if !classes.breaks.windows(2).all(|pair| pair[0] < pair[1]) {
+                return Err(anyhow::anyhow!("Class breaks must be strictly increasing"));
+            }
+        }
 
-        let min_val = values.iter().cloned().fold(f32::INFINITY, f32::min);
-        let max_val = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
-        let range = max_val - min_val;
+        let (min_val, max_val) = options.value_range.unwrap_or_else(|| {
+            values
+                .iter()
+                .copied()
+                .filter(|value| value.is_finite())
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+                    (min.min(value), max.max(value))
+                })
+        });
+        let nodata = if options.transparent_nodata {
+            Rgba([0, 0, 0, 0])
+        } else {
+            Rgba([0, 0, 0, 255])
+        };
 
         let mut img = ImageBuffer::new(width, height);
 
@@ -448,15 +667,26 @@ pub mod utils {
             let x = (i as u32) % width;
             let y = (i as u32) / width;
 
-            let normalized = if range > 0.0 {
-                (value - min_val) / range
-            } else {
-                0.5
-            };
+            if !value.is_finite() {
+                img.put_pixel(x, y, nodata);
+                continue;
+            }
 
-            let color = colormap_color(color_map, normalized);
+            let color = match &options.class_breaks {
+                Some(classes) => {
+                    let class = classes.breaks.partition_point(|edge| *edge <= value);
+                    classes.colors[class].clone().into()
+                }
+                None => {
+                    let normalized = match options.center {
+                        Some(center) => normalize_centered(value, min_val, center, max_val),
+                        None => normalize_value(value, min_val, max_val),
+                    };
+                    colormap_color(palette, normalized)
+                }
+            };
 
-            img.put_pixel(x, y, color);
+            img.put_pixel(x, y, Rgba([color.0[0], color.0[1], color.0[2], 255]));
         }
 
         Ok(img)
@@ -474,6 +704,7 @@ pub mod utils {
         if values.len() != (width * height) as usize {
             return Err(anyhow::anyhow!("Values length doesn't match dimensions"));
         }
+        let palette = palette(colormap)?;
 
         let finite_values = values
             .iter()
@@ -517,7 +748,7 @@ pub mod utils {
             }
 
             let normalized = normalize_value(value, range_min, range_max);
-            let color = colormap_color(colormap, normalized);
+            let color = colormap_color(palette, normalized);
             image.put_pixel(x, y, Rgba([color.0[0], color.0[1], color.0[2], 255]));
         }
 
@@ -526,7 +757,7 @@ pub mod utils {
             metadata: OverlayRenderMetadata {
                 colormap: colormap.to_string(),
                 value_range: Some(value_range),
-                legend_stops: legend_stops(colormap, value_range, legend_stop_count),
+                legend_stops: legend_stops(palette, value_range, legend_stop_count),
                 valid_pixel_count: finite_values.len(),
                 nodata_pixel_count,
                 spatial_bounds: spatial_bounds.clone(),
@@ -534,17 +765,33 @@ pub mod utils {
         })
     }
 
+    /// Position of `value` on the `[min, max]` ramp, clamped to `[0, 1]`.
+    /// A non-finite value or range maps to the middle of the ramp.
     fn normalize_value(value: f32, min: f32, max: f32) -> f32 {
         let range = max - min;
-        if range.abs() > f32::EPSILON {
+        if !value.is_finite() || !range.is_finite() {
+            0.5
+        } else if range.abs() > f32::EPSILON {
             ((value - min) / range).clamp(0.0, 1.0)
         } else {
             0.5
         }
     }
 
+    /// Maps `[min, center]` onto `[0, 0.5]` and `[center, max]` onto
+    /// `[0.5, 1]`, so the centre always lands mid-ramp.
+    fn normalize_centered(value: f32, min: f32, center: f32, max: f32) -> f32 {
+        if value < center {
+            normalize_value(value, min, center) * 0.5
+        } else if value > center {
+            0.5 + normalize_value(value, center, max) * 0.5
+        } else {
+            0.5
+        }
+    }
+
     fn legend_stops(
-        colormap: &str,
+        palette: &Palette,
         range: OverlayValueRange,
         legend_stop_count: usize,
     ) -> Vec<LegendStop> {
@@ -563,81 +810,15 @@ pub mod utils {
                 let value = range.min + (range.max - range.min) * t;
                 LegendStop {
                     value,
-                    color: RgbColor::from(colormap_color(colormap, t)),
+                    color: RgbColor::from(colormap_color(palette, t)),
                     alpha: 255,
                 }
             })
             .collect()
     }
 
-    fn colormap_color(color_map: &str, normalized: f32) -> Rgb<u8> {
-        match color_map {
-            "viridis" => viridis_colormap(normalized),
-            "jet" => jet_colormap(normalized),
-            "hot" => hot_colormap(normalized),
-            _ => grayscale_colormap(normalized),
-        }
-    }
-
-    fn viridis_colormap(t: f32) -> Rgb<u8> {
-        let t = t.clamp(0.0, 1.0);
-        // Simplified viridis colormap
-        let r = (0.267004 + t * (0.282623 - 0.267004)) * 255.0;
-        let g = (0.004874 + t * (0.940015 - 0.004874)) * 255.0;
-        let b = (0.329415 + t * (0.644450 - 0.329415)) * 255.0;
-
-        Rgb([r as u8, g as u8, b as u8])
-    }
-
-    fn jet_colormap(t: f32) -> Rgb<u8> {
-        let t = t.clamp(0.0, 1.0);
-        let r = if t < 0.35 {
-            0.0
-        } else if t < 0.66 {
-            (t - 0.35) / 0.31
-        } else {
-            1.0
-        };
-        let g = if t < 0.125 {
-            0.0
-        } else if t < 0.375 {
-            (t - 0.125) / 0.25
-        } else if t < 0.64 {
-            1.0
-        } else {
-            1.0 - (t - 0.64) / 0.36
-        };
-        let b = if t < 0.11 {
-            0.5 + t / 0.22
-        } else if t < 0.34 {
-            1.0
-        } else if t < 0.65 {
-            1.0 - (t - 0.34) / 0.31
-        } else {
-            0.0
-        };
-
-        Rgb([(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8])
-    }
-
-    fn hot_colormap(t: f32) -> Rgb<u8> {
-        let t = t.clamp(0.0, 1.0);
-        let r = if t < 0.33 { t / 0.33 } else { 1.0 };
-        let g = if t < 0.33 {
-            0.0
-        } else if t < 0.66 {
-            (t - 0.33) / 0.33
-        } else {
-            1.0
-        };
-        let b = if t < 0.66 { 0.0 } else { (t - 0.66) / 0.34 };
-
-        Rgb([(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8])
-    }
-
-    fn grayscale_colormap(t: f32) -> Rgb<u8> {
-        let intensity = (t.clamp(0.0, 1.0) * 255.0) as u8;
-        Rgb([intensity, intensity, intensity])
+    fn colormap_color(palette: &Palette, normalized: f32) -> Rgb<u8> {
+        Rgb(palette.color_at(normalized))
     }
 
     pub fn interpolate_grid(
@@ -753,11 +934,230 @@ mod tests {
         assert_eq!(engine.get_queue_length(), 1);
     }
 
+    /// Works through `tiles` 5 ms tiles, checking for cancellation between
+    /// them.
+    struct TiledProcessor {
+        name: &'static str,
+        tiles: usize,
+    }
+
+    impl OverlayProcessor for TiledProcessor {
+        fn process(&self, inputs: &[SensorInput]) -> Result<SensorOverlay> {
+            self.process_cancellable(inputs, &CancellationToken::new())
+        }
+
+        fn process_cancellable(
+            &self,
+            _inputs: &[SensorInput],
+            cancel: &CancellationToken,
+        ) -> Result<SensorOverlay> {
+            for _ in 0..self.tiles {
+                ensure_not_cancelled(cancel)?;
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+            Ok(SensorOverlay {
+                id: Uuid::new_v4(),
+                overlay_type: self.get_overlay_type(),
+                timestamp: Utc::now(),
+                spatial_bounds: SpatialBounds::new(0.0, 0.0, 1.0, 1.0),
+                resolution: (1, 1),
+                data: OverlayData::Grid {
+                    width: 1,
+                    height: 1,
+                    values: vec![1.0],
+                    min_value: 1.0,
+                    max_value: 1.0,
+                },
+                metadata: HashMap::new(),
+            })
+        }
+
+        fn can_process(&self, sensor_type: &str) -> bool {
+            sensor_type == self.name
+        }
+
+        fn get_overlay_type(&self) -> OverlayType {
+            OverlayType::Custom(self.name.to_string())
+        }
+    }
+
+    async fn wait_until(mut condition: impl FnMut() -> bool) {
+        for _ in 0..1_000 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("condition not reached within 10 s");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn cancelled_jobs_produce_no_output_and_free_their_slot() {
+        let mut engine = OverlayEngine::new().with_worker_limit(1);
+        engine.register_processor(Box::new(TiledProcessor {
+            name: "hold",
+            tiles: 2_000,
+        }));
+        engine.register_processor(Box::new(TiledProcessor {
+            name: "quick",
+            tiles: 1,
+        }));
+        let hold = OverlayType::Custom("hold".to_string());
+        let quick = OverlayType::Custom("quick".to_string());
+
+        let dropped = engine.submit_job(hold.clone(), vec![]).await.unwrap();
+        let first = engine.submit_job(hold.clone(), vec![]).await.unwrap();
+        let second = engine.submit_job(hold, vec![]).await.unwrap();
+        let survivor = engine.submit_job(quick, vec![]).await.unwrap();
+
+        // Cancelled before processing starts: it leaves the queue at once.
+        assert!(engine.cancel_job(&dropped));
+        assert_eq!(engine.get_queue_length(), 3);
+
+        let jobs = engine.job_manager();
+        let processing = tokio::spawn(async move {
+            let results = engine.process_all_pending().await;
+            (engine, results)
+        });
+
+        // One slot: one holding job runs while the other waits for it.
+        let running = |id: &Uuid| jobs.status(id) == Some(OverlayJobStatus::Running);
+        wait_until(|| running(&first) || running(&second)).await;
+        let (running_job, waiting_job) = if running(&first) {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        assert_eq!(jobs.available_slots(), 0);
+
+        assert!(jobs.cancel(&waiting_job));
+        assert_eq!(jobs.status(&waiting_job), Some(OverlayJobStatus::Cancelled));
+        assert!(jobs.cancel(&running_job));
+
+        let (engine, results) = processing.await.unwrap();
+        let results = results.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].overlay_type,
+            OverlayType::Custom("quick".to_string())
+        );
+        assert!(engine.get_overlay(&survivor).is_some());
+        for job in [dropped, first, second] {
+            assert!(engine.get_overlay(&job).is_none());
+            assert_eq!(jobs.status(&job), Some(OverlayJobStatus::Cancelled));
+        }
+        assert_eq!(jobs.status(&survivor), Some(OverlayJobStatus::Completed));
+        assert_eq!(jobs.available_slots(), 1);
+        assert!(!jobs.cancel(&survivor));
+    }
+
     #[test]
     fn test_heatmap_creation() {
         let values = vec![0.0, 0.5, 1.0, 0.25];
-        let result = utils::create_heatmap_image(&values, 2, 2, "viridis");
-        assert!(result.is_ok());
+        let image = utils::create_heatmap_image(&values, 2, 2, "viridis").unwrap();
+        assert_eq!(*image.get_pixel(0, 0), Rgb([68, 1, 84]));
+        assert_eq!(*image.get_pixel(1, 0), Rgb([33, 145, 140]));
+        assert_eq!(*image.get_pixel(0, 1), Rgb([253, 231, 37]));
+
+        let error = utils::create_heatmap_image(&values, 2, 2, "rainbow").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("unknown palette 'rainbow'; available palettes: viridis, cividis"));
+    }
+
+    #[test]
+    fn heatmap_explicit_range_clamps_and_diverging_center_is_white() {
+        let options = utils::HeatmapOptions {
+            value_range: Some((-0.5, 0.5)),
+            center: Some(0.0),
+            ..Default::default()
+        };
+        let image = utils::create_heatmap_image_with(
+            &[-0.9, -0.5, 0.0, 0.5, 0.9, 0.25],
+            3,
+            2,
+            "rdbu",
+            &options,
+        )
+        .unwrap();
+
+        let red = Rgb([178, 24, 43]);
+        let blue = Rgb([33, 102, 172]);
+        assert_eq!(*image.get_pixel(0, 0), red);
+        assert_eq!(*image.get_pixel(1, 0), red);
+        assert_eq!(*image.get_pixel(2, 0), Rgb([255, 255, 255]));
+        assert_eq!(*image.get_pixel(0, 1), blue);
+        assert_eq!(*image.get_pixel(1, 1), blue);
+        assert_eq!(*image.get_pixel(2, 1), Rgb([144, 179, 214]));
+
+        // The range holds even when the data spans less of it.
+        let narrow = utils::create_heatmap_image_with(
+            &[0.0, 0.25],
+            2,
+            1,
+            "grayscale",
+            &utils::HeatmapOptions {
+                value_range: Some((0.0, 1.0)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(*narrow.get_pixel(0, 0), Rgb([0, 0, 0]));
+        assert_eq!(*narrow.get_pixel(1, 0), Rgb([64, 64, 64]));
+    }
+
+    #[test]
+    fn heatmap_class_breaks_and_transparent_nodata() {
+        let options = utils::HeatmapOptions {
+            class_breaks: Some(utils::HeatmapClassBreaks {
+                breaks: vec![0.2, 0.6],
+                colors: vec![
+                    RgbColor { r: 255, g: 0, b: 0 },
+                    RgbColor {
+                        r: 255,
+                        g: 255,
+                        b: 0,
+                    },
+                    RgbColor { r: 0, g: 128, b: 0 },
+                ],
+            }),
+            transparent_nodata: true,
+            ..Default::default()
+        };
+        let image = utils::create_heatmap_image_rgba(
+            &[0.1, 0.2, 0.59, 0.6, 0.95, f32::NAN],
+            3,
+            2,
+            "viridis",
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(*image.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(*image.get_pixel(1, 0), Rgba([255, 255, 0, 255]));
+        assert_eq!(*image.get_pixel(2, 0), Rgba([255, 255, 0, 255]));
+        assert_eq!(*image.get_pixel(0, 1), Rgba([0, 128, 0, 255]));
+        assert_eq!(*image.get_pixel(1, 1), Rgba([0, 128, 0, 255]));
+        assert_eq!(*image.get_pixel(2, 1), Rgba([0, 0, 0, 0]));
+
+        let opaque = utils::create_heatmap_image_rgba(
+            &[f32::NAN],
+            1,
+            1,
+            "viridis",
+            &utils::HeatmapOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(*opaque.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+
+        let mismatched = utils::HeatmapOptions {
+            class_breaks: Some(utils::HeatmapClassBreaks {
+                breaks: vec![0.5],
+                colors: vec![RgbColor { r: 0, g: 0, b: 0 }],
+            }),
+            ..Default::default()
+        };
+        assert!(utils::create_heatmap_image_rgba(&[0.0], 1, 1, "viridis", &mismatched).is_err());
     }
 
     #[test]
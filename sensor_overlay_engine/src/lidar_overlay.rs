@@ -19,14 +19,73 @@ pub struct LidarOverlayProcessor {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct LidarConfig {
     pub point_cloud_resolution: f32,
     pub height_color_mapping: HeightColorMapping,
     pub occupancy_grid_resolution: f32,
     pub max_range: f32,
+    pub ground_filter: GroundFilterConfig,
 }
 
+/// Progressive morphological filter that separates ground from the points
+/// standing on it. Lengths are in metres.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GroundFilterConfig {
+    /// Cell size of the minimum-elevation grid the filter opens.
+    pub cell_size: f32,
+    /// Side of the first opening window; it doubles each pass up to
+    /// `max_window`.
+    pub initial_window: f32,
+    pub max_window: f32,
+    /// Height above the ground surface still labelled ground.
+    pub ground_threshold: f32,
+    /// Rise per metre of window growth tolerated as terrain slope.
+    pub slope: f32,
+    /// Non-ground points at least this high above ground are structures;
+    /// also the largest height a pass accepts as terrain.
+    pub structure_height: f32,
+}
+
+/// Label of a point after ground filtering.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum LidarPointClass {
+    Ground,
+    Vegetation,
+    Structure,
+}
+
+impl LidarPointClass {
+    /// ASPRS LAS classification code (medium vegetation for vegetation).
+    pub fn asprs_code(self) -> u8 {
+        match self {
+            Self::Ground => 2,
+            Self::Vegetation => 4,
+            Self::Structure => 6,
+        }
+    }
+}
+
+/// Per-point labels, parallel to the classified points.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LidarClassificationResult {
+    pub classes: Vec<LidarPointClass>,
+    /// Height of each point above the filtered ground surface, in metres.
+    pub heights_above_ground: Vec<f32>,
+}
+
+impl LidarClassificationResult {
+    pub fn count(&self, class: LidarPointClass) -> usize {
+        self.classes
+            .iter()
+            .filter(|candidate| **candidate == class)
+            .count()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct HeightColorMapping {
     pub ground_level: [u8; 4],      // RGBA for ground
     pub low_vegetation: [u8; 4],    // RGBA for low plants
@@ -93,6 +152,20 @@ impl Default for LidarConfig {
             },
             occupancy_grid_resolution: 0.2,
             max_range: 100.0,
+            ground_filter: GroundFilterConfig::default(),
+        }
+    }
+}
+
+impl Default for GroundFilterConfig {
+    fn default() -> Self {
+        Self {
+            cell_size: 0.5,
+            initial_window: 1.0,
+            max_window: 16.0,
+            ground_threshold: 0.2,
+            slope: 0.3,
+            structure_height: 2.5,
         }
     }
 }
@@ -163,6 +236,245 @@ impl LidarOverlayProcessor {
         })
     }
 
+    /// Labels every point as ground, vegetation or structure with a
+    /// progressive morphological filter over the minimum-elevation grid.
+    /// Each pass opens the surface with a larger window and marks cells that
+    /// drop by more than the pass threshold as non-ground; points are then
+    /// labelled by their height above the resulting ground surface.
+    pub fn classify_points(&self, points: &[Point3<f32>]) -> LidarClassificationResult {
+        let filter = &self.config.ground_filter;
+        let cell_size = filter.cell_size;
+        let cell_of = |point: &Point3<f32>| {
+            (
+                (point.x / cell_size).floor() as i32,
+                (point.y / cell_size).floor() as i32,
+            )
+        };
+        let Some(bounds) = grid_bounds(points.iter().map(cell_of)) else {
+            return LidarClassificationResult {
+                classes: Vec::new(),
+                heights_above_ground: Vec::new(),
+            };
+        };
+        let width = (bounds.max_x - bounds.min_x + 1) as usize;
+        let height = (bounds.max_y - bounds.min_y + 1) as usize;
+        let index_of =
+            |(x, y): (i32, i32)| (y - bounds.min_y) as usize * width + (x - bounds.min_x) as usize;
+
+        let mut minimum = vec![None::<f32>; width * height];
+        for point in points {
+            let cell = &mut minimum[index_of(cell_of(point))];
+            *cell = Some(cell.map_or(point.z, |z| z.min(point.z)));
+        }
+
+        let mut surface = minimum.clone();
+        let mut is_ground = minimum.iter().map(Option::is_some).collect::<Vec<_>>();
+        let mut window = filter.initial_window;
+        let mut previous_window = 0.0;
+        while window <= filter.max_window {
+            let radius = ((window / cell_size / 2.0).floor() as usize).max(1);
+            let opened = dilate(
+                &erode(&surface, width, height, radius),
+                width,
+                height,
+                radius,
+            );
+            let threshold = if previous_window == 0.0 {
+                filter.ground_threshold
+            } else {
+                (filter.ground_threshold + filter.slope * (window - previous_window))
+                    .min(filter.structure_height)
+            };
+            for (index, ground) in is_ground.iter_mut().enumerate() {
+                if let (Some(current), Some(open)) = (surface[index], opened[index]) {
+                    if current - open > threshold {
+                        *ground = false;
+                    }
+                }
+            }
+            surface = opened;
+            previous_window = window;
+            window *= 2.0;
+        }
+
+        let mut classes = Vec::with_capacity(points.len());
+        let mut heights_above_ground = Vec::with_capacity(points.len());
+        for point in points {
+            let index = index_of(cell_of(point));
+            let ground_z = if is_ground[index] {
+                minimum[index]
+            } else {
+                surface[index]
+            }
+            .unwrap_or(point.z);
+            let above = point.z - ground_z;
+            classes.push(if is_ground[index] && above <= filter.ground_threshold {
+                LidarPointClass::Ground
+            } else if above < filter.structure_height {
+                LidarPointClass::Vegetation
+            } else {
+                LidarPointClass::Structure
+            });
+            heights_above_ground.push(above);
+        }
+        LidarClassificationResult {
+            classes,
+            heights_above_ground,
+        }
+    }
+
+    /// Point cloud overlay coloured by [`Self::classify_points`]; values are
+    /// the ASPRS class codes.
+    pub fn classification_overlay(&self, point_cloud: &PointCloudData) -> SensorOverlay {
+        let classification = self.classify_points(&point_cloud.points);
+        let colors = &self.config.height_color_mapping;
+        let color_of = |class: LidarPointClass| {
+            let [r, g, b, _] = match class {
+                LidarPointClass::Ground => colors.ground_level,
+                LidarPointClass::Vegetation => colors.medium_vegetation,
+                LidarPointClass::Structure => colors.obstacles,
+            };
+            RgbColor { r, g, b }
+        };
+        let axis_range = |axis: fn(&Point3<f32>) -> f32| {
+            point_cloud
+                .points
+                .iter()
+                .map(axis)
+                .fold(None, |range: Option<(f32, f32)>, value| {
+                    Some(range.map_or((value, value), |(low, high)| {
+                        (low.min(value), high.max(value))
+                    }))
+                })
+                .unwrap_or((0.0, 0.0))
+        };
+        let (min_x, max_x) = axis_range(|point| point.x);
+        let (min_y, max_y) = axis_range(|point| point.y);
+        let (min_z, max_z) = axis_range(|point| point.z);
+
+        let metadata = [
+            LidarPointClass::Ground,
+            LidarPointClass::Vegetation,
+            LidarPointClass::Structure,
+        ]
+        .into_iter()
+        .map(|class| {
+            (
+                format!("{class:?}_points").to_lowercase(),
+                classification.count(class).to_string(),
+            )
+        })
+        .collect();
+
+        SensorOverlay {
+            id: Uuid::new_v4(),
+            overlay_type: OverlayType::LidarClassification,
+            timestamp: point_cloud.timestamp,
+            spatial_bounds: SpatialBounds {
+                min_x: f64::from(min_x),
+                min_y: f64::from(min_y),
+                max_x: f64::from(max_x),
+                max_y: f64::from(max_y),
+                min_z: Some(f64::from(min_z)),
+                max_z: Some(f64::from(max_z)),
+            },
+            resolution: (point_cloud.points.len() as u32, 1),
+            data: OverlayData::PointCloud {
+                points: point_cloud.points.clone(),
+                values: classification
+                    .classes
+                    .iter()
+                    .map(|class| f32::from(class.asprs_code()))
+                    .collect(),
+                colors: Some(
+                    classification
+                        .classes
+                        .iter()
+                        .copied()
+                        .map(color_of)
+                        .collect(),
+                ),
+            },
+            metadata,
+        }
+    }
+
+    /// Canopy height model: per `resolution`-sized cell, the highest return
+    /// minus the ground elevation, taken as the `ground_percentile`
+    /// (0-100) of the cell's returns. Negative heights are clamped to zero
+    /// and cells without returns are NaN. Rows run north to south, from the
+    /// highest y down.
+    pub fn canopy_height_model(
+        &self,
+        points: &[Point3<f32>],
+        resolution: f32,
+        ground_percentile: f32,
+    ) -> Result<OverlayData> {
+        anyhow::ensure!(
+            resolution.is_finite() && resolution > 0.0,
+            "canopy height model resolution must be positive, got {resolution}"
+        );
+        anyhow::ensure!(
+            (0.0..=100.0).contains(&ground_percentile),
+            "ground percentile must be within 0-100, got {ground_percentile}"
+        );
+        let cell_of = |point: &Point3<f32>| {
+            (
+                (point.x / resolution).floor() as i32,
+                (point.y / resolution).floor() as i32,
+            )
+        };
+        let Some(bounds) = grid_bounds(points.iter().map(cell_of)) else {
+            return Ok(OverlayData::Grid {
+                width: 0,
+                height: 0,
+                values: Vec::new(),
+                min_value: 0.0,
+                max_value: 0.0,
+            });
+        };
+        let width = (bounds.max_x - bounds.min_x + 1) as usize;
+        let height = (bounds.max_y - bounds.min_y + 1) as usize;
+
+        let mut returns = vec![Vec::new(); width * height];
+        for point in points {
+            let (x, y) = cell_of(point);
+            let row = (bounds.max_y - y) as usize;
+            returns[row * width + (x - bounds.min_x) as usize].push(point.z);
+        }
+
+        let values = returns
+            .into_iter()
+            .map(|mut elevations| {
+                if elevations.is_empty() {
+                    return f32::NAN;
+                }
+                elevations.sort_by(f32::total_cmp);
+                let ground = percentile(&elevations, ground_percentile);
+                let canopy = elevations[elevations.len() - 1];
+                (canopy - ground).max(0.0)
+            })
+            .collect::<Vec<_>>();
+        let (min_value, max_value) = values
+            .iter()
+            .copied()
+            .filter(|value| value.is_finite())
+            .fold(None, |range: Option<(f32, f32)>, value| {
+                Some(range.map_or((value, value), |(low, high)| {
+                    (low.min(value), high.max(value))
+                }))
+            })
+            .unwrap_or((0.0, 0.0));
+
+        Ok(OverlayData::Grid {
+            width: width as u32,
+            height: height as u32,
+            values,
+            min_value,
+            max_value,
+        })
+    }
+
     /// Create a 2D height map from 3D point cloud
     fn create_height_map(&self, points: &[Point3<f32>]) -> Result<HeightMap> {
         let mut grid: HashMap<(i32, i32), Vec<f32>> = HashMap::new();
@@ -211,11 +523,20 @@ impl LidarOverlayProcessor {
         let mut image = ImageBuffer::new(width, height);
 
         // Find height range for normalization
-        let heights: Vec<f32> = height_map.data.values().cloned().collect();
-        let min_height = heights.iter().fold(f32::INFINITY, |a, &b| a.min(b));
-        let max_height = heights.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+        let heights = height_map
+            .data
+            .values()
+            .copied()
+            .filter(|height| height.is_finite());
+        let (min_height, max_height) = heights
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), height| {
+                (min.min(height), max.max(height))
+            });
 
         for ((grid_x, grid_y), &height_val) in &height_map.data {
+            if !height_val.is_finite() {
+                continue;
+            }
             let img_x = (grid_x - height_map.bounds.min_x) as u32;
             let img_y = (grid_y - height_map.bounds.min_y) as u32;
 
@@ -405,7 +726,12 @@ impl LidarOverlayProcessor {
 
     /// Calculate terrain statistics
     fn calculate_terrain_statistics(&self, height_map: &HeightMap) -> TerrainStatistics {
-        let heights: Vec<f32> = height_map.data.values().cloned().collect();
+        let heights: Vec<f32> = height_map
+            .data
+            .values()
+            .copied()
+            .filter(|height| height.is_finite())
+            .collect();
 
         if heights.is_empty() {
             return TerrainStatistics::default();
@@ -413,7 +739,7 @@ impl LidarOverlayProcessor {
 
         let mean = heights.iter().sum::<f32>() / heights.len() as f32;
         let mut sorted_heights = heights.clone();
-        sorted_heights.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        sorted_heights.sort_by(f32::total_cmp);
 
         let median = if sorted_heights.len() % 2 == 0 {
             (sorted_heights[sorted_heights.len() / 2 - 1]
@@ -444,6 +770,77 @@ impl LidarOverlayProcessor {
     }
 }
 
+fn grid_bounds(cells: impl Iterator<Item = (i32, i32)>) -> Option<GridBounds> {
+    cells.fold(None, |bounds, (x, y)| {
+        Some(match bounds {
+            None => GridBounds {
+                min_x: x,
+                max_x: x,
+                min_y: y,
+                max_y: y,
+            },
+            Some(bounds) => GridBounds {
+                min_x: bounds.min_x.min(x),
+                max_x: bounds.max_x.max(x),
+                min_y: bounds.min_y.min(y),
+                max_y: bounds.max_y.max(y),
+            },
+        })
+    })
+}
+
+/// Minimum over the square window of `radius` cells around each cell,
+/// ignoring empty cells; separable, rows then columns.
+/// Linearly interpolated percentile (0-100) of ascending, non-empty `sorted`.
+fn percentile(sorted: &[f32], percentile: f32) -> f32 {
+    let rank = percentile / 100.0 * (sorted.len() - 1) as f32;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f32)
+}
+
+fn erode(grid: &[Option<f32>], width: usize, height: usize, radius: usize) -> Vec<Option<f32>> {
+    window_extreme(grid, width, height, radius, f32::min)
+}
+
+fn dilate(grid: &[Option<f32>], width: usize, height: usize, radius: usize) -> Vec<Option<f32>> {
+    window_extreme(grid, width, height, radius, f32::max)
+}
+
+fn window_extreme(
+    grid: &[Option<f32>],
+    width: usize,
+    height: usize,
+    radius: usize,
+    pick: fn(f32, f32) -> f32,
+) -> Vec<Option<f32>> {
+    let combine = |values: &mut dyn Iterator<Item = Option<f32>>| {
+        values.flatten().fold(None, |best: Option<f32>, value| {
+            Some(best.map_or(value, |best| pick(best, value)))
+        })
+    };
+    let mut rows = vec![None; grid.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let span = x.saturating_sub(radius)..(x + radius + 1).min(width);
+            rows[y * width + x] = combine(&mut span.map(|column| grid[y * width + column]));
+        }
+    }
+    let mut result = vec![None; grid.len()];
+    for y in 0..height {
+        for x in 0..width {
+            // Empty cells stay empty so the surface never spreads past the
+            // measured area.
+            if grid[y * width + x].is_none() {
+                continue;
+            }
+            let span = y.saturating_sub(radius)..(y + radius + 1).min(height);
+            result[y * width + x] = combine(&mut span.map(|row| rows[row * width + x]));
+        }
+    }
+    result
+}
+
 fn spatial_bounds_from_ref(spatial_ref: &RasterSpatialRef) -> Result<SpatialBounds> {
     let bbox = spatial_ref
         .bbox
@@ -591,6 +988,112 @@ mod tests {
         assert!(!grid.data.is_empty());
     }
 
+    #[test]
+    fn ground_filter_separates_flat_ground_from_elevated_points() {
+        let processor = LidarOverlayProcessor::new(LidarConfig::default());
+        let mut points = Vec::new();
+        // 20 m x 20 m ground plane with a little sensor noise; the ground
+        // under the shed roof below is not visible to the scanner.
+        for x in 0..40 {
+            for y in 0..40 {
+                if (29..=35).contains(&x) && (29..=35).contains(&y) {
+                    continue;
+                }
+                let noise = if (x + y) % 3 == 0 { 0.03 } else { 0.0 };
+                points.push(Point3::new(x as f32 * 0.5, y as f32 * 0.5, 10.0 + noise));
+            }
+        }
+        let ground_count = points.len();
+        // A row of crop plants 0.8 m tall and a 4 m x 4 m shed with a 3.5 m roof.
+        for y in 0..20 {
+            points.push(Point3::new(5.2, 2.0 + y as f32 * 0.5, 10.8));
+        }
+        for x in 0..8 {
+            for y in 0..8 {
+                points.push(Point3::new(
+                    14.1 + x as f32 * 0.5,
+                    14.1 + y as f32 * 0.5,
+                    13.5,
+                ));
+            }
+        }
+
+        let result = processor.classify_points(&points);
+
+        let (ground, elevated) = result.classes.split_at(ground_count);
+        assert!(ground.iter().all(|class| *class == LidarPointClass::Ground));
+        assert!(elevated[..20]
+            .iter()
+            .all(|class| *class == LidarPointClass::Vegetation));
+        assert!(elevated[20..]
+            .iter()
+            .all(|class| *class == LidarPointClass::Structure));
+        assert!((result.heights_above_ground[ground_count] - 0.8).abs() < 0.05);
+
+        let overlay = processor.classification_overlay(&PointCloudData {
+            points,
+            intensities: Vec::new(),
+            gps_origin: Point3::origin(),
+            timestamp: Utc::now(),
+        });
+        assert_eq!(overlay.overlay_type, OverlayType::LidarClassification);
+        assert_eq!(overlay.metadata["structure_points"], "64");
+        let OverlayData::PointCloud { values, .. } = overlay.data else {
+            panic!("classification overlay is a point cloud");
+        };
+        assert_eq!(values[0], 2.0);
+        assert_eq!(values[ground_count], 4.0);
+    }
+
+    #[test]
+    fn canopy_height_model_subtracts_ground_from_canopy_returns() {
+        let processor = LidarOverlayProcessor::new(LidarConfig::default());
+        let mut points = Vec::new();
+        // 3 m x 2 m of 1 m cells: ground at 10 m everywhere, a 2.5 m crop
+        // canopy over the west column and 1.25 m over the middle of the
+        // north row. The east column is bare ground; a low outlier in its
+        // south cell pulls that cell's 20th percentile down to 9.84 m.
+        for x in 0..3 {
+            for y in 0..2 {
+                for (dx, dy) in [(0.2, 0.2), (0.8, 0.2), (0.2, 0.8), (0.8, 0.8)] {
+                    points.push(Point3::new(x as f32 + dx, y as f32 + dy, 10.0));
+                }
+                let canopy = match (x, y) {
+                    (0, _) => Some(12.5),
+                    (1, 1) => Some(11.25),
+                    _ => None,
+                };
+                if let Some(z) = canopy {
+                    points.push(Point3::new(x as f32 + 0.5, y as f32 + 0.5, z - 0.3));
+                    points.push(Point3::new(x as f32 + 0.4, y as f32 + 0.6, z));
+                }
+            }
+        }
+        points.push(Point3::new(2.5, 0.5, 9.2));
+
+        let chm = processor.canopy_height_model(&points, 1.0, 20.0).unwrap();
+
+        let OverlayData::Grid {
+            width,
+            height,
+            values,
+            min_value,
+            max_value,
+        } = chm
+        else {
+            panic!("canopy height model is a grid");
+        };
+        assert_eq!((width, height), (3, 2));
+        // North row first.
+        let expected = [2.5, 1.25, 0.0, 2.5, 0.0, 0.16];
+        for (value, expected) in values.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-4, "{values:?}");
+        }
+        assert_eq!((min_value, max_value), (0.0, 2.5));
+        assert!(processor.canopy_height_model(&points, 0.0, 5.0).is_err());
+        assert!(processor.canopy_height_model(&points, 1.0, 101.0).is_err());
+    }
+
     #[test]
     fn test_height_to_color_mapping() {
         let processor = LidarOverlayProcessor::new(LidarConfig::default());
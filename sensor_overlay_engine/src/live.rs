@@ -0,0 +1,794 @@
+//! Event-driven overlay generation.
+//!
+//! Instead of batch-processing a whole flight, the live mode listens for
+//! newly ingested records (an HTTP webhook from the data collector, or a
+//! channel when embedded in-process), groups co-located inputs with an
+//! [`InputAccumulator`], and runs a composite overlay job as soon as a group
+//! is complete.
+
+use anyhow::{bail, Context, Result};
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
+use chrono::{DateTime, Duration, Utc};
+use nalgebra::{Point3, Vector3};
+use serde::{Deserialize, Serialize};
+use shared::http_client::{HttpClient, HttpClientConfig};
+use shared::schemas::{GpsCoords, OverlayNotification};
+use shared::webhooks::{WebhookEvent, WebhookEventKind};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::composite::{CompositeOverlayEngine, CompositeScanData, IndividualOverlayResult};
+use crate::ndvi::FieldScanData;
+use crate::thermal::ThermalScanData;
+use crate::{ImageData, MultispectralCalibration, SensorInput, SensorInputData};
+
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+/// Band names a multispectral input needs before it can produce NDVI.
+pub const RED_BAND: &str = "red";
+pub const NIR_BAND: &str = "nir";
+/// `ImageData::format` for little-endian 16-bit samples; anything else is
+/// read as 8-bit.
+pub const U16_LE_FORMAT: &str = "u16le";
+const DEFAULT_EMISSIVITY: f32 = 0.95;
+/// Record ids remembered so a redelivered webhook is not handled twice.
+const RECENT_RECORD_IDS: usize = 1024;
+
+/// How close in time and space a thermal frame must be to a multispectral
+/// image to be composited with it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchWindow {
+    pub max_time_gap_secs: f64,
+    /// Ground distance between capture positions, with positions read as
+    /// (longitude, latitude, altitude).
+    pub max_distance_m: f64,
+}
+
+impl Default for MatchWindow {
+    fn default() -> Self {
+        Self {
+            max_time_gap_secs: 5.0,
+            max_distance_m: 25.0,
+        }
+    }
+}
+
+impl MatchWindow {
+    fn max_time_gap(&self) -> Duration {
+        Duration::milliseconds((self.max_time_gap_secs * 1000.0) as i64)
+    }
+
+    fn matches(&self, a: &SensorInput, b: &SensorInput) -> bool {
+        (a.timestamp - b.timestamp).abs() <= self.max_time_gap()
+            && ground_distance_m(&a.position, &b.position) <= self.max_distance_m
+    }
+}
+
+/// A group of inputs ready for one composite overlay job.
+#[derive(Debug, Clone)]
+pub struct MatchedInputs {
+    pub multispectral: SensorInput,
+    pub thermal: Option<SensorInput>,
+}
+
+impl MatchedInputs {
+    pub fn captured_at(&self) -> DateTime<Utc> {
+        self.thermal
+            .as_ref()
+            .map_or(self.multispectral.timestamp, |thermal| {
+                thermal.timestamp.min(self.multispectral.timestamp)
+            })
+    }
+
+    pub fn source_sensor_ids(&self) -> Vec<String> {
+        std::iter::once(&self.multispectral)
+            .chain(self.thermal.as_ref())
+            .map(|input| input.sensor_id.clone())
+            .collect()
+    }
+
+    pub fn to_scan_data(&self) -> Result<CompositeScanData> {
+        let SensorInputData::MultispectralImage { bands, .. } = &self.multispectral.data else {
+            bail!(
+                "input {} is not multispectral",
+                self.multispectral.sensor_id
+            );
+        };
+        let red = band(bands, RED_BAND).context("multispectral input has no red band")?;
+        let nir = band(bands, NIR_BAND).context("multispectral input has no NIR band")?;
+        if (red.width, red.height) != (nir.width, nir.height) {
+            bail!(
+                "red band is {}x{} but NIR band is {}x{}",
+                red.width,
+                red.height,
+                nir.width,
+                nir.height
+            );
+        }
+        let pixel_count = (red.width * red.height) as usize;
+        let ndvi_data = FieldScanData {
+            red_band: reflectance(red)?,
+            nir_band: reflectance(nir)?,
+            width: red.width,
+            height: red.height,
+            gps_coordinates: vec![self.multispectral.position; pixel_count],
+            timestamp: self.multispectral.timestamp,
+        };
+
+        let thermal_data = match &self.thermal {
+            Some(input) => {
+                let SensorInputData::ThermalImage { image, .. } = &input.data else {
+                    bail!("input {} is not a thermal image", input.sensor_id);
+                };
+                Some(ThermalScanData {
+                    raw_thermal_data: samples(image)?,
+                    width: image.width,
+                    height: image.height,
+                    gps_coordinates: vec![input.position; (image.width * image.height) as usize],
+                    timestamp: input.timestamp,
+                })
+            }
+            None => None,
+        };
+
+        Ok(CompositeScanData {
+            ndvi_data: Some(ndvi_data),
+            thermal_data,
+            lidar_data: None,
+            rgb_image: None,
+            gps_reference: self.multispectral.position,
+            timestamp: self.captured_at(),
+        })
+    }
+}
+
+enum InputRole {
+    Multispectral,
+    Thermal,
+}
+
+fn input_role(input: &SensorInput) -> Option<InputRole> {
+    match &input.data {
+        SensorInputData::MultispectralImage { bands, .. }
+            if band(bands, RED_BAND).is_some() && band(bands, NIR_BAND).is_some() =>
+        {
+            Some(InputRole::Multispectral)
+        }
+        SensorInputData::ThermalImage { .. } => Some(InputRole::Thermal),
+        _ => None,
+    }
+}
+
+/// Groups NDVI-capable multispectral images with an optional thermal frame
+/// captured within a [`MatchWindow`].
+///
+/// Windows are judged on capture timestamps, not arrival order: the newest
+/// timestamp seen so far is the watermark. A multispectral image still
+/// unpaired once the watermark is more than `max_time_gap_secs` past it is
+/// released on its own; an unpaired thermal frame is dropped.
+#[derive(Debug, Default)]
+pub struct InputAccumulator {
+    window: MatchWindow,
+    pending_multispectral: Vec<SensorInput>,
+    pending_thermal: Vec<SensorInput>,
+    watermark: Option<DateTime<Utc>>,
+}
+
+impl InputAccumulator {
+    pub fn new(window: MatchWindow) -> Self {
+        Self {
+            window,
+            ..Self::default()
+        }
+    }
+
+    pub fn window(&self) -> &MatchWindow {
+        &self.window
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending_multispectral.len() + self.pending_thermal.len()
+    }
+
+    /// Adds an input and returns every group it completed or released.
+    /// Inputs that cannot contribute to an overlay are ignored.
+    pub fn push(&mut self, input: SensorInput) -> Vec<MatchedInputs> {
+        let Some(role) = input_role(&input) else {
+            debug!(sensor_id = %input.sensor_id, "ignoring input with no overlay role");
+            return Vec::new();
+        };
+        let timestamp = input.timestamp;
+
+        let mut ready = Vec::new();
+        match role {
+            InputRole::Multispectral => {
+                match take_closest(&mut self.pending_thermal, &input, &self.window) {
+                    Some(thermal) => ready.push(MatchedInputs {
+                        multispectral: input,
+                        thermal: Some(thermal),
+                    }),
+                    None => self.pending_multispectral.push(input),
+                }
+            }
+            InputRole::Thermal => {
+                match take_closest(&mut self.pending_multispectral, &input, &self.window) {
+                    Some(multispectral) => ready.push(MatchedInputs {
+                        multispectral,
+                        thermal: Some(input),
+                    }),
+                    None => self.pending_thermal.push(input),
+                }
+            }
+        }
+
+        ready.extend(self.advance(timestamp));
+        ready
+    }
+
+    /// Advances the watermark without a new input, e.g. from a wall-clock
+    /// timer so the last image of a flight is not held indefinitely.
+    pub fn advance(&mut self, now: DateTime<Utc>) -> Vec<MatchedInputs> {
+        let watermark = self.watermark.map_or(now, |current| current.max(now));
+        self.watermark = Some(watermark);
+        let cutoff = watermark - self.window.max_time_gap();
+
+        self.pending_thermal.retain(|thermal| {
+            let keep = thermal.timestamp >= cutoff;
+            if !keep {
+                debug!(sensor_id = %thermal.sensor_id, "thermal frame expired unpaired");
+            }
+            keep
+        });
+        let (expired, pending): (Vec<_>, Vec<_>) = self
+            .pending_multispectral
+            .drain(..)
+            .partition(|input| input.timestamp < cutoff);
+        self.pending_multispectral = pending;
+        expired
+            .into_iter()
+            .map(|multispectral| MatchedInputs {
+                multispectral,
+                thermal: None,
+            })
+            .collect()
+    }
+
+    /// Releases every pending multispectral image and drops pending thermal
+    /// frames.
+    pub fn flush(&mut self) -> Vec<MatchedInputs> {
+        self.pending_thermal.clear();
+        self.pending_multispectral
+            .drain(..)
+            .map(|multispectral| MatchedInputs {
+                multispectral,
+                thermal: None,
+            })
+            .collect()
+    }
+}
+
+fn take_closest(
+    candidates: &mut Vec<SensorInput>,
+    input: &SensorInput,
+    window: &MatchWindow,
+) -> Option<SensorInput> {
+    let index = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, candidate)| window.matches(candidate, input))
+        .min_by_key(|(_, candidate)| (candidate.timestamp - input.timestamp).abs())
+        .map(|(index, _)| index)?;
+    Some(candidates.remove(index))
+}
+
+fn ground_distance_m(a: &Point3<f64>, b: &Point3<f64>) -> f64 {
+    let mean_latitude = ((a.y + b.y) / 2.0).to_radians();
+    let east = (b.x - a.x).to_radians() * mean_latitude.cos() * EARTH_RADIUS_M;
+    let north = (b.y - a.y).to_radians() * EARTH_RADIUS_M;
+    east.hypot(north)
+}
+
+pub(crate) fn band<'a>(bands: &'a HashMap<String, ImageData>, name: &str) -> Option<&'a ImageData> {
+    bands
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, image)| image)
+}
+
+fn samples(image: &ImageData) -> Result<Vec<u16>> {
+    let pixel_count = (image.width * image.height) as usize;
+    let values: Vec<u16> = if image.format == U16_LE_FORMAT {
+        image
+            .pixel_data
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect()
+    } else {
+        image.pixel_data.iter().map(|&value| value.into()).collect()
+    };
+    if values.len() != pixel_count {
+        bail!(
+            "{}x{} image holds {} samples, expected one channel",
+            image.width,
+            image.height,
+            values.len()
+        );
+    }
+    Ok(values)
+}
+
+pub(crate) fn reflectance(image: &ImageData) -> Result<Vec<f32>> {
+    let full_scale = if image.format == U16_LE_FORMAT {
+        f32::from(u16::MAX)
+    } else {
+        f32::from(u8::MAX)
+    };
+    Ok(samples(image)?
+        .into_iter()
+        .map(|value| f32::from(value) / full_scale)
+        .collect())
+}
+
+/// Payload of the data collector's `record.stored` webhook events. Only the
+/// fields the overlay engine needs are read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectorRecordNotification {
+    pub session_id: Uuid,
+    pub record: CollectorRecord,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectorRecord {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub data_type: String,
+    #[serde(default)]
+    pub sensor_id: String,
+    #[serde(default)]
+    pub gps_coords: Option<GpsCoords>,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    pub file_path: Option<PathBuf>,
+}
+
+/// Loads the image behind a collector record as a [`SensorInput`].
+///
+/// Multispectral records take their red and NIR bands from the given image
+/// channels; thermal records are read as single-channel 16-bit. Records of
+/// other types, or without a file or position, yield `None`.
+pub fn sensor_input_from_record(
+    record: &CollectorRecord,
+    red_channel: usize,
+    nir_channel: usize,
+) -> Result<Option<SensorInput>> {
+    let (Some(path), Some(gps)) = (&record.file_path, &record.gps_coords) else {
+        return Ok(None);
+    };
+    let data = match record.data_type.as_str() {
+        "MultispectralImage" => multispectral_from_file(path, red_channel, nir_channel)?,
+        "ThermalImage" => thermal_from_file(path, &record.metadata)?,
+        _ => return Ok(None),
+    };
+    Ok(Some(SensorInput {
+        sensor_id: record.sensor_id.clone(),
+        sensor_type: record.data_type.clone(),
+        timestamp: record.timestamp,
+        position: Point3::new(gps.longitude, gps.latitude, gps.altitude),
+        orientation: Vector3::zeros(),
+        data,
+    }))
+}
+
+fn multispectral_from_file(
+    path: &Path,
+    red_channel: usize,
+    nir_channel: usize,
+) -> Result<SensorInputData> {
+    let image = image::open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+    let channel = |index: usize| -> Result<ImageData> {
+        if index >= 4 {
+            bail!("band channel {index} is outside the image's channels");
+        }
+        Ok(ImageData {
+            width,
+            height,
+            channels: 1,
+            pixel_data: image.pixels().map(|pixel| pixel.0[index]).collect(),
+            format: "u8".to_string(),
+        })
+    };
+    let bands = HashMap::from([
+        (RED_BAND.to_string(), channel(red_channel)?),
+        (NIR_BAND.to_string(), channel(nir_channel)?),
+    ]);
+    Ok(SensorInputData::MultispectralImage {
+        bands,
+        calibration: MultispectralCalibration {
+            dark_current: HashMap::new(),
+            gain: HashMap::new(),
+            reflectance_panel: HashMap::new(),
+        },
+    })
+}
+
+fn thermal_from_file(path: &Path, metadata: &HashMap<String, String>) -> Result<SensorInputData> {
+    let image = image::open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?
+        .into_luma16();
+    let (width, height) = image.dimensions();
+    let emissivity = metadata
+        .get("emissivity")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_EMISSIVITY);
+    Ok(SensorInputData::ThermalImage {
+        image: ImageData {
+            width,
+            height,
+            channels: 1,
+            pixel_data: image
+                .pixels()
+                .flat_map(|pixel| pixel.0[0].to_le_bytes())
+                .collect(),
+            format: U16_LE_FORMAT.to_string(),
+        },
+        temperature_range: (0.0, 0.0),
+        emissivity,
+    })
+}
+
+/// Source of "now" for releasing pending inputs, injectable so tests can
+/// close matching windows.
+pub trait LiveClock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemLiveClock;
+
+impl LiveClock for SystemLiveClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Runs composite overlay jobs for matched inputs and reports completions.
+pub struct LiveOverlayService {
+    engine: CompositeOverlayEngine,
+    accumulator: InputAccumulator,
+    output_dir: PathBuf,
+    completion: Option<(String, HttpClient)>,
+    recent_records: VecDeque<Uuid>,
+    clock: Arc<dyn LiveClock>,
+}
+
+impl LiveOverlayService {
+    pub fn new(engine: CompositeOverlayEngine, window: MatchWindow, output_dir: PathBuf) -> Self {
+        Self {
+            engine,
+            accumulator: InputAccumulator::new(window),
+            output_dir,
+            completion: None,
+            recent_records: VecDeque::new(),
+            clock: Arc::new(SystemLiveClock),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn LiveClock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Each completed job is POSTed as an [`OverlayNotification`] to `url`,
+    /// e.g. mission control's `/overlays/completed`, retried under `config`.
+    pub fn with_completion_url(
+        mut self,
+        url: impl Into<String>,
+        config: HttpClientConfig,
+    ) -> Result<Self> {
+        self.completion = Some((url.into(), HttpClient::new(config)?));
+        Ok(self)
+    }
+
+    pub fn window(&self) -> &MatchWindow {
+        self.accumulator.window()
+    }
+
+    pub async fn handle_input(&mut self, input: SensorInput) -> Result<Vec<OverlayNotification>> {
+        let ready = self.accumulator.push(input);
+        self.run_jobs(ready).await
+    }
+
+    /// Handles a stored record; one already handled recently, such as a
+    /// webhook redelivered after a lost response, completes nothing.
+    pub async fn handle_record(
+        &mut self,
+        notification: &CollectorRecordNotification,
+    ) -> Result<Vec<OverlayNotification>> {
+        let record_id = notification.record.id;
+        if self.recent_records.contains(&record_id) {
+            debug!("Record {} was already handled", record_id);
+            return Ok(Vec::new());
+        }
+        if self.recent_records.len() == RECENT_RECORD_IDS {
+            self.recent_records.pop_front();
+        }
+        self.recent_records.push_back(record_id);
+        let config = &self.engine.ndvi_processor.config;
+        let Some(input) = sensor_input_from_record(
+            &notification.record,
+            config.red_band_index,
+            config.nir_band_index,
+        )?
+        else {
+            return Ok(Vec::new());
+        };
+        self.handle_input(input).await
+    }
+
+    /// Releases inputs whose window closed before the clock's current time.
+    pub async fn advance(&mut self) -> Result<Vec<OverlayNotification>> {
+        let ready = self.accumulator.advance(self.clock.now());
+        self.run_jobs(ready).await
+    }
+
+    pub async fn flush(&mut self) -> Result<Vec<OverlayNotification>> {
+        let ready = self.accumulator.flush();
+        self.run_jobs(ready).await
+    }
+
+    /// Consumes inputs from an in-process channel until it closes, then
+    /// flushes what is still pending.
+    pub async fn run(mut self, mut inputs: mpsc::Receiver<SensorInput>) -> Result<()> {
+        while let Some(input) = inputs.recv().await {
+            if let Err(e) = self.handle_input(input).await {
+                warn!("Live overlay job failed: {:#}", e);
+            }
+        }
+        self.flush().await?;
+        Ok(())
+    }
+
+    async fn run_jobs(&self, ready: Vec<MatchedInputs>) -> Result<Vec<OverlayNotification>> {
+        let mut completed = Vec::with_capacity(ready.len());
+        for matched in ready {
+            completed.push(self.run_job(&matched).await?);
+        }
+        Ok(completed)
+    }
+
+    async fn run_job(&self, matched: &MatchedInputs) -> Result<OverlayNotification> {
+        let job_id = Uuid::new_v4();
+        let job_dir = self.output_dir.join(job_id.to_string());
+        tokio::fs::create_dir_all(&job_dir).await?;
+
+        let scan = matched.to_scan_data()?;
+        let result = self.engine.process_field_scan(&scan, &job_dir).await?;
+        let position = matched.multispectral.position;
+        let notification = OverlayNotification {
+            job_id,
+            completed_at: result.timestamp,
+            captured_at: matched.captured_at(),
+            overlay_types: result
+                .individual_overlays
+                .iter()
+                .map(|overlay| match overlay {
+                    IndividualOverlayResult::Ndvi(_) => "ndvi",
+                    IndividualOverlayResult::Thermal(_) => "thermal",
+                    IndividualOverlayResult::Lidar(_) => "lidar",
+                })
+                .map(str::to_string)
+                .collect(),
+            source_sensor_ids: matched.source_sensor_ids(),
+            composite_path: result.composite_image_path.display().to_string(),
+            position: Some(GpsCoords {
+                latitude: position.y,
+                longitude: position.x,
+                altitude: position.z,
+            }),
+        };
+        tokio::fs::write(
+            job_dir.join("overlay.json"),
+            serde_json::to_vec_pretty(&notification)?,
+        )
+        .await?;
+        info!("Live overlay {} written to {}", job_id, job_dir.display());
+
+        if let Some((url, http)) = &self.completion {
+            // The notification names its job, so a repeat after a lost
+            // response describes the same overlay.
+            if let Err(e) = http
+                .send_retryable(http.post(url).json(&notification))
+                .await
+            {
+                warn!("Failed to post overlay completion to {}: {}", url, e);
+            }
+        }
+        Ok(notification)
+    }
+}
+
+pub type SharedLiveOverlayService = Arc<Mutex<LiveOverlayService>>;
+
+/// Webhook surface for live mode: `/records` accepts the data collector's
+/// `record.stored` webhook events, `/inputs` accepts a [`SensorInput`]
+/// directly. Both return the overlays the input completed.
+pub fn router(service: SharedLiveOverlayService) -> Router {
+    Router::new()
+        .route("/records", post(receive_record))
+        .route("/inputs", post(receive_input))
+        .with_state(service)
+}
+
+type ApiError = (StatusCode, String);
+
+async fn receive_record(
+    State(service): State<SharedLiveOverlayService>,
+    Json(event): Json<WebhookEvent>,
+) -> Result<(StatusCode, Json<Vec<OverlayNotification>>), ApiError> {
+    if event.kind != WebhookEventKind::RecordStored {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "expected a record.stored event, got {}",
+                event.kind.as_str()
+            ),
+        ));
+    }
+    let notification: CollectorRecordNotification = serde_json::from_value(event.payload)
+        .map_err(|error| (StatusCode::UNPROCESSABLE_ENTITY, error.to_string()))?;
+    let completed = service
+        .lock()
+        .await
+        .handle_record(&notification)
+        .await
+        .map_err(job_error)?;
+    Ok((StatusCode::ACCEPTED, Json(completed)))
+}
+
+async fn receive_input(
+    State(service): State<SharedLiveOverlayService>,
+    Json(input): Json<SensorInput>,
+) -> Result<(StatusCode, Json<Vec<OverlayNotification>>), ApiError> {
+    let completed = service
+        .lock()
+        .await
+        .handle_input(input)
+        .await
+        .map_err(job_error)?;
+    Ok((StatusCode::ACCEPTED, Json(completed)))
+}
+
+fn job_error(error: anyhow::Error) -> ApiError {
+    (StatusCode::UNPROCESSABLE_ENTITY, format!("{error:#}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_760_000_000 + seconds, 0).unwrap()
+    }
+
+    fn gray(width: u32, height: u32, value: u8) -> ImageData {
+        ImageData {
+            width,
+            height,
+            channels: 1,
+            pixel_data: vec![value; (width * height) as usize],
+            format: "u8".to_string(),
+        }
+    }
+
+    fn input(
+        sensor_id: &str,
+        seconds: i64,
+        position: (f64, f64),
+        data: SensorInputData,
+    ) -> SensorInput {
+        SensorInput {
+            sensor_id: sensor_id.to_string(),
+            sensor_type: "test".to_string(),
+            timestamp: at(seconds),
+            position: Point3::new(position.0, position.1, 30.0),
+            orientation: Vector3::zeros(),
+            data,
+        }
+    }
+
+    fn multispectral(sensor_id: &str, seconds: i64, position: (f64, f64)) -> SensorInput {
+        let bands = HashMap::from([
+            ("Red".to_string(), gray(2, 2, 40)),
+            ("NIR".to_string(), gray(2, 2, 200)),
+        ]);
+        input(
+            sensor_id,
+            seconds,
+            position,
+            SensorInputData::MultispectralImage {
+                bands,
+                calibration: MultispectralCalibration {
+                    dark_current: HashMap::new(),
+                    gain: HashMap::new(),
+                    reflectance_panel: HashMap::new(),
+                },
+            },
+        )
+    }
+
+    fn thermal(sensor_id: &str, seconds: i64, position: (f64, f64)) -> SensorInput {
+        input(
+            sensor_id,
+            seconds,
+            position,
+            SensorInputData::ThermalImage {
+                image: gray(2, 2, 30),
+                temperature_range: (0.0, 0.0),
+                emissivity: 0.95,
+            },
+        )
+    }
+
+    const FIELD: (f64, f64) = (-105.0, 40.0);
+    // Roughly 11 m north of FIELD.
+    const NEARBY: (f64, f64) = (-105.0, 40.0001);
+    // Roughly 1.1 km north of FIELD.
+    const FAR: (f64, f64) = (-105.0, 40.01);
+
+    #[test]
+    fn thermal_within_window_pairs_with_multispectral() {
+        let mut accumulator = InputAccumulator::new(MatchWindow::default());
+
+        assert!(accumulator.push(multispectral("ms", 0, FIELD)).is_empty());
+        assert!(accumulator.push(thermal("far", 1, FAR)).is_empty());
+        let ready = accumulator.push(thermal("th", 3, NEARBY));
+
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].source_sensor_ids(), vec!["ms", "th"]);
+        assert_eq!(ready[0].captured_at(), at(0));
+        assert_eq!(accumulator.pending(), 1);
+        let scan = ready[0].to_scan_data().unwrap();
+        assert!((scan.ndvi_data.unwrap().nir_band[0] - 200.0 / 255.0).abs() < 1e-6);
+        assert_eq!(scan.thermal_data.unwrap().raw_thermal_data, vec![30; 4]);
+    }
+
+    #[test]
+    fn expired_window_releases_multispectral_alone_and_drops_thermal() {
+        let mut accumulator = InputAccumulator::new(MatchWindow::default());
+
+        assert!(accumulator.push(multispectral("ms", 0, FIELD)).is_empty());
+        // Too late to pair; its timestamp moves the watermark past the image's window.
+        let ready = accumulator.push(thermal("th", 10, FIELD));
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].multispectral.sensor_id, "ms");
+        assert!(ready[0].thermal.is_none());
+        assert_eq!(accumulator.pending(), 1);
+
+        assert!(accumulator.advance(at(16)).is_empty());
+        assert_eq!(accumulator.pending(), 0);
+    }
+
+    #[test]
+    fn out_of_order_arrivals_pair_on_capture_time() {
+        let mut accumulator = InputAccumulator::new(MatchWindow::default());
+
+        assert!(accumulator.push(thermal("th-late", 20, FIELD)).is_empty());
+        // Captured earlier but delivered later; still inside the watermark window.
+        let ready = accumulator.push(multispectral("ms", 17, NEARBY));
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].source_sensor_ids(), vec!["ms", "th-late"]);
+        assert_eq!(ready[0].captured_at(), at(17));
+
+        // A multispectral image older than the watermark window is released unpaired.
+        let stale = accumulator.push(multispectral("ms-stale", 2, FIELD));
+        assert_eq!(stale.len(), 1);
+        assert!(stale[0].thermal.is_none());
+        assert_eq!(accumulator.pending(), 0);
+    }
+}
@@ -1,16 +1,18 @@
 use anyhow::Result;
 use clap::{Arg, Command};
 use sensor_overlay_engine::{
-    composite::{CompositeConfig, CompositeScanData},
-    lidar_overlay::{HeightColorMapping, LidarConfig, PointCloudData},
-    ndvi::{ColorMapping, FieldScanData, NdviConfig},
-    thermal::{
-        TemperatureRange, ThermalCalibration, ThermalColorPalette, ThermalConfig, ThermalScanData,
-    },
-    CompositeOverlayEngine, LidarOverlayProcessor, NdviProcessor, ThermalProcessor,
+    composite::CompositeScanData, lidar_overlay::PointCloudData, live, ndvi::FieldScanData,
+    thermal::ThermalScanData, CompositeOverlayEngine, LidarOverlayProcessor, LiveOverlayService,
+    MatchWindow, NdviProcessor, OverlayEngineConfig, SpatialBounds, ThermalProcessor,
 };
-use std::path::PathBuf;
+use sensor_overlay_engine::{parse_control_points_csv, RigCalibration};
+use shared::config::CorsConfig;
+use shared::http_client::HttpClientConfig;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio;
+use tokio::sync::Mutex;
 use tracing::{error, info, warn};
 
 #[tokio::main]
@@ -56,6 +58,97 @@ async fn main() -> Result<()> {
                         .help("Configuration file path"),
                 ),
         )
+        .subcommand(
+            Command::new("serve")
+                .about("Generate overlays live from data collector record notifications")
+                .arg(
+                    Arg::new("bind")
+                        .long("bind")
+                        .value_name("ADDR")
+                        .help("Address for the /records and /inputs webhooks")
+                        .default_value("0.0.0.0:8090"),
+                )
+                .arg(
+                    Arg::new("output-dir")
+                        .long("output-dir")
+                        .short('o')
+                        .value_name("DIR")
+                        .help("Output directory for generated overlays")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("completion-url")
+                        .long("completion-url")
+                        .value_name("URL")
+                        .help("URL each completed overlay is POSTed to, e.g. mission control's /overlays/completed"),
+                )
+                .arg(
+                    Arg::new("max-time-gap")
+                        .long("max-time-gap")
+                        .value_name("SECONDS")
+                        .help("Largest capture time gap between inputs composited together")
+                        .value_parser(clap::value_parser!(f64))
+                        .default_value("5"),
+                )
+                .arg(
+                    Arg::new("max-distance")
+                        .long("max-distance")
+                        .value_name("METERS")
+                        .help("Largest ground distance between inputs composited together")
+                        .value_parser(clap::value_parser!(f64))
+                        .default_value("25"),
+                )
+                .arg(
+                    Arg::new("config")
+                        .long("config")
+                        .short('c')
+                        .value_name("FILE")
+                        .help("Configuration file path"),
+                ),
+        )
+        .subcommand(
+            Command::new("calibrate")
+                .about("Fit a thermal-to-RGB rig calibration to matched control points")
+                .arg(
+                    Arg::new("points")
+                        .long("points")
+                        .short('p')
+                        .value_name("FILE")
+                        .help("Matched points, CSV rows of thermal_x,thermal_y,rgb_x,rgb_y or a JSON array")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("rig-id")
+                        .long("rig-id")
+                        .value_name("ID")
+                        .help("Identifier of the camera rig")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .value_name("FILE")
+                        .help("Rig calibration JSON to write")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Inspect the processing configuration")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("example")
+                        .about("Print a default configuration to start from")
+                        .arg(
+                            Arg::new("output")
+                                .long("output")
+                                .short('o')
+                                .value_name("FILE")
+                                .help("Write the JSON to FILE and field documentation to FILE.md"),
+                        ),
+                ),
+        )
         .get_matches();
 
     match matches.subcommand() {
@@ -67,6 +160,32 @@ async fn main() -> Result<()> {
 
             process_sensor_data(input_dir, output_dir, overlay_types, config_file).await?;
         }
+        Some(("serve", sub_matches)) => {
+            let window = MatchWindow {
+                max_time_gap_secs: *sub_matches.get_one::<f64>("max-time-gap").unwrap(),
+                max_distance_m: *sub_matches.get_one::<f64>("max-distance").unwrap(),
+            };
+            serve_live_overlays(
+                sub_matches.get_one::<String>("bind").unwrap(),
+                PathBuf::from(sub_matches.get_one::<String>("output-dir").unwrap()),
+                sub_matches.get_one::<String>("completion-url"),
+                window,
+                sub_matches.get_one::<String>("config"),
+            )
+            .await?;
+        }
+        Some(("calibrate", sub_matches)) => {
+            calibrate_rig(
+                Path::new(sub_matches.get_one::<String>("points").unwrap()),
+                sub_matches.get_one::<String>("rig-id").unwrap(),
+                Path::new(sub_matches.get_one::<String>("output").unwrap()),
+            )?;
+        }
+        Some(("config", sub_matches)) => {
+            if let Some(("example", example_matches)) = sub_matches.subcommand() {
+                write_example_config(example_matches.get_one::<String>("output")).await?;
+            }
+        }
         _ => {
             eprintln!("No subcommand provided. Use --help for usage information.");
             std::process::exit(1);
@@ -96,58 +215,16 @@ async fn process_sensor_data(
     let requested_types: Vec<&str> = overlay_types.split(',').collect();
     info!("Requested overlay types: {:?}", requested_types);
 
-    // Initialize processors with concrete configurations
-    let ndvi_config = NdviConfig {
-        red_band_index: 0,
-        nir_band_index: 1,
-        output_format: "PNG".to_string(),
-        color_mapping: ColorMapping {
-            low_vegetation: [255, 0, 0],
-            medium_vegetation: [255, 255, 0],
-            high_vegetation: [0, 255, 0],
-            water: [0, 0, 255],
-            soil: [139, 69, 19],
-        },
-    };
+    let ndvi_processor = NdviProcessor::new(config.ndvi);
+    let thermal_processor = ThermalProcessor::new(config.thermal);
+    let lidar_processor = LidarOverlayProcessor::new(config.lidar);
 
-    let thermal_config = ThermalConfig {
-        temperature_range: TemperatureRange {
-            min_celsius: -10.0,
-            max_celsius: 50.0,
-        },
-        color_palette: ThermalColorPalette {
-            cold: [0, 0, 255],
-            cool: [0, 255, 255],
-            moderate: [0, 255, 0],
-            warm: [255, 255, 0],
-            hot: [255, 0, 0],
-        },
-        calibration: ThermalCalibration {
-            offset: 0.0,
-            scale: 1.0,
-            ambient_temp: 20.0,
-        },
-    };
-
-    let lidar_config = LidarConfig {
-        point_cloud_resolution: 0.1,
-        height_color_mapping: HeightColorMapping {
-            ground_level: [139, 69, 19, 255],
-            low_vegetation: [50, 205, 50, 255],
-            medium_vegetation: [34, 139, 34, 255],
-            high_vegetation: [0, 100, 0, 255],
-            obstacles: [255, 0, 0, 255],
-        },
-        occupancy_grid_resolution: 0.2,
-        max_range: 100.0,
-    };
-
-    let ndvi_processor = NdviProcessor::new(ndvi_config);
-    let thermal_processor = ThermalProcessor::new(thermal_config);
-    let lidar_processor = LidarOverlayProcessor::new(lidar_config);
-
-    let composite_engine =
-        CompositeOverlayEngine::new(config, ndvi_processor, thermal_processor, lidar_processor);
+    let composite_engine = CompositeOverlayEngine::new(
+        config.composite,
+        ndvi_processor,
+        thermal_processor,
+        lidar_processor,
+    );
 
     // Scan for sensor data files
     let scan_data = load_sensor_data(&input_dir).await?;
@@ -176,6 +253,18 @@ async fn process_sensor_data(
 
                 // Print analysis results
                 print_analysis_results(&result.analysis);
+
+                if let Err(e) = write_colormap_overlays(
+                    &composite_engine,
+                    data,
+                    &config.colormaps,
+                    &scan_output_dir,
+                ) {
+                    warn!(
+                        "Failed to render colormap overlays for scan {}: {}",
+                        index, e
+                    );
+                }
             }
             Err(e) => {
                 error!("Failed to process scan {}: {}", index, e);
@@ -187,16 +276,150 @@ async fn process_sensor_data(
     Ok(())
 }
 
-async fn load_config(config_file: Option<&String>) -> Result<CompositeConfig> {
+async fn serve_live_overlays(
+    bind: &str,
+    output_dir: PathBuf,
+    completion_url: Option<&String>,
+    window: MatchWindow,
+    config_file: Option<&String>,
+) -> Result<()> {
+    let config = load_config(config_file).await?;
+    tokio::fs::create_dir_all(&output_dir).await?;
+    let engine = CompositeOverlayEngine::new(
+        config.composite,
+        NdviProcessor::new(config.ndvi),
+        ThermalProcessor::new(config.thermal),
+        LidarOverlayProcessor::new(config.lidar),
+    );
+    let mut service = LiveOverlayService::new(engine, window, output_dir);
+    if let Some(url) = completion_url {
+        service = service.with_completion_url(url.clone(), HttpClientConfig::default())?;
+    }
+    let service = Arc::new(Mutex::new(service));
+
+    // Release images whose pairing window has closed even when no further
+    // inputs arrive, e.g. the last capture of a flight.
+    let ticker = Arc::clone(&service);
+    let tick_period =
+        Duration::from_secs_f64(ticker.lock().await.window().max_time_gap_secs.max(1.0));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tick_period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = ticker.lock().await.advance().await {
+                warn!("Live overlay job failed: {:#}", e);
+            }
+        }
+    });
+
+    let cors = CorsConfig::from_env()?;
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    info!("Live overlay webhooks listening on {}", bind);
+    axum::serve(listener, live::router(service).layer(cors.layer())).await?;
+    Ok(())
+}
+
+async fn load_config(config_file: Option<&String>) -> Result<OverlayEngineConfig> {
     if let Some(config_path) = config_file {
         info!("Loading configuration from: {}", config_path);
         let config_data = tokio::fs::read_to_string(config_path).await?;
-        let config: CompositeConfig = serde_json::from_str(&config_data)?;
+        let config = OverlayEngineConfig::from_json_str(&config_data)?;
         Ok(config)
     } else {
         info!("Using default configuration");
-        Ok(CompositeConfig::default())
+        Ok(OverlayEngineConfig::default())
+    }
+}
+
+async fn write_example_config(output: Option<&String>) -> Result<()> {
+    let Some(output) = output else {
+        println!("{}", OverlayEngineConfig::example_json());
+        return Ok(());
+    };
+    let doc_path = format!("{output}.md");
+    tokio::fs::write(output, OverlayEngineConfig::example_json()).await?;
+    tokio::fs::write(&doc_path, OverlayEngineConfig::example_doc()).await?;
+    info!("Wrote example configuration to {} ({})", output, doc_path);
+    Ok(())
+}
+
+fn calibrate_rig(points: &Path, rig_id: &str, output: &Path) -> Result<()> {
+    let text = std::fs::read_to_string(points)?;
+    let pairs = if points
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        serde_json::from_str(&text)?
+    } else {
+        parse_control_points_csv(&text)?
+    };
+    let calibration = RigCalibration::from_control_points(rig_id, pairs)?;
+    calibration.save(output)?;
+    info!(
+        "Wrote calibration for rig {} to {} ({} points, RMS residual {:.2} px)",
+        rig_id,
+        output.display(),
+        calibration.control_points.len(),
+        calibration.residual_px
+    );
+    Ok(())
+}
+
+/// Renders the NDVI and thermal value overlays of a scan with the configured
+/// colormaps next to its composite.
+fn write_colormap_overlays(
+    engine: &CompositeOverlayEngine,
+    scan: &CompositeScanData,
+    colormaps: &sensor_overlay_engine::config::ColormapSettings,
+    output_dir: &Path,
+) -> Result<()> {
+    if let Some(ndvi) = &scan.ndvi_data {
+        let values = engine
+            .ndvi_processor
+            .calculate_ndvi(&ndvi.red_band, &ndvi.nir_band)?;
+        let rendered = engine.ndvi_processor.render_colormap_overlay(
+            &values,
+            ndvi.width,
+            ndvi.height,
+            &coordinate_bounds(&ndvi.gps_coordinates),
+            &colormaps.ndvi,
+        )?;
+        rendered.image.save(output_dir.join("ndvi_colormap.png"))?;
+    }
+    if let Some(thermal) = &scan.thermal_data {
+        let values = engine
+            .thermal_processor
+            .raw_to_temperature(&thermal.raw_thermal_data)?;
+        let rendered = engine.thermal_processor.render_colormap_overlay(
+            &values,
+            thermal.width,
+            thermal.height,
+            &coordinate_bounds(&thermal.gps_coordinates),
+            &colormaps.thermal,
+        )?;
+        rendered
+            .image
+            .save(output_dir.join("thermal_colormap.png"))?;
+    }
+    Ok(())
+}
+
+fn coordinate_bounds(coordinates: &[nalgebra::Point3<f64>]) -> SpatialBounds {
+    let mut bounds = SpatialBounds {
+        min_x: f64::INFINITY,
+        min_y: f64::INFINITY,
+        max_x: f64::NEG_INFINITY,
+        max_y: f64::NEG_INFINITY,
+        min_z: None,
+        max_z: None,
+    };
+    for point in coordinates {
+        bounds.min_x = bounds.min_x.min(point.x);
+        bounds.min_y = bounds.min_y.min(point.y);
+        bounds.max_x = bounds.max_x.max(point.x);
+        bounds.max_y = bounds.max_y.max(point.y);
     }
+    bounds
 }
 
 async fn load_sensor_data(input_dir: &PathBuf) -> Result<Vec<CompositeScanData>> {
@@ -6,6 +6,8 @@ use chrono::Utc;
 use image::{ImageBuffer, Rgb, RgbImage};
 use nalgebra::Point3;
 use serde::{Deserialize, Serialize};
+use shared::legend::{categorical_legend, legend_path_for, Legend};
+use shared::palette::okabe_ito;
 use std::collections::HashMap;
 use std::path::Path;
 use uuid::Uuid;
@@ -16,6 +18,7 @@ pub struct NdviProcessor {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct NdviConfig {
     pub red_band_index: usize,
     pub nir_band_index: usize,
@@ -24,6 +27,7 @@ pub struct NdviConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ColorMapping {
     pub low_vegetation: [u8; 3],
     pub medium_vegetation: [u8; 3],
@@ -32,19 +36,27 @@ pub struct ColorMapping {
     pub soil: [u8; 3],
 }
 
+/// Okabe-Ito class colours, which stay distinct with red-green colour
+/// blindness.
+impl Default for ColorMapping {
+    fn default() -> Self {
+        Self {
+            low_vegetation: okabe_ito::ORANGE,
+            medium_vegetation: okabe_ito::YELLOW,
+            high_vegetation: okabe_ito::BLUISH_GREEN,
+            water: okabe_ito::BLUE,
+            soil: okabe_ito::VERMILLION,
+        }
+    }
+}
+
 impl Default for NdviConfig {
     fn default() -> Self {
         Self {
             red_band_index: 0,
             nir_band_index: 1,
             output_format: "PNG".to_string(),
-            color_mapping: ColorMapping {
-                low_vegetation: [255, 0, 0],
-                medium_vegetation: [255, 255, 0],
-                high_vegetation: [0, 255, 0],
-                water: [0, 0, 255],
-                soil: [139, 69, 19],
-            },
+            color_mapping: ColorMapping::default(),
         }
     }
 }
@@ -120,6 +132,18 @@ impl NdviProcessor {
         )
     }
 
+    /// Swatch table of the classes [`Self::generate_visualization`] colours.
+    pub fn visualization_legend(&self) -> Legend {
+        let colors = &self.config.color_mapping;
+        categorical_legend([
+            ("Water (< -0.1)", colors.water),
+            ("Soil (-0.1 to 0.2)", colors.soil),
+            ("Low vegetation (0.2 to 0.4)", colors.low_vegetation),
+            ("Medium vegetation (0.4 to 0.6)", colors.medium_vegetation),
+            ("High vegetation (>= 0.6)", colors.high_vegetation),
+        ])
+    }
+
     /// Map NDVI value to color based on vegetation health
     fn map_ndvi_to_color(&self, ndvi: f32) -> [u8; 3] {
         match ndvi {
@@ -142,71 +166,68 @@ impl NdviProcessor {
         let visualization =
             self.generate_visualization(&ndvi_values, scan_data.width, scan_data.height)?;
 
-        // Save visualization
+        // Save visualization and its legend
         visualization.save(output_path)?;
+        self.visualization_legend()
+            .write_png(&legend_path_for(output_path))?;
 
         // Calculate statistics
         let stats = self.calculate_statistics(&ndvi_values);
 
         Ok(NdviOverlayResult {
             ndvi_values,
+            width: scan_data.width,
+            height: scan_data.height,
             statistics: stats,
             output_path: output_path.to_path_buf(),
             timestamp: chrono::Utc::now(),
         })
     }
 
-    /// Calculate NDVI statistics for the processed area
+    /// Calculate NDVI statistics for the processed area. Masked (NaN) and
+    /// infinite pixels are left out of every figure but `total_pixels`.
     fn calculate_statistics(&self, ndvi_values: &[f32]) -> NdviStatistics {
-        let valid_values: Vec<f32> = ndvi_values
+        let mut sorted: Vec<f32> = ndvi_values
             .iter()
             .filter(|&&v| v.is_finite())
             .copied()
             .collect();
 
-        if valid_values.is_empty() {
-            return NdviStatistics::default();
+        if sorted.is_empty() {
+            return NdviStatistics {
+                total_pixels: ndvi_values.len(),
+                ..NdviStatistics::default()
+            };
         }
 
-        let mean = valid_values.iter().sum::<f32>() / valid_values.len() as f32;
-        let mut sorted = valid_values.clone();
-        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        sorted.sort_by(f32::total_cmp);
+        let valid_pixels = sorted.len();
+        let mean = sorted.iter().sum::<f32>() / valid_pixels as f32;
 
-        let median = if sorted.len() % 2 == 0 {
-            (sorted[sorted.len() / 2 - 1] + sorted[sorted.len() / 2]) / 2.0
+        let median = if valid_pixels % 2 == 0 {
+            (sorted[valid_pixels / 2 - 1] + sorted[valid_pixels / 2]) / 2.0
         } else {
-            sorted[sorted.len() / 2]
+            sorted[valid_pixels / 2]
         };
 
-        let min = *sorted.first().unwrap();
-        let max = *sorted.last().unwrap();
+        let min = sorted[0];
+        let max = sorted[valid_pixels - 1];
 
         // Calculate vegetation coverage percentages
-        let total_pixels = valid_values.len() as f32;
-        let high_vegetation =
-            valid_values.iter().filter(|&&v| v >= 0.6).count() as f32 / total_pixels * 100.0;
-        let medium_vegetation = valid_values
-            .iter()
-            .filter(|&&v| v >= 0.4 && v < 0.6)
-            .count() as f32
-            / total_pixels
-            * 100.0;
-        let low_vegetation = valid_values
-            .iter()
-            .filter(|&&v| v >= 0.2 && v < 0.4)
-            .count() as f32
-            / total_pixels
-            * 100.0;
+        let coverage_percent = |range: std::ops::Range<f32>| {
+            sorted.iter().filter(|v| range.contains(v)).count() as f32 / valid_pixels as f32 * 100.0
+        };
 
         NdviStatistics {
             mean,
             median,
             min,
             max,
-            high_vegetation_percent: high_vegetation,
-            medium_vegetation_percent: medium_vegetation,
-            low_vegetation_percent: low_vegetation,
-            total_pixels: valid_values.len(),
+            high_vegetation_percent: coverage_percent(0.6..f32::INFINITY),
+            medium_vegetation_percent: coverage_percent(0.4..0.6),
+            low_vegetation_percent: coverage_percent(0.2..0.4),
+            total_pixels: ndvi_values.len(),
+            valid_pixel_count: valid_pixels,
         }
     }
 }
@@ -262,6 +283,8 @@ pub struct FieldScanData {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NdviOverlayResult {
     pub ndvi_values: Vec<f32>,
+    pub width: u32,
+    pub height: u32,
     pub statistics: NdviStatistics,
     pub output_path: std::path::PathBuf,
     pub timestamp: chrono::DateTime<chrono::Utc>,
@@ -277,6 +300,9 @@ pub struct NdviStatistics {
     pub medium_vegetation_percent: f32,
     pub low_vegetation_percent: f32,
     pub total_pixels: usize,
+    /// Pixels with a finite NDVI; the rest were masked out.
+    #[serde(default)]
+    pub valid_pixel_count: usize,
 }
 
 #[cfg(test)]
@@ -294,17 +320,38 @@ mod tests {
         assert!((ndvi[0] - 0.5).abs() < 0.001); // (0.3-0.1)/(0.3+0.1) = 0.5
     }
 
+    #[test]
+    fn statistics_skip_masked_pixels() {
+        let processor = NdviProcessor::new(NdviConfig::default());
+
+        let stats =
+            processor.calculate_statistics(&[0.2, f32::NAN, 0.8, f32::INFINITY, 0.5, f32::NAN]);
+        assert_eq!(stats.total_pixels, 6);
+        assert_eq!(stats.valid_pixel_count, 3);
+        assert!((stats.mean - 0.5).abs() < 1e-6);
+        assert_eq!((stats.min, stats.median, stats.max), (0.2, 0.5, 0.8));
+
+        let masked = processor.calculate_statistics(&[f32::NAN; 4]);
+        assert_eq!(masked.total_pixels, 4);
+        assert_eq!(masked.valid_pixel_count, 0);
+        assert_eq!(masked.mean, 0.0);
+    }
+
     #[test]
     fn test_color_mapping() {
         let processor = NdviProcessor::new(NdviConfig::default());
 
         // Test water (negative NDVI)
         let water_color = processor.map_ndvi_to_color(-0.2);
-        assert_eq!(water_color, [0, 0, 255]);
+        assert_eq!(water_color, [0, 114, 178]);
 
         // Test high vegetation
         let veg_color = processor.map_ndvi_to_color(0.8);
-        assert_eq!(veg_color, [0, 255, 0]);
+        assert_eq!(veg_color, [0, 158, 115]);
+
+        let legend = processor.visualization_legend();
+        assert_eq!(legend.labels()[0], "Water (< -0.1)");
+        assert_eq!(legend.entries[4].color, veg_color);
     }
 
     #[test]
@@ -16,6 +16,7 @@ pub struct ThermalProcessor {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ThermalConfig {
     pub temperature_range: TemperatureRange,
     pub color_palette: ThermalColorPalette,
@@ -23,12 +24,14 @@ pub struct ThermalConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TemperatureRange {
     pub min_celsius: f32,
     pub max_celsius: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ThermalColorPalette {
     pub cold: [u8; 3],     // Blue
     pub cool: [u8; 3],     // Cyan
@@ -38,6 +41,7 @@ pub struct ThermalColorPalette {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ThermalCalibration {
     pub offset: f32,
     pub scale: f32,
@@ -198,6 +202,8 @@ impl ThermalProcessor {
 
         Ok(ThermalOverlayResult {
             temperatures,
+            width: scan_data.width,
+            height: scan_data.height,
             statistics: stats,
             anomalies,
             output_path: output_path.to_path_buf(),
@@ -205,31 +211,40 @@ impl ThermalProcessor {
         })
     }
 
-    /// Calculate thermal statistics
+    /// Calculate thermal statistics over the finite temperatures; masked
+    /// (NaN) and infinite pixels only count towards `total_pixels`.
     fn calculate_thermal_statistics(&self, temperatures: &[f32]) -> ThermalStatistics {
-        if temperatures.is_empty() {
-            return ThermalStatistics::default();
+        let mut sorted: Vec<f32> = temperatures
+            .iter()
+            .copied()
+            .filter(|temp| temp.is_finite())
+            .collect();
+        if sorted.is_empty() {
+            return ThermalStatistics {
+                total_pixels: temperatures.len(),
+                ..ThermalStatistics::default()
+            };
         }
 
-        let mean = temperatures.iter().sum::<f32>() / temperatures.len() as f32;
-        let mut sorted = temperatures.to_vec();
-        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        sorted.sort_by(f32::total_cmp);
+        let valid_pixels = sorted.len();
+        let mean = sorted.iter().sum::<f32>() / valid_pixels as f32;
 
-        let median = if sorted.len() % 2 == 0 {
-            (sorted[sorted.len() / 2 - 1] + sorted[sorted.len() / 2]) / 2.0
+        let median = if valid_pixels % 2 == 0 {
+            (sorted[valid_pixels / 2 - 1] + sorted[valid_pixels / 2]) / 2.0
         } else {
-            sorted[sorted.len() / 2]
+            sorted[valid_pixels / 2]
         };
 
         let min = sorted[0];
-        let max = sorted[sorted.len() - 1];
+        let max = sorted[valid_pixels - 1];
 
         // Calculate standard deviation
-        let variance = temperatures
+        let variance = sorted
             .iter()
             .map(|&temp| (temp - mean).powi(2))
             .sum::<f32>()
-            / temperatures.len() as f32;
+            / valid_pixels as f32;
         let std_dev = variance.sqrt();
 
         ThermalStatistics {
@@ -238,15 +253,10 @@ impl ThermalProcessor {
             min,
             max,
             std_dev,
-            hot_spots: temperatures
-                .iter()
-                .filter(|&&t| t > mean + 2.0 * std_dev)
-                .count(),
-            cold_spots: temperatures
-                .iter()
-                .filter(|&&t| t < mean - 2.0 * std_dev)
-                .count(),
+            hot_spots: sorted.iter().filter(|&&t| t > mean + 2.0 * std_dev).count(),
+            cold_spots: sorted.iter().filter(|&&t| t < mean - 2.0 * std_dev).count(),
             total_pixels: temperatures.len(),
+            valid_pixel_count: valid_pixels,
         }
     }
 
@@ -365,6 +375,8 @@ pub struct ThermalScanData {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThermalOverlayResult {
     pub temperatures: Vec<f32>,
+    pub width: u32,
+    pub height: u32,
     pub statistics: ThermalStatistics,
     pub anomalies: Vec<ThermalAnomaly>,
     pub output_path: std::path::PathBuf,
@@ -381,6 +393,9 @@ pub struct ThermalStatistics {
     pub hot_spots: usize,
     pub cold_spots: usize,
     pub total_pixels: usize,
+    /// Pixels with a finite temperature; the rest were masked out.
+    #[serde(default)]
+    pub valid_pixel_count: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,232 @@
+use axum::{
+    body::Body,
+    http::{header, Request, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use image::{ImageBuffer, Luma, Rgb};
+use sensor_overlay_engine::{
+    live, CompositeOverlayEngine, LidarOverlayProcessor, LiveClock, LiveOverlayService,
+    MatchWindow, NdviProcessor, OverlayEngineConfig, ThermalProcessor,
+};
+use shared::http_client::HttpClientConfig;
+use shared::schemas::OverlayNotification;
+use shared::webhooks::{WebhookEvent, WebhookEventKind};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+fn engine() -> CompositeOverlayEngine {
+    let config = OverlayEngineConfig::default();
+    CompositeOverlayEngine::new(
+        config.composite,
+        NdviProcessor::new(config.ndvi),
+        ThermalProcessor::new(config.thermal),
+        LidarOverlayProcessor::new(config.lidar),
+    )
+}
+
+/// The data collector's `record.stored` webhook event for a stored image file.
+fn record_event(
+    session_id: Uuid,
+    data_type: &str,
+    sensor_id: &str,
+    captured_at: &str,
+    latitude: f64,
+    file_path: &Path,
+) -> WebhookEvent {
+    let payload = serde_json::json!({
+        "session_id": session_id,
+        "record": {
+            "id": Uuid::new_v4(),
+            "session_id": session_id,
+            "flight_id": Uuid::new_v4(),
+            "drone_id": Uuid::new_v4(),
+            "timestamp": captured_at,
+            "data_type": data_type,
+            "payload": { "MediaFile": {
+                "file_type": "png",
+                "dimensions": [4, 4],
+                "duration_seconds": null,
+                "compression": null
+            } },
+            "sensor_id": sensor_id,
+            "gps_coords": { "latitude": latitude, "longitude": -105.0, "altitude": 30.0 },
+            "calibration_ref": "cal-1",
+            "metadata": {},
+            "file_path": file_path,
+            "size_bytes": 64
+        }
+    });
+    WebhookEvent::new(WebhookEventKind::RecordStored, payload)
+}
+
+fn post_event(event: &WebhookEvent) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/records")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(event).unwrap()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn collector_records_produce_exactly_one_composite_overlay_job() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let multispectral_path = temp_dir.path().join("ms.png");
+    ImageBuffer::from_pixel(4, 4, Rgb([40u8, 200, 0]))
+        .save(&multispectral_path)
+        .unwrap();
+    let thermal_path = temp_dir.path().join("thermal.png");
+    ImageBuffer::from_pixel(4, 4, Luma([30u16]))
+        .save(&thermal_path)
+        .unwrap();
+
+    // Stands in for mission control's completion endpoint.
+    let (completed_tx, mut completed) = mpsc::unbounded_channel();
+    let mission_control = Router::new().route(
+        "/overlays/completed",
+        post(move |Json(overlay): Json<OverlayNotification>| async move {
+            completed_tx.send(overlay).unwrap();
+            StatusCode::ACCEPTED
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, mission_control).await });
+
+    let output_dir = temp_dir.path().join("overlays");
+    let service = LiveOverlayService::new(engine(), MatchWindow::default(), output_dir.clone())
+        .with_completion_url(
+            format!("http://{address}/overlays/completed"),
+            HttpClientConfig::default(),
+        )
+        .unwrap();
+    let app = live::router(Arc::new(Mutex::new(service)));
+
+    let session_id = Uuid::new_v4();
+    let feed = [
+        (
+            "MultispectralImage",
+            "ms-1",
+            "2026-06-01T10:00:00Z",
+            40.0,
+            &multispectral_path,
+        ),
+        // ~1 km away: never pairs with the image above.
+        (
+            "ThermalImage",
+            "th-far",
+            "2026-06-01T10:00:01Z",
+            40.01,
+            &thermal_path,
+        ),
+        (
+            "ThermalImage",
+            "th-1",
+            "2026-06-01T10:00:02Z",
+            40.0001,
+            &thermal_path,
+        ),
+    ];
+    let mut jobs = Vec::new();
+    let events: Vec<WebhookEvent> = feed
+        .into_iter()
+        .map(|(data_type, sensor_id, captured_at, latitude, path)| {
+            record_event(
+                session_id,
+                data_type,
+                sensor_id,
+                captured_at,
+                latitude,
+                path,
+            )
+        })
+        .collect();
+    // Each event is delivered twice, as after a lost webhook response; the
+    // repeats must not pair again.
+    for event in events.iter().chain(&events) {
+        let response = app.clone().oneshot(post_event(event)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::ACCEPTED);
+        let body = axum::body::to_bytes(response.into_body(), 64 * 1024)
+            .await
+            .unwrap();
+        jobs.extend(serde_json::from_slice::<Vec<OverlayNotification>>(&body).unwrap());
+    }
+
+    assert_eq!(jobs.len(), 1);
+    let job = &jobs[0];
+    assert_eq!(job.overlay_types, vec!["ndvi", "thermal"]);
+    assert_eq!(job.source_sensor_ids, vec!["ms-1", "th-1"]);
+    assert!(Path::new(&job.composite_path).exists());
+    assert!(output_dir
+        .join(job.job_id.to_string())
+        .join("overlay.json")
+        .exists());
+
+    let forwarded = tokio::time::timeout(Duration::from_secs(5), completed.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(&forwarded, job);
+    assert!(completed.try_recv().is_err());
+}
+
+/// A clock the test moves by hand.
+#[derive(Default)]
+struct ManualClock(std::sync::Mutex<Option<DateTime<Utc>>>);
+
+impl ManualClock {
+    fn set(&self, rfc3339: &str) {
+        *self.0.lock().unwrap() = Some(rfc3339.parse().unwrap());
+    }
+}
+
+impl LiveClock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0.lock().unwrap().unwrap()
+    }
+}
+
+#[tokio::test]
+async fn unpaired_image_is_released_once_the_clock_closes_its_window() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let multispectral_path = temp_dir.path().join("ms.png");
+    ImageBuffer::from_pixel(4, 4, Rgb([40u8, 200, 0]))
+        .save(&multispectral_path)
+        .unwrap();
+    let clock = Arc::new(ManualClock::default());
+    let service = Arc::new(Mutex::new(
+        LiveOverlayService::new(
+            engine(),
+            MatchWindow::default(),
+            temp_dir.path().join("overlays"),
+        )
+        .with_clock(clock.clone()),
+    ));
+
+    let response = live::router(Arc::clone(&service))
+        .oneshot(post_event(&record_event(
+            Uuid::new_v4(),
+            "MultispectralImage",
+            "ms-1",
+            "2026-06-01T10:00:00Z",
+            40.0,
+            &multispectral_path,
+        )))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+
+    clock.set("2026-06-01T10:00:04Z");
+    assert!(service.lock().await.advance().await.unwrap().is_empty());
+    clock.set("2026-06-01T10:00:06Z");
+    let released = service.lock().await.advance().await.unwrap();
+    assert_eq!(released.len(), 1);
+    assert_eq!(released[0].overlay_types, vec!["ndvi"]);
+    assert_eq!(released[0].source_sensor_ids, vec!["ms-1"]);
+}
@@ -0,0 +1,487 @@
+//! Versioned layout for the JSON artifacts written to disk (LiDAR scans,
+//! image metadata, NDVI results).
+//!
+//! Writers wrap an artifact in an [`ArtifactEnvelope`] carrying its schema
+//! name and version. Readers also accept the legacy layout, the bare struct
+//! with no envelope, as version 1, and upgrade older documents through the
+//! [`MigrationRegistry`] before deserializing.
+
+use crate::schemas::{LidarScan, MultispectralImage, NdviResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Version given to documents written before artifacts carried an envelope.
+pub const LEGACY_VERSION: u32 = 1;
+
+/// A kind of on-disk artifact and the version writers currently emit.
+#[derive(Debug, Clone, Copy)]
+pub struct ArtifactSchema {
+    pub name: &'static str,
+    pub version: u32,
+    /// Top-level fields that identify a legacy document of this schema.
+    legacy_fields: &'static [&'static str],
+    /// Whether an upgraded document deserializes into the current struct.
+    check: fn(Value) -> Result<(), serde_json::Error>,
+}
+
+impl ArtifactSchema {
+    fn matches_legacy(&self, value: &Value) -> bool {
+        value.as_object().is_some_and(|fields| {
+            self.legacy_fields
+                .iter()
+                .all(|field| fields.contains_key(*field))
+        })
+    }
+}
+
+fn check_as<T: DeserializeOwned>(value: Value) -> Result<(), serde_json::Error> {
+    serde_json::from_value::<T>(value).map(drop)
+}
+
+pub const LIDAR_SCAN_SCHEMA: ArtifactSchema = ArtifactSchema {
+    name: "lidar_scan",
+    version: 2,
+    legacy_fields: &["timestamp", "points", "scan_id"],
+    check: check_as::<LidarScan>,
+};
+
+pub const MULTISPECTRAL_IMAGE_SCHEMA: ArtifactSchema = ArtifactSchema {
+    name: "multispectral_image",
+    version: 2,
+    legacy_fields: &["metadata", "file_paths", "image_id"],
+    check: check_as::<MultispectralImage>,
+};
+
+pub const NDVI_RESULT_SCHEMA: ArtifactSchema = ArtifactSchema {
+    name: "ndvi_result",
+    version: 2,
+    legacy_fields: &["source_images", "output_path", "mean_ndvi"],
+    check: check_as::<NdviResult>,
+};
+
+/// Every schema [`migrate_dir`] recognises.
+pub const ARTIFACT_SCHEMAS: [ArtifactSchema; 3] = [
+    LIDAR_SCAN_SCHEMA,
+    MULTISPECTRAL_IMAGE_SCHEMA,
+    NDVI_RESULT_SCHEMA,
+];
+
+fn schema_named(name: &str) -> Option<&'static ArtifactSchema> {
+    ARTIFACT_SCHEMAS.iter().find(|schema| schema.name == name)
+}
+
+/// A struct stored on disk as a versioned artifact.
+pub trait VersionedArtifact: Serialize + DeserializeOwned {
+    const SCHEMA: ArtifactSchema;
+}
+
+impl VersionedArtifact for LidarScan {
+    const SCHEMA: ArtifactSchema = LIDAR_SCAN_SCHEMA;
+}
+
+impl VersionedArtifact for MultispectralImage {
+    const SCHEMA: ArtifactSchema = MULTISPECTRAL_IMAGE_SCHEMA;
+}
+
+impl VersionedArtifact for NdviResult {
+    const SCHEMA: ArtifactSchema = NDVI_RESULT_SCHEMA;
+}
+
+#[derive(Debug, Error)]
+pub enum ArtifactSchemaError {
+    #[error("expected a {expected} artifact, found {found}")]
+    SchemaMismatch {
+        expected: &'static str,
+        found: String,
+    },
+
+    #[error("unknown artifact schema {0}")]
+    UnknownSchema(String),
+
+    #[error("{schema} version {version} is newer than the supported version {latest}")]
+    UnsupportedVersion {
+        schema: String,
+        version: u32,
+        latest: u32,
+    },
+
+    #[error("no migration for {schema} from version {from_version}")]
+    MissingMigration { schema: String, from_version: u32 },
+
+    #[error("{schema} migration from version {from_version} failed: {message}")]
+    MigrationFailed {
+        schema: String,
+        from_version: u32,
+        message: String,
+    },
+
+    #[error("artifact JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("artifact I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// The current on-disk layout: `{ "schema": ..., "version": ..., "data": ... }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArtifactEnvelope<T = Value> {
+    pub schema: String,
+    pub version: u32,
+    pub data: T,
+}
+
+/// Either layout an artifact file may be in.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StoredArtifact {
+    Versioned(ArtifactEnvelope),
+    Legacy(Value),
+}
+
+/// Rewrites a document's data from one version to the next.
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+/// Migrations keyed by `(schema, from_version)`, each producing the data of
+/// `from_version + 1`.
+#[derive(Debug, Clone)]
+pub struct MigrationRegistry {
+    migrations: HashMap<(&'static str, u32), Migration>,
+}
+
+impl Default for MigrationRegistry {
+    /// The migrations for every schema in [`ARTIFACT_SCHEMAS`].
+    fn default() -> Self {
+        Self {
+            migrations: HashMap::new(),
+        }
+        // Legacy scans and NDVI results hold the same fields as version 2;
+        // only the envelope is new.
+        .with_migration(LIDAR_SCAN_SCHEMA.name, 1, Ok)
+        .with_migration(MULTISPECTRAL_IMAGE_SCHEMA.name, 1, spell_out_spatial_ref)
+        .with_migration(NDVI_RESULT_SCHEMA.name, 1, Ok)
+    }
+}
+
+/// Image metadata written before georeferencing has no `spatial_ref`.
+fn spell_out_spatial_ref(mut data: Value) -> Result<Value, String> {
+    let metadata = data
+        .get_mut("metadata")
+        .and_then(Value::as_object_mut)
+        .ok_or("metadata is not an object")?;
+    metadata.entry("spatial_ref").or_insert(Value::Null);
+    Ok(data)
+}
+
+impl MigrationRegistry {
+    pub fn with_migration(
+        mut self,
+        schema: &'static str,
+        from_version: u32,
+        migration: Migration,
+    ) -> Self {
+        self.migrations.insert((schema, from_version), migration);
+        self
+    }
+
+    /// Runs `data` through each migration from `version` up to `latest`.
+    pub fn upgrade(
+        &self,
+        schema: &str,
+        version: u32,
+        latest: u32,
+        mut data: Value,
+    ) -> Result<Value, ArtifactSchemaError> {
+        if version > latest {
+            return Err(ArtifactSchemaError::UnsupportedVersion {
+                schema: schema.to_string(),
+                version,
+                latest,
+            });
+        }
+        for from_version in version..latest {
+            let migration = self
+                .migrations
+                .get(&(schema, from_version))
+                .ok_or_else(|| ArtifactSchemaError::MissingMigration {
+                    schema: schema.to_string(),
+                    from_version,
+                })?;
+            data = migration(data).map_err(|message| ArtifactSchemaError::MigrationFailed {
+                schema: schema.to_string(),
+                from_version,
+                message,
+            })?;
+        }
+        Ok(data)
+    }
+
+    /// Reads a `T` from either layout, upgrading older versions.
+    pub fn read<T: VersionedArtifact>(&self, json: &str) -> Result<T, ArtifactSchemaError> {
+        let (schema, version, data) = match serde_json::from_str(json)? {
+            StoredArtifact::Versioned(envelope) => {
+                (envelope.schema, envelope.version, envelope.data)
+            }
+            StoredArtifact::Legacy(data) => (T::SCHEMA.name.to_string(), LEGACY_VERSION, data),
+        };
+        if schema != T::SCHEMA.name {
+            return Err(ArtifactSchemaError::SchemaMismatch {
+                expected: T::SCHEMA.name,
+                found: schema,
+            });
+        }
+        let data = self.upgrade(&schema, version, T::SCHEMA.version, data)?;
+        Ok(serde_json::from_value(data)?)
+    }
+}
+
+/// Reads a `T` written in any supported version.
+pub fn from_artifact_str<T: VersionedArtifact>(json: &str) -> Result<T, ArtifactSchemaError> {
+    MigrationRegistry::default().read(json)
+}
+
+/// Pretty JSON for `artifact` in the current version's envelope.
+pub fn to_artifact_string<T: VersionedArtifact>(
+    artifact: &T,
+) -> Result<String, ArtifactSchemaError> {
+    Ok(serde_json::to_string_pretty(&ArtifactEnvelope {
+        schema: T::SCHEMA.name.to_string(),
+        version: T::SCHEMA.version,
+        data: artifact,
+    })?)
+}
+
+/// An artifact file [`migrate_dir`] upgraded, or would upgrade on a dry run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigratedArtifact {
+    pub path: PathBuf,
+    pub schema: &'static str,
+    pub from_version: u32,
+}
+
+/// Outcome of [`migrate_dir`] for each JSON file found.
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    pub migrated: Vec<MigratedArtifact>,
+    /// Artifacts already at the current version.
+    pub current: Vec<PathBuf>,
+    /// JSON files that are not artifacts of a known schema.
+    pub skipped: Vec<PathBuf>,
+    /// Artifacts that could not be upgraded; they are left untouched.
+    pub failed: Vec<(PathBuf, ArtifactSchemaError)>,
+}
+
+/// Upgrades every artifact under `dir`, recursively, to the current version
+/// in place. On a dry run nothing is written but the report is the same.
+/// Data is rewritten as migrated rather than through the structs, so fields
+/// the structs do not know about are kept.
+pub fn migrate_dir(dir: &Path, dry_run: bool) -> Result<MigrationReport, ArtifactSchemaError> {
+    let registry = MigrationRegistry::default();
+    let mut report = MigrationReport::default();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(directory) = pending.pop() {
+        let mut entries = fs::read_dir(&directory)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+        for path in entries {
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                migrate_file(&registry, path, dry_run, &mut report)?;
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn migrate_file(
+    registry: &MigrationRegistry,
+    path: PathBuf,
+    dry_run: bool,
+    report: &mut MigrationReport,
+) -> Result<(), ArtifactSchemaError> {
+    let Ok(stored) = serde_json::from_slice::<StoredArtifact>(&fs::read(&path)?) else {
+        report.skipped.push(path);
+        return Ok(());
+    };
+    let (schema, version, data) = match stored {
+        StoredArtifact::Versioned(envelope) => match schema_named(&envelope.schema) {
+            Some(schema) => (schema, envelope.version, envelope.data),
+            None => {
+                let error = ArtifactSchemaError::UnknownSchema(envelope.schema);
+                report.failed.push((path, error));
+                return Ok(());
+            }
+        },
+        StoredArtifact::Legacy(data) => {
+            match ARTIFACT_SCHEMAS
+                .iter()
+                .find(|schema| schema.matches_legacy(&data))
+            {
+                Some(schema) => (schema, LEGACY_VERSION, data),
+                None => {
+                    report.skipped.push(path);
+                    return Ok(());
+                }
+            }
+        }
+    };
+    if version == schema.version {
+        report.current.push(path);
+        return Ok(());
+    }
+
+    let upgraded = registry
+        .upgrade(schema.name, version, schema.version, data)
+        .and_then(|data| {
+            // Never leave behind a file the readers would reject.
+            (schema.check)(data.clone())?;
+            Ok(data)
+        });
+    let data = match upgraded {
+        Ok(data) => data,
+        Err(error) => {
+            report.failed.push((path, error));
+            return Ok(());
+        }
+    };
+    if !dry_run {
+        let json = serde_json::to_string_pretty(&ArtifactEnvelope {
+            schema: schema.name.to_string(),
+            version: schema.version,
+            data,
+        })?;
+        let mut staged = path.clone().into_os_string();
+        staged.push(".migrating");
+        fs::write(&staged, json)?;
+        fs::rename(&staged, &path)?;
+    }
+    report.migrated.push(MigratedArtifact {
+        path,
+        schema: schema.name,
+        from_version: version,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY_LIDAR_SCAN: &str = include_str!("../fixtures/legacy_lidar_scan_v1.json");
+    const LEGACY_MULTISPECTRAL_IMAGE: &str =
+        include_str!("../fixtures/legacy_multispectral_image_v1.json");
+    const LEGACY_NDVI_RESULT: &str = include_str!("../fixtures/legacy_ndvi_result_v1.json");
+
+    /// Asserts every field in `legacy`, at any depth, has the same value in
+    /// `written`.
+    fn assert_fields_kept(written: &Value, legacy: &Value, path: &str) {
+        match legacy.as_object() {
+            Some(fields) => {
+                for (field, value) in fields {
+                    assert_fields_kept(&written[field], value, &format!("{path}.{field}"));
+                }
+            }
+            None => assert_eq!(written, legacy, "{path}"),
+        }
+    }
+
+    /// Loads a legacy fixture as `T`, writes it back out and checks every
+    /// field of the legacy document survives in the current envelope.
+    fn assert_reserialized_losslessly<T: VersionedArtifact>(legacy: &str) -> Value {
+        let artifact: T = from_artifact_str(legacy).unwrap();
+        let written: Value = serde_json::from_str(&to_artifact_string(&artifact).unwrap()).unwrap();
+
+        assert_eq!(written["schema"], T::SCHEMA.name);
+        assert_eq!(written["version"], T::SCHEMA.version);
+        let legacy: Value = serde_json::from_str(legacy).unwrap();
+        assert_fields_kept(&written["data"], &legacy, T::SCHEMA.name);
+        written["data"].clone()
+    }
+
+    #[test]
+    fn legacy_fixtures_load_and_reserialize_at_the_current_version() {
+        assert_reserialized_losslessly::<LidarScan>(LEGACY_LIDAR_SCAN);
+        assert_reserialized_losslessly::<NdviResult>(LEGACY_NDVI_RESULT);
+        let image =
+            assert_reserialized_losslessly::<MultispectralImage>(LEGACY_MULTISPECTRAL_IMAGE);
+        assert_eq!(image["metadata"]["spatial_ref"], Value::Null);
+
+        // A current envelope reads back as written.
+        let scan: LidarScan = from_artifact_str(LEGACY_LIDAR_SCAN).unwrap();
+        let reread: LidarScan = from_artifact_str(&to_artifact_string(&scan).unwrap()).unwrap();
+        assert_eq!(reread.scan_id, scan.scan_id);
+        assert_eq!(reread.points.len(), 3);
+    }
+
+    #[test]
+    fn newer_versions_and_other_schemas_are_rejected() {
+        let scan: Value = serde_json::from_str(LEGACY_LIDAR_SCAN).unwrap();
+        let envelope = |schema: &str, version: u32| {
+            serde_json::json!({ "schema": schema, "version": version, "data": scan }).to_string()
+        };
+
+        assert!(matches!(
+            from_artifact_str::<LidarScan>(&envelope("lidar_scan", 3)),
+            Err(ArtifactSchemaError::UnsupportedVersion {
+                version: 3,
+                latest: 2,
+                ..
+            })
+        ));
+        assert!(matches!(
+            from_artifact_str::<NdviResult>(&envelope("lidar_scan", 2)),
+            Err(ArtifactSchemaError::SchemaMismatch {
+                expected: "ndvi_result",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn migrate_dir_upgrades_legacy_files_in_place_unless_dry_run() {
+        let dir = std::env::temp_dir().join(format!("artifact-migrate-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("images")).unwrap();
+        fs::write(dir.join("scan.json"), LEGACY_LIDAR_SCAN).unwrap();
+        fs::write(dir.join("images/metadata.json"), LEGACY_MULTISPECTRAL_IMAGE).unwrap();
+        fs::write(dir.join("ndvi.json"), LEGACY_NDVI_RESULT).unwrap();
+        fs::write(dir.join("settings.json"), r#"{"theme":"dark"}"#).unwrap();
+        fs::write(dir.join("notes.txt"), "not json").unwrap();
+
+        let dry_run = migrate_dir(&dir, true).unwrap();
+        assert_eq!(dry_run.migrated.len(), 3);
+        assert!(dry_run
+            .migrated
+            .iter()
+            .all(|artifact| artifact.from_version == LEGACY_VERSION));
+        assert_eq!(dry_run.skipped, vec![dir.join("settings.json")]);
+        assert_eq!(
+            fs::read_to_string(dir.join("scan.json")).unwrap(),
+            LEGACY_LIDAR_SCAN
+        );
+
+        let report = migrate_dir(&dir, false).unwrap();
+        assert_eq!(report.migrated, dry_run.migrated);
+        assert!(report.failed.is_empty());
+        let migrated: Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("images/metadata.json")).unwrap())
+                .unwrap();
+        assert_eq!(migrated["schema"], "multispectral_image");
+        assert_eq!(migrated["version"], 2);
+        let image: MultispectralImage =
+            from_artifact_str(&fs::read_to_string(dir.join("images/metadata.json")).unwrap())
+                .unwrap();
+        assert_eq!(image.metadata.width, 1280);
+
+        let rerun = migrate_dir(&dir, false).unwrap();
+        assert!(rerun.migrated.is_empty());
+        assert_eq!(rerun.current.len(), 3);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,31 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use shared::config::{AgroConfig, ConfigFormat};
+
+#[derive(Parser)]
+#[command(author, version, about = "Inspect AgroConfig settings", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Print the default config with every field, ready to redirect into a
+    /// starter file for --config
+    PrintDefault {
+        /// Output format: toml or json
+        #[arg(short, long, default_value = "toml")]
+        format: ConfigFormat,
+    },
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Commands::PrintDefault { format } => {
+            let document = AgroConfig::default().to_document(format)?;
+            println!("{}", document.trim_end());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use shared::artifact_schema::migrate_dir;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(author, version, about = "Maintain on-disk artifacts", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Rewrite the scans, image metadata and NDVI results under a directory
+    /// in the current artifact version
+    MigrateDir {
+        /// Directory to walk, including subdirectories
+        dir: PathBuf,
+        /// Report what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Commands::MigrateDir { dir, dry_run } => {
+            let report = migrate_dir(&dir, dry_run)?;
+            let verb = if dry_run { "would migrate" } else { "migrated" };
+            for artifact in &report.migrated {
+                println!(
+                    "{verb} {} ({} v{})",
+                    artifact.path.display(),
+                    artifact.schema,
+                    artifact.from_version
+                );
+            }
+            for (path, error) in &report.failed {
+                eprintln!("failed {}: {error}", path.display());
+            }
+            println!(
+                "{} {verb}, {} already current, {} skipped, {} failed",
+                report.migrated.len(),
+                report.current.len(),
+                report.skipped.len(),
+                report.failed.len()
+            );
+            if !report.failed.is_empty() {
+                anyhow::bail!("{} artifacts could not be migrated", report.failed.len());
+            }
+        }
+    }
+    Ok(())
+}
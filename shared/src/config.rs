@@ -1,8 +1,12 @@
 use crate::{error::AgroError, AgroResult, RuntimeMode};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt::Display;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgroConfig {
@@ -14,6 +18,96 @@ pub struct AgroConfig {
     pub server: ServerConfig,
     pub gps: GpsConfig,
     pub processing: ProcessingConfig,
+    pub cors: CorsConfig,
+    #[serde(default)]
+    pub operator: OperatorConfig,
+}
+
+/// The settings an empty environment loads in simulation mode.
+impl Default for AgroConfig {
+    fn default() -> Self {
+        Self {
+            runtime_mode: RuntimeMode::Simulation,
+            mavlink: MavlinkConfig {
+                serial_port: "/dev/ttyUSB0".to_string(),
+                baud_rate: 57600,
+                timeout_ms: 1000,
+                heartbeat_interval_ms: 1000,
+            },
+            lidar: LidarConfig {
+                serial_port: "/dev/ttyUSB1".to_string(),
+                baud_rate: 230400,
+                timeout_ms: 1000,
+                scan_frequency: 10.0,
+                points_per_scan: default_lidar_points_per_scan(),
+                simulated_obstacles: Vec::new(),
+            },
+            camera: CameraConfig {
+                device: "/dev/video0".to_string(),
+                multispectral_bands: 4,
+                capture_interval_ms: 5000,
+                exposure_time: 1.0 / 60.0,
+                gain: 1.0,
+            },
+            storage: StorageConfig {
+                data_root_path: "/tmp/agrodrone/data".into(),
+                mission_data_path: "/tmp/agrodrone/missions".into(),
+            },
+            server: ServerConfig {
+                ws_bind_address: "0.0.0.0:8080".to_string(),
+                api_bind_address: "0.0.0.0:3000".to_string(),
+                ws_history_size: default_ws_history_size(),
+                control_token: None,
+            },
+            gps: GpsConfig {
+                home_latitude: 37.7749,
+                home_longitude: -122.4194,
+                home_altitude: 100.0,
+            },
+            processing: ProcessingConfig {
+                ndvi_output_format: "GEOTIFF".to_string(),
+                lidar_grid_resolution: 0.1,
+                lidar_obstacle_distance_threshold: 5.0,
+                lidar_quality_threshold: 20,
+                lidar_occupancy_threshold: 0.5,
+                lidar_image_flip_y: false,
+            },
+            cors: CorsConfig::default(),
+            operator: OperatorConfig::default(),
+        }
+    }
+}
+
+/// Structured file formats an `AgroConfig` can be written to and loaded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// The format matching `path`'s extension; `None` for `KEY=value` files.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "toml" => Some(Self::Toml),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for ConfigFormat {
+    type Err = AgroError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "toml" => Ok(Self::Toml),
+            "json" => Ok(Self::Json),
+            _ => Err(AgroError::ConfigValidation(format!(
+                "unknown config format `{value}`; expected `toml` or `json`"
+            ))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,7 +123,80 @@ pub struct LidarConfig {
     pub serial_port: String,
     pub baud_rate: u32,
     pub timeout_ms: u64,
+    /// Scans per second.
     pub scan_frequency: f32,
+    /// Points in each simulated scan, spread evenly over 360 degrees.
+    #[serde(default = "default_lidar_points_per_scan")]
+    pub points_per_scan: u32,
+    /// Virtual obstacles the simulated reader ray-casts against; when empty
+    /// it falls back to its built-in obstacle sectors.
+    #[serde(default)]
+    pub simulated_obstacles: Vec<SimulatedObstacle>,
+}
+
+fn default_lidar_points_per_scan() -> u32 {
+    360
+}
+
+/// Obstacle geometry for the simulated LiDAR, in metres in the sensor frame
+/// (x along 0 degrees, y along 90 degrees).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SimulatedObstacle {
+    Wall { start: (f32, f32), end: (f32, f32) },
+    Circle { center: (f32, f32), radius: f32 },
+}
+
+impl SimulatedObstacle {
+    /// Distance along the unit ray `direction` from the sensor to the
+    /// nearest hit, if any.
+    pub fn ray_distance(&self, direction: (f32, f32)) -> Option<f32> {
+        let (dx, dy) = direction;
+        match *self {
+            SimulatedObstacle::Wall {
+                start: (ax, ay),
+                end: (bx, by),
+            } => {
+                let (ex, ey) = (bx - ax, by - ay);
+                let denominator = dx * ey - dy * ex;
+                if denominator.abs() <= f32::EPSILON {
+                    return None;
+                }
+                let distance = (ax * ey - ay * ex) / denominator;
+                let along_wall = (ax * dy - ay * dx) / denominator;
+                (distance > 0.0 && (0.0..=1.0).contains(&along_wall)).then_some(distance)
+            }
+            SimulatedObstacle::Circle {
+                center: (cx, cy),
+                radius,
+            } => {
+                let projection = cx * dx + cy * dy;
+                let offset_sq = cx * cx + cy * cy - projection * projection;
+                let half_chord_sq = radius * radius - offset_sq;
+                if half_chord_sq < 0.0 {
+                    return None;
+                }
+                let half_chord = half_chord_sq.sqrt();
+                [projection - half_chord, projection + half_chord]
+                    .into_iter()
+                    .find(|distance| *distance > 0.0)
+            }
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match *self {
+            SimulatedObstacle::Wall { start, end } => {
+                [start.0, start.1, end.0, end.1]
+                    .iter()
+                    .all(|value| value.is_finite())
+                    && start != end
+            }
+            SimulatedObstacle::Circle { center, radius } => {
+                center.0.is_finite() && center.1.is_finite() && radius.is_finite() && radius > 0.0
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,6 +218,18 @@ pub struct StorageConfig {
 pub struct ServerConfig {
     pub ws_bind_address: String,
     pub api_bind_address: String,
+    /// Telemetry and status messages replayed to each new WebSocket client;
+    /// 0 disables the history.
+    #[serde(default = "default_ws_history_size")]
+    pub ws_history_size: usize,
+    /// Bearer token clients present to publish events and command the
+    /// vehicle; those paths are refused while it is unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control_token: Option<String>,
+}
+
+fn default_ws_history_size() -> usize {
+    100
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,79 +251,338 @@ pub struct ProcessingConfig {
     pub lidar_image_flip_y: bool,
 }
 
+/// Cross-origin policy applied by every HTTP/WebSocket server in the workspace.
+///
+/// An empty origin list disables cross-origin access entirely; `*` must be listed
+/// explicitly to re-open the server to any origin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    pub max_age_secs: u64,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec![
+                "http://localhost:3000".to_string(),
+                "http://127.0.0.1:3000".to_string(),
+            ],
+            allowed_methods: ["GET", "POST", "PUT", "DELETE", "OPTIONS"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            allowed_headers: vec!["content-type".to_string(), "authorization".to_string()],
+            max_age_secs: 600,
+        }
+    }
+}
+
+impl CorsConfig {
+    /// Reads `CORS_ALLOWED_ORIGINS`, `CORS_ALLOWED_METHODS`, `CORS_ALLOWED_HEADERS`
+    /// (comma-separated) and `CORS_MAX_AGE_SECS`, falling back to the defaults.
+    pub fn from_env() -> AgroResult<Self> {
+        let defaults = Self::default();
+        let config = Self {
+            allowed_origins: env_list("CORS_ALLOWED_ORIGINS", defaults.allowed_origins)?,
+            allowed_methods: env_list("CORS_ALLOWED_METHODS", defaults.allowed_methods)?
+                .into_iter()
+                .map(|method| method.to_ascii_uppercase())
+                .collect(),
+            allowed_headers: env_list("CORS_ALLOWED_HEADERS", defaults.allowed_headers)?
+                .into_iter()
+                .map(|header| header.to_ascii_lowercase())
+                .collect(),
+            max_age_secs: env_parse("CORS_MAX_AGE_SECS", defaults.max_age_secs)?,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> AgroResult<()> {
+        for origin in &self.allowed_origins {
+            let valid = origin == "*"
+                || ((origin.starts_with("http://") || origin.starts_with("https://"))
+                    && !origin.ends_with('/')
+                    && http::HeaderValue::from_str(origin).is_ok());
+            if !valid {
+                return Err(AgroError::ConfigValidation(format!(
+                    "config field `CORS_ALLOWED_ORIGINS` has invalid origin `{origin}`"
+                )));
+            }
+        }
+        if self.allowed_methods.is_empty() {
+            return Err(AgroError::ConfigValidation(
+                "config field `CORS_ALLOWED_METHODS` cannot be empty".to_string(),
+            ));
+        }
+        for method in &self.allowed_methods {
+            if http::Method::from_bytes(method.as_bytes()).is_err() {
+                return Err(AgroError::ConfigValidation(format!(
+                    "config field `CORS_ALLOWED_METHODS` has invalid method `{method}`"
+                )));
+            }
+        }
+        for header in &self.allowed_headers {
+            if http::HeaderName::from_bytes(header.as_bytes()).is_err() {
+                return Err(AgroError::ConfigValidation(format!(
+                    "config field `CORS_ALLOWED_HEADERS` has invalid header `{header}`"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|origin| origin == "*")
+    }
+
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allows_any_origin() || self.allowed_origins.iter().any(|allowed| allowed == origin)
+    }
+
+    /// Builds the tower-http layer; entries that fail to parse are skipped, so
+    /// call [`CorsConfig::validate`] first to surface them as errors.
+    pub fn layer(&self) -> CorsLayer {
+        let origins = if self.allows_any_origin() {
+            AllowOrigin::any()
+        } else {
+            AllowOrigin::list(
+                self.allowed_origins
+                    .iter()
+                    .filter_map(|origin| http::HeaderValue::from_str(origin).ok()),
+            )
+        };
+        let methods = AllowMethods::list(
+            self.allowed_methods
+                .iter()
+                .filter_map(|method| http::Method::from_bytes(method.as_bytes()).ok()),
+        );
+        let headers = AllowHeaders::list(
+            self.allowed_headers
+                .iter()
+                .filter_map(|header| http::HeaderName::from_bytes(header.as_bytes()).ok()),
+        );
+
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(headers)
+            .max_age(Duration::from_secs(self.max_age_secs))
+    }
+}
+
+/// Remote pilot / operator identity stamped onto compliance exports.
+///
+/// Every field is optional; exports state explicitly which ones were not configured.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OperatorConfig {
+    pub name: String,
+    pub organization: String,
+    pub license_id: String,
+    pub contact: String,
+}
+
+impl OperatorConfig {
+    /// Reads `OPERATOR_NAME`, `OPERATOR_ORGANIZATION`, `OPERATOR_LICENSE_ID` and
+    /// `OPERATOR_CONTACT`; unset variables stay empty.
+    pub fn from_env(runtime_mode: RuntimeMode) -> AgroResult<Self> {
+        Ok(Self {
+            name: env_string("OPERATOR_NAME", "", runtime_mode, false)?,
+            organization: env_string("OPERATOR_ORGANIZATION", "", runtime_mode, false)?,
+            license_id: env_string("OPERATOR_LICENSE_ID", "", runtime_mode, false)?,
+            contact: env_string("OPERATOR_CONTACT", "", runtime_mode, false)?,
+        })
+    }
+}
+
 impl AgroConfig {
     pub fn load() -> AgroResult<Self> {
-        dotenvy::dotenv().ok();
+        load_dotenv();
+        Self::from_env()
+    }
+
+    /// Loads `path` in place of `.env` discovery. A `.toml` or `.json` file is
+    /// a complete config as written by `to_document`, with environment
+    /// references in its string values expanded. Any other
+    /// file holds `KEY=value` lines with the same keys as `.env`, and variables
+    /// already set in the process environment still win over it.
+    pub fn load_from(path: &Path) -> AgroResult<Self> {
+        let read_error = |error: &dyn Display| {
+            AgroError::ConfigValidation(format!(
+                "failed to read config file `{}`: {error}",
+                path.display()
+            ))
+        };
+        match ConfigFormat::from_path(path) {
+            Some(format) => {
+                let document = std::fs::read_to_string(path).map_err(|error| read_error(&error))?;
+                Self::from_document(&document, format).map_err(|error| read_error(&error))
+            }
+            None => {
+                let lines = dotenvy::from_path_iter(path).map_err(|error| read_error(&error))?;
+                load_env_lines(lines).map_err(|error| read_error(&error))?;
+                Self::from_env()
+            }
+        }
+    }
+
+    /// Parses and validates a complete config in `format`, expanding
+    /// environment references in its string values like `.env` values.
+    pub fn from_document(document: &str, format: ConfigFormat) -> AgroResult<Self> {
+        let config: Self = match format {
+            ConfigFormat::Toml => toml::from_str(document)
+                .map_err(|error| AgroError::ConfigValidation(error.to_string()))?,
+            ConfigFormat::Json => serde_json::from_str(document)?,
+        };
+        let mut fields = serde_json::to_value(config)?;
+        map_string_fields("", &mut fields, &mut expand_env_references)?;
+        let config: Self = serde_json::from_value(fields)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Every field of this config in `format`, loadable with `from_document`;
+    /// a literal `$` in a string value is written as `$$`.
+    pub fn to_document(&self, format: ConfigFormat) -> AgroResult<String> {
+        let mut fields = serde_json::to_value(self)?;
+        map_string_fields("", &mut fields, &mut |_, text| Ok(text.replace('$', "$$")))?;
+        let escaped: Self = serde_json::from_value(fields)?;
+        match format {
+            ConfigFormat::Toml => toml::to_string_pretty(&escaped)
+                .map_err(|error| AgroError::ConfigValidation(error.to_string())),
+            ConfigFormat::Json => Ok(serde_json::to_string_pretty(&escaped)?),
+        }
+    }
+
+    /// `load_from` for a CLI-provided `--config` path, `load` otherwise.
+    pub fn load_with_override(path: Option<&Path>) -> AgroResult<Self> {
+        match path {
+            Some(path) => Self::load_from(path),
+            None => Self::load(),
+        }
+    }
 
-        let runtime_mode = env_parse("RUNTIME_MODE", RuntimeMode::Simulation)?;
+    fn from_env() -> AgroResult<Self> {
+        let defaults = Self::default();
+        let runtime_mode = env_parse("RUNTIME_MODE", defaults.runtime_mode)?;
 
         let config = AgroConfig {
             runtime_mode,
             mavlink: MavlinkConfig {
-                serial_port: env_string("MAVLINK_SERIAL_PORT", "/dev/ttyUSB0", runtime_mode, true)?,
-                baud_rate: env_parse("MAVLINK_BAUD_RATE", 57600u32)?,
-                timeout_ms: env_parse("MAVLINK_TIMEOUT_MS", 1000u64)?,
-                heartbeat_interval_ms: env_parse("MAVLINK_HEARTBEAT_INTERVAL_MS", 1000u64)?,
+                serial_port: env_string(
+                    "MAVLINK_SERIAL_PORT",
+                    &defaults.mavlink.serial_port,
+                    runtime_mode,
+                    true,
+                )?,
+                baud_rate: env_parse("MAVLINK_BAUD_RATE", defaults.mavlink.baud_rate)?,
+                timeout_ms: env_parse("MAVLINK_TIMEOUT_MS", defaults.mavlink.timeout_ms)?,
+                heartbeat_interval_ms: env_parse(
+                    "MAVLINK_HEARTBEAT_INTERVAL_MS",
+                    defaults.mavlink.heartbeat_interval_ms,
+                )?,
             },
             lidar: LidarConfig {
-                serial_port: env_string("LIDAR_SERIAL_PORT", "/dev/ttyUSB1", runtime_mode, true)?,
-                baud_rate: env_parse("LIDAR_BAUD_RATE", 230400u32)?,
-                timeout_ms: env_parse("LIDAR_TIMEOUT_MS", 1000u64)?,
-                scan_frequency: env_parse("LIDAR_SCAN_FREQUENCY", 10.0f32)?,
+                serial_port: env_string(
+                    "LIDAR_SERIAL_PORT",
+                    &defaults.lidar.serial_port,
+                    runtime_mode,
+                    true,
+                )?,
+                baud_rate: env_parse("LIDAR_BAUD_RATE", defaults.lidar.baud_rate)?,
+                timeout_ms: env_parse("LIDAR_TIMEOUT_MS", defaults.lidar.timeout_ms)?,
+                scan_frequency: env_parse("LIDAR_SCAN_FREQUENCY", defaults.lidar.scan_frequency)?,
+                points_per_scan: env_parse(
+                    "LIDAR_POINTS_PER_SCAN",
+                    defaults.lidar.points_per_scan,
+                )?,
+                simulated_obstacles: simulated_obstacles_from_env()?,
             },
             camera: CameraConfig {
-                device: env_string("CAMERA_DEVICE", "/dev/video0", runtime_mode, true)?,
-                multispectral_bands: env_parse("MULTISPECTRAL_BANDS", 4u8)?,
-                capture_interval_ms: env_parse("CAMERA_CAPTURE_INTERVAL_MS", 5000u64)?,
-                exposure_time: env_parse("CAMERA_EXPOSURE_TIME", 1.0f32 / 60.0f32)?,
-                gain: env_parse("CAMERA_GAIN", 1.0f32)?,
+                device: env_string("CAMERA_DEVICE", &defaults.camera.device, runtime_mode, true)?,
+                multispectral_bands: env_parse(
+                    "MULTISPECTRAL_BANDS",
+                    defaults.camera.multispectral_bands,
+                )?,
+                capture_interval_ms: env_parse(
+                    "CAMERA_CAPTURE_INTERVAL_MS",
+                    defaults.camera.capture_interval_ms,
+                )?,
+                exposure_time: env_parse("CAMERA_EXPOSURE_TIME", defaults.camera.exposure_time)?,
+                gain: env_parse("CAMERA_GAIN", defaults.camera.gain)?,
             },
             storage: StorageConfig {
                 data_root_path: env_string(
                     "DATA_ROOT_PATH",
-                    "/tmp/agrodrone/data",
+                    &defaults.storage.data_root_path.to_string_lossy(),
                     runtime_mode,
                     true,
                 )?
                 .into(),
                 mission_data_path: env_string(
                     "MISSION_DATA_PATH",
-                    "/tmp/agrodrone/missions",
+                    &defaults.storage.mission_data_path.to_string_lossy(),
                     runtime_mode,
                     true,
                 )?
                 .into(),
             },
             server: ServerConfig {
-                ws_bind_address: env_string("WS_BIND_ADDRESS", "0.0.0.0:8080", runtime_mode, true)?,
+                ws_bind_address: env_string(
+                    "WS_BIND_ADDRESS",
+                    &defaults.server.ws_bind_address,
+                    runtime_mode,
+                    true,
+                )?,
                 api_bind_address: env_string(
                     "API_BIND_ADDRESS",
-                    "0.0.0.0:3000",
+                    &defaults.server.api_bind_address,
                     runtime_mode,
                     true,
                 )?,
+                ws_history_size: env_parse("WS_HISTORY_SIZE", defaults.server.ws_history_size)?,
+                control_token: env_var("CONTROL_TOKEN")?.filter(|token| !token.trim().is_empty()),
             },
             gps: GpsConfig {
-                home_latitude: env_parse("HOME_LATITUDE", 37.7749f64)?,
-                home_longitude: env_parse("HOME_LONGITUDE", -122.4194f64)?,
-                home_altitude: env_parse("HOME_ALTITUDE", 100.0f64)?,
+                home_latitude: env_parse("HOME_LATITUDE", defaults.gps.home_latitude)?,
+                home_longitude: env_parse("HOME_LONGITUDE", defaults.gps.home_longitude)?,
+                home_altitude: env_parse("HOME_ALTITUDE", defaults.gps.home_altitude)?,
             },
             processing: ProcessingConfig {
                 ndvi_output_format: env_string(
                     "NDVI_OUTPUT_FORMAT",
-                    "GEOTIFF",
+                    &defaults.processing.ndvi_output_format,
                     runtime_mode,
                     false,
                 )?,
-                lidar_grid_resolution: env_parse("LIDAR_GRID_RESOLUTION", 0.1f32)?,
+                lidar_grid_resolution: env_parse(
+                    "LIDAR_GRID_RESOLUTION",
+                    defaults.processing.lidar_grid_resolution,
+                )?,
                 lidar_obstacle_distance_threshold: env_parse(
                     "LIDAR_OBSTACLE_DISTANCE_THRESHOLD",
-                    5.0f32,
+                    defaults.processing.lidar_obstacle_distance_threshold,
+                )?,
+                lidar_quality_threshold: env_parse(
+                    "LIDAR_QUALITY_THRESHOLD",
+                    defaults.processing.lidar_quality_threshold,
+                )?,
+                lidar_occupancy_threshold: env_parse(
+                    "LIDAR_OCCUPANCY_THRESHOLD",
+                    defaults.processing.lidar_occupancy_threshold,
+                )?,
+                lidar_image_flip_y: env_parse(
+                    "LIDAR_IMAGE_FLIP_Y",
+                    defaults.processing.lidar_image_flip_y,
                 )?,
-                lidar_quality_threshold: env_parse("LIDAR_QUALITY_THRESHOLD", 20u8)?,
-                lidar_occupancy_threshold: env_parse("LIDAR_OCCUPANCY_THRESHOLD", 0.5f32)?,
-                lidar_image_flip_y: env_parse("LIDAR_IMAGE_FLIP_Y", false)?,
             },
+            cors: CorsConfig::from_env()?,
+            operator: OperatorConfig::from_env(runtime_mode)?,
         };
 
         config.validate()?;
@@ -174,6 +612,22 @@ impl AgroConfig {
         require_range("LIDAR_BAUD_RATE", self.lidar.baud_rate, 1u32, u32::MAX)?;
         require_range("LIDAR_TIMEOUT_MS", self.lidar.timeout_ms, 1u64, u64::MAX)?;
         require_positive_f32("LIDAR_SCAN_FREQUENCY", self.lidar.scan_frequency)?;
+        require_range(
+            "LIDAR_POINTS_PER_SCAN",
+            self.lidar.points_per_scan,
+            1u32,
+            100_000u32,
+        )?;
+        if let Some(index) = self
+            .lidar
+            .simulated_obstacles
+            .iter()
+            .position(|obstacle| !obstacle.is_valid())
+        {
+            return Err(AgroError::ConfigValidation(format!(
+                "config field `LIDAR_SIM_OBSTACLES_FILE` has invalid obstacle at index {index}"
+            )));
+        }
         require_range(
             "MULTISPECTRAL_BANDS",
             self.camera.multispectral_bands,
@@ -210,6 +664,7 @@ impl AgroConfig {
             "LIDAR_OCCUPANCY_THRESHOLD",
             self.processing.lidar_occupancy_threshold,
         )?;
+        self.cors.validate()?;
 
         Ok(())
     }
@@ -221,21 +676,12 @@ fn env_string(
     runtime_mode: RuntimeMode,
     required_in_flight: bool,
 ) -> AgroResult<String> {
-    match std::env::var(key) {
-        Ok(value) if !value.trim().is_empty() => Ok(value),
-        Ok(_) if runtime_mode == RuntimeMode::Flight && required_in_flight => {
+    match env_var(key)? {
+        Some(value) if !value.trim().is_empty() => Ok(value),
+        _ if runtime_mode == RuntimeMode::Flight && required_in_flight => {
             Err(missing_required_field(key))
         }
-        Ok(_) => Ok(fallback.to_string()),
-        Err(std::env::VarError::NotPresent)
-            if runtime_mode == RuntimeMode::Flight && required_in_flight =>
-        {
-            Err(missing_required_field(key))
-        }
-        Err(std::env::VarError::NotPresent) => Ok(fallback.to_string()),
-        Err(error) => Err(AgroError::ConfigValidation(format!(
-            "invalid env var `{key}`: {error}"
-        ))),
+        _ => Ok(fallback.to_string()),
     }
 }
 
@@ -244,20 +690,214 @@ where
     T: FromStr + Copy,
     T::Err: Display,
 {
-    match std::env::var(key) {
-        Ok(value) if value.trim().is_empty() => Ok(fallback),
-        Ok(value) => value.parse::<T>().map_err(|error| {
+    match env_var(key)? {
+        Some(value) if !value.trim().is_empty() => value.parse::<T>().map_err(|error| {
             AgroError::ConfigValidation(format!(
                 "invalid config field `{key}` value `{value}`: {error}"
             ))
         }),
-        Err(std::env::VarError::NotPresent) => Ok(fallback),
+        _ => Ok(fallback),
+    }
+}
+
+fn env_list(key: &str, fallback: Vec<String>) -> AgroResult<Vec<String>> {
+    match env_var(key)? {
+        Some(value) if !value.trim().is_empty() => Ok(value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect()),
+        _ => Ok(fallback),
+    }
+}
+
+/// Values set from `.env` or `--config` lines, by key. Only these have their
+/// environment references expanded; a value set in the process environment
+/// is taken literally, so secrets containing `$` load unchanged.
+fn file_env_values() -> &'static Mutex<HashMap<String, String>> {
+    static VALUES: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+    VALUES.get_or_init(Default::default)
+}
+
+/// Loads `.env` from the working directory or its parents, if there is one,
+/// like `dotenvy::dotenv` but remembering which values came from the file.
+pub fn load_dotenv() {
+    if let Ok(lines) = dotenvy::dotenv_iter() {
+        if let Err(error) = load_env_lines(lines) {
+            tracing::warn!(%error, "failed to read .env");
+        }
+    }
+}
+
+/// Sets each `KEY=value` line that the process environment does not already
+/// set, recording it as a file value.
+fn load_env_lines<R: std::io::Read>(lines: dotenvy::Iter<R>) -> dotenvy::Result<()> {
+    let mut file_values = file_env_values()
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    for line in lines {
+        let (key, value) = line?;
+        if std::env::var_os(&key).is_none() {
+            std::env::set_var(&key, &value);
+            file_values.insert(key, value);
+        }
+    }
+    Ok(())
+}
+
+/// Reads `key`, expanding environment references when its value came from a
+/// `.env` or `--config` file; `None` when unset.
+fn env_var(key: &str) -> AgroResult<Option<String>> {
+    match std::env::var(key) {
+        Ok(value) => {
+            let from_file = file_env_values()
+                .lock()
+                .unwrap_or_else(|error| error.into_inner())
+                .get(key)
+                == Some(&value);
+            if from_file {
+                expand_env_references(key, &value).map(Some)
+            } else {
+                Ok(Some(value))
+            }
+        }
+        Err(std::env::VarError::NotPresent) => Ok(None),
         Err(error) => Err(AgroError::ConfigValidation(format!(
             "invalid env var `{key}`: {error}"
         ))),
     }
 }
 
+/// Expands `${VAR}`, `${VAR:-default}` and `$VAR` references in the value of
+/// config field `key` from the process environment; `$$` is a literal `$`.
+/// The default applies when `VAR` is unset or empty. A reference to an unset
+/// variable without a default is an error rather than an empty string.
+///
+/// `.env` and `--config` files already substitute unquoted and double-quoted
+/// references themselves, silently and without defaults, so references meant
+/// for this pass belong in single quotes there.
+pub fn expand_env_references(key: &str, value: &str) -> AgroResult<String> {
+    let mut expanded = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('$') => {
+                chars.next();
+                expanded.push('$');
+            }
+            Some('{') => {
+                chars.next();
+                let mut reference = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => reference.push(c),
+                        None => {
+                            return Err(AgroError::ConfigValidation(format!(
+                                "config field `{key}` has an unterminated `${{` reference"
+                            )))
+                        }
+                    }
+                }
+                let (name, default) = match reference.split_once(":-") {
+                    Some((name, default)) => (name, Some(default)),
+                    None => (reference.as_str(), None),
+                };
+                if name.is_empty() || !name.chars().all(is_env_name_char) {
+                    return Err(AgroError::ConfigValidation(format!(
+                        "config field `{key}` references invalid environment variable name `{name}`"
+                    )));
+                }
+                expanded.push_str(&resolve_env_reference(key, name, default)?);
+            }
+            Some(&next) if next.is_ascii_alphabetic() || next == '_' => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !is_env_name_char(c) {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                expanded.push_str(&resolve_env_reference(key, &name, None)?);
+            }
+            _ => expanded.push('$'),
+        }
+    }
+    Ok(expanded)
+}
+
+/// Replaces every string in `value` with `map(field, string)`, naming each
+/// field by its dotted path below `path`.
+fn map_string_fields(
+    path: &str,
+    value: &mut serde_json::Value,
+    map: &mut impl FnMut(&str, &str) -> AgroResult<String>,
+) -> AgroResult<()> {
+    match value {
+        serde_json::Value::String(text) => *text = map(path, text)?,
+        serde_json::Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                map_string_fields(&format!("{path}[{index}]"), item, map)?;
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                let path = if path.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{path}.{name}")
+                };
+                map_string_fields(&path, field, map)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn is_env_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn resolve_env_reference(key: &str, name: &str, default: Option<&str>) -> AgroResult<String> {
+    match (std::env::var(name), default) {
+        (Ok(value), Some(default)) if value.is_empty() => Ok(default.to_string()),
+        (Ok(value), _) => Ok(value),
+        (Err(std::env::VarError::NotPresent), Some(default)) => Ok(default.to_string()),
+        (Err(std::env::VarError::NotPresent), None) => Err(AgroError::ConfigValidation(format!(
+            "config field `{key}` references unset environment variable `{name}`"
+        ))),
+        (Err(error), _) => Err(AgroError::ConfigValidation(format!(
+            "config field `{key}` references invalid environment variable `{name}`: {error}"
+        ))),
+    }
+}
+
+/// Reads the JSON obstacle list named by `LIDAR_SIM_OBSTACLES_FILE`; unset
+/// means no configured obstacles.
+fn simulated_obstacles_from_env() -> AgroResult<Vec<SimulatedObstacle>> {
+    let key = "LIDAR_SIM_OBSTACLES_FILE";
+    let Some(path) = env_var(key)?.filter(|path| !path.trim().is_empty()) else {
+        return Ok(Vec::new());
+    };
+    let content = std::fs::read(&path).map_err(|error| {
+        AgroError::ConfigValidation(format!(
+            "config field `{key}` file `{path}` could not be read: {error}"
+        ))
+    })?;
+    serde_json::from_slice(&content).map_err(|error| {
+        AgroError::ConfigValidation(format!(
+            "config field `{key}` file `{path}` is not a valid obstacle list: {error}"
+        ))
+    })
+}
+
 fn missing_required_field(key: &str) -> AgroError {
     AgroError::ConfigValidation(format!("missing required flight config field `{key}`"))
 }
@@ -351,7 +991,7 @@ fn require_fraction(key: &str, value: f32) -> AgroResult<()> {
 
 #[cfg(test)]
 mod tests {
-    use super::AgroConfig;
+    use super::{AgroConfig, ConfigFormat, CorsConfig};
     use crate::RuntimeMode;
     use std::sync::{Mutex, OnceLock};
 
@@ -365,6 +1005,8 @@ mod tests {
         "LIDAR_BAUD_RATE",
         "LIDAR_TIMEOUT_MS",
         "LIDAR_SCAN_FREQUENCY",
+        "LIDAR_POINTS_PER_SCAN",
+        "LIDAR_SIM_OBSTACLES_FILE",
         "CAMERA_DEVICE",
         "MULTISPECTRAL_BANDS",
         "CAMERA_CAPTURE_INTERVAL_MS",
@@ -374,6 +1016,8 @@ mod tests {
         "MISSION_DATA_PATH",
         "WS_BIND_ADDRESS",
         "API_BIND_ADDRESS",
+        "WS_HISTORY_SIZE",
+        "CONTROL_TOKEN",
         "HOME_LATITUDE",
         "HOME_LONGITUDE",
         "HOME_ALTITUDE",
@@ -383,6 +1027,14 @@ mod tests {
         "LIDAR_QUALITY_THRESHOLD",
         "LIDAR_OCCUPANCY_THRESHOLD",
         "LIDAR_IMAGE_FLIP_Y",
+        "CORS_ALLOWED_ORIGINS",
+        "CORS_ALLOWED_METHODS",
+        "CORS_ALLOWED_HEADERS",
+        "CORS_MAX_AGE_SECS",
+        "OPERATOR_NAME",
+        "OPERATOR_ORGANIZATION",
+        "OPERATOR_LICENSE_ID",
+        "OPERATOR_CONTACT",
     ];
 
     fn env_lock() -> &'static Mutex<()> {
@@ -444,6 +1096,165 @@ mod tests {
         assert!(error.to_string().contains("MAVLINK_SERIAL_PORT"));
     }
 
+    #[test]
+    fn config_file_values_override_defaults() {
+        let _lock = env_lock().lock().unwrap_or_else(|error| error.into_inner());
+        let _restore = EnvRestore::clear();
+        let path = std::env::temp_dir().join(format!("agro-config-{}.env", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "WS_BIND_ADDRESS=127.0.0.1:9080\nMAVLINK_BAUD_RATE=115200\nHOME_LATITUDE=12.5\n",
+        )
+        .expect("config file should write");
+
+        let config = AgroConfig::load_with_override(Some(&path));
+        std::fs::remove_file(&path).ok();
+        let config = config.expect("config file should load");
+
+        assert_eq!(config.server.ws_bind_address, "127.0.0.1:9080");
+        assert_eq!(config.mavlink.baud_rate, 115200);
+        assert_eq!(config.gps.home_latitude, 12.5);
+        assert_eq!(config.server.api_bind_address, "0.0.0.0:3000");
+
+        let missing =
+            std::env::temp_dir().join(format!("agro-config-{}.env", uuid::Uuid::new_v4()));
+        let error = AgroConfig::load_from(&missing).expect_err("missing config file should fail");
+        assert!(error.to_string().contains(&missing.display().to_string()));
+    }
+
+    #[test]
+    fn printed_default_config_round_trips_and_matches_env_defaults() {
+        let _lock = env_lock().lock().unwrap_or_else(|error| error.into_inner());
+        let _restore = EnvRestore::clear();
+        let defaults = AgroConfig::default();
+
+        for format in [ConfigFormat::Toml, ConfigFormat::Json] {
+            let document = defaults
+                .to_document(format)
+                .expect("defaults should serialize");
+            let parsed = AgroConfig::from_document(&document, format)
+                .expect("printed defaults should load back");
+            assert_eq!(parsed.to_document(format).unwrap(), document);
+        }
+
+        // An empty environment loads exactly the printed defaults.
+        let from_env = AgroConfig::from_env().expect("simulation defaults should load");
+        assert_eq!(
+            from_env.to_document(ConfigFormat::Json).unwrap(),
+            defaults.to_document(ConfigFormat::Json).unwrap()
+        );
+
+        let path = std::env::temp_dir().join(format!("agro-config-{}.toml", uuid::Uuid::new_v4()));
+        let mut edited = defaults.clone();
+        edited.server.ws_bind_address = "127.0.0.1:9080".to_string();
+        std::fs::write(&path, edited.to_document(ConfigFormat::Toml).unwrap())
+            .expect("config file should write");
+        let loaded = AgroConfig::load_from(&path);
+        std::fs::remove_file(&path).ok();
+        assert_eq!(
+            loaded
+                .expect("toml config should load")
+                .server
+                .ws_bind_address,
+            "127.0.0.1:9080"
+        );
+
+        let mut invalid = defaults.to_document(ConfigFormat::Toml).unwrap();
+        invalid = invalid.replace("home_latitude = 37.7749", "home_latitude = 137.0");
+        assert!(AgroConfig::from_document(&invalid, ConfigFormat::Toml).is_err());
+    }
+
+    #[test]
+    fn config_values_expand_environment_references() {
+        let _lock = env_lock().lock().unwrap_or_else(|error| error.into_inner());
+        let _restore = EnvRestore::clear();
+        std::env::set_var("TEST_VAR", "/dev/ttyACM3");
+        std::env::set_var("AGRO_TEST_API_PORT", "3100");
+        std::env::remove_var("AGRO_TEST_API_HOST");
+        std::env::remove_var("AGRO_TEST_UNSET_VAR");
+        // Single quotes keep the file loader from substituting first.
+        let path = std::env::temp_dir().join(format!("agro-config-{}.env", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "MAVLINK_SERIAL_PORT='${TEST_VAR}'\n\
+             API_BIND_ADDRESS='${AGRO_TEST_API_HOST:-127.0.0.1}:$AGRO_TEST_API_PORT'\n\
+             OPERATOR_CONTACT='ops$$desk'\n",
+        )
+        .expect("config file should write");
+
+        let config = AgroConfig::load_from(&path);
+        std::fs::remove_file(&path).ok();
+        let config = config.expect("references should expand");
+
+        assert_eq!(config.mavlink.serial_port, "/dev/ttyACM3");
+        assert_eq!(config.server.api_bind_address, "127.0.0.1:3100");
+        assert_eq!(config.operator.contact, "ops$desk");
+
+        std::env::set_var("CONTROL_TOKEN", "pa$$word${AGRO_TEST_UNSET_VAR}");
+        let config = AgroConfig::from_env().expect("process environment values are literal");
+        assert_eq!(
+            config.server.control_token.as_deref(),
+            Some("pa$$word${AGRO_TEST_UNSET_VAR}")
+        );
+        std::env::remove_var("CONTROL_TOKEN");
+
+        let path = std::env::temp_dir().join(format!("agro-config-{}.env", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "WS_BIND_ADDRESS='${AGRO_TEST_UNSET_VAR}'\n")
+            .expect("config file should write");
+        let error = AgroConfig::load_from(&path);
+        std::fs::remove_file(&path).ok();
+        let error = error.expect_err("unset reference without default should fail");
+        std::env::remove_var("TEST_VAR");
+        std::env::remove_var("AGRO_TEST_API_PORT");
+
+        let message = error.to_string();
+        assert!(message.contains("WS_BIND_ADDRESS"));
+        assert!(message.contains("AGRO_TEST_UNSET_VAR"));
+    }
+
+    #[test]
+    fn config_documents_expand_environment_references() {
+        let _lock = env_lock().lock().unwrap_or_else(|error| error.into_inner());
+        let _restore = EnvRestore::clear();
+        std::env::set_var("TEST_VAR", "/dev/ttyACM3");
+        std::env::remove_var("AGRO_TEST_UNSET_VAR");
+        let defaults = AgroConfig::default();
+        let document = defaults.to_document(ConfigFormat::Toml).unwrap();
+        let serial_port = format!("serial_port = \"{}\"", defaults.mavlink.serial_port);
+        assert!(document.contains(&serial_port));
+        let document = document.replacen(&serial_port, "serial_port = \"${TEST_VAR}\"", 1);
+
+        let path = std::env::temp_dir().join(format!("agro-config-{}.toml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, &document).expect("config file should write");
+        let config = AgroConfig::load_from(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(
+            config
+                .expect("toml references should expand")
+                .mavlink
+                .serial_port,
+            "/dev/ttyACM3"
+        );
+
+        let mut literal = defaults.clone();
+        literal.operator.contact = "ops$desk".to_string();
+        let printed = literal.to_document(ConfigFormat::Toml).unwrap();
+        assert_eq!(
+            AgroConfig::from_document(&printed, ConfigFormat::Toml)
+                .unwrap()
+                .operator
+                .contact,
+            "ops$desk"
+        );
+
+        let unset = document.replace("${TEST_VAR}", "${AGRO_TEST_UNSET_VAR}");
+        let error = AgroConfig::from_document(&unset, ConfigFormat::Toml)
+            .expect_err("unset reference without default should fail");
+        std::env::remove_var("TEST_VAR");
+        assert!(error.to_string().contains("mavlink.serial_port"));
+    }
+
     #[test]
     fn config_rejects_out_of_range_gps_values() {
         let _lock = env_lock().lock().unwrap_or_else(|error| error.into_inner());
@@ -454,4 +1265,48 @@ mod tests {
 
         assert!(error.to_string().contains("HOME_LATITUDE"));
     }
+
+    #[test]
+    fn cors_config_parses_allowlist_from_env() {
+        let _lock = env_lock().lock().unwrap_or_else(|error| error.into_inner());
+        let _restore = EnvRestore::clear();
+        std::env::set_var(
+            "CORS_ALLOWED_ORIGINS",
+            "https://ops.example.com, https://field.example.com",
+        );
+        std::env::set_var("CORS_ALLOWED_METHODS", "get,post");
+
+        let config = AgroConfig::load().expect("cors allowlist should load");
+
+        assert_eq!(
+            config.cors.allowed_origins,
+            vec!["https://ops.example.com", "https://field.example.com"]
+        );
+        assert_eq!(config.cors.allowed_methods, vec!["GET", "POST"]);
+        assert!(config.cors.allows_origin("https://ops.example.com"));
+        assert!(!config.cors.allows_origin("https://evil.example.com"));
+        assert!(!config.cors.allows_any_origin());
+    }
+
+    #[test]
+    fn cors_config_rejects_malformed_origin() {
+        let _lock = env_lock().lock().unwrap_or_else(|error| error.into_inner());
+        let _restore = EnvRestore::clear();
+        std::env::set_var("CORS_ALLOWED_ORIGINS", "ops.example.com");
+
+        let error = AgroConfig::load().expect_err("origin without scheme should fail");
+
+        assert!(error.to_string().contains("CORS_ALLOWED_ORIGINS"));
+    }
+
+    #[test]
+    fn cors_wildcard_must_be_explicit() {
+        let config = CorsConfig {
+            allowed_origins: vec!["*".to_string()],
+            ..CorsConfig::default()
+        };
+
+        assert!(config.allows_origin("https://anything.example.com"));
+        assert!(!CorsConfig::default().allows_origin("https://anything.example.com"));
+    }
 }
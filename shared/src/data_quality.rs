@@ -0,0 +1,156 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Outcome of the data-quality pass over one capture session. Component
+/// metrics are `None` when the session holds no data of that kind, in which
+/// case they do not count towards `score`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionQualityAssessment {
+    pub session_id: Uuid,
+    pub assessed_at: DateTime<Utc>,
+    /// Weighted roll-up of the component scores, 0 (unusable) to 100.
+    pub score: u8,
+    pub telemetry: TelemetryQualityMetrics,
+    pub image_spacing: Option<ImageSpacingMetrics>,
+    pub band_completeness: Option<BandCompletenessMetrics>,
+    pub lidar_stability: Option<LidarStabilityMetrics>,
+    pub flags: Vec<QualityFlag>,
+}
+
+impl SessionQualityAssessment {
+    pub fn flags_of(&self, flag_type: QualityFlagType) -> impl Iterator<Item = &QualityFlag> {
+        self.flags
+            .iter()
+            .filter(move |flag| flag.flag_type == flag_type)
+    }
+
+    pub fn worst_severity(&self) -> Option<QualitySeverity> {
+        self.flags.iter().map(|flag| flag.severity).max()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryQualityMetrics {
+    pub sample_count: usize,
+    pub max_gap_seconds: f64,
+    pub gap_histogram: Vec<GapHistogramBucket>,
+    /// Fraction of the track not lost to gaps, 0.0 to 1.0.
+    pub score: f64,
+}
+
+/// Number of sample-to-sample gaps no longer than `max_seconds`; the last
+/// bucket is open-ended.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GapHistogramBucket {
+    pub max_seconds: Option<f64>,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageSpacingMetrics {
+    pub image_count: usize,
+    pub expected_interval_seconds: f64,
+    pub mean_interval_seconds: f64,
+    pub max_interval_seconds: f64,
+    pub late_interval_count: u32,
+    /// Captured images over the number the expected interval calls for.
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BandCompletenessMetrics {
+    pub capture_count: usize,
+    pub complete_count: usize,
+    pub required_bands: Vec<String>,
+    /// Required bands absent from at least one capture, sorted.
+    pub missing_bands: Vec<String>,
+    /// Fraction of captures with every required band.
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LidarStabilityMetrics {
+    pub scan_count: usize,
+    pub mean_scans_per_minute: f64,
+    pub min_scans_per_minute: f64,
+    pub max_scans_per_minute: f64,
+    /// Standard deviation over mean of the per-minute scan counts.
+    pub coefficient_of_variation: f64,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityFlag {
+    pub flag_type: QualityFlagType,
+    pub severity: QualitySeverity,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Part of the field the affected data was captured over, relative to
+    /// the centre of the flight track.
+    pub area: Option<FieldStrip>,
+    pub message: String,
+    pub suggestion: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityFlagType {
+    NoTelemetry,
+    TelemetryGap,
+    ImageSpacingGap,
+    MissingBands,
+    LidarDropout,
+}
+
+impl QualityFlagType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QualityFlagType::NoTelemetry => "no_telemetry",
+            QualityFlagType::TelemetryGap => "telemetry_gap",
+            QualityFlagType::ImageSpacingGap => "image_spacing_gap",
+            QualityFlagType::MissingBands => "missing_bands",
+            QualityFlagType::LidarDropout => "lidar_dropout",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualitySeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl QualitySeverity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QualitySeverity::Info => "info",
+            QualitySeverity::Warning => "warning",
+            QualitySeverity::Critical => "critical",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldStrip {
+    North,
+    South,
+    East,
+    West,
+    Center,
+}
+
+impl FieldStrip {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FieldStrip::North => "north",
+            FieldStrip::South => "south",
+            FieldStrip::East => "east",
+            FieldStrip::West => "west",
+            FieldStrip::Center => "center",
+        }
+    }
+}
@@ -14,6 +14,9 @@ pub enum AgroError {
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
+    #[error("Artifact schema error: {0}")]
+    ArtifactSchema(#[from] crate::artifact_schema::ArtifactSchemaError),
+
     #[error("MAVLink communication error: {0}")]
     Mavlink(String),
 
@@ -23,6 +26,22 @@ pub enum AgroError {
     #[error("Processing error: {0}")]
     Processing(String),
 
+    #[error("{role} band '{band}' not found")]
+    MissingBand { role: String, band: String },
+
+    #[error("{subject} dimensions mismatch: expected {expected}, got {actual}")]
+    DimensionMismatch {
+        subject: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Failed to decode {path}: {reason}")]
+    DecodeError { path: String, reason: String },
+
+    #[error("Empty input: {0}")]
+    EmptyInput(String),
+
     #[error("Network error: {0}")]
     Network(String),
 
@@ -32,3 +51,24 @@ pub enum AgroError {
     #[error("Unknown error: {0}")]
     Other(#[from] anyhow::Error),
 }
+
+impl AgroError {
+    pub fn dimension_mismatch(
+        subject: impl Into<String>,
+        expected: (u32, u32),
+        actual: (u32, u32),
+    ) -> Self {
+        Self::DimensionMismatch {
+            subject: subject.into(),
+            expected: format!("{}x{}", expected.0, expected.1),
+            actual: format!("{}x{}", actual.0, actual.1),
+        }
+    }
+
+    /// Whether running the same work again may succeed. Bad or missing
+    /// inputs fail the same way every time; I/O and network failures may
+    /// not.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Io(_) | Self::Network(_))
+    }
+}
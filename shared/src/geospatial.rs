@@ -0,0 +1,622 @@
+use crate::schemas;
+use crate::types::GeoCoordinate;
+use geo_types::{LineString, Point, Polygon};
+use nalgebra::Point3;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// WGS84 position with named latitude/longitude fields.
+///
+/// There is deliberately no `From<(f64, f64)>`: a bare tuple does not say
+/// which axis comes first, so tuple conversions go through the explicitly
+/// ordered helpers instead.
+///
+/// ```compile_fail
+/// let point: shared::GeoPoint = (41.25, -96.01).into();
+/// ```
+///
+/// ```
+/// let point = shared::GeoPoint::from_lat_lon_tuple((41.25, -96.01));
+/// assert_eq!(point.to_lon_lat_tuple(), (-96.01, 41.25));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeoPoint {
+    #[serde(alias = "latitude")]
+    pub lat: f64,
+    #[serde(alias = "longitude")]
+    pub lon: f64,
+    #[serde(default, alias = "altitude_m", skip_serializing_if = "Option::is_none")]
+    pub alt_m: Option<f64>,
+}
+
+impl GeoPoint {
+    pub fn new(lat: f64, lon: f64) -> Self {
+        Self {
+            lat,
+            lon,
+            alt_m: None,
+        }
+    }
+
+    pub fn with_altitude(mut self, alt_m: f64) -> Self {
+        self.alt_m = Some(alt_m);
+        self
+    }
+
+    pub fn from_lat_lon_tuple((lat, lon): (f64, f64)) -> Self {
+        Self::new(lat, lon)
+    }
+
+    pub fn from_lon_lat_tuple((lon, lat): (f64, f64)) -> Self {
+        Self::new(lat, lon)
+    }
+
+    pub fn to_lat_lon_tuple(&self) -> (f64, f64) {
+        (self.lat, self.lon)
+    }
+
+    pub fn to_lon_lat_tuple(&self) -> (f64, f64) {
+        (self.lon, self.lat)
+    }
+
+    /// True when both axes are finite and inside the WGS84 ranges.
+    pub fn is_valid(&self) -> bool {
+        self.lat.is_finite()
+            && self.lon.is_finite()
+            && (-90.0..=90.0).contains(&self.lat)
+            && (-180.0..=180.0).contains(&self.lon)
+            && self.alt_m.is_none_or(f64::is_finite)
+    }
+
+    /// Great-circle distance in metres, ignoring altitude.
+    pub fn haversine_distance_m(&self, other: &GeoPoint) -> f64 {
+        let delta_lat = (other.lat - self.lat).to_radians();
+        let delta_lon = (other.lon - self.lon).to_radians();
+        let a = (delta_lat / 2.0).sin().powi(2)
+            + self.lat.to_radians().cos()
+                * other.lat.to_radians().cos()
+                * (delta_lon / 2.0).sin().powi(2);
+        EARTH_RADIUS_M * 2.0 * a.sqrt().atan2((1.0 - a).sqrt())
+    }
+}
+
+/// `geo` stores longitude in `x` and latitude in `y`.
+impl From<Point<f64>> for GeoPoint {
+    fn from(point: Point<f64>) -> Self {
+        Self::new(point.y(), point.x())
+    }
+}
+
+impl From<GeoPoint> for Point<f64> {
+    fn from(point: GeoPoint) -> Self {
+        Point::new(point.lon, point.lat)
+    }
+}
+
+impl From<GeoCoordinate> for GeoPoint {
+    fn from(coordinate: GeoCoordinate) -> Self {
+        Self::new(coordinate.latitude, coordinate.longitude)
+            .with_altitude(coordinate.altitude_m as f64)
+    }
+}
+
+impl From<GeoPoint> for GeoCoordinate {
+    fn from(point: GeoPoint) -> Self {
+        GeoCoordinate::new(point.lat, point.lon, point.alt_m.unwrap_or(0.0) as f32)
+    }
+}
+
+impl From<schemas::GeoPoint> for GeoPoint {
+    fn from(point: schemas::GeoPoint) -> Self {
+        Self::new(point.latitude, point.longitude)
+    }
+}
+
+impl From<GeoPoint> for schemas::GeoPoint {
+    fn from(point: GeoPoint) -> Self {
+        schemas::GeoPoint {
+            longitude: point.lon,
+            latitude: point.lat,
+        }
+    }
+}
+
+/// Serde adapter keeping the `{"x": lon, "y": lat}` layout written by
+/// `geo::Point` fields, for stored documents that predate [`GeoPoint`].
+pub mod geo_point_xy {
+    use super::GeoPoint;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct XyPoint {
+        x: f64,
+        y: f64,
+    }
+
+    pub fn serialize<S: Serializer>(point: &GeoPoint, serializer: S) -> Result<S::Ok, S::Error> {
+        XyPoint {
+            x: point.lon,
+            y: point.lat,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<GeoPoint, D::Error> {
+        let point = XyPoint::deserialize(deserializer)?;
+        Ok(GeoPoint::new(point.y, point.x))
+    }
+}
+
+/// Open ring of WGS84 vertices; the closing vertex is implied.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GeoPolygon {
+    pub exterior: Vec<GeoPoint>,
+}
+
+impl GeoPolygon {
+    pub fn new(exterior: Vec<GeoPoint>) -> Self {
+        Self { exterior }
+    }
+
+    /// Planar ray-cast test in degrees; fine for field-sized polygons.
+    pub fn contains(&self, point: &GeoPoint) -> bool {
+        ring_contains(
+            self.exterior.iter().map(GeoPoint::to_lon_lat_tuple),
+            point.to_lon_lat_tuple(),
+        )
+    }
+}
+
+impl From<Polygon<f64>> for GeoPolygon {
+    fn from(polygon: Polygon<f64>) -> Self {
+        let mut exterior: Vec<GeoPoint> = polygon.exterior().points().map(GeoPoint::from).collect();
+        if exterior.len() > 1 && exterior.first() == exterior.last() {
+            exterior.pop();
+        }
+        Self { exterior }
+    }
+}
+
+impl From<GeoPolygon> for Polygon<f64> {
+    fn from(polygon: GeoPolygon) -> Self {
+        let ring: LineString<f64> = polygon
+            .exterior
+            .into_iter()
+            .map(Point::from)
+            .collect::<Vec<_>>()
+            .into();
+        Polygon::new(ring, Vec::new())
+    }
+}
+
+/// Position in a local east/north/up metre frame.
+///
+/// Serialized as `[east, north]` (or `[east, north, up]` when `up_m` is
+/// non-zero) so it reads and writes the tuple layout used before; the named
+/// struct form is accepted on input too. Like [`GeoPoint`] there is no
+/// `From` tuple conversion.
+///
+/// ```compile_fail
+/// let point: shared::LocalPoint = (120.0, -40.0).into();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LocalPoint {
+    pub east_m: f64,
+    pub north_m: f64,
+    pub up_m: f64,
+}
+
+impl LocalPoint {
+    pub fn new(east_m: f64, north_m: f64) -> Self {
+        Self {
+            east_m,
+            north_m,
+            up_m: 0.0,
+        }
+    }
+
+    pub fn with_up(mut self, up_m: f64) -> Self {
+        self.up_m = up_m;
+        self
+    }
+
+    pub fn from_xy((east_m, north_m): (f64, f64)) -> Self {
+        Self::new(east_m, north_m)
+    }
+
+    pub fn from_xyz((east_m, north_m, up_m): (f64, f64, f32)) -> Self {
+        Self::new(east_m, north_m).with_up(up_m as f64)
+    }
+
+    pub fn to_xy(&self) -> (f64, f64) {
+        (self.east_m, self.north_m)
+    }
+
+    pub fn to_xyz(&self) -> (f64, f64, f32) {
+        (self.east_m, self.north_m, self.up_m as f32)
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.east_m.is_finite() && self.north_m.is_finite() && self.up_m.is_finite()
+    }
+
+    pub fn horizontal_distance_m(&self, other: &LocalPoint) -> f64 {
+        (self.east_m - other.east_m).hypot(self.north_m - other.north_m)
+    }
+}
+
+impl From<Point<f64>> for LocalPoint {
+    fn from(point: Point<f64>) -> Self {
+        Self::new(point.x(), point.y())
+    }
+}
+
+impl From<LocalPoint> for Point<f64> {
+    fn from(point: LocalPoint) -> Self {
+        Point::new(point.east_m, point.north_m)
+    }
+}
+
+impl From<Point3<f64>> for LocalPoint {
+    fn from(point: Point3<f64>) -> Self {
+        Self::new(point.x, point.y).with_up(point.z)
+    }
+}
+
+impl From<LocalPoint> for Point3<f64> {
+    fn from(point: LocalPoint) -> Self {
+        Point3::new(point.east_m, point.north_m, point.up_m)
+    }
+}
+
+impl Serialize for LocalPoint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.up_m == 0.0 {
+            (self.east_m, self.north_m).serialize(serializer)
+        } else {
+            (self.east_m, self.north_m, self.up_m).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for LocalPoint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Xy(f64, f64),
+            Xyz(f64, f64, f64),
+            Named {
+                east_m: f64,
+                north_m: f64,
+                #[serde(default)]
+                up_m: f64,
+            },
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::Xy(east_m, north_m) => LocalPoint::new(east_m, north_m),
+            Repr::Xyz(east_m, north_m, up_m) => LocalPoint::new(east_m, north_m).with_up(up_m),
+            Repr::Named {
+                east_m,
+                north_m,
+                up_m,
+            } => LocalPoint::new(east_m, north_m).with_up(up_m),
+        })
+    }
+}
+
+/// Open ring of local-frame vertices; serializes as a plain array of points.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LocalPolygon {
+    pub vertices: Vec<LocalPoint>,
+}
+
+impl LocalPolygon {
+    pub fn new(vertices: Vec<LocalPoint>) -> Self {
+        Self { vertices }
+    }
+
+    pub fn from_xy_points(points: impl IntoIterator<Item = (f64, f64)>) -> Self {
+        Self::new(points.into_iter().map(LocalPoint::from_xy).collect())
+    }
+
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Horizontal ray-cast test; always false for fewer than three vertices.
+    pub fn contains(&self, point: &LocalPoint) -> bool {
+        ring_contains(self.vertices.iter().map(LocalPoint::to_xy), point.to_xy())
+    }
+}
+
+/// Tangent-plane frame anchored at `origin`, using an equirectangular
+/// approximation that is accurate to well under a metre across a farm.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LocalFrame {
+    pub origin: GeoPoint,
+}
+
+impl LocalFrame {
+    pub fn new(origin: GeoPoint) -> Self {
+        Self { origin }
+    }
+
+    pub fn to_local(&self, point: &GeoPoint) -> LocalPoint {
+        let up_m = match (point.alt_m, self.origin.alt_m) {
+            (Some(alt_m), Some(origin_alt_m)) => alt_m - origin_alt_m,
+            (Some(alt_m), None) => alt_m,
+            _ => 0.0,
+        };
+        LocalPoint::new(
+            (point.lon - self.origin.lon) * METERS_PER_DEGREE * self.origin.lat.to_radians().cos(),
+            (point.lat - self.origin.lat) * METERS_PER_DEGREE,
+        )
+        .with_up(up_m)
+    }
+
+    /// Altitude is only set when the origin carries one or `up_m` is non-zero.
+    pub fn to_geo(&self, point: &LocalPoint) -> GeoPoint {
+        let lat = self.origin.lat + point.north_m / METERS_PER_DEGREE;
+        let lon = self.origin.lon
+            + point.east_m / (METERS_PER_DEGREE * self.origin.lat.to_radians().cos());
+        let alt_m = match self.origin.alt_m {
+            Some(origin_alt_m) => Some(origin_alt_m + point.up_m),
+            None if point.up_m != 0.0 => Some(point.up_m),
+            None => None,
+        };
+        GeoPoint { lat, lon, alt_m }
+    }
+}
+
+/// Datum an altitude is measured from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AltitudeReference {
+    /// Above the terrain directly below the aircraft.
+    Agl,
+    /// Above mean sea level, as reported by GPS.
+    Msl,
+    /// Above the home (arming) position; the autopilot default for missions.
+    #[default]
+    RelativeToHome,
+}
+
+/// Elevations needed to move an altitude between references. Only the ones
+/// the conversion touches have to be known.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AltitudeDatum {
+    pub home_elevation_msl_m: Option<f64>,
+    pub terrain_elevation_msl_m: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum AltitudeConversionError {
+    #[error("converting between {from:?} and {to:?} altitudes needs the home elevation")]
+    MissingHomeElevation {
+        from: AltitudeReference,
+        to: AltitudeReference,
+    },
+    #[error("converting between {from:?} and {to:?} altitudes needs the terrain elevation")]
+    MissingTerrainElevation {
+        from: AltitudeReference,
+        to: AltitudeReference,
+    },
+}
+
+impl AltitudeDatum {
+    pub fn new(home_elevation_msl_m: Option<f64>, terrain_elevation_msl_m: Option<f64>) -> Self {
+        Self {
+            home_elevation_msl_m,
+            terrain_elevation_msl_m,
+        }
+    }
+}
+
+impl AltitudeReference {
+    /// Re-expresses `altitude_m` (measured from `self`) against `target`.
+    /// Converting to the same reference never needs a datum.
+    pub fn convert(
+        self,
+        altitude_m: f64,
+        target: AltitudeReference,
+        datum: &AltitudeDatum,
+    ) -> Result<f64, AltitudeConversionError> {
+        if self == target {
+            return Ok(altitude_m);
+        }
+        let offset = |reference: AltitudeReference| -> Result<f64, AltitudeConversionError> {
+            match reference {
+                Self::Msl => Ok(0.0),
+                Self::RelativeToHome => datum.home_elevation_msl_m.ok_or(
+                    AltitudeConversionError::MissingHomeElevation {
+                        from: self,
+                        to: target,
+                    },
+                ),
+                Self::Agl => datum.terrain_elevation_msl_m.ok_or(
+                    AltitudeConversionError::MissingTerrainElevation {
+                        from: self,
+                        to: target,
+                    },
+                ),
+            }
+        };
+        Ok(altitude_m + offset(self)? - offset(target)?)
+    }
+}
+
+fn ring_contains(
+    ring: impl ExactSizeIterator<Item = (f64, f64)> + Clone,
+    (x, y): (f64, f64),
+) -> bool {
+    if ring.len() < 3 {
+        return false;
+    }
+
+    let mut inside = false;
+    let mut previous = ring.clone().last().expect("ring has at least three points");
+    for (xi, yi) in ring {
+        let (xj, yj) = previous;
+        if ((yi > y) != (yj > y)) && (x < (xj - xi) * (y - yi) / (yj - yi) + xi) {
+            inside = !inside;
+        }
+        previous = (xi, yi);
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn altitude_conversions_go_through_msl_and_need_only_the_datums_they_touch() {
+        let datum = AltitudeDatum::new(Some(350.0), Some(342.5));
+        let relative = AltitudeReference::RelativeToHome;
+
+        assert_eq!(
+            relative.convert(40.0, AltitudeReference::Msl, &datum),
+            Ok(390.0)
+        );
+        assert_eq!(
+            relative.convert(40.0, AltitudeReference::Agl, &datum),
+            Ok(47.5)
+        );
+        assert_eq!(
+            AltitudeReference::Agl.convert(47.5, relative, &datum),
+            Ok(40.0)
+        );
+        assert_eq!(
+            AltitudeReference::Msl.convert(390.0, relative, &AltitudeDatum::default()),
+            Err(AltitudeConversionError::MissingHomeElevation {
+                from: AltitudeReference::Msl,
+                to: relative,
+            })
+        );
+        assert_eq!(
+            AltitudeReference::Agl.convert(12.0, AltitudeReference::Agl, &AltitudeDatum::default()),
+            Ok(12.0)
+        );
+        assert_eq!(
+            serde_json::to_value(relative).unwrap(),
+            serde_json::json!("relative_to_home")
+        );
+    }
+
+    #[test]
+    fn geo_point_round_trips_through_geo_tuples_and_legacy_types() {
+        let point = GeoPoint::new(41.25, -96.01);
+
+        let geo_point: Point<f64> = point.into();
+        assert_eq!((geo_point.x(), geo_point.y()), (-96.01, 41.25));
+        assert_eq!(GeoPoint::from(geo_point), point);
+        assert_eq!(
+            GeoPoint::from_lat_lon_tuple(point.to_lat_lon_tuple()),
+            point
+        );
+        assert_eq!(
+            GeoPoint::from_lon_lat_tuple(point.to_lon_lat_tuple()),
+            point
+        );
+
+        let coordinate: GeoCoordinate = point.with_altitude(120.0).into();
+        assert_eq!(
+            (
+                coordinate.latitude,
+                coordinate.longitude,
+                coordinate.altitude_m
+            ),
+            (41.25, -96.01, 120.0)
+        );
+        assert_eq!(GeoPoint::from(coordinate), point.with_altitude(120.0));
+        assert_eq!(GeoPoint::from(schemas::GeoPoint::from(point)), point);
+    }
+
+    #[test]
+    fn geo_point_reads_existing_json_layouts() {
+        let compact: GeoPoint = serde_json::from_str(r#"{"lat":41.25,"lon":-96.01}"#).unwrap();
+        let schema: GeoPoint =
+            serde_json::from_str(r#"{"longitude":-96.01,"latitude":41.25}"#).unwrap();
+        let telemetry: GeoPoint =
+            serde_json::from_str(r#"{"latitude":41.25,"longitude":-96.01,"altitude_m":120.0}"#)
+                .unwrap();
+
+        assert_eq!(compact, GeoPoint::new(41.25, -96.01));
+        assert_eq!(schema, compact);
+        assert_eq!(telemetry, compact.with_altitude(120.0));
+        assert_eq!(
+            serde_json::to_value(compact).unwrap(),
+            serde_json::json!({"lat": 41.25, "lon": -96.01})
+        );
+
+        #[derive(Serialize, Deserialize)]
+        struct Stored {
+            #[serde(with = "geo_point_xy")]
+            position: GeoPoint,
+        }
+        let stored: Stored =
+            serde_json::from_str(r#"{"position":{"x":-96.01,"y":41.25}}"#).unwrap();
+        assert_eq!(stored.position, compact);
+        assert_eq!(
+            serde_json::to_value(&stored).unwrap(),
+            serde_json::json!({"position": {"x": -96.01, "y": 41.25}})
+        );
+    }
+
+    #[test]
+    fn local_points_keep_tuple_json_and_convert_to_geo_and_nalgebra() {
+        let boundary: LocalPolygon =
+            serde_json::from_str("[[-10.0,-10.0],[10.0,-10.0],[10.0,10.0],[-10.0,10.0]]").unwrap();
+        assert_eq!(
+            serde_json::to_string(&boundary).unwrap(),
+            "[[-10.0,-10.0],[10.0,-10.0],[10.0,10.0],[-10.0,10.0]]"
+        );
+        assert!(boundary.contains(&LocalPoint::new(0.0, 0.0)));
+        assert!(!boundary.contains(&LocalPoint::new(15.0, 0.0)));
+        assert!(!LocalPolygon::from_xy_points([(0.0, 0.0), (1.0, 1.0)])
+            .contains(&LocalPoint::new(0.5, 0.5)));
+
+        let named: LocalPoint =
+            serde_json::from_str(r#"{"east_m":3.0,"north_m":4.0,"up_m":5.0}"#).unwrap();
+        assert_eq!(named, LocalPoint::from_xyz((3.0, 4.0, 5.0)));
+        assert_eq!(serde_json::to_string(&named).unwrap(), "[3.0,4.0,5.0]");
+
+        let point3: Point3<f64> = named.into();
+        assert_eq!(LocalPoint::from(point3), named);
+        let planar: Point<f64> = named.into();
+        assert_eq!((planar.x(), planar.y()), (3.0, 4.0));
+        assert_eq!(LocalPoint::from(planar), LocalPoint::new(3.0, 4.0));
+    }
+
+    #[test]
+    fn local_frame_round_trips_geo_points() {
+        let frame = LocalFrame::new(GeoPoint::new(41.25, -96.01).with_altitude(350.0));
+        let target = GeoPoint::new(41.2509, -96.0088).with_altitude(380.0);
+
+        let local = frame.to_local(&target);
+        assert!(local.east_m > 90.0 && local.east_m < 110.0);
+        assert!((local.north_m - 100.188).abs() < 0.01);
+        assert!((local.up_m - 30.0).abs() < 1e-9);
+        assert!(
+            (frame.origin.haversine_distance_m(&target)
+                - local.horizontal_distance_m(&LocalPoint::default()))
+            .abs()
+                < 0.5
+        );
+
+        let back = frame.to_geo(&local);
+        assert!((back.lat - target.lat).abs() < 1e-9);
+        assert!((back.lon - target.lon).abs() < 1e-9);
+        assert_eq!(back.alt_m, Some(380.0));
+    }
+}
@@ -0,0 +1,828 @@
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{IntoUrl, Method, Request, RequestBuilder, Response, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Headers logged as `<redacted>` on every client, on top of
+/// [`HttpClientConfig::redacted_headers`].
+const ALWAYS_REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+];
+
+/// When and how often a failed call is repeated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Attempts per call, the first included.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles with each further retry.
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Random extra delay on each retry, as a fraction of the backoff.
+    pub jitter: f64,
+    /// Retry POST and PATCH too. Otherwise only idempotent methods are
+    /// retried, unless the call goes through [`HttpClient::send_retryable`].
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 200,
+            max_backoff_ms: 10_000,
+            jitter: 0.2,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry`, counting from 1, for a
+    /// `jitter_sample` drawn from `[0, 1)`.
+    pub fn backoff(&self, retry: u32, jitter_sample: f64) -> Duration {
+        let factor = 2u64.saturating_pow(retry.saturating_sub(1));
+        let base_ms = self
+            .initial_backoff_ms
+            .saturating_mul(factor)
+            .min(self.max_backoff_ms);
+        let jitter_ms = base_ms as f64 * self.jitter * jitter_sample.clamp(0.0, 1.0);
+        Duration::from_millis(base_ms.saturating_add(jitter_ms as u64))
+    }
+}
+
+/// Per-host breaker that stops calls to a host that keeps failing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerPolicy {
+    /// Consecutive failed attempts that open the breaker; 0 disables it.
+    pub failure_threshold: u32,
+    /// How long an open breaker rejects calls before letting one probe
+    /// through.
+    pub open_ms: u64,
+}
+
+impl Default for CircuitBreakerPolicy {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_ms: 30_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpClientConfig {
+    /// Per-attempt timeout for requests that do not set their own.
+    pub timeout_ms: u64,
+    pub retry: RetryPolicy,
+    pub circuit_breaker: CircuitBreakerPolicy,
+    /// Extra header names whose values are kept out of the logs.
+    pub redacted_headers: Vec<String>,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 10_000,
+            retry: RetryPolicy::default(),
+            circuit_breaker: CircuitBreakerPolicy::default(),
+            redacted_headers: Vec::new(),
+        }
+    }
+}
+
+impl HttpClientConfig {
+    pub fn validate(&self) -> Result<(), HttpClientError> {
+        let reason = if self.timeout_ms == 0 {
+            Some("timeout must be positive")
+        } else if self.retry.max_attempts == 0 {
+            Some("retry policy needs at least one attempt")
+        } else if !(0.0..=1.0).contains(&self.retry.jitter) {
+            Some("retry jitter must be between 0 and 1")
+        } else if self.circuit_breaker.failure_threshold > 0 && self.circuit_breaker.open_ms == 0 {
+            Some("circuit breaker open time must be positive")
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => Err(HttpClientError::Config {
+                reason: reason.to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    /// Calls are rejected without reaching the host.
+    Open,
+    /// One probe call is in flight; its outcome closes or reopens the breaker.
+    HalfOpen,
+}
+
+/// Breaker state for one host, as shown in health reports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitBreakerStatus {
+    /// `host:port`.
+    pub host: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    /// When the breaker last opened; cleared once it closes.
+    pub opened_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HttpClientError {
+    #[error("invalid HTTP client configuration: {reason}")]
+    Config { reason: String },
+    #[error("failed to build HTTP client: {reason}")]
+    Client { reason: String },
+    #[error("invalid HTTP request: {reason}")]
+    Request { reason: String },
+    #[error("circuit breaker for {host} is open after {attempts} attempts")]
+    CircuitOpen { host: String, attempts: u32 },
+    #[error("request to {host} failed after {attempts} attempts: {reason}")]
+    Transport {
+        host: String,
+        attempts: u32,
+        reason: String,
+    },
+    #[error("{host} returned {status} after {attempts} attempts")]
+    Status {
+        host: String,
+        status: StatusCode,
+        attempts: u32,
+    },
+}
+
+impl HttpClientError {
+    /// Attempts made before the call gave up; 0 when nothing was sent.
+    pub fn attempts(&self) -> u32 {
+        match self {
+            Self::CircuitOpen { attempts, .. }
+            | Self::Transport { attempts, .. }
+            | Self::Status { attempts, .. } => *attempts,
+            Self::Config { .. } | Self::Client { .. } | Self::Request { .. } => 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum BreakerState {
+    Closed,
+    Open {
+        until: Instant,
+    },
+    /// A probe is in flight; another is let through after `until` in case
+    /// the first was cancelled before reporting back.
+    HalfOpen {
+        until: Instant,
+    },
+}
+
+#[derive(Debug)]
+struct Breaker {
+    state: BreakerState,
+    consecutive_failures: u32,
+    opened_at: Option<DateTime<Utc>>,
+}
+
+impl Default for Breaker {
+    fn default() -> Self {
+        Self {
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+        }
+    }
+}
+
+/// Outbound HTTP with retries, per-host circuit breaking and request
+/// tracing. Clones share the connection pool and the breakers.
+///
+/// Every call resolves to `Ok` only for responses below 400; error
+/// statuses come back as [`HttpClientError::Status`] once retries are
+/// spent.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    config: Arc<HttpClientConfig>,
+    breakers: Arc<Mutex<BTreeMap<String, Breaker>>>,
+}
+
+impl HttpClient {
+    pub fn new(config: HttpClientConfig) -> Result<Self, HttpClientError> {
+        let client =
+            reqwest::Client::builder()
+                .build()
+                .map_err(|error| HttpClientError::Client {
+                    reason: error.to_string(),
+                })?;
+        Self::with_client(client, config)
+    }
+
+    /// Wraps an already configured `client`, e.g. one with proxies turned
+    /// off.
+    pub fn with_client(
+        client: reqwest::Client,
+        config: HttpClientConfig,
+    ) -> Result<Self, HttpClientError> {
+        config.validate()?;
+        Ok(Self {
+            client,
+            config: Arc::new(config),
+            breakers: Arc::new(Mutex::new(BTreeMap::new())),
+        })
+    }
+
+    pub fn config(&self) -> &HttpClientConfig {
+        &self.config
+    }
+
+    pub fn get(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn post(&self, url: impl IntoUrl) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    pub fn request(&self, method: Method, url: impl IntoUrl) -> RequestBuilder {
+        self.client.request(method, url)
+    }
+
+    /// Sends `request`, retrying it under the retry policy when its method is
+    /// idempotent.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response, HttpClientError> {
+        self.execute(request, false).await
+    }
+
+    /// Like [`Self::send`], but retries whatever the method; for calls the
+    /// receiver can deduplicate, such as a POST carrying a delivery id.
+    pub async fn send_retryable(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<Response, HttpClientError> {
+        self.execute(request, true).await
+    }
+
+    /// Breaker state for every host called so far, sorted by host.
+    pub fn circuit_breakers(&self) -> Vec<CircuitBreakerStatus> {
+        let now = Instant::now();
+        lock(&self.breakers)
+            .iter()
+            .map(|(host, breaker)| CircuitBreakerStatus {
+                host: host.clone(),
+                state: match breaker.state {
+                    BreakerState::Closed => CircuitState::Closed,
+                    BreakerState::Open { until } if now < until => CircuitState::Open,
+                    BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => {
+                        CircuitState::HalfOpen
+                    }
+                },
+                consecutive_failures: breaker.consecutive_failures,
+                opened_at: breaker.opened_at,
+            })
+            .collect()
+    }
+
+    async fn execute(
+        &self,
+        request: RequestBuilder,
+        retry_any_method: bool,
+    ) -> Result<Response, HttpClientError> {
+        let mut request = request.build().map_err(|error| HttpClientError::Request {
+            reason: error.to_string(),
+        })?;
+        if request.timeout().is_none() {
+            *request.timeout_mut() = Some(Duration::from_millis(self.config.timeout_ms));
+        }
+        let host = host_key(request.url());
+        let policy = &self.config.retry;
+        let max_attempts =
+            if retry_any_method || policy.retry_non_idempotent || is_idempotent(request.method()) {
+                policy.max_attempts
+            } else {
+                1
+            };
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            // A streaming body cannot be replayed, so such a call gets one attempt.
+            let replay = (attempt < max_attempts)
+                .then(|| request.try_clone())
+                .flatten();
+            if !self.acquire(&host) {
+                return Err(HttpClientError::CircuitOpen {
+                    host,
+                    attempts: attempt - 1,
+                });
+            }
+
+            self.trace_request(&request, attempt);
+            let started = Instant::now();
+            let (replay, retry_after) = match self.client.execute(request).await {
+                Ok(response) => {
+                    let status = response.status();
+                    self.trace_response(&response, attempt, started.elapsed());
+                    self.record(&host, !is_host_failure(status));
+                    if !status.is_client_error() && !status.is_server_error() {
+                        return Ok(response);
+                    }
+                    let retry_after = retry_after(response.headers());
+                    // Retrying sooner than the host asked would only be refused again.
+                    let too_soon = retry_after
+                        .is_some_and(|delay| delay > Duration::from_millis(policy.max_backoff_ms));
+                    match replay {
+                        Some(replay) if is_retryable(status) && !too_soon => (replay, retry_after),
+                        _ => {
+                            return Err(HttpClientError::Status {
+                                host,
+                                status,
+                                attempts: attempt,
+                            })
+                        }
+                    }
+                }
+                Err(error) => {
+                    tracing::debug!(%host, attempt, %error, "HTTP request failed");
+                    self.record(&host, false);
+                    let Some(replay) = replay else {
+                        return Err(HttpClientError::Transport {
+                            host,
+                            attempts: attempt,
+                            reason: error.to_string(),
+                        });
+                    };
+                    (replay, None)
+                }
+            };
+
+            let backoff = policy.backoff(attempt, rand::thread_rng().gen());
+            let delay = retry_after.map_or(backoff, |retry_after| retry_after.max(backoff));
+            tracing::debug!(
+                %host,
+                attempt,
+                delay_ms = delay.as_millis() as u64,
+                "retrying HTTP request"
+            );
+            tokio::time::sleep(delay).await;
+            request = replay;
+        }
+    }
+
+    /// Whether a call to `host` may go ahead; an open breaker past its open
+    /// time lets this call through as the probe.
+    fn acquire(&self, host: &str) -> bool {
+        let policy = &self.config.circuit_breaker;
+        if policy.failure_threshold == 0 {
+            return true;
+        }
+        let mut breakers = lock(&self.breakers);
+        let breaker = breakers.entry(host.to_string()).or_default();
+        let now = Instant::now();
+        match breaker.state {
+            BreakerState::Closed => true,
+            BreakerState::Open { until } | BreakerState::HalfOpen { until } if now >= until => {
+                breaker.state = BreakerState::HalfOpen {
+                    until: now + Duration::from_millis(policy.open_ms),
+                };
+                tracing::info!(%host, "circuit breaker half-open; sending a probe");
+                true
+            }
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => false,
+        }
+    }
+
+    fn record(&self, host: &str, succeeded: bool) {
+        let policy = &self.config.circuit_breaker;
+        if policy.failure_threshold == 0 {
+            return;
+        }
+        let mut breakers = lock(&self.breakers);
+        let breaker = breakers.entry(host.to_string()).or_default();
+        if succeeded {
+            if !matches!(breaker.state, BreakerState::Closed) {
+                tracing::info!(%host, "circuit breaker closed");
+            }
+            *breaker = Breaker::default();
+            return;
+        }
+        breaker.consecutive_failures = breaker.consecutive_failures.saturating_add(1);
+        let probe_failed = matches!(breaker.state, BreakerState::HalfOpen { .. });
+        if probe_failed || breaker.consecutive_failures >= policy.failure_threshold {
+            if !matches!(breaker.state, BreakerState::Open { .. }) {
+                tracing::warn!(
+                    %host,
+                    consecutive_failures = breaker.consecutive_failures,
+                    "circuit breaker opened"
+                );
+            }
+            breaker.state = BreakerState::Open {
+                until: Instant::now() + Duration::from_millis(policy.open_ms),
+            };
+            breaker.opened_at = Some(Utc::now());
+        }
+    }
+
+    fn trace_request(&self, request: &Request, attempt: u32) {
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            // The query is left out because it may carry API keys.
+            tracing::debug!(
+                method = %request.method(),
+                url = %without_query(request.url()),
+                attempt,
+                headers = ?self.redacted(request.headers()),
+                "sending HTTP request"
+            );
+        }
+    }
+
+    fn trace_response(&self, response: &Response, attempt: u32, elapsed: Duration) {
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            tracing::debug!(
+                url = %without_query(response.url()),
+                status = %response.status(),
+                attempt,
+                elapsed_ms = elapsed.as_millis() as u64,
+                headers = ?self.redacted(response.headers()),
+                "received HTTP response"
+            );
+        }
+    }
+
+    fn redacted(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let name = name.as_str();
+                let hidden = ALWAYS_REDACTED_HEADERS.contains(&name)
+                    || self
+                        .config
+                        .redacted_headers
+                        .iter()
+                        .any(|redacted| redacted.eq_ignore_ascii_case(name));
+                let value = if hidden {
+                    "<redacted>".to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).into_owned()
+                };
+                (name.to_string(), value)
+            })
+            .collect()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn host_key(url: &Url) -> String {
+    format!(
+        "{}:{}",
+        url.host_str().unwrap_or_default(),
+        url.port_or_known_default().unwrap_or_default()
+    )
+}
+
+fn without_query(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_query(None);
+    url.to_string()
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    [
+        Method::GET,
+        Method::HEAD,
+        Method::PUT,
+        Method::DELETE,
+        Method::OPTIONS,
+        Method::TRACE,
+    ]
+    .contains(method)
+}
+
+/// Statuses worth another attempt: timeouts, rate limits and transient
+/// server errors.
+fn is_retryable(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Responses that count against the host's breaker. Rate limits and other
+/// client errors mean the host is up.
+fn is_host_failure(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT
+}
+
+/// `Retry-After` as delay seconds or an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (at.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::State,
+        http::{header, HeaderValue},
+        response::IntoResponse,
+        routing::any,
+        Router,
+    };
+    use std::collections::VecDeque;
+
+    /// Answers with the scripted statuses in order, then with `fallback`,
+    /// and records when each request arrived.
+    #[derive(Default)]
+    struct Script {
+        responses: VecDeque<(StatusCode, Option<&'static str>)>,
+        fallback: Option<StatusCode>,
+        arrivals: Vec<Instant>,
+    }
+
+    type SharedScript = Arc<Mutex<Script>>;
+
+    async fn answer(State(script): State<SharedScript>) -> impl IntoResponse {
+        let mut script = script.lock().unwrap();
+        script.arrivals.push(Instant::now());
+        let (status, retry_after) = script
+            .responses
+            .pop_front()
+            .unwrap_or((script.fallback.unwrap_or(StatusCode::OK), None));
+        let mut headers = HeaderMap::new();
+        if let Some(retry_after) = retry_after {
+            headers.insert(header::RETRY_AFTER, HeaderValue::from_static(retry_after));
+        }
+        (status, headers)
+    }
+
+    async fn serve(script: Script) -> (String, SharedScript) {
+        let script = Arc::new(Mutex::new(script));
+        let app = Router::new()
+            .route("/resource", any(answer))
+            .with_state(script.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/resource", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, script)
+    }
+
+    fn client(retry: RetryPolicy, circuit_breaker: CircuitBreakerPolicy) -> HttpClient {
+        HttpClient::new(HttpClientConfig {
+            timeout_ms: 2_000,
+            retry,
+            circuit_breaker,
+            redacted_headers: Vec::new(),
+        })
+        .unwrap()
+    }
+
+    fn hits(script: &SharedScript) -> usize {
+        script.lock().unwrap().arrivals.len()
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap_with_bounded_jitter() {
+        let policy = RetryPolicy {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 500,
+            jitter: 0.5,
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(policy.backoff(1, 0.0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2, 0.0), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4, 0.0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1, 0.999), Duration::from_millis(149));
+        // Jitter never lets a retry wait less than the one before it.
+        assert!(policy.backoff(1, 0.999) < policy.backoff(2, 0.0));
+    }
+
+    #[tokio::test]
+    async fn idempotent_calls_are_retried_with_growing_backoff_until_they_succeed() {
+        let (url, script) = serve(Script {
+            responses: VecDeque::from([
+                (StatusCode::SERVICE_UNAVAILABLE, None),
+                (StatusCode::BAD_GATEWAY, None),
+                (StatusCode::SERVICE_UNAVAILABLE, None),
+            ]),
+            ..Script::default()
+        })
+        .await;
+        let retry = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff_ms: 40,
+            max_backoff_ms: 1_000,
+            jitter: 0.5,
+            retry_non_idempotent: false,
+        };
+        let client = client(retry.clone(), CircuitBreakerPolicy::default());
+
+        let response = client.send(client.get(&url)).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let arrivals = script.lock().unwrap().arrivals.clone();
+        assert_eq!(arrivals.len(), 4);
+        let gaps = arrivals
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .collect::<Vec<_>>();
+        for (retry_number, gap) in (1..).zip(&gaps) {
+            assert!(*gap >= retry.backoff(retry_number, 0.0), "{gaps:?}");
+        }
+        assert!(gaps.windows(2).all(|pair| pair[0] < pair[1]), "{gaps:?}");
+        assert_eq!(
+            client.circuit_breakers()[0].state,
+            CircuitState::Closed,
+            "a success resets the failure count"
+        );
+        assert_eq!(client.circuit_breakers()[0].consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn retry_after_is_waited_out_or_ends_the_call_when_too_long() {
+        let (url, script) = serve(Script {
+            responses: VecDeque::from([
+                (StatusCode::TOO_MANY_REQUESTS, Some("1")),
+                (StatusCode::TOO_MANY_REQUESTS, Some("3600")),
+            ]),
+            ..Script::default()
+        })
+        .await;
+        let retry = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 5,
+            max_backoff_ms: 2_000,
+            jitter: 0.0,
+            retry_non_idempotent: false,
+        };
+        let client = client(retry, CircuitBreakerPolicy::default());
+
+        let error = client.send(client.get(&url)).await.unwrap_err();
+
+        assert!(
+            matches!(
+                error,
+                HttpClientError::Status {
+                    status: StatusCode::TOO_MANY_REQUESTS,
+                    attempts: 2,
+                    ..
+                }
+            ),
+            "{error}"
+        );
+        let arrivals = script.lock().unwrap().arrivals.clone();
+        assert_eq!(arrivals.len(), 2);
+        assert!(arrivals[1] - arrivals[0] >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn breaker_opens_on_persistent_failure_and_recovers_after_a_probe() {
+        let (url, script) = serve(Script {
+            fallback: Some(StatusCode::INTERNAL_SERVER_ERROR),
+            ..Script::default()
+        })
+        .await;
+        let client = client(
+            RetryPolicy {
+                max_attempts: 2,
+                initial_backoff_ms: 1,
+                jitter: 0.0,
+                ..RetryPolicy::default()
+            },
+            CircuitBreakerPolicy {
+                failure_threshold: 3,
+                open_ms: 150,
+            },
+        );
+
+        let first = client.send(client.get(&url)).await.unwrap_err();
+        assert_eq!(first.attempts(), 2);
+        // The third failure opens the breaker, so the call's retry never leaves.
+        let second = client.send(client.get(&url)).await.unwrap_err();
+        assert!(
+            matches!(second, HttpClientError::CircuitOpen { attempts: 1, .. }),
+            "{second}"
+        );
+        let rejected = client.send(client.get(&url)).await.unwrap_err();
+        assert!(
+            matches!(rejected, HttpClientError::CircuitOpen { attempts: 0, .. }),
+            "{rejected}"
+        );
+        assert_eq!(hits(&script), 3);
+        let status = &client.circuit_breakers()[0];
+        assert_eq!(status.state, CircuitState::Open);
+        assert_eq!(status.consecutive_failures, 3);
+        assert!(status.opened_at.is_some());
+
+        // A failed probe reopens the breaker straight away.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(client.circuit_breakers()[0].state, CircuitState::HalfOpen);
+        let probe = client.send(client.get(&url)).await.unwrap_err();
+        assert!(
+            matches!(probe, HttpClientError::CircuitOpen { attempts: 1, .. }),
+            "{probe}"
+        );
+        assert_eq!(hits(&script), 4);
+        assert_eq!(client.circuit_breakers()[0].state, CircuitState::Open);
+
+        script.lock().unwrap().fallback = Some(StatusCode::OK);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        client.send(client.get(&url)).await.unwrap();
+        let status = &client.circuit_breakers()[0];
+        assert_eq!(status.state, CircuitState::Closed);
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(status.opened_at, None);
+    }
+
+    #[tokio::test]
+    async fn posts_are_retried_only_when_the_call_opts_in() {
+        let (url, script) = serve(Script {
+            fallback: Some(StatusCode::SERVICE_UNAVAILABLE),
+            ..Script::default()
+        })
+        .await;
+        let client = client(
+            RetryPolicy {
+                max_attempts: 3,
+                initial_backoff_ms: 1,
+                ..RetryPolicy::default()
+            },
+            CircuitBreakerPolicy {
+                failure_threshold: 0,
+                ..CircuitBreakerPolicy::default()
+            },
+        );
+
+        let error = client
+            .send(client.post(&url).body("reading"))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(error, HttpClientError::Status { attempts: 1, .. }),
+            "{error}"
+        );
+        assert_eq!(hits(&script), 1);
+
+        let error = client
+            .send_retryable(client.post(&url).body("reading"))
+            .await
+            .unwrap_err();
+        assert_eq!(error.attempts(), 3);
+        assert_eq!(hits(&script), 4);
+        assert!(client.circuit_breakers().is_empty());
+    }
+
+    #[test]
+    fn credentials_are_redacted_from_traced_headers() {
+        let client = HttpClient::new(HttpClientConfig {
+            redacted_headers: vec!["X-Agbot-Signature".to_string()],
+            ..HttpClientConfig::default()
+        })
+        .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer t"));
+        headers.insert("x-agbot-signature", HeaderValue::from_static("sha256=ab"));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("application/json"));
+
+        let redacted = client.redacted(&headers);
+
+        assert!(redacted.contains(&("authorization".to_string(), "<redacted>".to_string())));
+        assert!(redacted.contains(&("x-agbot-signature".to_string(), "<redacted>".to_string())));
+        assert!(redacted.contains(&("accept".to_string(), "application/json".to_string())));
+    }
+}
@@ -0,0 +1,349 @@
+//! Legend images for colour-mapped outputs.
+//!
+//! A continuous legend is a horizontal strip of its palette with labelled
+//! ticks below it; a categorical legend is a table of swatches, one labelled
+//! row per class. Labels are drawn with a small built-in bitmap font, so
+//! they read the same on every machine, and are also stored in the PNG as
+//! an iTXt chunk that [`legend_labels`] reads back.
+
+use crate::palette::{Palette, Rgb8};
+use image::{Rgba, RgbaImage};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Keyword of the PNG text chunk holding the legend's labels, one per line.
+pub const LEGEND_TEXT_KEYWORD: &str = "Legend";
+
+const SCALE: u32 = 2;
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+const TEXT_HEIGHT: u32 = GLYPH_HEIGHT * SCALE;
+const MARGIN: u32 = 8;
+const STRIP_LENGTH: u32 = 256;
+const STRIP_HEIGHT: u32 = 16;
+const TICK_LENGTH: u32 = 4;
+const SWATCH_SIZE: u32 = 16;
+const ROW_GAP: u32 = 4;
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const INK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegendEntry {
+    pub label: String,
+    pub color: Rgb8,
+    /// Value the tick marks; `None` for a class.
+    pub value: Option<f32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Legend {
+    /// Ticks from low to high, or classes in table order.
+    pub entries: Vec<LegendEntry>,
+    pub image: RgbaImage,
+}
+
+impl Legend {
+    pub fn labels(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|entry| entry.label.as_str())
+            .collect()
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.image.width(), self.image.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.add_itxt_chunk(LEGEND_TEXT_KEYWORD.to_string(), self.labels().join("\n"))?;
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(self.image.as_raw())?;
+        writer.finish()?;
+        Ok(bytes)
+    }
+
+    pub fn write_png(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, self.to_png().map_err(io::Error::other)?)
+    }
+}
+
+/// Where the legend of the image at `product_path` is written:
+/// `field.png` gets `field_legend.png` beside it.
+pub fn legend_path_for(product_path: &Path) -> PathBuf {
+    let stem = product_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    product_path.with_file_name(format!("{stem}_legend.png"))
+}
+
+/// Labels stored in a legend PNG written by [`Legend::to_png`].
+pub fn legend_labels(png_bytes: &[u8]) -> Result<Vec<String>, png::DecodingError> {
+    let reader = png::Decoder::new(png_bytes).read_info()?;
+    let mut labels = Vec::new();
+    for chunk in &reader.info().utf8_text {
+        if chunk.keyword == LEGEND_TEXT_KEYWORD {
+            let text = chunk.get_text()?;
+            labels.extend(text.lines().map(str::to_string));
+        }
+    }
+    Ok(labels)
+}
+
+/// A strip of `palette` from `min` to `max` with `tick_count` evenly spaced
+/// labelled ticks, at least one at each end.
+pub fn continuous_legend(palette: &Palette, (min, max): (f32, f32), tick_count: usize) -> Legend {
+    let tick_count = tick_count.max(2);
+    let last = (tick_count - 1) as f32;
+    let step = (max - min) / last;
+    let decimals = label_decimals(min, step);
+    let entries: Vec<_> = (0..tick_count)
+        .map(|index| {
+            let t = index as f32 / last;
+            let value = min + step * index as f32;
+            LegendEntry {
+                label: format_tick(value, decimals),
+                color: palette.color_at(t),
+                value: Some(value),
+            }
+        })
+        .collect();
+
+    let widest = entries
+        .iter()
+        .map(|entry| text_width(&entry.label))
+        .max()
+        .unwrap_or(0);
+    let pad = MARGIN.max(widest / 2 + 2);
+    let width = STRIP_LENGTH + 2 * pad;
+    let height = MARGIN + STRIP_HEIGHT + TICK_LENGTH + 2 + TEXT_HEIGHT + MARGIN;
+    let mut image = RgbaImage::from_pixel(width, height, BACKGROUND);
+
+    for column in 0..STRIP_LENGTH {
+        let [r, g, b] = palette.color_at(column as f32 / (STRIP_LENGTH - 1) as f32);
+        for row in MARGIN..MARGIN + STRIP_HEIGHT {
+            image.put_pixel(pad + column, row, Rgba([r, g, b, 255]));
+        }
+    }
+
+    let tick_top = MARGIN + STRIP_HEIGHT;
+    for (index, entry) in entries.iter().enumerate() {
+        let x = pad + ((index as f32 / last) * (STRIP_LENGTH - 1) as f32).round() as u32;
+        for row in tick_top..tick_top + TICK_LENGTH {
+            image.put_pixel(x, row, INK);
+        }
+        let label_x = x.saturating_sub(text_width(&entry.label) / 2);
+        draw_text(
+            &mut image,
+            label_x,
+            tick_top + TICK_LENGTH + 2,
+            &entry.label,
+        );
+    }
+
+    Legend { entries, image }
+}
+
+/// A swatch table with one row per `(label, colour)` class, in the given
+/// order.
+pub fn categorical_legend<'a>(classes: impl IntoIterator<Item = (&'a str, Rgb8)>) -> Legend {
+    let entries: Vec<_> = classes
+        .into_iter()
+        .map(|(label, color)| LegendEntry {
+            label: label.to_string(),
+            color,
+            value: None,
+        })
+        .collect();
+
+    let widest = entries
+        .iter()
+        .map(|entry| text_width(&entry.label))
+        .max()
+        .unwrap_or(0);
+    let row_height = SWATCH_SIZE.max(TEXT_HEIGHT);
+    let rows = entries.len() as u32;
+    let width = MARGIN + SWATCH_SIZE + MARGIN + widest + MARGIN;
+    let height = 2 * MARGIN + rows * row_height + rows.saturating_sub(1) * ROW_GAP;
+    let mut image = RgbaImage::from_pixel(width, height, BACKGROUND);
+
+    for (index, entry) in entries.iter().enumerate() {
+        let top = MARGIN + index as u32 * (row_height + ROW_GAP);
+        let [r, g, b] = entry.color;
+        for y in 0..SWATCH_SIZE {
+            for x in 0..SWATCH_SIZE {
+                let edge = x == 0 || y == 0 || x == SWATCH_SIZE - 1 || y == SWATCH_SIZE - 1;
+                let pixel = if edge { INK } else { Rgba([r, g, b, 255]) };
+                image.put_pixel(MARGIN + x, top + y, pixel);
+            }
+        }
+        draw_text(
+            &mut image,
+            MARGIN + SWATCH_SIZE + MARGIN,
+            top + (row_height - TEXT_HEIGHT) / 2,
+            &entry.label,
+        );
+    }
+
+    Legend { entries, image }
+}
+
+/// Decimals needed to show `start` and every `step` after it, up to three.
+fn label_decimals(start: f32, step: f32) -> usize {
+    (0..3)
+        .find(|&decimals| {
+            let scale = 10_f32.powi(decimals as i32);
+            [start, step]
+                .iter()
+                .all(|value| ((value * scale).round() - value * scale).abs() < 1e-3)
+        })
+        .unwrap_or(3)
+}
+
+fn format_tick(value: f32, decimals: usize) -> String {
+    let label = format!("{value:.decimals$}");
+    match label.strip_prefix('-') {
+        Some(magnitude) if magnitude.chars().all(|c| c == '0' || c == '.') => magnitude.to_string(),
+        _ => label,
+    }
+}
+
+fn text_width(text: &str) -> u32 {
+    let chars = text.chars().count() as u32;
+    (chars * (GLYPH_WIDTH + 1)).saturating_sub(1) * SCALE
+}
+
+fn draw_text(image: &mut RgbaImage, x: u32, y: u32, text: &str) {
+    for (index, c) in text.chars().enumerate() {
+        let left = x + index as u32 * (GLYPH_WIDTH + 1) * SCALE;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (0x10 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..SCALE {
+                    for dx in 0..SCALE {
+                        let (px, py) = (left + column * SCALE + dx, y + row as u32 * SCALE + dy);
+                        if px < image.width() && py < image.height() {
+                            image.put_pixel(px, py, INK);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 5x7 rows, high bit on the left. Lower case draws as upper case and
+/// anything else without a glyph as `?`.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        ' ' => [0x00; 7],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '<' => [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02],
+        '>' => [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::palette;
+
+    #[test]
+    fn continuous_legend_labels_its_ticks_and_stores_them_in_the_png() {
+        let viridis = palette("viridis").unwrap();
+        let legend = continuous_legend(viridis, (-1.0, 1.0), 5);
+
+        assert_eq!(legend.labels(), ["-1.0", "-0.5", "0.0", "0.5", "1.0"]);
+        assert_eq!(legend.entries[0].color, [68, 1, 84]);
+        assert_eq!(legend.entries[4].color, [253, 231, 37]);
+        // The strip starts and ends on the palette's end stops.
+        let pad = (legend.image.width() - STRIP_LENGTH) / 2;
+        assert_eq!(legend.image.get_pixel(pad, MARGIN).0, [68, 1, 84, 255]);
+        assert_eq!(
+            legend.image.get_pixel(pad + STRIP_LENGTH - 1, MARGIN).0,
+            [253, 231, 37, 255]
+        );
+        // Label glyphs are inked below the strip.
+        let label_row = MARGIN + STRIP_HEIGHT + TICK_LENGTH + 2;
+        assert!((label_row..legend.image.height())
+            .any(|y| (0..legend.image.width()).any(|x| *legend.image.get_pixel(x, y) == INK)));
+
+        let png = legend.to_png().unwrap();
+        assert_eq!(legend_labels(&png).unwrap(), legend.labels());
+        let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(decoded, legend.image);
+
+        let counts = continuous_legend(viridis, (0.0, 12.0), 4);
+        assert_eq!(counts.labels(), ["0", "4", "8", "12"]);
+    }
+
+    #[test]
+    fn categorical_legend_has_a_labelled_swatch_per_class() {
+        let legend =
+            categorical_legend([("Water", [0, 114, 178]), ("High vegetation", [0, 158, 115])]);
+
+        assert_eq!(legend.labels(), ["Water", "High vegetation"]);
+        let second_row = MARGIN + SWATCH_SIZE.max(TEXT_HEIGHT) + ROW_GAP;
+        assert_eq!(
+            legend.image.get_pixel(MARGIN + 1, MARGIN + 1).0,
+            [0, 114, 178, 255]
+        );
+        assert_eq!(
+            legend.image.get_pixel(MARGIN + 1, second_row + 1).0,
+            [0, 158, 115, 255]
+        );
+        assert_eq!(
+            legend_labels(&legend.to_png().unwrap()).unwrap(),
+            ["Water", "High vegetation"]
+        );
+    }
+}
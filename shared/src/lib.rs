@@ -4,30 +4,71 @@ use serde::{Deserialize, Serialize};
 // use chrono::{DateTime, Utc}; // uncomment when needed
 // use nalgebra::{Point3, Vector3}; // uncomment when needed
 
+pub mod artifact_schema;
 pub mod config;
 pub mod control_plane;
+pub mod data_quality;
 pub mod error;
 pub mod fleet_alerts;
+pub mod geospatial;
+pub mod http_client;
+pub mod legend;
 pub mod logging;
+pub mod mission_sync;
 pub mod observability;
+pub mod output_manifest;
+pub mod palette;
 pub mod plugin_extensions;
 pub mod resource_budget;
 pub mod schemas;
 pub mod secrets;
+pub mod supervision;
 pub mod twin_contract_v1;
 pub mod types;
+pub mod webhooks;
 
+pub use artifact_schema::{
+    from_artifact_str, migrate_dir, to_artifact_string, ArtifactEnvelope, ArtifactSchemaError,
+    MigrationRegistry, MigrationReport, VersionedArtifact,
+};
 pub use control_plane::*;
+pub use data_quality::*;
 pub use fleet_alerts::*;
+pub use geospatial::{
+    AltitudeConversionError, AltitudeDatum, AltitudeReference, GeoPoint, GeoPolygon, LocalFrame,
+    LocalPoint, LocalPolygon,
+};
+pub use http_client::{
+    CircuitBreakerPolicy, CircuitBreakerStatus, CircuitState, HttpClient, HttpClientConfig,
+    HttpClientError, RetryPolicy,
+};
 pub use logging::{
     active_logging_context, current_operation_span, init_logging, init_logging_with_context,
     logging_operation_span, with_correlation_id, LoggingContext, LoggingNodeIdSource,
 };
+pub use mission_sync::{
+    chunk_mission, chunk_mission_document, sync_mission, MissionChunk, MissionSyncError,
+    MissionSyncManifest, MissionSyncMessage, MissionSyncPhase, MissionSyncPolicy,
+    MissionSyncProgress, MissionSyncReceiver, MissionSyncSender, MissionSyncTransport,
+    SyncedMission, DEFAULT_SYNC_CHUNK_SIZE,
+};
 pub use observability::*;
+pub use output_manifest::{
+    OutputFileEntry, OutputManifest, OutputManifestMismatch, OUTPUT_MANIFEST_FILE_NAME,
+};
 pub use resource_budget::*;
 pub use secrets::*;
+pub use supervision::{
+    EscalationHandler, SupervisedTaskState, SupervisedTaskStatus, SupervisionPolicy,
+    SupervisionReport, TaskSupervisor,
+};
 pub use twin_contract_v1::*;
 pub use types::*;
+pub use webhooks::{
+    sign_webhook_body, verify_webhook_signature, WebhookConfig, WebhookDeadLetter,
+    WebhookDeliveryPolicy, WebhookDispatcher, WebhookEndpoint, WebhookError, WebhookEvent,
+    WebhookEventKind,
+};
 
 /// Common result type used across the workspace
 pub type AgroResult<T> = Result<T, error::AgroError>;
@@ -64,7 +64,7 @@ pub enum LoggingNodeIdSource {
 
 /// Initialize logging for the application.
 pub fn init_logging() -> Result<()> {
-    dotenvy::dotenv().ok();
+    crate::config::load_dotenv();
     init_logging_with_context(LoggingContext::from_env())
 }
 
@@ -0,0 +1,977 @@
+//! Resumable, differential transfer of a mission to the ground station.
+//!
+//! A mission is a JSON header plus an ordered list of waypoint items. The
+//! sender splits the items into fixed-size chunks and offers a
+//! [`MissionSyncManifest`] listing each chunk's SHA-256. The receiver keeps
+//! chunks content-addressed by that hash, so it answers an offer with the
+//! hashes it already holds and only missing or changed chunks travel, whether
+//! the sender is resuming after a dropped connection or sending an edited
+//! revision of a mission it synced before. Chunks may arrive in any order.
+//! The receiver only hands the mission on once every chunk is present and the
+//! reassembled mission hashes to the manifest hash named in the commit.
+//!
+//! [`MissionSyncSender`] and [`MissionSyncReceiver`] hold the protocol state
+//! and never block; [`sync_mission`] drives a sender over any
+//! [`MissionSyncTransport`], re-offering and resending until the receiver
+//! confirms or the round budget runs out.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::future::Future;
+use std::time::Duration;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Waypoint items per chunk unless the sender asks otherwise.
+pub const DEFAULT_SYNC_CHUNK_SIZE: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MissionSyncError {
+    #[error("mission has no items to sync")]
+    EmptyMission,
+    #[error("chunk size must be at least one item")]
+    ZeroChunkSize,
+    #[error("mission document has no `{0}` array")]
+    MissingItems(String),
+    #[error("failed to encode mission: {0}")]
+    Encode(String),
+    #[error("no offer for mission {0}")]
+    UnknownMission(Uuid),
+    #[error("chunk {index} is outside the {chunks} chunks of the manifest")]
+    ChunkOutOfRange { index: u32, chunks: u32 },
+    #[error("chunk {index} does not match its hash")]
+    ChunkHashMismatch { index: u32 },
+    #[error("manifest hash does not match the mission contents")]
+    ManifestHashMismatch,
+    #[error("mission is missing chunks {missing:?}")]
+    Incomplete { missing: Vec<u32> },
+    #[error("receiver rejected the mission: {0}")]
+    Rejected(String),
+    #[error("transport closed")]
+    TransportClosed,
+    #[error("transport error: {0}")]
+    Transport(String),
+    #[error("mission did not sync within {rounds} rounds")]
+    RoundsExhausted { rounds: u32 },
+}
+
+/// Describes one revision of a mission: its header, how its items were cut
+/// into chunks, and a hash over all of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissionSyncManifest {
+    pub mission_id: Uuid,
+    /// Mission fields other than the items, e.g. the MAVLink target.
+    pub header: Value,
+    pub item_count: u32,
+    pub chunk_size: u32,
+    /// Hex SHA-256 of each chunk, in item order.
+    pub chunk_hashes: Vec<String>,
+    /// Hex SHA-256 over the fields above.
+    pub manifest_hash: String,
+}
+
+/// A run of consecutive items starting at `index * chunk_size`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MissionChunk {
+    pub index: u32,
+    pub hash: String,
+    pub items: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissionSyncPhase {
+    /// Waiting for the receiver to say which chunks it holds.
+    Negotiating,
+    Transferring,
+    /// Every chunk is confirmed and the commit is outstanding.
+    Verifying,
+    Complete,
+    Failed,
+}
+
+/// How far a mission transfer has got, for progress displays.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MissionSyncProgress {
+    pub mission_id: Uuid,
+    pub phase: MissionSyncPhase,
+    pub chunks_total: u32,
+    pub chunks_confirmed: u32,
+    pub items_total: u32,
+    pub items_confirmed: u32,
+    /// Chunks the receiver already held when the transfer began.
+    pub chunks_reused: u32,
+}
+
+impl MissionSyncProgress {
+    /// Share of items the receiver holds, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.items_total == 0 {
+            return 1.0;
+        }
+        self.items_confirmed as f32 / self.items_total as f32
+    }
+}
+
+/// Messages exchanged between a [`MissionSyncSender`] and a
+/// [`MissionSyncReceiver`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MissionSyncMessage {
+    /// Sender to receiver: a mission revision to sync.
+    Offer {
+        manifest: MissionSyncManifest,
+    },
+    /// Receiver to sender: the manifest's chunks the receiver holds.
+    Have {
+        mission_id: Uuid,
+        chunk_hashes: Vec<String>,
+    },
+    Chunk {
+        mission_id: Uuid,
+        chunk: MissionChunk,
+    },
+    ChunkAck {
+        mission_id: Uuid,
+        index: u32,
+        hash: String,
+    },
+    /// Sender to receiver: every chunk should now be present.
+    Commit {
+        mission_id: Uuid,
+        manifest_hash: String,
+    },
+    /// Receiver to sender: the mission verified against the manifest hash.
+    Committed {
+        mission_id: Uuid,
+        manifest_hash: String,
+    },
+    Rejected {
+        mission_id: Uuid,
+        reason: String,
+    },
+}
+
+impl MissionSyncMessage {
+    pub fn mission_id(&self) -> Uuid {
+        match self {
+            Self::Offer { manifest } => manifest.mission_id,
+            Self::Have { mission_id, .. }
+            | Self::Chunk { mission_id, .. }
+            | Self::ChunkAck { mission_id, .. }
+            | Self::Commit { mission_id, .. }
+            | Self::Committed { mission_id, .. }
+            | Self::Rejected { mission_id, .. } => *mission_id,
+        }
+    }
+}
+
+/// Splits `items` into chunks of `chunk_size` and builds their manifest.
+pub fn chunk_mission(
+    mission_id: Uuid,
+    header: Value,
+    items: Vec<Value>,
+    chunk_size: usize,
+) -> Result<(MissionSyncManifest, Vec<MissionChunk>), MissionSyncError> {
+    if items.is_empty() {
+        return Err(MissionSyncError::EmptyMission);
+    }
+    if chunk_size == 0 {
+        return Err(MissionSyncError::ZeroChunkSize);
+    }
+
+    let chunks: Vec<MissionChunk> = items
+        .chunks(chunk_size)
+        .enumerate()
+        .map(|(index, items)| MissionChunk {
+            index: index as u32,
+            hash: chunk_hash(items),
+            items: items.to_vec(),
+        })
+        .collect();
+    let chunk_hashes: Vec<String> = chunks.iter().map(|chunk| chunk.hash.clone()).collect();
+    let item_count = items.len() as u32;
+    let chunk_size = chunk_size as u32;
+    let manifest = MissionSyncManifest {
+        manifest_hash: manifest_hash(mission_id, &header, item_count, chunk_size, &chunk_hashes),
+        mission_id,
+        header,
+        item_count,
+        chunk_size,
+        chunk_hashes,
+    };
+    Ok((manifest, chunks))
+}
+
+/// Chunks a serialized mission, taking its items from the `items_key`
+/// array and keeping every other field as the header.
+pub fn chunk_mission_document<T: Serialize>(
+    mission_id: Uuid,
+    mission: &T,
+    items_key: &str,
+    chunk_size: usize,
+) -> Result<(MissionSyncManifest, Vec<MissionChunk>), MissionSyncError> {
+    let mut header =
+        serde_json::to_value(mission).map_err(|e| MissionSyncError::Encode(e.to_string()))?;
+    let items = match header
+        .as_object_mut()
+        .and_then(|fields| fields.remove(items_key))
+    {
+        Some(Value::Array(items)) => items,
+        _ => return Err(MissionSyncError::MissingItems(items_key.to_string())),
+    };
+    chunk_mission(mission_id, header, items, chunk_size)
+}
+
+/// Hex SHA-256 of a chunk's items in canonical JSON.
+pub fn chunk_hash(items: &[Value]) -> String {
+    let mut hasher = Sha256::new();
+    for item in items {
+        hasher.update(canonical_json(item).as_bytes());
+        hasher.update(b"\n");
+    }
+    hex(&hasher.finalize())
+}
+
+pub fn manifest_hash(
+    mission_id: Uuid,
+    header: &Value,
+    item_count: u32,
+    chunk_size: u32,
+    chunk_hashes: &[String],
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(mission_id.as_bytes());
+    hasher.update(canonical_json(header).as_bytes());
+    hasher.update(item_count.to_be_bytes());
+    hasher.update(chunk_size.to_be_bytes());
+    for hash in chunk_hashes {
+        hasher.update(hash.as_bytes());
+    }
+    hex(&hasher.finalize())
+}
+
+impl MissionSyncManifest {
+    pub fn chunk_count(&self) -> u32 {
+        self.chunk_hashes.len() as u32
+    }
+
+    /// Items in chunk `index`; only the last chunk may be short.
+    pub fn chunk_len(&self, index: u32) -> u32 {
+        let start = index.saturating_mul(self.chunk_size);
+        self.item_count.saturating_sub(start).min(self.chunk_size)
+    }
+
+    /// Whether the hash, item count and chunk layout agree with each other.
+    pub fn is_consistent(&self) -> bool {
+        self.chunk_size > 0
+            && self.item_count > 0
+            && self.chunk_count() == self.item_count.div_ceil(self.chunk_size)
+            && self.manifest_hash
+                == manifest_hash(
+                    self.mission_id,
+                    &self.header,
+                    self.item_count,
+                    self.chunk_size,
+                    &self.chunk_hashes,
+                )
+    }
+
+    /// Indices of chunks whose hash is not in `have`.
+    pub fn missing_chunks(&self, have: &HashSet<&str>) -> Vec<u32> {
+        self.chunk_hashes
+            .iter()
+            .enumerate()
+            .filter(|(_, hash)| !have.contains(hash.as_str()))
+            .map(|(index, _)| index as u32)
+            .collect()
+    }
+}
+
+/// A mission the receiver reassembled and verified.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncedMission {
+    pub manifest: MissionSyncManifest,
+    pub items: Vec<Value>,
+}
+
+impl SyncedMission {
+    /// The header with the items put back under `items_key`, the inverse of
+    /// [`chunk_mission_document`].
+    pub fn into_document(self, items_key: &str) -> Value {
+        let mut document = self.manifest.header;
+        if let Value::Object(fields) = &mut document {
+            fields.insert(items_key.to_string(), Value::Array(self.items));
+        }
+        document
+    }
+}
+
+/// Sender-side state for one mission revision.
+#[derive(Debug, Clone)]
+pub struct MissionSyncSender {
+    manifest: MissionSyncManifest,
+    chunks: Vec<MissionChunk>,
+    confirmed: BTreeSet<u32>,
+    reused: u32,
+    phase: MissionSyncPhase,
+}
+
+impl MissionSyncSender {
+    pub fn new(manifest: MissionSyncManifest, chunks: Vec<MissionChunk>) -> Self {
+        Self {
+            manifest,
+            chunks,
+            confirmed: BTreeSet::new(),
+            reused: 0,
+            phase: MissionSyncPhase::Negotiating,
+        }
+    }
+
+    pub fn manifest(&self) -> &MissionSyncManifest {
+        &self.manifest
+    }
+
+    pub fn offer(&self) -> MissionSyncMessage {
+        MissionSyncMessage::Offer {
+            manifest: self.manifest.clone(),
+        }
+    }
+
+    pub fn commit(&self) -> MissionSyncMessage {
+        MissionSyncMessage::Commit {
+            mission_id: self.manifest.mission_id,
+            manifest_hash: self.manifest.manifest_hash.clone(),
+        }
+    }
+
+    /// Replaces what the sender believes the receiver holds with `have`.
+    pub fn on_have(&mut self, have: &[String]) {
+        let have: HashSet<&str> = have.iter().map(String::as_str).collect();
+        let missing: HashSet<u32> = self.manifest.missing_chunks(&have).into_iter().collect();
+        let first_answer = self.phase == MissionSyncPhase::Negotiating && self.confirmed.is_empty();
+        self.confirmed = (0..self.manifest.chunk_count())
+            .filter(|index| !missing.contains(index))
+            .collect();
+        if first_answer {
+            self.reused = self.confirmed.len() as u32;
+        }
+        self.phase = if self.is_transferred() {
+            MissionSyncPhase::Verifying
+        } else {
+            MissionSyncPhase::Transferring
+        };
+    }
+
+    /// Records an acknowledgement; ignores acks for another revision.
+    pub fn on_ack(&mut self, index: u32, hash: &str) {
+        if self
+            .manifest
+            .chunk_hashes
+            .get(index as usize)
+            .map(String::as_str)
+            == Some(hash)
+        {
+            self.confirmed.insert(index);
+            if self.is_transferred() {
+                self.phase = MissionSyncPhase::Verifying;
+            }
+        }
+    }
+
+    /// Chunks the receiver has not confirmed, in order.
+    pub fn pending(&self) -> impl Iterator<Item = &MissionChunk> {
+        self.chunks
+            .iter()
+            .filter(|chunk| !self.confirmed.contains(&chunk.index))
+    }
+
+    pub fn is_transferred(&self) -> bool {
+        self.confirmed.len() as u32 == self.manifest.chunk_count()
+    }
+
+    /// Drops back to negotiating, e.g. after reconnecting, keeping what the
+    /// receiver confirmed so far.
+    pub fn renegotiate(&mut self) {
+        if self.phase != MissionSyncPhase::Complete {
+            self.phase = MissionSyncPhase::Negotiating;
+        }
+    }
+
+    pub fn finish(&mut self, outcome: MissionSyncPhase) {
+        self.phase = outcome;
+    }
+
+    pub fn progress(&self) -> MissionSyncProgress {
+        let items_confirmed = self
+            .confirmed
+            .iter()
+            .map(|index| self.manifest.chunk_len(*index))
+            .sum();
+        MissionSyncProgress {
+            mission_id: self.manifest.mission_id,
+            phase: self.phase,
+            chunks_total: self.manifest.chunk_count(),
+            chunks_confirmed: self.confirmed.len() as u32,
+            items_total: self.manifest.item_count,
+            items_confirmed,
+            chunks_reused: self.reused,
+        }
+    }
+}
+
+/// What the receiver did with one message.
+#[derive(Debug, Clone, Default)]
+pub struct ReceiverStep {
+    /// Message to send back to the sender.
+    pub reply: Option<MissionSyncMessage>,
+    /// Set once a mission verified and should be activated.
+    pub committed: Option<SyncedMission>,
+    pub progress: Option<MissionSyncProgress>,
+}
+
+/// Receiver-side state, shared by every connection so a sender that
+/// reconnects picks up where it left off.
+#[derive(Debug, Default)]
+pub struct MissionSyncReceiver {
+    /// Chunk items keyed by their hash.
+    chunks: HashMap<String, Vec<Value>>,
+    offers: HashMap<Uuid, MissionSyncManifest>,
+    /// Last revision of each mission that verified.
+    committed: HashMap<Uuid, MissionSyncManifest>,
+}
+
+impl MissionSyncReceiver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `manifest` and returns the hashes of its chunks already
+    /// held, from earlier attempts or earlier revisions.
+    pub fn offer(
+        &mut self,
+        manifest: MissionSyncManifest,
+    ) -> Result<Vec<String>, MissionSyncError> {
+        if !manifest.is_consistent() {
+            return Err(MissionSyncError::ManifestHashMismatch);
+        }
+        let have = self.held_chunks(&manifest);
+        self.offers.insert(manifest.mission_id, manifest);
+        Ok(have)
+    }
+
+    /// Stores a chunk of an offered mission after checking its hash.
+    pub fn accept_chunk(
+        &mut self,
+        mission_id: Uuid,
+        chunk: MissionChunk,
+    ) -> Result<MissionSyncProgress, MissionSyncError> {
+        let manifest = self
+            .offers
+            .get(&mission_id)
+            .ok_or(MissionSyncError::UnknownMission(mission_id))?;
+        let expected = manifest.chunk_hashes.get(chunk.index as usize).ok_or(
+            MissionSyncError::ChunkOutOfRange {
+                index: chunk.index,
+                chunks: manifest.chunk_count(),
+            },
+        )?;
+        if chunk.hash != *expected
+            || chunk_hash(&chunk.items) != chunk.hash
+            || chunk.items.len() as u32 != manifest.chunk_len(chunk.index)
+        {
+            return Err(MissionSyncError::ChunkHashMismatch { index: chunk.index });
+        }
+        self.chunks.insert(chunk.hash, chunk.items);
+        self.progress(mission_id)
+            .ok_or(MissionSyncError::UnknownMission(mission_id))
+    }
+
+    /// Indices of the offered mission's chunks not yet held.
+    pub fn missing(&self, mission_id: Uuid) -> Result<Vec<u32>, MissionSyncError> {
+        let manifest = self
+            .offers
+            .get(&mission_id)
+            .ok_or(MissionSyncError::UnknownMission(mission_id))?;
+        Ok(self.missing_from(manifest))
+    }
+
+    /// Reassembles the offered mission and checks it against
+    /// `manifest_hash`. The offer is closed on success.
+    pub fn commit(
+        &mut self,
+        mission_id: Uuid,
+        manifest_hash: &str,
+    ) -> Result<SyncedMission, MissionSyncError> {
+        let manifest = self
+            .offers
+            .get(&mission_id)
+            .ok_or(MissionSyncError::UnknownMission(mission_id))?;
+        if manifest.manifest_hash != manifest_hash {
+            return Err(MissionSyncError::ManifestHashMismatch);
+        }
+        let missing = self.missing_from(manifest);
+        if !missing.is_empty() {
+            return Err(MissionSyncError::Incomplete { missing });
+        }
+
+        let mut items = Vec::with_capacity(manifest.item_count as usize);
+        let mut chunk_hashes = Vec::with_capacity(manifest.chunk_hashes.len());
+        for hash in &manifest.chunk_hashes {
+            let chunk = &self.chunks[hash];
+            chunk_hashes.push(chunk_hash(chunk));
+            items.extend(chunk.iter().cloned());
+        }
+        let recomputed = self::manifest_hash(
+            manifest.mission_id,
+            &manifest.header,
+            items.len() as u32,
+            manifest.chunk_size,
+            &chunk_hashes,
+        );
+        if recomputed != manifest_hash {
+            return Err(MissionSyncError::ManifestHashMismatch);
+        }
+
+        let manifest = self
+            .offers
+            .remove(&mission_id)
+            .expect("offer was looked up above");
+        self.committed.insert(mission_id, manifest.clone());
+        self.prune();
+        Ok(SyncedMission { manifest, items })
+    }
+
+    pub fn progress(&self, mission_id: Uuid) -> Option<MissionSyncProgress> {
+        let manifest = self.offers.get(&mission_id)?;
+        let (chunks_confirmed, items_confirmed) = manifest
+            .chunk_hashes
+            .iter()
+            .enumerate()
+            .filter(|(_, hash)| self.chunks.contains_key(*hash))
+            .fold((0, 0), |(chunks, items), (index, _)| {
+                (chunks + 1, items + manifest.chunk_len(index as u32))
+            });
+        let phase = if chunks_confirmed == manifest.chunk_count() {
+            MissionSyncPhase::Verifying
+        } else {
+            MissionSyncPhase::Transferring
+        };
+        Some(MissionSyncProgress {
+            mission_id,
+            phase,
+            chunks_total: manifest.chunk_count(),
+            chunks_confirmed,
+            items_total: manifest.item_count,
+            items_confirmed,
+            chunks_reused: 0,
+        })
+    }
+
+    /// Applies one message from a sender.
+    pub fn handle(&mut self, message: MissionSyncMessage) -> ReceiverStep {
+        let mission_id = message.mission_id();
+        let mut step = ReceiverStep::default();
+        match message {
+            MissionSyncMessage::Offer { manifest } => match self.offer(manifest) {
+                Ok(chunk_hashes) => {
+                    step.progress = self.progress(mission_id);
+                    step.reply = Some(MissionSyncMessage::Have {
+                        mission_id,
+                        chunk_hashes,
+                    });
+                }
+                Err(e) => step.reply = Some(rejected(mission_id, &e)),
+            },
+            MissionSyncMessage::Chunk { chunk, .. } => {
+                let (index, hash) = (chunk.index, chunk.hash.clone());
+                match self.accept_chunk(mission_id, chunk) {
+                    Ok(progress) => {
+                        step.progress = Some(progress);
+                        step.reply = Some(MissionSyncMessage::ChunkAck {
+                            mission_id,
+                            index,
+                            hash,
+                        });
+                    }
+                    // The sender resends whatever stays unacknowledged.
+                    Err(e) => debug!("Dropping chunk {} of mission {}: {}", index, mission_id, e),
+                }
+            }
+            MissionSyncMessage::Commit { manifest_hash, .. } => {
+                let already_committed = !self.offers.contains_key(&mission_id)
+                    && self
+                        .committed
+                        .get(&mission_id)
+                        .is_some_and(|manifest| manifest.manifest_hash == manifest_hash);
+                if already_committed {
+                    // Our earlier confirmation was lost; confirm again
+                    // without activating the mission twice.
+                    step.reply = Some(MissionSyncMessage::Committed {
+                        mission_id,
+                        manifest_hash,
+                    });
+                    return step;
+                }
+                match self.commit(mission_id, &manifest_hash) {
+                    Ok(mission) => {
+                        let mut progress = self.committed_progress(&mission.manifest);
+                        progress.phase = MissionSyncPhase::Complete;
+                        step.progress = Some(progress);
+                        step.committed = Some(mission);
+                        step.reply = Some(MissionSyncMessage::Committed {
+                            mission_id,
+                            manifest_hash,
+                        });
+                    }
+                    Err(MissionSyncError::Incomplete { .. }) => {
+                        let manifest = &self.offers[&mission_id];
+                        step.reply = Some(MissionSyncMessage::Have {
+                            mission_id,
+                            chunk_hashes: self.held_chunks(manifest),
+                        });
+                    }
+                    Err(e) => step.reply = Some(rejected(mission_id, &e)),
+                }
+            }
+            MissionSyncMessage::Have { .. }
+            | MissionSyncMessage::ChunkAck { .. }
+            | MissionSyncMessage::Committed { .. }
+            | MissionSyncMessage::Rejected { .. } => {}
+        }
+        step
+    }
+
+    fn held_chunks(&self, manifest: &MissionSyncManifest) -> Vec<String> {
+        manifest
+            .chunk_hashes
+            .iter()
+            .filter(|hash| self.chunks.contains_key(*hash))
+            .cloned()
+            .collect()
+    }
+
+    fn missing_from(&self, manifest: &MissionSyncManifest) -> Vec<u32> {
+        let have: HashSet<&str> = self.chunks.keys().map(String::as_str).collect();
+        manifest.missing_chunks(&have)
+    }
+
+    fn committed_progress(&self, manifest: &MissionSyncManifest) -> MissionSyncProgress {
+        MissionSyncProgress {
+            mission_id: manifest.mission_id,
+            phase: MissionSyncPhase::Complete,
+            chunks_total: manifest.chunk_count(),
+            chunks_confirmed: manifest.chunk_count(),
+            items_total: manifest.item_count,
+            items_confirmed: manifest.item_count,
+            chunks_reused: 0,
+        }
+    }
+
+    /// Keeps only chunks an open offer or a committed revision refers to.
+    fn prune(&mut self) {
+        let referenced: HashSet<&String> = self
+            .offers
+            .values()
+            .chain(self.committed.values())
+            .flat_map(|manifest| manifest.chunk_hashes.iter())
+            .collect();
+        self.chunks.retain(|hash, _| referenced.contains(hash));
+    }
+}
+
+fn rejected(mission_id: Uuid, error: &MissionSyncError) -> MissionSyncMessage {
+    MissionSyncMessage::Rejected {
+        mission_id,
+        reason: error.to_string(),
+    }
+}
+
+/// Carries sync messages between the two sides. `recv` returns `None`
+/// when nothing arrived within `timeout`.
+pub trait MissionSyncTransport {
+    fn send(
+        &mut self,
+        message: MissionSyncMessage,
+    ) -> impl Future<Output = Result<(), MissionSyncError>> + Send;
+
+    fn recv(
+        &mut self,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Option<MissionSyncMessage>, MissionSyncError>> + Send;
+}
+
+/// How patiently [`sync_mission`] waits on the receiver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissionSyncPolicy {
+    /// Silence after which an offer, chunk batch or commit is retried.
+    pub reply_timeout: Duration,
+    /// Offer, transfer and commit attempts before giving up.
+    pub max_rounds: u32,
+}
+
+impl Default for MissionSyncPolicy {
+    fn default() -> Self {
+        Self {
+            reply_timeout: Duration::from_secs(2),
+            max_rounds: 40,
+        }
+    }
+}
+
+/// Syncs `sender`'s mission over `transport`, calling `on_progress` each
+/// time the receiver confirms something. A sender whose transport failed
+/// can be passed again over a new transport to resume.
+pub async fn sync_mission<T, F>(
+    transport: &mut T,
+    sender: &mut MissionSyncSender,
+    policy: MissionSyncPolicy,
+    mut on_progress: F,
+) -> Result<(), MissionSyncError>
+where
+    T: MissionSyncTransport,
+    F: FnMut(&MissionSyncProgress),
+{
+    let mission_id = sender.manifest().mission_id;
+    sender.renegotiate();
+    on_progress(&sender.progress());
+
+    for _ in 0..policy.max_rounds {
+        let outgoing: Vec<MissionSyncMessage> = match sender.progress().phase {
+            MissionSyncPhase::Negotiating => vec![sender.offer()],
+            MissionSyncPhase::Transferring => sender
+                .pending()
+                .map(|chunk| MissionSyncMessage::Chunk {
+                    mission_id,
+                    chunk: chunk.clone(),
+                })
+                .collect(),
+            MissionSyncPhase::Verifying => vec![sender.commit()],
+            MissionSyncPhase::Complete => return Ok(()),
+            MissionSyncPhase::Failed => break,
+        };
+        for message in outgoing {
+            transport.send(message).await?;
+        }
+
+        // Read replies until the receiver goes quiet or moves us on.
+        let round_phase = sender.progress().phase;
+        while let Some(reply) = transport.recv(policy.reply_timeout).await? {
+            if reply.mission_id() != mission_id {
+                continue;
+            }
+            match reply {
+                MissionSyncMessage::Have { chunk_hashes, .. } => sender.on_have(&chunk_hashes),
+                MissionSyncMessage::ChunkAck { index, hash, .. } => sender.on_ack(index, &hash),
+                MissionSyncMessage::Committed { manifest_hash, .. }
+                    if manifest_hash == sender.manifest().manifest_hash =>
+                {
+                    sender.finish(MissionSyncPhase::Complete);
+                }
+                MissionSyncMessage::Rejected { reason, .. } => {
+                    sender.finish(MissionSyncPhase::Failed);
+                    on_progress(&sender.progress());
+                    return Err(MissionSyncError::Rejected(reason));
+                }
+                _ => continue,
+            }
+            let progress = sender.progress();
+            on_progress(&progress);
+            if progress.phase == MissionSyncPhase::Complete {
+                return Ok(());
+            }
+            if progress.phase != round_phase {
+                break;
+            }
+        }
+    }
+
+    sender.finish(MissionSyncPhase::Failed);
+    on_progress(&sender.progress());
+    Err(MissionSyncError::RoundsExhausted {
+        rounds: policy.max_rounds,
+    })
+}
+
+/// JSON with object keys sorted at every level, so equal values always
+/// hash the same.
+fn canonical_json(value: &Value) -> String {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(fields) => {
+                let fields: BTreeMap<&String, Value> = fields
+                    .iter()
+                    .map(|(key, value)| (key, sorted(value)))
+                    .collect();
+                Value::Object(
+                    fields
+                        .into_iter()
+                        .map(|(key, value)| (key.clone(), value))
+                        .collect(),
+                )
+            }
+            Value::Array(items) => Value::Array(items.iter().map(sorted).collect()),
+            other => other.clone(),
+        }
+    }
+    sorted(value).to_string()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn waypoints(count: usize, altitude: f64) -> Vec<Value> {
+        (0..count)
+            .map(|seq| json!({ "seq": seq, "command": 16, "x": 41.0 + seq as f64 * 1e-4, "y": -96.0, "z": altitude }))
+            .collect()
+    }
+
+    fn manifest_and_chunks(items: Vec<Value>) -> (MissionSyncManifest, Vec<MissionChunk>) {
+        let mission_id = Uuid::from_u128(7);
+        chunk_mission(mission_id, json!({ "target_system": 1 }), items, 4).unwrap()
+    }
+
+    /// Hands every chunk the sender still has to the receiver, in the given
+    /// order, and commits.
+    fn deliver(
+        receiver: &mut MissionSyncReceiver,
+        sender: &mut MissionSyncSender,
+        order: impl Fn(Vec<MissionChunk>) -> Vec<MissionChunk>,
+    ) -> Vec<u32> {
+        let mission_id = sender.manifest().mission_id;
+        let MissionSyncMessage::Have { chunk_hashes, .. } =
+            receiver.handle(sender.offer()).reply.unwrap()
+        else {
+            panic!("an offer should be answered with the held chunks");
+        };
+        sender.on_have(&chunk_hashes);
+        let pending = order(sender.pending().cloned().collect());
+        let sent = pending.iter().map(|chunk| chunk.index).collect();
+        for chunk in pending {
+            if let Some(MissionSyncMessage::ChunkAck { index, hash, .. }) = receiver
+                .handle(MissionSyncMessage::Chunk { mission_id, chunk })
+                .reply
+            {
+                sender.on_ack(index, &hash);
+            }
+        }
+        sent
+    }
+
+    #[test]
+    fn modified_middle_chunk_is_the_only_chunk_resent() {
+        let mut receiver = MissionSyncReceiver::new();
+        let original = waypoints(20, 40.0);
+        let (manifest, chunks) = manifest_and_chunks(original.clone());
+        let mut sender = MissionSyncSender::new(manifest, chunks);
+        assert_eq!(deliver(&mut receiver, &mut sender, |c| c).len(), 5);
+        let step = receiver.handle(sender.commit());
+        assert_eq!(step.committed.unwrap().items, original);
+
+        let mut edited = original.clone();
+        edited[9]["z"] = json!(55.0);
+        let (manifest, chunks) = manifest_and_chunks(edited.clone());
+        let mut sender = MissionSyncSender::new(manifest, chunks);
+        assert_eq!(deliver(&mut receiver, &mut sender, |c| c), vec![2]);
+        assert_eq!(sender.progress().chunks_reused, 4);
+        let step = receiver.handle(sender.commit());
+        assert!(matches!(
+            step.reply,
+            Some(MissionSyncMessage::Committed { .. })
+        ));
+        assert_eq!(step.committed.unwrap().items, edited);
+    }
+
+    #[test]
+    fn truncated_transfer_resumes_with_the_missing_chunks() {
+        let mut receiver = MissionSyncReceiver::new();
+        let items = waypoints(18, 40.0);
+        let (manifest, chunks) = manifest_and_chunks(items.clone());
+        let mut sender = MissionSyncSender::new(manifest, chunks);
+        // The link drops after two chunks; nothing acknowledged survives on
+        // the sender, as after a restart.
+        deliver(&mut receiver, &mut sender, |chunks| {
+            chunks.into_iter().take(2).collect()
+        });
+        assert_eq!(
+            receiver.missing(sender.manifest().mission_id).unwrap(),
+            vec![2, 3, 4]
+        );
+        let step = receiver.handle(sender.commit());
+        assert!(step.committed.is_none());
+        assert!(
+            matches!(step.reply, Some(MissionSyncMessage::Have { ref chunk_hashes, .. }) if chunk_hashes.len() == 2)
+        );
+
+        let (manifest, chunks) = manifest_and_chunks(items.clone());
+        let mut resumed = MissionSyncSender::new(manifest, chunks);
+        assert_eq!(deliver(&mut receiver, &mut resumed, |c| c), vec![2, 3, 4]);
+        assert!(resumed.is_transferred());
+        let mission = receiver.handle(resumed.commit()).committed.unwrap();
+        assert_eq!(mission.items, items);
+        assert_eq!(mission.manifest.chunk_len(4), 2);
+    }
+
+    #[test]
+    fn chunks_arriving_out_of_order_reassemble_in_item_order() {
+        let mut receiver = MissionSyncReceiver::new();
+        let items = waypoints(13, 40.0);
+        let (manifest, chunks) = manifest_and_chunks(items.clone());
+        let mut sender = MissionSyncSender::new(manifest, chunks);
+        deliver(&mut receiver, &mut sender, |mut chunks| {
+            chunks.reverse();
+            chunks.swap(0, 2);
+            chunks
+        });
+        let mission = receiver.handle(sender.commit()).committed.unwrap();
+        assert_eq!(mission.items, items);
+    }
+
+    #[test]
+    fn tampered_chunks_and_manifests_are_refused() {
+        let mut receiver = MissionSyncReceiver::new();
+        let (mut manifest, mut chunks) = manifest_and_chunks(waypoints(8, 40.0));
+        let mission_id = manifest.mission_id;
+        receiver.offer(manifest.clone()).unwrap();
+
+        chunks[1].items[0]["z"] = json!(999.0);
+        assert_eq!(
+            receiver.accept_chunk(mission_id, chunks[1].clone()),
+            Err(MissionSyncError::ChunkHashMismatch { index: 1 })
+        );
+
+        manifest.item_count = 9;
+        assert!(matches!(
+            receiver
+                .handle(MissionSyncMessage::Offer { manifest })
+                .reply,
+            Some(MissionSyncMessage::Rejected { .. })
+        ));
+    }
+
+    #[test]
+    fn documents_round_trip_through_their_items_key() {
+        let document = json!({
+            "target_system": 1,
+            "target_component": 1,
+            "count": 6,
+            "items": waypoints(6, 30.0),
+        });
+        let (manifest, chunks) =
+            chunk_mission_document(Uuid::from_u128(3), &document, "items", 4).unwrap();
+        assert_eq!(manifest.header.get("items"), None);
+        let mut receiver = MissionSyncReceiver::new();
+        let mut sender = MissionSyncSender::new(manifest, chunks);
+        deliver(&mut receiver, &mut sender, |c| c);
+        let mission = receiver.handle(sender.commit()).committed.unwrap();
+        assert_eq!(mission.into_document("items"), document);
+    }
+}
@@ -0,0 +1,225 @@
+//! `manifest.json` describing the files a batch run wrote.
+//!
+//! Processors write the manifest at the top of their output directory once a
+//! run has succeeded. Each entry names a file relative to that directory with
+//! its type, the inputs it was derived from, its size and its SHA-256, so a
+//! downstream system can check it received every file intact with
+//! [`OutputManifest::verify`]. A directory without a manifest holds the
+//! outputs of a run that did not finish.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File name of the manifest inside an output directory.
+pub const OUTPUT_MANIFEST_FILE_NAME: &str = "manifest.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputManifest {
+    /// Tool that wrote the outputs, e.g. `lidar_mapper`.
+    pub producer: String,
+    pub generated_at: DateTime<Utc>,
+    /// Sorted by path.
+    pub files: Vec<OutputFileEntry>,
+}
+
+/// One file listed in an [`OutputManifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputFileEntry {
+    /// Relative to the output directory, `/`-separated.
+    pub path: String,
+    /// Lower-case file extension, e.g. `png`, `json` or `pcd`.
+    pub file_type: String,
+    /// Input files the output was derived from.
+    pub source_inputs: Vec<String>,
+    pub size_bytes: u64,
+    /// Hex SHA-256 of the file contents.
+    pub sha256: String,
+}
+
+/// A listed file that is not on disk as the manifest describes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputManifestMismatch {
+    Missing {
+        path: String,
+    },
+    Size {
+        path: String,
+        expected: u64,
+        actual: u64,
+    },
+    Checksum {
+        path: String,
+    },
+}
+
+impl OutputFileEntry {
+    pub fn from_contents(
+        path: impl Into<String>,
+        contents: &[u8],
+        source_inputs: Vec<String>,
+    ) -> Self {
+        let path = path.into();
+        Self {
+            file_type: file_type(&path),
+            path,
+            source_inputs,
+            size_bytes: contents.len() as u64,
+            sha256: sha256_hex(contents),
+        }
+    }
+}
+
+impl OutputManifest {
+    pub fn new(producer: impl Into<String>, mut files: Vec<OutputFileEntry>) -> Self {
+        files.sort_by(|left, right| left.path.cmp(&right.path));
+        Self {
+            producer: producer.into(),
+            generated_at: Utc::now(),
+            files,
+        }
+    }
+
+    /// Lists `written`, the files one run wrote under `output_dir` paired
+    /// with the inputs each was derived from. Anything else in the
+    /// directory, such as outputs of earlier runs, is left out.
+    pub fn from_written(
+        producer: impl Into<String>,
+        output_dir: &Path,
+        written: impl IntoIterator<Item = (PathBuf, Vec<String>)>,
+    ) -> io::Result<Self> {
+        let mut files = Vec::new();
+        for (path, sources) in written {
+            let contents = fs::read(&path)?;
+            let relative = relative_path(output_dir, &path);
+            files.push(OutputFileEntry::from_contents(relative, &contents, sources));
+        }
+        Ok(Self::new(producer, files))
+    }
+
+    /// Deletes the manifest of an earlier run, so a run that fails part way
+    /// leaves the directory marked unfinished. Call it before writing
+    /// outputs.
+    pub fn remove(output_dir: &Path) -> io::Result<()> {
+        match fs::remove_file(output_dir.join(OUTPUT_MANIFEST_FILE_NAME)) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn to_json_bytes(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec_pretty(self)
+    }
+
+    /// Writes the manifest to `output_dir/manifest.json`.
+    pub fn write(&self, output_dir: &Path) -> io::Result<PathBuf> {
+        let path = output_dir.join(OUTPUT_MANIFEST_FILE_NAME);
+        fs::write(&path, self.to_json_bytes()?)?;
+        Ok(path)
+    }
+
+    pub fn read(output_dir: &Path) -> io::Result<Self> {
+        let contents = fs::read(output_dir.join(OUTPUT_MANIFEST_FILE_NAME))?;
+        Ok(serde_json::from_slice(&contents)?)
+    }
+
+    /// Listed files that are missing from `output_dir` or differ from their
+    /// entry; empty when the directory holds the complete output.
+    pub fn verify(&self, output_dir: &Path) -> Vec<OutputManifestMismatch> {
+        self.files
+            .iter()
+            .filter_map(|entry| {
+                let Ok(contents) = fs::read(output_dir.join(&entry.path)) else {
+                    return Some(OutputManifestMismatch::Missing {
+                        path: entry.path.clone(),
+                    });
+                };
+                if contents.len() as u64 != entry.size_bytes {
+                    Some(OutputManifestMismatch::Size {
+                        path: entry.path.clone(),
+                        expected: entry.size_bytes,
+                        actual: contents.len() as u64,
+                    })
+                } else if sha256_hex(&contents) != entry.sha256 {
+                    Some(OutputManifestMismatch::Checksum {
+                        path: entry.path.clone(),
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn sha256_hex(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+fn file_type(path: &str) -> String {
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default()
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_of_written_files_round_trips_and_detects_changed_files() {
+        let root = std::env::temp_dir().join(format!("agbot_manifest_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(root.join("tiles")).unwrap();
+        fs::write(root.join("earlier_run.png"), b"old bytes").unwrap();
+        fs::write(root.join(OUTPUT_MANIFEST_FILE_NAME), b"stale").unwrap();
+
+        OutputManifest::remove(&root).unwrap();
+        assert!(!root.join(OUTPUT_MANIFEST_FILE_NAME).exists());
+        OutputManifest::remove(&root).unwrap();
+        fs::write(root.join("heatmap.PNG"), b"png bytes").unwrap();
+        fs::write(root.join("tiles").join("0.json"), b"{}").unwrap();
+        let written = ["tiles/0.json", "heatmap.PNG"]
+            .map(|path| (root.join(path), vec![format!("in/{path}")]));
+
+        let manifest = OutputManifest::from_written("test", &root, written).unwrap();
+        manifest.write(&root).unwrap();
+        let manifest = OutputManifest::read(&root).unwrap();
+
+        let paths: Vec<_> = manifest.files.iter().map(|entry| &entry.path).collect();
+        assert_eq!(paths, ["heatmap.PNG", "tiles/0.json"]);
+        assert_eq!(manifest.files[0].file_type, "png");
+        assert_eq!(manifest.files[0].size_bytes, 9);
+        assert_eq!(manifest.files[0].sha256, sha256_hex(b"png bytes"));
+        assert_eq!(manifest.files[1].source_inputs, ["in/tiles/0.json"]);
+        assert!(manifest.verify(&root).is_empty());
+
+        fs::write(root.join("heatmap.PNG"), b"PNG bytes").unwrap();
+        fs::remove_file(root.join("tiles").join("0.json")).unwrap();
+        assert_eq!(
+            manifest.verify(&root),
+            [
+                OutputManifestMismatch::Checksum {
+                    path: "heatmap.PNG".to_string()
+                },
+                OutputManifestMismatch::Missing {
+                    path: "tiles/0.json".to_string()
+                },
+            ]
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+}
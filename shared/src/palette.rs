@@ -0,0 +1,339 @@
+//! Named colour palettes shared by every visual output.
+//!
+//! Each palette is defined once here as exact RGB stops and looked up by
+//! name with [`palette`]. Continuous palettes interpolate linearly between
+//! their stops; categorical palettes hand out distinct colours to classes.
+//! `viridis`, `cividis` and `okabe-ito` stay readable with the common forms
+//! of colour blindness and are the defaults for new outputs; `hot`, `jet` and
+//! `grayscale` remain for existing configurations.
+
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// An sRGB colour.
+pub type Rgb8 = [u8; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteKind {
+    /// A ramp sampled anywhere in `[0, 1]`.
+    Continuous,
+    /// Distinct colours for unordered classes.
+    Categorical,
+}
+
+/// One colour of a palette at its position in `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaletteStop {
+    pub position: f32,
+    pub color: Rgb8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub name: &'static str,
+    pub kind: PaletteKind,
+    /// Sorted by position, first at 0 and last at 1.
+    pub stops: &'static [PaletteStop],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("unknown palette '{name}'; available palettes: {}", available.join(", "))]
+pub struct UnknownPaletteError {
+    pub name: String,
+    pub available: Vec<&'static str>,
+}
+
+const fn stop(position: f32, color: Rgb8) -> PaletteStop {
+    PaletteStop { position, color }
+}
+
+/// Matplotlib's viridis sampled at 11 even steps.
+pub const VIRIDIS: Palette = Palette {
+    name: "viridis",
+    kind: PaletteKind::Continuous,
+    stops: &[
+        stop(0.0, [68, 1, 84]),
+        stop(0.1, [72, 36, 117]),
+        stop(0.2, [65, 68, 135]),
+        stop(0.3, [53, 95, 141]),
+        stop(0.4, [42, 120, 142]),
+        stop(0.5, [33, 145, 140]),
+        stop(0.6, [34, 168, 132]),
+        stop(0.7, [68, 191, 112]),
+        stop(0.8, [122, 209, 81]),
+        stop(0.9, [189, 223, 38]),
+        stop(1.0, [253, 231, 37]),
+    ],
+};
+
+/// Cividis (Nuñez et al. 2018) sampled at 10 even steps; blue to yellow
+/// with no red-green contrast at all.
+pub const CIVIDIS: Palette = Palette {
+    name: "cividis",
+    kind: PaletteKind::Continuous,
+    stops: &[
+        stop(0.0, [0, 32, 77]),
+        stop(1.0 / 9.0, [0, 51, 111]),
+        stop(2.0 / 9.0, [57, 72, 107]),
+        stop(3.0 / 9.0, [87, 92, 109]),
+        stop(4.0 / 9.0, [112, 113, 115]),
+        stop(5.0 / 9.0, [138, 135, 121]),
+        stop(6.0 / 9.0, [166, 157, 117]),
+        stop(7.0 / 9.0, [196, 181, 108]),
+        stop(8.0 / 9.0, [228, 207, 91]),
+        stop(1.0, [255, 234, 70]),
+    ],
+};
+
+/// Diverging red-white-blue with pure white at the midpoint.
+pub const RDBU: Palette = Palette {
+    name: "rdbu",
+    kind: PaletteKind::Continuous,
+    stops: &[
+        stop(0.0, [178, 24, 43]),
+        stop(0.5, [255, 255, 255]),
+        stop(1.0, [33, 102, 172]),
+    ],
+};
+
+pub const HOT: Palette = Palette {
+    name: "hot",
+    kind: PaletteKind::Continuous,
+    stops: &[
+        stop(0.0, [0, 0, 0]),
+        stop(0.33, [255, 0, 0]),
+        stop(0.66, [255, 255, 0]),
+        stop(1.0, [255, 255, 255]),
+    ],
+};
+
+pub const JET: Palette = Palette {
+    name: "jet",
+    kind: PaletteKind::Continuous,
+    stops: &[
+        stop(0.0, [0, 0, 128]),
+        stop(0.11, [0, 0, 255]),
+        stop(0.125, [0, 0, 255]),
+        stop(0.34, [0, 219, 255]),
+        stop(0.35, [0, 230, 247]),
+        stop(0.375, [21, 255, 226]),
+        stop(0.64, [238, 255, 8]),
+        stop(0.65, [247, 248, 0]),
+        stop(0.66, [255, 241, 0]),
+        stop(1.0, [255, 0, 0]),
+    ],
+};
+
+pub const GRAYSCALE: Palette = Palette {
+    name: "grayscale",
+    kind: PaletteKind::Continuous,
+    stops: &[stop(0.0, [0, 0, 0]), stop(1.0, [255, 255, 255])],
+};
+
+/// The eight colours of Okabe & Ito (2008), named for use as fixed class
+/// colours.
+pub mod okabe_ito {
+    use super::Rgb8;
+
+    pub const BLACK: Rgb8 = [0, 0, 0];
+    pub const ORANGE: Rgb8 = [230, 159, 0];
+    pub const SKY_BLUE: Rgb8 = [86, 180, 233];
+    pub const BLUISH_GREEN: Rgb8 = [0, 158, 115];
+    pub const YELLOW: Rgb8 = [240, 228, 66];
+    pub const BLUE: Rgb8 = [0, 114, 178];
+    pub const VERMILLION: Rgb8 = [213, 94, 0];
+    pub const REDDISH_PURPLE: Rgb8 = [204, 121, 167];
+}
+
+/// The Okabe-Ito colours in their published order.
+pub const OKABE_ITO: Palette = Palette {
+    name: "okabe-ito",
+    kind: PaletteKind::Categorical,
+    stops: &[
+        stop(0.0, okabe_ito::BLACK),
+        stop(1.0 / 7.0, okabe_ito::ORANGE),
+        stop(2.0 / 7.0, okabe_ito::SKY_BLUE),
+        stop(3.0 / 7.0, okabe_ito::BLUISH_GREEN),
+        stop(4.0 / 7.0, okabe_ito::YELLOW),
+        stop(5.0 / 7.0, okabe_ito::BLUE),
+        stop(6.0 / 7.0, okabe_ito::VERMILLION),
+        stop(1.0, okabe_ito::REDDISH_PURPLE),
+    ],
+};
+
+pub const PALETTES: &[Palette] = &[VIRIDIS, CIVIDIS, RDBU, HOT, JET, GRAYSCALE, OKABE_ITO];
+
+/// Names of the palettes usable as a colormap for continuous values.
+pub const CONTINUOUS_PALETTE_NAMES: &[&str] =
+    &["viridis", "cividis", "rdbu", "hot", "jet", "grayscale"];
+
+/// The palette called `name`.
+pub fn palette(name: &str) -> Result<&'static Palette, UnknownPaletteError> {
+    PALETTES
+        .iter()
+        .find(|palette| palette.name == name)
+        .ok_or_else(|| UnknownPaletteError {
+            name: name.to_string(),
+            available: PALETTES.iter().map(|palette| palette.name).collect(),
+        })
+}
+
+impl Palette {
+    /// Colour at `t` in `[0, 1]`, clamped. Continuous palettes interpolate
+    /// between the neighbouring stops; categorical ones split the range into
+    /// one equal band per colour. A non-finite `t` gives the middle colour.
+    pub fn color_at(&self, t: f32) -> Rgb8 {
+        let t = if t.is_finite() {
+            t.clamp(0.0, 1.0)
+        } else {
+            0.5
+        };
+        match self.kind {
+            PaletteKind::Categorical => {
+                let index = (t * self.stops.len() as f32) as usize;
+                self.stops[index.min(self.stops.len() - 1)].color
+            }
+            PaletteKind::Continuous => {
+                let upper = self
+                    .stops
+                    .iter()
+                    .position(|stop| stop.position >= t)
+                    .unwrap_or(self.stops.len() - 1);
+                if upper == 0 {
+                    return self.stops[0].color;
+                }
+                let (low, high) = (self.stops[upper - 1], self.stops[upper]);
+                let span = high.position - low.position;
+                let weight = if span > 0.0 {
+                    (t - low.position) / span
+                } else {
+                    1.0
+                };
+                let channel = |index: usize| {
+                    let (from, to) = (f32::from(low.color[index]), f32::from(high.color[index]));
+                    (from + (to - from) * weight).round() as u8
+                };
+                [channel(0), channel(1), channel(2)]
+            }
+        }
+    }
+
+    /// The palette's colours in order.
+    pub fn colors(&self) -> impl Iterator<Item = Rgb8> + '_ {
+        self.stops.iter().map(|stop| stop.color)
+    }
+
+    /// Colour of the class called `class_name`, picked by a hash of the name
+    /// so the same class gets the same colour in every run. Two classes may
+    /// share a colour; use [`Palette::assign_class_colors`] to colour a known
+    /// set of classes distinctly.
+    pub fn class_color(&self, class_name: &str) -> Rgb8 {
+        self.stops[self.class_slot(class_name)].color
+    }
+
+    /// Distinct colours for each of `class_names` while the palette has
+    /// colours left. Classes are placed in name order at their hashed slot,
+    /// moving to the next free one on a collision, so the result depends
+    /// only on the set of names, not on their order or on the run.
+    pub fn assign_class_colors<'a>(
+        &self,
+        class_names: impl IntoIterator<Item = &'a str>,
+    ) -> BTreeMap<String, Rgb8> {
+        let names: std::collections::BTreeSet<&str> = class_names.into_iter().collect();
+        let mut used = vec![false; self.stops.len()];
+        let mut assigned = BTreeMap::new();
+        for name in names {
+            if used.iter().all(|used| *used) {
+                used.fill(false);
+            }
+            let mut slot = self.class_slot(name);
+            while used[slot] {
+                slot = (slot + 1) % self.stops.len();
+            }
+            used[slot] = true;
+            assigned.insert(name.to_string(), self.stops[slot].color);
+        }
+        assigned
+    }
+
+    fn class_slot(&self, class_name: &str) -> usize {
+        (fnv1a(class_name.as_bytes()) % self.stops.len() as u64) as usize
+    }
+}
+
+/// 64-bit FNV-1a; unlike the std hasher its output is fixed across builds.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// `#rrggbb` for `color`.
+pub fn hex(color: Rgb8) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palettes_hit_their_exact_stops_and_interpolate_between_them() {
+        let viridis = palette("viridis").unwrap();
+        assert_eq!(viridis.color_at(0.0), [68, 1, 84]);
+        assert_eq!(viridis.color_at(0.5), [33, 145, 140]);
+        assert_eq!(viridis.color_at(1.0), [253, 231, 37]);
+        assert_eq!(viridis.color_at(0.05), [70, 19, 101]);
+
+        let cividis = palette("cividis").unwrap();
+        assert_eq!(cividis.color_at(0.0), [0, 32, 77]);
+        assert_eq!(cividis.color_at(1.0), [255, 234, 70]);
+
+        let rdbu = palette("rdbu").unwrap();
+        assert_eq!(rdbu.color_at(-3.0), [178, 24, 43]);
+        assert_eq!(rdbu.color_at(0.5), [255, 255, 255]);
+        assert_eq!(rdbu.color_at(0.75), [144, 179, 214]);
+
+        let okabe_ito = palette("okabe-ito").unwrap();
+        assert_eq!(okabe_ito.kind, PaletteKind::Categorical);
+        assert_eq!(okabe_ito.colors().nth(3), Some([0, 158, 115]));
+        assert_eq!(okabe_ito.color_at(1.0), [204, 121, 167]);
+
+        for palette in PALETTES {
+            assert_eq!(palette.stops.first().unwrap().position, 0.0);
+            assert_eq!(palette.stops.last().unwrap().position, 1.0);
+        }
+    }
+
+    #[test]
+    fn unknown_palette_lists_the_available_ones() {
+        let error = palette("rainbow").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown palette 'rainbow'; available palettes: viridis, cividis, rdbu, hot, jet, grayscale, okabe-ito"
+        );
+    }
+
+    #[test]
+    fn class_colors_are_stable_and_distinct_within_a_set() {
+        let palette = palette("okabe-ito").unwrap();
+        assert_eq!(
+            palette.class_color("stressed"),
+            palette.class_color("stressed")
+        );
+
+        let classes = ["improved", "stable", "degraded", "no data"];
+        let forward = palette.assign_class_colors(classes);
+        let reversed = palette.assign_class_colors(classes.iter().rev().copied());
+        assert_eq!(forward, reversed);
+        let distinct: std::collections::BTreeSet<_> = forward.values().collect();
+        assert_eq!(distinct.len(), classes.len());
+
+        // Pinned so a change to the hash or the palette order shows up here.
+        assert_eq!(palette.class_color("stressed"), [240, 228, 66]);
+        assert_eq!(forward["no data"], [213, 94, 0]);
+        // "stable" hashes to the same slot as "no data" and moves on.
+        assert_eq!(palette.class_color("stable"), [213, 94, 0]);
+        assert_eq!(forward["stable"], [204, 121, 167]);
+    }
+}
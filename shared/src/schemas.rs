@@ -1,3 +1,4 @@
+use crate::geospatial::{AltitudeConversionError, AltitudeDatum, AltitudeReference};
 use chrono::{Datelike, NaiveDate};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashMap};
@@ -18489,6 +18490,7 @@ fn assert_extent_edge(
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Telemetry {
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// `position.altitude` is the GPS altitude above mean sea level.
     pub position: GpsCoords,
     pub battery_voltage: f32,
     pub battery_percentage: u8,
@@ -18497,14 +18499,39 @@ pub struct Telemetry {
     pub ground_speed: f32,
     pub air_speed: f32,
     pub heading: f32,
+    /// Altitude above the home position.
     pub altitude_relative: f32,
 }
 
+impl Telemetry {
+    /// Home elevation implied by the absolute and relative altitudes.
+    pub fn home_elevation_msl_m(&self) -> f64 {
+        self.position.altitude - f64::from(self.altitude_relative)
+    }
+
+    /// Current altitude against `reference`; AGL needs the terrain elevation
+    /// under the aircraft.
+    pub fn altitude_m(
+        &self,
+        reference: AltitudeReference,
+        terrain_elevation_msl_m: Option<f64>,
+    ) -> Result<f64, AltitudeConversionError> {
+        AltitudeReference::Msl.convert(
+            self.position.altitude,
+            reference,
+            &AltitudeDatum::new(Some(self.home_elevation_msl_m()), terrain_elevation_msl_m),
+        )
+    }
+}
+
 /// Mission waypoint
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Waypoint {
     pub sequence: u16,
+    /// `position.altitude` is measured from `altitude_reference`.
     pub position: GpsCoords,
+    #[serde(default)]
+    pub altitude_reference: AltitudeReference,
     pub command: u16,
     pub auto_continue: bool,
     pub param1: f32,
@@ -18574,6 +18601,19 @@ pub struct NdviResult {
     pub vegetation_percentage: f32,
 }
 
+/// Completion notice for an overlay generated live from ingested inputs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OverlayNotification {
+    pub job_id: uuid::Uuid,
+    pub completed_at: chrono::DateTime<chrono::Utc>,
+    /// Capture time of the earliest input that fed the overlay.
+    pub captured_at: chrono::DateTime<chrono::Utc>,
+    pub overlay_types: Vec<String>,
+    pub source_sensor_ids: Vec<String>,
+    pub composite_path: String,
+    pub position: Option<GpsCoords>,
+}
+
 /// WebSocket message types for ground station communication
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -18587,6 +18627,9 @@ pub enum WebSocketMessage {
     },
     LidarUpdate {
         scan: LidarScan,
+        /// Sensor that produced the scan, for vehicles carrying more than one.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sensor_id: Option<String>,
     },
     ImageCaptured {
         image: MultispectralImage,
@@ -18594,10 +18637,27 @@ pub enum WebSocketMessage {
     NdviProcessed {
         result: NdviResult,
     },
+    OverlayGenerated {
+        overlay: OverlayNotification,
+    },
     SystemStatus {
         status: String,
         message: String,
     },
+    /// Recent telemetry and status messages, oldest first, sent once to a
+    /// client when it connects and before any live message.
+    HistorySnapshot {
+        messages: Vec<WebSocketMessage>,
+    },
+    /// Point-to-point mission sync traffic between a planner and mission
+    /// control; never broadcast.
+    MissionSync {
+        message: crate::mission_sync::MissionSyncMessage,
+    },
+    /// How far a mission sync has got, for progress displays.
+    MissionSyncProgress {
+        progress: crate::mission_sync::MissionSyncProgress,
+    },
 }
 
 pub fn bounds_from_points(points: &[GeoPoint]) -> Option<GeoBounds> {
@@ -0,0 +1,327 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Called once when a task has used up its restart budget.
+pub type EscalationHandler = Arc<dyn Fn(&SupervisedTaskStatus) + Send + Sync>;
+
+/// Restart budget and backoff for a supervised task.
+#[derive(Clone)]
+pub struct SupervisionPolicy {
+    /// Restarts allowed within `restart_window` before escalating.
+    pub max_restarts: u32,
+    pub restart_window: Duration,
+    /// Delay before the first restart; doubles with each further failure in
+    /// the window.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub escalation: Option<EscalationHandler>,
+}
+
+impl Default for SupervisionPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            restart_window: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            escalation: None,
+        }
+    }
+}
+
+impl fmt::Debug for SupervisionPolicy {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("SupervisionPolicy")
+            .field("max_restarts", &self.max_restarts)
+            .field("restart_window", &self.restart_window)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("escalation", &self.escalation.is_some())
+            .finish()
+    }
+}
+
+impl SupervisionPolicy {
+    pub fn with_escalation(
+        mut self,
+        escalation: impl Fn(&SupervisedTaskStatus) + Send + Sync + 'static,
+    ) -> Self {
+        self.escalation = Some(Arc::new(escalation));
+        self
+    }
+
+    /// Escalation that terminates the process so the service manager can
+    /// restart it from scratch.
+    pub fn exit_process_on_escalation(self) -> Self {
+        self.with_escalation(|status| {
+            tracing::error!(
+                "Supervised task '{}' exhausted its restart budget; exiting",
+                status.name
+            );
+            std::process::exit(1);
+        })
+    }
+
+    fn backoff(&self, failures_in_window: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures_in_window.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupervisedTaskState {
+    Running,
+    /// Waiting out the backoff before the next restart.
+    Restarting,
+    /// Returned `Ok(())`; not restarted.
+    Completed,
+    /// Restart budget exhausted; escalation has run.
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SupervisedTaskStatus {
+    pub name: String,
+    pub state: SupervisedTaskState,
+    pub restart_count: u32,
+    pub last_error: Option<String>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+}
+
+/// Supervised task states for a service's health/ready endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SupervisionReport {
+    /// False once any task has exhausted its restart budget.
+    pub healthy: bool,
+    pub total_restarts: u32,
+    pub tasks: Vec<SupervisedTaskStatus>,
+}
+
+/// Spawns tasks that are restarted when they fail and records their status.
+/// Clones share the same status table.
+#[derive(Debug, Clone, Default)]
+pub struct TaskSupervisor {
+    tasks: Arc<Mutex<BTreeMap<String, SupervisedTaskStatus>>>,
+}
+
+impl TaskSupervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the future built by `factory` until it returns `Ok(())`. Errors
+    /// and panics restart it after the policy's backoff; once more than
+    /// `max_restarts` failures land within `restart_window` the task is
+    /// marked failed and the escalation handler runs. The handle resolves to
+    /// the task's final status.
+    pub fn spawn_supervised<F, Fut, E>(
+        &self,
+        name: impl Into<String>,
+        mut factory: F,
+        policy: SupervisionPolicy,
+    ) -> JoinHandle<SupervisedTaskStatus>
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: fmt::Display + Send + 'static,
+    {
+        let name = name.into();
+        let supervisor = self.clone();
+        supervisor.update(&name, |status| status.state = SupervisedTaskState::Running);
+
+        tokio::spawn(async move {
+            let mut recent_failures = VecDeque::new();
+            loop {
+                let error = match tokio::spawn(factory()).await {
+                    Ok(Ok(())) => {
+                        return supervisor.update(&name, |status| {
+                            status.state = SupervisedTaskState::Completed;
+                        });
+                    }
+                    Ok(Err(error)) => error.to_string(),
+                    Err(join_error) if join_error.is_panic() => {
+                        format!("panicked: {}", panic_message(join_error.into_panic()))
+                    }
+                    Err(join_error) => join_error.to_string(),
+                };
+
+                let now = Instant::now();
+                recent_failures.push_back(now);
+                while recent_failures
+                    .front()
+                    .is_some_and(|failed_at| now.duration_since(*failed_at) > policy.restart_window)
+                {
+                    recent_failures.pop_front();
+                }
+
+                if recent_failures.len() > policy.max_restarts as usize {
+                    tracing::error!("Supervised task '{}' failed permanently: {}", name, error);
+                    let status = supervisor.update(&name, |status| {
+                        status.state = SupervisedTaskState::Failed;
+                        status.last_error = Some(error);
+                        status.last_failure_at = Some(Utc::now());
+                    });
+                    if let Some(escalation) = &policy.escalation {
+                        escalation(&status);
+                    }
+                    return status;
+                }
+
+                let backoff = policy.backoff(recent_failures.len() as u32);
+                tracing::warn!(
+                    "Supervised task '{}' failed ({}); restarting in {:?}",
+                    name,
+                    error,
+                    backoff
+                );
+                supervisor.update(&name, |status| {
+                    status.state = SupervisedTaskState::Restarting;
+                    status.last_error = Some(error);
+                    status.last_failure_at = Some(Utc::now());
+                });
+                tokio::time::sleep(backoff).await;
+                supervisor.update(&name, |status| {
+                    status.state = SupervisedTaskState::Running;
+                    status.restart_count += 1;
+                });
+            }
+        })
+    }
+
+    pub fn status(&self, name: &str) -> Option<SupervisedTaskStatus> {
+        self.lock().get(name).cloned()
+    }
+
+    pub fn report(&self) -> SupervisionReport {
+        let tasks = self.lock().values().cloned().collect::<Vec<_>>();
+        SupervisionReport {
+            healthy: tasks
+                .iter()
+                .all(|task| task.state != SupervisedTaskState::Failed),
+            total_restarts: tasks.iter().map(|task| task.restart_count).sum(),
+            tasks,
+        }
+    }
+
+    fn update(
+        &self,
+        name: &str,
+        apply: impl FnOnce(&mut SupervisedTaskStatus),
+    ) -> SupervisedTaskStatus {
+        let mut tasks = self.lock();
+        let status = tasks
+            .entry(name.to_string())
+            .or_insert_with(|| SupervisedTaskStatus {
+                name: name.to_string(),
+                state: SupervisedTaskState::Running,
+                restart_count: 0,
+                last_error: None,
+                last_failure_at: None,
+            });
+        apply(status);
+        status.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, SupervisedTaskStatus>> {
+        // A panic while holding the lock leaves the table consistent, so keep
+        // serving it.
+        self.tasks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    async fn always_panics() -> Result<(), String> {
+        panic!("always broken")
+    }
+
+    fn fast_policy(max_restarts: u32) -> SupervisionPolicy {
+        SupervisionPolicy {
+            max_restarts,
+            restart_window: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            escalation: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn task_that_panics_twice_is_restarted_until_it_succeeds() {
+        let supervisor = TaskSupervisor::new();
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = attempts.clone();
+
+        let status = supervisor
+            .spawn_supervised(
+                "flaky",
+                move || {
+                    let attempt = counter.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        if attempt < 2 {
+                            panic!("attempt {attempt} blew up");
+                        }
+                        Ok::<(), String>(())
+                    }
+                },
+                fast_policy(3),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(status.state, SupervisedTaskState::Completed);
+        assert_eq!(status.restart_count, 2);
+        assert_eq!(
+            status.last_error.as_deref(),
+            Some("panicked: attempt 1 blew up")
+        );
+        let report = supervisor.report();
+        assert!(report.healthy);
+        assert_eq!(report.total_restarts, 2);
+    }
+
+    #[tokio::test]
+    async fn task_that_always_fails_escalates_once_the_budget_is_spent() {
+        let supervisor = TaskSupervisor::new();
+        let escalated = Arc::new(Mutex::new(Vec::new()));
+        let sink = escalated.clone();
+        let policy = fast_policy(2).with_escalation(move |status| {
+            sink.lock().unwrap().push(status.clone());
+        });
+
+        let status = supervisor
+            .spawn_supervised("doomed", always_panics, policy)
+            .await
+            .unwrap();
+
+        assert_eq!(status.state, SupervisedTaskState::Failed);
+        assert_eq!(status.restart_count, 2);
+        assert_eq!(escalated.lock().unwrap().len(), 1);
+        assert_eq!(escalated.lock().unwrap()[0], status);
+        assert!(!supervisor.report().healthy);
+        assert_eq!(supervisor.status("doomed"), Some(status));
+    }
+}
@@ -0,0 +1,580 @@
+use crate::http_client::{
+    CircuitBreakerPolicy, CircuitBreakerStatus, HttpClient, HttpClientConfig, RetryPolicy,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, Notify, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use uuid::Uuid;
+
+/// Header carrying `sha256=<hex HMAC-SHA256 of the body>`.
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-agbot-signature";
+/// Header carrying the event kind, e.g. `job.completed`.
+pub const WEBHOOK_EVENT_HEADER: &str = "x-agbot-event";
+/// Header carrying the event id; retries of one event reuse it.
+pub const WEBHOOK_DELIVERY_HEADER: &str = "x-agbot-delivery";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WebhookEventKind {
+    #[serde(rename = "battery.alert")]
+    BatteryAlert,
+    #[serde(rename = "job.completed")]
+    JobCompleted,
+    #[serde(rename = "job.failed")]
+    JobFailed,
+    #[serde(rename = "mission.deployed")]
+    MissionDeployed,
+    #[serde(rename = "record.stored")]
+    RecordStored,
+    #[serde(rename = "report.generated")]
+    ReportGenerated,
+    #[serde(rename = "safety.violation")]
+    SafetyViolation,
+}
+
+impl WebhookEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::BatteryAlert => "battery.alert",
+            Self::JobCompleted => "job.completed",
+            Self::JobFailed => "job.failed",
+            Self::MissionDeployed => "mission.deployed",
+            Self::RecordStored => "record.stored",
+            Self::ReportGenerated => "report.generated",
+            Self::SafetyViolation => "safety.violation",
+        }
+    }
+}
+
+/// Body POSTed to every endpoint subscribed to `kind`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    pub id: Uuid,
+    pub kind: WebhookEventKind,
+    pub occurred_at: DateTime<Utc>,
+    pub payload: serde_json::Value,
+}
+
+impl WebhookEvent {
+    pub fn new(kind: WebhookEventKind, payload: serde_json::Value) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            occurred_at: Utc::now(),
+            payload,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    pub id: String,
+    pub url: String,
+    /// HMAC key for the signature header.
+    pub secret: String,
+    /// Event kinds delivered to this endpoint; empty means all of them.
+    #[serde(default)]
+    pub events: Vec<WebhookEventKind>,
+}
+
+impl fmt::Debug for WebhookEndpoint {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("WebhookEndpoint")
+            .field("id", &self.id)
+            .field("url", &self.url)
+            .field("secret", &"<redacted>")
+            .field("events", &self.events)
+            .finish()
+    }
+}
+
+impl WebhookEndpoint {
+    pub fn accepts(&self, kind: WebhookEventKind) -> bool {
+        self.events.is_empty() || self.events.contains(&kind)
+    }
+}
+
+/// Retry, timeout and concurrency limits shared by all endpoints.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookDeliveryPolicy {
+    /// Retries after the first attempt before the delivery is dead-lettered.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles with each further retry.
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Random extra delay on each retry, as a fraction of the backoff.
+    pub jitter: f64,
+    /// Per-attempt request timeout.
+    pub timeout_ms: u64,
+    /// Deliveries in progress at once across all endpoints.
+    pub max_in_flight: usize,
+    /// Endpoints sharing a host share its breaker.
+    pub circuit_breaker: CircuitBreakerPolicy,
+}
+
+impl Default for WebhookDeliveryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            initial_backoff_ms: 1_000,
+            max_backoff_ms: 60_000,
+            jitter: 0.2,
+            timeout_ms: 10_000,
+            max_in_flight: 8,
+            circuit_breaker: CircuitBreakerPolicy::default(),
+        }
+    }
+}
+
+impl WebhookDeliveryPolicy {
+    fn http_config(&self) -> HttpClientConfig {
+        HttpClientConfig {
+            timeout_ms: self.timeout_ms,
+            retry: RetryPolicy {
+                max_attempts: self.max_retries.saturating_add(1),
+                initial_backoff_ms: self.initial_backoff_ms,
+                max_backoff_ms: self.max_backoff_ms,
+                jitter: self.jitter,
+                retry_non_idempotent: false,
+            },
+            circuit_breaker: self.circuit_breaker.clone(),
+            redacted_headers: vec![WEBHOOK_SIGNATURE_HEADER.to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub endpoints: Vec<WebhookEndpoint>,
+    #[serde(default)]
+    pub delivery: WebhookDeliveryPolicy,
+    /// Events waiting for delivery before `publish` starts rejecting them.
+    #[serde(default = "default_queue_capacity")]
+    pub queue_capacity: usize,
+}
+
+fn default_queue_capacity() -> usize {
+    1_024
+}
+
+impl WebhookConfig {
+    pub fn load(path: &std::path::Path) -> Result<Self, WebhookError> {
+        let config_error = |reason: String| WebhookError::Config { reason };
+        let content = std::fs::read(path)
+            .map_err(|error| config_error(format!("{}: {error}", path.display())))?;
+        serde_json::from_slice(&content)
+            .map_err(|error| config_error(format!("{}: {error}", path.display())))
+    }
+}
+
+/// A delivery that failed every attempt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookDeadLetter {
+    pub endpoint_id: String,
+    pub event: WebhookEvent,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WebhookError {
+    #[error("webhook endpoint {index} is invalid: {reason}")]
+    InvalidEndpoint { index: usize, reason: String },
+    #[error("webhook queue capacity and max in-flight deliveries must be positive")]
+    InvalidLimits,
+    #[error("webhook queue is full; event {event_id} was dropped")]
+    QueueFull { event_id: Uuid },
+    #[error("webhook dispatcher has stopped")]
+    Stopped,
+    #[error("failed to build webhook HTTP client: {reason}")]
+    Client { reason: String },
+    #[error("failed to load webhook configuration: {reason}")]
+    Config { reason: String },
+}
+
+/// `sha256=<hex>` signature of `body` under `secret`.
+pub fn sign_webhook_body(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex = digest
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    format!("sha256={hex}")
+}
+
+/// Checks a signature header value in constant time.
+pub fn verify_webhook_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(hex) = signature.strip_prefix("sha256=") else {
+        return false;
+    };
+    let Some(expected) = decode_hex(hex) else {
+        return false;
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// Handle for publishing lifecycle events to the configured webhooks.
+/// Events are queued and delivered by a background task; clones share the
+/// queue and the dead-letter log.
+#[derive(Debug, Clone)]
+pub struct WebhookDispatcher {
+    sender: mpsc::Sender<WebhookEvent>,
+    http: HttpClient,
+    dead_letters: Arc<Mutex<Vec<WebhookDeadLetter>>>,
+    pending: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+}
+
+impl WebhookDispatcher {
+    /// Validates `config` and spawns the delivery task. The task drains the
+    /// queue and finishes once every handle has been dropped.
+    pub fn spawn(config: WebhookConfig) -> Result<(Self, JoinHandle<()>), WebhookError> {
+        for (index, endpoint) in config.endpoints.iter().enumerate() {
+            let reason = if endpoint.id.trim().is_empty() {
+                Some("id is empty")
+            } else if !(endpoint.url.starts_with("http://") || endpoint.url.starts_with("https://"))
+            {
+                Some("url must be http(s)")
+            } else if endpoint.secret.is_empty() {
+                Some("secret is empty")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(WebhookError::InvalidEndpoint {
+                    index,
+                    reason: reason.to_string(),
+                });
+            }
+        }
+        if config.queue_capacity == 0 || config.delivery.max_in_flight == 0 {
+            return Err(WebhookError::InvalidLimits);
+        }
+        let http = HttpClient::new(config.delivery.http_config()).map_err(|error| {
+            WebhookError::Client {
+                reason: error.to_string(),
+            }
+        })?;
+
+        let (sender, receiver) = mpsc::channel(config.queue_capacity);
+        let dispatcher = Self {
+            sender,
+            http: http.clone(),
+            dead_letters: Arc::new(Mutex::new(Vec::new())),
+            pending: Arc::new(AtomicUsize::new(0)),
+            idle: Arc::new(Notify::new()),
+        };
+        let worker = tokio::spawn(run_deliveries(
+            receiver,
+            http,
+            Arc::new(config),
+            dispatcher.dead_letters.clone(),
+            dispatcher.pending.clone(),
+            dispatcher.idle.clone(),
+        ));
+        Ok((dispatcher, worker))
+    }
+
+    /// Queues `event` without waiting; a full queue drops the event rather
+    /// than stalling the producer.
+    pub fn publish(&self, event: WebhookEvent) -> Result<(), WebhookError> {
+        let event_id = event.id;
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        self.sender.try_send(event).map_err(|error| {
+            self.finish_one();
+            match error {
+                mpsc::error::TrySendError::Full(_) => WebhookError::QueueFull { event_id },
+                mpsc::error::TrySendError::Closed(_) => WebhookError::Stopped,
+            }
+        })
+    }
+
+    /// Deliveries that exhausted their retries, oldest first.
+    pub fn dead_letters(&self) -> Vec<WebhookDeadLetter> {
+        lock(&self.dead_letters).clone()
+    }
+
+    /// Breaker state for each endpoint host delivered to so far.
+    pub fn circuit_breakers(&self) -> Vec<CircuitBreakerStatus> {
+        self.http.circuit_breakers()
+    }
+
+    /// Waits until every published event has been delivered or dead-lettered.
+    pub async fn flush(&self) {
+        loop {
+            let idle = self.idle.notified();
+            if self.pending.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    fn finish_one(&self) {
+        finish_one(&self.pending, &self.idle);
+    }
+}
+
+fn finish_one(pending: &AtomicUsize, idle: &Notify) {
+    if pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+        idle.notify_waiters();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+async fn run_deliveries(
+    mut receiver: mpsc::Receiver<WebhookEvent>,
+    http: HttpClient,
+    config: Arc<WebhookConfig>,
+    dead_letters: Arc<Mutex<Vec<WebhookDeadLetter>>>,
+    pending: Arc<AtomicUsize>,
+    idle: Arc<Notify>,
+) {
+    let slots = Arc::new(Semaphore::new(config.delivery.max_in_flight));
+    let mut deliveries = JoinSet::new();
+    while let Some(event) = receiver.recv().await {
+        while deliveries.try_join_next().is_some() {}
+        let event = Arc::new(event);
+        let body = match serde_json::to_vec(event.as_ref()) {
+            Ok(body) => Arc::new(body),
+            Err(error) => {
+                tracing::error!("Dropping webhook event {}: {}", event.id, error);
+                finish_one(&pending, &idle);
+                continue;
+            }
+        };
+        let endpoints = config
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.accepts(event.kind))
+            .cloned()
+            .collect::<Vec<_>>();
+        let remaining = Arc::new(AtomicUsize::new(endpoints.len()));
+        if endpoints.is_empty() {
+            finish_one(&pending, &idle);
+            continue;
+        }
+
+        for endpoint in endpoints {
+            let slot = slots
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("delivery slots are never closed");
+            let (http, event, body) = (http.clone(), event.clone(), body.clone());
+            let (dead_letters, pending, idle, remaining) = (
+                dead_letters.clone(),
+                pending.clone(),
+                idle.clone(),
+                remaining.clone(),
+            );
+            deliveries.spawn(async move {
+                if let Err(dead_letter) = deliver(&http, &endpoint, &event, &body).await {
+                    tracing::warn!(
+                        "Webhook {} to endpoint '{}' dead-lettered after {} attempts: {}",
+                        event.id,
+                        endpoint.id,
+                        dead_letter.attempts,
+                        dead_letter.last_error
+                    );
+                    lock(&dead_letters).push(dead_letter);
+                }
+                drop(slot);
+                if remaining.fetch_sub(1, Ordering::SeqCst) == 1 {
+                    finish_one(&pending, &idle);
+                }
+            });
+        }
+    }
+    while deliveries.join_next().await.is_some() {}
+}
+
+async fn deliver(
+    http: &HttpClient,
+    endpoint: &WebhookEndpoint,
+    event: &WebhookEvent,
+    body: &[u8],
+) -> Result<(), WebhookDeadLetter> {
+    let signature = sign_webhook_body(&endpoint.secret, body);
+    let request = http
+        .post(&endpoint.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(WEBHOOK_SIGNATURE_HEADER, &signature)
+        .header(WEBHOOK_EVENT_HEADER, event.kind.as_str())
+        .header(WEBHOOK_DELIVERY_HEADER, event.id.to_string())
+        .body(body.to_vec());
+    // Retries reuse the delivery header, so receivers can drop repeats.
+    http.send_retryable(request)
+        .await
+        .map(drop)
+        .map_err(|error| WebhookDeadLetter {
+            endpoint_id: endpoint.id.clone(),
+            event: event.clone(),
+            attempts: error.attempts(),
+            last_error: error.to_string(),
+            failed_at: Utc::now(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Bytes,
+        extract::{Path, State},
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use std::collections::HashMap;
+
+    #[derive(Clone, Default)]
+    struct Receiver {
+        deliveries: Arc<Mutex<Vec<(String, HeaderMap, Bytes)>>>,
+        failures_left: Arc<Mutex<HashMap<String, u32>>>,
+    }
+
+    async fn receive(
+        State(receiver): State<Receiver>,
+        Path(endpoint): Path<String>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        receiver
+            .deliveries
+            .lock()
+            .unwrap()
+            .push((endpoint.clone(), headers, body));
+        let mut failures_left = receiver.failures_left.lock().unwrap();
+        match failures_left.get_mut(&endpoint) {
+            Some(left) if *left > 0 => {
+                *left -= 1;
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            _ => StatusCode::NO_CONTENT,
+        }
+    }
+
+    fn endpoint(base: &str, id: &str, events: Vec<WebhookEventKind>) -> WebhookEndpoint {
+        WebhookEndpoint {
+            id: id.to_string(),
+            url: format!("{base}/hooks/{id}"),
+            secret: format!("{id}-secret"),
+            events,
+        }
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_signed_retried_and_dead_lettered() {
+        let receiver = Receiver::default();
+        receiver
+            .failures_left
+            .lock()
+            .unwrap()
+            .extend([("flaky".to_string(), 2), ("broken".to_string(), u32::MAX)]);
+        let app = Router::new()
+            .route("/hooks/:endpoint", post(receive))
+            .with_state(receiver.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let (dispatcher, worker) = WebhookDispatcher::spawn(WebhookConfig {
+            endpoints: vec![
+                endpoint(&base, "flaky", vec![WebhookEventKind::JobCompleted]),
+                endpoint(&base, "broken", vec![WebhookEventKind::SafetyViolation]),
+            ],
+            delivery: WebhookDeliveryPolicy {
+                max_retries: 2,
+                initial_backoff_ms: 1,
+                max_backoff_ms: 4,
+                jitter: 0.0,
+                timeout_ms: 2_000,
+                max_in_flight: 2,
+                // Both endpoints share the test host; keep its breaker out of the way.
+                circuit_breaker: CircuitBreakerPolicy {
+                    failure_threshold: 0,
+                    ..CircuitBreakerPolicy::default()
+                },
+            },
+            queue_capacity: 8,
+        })
+        .unwrap();
+
+        let completed = WebhookEvent::new(
+            WebhookEventKind::JobCompleted,
+            serde_json::json!({"job_id": "a"}),
+        );
+        let violation = WebhookEvent::new(
+            WebhookEventKind::SafetyViolation,
+            serde_json::json!({"drone_id": "b"}),
+        );
+        dispatcher.publish(completed.clone()).unwrap();
+        dispatcher.publish(violation.clone()).unwrap();
+        dispatcher
+            .publish(WebhookEvent::new(
+                WebhookEventKind::ReportGenerated,
+                serde_json::Value::Null,
+            ))
+            .unwrap();
+        dispatcher.flush().await;
+
+        let deliveries = receiver.deliveries.lock().unwrap().clone();
+        let to = |id: &str| {
+            deliveries
+                .iter()
+                .filter(|(endpoint, ..)| endpoint == id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(to("flaky").len(), 3);
+        assert_eq!(to("broken").len(), 3);
+        let (_, headers, body) = to("flaky")[2];
+        let signature = headers[WEBHOOK_SIGNATURE_HEADER].to_str().unwrap();
+        assert!(verify_webhook_signature("flaky-secret", body, signature));
+        assert!(!verify_webhook_signature("broken-secret", body, signature));
+        assert_eq!(headers[WEBHOOK_EVENT_HEADER], "job.completed");
+        assert_eq!(
+            serde_json::from_slice::<WebhookEvent>(body).unwrap(),
+            completed
+        );
+
+        let dead_letters = dispatcher.dead_letters();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].endpoint_id, "broken");
+        assert_eq!(dead_letters[0].event, violation);
+        assert_eq!(dead_letters[0].attempts, 3);
+        assert!(dead_letters[0].last_error.contains("503"));
+
+        drop(dispatcher);
+        worker.await.unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value};
+use shared::mission_sync::{
+    chunk_mission_document, sync_mission, MissionSyncError, MissionSyncMessage, MissionSyncPhase,
+    MissionSyncPolicy, MissionSyncReceiver, MissionSyncSender, MissionSyncTransport, SyncedMission,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+const DROP_RATE: f64 = 0.3;
+
+/// One end of a link that loses `DROP_RATE` of what it sends and closes
+/// after `send_budget` messages.
+struct LossyTransport {
+    tx: mpsc::UnboundedSender<MissionSyncMessage>,
+    rx: mpsc::UnboundedReceiver<MissionSyncMessage>,
+    rng: StdRng,
+    send_budget: usize,
+    dropped: usize,
+}
+
+impl MissionSyncTransport for LossyTransport {
+    async fn send(&mut self, message: MissionSyncMessage) -> Result<(), MissionSyncError> {
+        if self.send_budget == 0 {
+            return Err(MissionSyncError::TransportClosed);
+        }
+        self.send_budget -= 1;
+        if self.rng.gen_bool(DROP_RATE) {
+            self.dropped += 1;
+            return Ok(());
+        }
+        self.tx
+            .send(message)
+            .map_err(|_| MissionSyncError::TransportClosed)
+    }
+
+    async fn recv(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<Option<MissionSyncMessage>, MissionSyncError> {
+        match tokio::time::timeout(timeout, self.rx.recv()).await {
+            Ok(Some(message)) => Ok(Some(message)),
+            Ok(None) => Err(MissionSyncError::TransportClosed),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// Connects a sender-side transport to a receiver task sharing `receiver`,
+/// which replies over an equally lossy link and records commits.
+fn connect(
+    receiver: Arc<Mutex<MissionSyncReceiver>>,
+    committed: Arc<Mutex<Vec<SyncedMission>>>,
+    seed: u64,
+    send_budget: usize,
+) -> LossyTransport {
+    let (to_receiver, mut receiver_inbox) = mpsc::unbounded_channel();
+    let (to_sender, sender_inbox) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut rng = StdRng::seed_from_u64(seed ^ 0xa5a5);
+        while let Some(message) = receiver_inbox.recv().await {
+            let step = receiver.lock().unwrap().handle(message);
+            if let Some(mission) = step.committed {
+                committed.lock().unwrap().push(mission);
+            }
+            if let Some(reply) = step.reply {
+                if !rng.gen_bool(DROP_RATE) && to_sender.send(reply).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    LossyTransport {
+        tx: to_receiver,
+        rx: sender_inbox,
+        rng: StdRng::seed_from_u64(seed),
+        send_budget,
+        dropped: 0,
+    }
+}
+
+fn survey_mission(waypoints: usize) -> Value {
+    let items: Vec<Value> = (0..waypoints)
+        .map(|seq| {
+            json!({
+                "seq": seq,
+                "frame": 3,
+                "command": 16,
+                "current": u8::from(seq == 0),
+                "autocontinue": 1,
+                "x": 41.25 + (seq / 20) as f64 * 1e-4,
+                "y": -96.0 + (seq % 20) as f64 * 1e-4,
+                "z": 40.0,
+            })
+        })
+        .collect();
+    json!({
+        "target_system": 1,
+        "target_component": 1,
+        "count": waypoints,
+        "items": items,
+    })
+}
+
+#[tokio::test]
+async fn lossy_link_with_a_reconnect_still_converges() {
+    let document = survey_mission(400);
+    let (manifest, chunks) =
+        chunk_mission_document(Uuid::new_v4(), &document, "items", 25).unwrap();
+    assert_eq!(manifest.chunk_count(), 16);
+    let mut sender = MissionSyncSender::new(manifest, chunks);
+    let receiver = Arc::new(Mutex::new(MissionSyncReceiver::new()));
+    let committed = Arc::new(Mutex::new(Vec::new()));
+    let policy = MissionSyncPolicy {
+        reply_timeout: Duration::from_millis(25),
+        max_rounds: 200,
+    };
+    let mut progress = Vec::new();
+
+    // The first connection dies partway through the transfer.
+    let mut first = connect(receiver.clone(), committed.clone(), 1, 12);
+    let outcome = sync_mission(&mut first, &mut sender, policy, |p| {
+        progress.push(p.clone())
+    })
+    .await;
+    assert_eq!(outcome, Err(MissionSyncError::TransportClosed));
+    let confirmed_before_reconnect = sender.progress().chunks_confirmed;
+    assert!(confirmed_before_reconnect < 16);
+
+    let mut second = connect(receiver.clone(), committed.clone(), 2, usize::MAX);
+    sync_mission(&mut second, &mut sender, policy, |p| {
+        progress.push(p.clone())
+    })
+    .await
+    .expect("sync should converge despite dropped messages");
+    assert!(first.dropped + second.dropped > 0);
+
+    let last = progress.last().unwrap();
+    assert_eq!(last.phase, MissionSyncPhase::Complete);
+    assert_eq!(last.items_confirmed, 400);
+    assert!(progress
+        .windows(2)
+        .filter(|pair| pair[0].phase != MissionSyncPhase::Negotiating
+            && pair[1].phase != MissionSyncPhase::Negotiating)
+        .all(|pair| pair[0].items_confirmed <= pair[1].items_confirmed));
+
+    let committed = committed.lock().unwrap();
+    assert_eq!(committed.len(), 1, "a mission should be activated once");
+    assert_eq!(committed[0].clone().into_document("items"), document);
+}